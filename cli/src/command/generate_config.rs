@@ -34,9 +34,10 @@ use std::{
 
 use crate::{
     command::{ExonumCommand, StandardResult},
-    config::{NodePrivateConfig, NodePublicConfig},
+    config::{NodePrivateConfig, NodeProfile, NodePublicConfig},
     io::{load_config_file, save_config_file},
     password::{PassInputMethod, Passphrase, PassphraseUsage},
+    secret_store::SecretStoreConfig,
 };
 
 /// Name for a file containing the public part of the node configuration.
@@ -90,6 +91,14 @@ pub struct GenerateConfig {
     /// Path to the master key file. If empty, file will be placed to <output_dir>.
     #[structopt(long)]
     pub master_key_path: Option<PathBuf>,
+
+    /// Profile providing defaults for the node-local parameters.
+    ///
+    /// Possible values are: `validator-lan`, `validator-wan`, `auditor-archive`,
+    /// `api-replica`. The values of the profile are written into the private configuration
+    /// and can be adjusted afterwards.
+    #[structopt(long)]
+    pub profile: Option<NodeProfile>,
 }
 
 impl GenerateConfig {
@@ -168,13 +177,27 @@ impl ExonumCommand for GenerateConfig {
             listen_address,
             external_address: self.peer_address,
             master_key_path: master_key_path.clone(),
-            api: NodeApiConfig::default(),
-            network: NetworkConfiguration::default(),
-            mempool: MemoryPoolConfig::default(),
-            database: DbOptions::default(),
+            secret_store: SecretStoreConfig::default(),
+            api: self
+                .profile
+                .map_or_else(NodeApiConfig::default, NodeProfile::api_config),
+            network: self
+                .profile
+                .map_or_else(NetworkConfiguration::default, NodeProfile::network_config),
+            mempool: self
+                .profile
+                .map_or_else(MemoryPoolConfig::default, NodeProfile::mempool_config),
+            database: self
+                .profile
+                .map_or_else(DbOptions::default, NodeProfile::database_config),
             thread_pool_size: None,
             connect_list: ConnectListConfig::default(),
             consensus_public_key: keys.consensus_pk(),
+            standby: None,
+            bootstrap: None,
+            pruning: None,
+            replication: None,
+            profile: self.profile,
         };
 
         save_config_file(&private_config, &private_config_path)?;
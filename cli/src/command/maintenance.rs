@@ -14,10 +14,15 @@
 
 //! Standard Exonum CLI command used to perform different maintenance actions.
 
-use anyhow::Error;
-use exonum::merkledb::{migration::rollback_migration, Database, RocksDB};
+use anyhow::{bail, format_err, Error};
+use exonum::keys::DataEncryptionSecret;
+use exonum::merkledb::{
+    copy_database, migration::rollback_migration, remove_orphaned_data, verify_database, Database,
+    DbOptions, EncryptionKeys, RocksDB,
+};
 use exonum::runtime::remove_local_migration_result;
 use exonum_node::helpers::clear_consensus_messages_cache;
+use log::info;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use structopt::StructOpt;
@@ -26,6 +31,7 @@ use crate::{
     command::{ExonumCommand, StandardResult},
     config::NodeConfig,
     io::load_config_file,
+    password::PassInputMethod,
 };
 
 /// Perform different maintenance actions.
@@ -40,6 +46,16 @@ pub struct Maintenance {
     #[structopt(long, short = "d")]
     pub db_path: PathBuf,
 
+    /// Passphrase entry method for master key. Used only if the database is encrypted
+    /// at rest and the secret store of the node is the master key file.
+    ///
+    /// Possible values are: `stdin`, `env{:ENV_VAR_NAME}`, `pass:PASSWORD`.
+    /// Default Value is `stdin`.
+    /// If `ENV_VAR_NAME` is not specified `$EXONUM_MASTER_PASS` is used
+    /// by default.
+    #[structopt(long)]
+    pub master_key_pass: Option<PassInputMethod>,
+
     /// Action to be performed.
     #[structopt(subcommand)]
     pub action: MaintenanceAction,
@@ -59,31 +75,104 @@ pub enum MaintenanceAction {
         /// Name of the service for migration restart, e.g. "explorer" or "my-service".
         service_name: String,
     },
+
+    /// Copy the database into a new location, e.g., to apply database options which cannot
+    /// be changed for an existing database. The state hash of the copy is verified afterwards.
+    #[structopt(name = "copy-db")]
+    CopyDatabase {
+        /// Path to a directory of the new database. The directory must not contain
+        /// an existing database.
+        #[structopt(long)]
+        target_db_path: PathBuf,
+
+        /// Path to a node configuration file with database options for the new database.
+        /// If not specified, the options from the main node configuration are used.
+        #[structopt(long)]
+        target_node_config: Option<PathBuf>,
+    },
+
+    /// Compact the database to reclaim space occupied by removed and overwritten entries.
+    /// Compaction is resource-intensive, so it is advised to run it during off-peak hours.
+    #[structopt(name = "compact-db")]
+    CompactDatabase {
+        /// Name of the index or index group to compact. If not specified,
+        /// the entire database is compacted.
+        #[structopt(long)]
+        index: Option<String>,
+
+        /// Maximum number of concurrent background jobs of the database during compaction.
+        #[structopt(long)]
+        background_jobs: Option<u32>,
+    },
+
+    /// Remove data of indexes which are no longer tracked by the database, e.g., indexes
+    /// of rolled back service migrations.
+    #[structopt(name = "gc-db")]
+    CollectGarbage {
+        /// Compact the database after removing the data to reclaim the disk space.
+        #[structopt(long)]
+        compact: bool,
+    },
+
+    /// Verify the integrity of the database by recomputing hashes of all aggregated indexes
+    /// and comparing them with the state aggregator. The database is opened in read-only mode.
+    #[structopt(name = "verify-db")]
+    VerifyDatabase,
+
+    /// Re-encrypt values encrypted with retired keys using the active key of the database
+    /// encryption at rest. Afterwards, the retired key versions can be removed
+    /// from the database options.
+    #[structopt(name = "reencrypt-db")]
+    ReencryptDatabase,
+}
+
+/// Database of the node along with the secret used to open it.
+struct NodeDatabase<'a> {
+    path: &'a Path,
+    options: DbOptions,
+    data_encryption_secret: Option<&'a DataEncryptionSecret>,
+}
+
+impl NodeDatabase<'_> {
+    /// Derives the encryption keys if the database is encrypted at rest.
+    fn encryption_keys(&self) -> Result<Option<EncryptionKeys>, Error> {
+        let encryption = match &self.options.encryption {
+            Some(encryption) => encryption,
+            None => return Ok(None),
+        };
+        let secret = self.data_encryption_secret.ok_or_else(|| {
+            format_err!("Data encryption secret is required to open the encrypted database")
+        })?;
+        Ok(Some(EncryptionKeys::derive(secret.as_bytes(), encryption)))
+    }
+
+    /// Opens the database in the same way as the node does.
+    fn open(&self) -> Result<RocksDB, Error> {
+        Ok(match self.encryption_keys()? {
+            Some(keys) => RocksDB::open_encrypted(self.path, &self.options, keys)?,
+            None => RocksDB::open(self.path, &self.options)?,
+        })
+    }
+
+    fn open_read_only(&self) -> Result<RocksDB, Error> {
+        Ok(match self.encryption_keys()? {
+            Some(keys) => RocksDB::open_read_only_encrypted(self.path, &self.options, keys)?,
+            None => RocksDB::open_read_only(self.path, &self.options)?,
+        })
+    }
 }
 
 impl MaintenanceAction {
-    fn clear_cache(node_config: &Path, db_path: &Path) -> Result<(), Error> {
-        let node_config: NodeConfig = load_config_file(node_config)?;
-        let db: Box<dyn Database> = Box::new(RocksDB::open(
-            db_path,
-            &node_config.private_config.database,
-        )?);
+    fn clear_cache(db: &NodeDatabase<'_>) -> Result<(), Error> {
+        let db: Box<dyn Database> = Box::new(db.open()?);
         let fork = db.fork();
         clear_consensus_messages_cache(&fork);
         db.merge_sync(fork.into_patch())?;
         Ok(())
     }
 
-    fn restart_migration(
-        node_config: &Path,
-        db_path: &Path,
-        service_name: &str,
-    ) -> Result<(), Error> {
-        let node_config: NodeConfig = load_config_file(node_config)?;
-        let db: Box<dyn Database> = Box::new(RocksDB::open(
-            db_path,
-            &node_config.private_config.database,
-        )?);
+    fn restart_migration(db: &NodeDatabase<'_>, service_name: &str) -> Result<(), Error> {
+        let db: Box<dyn Database> = Box::new(db.open()?);
         let mut fork = db.fork();
         rollback_migration(&mut fork, service_name);
         remove_local_migration_result(&fork, service_name);
@@ -91,20 +180,156 @@ impl MaintenanceAction {
 
         Ok(())
     }
+
+    fn copy_database(db: &NodeDatabase<'_>, target_db: &NodeDatabase<'_>) -> Result<(), Error> {
+        let source = db.open()?;
+        let target = target_db.open()?;
+        copy_database(&source, &target)?;
+        Ok(())
+    }
+
+    fn compact_database(
+        db: &NodeDatabase<'_>,
+        index: Option<&str>,
+        background_jobs: Option<u32>,
+    ) -> Result<(), Error> {
+        let db: Box<dyn Database> = Box::new(db.open()?);
+        if let Some(limit) = background_jobs {
+            db.set_background_jobs_limit(limit)?;
+        }
+        db.compact_range(index)?;
+        Ok(())
+    }
+
+    fn collect_garbage(db: &NodeDatabase<'_>, compact: bool) -> Result<(), Error> {
+        let db: Box<dyn Database> = Box::new(db.open()?);
+        let removed_addrs = remove_orphaned_data(db.as_ref())?;
+        if compact {
+            let mut compacted_names: Vec<_> = removed_addrs
+                .iter()
+                .map(|addr| addr.name.as_str())
+                .collect();
+            compacted_names.sort_unstable();
+            compacted_names.dedup();
+            for name in compacted_names {
+                db.compact_range(Some(name))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_database(db: &NodeDatabase<'_>) -> Result<(), Error> {
+        let db = db.open_read_only()?;
+        let report = verify_database(&db);
+        if !report.is_ok() {
+            let names: Vec<_> = report
+                .mismatches
+                .iter()
+                .map(|mismatch| mismatch.name.as_str())
+                .collect();
+            bail!(
+                "Database integrity check failed for {} of {} indexes: {}",
+                names.len(),
+                report.checked_indexes,
+                names.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    fn reencrypt_database(db: &NodeDatabase<'_>) -> Result<(), Error> {
+        if db.options.encryption.is_none() {
+            bail!("Encryption at rest is not configured in the database options");
+        }
+        let count = db.open()?.reencrypt()?;
+        info!("Re-encrypted {} values with the active key", count);
+        Ok(())
+    }
+}
+
+impl Maintenance {
+    /// Loads the data encryption secret from the secret store of the node.
+    fn load_data_encryption_secret(
+        node_config_path: &Path,
+        node_config: &NodeConfig,
+        master_key_pass: Option<PassInputMethod>,
+    ) -> Result<DataEncryptionSecret, Error> {
+        // Relative paths in the secret store configuration are resolved
+        // relative to the configuration file.
+        let config_folder = node_config_path.parent().unwrap();
+        let secret_store = node_config.private_config.secret_store.create_store(
+            config_folder,
+            &node_config.private_config.master_key_path,
+            master_key_pass.unwrap_or_default(),
+        )?;
+        secret_store.load_data_encryption_secret()
+    }
 }
 
 impl ExonumCommand for Maintenance {
     fn execute(self) -> Result<StandardResult, Error> {
+        let node_config: NodeConfig = load_config_file(&self.node_config)?;
+        let target_options = match self.action {
+            MaintenanceAction::CopyDatabase {
+                target_node_config: Some(ref path),
+                ..
+            } => {
+                let target_config: NodeConfig = load_config_file(path)?;
+                Some(target_config.private_config.database)
+            }
+            _ => None,
+        };
+
+        let is_encrypted = node_config.private_config.database.encryption.is_some()
+            || target_options
+                .as_ref()
+                .map_or(false, |options| options.encryption.is_some());
+        let data_encryption_secret = if is_encrypted {
+            Some(Self::load_data_encryption_secret(
+                &self.node_config,
+                &node_config,
+                self.master_key_pass,
+            )?)
+        } else {
+            None
+        };
+        let db = NodeDatabase {
+            path: &self.db_path,
+            options: node_config.private_config.database,
+            data_encryption_secret: data_encryption_secret.as_ref(),
+        };
+
         match self.action {
             MaintenanceAction::ClearCache => {
-                MaintenanceAction::clear_cache(&self.node_config, &self.db_path)?;
+                MaintenanceAction::clear_cache(&db)?;
             }
             MaintenanceAction::RestartMigration { ref service_name } => {
-                MaintenanceAction::restart_migration(
-                    &self.node_config,
-                    &self.db_path,
-                    service_name,
-                )?;
+                MaintenanceAction::restart_migration(&db, service_name)?;
+            }
+            MaintenanceAction::CopyDatabase {
+                ref target_db_path, ..
+            } => {
+                let target_db = NodeDatabase {
+                    path: target_db_path,
+                    options: target_options.unwrap_or_else(|| db.options.clone()),
+                    data_encryption_secret: db.data_encryption_secret,
+                };
+                MaintenanceAction::copy_database(&db, &target_db)?;
+            }
+            MaintenanceAction::CompactDatabase {
+                ref index,
+                background_jobs,
+            } => {
+                MaintenanceAction::compact_database(&db, index.as_deref(), background_jobs)?;
+            }
+            MaintenanceAction::CollectGarbage { compact } => {
+                MaintenanceAction::collect_garbage(&db, compact)?;
+            }
+            MaintenanceAction::VerifyDatabase => {
+                MaintenanceAction::verify_database(&db)?;
+            }
+            MaintenanceAction::ReencryptDatabase => {
+                MaintenanceAction::reencrypt_database(&db)?;
             }
         }
 
@@ -22,16 +22,22 @@ pub use self::{
     },
     generate_template::GenerateTemplate,
     maintenance::{Maintenance, MaintenanceAction},
+    preflight::{Preflight, PreflightConfig, PreflightReport, UpgradeReadiness},
     run::{NodeRunConfig, Run},
     run_dev::RunDev,
+    self_test::{SelfTest, SelfTestCheck, SelfTestConfig, SelfTestReport},
+    sign_tx::{SignTx, SignedTransaction, TransactionDescription},
 };
 
 mod finalize;
 mod generate_config;
 mod generate_template;
 mod maintenance;
+mod preflight;
 mod run;
 mod run_dev;
+mod self_test;
+mod sign_tx;
 
 use anyhow::Error;
 use serde::{Deserialize, Serialize};
@@ -73,6 +79,18 @@ pub enum Command {
     /// Perform different maintenance actions.
     #[structopt(name = "maintenance")]
     Maintenance(Maintenance),
+
+    /// Check the node readiness for the upgrades pending in the supervisor.
+    #[structopt(name = "preflight")]
+    Preflight(Preflight),
+
+    /// Test the node on an ephemeral single-validator network.
+    #[structopt(name = "selftest")]
+    SelfTest(SelfTest),
+
+    /// Sign transactions offline with the service key of the node.
+    #[structopt(name = "sign-tx")]
+    SignTx(SignTx),
 }
 
 impl Command {
@@ -92,6 +110,9 @@ impl ExonumCommand for Command {
             Self::Run(command) => command.execute(),
             Self::RunDev(command) => command.execute(),
             Self::Maintenance(command) => command.execute(),
+            Self::Preflight(command) => command.execute(),
+            Self::SelfTest(command) => command.execute(),
+            Self::SignTx(command) => command.execute(),
         }
     }
 }
@@ -134,4 +155,18 @@ pub enum StandardResult {
         /// Performed action.
         performed_action: MaintenanceAction,
     },
+
+    /// `preflight` command output.
+    Preflight(Box<PreflightConfig>),
+
+    /// `selftest` command output.
+    SelfTest(Box<SelfTestConfig>),
+
+    /// `sign-tx` command output.
+    SignTx {
+        /// Path to a file with signed transactions.
+        output_path: PathBuf,
+        /// Number of signed transactions.
+        transactions_count: usize,
+    },
 }
@@ -0,0 +1,213 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to check the node readiness for pending upgrades.
+
+use anyhow::{anyhow, Context, Error};
+use exonum::{
+    crypto::Hash,
+    merkledb::{Database, RocksDB},
+    runtime::ArtifactId,
+};
+use exonum_supervisor::{PendingUpgrade, ReadinessAttestation};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+    command::{ExonumCommand, StandardResult},
+    config::NodeConfig,
+    io::load_config_file,
+};
+
+/// Number of bytes in a mebibyte.
+const MIB: u64 = 1 << 20;
+
+/// Check the node readiness for the upgrades pending in the supervisor.
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Preflight {
+    /// Path to a node configuration file.
+    #[structopt(long, short = "c")]
+    pub node_config: PathBuf,
+
+    /// Path to a database directory. The database is opened in the read-only mode,
+    /// so the command may be executed while the node is running.
+    #[structopt(long, short = "d")]
+    pub db_path: PathBuf,
+
+    /// Minimum free space on the volume with the database, in mebibytes.
+    #[structopt(long, default_value = "1024")]
+    pub min_free_space: u64,
+
+    /// Record the results of the checks on chain. Attestations are sent to the private API
+    /// of the running node, which signs them with its service key.
+    #[structopt(long)]
+    pub attest: bool,
+}
+
+/// Container for the data collected by the `preflight` command.
+///
+/// The artifacts required by the upgrades should be checked against the runtimes
+/// of the node, thus the command itself does not produce the readiness report; use
+/// [`check`](#method.check) to create one.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct PreflightConfig {
+    /// Final node configuration parameters.
+    pub node_config: NodeConfig,
+    /// Upgrades pending in the supervisor.
+    pub upgrades: Vec<PendingUpgrade>,
+    /// Free space on the volume with the database in bytes, or `None` if it cannot
+    /// be determined on the current platform.
+    pub free_space: Option<u64>,
+    /// Minimum free space on the volume with the database in bytes.
+    pub min_free_space: u64,
+    /// Whether the results of the checks should be recorded on chain.
+    pub attest: bool,
+}
+
+impl PreflightConfig {
+    /// Checks the node readiness for each pending upgrade. `check_artifact` should return
+    /// an error description if the artifact cannot be deployed on the node, e.g., because
+    /// it is missing or its runtime is not available.
+    #[must_use]
+    pub fn check<F>(&self, mut check_artifact: F) -> PreflightReport
+    where
+        F: FnMut(&ArtifactId) -> Result<(), String>,
+    {
+        let disk_check = match self.free_space {
+            Some(free_space) if free_space < self.min_free_space => Some(format!(
+                "Only {} MiB of disk space is available, while at least {} MiB is required",
+                free_space / MIB,
+                self.min_free_space / MIB
+            )),
+            _ => None,
+        };
+
+        let upgrades = self
+            .upgrades
+            .iter()
+            .map(|upgrade| {
+                let mut failed_checks: Vec<_> = upgrade
+                    .artifacts
+                    .iter()
+                    .filter_map(|artifact| check_artifact(artifact).err())
+                    .collect();
+                failed_checks.extend(disk_check.clone());
+                UpgradeReadiness {
+                    upgrade: upgrade.clone(),
+                    failed_checks,
+                }
+            })
+            .collect();
+        PreflightReport { upgrades }
+    }
+
+    /// Records the report on chain by sending readiness attestations to the private API
+    /// of the node. Returns hashes of the broadcast transactions.
+    pub fn attest(&self, report: &PreflightReport) -> Result<Vec<Hash>, Error> {
+        let address = self
+            .node_config
+            .private_config
+            .api
+            .private_api_address
+            .ok_or_else(|| anyhow!("Private API of the node is disabled"))?;
+        let url = format!(
+            "http://{}/api/services/supervisor/report-readiness",
+            address
+        );
+
+        report
+            .attestations()
+            .map(|attestation| {
+                ureq::post(&url)
+                    .send_json(&attestation)
+                    .with_context(|| format!("Failed to send readiness attestation to {}", url))?
+                    .into_json()
+                    .context("Failed to parse the hash of the attestation transaction")
+            })
+            .collect()
+    }
+}
+
+/// Readiness of the node for a pending upgrade.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct UpgradeReadiness {
+    /// Pending upgrade.
+    pub upgrade: PendingUpgrade,
+    /// Descriptions of the failed checks; empty if the node is ready for the upgrade.
+    pub failed_checks: Vec<String>,
+}
+
+/// Readiness of the node for all pending upgrades.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PreflightReport {
+    /// Readiness for each pending upgrade.
+    pub upgrades: Vec<UpgradeReadiness>,
+}
+
+impl PreflightReport {
+    /// Checks whether the node is ready for all pending upgrades.
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.upgrades
+            .iter()
+            .all(|readiness| readiness.failed_checks.is_empty())
+    }
+
+    /// Returns readiness attestations corresponding to the report.
+    pub fn attestations(&self) -> impl Iterator<Item = ReadinessAttestation> + '_ {
+        self.upgrades.iter().map(|readiness| {
+            ReadinessAttestation::new(readiness.upgrade.subject, readiness.failed_checks.clone())
+        })
+    }
+}
+
+/// Returns space available to unprivileged users on the volume containing `path`.
+#[cfg(unix)]
+#[allow(clippy::useless_conversion)] // Types of `statvfs` fields are platform-dependent.
+fn available_space(path: &Path) -> Result<Option<u64>, Error> {
+    let stat = nix::sys::statvfs::statvfs(path)
+        .with_context(|| format!("Failed to get free space for {}", path.display()))?;
+    Ok(Some(
+        u64::from(stat.blocks_available()) * u64::from(stat.fragment_size()),
+    ))
+}
+
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)]
+fn available_space(_path: &Path) -> Result<Option<u64>, Error> {
+    Ok(None)
+}
+
+impl ExonumCommand for Preflight {
+    fn execute(self) -> Result<StandardResult, Error> {
+        let node_config: NodeConfig = load_config_file(&self.node_config)?;
+        let db = RocksDB::open_read_only(&self.db_path, &node_config.private_config.database)?;
+        let upgrades = PendingUpgrade::load_all(db.snapshot().as_ref());
+        let free_space = available_space(&self.db_path)?;
+
+        Ok(StandardResult::Preflight(Box::new(PreflightConfig {
+            node_config,
+            upgrades,
+            free_space,
+            min_free_space: self.min_free_space * MIB,
+            attest: self.attest,
+        })))
+    }
+}
@@ -16,19 +16,16 @@
 //! configuration file.
 
 use anyhow::Error;
-use exonum::keys::{read_keys_from_file, Keys};
+use exonum::keys::{DataEncryptionSecret, Keys};
 use serde::{Deserialize, Serialize};
-use std::{
-    net::SocketAddr,
-    path::{Path, PathBuf},
-};
+use std::{net::SocketAddr, path::PathBuf};
 use structopt::StructOpt;
 
 use crate::{
     command::{ExonumCommand, StandardResult},
     config::NodeConfig,
     io::load_config_file,
-    password::{PassInputMethod, PassphraseUsage},
+    password::PassInputMethod,
 };
 
 /// Container for node configuration parameters produced by `Run` command.
@@ -39,6 +36,9 @@ pub struct NodeRunConfig {
     pub node_config: NodeConfig,
     /// Node keys.
     pub node_keys: Keys,
+    /// Secret used to derive the keys for the encryption of the database at rest.
+    /// Loaded only if the encryption is configured in the database options.
+    pub data_encryption_secret: Option<DataEncryptionSecret>,
     /// Path to a directory containing database files, provided by user.
     pub db_path: PathBuf,
     /// User-provided path to the node configuration file.
@@ -65,7 +65,8 @@ pub struct Run {
     /// Private API is used by node administrators for node monitoring and control.
     #[structopt(long)]
     pub private_api_address: Option<SocketAddr>,
-    /// Passphrase entry method for master key.
+    /// Passphrase entry method for master key. Used only if the secret store
+    /// of the node is the master key file.
     ///
     /// Possible values are: `stdin`, `env{:ENV_VAR_NAME}`, `pass:PASSWORD`.
     /// Default Value is `stdin`.
@@ -91,19 +92,25 @@ impl ExonumCommand for Run {
             config.private_config.api.private_api_address = Some(private_api_address);
         }
 
-        let master_passphrase = self
-            .master_key_pass
-            .unwrap_or_default()
-            .get_passphrase(PassphraseUsage::Using)?;
-        let node_keys = read_secret_keys(
-            config_path,
+        // Relative paths in the secret store configuration are resolved
+        // relative to the configuration file.
+        let config_folder = config_path.parent().unwrap();
+        let secret_store = config.private_config.secret_store.create_store(
+            config_folder,
             &config.private_config.master_key_path,
-            master_passphrase.as_bytes(),
-        );
+            self.master_key_pass.unwrap_or_default(),
+        )?;
+        let node_keys = secret_store.load_keys()?;
+        let data_encryption_secret = if config.private_config.database.encryption.is_some() {
+            Some(secret_store.load_data_encryption_secret()?)
+        } else {
+            None
+        };
 
         let run_config = NodeRunConfig {
             node_config: config,
             node_keys,
+            data_encryption_secret,
             db_path: self.db_path,
             node_config_path: self.node_config,
         };
@@ -111,20 +118,3 @@ impl ExonumCommand for Run {
         Ok(StandardResult::Run(Box::new(run_config)))
     }
 }
-
-/// Reads validator keys from the encrypted file.
-fn read_secret_keys(
-    config_file_path: impl AsRef<Path>,
-    master_key_path: &Path,
-    master_key_passphrase: &[u8],
-) -> Keys {
-    let config_folder = config_file_path.as_ref().parent().unwrap();
-    let master_key_path = if master_key_path.is_absolute() {
-        master_key_path.to_owned()
-    } else {
-        config_folder.join(&master_key_path)
-    };
-
-    read_keys_from_file(&master_key_path, master_key_passphrase)
-        .expect("Could not read master_key_path from file")
-}
@@ -96,6 +96,7 @@ impl ExonumCommand for RunDev {
                 no_password: true,
                 master_key_pass: None,
                 master_key_path: None,
+                profile: None,
             };
             generate_config.execute()?;
 
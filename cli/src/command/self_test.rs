@@ -0,0 +1,362 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to test the node on an ephemeral single-node network.
+
+use anyhow::{anyhow, bail, Error};
+use exonum::{
+    blockchain::Blockchain,
+    crypto::Hash,
+    helpers::Height,
+    merkledb::ObjectHash,
+    runtime::{InstanceStatus, SnapshotExt, SUPERVISOR_INSTANCE_ID},
+};
+use exonum_node::Node;
+use exonum_supervisor::{mode::Mode as SupervisorMode, ConfigPropose, SupervisorInterface};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+use tempfile::TempDir;
+use tokio::{task, time::sleep};
+
+use std::{
+    net::{Ipv4Addr, SocketAddr, TcpListener},
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    command::{
+        finalize::Finalize,
+        generate_config::{GenerateConfig, PRIVATE_CONFIG_FILE_NAME, PUBLIC_CONFIG_FILE_NAME},
+        generate_template::GenerateTemplate,
+        run::{NodeRunConfig, Run},
+        ExonumCommand, StandardResult,
+    },
+    config::NodeConfig,
+    io::{load_config_file, save_config_file},
+};
+
+/// Interval between polls of the blockchain state.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Test the node on an ephemeral single-validator network.
+///
+/// The command starts the node with all its components (storage, runtimes and HTTP API)
+/// and the services built into the node binary, waits until several blocks are committed,
+/// and runs smoke checks of the API and the built-in services. The blockchain data is stored
+/// in a temporary directory, which is removed after the test.
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SelfTest {
+    /// Path to a node configuration file to test.
+    ///
+    /// If specified, the consensus parameters (except for validator keys), database, memory
+    /// pool, network and API parameters (except for listen addresses) are taken from the file.
+    /// Node keys, addresses and peers are replaced with ephemeral ones.
+    #[structopt(long, short = "c")]
+    pub node_config: Option<PathBuf>,
+
+    /// Number of blocks to commit before running the smoke checks.
+    #[structopt(long, default_value = "3")]
+    pub blocks: u64,
+
+    /// Maximum duration of the test, in seconds.
+    #[structopt(long, default_value = "60")]
+    pub timeout: u64,
+}
+
+/// Container for the parameters of the node started by the `selftest` command.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct SelfTestConfig {
+    /// Configuration of the ephemeral node.
+    pub run_config: NodeRunConfig,
+    /// Number of blocks to commit before running the smoke checks.
+    pub blocks: u64,
+    /// Maximum duration of the test.
+    pub timeout: Duration,
+    /// Directory with the ephemeral node configuration and database. Removed on drop.
+    temp_dir: TempDir,
+}
+
+/// Result of a single check performed by the `selftest` command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SelfTestCheck {
+    /// Name of the check.
+    pub name: String,
+    /// Description of the failure, or `None` if the check has passed.
+    pub error: Option<String>,
+}
+
+impl SelfTestCheck {
+    fn new(name: impl Into<String>, result: Result<(), Error>) -> Self {
+        Self {
+            name: name.into(),
+            error: result.err().map(|err| format!("{:#}", err)),
+        }
+    }
+}
+
+/// Results of all checks performed by the `selftest` command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SelfTestReport {
+    /// Results of the checks in the order of their execution.
+    pub checks: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+    /// Checks whether all checks have passed.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(|check| check.error.is_none())
+    }
+}
+
+/// Returns a local address with a currently unused port.
+fn unused_address() -> Result<SocketAddr, Error> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    Ok(listener.local_addr()?)
+}
+
+/// Performs a blocking HTTP request to the node API.
+async fn get_json(url: String) -> Result<serde_json::Value, Error> {
+    task::spawn_blocking(move || {
+        let response = ureq::get(&url).call()?;
+        response.into_json().map_err(Error::from)
+    })
+    .await?
+}
+
+impl SelfTest {
+    /// Overrides parameters of the generated ephemeral config with ones from the tested config.
+    fn merge_configs(generated: &mut NodeConfig, tested: NodeConfig) {
+        let validator_keys = generated.public_config.consensus.validator_keys.clone();
+        generated.public_config.consensus = tested.public_config.consensus;
+        generated.public_config.consensus.validator_keys = validator_keys;
+
+        let private_config = &mut generated.private_config;
+        let api = &mut private_config.api;
+        let (public_api_address, private_api_address) =
+            (api.public_api_address, api.private_api_address);
+        *api = tested.private_config.api;
+        api.public_api_address = public_api_address;
+        api.private_api_address = private_api_address;
+
+        private_config.network = tested.private_config.network;
+        private_config.mempool = tested.private_config.mempool;
+        private_config.database = tested.private_config.database;
+        private_config.thread_pool_size = tested.private_config.thread_pool_size;
+        private_config.pruning = tested.private_config.pruning;
+    }
+}
+
+impl ExonumCommand for SelfTest {
+    fn execute(self) -> Result<StandardResult, Error> {
+        let tested_config: Option<NodeConfig> = self
+            .node_config
+            .as_ref()
+            .map(load_config_file)
+            .transpose()?;
+
+        let temp_dir = TempDir::new()?;
+        let config_dir = temp_dir.path().join("config");
+        let node_config_path = config_dir.join("node.toml");
+        let common_config_path = config_dir.join("template.toml");
+        let public_config_path = config_dir.join(PUBLIC_CONFIG_FILE_NAME);
+        let private_config_path = config_dir.join(PRIVATE_CONFIG_FILE_NAME);
+        let supervisor_mode = tested_config
+            .as_ref()
+            .map_or(SupervisorMode::Simple, |config| {
+                config.public_config.general.supervisor_mode.clone()
+            });
+
+        GenerateTemplate {
+            common_config: common_config_path.clone(),
+            validators_count: 1,
+            supervisor_mode,
+        }
+        .execute()?;
+
+        GenerateConfig {
+            common_config: common_config_path,
+            output_dir: config_dir,
+            peer_address: unused_address()?.to_string(),
+            listen_address: None,
+            no_password: true,
+            master_key_pass: None,
+            master_key_path: None,
+            profile: None,
+        }
+        .execute()?;
+
+        Finalize {
+            private_config_path,
+            output_config_path: node_config_path.clone(),
+            public_configs: vec![public_config_path],
+            public_api_address: Some(unused_address()?),
+            private_api_address: Some(unused_address()?),
+            public_allow_origin: None,
+            private_allow_origin: None,
+        }
+        .execute()?;
+
+        if let Some(tested_config) = tested_config {
+            let mut config: NodeConfig = load_config_file(&node_config_path)?;
+            Self::merge_configs(&mut config, tested_config);
+            save_config_file(&config, &node_config_path)?;
+        }
+
+        let run = Run {
+            node_config: node_config_path,
+            db_path: temp_dir.path().join("db"),
+            public_api_address: None,
+            private_api_address: None,
+            master_key_pass: Some(FromStr::from_str("pass:").unwrap()),
+        };
+        let run_config = match run.execute()? {
+            StandardResult::Run(run_config) => *run_config,
+            _ => unreachable!("`run` command always returns `StandardResult::Run`"),
+        };
+
+        Ok(StandardResult::SelfTest(Box::new(SelfTestConfig {
+            run_config,
+            blocks: self.blocks,
+            timeout: Duration::from_secs(self.timeout),
+            temp_dir,
+        })))
+    }
+}
+
+impl SelfTestConfig {
+    /// Runs the node built according to this config and performs the checks. The node
+    /// is shut down after the checks are completed.
+    pub async fn run(self, node: Node) -> Result<SelfTestReport, Error> {
+        let blockchain = node.blockchain().clone();
+        let shutdown_handle = node.shutdown_handle();
+        let node_task = tokio::spawn(node.run());
+
+        let deadline = Instant::now() + self.timeout;
+        let mut checks = vec![SelfTestCheck::new(
+            "consensus",
+            self.wait_for_blocks(&blockchain, deadline).await,
+        )];
+        // Other checks rely on the blocks being committed.
+        if checks[0].error.is_none() {
+            checks.push(SelfTestCheck::new("api", self.check_api().await));
+            checks.extend(Self::check_services(&blockchain));
+            checks.push(SelfTestCheck::new(
+                "supervisor",
+                Self::check_supervisor(&blockchain, deadline).await,
+            ));
+        }
+
+        shutdown_handle
+            .shutdown()
+            .await
+            .map_err(|_| anyhow!("Node is already shut down"))?;
+        node_task.await??;
+        drop(self.temp_dir);
+        Ok(SelfTestReport { checks })
+    }
+
+    async fn wait_for_blocks(
+        &self,
+        blockchain: &Blockchain,
+        deadline: Instant,
+    ) -> Result<(), Error> {
+        let target_height = Height(self.blocks);
+        loop {
+            let height = blockchain.last_block().height;
+            if height >= target_height {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                bail!(
+                    "Only {} blocks out of {} were committed in time",
+                    height.0,
+                    target_height.0
+                );
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn check_api(&self) -> Result<(), Error> {
+        let api_config = &self.run_config.node_config.private_config.api;
+        if let Some(address) = api_config.public_api_address {
+            let url = format!("http://{}/api/explorer/v1/blocks?count=1", address);
+            get_json(url).await?;
+        }
+        if let Some(address) = api_config.private_api_address {
+            let url = format!("http://{}/api/system/v1/info", address);
+            get_json(url).await?;
+        }
+        Ok(())
+    }
+
+    /// Checks that all services started at the genesis block are active.
+    fn check_services(blockchain: &Blockchain) -> Vec<SelfTestCheck> {
+        let snapshot = blockchain.snapshot();
+        let instances = snapshot.for_dispatcher().service_instances();
+        let checks = instances.values().map(|state| {
+            let result = if state.status == Some(InstanceStatus::Active) {
+                Ok(())
+            } else {
+                Err(anyhow!("Service is not active: {:?}", state.status))
+            };
+            SelfTestCheck::new(format!("service `{}`", state.spec.name), result)
+        });
+        checks.collect()
+    }
+
+    /// Checks the supervisor by applying a configuration proposal which does not modify
+    /// the consensus configuration.
+    async fn check_supervisor(blockchain: &Blockchain, deadline: Instant) -> Result<(), Error> {
+        let consensus_config = blockchain.snapshot().for_core().consensus_config();
+        // The supervisor has not processed any proposals on the fresh chain.
+        let propose = ConfigPropose::immediate(0).consensus_config(consensus_config);
+        let tx = blockchain
+            .service_keypair()
+            .propose_config_change(SUPERVISOR_INSTANCE_ID, propose);
+        let tx_hash = tx.object_hash();
+        blockchain.sender().broadcast_transaction(tx).await?;
+
+        Self::wait_for_transaction(blockchain, tx_hash, deadline).await
+    }
+
+    async fn wait_for_transaction(
+        blockchain: &Blockchain,
+        tx_hash: Hash,
+        deadline: Instant,
+    ) -> Result<(), Error> {
+        loop {
+            let snapshot = blockchain.snapshot();
+            let schema = snapshot.for_core();
+            if let Some(location) = schema.transactions_locations().get(&tx_hash) {
+                return match schema.transaction_result(location) {
+                    Some(Ok(())) => Ok(()),
+                    Some(Err(err)) => Err(anyhow!("Transaction {:?} failed: {}", tx_hash, err)),
+                    None => Err(anyhow!("No result for transaction {:?}", tx_hash)),
+                };
+            }
+            if Instant::now() >= deadline {
+                bail!("Transaction {:?} was not committed in time", tx_hash);
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+    }
+}
@@ -0,0 +1,148 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Standard Exonum CLI command used to sign transactions offline.
+
+use anyhow::{Context, Error};
+use exonum::{
+    crypto::{self, Hash},
+    messages::offline,
+    runtime::{InstanceId, MethodId},
+};
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use std::{fs, path::PathBuf};
+
+use crate::{
+    command::{ExonumCommand, StandardResult},
+    password::{PassInputMethod, PassphraseUsage},
+    secret_store::{FileSecretStore, SecretStore},
+};
+
+/// Sign transactions with the service key of the node, e.g., on an air-gapped machine.
+///
+/// Transactions are read from a JSON file with an array of transaction descriptions.
+/// Signed transactions are written to the output JSON file as an array of objects
+/// with `tx_hash` and hex-encoded `tx_body` fields; the latter can be submitted to the node
+/// via the explorer service API. The command does not require network access.
+#[derive(StructOpt, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SignTx {
+    /// Path to a JSON file with descriptions of transactions to sign.
+    pub transactions: PathBuf,
+
+    /// Path to the master key file, the service key from which is used for signing.
+    #[structopt(long)]
+    pub master_key_path: PathBuf,
+
+    /// Passphrase entry method for master key.
+    ///
+    /// Possible values are: `stdin`, `env{:ENV_VAR_NAME}`, `pass:PASSWORD`.
+    /// Default Value is `stdin`.
+    /// If `ENV_VAR_NAME` is not specified `$EXONUM_MASTER_PASS` is used
+    /// by default.
+    #[structopt(long)]
+    pub master_key_pass: Option<PassInputMethod>,
+
+    /// Path to the output file with signed transactions.
+    #[structopt(long, short = "o")]
+    pub output: PathBuf,
+}
+
+/// Description of a transaction to be signed by the `sign-tx` command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct TransactionDescription {
+    /// Identifier of the service instance.
+    pub instance_id: InstanceId,
+    /// Identifier of the called method.
+    pub method_id: MethodId,
+    /// Hex-encoded serialized transaction arguments.
+    #[serde(default)]
+    pub arguments: String,
+}
+
+/// Transaction signed by the `sign-tx` command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SignedTransaction {
+    /// Hash of the transaction.
+    pub tx_hash: Hash,
+    /// Hex-encoded serialized transaction.
+    pub tx_body: String,
+}
+
+impl TransactionDescription {
+    /// Creates a description of a transaction with the specified arguments.
+    pub fn new(instance_id: InstanceId, method_id: MethodId, arguments: &[u8]) -> Self {
+        Self {
+            instance_id,
+            method_id,
+            arguments: hex::encode(arguments),
+        }
+    }
+}
+
+impl ExonumCommand for SignTx {
+    fn execute(self) -> Result<StandardResult, Error> {
+        let descriptions = fs::read(&self.transactions).with_context(|| {
+            format!(
+                "Cannot read transactions from {}",
+                self.transactions.display()
+            )
+        })?;
+        let descriptions: Vec<TransactionDescription> = serde_json::from_slice(&descriptions)
+            .context("Cannot parse transaction descriptions")?;
+
+        let passphrase = self
+            .master_key_pass
+            .unwrap_or_default()
+            .get_passphrase(PassphraseUsage::Using)?;
+        let keys = FileSecretStore::new(&self.master_key_path, passphrase).load_keys()?;
+
+        let signed_transactions = descriptions
+            .iter()
+            .enumerate()
+            .map(|(i, description)| {
+                let arguments = hex::decode(&description.arguments).with_context(|| {
+                    format!("Arguments of transaction #{} are not a valid hex string", i)
+                })?;
+                let tx_body = offline::sign_transaction(
+                    description.instance_id,
+                    description.method_id,
+                    &arguments,
+                    &keys.service,
+                );
+                Ok(SignedTransaction {
+                    tx_hash: crypto::hash(&tx_body),
+                    tx_body: hex::encode(tx_body),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let output = serde_json::to_string_pretty(&signed_transactions)?;
+        fs::write(&self.output, output).with_context(|| {
+            format!(
+                "Cannot write signed transactions to {}",
+                self.output.display()
+            )
+        })?;
+
+        Ok(StandardResult::SignTx {
+            output_path: self.output,
+            transactions_count: signed_transactions.len(),
+        })
+    }
+}
@@ -14,19 +14,23 @@
 
 //! Contains various config structures used during configuration process.
 
+use anyhow::format_err;
 use exonum::{
-    blockchain::{ConsensusConfig, ValidatorKeys},
+    blockchain::{ConsensusConfig, PruningConfig, ValidatorKeys},
     crypto::PublicKey,
-    merkledb::DbOptions,
+    merkledb::{CompressionType, DbOptions},
 };
 use exonum_node::{
-    ConnectListConfig, MemoryPoolConfig, NetworkConfiguration, NodeApiConfig,
-    NodeConfig as CoreNodeConfig,
+    BootstrapConfig, ConnectListConfig, FlushPoolStrategy, MemoryBudgetConfig, MemoryPoolConfig,
+    MemorySubsystem, NetworkConfiguration, NodeApiConfig, NodeConfig as CoreNodeConfig,
+    ReplicationConfig, StandbyConfig,
 };
 use exonum_supervisor::mode::Mode as SupervisorMode;
 use serde::{Deserialize, Serialize};
 
-use std::{net::SocketAddr, path::PathBuf};
+use std::{fmt, net::SocketAddr, path::PathBuf, str::FromStr};
+
+use crate::secret_store::SecretStoreConfig;
 
 /// Part of the template configuration.
 #[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
@@ -54,15 +58,212 @@ pub struct NodePublicConfig {
     pub address: Option<String>,
 }
 
+/// Named set of coherent defaults for the node-local parameters, tuned for a common
+/// deployment shape.
+///
+/// A profile provides defaults for the `api`, `network`, `mempool` and `database` sections
+/// of the private node configuration. If the profile is specified in the configuration file,
+/// the values explicitly set in the file override the values of the profile; all other
+/// values are taken from the profile. Thus, a configuration may only list the parameters
+/// that differ from the profile:
+///
+/// ```toml
+/// profile = "validator-wan"
+///
+/// [network]
+/// max_incoming_connections = 64
+/// ```
+///
+/// Consensus timeouts are a part of the consensus configuration shared among validators,
+/// and thus are not affected by profiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum NodeProfile {
+    /// Validator connected to other validators via a low-latency trusted network.
+    /// Broken connections are retried quickly, and the node keeps few connections.
+    ValidatorLan,
+    /// Validator connected to other validators via the Internet. Connections are monitored
+    /// with keep-alive probes, and memory used by the consensus and peer queues is limited
+    /// to withstand bursts of traffic from slow or malicious peers.
+    ValidatorWan,
+    /// Auditor node storing the full history of the blockchain. Storage is tuned
+    /// for large databases: data is compressed and a larger block cache is used.
+    AuditorArchive,
+    /// Node serving read-heavy HTTP API traffic. Proof cache and block cache are larger,
+    /// and the API state is refreshed more often.
+    ApiReplica,
+}
+
+impl NodeProfile {
+    /// Returns the HTTP API configuration of the profile.
+    pub fn api_config(self) -> NodeApiConfig {
+        let mut config = NodeApiConfig::default();
+        if self == Self::ApiReplica {
+            config.state_update_timeout = 1_000;
+            config.json_payload_size = Some(1 << 20);
+            config.proof_cache_size = Some(64 << 20);
+            config.value_cache_capacity = Some(100_000);
+        }
+        config
+    }
+
+    /// Returns the P2P network configuration of the profile.
+    pub fn network_config(self) -> NetworkConfiguration {
+        let mut config = NetworkConfiguration::default();
+        match self {
+            Self::ValidatorLan => {
+                config.max_incoming_connections = 32;
+                config.max_outgoing_connections = 32;
+                config.tcp_connect_retry_timeout = 1_000;
+                config.tcp_connect_max_retries = 100;
+            }
+            Self::ValidatorWan => {
+                config.tcp_keep_alive = Some(30_000);
+                config.tcp_connect_max_retries = 40;
+            }
+            Self::AuditorArchive | Self::ApiReplica => {
+                config.max_incoming_connections = 16;
+                config.tcp_keep_alive = Some(60_000);
+            }
+        }
+        config
+    }
+
+    /// Returns the memory pool configuration of the profile.
+    pub fn mempool_config(self) -> MemoryPoolConfig {
+        let mut config = MemoryPoolConfig::default();
+        match self {
+            Self::ValidatorLan => {}
+            Self::ValidatorWan => {
+                config.memory_budget = MemoryBudgetConfig::default()
+                    .with_budget(MemorySubsystem::ConsensusCache, 32 << 20)
+                    .with_budget(MemorySubsystem::PeerQueues, 64 << 20);
+            }
+            Self::AuditorArchive => {
+                // Auditors do not propose blocks, thus transactions are persisted less often.
+                config.flush_pool_strategy = FlushPoolStrategy::Timeout { timeout: 100 };
+            }
+            Self::ApiReplica => {
+                config.memory_budget = MemoryBudgetConfig::default()
+                    .with_budget(MemorySubsystem::ApiBuffers, 128 << 20);
+            }
+        }
+        config
+    }
+
+    /// Returns the database configuration of the profile.
+    pub fn database_config(self) -> DbOptions {
+        let options = DbOptions::default().with_bloom_filter_bits(10);
+        match self {
+            Self::ValidatorLan | Self::ValidatorWan => options,
+            Self::AuditorArchive => {
+                let mut options = options.with_block_cache_size(256 << 20);
+                options.compression_type = CompressionType::Snappy;
+                options
+            }
+            Self::ApiReplica => options.with_block_cache_size(1 << 30),
+        }
+    }
+
+    /// Returns the defaults of the profile as a TOML table with the sections
+    /// of the private node configuration.
+    fn defaults(self) -> anyhow::Result<toml::Value> {
+        let mut table = toml::value::Table::new();
+        table.insert("api".to_owned(), toml::Value::try_from(self.api_config())?);
+        table.insert(
+            "network".to_owned(),
+            toml::Value::try_from(self.network_config())?,
+        );
+        table.insert(
+            "mempool".to_owned(),
+            toml::Value::try_from(self.mempool_config())?,
+        );
+        table.insert(
+            "database".to_owned(),
+            toml::Value::try_from(self.database_config())?,
+        );
+        Ok(toml::Value::Table(table))
+    }
+}
+
+impl fmt::Display for NodeProfile {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(match self {
+            Self::ValidatorLan => "validator-lan",
+            Self::ValidatorWan => "validator-wan",
+            Self::AuditorArchive => "auditor-archive",
+            Self::ApiReplica => "api-replica",
+        })
+    }
+}
+
+impl FromStr for NodeProfile {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "validator-lan" => Ok(Self::ValidatorLan),
+            "validator-wan" => Ok(Self::ValidatorWan),
+            "auditor-archive" => Ok(Self::AuditorArchive),
+            "api-replica" => Ok(Self::ApiReplica),
+            _ => Err(format_err!(
+                "Invalid node profile: {}. Should be 'validator-lan', 'validator-wan', \
+                 'auditor-archive' or 'api-replica'",
+                input
+            )),
+        }
+    }
+}
+
+/// Fills the values of the private node configuration missing in `config` with the defaults
+/// of the profile specified in the configuration, if any. `config` may be either a private
+/// node configuration or a full node configuration.
+pub(crate) fn apply_profile(config: &mut toml::Value) -> anyhow::Result<()> {
+    let private_config = if config.get("private_config").is_some() {
+        config.get_mut("private_config").unwrap()
+    } else {
+        config
+    };
+    let profile = match private_config.get("profile") {
+        Some(profile) => profile.clone().try_into::<NodeProfile>()?,
+        None => return Ok(()),
+    };
+    merge_defaults(private_config, profile.defaults()?);
+    Ok(())
+}
+
+/// Recursively inserts values from `defaults` missing in `value`.
+fn merge_defaults(value: &mut toml::Value, defaults: toml::Value) {
+    if let (toml::Value::Table(table), toml::Value::Table(defaults)) = (value, defaults) {
+        for (key, default_value) in defaults {
+            match table.get_mut(&key) {
+                Some(value) => merge_defaults(value, default_value),
+                None => {
+                    table.insert(key, default_value);
+                }
+            }
+        }
+    }
+}
+
 /// Private configuration of the node.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct NodePrivateConfig {
+    /// Profile of the node providing defaults for the node-local parameters.
+    /// Parameters explicitly specified in the configuration override the profile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<NodeProfile>,
     /// Network listening address.
     pub listen_address: SocketAddr,
     /// The address advertised by the node for peers to connect to.
     pub external_address: String,
     /// Path to the master key file.
     pub master_key_path: PathBuf,
+    /// Storage of the node secret material. By default, the master key file
+    /// specified by `master_key_path` is used.
+    #[serde(default)]
+    pub secret_store: SecretStoreConfig,
     /// API configuration.
     pub api: NodeApiConfig,
     /// Network configuration.
@@ -78,6 +279,22 @@ pub struct NodePrivateConfig {
     pub connect_list: ConnectListConfig,
     /// Consensus public key of the node.
     pub consensus_public_key: PublicKey,
+    /// Standby mode configuration. If set, the node starts as a warm standby
+    /// for the validator with the same keys.
+    #[serde(default)]
+    pub standby: Option<StandbyConfig>,
+    /// Bootstrap mode configuration. If set, the node only communicates with the peers
+    /// from the allowlist until the first block is committed.
+    #[serde(default)]
+    pub bootstrap: Option<BootstrapConfig>,
+    /// Pruning of historical block data. If set, the node removes historical data
+    /// for blocks older than the configured number of heights.
+    #[serde(default)]
+    pub pruning: Option<PruningConfig>,
+    /// Streaming of database patches to read replicas. If set, the node listens to
+    /// replica connections on the configured address.
+    #[serde(default)]
+    pub replication: Option<ReplicationConfig>,
 }
 
 /// Configuration for the `Node`.
@@ -100,6 +317,10 @@ impl From<NodeConfig> for CoreNodeConfig {
             mempool: config.private_config.mempool,
             connect_list: config.private_config.connect_list,
             thread_pool_size: config.private_config.thread_pool_size,
+            standby: config.private_config.standby,
+            bootstrap: config.private_config.bootstrap,
+            pruning: config.private_config.pruning,
+            replication: config.private_config.replication,
         }
     }
 }
@@ -91,6 +91,7 @@ mod tests {
     use super::DefaultConfigManager;
     use crate::config::{GeneralConfig, NodeConfig, NodePrivateConfig, NodePublicConfig};
     use crate::io::{load_config_file, save_config_file};
+    use crate::secret_store::SecretStoreConfig;
 
     #[test]
     fn test_update_config() {
@@ -99,6 +100,7 @@ mod tests {
                 listen_address: "127.0.0.1:5400".parse().unwrap(),
                 external_address: "127.0.0.1:5400".to_string(),
                 master_key_path: PathBuf::default(),
+                secret_store: SecretStoreConfig::default(),
                 api: NodeApiConfig::default(),
                 network: NetworkConfiguration::default(),
                 mempool: MemoryPoolConfig::default(),
@@ -106,6 +108,11 @@ mod tests {
                 thread_pool_size: None,
                 connect_list: ConnectListConfig::default(),
                 consensus_public_key: KeyPair::random().public_key(),
+                standby: None,
+                bootstrap: None,
+                pruning: None,
+                replication: None,
+                profile: None,
             },
             public_config: NodePublicConfig {
                 consensus: ConsensusConfig::default(),
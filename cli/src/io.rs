@@ -22,7 +22,14 @@ use std::{
     path::Path,
 };
 
+use crate::config::apply_profile;
+
 /// Loads TOML-encoded file.
+///
+/// If the file contains a private node configuration with a [profile], the values missing
+/// in the file are filled from the profile.
+///
+/// [profile]: config/enum.NodeProfile.html
 pub fn load_config_file<P, T>(path: P) -> Result<T, Error>
 where
     T: for<'r> Deserialize<'r>,
@@ -50,7 +57,9 @@ fn do_load<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
     let mut file = File::open(path)?;
     let mut toml = String::new();
     file.read_to_string(&mut toml)?;
-    Ok(toml::de::from_str(&toml)?)
+    let mut value: toml::Value = toml::de::from_str(&toml)?;
+    apply_profile(&mut value)?;
+    Ok(value.try_into()?)
 }
 
 fn do_save<T: Serialize>(value: &T, path: &Path) -> Result<(), Error> {
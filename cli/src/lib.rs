@@ -30,6 +30,9 @@
 //!   mainly to sign transactions generated by the node. Both secret keys may be encrypted with a
 //!   password. The public part of the node configuration must be spread among all nodes, while the
 //!   secret part must be only accessible by the node administrator only.
+//!   With the `--profile` option, node-local parameters (networking, memory pool, storage
+//!   and API limits) are populated with coherent defaults for a common deployment shape,
+//!   such as `validator-wan` or `api-replica`.
 //! 3. Generate final node configuration using `finalize` command. Exonum combines secret part of
 //!   the node configuration with public configurations of every other node, producing a single
 //!   configuration file with all the necessary node and network settings.
@@ -45,8 +48,22 @@
 //!
 //! - `run-dev` command automatically generates network configuration with a single node and runs
 //!   it. This command can be useful for fast testing of the services during development process.
-//! - `maintenance` command allows to clear node's consensus messages with `clear-cache`, and
-//!   restart node's service migration script with `restart-migration`.
+//! - `maintenance` command allows to clear node's consensus messages with `clear-cache`,
+//!   restart node's service migration script with `restart-migration`, copy the node
+//!   database into a new location with `copy-db`, compact the database with `compact-db`,
+//!   and remove data of the indexes no longer tracked by the database with `gc-db`.
+//!   The integrity of the database state can be checked with `verify-db`; values
+//!   of a database encrypted at rest can be re-encrypted with the active key with `reencrypt-db`.
+//! - `preflight` command checks whether the node is ready for the upgrades pending
+//!   in the supervisor (artifact deployments, service migrations and configuration proposals):
+//!   whether the required artifacts and runtimes are available and whether there is
+//!   enough disk space. With the `--attest` flag, the results of the checks are recorded
+//!   on chain, so that unprepared validators can be found before the upgrade.
+//! - `selftest` command runs the node with the built-in services on an ephemeral single-validator
+//!   network, waits for several blocks, and performs smoke checks of the API and the services.
+//!   If a node configuration file is specified, its consensus, storage and networking parameters
+//!   are used, so that the command can validate a combination of the node binary and config
+//!   before joining the production network.
 //!
 //! ## How to Extend Parameters
 //!
@@ -131,10 +148,10 @@ pub use crate::{
 pub use exonum_rust_runtime::spec::Spec;
 pub use structopt;
 
+use anyhow::{bail, ensure};
 use exonum::{
     blockchain::config::{GenesisConfig, GenesisConfigBuilder},
-    merkledb::RocksDB,
-    runtime::{RuntimeInstance, WellKnownRuntime},
+    runtime::{RuntimeIdentifier, RuntimeInstance, WellKnownRuntime},
 };
 use exonum_explorer_service::ExplorerFactory;
 use exonum_node::{Node, NodeBuilder as CoreNodeBuilder};
@@ -146,12 +163,15 @@ use tempfile::TempDir;
 
 use std::{env, ffi::OsString, iter, path::PathBuf};
 
-use crate::command::{Command, ExonumCommand, NodeRunConfig, StandardResult};
+use crate::command::{
+    Command, ExonumCommand, NodeRunConfig, PreflightConfig, SelfTestConfig, StandardResult,
+};
 
 pub mod command;
 pub mod config;
 mod io;
 pub mod password;
+pub mod secret_store;
 
 mod config_manager;
 
@@ -246,41 +266,35 @@ impl NodeBuilder {
     /// - `Ok(None)` if the command executed successfully and did not lead to node creation
     /// - `Err(_)` if an error occurred during command execution
     #[doc(hidden)] // unstable
-    pub fn execute_command(mut self) -> anyhow::Result<Option<Node>> {
+    pub fn execute_command(self) -> anyhow::Result<Option<Node>> {
+        match self.execute_command_inner()? {
+            CommandOutput::Node(node) => Ok(Some(node)),
+            CommandOutput::SelfTest(..) => {
+                bail!("`selftest` command can only be executed with `NodeBuilder::run`")
+            }
+            CommandOutput::None => Ok(None),
+        }
+    }
+
+    fn execute_command_inner(self) -> anyhow::Result<CommandOutput> {
         let command = self
             .args
+            .clone()
             .map_or_else(Command::from_args, Command::from_iter);
 
-        if let StandardResult::Run(run_config) = command.execute()? {
-            // Deploy "default" services (supervisor and the explorer).
-            let supervisor = Self::supervisor_service(&run_config);
-            supervisor.deploy(&mut self.genesis_config, &mut self.rust_runtime);
-            Spec::new(ExplorerFactory)
-                .with_default_instance()
-                .deploy(&mut self.genesis_config, &mut self.rust_runtime);
-
-            let genesis_config = Self::genesis_config(&run_config, self.genesis_config);
-            let db_options = &run_config.node_config.private_config.database;
-            let database = RocksDB::open(run_config.db_path, db_options)?;
-
-            let node_config_path = run_config.node_config_path.to_string_lossy();
-            let config_manager = DefaultConfigManager::new(node_config_path.into_owned());
-            let rust_runtime = self.rust_runtime;
-
-            let node_config = run_config.node_config.into();
-            let node_keys = run_config.node_keys;
-
-            let mut node_builder = CoreNodeBuilder::new(database, node_config, node_keys)
-                .with_genesis_config(genesis_config)
-                .with_config_manager(config_manager)
-                .with_plugin(SystemApiPlugin)
-                .with_runtime_fn(|channel| rust_runtime.build(channel.endpoints_sender()));
-            for runtime in self.external_runtimes {
-                node_builder = node_builder.with_runtime(runtime);
+        match command.execute()? {
+            StandardResult::Preflight(preflight_config) => {
+                self.preflight(&preflight_config)?;
+                Ok(CommandOutput::None)
             }
-            Ok(Some(node_builder.build()))
-        } else {
-            Ok(None)
+            StandardResult::Run(run_config) => {
+                Ok(CommandOutput::Node(self.build_node(&run_config)?))
+            }
+            StandardResult::SelfTest(self_test_config) => {
+                let node = self.build_node(&self_test_config.run_config)?;
+                Ok(CommandOutput::SelfTest(self_test_config, node))
+            }
+            _ => Ok(CommandOutput::None),
         }
     }
 
@@ -288,11 +302,99 @@ impl NodeBuilder {
     pub async fn run(mut self) -> anyhow::Result<()> {
         // Store temporary directory until the node is done.
         let _temp_dir = self.temp_dir.take();
-        if let Some(node) = self.execute_command()? {
-            node.run().await
+        match self.execute_command_inner()? {
+            CommandOutput::Node(node) => node.run().await,
+            CommandOutput::SelfTest(config, node) => {
+                let report = config.run(node).await?;
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                ensure!(report.is_ok(), "Some of the self-test checks have failed");
+                Ok(())
+            }
+            CommandOutput::None => Ok(()),
+        }
+    }
+
+    /// Builds the node with the runtimes and services added to the builder
+    /// and the default services (supervisor and the explorer).
+    fn build_node(mut self, run_config: &NodeRunConfig) -> anyhow::Result<Node> {
+        let supervisor = Self::supervisor_service(run_config);
+        supervisor.deploy(&mut self.genesis_config, &mut self.rust_runtime);
+        Spec::new(ExplorerFactory)
+            .with_default_instance()
+            .deploy(&mut self.genesis_config, &mut self.rust_runtime);
+
+        let genesis_config = Self::genesis_config(run_config, self.genesis_config);
+        let db_options = run_config.node_config.private_config.database.clone();
+
+        let node_config_path = run_config.node_config_path.to_string_lossy();
+        let config_manager = DefaultConfigManager::new(node_config_path.into_owned());
+        let rust_runtime = self.rust_runtime;
+
+        let node_config = run_config.node_config.clone().into();
+        let node_keys = run_config.node_keys.clone();
+
+        let node_builder = if let Some(secret) = &run_config.data_encryption_secret {
+            CoreNodeBuilder::with_encrypted_rocksdb(
+                &run_config.db_path,
+                &db_options,
+                secret,
+                node_config,
+                node_keys,
+            )?
         } else {
-            Ok(())
+            CoreNodeBuilder::with_rocksdb(&run_config.db_path, &db_options, node_config, node_keys)?
+        };
+        let mut node_builder = node_builder
+            .with_genesis_config(genesis_config)
+            .with_config_manager(config_manager)
+            .with_plugin(SystemApiPlugin)
+            .with_runtime_fn(|channel| rust_runtime.build(channel.endpoints_sender()));
+        for runtime in self.external_runtimes {
+            node_builder = node_builder.with_runtime(runtime);
         }
+        Ok(node_builder.build())
+    }
+
+    /// Checks the node readiness for pending upgrades against the runtimes and artifacts
+    /// added to the builder, and prints the report.
+    fn preflight(self, config: &PreflightConfig) -> anyhow::Result<()> {
+        // Default services are available on every node.
+        let rust_runtime = self
+            .rust_runtime
+            .with_factory(Supervisor)
+            .with_factory(ExplorerFactory);
+        let external_runtimes: Vec<_> = self.external_runtimes.iter().map(|rt| rt.id).collect();
+
+        let report = config.check(|artifact| {
+            if artifact.runtime_id == RuntimeIdentifier::Rust as u32 {
+                if rust_runtime.has_artifact(artifact) {
+                    return Ok(());
+                }
+                Err(format!(
+                    "Artifact `{}` is not available on the node",
+                    artifact
+                ))
+            } else if external_runtimes.contains(&artifact.runtime_id) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Runtime with ID {} required by artifact `{}` is not available on the node",
+                    artifact.runtime_id, artifact
+                ))
+            }
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+
+        if config.attest {
+            for tx_hash in config.attest(&report)? {
+                println!("Sent readiness attestation {}", tx_hash);
+            }
+        }
+        ensure!(
+            report.is_ready(),
+            "Node is not ready for some of the pending upgrades"
+        );
+        Ok(())
     }
 
     fn genesis_config(run_config: &NodeRunConfig, builder: GenesisConfigBuilder) -> GenesisConfig {
@@ -313,3 +415,14 @@ impl NodeBuilder {
         Supervisor::builtin_instance(SupervisorConfig::new(mode))
     }
 }
+
+/// Outcome of a command executed by the `NodeBuilder`.
+#[derive(Debug)]
+enum CommandOutput {
+    /// Node to run.
+    Node(Node),
+    /// Node to test with the `selftest` command.
+    SelfTest(Box<SelfTestConfig>, Node),
+    /// The command has finished.
+    None,
+}
@@ -0,0 +1,618 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pluggable storage for the secret material of the node.
+//!
+//! Consensus and service keys of the node, as well as the secret used for the encryption
+//! of the node database at rest, are derived from a single master seed.
+//! [`SecretStore`] abstracts away the way this seed is obtained. The store used by the node
+//! is selected with the `secret_store` section of the private node configuration:
+//!
+//! ```toml
+//! # Encrypted master key file specified by `master_key_path` (the default).
+//! [secret_store]
+//! type = "file"
+//!
+//! # Hex-encoded master seed in an environment variable.
+//! [secret_store]
+//! type = "env"
+//! variable = "EXONUM_MASTER_KEY"
+//!
+//! # Hex-encoded master seed in a HashiCorp Vault secret. The access token
+//! # is read from the `VAULT_TOKEN` environment variable.
+//! [secret_store]
+//! type = "vault"
+//! address = "https://vault.example.com:8200"
+//! path = "secret/data/exonum/node0"
+//! field = "master_key"
+//!
+//! # Master seed encrypted by a key management service. The ciphertext is decrypted
+//! # with the AWS CLI by default.
+//! [secret_store]
+//! type = "kms"
+//! ciphertext_path = "master_key.enc"
+//! ```
+//!
+//! [`SecretStore`]: trait.SecretStore.html
+
+use anyhow::{anyhow, ensure, Context, Error};
+use exonum::keys::{
+    data_encryption_secret_from_master_seed, keys_from_master_seed,
+    read_data_encryption_secret_from_file, read_keys_from_file, DataEncryptionSecret, Keys,
+};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::password::{PassInputMethod, Passphrase, PassphraseUsage};
+
+/// Default name of the environment variable with a hex-encoded master seed.
+pub const DEFAULT_MASTER_KEY_ENV_VAR: &str = "EXONUM_MASTER_KEY";
+/// Default name of the environment variable with a Vault access token.
+pub const DEFAULT_VAULT_TOKEN_ENV_VAR: &str = "VAULT_TOKEN";
+/// Placeholder in the KMS decryption command replaced with the path to the ciphertext.
+pub const KMS_CIPHERTEXT_PLACEHOLDER: &str = "{ciphertext_path}";
+
+/// Storage of the secret material of the node.
+pub trait SecretStore: fmt::Debug {
+    /// Loads the node keys from the store.
+    fn load_keys(&self) -> Result<Keys, Error>;
+
+    /// Loads the secret used to derive the keys for the encryption of the node database
+    /// at rest. The secret is independent of the node keys.
+    fn load_data_encryption_secret(&self) -> Result<DataEncryptionSecret, Error>;
+}
+
+/// Configuration of the secret store used by the node.
+///
+/// Relative paths are resolved relative to the directory containing the node configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum SecretStoreConfig {
+    /// Master key is stored in the encrypted file specified by `master_key_path`
+    /// of the private node configuration.
+    File,
+    /// Hex-encoded master seed is stored in an environment variable.
+    Env {
+        /// Name of the environment variable.
+        #[serde(default = "default_master_key_env_var")]
+        variable: String,
+    },
+    /// Hex-encoded master seed is stored in a Vault secret.
+    Vault {
+        /// Address of the Vault server, e.g., `https://127.0.0.1:8200`.
+        address: String,
+        /// API path to the secret, e.g., `secret/data/exonum/node0` for the KV v2 engine.
+        path: String,
+        /// Name of the field in the secret containing the master seed.
+        #[serde(default = "default_vault_field")]
+        field: String,
+        /// Name of the environment variable with the Vault access token.
+        #[serde(default = "default_vault_token_env_var")]
+        token_variable: String,
+    },
+    /// Master seed is encrypted by a key management service.
+    Kms {
+        /// Path to the file with the encrypted master seed.
+        ciphertext_path: PathBuf,
+        /// Command decrypting the master seed and printing it base64-encoded to stdout.
+        /// The `{ciphertext_path}` placeholder in the arguments is replaced with the path
+        /// to the ciphertext. By default, the AWS CLI is used.
+        #[serde(default = "default_kms_command")]
+        command: Vec<String>,
+    },
+}
+
+impl Default for SecretStoreConfig {
+    fn default() -> Self {
+        Self::File
+    }
+}
+
+fn default_master_key_env_var() -> String {
+    DEFAULT_MASTER_KEY_ENV_VAR.to_owned()
+}
+
+fn default_vault_field() -> String {
+    "master_key".to_owned()
+}
+
+fn default_vault_token_env_var() -> String {
+    DEFAULT_VAULT_TOKEN_ENV_VAR.to_owned()
+}
+
+fn default_kms_command() -> Vec<String> {
+    let ciphertext_arg = format!("fileb://{}", KMS_CIPHERTEXT_PLACEHOLDER);
+    [
+        "aws",
+        "kms",
+        "decrypt",
+        "--ciphertext-blob",
+        &ciphertext_arg,
+        "--output",
+        "text",
+        "--query",
+        "Plaintext",
+    ]
+    .iter()
+    .map(|&arg| arg.to_owned())
+    .collect()
+}
+
+impl SecretStoreConfig {
+    /// Creates a secret store from this configuration.
+    ///
+    /// The passphrase for the master key is requested only for the `File` store.
+    pub fn create_store(
+        &self,
+        config_dir: &Path,
+        master_key_path: &Path,
+        master_key_pass: PassInputMethod,
+    ) -> Result<Box<dyn SecretStore>, Error> {
+        Ok(match self {
+            Self::File => {
+                let passphrase = master_key_pass.get_passphrase(PassphraseUsage::Using)?;
+                Box::new(FileSecretStore::new(
+                    resolve_path(config_dir, master_key_path),
+                    passphrase,
+                ))
+            }
+            Self::Env { variable } => Box::new(EnvSecretStore::new(variable.clone())),
+            Self::Vault {
+                address,
+                path,
+                field,
+                token_variable,
+            } => {
+                let token = env::var(token_variable).with_context(|| {
+                    format!(
+                        "Failed to get Vault token from env variable {}",
+                        token_variable
+                    )
+                })?;
+                Box::new(VaultSecretStore::new(
+                    address.clone(),
+                    path.clone(),
+                    field.clone(),
+                    token,
+                ))
+            }
+            Self::Kms {
+                ciphertext_path,
+                command,
+            } => Box::new(KmsSecretStore::new(
+                resolve_path(config_dir, ciphertext_path),
+                command.clone(),
+            )?),
+        })
+    }
+}
+
+fn resolve_path(config_dir: &Path, path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_owned()
+    } else {
+        config_dir.join(path)
+    }
+}
+
+fn decode_hex_seed(encoded_seed: &str) -> Result<Zeroizing<Vec<u8>>, Error> {
+    hex::decode(encoded_seed.trim())
+        .map(Zeroizing::new)
+        .context("Master seed is not a valid hex string")
+}
+
+/// Secret store reading the master key from the encrypted file.
+pub struct FileSecretStore {
+    path: PathBuf,
+    passphrase: Passphrase,
+}
+
+impl fmt::Debug for FileSecretStore {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("FileSecretStore")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl FileSecretStore {
+    /// Creates a store for the master key file protected with the provided passphrase.
+    pub fn new(path: impl Into<PathBuf>, passphrase: Passphrase) -> Self {
+        Self {
+            path: path.into(),
+            passphrase,
+        }
+    }
+}
+
+impl SecretStore for FileSecretStore {
+    fn load_keys(&self) -> Result<Keys, Error> {
+        read_keys_from_file(&self.path, self.passphrase.as_bytes()).with_context(|| {
+            format!(
+                "Could not read master key from file {}",
+                self.path.display()
+            )
+        })
+    }
+
+    fn load_data_encryption_secret(&self) -> Result<DataEncryptionSecret, Error> {
+        read_data_encryption_secret_from_file(&self.path, self.passphrase.as_bytes()).with_context(
+            || {
+                format!(
+                    "Could not read master key from file {}",
+                    self.path.display()
+                )
+            },
+        )
+    }
+}
+
+/// Secret store reading the hex-encoded master seed from an environment variable.
+#[derive(Debug)]
+pub struct EnvSecretStore {
+    variable: String,
+}
+
+impl EnvSecretStore {
+    /// Creates a store for the specified environment variable.
+    pub fn new(variable: impl Into<String>) -> Self {
+        Self {
+            variable: variable.into(),
+        }
+    }
+
+    fn load_seed(&self) -> Result<Zeroizing<Vec<u8>>, Error> {
+        let encoded_seed = Zeroizing::new(env::var(&self.variable).with_context(|| {
+            format!(
+                "Failed to get master seed from env variable {}",
+                self.variable
+            )
+        })?);
+        decode_hex_seed(&encoded_seed)
+    }
+}
+
+impl SecretStore for EnvSecretStore {
+    fn load_keys(&self) -> Result<Keys, Error> {
+        keys_from_master_seed(&self.load_seed()?)
+    }
+
+    fn load_data_encryption_secret(&self) -> Result<DataEncryptionSecret, Error> {
+        data_encryption_secret_from_master_seed(&self.load_seed()?)
+    }
+}
+
+/// Secret store reading the hex-encoded master seed from a [Vault] secret.
+///
+/// Both KV v1 and KV v2 secret engines are supported.
+///
+/// [Vault]: https://www.vaultproject.io/
+pub struct VaultSecretStore {
+    address: String,
+    path: String,
+    field: String,
+    token: Zeroizing<String>,
+}
+
+impl fmt::Debug for VaultSecretStore {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("VaultSecretStore")
+            .field("address", &self.address)
+            .field("path", &self.path)
+            .field("field", &self.field)
+            .finish()
+    }
+}
+
+impl VaultSecretStore {
+    /// Creates a store for the secret at the specified Vault server and path.
+    pub fn new(
+        address: impl Into<String>,
+        path: impl Into<String>,
+        field: impl Into<String>,
+        token: impl Into<String>,
+    ) -> Self {
+        Self {
+            address: address.into(),
+            path: path.into(),
+            field: field.into(),
+            token: Zeroizing::new(token.into()),
+        }
+    }
+
+    fn secret_url(&self) -> String {
+        format!(
+            "{}/v1/{}",
+            self.address.trim_end_matches('/'),
+            self.path.trim_start_matches('/')
+        )
+    }
+
+    fn load_seed(&self) -> Result<Zeroizing<Vec<u8>>, Error> {
+        let url = self.secret_url();
+        let response: serde_json::Value = ureq::get(&url)
+            .set("X-Vault-Token", &self.token)
+            .call()
+            .with_context(|| format!("Failed to read secret from Vault at {}", url))?
+            .into_json()
+            .context("Failed to parse Vault response")?;
+
+        let encoded_seed = vault_secret_field(&response, &self.field)
+            .ok_or_else(|| anyhow!("Vault secret at {} has no field `{}`", url, self.field))?;
+        decode_hex_seed(encoded_seed)
+    }
+}
+
+/// Extracts a string field from the response of the Vault KV v1 or KV v2 secret engine.
+fn vault_secret_field<'a>(response: &'a serde_json::Value, field: &str) -> Option<&'a str> {
+    // KV v2 engine wraps secret data into an additional `data` object.
+    let data = &response["data"];
+    data["data"][field]
+        .as_str()
+        .or_else(|| data[field].as_str())
+}
+
+impl SecretStore for VaultSecretStore {
+    fn load_keys(&self) -> Result<Keys, Error> {
+        keys_from_master_seed(&self.load_seed()?)
+    }
+
+    fn load_data_encryption_secret(&self) -> Result<DataEncryptionSecret, Error> {
+        data_encryption_secret_from_master_seed(&self.load_seed()?)
+    }
+}
+
+/// Secret store decrypting the master seed with a key management service.
+///
+/// Decryption is performed by an external command (e.g., the AWS CLI), which should print
+/// the base64-encoded master seed to stdout.
+#[derive(Debug)]
+pub struct KmsSecretStore {
+    ciphertext_path: PathBuf,
+    command: Vec<String>,
+}
+
+impl KmsSecretStore {
+    /// Creates a store for the specified ciphertext and decryption command.
+    pub fn new(ciphertext_path: impl Into<PathBuf>, command: Vec<String>) -> Result<Self, Error> {
+        ensure!(!command.is_empty(), "KMS decryption command is empty");
+        Ok(Self {
+            ciphertext_path: ciphertext_path.into(),
+            command,
+        })
+    }
+
+    fn load_seed(&self) -> Result<Zeroizing<Vec<u8>>, Error> {
+        let ciphertext_path = self.ciphertext_path.to_string_lossy();
+        let args = self.command[1..]
+            .iter()
+            .map(|arg| arg.replace(KMS_CIPHERTEXT_PLACEHOLDER, &ciphertext_path));
+        let output = Command::new(&self.command[0])
+            .args(args)
+            .output()
+            .with_context(|| format!("Failed to run KMS command `{}`", self.command[0]))?;
+        ensure!(
+            output.status.success(),
+            "KMS command `{}` failed ({}): {}",
+            self.command[0],
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+
+        let stdout = Zeroizing::new(output.stdout);
+        let encoded_seed = std::str::from_utf8(&stdout)
+            .context("KMS command output is not a valid UTF-8 string")?;
+        base64::decode(encoded_seed.trim())
+            .map(Zeroizing::new)
+            .context("KMS command output is not a valid base64 string")
+    }
+}
+
+impl SecretStore for KmsSecretStore {
+    fn load_keys(&self) -> Result<Keys, Error> {
+        keys_from_master_seed(&self.load_seed()?)
+    }
+
+    fn load_data_encryption_secret(&self) -> Result<DataEncryptionSecret, Error> {
+        data_encryption_secret_from_master_seed(&self.load_seed()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum::keys::generate_keys;
+    use tempfile::tempdir;
+
+    use std::{env, path::Path};
+
+    use super::{
+        vault_secret_field, EnvSecretStore, FileSecretStore, KmsSecretStore, SecretStore,
+        SecretStoreConfig, KMS_CIPHERTEXT_PLACEHOLDER,
+    };
+    use crate::password::{PassInputMethod, Passphrase};
+
+    #[test]
+    fn secret_store_config_serialization() {
+        let config: SecretStoreConfig = toml::from_str("type = \"file\"").unwrap();
+        assert_eq!(config, SecretStoreConfig::File);
+
+        let config: SecretStoreConfig = toml::from_str("type = \"env\"").unwrap();
+        assert_eq!(
+            config,
+            SecretStoreConfig::Env {
+                variable: "EXONUM_MASTER_KEY".to_owned()
+            }
+        );
+
+        let config: SecretStoreConfig = toml::from_str(
+            r#"
+            type = "vault"
+            address = "https://127.0.0.1:8200"
+            path = "secret/data/node0"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            SecretStoreConfig::Vault {
+                address: "https://127.0.0.1:8200".to_owned(),
+                path: "secret/data/node0".to_owned(),
+                field: "master_key".to_owned(),
+                token_variable: "VAULT_TOKEN".to_owned(),
+            }
+        );
+
+        let config: SecretStoreConfig =
+            toml::from_str("type = \"kms\"\nciphertext_path = \"key.enc\"").unwrap();
+        match config {
+            SecretStoreConfig::Kms { command, .. } => assert_eq!(command[0], "aws"),
+            other => panic!("Unexpected config: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn file_secret_store() {
+        let dir = tempdir().unwrap();
+        let keys = generate_keys(dir.path().join("master.key.toml"), b"passphrase").unwrap();
+
+        let store = SecretStoreConfig::File
+            .create_store(
+                dir.path(),
+                Path::new("master.key.toml"),
+                PassInputMethod::CmdLineParameter(Passphrase::new("passphrase".to_owned())),
+            )
+            .unwrap();
+        assert_eq!(store.load_keys().unwrap(), keys);
+        let secret = store.load_data_encryption_secret().unwrap();
+        assert_ne!(secret.as_bytes(), &keys.consensus_sk().as_ref()[..32]);
+
+        let store = FileSecretStore::new(
+            dir.path().join("master.key.toml"),
+            Passphrase::new("wrong".to_owned()),
+        );
+        assert!(store.load_keys().is_err());
+        assert!(store.load_data_encryption_secret().is_err());
+    }
+
+    #[test]
+    fn env_secret_store() {
+        const VAR_NAME: &str = "EXONUM_TEST_SECRET_STORE_SEED";
+
+        let seed = [1_u8; 32];
+        let expected_keys = exonum::keys::keys_from_master_seed(&seed).unwrap();
+        let expected_secret = exonum::keys::data_encryption_secret_from_master_seed(&seed).unwrap();
+        let store = EnvSecretStore::new(VAR_NAME);
+        assert!(store.load_keys().is_err());
+
+        env::set_var(VAR_NAME, hex::encode(seed));
+        assert_eq!(store.load_keys().unwrap(), expected_keys);
+        let secret = store.load_data_encryption_secret().unwrap();
+        assert_eq!(secret.as_bytes(), expected_secret.as_bytes());
+        env::set_var(VAR_NAME, "not a hex string");
+        assert!(store.load_keys().is_err());
+        env::remove_var(VAR_NAME);
+    }
+
+    #[test]
+    fn vault_kv_responses() {
+        let seed = hex::encode([2_u8; 32]);
+        let kv1_response = format!(
+            r#"{{
+                "request_id": "5f4b0ad1-2e47-4d4b-9a0b-0d4bd1c4a7a1",
+                "lease_duration": 2764800,
+                "renewable": false,
+                "data": {{ "master_key": "{}", "other": "value" }}
+            }}"#,
+            seed
+        );
+        let response: serde_json::Value = serde_json::from_str(&kv1_response).unwrap();
+        assert_eq!(vault_secret_field(&response, "master_key"), Some(&*seed));
+        assert_eq!(vault_secret_field(&response, "missing"), None);
+
+        let kv2_response = format!(
+            r#"{{
+                "request_id": "9b3c5f0e-8b57-4f1a-8a7e-3d1c9d2b6f40",
+                "lease_duration": 0,
+                "renewable": false,
+                "data": {{
+                    "data": {{ "master_key": "{}" }},
+                    "metadata": {{
+                        "created_time": "2020-05-12T09:44:31.428374Z",
+                        "deletion_time": "",
+                        "destroyed": false,
+                        "version": 3
+                    }}
+                }}
+            }}"#,
+            seed
+        );
+        let response: serde_json::Value = serde_json::from_str(&kv2_response).unwrap();
+        assert_eq!(vault_secret_field(&response, "master_key"), Some(&*seed));
+        assert_eq!(vault_secret_field(&response, "metadata"), None);
+
+        // Non-string fields are not accepted.
+        let response = serde_json::json!({ "data": { "master_key": 42 } });
+        assert_eq!(vault_secret_field(&response, "master_key"), None);
+        let response = serde_json::json!({ "errors": ["permission denied"] });
+        assert_eq!(vault_secret_field(&response, "master_key"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn kms_secret_store() {
+        let seed = [3_u8; 32];
+        let expected_keys = exonum::keys::keys_from_master_seed(&seed).unwrap();
+        let dir = tempdir().unwrap();
+        let ciphertext_path = dir.path().join("master_key.enc");
+        std::fs::write(&ciphertext_path, format!("{}\n", base64::encode(seed))).unwrap();
+
+        let stub_command = |script: &str| {
+            let command = vec![
+                "sh".to_owned(),
+                "-c".to_owned(),
+                script.to_owned(),
+                "sh".to_owned(),
+                KMS_CIPHERTEXT_PLACEHOLDER.to_owned(),
+            ];
+            KmsSecretStore::new(&ciphertext_path, command).unwrap()
+        };
+
+        // The stub "decrypts" the ciphertext by printing the file contents.
+        let store = stub_command("cat \"$1\"");
+        assert_eq!(store.load_keys().unwrap(), expected_keys);
+        store.load_data_encryption_secret().unwrap();
+
+        let store = stub_command("echo 'access denied' >&2; exit 3");
+        let err = store.load_keys().unwrap_err();
+        let message = format!("{:#}", err);
+        assert!(message.contains("KMS command `sh` failed"), "{}", message);
+        assert!(message.contains("access denied"), "{}", message);
+
+        let store = stub_command("echo 'not base64!'");
+        let err = store.load_keys().unwrap_err();
+        assert!(format!("{:#}", err).contains("not a valid base64 string"));
+
+        let err = KmsSecretStore::new(&ciphertext_path, vec![]).unwrap_err();
+        assert!(err.to_string().contains("KMS decryption command is empty"));
+    }
+}
@@ -14,7 +14,18 @@
 
 // This is a regression test for exonum configuration.
 
-use exonum::{blockchain::ValidatorKeys, crypto::KeyPair};
+use exonum::{
+    blockchain::ValidatorKeys,
+    crypto::KeyPair,
+    keys::read_keys_from_file,
+    merkledb::{
+        access::CopyAccessExt,
+        migration::{rollback_migration, Migration},
+        orphaned_addresses, BinaryValue, Database, EncryptionOptions, ObjectHash, RocksDB,
+    },
+    messages::SignedMessage,
+    runtime::AnyTx,
+};
 use exonum_supervisor::mode::Mode as SupervisorMode;
 use pretty_assertions::assert_eq;
 use structopt::StructOpt;
@@ -31,9 +42,10 @@ use std::{
 
 use exonum_cli::{
     command::{
-        Command, ExonumCommand, Finalize, GenerateConfig, GenerateTemplate, Run, StandardResult,
+        Command, ExonumCommand, Finalize, GenerateConfig, GenerateTemplate, Run, SignedTransaction,
+        StandardResult, TransactionDescription,
     },
-    config::{GeneralConfig, NodePrivateConfig, NodePublicConfig},
+    config::{GeneralConfig, NodeConfig, NodePrivateConfig, NodeProfile, NodePublicConfig},
     load_config_file,
     password::DEFAULT_MASTER_PASS_ENV_VAR,
     save_config_file,
@@ -286,6 +298,52 @@ fn test_generate_config_key_files() {
     );
 }
 
+#[test]
+fn test_generate_config_with_profile() {
+    let env = ConfigSpec::new_without_pass();
+
+    env.command("generate-config")
+        .with_arg(&env.expected_template_file(SupervisorMode::Simple))
+        .with_arg(&env.output_node_config_dir(0))
+        .with_named_arg("-a", "0.0.0.0:8000")
+        .with_named_arg("--profile", "validator-wan")
+        .with_arg("--no-password")
+        .run()
+        .unwrap();
+
+    let profile = NodeProfile::ValidatorWan;
+    let private_config_path = env.output_private_config(0);
+    let private_config: NodePrivateConfig = load_config_file(&private_config_path).unwrap();
+    assert_eq!(private_config.profile, Some(profile));
+    assert_eq!(private_config.network, profile.network_config());
+    assert_eq!(private_config.mempool, profile.mempool_config());
+
+    // Values missing in the config are taken from the profile, while values
+    // specified explicitly override the profile.
+    let mut raw_config: toml::Value = load_config_file(&private_config_path).unwrap();
+    let raw_table = raw_config.as_table_mut().unwrap();
+    for section in &["api", "mempool", "database"] {
+        raw_table.remove(*section);
+    }
+    let mut network = toml::value::Table::new();
+    network.insert(
+        "max_incoming_connections".to_owned(),
+        toml::Value::Integer(64),
+    );
+    raw_table.insert("network".to_owned(), toml::Value::Table(network));
+    save_config_file(&raw_config, &private_config_path).unwrap();
+
+    let private_config: NodePrivateConfig = load_config_file(&private_config_path).unwrap();
+    assert_eq!(private_config.network.max_incoming_connections, 64);
+    assert_eq!(
+        private_config.network.tcp_keep_alive,
+        profile.network_config().tcp_keep_alive
+    );
+    assert_eq!(private_config.api, profile.api_config());
+    assert_eq!(private_config.mempool, profile.mempool_config());
+    assert_eq!(private_config.database, profile.database_config());
+}
+
 #[test]
 fn master_key_path_current_dir() {
     let env = ConfigSpec::new_without_pass();
@@ -603,6 +661,222 @@ fn test_restart_migration() {
         .unwrap();
 }
 
+#[test]
+fn test_copy_database() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    let target_db_path = env.output_dir().join("db0_copy");
+
+    env.command("maintenance")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .with_arg("copy-db")
+        .with_named_arg("--target-db-path", &target_db_path)
+        .run()
+        .unwrap();
+    assert!(target_db_path.exists());
+}
+
+#[test]
+fn test_compact_database() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+
+    env.command("maintenance")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .with_arg("compact-db")
+        .with_named_arg("--background-jobs", "2")
+        .run()
+        .unwrap();
+}
+
+#[test]
+fn test_collect_garbage() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    let node_config: NodeConfig = load_config_file(env.expected_node_config_file(0)).unwrap();
+    let db_options = &node_config.private_config.database;
+
+    // Seed the database with a tracked index and an index orphaned
+    // by a rolled back migration.
+    let orphaned = {
+        let db = RocksDB::open(&db_path, db_options).unwrap();
+        let fork = db.fork();
+        fork.get_list("tracked").extend(vec![1_u32, 2, 3]);
+        Migration::new("service", &fork)
+            .get_list("list")
+            .extend(vec![4_u32, 5]);
+        db.merge(fork.into_patch()).unwrap();
+
+        let mut fork = db.fork();
+        rollback_migration(&mut fork, "service");
+        db.merge(fork.into_patch()).unwrap();
+
+        let mut orphaned = orphaned_addresses(&db).unwrap();
+        assert_eq!(orphaned.len(), 1);
+        assert!(db.snapshot().iter(&orphaned[0], &[]).next().is_some());
+        orphaned.pop().unwrap()
+    };
+
+    env.command("maintenance")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .with_arg("gc-db")
+        .with_arg("--compact")
+        .run()
+        .unwrap();
+
+    let db = RocksDB::open(&db_path, db_options).unwrap();
+    assert!(orphaned_addresses(&db).unwrap().is_empty());
+    let snapshot = db.snapshot();
+    assert!(snapshot.iter(&orphaned, &[]).next().is_none());
+    // Data of the tracked indexes is not affected.
+    let tracked = snapshot.get_list::<_, u32>("tracked");
+    assert_eq!(tracked.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_verify_database() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    // Create the database, since `verify-db` opens it in the read-only mode.
+    env.command("maintenance")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .with_arg("clear-cache")
+        .run()
+        .unwrap();
+
+    env.command("maintenance")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .with_arg("verify-db")
+        .run()
+        .unwrap();
+}
+
+#[test]
+fn test_encrypted_database_maintenance() {
+    let env = ConfigSpec::new_without_pass();
+    env.copy_node_config_to_output(0);
+    let node_config_path = env.output_node_config(0);
+    let db_path = env.output_dir().join("db0");
+    let mut node_config: NodeConfig = load_config_file(env.expected_node_config_file(0)).unwrap();
+    node_config.private_config.database.encryption = Some(EncryptionOptions::new(0));
+    save_config_file(&node_config, &node_config_path).unwrap();
+
+    let maintenance = |action: &str, pass: &str| {
+        env.command("maintenance")
+            .with_named_arg("--node-config", &node_config_path)
+            .with_named_arg("--db-path", &db_path)
+            .with_named_arg("--master-key-pass", pass)
+            .with_arg(action)
+            .run()
+    };
+    maintenance("clear-cache", "pass:").unwrap();
+    maintenance("gc-db", "pass:").unwrap();
+    // The data encryption secret is derived from the master key.
+    maintenance("clear-cache", "pass:wrong").unwrap_err();
+
+    // Rotate the encryption key.
+    let encryption = EncryptionOptions::new(1).with_retired_key_version(0);
+    node_config.private_config.database.encryption = Some(encryption);
+    save_config_file(&node_config, &node_config_path).unwrap();
+    maintenance("reencrypt-db", "pass:").unwrap();
+
+    // After re-encryption, the retired key is no longer necessary.
+    node_config.private_config.database.encryption = Some(EncryptionOptions::new(1));
+    save_config_file(&node_config, &node_config_path).unwrap();
+    maintenance("verify-db", "pass:").unwrap();
+}
+
+#[test]
+fn test_preflight() {
+    let env = ConfigSpec::new_without_pass();
+    let db_path = env.output_dir().join("db0");
+    // Create the database, since `preflight` opens it in the read-only mode.
+    env.command("maintenance")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .with_arg("clear-cache")
+        .run()
+        .unwrap();
+
+    let result = env
+        .command("preflight")
+        .with_named_arg("--node-config", &env.expected_node_config_file(0))
+        .with_named_arg("--db-path", &db_path)
+        .run()
+        .unwrap();
+    let config = match result {
+        StandardResult::Preflight(config) => config,
+        other => panic!("Unexpected command result: {:?}", other),
+    };
+    assert!(config.upgrades.is_empty());
+    assert!(!config.attest);
+    assert_eq!(config.min_free_space, 1_024 << 20);
+
+    let report = config.check(|_| Err("Unknown artifact".to_owned()));
+    assert!(report.is_ready());
+    assert_eq!(report.attestations().count(), 0);
+}
+
+#[test]
+fn test_sign_tx() {
+    let env = ConfigSpec::new_without_pass();
+    env.copy_node_config_to_output(0);
+    let master_key_path = env.output_node_config_dir(0).join("master.key.toml");
+    let transactions_path = env.output_dir().join("transactions.json");
+    let output_path = env.output_dir().join("signed.json");
+    let transactions = vec![
+        TransactionDescription::new(100, 0, &[10, 11, 12]),
+        TransactionDescription::new(101, 2, &[]),
+    ];
+    fs::write(
+        &transactions_path,
+        serde_json::to_vec(&transactions).unwrap(),
+    )
+    .unwrap();
+
+    let result = env
+        .command("sign-tx")
+        .with_arg(&transactions_path)
+        .with_named_arg("--master-key-path", &master_key_path)
+        .with_named_arg("--master-key-pass", "pass:")
+        .with_named_arg("-o", &output_path)
+        .run()
+        .unwrap();
+    match result {
+        StandardResult::SignTx {
+            output_path: path,
+            transactions_count,
+        } => {
+            assert_eq!(path, output_path);
+            assert_eq!(transactions_count, 2);
+        }
+        other => panic!("Unexpected command result: {:?}", other),
+    }
+
+    let keys = read_keys_from_file(&master_key_path, "").unwrap();
+    let signed: Vec<SignedTransaction> =
+        serde_json::from_slice(&fs::read(&output_path).unwrap()).unwrap();
+    assert_eq!(signed.len(), transactions.len());
+    for (signed, description) in signed.iter().zip(&transactions) {
+        let bytes = hex::decode(&signed.tx_body).unwrap();
+        let tx = SignedMessage::from_bytes(bytes.into())
+            .unwrap()
+            .into_verified::<AnyTx>()
+            .unwrap();
+        assert_eq!(tx.object_hash(), signed.tx_hash);
+        assert_eq!(tx.author(), keys.service.public_key());
+        let call_info = &tx.payload().call_info;
+        assert_eq!(call_info.instance_id, description.instance_id);
+        assert_eq!(call_info.method_id, description.method_id);
+        assert_eq!(hex::encode(&tx.payload().arguments), description.arguments);
+    }
+}
+
 #[test]
 fn run_node_with_simple_supervisor() {
     run_node_with_supervisor(&SupervisorMode::Simple).unwrap();
@@ -621,6 +895,7 @@ fn different_supervisor_modes_in_public_configs() -> anyhow::Result<()> {
         listen_address: "127.0.0.1:5400".parse().unwrap(),
         external_address: "127.0.0.1:5400".to_string(),
         master_key_path: Default::default(),
+        secret_store: Default::default(),
         api: Default::default(),
         network: Default::default(),
         mempool: Default::default(),
@@ -628,6 +903,11 @@ fn different_supervisor_modes_in_public_configs() -> anyhow::Result<()> {
         thread_pool_size: None,
         connect_list: Default::default(),
         consensus_public_key: KeyPair::random().public_key(),
+        standby: None,
+        bootstrap: None,
+        pruning: None,
+        replication: None,
+        profile: None,
     };
 
     let testnet_dir = tempfile::tempdir()?;
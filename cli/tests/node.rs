@@ -167,3 +167,11 @@ async fn node_basic_workflow() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn node_self_test() -> anyhow::Result<()> {
+    NodeBuilder::with_args(vec!["selftest", "--blocks", "2", "--timeout", "30"])
+        .with(Spec::new(SimpleService).with_default_instance())
+        .run()
+        .await
+}
@@ -242,37 +242,59 @@ where
     }
 }
 
-impl<Q, I, F, R> From<NamedWith<Q, I, R, F>> for RequestHandler
-where
-    F: Fn(Q) -> R + 'static + Clone + Send + Sync,
-    Q: DeserializeOwned + 'static,
-    I: Serialize + 'static,
-    R: Future<Output = Result<I, crate::Error>>,
-{
-    fn from(f: NamedWith<Q, I, R, F>) -> Self {
-        let handler = f.inner.handler;
-        let actuality = f.inner.actuality;
-        let mutability = f.mutability;
+impl RequestHandler {
+    /// Creates a handler, which has access to the HTTP request in addition to the query
+    /// extracted from it, e.g., to read request headers.
+    pub fn with_request<Q, I, F, R>(
+        name: impl Into<String>,
+        mutability: EndpointMutability,
+        actuality: Actuality,
+        handler: F,
+    ) -> Self
+    where
+        F: Fn(HttpRequest, Q) -> R + 'static + Clone + Send + Sync,
+        Q: DeserializeOwned + 'static,
+        I: Serialize + 'static,
+        R: Future<Output = Result<I, crate::Error>>,
+    {
         let index = move |request: HttpRequest, payload: Payload| {
             let handler = handler.clone();
             let actuality = actuality.clone();
 
             async move {
-                let query = extract_query(request, payload, mutability).await?;
-                let response = handler(query).await?;
+                let query = extract_query(request.clone(), payload, mutability).await?;
+                let response = handler(request, query).await?;
                 Ok(json_response(actuality, response))
             }
             .boxed_local()
         };
 
         Self {
-            name: f.name,
-            method: f.mutability.into(),
+            name: name.into(),
+            method: mutability.into(),
             inner: Arc::from(index) as Arc<RawHandler>,
         }
     }
 }
 
+impl<Q, I, F, R> From<NamedWith<Q, I, R, F>> for RequestHandler
+where
+    F: Fn(Q) -> R + 'static + Clone + Send + Sync,
+    Q: DeserializeOwned + 'static,
+    I: Serialize + 'static,
+    R: Future<Output = Result<I, crate::Error>>,
+{
+    fn from(f: NamedWith<Q, I, R, F>) -> Self {
+        let handler = f.inner.handler;
+        Self::with_request(
+            f.name,
+            f.mutability,
+            f.inner.actuality,
+            move |_request, query| handler(query),
+        )
+    }
+}
+
 impl From<&AllowOrigin> for Cors {
     fn from(origin: &AllowOrigin) -> Self {
         match *origin {
@@ -72,6 +72,7 @@ pub mod backends;
 mod cors;
 mod error;
 mod manager;
+pub mod query;
 mod with;
 
 use serde::{de::DeserializeOwned, Serialize};
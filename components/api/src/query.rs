@@ -0,0 +1,567 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Common conventions for query parameters of endpoints returning lists of items.
+//!
+//! The module provides building blocks for endpoint queries and responses:
+//!
+//! - [`PageQuery`] and [`Page`] implement cursor-based pagination
+//!   (`cursor` and `limit` query parameters; `items` and `next_cursor` in the response)
+//! - [`SortSpec`] specifies the sort field and [`SortOrder`] (e.g., `sort=-height`)
+//! - [`FieldSet`] implements sparse fieldsets, i.e., selecting a subset of fields
+//!   of the returned items (e.g., `fields=height,tx_count`)
+//!
+//! All types can be embedded into endpoint queries, including via `#[serde(flatten)]`.
+//! Cursors and sort specifiers are encoded as strings, so they may be treated
+//! by clients as opaque values.
+//!
+//! [`PageQuery`]: struct.PageQuery.html
+//! [`Page`]: struct.Page.html
+//! [`SortSpec`]: struct.SortSpec.html
+//! [`SortOrder`]: enum.SortOrder.html
+//! [`FieldSet`]: struct.FieldSet.html
+//!
+//! # Examples
+//!
+//! ```
+//! use exonum_api::{query::{FieldSet, Page, PageQuery, SortOrder}, ApiBuilder};
+//! # use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! pub struct NumbersQuery {
+//!     #[serde(flatten)]
+//!     pub page: PageQuery<u64>,
+//!     pub order: Option<SortOrder>,
+//!     #[serde(default)]
+//!     pub fields: FieldSet,
+//! }
+//!
+//! #[derive(Serialize)]
+//! pub struct Number {
+//!     pub value: u64,
+//!     pub square: u64,
+//! }
+//!
+//! fn numbers(query: NumbersQuery) -> exonum_api::Result<Page<serde_json::Value, u64>> {
+//!     let limit = query.page.limit_or(10, 100)?;
+//!     let start = query.page.cursor.unwrap_or(0);
+//!     let numbers = (start..).map(|value| Number { value, square: value * value });
+//!     let page = Page::take(numbers, limit, |number| number.value);
+//!     page.try_map(|number| query.fields.select(&number))
+//! }
+//!
+//! let mut builder = ApiBuilder::new();
+//! builder
+//!     .public_scope()
+//!     .endpoint("numbers", |query: NumbersQuery| async move { numbers(query) });
+//! ```
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+use crate::Error;
+
+/// Cursor-based pagination parameters of a list endpoint.
+///
+/// The cursor points to the first item of the requested page; it is usually obtained
+/// from the `next_cursor` field of the previous [`Page`]. If the cursor is not specified,
+/// the first page is returned. Both parameters are encoded as strings, but integer values
+/// are accepted as well.
+///
+/// [`Page`]: struct.Page.html
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "C: fmt::Display",
+    deserialize = "C: FromStr, C::Err: fmt::Display"
+))]
+#[non_exhaustive]
+pub struct PageQuery<C> {
+    /// Cursor pointing to the first item of the page.
+    #[serde(
+        default,
+        with = "display_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub cursor: Option<C>,
+    /// Maximum number of items on the page.
+    #[serde(
+        default,
+        with = "display_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub limit: Option<usize>,
+}
+
+impl<C> Default for PageQuery<C> {
+    fn default() -> Self {
+        Self {
+            cursor: None,
+            limit: None,
+        }
+    }
+}
+
+impl<C> PageQuery<C> {
+    /// Creates a query for the first page with the default number of items.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the cursor pointing to the first item of the page.
+    pub fn with_cursor(mut self, cursor: C) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    /// Sets the maximum number of items on the page.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Returns the number of items on the page, or `default_limit` if the limit is not
+    /// specified in the query. Returns an error if the limit is zero or exceeds `max_limit`.
+    pub fn limit_or(&self, default_limit: usize, max_limit: usize) -> crate::Result<usize> {
+        let limit = self.limit.unwrap_or(default_limit);
+        if limit == 0 || limit > max_limit {
+            let detail = format!(
+                "Page limit should be in range 1..={}, got {}",
+                max_limit, limit
+            );
+            return Err(Error::bad_request()
+                .title("Invalid page limit")
+                .detail(detail));
+        }
+        Ok(limit)
+    }
+}
+
+/// Page of items returned by a list endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Serialize, C: fmt::Display",
+    deserialize = "T: Deserialize<'de>, C: FromStr, C::Err: fmt::Display"
+))]
+#[non_exhaustive]
+pub struct Page<T, C> {
+    /// Items on the page.
+    pub items: Vec<T>,
+    /// Cursor pointing to the first item of the next page, or `None` if this page is the last one.
+    #[serde(
+        default,
+        with = "display_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub next_cursor: Option<C>,
+}
+
+impl<T, C> Page<T, C> {
+    /// Creates a page with the specified items and the cursor to the next page.
+    pub fn new(items: Vec<T>, next_cursor: Option<C>) -> Self {
+        Self { items, next_cursor }
+    }
+
+    /// Takes at most `limit` items from the iterator. If the iterator yields more items,
+    /// the next cursor is obtained from the first item not fitting onto the page.
+    pub fn take<I, F>(items: I, limit: usize, cursor: F) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        F: FnOnce(&T) -> C,
+    {
+        let mut items: Vec<_> = items.into_iter().take(limit.saturating_add(1)).collect();
+        let next_cursor = if items.len() > limit {
+            items.pop().as_ref().map(cursor)
+        } else {
+            None
+        };
+        Self { items, next_cursor }
+    }
+
+    /// Converts items on the page, retaining the cursor to the next page.
+    pub fn try_map<U, E, F>(self, f: F) -> Result<Page<U, C>, E>
+    where
+        F: FnMut(T) -> Result<U, E>,
+    {
+        let items = self.items.into_iter().map(f).collect::<Result<_, _>>()?;
+        Ok(Page {
+            items,
+            next_cursor: self.next_cursor,
+        })
+    }
+}
+
+/// Order in which items are sorted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum SortOrder {
+    /// Ascending order.
+    Asc,
+    /// Descending order.
+    Desc,
+}
+
+/// Sort specifier consisting of the field name and the sort order.
+///
+/// The specifier is encoded as the field name, prefixed with `-` for the descending order
+/// (e.g., `height` or `-height`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct SortSpec {
+    /// Name of the field to sort items by.
+    pub field: String,
+    /// Sort order.
+    pub order: SortOrder,
+}
+
+impl SortSpec {
+    /// Creates a new sort specifier.
+    pub fn new(field: impl Into<String>, order: SortOrder) -> Self {
+        Self {
+            field: field.into(),
+            order,
+        }
+    }
+
+    /// Checks that the sort field is one of `allowed_fields`.
+    pub fn check_field(&self, allowed_fields: &[&str]) -> crate::Result<()> {
+        if allowed_fields.contains(&self.field.as_str()) {
+            Ok(())
+        } else {
+            let detail = format!(
+                "Items cannot be sorted by `{}`; supported fields are: {}",
+                self.field,
+                allowed_fields.join(", ")
+            );
+            Err(Error::bad_request()
+                .title("Invalid sort field")
+                .detail(detail))
+        }
+    }
+}
+
+impl fmt::Display for SortSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.order == SortOrder::Desc {
+            f.write_str("-")?;
+        }
+        f.write_str(&self.field)
+    }
+}
+
+impl FromStr for SortSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (field, order) = if let Some(field) = s.strip_prefix('-') {
+            (field, SortOrder::Desc)
+        } else {
+            (s.strip_prefix('+').unwrap_or(s), SortOrder::Asc)
+        };
+        if field.is_empty() {
+            anyhow::bail!("Sort field is empty");
+        }
+        Ok(Self::new(field, order))
+    }
+}
+
+impl Serialize for SortSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SortSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/// Sparse fieldset, i.e., the set of fields to include into the returned items.
+///
+/// The fieldset is encoded as a comma-separated list of field names (e.g., `height,tx_count`).
+/// An empty fieldset selects all fields.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldSet {
+    fields: BTreeSet<String>,
+}
+
+impl FieldSet {
+    /// Creates a fieldset selecting all fields.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Creates a fieldset selecting the specified fields.
+    pub fn new<I>(fields: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            fields: fields.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Checks if the fieldset selects all fields.
+    pub fn is_all(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Checks if the specified field is selected.
+    pub fn contains(&self, field: &str) -> bool {
+        self.is_all() || self.fields.contains(field)
+    }
+
+    /// Checks that all fields in the set are among `allowed_fields`.
+    pub fn check_fields(&self, allowed_fields: &[&str]) -> crate::Result<()> {
+        let unknown: Vec<_> = self
+            .fields
+            .iter()
+            .filter(|field| !allowed_fields.contains(&field.as_str()))
+            .map(String::as_str)
+            .collect();
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            let detail = format!(
+                "Unknown fields: {}; supported fields are: {}",
+                unknown.join(", "),
+                allowed_fields.join(", ")
+            );
+            Err(Error::bad_request()
+                .title("Invalid field selection")
+                .detail(detail))
+        }
+    }
+
+    /// Serializes the item to JSON, retaining only the selected top-level fields.
+    /// Items not serialized as JSON objects are returned as is.
+    pub fn select<T: Serialize>(&self, item: &T) -> crate::Result<Value> {
+        let mut value = serde_json::to_value(item).map_err(Error::internal)?;
+        if let Value::Object(ref mut map) = value {
+            if !self.is_all() {
+                map.retain(|field, _| self.fields.contains(field));
+            }
+        }
+        Ok(value)
+    }
+}
+
+impl fmt::Display for FieldSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            f.write_str(field)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for FieldSet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty());
+        Ok(Self::new(fields))
+    }
+}
+
+impl Serialize for FieldSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for FieldSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/// (De)serialization of optional values via their `Display` / `FromStr` implementations.
+/// Integers are accepted during deserialization as well.
+mod display_option {
+    use serde::{
+        de::{self, Visitor},
+        Deserializer, Serializer,
+    };
+
+    use std::{fmt, marker::PhantomData, str::FromStr};
+
+    pub fn serialize<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: fmt::Display,
+        S: Serializer,
+    {
+        match value {
+            Some(value) => serializer.collect_str(value),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_option(OptionVisitor(PhantomData))
+    }
+
+    struct OptionVisitor<T>(PhantomData<T>);
+
+    impl<'de, T> Visitor<'de> for OptionVisitor<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        type Value = Option<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a string or an integer")
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserializer
+                .deserialize_any(ValueVisitor(PhantomData))
+                .map(Some)
+        }
+    }
+
+    struct ValueVisitor<T>(PhantomData<T>);
+
+    impl<'de, T> Visitor<'de> for ValueVisitor<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        type Value = T;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a string or an integer")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            value
+                .parse()
+                .map_err(|err| E::custom(format!("invalid value `{}`: {}", value, err)))
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+            self.visit_str(&value.to_string())
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+            self.visit_str(&value.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct ItemsQuery {
+        #[serde(flatten)]
+        page: PageQuery<u64>,
+        sort: Option<SortSpec>,
+        #[serde(default)]
+        fields: FieldSet,
+    }
+
+    #[test]
+    fn query_from_url() {
+        let query: ItemsQuery =
+            serde_urlencoded::from_str("cursor=10&limit=5&sort=-height&fields=height,time")
+                .unwrap();
+        assert_eq!(query.page, PageQuery::new().with_cursor(10).with_limit(5));
+        assert_eq!(query.sort, Some(SortSpec::new("height", SortOrder::Desc)));
+        assert_eq!(query.fields, FieldSet::new(vec!["height", "time"]));
+        let url = serde_urlencoded::to_string(&query).unwrap();
+        assert_eq!(url, "cursor=10&limit=5&sort=-height&fields=height%2Ctime");
+
+        let query: ItemsQuery = serde_urlencoded::from_str("").unwrap();
+        assert_eq!(query.page, PageQuery::new());
+        assert_eq!(query.sort, None);
+        assert!(query.fields.is_all());
+
+        let err = serde_urlencoded::from_str::<ItemsQuery>("limit=many").unwrap_err();
+        assert!(err.to_string().contains("invalid value `many`"));
+    }
+
+    #[test]
+    fn query_from_json() {
+        let query: ItemsQuery =
+            serde_json::from_value(json!({ "cursor": "3", "limit": 2, "sort": "height" })).unwrap();
+        assert_eq!(query.page, PageQuery::new().with_cursor(3).with_limit(2));
+        assert_eq!(query.sort, Some(SortSpec::new("height", SortOrder::Asc)));
+    }
+
+    #[test]
+    fn page_limits() {
+        let query = PageQuery::<u64>::new();
+        assert_eq!(query.limit_or(10, 100).unwrap(), 10);
+        assert!(query.clone().with_limit(0).limit_or(10, 100).is_err());
+        assert!(query.with_limit(101).limit_or(10, 100).is_err());
+    }
+
+    #[test]
+    fn page_taking() {
+        let page = Page::take(0_u64..10, 4, |&i| i);
+        assert_eq!(page, Page::new(vec![0, 1, 2, 3], Some(4)));
+        assert_eq!(
+            serde_json::to_value(&page).unwrap(),
+            json!({ "items": [0, 1, 2, 3], "next_cursor": "4" })
+        );
+        let page = Page::take(0_u64..4, 4, |&i| i);
+        assert_eq!(page, Page::new(vec![0, 1, 2, 3], None));
+    }
+
+    #[test]
+    fn selecting_fields() {
+        let item = json!({ "height": 1, "time": 2, "hash": 3 });
+        let fields = FieldSet::new(vec!["height", "hash"]);
+        assert_eq!(
+            fields.select(&item).unwrap(),
+            json!({ "height": 1, "hash": 3 })
+        );
+        assert_eq!(FieldSet::all().select(&item).unwrap(), item);
+        assert!(fields.check_fields(&["height", "hash", "time"]).is_ok());
+        assert!(fields.check_fields(&["height"]).is_err());
+    }
+}
@@ -59,6 +59,7 @@ use self::sodiumoxide::crypto::{
     sign::{convert_sk_to_pk, ed25519},
 };
 
+pub mod secretbox;
 pub mod x25519;
 
 /// Number of bytes in a `Hash`.
@@ -0,0 +1,114 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Symmetric authenticated encryption based on XSalsa20-Poly1305.
+
+// spell-checker:ignore KEYBYTES, NONCEBYTES, xsalsa
+
+use std::fmt;
+
+use super::sodiumoxide::crypto::secretbox::xsalsa20poly1305 as sodium_secretbox;
+use crate::write_short_hex;
+
+/// Length of the secret key.
+pub const KEY_LENGTH: usize = sodium_secretbox::KEYBYTES;
+/// Length of the nonce.
+pub const NONCE_LENGTH: usize = sodium_secretbox::NONCEBYTES;
+
+/// Secret key used for symmetric encryption.
+///
+/// The key is zeroed when dropped.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Key(sodium_secretbox::Key);
+
+impl Key {
+    /// Creates a key from the bytes slice. Returns `None` if the slice has an incorrect length.
+    pub fn from_slice(bytes_slice: &[u8]) -> Option<Self> {
+        sodium_secretbox::Key::from_slice(bytes_slice).map(Self)
+    }
+}
+
+impl AsRef<[u8]> for Key {
+    fn as_ref(&self) -> &[u8] {
+        &(self.0).0[..]
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Key(..)")
+    }
+}
+
+/// Nonce used for symmetric encryption. The same nonce must not be used twice
+/// with the same key.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Nonce(sodium_secretbox::Nonce);
+
+impl Nonce {
+    /// Creates a nonce from the bytes slice. Returns `None` if the slice has an incorrect length.
+    pub fn from_slice(bytes_slice: &[u8]) -> Option<Self> {
+        sodium_secretbox::Nonce::from_slice(bytes_slice).map(Self)
+    }
+}
+
+impl AsRef<[u8]> for Nonce {
+    fn as_ref(&self) -> &[u8] {
+        &(self.0).0[..]
+    }
+}
+
+impl fmt::Debug for Nonce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Nonce(")?;
+        write_short_hex(f, self.as_ref())?;
+        f.write_str(")")
+    }
+}
+
+/// Generates a random secret key.
+pub fn gen_key() -> Key {
+    Key(sodium_secretbox::gen_key())
+}
+
+/// Generates a random nonce.
+pub fn gen_nonce() -> Nonce {
+    Nonce(sodium_secretbox::gen_nonce())
+}
+
+/// Encrypts and authenticates `plaintext` with the specified `nonce` and `key`.
+///
+/// # Examples
+///
+/// ```
+/// use exonum_crypto::secretbox;
+/// # exonum_crypto::init();
+///
+/// let key = secretbox::gen_key();
+/// let nonce = secretbox::gen_nonce();
+/// let ciphertext = secretbox::seal(b"secret", &nonce, &key);
+/// let plaintext = secretbox::open(&ciphertext, &nonce, &key).unwrap();
+/// assert_eq!(plaintext, b"secret");
+/// ```
+pub fn seal(plaintext: &[u8], nonce: &Nonce, key: &Key) -> Vec<u8> {
+    sodium_secretbox::seal(plaintext, &nonce.0, &key.0)
+}
+
+/// Verifies and decrypts `ciphertext` produced by [`seal()`](fn.seal.html).
+/// Returns an error if the ciphertext was not produced with the specified `nonce` and `key`,
+/// or was tampered with.
+#[allow(clippy::result_unit_err)]
+pub fn open(ciphertext: &[u8], nonce: &Nonce, key: &Key) -> Result<Vec<u8>, ()> {
+    sodium_secretbox::open(ciphertext, &nonce.0, &key.0)
+}
@@ -43,7 +43,7 @@ pub use self::crypto_impl::{
     HASH_SIZE, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH, SEED_LENGTH, SIGNATURE_LENGTH,
 };
 #[cfg(feature = "sodiumoxide-crypto")]
-pub use self::crypto_lib::sodiumoxide::x25519;
+pub use self::crypto_lib::sodiumoxide::{secretbox, x25519};
 
 #[cfg(feature = "with-protobuf")]
 #[doc(hidden)]
@@ -26,6 +26,8 @@ use crate::find_meta_attrs;
 struct BinaryValueStruct {
     ident: Ident,
     attrs: BinaryValueAttrs,
+    /// Fields marked with the `sorted` attribute.
+    sorted_fields: Vec<Ident>,
 }
 
 impl FromDeriveInput for BinaryValueStruct {
@@ -34,9 +36,26 @@ impl FromDeriveInput for BinaryValueStruct {
             .map(|meta| BinaryValueAttrs::from_nested_meta(&meta))
             .unwrap_or_else(|| Ok(BinaryValueAttrs::default()))?;
 
+        let mut sorted_fields = vec![];
+        if let Data::Struct(DataStruct { fields, .. }) = &input.data {
+            for field in fields {
+                let field_attrs = find_meta_attrs("binary_value", &field.attrs)
+                    .map(|meta| BinaryValueFieldAttrs::from_nested_meta(&meta))
+                    .unwrap_or_else(|| Ok(BinaryValueFieldAttrs::default()))?;
+                if field_attrs.sorted {
+                    let ident = field.ident.clone().ok_or_else(|| {
+                        darling::Error::custom("`sorted` attribute requires a named field")
+                            .with_span(field)
+                    })?;
+                    sorted_fields.push(ident);
+                }
+            }
+        }
+
         Ok(Self {
             ident: input.ident.clone(),
             attrs,
+            sorted_fields,
         })
     }
 }
@@ -73,6 +92,14 @@ impl FromMeta for Codec {
 struct BinaryValueAttrs {
     #[darling(default)]
     codec: Codec,
+    #[darling(default)]
+    canonical: bool,
+}
+
+#[derive(Debug, Default, FromMeta)]
+struct BinaryValueFieldAttrs {
+    #[darling(default)]
+    sorted: bool,
 }
 
 #[derive(Debug, FromDeriveInput)]
@@ -96,8 +123,52 @@ impl ObjectHashStruct {
 }
 
 impl BinaryValueStruct {
+    /// Generates a check that elements of the `sorted` fields of the decoded `value`
+    /// are strictly increasing, and (for `canonical` types) that the `value` is encoded
+    /// exactly as the `bytes` it was decoded from.
+    fn canonical_check(&self) -> proc_macro2::TokenStream {
+        let name = &self.ident;
+
+        let sorted_checks = self.sorted_fields.iter().map(|field| {
+            quote! {
+                let mut items = std::iter::IntoIterator::into_iter(&value.#field);
+                if let Some(mut prev) = items.next() {
+                    for item in items {
+                        if prev >= item {
+                            return Err(exonum_merkledb::_reexports::Error::msg(concat!(
+                                "Elements of `",
+                                stringify!(#field),
+                                "` in ",
+                                stringify!(#name),
+                                " are not sorted or contain duplicates"
+                            )));
+                        }
+                        prev = item;
+                    }
+                }
+            }
+        });
+        let bytes_check = if self.attrs.canonical {
+            quote! {
+                if exonum_merkledb::BinaryValue::to_bytes(&value) != bytes.as_ref() {
+                    return Err(exonum_merkledb::_reexports::Error::msg(
+                        concat!("Non-canonical binary encoding of ", stringify!(#name))
+                    ));
+                }
+            }
+        } else {
+            quote!()
+        };
+
+        quote! {
+            #( { #sorted_checks } )*
+            #bytes_check
+        }
+    }
+
     fn implement_binary_value_from_pb(&self) -> proc_macro2::TokenStream {
         let name = &self.ident;
+        let canonical_check = self.canonical_check();
 
         quote! {
             impl exonum_merkledb::BinaryValue for #name {
@@ -110,13 +181,15 @@ impl BinaryValueStruct {
                 }
 
                 fn from_bytes(
-                    value: std::borrow::Cow<[u8]>,
+                    bytes: std::borrow::Cow<[u8]>,
                 ) -> std::result::Result<Self, exonum_merkledb::_reexports::Error> {
                     use protobuf::Message as _;
 
                     let mut block = <Self as exonum_proto::ProtobufConvert>::ProtoStruct::new();
-                    block.merge_from_bytes(value.as_ref())?;
-                    exonum_proto::ProtobufConvert::from_pb(block)
+                    block.merge_from_bytes(bytes.as_ref())?;
+                    let value = exonum_proto::ProtobufConvert::from_pb(block)?;
+                    #canonical_check
+                    Ok(value)
                 }
             }
         }
@@ -124,6 +197,7 @@ impl BinaryValueStruct {
 
     fn implement_binary_value_from_bincode(&self) -> proc_macro2::TokenStream {
         let name = &self.ident;
+        let canonical_check = self.canonical_check();
 
         quote! {
             impl exonum_merkledb::BinaryValue for #name {
@@ -134,9 +208,11 @@ impl BinaryValueStruct {
                 }
 
                 fn from_bytes(
-                    value: std::borrow::Cow<[u8]>,
+                    bytes: std::borrow::Cow<[u8]>,
                 ) -> std::result::Result<Self, exonum_merkledb::_reexports::Error> {
-                    bincode::deserialize(value.as_ref()).map_err(From::from)
+                    let value = bincode::deserialize(bytes.as_ref())?;
+                    #canonical_check
+                    Ok(value)
                 }
             }
         }
@@ -232,6 +308,8 @@ struct FromAccess {
 struct FromAccessAttrs {
     #[darling(default)]
     transparent: bool,
+    #[darling(default)]
+    describe: bool,
 }
 
 #[derive(Debug, Default, FromMeta)]
@@ -343,6 +421,7 @@ impl FromDeriveInput for FromAccess {
 struct AccessField {
     span: Span,
     ident: Option<Ident>,
+    ty: syn::Type,
     name_suffix: Option<String>,
     flatten: bool,
 }
@@ -360,6 +439,7 @@ impl FromField for AccessField {
             .or_else(|| ident.as_ref().map(ToString::to_string));
         Ok(Self {
             ident,
+            ty: field.ty.clone(),
             name_suffix,
             span: field.span(),
             flatten: attrs.flatten,
@@ -388,6 +468,23 @@ impl AccessField {
         }
     }
 
+    fn description(&self) -> impl ToTokens {
+        let describe = quote!(exonum_merkledb::access::DescribeSchema);
+        let ty = &self.ty;
+        if self.flatten {
+            quote!(<#ty as #describe>::describe_schema(name))
+        } else {
+            let suffix = self.name_suffix.as_ref().unwrap();
+            quote! {
+                <#ty as #describe>::describe_schema(
+                    exonum_merkledb::IndexAddress::from_root(name)
+                        .append_name(#suffix)
+                        .name()
+                )
+            }
+        }
+    }
+
     fn root_constructor(&self, field_index: usize) -> impl ToTokens {
         let from_access = quote!(exonum_merkledb::access::FromAccess);
         let ident = self.ident(field_index);
@@ -451,6 +548,36 @@ impl FromAccess {
     }
 }
 
+impl FromAccess {
+    fn describe_impl(&self) -> impl ToTokens {
+        let name = &self.ident;
+        let tr = quote!(exonum_merkledb::access::DescribeSchema);
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+
+        let fn_impl = if self.attrs.transparent {
+            let ty = &self.fields[0].ty;
+            quote!(<#ty as #tr>::describe_schema(name))
+        } else {
+            let field_descriptions = self.fields.iter().map(AccessField::description);
+            quote! {
+                let mut descriptions = Vec::new();
+                #(descriptions.extend(#field_descriptions);)*
+                descriptions
+            }
+        };
+
+        quote! {
+            impl #impl_generics #tr for #name #ty_generics #where_clause {
+                fn describe_schema(
+                    name: &str,
+                ) -> Vec<exonum_merkledb::access::IndexDescription> {
+                    #fn_impl
+                }
+            }
+        }
+    }
+}
+
 impl ToTokens for FromAccess {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let name = &self.ident;
@@ -468,6 +595,10 @@ impl ToTokens for FromAccess {
             }
         };
         tokens.extend(expanded);
+
+        if self.attrs.describe {
+            self.describe_impl().to_tokens(tokens);
+        }
     }
 }
 
@@ -367,11 +367,25 @@ impl ExonumInterface {
             .iter()
             .map(impl_match_arm_for_removed_method);
 
+        // Method names and IDs are exposed to check compatibility of artifacts on migration.
+        let method_entries = self.methods.iter().map(|descriptor| {
+            let ServiceMethodDescriptor { name, id, .. } = descriptor;
+            let name = name.to_string();
+            quote!((#id, #name))
+        });
+        let removed_ids = &self.attrs.removed_method_ids.ids;
+
         let ctx = quote!(#cr::_reexports::ExecutionContext<'a>);
         let res = quote!(std::result::Result<(), exonum::runtime::ExecutionError>);
         quote! {
             impl<'a> #cr::Interface<'a> for dyn #trait_name<#ctx, Output = #res> {
                 const INTERFACE_NAME: &'static str = #interface_name;
+                const METHODS: &'static [(exonum::runtime::MethodId, &'static str)] = &[
+                    #( #method_entries, )*
+                ];
+                const REMOVED_METHOD_IDS: &'static [exonum::runtime::MethodId] = &[
+                    #( #removed_ids, )*
+                ];
 
                 fn dispatch(
                     &self,
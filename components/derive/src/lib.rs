@@ -26,6 +26,7 @@ mod exonum_interface;
 mod require_artifact;
 mod service_dispatcher;
 mod service_factory;
+mod typed_config;
 
 use darling::FromMeta;
 use proc_macro::TokenStream;
@@ -51,6 +52,50 @@ use syn::{Attribute, NestedMeta};
 /// Selects the serialization codec to use. Allowed values are `protobuf` (used by default)
 /// and `bincode`.
 ///
+/// ## `canonical`
+///
+/// If set, `BinaryValue::from_bytes` rejects non-canonical encodings, i.e., ones that
+/// differ from the output of `BinaryValue::to_bytes` for the decoded value. Protobuf
+/// allows several binary representations of the same message (e.g., fields in a different
+/// order, or explicitly encoded default values); this attribute ensures that all parties
+/// processing the value agree on its bytes and thus on its hash. The attribute is
+/// recommended for types used in consensus-critical messages, such as votes.
+///
+/// ```ignore
+/// #[derive(Clone, Debug, BinaryValue)]
+/// #[protobuf_convert(source = "proto::Vote")]
+/// #[binary_value(canonical)]
+/// pub struct Vote {
+///     pub proposal_hash: Hash,
+/// }
+/// ```
+///
+/// # Field Attributes
+///
+/// ## `sorted`
+///
+/// If set on a field, `BinaryValue::from_bytes` rejects values in which the elements
+/// of the field (as yielded by iterating over a reference to the field) are not strictly
+/// increasing. The attribute is intended for sequences which semantically represent
+/// sets or maps (e.g., a `Vec` of keys), so that such sequences have a single canonical
+/// ordering. Collections which are sorted by construction (e.g., `BTreeMap`) do not need
+/// this attribute; their ordering is enforced by the `canonical` check. Collections with
+/// an unspecified iteration order, such as Protobuf `map` fields decoded to `HashMap`,
+/// must not be used in canonical types.
+///
+/// The check applies to the fields of the derived type only. Collections in nested types
+/// should be checked in the corresponding `ProtobufConvert::from_pb` implementation.
+///
+/// ```ignore
+/// #[derive(Clone, Debug, BinaryValue)]
+/// #[protobuf_convert(source = "proto::Config")]
+/// #[binary_value(canonical)]
+/// pub struct Config {
+///     #[binary_value(sorted)]
+///     pub keys: Vec<PublicKey>,
+/// }
+/// ```
+///
 /// # Examples
 ///
 /// With Protobuf serialization:
@@ -142,6 +187,17 @@ pub fn object_hash(input: TokenStream) -> TokenStream {
 /// A struct with the transparent layout must have a single field. The field will be created at
 /// the same address as the struct itself (i.e., no suffix will be added).
 ///
+/// ## `describe`
+///
+/// ```text
+/// #[from_access(describe)]
+/// ```
+///
+/// Additionally implements the `DescribeSchema` trait for the struct, which provides
+/// a machine-readable description of the storage layout (index names, types, and key / value
+/// types). Each field of the struct must implement `DescribeSchema` as well; this is the case
+/// for all indexes, `Group`s and `Lazy` components.
+///
 /// # Field Attributes
 ///
 /// ## `rename`
@@ -385,6 +441,65 @@ pub fn require_artifact(input: TokenStream) -> TokenStream {
     require_artifact::impl_require_artifact(input)
 }
 
+/// Implements the `TypedConfig` trait from the `exonum-supervisor` crate for the given type,
+/// together with `BinaryValue`, so that the type can be used as `Configure::Params`.
+///
+/// The target type must implement `Serialize` and `Deserialize` traits from `serde`. Configuration
+/// parameters are encoded as JSON; thus, fields with `#[serde(default)]` may be omitted
+/// from the proposed configuration, in which case the default value is used. Parameters which
+/// cannot be decoded or do not pass validation are rejected by the supervisor with
+/// the `MalformedArguments` error before reaching `Configure` methods of the service.
+///
+/// # Container Attributes
+///
+/// ## `crate`
+///
+/// ```text
+/// #[typed_config(crate = "path")]
+/// ```
+///
+/// Prefix of the `exonum-supervisor` crate. The default value is `exonum_supervisor`.
+///
+/// ## `validate`
+///
+/// ```text
+/// #[typed_config(validate = "path")]
+/// ```
+///
+/// Path to the function with the `fn(&Self) -> Result<(), E>` signature, where `E`
+/// implements `Display`, which checks the decoded parameters. If omitted, all decoded
+/// parameters are considered valid.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[derive(Debug, Serialize, Deserialize, TypedConfig)]
+/// #[typed_config(validate = "Self::check")]
+/// pub struct Config {
+///     #[serde(default = "Config::default_fee")]
+///     pub fee: u64,
+///     pub admin: PublicKey,
+/// }
+///
+/// impl Config {
+///     fn default_fee() -> u64 {
+///         10
+///     }
+///
+///     fn check(&self) -> Result<(), &'static str> {
+///         if self.fee == 0 {
+///             Err("Fee must be positive")
+///         } else {
+///             Ok(())
+///         }
+///     }
+/// }
+/// ```
+#[proc_macro_derive(TypedConfig, attributes(typed_config))]
+pub fn typed_config(input: TokenStream) -> TokenStream {
+    typed_config::impl_typed_config(input)
+}
+
 pub(crate) fn find_meta_attrs(name: &str, args: &[Attribute]) -> Option<NestedMeta> {
     args.as_ref()
         .iter()
@@ -424,3 +539,19 @@ impl ToTokens for RustRuntimeCratePath {
         self.0.to_tokens(tokens)
     }
 }
+
+#[derive(Debug, FromMeta)]
+#[darling(default)]
+struct SupervisorCratePath(syn::Path);
+
+impl Default for SupervisorCratePath {
+    fn default() -> Self {
+        Self(syn::parse_str("exonum_supervisor").unwrap())
+    }
+}
+
+impl ToTokens for SupervisorCratePath {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        self.0.to_tokens(tokens)
+    }
+}
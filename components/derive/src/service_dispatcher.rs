@@ -96,15 +96,22 @@ impl ToTokens for ServiceDispatcher {
         let ctx = quote!(#cr::_reexports::ExecutionContext<'_>);
         let res = quote!(std::result::Result<(), #cr::_reexports::ExecutionError>);
 
-        let match_arms = self.implements.0.iter().map(|interface| {
-            let trait_name = &interface.path;
-            let interface_trait = if interface.is_raw {
-                quote!(dyn #trait_name)
-            } else {
-                quote!(dyn #trait_name<#ctx, Output = #res>)
-            };
-            let interface_trait = quote!(<#interface_trait as #cr::Interface>);
+        let interface_traits: Vec<_> = self
+            .implements
+            .0
+            .iter()
+            .map(|interface| {
+                let trait_name = &interface.path;
+                let interface_trait = if interface.is_raw {
+                    quote!(dyn #trait_name)
+                } else {
+                    quote!(dyn #trait_name<#ctx, Output = #res>)
+                };
+                quote!(<#interface_trait as #cr::Interface>)
+            })
+            .collect();
 
+        let match_arms = interface_traits.iter().map(|interface_trait| {
             quote! {
                 #interface_trait::INTERFACE_NAME => {
                     #interface_trait::dispatch(self, ctx, method, payload)
@@ -125,6 +132,12 @@ impl ToTokens for ServiceDispatcher {
                         other => Err(#cr::_reexports::CommonError::NoSuchInterface.into()),
                     }
                 }
+
+                fn interface_methods(&self) -> #cr::_reexports::InterfaceMethods {
+                    let mut methods = #cr::_reexports::InterfaceMethods::new();
+                    #( #interface_traits::describe_methods(&mut methods); )*
+                    methods
+                }
             }
         };
         tokens.extend(expanded);
@@ -0,0 +1,79 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use darling::FromDeriveInput;
+use proc_macro::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{DeriveInput, Generics, Ident, Path};
+
+use crate::SupervisorCratePath;
+
+#[derive(Debug, FromDeriveInput)]
+#[darling(attributes(typed_config), forward_attrs(allow, doc, cfg))]
+struct TypedConfig {
+    ident: Ident,
+    #[darling(rename = "crate", default)]
+    cr: SupervisorCratePath,
+    #[darling(default)]
+    validate: Option<Path>,
+    #[darling(default)]
+    generics: Generics,
+}
+
+impl TypedConfig {
+    fn validate_fn(&self) -> impl ToTokens {
+        self.validate.as_ref().map(|validate| {
+            quote! {
+                fn validate(&self) -> std::result::Result<(), std::string::String> {
+                    #validate(self).map_err(|e| e.to_string())
+                }
+            }
+        })
+    }
+}
+
+impl ToTokens for TypedConfig {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let name = &self.ident;
+        let cr = &self.cr;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        let validate_fn = self.validate_fn();
+
+        let expanded = quote! {
+            impl #impl_generics #cr::TypedConfig for #name #ty_generics #where_clause {
+                #validate_fn
+            }
+
+            impl #impl_generics #cr::_reexports::BinaryValue for #name #ty_generics #where_clause {
+                fn to_bytes(&self) -> std::vec::Vec<u8> {
+                    #cr::TypedConfig::to_config_bytes(self)
+                }
+
+                fn from_bytes(
+                    bytes: std::borrow::Cow<[u8]>,
+                ) -> std::result::Result<Self, #cr::_reexports::Error> {
+                    #cr::TypedConfig::from_config_bytes(bytes.as_ref())
+                }
+            }
+        };
+        tokens.extend(expanded)
+    }
+}
+
+pub fn impl_typed_config(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = syn::parse(input).unwrap();
+    let typed_config =
+        TypedConfig::from_derive_input(&input).unwrap_or_else(|e| panic!("TypedConfig: {}", e));
+    quote!(#typed_config).into()
+}
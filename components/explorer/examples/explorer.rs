@@ -185,7 +185,7 @@ fn main() {
 
     // It is possible to extract a proof of a transaction error using `BlockInfo`. The proof is tied
     // to the `error_hash` mentioned in the block header.
-    let proof: CallProof = block_info.call_proof(CallInBlock::transaction(1));
+    let proof: CallProof = block_info.call_proof(CallInBlock::transaction(1)).unwrap();
     // To verify a proof, we need to know public keys of current validators. In our case,
     // we have a single validator.
     let validator_keys = [consensus_keys().public_key()];
@@ -218,13 +218,14 @@ fn main() {
 
     // `TransactionInfo` usage
     let hash = mempool_transaction().object_hash();
-    let tx: TransactionInfo = explorer.transaction(&hash).unwrap();
+    let tx: TransactionInfo = explorer.transaction(&hash).unwrap().unwrap();
     assert!(tx.is_in_pool());
     println!("{:?}", tx.message());
 
     // JSON serialization for committed transactions
     let committed_tx: TransactionInfo = explorer
         .transaction(&block[0].message().object_hash())
+        .unwrap()
         .unwrap();
     let tx_ref = committed_tx.as_committed().unwrap();
     assert_eq!(
@@ -242,6 +243,7 @@ fn main() {
     // JSON serialization for transactions in pool
     let tx_in_pool: TransactionInfo = explorer
         .transaction(&mempool_transaction().object_hash())
+        .unwrap()
         .unwrap();
     assert_eq!(
         serde_json::to_value(&tx_in_pool).unwrap(),
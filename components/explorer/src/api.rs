@@ -18,10 +18,10 @@
 //! in order to ease dependency management for client apps.
 
 use exonum::{
-    blockchain::{Block, CallProof},
+    blockchain::{Block, CallProof, IndexChange, Schema},
     crypto::Hash,
     helpers::Height,
-    merkledb::BinaryValue,
+    merkledb::{access::Access, BinaryValue},
     messages::{Precommit, Verified},
     runtime::{AnyTx, CallInfo, ExecutionStatus, InstanceId},
 };
@@ -184,12 +184,37 @@ impl BlockQuery {
     }
 }
 
+/// Summaries of changes made to service indexes by a block.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BlockIndexChanges {
+    /// Height of the block.
+    pub height: Height,
+    /// Summaries of changes ordered by the service identifier and then by the index address.
+    pub changes: Vec<IndexChange>,
+}
+
+impl BlockIndexChanges {
+    /// Reads summaries of changes in the block at the specified height from the core schema.
+    pub fn new(schema: &Schema<impl Access>, height: Height) -> Self {
+        Self {
+            height,
+            changes: schema.index_changes(height).iter().collect(),
+        }
+    }
+}
+
 /// Raw transaction in hex representation.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[non_exhaustive]
 pub struct TransactionHex {
     /// The hex value of the transaction to be broadcasted.
     pub tx_body: String,
+    /// Client-provided identifier used to correlate the node logs and events
+    /// related to the transaction. The identifier is stored node-locally and is not
+    /// a part of the transaction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
 }
 
 impl TransactionHex {
@@ -197,8 +222,15 @@ impl TransactionHex {
     pub fn new(transaction: &Verified<AnyTx>) -> Self {
         Self {
             tx_body: hex::encode(transaction.to_bytes()),
+            trace_id: None,
         }
     }
+
+    /// Attaches a trace identifier to the transaction.
+    pub fn with_trace_id(mut self, trace_id: impl Into<String>) -> Self {
+        self.trace_id = Some(trace_id.into());
+        self
+    }
 }
 
 /// Response to a request to broadcast a transaction over the blockchain network.
@@ -17,6 +17,7 @@
 use exonum::{
     blockchain::{Block, Schema, TxLocation},
     crypto::Hash,
+    helpers::Height,
     merkledb::{access::Access, ListProof},
     runtime::{ExecutionStatus, InstanceId, MethodId},
 };
@@ -24,7 +25,10 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use time::OffsetDateTime;
 
-use crate::{api::TransactionHex, median_precommits_time};
+use crate::{
+    api::{BlockIndexChanges, TransactionHex},
+    median_precommits_time,
+};
 
 /// Messages proactively sent by WebSocket clients to the server.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
@@ -36,7 +40,7 @@ pub enum IncomingMessage {
     Transaction(TransactionHex),
 }
 
-/// Subscription type for new blocks or committed transactions.
+/// Subscription type for new blocks, committed transactions or changes in service indexes.
 #[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
 #[derive(Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -50,6 +54,8 @@ pub enum SubscriptionType {
         /// Optional filter for the subscription.
         filter: Option<TransactionFilter>,
     },
+    /// Subscription to summaries of changes in service indexes made by new blocks.
+    IndexChanges,
 }
 
 /// Filter for transactions by service instance and (optionally) method identifier
@@ -74,6 +80,28 @@ impl TransactionFilter {
     }
 }
 
+/// Query parameters allowing a WebSocket client to resume a subscription after reconnecting.
+///
+/// Events are identified by cursors, which are equal to the height of the block that
+/// has produced the event (see [`Notification::cursor`]). If `since` is set, the server
+/// replays events for all blocks with a greater height before streaming new events,
+/// so that the client does not miss events committed while it was disconnected.
+///
+/// [`Notification::cursor`]: enum.Notification.html#method.cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CursorQuery {
+    /// Cursor of the last event received by the client.
+    pub since: Option<Height>,
+}
+
+impl CursorQuery {
+    /// Creates a query to resume a subscription after the specified cursor.
+    pub fn new(since: Height) -> Self {
+        Self { since: Some(since) }
+    }
+}
+
 /// Response to a WebSocket client. Roughly equivalent to `Result<T, String>`.
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "result", rename_all = "snake_case")]
@@ -138,6 +166,10 @@ pub struct CommittedTransactionSummary {
     pub location_proof: ListProof<Hash>,
     /// Approximate finalization time.
     pub time: OffsetDateTime,
+    /// Trace identifier provided by the client when submitting the transaction
+    /// to this node, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
 }
 
 impl CommittedTransactionSummary {
@@ -149,6 +181,9 @@ impl CommittedTransactionSummary {
         let method_id = tx.call_info.method_id;
         let location = schema.transactions_locations().get(tx_hash)?;
         let tx_result = schema.transaction_result(location)?;
+        schema
+            .check_history_retained(location.block_height())
+            .ok()?;
         let location_proof = schema
             .block_transactions(location.block_height())
             .get_proof(location.position_in_block().into());
@@ -166,6 +201,7 @@ impl CommittedTransactionSummary {
             location,
             location_proof,
             time,
+            trace_id: schema.trace_id(tx_hash),
         })
     }
 }
@@ -178,4 +214,21 @@ pub enum Notification {
     Block(Block),
     /// Notification about new transaction.
     Transaction(CommittedTransactionSummary),
+    /// Notification about changes in service indexes made by a new block.
+    IndexChanges(BlockIndexChanges),
+}
+
+impl Notification {
+    /// Returns the cursor of the notification, which is equal to the height of the block
+    /// that has produced the event. Cursors are persistent, i.e., the notifications
+    /// can be replayed after the cursor with the help of [`CursorQuery`].
+    ///
+    /// [`CursorQuery`]: struct.CursorQuery.html
+    pub fn cursor(&self) -> Height {
+        match self {
+            Notification::Block(block) => block.height,
+            Notification::Transaction(summary) => summary.location.block_height(),
+            Notification::IndexChanges(changes) => changes.height,
+        }
+    }
 }
@@ -50,7 +50,7 @@
 )]
 
 use exonum::{
-    blockchain::{Block, CallInBlock, CallProof, Schema, TxLocation},
+    blockchain::{Block, CallInBlock, CallProof, HistoryPruned, Schema, TxLocation},
     crypto::Hash,
     helpers::Height,
     merkledb::{ListProof, ObjectHash, Snapshot},
@@ -155,6 +155,16 @@ impl<'a> BlockInfo<'a> {
         self.len() == 0
     }
 
+    /// Checks whether historical data of this block is pruned by the node. Transactions
+    /// of a pruned block cannot be listed, although the block header and precommits
+    /// are available.
+    pub fn is_pruned(&self) -> bool {
+        self.explorer
+            .schema
+            .check_history_retained(self.header.height)
+            .is_err()
+    }
+
     /// Returns a list of precommits for this block.
     pub fn precommits(&self) -> Ref<'_, [Verified<Precommit>]> {
         if self.precommits.borrow().is_none() {
@@ -167,7 +177,8 @@ impl<'a> BlockInfo<'a> {
         })
     }
 
-    /// Lists hashes of transactions included in this block.
+    /// Lists hashes of transactions included in this block. The list is empty
+    /// if the block [is pruned](#method.is_pruned).
     pub fn transaction_hashes(&self) -> Ref<'_, [Hash]> {
         if self.txs.borrow().is_none() {
             let txs = self.explorer.transaction_hashes(&self.header);
@@ -177,11 +188,12 @@ impl<'a> BlockInfo<'a> {
         Ref::map(self.txs.borrow(), |cache| cache.as_ref().unwrap().as_ref())
     }
 
-    /// Returns a transaction with the specified index in the block.
+    /// Returns a transaction with the specified index in the block, or `None` if there
+    /// is no such transaction or the block [is pruned](#method.is_pruned).
     pub fn transaction(&self, index: usize) -> Option<CommittedTransaction> {
         self.transaction_hashes()
             .get(index)
-            .map(|hash| self.explorer.committed_transaction(hash, None))
+            .and_then(|hash| self.explorer.committed_transaction(hash, None).ok())
     }
 
     /// Returns the proof for the execution status of a call within this block.
@@ -190,12 +202,18 @@ impl<'a> BlockInfo<'a> {
     /// proof will not contain entries. To distinguish between two cases, one can inspect
     /// the number of transactions in the block or IDs of the active services when the block
     /// was executed.
-    pub fn call_proof(&self, call_location: CallInBlock) -> CallProof {
-        self.explorer
-            .schema
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if call errors in the block were rolled into the call errors
+    /// checkpoint, so the proof cannot be built.
+    pub fn call_proof(&self, call_location: CallInBlock) -> Result<CallProof, HistoryPruned> {
+        let schema = &self.explorer.schema;
+        schema.check_call_errors_retained(self.header.height)?;
+        let records = schema
             .call_records(self.header.height)
-            .unwrap() // safe: we know that the block exists
-            .get_proof(call_location)
+            .expect("BUG: No call records for a committed block");
+        Ok(records.get_proof(call_location))
     }
 
     /// Iterates over transactions in the block.
@@ -219,17 +237,17 @@ impl<'a> BlockInfo<'a> {
             .into_inner()
             .unwrap_or_else(|| explorer.transaction_hashes(&header))
             .iter()
-            .map(|tx_hash| explorer.committed_transaction(tx_hash, None))
+            .filter_map(|tx_hash| explorer.committed_transaction(tx_hash, None).ok())
             .collect();
-        let errors = self
-            .explorer
+        let errors = explorer
             .schema
             .call_records(header.height)
-            .expect("No call record for a committed block");
-        let errors: Vec<_> = errors
-            .errors()
-            .map(|(location, error)| ErrorWithLocation { location, error })
-            .collect();
+            .map_or_else(Vec::new, |errors| {
+                errors
+                    .errors()
+                    .map(|(location, error)| ErrorWithLocation { location, error })
+                    .collect()
+            });
 
         BlockWithTransactions {
             header,
@@ -294,7 +312,8 @@ pub struct BlockWithTransactions {
     pub precommits: Vec<Verified<Precommit>>,
     /// Transactions in the order they appear in the block.
     pub transactions: Vec<CommittedTransaction>,
-    /// Errors that have occurred within the block.
+    /// Errors that have occurred within the block. Empty if call errors in the block
+    /// were rolled into the call errors checkpoint.
     pub errors: Vec<ErrorWithLocation>,
 }
 
@@ -619,32 +638,49 @@ impl<'a> BlockchainExplorer<'a> {
         BlockchainExplorer { schema }
     }
 
-    /// Returns information about the transaction identified by the hash.
-    pub fn transaction(&self, tx_hash: &Hash) -> Option<TransactionInfo> {
-        let message = self.transaction_without_proof(tx_hash)?;
+    /// Returns information about the transaction identified by the hash, or `None`
+    /// if the transaction is unknown.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the transaction is committed in a block which historical data
+    /// is pruned by the node, so the proof of the transaction location cannot be built,
+    /// or if call errors in the block were rolled into the call errors checkpoint,
+    /// so the execution status of the transaction is unknown.
+    pub fn transaction(&self, tx_hash: &Hash) -> Result<Option<TransactionInfo>, HistoryPruned> {
+        let message = match self.transaction_without_proof(tx_hash) {
+            Some(message) => message,
+            None => return Ok(None),
+        };
         if self.schema.transactions_pool().contains(tx_hash) {
-            return Some(TransactionInfo::InPool { message });
+            return Ok(Some(TransactionInfo::InPool { message }));
         }
 
-        let tx = self.committed_transaction(tx_hash, Some(message));
-        Some(TransactionInfo::Committed(tx))
+        let tx = self.committed_transaction(tx_hash, Some(message))?;
+        Ok(Some(TransactionInfo::Committed(tx)))
     }
 
     /// Returns the status of a call in a block.
     ///
     /// # Return value
     ///
-    /// This method will return `Ok(())` both if the call completed successfully, or if
+    /// This method will return `Ok(Ok(()))` both if the call completed successfully, or if
     /// was not performed at all. The caller is responsible to distinguish these two outcomes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if call errors in the block were rolled into the call errors
+    /// checkpoint, so the status of the call is unknown.
     pub fn call_status(
         &self,
         block_height: Height,
         call_location: CallInBlock,
-    ) -> Result<(), ExecutionError> {
-        match self.schema.call_records(block_height) {
+    ) -> Result<Result<(), ExecutionError>, HistoryPruned> {
+        self.schema.check_call_errors_retained(block_height)?;
+        Ok(match self.schema.call_records(block_height) {
             Some(errors) => errors.get(call_location),
             None => Ok(()),
-        }
+        })
     }
 
     /// Return transaction message without proof.
@@ -669,12 +705,14 @@ impl<'a> BlockchainExplorer<'a> {
         &self,
         tx_hash: &Hash,
         maybe_content: Option<Verified<AnyTx>>,
-    ) -> CommittedTransaction {
+    ) -> Result<CommittedTransaction, HistoryPruned> {
         let location = self
             .schema
             .transactions_locations()
             .get(tx_hash)
             .unwrap_or_else(|| panic!("Location not found for transaction hash {:?}", tx_hash));
+        self.schema
+            .check_history_retained(location.block_height())?;
 
         let location_proof = self
             .schema
@@ -687,10 +725,13 @@ impl<'a> BlockchainExplorer<'a> {
             .unwrap();
         let time = median_precommits_time(&block_precommits.precommits);
 
-        // Unwrap is OK here, because we already know that transaction is committed.
+        self.schema
+            .check_call_errors_retained(location.block_height())?;
+        // Unwrap is OK here, because we already know that transaction is committed
+        // and call errors in its block are retained.
         let status = self.schema.transaction_result(location).unwrap();
 
-        CommittedTransaction {
+        Ok(CommittedTransaction {
             message: maybe_content.unwrap_or_else(|| {
                 self.schema
                     .transactions()
@@ -701,7 +742,7 @@ impl<'a> BlockchainExplorer<'a> {
             location_proof,
             status: ExecutionStatus(status),
             time,
-        }
+        })
     }
 
     /// Return the height of the blockchain.
@@ -723,19 +764,24 @@ impl<'a> BlockchainExplorer<'a> {
     pub fn block_with_txs(&self, height: Height) -> Option<BlockWithTransactions> {
         let txs_table = self.schema.block_transactions(height);
         let block_proof = self.schema.block_and_precommits(height)?;
-        let errors = self.schema.call_records(height)?;
+        let errors = self
+            .schema
+            .call_records(height)
+            .map_or_else(Vec::new, |errors| {
+                errors
+                    .errors()
+                    .map(|(location, error)| ErrorWithLocation { location, error })
+                    .collect()
+            });
 
         Some(BlockWithTransactions {
             header: block_proof.block,
             precommits: block_proof.precommits,
             transactions: txs_table
                 .iter()
-                .map(|tx_hash| self.committed_transaction(&tx_hash, None))
-                .collect(),
-            errors: errors
-                .errors()
-                .map(|(location, error)| ErrorWithLocation { location, error })
+                .filter_map(|tx_hash| self.committed_transaction(&tx_hash, None).ok())
                 .collect(),
+            errors,
         })
     }
 
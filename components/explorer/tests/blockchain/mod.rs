@@ -17,7 +17,7 @@
 use exonum::{
     blockchain::{
         config::GenesisConfigBuilder, ApiSender, BlockParams, Blockchain, BlockchainBuilder,
-        BlockchainMut, ConsensusConfig,
+        BlockchainMut, ConsensusConfig, PruningConfig,
     },
     crypto::{self, KeyPair, PublicKey},
     merkledb::{ObjectHash, TemporaryDB},
@@ -105,6 +105,10 @@ pub fn consensus_keys() -> KeyPair {
 
 /// Creates a blockchain with no blocks.
 pub fn create_blockchain() -> BlockchainMut {
+    create_blockchain_with_pruning(None)
+}
+
+pub fn create_blockchain_with_pruning(pruning: Option<PruningConfig>) -> BlockchainMut {
     let (config, node_keys) = ConsensusConfig::for_tests(1);
     let blockchain = Blockchain::new(TemporaryDB::new(), node_keys.service, ApiSender::closed());
 
@@ -117,10 +121,13 @@ pub fn create_blockchain() -> BlockchainMut {
     let rust_runtime = RustRuntime::builder()
         .with_factory(my_service)
         .build_for_tests();
-    BlockchainBuilder::new(blockchain)
+    let builder = BlockchainBuilder::new(blockchain)
         .with_genesis_config(genesis_config)
-        .with_runtime(rust_runtime)
-        .build()
+        .with_runtime(rust_runtime);
+    match pruning {
+        Some(pruning) => builder.with_pruning(pruning).build(),
+        None => builder.build(),
+    }
 }
 
 /// Simplified compared to real life / testkit, but we don't need to test *everything*
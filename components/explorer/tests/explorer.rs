@@ -15,7 +15,7 @@
 //! Tests for the blockchain explorer functionality.
 
 use exonum::{
-    blockchain::TxLocation,
+    blockchain::{PruningConfig, TxLocation},
     crypto::{Hash, KeyPair},
     helpers::Height,
     merkledb::ObjectHash,
@@ -31,7 +31,8 @@ use std::iter;
 mod blockchain;
 
 use self::blockchain::{
-    create_block, create_blockchain, CreateWallet, ExplorerTransactions, Transfer, SERVICE_ID,
+    create_block, create_blockchain, create_blockchain_with_pruning, CreateWallet,
+    ExplorerTransactions, Transfer, SERVICE_ID,
 };
 
 #[test]
@@ -51,7 +52,10 @@ fn test_explorer_basics() {
     let block = explorer.block(Height(0)).unwrap();
     assert_eq!(block.len(), 0);
     assert!(block.transaction(0).is_none());
-    assert!(explorer.transaction(&tx_alice.object_hash()).is_none());
+    assert!(explorer
+        .transaction(&tx_alice.object_hash())
+        .unwrap()
+        .is_none());
 
     // Block #1: Alice's transaction.
     create_block(&mut blockchain, vec![tx_alice.clone()]);
@@ -72,7 +76,10 @@ fn test_explorer_basics() {
         block.transaction_hashes()[0]
     );
 
-    let tx_info = explorer.transaction(&tx_alice.object_hash()).unwrap();
+    let tx_info = explorer
+        .transaction(&tx_alice.object_hash())
+        .unwrap()
+        .unwrap();
     assert!(!tx_info.is_in_pool());
     assert!(tx_info.is_committed());
     assert_eq!(tx_info.message(), &tx_alice);
@@ -220,13 +227,13 @@ fn test_explorer_pool_transaction() {
 
     let snapshot = blockchain.snapshot();
     let explorer = BlockchainExplorer::new(snapshot.as_ref());
-    assert!(explorer.transaction(&tx_hash).is_none());
+    assert!(explorer.transaction(&tx_hash).unwrap().is_none());
 
     blockchain.add_transactions_into_pool(iter::once(tx_alice.clone()));
 
     let snapshot = blockchain.snapshot();
     let explorer = BlockchainExplorer::new(snapshot.as_ref());
-    let tx_info = explorer.transaction(&tx_hash).unwrap();
+    let tx_info = explorer.transaction(&tx_hash).unwrap().unwrap();
     assert!(tx_info.is_in_pool());
     assert!(!tx_info.is_committed());
     assert_eq!(tx_info.message(), &tx_alice);
@@ -472,7 +479,7 @@ fn test_transaction_info_roundtrip() {
 
     let snapshot = blockchain.snapshot();
     let explorer = BlockchainExplorer::new(snapshot.as_ref());
-    let info: TransactionInfo = explorer.transaction(&tx.object_hash()).unwrap();
+    let info: TransactionInfo = explorer.transaction(&tx.object_hash()).unwrap().unwrap();
     let json = serde_json::to_value(&info).unwrap();
     let info: TransactionInfo = serde_json::from_value(json).unwrap();
 
@@ -492,3 +499,33 @@ fn test_block_with_transactions_roundtrip() {
     let block_copy: BlockWithTransactions = serde_json::from_value(block_json).unwrap();
     assert_eq!(block_copy[0].message(), block[0].message());
 }
+
+#[test]
+fn test_explorer_with_pruned_blocks() {
+    let mut blockchain = create_blockchain_with_pruning(Some(PruningConfig::new(1)));
+    let tx_alice = KeyPair::random().create_wallet(SERVICE_ID, CreateWallet::new("Alice"));
+    let tx_bob = KeyPair::random().create_wallet(SERVICE_ID, CreateWallet::new("Bob"));
+    create_block(&mut blockchain, vec![tx_alice.clone()]);
+    create_block(&mut blockchain, vec![tx_bob.clone()]);
+
+    let snapshot = blockchain.snapshot();
+    let explorer = BlockchainExplorer::new(snapshot.as_ref());
+    let err = explorer.transaction(&tx_alice.object_hash()).unwrap_err();
+    assert_eq!(err.height, Height(1));
+    assert_eq!(err.retained_from, Height(2));
+    let block = explorer.block(Height(1)).unwrap();
+    assert!(block.is_pruned());
+    assert!(block.transaction(0).is_none());
+
+    let tx_info = explorer
+        .transaction(&tx_bob.object_hash())
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        tx_info.as_committed().unwrap().location(),
+        &TxLocation::new(Height(2), 0)
+    );
+    let block = explorer.block(Height(2)).unwrap();
+    assert!(!block.is_pruned());
+    assert!(block.transaction(0).is_some());
+}
@@ -56,13 +56,14 @@
 use exonum_crypto::{KeyPair, PublicKey, SecretKey, Seed, SEED_LENGTH};
 use pwbox::{sodium::Sodium, ErasedPwBox, Eraser, SensitiveData, Suite};
 use rand::thread_rng;
-use secrecy::ExposeSecret;
+use secrecy::{ExposeSecret, Secret};
 use secret_tree::{Name, SecretTree};
 use serde::{Deserialize, Serialize};
 
 #[cfg(unix)]
 use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
 use std::{
+    fmt,
     fs::{File, OpenOptions},
     io::{Error, ErrorKind, Read, Write},
     path::Path,
@@ -139,6 +140,28 @@ impl Keys {
     }
 }
 
+/// Length of the data encryption secret in bytes.
+pub const DATA_ENCRYPTION_SECRET_LENGTH: usize = 32;
+
+/// Secret used to derive keys for the encryption at rest of the node database.
+///
+/// The secret is derived from the master seed independently of the consensus
+/// and service keys; thus, a leaked consensus key does not allow to decrypt the database.
+pub struct DataEncryptionSecret(Secret<[u8; DATA_ENCRYPTION_SECRET_LENGTH]>);
+
+impl fmt::Debug for DataEncryptionSecret {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.debug_tuple("DataEncryptionSecret").finish()
+    }
+}
+
+impl DataEncryptionSecret {
+    /// Returns the bytes of the secret.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.expose_secret()
+    }
+}
+
 fn save_master_key<P: AsRef<Path>>(
     path: P,
     encrypted_key: &EncryptedMasterKey,
@@ -226,11 +249,33 @@ fn generate_keys_from_master_password(tree: &SecretTree) -> Keys {
     Keys::from_keys(consensus_keys, service_keys)
 }
 
-/// Reads encrypted master key from file and generate validator keys from it.
-pub fn read_keys_from_file<P: AsRef<Path>, W: AsRef<[u8]>>(
+fn data_encryption_secret_from_tree(tree: &SecretTree) -> DataEncryptionSecret {
+    let mut buffer = [0_u8; DATA_ENCRYPTION_SECRET_LENGTH];
+    tree.child(Name::new("data_encryption")).fill(&mut buffer);
+    DataEncryptionSecret(Secret::new(buffer))
+}
+
+/// Derives validator keys from an unencrypted master key seed.
+///
+/// This can be used if the master seed is stored outside of the encrypted key file,
+/// e.g., in an external secret storage.
+pub fn keys_from_master_seed(seed: &[u8]) -> anyhow::Result<Keys> {
+    let tree = SecretTree::from_slice(seed)?;
+    Ok(generate_keys_from_master_password(&tree))
+}
+
+/// Derives the data encryption secret from an unencrypted master key seed.
+pub fn data_encryption_secret_from_master_seed(
+    seed: &[u8],
+) -> anyhow::Result<DataEncryptionSecret> {
+    let tree = SecretTree::from_slice(seed)?;
+    Ok(data_encryption_secret_from_tree(&tree))
+}
+
+fn read_master_key_tree<P: AsRef<Path>, W: AsRef<[u8]>>(
     path: P,
     pass_phrase: W,
-) -> anyhow::Result<Keys> {
+) -> anyhow::Result<SecretTree> {
     let mut key_file = File::open(path)?;
 
     #[cfg(unix)]
@@ -241,14 +286,34 @@ pub fn read_keys_from_file<P: AsRef<Path>, W: AsRef<[u8]>>(
     let keys: EncryptedMasterKey =
         toml::from_slice(file_content.as_slice()).map_err(|e| Error::new(ErrorKind::Other, e))?;
     let seed = keys.decrypt(pass_phrase)?;
-    let tree = SecretTree::from_slice(&seed)?;
+    Ok(SecretTree::from_slice(&seed)?)
+}
 
+/// Reads encrypted master key from file and generate validator keys from it.
+pub fn read_keys_from_file<P: AsRef<Path>, W: AsRef<[u8]>>(
+    path: P,
+    pass_phrase: W,
+) -> anyhow::Result<Keys> {
+    let tree = read_master_key_tree(path, pass_phrase)?;
     Ok(generate_keys_from_master_password(&tree))
 }
 
+/// Reads encrypted master key from file and derives the data encryption secret from it.
+pub fn read_data_encryption_secret_from_file<P: AsRef<Path>, W: AsRef<[u8]>>(
+    path: P,
+    pass_phrase: W,
+) -> anyhow::Result<DataEncryptionSecret> {
+    let tree = read_master_key_tree(path, pass_phrase)?;
+    Ok(data_encryption_secret_from_tree(&tree))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{generate_keys, read_keys_from_file, thread_rng, EncryptedMasterKey, SecretTree};
+    use super::{
+        data_encryption_secret_from_master_seed, generate_keys, generate_keys_from_seed,
+        keys_from_master_seed, read_data_encryption_secret_from_file, read_keys_from_file,
+        thread_rng, EncryptedMasterKey, SecretTree,
+    };
     use secrecy::ExposeSecret;
     use tempfile::TempDir;
 
@@ -262,6 +327,34 @@ mod tests {
         assert_eq!(pk1, pk2);
     }
 
+    #[test]
+    fn keys_from_unencrypted_seed() {
+        let seed = [7_u8; 32];
+        let (keys, _) = generate_keys_from_seed(b"passphrase", &seed).unwrap();
+        assert_eq!(keys_from_master_seed(&seed).unwrap(), keys);
+        assert!(keys_from_master_seed(&seed[..16]).is_err());
+    }
+
+    #[test]
+    fn data_encryption_secret() {
+        let dir = TempDir::new().expect("Couldn't create TempDir");
+        let file_path = dir.path().join("private_key.toml");
+        let seed = [7_u8; 32];
+        let (keys, encrypted_key) = generate_keys_from_seed(b"passphrase", &seed).unwrap();
+        super::save_master_key(&file_path, &encrypted_key).unwrap();
+
+        let secret = data_encryption_secret_from_master_seed(&seed).unwrap();
+        let restored_secret =
+            read_data_encryption_secret_from_file(&file_path, b"passphrase").unwrap();
+        assert_eq!(secret.as_bytes(), restored_secret.as_bytes());
+        // The secret is independent of the node keys.
+        assert_ne!(secret.as_bytes(), &keys.consensus_sk().as_ref()[..32]);
+        assert_ne!(secret.as_bytes(), &keys.service_sk().as_ref()[..32]);
+
+        let other_secret = data_encryption_secret_from_master_seed(&[8; 32]).unwrap();
+        assert_ne!(secret.as_bytes(), other_secret.as_bytes());
+    }
+
     #[test]
     fn encrypt_decrypt() {
         let pass_phrase = b"passphrase";
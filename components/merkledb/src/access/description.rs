@@ -0,0 +1,117 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Machine-readable descriptions of data schemas.
+
+use serde::{Deserialize, Serialize};
+
+use std::any::type_name;
+
+use crate::views::IndexType;
+
+/// Description of an index within a data schema.
+///
+/// Descriptions allow to learn the storage layout of a schema (e.g., for auditing or indexing
+/// purposes) without reading its source code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct IndexDescription {
+    /// Name of the index relative to the schema root. For indexes in a group, this is
+    /// the name of the group.
+    pub name: String,
+    /// Type of the index.
+    pub index_type: IndexType,
+    /// Types of keys of the groups containing the index, starting from the outermost group.
+    /// Empty if the index does not belong to a group.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub group_key_types: Vec<String>,
+    /// Type of the index keys. `None` for indexes without keys or with implicit `u64` keys
+    /// (e.g., lists).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_type: Option<String>,
+    /// Type of the index values. `None` for indexes without values (e.g., `KeySetIndex`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_type: Option<String>,
+}
+
+impl IndexDescription {
+    /// Creates a description of an index with the specified name and type.
+    pub fn new(name: impl Into<String>, index_type: IndexType) -> Self {
+        Self {
+            name: name.into(),
+            index_type,
+            group_key_types: vec![],
+            key_type: None,
+            value_type: None,
+        }
+    }
+
+    /// Sets the key type of the index.
+    pub fn with_key<K: ?Sized>(mut self) -> Self {
+        self.key_type = Some(type_name::<K>().to_owned());
+        self
+    }
+
+    /// Sets the value type of the index.
+    pub fn with_value<V>(mut self) -> Self {
+        self.value_type = Some(type_name::<V>().to_owned());
+        self
+    }
+
+    /// Marks the index as belonging to a group with the specified key type.
+    pub fn in_group<K: ?Sized>(mut self) -> Self {
+        self.group_key_types.insert(0, type_name::<K>().to_owned());
+        self
+    }
+}
+
+/// Component of a data schema able to describe its storage layout.
+///
+/// This trait is implemented for all indexes, [`Group`]s and [`Lazy`] components.
+/// For structs deriving [`FromAccess`], the trait can be derived by specifying
+/// the `#[from_access(describe)]` attribute on the struct.
+///
+/// [`Group`]: ../struct.Group.html
+/// [`Lazy`]: ../struct.Lazy.html
+/// [`FromAccess`]: trait.FromAccess.html
+///
+/// # Examples
+///
+/// ```
+/// # use exonum_derive::FromAccess;
+/// use exonum_merkledb::{
+///     access::{Access, DescribeSchema},
+///     Group, IndexType, ListIndex, ProofMapIndex, Snapshot,
+/// };
+///
+/// #[derive(FromAccess)]
+/// #[from_access(describe)]
+/// struct Schema<T: Access> {
+///     wallets: ProofMapIndex<T::Base, str, u64>,
+///     history: Group<T, str, ListIndex<T::Base, u64>>,
+/// }
+///
+/// let descriptions = Schema::<&dyn Snapshot>::describe_schema("");
+/// assert_eq!(descriptions.len(), 2);
+/// assert_eq!(descriptions[0].name, "wallets");
+/// assert_eq!(descriptions[0].index_type, IndexType::ProofMap);
+/// assert_eq!(descriptions[0].key_type.as_deref(), Some("str"));
+/// assert_eq!(descriptions[1].name, "history");
+/// assert_eq!(descriptions[1].group_key_types, vec!["str".to_owned()]);
+/// ```
+pub trait DescribeSchema {
+    /// Describes indexes in this component, assuming that it is created at the address
+    /// with the specified `name` (an empty name corresponds to the schema root).
+    fn describe_schema(name: &str) -> Vec<IndexDescription>;
+}
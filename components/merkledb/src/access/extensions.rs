@@ -16,10 +16,14 @@
 
 use crate::{
     access::{Access, FromAccess},
-    indexes::proof_map::{Raw, ToProofPath},
+    indexes::{
+        proof_map::{Raw, ToProofPath},
+        SecondaryKey,
+    },
     views::IndexType,
-    BinaryKey, BinaryValue, Entry, Group, IndexAddress, KeySetIndex, ListIndex, MapIndex,
-    ObjectHash, ProofEntry, ProofListIndex, ProofMapIndex, SparseListIndex, ValueSetIndex,
+    BinaryKey, BinaryValue, Entry, Group, IndexAddress, IndexedProofMap, KeySetIndex, ListIndex,
+    MapIndex, ObjectHash, PatriciaTrieIndex, ProofEntry, ProofListIndex, ProofMapIndex,
+    SortedValueIndex, SparseListIndex, SparseMerkleIndex, TtlMapIndex, ValueSetIndex,
 };
 
 /// Extension trait allowing for easy access to indexes from any type implementing
@@ -168,6 +172,22 @@ pub trait CopyAccessExt: Access + Copy {
             .unwrap_or_else(|e| panic!("MerkleDB error: {}", e))
     }
 
+    /// Gets a Merkelized map index with a secondary index declared by `S`.
+    ///
+    /// # Panics
+    ///
+    /// If the map or its secondary index exist, but have an unexpected type.
+    fn get_indexed_proof_map<I, K, V, S>(self, addr: I) -> IndexedProofMap<Self::Base, K, V, S>
+    where
+        I: Into<IndexAddress>,
+        K: BinaryKey + ObjectHash + ?Sized,
+        V: BinaryValue,
+        S: SecondaryKey<V>,
+    {
+        IndexedProofMap::from_access(self, addr.into())
+            .unwrap_or_else(|e| panic!("MerkleDB error: {}", e))
+    }
+
     /// Variant of the proof map with keys that can be mapped directly to `ProofPath`.
     ///
     /// # Panics
@@ -233,6 +253,66 @@ pub trait CopyAccessExt: Access + Copy {
             .unwrap_or_else(|e| panic!("MerkleDB error: {}", e))
     }
 
+    /// Gets a map index with expiring entries with the specified address.
+    ///
+    /// # Panics
+    ///
+    /// If the index exists, but is not a map with expiring entries.
+    fn get_ttl_map<I, K, V>(self, addr: I) -> TtlMapIndex<Self::Base, K, V>
+    where
+        I: Into<IndexAddress>,
+        K: BinaryKey + ?Sized,
+        V: BinaryValue,
+    {
+        TtlMapIndex::from_access(self, addr.into())
+            .unwrap_or_else(|e| panic!("MerkleDB error: {}", e))
+    }
+
+    /// Gets a sorted value index with the specified address.
+    ///
+    /// # Panics
+    ///
+    /// If the index exists, but is not a sorted value index.
+    fn get_sorted_value<I, S, V>(self, addr: I) -> SortedValueIndex<Self::Base, S, V>
+    where
+        I: Into<IndexAddress>,
+        S: BinaryKey + Clone,
+        V: BinaryValue,
+    {
+        SortedValueIndex::from_access(self, addr.into())
+            .unwrap_or_else(|e| panic!("MerkleDB error: {}", e))
+    }
+
+    /// Gets a sparse Merkle tree index with the specified address.
+    ///
+    /// # Panics
+    ///
+    /// If the index exists, but is not a sparse Merkle tree.
+    fn get_sparse_merkle<I, K, V>(self, addr: I) -> SparseMerkleIndex<Self::Base, K, V>
+    where
+        I: Into<IndexAddress>,
+        K: BinaryKey + ObjectHash,
+        V: BinaryValue,
+    {
+        SparseMerkleIndex::from_access(self, addr.into())
+            .unwrap_or_else(|e| panic!("MerkleDB error: {}", e))
+    }
+
+    /// Gets a Merkle Patricia trie index with the specified address.
+    ///
+    /// # Panics
+    ///
+    /// If the index exists, but is not a Merkle Patricia trie.
+    fn get_patricia_trie<I, K, V>(self, addr: I) -> PatriciaTrieIndex<Self::Base, K, V>
+    where
+        I: Into<IndexAddress>,
+        K: BinaryKey + ?Sized,
+        V: BinaryValue,
+    {
+        PatriciaTrieIndex::from_access(self, addr.into())
+            .unwrap_or_else(|e| panic!("MerkleDB error: {}", e))
+    }
+
     /// Gets a key set index with the specified address.
     ///
     /// # Panics
@@ -390,6 +470,22 @@ pub trait AccessExt: Access {
             .unwrap_or_else(|e| panic!("MerkleDB error: {}", e))
     }
 
+    /// Gets a Merkelized map index with a secondary index declared by `S`.
+    ///
+    /// # Panics
+    ///
+    /// If the map or its secondary index exist, but have an unexpected type.
+    fn get_indexed_proof_map<I, K, V, S>(&self, addr: I) -> IndexedProofMap<Self::Base, K, V, S>
+    where
+        I: Into<IndexAddress>,
+        K: BinaryKey + ObjectHash + ?Sized,
+        V: BinaryValue,
+        S: SecondaryKey<V>,
+    {
+        IndexedProofMap::from_access(self.clone(), addr.into())
+            .unwrap_or_else(|e| panic!("MerkleDB error: {}", e))
+    }
+
     /// Variant of the proof map with keys that can be mapped directly to `ProofPath`.
     ///
     /// # Panics
@@ -456,6 +552,66 @@ pub trait AccessExt: Access {
             .unwrap_or_else(|e| panic!("MerkleDB error: {}", e))
     }
 
+    /// Gets a map index with expiring entries with the specified address.
+    ///
+    /// # Panics
+    ///
+    /// If the index exists, but is not a map with expiring entries.
+    fn get_ttl_map<I, K, V>(&self, addr: I) -> TtlMapIndex<Self::Base, K, V>
+    where
+        I: Into<IndexAddress>,
+        K: BinaryKey + ?Sized,
+        V: BinaryValue,
+    {
+        TtlMapIndex::from_access(self.clone(), addr.into())
+            .unwrap_or_else(|e| panic!("MerkleDB error: {}", e))
+    }
+
+    /// Gets a sorted value index with the specified address.
+    ///
+    /// # Panics
+    ///
+    /// If the index exists, but is not a sorted value index.
+    fn get_sorted_value<I, S, V>(&self, addr: I) -> SortedValueIndex<Self::Base, S, V>
+    where
+        I: Into<IndexAddress>,
+        S: BinaryKey + Clone,
+        V: BinaryValue,
+    {
+        SortedValueIndex::from_access(self.clone(), addr.into())
+            .unwrap_or_else(|e| panic!("MerkleDB error: {}", e))
+    }
+
+    /// Gets a sparse Merkle tree index with the specified address.
+    ///
+    /// # Panics
+    ///
+    /// If the index exists, but is not a sparse Merkle tree.
+    fn get_sparse_merkle<I, K, V>(&self, addr: I) -> SparseMerkleIndex<Self::Base, K, V>
+    where
+        I: Into<IndexAddress>,
+        K: BinaryKey + ObjectHash,
+        V: BinaryValue,
+    {
+        SparseMerkleIndex::from_access(self.clone(), addr.into())
+            .unwrap_or_else(|e| panic!("MerkleDB error: {}", e))
+    }
+
+    /// Gets a Merkle Patricia trie index with the specified address.
+    ///
+    /// # Panics
+    ///
+    /// If the index exists, but is not a Merkle Patricia trie.
+    fn get_patricia_trie<I, K, V>(&self, addr: I) -> PatriciaTrieIndex<Self::Base, K, V>
+    where
+        I: Into<IndexAddress>,
+        K: BinaryKey + ?Sized,
+        V: BinaryValue,
+    {
+        PatriciaTrieIndex::from_access(self.clone(), addr.into())
+            .unwrap_or_else(|e| panic!("MerkleDB error: {}", e))
+    }
+
     /// Gets a key set index with the specified address.
     ///
     /// # Panics
@@ -28,7 +28,8 @@
 //!
 //! [`CopyAccessExt`] extends [`Access`] and provides helper methods to instantiate indexes. This
 //! is useful in quick-and-dirty testing. For more complex applications, consider deriving
-//! data schema via [`FromAccess`].
+//! data schema via [`FromAccess`]. The storage layout of such a schema can be described
+//! in a machine-readable form via [`DescribeSchema`].
 //!
 //! # Guarantees
 //!
@@ -54,12 +55,16 @@
 //! [`Scratchpad`]: ../migration/struct.Scratchpad.html
 //! [`CopyAccessExt`]: trait.CopyAccessExt.html
 //! [`FromAccess`]: trait.FromAccess.html
+//! [`DescribeSchema`]: trait.DescribeSchema.html
 
 use thiserror::Error;
 
 use std::fmt;
 
-pub use self::extensions::{AccessExt, CopyAccessExt};
+pub use self::{
+    description::{DescribeSchema, IndexDescription},
+    extensions::{AccessExt, CopyAccessExt},
+};
 pub use crate::views::{AsReadonly, RawAccess, RawAccessMut};
 
 use crate::{
@@ -68,6 +73,7 @@ use crate::{
     BinaryKey,
 };
 
+mod description;
 mod extensions;
 
 /// High-level access to database data.
@@ -0,0 +1,182 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Asynchronous interface to databases and snapshots.
+//!
+//! Merging patches into a database and reading from a snapshot are blocking operations.
+//! For example, a RocksDB write may stall for a considerable time if the database compaction
+//! falls behind. The traits in this module offload such operations to the blocking thread pool
+//! of the [Tokio] runtime, so that they do not block runtime worker threads.
+//!
+//! The module is available if the `async` crate feature is enabled. Futures returned
+//! by the trait methods must be polled within the context of a Tokio runtime.
+//!
+//! # Examples
+//!
+//! ```
+//! use exonum_merkledb::{
+//!     access::CopyAccessExt,
+//!     asynchronous::{AsyncDatabase, AsyncSnapshot},
+//!     Database, Snapshot, TemporaryDB,
+//! };
+//! use std::sync::Arc;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let db: Arc<dyn Database> = Arc::new(TemporaryDB::new());
+//! let fork = db.fork();
+//! fork.get_list("list").extend(vec![1_u32, 2, 3]);
+//! db.merge_async(fork.into_patch()).await?;
+//!
+//! let snapshot: Arc<dyn Snapshot> = db.snapshot().into();
+//! let len = snapshot
+//!     .read(|snapshot| snapshot.get_list::<_, u32>("list").len())
+//!     .await;
+//! assert_eq!(len, 3);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [Tokio]: https://docs.rs/tokio/
+
+use tokio::task;
+
+use std::{future::Future, panic, pin::Pin, sync::Arc};
+
+use crate::{Database, Patch, Result, Snapshot};
+
+/// Boxed future returned by the methods of asynchronous traits.
+pub type DbFuture<T> = Pin<Box<dyn Future<Output = T> + Send + 'static>>;
+
+/// Runs a blocking operation on the blocking thread pool of the Tokio runtime.
+/// Panics in the operation are propagated to the caller.
+fn spawn_blocking<F, R>(operation: F) -> DbFuture<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    Box::pin(async move {
+        match task::spawn_blocking(operation).await {
+            Ok(output) => output,
+            Err(err) if err.is_panic() => panic::resume_unwind(err.into_panic()),
+            Err(err) => panic!("Blocking database operation was cancelled: {}", err),
+        }
+    })
+}
+
+/// Asynchronous variants of [`Database`] methods merging patches into the database.
+///
+/// The trait is implemented for database handles shared via `Arc`, since the database
+/// needs to outlive the offloaded operation.
+///
+/// [`Database`]: ../trait.Database.html
+pub trait AsyncDatabase {
+    /// Asynchronous variant of [`Database::merge`].
+    ///
+    /// [`Database::merge`]: ../trait.Database.html#tymethod.merge
+    fn merge_async(&self, patch: Patch) -> DbFuture<Result<()>>;
+
+    /// Asynchronous variant of [`Database::merge_sync`].
+    ///
+    /// [`Database::merge_sync`]: ../trait.Database.html#tymethod.merge_sync
+    fn merge_sync_async(&self, patch: Patch) -> DbFuture<Result<()>>;
+
+    /// Asynchronous variant of [`Database::merge_batch`].
+    ///
+    /// [`Database::merge_batch`]: ../trait.Database.html#method.merge_batch
+    fn merge_batch_async(&self, patches: Vec<Patch>) -> DbFuture<Result<()>>;
+}
+
+impl<T: Database + ?Sized> AsyncDatabase for Arc<T> {
+    fn merge_async(&self, patch: Patch) -> DbFuture<Result<()>> {
+        let db = Arc::clone(self);
+        spawn_blocking(move || db.merge(patch))
+    }
+
+    fn merge_sync_async(&self, patch: Patch) -> DbFuture<Result<()>> {
+        let db = Arc::clone(self);
+        spawn_blocking(move || db.merge_sync(patch))
+    }
+
+    fn merge_batch_async(&self, patches: Vec<Patch>) -> DbFuture<Result<()>> {
+        let db = Arc::clone(self);
+        spawn_blocking(move || db.merge_batch(patches))
+    }
+}
+
+/// Asynchronous reads from a [`Snapshot`].
+///
+/// [`Snapshot`]: ../trait.Snapshot.html
+pub trait AsyncSnapshot {
+    /// Performs reads from the snapshot in the provided closure. The snapshot can be used
+    /// as an access to indexes within the closure.
+    fn read<F, R>(&self, reader: F) -> DbFuture<R>
+    where
+        F: FnOnce(&dyn Snapshot) -> R + Send + 'static,
+        R: Send + 'static;
+}
+
+impl AsyncSnapshot for Arc<dyn Snapshot> {
+    fn read<F, R>(&self, reader: F) -> DbFuture<R>
+    where
+        F: FnOnce(&dyn Snapshot) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let snapshot = Arc::clone(self);
+        spawn_blocking(move || reader(snapshot.as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{access::CopyAccessExt, TemporaryDB};
+
+    #[tokio::test]
+    async fn async_merges_and_reads() {
+        let db = Arc::new(TemporaryDB::new());
+        let fork = db.fork();
+        fork.get_entry("entry").set(1_u64);
+        db.merge_async(fork.into_patch()).await.unwrap();
+
+        let patches = (2_u64..=3)
+            .map(|value| {
+                let fork = db.fork();
+                fork.get_entry("entry").set(value);
+                fork.into_patch()
+            })
+            .collect::<Vec<_>>();
+        let snapshot: Arc<dyn Snapshot> = db.snapshot().into();
+        db.merge_batch_async(patches).await.unwrap();
+
+        // The snapshot is not affected by the later merge.
+        let value = snapshot
+            .read(|snapshot| snapshot.get_entry::<_, u64>("entry").get())
+            .await;
+        assert_eq!(value, Some(1));
+        let snapshot: Arc<dyn Snapshot> = db.snapshot().into();
+        let value = snapshot
+            .read(|snapshot| snapshot.get_entry::<_, u64>("entry").get())
+            .await;
+        assert_eq!(value, Some(3));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "Reader panic")]
+    async fn panics_are_propagated() {
+        let db = TemporaryDB::new();
+        let snapshot: Arc<dyn Snapshot> = db.snapshot().into();
+        snapshot.read(|_| panic!("Reader panic")).await;
+    }
+}
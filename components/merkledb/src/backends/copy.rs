@@ -0,0 +1,190 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Copying of databases between storage backends.
+
+use exonum_crypto::Hash;
+
+use crate::{
+    db::DB_METADATA,
+    views::{IndexesPool, ResolvedAddress, View},
+    Database, Error, Result, SystemSchema,
+};
+
+/// Maximum number of entries written to the target database in a single patch.
+const BATCH_SIZE: usize = 10_000;
+
+/// Copies all data from the `source` database into the empty `target` database.
+///
+/// The data is copied verbatim, including index metadata and state aggregators; thus,
+/// the target database is indistinguishable from the source one for the client code.
+/// This allows, for example, to switch a node to another storage backend or to apply
+/// `DbOptions` which cannot be changed for an existing database without re-syncing
+/// the blockchain. The data is read from a single snapshot of the `source` and is written
+/// to the `target` in batches, so the memory consumption does not depend on the database size.
+///
+/// # Return value
+///
+/// Returns the state hash of the copied data. Before returning, the state hash of the `target`
+/// is checked to coincide with the state hash of the `source`.
+///
+/// # Errors
+///
+/// Returns an error if the `target` database is not empty, if writing to the `target` fails,
+/// or if the state hashes of the databases differ after copying.
+///
+/// # Examples
+///
+/// ```
+/// # use exonum_merkledb::{
+/// #     access::CopyAccessExt, copy_database, Database, SystemSchema, TemporaryDB,
+/// # };
+/// let source = TemporaryDB::new();
+/// let fork = source.fork();
+/// fork.get_proof_list("list").extend(vec![1_u32, 2, 3]);
+/// fork.get_map("map").put(&1_u8, "foo".to_owned());
+/// source.merge(fork.into_patch()).unwrap();
+///
+/// let target = TemporaryDB::new();
+/// let state_hash = copy_database(&source, &target).unwrap();
+/// let snapshot = target.snapshot();
+/// assert_eq!(SystemSchema::new(&snapshot).state_hash(), state_hash);
+/// assert_eq!(snapshot.get_proof_list::<_, u32>("list").len(), 3);
+/// ```
+pub fn copy_database(source: &dyn Database, target: &dyn Database) -> Result<Hash> {
+    if IndexesPool::new(&target.snapshot()).len() > 0 {
+        return Err(Error::new("Target database is not empty"));
+    }
+
+    let snapshot = source.snapshot();
+    let mut addrs = vec![ResolvedAddress::system(DB_METADATA)];
+    let pool = IndexesPool::new(&snapshot);
+    addrs.extend(pool.resolved_addresses());
+    // Access patterns are passed to the target, so that it could tune the storage of indexes.
+    let access_patterns = pool.access_patterns();
+
+    let mut fork = target.fork();
+    let mut batch_len = 0;
+    for addr in addrs {
+        let mut iter = snapshot.iter(&addr, &[]);
+        let access_pattern = access_patterns.get(&addr.name).copied();
+        let mut view = View::new(&fork, addr.clone());
+        view.set_or_forget_access_pattern(access_pattern);
+        while let Some((key, value)) = iter.next() {
+            view.put(key, value.to_vec());
+            batch_len += 1;
+
+            if batch_len == BATCH_SIZE {
+                drop(view);
+                target.merge(fork.into_patch())?;
+                fork = target.fork();
+                view = View::new(&fork, addr.clone());
+                view.set_or_forget_access_pattern(access_pattern);
+                batch_len = 0;
+            }
+        }
+    }
+    target.merge(fork.into_patch())?;
+
+    let source_hash = SystemSchema::new(&snapshot).state_hash();
+    let target_hash = SystemSchema::new(&target.snapshot()).state_hash();
+    if source_hash != target_hash {
+        let msg = format!(
+            "State hash of the copied database ({:?}) differs from the state hash \
+             of the source database ({:?})",
+            target_hash, source_hash
+        );
+        return Err(Error::new(msg));
+    }
+    Ok(target_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        access::{AccessExt, CopyAccessExt},
+        migration::Migration,
+        ObjectHash, TemporaryDB,
+    };
+
+    #[test]
+    fn copying_database() {
+        let source = TemporaryDB::new();
+        let fork = source.fork();
+        fork.get_proof_list("list")
+            .extend((0..BATCH_SIZE as u64 * 2 + 5).map(|i| i.to_string()));
+        fork.get_proof_map(("group", &1_u8)).put(&2_u32, 3_u64);
+        fork.get_value_set("set").insert(42_u64);
+        fork.get_proof_entry("ns.entry").set("foo".to_owned());
+        Migration::new("ns", &fork)
+            .get_proof_entry("entry")
+            .set("bar".to_owned());
+        source.merge(fork.into_patch()).unwrap();
+        let fork = source.fork();
+        fork.get_map("map").put(&1_u8, vec![1_u8, 2, 3]);
+        source.merge(fork.into_patch()).unwrap();
+
+        let target = TemporaryDB::new();
+        let state_hash = copy_database(&source, &target).unwrap();
+        let source_snapshot = source.snapshot();
+        let snapshot = target.snapshot();
+        assert_eq!(state_hash, SystemSchema::new(&source_snapshot).state_hash());
+
+        let list = snapshot.get_proof_list::<_, String>("list");
+        assert_eq!(list.len(), BATCH_SIZE as u64 * 2 + 5);
+        assert_eq!(
+            list.object_hash(),
+            source_snapshot
+                .get_proof_list::<_, String>("list")
+                .object_hash()
+        );
+        let map = snapshot.get_proof_map::<_, u32, u64>(("group", &1_u8));
+        assert_eq!(map.get(&2), Some(3));
+        assert!(snapshot.get_value_set::<_, u64>("set").contains(&42));
+        assert_eq!(
+            snapshot.get_map::<_, u8, Vec<u8>>("map").get(&1),
+            Some(vec![1, 2, 3])
+        );
+        let migration_hash = Migration::new("ns", &snapshot).state_hash();
+        assert_eq!(
+            migration_hash,
+            Migration::new("ns", &source_snapshot).state_hash()
+        );
+
+        // New indexes in the target database must not clash with the copied ones.
+        let fork = target.fork();
+        fork.get_list("other_list").push(1_u32);
+        assert_eq!(fork.get_proof_list::<_, String>("list").len(), list.len());
+        target.merge(fork.into_patch()).unwrap();
+        let snapshot = target.snapshot();
+        assert_eq!(snapshot.get_list::<_, u32>("other_list").len(), 1);
+        assert_eq!(
+            snapshot.get_map::<_, u8, Vec<u8>>("map").get(&1),
+            Some(vec![1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn copying_into_non_empty_database() {
+        let source = TemporaryDB::new();
+        let target = TemporaryDB::new();
+        let fork = target.fork();
+        fork.get_entry("entry").set(1_u8);
+        target.merge(fork.into_patch()).unwrap();
+
+        let err = copy_database(&source, &target).unwrap_err();
+        assert!(err.to_string().contains("not empty"));
+    }
+}
@@ -0,0 +1,277 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encryption of values stored in the database.
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use sha3::{Digest, Sha3_256};
+
+use std::{collections::BTreeMap, convert::TryInto, fmt};
+
+use crate::options::EncryptionOptions;
+
+/// Size of an encryption key in bytes.
+pub const ENCRYPTION_KEY_LENGTH: usize = 32;
+
+/// Size of the key version prepended to encrypted values.
+const VERSION_SIZE: usize = 4;
+/// Size of the nonce prepended to encrypted values.
+const NONCE_SIZE: usize = 12;
+/// Domain separator for the derivation of encryption keys.
+const KEY_DERIVATION_DOMAIN: &[u8] = b"exonum-merkledb-encryption";
+
+/// Versioned keys used to encrypt values in the database.
+///
+/// Values are encrypted with the key of the active version. Keys of other versions
+/// are retired: they are only used to decrypt values written before key rotation.
+pub struct EncryptionKeys {
+    ciphers: BTreeMap<u32, Aes256Gcm>,
+    active_version: u32,
+}
+
+impl fmt::Debug for EncryptionKeys {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("EncryptionKeys")
+            .field("versions", &self.ciphers.keys().collect::<Vec<_>>())
+            .field("active_version", &self.active_version)
+            .finish()
+    }
+}
+
+impl EncryptionKeys {
+    /// Creates keys with the specified active key.
+    pub fn new(active_version: u32, key: &[u8; ENCRYPTION_KEY_LENGTH]) -> Self {
+        let mut ciphers = BTreeMap::new();
+        ciphers.insert(active_version, Self::cipher(key));
+        Self {
+            ciphers,
+            active_version,
+        }
+    }
+
+    /// Derives keys from the provided secret, e.g., a dedicated data encryption secret
+    /// of the node. Besides the key of the active version, keys are derived only for
+    /// the retired versions explicitly listed in `options`.
+    pub fn derive(secret: &[u8], options: &EncryptionOptions) -> Self {
+        let ciphers = options
+            .retired_key_versions
+            .iter()
+            .chain(Some(&options.key_version))
+            .map(|&version| {
+                let key = Sha3_256::new()
+                    .chain_update(KEY_DERIVATION_DOMAIN)
+                    .chain_update(version.to_be_bytes())
+                    .chain_update(secret)
+                    .finalize();
+                (version, Self::cipher(&key.into()))
+            })
+            .collect();
+        Self {
+            ciphers,
+            active_version: options.key_version,
+        }
+    }
+
+    /// Adds a retired key, which is used to decrypt values written with it.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `version` is the active version.
+    #[must_use]
+    pub fn with_retired_key(mut self, version: u32, key: &[u8; ENCRYPTION_KEY_LENGTH]) -> Self {
+        assert_ne!(
+            version, self.active_version,
+            "Retired key cannot have the active version"
+        );
+        self.ciphers.insert(version, Self::cipher(key));
+        self
+    }
+
+    /// Returns the version of the active key.
+    pub fn active_version(&self) -> u32 {
+        self.active_version
+    }
+
+    fn cipher(key: &[u8; ENCRYPTION_KEY_LENGTH]) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key))
+    }
+}
+
+/// Encrypts and decrypts values of the configured column families.
+///
+/// Encrypted values have the following layout: the big-endian version of the key (4 bytes),
+/// the random nonce (12 bytes) and the ciphertext with the authentication tag. The name
+/// of the column family and the key of the entry are authenticated as associated data,
+/// so that the encrypted values cannot be moved among entries.
+#[derive(Debug)]
+pub(crate) struct Encryptor {
+    options: EncryptionOptions,
+    keys: EncryptionKeys,
+}
+
+impl Encryptor {
+    pub fn new(options: &EncryptionOptions, keys: EncryptionKeys) -> crate::Result<Self> {
+        if keys.active_version != options.key_version {
+            return Err(crate::Error::new(format!(
+                "Active encryption key has version {}, while the database is configured \
+                 to use version {}",
+                keys.active_version, options.key_version
+            )));
+        }
+        Ok(Self {
+            options: options.clone(),
+            keys,
+        })
+    }
+
+    pub fn is_encrypted(&self, cf_name: &str) -> bool {
+        self.options.is_encrypted(cf_name)
+    }
+
+    pub fn active_version(&self) -> u32 {
+        self.keys.active_version
+    }
+
+    /// Returns the version of the key used to encrypt the value.
+    pub fn key_version(value: &[u8]) -> Option<u32> {
+        let version_bytes = value.get(..VERSION_SIZE)?;
+        Some(u32::from_be_bytes(version_bytes.try_into().unwrap()))
+    }
+
+    pub fn encrypt(&self, cf_name: &str, key: &[u8], value: &[u8]) -> Vec<u8> {
+        let version = self.keys.active_version;
+        let cipher = &self.keys.ciphers[&version];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let aad = associated_data(cf_name, key);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: value,
+                    aad: &aad,
+                },
+            )
+            .expect("Cannot encrypt value");
+
+        let mut output = Vec::with_capacity(VERSION_SIZE + NONCE_SIZE + ciphertext.len());
+        output.extend_from_slice(&version.to_be_bytes());
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&ciphertext);
+        output
+    }
+
+    pub fn decrypt(&self, cf_name: &str, key: &[u8], value: &[u8]) -> crate::Result<Vec<u8>> {
+        let version = Self::key_version(value).ok_or_else(|| {
+            crate::Error::new(format!(
+                "Encrypted value in column family `{}` is truncated",
+                cf_name
+            ))
+        })?;
+        let cipher = self.keys.ciphers.get(&version).ok_or_else(|| {
+            crate::Error::new(format!(
+                "Encryption key of version {} required for column family `{}` is unknown",
+                version, cf_name
+            ))
+        })?;
+        let value = &value[VERSION_SIZE..];
+        if value.len() < NONCE_SIZE {
+            return Err(crate::Error::new(format!(
+                "Encrypted value in column family `{}` is truncated",
+                cf_name
+            )));
+        }
+
+        let (nonce, ciphertext) = value.split_at(NONCE_SIZE);
+        let aad = associated_data(cf_name, key);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: &aad,
+        };
+        cipher
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| {
+                crate::Error::new(format!(
+                    "Cannot decrypt value in column family `{}`",
+                    cf_name
+                ))
+            })
+    }
+}
+
+fn associated_data(cf_name: &str, key: &[u8]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(cf_name.len() + 1 + key.len());
+    aad.extend_from_slice(cf_name.as_bytes());
+    aad.push(0);
+    aad.extend_from_slice(key);
+    aad
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EncryptionKeys, Encryptor};
+    use crate::options::EncryptionOptions;
+
+    #[test]
+    fn encryption_roundtrip() {
+        let options = EncryptionOptions::new(0);
+        let encryptor = Encryptor::new(&options, EncryptionKeys::new(0, &[1; 32])).unwrap();
+        let encrypted = encryptor.encrypt("cf", b"key", b"value");
+        assert_eq!(Encryptor::key_version(&encrypted), Some(0));
+        assert_ne!(&encrypted[4..], b"value");
+        let decrypted = encryptor.decrypt("cf", b"key", &encrypted).unwrap();
+        assert_eq!(decrypted, b"value");
+
+        // The value cannot be decrypted for another entry.
+        encryptor.decrypt("cf", b"other", &encrypted).unwrap_err();
+        encryptor.decrypt("other", b"key", &encrypted).unwrap_err();
+        encryptor
+            .decrypt("cf", b"key", &encrypted[..10])
+            .unwrap_err();
+    }
+
+    #[test]
+    fn key_rotation() {
+        let secret = b"secret";
+        let old_options = EncryptionOptions::new(0);
+        let keys = EncryptionKeys::derive(secret, &old_options);
+        let encryptor = Encryptor::new(&old_options, keys).unwrap();
+        let encrypted = encryptor.encrypt("cf", b"key", b"value");
+
+        let new_options = EncryptionOptions::new(1);
+        Encryptor::new(&new_options, EncryptionKeys::derive(secret, &old_options)).unwrap_err();
+        // Retired keys are not derived unless they are explicitly listed in the options.
+        let keys = EncryptionKeys::derive(secret, &new_options);
+        let encryptor = Encryptor::new(&new_options, keys).unwrap();
+        encryptor.decrypt("cf", b"key", &encrypted).unwrap_err();
+
+        let new_options = new_options.with_retired_key_version(0);
+        let keys = EncryptionKeys::derive(secret, &new_options);
+        let encryptor = Encryptor::new(&new_options, keys).unwrap();
+        assert_eq!(
+            encryptor.decrypt("cf", b"key", &encrypted).unwrap(),
+            b"value"
+        );
+        let reencrypted = encryptor.encrypt("cf", b"key", b"value");
+        assert_eq!(Encryptor::key_version(&reencrypted), Some(1));
+
+        // Values cannot be decrypted with the keys derived from another secret.
+        let keys = EncryptionKeys::derive(b"other", &new_options);
+        let encryptor = Encryptor::new(&new_options, keys).unwrap();
+        encryptor.decrypt("cf", b"key", &encrypted).unwrap_err();
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Garbage collection of data left from removed indexes.
+
+use std::collections::HashSet;
+
+use crate::{
+    views::{IndexesPool, ResolvedAddress, View},
+    Database, Result,
+};
+
+/// Returns addresses of the data stored in the database, which does not belong to any index
+/// tracked by the index metadata. Such data may be left, for example, after a migration
+/// is rolled back: the migrated indexes are removed from the metadata, but the data merged
+/// into the database before the rollback remains in the storage. Orphaned data cannot be
+/// accessed by the client code and does not influence the state hash of the database.
+///
+/// System views (such as the index metadata itself) are never considered orphaned.
+///
+/// # Errors
+///
+/// Returns an error if the database does not support enumerating stored data
+/// (see [`Database::stored_addresses`]).
+///
+/// [`Database::stored_addresses`]: trait.Database.html#method.stored_addresses
+pub fn orphaned_addresses(db: &dyn Database) -> Result<Vec<ResolvedAddress>> {
+    // Stored addresses are retrieved before the snapshot, so that the indexes created
+    // concurrently are either not enumerated or present in the snapshot metadata.
+    let stored_addrs = db.stored_addresses()?;
+    let snapshot = db.snapshot();
+    let tracked_addrs: HashSet<_> = IndexesPool::new(&snapshot)
+        .resolved_addresses()
+        .into_iter()
+        .collect();
+
+    let orphaned_addrs = stored_addrs
+        .into_iter()
+        .filter(|addr| addr.id.is_some() && !tracked_addrs.contains(addr))
+        .collect();
+    Ok(orphaned_addrs)
+}
+
+/// Removes orphaned data from the database (see [`orphaned_addresses`] for details).
+/// The data is removed in a single patch, which is merged with the `merge_sync` method.
+/// Depending on the database, the occupied disk space may be reclaimed only after
+/// the database is compacted (see [`Database::compact_range`]).
+///
+/// # Return value
+///
+/// Returns addresses of the removed data.
+///
+/// # Errors
+///
+/// Returns an error if the database does not support enumerating stored data,
+/// or if the removal patch cannot be merged.
+///
+/// # Examples
+///
+/// ```
+/// # use exonum_merkledb::{
+/// #     access::CopyAccessExt, migration::{rollback_migration, Migration},
+/// #     orphaned_addresses, remove_orphaned_data, Database, TemporaryDB,
+/// # };
+/// let db = TemporaryDB::new();
+/// let fork = db.fork();
+/// Migration::new("ns", &fork).get_proof_list("list").extend(vec![1_u32, 2, 3]);
+/// db.merge(fork.into_patch()).unwrap();
+///
+/// // Rolling back the migration leaves the data of the migrated list in the storage.
+/// let mut fork = db.fork();
+/// rollback_migration(&mut fork, "ns");
+/// db.merge(fork.into_patch()).unwrap();
+/// assert_eq!(orphaned_addresses(&db).unwrap().len(), 1);
+///
+/// let removed = remove_orphaned_data(&db).unwrap();
+/// assert_eq!(removed.len(), 1);
+/// assert!(orphaned_addresses(&db).unwrap().is_empty());
+/// ```
+///
+/// [`orphaned_addresses`]: fn.orphaned_addresses.html
+/// [`Database::compact_range`]: trait.Database.html#method.compact_range
+pub fn remove_orphaned_data(db: &dyn Database) -> Result<Vec<ResolvedAddress>> {
+    let orphaned_addrs = orphaned_addresses(db)?;
+    if orphaned_addrs.is_empty() {
+        return Ok(orphaned_addrs);
+    }
+
+    let fork = db.fork();
+    for addr in &orphaned_addrs {
+        View::new(&fork, addr.clone()).clear();
+    }
+    db.merge_sync(fork.into_patch())?;
+    Ok(orphaned_addrs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        access::CopyAccessExt,
+        migration::{rollback_migration, Migration},
+        SystemSchema, TemporaryDB,
+    };
+
+    #[test]
+    fn removing_orphaned_data() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        fork.get_proof_list("list").extend(vec![1_u32, 2, 3]);
+        fork.get_map(("group", &1_u8)).put(&2_u32, 3_u64);
+        Migration::new("ns", &fork)
+            .get_proof_entry("entry")
+            .set("foo".to_owned());
+        Migration::new("ns", &fork)
+            .get_map(("group", &1_u8))
+            .put(&1_u32, 1_u64);
+        db.merge(fork.into_patch()).unwrap();
+        assert!(orphaned_addresses(&db).unwrap().is_empty());
+
+        let mut fork = db.fork();
+        rollback_migration(&mut fork, "ns");
+        db.merge(fork.into_patch()).unwrap();
+        let state_hash = SystemSchema::new(&db.snapshot()).state_hash();
+
+        let orphaned = orphaned_addresses(&db).unwrap();
+        assert_eq!(orphaned.len(), 2);
+        assert_eq!(remove_orphaned_data(&db).unwrap(), orphaned);
+        assert!(orphaned_addresses(&db).unwrap().is_empty());
+
+        // Data of the remaining indexes is not affected.
+        let snapshot = db.snapshot();
+        assert_eq!(SystemSchema::new(&snapshot).state_hash(), state_hash);
+        let list = snapshot.get_proof_list::<_, u32>("list");
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+        let map = snapshot.get_map::<_, u32, u64>(("group", &1_u8));
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(2, 3)]);
+        for addr in &orphaned {
+            assert!(snapshot.iter(addr, &[]).next().is_none());
+        }
+    }
+}
@@ -0,0 +1,619 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of `LMDB` database.
+
+use lmdb::{
+    Cursor, Database as LmdbDatabase, DatabaseFlags, Environment, EnvironmentFlags, RoCursor,
+    RoTransaction, RwTransaction, Transaction, WriteFlags,
+};
+use lmdb_sys::{MDB_LAST, MDB_PREV, MDB_SET_RANGE};
+use serde::{Deserialize, Serialize};
+
+use std::{fmt, fs, iter::Peekable, mem, ops::Bound, path::Path, sync::Arc};
+
+use crate::{
+    db::{check_database, check_database_version, precedes, Change},
+    views::prefix_successor,
+    Database, Iter, Iterator, Patch, ResolvedAddress, Snapshot, ValueRef,
+};
+
+/// Name of the LMDB database storing all entries.
+const DB_NAME: &str = "exonum";
+
+/// Options for the `Lmdb` database.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LmdbOptions {
+    /// Maximum size of the database in bytes. The database file is memory-mapped with
+    /// this size, so it should be a multiple of the OS page size.
+    ///
+    /// Defaults to 1 TiB.
+    pub map_size: usize,
+    /// Maximum number of simultaneously open snapshots.
+    ///
+    /// Defaults to 1024.
+    pub max_readers: u32,
+    /// Whether to create the database if it is missing.
+    ///
+    /// Defaults to `true`.
+    pub create_if_missing: bool,
+}
+
+impl Default for LmdbOptions {
+    fn default() -> Self {
+        Self {
+            map_size: 1 << 40,
+            max_readers: 1_024,
+            create_if_missing: true,
+        }
+    }
+}
+
+impl LmdbOptions {
+    /// Creates options with the specified map size.
+    pub fn new(map_size: usize) -> Self {
+        Self {
+            map_size,
+            ..Self::default()
+        }
+    }
+}
+
+/// Database implementation on top of [`LMDB`](https://symas.com/lmdb/).
+///
+/// LMDB is a memory-mapped B+tree key-value storage. Reads are served directly from
+/// the mapped memory without copying, so the backend is well suited for read-heavy
+/// workloads, such as generating proofs for many API clients. On the other hand,
+/// writes are slower than in [`RocksDB`], and the database size is limited by
+/// the map size specified on opening.
+///
+/// All indexes are stored in a single LMDB database; each key is prefixed by the name
+/// of the index followed by a zero byte and, for indexes in groups, the index ID.
+/// LMDB limits the size of keys, thus the total length of the prefixed key must not exceed
+/// 511 bytes; otherwise, merging a patch with such a key fails.
+///
+/// [`RocksDB`]: struct.RocksDB.html
+pub struct Lmdb {
+    env: Arc<Environment>,
+    db: LmdbDatabase,
+}
+
+/// A snapshot of an `Lmdb` database.
+pub struct LmdbSnapshot {
+    // The transaction must be dropped before the environment.
+    txn: RoTransaction<'static>,
+    db: LmdbDatabase,
+    _env: Arc<Environment>,
+}
+
+/// An iterator over the entries of an `Lmdb` database.
+struct LmdbIterator<'a> {
+    // The iterator must be dropped before the cursor.
+    iter: Peekable<lmdb::Iter<'a>>,
+    cursor: RoCursor<'a>,
+    prefix: Vec<u8>,
+    ended: bool,
+}
+
+/// Iterator over the LMDB entries in descending order. LMDB does not provide a reverse
+/// iterator, so the cursor is moved manually.
+struct LmdbRevIterator<'a> {
+    cursor: RoCursor<'a>,
+    prefix: Vec<u8>,
+    current: Option<(&'a [u8], &'a [u8])>,
+}
+
+impl Lmdb {
+    /// Opens a database stored at the specified path with the specified options.
+    ///
+    /// If the database does not exist at the indicated path and the option
+    /// `create_if_missing` is switched on in `LmdbOptions`, a new database will
+    /// be created at the indicated path.
+    pub fn open<P: AsRef<Path>>(path: P, options: &LmdbOptions) -> crate::Result<Self> {
+        let path = path.as_ref();
+        if options.create_if_missing {
+            fs::create_dir_all(path).map_err(|e| crate::Error::new(e.to_string()))?;
+        }
+
+        let env = Self::environment(options, EnvironmentFlags::empty()).open(path)?;
+        let db = env.create_db(Some(DB_NAME), DatabaseFlags::empty())?;
+        let mut db = Self {
+            env: Arc::new(env),
+            db,
+        };
+        check_database(&mut db)?;
+        Ok(db)
+    }
+
+    /// Opens an existing database stored at the specified path in the read-only mode.
+    ///
+    /// The database may be opened in the read-only mode while it is used by another process
+    /// (e.g., a running node). Any attempt to merge changes into a read-only database
+    /// results in an error.
+    pub fn open_read_only<P: AsRef<Path>>(path: P, options: &LmdbOptions) -> crate::Result<Self> {
+        let env = Self::environment(options, EnvironmentFlags::READ_ONLY).open(path.as_ref())?;
+        let db = env.open_db(Some(DB_NAME))?;
+        let db = Self {
+            env: Arc::new(env),
+            db,
+        };
+        check_database_version(db.snapshot().as_ref())?;
+        Ok(db)
+    }
+
+    fn environment(options: &LmdbOptions, flags: EnvironmentFlags) -> lmdb::EnvironmentBuilder {
+        let mut builder = Environment::new();
+        builder
+            // Snapshots may be sent to and used from other threads.
+            .set_flags(flags | EnvironmentFlags::NO_TLS)
+            .set_map_size(options.map_size)
+            .set_max_readers(options.max_readers)
+            .set_max_dbs(1);
+        builder
+    }
+
+    fn do_merge(&self, patch: Patch) -> crate::Result<()> {
+        let mut txn = self.env.begin_rw_txn()?;
+        self.write_patch(&mut txn, patch)?;
+        txn.commit().map_err(Into::into)
+    }
+
+    /// Writes changes from the patch within the transaction.
+    fn write_patch(&self, txn: &mut RwTransaction<'_>, patch: Patch) -> crate::Result<()> {
+        for (resolved, changes) in patch.into_changes() {
+            let prefix = key_prefix(&resolved);
+            if changes.is_cleared() {
+                clear_prefix(txn, self.db, &prefix)?;
+            }
+
+            let mut buffer = prefix.clone();
+            for (from, to) in changes.removed_ranges() {
+                let mut to_buffer = prefix.clone();
+                buffer.truncate(prefix.len());
+                buffer.extend_from_slice(from);
+                to_buffer.extend_from_slice(to);
+                remove_range(txn, self.db, &buffer, &to_buffer)?;
+            }
+            for (key, change) in changes.into_data() {
+                buffer.truncate(prefix.len());
+                buffer.extend_from_slice(&key);
+                match change {
+                    Change::Put(ref value) => {
+                        txn.put(self.db, &buffer, value, WriteFlags::empty())?;
+                    }
+                    Change::Delete => match txn.del(self.db, &buffer, None) {
+                        Ok(()) | Err(lmdb::Error::NotFound) => {}
+                        Err(e) => return Err(e.into()),
+                    },
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(unsafe_code)]
+    fn lmdb_snapshot(&self) -> LmdbSnapshot {
+        let txn = self
+            .env
+            .begin_ro_txn()
+            .expect("Cannot begin read-only LMDB transaction");
+        LmdbSnapshot {
+            // SAFETY:
+            // The snapshot carries an `Arc` to the environment to make sure that it
+            // is not dropped before the transaction; the transaction is declared before
+            // the environment, so it is dropped first.
+            txn: unsafe { mem::transmute::<RoTransaction<'_>, RoTransaction<'static>>(txn) },
+            db: self.db,
+            _env: Arc::clone(&self.env),
+        }
+    }
+}
+
+/// Returns the prefix of the keys belonging to the resolved address.
+fn key_prefix(resolved: &ResolvedAddress) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(resolved.name.len() + 1 + mem::size_of::<u64>());
+    prefix.extend_from_slice(resolved.name.as_bytes());
+    prefix.push(0);
+    if let Some(id_bytes) = resolved.id_to_bytes() {
+        prefix.extend_from_slice(&id_bytes);
+    }
+    prefix
+}
+
+/// Removes all keys with the specified prefix from the database.
+fn clear_prefix(txn: &mut RwTransaction<'_>, db: LmdbDatabase, prefix: &[u8]) -> lmdb::Result<()> {
+    let keys = {
+        let mut cursor = txn.open_ro_cursor(db)?;
+        cursor
+            .iter_from(prefix)
+            .map_while(Result::ok)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.to_vec())
+            .collect::<Vec<_>>()
+    };
+    for key in keys {
+        txn.del(db, &key, None)?;
+    }
+    Ok(())
+}
+
+/// Removes all keys in the `from..to` range from the database. LMDB has no native
+/// range deletion, so the keys are removed one by one.
+fn remove_range(
+    txn: &mut RwTransaction<'_>,
+    db: LmdbDatabase,
+    from: &[u8],
+    to: &[u8],
+) -> lmdb::Result<()> {
+    let keys = {
+        let mut cursor = txn.open_ro_cursor(db)?;
+        cursor
+            .iter_from(from)
+            .map_while(Result::ok)
+            .take_while(|(key, _)| *key < to)
+            .map(|(key, _)| key.to_vec())
+            .collect::<Vec<_>>()
+    };
+    for key in keys {
+        txn.del(db, &key, None)?;
+    }
+    Ok(())
+}
+
+impl Database for Lmdb {
+    fn snapshot(&self) -> Box<dyn Snapshot> {
+        Box::new(self.lmdb_snapshot())
+    }
+
+    fn merge(&self, patch: Patch) -> crate::Result<()> {
+        self.do_merge(patch)
+    }
+
+    fn merge_sync(&self, patch: Patch) -> crate::Result<()> {
+        self.do_merge(patch)?;
+        self.env.sync(true).map_err(Into::into)
+    }
+
+    /// Writes all patches within a single transaction, after which the environment
+    /// is synced to the disk once.
+    fn merge_batch(&self, patches: Vec<Patch>) -> crate::Result<()> {
+        let mut txn = self.env.begin_rw_txn()?;
+        for patch in patches {
+            self.write_patch(&mut txn, patch)?;
+        }
+        txn.commit()?;
+        self.env.sync(true).map_err(Into::into)
+    }
+}
+
+impl Snapshot for LmdbSnapshot {
+    fn get(&self, resolved_addr: &ResolvedAddress, key: &[u8]) -> Option<Vec<u8>> {
+        let mut raw_key = key_prefix(resolved_addr);
+        raw_key.extend_from_slice(key);
+        match self.txn.get(self.db, &raw_key) {
+            Ok(value) => Some(value.to_vec()),
+            Err(lmdb::Error::NotFound) => None,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    fn get_ref(&self, resolved_addr: &ResolvedAddress, key: &[u8]) -> Option<ValueRef<'_>> {
+        let mut raw_key = key_prefix(resolved_addr);
+        raw_key.extend_from_slice(key);
+        match self.txn.get(self.db, &raw_key) {
+            Ok(value) => Some(ValueRef::from(value)),
+            Err(lmdb::Error::NotFound) => None,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    fn iter(&self, name: &ResolvedAddress, from: &[u8]) -> Iter<'_> {
+        let prefix = key_prefix(name);
+        let mut from_key = prefix.clone();
+        from_key.extend_from_slice(from);
+
+        let mut cursor = self
+            .txn
+            .open_ro_cursor(self.db)
+            .expect("Cannot open LMDB cursor");
+        let iter = cursor.iter_from(from_key);
+        Box::new(LmdbIterator {
+            iter: iter.peekable(),
+            cursor,
+            prefix,
+            ended: false,
+        })
+    }
+
+    fn iter_rev(&self, name: &ResolvedAddress, upper: Bound<&[u8]>) -> Iter<'_> {
+        let prefix = key_prefix(name);
+        let with_prefix = |key: &[u8]| {
+            let mut raw_key = prefix.clone();
+            raw_key.extend_from_slice(key);
+            raw_key
+        };
+        let (upper, is_inclusive) = match upper {
+            Bound::Included(key) => (Some(with_prefix(key)), true),
+            Bound::Excluded(key) => (Some(with_prefix(key)), false),
+            Bound::Unbounded => (prefix_successor(&prefix), false),
+        };
+
+        let cursor = self
+            .txn
+            .open_ro_cursor(self.db)
+            .expect("Cannot open LMDB cursor");
+        let start = match upper {
+            Some(upper) => seek_rev(&cursor, &upper, is_inclusive),
+            None => cursor.get(None, None, MDB_LAST),
+        };
+
+        let mut iter = LmdbRevIterator {
+            cursor,
+            prefix,
+            current: None,
+        };
+        iter.set_current(start);
+        Box::new(iter)
+    }
+}
+
+/// Positions the cursor at the greatest key less than `key` (or equal to it if `inclusive`
+/// is set) and returns the corresponding entry.
+fn seek_rev<'a>(
+    cursor: &RoCursor<'a>,
+    key: &[u8],
+    inclusive: bool,
+) -> lmdb::Result<(Option<&'a [u8]>, &'a [u8])> {
+    // `MDB_SET_RANGE` positions the cursor at the least key not less than the bound.
+    match cursor.get(Some(key), None, MDB_SET_RANGE) {
+        Ok((Some(found), value)) if inclusive && found == key => Ok((Some(found), value)),
+        Ok(_) => cursor.get(None, None, MDB_PREV),
+        Err(_) => cursor.get(None, None, MDB_LAST),
+    }
+}
+
+// SAFETY: The environment is opened with the `NO_TLS` flag, which allows to use read-only
+// transactions from any thread; LMDB read-only transactions do not mutate shared state
+// and thus may be used concurrently.
+#[allow(unsafe_code)]
+unsafe impl Send for LmdbSnapshot {}
+#[allow(unsafe_code)]
+unsafe impl Sync for LmdbSnapshot {}
+
+impl<'a> Iterator for LmdbIterator<'a> {
+    fn next(&mut self) -> Option<(&[u8], &[u8])> {
+        if self.ended {
+            return None;
+        }
+
+        let (key, value) = self.iter.next()?.ok()?;
+        if !key.starts_with(&self.prefix) {
+            self.ended = true;
+            return None;
+        }
+        Some((&key[self.prefix.len()..], value))
+    }
+
+    fn peek(&mut self) -> Option<(&[u8], &[u8])> {
+        if self.ended {
+            return None;
+        }
+
+        let (key, value) = self.iter.peek()?.as_ref().ok()?;
+        if !key.starts_with(&self.prefix) {
+            self.ended = true;
+            return None;
+        }
+        Some((&key[self.prefix.len()..], value))
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        if self.ended {
+            return;
+        }
+        let mut target = self.prefix.clone();
+        target.extend_from_slice(key);
+        // Do nothing if the iterator is exhausted or is already positioned at the target.
+        let is_behind = matches!(
+            self.iter.peek(),
+            Some(Ok((current, _))) if precedes(current, &target, false)
+        );
+        if is_behind {
+            self.iter = self.cursor.iter_from(target).peekable();
+        }
+    }
+}
+
+impl<'a> LmdbRevIterator<'a> {
+    fn set_current(&mut self, entry: lmdb::Result<(Option<&'a [u8]>, &'a [u8])>) {
+        self.current = match entry {
+            Ok((Some(key), value)) if key.starts_with(&self.prefix) => Some((key, value)),
+            _ => None,
+        };
+    }
+}
+
+impl<'a> Iterator for LmdbRevIterator<'a> {
+    fn next(&mut self) -> Option<(&[u8], &[u8])> {
+        let (key, value) = self.current?;
+        let prev = self.cursor.get(None, None, MDB_PREV);
+        self.set_current(prev);
+        Some((&key[self.prefix.len()..], value))
+    }
+
+    fn peek(&mut self) -> Option<(&[u8], &[u8])> {
+        let (key, value) = self.current?;
+        Some((&key[self.prefix.len()..], value))
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        let mut target = self.prefix.clone();
+        target.extend_from_slice(key);
+        // Do nothing if the iterator is exhausted or is already positioned at the target.
+        let is_behind =
+            matches!(self.current, Some((current, _)) if precedes(current, &target, true));
+        if is_behind {
+            let entry = seek_rev(&self.cursor, &target, true);
+            self.set_current(entry);
+        }
+    }
+}
+
+impl From<Lmdb> for Arc<dyn Database> {
+    fn from(db: Lmdb) -> Self {
+        Self::from(Box::new(db) as Box<dyn Database>)
+    }
+}
+
+impl fmt::Debug for Lmdb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lmdb").finish()
+    }
+}
+
+impl fmt::Debug for LmdbSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LmdbSnapshot").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use super::{Lmdb, LmdbOptions};
+    use crate::{access::CopyAccessExt, Database};
+
+    fn options() -> LmdbOptions {
+        LmdbOptions::new(1 << 24)
+    }
+
+    #[test]
+    fn lmdb_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let db = Lmdb::open(temp_dir.path(), &options()).unwrap();
+            let fork = db.fork();
+            fork.get_entry("entry").set(1_u64);
+            fork.get_list("list").extend(vec![1_u64, 2, 3]);
+            fork.get_proof_map(("group", &1_u8))
+                .put(&1_u8, "1".to_owned());
+            fork.get_proof_map(("group", &2_u8))
+                .put(&2_u8, "2".to_owned());
+            db.merge_sync(fork.into_patch()).unwrap();
+        }
+
+        // Data is persisted after reopening.
+        let db = Lmdb::open(temp_dir.path(), &options()).unwrap();
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get_entry::<_, u64>("entry").get(), Some(1));
+        let list = snapshot.get_list::<_, u64>("list");
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(list.iter_rev().collect::<Vec<_>>(), vec![3, 2, 1]);
+
+        // Iteration does not leak into other indexes in the same group.
+        let map = snapshot.get_proof_map::<_, u8, String>(("group", &1_u8));
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(1, "1".to_owned())]);
+        let map = snapshot.get_proof_map::<_, u8, String>(("group", &2_u8));
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec![2]);
+        let set = snapshot.get_key_set::<_, u8>(("group", &1_u8));
+        assert_eq!(set.iter_rev().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn lmdb_snapshot_isolation_and_clearing() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Lmdb::open(temp_dir.path(), &options()).unwrap();
+        let fork = db.fork();
+        fork.get_list("list").extend(vec![1_u64, 2, 3]);
+        fork.get_list("list_other").push(4_u64);
+        db.merge(fork.into_patch()).unwrap();
+
+        let old_snapshot = db.snapshot();
+        let fork = db.fork();
+        fork.get_list::<_, u64>("list").clear();
+        db.merge(fork.into_patch()).unwrap();
+
+        let old_list = old_snapshot.get_list::<_, u64>("list");
+        assert_eq!(old_list.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+        let snapshot = db.snapshot();
+        assert!(snapshot.get_list::<_, u64>("list").is_empty());
+        let other_list = snapshot.get_list::<_, u64>("list_other");
+        assert_eq!(other_list.iter().collect::<Vec<_>>(), vec![4]);
+    }
+
+    #[test]
+    fn lmdb_remove_range() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Lmdb::open(temp_dir.path(), &options()).unwrap();
+        let fork = db.fork();
+        fork.get_map(("group", &1_u8))
+            .extend((0_u8..10).map(|i| (i, i)));
+        fork.get_map(("group", &2_u8)).put(&5_u8, 5_u8);
+        db.merge(fork.into_patch()).unwrap();
+
+        let fork = db.fork();
+        fork.remove_range(("group", &1_u8), &2_u8, &7_u8).unwrap();
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let map = snapshot.get_map::<_, u8, u8>(("group", &1_u8));
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec![0, 1, 7, 8, 9]);
+        // Other indexes in the same group are not affected.
+        let other_map = snapshot.get_map::<_, u8, u8>(("group", &2_u8));
+        assert_eq!(other_map.get(&5), Some(5));
+    }
+
+    #[test]
+    fn lmdb_merge_batch() {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let db = Lmdb::open(temp_dir.path(), &options()).unwrap();
+            let fork = db.fork();
+            fork.get_list("list").extend(vec![1_u64, 2, 3]);
+            db.merge(fork.into_patch()).unwrap();
+
+            let first_fork = db.fork();
+            first_fork.get_list::<_, u64>("list").clear();
+            let second_fork = db.fork();
+            second_fork.get_entry("entry").set(1_u64);
+            db.merge_batch(vec![first_fork.into_patch(), second_fork.into_patch()])
+                .unwrap();
+        }
+
+        let db = Lmdb::open(temp_dir.path(), &options()).unwrap();
+        let snapshot = db.snapshot();
+        assert!(snapshot.get_list::<_, u64>("list").is_empty());
+        assert_eq!(snapshot.get_entry::<_, u64>("entry").get(), Some(1));
+    }
+
+    #[test]
+    fn lmdb_read_only() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Lmdb::open(temp_dir.path(), &options()).unwrap();
+        let fork = db.fork();
+        fork.get_entry("first").set(1_u64);
+        db.merge_sync(fork.into_patch()).unwrap();
+
+        let read_only_db = Lmdb::open_read_only(temp_dir.path(), &options()).unwrap();
+        let snapshot = read_only_db.snapshot();
+        assert_eq!(snapshot.get_entry::<_, u64>("first").get(), Some(1));
+
+        let fork = read_only_db.fork();
+        fork.get_entry("second").set(2_u64);
+        assert!(read_only_db.merge(fork.into_patch()).is_err());
+    }
+}
@@ -12,5 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod copy;
+pub mod encryption;
+pub mod gc;
+#[cfg(feature = "lmdb")]
+pub mod lmdb;
 pub mod rocksdb;
 pub mod temporarydb;
+pub mod verify;
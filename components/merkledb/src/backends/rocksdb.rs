@@ -16,21 +16,47 @@
 
 pub use rocksdb::{BlockBasedOptions as RocksBlockOptions, WriteOptions as RocksDBWriteOptions};
 
+use num_traits::FromPrimitive;
 use rocksdb::{
-    self, checkpoint::Checkpoint, BoundColumnFamily, DBIterator, Options as RocksDbOptions,
-    WriteBatch,
+    self,
+    backup::{BackupEngine, BackupEngineOptions, RestoreOptions},
+    checkpoint::Checkpoint,
+    BoundColumnFamily, Cache, ColumnFamilyDescriptor, DBCompressionType, DBIterator, IteratorMode,
+    Options as RocksDbOptions, ReadOptions, SliceTransform, WriteBatch,
 };
 use smallvec::SmallVec;
-use std::{fmt, iter::Peekable, mem, path::Path, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt, fs, mem,
+    num::NonZeroU64,
+    ops::Bound,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
+use super::encryption::{EncryptionKeys, Encryptor};
 use crate::{
-    db::{check_database, Change},
-    Database, DbOptions, Iter, Iterator, Patch, ResolvedAddress, Snapshot,
+    db::{check_checkpoint_name, check_database, check_database_version, precedes, Change},
+    validation::is_reserved_name,
+    AccessPattern, Database, DbOptions, Iter, Iterator, Patch, ResolvedAddress, Snapshot,
+    StallStats, ValueRef,
 };
 
 /// Size of a byte representation of an index ID, which is used to prefix index keys
 /// in a column family.
 pub const ID_SIZE: usize = mem::size_of::<u64>();
+/// Name of the database subdirectory containing named checkpoints.
+const CHECKPOINTS_DIR: &str = "checkpoints";
+/// Number of values re-encrypted within a single write batch.
+const REENCRYPTION_BATCH_SIZE: usize = 10_000;
+/// Name of the column family recording access patterns of other column families.
+const ACCESS_PATTERNS_CF: &str = "__ACCESS_PATTERNS__";
+/// Number of bits per key in bloom filters of tuned column families, unless specified
+/// in the database options.
+const DEFAULT_BLOOM_FILTER_BITS: u32 = 10;
+/// Ratio of the memtable size used for the prefix bloom filter in column families
+/// with the prefix extractor.
+const MEMTABLE_PREFIX_BLOOM_RATIO: f64 = 0.1;
 
 /// Database implementation on top of [`RocksDB`](https://rocksdb.org)
 /// backend.
@@ -40,7 +66,10 @@ pub const ID_SIZE: usize = mem::size_of::<u64>();
 /// use different databases.
 pub struct RocksDB {
     db: Arc<rocksdb::DB>,
-    options: DbOptions,
+    // Options are retained so that column families created later share the block cache.
+    options: RocksDbOptions,
+    tuning: Option<AccessPatternTuning>,
+    encryption: Option<Arc<Encryptor>>,
 }
 
 impl From<DbOptions> for RocksDbOptions {
@@ -52,28 +81,184 @@ impl From<DbOptions> for RocksDbOptions {
 
 impl From<&DbOptions> for RocksDbOptions {
     fn from(opts: &DbOptions) -> Self {
-        let mut defaults = Self::default();
-        defaults.create_if_missing(opts.create_if_missing);
-        defaults.set_compression_type(opts.compression_type.into());
-        defaults.set_max_open_files(opts.max_open_files.unwrap_or(-1));
-        defaults.set_max_total_wal_size(opts.max_total_wal_size.unwrap_or(0));
-        defaults
+        rocksdb_options(opts, block_cache(opts).as_ref())
+    }
+}
+
+fn block_cache(opts: &DbOptions) -> Option<Cache> {
+    opts.block_cache_size
+        .map(|size| Cache::new_lru_cache(size).expect("Cannot create RocksDB block cache"))
+}
+
+fn rocksdb_options(opts: &DbOptions, block_cache: Option<&Cache>) -> RocksDbOptions {
+    let mut defaults = RocksDbOptions::default();
+    defaults.create_if_missing(opts.create_if_missing);
+    defaults.set_compression_type(opts.compression_type.into());
+    defaults.set_max_open_files(opts.max_open_files.unwrap_or(-1));
+    defaults.set_max_total_wal_size(opts.max_total_wal_size.unwrap_or(0));
+
+    if !opts.compression_per_level.is_empty() {
+        let levels: Vec<DBCompressionType> = opts
+            .compression_per_level
+            .iter()
+            .map(|&compression_type| compression_type.into())
+            .collect();
+        defaults.set_compression_per_level(&levels);
+    }
+    if let Some(size) = opts.write_buffer_size {
+        defaults.set_write_buffer_size(size);
+    }
+    if let Some(number) = opts.max_write_buffer_number {
+        defaults.set_max_write_buffer_number(number);
+    }
+    if let Some(number) = opts.min_write_buffer_number_to_merge {
+        defaults.set_min_write_buffer_number_to_merge(number);
+    }
+
+    if block_cache.is_some() || opts.bloom_filter_bits.is_some() {
+        let mut block_options = RocksBlockOptions::default();
+        if let Some(cache) = block_cache {
+            block_options.set_block_cache(cache);
+        }
+        if let Some(bits_per_key) = opts.bloom_filter_bits {
+            block_options.set_bloom_filter(f64::from(bits_per_key), false);
+        }
+        defaults.set_block_based_table_factory(&block_options);
+    }
+    defaults
+}
+
+/// Returns options for opening the database together with the tuning of column families.
+/// The block cache is shared by the base options and the tuned column families.
+fn open_options(opts: &DbOptions) -> (RocksDbOptions, Option<AccessPatternTuning>) {
+    let block_cache = block_cache(opts);
+    let options = rocksdb_options(opts, block_cache.as_ref());
+    (options, AccessPatternTuning::new(opts, block_cache))
+}
+
+/// Options of column families tuned according to the access patterns of indexes.
+struct AccessPatternTuning {
+    block_cache: Option<Cache>,
+    bloom_filter_bits: u32,
+}
+
+impl AccessPatternTuning {
+    fn new(opts: &DbOptions, block_cache: Option<Cache>) -> Option<Self> {
+        if !opts.access_pattern_tuning {
+            return None;
+        }
+        Some(Self {
+            block_cache,
+            bloom_filter_bits: opts.bloom_filter_bits.unwrap_or(DEFAULT_BLOOM_FILTER_BITS),
+        })
+    }
+
+    fn apply(&self, options: &mut RocksDbOptions, pattern: AccessPattern) {
+        let mut block_options = RocksBlockOptions::default();
+        if let Some(cache) = &self.block_cache {
+            block_options.set_block_cache(cache);
+        }
+        block_options.set_bloom_filter(f64::from(self.bloom_filter_bits), false);
+
+        match pattern {
+            AccessPattern::PointLookup => {}
+            AccessPattern::Scan => {
+                // Keys of an index are prefixed by the index ID, so prefix filters allow
+                // to skip data which does not belong to the iterated index.
+                options.set_prefix_extractor(SliceTransform::create_fixed_prefix(ID_SIZE));
+                options.set_memtable_prefix_bloom_ratio(MEMTABLE_PREFIX_BLOOM_RATIO);
+                block_options.set_whole_key_filtering(false);
+            }
+        }
+        options.set_block_based_table_factory(&block_options);
+    }
+}
+
+/// Returns options for a column family with the specified access pattern.
+fn cf_options(
+    options: &RocksDbOptions,
+    tuning: Option<&AccessPatternTuning>,
+    pattern: Option<AccessPattern>,
+) -> RocksDbOptions {
+    let mut options = options.clone();
+    if let (Some(tuning), Some(pattern)) = (tuning, pattern) {
+        tuning.apply(&mut options, pattern);
+    }
+    options
+}
+
+/// Creates descriptors for existing column families, so that the database options
+/// (e.g., the shared block cache) apply to them as well.
+fn cf_descriptors<'a>(
+    cf_names: Vec<String>,
+    options: &'a RocksDbOptions,
+    tuning: Option<&'a AccessPatternTuning>,
+    patterns: &'a HashMap<String, AccessPattern>,
+) -> impl Iterator<Item = ColumnFamilyDescriptor> + 'a {
+    cf_names.into_iter().map(move |name| {
+        let options = cf_options(options, tuning, patterns.get(&name).copied());
+        ColumnFamilyDescriptor::new(name, options)
+    })
+}
+
+/// Reads access patterns of column families recorded in the database. The patterns
+/// are read only if the tuning is enabled, since this requires opening the database
+/// in the read-only mode beforehand.
+fn read_access_patterns(
+    path: &Path,
+    cf_names: &[String],
+    tuning: Option<&AccessPatternTuning>,
+) -> crate::Result<HashMap<String, AccessPattern>> {
+    let mut patterns = HashMap::new();
+    if tuning.is_none() || !cf_names.iter().any(|name| name == ACCESS_PATTERNS_CF) {
+        return Ok(patterns);
+    }
+
+    let db = rocksdb::DB::open_cf_for_read_only(
+        &RocksDbOptions::default(),
+        path,
+        &[ACCESS_PATTERNS_CF],
+        false,
+    )?;
+    let cf = db.cf_handle(ACCESS_PATTERNS_CF).unwrap();
+    for item in db.iterator_cf(&cf, IteratorMode::Start) {
+        let (key, value) = item?;
+        let name = String::from_utf8(key.into_vec());
+        let pattern = value.first().copied().and_then(AccessPattern::from_u8);
+        if let (Ok(name), Some(pattern)) = (name, pattern) {
+            patterns.insert(name, pattern);
+        }
     }
+    Ok(patterns)
+}
+
+/// Returns read options for iterators which may cross key prefixes of different indexes.
+/// Such iterators cannot use prefix filters.
+fn total_order_read_options() -> ReadOptions {
+    let mut read_options = ReadOptions::default();
+    read_options.set_total_order_seek(true);
+    read_options
 }
 
 /// A snapshot of a `RocksDB`.
 pub struct RocksDBSnapshot {
     snapshot: rocksdb::Snapshot<'static>,
     db: Arc<rocksdb::DB>,
+    encryption: Option<Arc<Encryptor>>,
 }
 
 /// An iterator over the entries of a `RocksDB`.
 struct RocksDBIterator<'a> {
-    iter: Peekable<DBIterator<'a>>,
+    iter: DBIterator<'a>,
+    /// Entry read from `iter`, but not yet returned by `next()`.
+    peeked: Option<(Box<[u8]>, Box<[u8]>)>,
+    reversed: bool,
     key: Option<Box<[u8]>>,
     value: Option<Box<[u8]>>,
     prefix: Option<[u8; ID_SIZE]>,
     ended: bool,
+    /// Encryptor and the name of the column family, if the values are encrypted.
+    decryption: Option<(&'a Encryptor, String)>,
 }
 
 impl RocksDB {
@@ -82,23 +267,112 @@ impl RocksDB {
     /// If the database does not exist at the indicated path and the option
     /// `create_if_missing` is switched on in `DbOptions`, a new database will
     /// be created at the indicated path.
+    ///
+    /// Databases with configured encryption should be opened with
+    /// [`open_encrypted`](#method.open_encrypted) instead.
     pub fn open<P: AsRef<Path>>(path: P, options: &DbOptions) -> crate::Result<Self> {
+        check_no_encryption(options)?;
+        Self::open_with(path, options, None)
+    }
+
+    /// Opens a database with the encryption at rest specified by the `encryption` field
+    /// of `options`. Values are encrypted and decrypted with the provided keys; the version
+    /// of the active key must match the version specified in the options.
+    pub fn open_encrypted<P: AsRef<Path>>(
+        path: P,
+        options: &DbOptions,
+        keys: EncryptionKeys,
+    ) -> crate::Result<Self> {
+        let encryptor = encryptor(options, keys)?;
+        Self::open_with(path, options, Some(encryptor))
+    }
+
+    fn open_with<P: AsRef<Path>>(
+        path: P,
+        options: &DbOptions,
+        encryption: Option<Arc<Encryptor>>,
+    ) -> crate::Result<Self> {
+        let path = path.as_ref();
+        let (options, tuning) = open_options(options);
         let inner = {
-            if let Ok(names) = rocksdb::DB::list_cf(&RocksDbOptions::default(), &path) {
-                let cf_names = names.iter().map(String::as_str).collect::<Vec<_>>();
-                rocksdb::DB::open_cf(&options.into(), path, cf_names)?
+            if let Ok(names) = rocksdb::DB::list_cf(&RocksDbOptions::default(), path) {
+                let patterns = read_access_patterns(path, &names, tuning.as_ref())?;
+                let descriptors = cf_descriptors(names, &options, tuning.as_ref(), &patterns);
+                rocksdb::DB::open_cf_descriptors(&options, path, descriptors)?
             } else {
-                rocksdb::DB::open(&options.into(), path)?
+                rocksdb::DB::open(&options, path)?
             }
         };
         let mut db = Self {
             db: Arc::new(inner),
-            options: *options,
+            options,
+            tuning,
+            encryption,
         };
         check_database(&mut db)?;
         Ok(db)
     }
 
+    /// Opens an existing database stored at the specified path in the read-only mode.
+    ///
+    /// The database may be opened in the read-only mode while it is used by another process
+    /// (e.g., a running node). Any attempt to merge changes into a read-only database
+    /// results in an error.
+    pub fn open_read_only<P: AsRef<Path>>(path: P, options: &DbOptions) -> crate::Result<Self> {
+        check_no_encryption(options)?;
+        Self::open_read_only_with(path, options, None)
+    }
+
+    /// Opens an existing database with the encryption at rest in the read-only mode.
+    /// See [`open_encrypted`](#method.open_encrypted) for details.
+    pub fn open_read_only_encrypted<P: AsRef<Path>>(
+        path: P,
+        options: &DbOptions,
+        keys: EncryptionKeys,
+    ) -> crate::Result<Self> {
+        let encryptor = encryptor(options, keys)?;
+        Self::open_read_only_with(path, options, Some(encryptor))
+    }
+
+    fn open_read_only_with<P: AsRef<Path>>(
+        path: P,
+        options: &DbOptions,
+        encryption: Option<Arc<Encryptor>>,
+    ) -> crate::Result<Self> {
+        let path = path.as_ref();
+        let (options, tuning) = open_options(options);
+        let cf_names = rocksdb::DB::list_cf(&RocksDbOptions::default(), path)?;
+        let patterns = read_access_patterns(path, &cf_names, tuning.as_ref())?;
+        let descriptors = cf_descriptors(cf_names, &options, tuning.as_ref(), &patterns);
+        let inner = rocksdb::DB::open_cf_descriptors_read_only(&options, path, descriptors, false)?;
+        let db = Self {
+            db: Arc::new(inner),
+            options,
+            tuning,
+            encryption,
+        };
+        check_database_version(db.snapshot().as_ref())?;
+        Ok(db)
+    }
+
+    /// Restores a database from the latest backup in the `backup_path` directory
+    /// into the `db_path` directory and opens it with the specified options.
+    ///
+    /// Backups are created with [`Database::backup`]; the contents of `db_path`
+    /// are overwritten with the restored database.
+    ///
+    /// [`Database::backup`]: trait.Database.html#method.backup
+    pub fn restore<P, Q>(backup_path: P, db_path: Q, options: &DbOptions) -> crate::Result<Self>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let db_path = db_path.as_ref();
+        let mut engine = BackupEngine::open(&BackupEngineOptions::default(), backup_path)?;
+        engine.restore_from_latest_backup(db_path, db_path, &RestoreOptions::default())?;
+        Self::open(db_path, options)
+    }
+
     /// Creates checkpoint of this database in the given directory. See [`RocksDB` docs] for
     /// details.
     ///
@@ -111,14 +385,113 @@ impl RocksDB {
         Ok(())
     }
 
+    /// Returns the path to the named checkpoint of the database located at `db_path`.
+    /// Named checkpoints are created with [`Database::checkpoint`] and are stored
+    /// in the `checkpoints` subdirectory of the database directory.
+    ///
+    /// [`Database::checkpoint`]: trait.Database.html#method.checkpoint
+    pub fn checkpoint_path<P: AsRef<Path>>(db_path: P, name: &str) -> PathBuf {
+        db_path.as_ref().join(CHECKPOINTS_DIR).join(name)
+    }
+
+    /// Returns names of the checkpoints of the database located at `db_path`
+    /// in the alphabetical order.
+    pub fn checkpoints<P: AsRef<Path>>(db_path: P) -> crate::Result<Vec<String>> {
+        let dir = db_path.as_ref().join(CHECKPOINTS_DIR);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut names = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                if let Some(name) = entry.file_name().to_str() {
+                    names.push(name.to_owned());
+                }
+            }
+        }
+        names.sort_unstable();
+        Ok(names)
+    }
+
+    /// Opens the named checkpoint of the database located at `db_path` in the read-only mode.
+    /// The checkpoint may be opened while the database is used by another process.
+    ///
+    /// To obtain a writable copy of the database (e.g., to clone a node), copy the checkpoint
+    /// directory to a new location and open it with [`open`](#method.open).
+    pub fn open_checkpoint<P: AsRef<Path>>(
+        db_path: P,
+        name: &str,
+        options: &DbOptions,
+    ) -> crate::Result<Self> {
+        check_checkpoint_name(name)?;
+        Self::open_read_only(Self::checkpoint_path(db_path, name), options)
+    }
+
+    /// Re-encrypts values encrypted with retired keys using the active key, so that
+    /// retired keys are no longer necessary to read the database. Returns the number
+    /// of re-encrypted values.
+    ///
+    /// Values are rewritten without coordination with other writers; thus, this method
+    /// must only be called when no other changes are merged to the database
+    /// (e.g., when the node is stopped).
+    pub fn reencrypt(&self) -> crate::Result<u64> {
+        let encryptor = match self.encryption {
+            Some(ref encryptor) => encryptor,
+            None => return Err(crate::Error::new("Database is not encrypted")),
+        };
+        let active_version = encryptor.active_version();
+
+        let mut count = 0;
+        for cf_name in rocksdb::DB::list_cf(&self.options, self.db.path())? {
+            if cf_name == ACCESS_PATTERNS_CF || !encryptor.is_encrypted(&cf_name) {
+                continue;
+            }
+            let cf = match self.db.cf_handle(&cf_name) {
+                Some(cf) => cf,
+                None => continue,
+            };
+
+            let mut batch = WriteBatch::default();
+            let iter =
+                self.db
+                    .iterator_cf_opt(&cf, total_order_read_options(), IteratorMode::Start);
+            for item in iter {
+                let (key, value) = item?;
+                if Encryptor::key_version(&value) == Some(active_version) {
+                    continue;
+                }
+                let plaintext = encryptor.decrypt(&cf_name, &key, &value)?;
+                batch.put_cf(&cf, &key, encryptor.encrypt(&cf_name, &key, &plaintext));
+                count += 1;
+                if batch.len() >= REENCRYPTION_BATCH_SIZE {
+                    self.db.write(mem::take(&mut batch))?;
+                }
+            }
+            self.db.write(batch)?;
+        }
+        Ok(count)
+    }
+
     fn cf_exists(&self, cf_name: &str) -> bool {
         self.db.cf_handle(cf_name).is_some()
     }
 
-    fn create_cf(&self, cf_name: &str) -> crate::Result<()> {
-        self.db
-            .create_cf(cf_name, &self.options.into())
-            .map_err(Into::into)
+    /// Creates a column family with options corresponding to the access pattern of its data.
+    /// The access pattern is recorded, so that the same options are used when the database
+    /// is reopened.
+    fn create_cf(&self, cf_name: &str, pattern: Option<AccessPattern>) -> crate::Result<()> {
+        if let Some(pattern) = pattern {
+            if !self.cf_exists(ACCESS_PATTERNS_CF) {
+                self.db.create_cf(ACCESS_PATTERNS_CF, &self.options)?;
+            }
+            let patterns_cf = self.db.cf_handle(ACCESS_PATTERNS_CF).unwrap();
+            self.db.put_cf(&patterns_cf, cf_name, [pattern as u8])?;
+        }
+
+        let options = cf_options(&self.options, self.tuning.as_ref(), pattern);
+        self.db.create_cf(cf_name, &options).map_err(Into::into)
     }
 
     /// Clears the column family completely, removing all keys from it.
@@ -127,7 +500,7 @@ impl RocksDB {
         const LARGER_KEY: &[u8] = &[u8::MAX; 1_024];
         let from = [].as_ref();
 
-        let mut iter = self.db.raw_iterator_cf(cf);
+        let mut iter = self.db.raw_iterator_cf_opt(cf, total_order_read_options());
         iter.seek_to_last();
 
         if let Some(key) = iter.key() {
@@ -147,16 +520,25 @@ impl RocksDB {
 
     fn do_merge(&self, patch: Patch, w_opts: &RocksDBWriteOptions) -> crate::Result<()> {
         let mut batch = WriteBatch::default();
+        self.write_patch(&mut batch, patch)?;
+        self.db.write_opt(batch, w_opts).map_err(Into::into)
+    }
 
+    /// Writes changes from the patch into the batch.
+    fn write_patch(&self, batch: &mut WriteBatch, patch: Patch) -> crate::Result<()> {
         for (resolved, changes) in patch.into_changes() {
             if !self.cf_exists(&resolved.name) {
-                self.create_cf(&resolved.name)?;
+                self.create_cf(&resolved.name, changes.access_pattern())?;
             }
 
             let cf = self.db.cf_handle(&resolved.name).unwrap();
+            let encryptor = self
+                .encryption
+                .as_deref()
+                .filter(|encryptor| encryptor.is_encrypted(&resolved.name));
 
             if changes.is_cleared() {
-                self.clear_prefix(&mut batch, &cf, &resolved);
+                self.clear_prefix(batch, &cf, &resolved);
             }
 
             if let Some(id_bytes) = resolved.id_to_bytes() {
@@ -168,26 +550,40 @@ impl RocksDB {
                 let mut buffer: SmallVec<[u8; 1_024]> = SmallVec::new();
                 buffer.extend_from_slice(&id_bytes);
 
+                for (from, to) in changes.removed_ranges() {
+                    let mut to_buffer = buffer.clone();
+                    buffer.extend_from_slice(from);
+                    to_buffer.extend_from_slice(to);
+                    batch.delete_range_cf(&cf, &buffer, &to_buffer);
+                    buffer.truncate(ID_SIZE);
+                }
+
                 for (key, change) in changes.into_data() {
                     buffer.truncate(ID_SIZE);
                     buffer.extend_from_slice(&key);
                     match change {
-                        Change::Put(ref value) => batch.put_cf(&cf, &buffer, value),
+                        Change::Put(ref value) => {
+                            put_value(batch, &cf, encryptor, &resolved.name, &buffer, value);
+                        }
                         Change::Delete => batch.delete_cf(&cf, &buffer),
                     }
                 }
             } else {
                 // Write changes to the column family as-is.
+                for (from, to) in changes.removed_ranges() {
+                    batch.delete_range_cf(&cf, from, to);
+                }
                 for (key, change) in changes.into_data() {
                     match change {
-                        Change::Put(ref value) => batch.put_cf(&cf, &key, value),
+                        Change::Put(ref value) => {
+                            put_value(batch, &cf, encryptor, &resolved.name, &key, value);
+                        }
                         Change::Delete => batch.delete_cf(&cf, &key),
                     }
                 }
             }
         }
-
-        self.db.write_opt(batch, w_opts).map_err(Into::into)
+        Ok(())
     }
 
     /// Removes all keys with the specified prefix from a column family.
@@ -217,6 +613,7 @@ impl RocksDB {
             // FIXME: Investigate changing `rocksdb::Snapshot` / `DB` to remove `unsafe` (ECR-4273).
             snapshot: unsafe { mem::transmute(self.db.snapshot()) },
             db: Arc::clone(&self.db),
+            encryption: self.encryption.clone(),
         }
     }
 
@@ -227,25 +624,86 @@ impl RocksDB {
 }
 
 impl RocksDBSnapshot {
+    fn decryption(&self, name: &ResolvedAddress) -> Option<(&Encryptor, String)> {
+        self.encryption
+            .as_deref()
+            .filter(|encryptor| encryptor.is_encrypted(&name.name))
+            .map(|encryptor| (encryptor, name.name.clone()))
+    }
+
     fn rocksdb_iter(&self, name: &ResolvedAddress, from: &[u8]) -> RocksDBIterator<'_> {
-        use rocksdb::{Direction, IteratorMode};
+        use rocksdb::Direction;
 
         let from = name.keyed(from);
         let cf_handle = self.db.cf_handle(&name.name);
         let iter = match cf_handle {
-            Some(cf) => self
-                .snapshot
-                .iterator_cf(&cf, IteratorMode::From(from.as_ref(), Direction::Forward)),
+            Some(cf) => {
+                let mut read_options = ReadOptions::default();
+                // The iterator never leaves the ID prefix of the index, which allows to use
+                // prefix filters of the column family.
+                read_options.set_prefix_same_as_start(name.id.is_some());
+                read_options.set_total_order_seek(name.id.is_none());
+                self.snapshot.iterator_cf_opt(
+                    &cf,
+                    read_options,
+                    IteratorMode::From(from.as_ref(), Direction::Forward),
+                )
+            }
             None => self.snapshot.iterator(IteratorMode::Start),
         };
         RocksDBIterator {
-            iter: iter.peekable(),
+            iter,
+            peeked: None,
+            reversed: false,
             prefix: name.id_to_bytes(),
             key: None,
             value: None,
             ended: false,
+            decryption: self.decryption(name),
         }
     }
+
+    fn rocksdb_iter_rev(&self, name: &ResolvedAddress, upper: Bound<&[u8]>) -> RocksDBIterator<'_> {
+        use rocksdb::Direction;
+
+        let upper = name.keyed_upper_bound(upper);
+        let cf_handle = self.db.cf_handle(&name.name);
+        let iter = match (cf_handle, &upper) {
+            // The reverse iterator is positioned by the bound, which may lie outside
+            // the ID prefix of the index.
+            (Some(cf), Bound::Included(key)) | (Some(cf), Bound::Excluded(key)) => {
+                self.snapshot.iterator_cf_opt(
+                    &cf,
+                    total_order_read_options(),
+                    IteratorMode::From(key, Direction::Reverse),
+                )
+            }
+            (Some(cf), Bound::Unbounded) => {
+                self.snapshot
+                    .iterator_cf_opt(&cf, total_order_read_options(), IteratorMode::End)
+            }
+            (None, _) => self.snapshot.iterator(IteratorMode::End),
+        };
+
+        let mut iter = RocksDBIterator {
+            iter,
+            peeked: None,
+            reversed: true,
+            prefix: name.id_to_bytes(),
+            key: None,
+            value: None,
+            ended: false,
+            decryption: self.decryption(name),
+        };
+        if let Bound::Excluded(upper) = &upper {
+            // Reverse seek positions the iterator at the greatest key not exceeding the bound,
+            // which may coincide with the excluded bound itself.
+            if matches!(iter.peek_raw(), Some((key, _)) if **key == upper[..]) {
+                iter.peeked = None;
+            }
+        }
+        iter
+    }
 }
 
 impl Database for RocksDB {
@@ -263,22 +721,255 @@ impl Database for RocksDB {
         w_opts.set_sync(true);
         self.do_merge(patch, &w_opts)
     }
+
+    /// Writes all patches within a single `WriteBatch`, which is synced to the disk once.
+    fn merge_batch(&self, patches: Vec<Patch>) -> crate::Result<()> {
+        let mut batch = WriteBatch::default();
+        for patch in patches {
+            self.write_patch(&mut batch, patch)?;
+        }
+        let mut w_opts = RocksDBWriteOptions::default();
+        w_opts.set_sync(true);
+        self.db.write_opt(batch, &w_opts).map_err(Into::into)
+    }
+
+    /// Creates a new backup in the `path` directory using the `RocksDB` backup engine.
+    ///
+    /// Backups are incremental: if the directory already contains backups of the database,
+    /// only the files changed since then are copied. Memtables are flushed before the backup,
+    /// so it includes all merged changes.
+    fn backup(&self, path: &Path) -> crate::Result<()> {
+        let mut engine = BackupEngine::open(&BackupEngineOptions::default(), path)?;
+        engine.create_new_backup_flush(&self.db, true)?;
+        Ok(())
+    }
+
+    /// Creates a checkpoint in the `checkpoints` subdirectory of the database directory.
+    /// Data files of the checkpoint are hard-linked to the database files if possible.
+    fn checkpoint(&self, name: &str) -> crate::Result<()> {
+        check_checkpoint_name(name)?;
+        let path = Self::checkpoint_path(self.db.path(), name);
+        if path.exists() {
+            return Err(crate::Error::new(format!(
+                "Checkpoint `{}` already exists",
+                name
+            )));
+        }
+        fs::create_dir_all(self.db.path().join(CHECKPOINTS_DIR))?;
+        self.create_checkpoint(path)
+    }
+
+    fn checkpoint_snapshot(&self, name: &str) -> crate::Result<Box<dyn Snapshot>> {
+        check_checkpoint_name(name)?;
+        let path = Self::checkpoint_path(self.db.path(), name);
+        if !path.exists() {
+            return Err(crate::Error::new(format!(
+                "Checkpoint `{}` does not exist",
+                name
+            )));
+        }
+        let checkpoint =
+            Self::open_read_only_with(path, self.options.clone(), self.encryption.clone())?;
+        Ok(checkpoint.snapshot())
+    }
+
+    /// Compacts the column family corresponding to `name`, or all column families
+    /// if `name` is `None`.
+    fn compact_range(&self, name: Option<&str>) -> crate::Result<()> {
+        let cf_names = match name {
+            Some(name) if self.cf_exists(name) => vec![name.to_owned()],
+            Some(name) => {
+                return Err(crate::Error::new(format!(
+                    "Index `{}` does not exist",
+                    name
+                )));
+            }
+            None => rocksdb::DB::list_cf(&self.options, self.db.path())?,
+        };
+
+        for cf_name in cf_names {
+            if let Some(cf) = self.db.cf_handle(&cf_name) {
+                self.db.compact_range_cf(&cf, None::<&[u8]>, None::<&[u8]>);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads stall statistics from the `RocksDB` properties. Pending compaction bytes
+    /// are summed over all column families.
+    fn stall_stats(&self) -> crate::Result<StallStats> {
+        let int_property = |name: &str| -> crate::Result<u64> {
+            let value = self.db.property_int_value(name)?;
+            Ok(value.unwrap_or_default())
+        };
+
+        let mut pending_compaction_bytes = 0;
+        for cf_name in rocksdb::DB::list_cf(&self.options, self.db.path())? {
+            if let Some(cf) = self.db.cf_handle(&cf_name) {
+                pending_compaction_bytes += self
+                    .db
+                    .property_int_value_cf(&cf, "rocksdb.estimate-pending-compaction-bytes")?
+                    .unwrap_or_default();
+            }
+        }
+
+        Ok(StallStats {
+            is_write_stopped: int_property("rocksdb.is-write-stopped")? != 0,
+            delayed_write_rate: int_property("rocksdb.actual-delayed-write-rate")?,
+            pending_compaction_bytes,
+            running_compactions: int_property("rocksdb.num-running-compactions")?,
+            running_flushes: int_property("rocksdb.num-running-flushes")?,
+        })
+    }
+
+    /// Sets the `max_background_jobs` option of the database.
+    fn set_background_jobs_limit(&self, limit: u32) -> crate::Result<()> {
+        if limit == 0 {
+            return Err(crate::Error::new(
+                "Limit of background jobs must be positive",
+            ));
+        }
+        let limit = limit.to_string();
+        self.db
+            .set_options(&[("max_background_jobs", limit.as_str())])
+            .map_err(Into::into)
+    }
+
+    /// Enumerates non-empty column families. For column families of indexes, distinct
+    /// ID prefixes of the stored keys are enumerated by seeking past each found prefix,
+    /// so the operation does not scan all keys in the database.
+    fn stored_addresses(&self) -> crate::Result<Vec<ResolvedAddress>> {
+        let mut addrs = vec![];
+        for cf_name in rocksdb::DB::list_cf(&self.options, self.db.path())? {
+            let cf = match self.db.cf_handle(&cf_name) {
+                Some(cf) => cf,
+                None => continue,
+            };
+            if cf_name == ACCESS_PATTERNS_CF {
+                // The column family is maintained by the backend and does not contain views.
+                continue;
+            }
+            let mut iter = self.db.raw_iterator_cf_opt(&cf, total_order_read_options());
+            iter.seek_to_first();
+
+            if is_reserved_name(&cf_name) {
+                if iter.valid() {
+                    addrs.push(ResolvedAddress::system(cf_name));
+                }
+                continue;
+            }
+
+            while let Some(key) = iter.key() {
+                let id_bytes = match key.get(..ID_SIZE) {
+                    Some(prefix) => {
+                        let mut id_bytes = [0; ID_SIZE];
+                        id_bytes.copy_from_slice(prefix);
+                        id_bytes
+                    }
+                    None => {
+                        // Keys of indexes are always prefixed by the index ID.
+                        iter.next();
+                        continue;
+                    }
+                };
+
+                if let Some(id) = NonZeroU64::new(u64::from_le_bytes(id_bytes)) {
+                    addrs.push(ResolvedAddress::new(cf_name.clone(), Some(id)));
+                }
+                if id_bytes == [u8::MAX; ID_SIZE] {
+                    break;
+                }
+                iter.seek(next_id_bytes(id_bytes));
+            }
+            iter.status()?;
+        }
+        Ok(addrs)
+    }
 }
 
 impl Snapshot for RocksDBSnapshot {
     fn get(&self, resolved_addr: &ResolvedAddress, key: &[u8]) -> Option<Vec<u8>> {
         self.db.cf_handle(&resolved_addr.name).and_then(|cf| {
-            let value = self.snapshot.get_cf(&cf, resolved_addr.keyed(key));
-            match value {
-                Ok(value) => value,
+            let key = resolved_addr.keyed(key);
+            let value = match self.snapshot.get_cf(&cf, &key) {
+                Ok(value) => value?,
                 Err(e) => panic!("{}", e),
+            };
+            match self.decryption(resolved_addr) {
+                Some((encryptor, cf_name)) => Some(
+                    encryptor
+                        .decrypt(&cf_name, &key, &value)
+                        .unwrap_or_else(|e| panic!("{}", e)),
+                ),
+                None => Some(value),
             }
         })
     }
 
+    fn get_ref(&self, resolved_addr: &ResolvedAddress, key: &[u8]) -> Option<ValueRef<'_>> {
+        let cf = self.db.cf_handle(&resolved_addr.name)?;
+        let key = resolved_addr.keyed(key);
+        let mut read_options = ReadOptions::default();
+        read_options.set_snapshot(&self.snapshot);
+        let value = match self.db.get_pinned_cf_opt(&cf, &key, &read_options) {
+            Ok(value) => value?,
+            Err(e) => panic!("{}", e),
+        };
+        match self.decryption(resolved_addr) {
+            Some((encryptor, cf_name)) => Some(ValueRef::from(
+                encryptor
+                    .decrypt(&cf_name, &key, &value)
+                    .unwrap_or_else(|e| panic!("{}", e)),
+            )),
+            None => Some(ValueRef::pinned(value)),
+        }
+    }
+
+    fn multi_get(&self, resolved_addr: &ResolvedAddress, keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+        let cf = match self.db.cf_handle(&resolved_addr.name) {
+            Some(cf) => cf,
+            None => return vec![None; keys.len()],
+        };
+        let keys: Vec<_> = keys.iter().map(|key| resolved_addr.keyed(key)).collect();
+        let values = self
+            .snapshot
+            .multi_get_cf(keys.iter().map(|key| (&cf, key.as_ref())));
+        let decryption = self.decryption(resolved_addr);
+
+        keys.iter()
+            .zip(values)
+            .map(|(key, value)| {
+                let value = value.unwrap_or_else(|e| panic!("{}", e))?;
+                match &decryption {
+                    Some((encryptor, cf_name)) => Some(
+                        encryptor
+                            .decrypt(cf_name, key, &value)
+                            .unwrap_or_else(|e| panic!("{}", e)),
+                    ),
+                    None => Some(value),
+                }
+            })
+            .collect()
+    }
+
     fn iter(&self, name: &ResolvedAddress, from: &[u8]) -> Iter<'_> {
         Box::new(self.rocksdb_iter(name, from))
     }
+
+    fn iter_rev(&self, name: &ResolvedAddress, upper: Bound<&[u8]>) -> Iter<'_> {
+        Box::new(self.rocksdb_iter_rev(name, upper))
+    }
+}
+
+impl RocksDBIterator<'_> {
+    /// Returns the current entry with the full key and the raw value without advancing
+    /// the iterator.
+    fn peek_raw(&mut self) -> Option<&(Box<[u8]>, Box<[u8]>)> {
+        if self.peeked.is_none() {
+            self.peeked = self.iter.next().and_then(Result::ok);
+        }
+        self.peeked.as_ref()
+    }
 }
 
 impl<'a> Iterator for RocksDBIterator<'a> {
@@ -287,7 +978,10 @@ impl<'a> Iterator for RocksDBIterator<'a> {
             return None;
         }
 
-        let (key, value) = self.iter.next()?.ok()?;
+        let (key, value) = match self.peeked.take() {
+            Some(entry) => entry,
+            None => self.iter.next()?.ok()?,
+        };
         if let Some(ref prefix) = self.prefix {
             if &key[..ID_SIZE] != prefix {
                 self.ended = true;
@@ -295,6 +989,13 @@ impl<'a> Iterator for RocksDBIterator<'a> {
             }
         }
 
+        let value = match self.decryption {
+            Some((encryptor, ref cf_name)) => encryptor
+                .decrypt(cf_name, &key, &value)
+                .unwrap_or_else(|e| panic!("{}", e))
+                .into_boxed_slice(),
+            None => value,
+        };
         self.key = Some(key);
         let key = if self.prefix.is_some() {
             &self.key.as_ref()?[ID_SIZE..]
@@ -310,17 +1011,55 @@ impl<'a> Iterator for RocksDBIterator<'a> {
             return None;
         }
 
-        let (key, value) = self.iter.peek()?.as_ref().ok()?;
+        if self.peeked.is_none() {
+            self.peeked = self.iter.next().and_then(Result::ok);
+        }
+        let (full_key, value) = self.peeked.as_ref()?;
         let key = if let Some(prefix) = self.prefix {
-            if key[..ID_SIZE] != prefix {
+            if full_key[..ID_SIZE] != prefix {
                 self.ended = true;
                 return None;
             }
-            &key[ID_SIZE..]
+            &full_key[ID_SIZE..]
         } else {
-            &key[..]
+            &full_key[..]
         };
-        Some((key, &value[..]))
+        let value = match self.decryption {
+            Some((encryptor, ref cf_name)) => {
+                // The decrypted value is cached in the same way as the value
+                // of the current entry.
+                let value = encryptor
+                    .decrypt(cf_name, full_key, value)
+                    .unwrap_or_else(|e| panic!("{}", e));
+                &**self.value.insert(value.into_boxed_slice())
+            }
+            None => &value[..],
+        };
+        Some((key, value))
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        use rocksdb::Direction;
+
+        if self.ended {
+            return;
+        }
+        let mut target = self.prefix.map_or_else(Vec::new, |prefix| prefix.to_vec());
+        target.extend_from_slice(key);
+        let reversed = self.reversed;
+        match self.peek_raw() {
+            Some((current, _)) if precedes(current, &target, reversed) => {}
+            // The iterator is exhausted or is already positioned at the target.
+            _ => return,
+        }
+
+        let direction = if reversed {
+            Direction::Reverse
+        } else {
+            Direction::Forward
+        };
+        self.iter.set_mode(IteratorMode::From(&target, direction));
+        self.peeked = None;
     }
 }
 
@@ -342,6 +1081,38 @@ impl fmt::Debug for RocksDBSnapshot {
     }
 }
 
+/// Checks that the options do not require encryption keys to open the database.
+fn check_no_encryption(options: &DbOptions) -> crate::Result<()> {
+    if options.encryption.is_some() {
+        return Err(crate::Error::new(
+            "Database with encryption at rest should be opened with encryption keys",
+        ));
+    }
+    Ok(())
+}
+
+fn encryptor(options: &DbOptions, keys: EncryptionKeys) -> crate::Result<Arc<Encryptor>> {
+    let encryption = options.encryption.as_ref().ok_or_else(|| {
+        crate::Error::new("Encryption at rest is not configured in the database options")
+    })?;
+    Encryptor::new(encryption, keys).map(Arc::new)
+}
+
+/// Puts the value into the batch, encrypting it if necessary.
+fn put_value(
+    batch: &mut WriteBatch,
+    cf: &Arc<BoundColumnFamily>,
+    encryptor: Option<&Encryptor>,
+    cf_name: &str,
+    key: &[u8],
+    value: &[u8],
+) {
+    match encryptor {
+        Some(encryptor) => batch.put_cf(cf, key, encryptor.encrypt(cf_name, key, value)),
+        None => batch.put_cf(cf, key, value),
+    }
+}
+
 /// Generates the sequence of bytes lexicographically following the provided one. Assumes that
 /// the provided sequence is less than `[u8::MAX; ID_SIZE]`.
 pub fn next_id_bytes(id_bytes: [u8; ID_SIZE]) -> [u8; ID_SIZE] {
@@ -14,26 +14,36 @@
 
 //! An implementation of `TemporaryDB` database stored in RAM.
 
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use smallvec::SmallVec;
 use std::{
-    collections::{btree_map::Range, BTreeMap},
-    iter::{Iterator, Peekable},
+    collections::{btree_map, BTreeMap, HashMap},
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    num::NonZeroU64,
+    ops::Bound,
+    path::Path,
     sync::{Arc, RwLock},
 };
 
 use crate::{
     backends::rocksdb::{next_id_bytes, ID_SIZE},
-    db::{check_database, Change, Iterator as DbIterator},
-    Database, Iter, Patch, ResolvedAddress, Result, Snapshot,
+    db::{check_checkpoint_name, check_database, precedes, Change, Iterator as DbIterator},
+    Database, Error, Iter, Patch, ResolvedAddress, Result, Snapshot, ValueRef,
 };
 
-type MemoryDB = im::HashMap<ResolvedAddress, BTreeMap<Vec<u8>, Vec<u8>>>;
+type Collection = BTreeMap<Vec<u8>, Vec<u8>>;
+type MemoryDB = im::HashMap<ResolvedAddress, Collection>;
+
+/// Magic bytes at the start of a file with the saved database contents.
+const FILE_MAGIC: &[u8; 8] = b"EXTMPDB\x01";
 
 /// This in-memory database is only used for testing and experimenting; is not designed to
 /// operate under load in production.
 #[derive(Debug)]
 pub struct TemporaryDB {
     inner: Arc<RwLock<MemoryDB>>,
+    checkpoints: RwLock<HashMap<String, MemoryDB>>,
 }
 
 struct TemporarySnapshot {
@@ -41,7 +51,11 @@ struct TemporarySnapshot {
 }
 
 struct TemporaryDBIterator<'a> {
-    iter: Peekable<Range<'a, Vec<u8>, Vec<u8>>>,
+    collection: &'a Collection,
+    iter: btree_map::Range<'a, Vec<u8>, Vec<u8>>,
+    /// Entry read from `iter`, but not yet returned by `next()`.
+    peeked: Option<(&'a Vec<u8>, &'a Vec<u8>)>,
+    reversed: bool,
     prefix: Option<[u8; ID_SIZE]>,
     ended: bool,
 }
@@ -53,7 +67,10 @@ impl TemporaryDB {
 
         db.insert(ResolvedAddress::system("default"), BTreeMap::new());
         let inner = Arc::new(RwLock::new(db));
-        let mut db = Self { inner };
+        let mut db = Self {
+            inner,
+            checkpoints: RwLock::default(),
+        };
         check_database(&mut db).unwrap();
         db
     }
@@ -72,6 +89,109 @@ impl TemporaryDB {
         Ok(())
     }
 
+    /// Saves the contents of the database to a file at the specified `path`, overwriting
+    /// the file if it exists. The database can be restored from the file with [`load_from`].
+    ///
+    /// The contents are taken from a single consistent snapshot, so the database may be
+    /// modified concurrently with saving. The method is not available with the
+    /// `persisted_tempdb` feature.
+    ///
+    /// [`load_from`]: #method.load_from
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum_merkledb::{access::CopyAccessExt, Database, TemporaryDB};
+    /// # use tempfile::TempDir;
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// fork.get_list("list").extend(vec![1_u32, 2, 3]);
+    /// db.merge(fork.into_patch()).unwrap();
+    ///
+    /// let dir = TempDir::new().unwrap();
+    /// let path = dir.path().join("db.bin");
+    /// db.save_to(&path).unwrap();
+    /// let restored = TemporaryDB::load_from(&path).unwrap();
+    /// let snapshot = restored.snapshot();
+    /// assert_eq!(snapshot.get_list::<_, u32>("list").len(), 3);
+    /// ```
+    pub fn save_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        let snapshot = self.temporary_snapshot().snapshot;
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_contents(&mut writer, &snapshot)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Loads a database from a file previously created with [`save_to`].
+    ///
+    /// [`save_to`]: #method.save_to
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is malformed, or contains a database
+    /// of an incompatible version.
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut db = read_contents(&mut reader)?;
+        db.entry(ResolvedAddress::system("default"))
+            .or_insert_with(BTreeMap::new);
+
+        let mut db = Self {
+            inner: Arc::new(RwLock::new(db)),
+            checkpoints: RwLock::default(),
+        };
+        check_database(&mut db)?;
+        Ok(db)
+    }
+
+    /// Restores a database from a backup created with [`Database::backup`].
+    /// This is equivalent to [`load_from`].
+    ///
+    /// [`Database::backup`]: trait.Database.html#method.backup
+    /// [`load_from`]: #method.load_from
+    pub fn restore(path: impl AsRef<Path>) -> Result<Self> {
+        Self::load_from(path)
+    }
+
+    /// Creates an independent copy of the database, which includes its current contents
+    /// and named checkpoints. Changes to the copy do not affect this database and vice versa.
+    ///
+    /// Thanks to structural sharing, the copy is created in constant time and only occupies
+    /// memory for the data changed after its creation. This allows to cheaply branch
+    /// the database state, e.g., to explore alternative blocks in tests. The method
+    /// is not available with the `persisted_tempdb` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum_merkledb::{access::CopyAccessExt, Database, TemporaryDB};
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// fork.get_list("list").push(1_u32);
+    /// db.merge(fork.into_patch()).unwrap();
+    ///
+    /// let other_db = db.fork_db();
+    /// let fork = other_db.fork();
+    /// fork.get_list("list").push(2_u32);
+    /// other_db.merge(fork.into_patch()).unwrap();
+    ///
+    /// assert_eq!(db.snapshot().get_list::<_, u32>("list").len(), 1);
+    /// assert_eq!(other_db.snapshot().get_list::<_, u32>("list").len(), 2);
+    /// ```
+    pub fn fork_db(&self) -> Self {
+        let contents = self.temporary_snapshot().snapshot;
+        let checkpoints = self
+            .checkpoints
+            .read()
+            .expect("Couldn't get read lock")
+            .clone();
+        Self {
+            inner: Arc::new(RwLock::new(contents)),
+            checkpoints: RwLock::new(checkpoints),
+        }
+    }
+
     fn temporary_snapshot(&self) -> TemporarySnapshot {
         TemporarySnapshot {
             snapshot: self.inner.read().expect("Couldn't get read lock").clone(),
@@ -79,74 +199,237 @@ impl TemporaryDB {
     }
 }
 
-impl Database for TemporaryDB {
-    fn snapshot(&self) -> Box<dyn Snapshot> {
-        Box::new(self.temporary_snapshot())
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    writer.write_u64::<LittleEndian>(bytes.len() as u64)?;
+    writer.write_all(bytes)
+}
+
+fn read_bytes(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let len = reader.read_u64::<LittleEndian>()?;
+    let mut bytes = Vec::new();
+    reader.by_ref().take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 == len {
+        Ok(bytes)
+    } else {
+        Err(io::ErrorKind::UnexpectedEof.into())
     }
+}
 
-    fn merge(&self, patch: Patch) -> Result<()> {
-        let mut inner = self.inner.write().expect("Couldn't get write lock");
-        for (resolved, changes) in patch.into_changes() {
-            if !inner.contains_key(&resolved) {
-                inner.insert(resolved.clone(), BTreeMap::new());
-            }
+/// Writes the database contents. Each column family is written as its name, the index ID
+/// (`0` if the family is not split among indexes) and the sequence of key-value pairs.
+fn write_contents(writer: &mut impl Write, db: &MemoryDB) -> io::Result<()> {
+    writer.write_all(FILE_MAGIC)?;
+    writer.write_u64::<LittleEndian>(db.len() as u64)?;
+    for (address, collection) in db {
+        write_bytes(writer, address.name.as_bytes())?;
+        writer.write_u64::<LittleEndian>(address.id.map_or(0, NonZeroU64::get))?;
+        writer.write_u64::<LittleEndian>(collection.len() as u64)?;
+        for (key, value) in collection {
+            write_bytes(writer, key)?;
+            write_bytes(writer, value)?;
+        }
+    }
+    Ok(())
+}
 
-            let collection: &mut BTreeMap<Vec<u8>, Vec<u8>> = inner.get_mut(&resolved).unwrap();
-
-            if changes.is_cleared() {
-                if let Some(id_bytes) = resolved.id_to_bytes() {
-                    let next_bytes = next_id_bytes(id_bytes);
-                    let mut middle_and_tail = collection.split_off(id_bytes.as_ref());
-                    let mut tail = middle_and_tail.split_off(next_bytes.as_ref());
-                    collection.append(&mut tail);
-                } else {
-                    collection.clear();
-                }
-            }
+fn read_contents(reader: &mut impl Read) -> Result<MemoryDB> {
+    let mut magic = [0_u8; 8];
+    reader.read_exact(&mut magic)?;
+    if magic != *FILE_MAGIC {
+        return Err(Error::new("File does not contain a saved `TemporaryDB`"));
+    }
 
+    let mut db = MemoryDB::new();
+    let collections_count = reader.read_u64::<LittleEndian>()?;
+    for _ in 0..collections_count {
+        let name = String::from_utf8(read_bytes(reader)?)
+            .map_err(|_| Error::new("Column family name is not a valid UTF-8 string"))?;
+        let id = NonZeroU64::new(reader.read_u64::<LittleEndian>()?);
+        let entries_count = reader.read_u64::<LittleEndian>()?;
+        let mut collection = BTreeMap::new();
+        for _ in 0..entries_count {
+            let key = read_bytes(reader)?;
+            let value = read_bytes(reader)?;
+            collection.insert(key, value);
+        }
+        db.insert(ResolvedAddress::new(name, id), collection);
+    }
+    Ok(db)
+}
+
+/// Removes all keys in the `from..to` range from the collection.
+fn remove_range(collection: &mut BTreeMap<Vec<u8>, Vec<u8>>, from: &[u8], to: &[u8]) {
+    let mut range_and_tail = collection.split_off(from);
+    let mut tail = range_and_tail.split_off(to);
+    collection.append(&mut tail);
+}
+
+/// Applies changes from the patch to the in-memory database.
+fn apply_patch(inner: &mut MemoryDB, patch: Patch) {
+    for (resolved, changes) in patch.into_changes() {
+        if !inner.contains_key(&resolved) {
+            inner.insert(resolved.clone(), BTreeMap::new());
+        }
+
+        let collection: &mut BTreeMap<Vec<u8>, Vec<u8>> = inner.get_mut(&resolved).unwrap();
+
+        if changes.is_cleared() {
             if let Some(id_bytes) = resolved.id_to_bytes() {
-                // Write changes to the column family with each key prefixed by the ID of the
-                // resolved address.
-
-                // We assume that typical key sizes are less than `1_024 - ID_SIZE = 1_016` bytes,
-                // so that they fit into stack.
-                let mut buffer: SmallVec<[u8; 1_024]> = SmallVec::new();
-                buffer.extend_from_slice(&id_bytes);
-
-                for (key, change) in changes.into_data() {
-                    buffer.truncate(ID_SIZE);
-                    buffer.extend_from_slice(&key);
-
-                    match change {
-                        Change::Put(value) => collection.insert(buffer.to_vec(), value),
-                        Change::Delete => collection.remove(buffer.as_ref()),
-                    };
-                }
+                let next_bytes = next_id_bytes(id_bytes);
+                let mut middle_and_tail = collection.split_off(id_bytes.as_ref());
+                let mut tail = middle_and_tail.split_off(next_bytes.as_ref());
+                collection.append(&mut tail);
             } else {
-                // Write changes to the column family as-is.
-                for (key, change) in changes.into_data() {
-                    match change {
-                        Change::Put(value) => collection.insert(key, value),
-                        Change::Delete => collection.remove(&key),
-                    };
-                }
+                collection.clear();
             }
         }
+
+        if let Some(id_bytes) = resolved.id_to_bytes() {
+            // Write changes to the column family with each key prefixed by the ID of the
+            // resolved address.
+
+            // We assume that typical key sizes are less than `1_024 - ID_SIZE = 1_016` bytes,
+            // so that they fit into stack.
+            let mut buffer: SmallVec<[u8; 1_024]> = SmallVec::new();
+            buffer.extend_from_slice(&id_bytes);
+
+            for (from, to) in changes.removed_ranges() {
+                let mut to_buffer = buffer.clone();
+                buffer.extend_from_slice(from);
+                to_buffer.extend_from_slice(to);
+                remove_range(collection, &buffer, &to_buffer);
+                buffer.truncate(ID_SIZE);
+            }
+
+            for (key, change) in changes.into_data() {
+                buffer.truncate(ID_SIZE);
+                buffer.extend_from_slice(&key);
+
+                match change {
+                    Change::Put(value) => collection.insert(buffer.to_vec(), value),
+                    Change::Delete => collection.remove(buffer.as_ref()),
+                };
+            }
+        } else {
+            // Write changes to the column family as-is.
+            for (from, to) in changes.removed_ranges() {
+                remove_range(collection, from, to);
+            }
+            for (key, change) in changes.into_data() {
+                match change {
+                    Change::Put(value) => collection.insert(key, value),
+                    Change::Delete => collection.remove(&key),
+                };
+            }
+        }
+    }
+}
+
+impl Database for TemporaryDB {
+    fn snapshot(&self) -> Box<dyn Snapshot> {
+        Box::new(self.temporary_snapshot())
+    }
+
+    fn merge(&self, patch: Patch) -> Result<()> {
+        let mut inner = self.inner.write().expect("Couldn't get write lock");
+        apply_patch(&mut inner, patch);
         Ok(())
     }
 
     fn merge_sync(&self, patch: Patch) -> Result<()> {
         self.merge(patch)
     }
+
+    fn merge_batch(&self, patches: Vec<Patch>) -> Result<()> {
+        let mut inner = self.inner.write().expect("Couldn't get write lock");
+        for patch in patches {
+            apply_patch(&mut inner, patch);
+        }
+        Ok(())
+    }
+
+    /// Saves the database contents to a file, as with [`save_to`](#method.save_to).
+    fn backup(&self, path: &Path) -> Result<()> {
+        self.save_to(path)
+    }
+
+    /// Retains the current database state in memory. Thanks to structural sharing,
+    /// a checkpoint only occupies memory for the data changed after its creation.
+    fn checkpoint(&self, name: &str) -> Result<()> {
+        check_checkpoint_name(name)?;
+        let snapshot = self.temporary_snapshot().snapshot;
+        let mut checkpoints = self.checkpoints.write().expect("Couldn't get write lock");
+        if checkpoints.contains_key(name) {
+            return Err(Error::new(format!("Checkpoint `{}` already exists", name)));
+        }
+        checkpoints.insert(name.to_owned(), snapshot);
+        Ok(())
+    }
+
+    fn checkpoint_snapshot(&self, name: &str) -> Result<Box<dyn Snapshot>> {
+        let checkpoints = self.checkpoints.read().expect("Couldn't get read lock");
+        let snapshot = checkpoints
+            .get(name)
+            .ok_or_else(|| Error::new(format!("Checkpoint `{}` does not exist", name)))?;
+        Ok(Box::new(TemporarySnapshot {
+            snapshot: snapshot.clone(),
+        }))
+    }
+
+    fn stored_addresses(&self) -> Result<Vec<ResolvedAddress>> {
+        let inner = self.inner.read().expect("Couldn't get read lock");
+        let addrs = inner
+            .iter()
+            .filter(|(_, collection)| !collection.is_empty())
+            .map(|(addr, _)| addr.clone())
+            .collect();
+        Ok(addrs)
+    }
+}
+
+impl<'a> TemporaryDBIterator<'a> {
+    fn new(
+        collection: &'a Collection,
+        iter: btree_map::Range<'a, Vec<u8>, Vec<u8>>,
+        reversed: bool,
+        prefix: Option<[u8; ID_SIZE]>,
+    ) -> Self {
+        Self {
+            collection,
+            iter,
+            peeked: None,
+            reversed,
+            prefix,
+            ended: false,
+        }
+    }
+
+    fn advance(&mut self) -> Option<(&'a Vec<u8>, &'a Vec<u8>)> {
+        if self.reversed {
+            self.iter.next_back()
+        } else {
+            self.iter.next()
+        }
+    }
+
+    fn peek_raw(&mut self) -> Option<(&'a Vec<u8>, &'a Vec<u8>)> {
+        if self.peeked.is_none() {
+            self.peeked = self.advance();
+        }
+        self.peeked
+    }
 }
 
-impl<'a> DbIterator for TemporaryDBIterator<'a> {
+impl DbIterator for TemporaryDBIterator<'_> {
     fn next(&mut self) -> Option<(&[u8], &[u8])> {
         if self.ended {
             return None;
         }
 
-        let (key, value) = self.iter.next()?;
+        let (key, value) = match self.peeked.take() {
+            Some(entry) => entry,
+            None => self.advance()?,
+        };
 
         if let Some(ref prefix) = self.prefix {
             if &key[..ID_SIZE] != prefix {
@@ -169,7 +452,7 @@ impl<'a> DbIterator for TemporaryDBIterator<'a> {
             return None;
         }
 
-        let (key, value) = self.iter.peek()?;
+        let (key, value) = self.peek_raw()?;
         let key = if let Some(prefix) = self.prefix {
             if key[..ID_SIZE] != prefix {
                 self.ended = true;
@@ -182,6 +465,40 @@ impl<'a> DbIterator for TemporaryDBIterator<'a> {
 
         Some((key, value))
     }
+
+    fn seek(&mut self, key: &[u8]) {
+        if self.ended {
+            return;
+        }
+        let mut target = self.prefix.map_or_else(Vec::new, |prefix| prefix.to_vec());
+        target.extend_from_slice(key);
+        match self.peek_raw() {
+            Some((current, _)) if precedes(current, &target, self.reversed) => {}
+            // The iterator is exhausted or is already positioned at the target.
+            _ => return,
+        }
+
+        // The new range is contained in the original one, since the iterator is only
+        // moved forward.
+        let target = target.as_slice();
+        self.iter = if self.reversed {
+            self.collection
+                .range::<[u8], _>((Bound::Unbounded, Bound::Included(target)))
+        } else {
+            self.collection
+                .range::<[u8], _>((Bound::Included(target), Bound::Unbounded))
+        };
+        self.peeked = None;
+    }
+}
+
+impl TemporarySnapshot {
+    fn collection(&self, name: &ResolvedAddress) -> &Collection {
+        self.snapshot
+            .get(name)
+            .or_else(|| self.snapshot.get(&ResolvedAddress::system("default")))
+            .unwrap()
+    }
 }
 
 impl Snapshot for TemporarySnapshot {
@@ -190,19 +507,50 @@ impl Snapshot for TemporarySnapshot {
         collection.get(name.keyed(key).as_ref()).cloned()
     }
 
+    fn get_ref(&self, name: &ResolvedAddress, key: &[u8]) -> Option<ValueRef<'_>> {
+        let collection = self.snapshot.get(name)?;
+        collection
+            .get(name.keyed(key).as_ref())
+            .map(|value| ValueRef::from(value.as_slice()))
+    }
+
+    fn multi_get(&self, name: &ResolvedAddress, keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+        // Resolve the collection once for all keys.
+        match self.snapshot.get(name) {
+            Some(collection) => keys
+                .iter()
+                .map(|key| collection.get(name.keyed(key).as_ref()).cloned())
+                .collect(),
+            None => vec![None; keys.len()],
+        }
+    }
+
     fn iter(&self, name: &ResolvedAddress, from: &[u8]) -> Iter<'_> {
-        let collection = self
-            .snapshot
-            .get(name)
-            .or_else(|| self.snapshot.get(&ResolvedAddress::system("default")))
-            .unwrap();
+        let collection = self.collection(name);
         let from = name.keyed(from).into_owned();
         let iter = collection.range::<Vec<u8>, _>(&from..);
+        Box::new(TemporaryDBIterator::new(
+            collection,
+            iter,
+            false,
+            name.id_to_bytes(),
+        ))
+    }
 
-        Box::new(TemporaryDBIterator {
-            iter: iter.peekable(),
-            prefix: name.id_to_bytes(),
-            ended: false,
-        })
+    fn iter_rev(&self, name: &ResolvedAddress, upper: Bound<&[u8]>) -> Iter<'_> {
+        let collection = self.collection(name);
+        let upper = name.keyed_upper_bound(upper);
+        let upper = match upper {
+            Bound::Included(ref key) => Bound::Included(key.as_slice()),
+            Bound::Excluded(ref key) => Bound::Excluded(key.as_slice()),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let iter = collection.range::<[u8], _>((Bound::Unbounded, upper));
+        Box::new(TemporaryDBIterator::new(
+            collection,
+            iter,
+            true,
+            name.id_to_bytes(),
+        ))
     }
 }
@@ -39,6 +39,117 @@ impl From<TemporaryDB> for Arc<dyn Database> {
     }
 }
 
+#[cfg(not(feature = "persisted_tempdb"))]
+#[test]
+fn saving_and_loading_database() {
+    use crate::{access::CopyAccessExt, SystemSchema};
+    use std::fs;
+    use tempfile::TempDir;
+
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    fork.get_list("foo").extend(vec![1_u32, 2, 3]);
+    fork.get_proof_entry(("bar", &0_u8)).set("!".to_owned());
+    fork.get_proof_map("baz").put(&1_u8, "?".to_owned());
+    db.merge(fork.into_patch()).unwrap();
+    let state_hash = SystemSchema::new(&db.snapshot()).state_hash();
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("db.bin");
+    db.save_to(&path).unwrap();
+
+    // Changes after saving are not persisted.
+    let fork = db.fork();
+    fork.get_list("foo").push(4_u32);
+    db.merge(fork.into_patch()).unwrap();
+
+    let restored = TemporaryDB::load_from(&path).unwrap();
+    let snapshot = restored.snapshot();
+    assert_eq!(SystemSchema::new(&snapshot).state_hash(), state_hash);
+    let list = snapshot.get_list::<_, u32>("foo");
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert_eq!(
+        snapshot.get_proof_entry::<_, String>(("bar", &0_u8)).get(),
+        Some("!".to_owned())
+    );
+    assert_eq!(
+        snapshot.get_proof_map::<_, u8, String>("baz").get(&1),
+        Some("?".to_owned())
+    );
+
+    // The restored database can be modified further.
+    let fork = restored.fork();
+    fork.get_list("qux").push(5_u32);
+    fork.get_list("foo").push(6_u32);
+    restored.merge(fork.into_patch()).unwrap();
+    let snapshot = restored.snapshot();
+    assert_eq!(snapshot.get_list::<_, u32>("foo").len(), 4);
+    assert_eq!(snapshot.get_list::<_, u32>("qux").len(), 1);
+
+    // Malformed files are rejected.
+    let path = dir.path().join("garbage.bin");
+    fs::write(&path, b"garbage").unwrap();
+    assert!(TemporaryDB::load_from(&path).is_err());
+}
+
+#[cfg(not(feature = "persisted_tempdb"))]
+#[test]
+fn forking_database() {
+    use crate::{access::CopyAccessExt, SystemSchema};
+
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    fork.get_proof_list("foo").extend(vec![1_u32, 2, 3]);
+    db.merge(fork.into_patch()).unwrap();
+    db.checkpoint("first").unwrap();
+
+    let first_branch = db.fork_db();
+    let second_branch = db.fork_db();
+    let fork = first_branch.fork();
+    fork.get_proof_list("foo").push(4_u32);
+    first_branch.merge(fork.into_patch()).unwrap();
+    let fork = second_branch.fork();
+    fork.get_proof_list("foo").clear();
+    fork.get_proof_entry("bar").set("!".to_owned());
+    second_branch.merge(fork.into_patch()).unwrap();
+
+    // Branches are independent of each other and of the parent database.
+    let snapshot = db.snapshot();
+    assert_eq!(snapshot.get_proof_list::<_, u32>("foo").len(), 3);
+    assert!(snapshot.index_type("bar").is_none());
+    let first_snapshot = first_branch.snapshot();
+    assert_eq!(first_snapshot.get_proof_list::<_, u32>("foo").len(), 4);
+    assert!(first_snapshot.index_type("bar").is_none());
+    let second_snapshot = second_branch.snapshot();
+    assert!(second_snapshot.get_proof_list::<_, u32>("foo").is_empty());
+    assert_eq!(
+        second_snapshot.get_proof_entry::<_, String>("bar").get(),
+        Some("!".to_owned())
+    );
+    assert_ne!(
+        SystemSchema::new(&first_snapshot).state_hash(),
+        SystemSchema::new(&second_snapshot).state_hash()
+    );
+
+    // Checkpoints are inherited, but new checkpoints are not shared.
+    let checkpoint = first_branch.checkpoint_snapshot("first").unwrap();
+    assert_eq!(checkpoint.get_proof_list::<_, u32>("foo").len(), 3);
+    first_branch.checkpoint("second").unwrap();
+    assert!(db.checkpoint_snapshot("second").is_err());
+
+    // Changes to the parent database are not visible in branches.
+    let fork = db.fork();
+    fork.get_proof_list("foo").push(5_u32);
+    db.merge(fork.into_patch()).unwrap();
+    assert_eq!(
+        first_branch
+            .snapshot()
+            .get_proof_list::<_, u32>("foo")
+            .len(),
+        4
+    );
+}
+
 #[test]
 fn clearing_database() {
     use crate::access::CopyAccessExt;
@@ -66,3 +177,45 @@ fn clearing_database() {
     assert_eq!(list.len(), 3);
     assert_eq!(list.iter().collect::<Vec<_>>(), vec![4, 5, 6]);
 }
+
+#[test]
+fn backup_and_restore() {
+    use crate::{access::CopyAccessExt, SystemSchema};
+    use tempfile::TempDir;
+
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    fork.get_proof_list("foo").extend(vec![1_u32, 2, 3]);
+    db.merge(fork.into_patch()).unwrap();
+    let state_hash = SystemSchema::new(&db.snapshot()).state_hash();
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("backup");
+    db.backup(&path).unwrap();
+    let restored = TemporaryDB::restore(&path).unwrap();
+    let snapshot = restored.snapshot();
+    assert_eq!(SystemSchema::new(&snapshot).state_hash(), state_hash);
+    assert_eq!(snapshot.get_proof_list::<_, u32>("foo").len(), 3);
+}
+
+#[test]
+fn named_checkpoints() {
+    use crate::access::CopyAccessExt;
+
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    fork.get_list("foo").extend(vec![1_u32, 2, 3]);
+    db.merge(fork.into_patch()).unwrap();
+    db.checkpoint("first").unwrap();
+    assert!(db.checkpoint("first").is_err());
+    assert!(db.checkpoint("").is_err());
+
+    let fork = db.fork();
+    fork.get_list("foo").push(4_u32);
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.checkpoint_snapshot("first").unwrap();
+    assert_eq!(snapshot.get_list::<_, u32>("foo").len(), 3);
+    assert_eq!(db.snapshot().get_list::<_, u32>("foo").len(), 4);
+    assert!(db.checkpoint_snapshot("second").is_err());
+}
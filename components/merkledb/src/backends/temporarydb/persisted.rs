@@ -16,7 +16,9 @@
 
 use tempfile::TempDir;
 
-use crate::{Database, DbOptions, Patch, Result, RocksDB, Snapshot};
+use std::path::Path;
+
+use crate::{Database, DbOptions, Patch, ResolvedAddress, Result, RocksDB, Snapshot, StallStats};
 
 /// This database is only used for testing and experimenting; is not designed to
 /// operate under load in production.
@@ -36,6 +38,16 @@ impl TemporaryDB {
         Self { db, dir }
     }
 
+    /// Restores a database from a backup created with [`Database::backup`]
+    /// into a new temporary directory.
+    ///
+    /// [`Database::backup`]: trait.Database.html#method.backup
+    pub fn restore(path: impl AsRef<Path>) -> Result<Self> {
+        let dir = TempDir::new()?;
+        let db = RocksDB::restore(path, dir.path(), &DbOptions::default())?;
+        Ok(Self { db, dir })
+    }
+
     /// Clears the contents of the database.
     pub fn clear(&self) -> Result<()> {
         let opts = rocksdb::Options::default();
@@ -68,4 +80,36 @@ impl Database for TemporaryDB {
     fn merge_sync(&self, patch: Patch) -> Result<()> {
         self.db.merge_sync(patch)
     }
+
+    fn merge_batch(&self, patches: Vec<Patch>) -> Result<()> {
+        self.db.merge_batch(patches)
+    }
+
+    fn backup(&self, path: &Path) -> Result<()> {
+        self.db.backup(path)
+    }
+
+    fn checkpoint(&self, name: &str) -> Result<()> {
+        self.db.checkpoint(name)
+    }
+
+    fn checkpoint_snapshot(&self, name: &str) -> Result<Box<dyn Snapshot>> {
+        self.db.checkpoint_snapshot(name)
+    }
+
+    fn compact_range(&self, name: Option<&str>) -> Result<()> {
+        self.db.compact_range(name)
+    }
+
+    fn stall_stats(&self) -> Result<StallStats> {
+        self.db.stall_stats()
+    }
+
+    fn set_background_jobs_limit(&self, limit: u32) -> Result<()> {
+        self.db.set_background_jobs_limit(limit)
+    }
+
+    fn stored_addresses(&self) -> Result<Vec<ResolvedAddress>> {
+        self.db.stored_addresses()
+    }
 }
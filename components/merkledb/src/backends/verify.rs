@@ -0,0 +1,192 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verification of the database integrity.
+
+use exonum_crypto::Hash;
+
+use crate::{
+    views::{get_state_aggregator, recompute_object_hash, STATE_AGGREGATOR},
+    Database, ObjectHash,
+};
+
+/// Result of the database integrity check performed by [`verify_database`].
+///
+/// [`verify_database`]: fn.verify_database.html
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IntegrityReport {
+    /// Number of checked aggregated indexes.
+    pub checked_indexes: usize,
+    /// Indexes which data does not correspond to the state aggregator.
+    pub mismatches: Vec<IndexMismatch>,
+}
+
+impl IntegrityReport {
+    /// Returns `true` if no mismatches were found.
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Aggregated index which data does not correspond to the state aggregator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IndexMismatch {
+    /// Full name of the index.
+    pub name: String,
+    /// Hash of the index recorded in the state aggregator.
+    pub expected_hash: Hash,
+    /// Hash of the index recomputed from its data, or `None` if the hash cannot be computed
+    /// (e.g., the index metadata is missing).
+    pub actual_hash: Option<Hash>,
+}
+
+/// Checks the integrity of the aggregated database state.
+///
+/// The hash of each aggregated index is recomputed from the index data and compared with
+/// the hash recorded in the state aggregator. Hashes of `ProofListIndex`es and `ProofMapIndex`es
+/// are recomputed from their values, without relying on the hashes of the tree nodes stored
+/// in the database; for other Merkelized indexes, the root hash stored in the index metadata
+/// is checked. The state aggregator itself is checked in the same way as a `ProofMapIndex`.
+///
+/// The check is performed on a single snapshot of the database, so it may be run on a database
+/// which is being modified. The check reads all aggregated indexes, thus it may take
+/// considerable time for large databases.
+///
+/// # Examples
+///
+/// ```
+/// # use exonum_merkledb::{access::CopyAccessExt, verify_database, Database, TemporaryDB};
+/// let db = TemporaryDB::new();
+/// let fork = db.fork();
+/// fork.get_proof_list("list").extend(vec![1_u32, 2, 3]);
+/// fork.get_proof_map("map").put(&1_u8, "foo".to_owned());
+/// db.merge(fork.into_patch()).unwrap();
+///
+/// let report = verify_database(&db);
+/// assert!(report.is_ok());
+/// assert_eq!(report.checked_indexes, 2);
+/// ```
+pub fn verify_database(db: &dyn Database) -> IntegrityReport {
+    let snapshot = db.snapshot();
+    let aggregator = get_state_aggregator(&snapshot, "");
+    let mut report = IntegrityReport::default();
+
+    for (name, expected_hash) in &aggregator {
+        report.checked_indexes += 1;
+        let actual_hash = recompute_object_hash(&snapshot, &name);
+        if actual_hash != Some(expected_hash) {
+            report.mismatches.push(IndexMismatch {
+                name,
+                expected_hash,
+                actual_hash,
+            });
+        }
+    }
+
+    let expected_hash = aggregator.object_hash();
+    let actual_hash = aggregator.recompute_object_hash();
+    if actual_hash != Some(expected_hash) {
+        report.mismatches.push(IndexMismatch {
+            name: STATE_AGGREGATOR.to_owned(),
+            expected_hash,
+            actual_hash,
+        });
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        access::CopyAccessExt,
+        views::{IndexesPool, View},
+        TemporaryDB,
+    };
+
+    fn create_db() -> TemporaryDB {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        fork.get_proof_list("list").extend(vec![1_u32, 2, 3]);
+        fork.get_proof_map("map").put(&1_u8, "foo".to_owned());
+        fork.get_proof_map("map").put(&2_u8, "bar".to_owned());
+        fork.get_proof_entry("entry").set(42_u64);
+        fork.get_list("non_aggregated").push(1_u8);
+        db.merge(fork.into_patch()).unwrap();
+        db
+    }
+
+    /// Overwrites all values with the specified `prefix` in the view of the index
+    /// bypassing the index logic, as if the data on the disk were corrupted.
+    fn corrupt_index(db: &TemporaryDB, name: &str, prefix: u8) {
+        let snapshot = db.snapshot();
+        let addr = IndexesPool::new(&snapshot)
+            .resolved_addresses()
+            .into_iter()
+            .find(|addr| addr.name == name)
+            .unwrap();
+
+        let fork = db.fork();
+        let keys: Vec<Vec<u8>> = View::new(&snapshot, addr.clone())
+            .iter::<_, Vec<u8>, Vec<u8>>(&prefix)
+            .map(|(key, _)| key)
+            .collect();
+        let mut view = View::new(&fork, addr);
+        for key in keys {
+            view.put(&key, vec![0_u8; 4]);
+        }
+        drop(view);
+        db.merge(fork.into_patch()).unwrap();
+    }
+
+    #[test]
+    fn verifying_consistent_database() {
+        let db = create_db();
+        let report = verify_database(&db);
+        assert!(report.is_ok(), "{:?}", report);
+        assert_eq!(report.checked_indexes, 3);
+    }
+
+    #[test]
+    fn verifying_database_with_corrupted_list() {
+        let db = create_db();
+        // Values of the list are stored with the zero height prefix.
+        corrupt_index(&db, "list", 0);
+
+        let report = verify_database(&db);
+        assert_eq!(report.mismatches.len(), 1);
+        let mismatch = &report.mismatches[0];
+        assert_eq!(mismatch.name, "list");
+        assert_eq!(
+            mismatch.expected_hash,
+            db.snapshot().get_proof_list::<_, u32>("list").object_hash()
+        );
+        assert!(mismatch.actual_hash.is_some());
+        assert_ne!(mismatch.actual_hash, Some(mismatch.expected_hash));
+    }
+
+    #[test]
+    fn verifying_database_with_corrupted_map() {
+        let db = create_db();
+        // Values of the map are stored with the `2` prefix.
+        corrupt_index(&db, "map", 2);
+
+        let report = verify_database(&db);
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].name, "map");
+        assert_eq!(report.mismatches[0].actual_hash, None);
+    }
+}
@@ -12,24 +12,31 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use exonum_crypto::{Hash, HashStream};
+
 use std::{
     cell::RefCell,
+    cmp,
     collections::{BTreeMap, HashMap, HashSet},
     fmt,
     iter::{Iterator as StdIterator, Peekable},
     marker::PhantomData,
     mem,
+    num::NonZeroU64,
     ops::{Bound, Deref, DerefMut},
+    path::Path,
     rc::Rc,
     result::Result as StdResult,
 };
 
 use crate::{
-    validation::assert_valid_name_component,
+    access::{AccessError, AccessErrorKind},
+    validation::{assert_valid_name_component, is_valid_index_name_component},
     views::{
-        get_object_hash, AsReadonly, ChangesIter, IndexesPool, RawAccess, ResolvedAddress, View,
+        get_object_hash, AccessPattern, AsReadonly, ChangesIter, IndexAddress, IndexType,
+        IndexesPool, RawAccess, ResolvedAddress, View, ViewWithMetadata,
     },
-    Error, Result, SystemSchema,
+    BinaryKey, Error, Result, SystemSchema,
 };
 
 /// Changes related to a specific `View`.
@@ -39,10 +46,16 @@ pub struct ViewChanges {
     pub(super) data: BTreeMap<Vec<u8>, Change>,
     /// Was the view cleared as a part of changes?
     is_cleared: bool,
+    /// Half-open ranges of keys removed as a part of changes. The ranges are applied
+    /// to the underlying snapshot before `data`, so `data` may contain keys within the ranges.
+    removed_ranges: Vec<(Vec<u8>, Vec<u8>)>,
     /// Is the view aggregated into `state_hash` of the database?
     /// Storing this information directly in the changes allows to avoid relatively expensive
     /// metadata lookups during state aggregator update in `Fork::into_patch()`.
     namespace: Option<String>,
+    /// Expected access pattern of the view data. Used by database backends to tune
+    /// the storage when the data of a new index is written.
+    access_pattern: Option<AccessPattern>,
 }
 
 impl ViewChanges {
@@ -50,6 +63,21 @@ impl ViewChanges {
         Self::default()
     }
 
+    /// Creates changes from their components. Used to restore changes from a serialized form.
+    pub(crate) fn from_parts(
+        data: BTreeMap<Vec<u8>, Change>,
+        is_cleared: bool,
+        removed_ranges: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Self {
+        Self {
+            data,
+            is_cleared,
+            removed_ranges,
+            namespace: None,
+            access_pattern: None,
+        }
+    }
+
     pub fn is_cleared(&self) -> bool {
         self.is_cleared
     }
@@ -61,13 +89,76 @@ impl ViewChanges {
 
     pub fn clear(&mut self) {
         self.data.clear();
+        self.removed_ranges.clear();
         self.is_cleared = true;
     }
 
+    /// Removes all keys in the `from..to` range. Changes within the range are forgotten,
+    /// and the range itself is recorded as a tombstone, unless the view is cleared.
+    /// Tombstones are kept sorted and disjoint: the range is merged with the overlapping
+    /// or adjacent ranges removed previously.
+    pub fn remove_range(&mut self, mut from: Vec<u8>, mut to: Vec<u8>) {
+        if from >= to {
+            return;
+        }
+
+        let mut range_and_tail = self.data.split_off(&from);
+        let mut tail = range_and_tail.split_off(&to);
+        self.data.append(&mut tail);
+        if self.is_cleared {
+            return;
+        }
+
+        let ranges = mem::take(&mut self.removed_ranges);
+        let mut merged_ranges = Vec::with_capacity(ranges.len() + 1);
+        for (range_from, range_to) in ranges {
+            if range_to < from || to < range_from {
+                merged_ranges.push((range_from, range_to));
+            } else {
+                from = cmp::min(from, range_from);
+                to = cmp::max(to, range_to);
+            }
+        }
+        merged_ranges.push((from, to));
+        merged_ranges.sort_unstable();
+        self.removed_ranges = merged_ranges;
+    }
+
+    pub(crate) fn removed_ranges(&self) -> &[(Vec<u8>, Vec<u8>)] {
+        &self.removed_ranges
+    }
+
+    /// Checks whether the `key` falls into one of the removed ranges.
+    fn is_removed(&self, key: &[u8]) -> bool {
+        is_in_ranges(&self.removed_ranges, key)
+    }
+
+    /// Wraps an iterator over the underlying snapshot, so that it skips keys
+    /// within the removed ranges.
+    pub(crate) fn filter_removed<'a>(&'a self, iter: Iter<'a>) -> Iter<'a> {
+        if self.removed_ranges.is_empty() {
+            iter
+        } else {
+            Box::new(RemovedRangesFilter {
+                inner: iter,
+                ranges: &self.removed_ranges,
+            })
+        }
+    }
+
     pub fn set_aggregation(&mut self, namespace: Option<String>) {
         self.namespace = namespace;
     }
 
+    pub fn set_access_pattern(&mut self, access_pattern: Option<AccessPattern>) {
+        self.access_pattern = access_pattern;
+    }
+
+    /// Returns the expected access pattern of the view data, if known.
+    pub(crate) fn access_pattern(&self) -> Option<AccessPattern> {
+        self.access_pattern
+    }
+
     pub(crate) fn into_data(self) -> BTreeMap<Vec<u8>, Change> {
         self.data
     }
@@ -81,7 +172,22 @@ impl ViewChanges {
                 Change::Delete => None,
             });
         }
-        if self.is_cleared() {
+        if self.is_cleared() || self.is_removed(key) {
+            return Ok(None);
+        }
+        Err(())
+    }
+
+    /// Returns a reference to the value for the specified key, or an `Err(_)` if the value
+    /// should be determined by the underlying snapshot.
+    pub fn get_ref(&self, key: &[u8]) -> StdResult<Option<&[u8]>, ()> {
+        if let Some(change) = self.data.get(key) {
+            return Ok(match *change {
+                Change::Put(ref v) => Some(v),
+                Change::Delete => None,
+            });
+        }
+        if self.is_cleared() || self.is_removed(key) {
             return Ok(None);
         }
         Err(())
@@ -97,7 +203,7 @@ impl ViewChanges {
             });
         }
 
-        if self.is_cleared() {
+        if self.is_cleared() || self.is_removed(key) {
             return Ok(false);
         }
         Err(())
@@ -250,6 +356,56 @@ impl WorkingPatch {
             .clone()
     }
 
+    /// Computes the hash of changes in this patch. The hash does not depend on the order
+    /// in which the changes were made or on views accessed without modification.
+    fn changes_hash(&self) -> Hash {
+        fn update(stream: HashStream, bytes: &[u8]) -> HashStream {
+            stream
+                .update(&(bytes.len() as u64).to_le_bytes())
+                .update(bytes)
+        }
+
+        let changes = self.changes.borrow();
+        let mut changes: Vec<_> = changes
+            .iter()
+            .map(|(address, changes)| {
+                let changes = changes.as_ref().unwrap_or_else(|| {
+                    panic!(
+                        "changes are still mutably borrowed at address {:?}",
+                        address
+                    );
+                });
+                (address, changes)
+            })
+            .filter(|(_, changes)| {
+                changes.is_cleared()
+                    || !changes.data.is_empty()
+                    || !changes.removed_ranges.is_empty()
+            })
+            .collect();
+        changes.sort_unstable_by(|(x, _), (y, _)| (&x.name, x.id).cmp(&(&y.name, y.id)));
+
+        let mut stream = HashStream::new();
+        for (address, changes) in changes {
+            stream = update(stream, address.name.as_bytes())
+                .update(&address.id.map_or(0, NonZeroU64::get).to_le_bytes())
+                .update(&[u8::from(changes.is_cleared())])
+                .update(&(changes.removed_ranges.len() as u64).to_le_bytes());
+            for (from, to) in &changes.removed_ranges {
+                stream = update(update(stream, from), to);
+            }
+            stream = stream.update(&(changes.data.len() as u64).to_le_bytes());
+            for (key, change) in &changes.data {
+                stream = update(stream, key);
+                stream = match change {
+                    Change::Put(value) => update(stream.update(&[1]), value),
+                    Change::Delete => stream.update(&[0]),
+                };
+            }
+        }
+        stream.hash()
+    }
+
     // TODO: verify that this method updates `Change`s already in the `Patch` [ECR-2834]
     fn merge_into(self, patch: &mut Patch) {
         for (address, changes) in self.changes.into_inner() {
@@ -292,6 +448,12 @@ impl WorkingPatch {
             if changes.is_cleared() {
                 *patch_changes = changes;
             } else {
+                if changes.access_pattern.is_some() {
+                    patch_changes.access_pattern = changes.access_pattern;
+                }
+                for (from, to) in changes.removed_ranges {
+                    patch_changes.remove_range(from, to);
+                }
                 patch_changes.data.extend(changes.data);
             }
         }
@@ -301,6 +463,45 @@ impl WorkingPatch {
 /// A generalized iterator over the storage views.
 pub type Iter<'a> = Box<dyn Iterator + 'a>;
 
+fn is_in_ranges(ranges: &[(Vec<u8>, Vec<u8>)], key: &[u8]) -> bool {
+    ranges
+        .iter()
+        .any(|(from, to)| from.as_slice() <= key && key < to.as_slice())
+}
+
+/// Iterator over a snapshot skipping keys within the removed ranges.
+struct RemovedRangesFilter<'a> {
+    inner: Iter<'a>,
+    ranges: &'a [(Vec<u8>, Vec<u8>)],
+}
+
+impl RemovedRangesFilter<'_> {
+    fn skip_removed(&mut self) {
+        while let Some((key, _)) = self.inner.peek() {
+            if !is_in_ranges(self.ranges, key) {
+                break;
+            }
+            self.inner.next();
+        }
+    }
+}
+
+impl Iterator for RemovedRangesFilter<'_> {
+    fn next(&mut self) -> Option<(&[u8], &[u8])> {
+        self.skip_removed();
+        self.inner.next()
+    }
+
+    fn peek(&mut self) -> Option<(&[u8], &[u8])> {
+        self.skip_removed();
+        self.inner.peek()
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        self.inner.seek(key);
+    }
+}
+
 /// An enum that represents a type of change made to some key in the storage.
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(test, derive(Hash))] // needed for patch equality comparison
@@ -436,6 +637,7 @@ pub struct Patch {
 pub struct ForkIter<'a, T: StdIterator> {
     snapshot: Iter<'a>,
     changes: Option<Peekable<T>>,
+    reversed: bool,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -587,6 +789,169 @@ pub trait Database: Send + Sync + 'static {
     /// will be returned. In case of an error, the method guarantees no changes are applied to
     /// the database.
     fn merge_sync(&self, patch: Patch) -> Result<()>;
+
+    /// Atomically applies several patches to the database in the specified order with a single
+    /// fsync. This is more efficient than merging patches one by one with [`merge_sync`],
+    /// since the database needs to be synced to the disk only once.
+    ///
+    /// The default implementation merges patches one by one, only syncing the database
+    /// after the last patch; it is not atomic.
+    ///
+    /// # Logical Safety
+    ///
+    /// The same considerations as for [`merge`] apply to each of the patches.
+    ///
+    /// # Errors
+    ///
+    /// If this method encounters any form of I/O or other error during merging, an error variant
+    /// will be returned. Unless the default implementation is used, the method guarantees
+    /// that no changes are applied to the database in case of an error.
+    ///
+    /// [`merge`]: #tymethod.merge
+    /// [`merge_sync`]: #tymethod.merge_sync
+    fn merge_batch(&self, patches: Vec<Patch>) -> Result<()> {
+        let mut patches = patches.into_iter().peekable();
+        while let Some(patch) = patches.next() {
+            if patches.peek().is_some() {
+                self.merge(patch)?;
+            } else {
+                self.merge_sync(patch)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a backup of the current database state at the specified `path`.
+    ///
+    /// The backup is consistent: it corresponds to a single database state, so it may be
+    /// created while the database is being read from or merged into (e.g., by a running node).
+    /// The format of the backup is specific to the database implementation; it can be restored
+    /// with the `restore` constructor of the corresponding database type.
+    ///
+    /// # Errors
+    ///
+    /// The default implementation returns an error, meaning that the database
+    /// does not support backups.
+    fn backup(&self, _path: &Path) -> Result<()> {
+        Err(Error::new("Database does not support backups"))
+    }
+
+    /// Creates a named checkpoint of the current database state. Unlike [backups], checkpoints
+    /// are cheap to create (e.g., `RocksDB` hard-links data files of the database) and are
+    /// retained alongside the database; a checkpoint can be accessed via [`checkpoint_snapshot`].
+    ///
+    /// Checkpoint names must be non-empty and consist of chars allowed in index names
+    /// (`[0-9]`, `[a-z]`, `[A-Z]`, `_` and `-`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or a checkpoint with the same name exists.
+    /// The default implementation returns an error, meaning that the database does not support
+    /// checkpoints.
+    ///
+    /// [backups]: #method.backup
+    /// [`checkpoint_snapshot`]: #method.checkpoint_snapshot
+    fn checkpoint(&self, _name: &str) -> Result<()> {
+        Err(Error::new("Database does not support checkpoints"))
+    }
+
+    /// Returns a read-only snapshot of the database state captured by the named checkpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the checkpoint does not exist. The default implementation
+    /// returns an error, meaning that the database does not support checkpoints.
+    fn checkpoint_snapshot(&self, _name: &str) -> Result<Box<dyn Snapshot>> {
+        Err(Error::new("Database does not support checkpoints"))
+    }
+
+    /// Compacts the storage of the index or index group with the specified name, or
+    /// of the entire database if `name` is `None`. Compaction reclaims space occupied
+    /// by removed and overwritten entries and speeds up reads. The operation is blocking
+    /// and resource-intensive, so it is advised to run it during off-peak hours.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index with the specified name does not exist. The default
+    /// implementation returns an error, meaning that the database does not support manual
+    /// compaction.
+    fn compact_range(&self, _name: Option<&str>) -> Result<()> {
+        Err(Error::new("Database does not support manual compaction"))
+    }
+
+    /// Returns statistics related to write stalls, which occur if background compactions
+    /// and flushes cannot keep up with the incoming writes.
+    ///
+    /// # Errors
+    ///
+    /// The default implementation returns an error, meaning that the database does not
+    /// provide stall statistics.
+    fn stall_stats(&self) -> Result<StallStats> {
+        Err(Error::new("Database does not provide stall statistics"))
+    }
+
+    /// Limits the number of concurrent background jobs (compactions and flushes) of
+    /// the database. A lower limit reduces the impact of background jobs on the node
+    /// performance, at the cost of a higher probability of write stalls.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `limit` is zero. The default implementation returns an error,
+    /// meaning that the database does not support throttling background jobs.
+    fn set_background_jobs_limit(&self, _limit: u32) -> Result<()> {
+        Err(Error::new(
+            "Database does not support throttling background jobs",
+        ))
+    }
+
+    /// Returns resolved addresses of all views with data stored in the database. Unlike
+    /// addresses tracked by the index metadata, the returned addresses include the ones
+    /// of removed indexes, which data was not deleted from the storage (e.g., indexes
+    /// in a rolled back migration). Addresses of views without data may be omitted.
+    ///
+    /// This method is used by [`orphaned_addresses`] to find such data.
+    ///
+    /// # Errors
+    ///
+    /// The default implementation returns an error, meaning that the database does not
+    /// support enumerating stored data.
+    ///
+    /// [`orphaned_addresses`]: fn.orphaned_addresses.html
+    fn stored_addresses(&self) -> Result<Vec<ResolvedAddress>> {
+        Err(Error::new(
+            "Database does not support enumerating stored data",
+        ))
+    }
+}
+
+/// Statistics related to write stalls of a database.
+///
+/// See [`Database::stall_stats`] for details.
+///
+/// [`Database::stall_stats`]: trait.Database.html#method.stall_stats
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StallStats {
+    /// Whether writes to the database are currently stopped.
+    pub is_write_stopped: bool,
+    /// Rate of delayed writes in bytes per second, or 0 if writes are not delayed.
+    pub delayed_write_rate: u64,
+    /// Estimated number of bytes which compactions need to rewrite to bring
+    /// the storage into the target shape.
+    pub pending_compaction_bytes: u64,
+    /// Number of currently running compactions.
+    pub running_compactions: u64,
+    /// Number of currently running flushes.
+    pub running_flushes: u64,
+}
+
+/// Checks that the name of a database checkpoint is valid.
+pub(crate) fn check_checkpoint_name(name: &str) -> Result<()> {
+    if name.is_empty() || !is_valid_index_name_component(name) {
+        Err(Error::new(format!("Invalid checkpoint name: `{}`", name)))
+    } else {
+        Ok(())
+    }
 }
 
 /// Extension trait for `Database`.
@@ -661,13 +1026,24 @@ pub trait DatabaseExt: Database {
                     view_changes.insert(key.to_vec(), Change::Put(value.to_vec()));
                 }
             }
+            for (from, to) in &changes.removed_ranges {
+                let mut iter = snapshot.iter(name, from);
+                while let Some((key, value)) = iter.next() {
+                    if key >= to.as_slice() {
+                        break;
+                    }
+                    view_changes.insert(key.to_vec(), Change::Put(value.to_vec()));
+                }
+            }
 
             rev_changes.insert(
                 name.clone(),
                 ViewChanges {
                     data: view_changes,
                     is_cleared: false,
+                    removed_ranges: vec![],
                     namespace: changes.namespace.clone(),
+                    access_pattern: None,
                 },
             );
         }
@@ -701,10 +1077,104 @@ pub trait Snapshot: Send + Sync + 'static {
         self.get(name, key).is_some()
     }
 
+    /// Returns a value corresponding to the specified address and key without copying it
+    /// if the backend allows so, or `None` if it does not exist.
+    ///
+    /// The default implementation wraps the value returned by [`get`](#tymethod.get).
+    fn get_ref(&self, name: &ResolvedAddress, key: &[u8]) -> Option<ValueRef<'_>> {
+        self.get(name, key).map(ValueRef::from)
+    }
+
+    /// Returns values corresponding to the specified address and keys, in the order
+    /// of `keys`.
+    ///
+    /// The default implementation looks up keys one by one using [`get`](#tymethod.get).
+    /// Backends override it to batch lookups, e.g., to avoid the per-key overhead of calls
+    /// into the native database library.
+    fn multi_get(&self, name: &ResolvedAddress, keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+        keys.iter().map(|key| self.get(name, key)).collect()
+    }
+
     /// Returns an iterator over the entries of the snapshot in ascending order starting from
     /// the specified key. The iterator element type is `(&[u8], &[u8])`.
     #[allow(clippy::iter_not_returning_iterator)]
     fn iter(&self, name: &ResolvedAddress, from: &[u8]) -> Iter<'_>;
+
+    /// Returns an iterator over the entries of the snapshot in descending order starting from
+    /// the greatest key satisfying the `upper` bound. The iterator element type
+    /// is `(&[u8], &[u8])`.
+    fn iter_rev(&self, name: &ResolvedAddress, upper: Bound<&[u8]>) -> Iter<'_>;
+}
+
+/// Raw value retrieved from a [`Snapshot`]. Depending on the backend, the value may be
+/// borrowed from the memory of the backend, e.g., a pinned block of the `RocksDB` cache,
+/// so that it is not copied before deserialization.
+///
+/// [`Snapshot`]: trait.Snapshot.html
+pub struct ValueRef<'a> {
+    inner: ValueRefInner<'a>,
+}
+
+enum ValueRefInner<'a> {
+    Borrowed(&'a [u8]),
+    Owned(Vec<u8>),
+    Pinned(Box<dyn AsRef<[u8]> + 'a>),
+}
+
+impl<'a> ValueRef<'a> {
+    /// Creates a value which keeps `value` alive while the value is in use. This can be used
+    /// to wrap values owned by the database library, such as pinned slices.
+    pub fn pinned(value: impl AsRef<[u8]> + 'a) -> Self {
+        Self {
+            inner: ValueRefInner::Pinned(Box::new(value)),
+        }
+    }
+
+    /// Converts the value into a vector of bytes. The bytes are copied unless the value
+    /// is already owned.
+    pub fn into_vec(self) -> Vec<u8> {
+        match self.inner {
+            ValueRefInner::Owned(value) => value,
+            _ => self.to_vec(),
+        }
+    }
+}
+
+impl<'a> From<&'a [u8]> for ValueRef<'a> {
+    fn from(value: &'a [u8]) -> Self {
+        Self {
+            inner: ValueRefInner::Borrowed(value),
+        }
+    }
+}
+
+impl From<Vec<u8>> for ValueRef<'_> {
+    fn from(value: Vec<u8>) -> Self {
+        Self {
+            inner: ValueRefInner::Owned(value),
+        }
+    }
+}
+
+impl Deref for ValueRef<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match &self.inner {
+            ValueRefInner::Borrowed(value) => value,
+            ValueRefInner::Owned(value) => value,
+            ValueRefInner::Pinned(value) => (**value).as_ref(),
+        }
+    }
+}
+
+impl fmt::Debug for ValueRef<'_> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("ValueRef")
+            .field("len", &self.len())
+            .finish()
+    }
 }
 
 /// A trait that defines a streaming iterator over storage view entries. Unlike
@@ -716,13 +1186,192 @@ pub trait Iterator {
 
     /// Returns a reference to the current key and value without advancing the iterator.
     fn peek(&mut self) -> Option<(&[u8], &[u8])>;
+
+    /// Advances the iterator to the first entry with the key not less than `key`
+    /// (or not greater than `key` if the iterator yields entries in the descending order).
+    ///
+    /// The iterator is never moved backwards: if the current entry already satisfies
+    /// the condition, the call has no effect. Unlike skipping entries with `next`,
+    /// backends jump to the requested key directly, so seeking allows to skip large
+    /// ranges of keys cheaply.
+    fn seek(&mut self, key: &[u8]);
+}
+
+/// Looks up a value for `key` in `changes`, borrowing it from `snapshot` if the key
+/// is not affected by the changes.
+pub(crate) fn get_ref_with_changes<'a>(
+    snapshot: &'a dyn Snapshot,
+    changes: Option<&'a ViewChanges>,
+    name: &ResolvedAddress,
+    key: &[u8],
+) -> Option<ValueRef<'a>> {
+    match changes.map_or(Err(()), |changes| changes.get_ref(key)) {
+        Ok(value) => value.map(ValueRef::from),
+        // `Err(_)` signifies that we need to retrieve data from the snapshot.
+        Err(()) => snapshot.get_ref(name, key),
+    }
+}
+
+/// Looks up values for `keys` in `changes`, retrieving the values of keys not affected
+/// by the changes from `snapshot` with a single `multi_get` call.
+pub(crate) fn multi_get_with_changes(
+    snapshot: &dyn Snapshot,
+    changes: Option<&ViewChanges>,
+    name: &ResolvedAddress,
+    keys: &[&[u8]],
+) -> Vec<Option<Vec<u8>>> {
+    let changes = match changes {
+        Some(changes) => changes,
+        None => return snapshot.multi_get(name, keys),
+    };
+
+    let mut values = Vec::with_capacity(keys.len());
+    let mut missing_positions = vec![];
+    let mut missing_keys = vec![];
+    for (i, &key) in keys.iter().enumerate() {
+        // `Err(_)` signifies that we need to retrieve data from the snapshot.
+        if let Ok(value) = changes.get(key) {
+            values.push(value);
+        } else {
+            values.push(None);
+            missing_positions.push(i);
+            missing_keys.push(key);
+        }
+    }
+
+    if !missing_keys.is_empty() {
+        let snapshot_values = snapshot.multi_get(name, &missing_keys);
+        for (i, value) in missing_positions.into_iter().zip(snapshot_values) {
+            values[i] = value;
+        }
+    }
+    values
+}
+
+/// Checks whether an entry with `key` should be skipped when seeking to `target`.
+pub(crate) fn precedes(key: &[u8], target: &[u8], reversed: bool) -> bool {
+    if reversed {
+        key > target
+    } else {
+        key < target
+    }
+}
+
+/// Summary of changes made to a single index within a [`Patch`].
+///
+/// [`Patch`]: struct.Patch.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IndexChanges {
+    /// Address of the changed index.
+    pub address: IndexAddress,
+    /// Number of keys put into the index.
+    pub puts: u64,
+    /// Number of keys removed from the index.
+    pub deletes: u64,
+    /// Number of key ranges removed from the index.
+    pub removed_ranges: u64,
+    /// Was the index cleared as a part of changes? If so, the index contents preceding
+    /// the patch are irrelevant.
+    pub is_cleared: bool,
 }
 
 impl Patch {
+    /// Creates a patch with the specified changes on top of the `snapshot`. The changes
+    /// are expected to include updates of the state aggregator, if any.
+    pub(crate) fn from_changes(
+        snapshot: Box<dyn Snapshot>,
+        changes: HashMap<ResolvedAddress, ViewChanges>,
+    ) -> Self {
+        Self {
+            snapshot,
+            changes,
+            changed_aggregated_addrs: HashMap::new(),
+            removed_aggregated_addrs: HashSet::new(),
+        }
+    }
+
+    /// Returns changes in this patch.
+    pub(crate) fn changes(&self) -> &HashMap<ResolvedAddress, ViewChanges> {
+        &self.changes
+    }
+
     /// Iterates over changes in this patch.
     pub(crate) fn into_changes(self) -> HashMap<ResolvedAddress, ViewChanges> {
         self.changes
     }
+
+    /// Summarizes changes in this patch for each changed index. System views and indexes
+    /// accessed without modification are skipped. The summaries are ordered by the index address.
+    ///
+    /// Resolving addresses of the changed indexes from a group requires scanning
+    /// the metadata of the group, so the method may be expensive for patches touching
+    /// large groups.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum_merkledb::{access::CopyAccessExt, Database, IndexAddress, TemporaryDB};
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// fork.get_list("list").extend(vec![1_u32, 2, 3]);
+    /// fork.get_map(("group", &1_u8)).put(&1_u8, "foo".to_owned());
+    /// fork.get_entry::<_, u32>("untouched").get();
+    /// let patch = fork.into_patch();
+    ///
+    /// let changes = patch.index_changes();
+    /// assert_eq!(changes.len(), 2);
+    /// assert_eq!(changes[0].address, IndexAddress::from(("group", &1_u8)));
+    /// assert_eq!(changes[0].puts, 1);
+    /// assert_eq!(changes[1].address, IndexAddress::from("list"));
+    /// assert_eq!(changes[1].puts, 3);
+    /// ```
+    pub fn index_changes(&self) -> Vec<IndexChanges> {
+        let mut changed_ids: HashMap<&str, HashSet<NonZeroU64>> = HashMap::new();
+        for (address, changes) in &self.changes {
+            let is_changed = changes.is_cleared()
+                || !changes.data.is_empty()
+                || !changes.removed_ranges.is_empty();
+            if let (Some(id), true) = (address.id, is_changed) {
+                changed_ids.entry(&address.name).or_default().insert(id);
+            }
+        }
+
+        let pool = IndexesPool::new(self);
+        let mut summaries = vec![];
+        for (name, ids) in changed_ids {
+            for (id, index_address) in pool.index_addresses(name, &ids) {
+                let changes = &self.changes[&ResolvedAddress::new(name, Some(id))];
+                let deletes = changes
+                    .data
+                    .values()
+                    .filter(|change| **change == Change::Delete)
+                    .count() as u64;
+                summaries.push(IndexChanges {
+                    address: index_address,
+                    puts: changes.data.len() as u64 - deletes,
+                    deletes,
+                    removed_ranges: changes.removed_ranges.len() as u64,
+                    is_cleared: changes.is_cleared(),
+                });
+            }
+        }
+
+        summaries.sort_unstable_by(|x, y| {
+            let x_key = (
+                x.address.name(),
+                x.address.id_in_group(),
+                x.address.is_in_migration(),
+            );
+            let y_key = (
+                y.address.name(),
+                y.address.id_in_group(),
+                y.address.is_in_migration(),
+            );
+            x_key.cmp(&y_key)
+        });
+        summaries
+    }
 }
 
 impl Snapshot for Patch {
@@ -742,6 +1391,14 @@ impl Snapshot for Patch {
             .unwrap_or_else(|()| self.snapshot.contains(name, key))
     }
 
+    fn get_ref(&self, name: &ResolvedAddress, key: &[u8]) -> Option<ValueRef<'_>> {
+        get_ref_with_changes(self.snapshot.as_ref(), self.changes.get(name), name, key)
+    }
+
+    fn multi_get(&self, name: &ResolvedAddress, keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+        multi_get_with_changes(self.snapshot.as_ref(), self.changes.get(name), name, keys)
+    }
+
     fn iter(&self, name: &ResolvedAddress, from: &[u8]) -> Iter<'_> {
         let maybe_changes = self.changes.get(name);
         let changes_iter = maybe_changes.map(|changes| {
@@ -755,7 +1412,35 @@ impl Snapshot for Patch {
             // Ignore all changes from the snapshot.
             Box::new(ChangesIter::new(changes_iter.unwrap()))
         } else {
-            Box::new(ForkIter::new(self.snapshot.iter(name, from), changes_iter))
+            let snapshot_iter = self.snapshot.iter(name, from);
+            let snapshot_iter = match maybe_changes {
+                Some(changes) => changes.filter_removed(snapshot_iter),
+                None => snapshot_iter,
+            };
+            Box::new(ForkIter::new(snapshot_iter, changes_iter))
+        }
+    }
+
+    fn iter_rev(&self, name: &ResolvedAddress, upper: Bound<&[u8]>) -> Iter<'_> {
+        let maybe_changes = self.changes.get(name);
+        let changes_iter = maybe_changes.map(|changes| {
+            changes
+                .data
+                .range::<[u8], _>((Bound::Unbounded, upper))
+                .rev()
+        });
+
+        let is_cleared = maybe_changes.map_or(false, ViewChanges::is_cleared);
+        if is_cleared {
+            // Ignore all changes from the snapshot.
+            Box::new(ChangesIter::new_rev(changes_iter.unwrap()))
+        } else {
+            let snapshot_iter = self.snapshot.iter_rev(name, upper);
+            let snapshot_iter = match maybe_changes {
+                Some(changes) => changes.filter_removed(snapshot_iter),
+                None => snapshot_iter,
+            };
+            Box::new(ForkIter::new_rev(snapshot_iter, changes_iter))
         }
     }
 }
@@ -815,6 +1500,186 @@ impl Fork {
         }
     }
 
+    /// Moves all indexes from the `old_namespace` to the `new_namespace`. For example,
+    /// the index with the `old.list` address is moved to `new.list`, and the index
+    /// with the `(old.group, &1_u64)` address is moved to `(new.group, &1_u64)`.
+    ///
+    /// Data, types and states of the moved indexes are retained; in particular, the
+    /// Merkelized indexes have the same `object_hash()` after the move. Aggregated indexes are
+    /// removed from the state aggregator under their old names and are added under the new names.
+    ///
+    /// The indexes in the migration of `old_namespace` (i.e., ones with the `^old` prefix)
+    /// are not moved.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if either of namespaces is not a valid name component (i.e., contains
+    ///   chars other than `a-zA-Z0-9` and `_-`).
+    /// - Panics if an index in the `old_namespace` has a counterpart in the `new_namespace`.
+    pub fn move_namespace(&mut self, old_namespace: &str, new_namespace: &str) {
+        assert_valid_name_component(old_namespace);
+        assert_valid_name_component(new_namespace);
+        // Flushing is necessary to keep `self.patch` up to date.
+        self.flush();
+
+        let moved_addrs = IndexesPool::new(&*self).move_namespace(old_namespace, new_namespace);
+        for (old_addr, new_addr, is_aggregated) in &moved_addrs {
+            let mut old_view = View::new(&*self, old_addr.clone());
+            let mut new_view = View::new(&*self, new_addr.clone());
+            for (key, value) in old_view.iter::<_, Vec<u8>, Vec<u8>>(&()) {
+                new_view.put(&key, value);
+            }
+            old_view.clear();
+
+            if *is_aggregated {
+                new_view.set_or_forget_aggregation(Some(String::new()));
+            }
+        }
+        self.flush();
+
+        for (old_addr, _, is_aggregated) in moved_addrs {
+            self.patch.changed_aggregated_addrs.remove(&old_addr);
+            if is_aggregated {
+                self.patch.removed_aggregated_addrs.insert(old_addr.name);
+            }
+        }
+    }
+
+    /// Removes all entries with keys in the `from..to` range from the index with
+    /// the specified address. Keys are compared by their binary representation.
+    /// If the index does not exist, the method does nothing.
+    ///
+    /// Unlike removing entries one by one, the range is recorded in the fork as a single
+    /// tombstone regardless of the number of removed entries. Database backends apply
+    /// the tombstone efficiently where possible (e.g., RocksDB uses `delete_range`).
+    ///
+    /// Only `MapIndex` and `KeySetIndex` support range removal, since other indexes
+    /// store their entries under derived keys or maintain additional state.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index address is invalid or the index has an unsupported type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum_merkledb::{access::CopyAccessExt, Database, TemporaryDB};
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// fork.get_map("map").extend((0_u32..10).map(|i| (i, i.to_string())));
+    /// fork.remove_range("map", &2_u32, &8_u32).unwrap();
+    /// let map = fork.get_map::<_, u32, String>("map");
+    /// assert_eq!(map.keys().collect::<Vec<_>>(), vec![0, 1, 8, 9]);
+    /// ```
+    pub fn remove_range<K>(
+        &self,
+        addr: impl Into<IndexAddress>,
+        from: &K,
+        to: &K,
+    ) -> StdResult<(), AccessError>
+    where
+        K: BinaryKey + ?Sized,
+    {
+        let addr = addr.into();
+        let metadata = match ViewWithMetadata::get_metadata(self, &addr)? {
+            Some(metadata) => metadata,
+            None => return Ok(()),
+        };
+
+        match metadata.index_type() {
+            IndexType::Map | IndexType::KeySet => {}
+            actual => {
+                return Err(AccessError {
+                    addr,
+                    kind: AccessErrorKind::WrongIndexType {
+                        expected: IndexType::Map,
+                        actual,
+                    },
+                });
+            }
+        }
+
+        let resolved = ResolvedAddress::new(addr.name(), Some(metadata.identifier()));
+        View::new(self, resolved).remove_range(from, to);
+        Ok(())
+    }
+
+    /// Removes indexes in the group with the specified name, which keys precede `until`
+    /// in the binary representation, together with all their data (e.g., internal nodes
+    /// of Merkelized indexes). Returns the number of removed indexes.
+    ///
+    /// This method is intended to prune historical data, such as indexes grouped by
+    /// the block height. Removed indexes can be re-created afterwards by accessing them;
+    /// the re-created indexes are empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the removed indexes are borrowed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum_merkledb::{access::CopyAccessExt, Database, TemporaryDB};
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// for height in 0_u64..5 {
+    ///     fork.get_proof_list(("history", &height)).push(height);
+    /// }
+    /// assert_eq!(fork.remove_group_indexes("history", &3_u64), 3);
+    /// assert!(fork.index_type(("history", &2_u64)).is_none());
+    /// assert_eq!(fork.get_proof_list::<_, u64>(("history", &3_u64)).len(), 1);
+    /// ```
+    pub fn remove_group_indexes<K>(&self, group_name: &str, until: &K) -> usize
+    where
+        K: BinaryKey + ?Sized,
+    {
+        let group = IndexAddress::from_root(group_name);
+        let mut until_bytes = vec![0; until.size()];
+        until.write(&mut until_bytes);
+        let removed_addrs = IndexesPool::new(self).remove_group_indexes(&group, &until_bytes);
+        for addr in &removed_addrs {
+            View::new(self, addr.clone()).clear();
+        }
+        removed_addrs.len()
+    }
+
+    /// Computes the hash of changes that were made after the latest execution of the `flush`
+    /// or `rollback` method. The hash does not depend on the order in which the changes
+    /// to existing indexes were made, so it can be used to compare the effects of the same
+    /// operation executed in different forks, e.g., on different nodes. (Note that
+    /// the identifiers of the created indexes depend on the order of their creation.)
+    ///
+    /// The method borrows the fork mutably to ensure that no indexes are instantiated
+    /// in the client code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum_merkledb::{access::CopyAccessExt, Database, TemporaryDB};
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// fork.get_list("list").push(0_u32);
+    /// fork.get_entry("entry").set(0_u32);
+    /// db.merge(fork.into_patch()).unwrap();
+    ///
+    /// let mut fork = db.fork();
+    /// fork.get_list("list").extend(vec![1_u32, 2]);
+    /// fork.get_entry("entry").set(3_u32);
+    /// let hash = fork.unflushed_changes_hash();
+    ///
+    /// let mut other_fork = db.fork();
+    /// other_fork.get_entry("entry").set(3_u32);
+    /// other_fork.get_list("list").extend(vec![1_u32, 2]);
+    /// assert_eq!(other_fork.unflushed_changes_hash(), hash);
+    ///
+    /// // Flushed changes do not influence the hash.
+    /// fork.flush();
+    /// assert_ne!(fork.unflushed_changes_hash(), hash);
+    /// ```
+    pub fn unflushed_changes_hash(&mut self) -> Hash {
+        self.working_patch.changes_hash()
+    }
+
     /// Rolls back all changes that were made after the latest execution
     /// of the `flush` method.
     pub fn rollback(&mut self) {
@@ -1058,9 +1923,21 @@ impl Snapshot for Box<dyn Snapshot> {
         self.as_ref().contains(name, key)
     }
 
+    fn get_ref(&self, name: &ResolvedAddress, key: &[u8]) -> Option<ValueRef<'_>> {
+        self.as_ref().get_ref(name, key)
+    }
+
+    fn multi_get(&self, name: &ResolvedAddress, keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+        self.as_ref().multi_get(name, keys)
+    }
+
     fn iter(&self, name: &ResolvedAddress, from: &[u8]) -> Iter<'_> {
         self.as_ref().iter(name, from)
     }
+
+    fn iter_rev(&self, name: &ResolvedAddress, upper: Bound<&[u8]>) -> Iter<'_> {
+        self.as_ref().iter_rev(name, upper)
+    }
 }
 
 impl<'a, T> ForkIter<'a, T>
@@ -1071,6 +1948,17 @@ where
         ForkIter {
             snapshot,
             changes: changes.map(StdIterator::peekable),
+            reversed: false,
+        }
+    }
+
+    /// Creates an iterator merging `snapshot` and `changes`, both of which must iterate
+    /// in descending order.
+    pub fn new_rev(snapshot: Iter<'a>, changes: Option<T>) -> Self {
+        ForkIter {
+            snapshot,
+            changes: changes.map(StdIterator::peekable),
+            reversed: true,
         }
     }
 
@@ -1078,16 +1966,27 @@ where
     fn step(&mut self) -> NextIterValue {
         use std::cmp::Ordering::{Equal, Greater, Less};
 
+        // Compares keys in the iteration order.
+        let reversed = self.reversed;
+        let cmp = |change_key: &[u8], snapshot_key: &[u8]| {
+            let ordering = change_key.cmp(snapshot_key);
+            if reversed {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        };
+
         if let Some(ref mut changes) = self.changes {
             match changes.peek() {
                 Some(&(k, change)) => match self.snapshot.peek() {
                     Some((key, ..)) => match *change {
-                        Change::Put(..) => match k[..].cmp(key) {
+                        Change::Put(..) => match cmp(k.as_slice(), key) {
                             Equal => NextIterValue::Replaced,
                             Less => NextIterValue::Inserted,
                             Greater => NextIterValue::Stored,
                         },
-                        Change::Delete => match k[..].cmp(key) {
+                        Change::Delete => match cmp(k.as_slice(), key) {
                             Equal => NextIterValue::Deleted,
                             Less => NextIterValue::MissDeleted,
                             Greater => NextIterValue::Stored,
@@ -1181,6 +2080,18 @@ where
             }
         }
     }
+
+    fn seek(&mut self, key: &[u8]) {
+        self.snapshot.seek(key);
+        if let Some(ref mut changes) = self.changes {
+            while let Some(&(change_key, _)) = changes.peek() {
+                if !precedes(change_key, key, self.reversed) {
+                    break;
+                }
+                changes.next();
+            }
+        }
+    }
 }
 
 impl fmt::Debug for dyn Database {
@@ -1229,6 +2140,19 @@ pub fn check_database(db: &mut dyn Database) -> Result<()> {
     db.merge(fork.into_patch())
 }
 
+/// Checks that the version of the database (if any) is supported. Unlike `check_database`,
+/// this function does not write the version to the database.
+pub(crate) fn check_database_version(snapshot: &dyn Snapshot) -> Result<()> {
+    let view = View::new(snapshot, ResolvedAddress::system(DB_METADATA));
+    match view.get::<_, u8>(VERSION_NAME) {
+        Some(saved_version) if saved_version != DB_VERSION => Err(Error::new(format!(
+            "Database version doesn't match: actual {}, expected {}",
+            saved_version, DB_VERSION
+        ))),
+        _ => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
@@ -1251,6 +2175,89 @@ mod tests {
         fork.into_patch();
     }
 
+    #[test]
+    fn unflushed_changes_hash() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        fork.get_list("list").push(0_u32);
+        fork.get_map(("group", &1_u8)).put(&0_u8, 0_u32);
+        db.merge(fork.into_patch()).unwrap();
+
+        let mut fork = db.fork();
+        let empty_hash = fork.unflushed_changes_hash();
+        // Views accessed without modification do not influence the hash.
+        fork.get_list::<_, u32>("list").len();
+        assert_eq!(fork.unflushed_changes_hash(), empty_hash);
+
+        fork.get_list("list").extend(vec![1_u32, 2, 3]);
+        fork.get_map(("group", &1_u8)).put(&1_u8, 2_u32);
+        let hash = fork.unflushed_changes_hash();
+        assert_ne!(hash, empty_hash);
+        fork.rollback();
+        assert_eq!(fork.unflushed_changes_hash(), empty_hash);
+
+        // The same changes result in the same hash; different changes in different hashes.
+        let mut other_fork = db.fork();
+        other_fork.get_map(("group", &1_u8)).put(&1_u8, 2_u32);
+        other_fork.get_list("list").extend(vec![1_u32, 2, 3]);
+        assert_eq!(other_fork.unflushed_changes_hash(), hash);
+        other_fork
+            .get_map::<_, u8, u32>(("group", &1_u8))
+            .remove(&1);
+        assert_ne!(other_fork.unflushed_changes_hash(), hash);
+        other_fork.rollback();
+        other_fork.get_map(("group", &2_u8)).put(&1_u8, 2_u32);
+        other_fork.get_list("list").extend(vec![1_u32, 2, 3]);
+        assert_ne!(other_fork.unflushed_changes_hash(), hash);
+    }
+
+    #[test]
+    fn multi_get_combines_changes_and_snapshot() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        {
+            let mut view = View::new(&fork, "foo");
+            view.put(&vec![1], vec![1]);
+            view.put(&vec![2], vec![2]);
+            view.put(&vec![3], vec![3]);
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let fork = db.fork();
+        {
+            let mut view = View::new(&fork, "foo");
+            view.put(&vec![2], vec![20]);
+            view.remove(&vec![3]);
+            view.put(&vec![4], vec![4]);
+            let keys: &[&[u8]] = &[&[4], &[1], &[3], &[5], &[2], &[1]];
+            let expected = vec![
+                Some(vec![4]),
+                Some(vec![1]),
+                None,
+                None,
+                Some(vec![20]),
+                Some(vec![1]),
+            ];
+            assert_eq!(view.multi_get_bytes(keys), expected);
+        }
+
+        let patch = fork.into_patch();
+        let name = ResolvedAddress::system("foo");
+        let keys: &[&[u8]] = &[&[1], &[2], &[3], &[4], &[5]];
+        let values = patch.multi_get(&name, keys);
+        let expected: Vec<_> = keys.iter().map(|key| patch.get(&name, key)).collect();
+        assert_eq!(values, expected);
+        assert_eq!(values[1], Some(vec![20]));
+        let snapshot = db.snapshot();
+        let values = snapshot.multi_get(&name, keys);
+        assert_eq!(
+            values,
+            vec![Some(vec![1]), Some(vec![2]), Some(vec![3]), None, None]
+        );
+        let missing_index = ResolvedAddress::system("bar");
+        assert_eq!(snapshot.multi_get(&missing_index, keys), vec![None; 5]);
+    }
+
     /// Asserts that a patch contains only the specified changes.
     fn check_patch<'a, I>(patch: &Patch, changes: I)
     where
@@ -1269,6 +2276,27 @@ mod tests {
         assert_eq!(patch_set, expected_set);
     }
 
+    #[test]
+    fn merge_batch_applies_patches_in_order() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        fork.get_entry("entry").set(0_u32);
+        fork.get_list("list").extend(vec![1_u32, 2, 3]);
+        db.merge(fork.into_patch()).unwrap();
+
+        let first_fork = db.fork();
+        first_fork.get_entry("entry").set(1_u32);
+        first_fork.get_list::<_, u32>("list").clear();
+        let second_fork = db.fork();
+        second_fork.get_entry("entry").set(2_u32);
+        db.merge_batch(vec![first_fork.into_patch(), second_fork.into_patch()])
+            .unwrap();
+
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get_entry::<_, u32>("entry").get(), Some(2));
+        assert!(snapshot.get_list::<_, u32>("list").is_empty());
+    }
+
     #[test]
     fn backup_data_is_correct() {
         let db = TemporaryDB::new();
@@ -1525,6 +2553,49 @@ mod tests {
         assert_eq!(phantom_entry.get(), None);
     }
 
+    #[test]
+    fn moving_namespace() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        fork.get_proof_list("old.list").extend(vec![1_u64, 2, 3]);
+        fork.get_map(("old.group", &1_u8)).put(&1_u64, 2_u64);
+        fork.get_entry("other.entry").set(5_u32);
+        db.merge(fork.into_patch()).unwrap();
+        let list_hash = db
+            .snapshot()
+            .get_proof_list::<_, u64>("old.list")
+            .object_hash();
+
+        let mut fork = db.fork();
+        // Changes in the fork should be moved as well.
+        fork.get_proof_entry("old.entry").set(10_u32);
+        fork.move_namespace("old", "new");
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let list = snapshot.get_proof_list::<_, u64>("new.list");
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(list.object_hash(), list_hash);
+        let map = snapshot.get_map::<_, u64, u64>(("new.group", &1_u8));
+        assert_eq!(map.get(&1), Some(2));
+        assert_eq!(
+            snapshot.get_proof_entry::<_, u32>("new.entry").get(),
+            Some(10)
+        );
+        assert_eq!(snapshot.get_entry::<_, u32>("other.entry").get(), Some(5));
+
+        assert!(snapshot.index_type("old.list").is_none());
+        assert!(snapshot.index_type(("old.group", &1_u8)).is_none());
+        assert!(snapshot.index_type("old.entry").is_none());
+
+        let aggregator = SystemSchema::new(&snapshot).state_aggregator();
+        assert_eq!(
+            aggregator.keys().collect::<Vec<_>>(),
+            vec!["new.entry".to_owned(), "new.list".to_owned()]
+        );
+        assert_eq!(aggregator.get("new.list"), Some(list_hash));
+    }
+
     #[test]
     #[should_panic(expected = "immutably while it's borrowed mutably")]
     fn borrow_from_readonly_fork_after_index_is_created() {
@@ -16,6 +16,8 @@
 
 use thiserror::Error;
 
+use std::io;
+
 /// The error type for I/O operations with the `Database`.
 ///
 /// Application code in most cases should consider these errors as fatal. At the same time,
@@ -36,8 +38,21 @@ impl Error {
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::new(err.to_string())
+    }
+}
+
 impl From<rocksdb::Error> for Error {
     fn from(err: rocksdb::Error) -> Self {
         Self::new(err.to_string())
     }
 }
+
+#[cfg(feature = "lmdb")]
+impl From<lmdb::Error> for Error {
+    fn from(err: lmdb::Error) -> Self {
+        Self::new(err.to_string())
+    }
+}
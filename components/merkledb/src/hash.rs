@@ -64,6 +64,14 @@ pub enum HashTag {
     /// Hash prefix of a branch node in a Merkle Patricia tree built for
     /// a [Merkelized map](indexes/proof_map/struct.ProofMapIndex.html).
     MapBranchNode = 4,
+    /// Hash prefix of a [sparse Merkle tree](indexes/sparse_merkle/struct.SparseMerkleIndex.html).
+    SparseMerkleNode = 5,
+    /// Hash prefix of a branch node in a
+    /// [sparse Merkle tree](indexes/sparse_merkle/struct.SparseMerkleIndex.html).
+    SparseMerkleBranchNode = 6,
+    /// Hash prefix of a
+    /// [Merkle Patricia trie](indexes/patricia_trie/struct.PatriciaTrieIndex.html).
+    PatriciaTrieNode = 7,
 }
 
 impl HashTag {
@@ -187,6 +195,45 @@ impl HashTag {
     pub fn empty_map_hash() -> Hash {
         Hash::new(EMPTY_MAP_HASH)
     }
+
+    /// Obtains hash of a sparse Merkle tree. `root` is the hash of the root node of the tree,
+    /// which is `Hash::zero()` for an empty tree.
+    ///
+    /// ```text
+    /// h = sha256( HashTag::SparseMerkleNode || merkle_root )
+    /// ```
+    pub fn hash_sparse_merkle_node(root: Hash) -> Hash {
+        Self::SparseMerkleNode
+            .hash_stream()
+            .update(root.as_ref())
+            .hash()
+    }
+
+    /// Obtains hash of a branch node in a sparse Merkle tree.
+    ///
+    /// ```text
+    /// h = sha256( HashTag::SparseMerkleBranchNode || left_hash || right_hash )
+    /// ```
+    pub fn hash_sparse_merkle_branch(left_hash: &Hash, right_hash: &Hash) -> Hash {
+        Self::SparseMerkleBranchNode
+            .hash_stream()
+            .update(left_hash.as_ref())
+            .update(right_hash.as_ref())
+            .hash()
+    }
+
+    /// Obtains hash of a Merkle Patricia trie. `root` is the Keccak-256 hash of the root node
+    /// of the trie, as in Ethereum.
+    ///
+    /// ```text
+    /// h = sha256( HashTag::PatriciaTrieNode || merkle_root )
+    /// ```
+    pub fn hash_patricia_trie_node(root: Hash) -> Hash {
+        Self::PatriciaTrieNode
+            .hash_stream()
+            .update(root.as_ref())
+            .hash()
+    }
 }
 
 /// Computes a Merkle root hash for a the given list of hashes.
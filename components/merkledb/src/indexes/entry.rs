@@ -17,7 +17,7 @@
 use std::marker::PhantomData;
 
 use crate::{
-    access::{Access, AccessError, FromAccess},
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
     views::{IndexAddress, IndexType, RawAccess, RawAccessMut, View, ViewWithMetadata},
     BinaryValue,
 };
@@ -45,6 +45,16 @@ where
     }
 }
 
+impl<T, V> DescribeSchema for Entry<T, V>
+where
+    T: RawAccess,
+    V: BinaryValue,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        vec![IndexDescription::new(name, IndexType::Entry).with_value::<V>()]
+    }
+}
+
 impl<T, V> Entry<T, V>
 where
     T: RawAccess,
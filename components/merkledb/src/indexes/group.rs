@@ -15,7 +15,7 @@
 use std::marker::PhantomData;
 
 use crate::{
-    access::{Access, AccessError, FromAccess},
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
     views::{AsReadonly, GroupKeys, IndexAddress},
     BinaryKey,
 };
@@ -106,6 +106,19 @@ where
     }
 }
 
+impl<T, K, I> DescribeSchema for Group<T, K, I>
+where
+    K: ?Sized,
+    I: DescribeSchema,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        I::describe_schema(name)
+            .into_iter()
+            .map(IndexDescription::in_group::<K>)
+            .collect()
+    }
+}
+
 impl<T, K, I> Group<T, K, I>
 where
     T: Access,
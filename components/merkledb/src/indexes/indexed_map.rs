@@ -0,0 +1,412 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of a Merkelized map with a secondary index over its values.
+//!
+//! The given section contains methods related to `IndexedProofMap` and the `SecondaryKey`
+//! trait used to declare secondary indexes.
+
+use std::{marker::PhantomData, ops::Deref};
+
+use crate::{
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
+    indexes::iter::{Entries, Keys},
+    views::{IndexAddress, IndexType, RawAccess, RawAccessMut, View},
+    BinaryKey, BinaryValue, ObjectHash, ProofMapIndex,
+};
+
+/// Declaration of a secondary index over values of an [`IndexedProofMap`].
+///
+/// The implementing type is usually a unit struct, which serves as a marker of the index.
+///
+/// [`IndexedProofMap`]: struct.IndexedProofMap.html
+///
+/// # Examples
+///
+/// ```
+/// use exonum_merkledb::indexes::SecondaryKey;
+///
+/// /// Indexes account names by their first letter.
+/// struct ByInitial;
+///
+/// impl SecondaryKey<String> for ByInitial {
+///     type Key = String;
+///     const NAME: &'static str = "by_initial";
+///
+///     fn secondary_key(value: &String) -> Option<String> {
+///         value.chars().next().map(|c| c.to_string())
+///     }
+/// }
+/// ```
+pub trait SecondaryKey<V> {
+    /// Type of secondary keys.
+    type Key: BinaryKey;
+    /// Name of the secondary index. The index is stored at the address of the map
+    /// with this name appended; for example, the secondary index `by_owner` for the map
+    /// `wallets` is stored at `wallets.by_owner`.
+    const NAME: &'static str;
+
+    /// Extracts the secondary key from a value. If `None` is returned, the value
+    /// is not indexed.
+    fn secondary_key(value: &V) -> Option<Self::Key>;
+}
+
+/// A Merkelized map with a secondary index over its values.
+///
+/// `IndexedProofMap` wraps a [`ProofMapIndex`] and maintains a secondary index declared
+/// via the [`SecondaryKey`] trait. The secondary index is updated automatically on each
+/// `put` and `remove`, and allows to look up primary keys of entries with a certain
+/// secondary key. The secondary index is not Merkelized; it is derived from the map,
+/// which contributes to the state hash of the database as an ordinary `ProofMapIndex`.
+///
+/// `IndexedProofMap` dereferences to the underlying `ProofMapIndex`; thus, all read-only
+/// methods of the map (including proof generation) are available for the indexed map.
+///
+/// [`ProofMapIndex`]: proof_map/struct.ProofMapIndex.html
+/// [`SecondaryKey`]: trait.SecondaryKey.html
+///
+/// # Examples
+///
+/// ```
+/// use exonum_merkledb::{
+///     access::CopyAccessExt, indexes::SecondaryKey, Database, IndexedProofMap, TemporaryDB,
+/// };
+///
+/// struct ByOwner;
+///
+/// impl SecondaryKey<String> for ByOwner {
+///     type Key = String;
+///     const NAME: &'static str = "by_owner";
+///
+///     fn secondary_key(owner: &String) -> Option<String> {
+///         Some(owner.clone())
+///     }
+/// }
+///
+/// let db = TemporaryDB::new();
+/// let fork = db.fork();
+/// let mut accounts: IndexedProofMap<_, u64, String, ByOwner> =
+///     fork.get_indexed_proof_map("accounts");
+/// accounts.put(&1, "Alice".to_owned());
+/// accounts.put(&2, "Bob".to_owned());
+/// accounts.put(&3, "Alice".to_owned());
+///
+/// let alice_accounts: Vec<_> = accounts.lookup(&"Alice".to_owned()).collect();
+/// assert_eq!(alice_accounts, vec![1, 3]);
+/// // Read-only methods of `ProofMapIndex` are available as well.
+/// assert_eq!(accounts.get(&2), Some("Bob".to_owned()));
+/// ```
+pub struct IndexedProofMap<T: RawAccess, K: ObjectHash + ?Sized, V, S> {
+    map: ProofMapIndex<T, K, V>,
+    secondary: View<T>,
+    _s: PhantomData<S>,
+}
+
+impl<T, K, V, S> std::fmt::Debug for IndexedProofMap<T, K, V, S>
+where
+    T: RawAccess,
+    K: BinaryKey + ObjectHash + ?Sized,
+    V: BinaryValue + std::fmt::Debug,
+{
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("IndexedProofMap")
+            .field("map", &self.map)
+            .finish()
+    }
+}
+
+impl<T, K, V, S> FromAccess<T> for IndexedProofMap<T::Base, K, V, S>
+where
+    T: Access,
+    K: BinaryKey + ObjectHash + ?Sized,
+    V: BinaryValue,
+    S: SecondaryKey<V>,
+{
+    fn from_access(access: T, addr: IndexAddress) -> Result<Self, AccessError> {
+        let secondary_addr = addr.clone().append_name(S::NAME);
+        let map = ProofMapIndex::from_access(access.clone(), addr)?;
+        let secondary = access
+            .get_or_create_view(secondary_addr, IndexType::KeySet)?
+            .into();
+        Ok(Self {
+            map,
+            secondary,
+            _s: PhantomData,
+        })
+    }
+}
+
+impl<T, K, V, S> DescribeSchema for IndexedProofMap<T, K, V, S>
+where
+    T: RawAccess,
+    K: BinaryKey + ObjectHash + ?Sized,
+    V: BinaryValue,
+    S: SecondaryKey<V>,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        let mut descriptions = ProofMapIndex::<T, K, V>::describe_schema(name);
+        let secondary_name = [name, ".", S::NAME].concat();
+        descriptions
+            .push(IndexDescription::new(secondary_name, IndexType::KeySet).with_key::<S::Key>());
+        descriptions
+    }
+}
+
+impl<T, K, V, S> Deref for IndexedProofMap<T, K, V, S>
+where
+    T: RawAccess,
+    K: ObjectHash + ?Sized,
+{
+    type Target = ProofMapIndex<T, K, V>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+/// Serializes the secondary key with a LEB128 length prefix, so that entries for different
+/// secondary keys do not overlap even if the keys have variable length.
+fn secondary_prefix<K: BinaryKey>(key: &K) -> Vec<u8> {
+    let size = key.size();
+    let mut prefix = Vec::with_capacity(size + 2);
+    leb128::write::unsigned(&mut prefix, size as u64).unwrap();
+    let start = prefix.len();
+    prefix.resize(start + size, 0);
+    key.write(&mut prefix[start..]);
+    prefix
+}
+
+impl<T, K, V, S> IndexedProofMap<T, K, V, S>
+where
+    T: RawAccess,
+    K: BinaryKey + ObjectHash + ?Sized,
+    V: BinaryValue,
+    S: SecondaryKey<V>,
+{
+    /// Returns an iterator over primary keys of the entries with the specified secondary key.
+    /// The keys are returned in the ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum_merkledb::{
+    /// #     access::CopyAccessExt, indexes::SecondaryKey, Database, IndexedProofMap, TemporaryDB,
+    /// # };
+    /// struct ByLength;
+    ///
+    /// impl SecondaryKey<String> for ByLength {
+    ///     type Key = u64;
+    ///     const NAME: &'static str = "by_length";
+    ///
+    ///     fn secondary_key(value: &String) -> Option<u64> {
+    ///         Some(value.len() as u64)
+    ///     }
+    /// }
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index: IndexedProofMap<_, u8, String, ByLength> =
+    ///     fork.get_indexed_proof_map("name");
+    /// index.put(&1, "foo".to_owned());
+    /// index.put(&2, "quux".to_owned());
+    /// index.put(&3, "bar".to_owned());
+    /// assert_eq!(index.lookup(&3).collect::<Vec<_>>(), vec![1, 3]);
+    /// assert_eq!(index.lookup(&5).count(), 0);
+    /// ```
+    pub fn lookup(&self, key: &S::Key) -> Keys<'_, K> {
+        let prefix = secondary_prefix(key);
+        Entries::<K, ()>::with_detached_prefix(&self.secondary, &prefix, None).skip_values()
+    }
+
+    /// Returns `true` if the map contains at least one entry with the specified secondary key.
+    pub fn contains_secondary(&self, key: &S::Key) -> bool {
+        self.lookup(key).next().is_some()
+    }
+}
+
+impl<T, K, V, S> IndexedProofMap<T, K, V, S>
+where
+    T: RawAccessMut,
+    K: BinaryKey + ObjectHash + ?Sized,
+    V: BinaryValue,
+    S: SecondaryKey<V>,
+{
+    fn remove_secondary(&mut self, key: &K, value: &V) {
+        if let Some(secondary_key) = S::secondary_key(value) {
+            let prefix = secondary_prefix(&secondary_key);
+            self.secondary.remove(&concat_keys!(&prefix, key));
+        }
+    }
+
+    /// Inserts the key-value pair into the map and updates the secondary index.
+    pub fn put(&mut self, key: &K, value: V) {
+        if let Some(old_value) = self.map.get(key) {
+            self.remove_secondary(key, &old_value);
+        }
+        if let Some(secondary_key) = S::secondary_key(&value) {
+            let prefix = secondary_prefix(&secondary_key);
+            self.secondary.put(&concat_keys!(&prefix, key), ());
+        }
+        self.map.put(key, value);
+    }
+
+    /// Removes the key from the map and updates the secondary index.
+    pub fn remove(&mut self, key: &K) {
+        if let Some(old_value) = self.map.get(key) {
+            self.remove_secondary(key, &old_value);
+            self.map.remove(key);
+        }
+    }
+
+    /// Clears the map and the secondary index, removing all entries.
+    ///
+    /// # Notes
+    ///
+    /// Currently, this method is not optimized to delete a large set of data. During the execution of
+    /// this method, the amount of allocated memory is linearly dependent on the number of elements
+    /// in the index.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.secondary.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{access::CopyAccessExt, Database, ObjectHash, TemporaryDB};
+
+    struct ByLength;
+
+    impl SecondaryKey<String> for ByLength {
+        type Key = u64;
+        const NAME: &'static str = "by_length";
+
+        fn secondary_key(value: &String) -> Option<u64> {
+            if value.is_empty() {
+                None
+            } else {
+                Some(value.len() as u64)
+            }
+        }
+    }
+
+    struct ByContent;
+
+    impl SecondaryKey<String> for ByContent {
+        type Key = String;
+        const NAME: &'static str = "by_content";
+
+        fn secondary_key(value: &String) -> Option<String> {
+            Some(value.clone())
+        }
+    }
+
+    #[test]
+    fn secondary_index_is_maintained() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        let mut index: IndexedProofMap<_, u8, String, ByLength> =
+            fork.get_indexed_proof_map("index");
+
+        index.put(&1, "foo".to_owned());
+        index.put(&2, "bar".to_owned());
+        index.put(&3, "quux".to_owned());
+        index.put(&4, String::new());
+        assert_eq!(index.lookup(&3).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(index.lookup(&4).collect::<Vec<_>>(), vec![3]);
+        assert!(!index.contains_secondary(&0));
+
+        // Updating a value moves the entry in the secondary index.
+        index.put(&2, "quuz".to_owned());
+        assert_eq!(index.lookup(&3).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(index.lookup(&4).collect::<Vec<_>>(), vec![2, 3]);
+        index.put(&1, String::new());
+        assert!(!index.contains_secondary(&3));
+
+        index.remove(&3);
+        index.remove(&10);
+        assert_eq!(index.lookup(&4).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(index.get(&3), None);
+        assert_eq!(index.keys().collect::<Vec<_>>(), vec![1, 2, 4]);
+
+        index.clear();
+        assert!(index.iter().next().is_none());
+        assert!(!index.contains_secondary(&4));
+    }
+
+    #[test]
+    fn variable_length_secondary_keys() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        let mut index: IndexedProofMap<_, str, String, ByContent> =
+            fork.get_indexed_proof_map("index");
+
+        index.put("a", "b".to_owned());
+        index.put("ab", "c".to_owned());
+        index.put("c", "bc".to_owned());
+        index.put("d", "b".to_owned());
+        assert_eq!(
+            index.lookup(&"b".to_owned()).collect::<Vec<_>>(),
+            vec!["a", "d"]
+        );
+        assert_eq!(
+            index.lookup(&"bc".to_owned()).collect::<Vec<_>>(),
+            vec!["c"]
+        );
+        assert_eq!(
+            index.lookup(&"c".to_owned()).collect::<Vec<_>>(),
+            vec!["ab"]
+        );
+    }
+
+    #[test]
+    fn state_hash_matches_plain_map() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        let mut index: IndexedProofMap<_, u8, String, ByLength> =
+            fork.get_indexed_proof_map("indexed");
+        let mut map = fork.get_proof_map::<_, u8, String>("plain");
+        for (key, value) in &[(1, "foo"), (2, "bar"), (1, "quux")] {
+            index.put(key, (*value).to_owned());
+            map.put(key, (*value).to_owned());
+        }
+        assert_eq!(index.object_hash(), map.object_hash());
+
+        let proof = index.get_proof(1);
+        let checked_proof = proof.check_against_hash(map.object_hash()).unwrap();
+        assert_eq!(
+            checked_proof.entries().collect::<Vec<_>>(),
+            vec![(&1, &"quux".to_owned())]
+        );
+    }
+
+    #[test]
+    fn secondary_index_persists() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        fork.get_indexed_proof_map::<_, u8, String, ByLength>(("group", &1_u8))
+            .put(&1, "foo".to_owned());
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let index = snapshot.get_indexed_proof_map::<_, u8, String, ByLength>(("group", &1_u8));
+        assert_eq!(index.lookup(&3).collect::<Vec<_>>(), vec![1]);
+        let secondary = snapshot.get_key_set::<_, Vec<u8>>(
+            IndexAddress::from_root("group.by_length").append_key(&1_u8),
+        );
+        assert_eq!(secondary.iter().count(), 1);
+    }
+}
@@ -51,6 +51,18 @@ where
         Self { base_iter }
     }
 
+    /// Creates a new iterator over the provided view in descending order. The keys returned
+    /// by the iterator are additionally filtered by the `prefix`.
+    pub(crate) fn rev_with_prefix<T, P>(view: &'a View<T>, prefix: &P) -> Self
+    where
+        T: RawAccess,
+        P: BinaryKey + ?Sized,
+    {
+        Self {
+            base_iter: view.iter_rev(prefix),
+        }
+    }
+
     /// Creates a new iterator based on the provided view. The keys returned by the iterator
     /// are additionally filtered by the `prefix`, which is detached from the key before
     /// deserialization.
@@ -67,6 +79,14 @@ where
         Self { base_iter }
     }
 
+    /// Advances the iterator to the first entry with the key not less than `key`
+    /// (or not greater than `key` for iterators in the descending order), without reading
+    /// skipped entries. If the iterator is already positioned at or after such an entry,
+    /// the call has no effect.
+    pub fn seek(&mut self, key: &K) {
+        self.base_iter.seek(key);
+    }
+
     /// Skips values in the iterator output without parsing them.
     pub fn skip_values(self) -> Keys<'a, K> {
         Keys {
@@ -105,6 +125,19 @@ pub struct Keys<'a, K: ?Sized> {
     base_iter: Iter<'a, K, ()>,
 }
 
+impl<K> Keys<'_, K>
+where
+    K: BinaryKey + ?Sized,
+{
+    /// Advances the iterator to the first key not less than `key` (or not greater than `key`
+    /// for iterators in the descending order). See [`Entries::seek`] for details.
+    ///
+    /// [`Entries::seek`]: struct.Entries.html#method.seek
+    pub fn seek(&mut self, key: &K) {
+        self.base_iter.seek(key);
+    }
+}
+
 impl<K> Iterator for Keys<'_, K>
 where
     K: BinaryKey + ?Sized,
@@ -21,7 +21,7 @@
 use std::marker::PhantomData;
 
 use crate::{
-    access::{Access, AccessError, FromAccess},
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
     indexes::iter::{Entries, IndexIterator, Keys},
     views::{IndexAddress, IndexType, RawAccess, RawAccessMut, View, ViewWithMetadata},
     BinaryKey,
@@ -50,6 +50,16 @@ where
     }
 }
 
+impl<T, K> DescribeSchema for KeySetIndex<T, K>
+where
+    T: RawAccess,
+    K: BinaryKey + ?Sized,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        vec![IndexDescription::new(name, IndexType::KeySet).with_key::<K>()]
+    }
+}
+
 impl<T, K> KeySetIndex<T, K>
 where
     T: RawAccess,
@@ -119,6 +129,25 @@ where
     pub fn iter_from(&self, from: &K) -> Keys<'_, K> {
         self.index_iter(Some(from)).skip_values()
     }
+
+    /// Returns an iterator over set elements in the descending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, KeySetIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_key_set::<_, u8>("name");
+    /// index.insert(&1);
+    /// index.insert(&3);
+    /// index.insert(&2);
+    /// assert_eq!(index.iter_rev().collect::<Vec<_>>(), vec![3, 2, 1]);
+    /// ```
+    pub fn iter_rev(&self) -> Keys<'_, K> {
+        Entries::<K, ()>::rev_with_prefix(&self.base, &()).skip_values()
+    }
 }
 
 impl<T, K> KeySetIndex<T, K>
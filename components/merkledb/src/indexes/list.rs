@@ -20,7 +20,7 @@
 use std::marker::PhantomData;
 
 use crate::{
-    access::{Access, AccessError, FromAccess},
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
     indexes::iter::{Entries, IndexIterator, Values},
     views::{IndexAddress, IndexState, IndexType, RawAccess, RawAccessMut, View, ViewWithMetadata},
     BinaryValue,
@@ -53,6 +53,16 @@ where
     }
 }
 
+impl<T, V> DescribeSchema for ListIndex<T, V>
+where
+    T: RawAccess,
+    V: BinaryValue,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        vec![IndexDescription::new(name, IndexType::List).with_value::<V>()]
+    }
+}
+
 impl<T, V> ListIndex<T, V>
 where
     T: RawAccess,
@@ -191,6 +201,24 @@ where
     pub fn iter_from(&self, from: u64) -> Values<'_, V> {
         self.index_iter(Some(&from)).skip_keys()
     }
+
+    /// Returns an iterator over the list values in the reverse order, i.e., starting
+    /// from the last element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, ListIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_list("name");
+    /// index.extend(vec![1_u8, 2, 3]);
+    /// assert_eq!(index.iter_rev().collect::<Vec<_>>(), vec![3, 2, 1]);
+    /// ```
+    pub fn iter_rev(&self) -> Values<'_, V> {
+        Entries::<u64, V>::rev_with_prefix(&self.base, &()).skip_keys()
+    }
 }
 
 impl<T, V> ListIndex<T, V>
@@ -21,7 +21,7 @@
 use std::{borrow::Borrow, marker::PhantomData};
 
 use crate::{
-    access::{Access, AccessError, FromAccess},
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
     indexes::iter::{Entries, IndexIterator, Keys, Values},
     views::{IndexAddress, IndexType, RawAccess, RawAccessMut, View, ViewWithMetadata},
     BinaryKey, BinaryValue,
@@ -53,6 +53,19 @@ where
     }
 }
 
+impl<T, K, V> DescribeSchema for MapIndex<T, K, V>
+where
+    T: RawAccess,
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        vec![IndexDescription::new(name, IndexType::Map)
+            .with_key::<K>()
+            .with_value::<V>()]
+    }
+}
+
 impl<T, K, V> MapIndex<T, K, V>
 where
     T: RawAccess,
@@ -17,23 +17,31 @@
 pub use self::{
     entry::Entry,
     group::Group,
+    indexed_map::{IndexedProofMap, SecondaryKey},
     iter::{Entries, IndexIterator, Keys, Values},
     key_set::KeySetIndex,
     list::ListIndex,
     map::MapIndex,
     proof_entry::ProofEntry,
+    sorted_value::{ScoreRange, ScoredKey, SortedValueIndex},
     sparse_list::SparseListIndex,
+    ttl_map::{TtlEntries, TtlMapIndex},
     value_set::ValueSetIndex,
 };
 
 mod entry;
 mod group;
+mod indexed_map;
 mod iter;
 mod key_set;
 mod list;
 mod map;
+pub mod patricia_trie;
 mod proof_entry;
 pub mod proof_list;
 pub mod proof_map;
+mod sorted_value;
 mod sparse_list;
+pub mod sparse_merkle;
+mod ttl_map;
 mod value_set;
@@ -0,0 +1,716 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of the Merkle Patricia trie compatible with Ethereum.
+
+pub use self::proof::{
+    verify_patricia_trie_proof, CheckedPatriciaTrieProof, PatriciaTrieProof, PatriciaTrieProofError,
+};
+
+use exonum_crypto::Hash;
+
+use std::marker::PhantomData;
+
+use self::node::{common_prefix_len, keccak256, to_nibbles, Node, NodeRef};
+use crate::{
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
+    indexes::iter::{Entries, IndexIterator, Keys, Values},
+    views::{IndexAddress, IndexState, IndexType, RawAccess, RawAccessMut, View, ViewWithMetadata},
+    BinaryKey, BinaryValue, HashTag, ObjectHash,
+};
+
+mod node;
+mod proof;
+#[cfg(test)]
+mod tests;
+
+/// Root hash of an empty trie, i.e., Keccak-256 hash of the RLP encoding of an empty string.
+const EMPTY_TRIE_ROOT: [u8; 32] = [
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+];
+
+/// Prefix of the keys under which values are stored.
+const VALUE_KEY_PREFIX: u8 = 0;
+/// Prefix of the keys under which encoded trie nodes are stored.
+const NODE_KEY_PREFIX: u8 = 1;
+
+/// Returns the root hash of an empty trie.
+pub(crate) fn empty_trie_root() -> Hash {
+    Hash::new(EMPTY_TRIE_ROOT)
+}
+
+fn concat(prefix: &[u8], suffix: &[u8]) -> Vec<u8> {
+    let mut path = Vec::with_capacity(prefix.len() + suffix.len());
+    path.extend_from_slice(prefix);
+    path.extend_from_slice(suffix);
+    path
+}
+
+fn node_ref(node: &Node) -> NodeRef {
+    NodeRef::from_encoded(node.encode())
+}
+
+/// A Merkelized map with the Merkle Patricia trie (MPT) used in Ethereum as the authenticated
+/// data structure.
+///
+/// `PatriciaTrieIndex` is an alternative to [`ProofMapIndex`] for services bridging
+/// to Ethereum. The trie is built exactly as specified in the Appendix D of the Ethereum
+/// Yellow Paper: keys are split into 4-bit nibbles, trie nodes (leaves, extensions and
+/// branches) are encoded with RLP, and nodes with encoding of 32 bytes or longer are
+/// referenced by their Keccak-256 hash. Thus, [`merkle_root()`] of the index coincides with
+/// the root of an Ethereum trie with the same contents, and proofs produced by the index
+/// have the same format as the ones returned by the `eth_getProof` RPC method.
+///
+/// Keys are used in the trie as is, in their [`BinaryKey`] representation. To obtain
+/// the state or storage trie of Ethereum, use `Hash` keys equal to the Keccak-256 hash
+/// of an address or a storage slot (i.e., the *secure* trie). Values are stored
+/// in the trie in their [`BinaryValue`] representation; values with an empty representation
+/// cannot be stored in the index, since Ethereum tries treat them as absent.
+///
+/// The index stores the RLP encodings of all trie nodes, so proofs are generated without
+/// recomputing hashes. Updates recompute the nodes on the path from the updated leaf
+/// to the root only.
+///
+/// [`ProofMapIndex`]: ../proof_map/struct.ProofMapIndex.html
+/// [`merkle_root()`]: #method.merkle_root
+/// [`BinaryKey`]: ../../trait.BinaryKey.html
+/// [`BinaryValue`]: ../../trait.BinaryValue.html
+#[derive(Debug)]
+pub struct PatriciaTrieIndex<T: RawAccess, K: ?Sized, V> {
+    base: View<T>,
+    state: IndexState<T, Hash>,
+    _k: PhantomData<K>,
+    _v: PhantomData<V>,
+}
+
+impl<T, K, V> FromAccess<T> for PatriciaTrieIndex<T::Base, K, V>
+where
+    T: Access,
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+{
+    fn from_access(access: T, addr: IndexAddress) -> Result<Self, AccessError> {
+        let view = access.get_or_create_view(addr, IndexType::PatriciaTrie)?;
+        Ok(Self::new(view))
+    }
+}
+
+impl<T, K, V> DescribeSchema for PatriciaTrieIndex<T, K, V>
+where
+    T: RawAccess,
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        vec![IndexDescription::new(name, IndexType::PatriciaTrie)
+            .with_key::<K>()
+            .with_value::<V>()]
+    }
+}
+
+impl<T, K, V> PatriciaTrieIndex<T, K, V>
+where
+    T: RawAccess,
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+{
+    pub(crate) fn new(view: ViewWithMetadata<T>) -> Self {
+        let (base, state) = view.into_parts();
+        Self {
+            base,
+            state,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+
+    fn value_key(key: &K) -> Vec<u8> {
+        let mut buffer = vec![0_u8; key.size() + 1];
+        buffer[0] = VALUE_KEY_PREFIX;
+        key.write(&mut buffer[1..]);
+        buffer
+    }
+
+    fn key_nibbles(key: &K) -> Vec<u8> {
+        let mut buffer = vec![0_u8; key.size()];
+        key.write(&mut buffer);
+        to_nibbles(&buffer)
+    }
+
+    /// Returns the storage key of the node with the specified position in the trie.
+    /// The position is the nibble path from the root to the node.
+    fn node_key(position: &[u8]) -> Vec<u8> {
+        concat(&[NODE_KEY_PREFIX], position)
+    }
+
+    fn encoded_node(&self, position: &[u8]) -> Option<Vec<u8>> {
+        self.base.get(&Self::node_key(position))
+    }
+
+    fn node(&self, position: &[u8]) -> Option<Node> {
+        self.encoded_node(position).map(|encoded| {
+            Node::decode(&encoded).expect("BUG: cannot decode a stored Merkle Patricia trie node")
+        })
+    }
+
+    /// Returns the Keccak-256 hash of the root node of the trie. For an empty index,
+    /// returns the hash of the empty trie used in Ethereum,
+    /// `56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421`.
+    pub fn merkle_root(&self) -> Hash {
+        self.state.get().unwrap_or_else(empty_trie_root)
+    }
+
+    /// Returns a value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database};
+    /// use exonum_crypto::Hash;
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_patricia_trie("name");
+    ///
+    /// let hash = Hash::default();
+    /// assert_eq!(None, index.get(&hash));
+    ///
+    /// index.put(&hash, 2_u8);
+    /// assert_eq!(Some(2), index.get(&hash));
+    /// ```
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.base.get(&Self::value_key(key))
+    }
+
+    /// Returns `true` if the index contains a value for the specified key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database};
+    /// use exonum_crypto::Hash;
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_patricia_trie("name");
+    ///
+    /// let hash = Hash::default();
+    /// assert!(!index.contains(&hash));
+    ///
+    /// index.put(&hash, 2_u8);
+    /// assert!(index.contains(&hash));
+    /// ```
+    pub fn contains(&self, key: &K) -> bool {
+        self.base.contains(&Self::value_key(key))
+    }
+
+    /// Returns the proof of existence or non-existence for the specified key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, ObjectHash};
+    /// use exonum_crypto::hash;
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_patricia_trie("name");
+    /// index.put(&hash(&[1]), 100_u32);
+    ///
+    /// let proof = index.get_proof(hash(&[1]));
+    /// let checked_proof = proof.check_against_hash(index.object_hash()).unwrap();
+    /// assert_eq!(checked_proof.value(), Some(&100));
+    /// assert_eq!(checked_proof.merkle_root(), index.merkle_root());
+    ///
+    /// let proof = index.get_proof(hash(&[2]));
+    /// let checked_proof = proof.check_against_hash(index.object_hash()).unwrap();
+    /// assert_eq!(checked_proof.value(), None);
+    /// ```
+    pub fn get_proof(&self, key: K::Owned) -> PatriciaTrieProof<K::Owned, V> {
+        use std::borrow::Borrow;
+
+        let value = self.get(key.borrow());
+        let path = Self::key_nibbles(key.borrow());
+        let mut nodes = vec![];
+        let mut position = vec![];
+        // The root node is always referenced by its hash.
+        let mut is_hashed = true;
+
+        while let Some(encoded) = self.encoded_node(&position) {
+            let node = Node::decode(&encoded)
+                .expect("BUG: cannot decode a stored Merkle Patricia trie node");
+            if is_hashed {
+                nodes.push(encoded);
+            }
+
+            let rest = &path[position.len()..];
+            let child = match node {
+                Node::Leaf { .. } => None,
+                Node::Extension { path, child } if rest.starts_with(&path) => {
+                    position.extend_from_slice(&path);
+                    Some(child)
+                }
+                Node::Extension { .. } => None,
+                Node::Branch { mut children, .. } => rest.first().and_then(|&nibble| {
+                    position.push(nibble);
+                    children[usize::from(nibble)].take()
+                }),
+            };
+            match child {
+                Some(child) => is_hashed = matches!(child, NodeRef::Hash(_)),
+                None => break,
+            }
+        }
+        PatriciaTrieProof::new(key, value, nodes)
+    }
+
+    /// Returns an iterator over the entries of the index in ascending order of the keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database};
+    /// use exonum_crypto::Hash;
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let index = fork.get_patricia_trie::<_, Hash, u8>("name");
+    ///
+    /// for (key, value) in index.iter() {
+    ///     println!("{} = {}", key, value);
+    /// }
+    /// ```
+    pub fn iter(&self) -> Entries<'_, K, V> {
+        self.index_iter(None)
+    }
+
+    /// Returns an iterator over the keys of the index in ascending order.
+    pub fn keys(&self) -> Keys<'_, K> {
+        self.iter().skip_values()
+    }
+
+    /// Returns an iterator over the values of the index in ascending order of the keys.
+    pub fn values(&self) -> Values<'_, V> {
+        self.iter().skip_keys()
+    }
+
+    /// Returns an iterator over the entries of the index in ascending order of the keys
+    /// starting from the specified key.
+    pub fn iter_from(&self, from: &K) -> Entries<'_, K, V> {
+        self.index_iter(Some(from))
+    }
+}
+
+impl<T, K, V> PatriciaTrieIndex<T, K, V>
+where
+    T: RawAccessMut,
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+{
+    fn put_node(&mut self, position: &[u8], node: &Node) -> Vec<u8> {
+        let encoded = node.encode();
+        self.base.put(&Self::node_key(position), encoded.clone());
+        encoded
+    }
+
+    fn remove_node(&mut self, position: &[u8]) {
+        self.base.remove(&Self::node_key(position));
+    }
+
+    /// Stores the node at the specified position and returns the reference to it.
+    fn store(&mut self, position: &[u8], node: &Node) -> NodeRef {
+        NodeRef::from_encoded(self.put_node(position, node))
+    }
+
+    fn update_root(&mut self) {
+        if let Some(encoded) = self.encoded_node(&[]) {
+            self.state.set(Hash::new(keccak256(&encoded)));
+        } else {
+            self.state.unset();
+        }
+    }
+
+    /// Inserts a value into the subtrie at the specified position. `rest` is the remaining
+    /// part of the key path. Returns the reference to the updated subtrie root.
+    fn insert_at(&mut self, position: &[u8], rest: &[u8], value: Vec<u8>) -> NodeRef {
+        let node = match self.node(position) {
+            None => Node::Leaf {
+                path: rest.to_vec(),
+                value,
+            },
+
+            Some(Node::Leaf { path, .. }) if path == rest => Node::Leaf { path, value },
+
+            Some(Node::Leaf {
+                path,
+                value: old_value,
+            }) => {
+                let common_len = common_prefix_len(&path, rest);
+                let branch_position = concat(position, &path[..common_len]);
+                let mut children: [Option<NodeRef>; 16] = Default::default();
+                let mut branch_value = None;
+
+                for (path, value) in [(path.as_slice(), old_value), (rest, value)] {
+                    if let Some((&nibble, suffix)) = path[common_len..].split_first() {
+                        let leaf = Node::Leaf {
+                            path: suffix.to_vec(),
+                            value,
+                        };
+                        let leaf_position = concat(&branch_position, &[nibble]);
+                        children[usize::from(nibble)] = Some(self.store(&leaf_position, &leaf));
+                    } else {
+                        branch_value = Some(value);
+                    }
+                }
+
+                let branch = Node::Branch {
+                    children: Box::new(children),
+                    value: branch_value,
+                };
+                self.branch_with_extension(position, &rest[..common_len], &branch)
+            }
+
+            Some(Node::Extension { path, child }) => {
+                let common_len = common_prefix_len(&path, rest);
+                if common_len == path.len() {
+                    let child_position = concat(position, &path);
+                    let child = self.insert_at(&child_position, &rest[common_len..], value);
+                    Node::Extension { path, child }
+                } else {
+                    // Split the extension into a branch with the optional extensions
+                    // above and below it.
+                    let branch_position = concat(position, &path[..common_len]);
+                    let mut children: [Option<NodeRef>; 16] = Default::default();
+                    let mut branch_value = None;
+
+                    let nibble = path[common_len];
+                    let lower_path = &path[common_len + 1..];
+                    children[usize::from(nibble)] = Some(if lower_path.is_empty() {
+                        // The child of the extension is already stored at the correct position.
+                        child
+                    } else {
+                        let lower_extension = Node::Extension {
+                            path: lower_path.to_vec(),
+                            child,
+                        };
+                        let lower_position = concat(&branch_position, &[nibble]);
+                        self.store(&lower_position, &lower_extension)
+                    });
+
+                    if let Some((&nibble, suffix)) = rest[common_len..].split_first() {
+                        let leaf = Node::Leaf {
+                            path: suffix.to_vec(),
+                            value,
+                        };
+                        let leaf_position = concat(&branch_position, &[nibble]);
+                        children[usize::from(nibble)] = Some(self.store(&leaf_position, &leaf));
+                    } else {
+                        branch_value = Some(value);
+                    }
+
+                    let branch = Node::Branch {
+                        children: Box::new(children),
+                        value: branch_value,
+                    };
+                    self.branch_with_extension(position, &path[..common_len], &branch)
+                }
+            }
+
+            Some(Node::Branch {
+                mut children,
+                value: branch_value,
+            }) => {
+                if let Some((&nibble, suffix)) = rest.split_first() {
+                    let child_position = concat(position, &[nibble]);
+                    children[usize::from(nibble)] =
+                        Some(self.insert_at(&child_position, suffix, value));
+                    Node::Branch {
+                        children,
+                        value: branch_value,
+                    }
+                } else {
+                    Node::Branch {
+                        children,
+                        value: Some(value),
+                    }
+                }
+            }
+        };
+        self.store(position, &node)
+    }
+
+    /// Places the branch below an extension with the specified path, or returns the branch
+    /// itself if the path is empty. The returned node should be stored at `position`.
+    fn branch_with_extension(&mut self, position: &[u8], path: &[u8], branch: &Node) -> Node {
+        if path.is_empty() {
+            branch.clone()
+        } else {
+            let child = self.store(&concat(position, path), branch);
+            Node::Extension {
+                path: path.to_vec(),
+                child,
+            }
+        }
+    }
+
+    /// Removes a value from the subtrie at the specified position. `rest` is the remaining
+    /// part of the key path.
+    ///
+    /// Returns `None` if the key is absent from the subtrie, in which case the subtrie
+    /// is not modified. Otherwise, returns the updated subtrie root (`Some(None)` if
+    /// the subtrie has become empty), which is already stored at `position`.
+    #[allow(clippy::option_option)]
+    fn remove_at(&mut self, position: &[u8], rest: &[u8]) -> Option<Option<Node>> {
+        let node = match self.node(position)? {
+            Node::Leaf { path, .. } => {
+                if path != rest {
+                    return None;
+                }
+                self.remove_node(position);
+                return Some(None);
+            }
+
+            Node::Extension { path, .. } => {
+                if !rest.starts_with(&path) {
+                    return None;
+                }
+                let child_position = concat(position, &path);
+                // The child of an extension is a branch, which cannot become empty
+                // after a single removal.
+                let child = self
+                    .remove_at(&child_position, &rest[path.len()..])?
+                    .expect("BUG: branch node in a Merkle Patricia trie has become empty");
+
+                match child {
+                    Node::Leaf {
+                        path: child_path,
+                        value,
+                    } => {
+                        self.remove_node(&child_position);
+                        Node::Leaf {
+                            path: concat(&path, &child_path),
+                            value,
+                        }
+                    }
+                    Node::Extension {
+                        path: child_path,
+                        child,
+                    } => {
+                        self.remove_node(&child_position);
+                        Node::Extension {
+                            path: concat(&path, &child_path),
+                            child,
+                        }
+                    }
+                    branch @ Node::Branch { .. } => Node::Extension {
+                        path,
+                        child: node_ref(&branch),
+                    },
+                }
+            }
+
+            Node::Branch {
+                mut children,
+                mut value,
+            } => {
+                if let Some((&nibble, suffix)) = rest.split_first() {
+                    let index = usize::from(nibble);
+                    children[index].as_ref()?;
+                    let child_position = concat(position, &[nibble]);
+                    let child = self.remove_at(&child_position, suffix)?;
+                    children[index] = child.as_ref().map(node_ref);
+                } else {
+                    value.take()?;
+                }
+                self.normalize_branch(position, children, value)
+            }
+        };
+
+        self.put_node(position, &node);
+        Some(Some(node))
+    }
+
+    /// Converts a branch with a single remaining entry into an equivalent leaf or extension.
+    fn normalize_branch(
+        &mut self,
+        position: &[u8],
+        children: Box<[Option<NodeRef>; 16]>,
+        value: Option<Vec<u8>>,
+    ) -> Node {
+        let mut child_indices = children
+            .iter()
+            .enumerate()
+            .filter_map(|(index, child)| child.as_ref().map(|_| index));
+        let (first_child, second_child) = (child_indices.next(), child_indices.next());
+
+        match (first_child, second_child, value) {
+            (None, _, None) => unreachable!("BUG: branch node in a Merkle Patricia trie is empty"),
+            (None, _, Some(value)) => Node::Leaf {
+                path: vec![],
+                value,
+            },
+            (Some(index), None, None) => {
+                // `index` is lesser than 16 since it is an index of a child.
+                let nibble = index as u8;
+                let child_position = concat(position, &[nibble]);
+                let child = self
+                    .node(&child_position)
+                    .expect("BUG: child node of a Merkle Patricia trie is not stored");
+
+                match child {
+                    Node::Leaf { path, value } => {
+                        self.remove_node(&child_position);
+                        Node::Leaf {
+                            path: concat(&[nibble], &path),
+                            value,
+                        }
+                    }
+                    Node::Extension { path, child } => {
+                        self.remove_node(&child_position);
+                        Node::Extension {
+                            path: concat(&[nibble], &path),
+                            child,
+                        }
+                    }
+                    branch @ Node::Branch { .. } => Node::Extension {
+                        path: vec![nibble],
+                        child: node_ref(&branch),
+                    },
+                }
+            }
+            (_, _, value) => Node::Branch { children, value },
+        }
+    }
+
+    /// Inserts the key-value pair into the index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the binary representation of the value is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database};
+    /// use exonum_crypto::Hash;
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_patricia_trie("name");
+    ///
+    /// let hash = Hash::default();
+    /// index.put(&hash, 2_u8);
+    /// assert!(index.contains(&hash));
+    /// ```
+    pub fn put(&mut self, key: &K, value: V) {
+        let value_bytes = value.to_bytes();
+        assert!(
+            !value_bytes.is_empty(),
+            "Values with an empty binary representation cannot be stored in a Merkle Patricia trie"
+        );
+
+        let path = Self::key_nibbles(key);
+        self.base.put(&Self::value_key(key), value);
+        self.insert_at(&[], &path, value_bytes);
+        self.update_root();
+    }
+
+    /// Removes the key from the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database};
+    /// use exonum_crypto::Hash;
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_patricia_trie("name");
+    ///
+    /// let hash = Hash::default();
+    /// index.put(&hash, 2_u8);
+    /// assert!(index.contains(&hash));
+    ///
+    /// index.remove(&hash);
+    /// assert!(!index.contains(&hash));
+    /// ```
+    pub fn remove(&mut self, key: &K) {
+        let value_key = Self::value_key(key);
+        if self.base.contains(&value_key) {
+            self.base.remove(&value_key);
+            self.remove_at(&[], &Self::key_nibbles(key));
+            self.update_root();
+        }
+    }
+
+    /// Clears the index, removing all entries.
+    ///
+    /// # Notes
+    ///
+    /// Currently, this method is not optimized to delete a large set of data. During the execution of
+    /// this method, the amount of allocated memory is linearly dependent on the number of elements
+    /// in the index.
+    pub fn clear(&mut self) {
+        self.base.clear();
+        self.state.unset();
+    }
+}
+
+/// `object_hash()` of a Merkle Patricia trie index is computed from the root hash
+/// of the trie as
+///
+/// ```text
+/// h = sha256( HashTag::PatriciaTrieNode || merkle_root )
+/// ```
+///
+/// where `merkle_root` is the Keccak-256 hash of the root node, as in Ethereum.
+impl<T, K, V> ObjectHash for PatriciaTrieIndex<T, K, V>
+where
+    T: RawAccess,
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+{
+    fn object_hash(&self) -> Hash {
+        HashTag::hash_patricia_trie_node(self.merkle_root())
+    }
+}
+
+impl<'a, T, K, V> IntoIterator for &'a PatriciaTrieIndex<T, K, V>
+where
+    T: RawAccess,
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+{
+    type Item = (K::Owned, V);
+    type IntoIter = Entries<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, K, V> IndexIterator for PatriciaTrieIndex<T, K, V>
+where
+    T: RawAccess,
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+{
+    type Key = K;
+    type Value = V;
+
+    fn index_iter(&self, from: Option<&K>) -> Entries<'_, K, V> {
+        Entries::with_detached_prefix(&self.base, &VALUE_KEY_PREFIX, from)
+    }
+}
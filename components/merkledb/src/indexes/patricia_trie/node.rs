@@ -0,0 +1,213 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Nodes of the Merkle Patricia trie and their RLP encoding.
+
+use rlp::{DecoderError, Rlp, RlpStream};
+use sha3::{Digest, Keccak256};
+
+use std::convert::TryInto;
+
+/// Size of a Keccak-256 digest.
+const KECCAK_SIZE: usize = 32;
+/// Number of children of a branch node.
+const BRANCH_SIZE: usize = 16;
+
+/// Computes the Keccak-256 digest of the data.
+pub(super) fn keccak256(data: &[u8]) -> [u8; KECCAK_SIZE] {
+    Keccak256::digest(data).into()
+}
+
+/// Splits bytes into nibbles, starting from the high nibble of the first byte.
+pub(super) fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|&byte| vec![byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+/// Returns the length of the common prefix of two nibble sequences.
+pub(super) fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+/// Encodes a nibble path using the hex-prefix encoding.
+fn encode_path(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 2 } else { 0 };
+    let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        encoded.push(((flag + 1) << 4) | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        encoded.push(flag << 4);
+        nibbles
+    };
+    encoded.extend(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+    encoded
+}
+
+/// Decodes a hex-prefix encoded path. Returns the path nibbles and the leaf flag.
+fn decode_path(encoded: &[u8]) -> Result<(Vec<u8>, bool), DecoderError> {
+    let (&first, rest) = encoded
+        .split_first()
+        .ok_or(DecoderError::Custom("empty node path"))?;
+    let flag = first >> 4;
+    if flag > 3 || (flag % 2 == 0 && first & 0x0f != 0) {
+        return Err(DecoderError::Custom("invalid node path prefix"));
+    }
+
+    let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+    if flag % 2 == 1 {
+        nibbles.push(first & 0x0f);
+    }
+    nibbles.extend(to_nibbles(rest));
+    Ok((nibbles, flag >= 2))
+}
+
+/// Reference to a child node: either the Keccak-256 hash of its encoding, or the encoding
+/// itself if it is shorter than 32 bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum NodeRef {
+    Hash([u8; KECCAK_SIZE]),
+    Inline(Vec<u8>),
+}
+
+impl NodeRef {
+    pub fn from_encoded(encoded: Vec<u8>) -> Self {
+        if encoded.len() < KECCAK_SIZE {
+            Self::Inline(encoded)
+        } else {
+            Self::Hash(keccak256(&encoded))
+        }
+    }
+
+    fn append_to(&self, stream: &mut RlpStream) {
+        match self {
+            Self::Hash(hash) => {
+                stream.append(&&hash[..]);
+            }
+            Self::Inline(encoded) => {
+                stream.append_raw(encoded, 1);
+            }
+        }
+    }
+
+    fn decode(item: &Rlp<'_>) -> Result<Self, DecoderError> {
+        if item.is_list() {
+            Ok(Self::Inline(item.as_raw().to_vec()))
+        } else {
+            let hash = item
+                .data()?
+                .try_into()
+                .map_err(|_| DecoderError::Custom("invalid child reference"))?;
+            Ok(Self::Hash(hash))
+        }
+    }
+}
+
+/// Node of the Merkle Patricia trie.
+///
+/// Paths of leaves and extensions are stored as nibble sequences; each node is stored
+/// in the index at the nibble path of its position in the trie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Node {
+    Leaf {
+        path: Vec<u8>,
+        value: Vec<u8>,
+    },
+    Extension {
+        path: Vec<u8>,
+        child: NodeRef,
+    },
+    Branch {
+        children: Box<[Option<NodeRef>; BRANCH_SIZE]>,
+        value: Option<Vec<u8>>,
+    },
+}
+
+impl Node {
+    /// Encodes the node as RLP according to the Ethereum Yellow Paper.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut stream;
+        match self {
+            Self::Leaf { path, value } => {
+                stream = RlpStream::new_list(2);
+                stream.append(&encode_path(path, true));
+                stream.append(value);
+            }
+            Self::Extension { path, child } => {
+                stream = RlpStream::new_list(2);
+                stream.append(&encode_path(path, false));
+                child.append_to(&mut stream);
+            }
+            Self::Branch { children, value } => {
+                stream = RlpStream::new_list(BRANCH_SIZE + 1);
+                for child in children.iter() {
+                    if let Some(child) = child {
+                        child.append_to(&mut stream);
+                    } else {
+                        stream.append_empty_data();
+                    }
+                }
+                if let Some(value) = value {
+                    stream.append(value);
+                } else {
+                    stream.append_empty_data();
+                }
+            }
+        }
+        stream.out().to_vec()
+    }
+
+    /// Decodes the node from its RLP encoding.
+    pub fn decode(encoded: &[u8]) -> Result<Self, DecoderError> {
+        let rlp = Rlp::new(encoded);
+        match rlp.item_count()? {
+            2 => {
+                let (path, is_leaf) = decode_path(rlp.at(0)?.data()?)?;
+                if is_leaf {
+                    let value = rlp.at(1)?.data()?.to_vec();
+                    Ok(Self::Leaf { path, value })
+                } else if path.is_empty() {
+                    Err(DecoderError::Custom("empty extension path"))
+                } else {
+                    let child = NodeRef::decode(&rlp.at(1)?)?;
+                    Ok(Self::Extension { path, child })
+                }
+            }
+            17 => {
+                let mut children: [Option<NodeRef>; BRANCH_SIZE] = Default::default();
+                for (i, child) in children.iter_mut().enumerate() {
+                    let item = rlp.at(i)?;
+                    if !item.is_empty() {
+                        *child = Some(NodeRef::decode(&item)?);
+                    }
+                }
+                let value = rlp.at(BRANCH_SIZE)?.data()?;
+                let value = if value.is_empty() {
+                    None
+                } else {
+                    Some(value.to_vec())
+                };
+                Ok(Self::Branch {
+                    children: Box::new(children),
+                    value,
+                })
+            }
+            _ => Err(DecoderError::Custom(
+                "invalid number of items in a trie node",
+            )),
+        }
+    }
+}
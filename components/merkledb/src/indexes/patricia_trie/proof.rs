@@ -0,0 +1,318 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use exonum_crypto::Hash;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use std::collections::HashMap;
+
+use super::{
+    empty_trie_root,
+    node::{keccak256, to_nibbles, Node, NodeRef},
+};
+use crate::{BinaryKey, BinaryValue, HashTag, ValidationError};
+
+/// An error returned when a Merkle Patricia trie proof is invalid.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PatriciaTrieProofError {
+    /// A node referenced on the path to the key is missing from the proof.
+    #[error("node {0:?} referenced on the path to the key is missing from the proof")]
+    MissingNode(Hash),
+
+    /// A node in the proof is not a valid RLP-encoded trie node.
+    #[error("malformed trie node in the proof")]
+    MalformedNode,
+
+    /// The proof contains nodes which are not on the path to the key.
+    #[error("proof contains nodes which are not on the path to the key")]
+    RedundantNodes,
+
+    /// The value in the proof does not match the value in the trie.
+    #[error("value in the proof does not match the value in the trie")]
+    ValueMismatch,
+}
+
+/// Verifies a Merkle Patricia trie proof in the format used by Ethereum, i.e., a list
+/// of RLP-encoded trie nodes on the path from the root to the key (as returned in the
+/// `accountProof` and `storageProof` fields of the `eth_getProof` RPC method).
+///
+/// `root` is the Keccak-256 hash of the trie root, and `key` is the key in the trie
+/// (e.g., the Keccak-256 hash of an address for the Ethereum state trie). The order
+/// of nodes in the proof is not significant, but each node must be used to reach the key.
+///
+/// Returns the value at the key, or `None` if the proof asserts that the key is absent
+/// from the trie.
+///
+/// # Examples
+///
+/// ```
+/// # use exonum_merkledb::{access::CopyAccessExt, Database, TemporaryDB};
+/// # use exonum_merkledb::patricia_trie::verify_patricia_trie_proof;
+/// # use exonum_crypto::hash;
+/// let fork = { let db = TemporaryDB::new(); db.fork() };
+/// let mut index = fork.get_patricia_trie("index");
+/// let key = hash(&[1]);
+/// index.put(&key, vec![1_u8, 2, 3]);
+///
+/// let proof = index.get_proof(key);
+/// let value = verify_patricia_trie_proof(index.merkle_root(), key.as_ref(), proof.nodes())
+///     .unwrap();
+/// assert_eq!(value, Some(vec![1, 2, 3]));
+/// ```
+pub fn verify_patricia_trie_proof<N: AsRef<[u8]>>(
+    root: Hash,
+    key: &[u8],
+    nodes: &[N],
+) -> Result<Option<Vec<u8>>, PatriciaTrieProofError> {
+    if root == empty_trie_root() && nodes.is_empty() {
+        return Ok(None);
+    }
+
+    let nodes: HashMap<_, _> = nodes
+        .iter()
+        .map(|node| (keccak256(node.as_ref()), node.as_ref()))
+        .collect();
+    let mut used_nodes = 0;
+    let path = to_nibbles(key);
+    let mut rest = path.as_slice();
+    let mut next_ref = NodeRef::Hash(root.as_bytes());
+
+    let value = loop {
+        let encoded = match &next_ref {
+            NodeRef::Hash(hash) => {
+                used_nodes += 1;
+                *nodes
+                    .get(hash)
+                    .ok_or_else(|| PatriciaTrieProofError::MissingNode(Hash::new(*hash)))?
+            }
+            NodeRef::Inline(encoded) => encoded.as_slice(),
+        };
+        let node = Node::decode(encoded).map_err(|_| PatriciaTrieProofError::MalformedNode)?;
+
+        let child = match node {
+            Node::Leaf { path, value } => break Some(value).filter(|_| path == rest),
+            Node::Extension { path, child } => {
+                if !rest.starts_with(&path) {
+                    break None;
+                }
+                rest = &rest[path.len()..];
+                child
+            }
+            Node::Branch {
+                mut children,
+                value,
+            } => {
+                let (&nibble, suffix) = match rest.split_first() {
+                    Some(split) => split,
+                    None => break value,
+                };
+                rest = suffix;
+                match children[usize::from(nibble)].take() {
+                    Some(child) => child,
+                    None => break None,
+                }
+            }
+        };
+        next_ref = child;
+    };
+
+    if used_nodes == nodes.len() {
+        Ok(value)
+    } else {
+        Err(PatriciaTrieProofError::RedundantNodes)
+    }
+}
+
+/// Proof of existence or non-existence of a key in a [`PatriciaTrieIndex`].
+///
+/// The proof consists of the value at the key (or its absence) and RLP encodings
+/// of the trie nodes on the path from the root to the key, starting from the root.
+/// Nodes embedded into their parents (i.e., ones with encoding shorter than 32 bytes)
+/// are not included separately. Thus, the nodes in the proof are the same as in
+/// the proofs returned by the `eth_getProof` RPC method of Ethereum nodes, and can
+/// be verified with [`verify_patricia_trie_proof`] independently of the key and value
+/// types.
+///
+/// # Workflow
+///
+/// ```
+/// # use exonum_merkledb::{access::CopyAccessExt, Database, TemporaryDB, ObjectHash};
+/// # use exonum_crypto::hash;
+/// # fn main() -> anyhow::Result<()> {
+/// let fork = { let db = TemporaryDB::new(); db.fork() };
+/// let mut index = fork.get_patricia_trie("index");
+/// let (h1, h2) = (hash(&[1]), hash(&[2]));
+/// index.put(&h1, 100_u32);
+///
+/// let proof = index.get_proof(h1);
+/// let checked_proof = proof.check()?;
+/// assert_eq!(checked_proof.value(), Some(&100));
+/// assert_eq!(checked_proof.merkle_root(), index.merkle_root());
+/// assert_eq!(checked_proof.index_hash(), index.object_hash());
+///
+/// // Proofs of absence have the same form.
+/// let checked_proof = index.get_proof(h2).check_against_hash(index.object_hash())?;
+/// assert_eq!(checked_proof.key(), &h2);
+/// assert_eq!(checked_proof.value(), None);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # JSON serialization
+///
+/// `PatriciaTrieProof` is serialized to JSON as an object with the following fields:
+///
+/// - `key` is the requested key.
+/// - `value` is the value at the key, or `null` if the key is absent from the index.
+/// - `nodes` is an array of RLP-encoded trie nodes, each serialized as a hex string
+///   with the `0x` prefix.
+///
+/// [`PatriciaTrieIndex`]: struct.PatriciaTrieIndex.html
+/// [`verify_patricia_trie_proof`]: fn.verify_patricia_trie_proof.html
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatriciaTrieProof<K, V> {
+    key: K,
+    value: Option<V>,
+    #[serde(with = "hex_nodes")]
+    nodes: Vec<Vec<u8>>,
+}
+
+/// Version of `PatriciaTrieProof` obtained after verification.
+///
+/// See [`PatriciaTrieProof`] for an example of usage.
+///
+/// [`PatriciaTrieProof`]: struct.PatriciaTrieProof.html#workflow
+#[derive(Debug, Serialize)]
+pub struct CheckedPatriciaTrieProof<'a, K, V> {
+    key: &'a K,
+    value: Option<&'a V>,
+    merkle_root: Hash,
+}
+
+impl<K, V> PatriciaTrieProof<K, V> {
+    pub(super) fn new(key: K, value: Option<V>, nodes: Vec<Vec<u8>>) -> Self {
+        Self { key, value, nodes }
+    }
+
+    /// Returns the key, which existence or non-existence is asserted by the proof.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns the value at the key without verifying the proof.
+    pub fn value_unchecked(&self) -> Option<&V> {
+        self.value.as_ref()
+    }
+
+    /// Returns RLP encodings of the trie nodes in the proof, starting from the root node.
+    pub fn nodes(&self) -> &[Vec<u8>] {
+        &self.nodes
+    }
+}
+
+impl<K, V> PatriciaTrieProof<K, V>
+where
+    K: BinaryKey,
+    V: BinaryValue,
+{
+    /// Checks the proof for consistency and computes the root hash of the trie.
+    ///
+    /// The proof is considered well-formed if it contains exactly the nodes on the path
+    /// from the root to the key, and the value in the trie matches the value in the proof.
+    pub fn check(&self) -> Result<CheckedPatriciaTrieProof<'_, K, V>, PatriciaTrieProofError> {
+        let merkle_root = self
+            .nodes
+            .first()
+            .map_or_else(empty_trie_root, |root| Hash::new(keccak256(root)));
+
+        let mut key = vec![0_u8; self.key.size()];
+        self.key.write(&mut key);
+        let trie_value = verify_patricia_trie_proof(merkle_root, &key, &self.nodes)?;
+        let value = self.value.as_ref().map(BinaryValue::to_bytes);
+        if trie_value != value {
+            return Err(PatriciaTrieProofError::ValueMismatch);
+        }
+
+        Ok(CheckedPatriciaTrieProof {
+            key: &self.key,
+            value: self.value.as_ref(),
+            merkle_root,
+        })
+    }
+
+    /// Checks this proof against a trusted index hash. Fails if the proof is malformed or the
+    /// hash does not match the one computed from the proof.
+    pub fn check_against_hash(
+        &self,
+        expected_index_hash: Hash,
+    ) -> Result<CheckedPatriciaTrieProof<'_, K, V>, ValidationError<PatriciaTrieProofError>> {
+        self.check()
+            .map_err(ValidationError::Malformed)
+            .and_then(|checked| {
+                if checked.index_hash() == expected_index_hash {
+                    Ok(checked)
+                } else {
+                    Err(ValidationError::UnmatchedRootHash)
+                }
+            })
+    }
+}
+
+impl<'a, K, V> CheckedPatriciaTrieProof<'a, K, V> {
+    /// Returns the key, which existence or non-existence is asserted by the proof.
+    pub fn key(&self) -> &'a K {
+        self.key
+    }
+
+    /// Returns the value at the key, or `None` if the proof asserts that the key is absent
+    /// from the index.
+    pub fn value(&self) -> Option<&'a V> {
+        self.value
+    }
+
+    /// Returns the Keccak-256 hash of the trie root, which can be compared with the roots
+    /// of Ethereum tries.
+    pub fn merkle_root(&self) -> Hash {
+        self.merkle_root
+    }
+
+    /// Returns the `object_hash()` of the underlying `PatriciaTrieIndex`.
+    pub fn index_hash(&self) -> Hash {
+        HashTag::hash_patricia_trie_node(self.merkle_root)
+    }
+}
+
+/// (De)serialization of trie nodes as `0x`-prefixed hex strings.
+mod hex_nodes {
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(nodes: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(nodes.iter().map(|node| format!("0x{}", hex::encode(node))))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        let nodes = Vec::<String>::deserialize(deserializer)?;
+        nodes
+            .iter()
+            .map(|node| {
+                let node = node.strip_prefix("0x").unwrap_or(node);
+                hex::decode(node).map_err(D::Error::custom)
+            })
+            .collect()
+    }
+}
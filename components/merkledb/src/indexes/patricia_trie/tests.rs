@@ -0,0 +1,447 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use exonum_crypto::{hash, Hash};
+use rand::{seq::SliceRandom, thread_rng, Rng};
+
+use super::{
+    empty_trie_root,
+    node::{common_prefix_len, keccak256, to_nibbles, Node, NodeRef},
+    verify_patricia_trie_proof, PatriciaTrieProof, PatriciaTrieProofError, NODE_KEY_PREFIX,
+};
+use crate::{
+    access::CopyAccessExt, BinaryValue, Database, HashTag, ObjectHash, SystemSchema, TemporaryDB,
+    ValidationError,
+};
+
+const IDX_NAME: &str = "idx_name";
+
+/// Straightforward recursive construction of the trie node for sorted entries
+/// with distinct nibble paths.
+fn reference_node(entries: &[(Vec<u8>, Vec<u8>)], depth: usize) -> Node {
+    if let [(path, value)] = entries {
+        return Node::Leaf {
+            path: path[depth..].to_vec(),
+            value: value.clone(),
+        };
+    }
+
+    let first = &entries[0].0[depth..];
+    let prefix_len = entries
+        .iter()
+        .map(|(path, _)| common_prefix_len(first, &path[depth..]))
+        .min()
+        .unwrap();
+    if prefix_len > 0 {
+        let branch = reference_node(entries, depth + prefix_len);
+        return Node::Extension {
+            path: first[..prefix_len].to_vec(),
+            child: NodeRef::from_encoded(branch.encode()),
+        };
+    }
+
+    let mut children: [Option<NodeRef>; 16] = Default::default();
+    let mut value = None;
+    for nibble in 0..16 {
+        let child_entries: Vec<_> = entries
+            .iter()
+            .filter(|(path, _)| path.len() > depth && path[depth] == nibble)
+            .cloned()
+            .collect();
+        if !child_entries.is_empty() {
+            let child = reference_node(&child_entries, depth + 1);
+            children[usize::from(nibble)] = Some(NodeRef::from_encoded(child.encode()));
+        }
+    }
+    if let Some((_, branch_value)) = entries.iter().find(|(path, _)| path.len() == depth) {
+        value = Some(branch_value.clone());
+    }
+    Node::Branch {
+        children: Box::new(children),
+        value,
+    }
+}
+
+fn reference_root(entries: &[(Hash, u64)]) -> Hash {
+    let mut entries: Vec<_> = entries
+        .iter()
+        .map(|(key, value)| (to_nibbles(key.as_ref()), value.to_bytes()))
+        .collect();
+    if entries.is_empty() {
+        return empty_trie_root();
+    }
+    entries.sort();
+    Hash::new(keccak256(&reference_node(&entries, 0).encode()))
+}
+
+fn generate_entries(count: usize) -> Vec<(Hash, u64)> {
+    let mut rng = thread_rng();
+    (0..count)
+        .map(|i| (hash(&(i as u64).to_le_bytes()), u64::from(rng.gen::<u32>())))
+        .collect()
+}
+
+#[test]
+fn node_encoding_roundtrip() {
+    let leaf = Node::Leaf {
+        path: vec![1, 2, 3],
+        value: b"value".to_vec(),
+    };
+    let long_leaf = Node::Leaf {
+        path: vec![],
+        value: vec![7; 40],
+    };
+    let extension = Node::Extension {
+        path: vec![10, 11],
+        child: NodeRef::from_encoded(long_leaf.encode()),
+    };
+    let mut children: [Option<NodeRef>; 16] = Default::default();
+    children[3] = Some(NodeRef::from_encoded(leaf.encode()));
+    children[15] = Some(NodeRef::from_encoded(long_leaf.encode()));
+    let branch = Node::Branch {
+        children: Box::new(children),
+        value: Some(b"branch".to_vec()),
+    };
+
+    assert!(matches!(
+        NodeRef::from_encoded(leaf.encode()),
+        NodeRef::Inline(_)
+    ));
+    assert!(matches!(
+        NodeRef::from_encoded(long_leaf.encode()),
+        NodeRef::Hash(_)
+    ));
+    for node in &[leaf, long_leaf, extension, branch] {
+        assert_eq!(Node::decode(&node.encode()).unwrap(), *node);
+    }
+    assert!(Node::decode(&[0xc0]).is_err());
+    assert!(Node::decode(b"garbage").is_err());
+}
+
+#[test]
+fn empty_index() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let index = fork.get_patricia_trie::<_, Hash, u64>(IDX_NAME);
+    assert_eq!(
+        index.merkle_root().to_hex(),
+        "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"
+    );
+    assert_eq!(
+        index.object_hash(),
+        HashTag::hash_patricia_trie_node(empty_trie_root())
+    );
+    assert_eq!(index.iter().count(), 0);
+}
+
+/// Test vectors from the Ethereum test suite (`TrieTests/trietest.json`).
+#[test]
+fn root_hash_matches_ethereum() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+
+    let mut index = fork.get_patricia_trie::<_, str, String>("dogs");
+    index.put("doe", "reindeer".to_owned());
+    index.put("dog", "puppy".to_owned());
+    index.put("dogglesworth", "cat".to_owned());
+    assert_eq!(
+        index.merkle_root().to_hex(),
+        "8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3"
+    );
+
+    let mut index = fork.get_patricia_trie::<_, str, String>("puppy");
+    index.put("do", "verb".to_owned());
+    index.put("horse", "stallion".to_owned());
+    index.put("doge", "coin".to_owned());
+    index.put("dog", "puppy".to_owned());
+    assert_eq!(
+        index.merkle_root().to_hex(),
+        "5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84"
+    );
+}
+
+#[test]
+fn root_hash_matches_reference_implementation() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut entries = generate_entries(100);
+    {
+        let mut index = fork.get_patricia_trie(IDX_NAME);
+        for (key, value) in &entries {
+            index.put(key, *value);
+        }
+    }
+
+    let expected_root = reference_root(&entries);
+    let index = fork.get_patricia_trie::<_, Hash, u64>(IDX_NAME);
+    assert_eq!(index.merkle_root(), expected_root);
+    assert_eq!(index.iter().count(), entries.len());
+
+    // The root does not depend on the insertion order.
+    entries.shuffle(&mut thread_rng());
+    let mut other_index = fork.get_patricia_trie("other");
+    for (key, value) in &entries {
+        other_index.put(key, *value);
+    }
+    assert_eq!(other_index.merkle_root(), expected_root);
+}
+
+#[test]
+fn keys_with_common_prefixes() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut index = fork.get_patricia_trie::<_, [u8], u8>(IDX_NAME);
+    let keys: &[&[u8]] = &[
+        &[],
+        &[1],
+        &[1, 2],
+        &[1, 2, 3],
+        &[1, 0x23],
+        &[0x12],
+        &[0x12, 0],
+    ];
+    for (i, &key) in keys.iter().enumerate() {
+        index.put(key, i as u8 + 1);
+    }
+    for (i, &key) in keys.iter().enumerate() {
+        assert_eq!(index.get(key), Some(i as u8 + 1));
+        let proof = index.get_proof(key.to_vec());
+        let checked_proof = proof.check_against_hash(index.object_hash()).unwrap();
+        assert_eq!(checked_proof.value(), Some(&(i as u8 + 1)));
+    }
+    let proof = index.get_proof(vec![1, 2, 3, 4]);
+    let checked_proof = proof.check_against_hash(index.object_hash()).unwrap();
+    assert_eq!(checked_proof.value(), None);
+
+    for &key in keys.iter().rev() {
+        index.remove(key);
+    }
+    assert_eq!(index.merkle_root(), empty_trie_root());
+}
+
+#[test]
+fn updating_and_removing_entries() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut index = fork.get_patricia_trie(IDX_NAME);
+    let entries = generate_entries(50);
+    for (key, value) in &entries[..25] {
+        index.put(key, *value);
+    }
+    let root = index.merkle_root();
+
+    for (key, value) in &entries[25..] {
+        index.put(key, *value);
+    }
+    index.put(&entries[0].0, entries[0].1 + 1);
+    assert_eq!(index.get(&entries[0].0), Some(entries[0].1 + 1));
+    assert_ne!(index.merkle_root(), root);
+
+    index.put(&entries[0].0, entries[0].1);
+    for (key, _) in &entries[25..] {
+        index.remove(key);
+    }
+    // Removing a missing key is a no-op.
+    index.remove(&entries[25].0);
+    assert_eq!(index.merkle_root(), root);
+    assert_eq!(index.merkle_root(), reference_root(&entries[..25]));
+
+    // Stored nodes are the same as in the trie built from scratch.
+    let mut fresh_index = fork.get_patricia_trie("fresh");
+    for (key, value) in &entries[..25] {
+        fresh_index.put(key, *value);
+    }
+    let nodes: Vec<_> = index
+        .base
+        .iter::<_, [u8], Vec<u8>>(&NODE_KEY_PREFIX)
+        .collect();
+    let fresh_nodes: Vec<_> = fresh_index
+        .base
+        .iter::<_, [u8], Vec<u8>>(&NODE_KEY_PREFIX)
+        .collect();
+    assert_eq!(nodes, fresh_nodes);
+
+    for (key, _) in &entries[..25] {
+        index.remove(key);
+    }
+    assert_eq!(index.merkle_root(), empty_trie_root());
+    // No trie nodes should remain.
+    let nodes = index.base.iter::<_, [u8], Vec<u8>>(&NODE_KEY_PREFIX);
+    assert_eq!(nodes.count(), 0);
+
+    index.put(&entries[0].0, entries[0].1);
+    index.clear();
+    assert_eq!(index.merkle_root(), empty_trie_root());
+    assert!(!index.contains(&entries[0].0));
+}
+
+#[test]
+#[should_panic(expected = "empty binary representation")]
+fn empty_values_are_rejected() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut index = fork.get_patricia_trie::<_, Hash, Vec<u8>>(IDX_NAME);
+    index.put(&hash(&[1]), vec![]);
+}
+
+#[test]
+fn proofs_of_existence_and_absence() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut index = fork.get_patricia_trie(IDX_NAME);
+    let entries = generate_entries(60);
+    for (key, value) in &entries[..40] {
+        index.put(key, *value);
+    }
+    let index_hash = index.object_hash();
+
+    for (key, value) in &entries[..40] {
+        let proof = index.get_proof(*key);
+        let checked_proof = proof.check_against_hash(index_hash).unwrap();
+        assert_eq!(checked_proof.key(), key);
+        assert_eq!(checked_proof.value(), Some(value));
+        assert_eq!(checked_proof.merkle_root(), index.merkle_root());
+
+        let trie_value =
+            verify_patricia_trie_proof(index.merkle_root(), key.as_ref(), proof.nodes()).unwrap();
+        assert_eq!(trie_value, Some(value.to_bytes()));
+    }
+    for (key, _) in &entries[40..] {
+        let proof = index.get_proof(*key);
+        assert!(!proof.nodes().is_empty());
+        let checked_proof = proof.check_against_hash(index_hash).unwrap();
+        assert_eq!(checked_proof.value(), None);
+    }
+
+    // Proof for an empty index.
+    let empty_index = fork.get_patricia_trie::<_, Hash, u64>("empty");
+    let proof = empty_index.get_proof(entries[0].0);
+    assert!(proof.nodes().is_empty());
+    proof.check_against_hash(empty_index.object_hash()).unwrap();
+}
+
+#[test]
+fn invalid_proofs() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut index = fork.get_patricia_trie(IDX_NAME);
+    let entries = generate_entries(50);
+    for (key, value) in &entries[..40] {
+        index.put(key, *value);
+    }
+    let (key, value) = entries[0];
+    let proof = index.get_proof(key);
+    let nodes = proof.nodes().to_vec();
+    assert!(nodes.len() > 1);
+
+    let forged_proof = PatriciaTrieProof::new(key, Some(value + 1), nodes.clone());
+    assert!(matches!(
+        forged_proof.check().unwrap_err(),
+        PatriciaTrieProofError::ValueMismatch
+    ));
+    let forged_proof = PatriciaTrieProof::<_, u64>::new(key, None, nodes.clone());
+    assert!(matches!(
+        forged_proof.check().unwrap_err(),
+        PatriciaTrieProofError::ValueMismatch
+    ));
+
+    let truncated_proof = PatriciaTrieProof::new(key, Some(value), nodes[..1].to_vec());
+    assert!(matches!(
+        truncated_proof.check().unwrap_err(),
+        PatriciaTrieProofError::MissingNode(_)
+    ));
+
+    // Take nodes from the path to a key in another subtrie of the root branch.
+    let (other_key, _) = entries[1..40]
+        .iter()
+        .find(|(other_key, _)| other_key.as_ref()[0] >> 4 != key.as_ref()[0] >> 4)
+        .unwrap();
+    let mut redundant_nodes = nodes.clone();
+    redundant_nodes.extend_from_slice(&index.get_proof(*other_key).nodes()[1..]);
+    let redundant_proof = PatriciaTrieProof::new(key, Some(value), redundant_nodes);
+    assert!(matches!(
+        redundant_proof.check().unwrap_err(),
+        PatriciaTrieProofError::RedundantNodes
+    ));
+
+    let mut malformed_nodes = nodes.clone();
+    malformed_nodes[0] = b"garbage".to_vec();
+    let err = verify_patricia_trie_proof(index.merkle_root(), key.as_ref(), &malformed_nodes)
+        .unwrap_err();
+    assert!(matches!(err, PatriciaTrieProofError::MissingNode(_)));
+    let malformed_proof = PatriciaTrieProof::new(key, Some(value), malformed_nodes);
+    assert!(matches!(
+        malformed_proof.check().unwrap_err(),
+        PatriciaTrieProofError::MalformedNode
+    ));
+
+    // A valid proof from another trie does not match the index hash.
+    let (other_key, other_value) = entries[45];
+    index.put(&other_key, other_value);
+    let stale_proof = PatriciaTrieProof::new(key, Some(value), nodes);
+    assert!(matches!(
+        stale_proof
+            .check_against_hash(index.object_hash())
+            .unwrap_err(),
+        ValidationError::UnmatchedRootHash
+    ));
+}
+
+#[test]
+fn proof_serialization() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut index = fork.get_patricia_trie(IDX_NAME);
+    let (h1, h2) = (hash(&[1]), hash(&[2]));
+    index.put(&h1, 100_u32);
+    index.put(&h2, 200_u32);
+
+    let proof = index.get_proof(h1);
+    let json = serde_json::to_value(&proof).unwrap();
+    assert_eq!(json["key"], serde_json::to_value(h1).unwrap());
+    assert_eq!(json["value"], 100);
+    let nodes = json["nodes"].as_array().unwrap();
+    assert_eq!(nodes.len(), proof.nodes().len());
+    assert!(nodes[0].as_str().unwrap().starts_with("0x"));
+
+    let restored_proof: PatriciaTrieProof<Hash, u32> = serde_json::from_value(json).unwrap();
+    assert_eq!(restored_proof, proof);
+    restored_proof
+        .check_against_hash(index.object_hash())
+        .unwrap();
+}
+
+#[test]
+fn index_is_aggregated() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    fork.get_patricia_trie(IDX_NAME).put(&hash(&[1]), 1_u64);
+    let patch = fork.into_patch();
+    let index_hash = patch
+        .get_patricia_trie::<_, Hash, u64>(IDX_NAME)
+        .object_hash();
+    let aggregator = SystemSchema::new(&patch).state_aggregator();
+    assert_eq!(aggregator.get(IDX_NAME), Some(index_hash));
+
+    db.merge(patch).unwrap();
+    let fork = db.fork();
+    fork.get_patricia_trie::<_, Hash, u64>(IDX_NAME)
+        .remove(&hash(&[1]));
+    let patch = fork.into_patch();
+    let aggregator = SystemSchema::new(&patch).state_aggregator();
+    assert_eq!(
+        aggregator.get(IDX_NAME),
+        Some(HashTag::hash_patricia_trie_node(empty_trie_root()))
+    );
+}
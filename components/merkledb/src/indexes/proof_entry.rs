@@ -19,7 +19,7 @@ use std::marker::PhantomData;
 use exonum_crypto::Hash;
 
 use crate::{
-    access::{Access, AccessError, FromAccess},
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
     views::{IndexAddress, IndexState, IndexType, RawAccess, RawAccessMut, View, ViewWithMetadata},
     BinaryValue, ObjectHash,
 };
@@ -52,6 +52,16 @@ where
     }
 }
 
+impl<T, V> DescribeSchema for ProofEntry<T, V>
+where
+    T: RawAccess,
+    V: BinaryValue + ObjectHash,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        vec![IndexDescription::new(name, IndexType::ProofEntry).with_value::<V>()]
+    }
+}
+
 impl<T, V> ProofEntry<T, V>
 where
     T: RawAccess,
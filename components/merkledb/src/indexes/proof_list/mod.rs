@@ -14,7 +14,10 @@
 
 //! An implementation of a Merkelized version of an array list (Merkle tree).
 
-pub use self::proof::{CheckedListProof, ListProof, ListProofError, ValidationError};
+pub use self::{
+    page::{CheckedListProofPage, ListPageError, ListProofCursor, ListProofPage},
+    proof::{CheckedListProof, ListProof, ListProofError, ValidationError},
+};
 
 use exonum_crypto::Hash;
 
@@ -26,7 +29,7 @@ use self::{
     proof_builder::{BuildProof, MerkleTree},
 };
 use crate::{
-    access::{Access, AccessError, FromAccess},
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
     hash::HashTag,
     indexes::iter::{Entries, IndexIterator, Values},
     views::{IndexState, IndexType, RawAccess, RawAccessMut, View, ViewWithMetadata},
@@ -34,6 +37,7 @@ use crate::{
 };
 
 mod key;
+mod page;
 mod proof;
 mod proof_builder;
 #[cfg(test)]
@@ -115,6 +119,16 @@ where
     }
 }
 
+impl<T, V> DescribeSchema for ProofListIndex<T, V>
+where
+    T: RawAccess,
+    V: BinaryValue,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        vec![IndexDescription::new(name, IndexType::ProofList).with_value::<V>()]
+    }
+}
+
 impl<T, V> ProofListIndex<T, V>
 where
     T: RawAccess,
@@ -150,6 +164,31 @@ where
         ProofListKey::new(self.height(), 0)
     }
 
+    /// Recomputes the hash of the list from its values, without relying on the hashes
+    /// of the tree nodes stored in the database. Used to check integrity of the stored data.
+    pub(crate) fn recompute_object_hash(&self) -> Hash {
+        let mut hashes: Vec<_> = self
+            .base
+            .iter::<_, ProofListKey, Vec<u8>>(&0_u8)
+            .map(|(_, value)| HashTag::hash_leaf(&value))
+            .collect();
+        // The number of stored values is used instead of the stored length, so that
+        // the discrepancy between them is detected as well.
+        let len = hashes.len() as u64;
+
+        while hashes.len() > 1 {
+            hashes = hashes
+                .chunks(2)
+                .map(|chunk| match chunk {
+                    [left, right] => HashTag::hash_node(left, right),
+                    [single] => HashTag::hash_single_node(single),
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+        HashTag::hash_list_node(len, hashes.pop().unwrap_or_default())
+    }
+
     /// Returns the element at the indicated position or `None` if the indicated position
     /// is out of bounds.
     ///
@@ -309,6 +348,75 @@ where
         self.create_range_proof(range)
     }
 
+    /// Returns a page with at most `limit` list elements starting from the `start` index.
+    /// If the list contains elements after the page, the page includes a cursor, which
+    /// can be passed to [`get_next_proof_page()`] to obtain the following page.
+    ///
+    /// If `start` exceeds the list length, the page contains a proof of absence
+    /// and no cursor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, ProofListIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_proof_list("name");
+    /// index.extend(vec![1, 2, 3, 4, 5]);
+    ///
+    /// let page = index.get_proof_page(1, 2);
+    /// assert!(page.proof_unchecked().indexes_unchecked().eq(vec![1, 2]));
+    /// assert_eq!(page.next_cursor_unchecked().unwrap().next_index, 3);
+    /// // The last page does not have a cursor.
+    /// let page = index.get_proof_page(3, 10);
+    /// assert!(page.next_cursor_unchecked().is_none());
+    /// ```
+    ///
+    /// [`get_next_proof_page()`]: #method.get_next_proof_page
+    pub fn get_proof_page(&self, start: u64, limit: u64) -> ListProofPage<V> {
+        assert!(limit > 0, "Page limit must be positive");
+        let end = start.saturating_add(limit);
+        let proof = self.create_range_proof(start..end);
+        let len = self.len();
+        let next = if start < end && end < len {
+            Some(ListProofCursor {
+                next_index: end,
+                list_hash: self.object_hash(),
+            })
+        } else {
+            None
+        };
+        ListProofPage::new(proof, next)
+    }
+
+    /// Returns the page following the `cursor` obtained from the previous page, with at most
+    /// `limit` list elements.
+    ///
+    /// Returns `None` if the list has changed since the cursor was created, i.e.,
+    /// the list hash recorded in the cursor differs from the current `object_hash()`.
+    /// To paginate over a changing list, pages should be obtained from the same snapshot
+    /// of the database.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is zero.
+    pub fn get_next_proof_page(
+        &self,
+        cursor: &ListProofCursor,
+        limit: u64,
+    ) -> Option<ListProofPage<V>> {
+        if cursor.list_hash == self.object_hash() {
+            Some(self.get_proof_page(cursor.next_index, limit))
+        } else {
+            None
+        }
+    }
+
     /// Returns an iterator over the list values.
     ///
     /// # Examples
@@ -346,6 +454,25 @@ where
     pub fn iter_from(&self, from: u64) -> Values<'_, V> {
         self.index_iter(Some(&from)).skip_keys()
     }
+
+    /// Returns an iterator over the list values in the reverse order, i.e., starting
+    /// from the last element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, ProofListIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_proof_list("name");
+    /// index.extend(vec![1_u8, 2, 3]);
+    /// assert_eq!(index.iter_rev().collect::<Vec<_>>(), vec![3, 2, 1]);
+    /// ```
+    pub fn iter_rev(&self) -> Values<'_, V> {
+        // Leaf elements are stored with the `0` prefix, cf. `index_iter`.
+        Entries::<u64, V>::rev_with_prefix(&self.base, &0_u8).skip_keys()
+    }
 }
 
 impl<T, V> ProofListIndex<T, V>
@@ -788,7 +915,7 @@ mod proto {
         HashedEntry, ListProof, ProofListKey,
     };
     use crate::{
-        proto::{self, ListProofEntry},
+        proto::{self, proof_from_bytes, proof_to_bytes, ListProofEntry},
         BinaryValue,
     };
 
@@ -877,4 +1004,50 @@ mod proto {
             Ok(Self::from_raw_parts(proof, entries, pb.length()))
         }
     }
+
+    /// Compact binary encoding of the proof based on the `ListProof` Protobuf message.
+    /// Only the canonical encoding (i.e., the one produced by `to_bytes`) is accepted
+    /// by `from_bytes`.
+    impl<V> BinaryValue for ListProof<V>
+    where
+        V: BinaryValue,
+    {
+        fn to_bytes(&self) -> Vec<u8> {
+            proof_to_bytes(self)
+        }
+
+        fn from_bytes(bytes: Cow<'_, [u8]>) -> anyhow::Result<Self> {
+            proof_from_bytes(bytes.as_ref(), None)
+        }
+    }
+
+    impl<V> ListProof<V>
+    where
+        V: BinaryValue,
+    {
+        /// Decodes the proof from the compact binary encoding, rejecting encodings larger
+        /// than `max_size` bytes before parsing. This allows to bound resources spent
+        /// on decoding and verification of proofs received from untrusted sources.
+        ///
+        /// # Examples
+        ///
+        /// ```
+        /// # use exonum_merkledb::{
+        /// #     access::CopyAccessExt, BinaryValue, Database, ListProof, ObjectHash, TemporaryDB,
+        /// # };
+        /// let db = TemporaryDB::new();
+        /// let fork = db.fork();
+        /// let mut list = fork.get_proof_list("list");
+        /// list.extend(vec![1_u8, 2, 3]);
+        /// let bytes = list.get_range_proof(1..).to_bytes();
+        ///
+        /// let proof = ListProof::<u8>::from_bytes_bounded(&bytes, 1_024)?;
+        /// proof.check_against_hash(list.object_hash())?;
+        /// assert!(ListProof::<u8>::from_bytes_bounded(&bytes, 16).is_err());
+        /// # Ok::<_, anyhow::Error>(())
+        /// ```
+        pub fn from_bytes_bounded(bytes: &[u8], max_size: usize) -> anyhow::Result<Self> {
+            proof_from_bytes(bytes, Some(max_size))
+        }
+    }
 }
@@ -0,0 +1,227 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Paginated proofs for `ProofListIndex`.
+
+use exonum_crypto::Hash;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::proof::{CheckedListProof, ListProof, ListProofError};
+use crate::{BinaryValue, ValidationError};
+
+/// Cursor pointing to the continuation of a paginated list proof.
+///
+/// Besides the position of the next page, the cursor fixes the `object_hash()` of the list
+/// the preceding pages were proven against. Thus, all pages obtained by following cursors
+/// are guaranteed to belong to the same state of the list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ListProofCursor {
+    /// Index of the first element on the next page.
+    pub next_index: u64,
+    /// Hash of the list the pages are proven against.
+    pub list_hash: Hash,
+}
+
+/// Page of a `ProofListIndex`, i.e., a contiguous range of list elements coupled
+/// with a proof and an optional cursor pointing to the next page.
+///
+/// Pages are created with [`get_proof_page()`] and [`get_next_proof_page()`] methods
+/// of `ProofListIndex`.
+///
+/// # Workflow
+///
+/// ```
+/// # use exonum_merkledb::{access::CopyAccessExt, Database, TemporaryDB, ObjectHash};
+/// # fn main() -> anyhow::Result<()> {
+/// let fork = { let db = TemporaryDB::new(); db.fork() };
+/// let mut list = fork.get_proof_list("index");
+/// list.extend(0_u32..10);
+/// // The list hash is assumed to be obtained by the client from a trusted source.
+/// let list_hash = list.object_hash();
+///
+/// let page = list.get_proof_page(2, 5);
+/// let checked_page = page.check_against_hash(2, list_hash)?;
+/// assert!(checked_page.indexes().eq(2..7));
+///
+/// let mut cursor = checked_page.next_cursor();
+/// let mut elements = checked_page.entries().len();
+/// while let Some(next) = cursor {
+///     let page = list.get_next_proof_page(&next, 5).unwrap();
+///     let checked_page = page.check_continuation(&next)?;
+///     elements += checked_page.entries().len();
+///     cursor = checked_page.next_cursor();
+/// }
+/// assert_eq!(elements, 8);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # JSON serialization
+///
+/// `ListProofPage` is serialized to JSON as an object with the following fields:
+///
+/// - `proof` is a [`ListProof`] for the elements on the page.
+/// - `next` is the cursor to the next page (`{ next_index: number, list_hash: Hash }`),
+///   or `null` if the page is the last one.
+///
+/// [`get_proof_page()`]: struct.ProofListIndex.html#method.get_proof_page
+/// [`get_next_proof_page()`]: struct.ProofListIndex.html#method.get_next_proof_page
+/// [`ListProof`]: struct.ListProof.html
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListProofPage<V> {
+    proof: ListProof<V>,
+    next: Option<ListProofCursor>,
+}
+
+impl<V: BinaryValue> ListProofPage<V> {
+    pub(super) fn new(proof: ListProof<V>, next: Option<ListProofCursor>) -> Self {
+        Self { proof, next }
+    }
+
+    /// Returns the proof for the elements on the page without verifying it.
+    pub fn proof_unchecked(&self) -> &ListProof<V> {
+        &self.proof
+    }
+
+    /// Returns the cursor to the next page without verifying the page.
+    pub fn next_cursor_unchecked(&self) -> Option<ListProofCursor> {
+        self.next
+    }
+
+    /// Verifies the page starting at the `start` index according to the trusted list hash.
+    /// This method should be used for the first page; the following pages should be verified
+    /// with [`check_continuation()`].
+    ///
+    /// ## Errors
+    ///
+    /// Besides the checks performed by [`ListProof::check()`], the following is verified:
+    ///
+    /// - Elements on the page form a contiguous range starting at `start`.
+    /// - If the page has a continuation cursor, the page is non-empty, and the cursor
+    ///   points to the element immediately following the page and has the same list hash.
+    /// - If the page has no continuation cursor, the page reaches the end of the list.
+    ///
+    /// [`check_continuation()`]: #method.check_continuation
+    /// [`ListProof::check()`]: struct.ListProof.html#method.check
+    pub fn check_against_hash(
+        &self,
+        start: u64,
+        list_hash: Hash,
+    ) -> Result<CheckedListProofPage<'_, V>, ValidationError<ListPageError>> {
+        let checked_proof = self
+            .proof
+            .check()
+            .map_err(|e| ValidationError::Malformed(e.into()))?;
+        if checked_proof.index_hash() != list_hash {
+            return Err(ValidationError::UnmatchedRootHash);
+        }
+
+        let entries_count = checked_proof.entries().len() as u64;
+        let end = start
+            .checked_add(entries_count)
+            .ok_or(ValidationError::Malformed(ListPageError::UnexpectedIndexes))?;
+        if !checked_proof.indexes().eq(start..end) {
+            return Err(ValidationError::Malformed(ListPageError::UnexpectedIndexes));
+        }
+
+        if let Some(cursor) = self.next {
+            let is_consistent = entries_count > 0
+                && cursor.next_index == end
+                && cursor.next_index < checked_proof.list_len()
+                && cursor.list_hash == list_hash;
+            if !is_consistent {
+                return Err(ValidationError::Malformed(ListPageError::InvalidCursor));
+            }
+        } else if end < checked_proof.list_len() {
+            return Err(ValidationError::Malformed(ListPageError::Truncated));
+        }
+
+        Ok(CheckedListProofPage {
+            proof: checked_proof,
+            next: self.next,
+        })
+    }
+
+    /// Verifies the page obtained by following the `cursor` returned by the previous page.
+    ///
+    /// The method is a convenience wrapper around [`check_against_hash()`].
+    ///
+    /// [`check_against_hash()`]: #method.check_against_hash
+    pub fn check_continuation(
+        &self,
+        cursor: &ListProofCursor,
+    ) -> Result<CheckedListProofPage<'_, V>, ValidationError<ListPageError>> {
+        self.check_against_hash(cursor.next_index, cursor.list_hash)
+    }
+}
+
+/// Version of `ListProofPage` obtained after verification.
+///
+/// See [`ListProofPage`] for an example of usage.
+///
+/// [`ListProofPage`]: struct.ListProofPage.html#workflow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckedListProofPage<'a, V> {
+    proof: CheckedListProof<'a, V>,
+    next: Option<ListProofCursor>,
+}
+
+impl<'a, V> CheckedListProofPage<'a, V> {
+    /// Returns indexes and references to elements on the page.
+    pub fn entries(&self) -> &'a [(u64, V)] {
+        self.proof.entries()
+    }
+
+    /// Returns iterator over indexes of the elements on the page.
+    pub fn indexes(&self) -> impl Iterator<Item = u64> + '_ {
+        self.proof.indexes()
+    }
+
+    /// Returns the length of the underlying `ProofListIndex`.
+    pub fn list_len(&self) -> u64 {
+        self.proof.list_len()
+    }
+
+    /// Returns the `object_hash()` of the underlying `ProofListIndex`.
+    pub fn index_hash(&self) -> Hash {
+        self.proof.index_hash()
+    }
+
+    /// Returns the cursor to the next page, or `None` if this page is the last one.
+    pub fn next_cursor(&self) -> Option<ListProofCursor> {
+        self.next
+    }
+}
+
+/// An error that is returned when the list proof page is invalid.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+#[non_exhaustive]
+pub enum ListPageError {
+    /// The proof for the page elements is malformed.
+    #[error("{0}")]
+    Proof(#[from] ListProofError),
+
+    /// Elements on the page do not form a contiguous range starting at the expected index.
+    #[error("elements on the page do not form a contiguous range starting at the expected index")]
+    UnexpectedIndexes,
+
+    /// The continuation cursor is inconsistent with the page.
+    #[error("continuation cursor is inconsistent with the page")]
+    InvalidCursor,
+
+    /// The page does not reach the end of the list, but has no continuation cursor.
+    #[error("page does not reach the end of the list, but has no continuation cursor")]
+    Truncated,
+}
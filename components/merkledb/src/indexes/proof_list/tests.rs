@@ -22,7 +22,10 @@ use serde_json::{self, json};
 
 use std::cmp;
 
-use super::{key::ProofListKey, tree_height_by_length, ListProof, ListProofError, ProofListIndex};
+use super::{
+    key::ProofListKey, tree_height_by_length, ListPageError, ListProof, ListProofCursor,
+    ListProofError, ListProofPage, ProofListIndex, ValidationError,
+};
 use crate::{access::CopyAccessExt, BinaryValue, Database, HashTag, ObjectHash, TemporaryDB};
 
 const IDX_NAME: &str = "idx_name";
@@ -432,6 +435,195 @@ fn proofs_with_overly_large_index_ranges() {
     assert_eq!(checked_proof.entries().len(), 3);
 }
 
+#[test]
+fn paginated_proofs() {
+    const LIST_SIZE: usize = 95;
+    const PAGE_SIZE: u64 = 10;
+
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut index = fork.get_proof_list(IDX_NAME);
+    let values = random_values(&mut thread_rng(), LIST_SIZE);
+    index.extend(values.iter().cloned());
+    let list_hash = index.object_hash();
+
+    let start = 3;
+    let page = index.get_proof_page(start, PAGE_SIZE);
+    let checked_page = page.check_against_hash(start, list_hash).unwrap();
+    assert_eq!(checked_page.list_len(), LIST_SIZE as u64);
+    let mut collected: Vec<_> = checked_page.entries().to_vec();
+    let mut cursor = checked_page.next_cursor();
+    let mut page_count = 1;
+
+    while let Some(next) = cursor {
+        assert_eq!(next.list_hash, list_hash);
+        let page = index.get_next_proof_page(&next, PAGE_SIZE).unwrap();
+        let checked_page = page.check_continuation(&next).unwrap();
+        assert_eq!(checked_page.index_hash(), list_hash);
+        collected.extend_from_slice(checked_page.entries());
+        cursor = checked_page.next_cursor();
+        page_count += 1;
+    }
+
+    assert_eq!(page_count, 10);
+    let expected: Vec<_> = values
+        .into_iter()
+        .enumerate()
+        .skip(start as usize)
+        .map(|(i, value)| (i as u64, value))
+        .collect();
+    assert_eq!(collected, expected);
+
+    // Pages beyond the list end contain proofs of absence.
+    let page = index.get_proof_page(LIST_SIZE as u64, PAGE_SIZE);
+    let checked_page = page
+        .check_against_hash(LIST_SIZE as u64, list_hash)
+        .unwrap();
+    assert!(checked_page.entries().is_empty());
+    assert!(checked_page.next_cursor().is_none());
+
+    // Cursors are invalidated once the list changes.
+    let cursor = index
+        .get_proof_page(0, PAGE_SIZE)
+        .next_cursor_unchecked()
+        .unwrap();
+    index.push(vec![1, 2, 3]);
+    assert!(index.get_next_proof_page(&cursor, PAGE_SIZE).is_none());
+}
+
+#[test]
+fn paginated_proofs_in_empty_list() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let index = fork.get_proof_list::<_, u64>(IDX_NAME);
+    let page = index.get_proof_page(0, 10);
+    let checked_page = page.check_against_hash(0, index.object_hash()).unwrap();
+    assert!(checked_page.entries().is_empty());
+    assert!(checked_page.next_cursor().is_none());
+}
+
+#[test]
+#[should_panic(expected = "Page limit must be positive")]
+fn paginated_proof_with_zero_limit() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let index = fork.get_proof_list::<_, u64>(IDX_NAME);
+    index.get_proof_page(0, 0);
+}
+
+#[test]
+fn invalid_proof_pages() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut index = fork.get_proof_list(IDX_NAME);
+    index.extend(0_u64..20);
+    let list_hash = index.object_hash();
+    let cursor = ListProofCursor {
+        next_index: 5,
+        list_hash,
+    };
+
+    // The page does not start at the expected index.
+    let page = index.get_proof_page(0, 5);
+    let err = page.check_against_hash(1, list_hash).unwrap_err();
+    assert!(matches!(
+        err,
+        ValidationError::Malformed(ListPageError::UnexpectedIndexes)
+    ));
+    let err = page.check_against_hash(u64::MAX, list_hash).unwrap_err();
+    assert!(matches!(
+        err,
+        ValidationError::Malformed(ListPageError::UnexpectedIndexes)
+    ));
+
+    // The page is proven against another list hash.
+    let err = page.check_against_hash(0, Hash::zero()).unwrap_err();
+    assert!(matches!(err, ValidationError::UnmatchedRootHash));
+
+    // The page omits elements in the middle of the range.
+    let proof = index.get_range_proof(0..5);
+    let mut entries = proof.entries_unchecked().to_vec();
+    entries.remove(2);
+    let proof = ListProof::from_raw_parts(proof.proof_unchecked().to_vec(), entries, 20);
+    let page = ListProofPage::new(proof, Some(cursor));
+    let err = page.check_against_hash(0, list_hash).unwrap_err();
+    assert!(matches!(
+        err,
+        ValidationError::Malformed(ListPageError::Proof(_))
+    ));
+
+    // The page ends before the end of the list, but has no cursor.
+    let page = ListProofPage::new(index.get_range_proof(0..5), None);
+    let err = page.check_against_hash(0, list_hash).unwrap_err();
+    assert!(matches!(
+        err,
+        ValidationError::Malformed(ListPageError::Truncated)
+    ));
+
+    // The cursor skips elements.
+    let skipping_cursor = ListProofCursor {
+        next_index: 7,
+        ..cursor
+    };
+    let page = ListProofPage::new(index.get_range_proof(0..5), Some(skipping_cursor));
+    let err = page.check_against_hash(0, list_hash).unwrap_err();
+    assert!(matches!(
+        err,
+        ValidationError::Malformed(ListPageError::InvalidCursor)
+    ));
+
+    // The cursor refers to another list state.
+    let foreign_cursor = ListProofCursor {
+        list_hash: Hash::zero(),
+        ..cursor
+    };
+    let page = ListProofPage::new(index.get_range_proof(0..5), Some(foreign_cursor));
+    let err = page.check_against_hash(0, list_hash).unwrap_err();
+    assert!(matches!(
+        err,
+        ValidationError::Malformed(ListPageError::InvalidCursor)
+    ));
+
+    // The cursor points beyond the list end.
+    let page = ListProofPage::new(
+        index.get_range_proof(15..),
+        Some(ListProofCursor {
+            next_index: 20,
+            list_hash,
+        }),
+    );
+    let err = page.check_against_hash(15, list_hash).unwrap_err();
+    assert!(matches!(
+        err,
+        ValidationError::Malformed(ListPageError::InvalidCursor)
+    ));
+}
+
+#[test]
+fn proof_page_json_serialization() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut index = fork.get_proof_list(IDX_NAME);
+    index.extend(vec![1_u32, 2, 3]);
+
+    let page = index.get_proof_page(1, 1);
+    let json = serde_json::to_value(&page).unwrap();
+    assert_eq!(
+        json["proof"],
+        serde_json::to_value(&index.get_range_proof(1..2)).unwrap()
+    );
+    assert_eq!(
+        json["next"],
+        json!({ "next_index": 2, "list_hash": index.object_hash() })
+    );
+    let page_copy: ListProofPage<u32> = serde_json::from_value(json).unwrap();
+    assert_eq!(page_copy, page);
+
+    let page = index.get_proof_page(1, 5);
+    let json = serde_json::to_value(&page).unwrap();
+    assert_eq!(json["next"], serde_json::Value::Null);
+}
+
 #[test]
 fn index_and_proof_roots() {
     let db = TemporaryDB::new();
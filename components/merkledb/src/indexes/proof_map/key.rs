@@ -199,6 +199,28 @@ impl ProofPath {
         Self::from_inner(inner)
     }
 
+    /// Constructs the `ProofPath` consisting of the specified prefix bytes. If the prefix
+    /// has `KEY_SIZE` bytes, the returned path is a leaf one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix is longer than `KEY_SIZE`.
+    pub(crate) fn from_prefix(prefix: &[u8]) -> Self {
+        assert!(
+            prefix.len() <= KEY_SIZE,
+            "Prefix is longer than the key size ({} bytes)",
+            KEY_SIZE
+        );
+        let mut bytes = [0; KEY_SIZE];
+        bytes[..prefix.len()].copy_from_slice(prefix);
+        let path = Self::from_bytes(bytes);
+        if prefix.len() == KEY_SIZE {
+            path
+        } else {
+            path.prefix(prefix.len() as u16 * 8)
+        }
+    }
+
     /// Constructs the `ProofPath` from the inner buffer.
     fn from_inner(bytes: [u8; PROOF_PATH_SIZE]) -> Self {
         debug_assert!(
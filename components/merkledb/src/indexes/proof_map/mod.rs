@@ -22,15 +22,16 @@ pub use self::{
 
 use exonum_crypto::Hash;
 
-use std::{fmt, io, marker::PhantomData};
+use std::{borrow::Borrow, cmp::Ordering, fmt, io, marker::PhantomData};
 
 use self::{
-    key::{ChildKind, VALUE_KEY_PREFIX},
+    key::{ChildKind, LEAF_KEY_PREFIX, VALUE_KEY_PREFIX},
     node::{BranchNode, Node},
-    proof_builder::{BuildProof, MerklePatriciaTree},
+    proof::collect_leaves,
+    proof_builder::{collect_prefix_proof, BuildProof, MerklePatriciaTree},
 };
 use crate::{
-    access::{Access, AccessError, FromAccess},
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
     indexes::iter::{Entries, IndexIterator, Keys, Values},
     views::{
         BinaryAttribute, IndexAddress, IndexState, IndexType, RawAccess, RawAccessMut, View,
@@ -65,6 +66,19 @@ where
     fn value(&self, key: &K) -> V {
         self.get_value_unchecked(key)
     }
+
+    fn values(&self, keys: &[&K]) -> Vec<V> {
+        let paths: Vec<_> = keys.iter().map(|&key| key.to_value_path()).collect();
+        let paths: Vec<_> = paths.iter().map(Vec::as_slice).collect();
+        self.base
+            .multi_get_bytes(&paths)
+            .into_iter()
+            .map(|bytes| {
+                let bytes = bytes.expect("Value for the given key is absent");
+                V::from_bytes(bytes.into()).expect("Error while deserializing value")
+            })
+            .collect()
+    }
 }
 
 /// A Merkelized version of a map that provides proofs of existence or non-existence for the map
@@ -145,6 +159,20 @@ where
     }
 }
 
+impl<T, K, V, KeyMode> DescribeSchema for ProofMapIndex<T, K, V, KeyMode>
+where
+    T: RawAccess,
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+    KeyMode: ToProofPath<K>,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        vec![IndexDescription::new(name, IndexType::ProofMap)
+            .with_key::<K>()
+            .with_value::<V>()]
+    }
+}
+
 /// Raw variant of the `ProofMapIndex`, useful for keys that mapped directly to
 /// `ProofPath` without hashing. For example `Hash` and `PublicKey`.
 ///
@@ -202,6 +230,31 @@ where
         }
     }
 
+    /// Recomputes the hash of the map from its values, without relying on the hashes
+    /// of the branch nodes stored in the database. Used to check integrity of the stored data.
+    ///
+    /// Returns `None` if the stored leaves of the tree do not correspond to the map values.
+    pub(crate) fn recompute_object_hash(&self) -> Option<Hash> {
+        let mut leaves: Vec<(ProofPath, Hash)> = self.base.iter(&LEAF_KEY_PREFIX).collect();
+
+        // The mapping of keys to leaf paths depends on the key mode, so the leaf hashes
+        // are compared with the value hashes up to a permutation.
+        let mut leaf_hashes: Vec<_> = leaves.iter().map(|(_, hash)| *hash).collect();
+        let mut value_hashes: Vec<_> = self
+            .base
+            .iter::<_, Vec<u8>, Vec<u8>>(&VALUE_KEY_PREFIX)
+            .map(|(_, value)| HashTag::hash_leaf(&value))
+            .collect();
+        leaf_hashes.sort_unstable();
+        value_hashes.sort_unstable();
+        if leaf_hashes != value_hashes {
+            return None;
+        }
+
+        leaves.sort_unstable_by(|(x, _), (y, _)| x.partial_cmp(y).unwrap_or(Ordering::Equal));
+        collect_leaves(&leaves).ok().map(HashTag::hash_map_node)
+    }
+
     /// Returns a value corresponding to the key.
     ///
     /// # Examples
@@ -284,6 +337,73 @@ where
         self.create_multiproof(keys)
     }
 
+    /// Returns the proof that contains *all* entries of the map with keys starting with
+    /// the specified `prefix`, so that the client can verify that no entries were omitted.
+    /// An empty prefix corresponds to the entire map.
+    ///
+    /// The prefix is matched against the `ProofPath` of a key, i.e., the key itself
+    /// for maps with the [`Raw`] key mode, and the key hash for maps with the [`Hashed`]
+    /// key mode. Thus, for hashed maps the prefix can be used to split the map
+    /// into verifiable shards. Note that only prefixes consisting of whole bytes
+    /// are supported; other key ranges do not correspond to contiguous parts of the tree.
+    ///
+    /// The proof should be verified with [`MapProof::check_prefix()`]. Building the proof
+    /// requires iterating over the keys of the map if the prefix is not empty, so it may be
+    /// slow for large maps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix is longer than 32 bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, ObjectHash};
+    /// use exonum_crypto::Hash;
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_raw_proof_map("name");
+    /// index.put(&Hash::new([1; 32]), 1_u8);
+    /// index.put(&Hash::new([2; 32]), 2_u8);
+    ///
+    /// let proof = index.get_prefix_proof(&[1]);
+    /// let checked_proof = proof.check_prefix(&[1]).unwrap();
+    /// assert_eq!(checked_proof.entries().count(), 1);
+    /// assert_eq!(checked_proof.index_hash(), index.object_hash());
+    /// ```
+    ///
+    /// [`Raw`]: proof_map/struct.Raw.html
+    /// [`Hashed`]: proof_map/struct.Hashed.html
+    /// [`MapProof::check_prefix()`]: struct.MapProof.html#method.check_prefix
+    pub fn get_prefix_proof(&self, prefix: &[u8]) -> MapProof<K::Owned, V, KeyMode> {
+        let prefix = ProofPath::from_prefix(prefix);
+        let (proof_entries, leaf_count) = collect_prefix_proof(self, &prefix);
+
+        let mut entries: Vec<_> = if leaf_count == 0 {
+            vec![]
+        } else {
+            self.iter()
+                .map(|(key, value)| {
+                    let path = KeyMode::transform_key(key.borrow());
+                    (path, key, value)
+                })
+                .filter(|(path, ..)| path.starts_with(&prefix))
+                .take(leaf_count)
+                .collect()
+        };
+        debug_assert_eq!(entries.len(), leaf_count);
+        // `unwrap` is safe here because all paths start from the same position `0`.
+        entries.sort_unstable_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
+
+        entries
+            .into_iter()
+            .fold(MapProof::new(), |proof, (_, key, value)| {
+                proof.add_entry(key, value)
+            })
+            .add_proof_entries(proof_entries)
+    }
+
     /// Returns an iterator over the entries of the map in ascending order.
     ///
     /// # Examples
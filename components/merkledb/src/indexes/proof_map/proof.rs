@@ -125,6 +125,14 @@ pub enum MapProofError {
     /// Entries in the proof are not ordered by increasing path.
     #[error("invalid path ordering")]
     InvalidOrdering(ProofPath, ProofPath),
+
+    /// An entry in the proof does not start with the requested prefix.
+    #[error("entry outside of the requested prefix")]
+    OutsidePrefix(ProofPath),
+
+    /// A hashed node in the proof may hide entries starting with the requested prefix.
+    #[error("proof does not contain all entries with the requested prefix")]
+    IncompletePrefix(ProofPath),
 }
 
 // Used instead of `(ProofPath, Hash)` only for the purpose of clearer (de)serialization.
@@ -374,6 +382,16 @@ fn collect(entries: &[Cow<'_, MapProofEntry>]) -> Result<Hash, MapProofError> {
     }
 }
 
+/// Computes the root hash of the Merkle Patricia tree with the specified leaves.
+/// `leaves` are assumed to be sorted by the path in increasing order.
+pub(super) fn collect_leaves(leaves: &[(ProofPath, Hash)]) -> Result<Hash, MapProofError> {
+    let entries: Vec<_> = leaves
+        .iter()
+        .map(|&(path, hash)| Cow::Owned(MapProofEntry { path, hash }))
+        .collect();
+    collect(&entries)
+}
+
 impl<K, V, KeyMode> MapProof<K, V, KeyMode> {
     /// Provides access to the proof part of the view. Useful mainly for debug purposes.
     pub fn proof_unchecked(&self) -> Vec<(ProofPath, Hash)> {
@@ -577,6 +595,66 @@ where
         })
     }
 
+    /// Checks this proof and verifies that it contains *all* entries of the map with keys
+    /// starting with the specified `prefix`. As with [`get_prefix_proof()`], the prefix
+    /// is matched against the `ProofPath` of a key, i.e., the key itself for maps with
+    /// the [`Raw`] key mode, and the key hash for maps with the [`Hashed`] key mode.
+    ///
+    /// Unlike [`check()`], which only proves that the returned entries belong to the map,
+    /// this method guarantees that the map does not contain any other entries
+    /// with the prefix. An empty prefix corresponds to the entire map.
+    ///
+    /// ## Errors
+    ///
+    /// Apart from the errors returned by [`check()`], an error is returned if an entry
+    /// in the proof does not start with the prefix, or if a hashed node in the proof
+    /// intersects with the prefix (i.e., may hide some entries with the prefix).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix is longer than 32 bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum_merkledb::{access::CopyAccessExt, Database, TemporaryDB, ObjectHash};
+    /// # use exonum_crypto::Hash;
+    /// let fork = { let db = TemporaryDB::new(); db.fork() };
+    /// let mut map = fork.get_raw_proof_map("index");
+    /// let (h1, h2, h3) = (Hash::new([1; 32]), Hash::new([2; 32]), Hash::new([0x12; 32]));
+    /// map.put(&h1, 100u32);
+    /// map.put(&h2, 200u32);
+    /// map.put(&h3, 300u32);
+    ///
+    /// let proof = map.get_prefix_proof(&[2]);
+    /// let checked_proof = proof.check_prefix(&[2]).unwrap();
+    /// assert_eq!(checked_proof.entries().collect::<Vec<_>>(), vec![(&h2, &200u32)]);
+    /// assert_eq!(checked_proof.index_hash(), map.object_hash());
+    ///
+    /// // The proof does not show that there are no other entries with a shorter prefix.
+    /// assert!(proof.check_prefix(&[]).is_err());
+    /// ```
+    ///
+    /// [`get_prefix_proof()`]: struct.ProofMapIndex.html#method.get_prefix_proof
+    /// [`check()`]: #method.check
+    /// [`Raw`]: proof_map/struct.Raw.html
+    /// [`Hashed`]: proof_map/struct.Hashed.html
+    pub fn check_prefix(&self, prefix: &[u8]) -> Result<CheckedMapProof<'_, K, V>, MapProofError> {
+        let prefix = ProofPath::from_prefix(prefix);
+        for entry in &self.entries {
+            let path = KeyMode::transform_key(entry.key());
+            if !path.starts_with(&prefix) {
+                return Err(MapProofError::OutsidePrefix(path));
+            }
+        }
+        for entry in &self.proof {
+            if entry.path.starts_with(&prefix) || prefix.starts_with(&entry.path) {
+                return Err(MapProofError::IncompletePrefix(entry.path));
+            }
+        }
+        self.check()
+    }
+
     /// Checks this proof against a trusted map hash. Fails if the proof is malformed or the
     /// hash does not match the one computed from the proof.
     pub fn check_against_hash(
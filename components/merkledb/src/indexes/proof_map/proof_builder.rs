@@ -31,17 +31,15 @@ const DEFAULT_PROOF_CAPACITY: usize = 8;
 
 impl<K, V, KeyMode> MapProof<K, V, KeyMode> {
     /// Includes a proof of existence / absence of a single key when a proof of multiple
-    /// keys is requested.
+    /// keys is requested. Returns `true` if the key is present in the tree; the entry
+    /// for the key itself is not added to the proof, so that the values of present keys
+    /// can be retrieved in a single batch.
     fn process_key<Q: ?Sized>(
         mut self,
         tree: &impl MerklePatriciaTree<Q, V>,
         contour: &mut Vec<ContourNode>,
         proof_path: &ProofPath,
-        key: K,
-    ) -> Self
-    where
-        K: Borrow<Q>,
-    {
+    ) -> (Self, bool) {
         // `unwrap()` is safe: there is at least 1 element in the contour by design
         let common_prefix = proof_path.common_prefix_len(&contour.last().unwrap().key);
 
@@ -76,7 +74,7 @@ impl<K, V, KeyMode> MapProof<K, V, KeyMode> {
                 }
             } else {
                 // Both children of `branch` do not fit; stop here
-                break self.add_missing(key);
+                break (self, false);
             }
 
             let node = tree.node(&node_path);
@@ -86,8 +84,7 @@ impl<K, V, KeyMode> MapProof<K, V, KeyMode> {
                 }
                 Node::Leaf(_) => {
                     // We have reached the leaf node and haven't diverged!
-                    let value = tree.value(key.borrow());
-                    break self.add_entry(key, value);
+                    break (self, true);
                 }
             }
         }
@@ -111,6 +108,83 @@ pub trait MerklePatriciaTree<K: ?Sized, V> {
     /// It is assumed that this method cannot fail since it is queried with `key`s
     /// that are guaranteed to be present in the tree.
     fn value(&self, key: &K) -> V;
+
+    /// Looks up values by their full keys, returning them in the order of `keys`.
+    ///
+    /// The default implementation calls [`value`](#tymethod.value) for each key.
+    /// Implementations backed by the database should retrieve values in a single batch.
+    fn values(&self, keys: &[&K]) -> Vec<V> {
+        keys.iter().map(|&key| self.value(key)).collect()
+    }
+}
+
+/// Collects the nodes of a Merkle Patricia tree necessary to prove that a set of leaves
+/// constitutes *all* leaves with paths starting with the specified `prefix`.
+///
+/// Returns the hashed nodes, which do not intersect with the prefix, ordered by increasing
+/// `ProofPath`, and the number of leaves with the prefix. Leaves themselves are not returned
+/// since the tree does not allow to restore keys from leaf paths.
+pub(crate) fn collect_prefix_proof<K: ?Sized, V>(
+    tree: &impl MerklePatriciaTree<K, V>,
+    prefix: &ProofPath,
+) -> (Vec<(ProofPath, Hash)>, usize) {
+    fn count_leaves<K: ?Sized, V>(tree: &impl MerklePatriciaTree<K, V>, path: &ProofPath) -> usize {
+        match tree.node(path) {
+            Node::Leaf(_) => 1,
+            Node::Branch(branch) => {
+                count_leaves(tree, &branch.child_path(ChildKind::Left))
+                    + count_leaves(tree, &branch.child_path(ChildKind::Right))
+            }
+        }
+    }
+
+    fn visit_children<K: ?Sized, V>(
+        tree: &impl MerklePatriciaTree<K, V>,
+        branch: &BranchNode,
+        prefix: &ProofPath,
+        proof: &mut Vec<(ProofPath, Hash)>,
+        leaf_count: &mut usize,
+    ) {
+        for &kind in &[ChildKind::Left, ChildKind::Right] {
+            let path = branch.child_path(kind);
+            if path.starts_with(prefix) {
+                // The entire subtree lies within the prefix.
+                *leaf_count += count_leaves(tree, &path);
+            } else if prefix.starts_with(&path) {
+                // The subtree contains the prefix; it can only be a branch since
+                // leaf paths have the maximum possible length.
+                if let Node::Branch(child) = tree.node(&path) {
+                    visit_children(tree, &child, prefix, proof, leaf_count);
+                }
+            } else {
+                // The subtree does not intersect with the prefix.
+                proof.push((path, branch.child_hash(kind)));
+            }
+        }
+    }
+
+    let mut proof = vec![];
+    let mut leaf_count = 0;
+    match tree.root_node() {
+        Some((root_path, Node::Branch(root_branch))) => {
+            if root_path.starts_with(prefix) {
+                leaf_count = count_leaves(tree, &root_path);
+            } else {
+                // Children of the root are visited even if the root does not intersect
+                // with the prefix, since a proof cannot consist of a single branch node.
+                visit_children(tree, &root_branch, prefix, &mut proof, &mut leaf_count);
+            }
+        }
+        Some((root_path, Node::Leaf(hash))) => {
+            if root_path.starts_with(prefix) {
+                leaf_count = 1;
+            } else {
+                proof.push((root_path, hash));
+            }
+        }
+        None => {}
+    }
+    (proof, leaf_count)
 }
 
 /// Combines two lists of hashes produces when building a `MapProof`.
@@ -291,13 +365,18 @@ where
                 let mut contour = Vec::with_capacity(DEFAULT_PROOF_CAPACITY);
                 contour.push(ContourNode::new(root_path, root_branch));
 
+                // Keys together with the flag whether the key is present in the tree.
+                let mut processed_keys = Vec::with_capacity(searched_paths.len());
                 let mut last_searched_path: Option<ProofPath> = None;
                 for (proof_path, key) in searched_paths {
                     if last_searched_path == Some(proof_path) {
                         // The key has already been looked up; skipping.
                         continue;
                     }
-                    proof = proof.process_key(self, &mut contour, &proof_path, key);
+                    let (new_proof, is_present) =
+                        proof.process_key(self, &mut contour, &proof_path);
+                    proof = new_proof;
+                    processed_keys.push((key, is_present));
                     last_searched_path = Some(proof_path);
                 }
 
@@ -305,6 +384,21 @@ where
                 while let Some(node) = contour.pop() {
                     proof = node.add_to_proof(proof);
                 }
+
+                let present_keys: Vec<&K> = processed_keys
+                    .iter()
+                    .filter(|(_, is_present)| *is_present)
+                    .map(|(key, _)| key.borrow())
+                    .collect();
+                let mut values = self.values(&present_keys).into_iter();
+                for (key, is_present) in processed_keys {
+                    proof = if is_present {
+                        // `unwrap()` is safe: there is a value for each present key.
+                        proof.add_entry(key, values.next().unwrap())
+                    } else {
+                        proof.add_missing(key)
+                    };
+                }
                 proof
             }
             Some((root_path, Node::Leaf(merkle_root))) => {
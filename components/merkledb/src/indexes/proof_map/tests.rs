@@ -1746,3 +1746,99 @@ fn unsized_key() {
 
     assert!(proof.check().is_ok());
 }
+
+fn check_prefix_proofs<KeyMode>(fork: &Fork, data: &[([u8; KEY_SIZE], Vec<u8>)])
+where
+    KeyMode: ToProofPath<[u8; KEY_SIZE]>,
+{
+    let mut table = fork.get_generic_proof_map::<_, [u8; KEY_SIZE], Vec<u8>, KeyMode>(IDX_NAME);
+    table.clear();
+    for (key, value) in data {
+        table.put(key, value.clone());
+    }
+    let table_hash = table.object_hash();
+
+    let mut prefixes: Vec<Vec<u8>> = vec![vec![], vec![0], vec![0xff]];
+    for (key, _) in data.iter().take(5) {
+        let path = KeyMode::transform_key(key);
+        let raw_key = path.raw_key();
+        prefixes.extend((1..=3).map(|len| raw_key[..len].to_vec()));
+        prefixes.push(raw_key.to_vec());
+    }
+
+    for prefix in &prefixes {
+        let proof = table.get_prefix_proof(prefix);
+        let checked_proof = proof.check_prefix(prefix).unwrap();
+        assert_eq!(checked_proof.index_hash(), table_hash);
+
+        let mut expected: Vec<_> = data
+            .iter()
+            .filter(|(key, _)| KeyMode::transform_key(key).raw_key().starts_with(prefix))
+            .collect();
+        expected.sort_unstable_by_key(|(key, _)| *key);
+        let mut actual: Vec<_> = checked_proof.entries().collect();
+        actual.sort_unstable_by_key(|(key, _)| **key);
+        assert_eq!(actual.len(), expected.len());
+        for ((key, value), (expected_key, expected_value)) in actual.into_iter().zip(expected) {
+            assert_eq!(key, expected_key);
+            assert_eq!(value, expected_value);
+        }
+    }
+}
+
+#[test]
+fn test_prefix_proofs() {
+    let db = TemporaryDB::default();
+    let fork = db.fork();
+
+    for &len in &[0, 1, 2, 10, 100] {
+        let data = generate_random_data(len);
+        check_prefix_proofs::<Raw>(&fork, &data);
+        check_prefix_proofs::<Hashed>(&fork, &data);
+    }
+}
+
+#[test]
+fn test_invalid_prefix_proofs() {
+    use self::MapProofError::{IncompletePrefix, OutsidePrefix};
+
+    let db = TemporaryDB::default();
+    let fork = db.fork();
+    let mut table = fork.get_raw_proof_map::<_, [u8; 32], Vec<u8>>(IDX_NAME);
+    for i in 0..8 {
+        table.put(&[i; 32], vec![i]);
+    }
+
+    // A proof for separate keys is not a proof of completeness.
+    let proof = table.get_multiproof(vec![[1; 32], [3; 32]]);
+    assert!(proof.check().is_ok());
+    match proof.check_prefix(&[1]).unwrap_err() {
+        OutsidePrefix(..) => {}
+        e => panic!("expected outside prefix error, got {}", e),
+    }
+    let proof = table.get_multiproof(vec![[1; 32]]);
+    match proof.check_prefix(&[]).unwrap_err() {
+        IncompletePrefix(..) => {}
+        e => panic!("expected incomplete prefix error, got {}", e),
+    }
+
+    // The proof cannot be used for a shorter prefix...
+    let proof = table.get_prefix_proof(&[1, 1]);
+    assert!(proof.check_prefix(&[1, 1]).is_ok());
+    match proof.check_prefix(&[]).unwrap_err() {
+        IncompletePrefix(..) => {}
+        e => panic!("expected incomplete prefix error, got {}", e),
+    }
+    // ...but it can be used for a longer one if the entries fit.
+    assert!(proof.check_prefix(&[1, 1, 1]).is_ok());
+
+    // Omitting an entry is detected.
+    let proof = table.get_prefix_proof(&[]);
+    assert_eq!(proof.all_entries_unchecked().count(), 8);
+    assert!(proof.check_prefix(&[]).is_ok());
+    let mut json = serde_json::to_value(&proof).unwrap();
+    json["entries"].as_array_mut().unwrap().pop();
+    let proof: MapProof<[u8; 32], Vec<u8>, Raw> = serde_json::from_value(json).unwrap();
+    let checked_proof = proof.check_prefix(&[]).unwrap();
+    assert_ne!(checked_proof.index_hash(), table.object_hash());
+}
@@ -0,0 +1,625 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of a collection of values ordered by a score.
+//!
+//! The given section contains methods related to `SortedValueIndex` and iterators
+//! over the items of this index.
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use std::{
+    io::Error,
+    marker::PhantomData,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::{
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
+    indexes::iter::{Entries, IndexIterator, Values},
+    views::{
+        BinaryAttribute, IndexAddress, IndexState, IndexType, RawAccess, RawAccessMut, View,
+        ViewWithMetadata,
+    },
+    BinaryKey, BinaryValue,
+};
+
+#[derive(Debug, Default, Clone, Copy)]
+struct SortedValueSize {
+    /// Sequence number to assign to the next inserted element.
+    next_seq: u64,
+    /// Amount of elements in the index.
+    length: u64,
+}
+
+impl BinaryAttribute for SortedValueSize {
+    fn size(&self) -> usize {
+        16
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) {
+        buffer.write_u64::<LittleEndian>(self.next_seq).unwrap();
+        buffer.write_u64::<LittleEndian>(self.length).unwrap();
+    }
+
+    fn read(mut buffer: &[u8]) -> Result<Self, Error> {
+        Ok(Self {
+            next_seq: buffer.read_u64::<LittleEndian>()?,
+            length: buffer.read_u64::<LittleEndian>()?,
+        })
+    }
+}
+
+/// Key of an element in a [`SortedValueIndex`].
+///
+/// The key consists of the score of the element and the sequence number assigned
+/// to the element on insertion. Elements with equal scores are ordered by their sequence numbers,
+/// that is, in the order of insertion.
+///
+/// [`SortedValueIndex`]: struct.SortedValueIndex.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScoredKey<S> {
+    /// Score of the element.
+    pub score: S,
+    /// Sequence number of the element, which is unique within the index.
+    pub seq: u64,
+}
+
+impl<S> ScoredKey<S> {
+    /// Creates a new key.
+    pub fn new(score: S, seq: u64) -> Self {
+        Self { score, seq }
+    }
+}
+
+/// Serializes the score followed by the big-endian sequence number.
+impl<S: BinaryKey + Clone> BinaryKey for ScoredKey<S> {
+    fn size(&self) -> usize {
+        self.score.size() + self.seq.size()
+    }
+
+    fn write(&self, buffer: &mut [u8]) -> usize {
+        let score_len = self.score.write(buffer);
+        score_len + self.seq.write(&mut buffer[score_len..])
+    }
+
+    fn read(buffer: &[u8]) -> Self {
+        let (score, seq) = buffer.split_at(buffer.len() - 8);
+        Self {
+            score: S::read(score),
+            seq: u64::read(seq),
+        }
+    }
+}
+
+/// A collection of values ordered by a user-supplied score.
+///
+/// `SortedValueIndex` can be used as a priority queue (for example, for scheduling tasks
+/// by their deadline) or as a sorted multiset (for example, for orders in an order book
+/// sorted by price). Each inserted element is identified by a [`ScoredKey`], which consists
+/// of the element score and the sequence number assigned to the element by the index.
+/// Elements with equal scores are ordered in the insertion order.
+///
+/// The order of scores is defined by their [`BinaryKey`] serialization, which is compared
+/// lexicographically. For built-in integer types, this order coincides with the numeric one.
+/// Scores should have fixed-length serialization; otherwise, elements whose score serialization
+/// is a prefix of the serialization of another score may be ordered incorrectly.
+///
+/// [`ScoredKey`]: struct.ScoredKey.html
+/// [`BinaryKey`]: ../trait.BinaryKey.html
+#[derive(Debug)]
+pub struct SortedValueIndex<T: RawAccess, S, V> {
+    base: View<T>,
+    state: IndexState<T, SortedValueSize>,
+    _s: PhantomData<S>,
+    _v: PhantomData<V>,
+}
+
+impl<T, S, V> FromAccess<T> for SortedValueIndex<T::Base, S, V>
+where
+    T: Access,
+    S: BinaryKey + Clone,
+    V: BinaryValue,
+{
+    fn from_access(access: T, addr: IndexAddress) -> Result<Self, AccessError> {
+        let view = access.get_or_create_view(addr, IndexType::SortedValue)?;
+        Ok(Self::new(view))
+    }
+}
+
+impl<T, S, V> DescribeSchema for SortedValueIndex<T, S, V>
+where
+    T: RawAccess,
+    S: BinaryKey + Clone,
+    V: BinaryValue,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        vec![IndexDescription::new(name, IndexType::SortedValue)
+            .with_key::<ScoredKey<S>>()
+            .with_value::<V>()]
+    }
+}
+
+impl<T, S, V> SortedValueIndex<T, S, V>
+where
+    T: RawAccess,
+    S: BinaryKey + Clone,
+    V: BinaryValue,
+{
+    fn new(view: ViewWithMetadata<T>) -> Self {
+        let (base, state) = view.into_parts();
+        Self {
+            base,
+            state,
+            _s: PhantomData,
+            _v: PhantomData,
+        }
+    }
+
+    fn size(&self) -> SortedValueSize {
+        self.state.get().unwrap_or_default()
+    }
+
+    /// Returns a value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, SortedValueIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_sorted_value::<_, u64, String>("name");
+    /// let key = index.insert(10, "foo".to_owned());
+    /// assert_eq!(index.get(&key), Some("foo".to_owned()));
+    /// ```
+    pub fn get(&self, key: &ScoredKey<S>) -> Option<V> {
+        self.base.get(key)
+    }
+
+    /// Returns `true` if the index contains a value corresponding to the key.
+    pub fn contains(&self, key: &ScoredKey<S>) -> bool {
+        self.base.contains(key)
+    }
+
+    /// Returns the number of elements in the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, SortedValueIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_sorted_value::<_, u64, String>("name");
+    /// assert_eq!(index.len(), 0);
+    /// index.insert(10, "foo".to_owned());
+    /// index.insert(10, "bar".to_owned());
+    /// assert_eq!(index.len(), 2);
+    /// ```
+    pub fn len(&self) -> u64 {
+        self.size().length
+    }
+
+    /// Returns `true` if the index contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the element with the lowest score, or `None` if the index is empty.
+    /// If several elements have the lowest score, the earliest inserted one is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, SortedValueIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_sorted_value::<_, u64, String>("name");
+    /// index.insert(20, "foo".to_owned());
+    /// index.insert(10, "bar".to_owned());
+    /// let (key, value) = index.peek_min().unwrap();
+    /// assert_eq!((key.score, value), (10, "bar".to_owned()));
+    /// ```
+    pub fn peek_min(&self) -> Option<(ScoredKey<S>, V)> {
+        self.iter().next()
+    }
+
+    /// Returns the element with the highest score, or `None` if the index is empty.
+    /// If several elements have the highest score, the latest inserted one is returned.
+    pub fn peek_max(&self) -> Option<(ScoredKey<S>, V)> {
+        self.iter_rev().next()
+    }
+
+    /// Returns an iterator over the elements of the index in the ascending order of scores.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, SortedValueIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_sorted_value::<_, u64, u8>("name");
+    /// index.insert(3, 1);
+    /// index.insert(1, 2);
+    /// index.insert(2, 3);
+    /// let values: Vec<_> = index.iter().map(|(_, value)| value).collect();
+    /// assert_eq!(values, vec![2, 3, 1]);
+    /// ```
+    pub fn iter(&self) -> Entries<'_, ScoredKey<S>, V> {
+        Entries::new(&self.base, None)
+    }
+
+    /// Returns an iterator over the values of the index in the ascending order of scores.
+    pub fn values(&self) -> Values<'_, V> {
+        self.iter().skip_keys()
+    }
+
+    /// Returns an iterator over the elements of the index in the descending order of scores.
+    pub fn iter_rev(&self) -> Entries<'_, ScoredKey<S>, V> {
+        Entries::rev_with_prefix(&self.base, &())
+    }
+
+    /// Returns an iterator over the elements of the index in the ascending order of scores
+    /// starting from the specified key.
+    pub fn iter_from(&self, from: &ScoredKey<S>) -> Entries<'_, ScoredKey<S>, V> {
+        Entries::new(&self.base, Some(from))
+    }
+
+    /// Returns an iterator over the elements with scores within the specified range
+    /// in the ascending order of scores.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, SortedValueIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_sorted_value::<_, u64, u8>("name");
+    /// for score in 0..10 {
+    ///     index.insert(score, score as u8);
+    /// }
+    /// let values: Vec<_> = index.range(3..6).map(|(_, value)| value).collect();
+    /// assert_eq!(values, vec![3, 4, 5]);
+    /// let values: Vec<_> = index.range(8..).map(|(_, value)| value).collect();
+    /// assert_eq!(values, vec![8, 9]);
+    /// ```
+    pub fn range<R: RangeBounds<S>>(&self, range: R) -> ScoreRange<'_, S, V> {
+        let entries = match range.start_bound() {
+            Bound::Included(score) | Bound::Excluded(score) => {
+                self.iter_from(&ScoredKey::new(score.clone(), 0))
+            }
+            Bound::Unbounded => self.iter(),
+        };
+        ScoreRange {
+            entries,
+            skipped_score: match range.start_bound() {
+                Bound::Excluded(score) => Some(concat_keys!(score)),
+                _ => None,
+            },
+            end: match range.end_bound() {
+                Bound::Included(score) => Bound::Included(concat_keys!(score)),
+                Bound::Excluded(score) => Bound::Excluded(concat_keys!(score)),
+                Bound::Unbounded => Bound::Unbounded,
+            },
+        }
+    }
+}
+
+impl<T, S, V> SortedValueIndex<T, S, V>
+where
+    T: RawAccessMut,
+    S: BinaryKey + Clone,
+    V: BinaryValue,
+{
+    /// Inserts a value with the specified score into the index and returns the key
+    /// assigned to the value. The key can be used to remove the value later.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, SortedValueIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_sorted_value::<_, u64, u8>("name");
+    /// let first = index.insert(5, 1);
+    /// let second = index.insert(5, 2);
+    /// assert_eq!(first.score, second.score);
+    /// assert!(first.seq < second.seq);
+    /// ```
+    pub fn insert(&mut self, score: S, value: V) -> ScoredKey<S> {
+        let mut size = self.size();
+        let key = ScoredKey::new(score, size.next_seq);
+        self.base.put(&key, value);
+        size.next_seq += 1;
+        size.length += 1;
+        self.state.set(size);
+        key
+    }
+
+    /// Removes the element with the specified key from the index and returns its value,
+    /// or `None` if the index does not contain such an element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, SortedValueIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_sorted_value::<_, u64, u8>("name");
+    /// let key = index.insert(5, 1);
+    /// assert_eq!(index.remove(&key), Some(1));
+    /// assert_eq!(index.remove(&key), None);
+    /// assert!(index.is_empty());
+    /// ```
+    pub fn remove(&mut self, key: &ScoredKey<S>) -> Option<V> {
+        let value = self.get(key)?;
+        self.remove_unchecked(key);
+        Some(value)
+    }
+
+    /// Removes the element with the lowest score from the index and returns it,
+    /// or `None` if the index is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, SortedValueIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_sorted_value::<_, u64, String>("name");
+    /// index.insert(20, "foo".to_owned());
+    /// index.insert(10, "bar".to_owned());
+    /// assert_eq!(index.pop_min().unwrap().1, "bar");
+    /// assert_eq!(index.pop_min().unwrap().1, "foo");
+    /// assert_eq!(index.pop_min(), None);
+    /// ```
+    pub fn pop_min(&mut self) -> Option<(ScoredKey<S>, V)> {
+        let (key, value) = self.peek_min()?;
+        self.remove_unchecked(&key);
+        Some((key, value))
+    }
+
+    /// Removes the element with the highest score from the index and returns it,
+    /// or `None` if the index is empty.
+    pub fn pop_max(&mut self) -> Option<(ScoredKey<S>, V)> {
+        let (key, value) = self.peek_max()?;
+        self.remove_unchecked(&key);
+        Some((key, value))
+    }
+
+    /// Clears the index, removing all elements.
+    ///
+    /// Sequence numbers of the elements inserted after clearing restart from zero.
+    ///
+    /// # Notes
+    ///
+    /// Currently, this method is not optimized to delete a large set of data. During the execution of
+    /// this method, the amount of allocated memory is linearly dependent on the number of elements
+    /// in the index.
+    pub fn clear(&mut self) {
+        self.base.clear();
+        self.state.unset();
+    }
+
+    fn remove_unchecked(&mut self, key: &ScoredKey<S>) {
+        let mut size = self.size();
+        self.base.remove(key);
+        size.length -= 1;
+        self.state.set(size);
+    }
+}
+
+/// Iterator over elements of a [`SortedValueIndex`] with scores within a certain range.
+///
+/// This structure is returned by [`SortedValueIndex::range`].
+///
+/// [`SortedValueIndex`]: struct.SortedValueIndex.html
+/// [`SortedValueIndex::range`]: struct.SortedValueIndex.html#method.range
+#[derive(Debug)]
+pub struct ScoreRange<'a, S, V> {
+    entries: Entries<'a, ScoredKey<S>, V>,
+    /// Serialized score excluded from the start of the range.
+    skipped_score: Option<Vec<u8>>,
+    /// Serialized end bound of the range.
+    end: Bound<Vec<u8>>,
+}
+
+impl<S, V> Iterator for ScoreRange<'_, S, V>
+where
+    S: BinaryKey + Clone,
+    V: BinaryValue,
+{
+    type Item = (ScoredKey<S>, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (key, value) = self.entries.next()?;
+            let score = concat_keys!(&key.score);
+            if self.skipped_score.as_ref() == Some(&score) {
+                continue;
+            }
+            self.skipped_score = None;
+
+            let is_in_range = match self.end {
+                Bound::Included(ref end) => score <= *end,
+                Bound::Excluded(ref end) => score < *end,
+                Bound::Unbounded => true,
+            };
+            return if is_in_range {
+                Some((key, value))
+            } else {
+                None
+            };
+        }
+    }
+}
+
+impl<'a, T, S, V> IntoIterator for &'a SortedValueIndex<T, S, V>
+where
+    T: RawAccess,
+    S: BinaryKey + Clone,
+    V: BinaryValue,
+{
+    type Item = (ScoredKey<S>, V);
+    type IntoIter = Entries<'a, ScoredKey<S>, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, S, V> IndexIterator for SortedValueIndex<T, S, V>
+where
+    T: RawAccess,
+    S: BinaryKey + Clone,
+    V: BinaryValue,
+{
+    type Key = ScoredKey<S>;
+    type Value = V;
+
+    fn index_iter(&self, from: Option<&ScoredKey<S>>) -> Entries<'_, ScoredKey<S>, V> {
+        Entries::new(&self.base, from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Bound::{Excluded, Included};
+
+    use super::{ScoredKey, SortedValueIndex};
+    use crate::{access::CopyAccessExt, BinaryKey, Database, TemporaryDB};
+
+    const INDEX_NAME: &str = "test_index_name";
+
+    #[test]
+    fn scored_key_serialization() {
+        let key = ScoredKey::new(-5_i32, 3);
+        let mut buffer = vec![0; key.size()];
+        assert_eq!(key.write(&mut buffer), 12);
+        assert_eq!(ScoredKey::<i32>::read(&buffer), key);
+
+        let other_key = ScoredKey::new(2_i32, 0);
+        let mut other_buffer = vec![0; other_key.size()];
+        other_key.write(&mut other_buffer);
+        assert!(buffer < other_buffer);
+    }
+
+    #[test]
+    fn elements_are_ordered_by_score_and_insertion() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        let mut index: SortedValueIndex<_, i64, String> = fork.get_sorted_value(INDEX_NAME);
+
+        index.insert(5, "a".to_owned());
+        index.insert(-3, "b".to_owned());
+        index.insert(5, "c".to_owned());
+        index.insert(0, "d".to_owned());
+        assert_eq!(index.len(), 4);
+
+        let values: Vec<_> = index.values().collect();
+        assert_eq!(values, vec!["b", "d", "a", "c"]);
+        let values: Vec<_> = index.iter_rev().map(|(_, value)| value).collect();
+        assert_eq!(values, vec!["c", "a", "d", "b"]);
+
+        assert_eq!(index.peek_min().unwrap().1, "b");
+        assert_eq!(index.peek_max().unwrap().1, "c");
+        assert_eq!(index.pop_max().unwrap().1, "c");
+        assert_eq!(index.pop_min().unwrap().1, "b");
+        assert_eq!(index.pop_min().unwrap().1, "d");
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.pop_max().unwrap().1, "a");
+        assert!(index.is_empty());
+        assert_eq!(index.pop_min(), None);
+        assert_eq!(index.pop_max(), None);
+    }
+
+    #[test]
+    fn removing_elements() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        let mut index: SortedValueIndex<_, u32, u8> = fork.get_sorted_value(INDEX_NAME);
+
+        let first = index.insert(1, 1);
+        let second = index.insert(1, 2);
+        assert!(index.contains(&first));
+        assert_eq!(index.remove(&ScoredKey::new(2, first.seq)), None);
+        assert_eq!(index.remove(&first), Some(1));
+        assert!(!index.contains(&first));
+        assert_eq!(index.remove(&first), None);
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.get(&second), Some(2));
+
+        // Sequence numbers are not reused after removal.
+        let third = index.insert(1, 3);
+        assert!(third.seq > second.seq);
+
+        index.clear();
+        assert!(index.is_empty());
+        assert_eq!(index.iter().count(), 0);
+        assert_eq!(index.insert(0, 4).seq, 0);
+    }
+
+    #[test]
+    fn range_queries() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        let mut index: SortedValueIndex<_, u64, u64> = fork.get_sorted_value(INDEX_NAME);
+        for score in &[1, 3, 3, 5, 7, 7, 9] {
+            index.insert(*score, *score);
+        }
+
+        let range_values = |values: Vec<(ScoredKey<u64>, u64)>| -> Vec<u64> {
+            values.into_iter().map(|(_, value)| value).collect()
+        };
+        assert_eq!(range_values(index.range(3..7).collect()), vec![3, 3, 5]);
+        assert_eq!(
+            range_values(index.range(3..=7).collect()),
+            vec![3, 3, 5, 7, 7]
+        );
+        assert_eq!(range_values(index.range(..4).collect()), vec![1, 3, 3]);
+        assert_eq!(range_values(index.range(6..).collect()), vec![7, 7, 9]);
+        assert_eq!(range_values(index.range(10..).collect()), vec![]);
+        assert_eq!(range_values(index.range(4..5).collect()), vec![]);
+        assert_eq!(index.range(..).count(), 7);
+
+        let values = index.range((Excluded(3), Included(7))).collect();
+        assert_eq!(range_values(values), vec![5, 7, 7]);
+        let values = index.range((Excluded(7), Excluded(9))).collect();
+        assert_eq!(range_values(values), vec![]);
+    }
+
+    #[test]
+    fn length_is_persisted() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        {
+            let mut index: SortedValueIndex<_, u64, u64> = fork.get_sorted_value(INDEX_NAME);
+            index.insert(2, 2);
+            index.insert(1, 1);
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let index: SortedValueIndex<_, u64, u64> = snapshot.get_sorted_value(INDEX_NAME);
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.peek_min().unwrap().1, 1);
+    }
+}
@@ -22,7 +22,7 @@ use std::{io::Error, marker::PhantomData};
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
 use crate::{
-    access::{Access, AccessError, FromAccess},
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
     indexes::iter::{Entries, IndexIterator, Keys, Values},
     views::{
         BinaryAttribute, IndexAddress, IndexState, IndexType, RawAccess, RawAccessMut, View,
@@ -92,6 +92,16 @@ where
     }
 }
 
+impl<T, V> DescribeSchema for SparseListIndex<T, V>
+where
+    T: RawAccess,
+    V: BinaryValue,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        vec![IndexDescription::new(name, IndexType::SparseList).with_value::<V>()]
+    }
+}
+
 impl<T, V> SparseListIndex<T, V>
 where
     T: RawAccess,
@@ -0,0 +1,483 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of a sparse Merkle tree with fixed-depth 256-bit keys.
+
+pub use self::proof::{CheckedSparseMerkleProof, SparseMerkleProof, SparseMerkleProofError};
+
+use exonum_crypto::{Hash, HASH_SIZE};
+
+use std::marker::PhantomData;
+
+use crate::{
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
+    indexes::iter::{Entries, IndexIterator, Keys, Values},
+    views::{IndexAddress, IndexState, IndexType, RawAccess, RawAccessMut, View, ViewWithMetadata},
+    BinaryKey, BinaryValue, HashTag, ObjectHash,
+};
+
+mod proof;
+#[cfg(test)]
+mod tests;
+
+/// Height of the tree, which is equal to the number of bits in a key path.
+pub const SPARSE_MERKLE_TREE_HEIGHT: usize = HASH_SIZE * 8;
+
+/// Prefix of the keys under which values are stored.
+const VALUE_KEY_PREFIX: u8 = 0;
+/// Prefix of the keys under which hashes of the non-empty tree nodes are stored.
+const NODE_KEY_PREFIX: u8 = 1;
+
+/// Path to a leaf of the tree.
+type KeyPath = [u8; HASH_SIZE];
+
+fn key_path<K: ObjectHash + ?Sized>(key: &K) -> KeyPath {
+    key.object_hash().as_bytes()
+}
+
+/// Returns the bit of the path with the specified index, counting from the most significant
+/// bit of the first byte. A set bit corresponds to the right child.
+fn path_bit(path: &KeyPath, index: usize) -> bool {
+    (path[index / 8] >> (7 - index % 8)) & 1 == 1
+}
+
+fn flip_path_bit(path: &mut KeyPath, index: usize) {
+    path[index / 8] ^= 1 << (7 - index % 8);
+}
+
+/// Returns the canonical path of the node at the specified height on the path to the leaf,
+/// i.e., the leaf path with the `height` least significant bits zeroed.
+fn node_path(path: &KeyPath, height: usize) -> KeyPath {
+    let mut node_path = *path;
+    let prefix_len = SPARSE_MERKLE_TREE_HEIGHT - height;
+    let (full_bytes, remaining_bits) = (prefix_len / 8, prefix_len % 8);
+    if full_bytes < HASH_SIZE {
+        node_path[full_bytes] &= !(0xff_u8 >> remaining_bits);
+        for byte in &mut node_path[full_bytes + 1..] {
+            *byte = 0;
+        }
+    }
+    node_path
+}
+
+/// Hashes a branch node of the tree. Branches with both subtrees empty are empty themselves.
+fn hash_branch(left: &Hash, right: &Hash) -> Hash {
+    if left == &Hash::zero() && right == &Hash::zero() {
+        Hash::zero()
+    } else {
+        HashTag::hash_sparse_merkle_branch(left, right)
+    }
+}
+
+/// Computes hashes of the nodes on the path from the leaf to the root.
+///
+/// `sibling` returns the hash of the sibling of the path node at the specified height
+/// (`0` corresponds to leaves); `visit` is called for each path node except for the root
+/// with its height, canonical path (i.e., with bits below the node zeroed) and hash.
+/// Returns the hash of the root node.
+fn fold_path(
+    path: &KeyPath,
+    leaf_hash: Hash,
+    mut sibling: impl FnMut(u8, &KeyPath) -> Hash,
+    mut visit: impl FnMut(u8, &KeyPath, Hash),
+) -> Hash {
+    let mut node_path = *path;
+    let mut node_hash = leaf_hash;
+    for height in 0..SPARSE_MERKLE_TREE_HEIGHT {
+        let bit_index = SPARSE_MERKLE_TREE_HEIGHT - 1 - height;
+        let height = height as u8;
+        visit(height, &node_path, node_hash);
+
+        let is_right = path_bit(&node_path, bit_index);
+        flip_path_bit(&mut node_path, bit_index);
+        let sibling_hash = sibling(height, &node_path);
+        node_hash = if is_right {
+            hash_branch(&sibling_hash, &node_hash)
+        } else {
+            hash_branch(&node_hash, &sibling_hash)
+        };
+        // Clear the bit to obtain the canonical path of the parent node.
+        if !is_right {
+            flip_path_bit(&mut node_path, bit_index);
+        }
+    }
+    node_hash
+}
+
+/// A Merkelized map with a fixed-depth sparse Merkle tree as the authenticated data structure.
+///
+/// `SparseMerkleIndex` is an alternative to [`ProofMapIndex`] for the use cases requiring
+/// interoperability with other systems based on sparse Merkle trees (SMTs).
+/// Each key is mapped to a 256-bit path in a complete binary tree of the height 256
+/// as the [`object_hash()`] of the key. Since `Hash::object_hash()` returns the hash
+/// itself, `Hash` keys are mapped to the paths directly.
+///
+/// The hashes of the tree nodes are defined as follows:
+///
+/// ```text
+/// empty_subtree = Hash::zero()
+/// leaf = sha256( HashTag::Blob || value )
+/// branch = sha256( HashTag::SparseMerkleBranchNode || left || right )
+/// ```
+///
+/// If both children of a branch are empty subtrees, the branch is an empty subtree as well.
+/// Only the hashes of non-empty nodes are stored in the database.
+///
+/// Unlike the Merkle Patricia tree of `ProofMapIndex`, the shape of the tree does not depend
+/// on its contents. Thus, the proofs for all keys (including [proofs of absence]) have
+/// the same structure: the value at the key path (or its absence) and hashes of 256 siblings
+/// of the nodes on the path. Hashes of empty subtrees are omitted from the proofs.
+///
+/// The downside of a fixed-depth tree is that each update recalculates 256 hashes
+/// on the path from the leaf to the root, so `SparseMerkleIndex` is slower than
+/// `ProofMapIndex` on writes.
+///
+/// [`ProofMapIndex`]: ../proof_map/struct.ProofMapIndex.html
+/// [`object_hash()`]: ../../trait.ObjectHash.html#tymethod.object_hash
+/// [proofs of absence]: struct.SparseMerkleProof.html
+#[derive(Debug)]
+pub struct SparseMerkleIndex<T: RawAccess, K, V> {
+    base: View<T>,
+    state: IndexState<T, Hash>,
+    _k: PhantomData<K>,
+    _v: PhantomData<V>,
+}
+
+impl<T, K, V> FromAccess<T> for SparseMerkleIndex<T::Base, K, V>
+where
+    T: Access,
+    K: BinaryKey + ObjectHash,
+    V: BinaryValue,
+{
+    fn from_access(access: T, addr: IndexAddress) -> Result<Self, AccessError> {
+        let view = access.get_or_create_view(addr, IndexType::SparseMerkle)?;
+        Ok(Self::new(view))
+    }
+}
+
+impl<T, K, V> DescribeSchema for SparseMerkleIndex<T, K, V>
+where
+    T: RawAccess,
+    K: BinaryKey + ObjectHash,
+    V: BinaryValue,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        vec![IndexDescription::new(name, IndexType::SparseMerkle)
+            .with_key::<K>()
+            .with_value::<V>()]
+    }
+}
+
+impl<T, K, V> SparseMerkleIndex<T, K, V>
+where
+    T: RawAccess,
+    K: BinaryKey + ObjectHash,
+    V: BinaryValue,
+{
+    pub(crate) fn new(view: ViewWithMetadata<T>) -> Self {
+        let (base, state) = view.into_parts();
+        Self {
+            base,
+            state,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+
+    fn value_key(key: &K) -> Vec<u8> {
+        let mut buffer = vec![0_u8; key.size() + 1];
+        buffer[0] = VALUE_KEY_PREFIX;
+        key.write(&mut buffer[1..]);
+        buffer
+    }
+
+    fn node_key(height: u8, path: &KeyPath) -> [u8; HASH_SIZE + 2] {
+        let mut buffer = [0_u8; HASH_SIZE + 2];
+        buffer[0] = NODE_KEY_PREFIX;
+        buffer[1] = height;
+        buffer[2..].copy_from_slice(path);
+        buffer
+    }
+
+    fn node_hash(&self, height: u8, path: &KeyPath) -> Hash {
+        self.base
+            .get(&Self::node_key(height, path)[..])
+            .unwrap_or_else(Hash::zero)
+    }
+
+    /// Returns the hash of the root node of the tree, or `Hash::zero()` if the index is empty.
+    pub fn merkle_root(&self) -> Hash {
+        self.state.get().unwrap_or_else(Hash::zero)
+    }
+
+    /// Returns a value corresponding to the key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database};
+    /// use exonum_crypto::Hash;
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_sparse_merkle("name");
+    ///
+    /// let hash = Hash::default();
+    /// assert_eq!(None, index.get(&hash));
+    ///
+    /// index.put(&hash, 2);
+    /// assert_eq!(Some(2), index.get(&hash));
+    /// ```
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.base.get(&Self::value_key(key))
+    }
+
+    /// Returns `true` if the index contains a value for the specified key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database};
+    /// use exonum_crypto::Hash;
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_sparse_merkle("name");
+    ///
+    /// let hash = Hash::default();
+    /// assert!(!index.contains(&hash));
+    ///
+    /// index.put(&hash, 2);
+    /// assert!(index.contains(&hash));
+    /// ```
+    pub fn contains(&self, key: &K) -> bool {
+        self.base.contains(&Self::value_key(key))
+    }
+
+    /// Returns the proof of existence or non-existence for the specified key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, ObjectHash};
+    /// use exonum_crypto::hash;
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_sparse_merkle("name");
+    /// index.put(&hash(&[1]), 100_u32);
+    ///
+    /// let proof = index.get_proof(hash(&[1]));
+    /// let checked_proof = proof.check_against_hash(index.object_hash()).unwrap();
+    /// assert_eq!(checked_proof.value(), Some(&100));
+    ///
+    /// let proof = index.get_proof(hash(&[2]));
+    /// let checked_proof = proof.check_against_hash(index.object_hash()).unwrap();
+    /// assert_eq!(checked_proof.value(), None);
+    /// ```
+    pub fn get_proof(&self, key: K) -> SparseMerkleProof<K, V> {
+        let value = self.get(&key);
+        let path = key_path(&key);
+        let siblings = (0..SPARSE_MERKLE_TREE_HEIGHT).filter_map(|height| {
+            let mut sibling_path = node_path(&path, height);
+            flip_path_bit(&mut sibling_path, SPARSE_MERKLE_TREE_HEIGHT - 1 - height);
+            let hash = self.node_hash(height as u8, &sibling_path);
+            if hash == Hash::zero() {
+                None
+            } else {
+                Some((height as u8, hash))
+            }
+        });
+        let siblings = siblings.collect();
+        SparseMerkleProof::new(key, value, siblings)
+    }
+
+    /// Returns an iterator over the entries of the index in ascending order of the keys.
+    /// Note that the order is determined by the binary representation of the keys
+    /// rather than by their paths in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database};
+    /// use exonum_crypto::Hash;
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let index = fork.get_sparse_merkle::<_, Hash, u8>("name");
+    ///
+    /// for (key, value) in index.iter() {
+    ///     println!("{} = {}", key, value);
+    /// }
+    /// ```
+    pub fn iter(&self) -> Entries<'_, K, V> {
+        self.index_iter(None)
+    }
+
+    /// Returns an iterator over the keys of the index in ascending order.
+    pub fn keys(&self) -> Keys<'_, K> {
+        self.iter().skip_values()
+    }
+
+    /// Returns an iterator over the values of the index in ascending order of the keys.
+    pub fn values(&self) -> Values<'_, V> {
+        self.iter().skip_keys()
+    }
+
+    /// Returns an iterator over the entries of the index in ascending order of the keys
+    /// starting from the specified key.
+    pub fn iter_from(&self, from: &K) -> Entries<'_, K, V> {
+        self.index_iter(Some(from))
+    }
+}
+
+impl<T, K, V> SparseMerkleIndex<T, K, V>
+where
+    T: RawAccessMut,
+    K: BinaryKey + ObjectHash,
+    V: BinaryValue,
+{
+    /// Updates the hashes of the nodes on the path to the specified leaf.
+    fn update_path(&mut self, path: &KeyPath, leaf_hash: Hash) {
+        let mut updated_nodes = Vec::with_capacity(SPARSE_MERKLE_TREE_HEIGHT);
+        let root_hash = fold_path(
+            path,
+            leaf_hash,
+            |height, sibling_path| self.node_hash(height, sibling_path),
+            |height, node_path, hash| updated_nodes.push((height, *node_path, hash)),
+        );
+
+        for (height, node_path, hash) in updated_nodes {
+            let node_key = Self::node_key(height, &node_path);
+            if hash == Hash::zero() {
+                self.base.remove(&node_key[..]);
+            } else {
+                self.base.put(&node_key[..], hash);
+            }
+        }
+        if root_hash == Hash::zero() {
+            self.state.unset();
+        } else {
+            self.state.set(root_hash);
+        }
+    }
+
+    /// Inserts the key-value pair into the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database};
+    /// use exonum_crypto::Hash;
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_sparse_merkle("name");
+    ///
+    /// let hash = Hash::default();
+    /// index.put(&hash, 2);
+    /// assert!(index.contains(&hash));
+    /// ```
+    pub fn put(&mut self, key: &K, value: V) {
+        let leaf_hash = HashTag::hash_leaf(&value.to_bytes());
+        self.base.put(&Self::value_key(key), value);
+        self.update_path(&key_path(key), leaf_hash);
+    }
+
+    /// Removes the key from the index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database};
+    /// use exonum_crypto::Hash;
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_sparse_merkle("name");
+    ///
+    /// let hash = Hash::default();
+    /// index.put(&hash, 2);
+    /// assert!(index.contains(&hash));
+    ///
+    /// index.remove(&hash);
+    /// assert!(!index.contains(&hash));
+    /// ```
+    pub fn remove(&mut self, key: &K) {
+        let value_key = Self::value_key(key);
+        if self.base.contains(&value_key) {
+            self.base.remove(&value_key);
+            self.update_path(&key_path(key), Hash::zero());
+        }
+    }
+
+    /// Clears the index, removing all entries.
+    ///
+    /// # Notes
+    ///
+    /// Currently, this method is not optimized to delete a large set of data. During the execution of
+    /// this method, the amount of allocated memory is linearly dependent on the number of elements
+    /// in the index.
+    pub fn clear(&mut self) {
+        self.base.clear();
+        self.state.unset();
+    }
+}
+
+/// `object_hash()` of a sparse Merkle index is computed from the hash of the root node
+/// of the tree as
+///
+/// ```text
+/// h = sha256( HashTag::SparseMerkleNode || merkle_root )
+/// ```
+///
+/// The root of an empty tree is `Hash::zero()`.
+impl<T, K, V> ObjectHash for SparseMerkleIndex<T, K, V>
+where
+    T: RawAccess,
+    K: BinaryKey + ObjectHash,
+    V: BinaryValue,
+{
+    fn object_hash(&self) -> Hash {
+        HashTag::hash_sparse_merkle_node(self.merkle_root())
+    }
+}
+
+impl<'a, T, K, V> IntoIterator for &'a SparseMerkleIndex<T, K, V>
+where
+    T: RawAccess,
+    K: BinaryKey + ObjectHash,
+    V: BinaryValue,
+{
+    type Item = (K::Owned, V);
+    type IntoIter = Entries<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, K, V> IndexIterator for SparseMerkleIndex<T, K, V>
+where
+    T: RawAccess,
+    K: BinaryKey + ObjectHash,
+    V: BinaryValue,
+{
+    type Key = K;
+    type Value = V;
+
+    fn index_iter(&self, from: Option<&K>) -> Entries<'_, K, V> {
+        Entries::with_detached_prefix(&self.base, &VALUE_KEY_PREFIX, from)
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use exonum_crypto::Hash;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{fold_path, key_path};
+use crate::{BinaryValue, HashTag, ObjectHash, ValidationError};
+
+/// An error returned when a sparse Merkle tree proof is invalid.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum SparseMerkleProofError {
+    /// Siblings in the proof are not ordered by increasing height, or the height
+    /// of a sibling is repeated.
+    #[error("invalid ordering of siblings in the proof")]
+    InvalidOrdering(u8, u8),
+
+    /// The proof contains a hash of an empty subtree. Such hashes must be omitted.
+    #[error("hash of an empty subtree in the proof")]
+    EmptySubtree(u8),
+}
+
+// Used instead of `(u8, Hash)` only for the purpose of clearer (de)serialization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Sibling {
+    height: u8,
+    hash: Hash,
+}
+
+/// Proof of existence or non-existence of a key in a [`SparseMerkleIndex`].
+///
+/// The proof consists of the value at the key (or its absence) and hashes of the siblings
+/// of all nodes on the path from the leaf corresponding to the key to the root of the tree.
+/// Each sibling is identified by its height, `0` corresponding to leaves. Siblings
+/// which are empty subtrees are omitted, so the proofs are compact for sparse trees.
+///
+/// # Workflow
+///
+/// ```
+/// # use exonum_merkledb::{access::CopyAccessExt, Database, TemporaryDB, ObjectHash};
+/// # use exonum_crypto::hash;
+/// # fn main() -> anyhow::Result<()> {
+/// let fork = { let db = TemporaryDB::new(); db.fork() };
+/// let mut index = fork.get_sparse_merkle("index");
+/// let (h1, h2) = (hash(&[1]), hash(&[2]));
+/// index.put(&h1, 100_u32);
+///
+/// let proof = index.get_proof(h1);
+/// let checked_proof = proof.check()?;
+/// assert_eq!(checked_proof.value(), Some(&100));
+/// assert_eq!(checked_proof.index_hash(), index.object_hash());
+///
+/// // Proofs of absence have the same form.
+/// let checked_proof = index.get_proof(h2).check_against_hash(index.object_hash())?;
+/// assert_eq!(checked_proof.key(), &h2);
+/// assert_eq!(checked_proof.value(), None);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # JSON serialization
+///
+/// `SparseMerkleProof` is serialized to JSON as an object with the following fields:
+///
+/// - `key` is the requested key.
+/// - `value` is the value at the key, or `null` if the key is absent from the index.
+/// - `siblings` is an array of `{ height: u8, hash: Hash }` objects ordered by increasing
+///   height.
+///
+/// [`SparseMerkleIndex`]: struct.SparseMerkleIndex.html
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SparseMerkleProof<K, V> {
+    key: K,
+    value: Option<V>,
+    siblings: Vec<Sibling>,
+}
+
+/// Version of `SparseMerkleProof` obtained after verification.
+///
+/// See [`SparseMerkleProof`] for an example of usage.
+///
+/// [`SparseMerkleProof`]: struct.SparseMerkleProof.html#workflow
+#[derive(Debug, Serialize)]
+pub struct CheckedSparseMerkleProof<'a, K, V> {
+    key: &'a K,
+    value: Option<&'a V>,
+    hash: Hash,
+}
+
+impl<K, V> SparseMerkleProof<K, V> {
+    pub(super) fn new(key: K, value: Option<V>, siblings: Vec<(u8, Hash)>) -> Self {
+        let siblings = siblings
+            .into_iter()
+            .map(|(height, hash)| Sibling { height, hash })
+            .collect();
+        Self {
+            key,
+            value,
+            siblings,
+        }
+    }
+
+    /// Returns the key, which existence or non-existence is asserted by the proof.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns the value at the key without verifying the proof.
+    pub fn value_unchecked(&self) -> Option<&V> {
+        self.value.as_ref()
+    }
+
+    /// Returns the non-empty siblings in the proof together with their heights,
+    /// without verifying the proof.
+    pub fn siblings_unchecked(&self) -> impl Iterator<Item = (u8, Hash)> + '_ {
+        self.siblings
+            .iter()
+            .map(|sibling| (sibling.height, sibling.hash))
+    }
+}
+
+impl<K, V> SparseMerkleProof<K, V>
+where
+    K: ObjectHash,
+    V: BinaryValue,
+{
+    /// Checks the proof for consistency and computes the `object_hash()` of the underlying
+    /// index.
+    ///
+    /// The proof is considered well-formed if the siblings are ordered by strictly increasing
+    /// height and none of them is a hash of an empty subtree (i.e., `Hash::zero()`).
+    pub fn check(&self) -> Result<CheckedSparseMerkleProof<'_, K, V>, SparseMerkleProofError> {
+        for window in self.siblings.windows(2) {
+            let (prev, next) = (window[0].height, window[1].height);
+            if prev >= next {
+                return Err(SparseMerkleProofError::InvalidOrdering(prev, next));
+            }
+        }
+        if let Some(sibling) = self.siblings.iter().find(|s| s.hash == Hash::zero()) {
+            return Err(SparseMerkleProofError::EmptySubtree(sibling.height));
+        }
+
+        let leaf_hash = self
+            .value
+            .as_ref()
+            .map_or_else(Hash::zero, |value| HashTag::hash_leaf(&value.to_bytes()));
+        let mut siblings = self.siblings.iter().peekable();
+        let root_hash = fold_path(
+            &key_path(&self.key),
+            leaf_hash,
+            |height, _| {
+                siblings
+                    .next_if(|sibling| sibling.height == height)
+                    .map_or_else(Hash::zero, |sibling| sibling.hash)
+            },
+            |_, _, _| {},
+        );
+
+        Ok(CheckedSparseMerkleProof {
+            key: &self.key,
+            value: self.value.as_ref(),
+            hash: HashTag::hash_sparse_merkle_node(root_hash),
+        })
+    }
+
+    /// Checks this proof against a trusted index hash. Fails if the proof is malformed or the
+    /// hash does not match the one computed from the proof.
+    pub fn check_against_hash(
+        &self,
+        expected_index_hash: Hash,
+    ) -> Result<CheckedSparseMerkleProof<'_, K, V>, ValidationError<SparseMerkleProofError>> {
+        self.check()
+            .map_err(ValidationError::Malformed)
+            .and_then(|checked| {
+                if checked.index_hash() == expected_index_hash {
+                    Ok(checked)
+                } else {
+                    Err(ValidationError::UnmatchedRootHash)
+                }
+            })
+    }
+}
+
+impl<'a, K, V> CheckedSparseMerkleProof<'a, K, V> {
+    /// Returns the key, which existence or non-existence is asserted by the proof.
+    pub fn key(&self) -> &'a K {
+        self.key
+    }
+
+    /// Returns the value at the key, or `None` if the proof asserts that the key is absent
+    /// from the index.
+    pub fn value(&self) -> Option<&'a V> {
+        self.value
+    }
+
+    /// Returns the `object_hash()` of the underlying `SparseMerkleIndex`.
+    pub fn index_hash(&self) -> Hash {
+        self.hash
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use exonum_crypto::{hash, Hash};
+use rand::{seq::SliceRandom, thread_rng, Rng};
+
+use super::{
+    key_path, path_bit, KeyPath, SparseMerkleProof, SparseMerkleProofError, NODE_KEY_PREFIX,
+    SPARSE_MERKLE_TREE_HEIGHT,
+};
+use crate::{
+    access::CopyAccessExt, BinaryValue, Database, HashTag, ObjectHash, SystemSchema, TemporaryDB,
+    ValidationError,
+};
+
+const IDX_NAME: &str = "idx_name";
+
+/// Straightforward recursive computation of the tree root.
+fn reference_root(entries: &[(KeyPath, Hash)], depth: usize) -> Hash {
+    if entries.is_empty() {
+        return Hash::zero();
+    }
+    if depth == SPARSE_MERKLE_TREE_HEIGHT {
+        assert_eq!(entries.len(), 1);
+        return entries[0].1;
+    }
+
+    let (right, left): (Vec<_>, Vec<_>) = entries
+        .iter()
+        .copied()
+        .partition(|(path, _)| path_bit(path, depth));
+    let left = reference_root(&left, depth + 1);
+    let right = reference_root(&right, depth + 1);
+    if left == Hash::zero() && right == Hash::zero() {
+        Hash::zero()
+    } else {
+        HashTag::hash_sparse_merkle_branch(&left, &right)
+    }
+}
+
+fn generate_entries(count: usize) -> Vec<(Hash, u64)> {
+    let mut rng = thread_rng();
+    (0..count)
+        .map(|i| (hash(&(i as u64).to_le_bytes()), rng.gen()))
+        .collect()
+}
+
+#[test]
+fn empty_index() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let index = fork.get_sparse_merkle::<_, Hash, u64>(IDX_NAME);
+    assert_eq!(index.merkle_root(), Hash::zero());
+    assert_eq!(
+        index.object_hash(),
+        HashTag::hash_sparse_merkle_node(Hash::zero())
+    );
+    assert_eq!(index.iter().count(), 0);
+}
+
+#[test]
+fn root_hash_matches_reference_implementation() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut entries = generate_entries(50);
+    {
+        let mut index = fork.get_sparse_merkle(IDX_NAME);
+        for (key, value) in &entries {
+            index.put(key, *value);
+        }
+    }
+
+    let reference_entries: Vec<_> = entries
+        .iter()
+        .map(|(key, value)| (key_path(key), HashTag::hash_leaf(&value.to_bytes())))
+        .collect();
+    let expected_root = reference_root(&reference_entries, 0);
+    let index = fork.get_sparse_merkle::<_, Hash, u64>(IDX_NAME);
+    assert_eq!(index.merkle_root(), expected_root);
+    assert_eq!(index.iter().count(), entries.len());
+
+    // The root does not depend on the insertion order.
+    entries.shuffle(&mut thread_rng());
+    let mut other_index = fork.get_sparse_merkle("other");
+    for (key, value) in &entries {
+        other_index.put(key, *value);
+    }
+    assert_eq!(other_index.merkle_root(), expected_root);
+}
+
+#[test]
+fn updating_and_removing_entries() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut index = fork.get_sparse_merkle(IDX_NAME);
+    let entries = generate_entries(20);
+    for (key, value) in &entries[..10] {
+        index.put(key, *value);
+    }
+    let root = index.merkle_root();
+
+    for (key, value) in &entries[10..] {
+        index.put(key, *value);
+    }
+    index.put(&entries[0].0, entries[0].1 + 1);
+    assert_eq!(index.get(&entries[0].0), Some(entries[0].1 + 1));
+    assert_ne!(index.merkle_root(), root);
+
+    index.put(&entries[0].0, entries[0].1);
+    for (key, _) in &entries[10..] {
+        index.remove(key);
+    }
+    // Removing a missing key is a no-op.
+    index.remove(&entries[10].0);
+    assert_eq!(index.merkle_root(), root);
+
+    for (key, _) in &entries[..10] {
+        index.remove(key);
+    }
+    assert_eq!(index.merkle_root(), Hash::zero());
+    // No hashes of internal nodes should remain.
+    let nodes = index.base.iter::<_, [u8], Hash>(&NODE_KEY_PREFIX);
+    assert_eq!(nodes.count(), 0);
+
+    index.put(&entries[0].0, entries[0].1);
+    index.clear();
+    assert_eq!(index.merkle_root(), Hash::zero());
+    assert!(!index.contains(&entries[0].0));
+}
+
+#[test]
+fn proofs_of_existence_and_absence() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut index = fork.get_sparse_merkle(IDX_NAME);
+    let entries = generate_entries(30);
+    for (key, value) in &entries[..20] {
+        index.put(key, *value);
+    }
+    let index_hash = index.object_hash();
+
+    for (key, value) in &entries[..20] {
+        let proof = index.get_proof(*key);
+        let checked_proof = proof.check_against_hash(index_hash).unwrap();
+        assert_eq!(checked_proof.key(), key);
+        assert_eq!(checked_proof.value(), Some(value));
+    }
+    for (key, _) in &entries[20..] {
+        let proof = index.get_proof(*key);
+        assert!(proof.siblings_unchecked().count() > 0);
+        let checked_proof = proof.check_against_hash(index_hash).unwrap();
+        assert_eq!(checked_proof.value(), None);
+    }
+
+    // Proof for an empty index.
+    let empty_index = fork.get_sparse_merkle::<_, Hash, u64>("empty");
+    let proof = empty_index.get_proof(entries[0].0);
+    assert_eq!(proof.siblings_unchecked().count(), 0);
+    proof.check_against_hash(empty_index.object_hash()).unwrap();
+}
+
+#[test]
+fn invalid_proofs() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut index = fork.get_sparse_merkle(IDX_NAME);
+    let entries = generate_entries(10);
+    for (key, value) in &entries {
+        index.put(key, *value);
+    }
+    let (key, value) = entries[0];
+    let proof = index.get_proof(key);
+    let siblings: Vec<_> = proof.siblings_unchecked().collect();
+    assert!(siblings.len() > 1);
+
+    let forged_proof = SparseMerkleProof::new(key, Some(value + 1), siblings.clone());
+    assert!(matches!(
+        forged_proof
+            .check_against_hash(index.object_hash())
+            .unwrap_err(),
+        ValidationError::UnmatchedRootHash
+    ));
+    let forged_proof = SparseMerkleProof::<_, u64>::new(key, None, siblings.clone());
+    assert!(matches!(
+        forged_proof
+            .check_against_hash(index.object_hash())
+            .unwrap_err(),
+        ValidationError::UnmatchedRootHash
+    ));
+
+    let mut reordered_siblings = siblings.clone();
+    reordered_siblings.swap(0, 1);
+    let malformed_proof = SparseMerkleProof::new(key, Some(value), reordered_siblings);
+    assert!(matches!(
+        malformed_proof.check().unwrap_err(),
+        SparseMerkleProofError::InvalidOrdering(..)
+    ));
+
+    let mut duplicate_siblings = siblings.clone();
+    duplicate_siblings.insert(0, siblings[0]);
+    let malformed_proof = SparseMerkleProof::new(key, Some(value), duplicate_siblings);
+    assert!(matches!(
+        malformed_proof.check().unwrap_err(),
+        SparseMerkleProofError::InvalidOrdering(..)
+    ));
+
+    let mut siblings_with_empty_subtree = siblings;
+    siblings_with_empty_subtree[0].1 = Hash::zero();
+    let malformed_proof = SparseMerkleProof::new(key, Some(value), siblings_with_empty_subtree);
+    assert!(matches!(
+        malformed_proof.check().unwrap_err(),
+        SparseMerkleProofError::EmptySubtree(_)
+    ));
+}
+
+#[test]
+fn proof_serialization() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut index = fork.get_sparse_merkle(IDX_NAME);
+    let (h1, h2) = (hash(&[1]), hash(&[2]));
+    index.put(&h1, 100_u32);
+    index.put(&h2, 200_u32);
+
+    let proof = index.get_proof(h1);
+    let json = serde_json::to_value(&proof).unwrap();
+    assert_eq!(json["key"], serde_json::to_value(h1).unwrap());
+    assert_eq!(json["value"], 100);
+    assert_eq!(json["siblings"].as_array().unwrap().len(), 1);
+
+    let restored_proof: SparseMerkleProof<Hash, u32> = serde_json::from_value(json).unwrap();
+    assert_eq!(restored_proof, proof);
+    restored_proof
+        .check_against_hash(index.object_hash())
+        .unwrap();
+}
+
+#[test]
+fn index_is_aggregated() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    fork.get_sparse_merkle(IDX_NAME).put(&hash(&[1]), 1_u64);
+    let patch = fork.into_patch();
+    let index_hash = patch
+        .get_sparse_merkle::<_, Hash, u64>(IDX_NAME)
+        .object_hash();
+    let aggregator = SystemSchema::new(&patch).state_aggregator();
+    assert_eq!(aggregator.get(IDX_NAME), Some(index_hash));
+
+    db.merge(patch).unwrap();
+    let fork = db.fork();
+    fork.get_sparse_merkle::<_, Hash, u64>(IDX_NAME)
+        .remove(&hash(&[1]));
+    let patch = fork.into_patch();
+    let aggregator = SystemSchema::new(&patch).state_aggregator();
+    assert_eq!(
+        aggregator.get(IDX_NAME),
+        Some(HashTag::hash_sparse_merkle_node(Hash::zero()))
+    );
+}
@@ -0,0 +1,477 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An implementation of a key-value map with entries expiring at a certain height.
+//!
+//! The given section contains methods related to `TtlMapIndex` and iterators
+//! over the items of this map.
+
+use anyhow::ensure;
+use byteorder::{ByteOrder, LittleEndian};
+
+use std::{borrow::Cow, marker::PhantomData};
+
+use crate::{
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
+    indexes::iter::Entries,
+    views::{IndexAddress, IndexState, IndexType, RawAccess, RawAccessMut, View, ViewWithMetadata},
+    BinaryKey, BinaryValue,
+};
+
+/// Prefix of the keys storing map entries.
+const ENTRY_PREFIX: u8 = 0;
+/// Prefix of the keys storing the expiration queue. Keys in the queue consist
+/// of the big-endian expiration height followed by the key of the entry.
+const EXPIRATION_PREFIX: u8 = 1;
+
+/// Map value together with its expiration height.
+#[derive(Debug)]
+struct TtlEntry<V> {
+    expires_at: u64,
+    value: V,
+}
+
+impl<V: BinaryValue> BinaryValue for TtlEntry<V> {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.expires_at.to_bytes();
+        bytes.extend_from_slice(&self.value.to_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> anyhow::Result<Self> {
+        ensure!(bytes.len() >= 8, "TTL map entry is too short");
+        let (expires_at, value) = bytes.split_at(8);
+        Ok(Self {
+            expires_at: LittleEndian::read_u64(expires_at),
+            value: V::from_bytes(Cow::Borrowed(value))?,
+        })
+    }
+}
+
+/// A map of keys and values with each entry expiring at a certain height.
+///
+/// `TtlMapIndex` tracks the current height, which is set by [`remove_expired`].
+/// Entries whose expiration height does not exceed the current height are considered expired
+/// and are invisible to all reads, even if they are not yet physically removed from the storage.
+/// Expired entries are removed in bulk by [`remove_expired`], or individually when
+/// the corresponding key is overwritten or removed.
+///
+/// Services usually call [`remove_expired`] with the height of the block being created
+/// from `Service::before_transactions`, so that all transactions in the block observe
+/// the same set of live entries.
+///
+/// `TtlMapIndex` requires that keys implement the [`BinaryKey`] trait and
+/// values implement the [`BinaryValue`] trait.
+///
+/// [`remove_expired`]: #method.remove_expired
+/// [`BinaryKey`]: ../trait.BinaryKey.html
+/// [`BinaryValue`]: ../trait.BinaryValue.html
+#[derive(Debug)]
+pub struct TtlMapIndex<T: RawAccess, K: ?Sized, V> {
+    base: View<T>,
+    state: IndexState<T, u64>,
+    _k: PhantomData<K>,
+    _v: PhantomData<V>,
+}
+
+impl<T, K, V> FromAccess<T> for TtlMapIndex<T::Base, K, V>
+where
+    T: Access,
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+{
+    fn from_access(access: T, addr: IndexAddress) -> Result<Self, AccessError> {
+        let view = access.get_or_create_view(addr, IndexType::TtlMap)?;
+        Ok(Self::new(view))
+    }
+}
+
+impl<T, K, V> DescribeSchema for TtlMapIndex<T, K, V>
+where
+    T: RawAccess,
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        vec![IndexDescription::new(name, IndexType::TtlMap)
+            .with_key::<K>()
+            .with_value::<V>()]
+    }
+}
+
+impl<T, K, V> TtlMapIndex<T, K, V>
+where
+    T: RawAccess,
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+{
+    fn new(view: ViewWithMetadata<T>) -> Self {
+        let (base, state) = view.into_parts();
+        Self {
+            base,
+            state,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+
+    fn get_entry(&self, key: &K) -> Option<TtlEntry<V>> {
+        self.base.get(&concat_keys!(&ENTRY_PREFIX, key))
+    }
+
+    fn get_live_entry(&self, key: &K) -> Option<TtlEntry<V>> {
+        let height = self.height();
+        self.get_entry(key)
+            .filter(|entry| entry.expires_at > height)
+    }
+
+    /// Returns the current height of the map, i.e., the height passed to the latest call
+    /// of [`remove_expired`]. Returns 0 if `remove_expired` was never called.
+    ///
+    /// [`remove_expired`]: #method.remove_expired
+    pub fn height(&self) -> u64 {
+        self.state.get().unwrap_or_default()
+    }
+
+    /// Returns a value corresponding to the key, or `None` if the map does not contain
+    /// the key or the corresponding entry has expired.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, TtlMapIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_ttl_map("name");
+    /// index.put(&1, 2, 10);
+    /// assert_eq!(index.get(&1), Some(2));
+    ///
+    /// index.remove_expired(10);
+    /// assert_eq!(index.get(&1), None);
+    /// ```
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.get_live_entry(key).map(|entry| entry.value)
+    }
+
+    /// Returns the expiration height of the entry with the specified key, or `None` if the map
+    /// does not contain the key or the corresponding entry has expired.
+    pub fn expires_at(&self, key: &K) -> Option<u64> {
+        self.get_live_entry(key).map(|entry| entry.expires_at)
+    }
+
+    /// Returns `true` if the map contains a non-expired entry for the specified key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, TtlMapIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_ttl_map("name");
+    /// assert!(!index.contains(&1));
+    ///
+    /// index.put(&1, 2, 10);
+    /// assert!(index.contains(&1));
+    /// ```
+    pub fn contains(&self, key: &K) -> bool {
+        self.get_live_entry(key).is_some()
+    }
+
+    /// Returns an iterator over non-expired entries of the map in ascending order of keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, TtlMapIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_ttl_map::<_, u8, u8>("name");
+    /// index.put(&1, 10, 5);
+    /// index.put(&2, 20, 15);
+    /// index.remove_expired(10);
+    /// assert_eq!(index.iter().collect::<Vec<_>>(), vec![(2, 20)]);
+    /// ```
+    pub fn iter(&self) -> TtlEntries<'_, K, V> {
+        TtlEntries {
+            base_iter: Entries::with_detached_prefix(&self.base, &ENTRY_PREFIX, None),
+            height: self.height(),
+        }
+    }
+
+    /// Returns an iterator over non-expired entries of the map in ascending order of keys
+    /// starting from the specified key.
+    pub fn iter_from(&self, from: &K) -> TtlEntries<'_, K, V> {
+        TtlEntries {
+            base_iter: Entries::with_detached_prefix(&self.base, &ENTRY_PREFIX, Some(from)),
+            height: self.height(),
+        }
+    }
+}
+
+impl<T, K, V> TtlMapIndex<T, K, V>
+where
+    T: RawAccessMut,
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+{
+    /// Inserts a key-value pair into the map, which expires at the specified height.
+    /// If the map already contains an entry with the same key, the entry is overwritten
+    /// together with its expiration height.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, TtlMapIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_ttl_map("name");
+    /// index.put(&1, 2, 10);
+    /// index.put(&1, 3, 20);
+    /// assert_eq!(index.get(&1), Some(3));
+    /// assert_eq!(index.expires_at(&1), Some(20));
+    /// ```
+    pub fn put(&mut self, key: &K, value: V, expires_at: u64) {
+        self.remove_expiration(key);
+        self.base
+            .put(&concat_keys!(&EXPIRATION_PREFIX, &expires_at, key), ());
+        self.base.put(
+            &concat_keys!(&ENTRY_PREFIX, key),
+            TtlEntry { expires_at, value },
+        );
+    }
+
+    /// Removes a key from the map.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, TtlMapIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_ttl_map("name");
+    /// index.put(&1, 2, 10);
+    /// index.remove(&1);
+    /// assert!(!index.contains(&1));
+    /// ```
+    pub fn remove(&mut self, key: &K) {
+        if self.remove_expiration(key) {
+            self.base.remove(&concat_keys!(&ENTRY_PREFIX, key));
+        }
+    }
+
+    /// Sets the current height of the map and removes all entries expiring
+    /// at this height or earlier. Returns the number of removed entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use exonum_merkledb::{access::CopyAccessExt, TemporaryDB, Database, TtlMapIndex};
+    ///
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut index = fork.get_ttl_map::<_, u8, u8>("name");
+    /// index.put(&1, 10, 5);
+    /// index.put(&2, 20, 10);
+    /// index.put(&3, 30, 15);
+    ///
+    /// assert_eq!(index.remove_expired(10), 2);
+    /// assert_eq!(index.height(), 10);
+    /// assert_eq!(index.iter().collect::<Vec<_>>(), vec![(3, 30)]);
+    /// ```
+    pub fn remove_expired(&mut self, height: u64) -> u64 {
+        self.state.set(height);
+
+        let expired: Vec<_> =
+            Entries::<[u8], ()>::with_detached_prefix(&self.base, &EXPIRATION_PREFIX, None)
+                .skip_values()
+                .take_while(|queue_key| u64::read(&queue_key[..8]) <= height)
+                .collect();
+
+        for queue_key in &expired {
+            self.base
+                .remove(&concat_keys!(&EXPIRATION_PREFIX, queue_key));
+            self.base
+                .remove(&concat_keys!(&ENTRY_PREFIX, &queue_key[8..]));
+        }
+        expired.len() as u64
+    }
+
+    /// Clears the map, removing all entries and resetting the current height.
+    ///
+    /// # Notes
+    ///
+    /// Currently, this method is not optimized to delete a large set of data. During the execution of
+    /// this method, the amount of allocated memory is linearly dependent on the number of elements
+    /// in the index.
+    pub fn clear(&mut self) {
+        self.base.clear();
+        self.state.unset();
+    }
+
+    /// Removes the expiration record of the entry with the specified key. Returns `true`
+    /// if the entry exists (expired or not).
+    fn remove_expiration(&mut self, key: &K) -> bool {
+        if let Some(entry) = self.get_entry(key) {
+            self.base
+                .remove(&concat_keys!(&EXPIRATION_PREFIX, &entry.expires_at, key));
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Iterator over non-expired entries of a [`TtlMapIndex`].
+///
+/// This structure is returned by [`TtlMapIndex::iter`] and [`TtlMapIndex::iter_from`].
+///
+/// [`TtlMapIndex`]: struct.TtlMapIndex.html
+/// [`TtlMapIndex::iter`]: struct.TtlMapIndex.html#method.iter
+/// [`TtlMapIndex::iter_from`]: struct.TtlMapIndex.html#method.iter_from
+#[derive(Debug)]
+pub struct TtlEntries<'a, K: ?Sized, V> {
+    base_iter: Entries<'a, K, TtlEntry<V>>,
+    height: u64,
+}
+
+impl<K, V> Iterator for TtlEntries<'_, K, V>
+where
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+{
+    type Item = (K::Owned, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let height = self.height;
+        self.base_iter
+            .find(|(_, entry)| entry.expires_at > height)
+            .map(|(key, entry)| (key, entry.value))
+    }
+}
+
+impl<'a, T, K, V> IntoIterator for &'a TtlMapIndex<T, K, V>
+where
+    T: RawAccess,
+    K: BinaryKey + ?Sized,
+    V: BinaryValue,
+{
+    type Item = (K::Owned, V);
+    type IntoIter = TtlEntries<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TtlMapIndex;
+    use crate::{access::CopyAccessExt, Database, TemporaryDB};
+
+    const INDEX_NAME: &str = "test_index_name";
+
+    #[test]
+    fn entries_expire() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        let mut index: TtlMapIndex<_, str, u64> = fork.get_ttl_map(INDEX_NAME);
+
+        index.put("foo", 1, 3);
+        index.put("bar", 2, 5);
+        index.put("baz", 3, 3);
+        assert_eq!(index.iter().count(), 3);
+
+        assert_eq!(index.remove_expired(2), 0);
+        assert_eq!(index.get("foo"), Some(1));
+        assert_eq!(index.expires_at("foo"), Some(3));
+
+        assert_eq!(index.remove_expired(3), 2);
+        assert_eq!(index.get("foo"), None);
+        assert_eq!(index.expires_at("foo"), None);
+        assert!(!index.contains("baz"));
+        let entries: Vec<_> = index.iter().collect();
+        assert_eq!(entries, vec![("bar".to_owned(), 2)]);
+
+        assert_eq!(index.remove_expired(10), 1);
+        assert_eq!(index.iter().count(), 0);
+        assert_eq!(index.remove_expired(20), 0);
+    }
+
+    #[test]
+    fn expired_entries_are_hidden_before_removal() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        let mut index: TtlMapIndex<_, u32, u32> = fork.get_ttl_map(INDEX_NAME);
+
+        index.remove_expired(10);
+        // The entry expires immediately, but is physically removed only by `remove_expired`.
+        index.put(&1, 1, 10);
+        index.put(&2, 2, 11);
+        assert!(!index.contains(&1));
+        assert_eq!(index.iter().collect::<Vec<_>>(), vec![(2, 2)]);
+        assert_eq!(index.iter_from(&2).collect::<Vec<_>>(), vec![(2, 2)]);
+
+        // Overwriting the entry makes it live again.
+        index.put(&1, 3, 12);
+        assert_eq!(index.get(&1), Some(3));
+        assert_eq!(index.remove_expired(11), 1);
+        assert_eq!(index.iter().collect::<Vec<_>>(), vec![(1, 3)]);
+    }
+
+    #[test]
+    fn overwritten_entries_do_not_expire_early() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        let mut index: TtlMapIndex<_, u32, u32> = fork.get_ttl_map(INDEX_NAME);
+
+        index.put(&1, 1, 5);
+        index.put(&1, 2, 15);
+        assert_eq!(index.remove_expired(10), 0);
+        assert_eq!(index.get(&1), Some(2));
+
+        index.remove(&1);
+        assert_eq!(index.remove_expired(20), 0);
+        // Removing a non-existing key is a no-op.
+        index.remove(&1);
+        assert_eq!(index.iter().count(), 0);
+    }
+
+    #[test]
+    fn clear_resets_height() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        {
+            let mut index: TtlMapIndex<_, u32, u32> = fork.get_ttl_map(INDEX_NAME);
+            index.put(&1, 1, 5);
+            index.remove_expired(3);
+            assert_eq!(index.height(), 3);
+            index.clear();
+            assert_eq!(index.height(), 0);
+            assert!(!index.contains(&1));
+            index.put(&2, 2, 5);
+            index.remove_expired(4);
+        }
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let index: TtlMapIndex<_, u32, u32> = snapshot.get_ttl_map(INDEX_NAME);
+        assert_eq!(index.height(), 4);
+        assert_eq!(index.get(&2), Some(2));
+    }
+}
@@ -23,7 +23,7 @@ use std::marker::PhantomData;
 use exonum_crypto::Hash;
 
 use crate::{
-    access::{Access, AccessError, FromAccess},
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
     indexes::iter::{Entries, IndexIterator, Keys},
     views::{IndexAddress, IndexType, RawAccess, RawAccessMut, View, ViewWithMetadata},
     BinaryValue, ObjectHash,
@@ -52,6 +52,16 @@ where
     }
 }
 
+impl<T, V> DescribeSchema for ValueSetIndex<T, V>
+where
+    T: RawAccess,
+    V: BinaryValue + ObjectHash,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        vec![IndexDescription::new(name, IndexType::ValueSet).with_value::<V>()]
+    }
+}
+
 impl<T, V> ValueSetIndex<T, V>
 where
     T: RawAccess,
@@ -15,7 +15,7 @@
 use std::marker::PhantomData;
 
 use crate::{
-    access::{Access, AccessError, FromAccess},
+    access::{Access, AccessError, DescribeSchema, FromAccess, IndexDescription},
     views::IndexAddress,
 };
 
@@ -71,6 +71,15 @@ where
     }
 }
 
+impl<T, I> DescribeSchema for Lazy<T, I>
+where
+    I: DescribeSchema,
+{
+    fn describe_schema(name: &str) -> Vec<IndexDescription> {
+        I::describe_schema(name)
+    }
+}
+
 impl<T, I> Lazy<T, I>
 where
     T: Access,
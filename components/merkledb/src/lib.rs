@@ -26,7 +26,10 @@
 //! that is, the Exonum process has exclusive access to the DB during blockchain operation.
 //! You can interact with the `Database` from multiple threads by cloning its instance.
 //!
-//! This crate provides two database types: [`RocksDB`] and [`TemporaryDB`].
+//! This crate provides two database types: [`RocksDB`] and [`TemporaryDB`]. Additionally,
+//! an `Lmdb` database optimized for read-heavy workloads is available with the `lmdb` feature.
+//! With the `async` feature, merging patches and reading from snapshots can be offloaded
+//! from asynchronous code with the help of the `asynchronous` module.
 //!
 //! # Snapshot and Fork
 //!
@@ -74,11 +77,24 @@
 //! - [`SparseListIndex`] is a list of items stored in a sequential order. Similar to `ListIndex`,
 //!   but may contain indexes without elements.
 //! - [`MapIndex`] is a map of keys and values. Similar to [`BTreeMap`].
+//! - [`SortedValueIndex`] is a collection of values ordered by a score. Similar to
+//!   [`BinaryHeap`], but supports retrieval of both the lowest and the highest scores,
+//!   removal of arbitrary elements and range queries by score.
+//! - [`TtlMapIndex`] is a map with entries expiring at a certain height. Expired entries
+//!   are invisible to reads and are removed by a maintenance call.
 //! - [`ProofEntry`] is a Merkelized version of `Entry`.
 //! - [`ProofListIndex`] is a Merkelized version of `ListIndex` that supports cryptographic
 //!   proofs of existence and is implemented as a Merkle tree.
 //! - [`ProofMapIndex`] is a Merkelized version of `MapIndex` that supports cryptographic
 //!   proofs of existence and is implemented as a binary Merkle Patricia tree.
+//! - [`IndexedProofMap`] is a `ProofMapIndex` with a secondary index over its values,
+//!   which is maintained automatically and allows to look up keys by a part of the value.
+//! - [`SparseMerkleIndex`] is a Merkelized map implemented as a fixed-depth sparse Merkle tree
+//!   with constant-shape proofs. It is useful for interoperability with other systems
+//!   based on sparse Merkle trees.
+//! - [`PatriciaTrieIndex`] is a Merkelized map implemented as the Merkle Patricia trie
+//!   from Ethereum. Its root hash and proofs are compatible with Ethereum tries, which is useful
+//!   for services bridging to Ethereum.
 //! - [`KeySetIndex`] and [`ValueSetIndex`] are sets of items, similar to [`BTreeSet`] and
 //!   [`HashSet`] accordingly.
 //!
@@ -93,7 +109,8 @@
 //! as per [`ObjectHash`] implementation. An index is aggregated if and only if it satisfies
 //! the following constraints:
 //!
-//! - Index has a matching type (`ProofListIndex`, `ProofMapIndex`, or `ProofEntry`)
+//! - Index has a matching type (`ProofListIndex`, `ProofMapIndex`, `SparseMerkleIndex`,
+//!   `PatriciaTrieIndex`, or `ProofEntry`)
 //! - Index is not a part of a group, i.e., its address does not contain the `bytes` part
 //!
 //! The aggregation is automatically updated when a `Fork` is converted into a `Patch`.
@@ -107,6 +124,12 @@
 //! of migration, it is possible to gradually accumulate changes to a set of indexes (including
 //! across process restarts) and then atomically apply or discard these changes.
 //!
+//! # Replication
+//!
+//! Patches merged into a database can be [streamed](replication/index.html) to read replicas.
+//! A replica applying the streamed patches in order maintains the same state as the primary
+//! database.
+//!
 //! [`Database`]: trait.Database.html
 //! [`RocksDB`]: struct.RocksDB.html
 //! [`TemporaryDB`]: struct.TemporaryDB.html
@@ -123,8 +146,13 @@
 //! [`ListIndex`]: indexes/struct.ListIndex.html
 //! [`SparseListIndex`]: indexes/struct.SparseListIndex.html
 //! [`MapIndex`]: indexes/struct.MapIndex.html
+//! [`SortedValueIndex`]: indexes/struct.SortedValueIndex.html
+//! [`TtlMapIndex`]: indexes/struct.TtlMapIndex.html
+//! [`IndexedProofMap`]: indexes/struct.IndexedProofMap.html
 //! [`ProofListIndex`]: indexes/proof_list/struct.ProofListIndex.html
 //! [`ProofMapIndex`]: indexes/proof_map/struct.ProofMapIndex.html
+//! [`SparseMerkleIndex`]: indexes/sparse_merkle/struct.SparseMerkleIndex.html
+//! [`PatriciaTrieIndex`]: indexes/patricia_trie/struct.PatriciaTrieIndex.html
 //! [`KeySetIndex`]: indexes/struct.KeySetIndex.html
 //! [`ValueSetIndex`]: indexes/struct.ValueSetIndex.html
 //! [`ObjectHash`]: trait.ObjectHash.html
@@ -133,6 +161,7 @@
 //! [`Box`]: https://doc.rust-lang.org/std/boxed/struct.Box.html
 //! [`Vec`]: https://doc.rust-lang.org/std/vec/struct.Vec.html
 //! [`BTreeMap`]: https://doc.rust-lang.org/std/collections/struct.BTreeMap.html
+//! [`BinaryHeap`]: https://doc.rust-lang.org/std/collections/struct.BinaryHeap.html
 //! [`BTreeSet`]: https://doc.rust-lang.org/std/collections/struct.BTreeSet.html
 //! [`HashSet`]: https://doc.rust-lang.org/std/collections/struct.HashSet.html
 //! [`state_aggregator`]: struct.SystemSchema.html#method.state_aggregator
@@ -167,32 +196,49 @@ pub mod _reexports {
     pub use exonum_crypto::{hash, Hash};
 }
 
+#[cfg(feature = "lmdb")]
+pub use self::backends::lmdb::{Lmdb, LmdbOptions};
 pub use self::{
-    backends::{rocksdb::RocksDB, temporarydb::TemporaryDB},
+    backends::{
+        copy::copy_database,
+        encryption::{EncryptionKeys, ENCRYPTION_KEY_LENGTH},
+        gc::{orphaned_addresses, remove_orphaned_data},
+        rocksdb::RocksDB,
+        temporarydb::TemporaryDB,
+        verify::{verify_database, IndexMismatch, IntegrityReport},
+    },
     db::{
-        Database, DatabaseExt, Fork, Iter, Iterator, OwnedReadonlyFork, Patch, ReadonlyFork,
-        Snapshot,
+        Database, DatabaseExt, Fork, IndexChanges, Iter, Iterator, OwnedReadonlyFork, Patch,
+        ReadonlyFork, Snapshot, StallStats, ValueRef,
     },
     error::Error,
     hash::{root_hash, HashTag, ObjectHash, ValidationError},
     keys::BinaryKey,
     lazy::Lazy,
-    options::DbOptions,
+    options::{CompressionType, DbOptions, EncryptionOptions},
     values::BinaryValue,
-    views::{AsReadonly, IndexAddress, IndexType, ResolvedAddress, SystemSchema},
+    views::{
+        AccessPattern, AsReadonly, IndexAddress, IndexSize, IndexType, ResolvedAddress,
+        SystemSchema,
+    },
 };
 // Workaround for 'Linked file at path {exonum_merkledb_path}/struct.ProofMapIndex.html
 // does not exist!'
 #[doc(no_inline)]
 pub use self::indexes::{
+    patricia_trie::{self, PatriciaTrieIndex, PatriciaTrieProof},
     proof_list::{self, ListProof, ProofListIndex},
     proof_map::{self, MapProof, ProofMapIndex, RawProofMapIndex},
-    Entry, Group, KeySetIndex, ListIndex, MapIndex, ProofEntry, SparseListIndex, ValueSetIndex,
+    sparse_merkle::{self, SparseMerkleIndex, SparseMerkleProof},
+    Entry, Group, IndexedProofMap, KeySetIndex, ListIndex, MapIndex, ProofEntry, SortedValueIndex,
+    SparseListIndex, TtlMapIndex, ValueSetIndex,
 };
 
 #[macro_use]
 mod macros;
 pub mod access;
+#[cfg(feature = "async")]
+pub mod asynchronous;
 mod backends;
 mod db;
 mod error;
@@ -201,8 +247,10 @@ mod hash;
 pub mod indexes;
 mod keys;
 mod lazy;
+pub mod metrics;
 pub mod migration;
 mod options;
+pub mod replication;
 pub mod validation;
 mod values;
 mod views;
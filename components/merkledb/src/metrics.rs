@@ -0,0 +1,496 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage metrics.
+//!
+//! A database wrapped into a [`MetricsDb`] collects metrics of its usage: the number of reads
+//! and iterator steps performed via snapshots and forks, latencies of merges, sizes of merged
+//! patches and the number of bytes written to each index. The metrics can be accessed
+//! via a [`DatabaseMetrics`] handle, e.g., to publish them in the Prometheus text format.
+//!
+//! The metrics are node-local and are reset when the database is reopened.
+//!
+//! # Examples
+//!
+//! ```
+//! # use exonum_merkledb::{access::CopyAccessExt, metrics::MetricsDb, Database, TemporaryDB};
+//! let db = MetricsDb::new(TemporaryDB::new());
+//! let metrics = db.metrics();
+//!
+//! let fork = db.fork();
+//! fork.get_list("list").extend(vec![1_u32, 2, 3]);
+//! db.merge(fork.into_patch()).unwrap();
+//! assert_eq!(metrics.merges(), 1);
+//! assert!(metrics.index_bytes()["list"] > 0);
+//!
+//! let snapshot = db.snapshot();
+//! assert_eq!(snapshot.get_list::<_, u32>("list").iter().count(), 3);
+//! assert!(metrics.iterator_steps() >= 3);
+//! println!("{}", metrics.render());
+//! ```
+//!
+//! [`MetricsDb`]: struct.MetricsDb.html
+//! [`DatabaseMetrics`]: struct.DatabaseMetrics.html
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write as _},
+    ops::Bound,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+
+use crate::{
+    db::{Change, Iterator as DbIterator},
+    Database, Iter, Patch, ResolvedAddress, Result, Snapshot, StallStats, ValueRef,
+};
+
+/// Upper bounds of the merge latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 9] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.5, 1.0];
+/// Upper bounds of the patch size histogram buckets, in bytes.
+const SIZE_BUCKETS: [f64; 9] = [
+    1_024.0,
+    4_096.0,
+    16_384.0,
+    65_536.0,
+    262_144.0,
+    1_048_576.0,
+    4_194_304.0,
+    16_777_216.0,
+    67_108_864.0,
+];
+
+#[derive(Debug)]
+struct Histogram {
+    bounds: &'static [f64],
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: vec![0; bounds.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bucket, &bound) in self.buckets.iter_mut().zip(self.bounds) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn write(&self, out: &mut String, name: &str, help: &str) -> fmt::Result {
+        writeln!(out, "# HELP {} {}", name, help)?;
+        writeln!(out, "# TYPE {} histogram", name)?;
+        for (count, bound) in self.buckets.iter().zip(self.bounds) {
+            writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, count)?;
+        }
+        writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, self.count)?;
+        writeln!(out, "{}_sum {}", name, self.sum)?;
+        writeln!(out, "{}_count {}", name, self.count)
+    }
+}
+
+#[derive(Debug)]
+struct MetricsInner {
+    gets: AtomicU64,
+    iterator_steps: AtomicU64,
+    merge_latency: Mutex<Histogram>,
+    patch_sizes: Mutex<Histogram>,
+    /// Number of bytes written to indexes, keyed by the index name.
+    index_bytes: Mutex<BTreeMap<String, u64>>,
+}
+
+/// Handle to the metrics collected by a [`MetricsDb`]. The handle can be cloned and shared
+/// among threads.
+///
+/// [`MetricsDb`]: struct.MetricsDb.html
+#[derive(Debug, Clone)]
+pub struct DatabaseMetrics {
+    inner: Arc<MetricsInner>,
+}
+
+impl Default for DatabaseMetrics {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(MetricsInner {
+                gets: AtomicU64::new(0),
+                iterator_steps: AtomicU64::new(0),
+                merge_latency: Mutex::new(Histogram::new(&LATENCY_BUCKETS)),
+                patch_sizes: Mutex::new(Histogram::new(&SIZE_BUCKETS)),
+                index_bytes: Mutex::new(BTreeMap::new()),
+            }),
+        }
+    }
+}
+
+impl DatabaseMetrics {
+    /// Returns the number of point reads (including existence checks) from the database.
+    pub fn gets(&self) -> u64 {
+        self.inner.gets.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of steps made by database iterators.
+    pub fn iterator_steps(&self) -> u64 {
+        self.inner.iterator_steps.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of successful merges. A batch of patches merged
+    /// via `Database::merge_batch` is counted as a single merge.
+    pub fn merges(&self) -> u64 {
+        self.inner.merge_latency.lock().unwrap().count
+    }
+
+    /// Returns the total number of bytes in keys and values of the merged patches.
+    pub fn patch_bytes(&self) -> u64 {
+        self.inner.patch_sizes.lock().unwrap().sum as u64
+    }
+
+    /// Returns the number of bytes in keys and values written to each index, keyed
+    /// by the index name. Indexes in a group are accounted together.
+    pub fn index_bytes(&self) -> BTreeMap<String, u64> {
+        self.inner.index_bytes.lock().unwrap().clone()
+    }
+
+    /// Renders metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out).expect("Cannot render storage metrics");
+        out
+    }
+
+    fn write(&self, out: &mut String) -> fmt::Result {
+        write_counter(
+            out,
+            "exonum_merkledb_gets_total",
+            "Number of point reads from the database.",
+            self.gets(),
+        )?;
+        write_counter(
+            out,
+            "exonum_merkledb_iterator_steps_total",
+            "Number of steps made by database iterators.",
+            self.iterator_steps(),
+        )?;
+        self.inner.merge_latency.lock().unwrap().write(
+            out,
+            "exonum_merkledb_merge_latency_seconds",
+            "Latency of merging patches into the database.",
+        )?;
+        self.inner.patch_sizes.lock().unwrap().write(
+            out,
+            "exonum_merkledb_patch_size_bytes",
+            "Size of keys and values in the merged patches.",
+        )?;
+
+        let name = "exonum_merkledb_index_written_bytes_total";
+        writeln!(
+            out,
+            "# HELP {} Number of bytes in keys and values written to indexes.",
+            name
+        )?;
+        writeln!(out, "# TYPE {} counter", name)?;
+        for (index, bytes) in &*self.inner.index_bytes.lock().unwrap() {
+            writeln!(out, "{}{{index=\"{}\"}} {}", name, index, bytes)?;
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::cast_precision_loss)] // Patch sizes are far below 2^52 bytes.
+    fn record_merge(&self, patch_sizes: Vec<(u64, BTreeMap<String, u64>)>, start: Instant) {
+        let latency = start.elapsed().as_secs_f64();
+        self.inner.merge_latency.lock().unwrap().observe(latency);
+
+        let mut sizes = self.inner.patch_sizes.lock().unwrap();
+        let mut index_bytes = self.inner.index_bytes.lock().unwrap();
+        for (size, written_bytes) in patch_sizes {
+            sizes.observe(size as f64);
+            for (name, bytes) in written_bytes {
+                *index_bytes.entry(name).or_default() += bytes;
+            }
+        }
+    }
+}
+
+fn write_counter(out: &mut String, name: &str, help: &str, value: u64) -> fmt::Result {
+    writeln!(out, "# HELP {} {}", name, help)?;
+    writeln!(out, "# TYPE {} counter", name)?;
+    writeln!(out, "{} {}", name, value)
+}
+
+/// Computes the total size of the patch and the number of bytes written to each index.
+/// Deleted keys are accounted with the size of the key.
+fn patch_size(patch: &Patch) -> (u64, BTreeMap<String, u64>) {
+    let mut total = 0;
+    let mut written_bytes = BTreeMap::new();
+    for (address, changes) in patch.changes() {
+        let bytes: u64 = changes
+            .data
+            .iter()
+            .map(|(key, change)| match change {
+                Change::Put(value) => (key.len() + value.len()) as u64,
+                Change::Delete => key.len() as u64,
+            })
+            .sum();
+        if bytes > 0 {
+            *written_bytes.entry(address.name.clone()).or_default() += bytes;
+            total += bytes;
+        }
+    }
+    (total, written_bytes)
+}
+
+/// Database wrapper collecting storage metrics.
+///
+/// See the [module docs](index.html) for an example of usage.
+pub struct MetricsDb {
+    db: Arc<dyn Database>,
+    metrics: DatabaseMetrics,
+}
+
+impl fmt::Debug for MetricsDb {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("MetricsDb")
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
+impl MetricsDb {
+    /// Wraps the provided database.
+    pub fn new(db: impl Into<Arc<dyn Database>>) -> Self {
+        Self {
+            db: db.into(),
+            metrics: DatabaseMetrics::default(),
+        }
+    }
+
+    /// Returns a handle to the metrics of this database.
+    pub fn metrics(&self) -> DatabaseMetrics {
+        self.metrics.clone()
+    }
+
+    fn merge_inner<F>(&self, patches: Vec<Patch>, merge: F) -> Result<()>
+    where
+        F: FnOnce(&dyn Database, Vec<Patch>) -> Result<()>,
+    {
+        let patch_sizes = patches.iter().map(patch_size).collect();
+        let start = Instant::now();
+        merge(&*self.db, patches)?;
+        self.metrics.record_merge(patch_sizes, start);
+        Ok(())
+    }
+}
+
+impl Database for MetricsDb {
+    fn snapshot(&self) -> Box<dyn Snapshot> {
+        Box::new(MetricsSnapshot {
+            inner: self.db.snapshot(),
+            metrics: self.metrics.clone(),
+        })
+    }
+
+    fn merge(&self, patch: Patch) -> Result<()> {
+        self.merge_inner(vec![patch], |db, mut patches| db.merge(patches.remove(0)))
+    }
+
+    fn merge_sync(&self, patch: Patch) -> Result<()> {
+        self.merge_inner(vec![patch], |db, mut patches| {
+            db.merge_sync(patches.remove(0))
+        })
+    }
+
+    fn merge_batch(&self, patches: Vec<Patch>) -> Result<()> {
+        self.merge_inner(patches, |db, patches| db.merge_batch(patches))
+    }
+
+    fn backup(&self, path: &Path) -> Result<()> {
+        self.db.backup(path)
+    }
+
+    fn checkpoint(&self, name: &str) -> Result<()> {
+        self.db.checkpoint(name)
+    }
+
+    fn checkpoint_snapshot(&self, name: &str) -> Result<Box<dyn Snapshot>> {
+        self.db.checkpoint_snapshot(name)
+    }
+
+    fn compact_range(&self, name: Option<&str>) -> Result<()> {
+        self.db.compact_range(name)
+    }
+
+    fn stall_stats(&self) -> Result<StallStats> {
+        self.db.stall_stats()
+    }
+
+    fn set_background_jobs_limit(&self, limit: u32) -> Result<()> {
+        self.db.set_background_jobs_limit(limit)
+    }
+
+    fn stored_addresses(&self) -> Result<Vec<ResolvedAddress>> {
+        self.db.stored_addresses()
+    }
+}
+
+impl From<MetricsDb> for Arc<dyn Database> {
+    fn from(db: MetricsDb) -> Self {
+        Arc::new(db)
+    }
+}
+
+/// Snapshot counting reads and iterator steps.
+struct MetricsSnapshot {
+    inner: Box<dyn Snapshot>,
+    metrics: DatabaseMetrics,
+}
+
+impl MetricsSnapshot {
+    fn count_get(&self) {
+        self.metrics.inner.gets.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn counting_iter<'a>(&'a self, inner: Iter<'a>) -> Iter<'a> {
+        Box::new(CountingIter {
+            inner,
+            steps: &self.metrics.inner.iterator_steps,
+        })
+    }
+}
+
+impl Snapshot for MetricsSnapshot {
+    fn get(&self, name: &ResolvedAddress, key: &[u8]) -> Option<Vec<u8>> {
+        self.count_get();
+        self.inner.get(name, key)
+    }
+
+    fn contains(&self, name: &ResolvedAddress, key: &[u8]) -> bool {
+        self.count_get();
+        self.inner.contains(name, key)
+    }
+
+    fn get_ref(&self, name: &ResolvedAddress, key: &[u8]) -> Option<ValueRef<'_>> {
+        self.count_get();
+        self.inner.get_ref(name, key)
+    }
+
+    fn multi_get(&self, name: &ResolvedAddress, keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+        self.metrics
+            .inner
+            .gets
+            .fetch_add(keys.len() as u64, Ordering::Relaxed);
+        self.inner.multi_get(name, keys)
+    }
+
+    fn iter(&self, name: &ResolvedAddress, from: &[u8]) -> Iter<'_> {
+        self.counting_iter(self.inner.iter(name, from))
+    }
+
+    fn iter_rev(&self, name: &ResolvedAddress, upper: Bound<&[u8]>) -> Iter<'_> {
+        self.counting_iter(self.inner.iter_rev(name, upper))
+    }
+}
+
+struct CountingIter<'a> {
+    inner: Iter<'a>,
+    steps: &'a AtomicU64,
+}
+
+impl DbIterator for CountingIter<'_> {
+    fn next(&mut self) -> Option<(&[u8], &[u8])> {
+        let item = self.inner.next();
+        if item.is_some() {
+            self.steps.fetch_add(1, Ordering::Relaxed);
+        }
+        item
+    }
+
+    fn peek(&mut self) -> Option<(&[u8], &[u8])> {
+        self.inner.peek()
+    }
+
+    fn seek(&mut self, key: &[u8]) {
+        self.inner.seek(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{access::CopyAccessExt, TemporaryDB};
+
+    #[test]
+    fn metrics_are_collected() {
+        let db = MetricsDb::new(TemporaryDB::new());
+        let metrics = db.metrics();
+
+        let fork = db.fork();
+        fork.get_map("map").put(&1_u64, "value".to_owned());
+        fork.get_entry("entry").set(1_u8);
+        db.merge(fork.into_patch()).unwrap();
+        assert_eq!(metrics.merges(), 1);
+        let index_bytes = metrics.index_bytes();
+        // Key (8 bytes) and value (5 bytes).
+        assert_eq!(index_bytes["map"], 13);
+        assert_eq!(index_bytes["entry"], 1);
+        assert!(metrics.patch_bytes() >= 14);
+
+        let patches = (0_u8..3)
+            .map(|i| {
+                let fork = db.fork();
+                fork.get_entry("entry").set(i);
+                fork.into_patch()
+            })
+            .collect();
+        db.merge_batch(patches).unwrap();
+        assert_eq!(metrics.merges(), 2);
+        assert_eq!(metrics.index_bytes()["entry"], 4);
+
+        let gets = metrics.gets();
+        let snapshot = db.snapshot();
+        assert_eq!(snapshot.get_entry::<_, u8>("entry").get(), Some(2));
+        assert!(metrics.gets() > gets);
+        let steps = metrics.iterator_steps();
+        assert_eq!(snapshot.get_map::<_, u64, String>("map").iter().count(), 1);
+        assert!(metrics.iterator_steps() > steps);
+    }
+
+    #[test]
+    fn metrics_rendering() {
+        let db = MetricsDb::new(TemporaryDB::new());
+        let fork = db.fork();
+        fork.get_list("list").push(1_u32);
+        db.merge_sync(fork.into_patch()).unwrap();
+
+        let out = db.metrics().render();
+        assert!(out.contains("# TYPE exonum_merkledb_gets_total counter\n"));
+        assert!(out.contains("exonum_merkledb_merge_latency_seconds_count 1\n"));
+        assert!(out.contains("exonum_merkledb_patch_size_bytes_bucket{le=\"1024\"} 1\n"));
+        assert!(out.contains("exonum_merkledb_index_written_bytes_total{index=\"list\"} "));
+    }
+}
@@ -550,11 +550,74 @@ impl MigrationHelper {
     /// If no iterators are instantiated within the closure, a single iteration will be performed.
     pub fn iter_loop(
         &mut self,
+        step: impl FnMut(&Self, &mut PersistentIters<Scratchpad<&Fork>>),
+    ) -> Result<(), MigrationError> {
+        self.run_loop(None, step)
+    }
+
+    /// Executes the provided closure in a loop similarly to [`iter_loop`], but limits
+    /// each persistent iterator instantiated within the closure to `persist_every_n_entries`
+    /// entries per iteration. Thus, the migration progress is persisted to the database
+    /// at least once per `persist_every_n_entries` entries of each iterator. If the migration
+    /// script is restarted (e.g., after the node restart), iteration resumes from the last
+    /// persisted position.
+    ///
+    /// Unlike using the `take` adapter in [`iter_loop`], the closure may consume iterators
+    /// fully; they will pause automatically.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `persist_every_n_entries` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum_merkledb::{access::{AccessExt, CopyAccessExt}, Database, TemporaryDB};
+    /// # use exonum_merkledb::migration::{MigrationHelper, MigrationError};
+    /// # fn main() -> Result<(), MigrationError> {
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// fork.get_list("test.list").extend(0_u64..1_000);
+    /// db.merge(fork.into_patch()).unwrap();
+    ///
+    /// let mut helper = MigrationHelper::new(db, "test");
+    /// helper.iter_loop_with_checkpoints(100, |helper, iters| {
+    ///     let old_list = helper.old_data().get_list::<_, u64>("list");
+    ///     let mut new_list = helper.new_data().get_proof_list::<_, u64>("list");
+    ///     // The iterator yields at most 100 items; the progress is then merged to the DB.
+    ///     for (_, item) in iters.create("list", &old_list) {
+    ///         new_list.push(item * 2);
+    ///     }
+    /// })?;
+    /// assert_eq!(helper.new_data().get_proof_list::<_, u64>("list").len(), 1_000);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`iter_loop`]: #method.iter_loop
+    pub fn iter_loop_with_checkpoints(
+        &mut self,
+        persist_every_n_entries: usize,
+        step: impl FnMut(&Self, &mut PersistentIters<Scratchpad<&Fork>>),
+    ) -> Result<(), MigrationError> {
+        assert!(
+            persist_every_n_entries > 0,
+            "Number of entries between checkpoints must be positive"
+        );
+        self.run_loop(Some(persist_every_n_entries), step)
+    }
+
+    fn run_loop(
+        &mut self,
+        chunk_size: Option<usize>,
         mut step: impl FnMut(&Self, &mut PersistentIters<Scratchpad<&Fork>>),
     ) -> Result<(), MigrationError> {
         let mut should_break = false;
         while !should_break {
-            let mut iterators = PersistentIters::new(self.scratchpad());
+            let mut iterators = match chunk_size {
+                Some(chunk_size) => PersistentIters::chunked(self.scratchpad(), chunk_size),
+                None => PersistentIters::new(self.scratchpad()),
+            };
             step(self, &mut iterators);
             should_break = iterators.all_ended();
             self.merge()?;
@@ -690,9 +753,9 @@ pub fn rollback_migration(fork: &mut Fork, namespace: &str) {
 #[cfg(test)]
 mod tests {
     use super::{
-        flush_migration, rollback_migration, AbortHandle, Arc, Database, Hash, IndexAddress,
-        IndexType, Migration, MigrationError, MigrationHelper, Scratchpad, ViewWithMetadata,
-        SCRATCHPAD_NAME,
+        flush_migration, rollback_migration, AbortHandle, AbortMigration, Arc, Database, Hash,
+        IndexAddress, IndexType, Migration, MigrationError, MigrationHelper, Scratchpad,
+        ViewWithMetadata, SCRATCHPAD_NAME,
     };
     use crate::{
         access::{AccessExt, CopyAccessExt, RawAccess},
@@ -700,7 +763,15 @@ mod tests {
     };
 
     use assert_matches::assert_matches;
-    use std::{collections::HashMap, sync::mpsc, thread, time::Duration};
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc,
+        },
+        thread,
+        time::Duration,
+    };
 
     #[test]
     fn in_memory_migration() {
@@ -1153,6 +1224,60 @@ mod tests {
         Ok(())
     }
 
+    #[derive(Debug)]
+    struct AbortAfterMerges(Arc<AtomicUsize>);
+
+    impl AbortMigration for AbortAfterMerges {
+        fn is_aborted(&self) -> bool {
+            self.0.fetch_sub(1, Ordering::SeqCst) == 0
+        }
+    }
+
+    #[test]
+    fn loop_iter_with_checkpoints_resumes_after_restart() -> Result<(), MigrationError> {
+        const CHUNK_SIZE: usize = 3;
+
+        let db = Arc::new(TemporaryDB::new());
+        let fork = db.fork();
+        fork.get_list("test.list").extend(0_u64..10);
+        db.merge(fork.into_patch()).unwrap();
+
+        // The first run is interrupted after 2 chunks are merged.
+        let mut helper = MigrationHelper::new(Arc::clone(&db) as Arc<dyn Database>, "test");
+        helper.set_abort_handle(AbortAfterMerges(Arc::new(AtomicUsize::new(2))));
+        let mut first_run = vec![];
+        let res = helper.iter_loop_with_checkpoints(CHUNK_SIZE, |helper, iters| {
+            let list = helper.old_data().get_list::<_, u64>("list");
+            let mut new_list = helper.new_data().get_proof_list::<_, u64>("list");
+            for (_, item) in iters.create("list", &list) {
+                first_run.push(item);
+                new_list.push(item + 1);
+            }
+        });
+        assert_matches!(res, Err(MigrationError::Aborted));
+        assert_eq!(first_run, (0..9).collect::<Vec<_>>());
+
+        // The restarted run continues from the last persisted entry.
+        let mut helper = MigrationHelper::new(Arc::clone(&db) as Arc<dyn Database>, "test");
+        let mut second_run = vec![];
+        helper.iter_loop_with_checkpoints(CHUNK_SIZE, |helper, iters| {
+            let list = helper.old_data().get_list::<_, u64>("list");
+            let mut new_list = helper.new_data().get_proof_list::<_, u64>("list");
+            for (_, item) in iters.create("list", &list) {
+                second_run.push(item);
+                new_list.push(item + 1);
+            }
+        })?;
+        assert_eq!(second_run, vec![6, 7, 8, 9]);
+
+        let new_list = helper.new_data().get_proof_list::<_, u64>("list");
+        assert_eq!(
+            new_list.iter().collect::<Vec<_>>(),
+            (1..=10).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
     struct MigrationRig {
         thread_handle: thread::JoinHandle<Result<Hash, MigrationError>>,
         abort_handle: AbortHandle,
@@ -136,6 +136,8 @@ where
 /// [`MigrationHelper`]: struct.MigrationHelper.html
 pub struct PersistentIter<'a, T: RawAccess, I: IndexIterator> {
     inner: Inner<'a, T, I>,
+    /// Number of entries the iterator may yield before pausing, if limited.
+    remaining: Option<usize>,
 }
 
 impl<T, I> fmt::Debug for PersistentIter<'_, T, I>
@@ -148,6 +150,7 @@ where
         formatter
             .debug_struct("PersistentIter")
             .field("inner", &self.inner)
+            .field("remaining", &self.remaining)
             .finish()
     }
 }
@@ -200,6 +203,7 @@ where
             Some(IteratorPosition::Ended) => {
                 return Self {
                     inner: Inner::Ended,
+                    remaining: None,
                 };
             }
         };
@@ -211,9 +215,26 @@ where
                     .peekable(),
                 position_entry,
             },
+            remaining: None,
         }
     }
 
+    /// Limits the number of entries yielded by the iterator to `chunk_size`. Once the chunk
+    /// is exhausted, the iterator pauses: it returns `None`, but is not considered ended,
+    /// and a recreated iterator resumes from the next entry.
+    ///
+    /// Unlike the `take` adapter, the pause does not advance the iterator past an entry
+    /// that was not yielded.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if `chunk_size` is zero.
+    pub fn chunked(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "Chunk size must be positive");
+        self.remaining = Some(chunk_size);
+        self
+    }
+
     /// Skips values in the iterator output without parsing them.
     pub fn skip_values(self) -> PersistentKeys<'a, T, I> {
         PersistentKeys { base_iter: self }
@@ -228,6 +249,10 @@ where
     type Item = (<I::Key as ToOwned>::Owned, I::Value);
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == Some(0) {
+            return None;
+        }
+
         if let Inner::Active {
             ref mut iter,
             ref mut position_entry,
@@ -245,6 +270,9 @@ where
                 position_entry.set(IteratorPosition::Ended);
                 self.inner = Inner::Ended;
             }
+            if let Some(remaining) = self.remaining.as_mut() {
+                *remaining -= 1;
+            }
             next
         } else {
             None
@@ -307,6 +335,7 @@ where
 pub struct PersistentIters<T> {
     access: T,
     names: HashSet<String>,
+    chunk_size: Option<usize>,
 }
 
 impl<T> PersistentIters<T>
@@ -319,6 +348,19 @@ where
         Self {
             access,
             names: HashSet::new(),
+            chunk_size: None,
+        }
+    }
+
+    /// Creates a new factory, iterators from which yield at most `chunk_size` entries
+    /// each. See [`PersistentIter::chunked`] for details.
+    ///
+    /// [`PersistentIter::chunked`]: struct.PersistentIter.html#method.chunked
+    pub fn chunked(access: T, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "Chunk size must be positive");
+        Self {
+            chunk_size: Some(chunk_size),
+            ..Self::new(access)
         }
     }
 
@@ -329,7 +371,12 @@ where
         index: &'a I,
     ) -> PersistentIter<'a, T::Base, I> {
         self.names.insert(name.to_owned());
-        PersistentIter::new(&self.access, name, index)
+        let iter = PersistentIter::new(&self.access, name, index);
+        if let Some(chunk_size) = self.chunk_size {
+            iter.chunked(chunk_size)
+        } else {
+            iter
+        }
     }
 
     /// Checks if all iterators instantiated via this instance have ended.
@@ -466,6 +513,33 @@ mod tests {
         assert_eq!(iter.count(), 2);
     }
 
+    #[test]
+    fn chunked_persistent_iter() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        let mut list = fork.get_list("list");
+        list.extend((0_u32..10).map(|i| i.to_string()));
+
+        let scratchpad = Scratchpad::new("iter", &fork);
+        let mut iter = PersistentIter::new(&scratchpad, "list", &list).chunked(4);
+        let indexes: Vec<_> = iter.by_ref().map(|(i, _)| i).collect();
+        assert_eq!(indexes, vec![0, 1, 2, 3]);
+        // The iterator is paused rather than ended.
+        assert!(iter.next().is_none());
+        drop(iter);
+        {
+            let position_entry = scratchpad.get_entry::<_, IteratorPosition<u64>>("list");
+            assert_eq!(position_entry.get(), Some(IteratorPosition::NextKey(4)));
+        }
+
+        let iter = PersistentIter::new(&scratchpad, "list", &list).chunked(4);
+        assert_eq!(iter.count(), 4);
+        let iter = PersistentIter::new(&scratchpad, "list", &list).chunked(4);
+        assert_eq!(iter.count(), 2);
+        let position_entry = scratchpad.get_entry::<_, IteratorPosition<u64>>("list");
+        assert_eq!(position_entry.get(), Some(IteratorPosition::Ended));
+    }
+
     #[test]
     fn empty_persistent_iter() {
         let db = TemporaryDB::new();
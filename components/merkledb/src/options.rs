@@ -20,7 +20,7 @@ use serde::{Deserialize, Serialize};
 /// Options for the database.
 ///
 /// These parameters apply to the underlying database of Exonum, currently `RocksDB`.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[non_exhaustive]
 pub struct DbOptions {
     /// Number of open files that can be used by the database.
@@ -51,6 +51,67 @@ pub struct DbOptions {
     /// Defaults to `None`, meaning that the size of WAL journal will be adjusted
     /// by the rocksdb.
     pub max_total_wal_size: Option<u64>,
+    /// Size of the LRU cache for uncompressed data blocks in bytes. The cache is shared
+    /// among all column families of the database.
+    ///
+    /// Defaults to `None`, meaning that the cache size is chosen by the rocksdb (8 MB).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_cache_size: Option<usize>,
+    /// Compression algorithms for the levels of the LSM tree, starting from level 0.
+    /// Levels not covered by the list use the algorithm for the last listed level.
+    ///
+    /// Defaults to an empty list, meaning that `compression_type` is used for all levels.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compression_per_level: Vec<CompressionType>,
+    /// Number of bits per key in the bloom filters of data blocks. Bloom filters speed up
+    /// lookups of missing keys at the cost of memory; 10 bits per key yield
+    /// approximately 1% false positive rate.
+    ///
+    /// Defaults to `None`, meaning that bloom filters are not used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bloom_filter_bits: Option<u32>,
+    /// Size of a single in-memory write buffer (memtable) in bytes.
+    ///
+    /// Defaults to `None`, meaning that the size is chosen by the rocksdb (64 MB).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write_buffer_size: Option<usize>,
+    /// Maximum number of write buffers built up in memory before writes are stalled.
+    ///
+    /// Defaults to `None`, meaning that the number is chosen by the rocksdb (2).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_write_buffer_number: Option<i32>,
+    /// Minimum number of write buffers merged together before being flushed to the storage.
+    ///
+    /// Defaults to `None`, meaning that the number is chosen by the rocksdb (1).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_write_buffer_number_to_merge: Option<i32>,
+    /// Encryption of the stored values.
+    ///
+    /// Defaults to `None`, meaning that values are stored unencrypted. If set, the database
+    /// needs to be opened with [`RocksDB::open_encrypted`].
+    ///
+    /// [`RocksDB::open_encrypted`]: struct.RocksDB.html#method.open_encrypted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionOptions>,
+    /// Whether column families should be tuned according to the access patterns of indexes
+    /// stored in them (see [`AccessPattern`]). Column families of indexes mostly accessed
+    /// by keys (e.g., `ProofMapIndex`) use whole-key bloom filters, and column families
+    /// of indexes mostly iterated over (e.g., `ProofListIndex`) use prefix extractors
+    /// with prefix bloom filters. The number of bits per key in the filters is taken from
+    /// `bloom_filter_bits`, or equals 10 if that option is not set.
+    ///
+    /// Access patterns of column families are recorded regardless of this option,
+    /// so the tuning can be enabled for an existing database.
+    ///
+    /// Defaults to `false`.
+    ///
+    /// [`AccessPattern`]: enum.AccessPattern.html
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub access_pattern_tuning: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 impl DbOptions {
@@ -66,8 +127,136 @@ impl DbOptions {
             create_if_missing,
             compression_type,
             max_total_wal_size,
+            block_cache_size: None,
+            compression_per_level: vec![],
+            bloom_filter_bits: None,
+            write_buffer_size: None,
+            max_write_buffer_number: None,
+            min_write_buffer_number_to_merge: None,
+            encryption: None,
+            access_pattern_tuning: false,
         }
     }
+
+    /// Sets the size of the block cache in bytes.
+    #[must_use]
+    pub fn with_block_cache_size(mut self, size: usize) -> Self {
+        self.block_cache_size = Some(size);
+        self
+    }
+
+    /// Sets compression algorithms for the levels of the LSM tree, starting from level 0.
+    #[must_use]
+    pub fn with_compression_per_level(
+        mut self,
+        levels: impl IntoIterator<Item = CompressionType>,
+    ) -> Self {
+        self.compression_per_level = levels.into_iter().collect();
+        self
+    }
+
+    /// Sets the number of bits per key in bloom filters.
+    #[must_use]
+    pub fn with_bloom_filter_bits(mut self, bits_per_key: u32) -> Self {
+        self.bloom_filter_bits = Some(bits_per_key);
+        self
+    }
+
+    /// Sets the size of a single write buffer in bytes.
+    #[must_use]
+    pub fn with_write_buffer_size(mut self, size: usize) -> Self {
+        self.write_buffer_size = Some(size);
+        self
+    }
+
+    /// Sets the maximum number of write buffers and the minimum number of write buffers
+    /// to merge before flushing.
+    #[must_use]
+    pub fn with_write_buffer_number(mut self, max_number: i32, min_number_to_merge: i32) -> Self {
+        self.max_write_buffer_number = Some(max_number);
+        self.min_write_buffer_number_to_merge = Some(min_number_to_merge);
+        self
+    }
+
+    /// Enables or disables tuning of column families according to the access patterns
+    /// of indexes.
+    #[must_use]
+    pub fn with_access_pattern_tuning(mut self, enabled: bool) -> Self {
+        self.access_pattern_tuning = enabled;
+        self
+    }
+
+    /// Sets encryption options.
+    #[must_use]
+    pub fn with_encryption(mut self, encryption: EncryptionOptions) -> Self {
+        self.encryption = Some(encryption);
+        self
+    }
+}
+
+/// Options of the encryption at rest.
+///
+/// Values in the encrypted column families are encrypted with AES-256-GCM. Keys of the entries
+/// are stored unencrypted, since the database relies on their order. Encryption cannot be
+/// enabled for an existing database in place; the database needs to be copied
+/// into a new encrypted one instead.
+///
+/// Keys are rotated by increasing `key_version`. Values are encrypted with the key
+/// of the active version, while values encrypted with the retired keys listed
+/// in `retired_key_versions` remain readable. Once the values are re-encrypted
+/// with the active key, retired versions can be removed from the list.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EncryptionOptions {
+    /// Names of the encrypted column families, which correspond to the names of indexes
+    /// (or index families). A name also covers the column families prefixed by it
+    /// and a dot; e.g., the name of a service covers all its indexes.
+    ///
+    /// An empty list means that all column families are encrypted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub column_families: Vec<String>,
+    /// Version of the active encryption key.
+    #[serde(default)]
+    pub key_version: u32,
+    /// Versions of the retired encryption keys, which are only used to decrypt values
+    /// written before key rotation.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retired_key_versions: Vec<u32>,
+}
+
+impl EncryptionOptions {
+    /// Creates options encrypting all column families with the key of the specified version.
+    pub fn new(key_version: u32) -> Self {
+        Self {
+            column_families: vec![],
+            key_version,
+            retired_key_versions: vec![],
+        }
+    }
+
+    /// Adds the version of a retired key, which is used to decrypt values written with it.
+    #[must_use]
+    pub fn with_retired_key_version(mut self, version: u32) -> Self {
+        self.retired_key_versions.push(version);
+        self
+    }
+
+    /// Adds a column family (or a prefix of column family names) to encrypt.
+    #[must_use]
+    pub fn with_column_family(mut self, name: impl Into<String>) -> Self {
+        self.column_families.push(name.into());
+        self
+    }
+
+    /// Checks whether the column family with the specified name is encrypted.
+    pub fn is_encrypted(&self, cf_name: &str) -> bool {
+        self.column_families.is_empty()
+            || self.column_families.iter().any(|name| {
+                cf_name
+                    .strip_prefix(name.as_str())
+                    .map_or(false, |rest| rest.is_empty() || rest.starts_with('.'))
+            })
+    }
 }
 
 /// Algorithms of compression for the database.
@@ -107,3 +296,51 @@ impl Default for DbOptions {
         Self::new(None, true, CompressionType::None, None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CompressionType, DbOptions, EncryptionOptions};
+
+    #[test]
+    fn tuning_options_are_optional_in_config() {
+        let options = DbOptions::default();
+        let serialized = serde_json::to_string(&options).unwrap();
+        assert!(!serialized.contains("block_cache_size"));
+        assert!(!serialized.contains("compression_per_level"));
+        assert!(!serialized.contains("access_pattern_tuning"));
+        let restored: DbOptions = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(restored, options);
+
+        let options = DbOptions::default()
+            .with_block_cache_size(1 << 30)
+            .with_compression_per_level(vec![CompressionType::None, CompressionType::Lz4])
+            .with_bloom_filter_bits(10)
+            .with_write_buffer_size(1 << 27)
+            .with_write_buffer_number(4, 2)
+            .with_access_pattern_tuning(true);
+        let serialized = serde_json::to_string(&options).unwrap();
+        let restored: DbOptions = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(restored, options);
+    }
+
+    #[test]
+    fn encrypted_column_families() {
+        let options = EncryptionOptions::new(0);
+        assert!(options.is_encrypted("token.wallets"));
+
+        let options = EncryptionOptions::new(1)
+            .with_column_family("token")
+            .with_column_family("__DB_METADATA__")
+            .with_retired_key_version(0);
+        assert!(options.is_encrypted("token"));
+        assert!(options.is_encrypted("token.wallets"));
+        assert!(options.is_encrypted("__DB_METADATA__"));
+        assert!(!options.is_encrypted("tokens.wallets"));
+        assert!(!options.is_encrypted("other.token"));
+
+        let db_options = DbOptions::default().with_encryption(options);
+        let serialized = serde_json::to_string(&db_options).unwrap();
+        let restored: DbOptions = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(restored, db_options);
+    }
+}
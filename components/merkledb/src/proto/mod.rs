@@ -21,7 +21,7 @@ pub use self::{list_proof::*, map_proof::*};
 use anyhow::{ensure, Error};
 use exonum_crypto::{proto::*, HASH_SIZE};
 use exonum_proto::ProtobufConvert;
-use protobuf::well_known_types::empty::Empty;
+use protobuf::{well_known_types::empty::Empty, Message};
 
 use std::borrow::Cow;
 
@@ -32,6 +32,42 @@ use crate::{
 
 include!(concat!(env!("OUT_DIR"), "/protobuf_mod.rs"));
 
+/// Encodes a proof in the compact binary form, i.e., as a Protobuf message.
+pub(crate) fn proof_to_bytes<T>(proof: &T) -> Vec<u8>
+where
+    T: ProtobufConvert,
+    T::ProtoStruct: Message,
+{
+    proof
+        .to_pb()
+        .write_to_bytes()
+        .expect("Failed to serialize proof")
+}
+
+/// Decodes a proof from the compact binary form. The encoding must be canonical, i.e.,
+/// coincide with the output of `proof_to_bytes` for the decoded proof. If `max_size` is
+/// specified, larger inputs are rejected before parsing.
+pub(crate) fn proof_from_bytes<T>(bytes: &[u8], max_size: Option<usize>) -> Result<T, Error>
+where
+    T: ProtobufConvert,
+    T::ProtoStruct: Message,
+{
+    if let Some(max_size) = max_size {
+        ensure!(
+            bytes.len() <= max_size,
+            "Proof size ({} bytes) exceeds the limit ({} bytes)",
+            bytes.len(),
+            max_size
+        );
+    }
+    let proof = T::from_pb(T::ProtoStruct::parse_from_bytes(bytes)?)?;
+    ensure!(
+        proof_to_bytes(&proof) == bytes,
+        "Non-canonical binary encoding of the proof"
+    );
+    Ok(proof)
+}
+
 fn parse_map_proof_entry(
     mut entry: MapProofEntry,
 ) -> Result<(ProofPath, exonum_crypto::Hash), Error> {
@@ -142,6 +178,54 @@ where
     }
 }
 
+/// Compact binary encoding of the proof based on the `MapProof` Protobuf message.
+/// Only the canonical encoding (i.e., the one produced by `to_bytes`) is accepted
+/// by `from_bytes`.
+impl<K, V, S> BinaryValue for crate::MapProof<K, V, S>
+where
+    K: BinaryValue,
+    V: BinaryValue,
+{
+    fn to_bytes(&self) -> Vec<u8> {
+        proof_to_bytes(self)
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Result<Self, Error> {
+        proof_from_bytes(bytes.as_ref(), None)
+    }
+}
+
+impl<K, V, S> crate::MapProof<K, V, S>
+where
+    K: BinaryValue,
+    V: BinaryValue,
+{
+    /// Decodes the proof from the compact binary encoding, rejecting encodings larger
+    /// than `max_size` bytes before parsing. This allows to bound resources spent
+    /// on decoding and verification of proofs received from untrusted sources.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum_merkledb::{
+    /// #     access::CopyAccessExt, BinaryValue, Database, MapProof, ObjectHash, TemporaryDB,
+    /// # };
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// let mut map = fork.get_proof_map::<_, u8, u8>("map");
+    /// map.put(&1, 2);
+    /// let bytes = map.get_proof(1).to_bytes();
+    ///
+    /// let proof = MapProof::<u8, u8>::from_bytes_bounded(&bytes, 1_024)?;
+    /// proof.check_against_hash(map.object_hash())?;
+    /// assert!(MapProof::<u8, u8>::from_bytes_bounded(&bytes, 16).is_err());
+    /// # Ok::<_, anyhow::Error>(())
+    /// ```
+    pub fn from_bytes_bounded(bytes: &[u8], max_size: usize) -> Result<Self, Error> {
+        proof_from_bytes(bytes, Some(max_size))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use exonum_crypto::{hash, proto::types, PublicKey};
@@ -294,6 +378,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn compact_proof_encoding() {
+        let db = TemporaryDB::default();
+        let fork = db.fork();
+        let mut list = fork.get_proof_list("list");
+        list.extend(0_u64..100);
+        let mut map = fork.get_proof_map("map");
+        for i in 0_u64..100 {
+            map.put(&i, i);
+        }
+
+        let list_proof = list.get_range_proof(10..20);
+        let bytes = list_proof.to_bytes();
+        assert!(bytes.len() < serde_json::to_vec(&list_proof).unwrap().len());
+        let restored = ListProof::<u64>::from_bytes(bytes.as_slice().into()).unwrap();
+        assert_eq!(restored, list_proof);
+        let restored = ListProof::<u64>::from_bytes_bounded(&bytes, bytes.len()).unwrap();
+        assert_eq!(restored, list_proof);
+        let err = ListProof::<u64>::from_bytes_bounded(&bytes, bytes.len() - 1).unwrap_err();
+        assert!(err.to_string().contains("exceeds the limit"));
+
+        // Repeat the `length` field; the decoded proof is the same, but the encoding differs.
+        let mut non_canonical_bytes = bytes;
+        non_canonical_bytes.extend_from_slice(&[0x18, 100]);
+        let err = ListProof::<u64>::from_bytes(non_canonical_bytes.into()).unwrap_err();
+        assert!(err.to_string().contains("Non-canonical"));
+
+        let map_proof = map.get_multiproof(vec![5, 10, 200]);
+        let bytes = map_proof.to_bytes();
+        assert!(bytes.len() < serde_json::to_vec(&map_proof).unwrap().len());
+        let restored = MapProof::<u64, u64>::from_bytes_bounded(&bytes, bytes.len()).unwrap();
+        assert_eq!(restored, map_proof);
+        restored.check_against_hash(map.object_hash()).unwrap();
+        let err = MapProof::<u64, u64>::from_bytes_bounded(&bytes, 10).unwrap_err();
+        assert!(err.to_string().contains("exceeds the limit"));
+    }
+
     #[test]
     fn invalid_list_proof_key() {
         let mut proof = proto::ListProof::new();
@@ -0,0 +1,716 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Replication of database changes.
+//!
+//! A *primary* database is wrapped into a [`ReplicationLog`]. Each patch merged into the log
+//! is assigned a sequence number, which is stored in the database atomically with the patch.
+//! The merged patches are published to subscribers as [`ReplicatedPatch`]es, which can be
+//! serialized, transferred to other processes and applied to *replica* databases.
+//! A replica applying all patches in order stays identical to the primary database,
+//! including the state hash and the sequence number of the last applied patch.
+//!
+//! The log retains a bounded backlog of recent patches in memory. A replica that has fallen
+//! behind the backlog (for example, because it was offline for a long time) must be re-seeded
+//! with a copy of the primary database, e.g., via [`copy_database`] or a database checkpoint.
+//!
+//! # Examples
+//!
+//! ```
+//! # use exonum_merkledb::{
+//! #     access::CopyAccessExt, replication::{replication_seq, ReplicationLog},
+//! #     Database, SystemSchema, TemporaryDB,
+//! # };
+//! let primary = ReplicationLog::new(TemporaryDB::new(), 16);
+//! let replica = TemporaryDB::new();
+//! let subscription = primary.subscribe(replication_seq(&replica.snapshot())).unwrap();
+//!
+//! let fork = primary.fork();
+//! fork.get_proof_list("list").extend(vec![1_u32, 2, 3]);
+//! primary.merge(fork.into_patch()).unwrap();
+//!
+//! for patch in subscription.take(1) {
+//!     patch.apply(&replica).unwrap();
+//! }
+//! let snapshot = replica.snapshot();
+//! assert_eq!(snapshot.get_proof_list::<_, u32>("list").len(), 3);
+//! assert_eq!(
+//!     SystemSchema::new(&snapshot).state_hash(),
+//!     SystemSchema::new(&primary.snapshot()).state_hash()
+//! );
+//! ```
+//!
+//! [`ReplicationLog`]: struct.ReplicationLog.html
+//! [`ReplicatedPatch`]: struct.ReplicatedPatch.html
+//! [`copy_database`]: ../fn.copy_database.html
+
+use anyhow::{bail, ensure, format_err};
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt,
+    io::Read,
+    num::NonZeroU64,
+    path::Path,
+    sync::{
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+};
+
+use crate::{
+    db::{Change, ViewChanges, DB_METADATA},
+    views::View,
+    BinaryValue, Database, Error, Fork, Patch, ResolvedAddress, Result, Snapshot, StallStats,
+};
+
+/// Name of the database metadata entry with the sequence number of the last merged patch.
+const REPLICATION_SEQ_NAME: &str = "replication_seq";
+
+/// Returns the sequence number of the last patch merged via a [`ReplicationLog`]
+/// or applied from a [`ReplicatedPatch`]. Returns 0 for databases which were never
+/// replicated.
+///
+/// [`ReplicationLog`]: struct.ReplicationLog.html
+/// [`ReplicatedPatch`]: struct.ReplicatedPatch.html
+pub fn replication_seq(snapshot: &dyn Snapshot) -> u64 {
+    View::new(snapshot, ResolvedAddress::system(DB_METADATA))
+        .get(REPLICATION_SEQ_NAME)
+        .unwrap_or(0)
+}
+
+/// Changes of a single patch merged into a [`ReplicationLog`].
+///
+/// A patch can be serialized with the help of the [`BinaryValue`] trait
+/// and applied to a replica database via [`apply`].
+///
+/// [`ReplicationLog`]: struct.ReplicationLog.html
+/// [`BinaryValue`]: ../trait.BinaryValue.html
+/// [`apply`]: #method.apply
+#[derive(Clone, PartialEq, Eq)]
+pub struct ReplicatedPatch {
+    seq: u64,
+    changes: Vec<ReplicatedView>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+struct ReplicatedView {
+    address: ResolvedAddress,
+    is_cleared: bool,
+    removed_ranges: Vec<(Vec<u8>, Vec<u8>)>,
+    data: BTreeMap<Vec<u8>, Change>,
+}
+
+impl fmt::Debug for ReplicatedPatch {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("ReplicatedPatch")
+            .field("seq", &self.seq)
+            .field("views", &self.changes.len())
+            .finish()
+    }
+}
+
+impl ReplicatedPatch {
+    fn new(seq: u64, patch: &Patch) -> Self {
+        let mut changes: Vec<_> = patch
+            .changes()
+            .iter()
+            .map(|(address, changes)| ReplicatedView {
+                address: address.clone(),
+                is_cleared: changes.is_cleared(),
+                removed_ranges: changes.removed_ranges().to_vec(),
+                data: changes.data.clone(),
+            })
+            .collect();
+        // Make serialization deterministic.
+        changes.sort_unstable_by(|x, y| {
+            (&x.address.name, x.address.id).cmp(&(&y.address.name, y.address.id))
+        });
+        Self { seq, changes }
+    }
+
+    /// Returns the sequence number of this patch.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Applies this patch to the replica database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the patch does not immediately follow the last patch applied
+    /// to the database (i.e., if its sequence number is not greater by one than
+    /// [`replication_seq`] of the database), or if merging the patch fails.
+    ///
+    /// [`replication_seq`]: fn.replication_seq.html
+    pub fn apply(&self, db: &dyn Database) -> Result<()> {
+        let snapshot = db.snapshot();
+        let db_seq = replication_seq(&snapshot);
+        if db_seq + 1 != self.seq {
+            return Err(Error::new(format!(
+                "Cannot apply replicated patch #{} to the database with the last applied patch #{}",
+                self.seq, db_seq
+            )));
+        }
+
+        let changes = self
+            .changes
+            .iter()
+            .map(|view| {
+                let changes = ViewChanges::from_parts(
+                    view.data.clone(),
+                    view.is_cleared,
+                    view.removed_ranges.clone(),
+                );
+                (view.address.clone(), changes)
+            })
+            .collect::<HashMap<_, _>>();
+        db.merge(Patch::from_changes(snapshot, changes))
+    }
+}
+
+fn write_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    leb128::write::unsigned(buffer, bytes.len() as u64).unwrap();
+    buffer.extend_from_slice(bytes);
+}
+
+fn read_len(reader: &mut &[u8]) -> anyhow::Result<usize> {
+    let len = leb128::read::unsigned(reader)
+        .map_err(|e| format_err!("Cannot read length prefix: {}", e))?;
+    ensure!(
+        len <= reader.len() as u64,
+        "Length prefix exceeds the remaining buffer"
+    );
+    Ok(len as usize)
+}
+
+fn read_bytes(reader: &mut &[u8]) -> anyhow::Result<Vec<u8>> {
+    let len = read_len(reader)?;
+    let (bytes, rest) = reader.split_at(len);
+    *reader = rest;
+    Ok(bytes.to_vec())
+}
+
+fn read_u8(reader: &mut &[u8]) -> anyhow::Result<u8> {
+    let mut byte = [0_u8];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn read_u64(reader: &mut &[u8]) -> anyhow::Result<u64> {
+    let mut bytes = [0_u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Uses a compact binary encoding: the sequence number and view IDs are encoded
+/// as little-endian `u64`s, and lengths of byte sequences and collections
+/// are encoded with LEB128.
+impl BinaryValue for ReplicatedPatch {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = self.seq.to_le_bytes().to_vec();
+        leb128::write::unsigned(&mut buffer, self.changes.len() as u64).unwrap();
+        for view in &self.changes {
+            write_bytes(&mut buffer, view.address.name.as_bytes());
+            let id = view.address.id.map_or(0, NonZeroU64::get);
+            buffer.extend_from_slice(&id.to_le_bytes());
+            buffer.push(u8::from(view.is_cleared));
+
+            leb128::write::unsigned(&mut buffer, view.removed_ranges.len() as u64).unwrap();
+            for (from, to) in &view.removed_ranges {
+                write_bytes(&mut buffer, from);
+                write_bytes(&mut buffer, to);
+            }
+
+            leb128::write::unsigned(&mut buffer, view.data.len() as u64).unwrap();
+            for (key, change) in &view.data {
+                write_bytes(&mut buffer, key);
+                match change {
+                    Change::Put(value) => {
+                        buffer.push(1);
+                        write_bytes(&mut buffer, value);
+                    }
+                    Change::Delete => buffer.push(0),
+                }
+            }
+        }
+        buffer
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> anyhow::Result<Self> {
+        let mut reader = bytes.as_ref();
+        let seq = read_u64(&mut reader)?;
+        let views_len = read_len(&mut reader)?;
+        let mut changes = Vec::with_capacity(views_len);
+
+        for _ in 0..views_len {
+            let name = String::from_utf8(read_bytes(&mut reader)?)?;
+            let id = NonZeroU64::new(read_u64(&mut reader)?);
+            let is_cleared = match read_u8(&mut reader)? {
+                0 => false,
+                1 => true,
+                other => bail!("Invalid `is_cleared` flag: {}", other),
+            };
+
+            let ranges_len = read_len(&mut reader)?;
+            let mut removed_ranges = Vec::with_capacity(ranges_len);
+            for _ in 0..ranges_len {
+                removed_ranges.push((read_bytes(&mut reader)?, read_bytes(&mut reader)?));
+            }
+
+            let data_len = read_len(&mut reader)?;
+            let mut data = BTreeMap::new();
+            for _ in 0..data_len {
+                let key = read_bytes(&mut reader)?;
+                let change = match read_u8(&mut reader)? {
+                    0 => Change::Delete,
+                    1 => Change::Put(read_bytes(&mut reader)?),
+                    other => bail!("Invalid change tag: {}", other),
+                };
+                data.insert(key, change);
+            }
+
+            changes.push(ReplicatedView {
+                address: ResolvedAddress { name, id },
+                is_cleared,
+                removed_ranges,
+                data,
+            });
+        }
+        ensure!(reader.is_empty(), "Trailing bytes after replicated patch");
+        Ok(Self { seq, changes })
+    }
+}
+
+/// Database wrapper publishing merged patches to replicas.
+///
+/// `ReplicationLog` implements [`Database`] and forwards all calls to the wrapped database.
+/// Each merged patch is assigned the next sequence number, which is written into the database
+/// metadata together with the patch. After a successful merge, the patch is retained
+/// in a bounded backlog and sent to all active [subscriptions].
+///
+/// Patches are merged sequentially; concurrent merges wait for each other.
+///
+/// [`Database`]: ../trait.Database.html
+/// [subscriptions]: struct.PatchSubscription.html
+#[derive(Clone)]
+pub struct ReplicationLog {
+    db: Arc<dyn Database>,
+    state: Arc<Mutex<LogState>>,
+}
+
+struct LogState {
+    /// Sequence number of the last merged patch.
+    seq: u64,
+    backlog: VecDeque<Arc<ReplicatedPatch>>,
+    backlog_size: usize,
+    subscribers: Vec<SyncSender<Arc<ReplicatedPatch>>>,
+}
+
+impl fmt::Debug for ReplicationLog {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock().unwrap();
+        formatter
+            .debug_struct("ReplicationLog")
+            .field("seq", &state.seq)
+            .field("backlog_len", &state.backlog.len())
+            .field("subscribers", &state.subscribers.len())
+            .finish()
+    }
+}
+
+impl ReplicationLog {
+    /// Wraps the database into a replication log retaining up to `backlog_size`
+    /// latest patches. Sequence numbers continue from the one stored in the database.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `backlog_size` is zero.
+    pub fn new(db: impl Into<Arc<dyn Database>>, backlog_size: usize) -> Self {
+        assert!(backlog_size > 0, "Backlog size must be positive");
+        let db = db.into();
+        let seq = replication_seq(&db.snapshot());
+        Self {
+            db,
+            state: Arc::new(Mutex::new(LogState {
+                seq,
+                backlog: VecDeque::with_capacity(backlog_size),
+                backlog_size,
+                subscribers: vec![],
+            })),
+        }
+    }
+
+    /// Returns the sequence number of the last merged patch.
+    pub fn seq(&self) -> u64 {
+        self.state.lock().unwrap().seq
+    }
+
+    /// Subscribes to patches following the patch with the `last_seq` sequence number,
+    /// which is usually the [`replication_seq`] of the replica database.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `last_seq` is greater than the sequence number of the log,
+    /// or if some patches following `last_seq` are no longer retained in the backlog.
+    ///
+    /// [`replication_seq`]: fn.replication_seq.html
+    pub fn subscribe(&self, last_seq: u64) -> Result<PatchSubscription> {
+        let mut state = self.state.lock().unwrap();
+        if last_seq > state.seq {
+            return Err(Error::new(format!(
+                "Replica is ahead of the primary database (replica patch #{}, primary patch #{})",
+                last_seq, state.seq
+            )));
+        }
+
+        let backlog: VecDeque<_> = state
+            .backlog
+            .iter()
+            .filter(|patch| patch.seq > last_seq)
+            .cloned()
+            .collect();
+        let missing_patches = state.seq - last_seq;
+        if backlog.len() as u64 != missing_patches {
+            return Err(Error::new(format!(
+                "Replica is too far behind the primary database (replica patch #{}, \
+                 primary patch #{}); the replica needs to be re-seeded",
+                last_seq, state.seq
+            )));
+        }
+
+        let (sender, receiver) = mpsc::sync_channel(state.backlog_size);
+        state.subscribers.push(sender);
+        Ok(PatchSubscription { backlog, receiver })
+    }
+
+    fn merge_inner<F>(&self, patches: Vec<Patch>, merge: F) -> Result<()>
+    where
+        F: FnOnce(&dyn Database, Vec<Patch>) -> Result<()>,
+    {
+        let mut state = self.state.lock().unwrap();
+        let mut seq = state.seq;
+
+        let mut replicated = Vec::with_capacity(patches.len());
+        let patches: Vec<_> = patches
+            .into_iter()
+            .map(|patch| {
+                seq += 1;
+                let fork = Fork::from(patch);
+                View::new(&fork, ResolvedAddress::system(DB_METADATA))
+                    .put(REPLICATION_SEQ_NAME, seq);
+                let patch = fork.into_patch();
+                replicated.push(Arc::new(ReplicatedPatch::new(seq, &patch)));
+                patch
+            })
+            .collect();
+        merge(&*self.db, patches)?;
+
+        state.seq = seq;
+        for replicated in replicated {
+            if state.backlog.len() == state.backlog_size {
+                state.backlog.pop_front();
+            }
+            state.backlog.push_back(Arc::clone(&replicated));
+            // Subscribers which have disconnected or cannot keep up with the log are dropped.
+            state
+                .subscribers
+                .retain(|sender| match sender.try_send(Arc::clone(&replicated)) {
+                    Ok(()) => true,
+                    Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => false,
+                });
+        }
+        Ok(())
+    }
+}
+
+impl Database for ReplicationLog {
+    fn snapshot(&self) -> Box<dyn Snapshot> {
+        self.db.snapshot()
+    }
+
+    fn merge(&self, patch: Patch) -> Result<()> {
+        self.merge_inner(vec![patch], |db, mut patches| db.merge(patches.remove(0)))
+    }
+
+    fn merge_sync(&self, patch: Patch) -> Result<()> {
+        self.merge_inner(vec![patch], |db, mut patches| {
+            db.merge_sync(patches.remove(0))
+        })
+    }
+
+    fn merge_batch(&self, patches: Vec<Patch>) -> Result<()> {
+        self.merge_inner(patches, |db, patches| db.merge_batch(patches))
+    }
+
+    fn backup(&self, path: &Path) -> Result<()> {
+        self.db.backup(path)
+    }
+
+    fn checkpoint(&self, name: &str) -> Result<()> {
+        self.db.checkpoint(name)
+    }
+
+    fn checkpoint_snapshot(&self, name: &str) -> Result<Box<dyn Snapshot>> {
+        self.db.checkpoint_snapshot(name)
+    }
+
+    fn compact_range(&self, name: Option<&str>) -> Result<()> {
+        self.db.compact_range(name)
+    }
+
+    fn stall_stats(&self) -> Result<StallStats> {
+        self.db.stall_stats()
+    }
+
+    fn set_background_jobs_limit(&self, limit: u32) -> Result<()> {
+        self.db.set_background_jobs_limit(limit)
+    }
+
+    fn stored_addresses(&self) -> Result<Vec<ResolvedAddress>> {
+        self.db.stored_addresses()
+    }
+}
+
+impl From<ReplicationLog> for Arc<dyn Database> {
+    fn from(log: ReplicationLog) -> Self {
+        Arc::new(log)
+    }
+}
+
+/// Subscription to patches merged into a [`ReplicationLog`].
+///
+/// The subscription is an iterator, which first yields patches from the log backlog
+/// and then blocks waiting for new patches. The iterator ends if the log is dropped,
+/// or if the subscriber does not keep up with the log (i.e., more than the backlog size
+/// of patches are queued for it). In the latter case, the subscriber may resubscribe.
+///
+/// [`ReplicationLog`]: struct.ReplicationLog.html
+#[derive(Debug)]
+pub struct PatchSubscription {
+    backlog: VecDeque<Arc<ReplicatedPatch>>,
+    receiver: Receiver<Arc<ReplicatedPatch>>,
+}
+
+impl PatchSubscription {
+    /// Returns the next patch if it is available without blocking.
+    pub fn try_next(&mut self) -> Option<Arc<ReplicatedPatch>> {
+        self.backlog
+            .pop_front()
+            .or_else(|| self.receiver.try_recv().ok())
+    }
+}
+
+impl Iterator for PatchSubscription {
+    type Item = Arc<ReplicatedPatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.backlog
+            .pop_front()
+            .or_else(|| self.receiver.recv().ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{access::CopyAccessExt, migration::Migration, SystemSchema, TemporaryDB};
+
+    fn assert_same_state(primary: &dyn Database, replica: &dyn Database) {
+        let primary_snapshot = primary.snapshot();
+        let replica_snapshot = replica.snapshot();
+        assert_eq!(
+            SystemSchema::new(&replica_snapshot).state_hash(),
+            SystemSchema::new(&primary_snapshot).state_hash()
+        );
+        assert_eq!(
+            replication_seq(&replica_snapshot),
+            replication_seq(&primary_snapshot)
+        );
+    }
+
+    #[test]
+    fn replicating_patches() {
+        let primary = ReplicationLog::new(TemporaryDB::new(), 4);
+        let replica = TemporaryDB::new();
+        let mut subscription = primary.subscribe(0).unwrap();
+
+        let fork = primary.fork();
+        fork.get_proof_list("list").extend(vec![1_u32, 2, 3]);
+        fork.get_map(("group", &1_u8)).put(&1_u8, "foo".to_owned());
+        fork.get_proof_entry("ns.entry").set(1_u64);
+        Migration::new("ns", &fork)
+            .get_proof_entry("entry")
+            .set(2_u64);
+        primary.merge(fork.into_patch()).unwrap();
+
+        let fork = primary.fork();
+        fork.get_proof_list::<_, u32>("list").clear();
+        fork.get_map::<_, u8, String>(("group", &1_u8)).remove(&1);
+        fork.get_key_set("set").insert(&5_u8);
+        primary.merge_sync(fork.into_patch()).unwrap();
+        assert_eq!(primary.seq(), 2);
+
+        for _ in 0..2 {
+            let patch = subscription.try_next().unwrap();
+            let patch = ReplicatedPatch::from_bytes(patch.to_bytes().into()).unwrap();
+            patch.apply(&replica).unwrap();
+        }
+        assert!(subscription.try_next().is_none());
+        assert_same_state(&primary, &replica);
+
+        let snapshot = replica.snapshot();
+        assert!(snapshot.get_proof_list::<_, u32>("list").is_empty());
+        assert!(snapshot.get_key_set::<_, u8>("set").contains(&5));
+        assert_eq!(
+            Migration::new("ns", &snapshot).state_hash(),
+            Migration::new("ns", &primary.snapshot()).state_hash()
+        );
+    }
+
+    #[test]
+    fn patches_are_applied_in_order() {
+        let primary = ReplicationLog::new(TemporaryDB::new(), 4);
+        let replica = TemporaryDB::new();
+        let mut subscription = primary.subscribe(0).unwrap();
+        for i in 0_u8..2 {
+            let fork = primary.fork();
+            fork.get_entry("entry").set(i);
+            primary.merge(fork.into_patch()).unwrap();
+        }
+
+        let first = subscription.try_next().unwrap();
+        let second = subscription.try_next().unwrap();
+        let err = second.apply(&replica).unwrap_err();
+        assert!(err.to_string().contains("Cannot apply replicated patch #2"));
+        first.apply(&replica).unwrap();
+        let err = first.apply(&replica).unwrap_err();
+        assert!(err.to_string().contains("Cannot apply replicated patch #1"));
+        second.apply(&replica).unwrap();
+        assert_same_state(&primary, &replica);
+    }
+
+    #[test]
+    fn replicating_patch_batches() {
+        let primary = ReplicationLog::new(TemporaryDB::new(), 4);
+        let replica = TemporaryDB::new();
+        let subscription = primary.subscribe(0).unwrap();
+        let fork = primary.fork();
+        fork.get_list("list").push(0_u8);
+        primary.merge(fork.into_patch()).unwrap();
+
+        let patches = (1_u8..=3).map(|i| {
+            let fork = primary.fork();
+            fork.get_list("list").set(0, i);
+            fork.into_patch()
+        });
+        primary.merge_batch(patches.collect()).unwrap();
+        assert_eq!(primary.seq(), 4);
+
+        for (seq, patch) in (1_u64..).zip(subscription.take(4)) {
+            assert_eq!(patch.seq(), seq);
+            patch.apply(&replica).unwrap();
+        }
+        assert_same_state(&primary, &replica);
+        let snapshot = replica.snapshot();
+        assert_eq!(snapshot.get_list::<_, u8>("list").get(0), Some(3));
+    }
+
+    #[test]
+    fn subscribing_from_backlog() {
+        let primary = ReplicationLog::new(TemporaryDB::new(), 2);
+        for i in 0_u8..3 {
+            let fork = primary.fork();
+            fork.get_entry("entry").set(i);
+            primary.merge(fork.into_patch()).unwrap();
+        }
+
+        let err = primary.subscribe(0).unwrap_err();
+        assert!(err.to_string().contains("re-seeded"));
+        let err = primary.subscribe(4).unwrap_err();
+        assert!(err.to_string().contains("ahead of the primary"));
+
+        let mut subscription = primary.subscribe(1).unwrap();
+        assert_eq!(subscription.try_next().unwrap().seq(), 2);
+        assert_eq!(subscription.try_next().unwrap().seq(), 3);
+        assert!(subscription.try_next().is_none());
+        let mut subscription = primary.subscribe(3).unwrap();
+        assert!(subscription.try_next().is_none());
+    }
+
+    #[test]
+    fn seeded_replica_catches_up() {
+        let db = TemporaryDB::new();
+        let primary = ReplicationLog::new(db, 4);
+        let fork = primary.fork();
+        fork.get_proof_map("map").put(&1_u8, 2_u8);
+        primary.merge(fork.into_patch()).unwrap();
+
+        let replica = TemporaryDB::new();
+        crate::copy_database(&primary, &replica).unwrap();
+        assert_eq!(replication_seq(&replica.snapshot()), 1);
+
+        let subscription = primary.subscribe(1).unwrap();
+        let fork = primary.fork();
+        fork.get_proof_map("map").put(&3_u8, 4_u8);
+        primary.merge(fork.into_patch()).unwrap();
+        for patch in subscription.take(1) {
+            patch.apply(&replica).unwrap();
+        }
+        assert_same_state(&primary, &replica);
+    }
+
+    #[test]
+    fn slow_subscribers_are_dropped() {
+        let primary = ReplicationLog::new(TemporaryDB::new(), 1);
+        let subscription = primary.subscribe(0).unwrap();
+        for i in 0_u8..3 {
+            let fork = primary.fork();
+            fork.get_entry("entry").set(i);
+            primary.merge(fork.into_patch()).unwrap();
+        }
+        let seqs: Vec<_> = subscription.map(|patch| patch.seq()).collect();
+        assert_eq!(seqs, vec![1]);
+    }
+
+    #[test]
+    fn sequence_numbers_persist() {
+        let db: Arc<dyn Database> = Arc::new(TemporaryDB::new());
+        let primary = ReplicationLog::new(Arc::clone(&db), 4);
+        let fork = primary.fork();
+        fork.get_entry("entry").set(1_u8);
+        primary.merge(fork.into_patch()).unwrap();
+        drop(primary);
+
+        let primary = ReplicationLog::new(db, 4);
+        assert_eq!(primary.seq(), 1);
+        assert!(primary.subscribe(0).is_err());
+    }
+
+    #[test]
+    fn corrupted_patch_is_rejected() {
+        let primary = ReplicationLog::new(TemporaryDB::new(), 4);
+        let mut subscription = primary.subscribe(0).unwrap();
+        let fork = primary.fork();
+        fork.get_entry("entry").set(1_u8);
+        primary.merge(fork.into_patch()).unwrap();
+
+        let mut bytes = subscription.try_next().unwrap().to_bytes();
+        assert!(ReplicatedPatch::from_bytes(bytes[..bytes.len() - 1].to_vec().into()).is_err());
+        bytes.push(0);
+        assert!(ReplicatedPatch::from_bytes(bytes.into()).is_err());
+    }
+}
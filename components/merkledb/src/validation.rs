@@ -67,9 +67,14 @@ where
     }
 }
 
+/// Checks if the name is reserved for system views, which cannot be accessed via indexes.
+pub(crate) fn is_reserved_name(name: &str) -> bool {
+    name.starts_with("__") && !name.contains('.')
+}
+
 /// Checks that provided address is valid index full name.
 pub(crate) fn check_index_valid_full_name(name: &str) -> Result<(), AccessErrorKind> {
-    if name.starts_with("__") && !name.contains('.') {
+    if is_reserved_name(name) {
         return Err(AccessErrorKind::ReservedName);
     }
     check_valid_name(name, is_valid_identifier, "a-zA-Z0-9 and _-.")
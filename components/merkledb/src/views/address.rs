@@ -12,14 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{borrow::Cow, num::NonZeroU64};
+use std::{borrow::Cow, num::NonZeroU64, ops::Bound};
 
-use crate::BinaryKey;
+use crate::{backends::rocksdb::next_id_bytes, BinaryKey};
 
 pub fn key_bytes<K: BinaryKey + ?Sized>(key: &K) -> Vec<u8> {
     concat_keys!(key)
 }
 
+/// Returns the lexicographically smallest byte sequence greater than all sequences starting
+/// with `prefix`, or `None` if there is no such sequence (i.e., if `prefix` is empty or consists
+/// of `0xff` bytes only).
+pub fn prefix_successor(prefix: &[u8]) -> Option<Vec<u8>> {
+    let last_incrementable = prefix.iter().rposition(|&byte| byte != u8::MAX)?;
+    let mut successor = prefix[..=last_incrementable].to_vec();
+    successor[last_incrementable] += 1;
+    Some(successor)
+}
+
 const SEPARATOR_CHAR: u8 = 0;
 const MIGRATION_CHAR: u8 = b'^';
 
@@ -157,6 +167,11 @@ impl IndexAddress {
         }
     }
 
+    /// Checks whether the address corresponds to an index in migration.
+    pub fn is_in_migration(&self) -> bool {
+        self.in_migration
+    }
+
     pub(crate) fn set_in_migration(&mut self) {
         self.in_migration = true;
     }
@@ -231,6 +246,30 @@ impl IndexAddress {
         (name, is_in_group)
     }
 
+    /// Restores an address from its fully qualified name, provided that the name part
+    /// of the address is known. Returns `None` if the qualified name does not correspond
+    /// to the `name`.
+    pub(super) fn from_fully_qualified_name(qualified_name: &[u8], name: &str) -> Option<Self> {
+        let (in_migration, qualified_name) = match qualified_name.split_first() {
+            Some((&MIGRATION_CHAR, rest)) => (true, rest),
+            _ => (false, qualified_name),
+        };
+        if !qualified_name.starts_with(name.as_bytes()) {
+            return None;
+        }
+
+        let id_in_group = match &qualified_name[name.len()..] {
+            [] => None,
+            [SEPARATOR_CHAR, key @ ..] => Some(key.to_vec()),
+            _ => return None,
+        };
+        Some(Self {
+            name: name.to_owned(),
+            id_in_group,
+            in_migration,
+        })
+    }
+
     /// Converts a migration namespace into the form that all indexes in the namespace
     /// begin with.
     #[inline]
@@ -306,6 +345,20 @@ impl ResolvedAddress {
         self.id.map(|id| id.get().to_le_bytes())
     }
 
+    /// Returns the upper bound for reverse iteration with keys prefixed by the `id`.
+    /// If the `upper` bound is unbounded, the returned bound excludes entries of the views
+    /// with greater IDs.
+    pub(crate) fn keyed_upper_bound(&self, upper: Bound<&[u8]>) -> Bound<Vec<u8>> {
+        match upper {
+            Bound::Included(key) => Bound::Included(self.keyed(key).into_owned()),
+            Bound::Excluded(key) => Bound::Excluded(self.keyed(key).into_owned()),
+            Bound::Unbounded => match self.id_to_bytes() {
+                Some(id_bytes) => Bound::Excluded(next_id_bytes(id_bytes).to_vec()),
+                None => Bound::Unbounded,
+            },
+        }
+    }
+
     /// Returns `key` prefixed by the `id`.
     pub(crate) fn keyed<'k>(&self, key: &'k [u8]) -> Cow<'k, [u8]> {
         match self.id {
@@ -19,7 +19,14 @@ use exonum_crypto::Hash;
 use num_traits::FromPrimitive;
 use serde::{Deserialize, Serialize};
 
-use std::{borrow::Cow, io::Error, mem, num::NonZeroU64, vec};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    io::Error,
+    mem,
+    num::NonZeroU64,
+    vec,
+};
 
 use crate::{
     access::{AccessError, AccessErrorKind},
@@ -55,6 +62,14 @@ pub enum IndexType {
     ProofMap = 8,
     /// Merkelized entry.
     ProofEntry = 9,
+    /// Merkelized map index based on a sparse Merkle tree.
+    SparseMerkle = 10,
+    /// Merkelized map index based on the Merkle Patricia trie used in Ethereum.
+    PatriciaTrie = 11,
+    /// Non-merkelized index of values ordered by a score.
+    SortedValue = 12,
+    /// Non-merkelized map index with expiring entries.
+    TtlMap = 13,
 
     /// Tombstone indicating necessity to remove an index after migration is completed.
     Tombstone = 254,
@@ -66,10 +81,61 @@ pub enum IndexType {
 impl IndexType {
     /// Checks if the index of this type is Merkelized.
     pub fn is_merkelized(self) -> bool {
-        matches!(self, Self::ProofList | Self::ProofMap | Self::ProofEntry)
+        matches!(
+            self,
+            Self::ProofList
+                | Self::ProofMap
+                | Self::ProofEntry
+                | Self::SparseMerkle
+                | Self::PatriciaTrie
+        )
+    }
+
+    /// Returns the expected pattern of accessing data of indexes of this type, or `None`
+    /// if the type does not correspond to an index with data.
+    pub fn access_pattern(self) -> Option<AccessPattern> {
+        match self {
+            Self::Map
+            | Self::Entry
+            | Self::ValueSet
+            | Self::KeySet
+            | Self::ProofMap
+            | Self::ProofEntry
+            | Self::SparseMerkle
+            | Self::PatriciaTrie
+            | Self::TtlMap => Some(AccessPattern::PointLookup),
+            Self::List | Self::SparseList | Self::ProofList | Self::SortedValue => {
+                Some(AccessPattern::Scan)
+            }
+            Self::Tombstone | Self::Unknown => None,
+        }
     }
 }
 
+/// Expected pattern of accessing data of an index. Database backends may use the access
+/// pattern to tune the storage of the index data; e.g., `RocksDB` configures bloom filters
+/// and prefix extractors for column families (see [`DbOptions::access_pattern_tuning`]).
+///
+/// [`DbOptions::access_pattern_tuning`]: struct.DbOptions.html#structfield.access_pattern_tuning
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Primitive,
+    Serialize,
+    Deserialize
+)]
+#[repr(u8)]
+pub enum AccessPattern {
+    /// Data is mostly accessed by exact keys, e.g., in maps.
+    PointLookup = 1,
+    /// Data is mostly accessed by iterating over key ranges, e.g., in lists.
+    Scan = 2,
+}
+
 /// Index state attribute tag.
 const INDEX_STATE_TAG: u32 = 0;
 
@@ -238,6 +304,12 @@ impl<V> IndexMetadata<V> {
         self.index_type
     }
 
+    /// Returns the expected pattern of accessing the index data, which is determined
+    /// by the index type.
+    pub fn access_pattern(&self) -> Option<AccessPattern> {
+        self.index_type.access_pattern()
+    }
+
     /// Returns a globally unique numeric index identifier.
     /// MerkleDB assigns a unique numeric ID for each fully-qualified index name.
     ///
@@ -320,7 +392,7 @@ impl<T: RawAccess> IndexesPool<T> {
         Self(view)
     }
 
-    pub(super) fn len(&self) -> u64 {
+    pub(crate) fn len(&self) -> u64 {
         self.0.get(&()).unwrap_or_default()
     }
 
@@ -332,6 +404,97 @@ impl<T: RawAccess> IndexesPool<T> {
         self.0.put_or_forget(&(), len);
     }
 
+    /// Returns resolved addresses of all views tracked by the pool, including the view
+    /// of the pool itself.
+    pub(crate) fn resolved_addresses(&self) -> Vec<ResolvedAddress> {
+        let indexes = self
+            .0
+            .iter::<_, Vec<u8>, Vec<u8>>(&())
+            // The empty key corresponds to the pool length.
+            .filter(|(key, _)| !key.is_empty())
+            .map(|(key, raw_metadata)| {
+                let metadata = IndexMetadata::<Vec<u8>>::from_bytes(raw_metadata.into())
+                    .expect("Cannot deserialize index metadata");
+                let (name, _) = IndexAddress::parse_fully_qualified_name(&key, 0);
+                ResolvedAddress::new(name, Some(metadata.identifier))
+            });
+
+        let mut addrs = vec![ResolvedAddress::system(INDEXES_POOL_NAME)];
+        addrs.extend(indexes);
+        addrs
+    }
+
+    /// Returns logical addresses of all indexes tracked by the pool together with
+    /// their identifiers.
+    pub(crate) fn index_addresses_with_ids(&self) -> Vec<(IndexAddress, NonZeroU64)> {
+        self.0
+            .iter::<_, Vec<u8>, Vec<u8>>(&())
+            // The empty key corresponds to the pool length.
+            .filter(|(key, _)| !key.is_empty())
+            .filter_map(|(key, raw_metadata)| {
+                let metadata = IndexMetadata::<Vec<u8>>::from_bytes(raw_metadata.into())
+                    .expect("Cannot deserialize index metadata");
+                let (name, _) = IndexAddress::parse_fully_qualified_name(&key, 0);
+                let address = IndexAddress::from_fully_qualified_name(&key, &name)?;
+                Some((address, metadata.identifier))
+            })
+            .collect()
+    }
+
+    /// Returns access patterns of the indexes in the pool keyed by the resolved index name
+    /// (i.e., the name of the column family the index data is stored in).
+    pub(crate) fn access_patterns(&self) -> HashMap<String, AccessPattern> {
+        self.0
+            .iter::<_, Vec<u8>, Vec<u8>>(&())
+            .filter(|(key, _)| !key.is_empty())
+            .filter_map(|(key, raw_metadata)| {
+                let metadata = IndexMetadata::<Vec<u8>>::from_bytes(raw_metadata.into())
+                    .expect("Cannot deserialize index metadata");
+                let (name, _) = IndexAddress::parse_fully_qualified_name(&key, 0);
+                let pattern = metadata.access_pattern()?;
+                Some((name, pattern))
+            })
+            .collect()
+    }
+
+    /// Resolves logical addresses of the indexes with the specified `name` part
+    /// and identifiers. Identifiers not found in the pool (e.g., belonging to removed indexes)
+    /// are skipped.
+    ///
+    /// The method scans metadata of all indexes with the `name`, so it may be expensive
+    /// for large index groups.
+    pub(crate) fn index_addresses(
+        &self,
+        name: &str,
+        ids: &HashSet<NonZeroU64>,
+    ) -> HashMap<NonZeroU64, IndexAddress> {
+        let mut addresses = HashMap::with_capacity(ids.len());
+        for &in_migration in &[false, true] {
+            let prefix = IndexAddress {
+                name: name.to_owned(),
+                id_in_group: None,
+                in_migration,
+            }
+            .fully_qualified_name();
+
+            for (key, raw_metadata) in self.0.iter::<_, Vec<u8>, Vec<u8>>(&prefix) {
+                if addresses.len() == ids.len() {
+                    return addresses;
+                }
+                let address = match IndexAddress::from_fully_qualified_name(&key, name) {
+                    Some(address) => address,
+                    None => continue,
+                };
+                let metadata = IndexMetadata::<Vec<u8>>::from_bytes(raw_metadata.into())
+                    .expect("Cannot deserialize index metadata");
+                if ids.contains(&metadata.identifier) {
+                    addresses.insert(metadata.identifier, address);
+                }
+            }
+        }
+        addresses
+    }
+
     /// # Return value
     ///
     /// Index metadata and a flag set to `true` if the index is phantom (i.e., is not in the storage
@@ -404,6 +567,61 @@ impl<T: RawAccessMut> IndexesPool<T> {
         removed_addrs
     }
 
+    /// Moves indexes with the `old_namespace` prefix to the `new_namespace` prefix (e.g.,
+    /// `old.list` to `new.list`). Moved indexes are assigned new identifiers; their types
+    /// and states are retained.
+    ///
+    /// # Return value
+    ///
+    /// Returns resolved addresses of the moved indexes before and after the move. For each pair
+    /// of addresses, we also return a flag indicating whether the index is aggregated.
+    pub(crate) fn move_namespace(
+        &mut self,
+        old_namespace: &str,
+        new_namespace: &str,
+    ) -> Vec<(ResolvedAddress, ResolvedAddress, bool)> {
+        let old_prefix = [old_namespace, "."].concat().into_bytes();
+        let new_prefix = [new_namespace, "."].concat().into_bytes();
+
+        let moved_indexes: Vec<_> = self
+            .0
+            .iter::<_, Vec<u8>, IndexMetadata>(&old_prefix)
+            .collect();
+        let mut moved_addrs = Vec::with_capacity(moved_indexes.len());
+        for (key, metadata) in moved_indexes {
+            let mut new_key = new_prefix.clone();
+            new_key.extend_from_slice(&key[old_prefix.len()..]);
+            assert!(
+                self.index_metadata(&new_key).is_none(),
+                "Cannot move index `{}`: index `{}` already exists",
+                String::from_utf8_lossy(&key),
+                String::from_utf8_lossy(&new_key)
+            );
+
+            let (old_name, is_in_group) =
+                IndexAddress::parse_fully_qualified_name(&key, old_prefix.len());
+            let (new_name, _) =
+                IndexAddress::parse_fully_qualified_name(&new_key, new_prefix.len());
+
+            let len = self.len();
+            let new_metadata = IndexMetadata {
+                identifier: NonZeroU64::new(len + 1).unwrap(),
+                index_type: metadata.index_type,
+                state: metadata.state,
+            };
+            self.set_len(len + 1);
+
+            let old_addr = ResolvedAddress::new(old_name, Some(metadata.identifier));
+            let new_addr = ResolvedAddress::new(new_name, Some(new_metadata.identifier));
+            let is_aggregated = !is_in_group && metadata.index_type.is_merkelized();
+            moved_addrs.push((old_addr, new_addr, is_aggregated));
+
+            self.0.put(&new_key, new_metadata);
+            self.0.remove(&key);
+        }
+        moved_addrs
+    }
+
     pub(crate) fn rollback_migration(&mut self, prefix: &str) -> Vec<ResolvedAddress> {
         let prefix = IndexAddress::qualify_migration_namespace(prefix);
         self.remove_by_prefix(&prefix, |key| {
@@ -423,6 +641,34 @@ impl<T: RawAccessMut> IndexesPool<T> {
         self.remove_by_prefix(&prefix, |_| name.to_owned())
     }
 
+    /// Removes indexes in the group with the specified address, which keys precede `until`
+    /// in the binary representation.
+    ///
+    /// # Return value
+    ///
+    /// Returns resolved addresses of the removed indexes.
+    pub(crate) fn remove_group_indexes(
+        &mut self,
+        group: &IndexAddress,
+        until: &[u8],
+    ) -> Vec<ResolvedAddress> {
+        let name = group.name();
+        let prefix = group.qualified_prefix();
+        let (removed_names, removed_addrs): (Vec<_>, Vec<_>) = self
+            .0
+            .iter::<_, Vec<u8>, IndexMetadata>(prefix.as_slice())
+            .take_while(|(key, _)| key[prefix.len()..] < *until)
+            .map(|(key, metadata)| {
+                let resolved = ResolvedAddress::new(name, Some(metadata.identifier));
+                (key, resolved)
+            })
+            .unzip();
+        for full_name in &removed_names {
+            self.0.remove(full_name);
+        }
+        removed_addrs
+    }
+
     /// Removes views with the full name starting with the specified prefix. The `extract_name`
     /// argument provides a way to map from a full name to the name of the column family
     /// where the view is stored.
@@ -526,7 +772,7 @@ pub fn get_object_hash<T: RawAccess>(
     addr: ResolvedAddress,
     is_in_migration: bool,
 ) -> Hash {
-    use crate::{ObjectHash, ProofListIndex, ProofMapIndex};
+    use crate::{patricia_trie, HashTag, ObjectHash, ProofListIndex, ProofMapIndex};
 
     let mut original_addr = IndexAddress::from_root(&addr.name);
     if is_in_migration {
@@ -546,6 +792,19 @@ pub fn get_object_hash<T: RawAccess>(
             // Hash is stored directly in the metadata.
             metadata.convert::<Hash>().state.unwrap_or_default()
         }
+        IndexType::SparseMerkle => {
+            // The root hash of the tree is stored directly in the metadata.
+            let root_hash = metadata.convert::<Hash>().state.unwrap_or_default();
+            HashTag::hash_sparse_merkle_node(root_hash)
+        }
+        IndexType::PatriciaTrie => {
+            // The Keccak-256 root hash of the trie is stored directly in the metadata.
+            let root_hash = metadata
+                .convert::<Hash>()
+                .state
+                .unwrap_or_else(patricia_trie::empty_trie_root);
+            HashTag::hash_patricia_trie_node(root_hash)
+        }
         IndexType::ProofList | IndexType::ProofMap => {
             let view_with_metadata = ViewWithMetadata {
                 view: View::new(access, addr),
@@ -568,6 +827,44 @@ pub fn get_object_hash<T: RawAccess>(
     }
 }
 
+/// Recomputes `object_hash` for an aggregated index in the default namespace. Unlike
+/// `get_object_hash`, the hashes of Merkelized lists and maps are recomputed from the index
+/// values without relying on the hashes of the tree nodes stored in the database. For other
+/// index types, the hash stored in the index metadata is used.
+///
+/// Returns `None` if the index metadata is absent or the index data is inconsistent.
+pub(crate) fn recompute_object_hash<T: RawAccess>(access: T, name: &str) -> Option<Hash> {
+    use crate::{ProofListIndex, ProofMapIndex};
+
+    let index_full_name = IndexAddress::from_root(name).fully_qualified_name();
+    let metadata = IndexesPool::new(access.clone()).index_metadata(&index_full_name)?;
+    let addr = ResolvedAddress::new(name, Some(metadata.identifier));
+    let index_type = metadata.index_type;
+
+    match index_type {
+        IndexType::ProofList | IndexType::ProofMap => {
+            let view_with_metadata = ViewWithMetadata {
+                view: View::new(access, addr),
+                metadata,
+                index_full_name,
+                is_phantom: false,
+            };
+
+            if index_type == IndexType::ProofList {
+                let list = ProofListIndex::<_, ()>::new(view_with_metadata);
+                Some(list.recompute_object_hash())
+            } else {
+                let map = ProofMapIndex::<_, (), ()>::new(view_with_metadata);
+                map.recompute_object_hash()
+            }
+        }
+        IndexType::ProofEntry | IndexType::SparseMerkle | IndexType::PatriciaTrie => {
+            Some(get_object_hash(access, addr, false))
+        }
+        _ => None, // other index types are not aggregated
+    }
+}
+
 /// Wrapper struct to manipulate `IndexMetadata` for an index with provided `index_name`.
 #[derive(Debug)]
 pub struct ViewWithMetadata<T: RawAccess> {
@@ -664,6 +961,7 @@ where
             View::new(index_access, addr)
         };
         view.set_or_forget_aggregation(namespace);
+        view.set_or_forget_access_pattern(real_index_type.access_pattern());
         let this = Self {
             view,
             metadata,
@@ -15,18 +15,25 @@
 pub use self::{
     address::{IndexAddress, ResolvedAddress},
     metadata::{
-        get_object_hash, BinaryAttribute, GroupKeys, IndexMetadata, IndexState, IndexType,
-        IndexesPool, ViewWithMetadata,
+        get_object_hash, AccessPattern, BinaryAttribute, GroupKeys, IndexMetadata, IndexState,
+        IndexType, IndexesPool, ViewWithMetadata,
     },
-    system_schema::{get_state_aggregator, SystemSchema},
+    system_schema::{get_state_aggregator, IndexSize, SystemSchema},
 };
 
-use std::{borrow::Cow, fmt, iter::Peekable, marker::PhantomData};
+use std::{borrow::Cow, fmt, iter::Peekable, marker::PhantomData, ops::Bound};
 
 use crate::{
-    db::{Change, ChangesMut, ChangesRef, ForkIter, ViewChanges},
+    db::{
+        get_ref_with_changes, multi_get_with_changes, precedes, Change, ChangesMut, ChangesRef,
+        ForkIter, ViewChanges,
+    },
     views::address::key_bytes,
-    BinaryKey, BinaryValue, Iter as BytesIter, Iterator as BytesIterator, Snapshot,
+    BinaryKey, BinaryValue, Iter as BytesIter, Iterator as BytesIterator, Snapshot, ValueRef,
+};
+
+pub(crate) use self::{
+    address::prefix_successor, metadata::recompute_object_hash, system_schema::STATE_AGGREGATOR,
 };
 
 mod address;
@@ -193,6 +200,14 @@ impl<T: RawAccess> ViewInner<T> {
             .unwrap_or_else(|()| self.snapshot().get(&self.address, key))
     }
 
+    fn get_bytes_ref(&self, key: &[u8]) -> Option<ValueRef<'_>> {
+        get_ref_with_changes(self.snapshot(), self.changes.as_ref(), &self.address, key)
+    }
+
+    fn multi_get_bytes(&self, keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+        multi_get_with_changes(self.snapshot(), self.changes.as_ref(), &self.address, keys)
+    }
+
     fn contains_raw_key(&self, key: &[u8]) -> bool {
         self.changes
             .as_ref()
@@ -214,10 +229,34 @@ impl<T: RawAccess> ViewInner<T> {
             // Ignore all changes from the snapshot.
             Box::new(ChangesIter::new(changes_iter.unwrap()))
         } else {
-            Box::new(ForkIter::new(
-                self.snapshot().iter(&self.address, from),
-                changes_iter,
-            ))
+            let snapshot_iter = self.snapshot().iter(&self.address, from);
+            let snapshot_iter = match self.changes.as_ref() {
+                Some(changes) => changes.filter_removed(snapshot_iter),
+                None => snapshot_iter,
+            };
+            Box::new(ForkIter::new(snapshot_iter, changes_iter))
+        }
+    }
+
+    fn iter_bytes_rev(&self, upper: Bound<&[u8]>) -> BytesIter<'_> {
+        let changes_iter = self.changes.as_ref().map(|changes| {
+            changes
+                .data
+                .range::<[u8], _>((Bound::Unbounded, upper))
+                .rev()
+        });
+
+        let is_cleared = self.changes.as_ref().map_or(false, ViewChanges::is_cleared);
+        if is_cleared {
+            // Ignore all changes from the snapshot.
+            Box::new(ChangesIter::new_rev(changes_iter.unwrap()))
+        } else {
+            let snapshot_iter = self.snapshot().iter_rev(&self.address, upper);
+            let snapshot_iter = match self.changes.as_ref() {
+                Some(changes) => changes.filter_removed(snapshot_iter),
+                None => snapshot_iter,
+            };
+            Box::new(ForkIter::new_rev(snapshot_iter, changes_iter))
         }
     }
 }
@@ -255,6 +294,22 @@ impl<T: RawAccess> View<T> {
         }
     }
 
+    fn get_bytes_ref(&self, key: &[u8]) -> Option<ValueRef<'_>> {
+        match self {
+            Self::Real(inner) => inner.get_bytes_ref(key),
+            Self::Phantom => None,
+        }
+    }
+
+    /// Returns values corresponding to the raw `keys`, in the order of `keys`. Values
+    /// absent from the changes are retrieved from the snapshot in a single batch.
+    pub(crate) fn multi_get_bytes(&self, keys: &[&[u8]]) -> Vec<Option<Vec<u8>>> {
+        match self {
+            Self::Real(inner) => inner.multi_get_bytes(keys),
+            Self::Phantom => vec![None; keys.len()],
+        }
+    }
+
     fn contains_raw_key(&self, key: &[u8]) -> bool {
         match self {
             Self::Real(inner) => inner.contains_raw_key(key),
@@ -269,14 +324,21 @@ impl<T: RawAccess> View<T> {
         }
     }
 
+    fn iter_bytes_rev(&self, upper: Bound<&[u8]>) -> BytesIter<'_> {
+        match self {
+            Self::Real(inner) => inner.iter_bytes_rev(upper),
+            Self::Phantom => Box::new(EmptyIterator),
+        }
+    }
+
     /// Returns a value of *any* type corresponding to the key of *any* type.
     pub fn get<K, V>(&self, key: &K) -> Option<V>
     where
         K: BinaryKey + ?Sized,
         V: BinaryValue,
     {
-        self.get_bytes(&key_bytes(key)).map(|v| {
-            BinaryValue::from_bytes(Cow::Owned(v)).expect("Error while deserializing value")
+        self.get_bytes_ref(&key_bytes(key)).map(|v| {
+            BinaryValue::from_bytes(Cow::Borrowed(&v)).expect("Error while deserializing value")
         })
     }
 
@@ -331,6 +393,28 @@ impl<T: RawAccess> View<T> {
         }
     }
 
+    /// Returns an iterator over the entries of the index in descending order. The iterator
+    /// element type is *any* key-value pair. An argument `subprefix` allows specifying a subset
+    /// of keys for iteration.
+    pub fn iter_rev<P, K, V>(&self, subprefix: &P) -> Iter<'_, K, V>
+    where
+        P: BinaryKey + ?Sized,
+        K: BinaryKey + ?Sized,
+        V: BinaryValue,
+    {
+        let iter_prefix = key_bytes(subprefix);
+        let upper = prefix_successor(&iter_prefix);
+        let upper = upper.as_deref().map_or(Bound::Unbounded, Bound::Excluded);
+        Iter {
+            base_iter: self.iter_bytes_rev(upper),
+            prefix: iter_prefix,
+            detach_prefix: false,
+            ended: false,
+            _k: PhantomData,
+            _v: PhantomData,
+        }
+    }
+
     /// Returns an iterator over the entries of the index in ascending order, optionally
     /// starting from the specified key. Unlike `iter_from`, the provided prefix will be detached
     /// from the beginning of each key slice.
@@ -388,6 +472,18 @@ impl<T: RawAccess> View<T> {
             }
         }
     }
+
+    /// Sets the access pattern of the view data, unless the view is backed by a readonly access
+    /// (in which case, the pattern is forgotten).
+    ///
+    /// The access pattern is used by database backends to tune storage of new indexes.
+    pub(crate) fn set_or_forget_access_pattern(&mut self, pattern: Option<AccessPattern>) {
+        if let Self::Real(ViewInner { changes, .. }) = self {
+            if let Some(changes) = changes.as_mut() {
+                changes.set_access_pattern(pattern);
+            }
+        }
+    }
 }
 
 impl<T: RawAccessMut> View<T> {
@@ -427,6 +523,15 @@ impl<T: RawAccessMut> View<T> {
     pub fn clear(&mut self) {
         self.changes_mut().clear();
     }
+
+    /// Removes all keys in the `from..to` range from the view.
+    pub fn remove_range<K>(&mut self, from: &K, to: &K)
+    where
+        K: BinaryKey + ?Sized,
+    {
+        self.changes_mut()
+            .remove_range(concat_keys!(from), concat_keys!(to));
+    }
 }
 
 /// A bytes iterator implementation that has no items.
@@ -440,10 +545,13 @@ impl BytesIterator for EmptyIterator {
     fn peek(&mut self) -> Option<(&[u8], &[u8])> {
         None
     }
+
+    fn seek(&mut self, _key: &[u8]) {}
 }
 
 pub struct ChangesIter<'a, T: Iterator + 'a> {
     inner: Peekable<T>,
+    reversed: bool,
     _lifetime: PhantomData<&'a ()>,
 }
 
@@ -455,6 +563,16 @@ where
     pub fn new(iterator: T) -> Self {
         ChangesIter {
             inner: iterator.peekable(),
+            reversed: false,
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Creates an iterator over changes, which must be iterated in descending order.
+    pub fn new_rev(iterator: T) -> Self {
+        ChangesIter {
+            inner: iterator.peekable(),
+            reversed: true,
             _lifetime: PhantomData,
         }
     }
@@ -495,6 +613,15 @@ where
             }
         }
     }
+
+    fn seek(&mut self, key: &[u8]) {
+        while let Some(&(change_key, _)) = self.inner.peek() {
+            if !precedes(change_key, key, self.reversed) {
+                break;
+            }
+            self.inner.next();
+        }
+    }
 }
 
 /// An iterator over the entries of a `View`.
@@ -525,6 +652,25 @@ where
     K: BinaryKey + ?Sized,
     V: BinaryValue,
 {
+    /// Advances the iterator to the first entry with the key not less than `key`
+    /// (or not greater than `key` for iterators in the descending order). If the iterator
+    /// is already positioned at or after such an entry, the call has no effect.
+    ///
+    /// Unlike skipping entries one by one, seeking does not read skipped entries
+    /// from the database.
+    pub fn seek(&mut self, key: &K) {
+        if self.ended {
+            return;
+        }
+        if self.detach_prefix {
+            let mut raw_key = self.prefix.clone();
+            raw_key.extend_from_slice(&key_bytes(key));
+            self.base_iter.seek(&raw_key);
+        } else {
+            self.base_iter.seek(&key_bytes(key));
+        }
+    }
+
     /// Drops the keys returned by the underlying iterator without parsing them.
     pub(crate) fn drop_key_type(self) -> Iter<'a, (), V> {
         Iter {
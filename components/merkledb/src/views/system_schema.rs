@@ -15,12 +15,15 @@
 use exonum_crypto::Hash;
 
 use crate::{
-    views::{AsReadonly, IndexType, RawAccess, ViewWithMetadata},
+    views::{
+        AsReadonly, IndexAddress, IndexType, IndexesPool, RawAccess, ResolvedAddress, View,
+        ViewWithMetadata,
+    },
     Fork, ObjectHash, ProofMapIndex,
 };
 
 /// Name of the state aggregator proof map.
-const STATE_AGGREGATOR: &str = "__STATE_AGGREGATOR__";
+pub(crate) const STATE_AGGREGATOR: &str = "__STATE_AGGREGATOR__";
 
 pub fn get_state_aggregator<T: RawAccess>(
     access: T,
@@ -35,6 +38,42 @@ pub fn get_state_aggregator<T: RawAccess>(
     ProofMapIndex::new(view)
 }
 
+/// Size of an index in the storage.
+///
+/// The size is measured in terms of key-value pairs stored by the index in the database,
+/// so it includes auxiliary data (e.g., internal nodes of Merkelized indexes) and does not
+/// include overhead of the database backend, such as compression or write amplification.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IndexSize {
+    /// Number of key-value pairs in the index.
+    pub entries: u64,
+    /// Total size of keys and values in the index, in bytes.
+    pub bytes: u64,
+}
+
+impl IndexSize {
+    fn add(&mut self, key_len: usize, value_len: usize) {
+        self.entries += 1;
+        self.bytes += (key_len + value_len) as u64;
+    }
+
+    fn is_empty(self) -> bool {
+        self.entries == 0 && self.bytes == 0
+    }
+}
+
+/// Computes the size of the view at the specified address by iterating over its entries.
+fn compute_index_size<T: RawAccess>(access: T, address: ResolvedAddress) -> IndexSize {
+    let view = View::new(access, address);
+    let mut size = IndexSize::default();
+    let mut iter = view.iter_bytes(&[]);
+    while let Some((key, value)) = iter.next() {
+        size.add(key.len(), value.len());
+    }
+    size
+}
+
 /// System-wide information about the database.
 ///
 /// # Examples
@@ -88,6 +127,64 @@ impl<T: RawAccess> SystemSchema<T> {
     }
 }
 
+impl<T: RawAccess> SystemSchema<T> {
+    /// Returns the size of the index with the specified address, or `None`
+    /// if the index does not exist. Sizes take into account changes in `Fork`s.
+    ///
+    /// Sizes are not tracked by the database, so that the size accounting does not slow down
+    /// merging changes. Instead, the method iterates over the index, which takes time
+    /// proportional to the index size.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum_merkledb::{access::CopyAccessExt, Database, TemporaryDB, SystemSchema};
+    /// let db = TemporaryDB::new();
+    /// let fork = db.fork();
+    /// fork.get_map("map").put(&1_u32, "value".to_owned());
+    /// db.merge(fork.into_patch()).unwrap();
+    ///
+    /// let snapshot = db.snapshot();
+    /// let size = SystemSchema::new(&snapshot).index_size("map").unwrap();
+    /// assert_eq!(size.entries, 1);
+    /// // Key (4 bytes) and value (5 bytes).
+    /// assert_eq!(size.bytes, 9);
+    /// ```
+    pub fn index_size(&self, addr: impl Into<IndexAddress>) -> Option<IndexSize> {
+        let addr = addr.into();
+        let metadata = ViewWithMetadata::get_metadata_unchecked(self.0.clone(), &addr)?;
+        let resolved = ResolvedAddress::new(addr.name(), Some(metadata.identifier()));
+        Some(compute_index_size(self.0.clone(), resolved))
+    }
+
+    /// Returns sizes of all non-empty indexes in the database, ordered by the index address.
+    ///
+    /// The method iterates over all indexes in the database, so it is intended
+    /// for diagnostics and maintenance tasks rather than for regular use.
+    pub fn index_sizes(&self) -> Vec<(IndexAddress, IndexSize)> {
+        let pool = IndexesPool::new(self.0.clone());
+        let mut sizes: Vec<_> = pool
+            .index_addresses_with_ids()
+            .into_iter()
+            .filter_map(|(address, id)| {
+                let resolved = ResolvedAddress::new(address.name(), Some(id));
+                let size = compute_index_size(self.0.clone(), resolved);
+                if size.is_empty() {
+                    None
+                } else {
+                    Some((address, size))
+                }
+            })
+            .collect();
+        sizes.sort_unstable_by(|(x, _), (y, _)| {
+            let x_key = (x.name(), x.id_in_group(), x.is_in_migration());
+            let y_key = (y.name(), y.id_in_group(), y.is_in_migration());
+            x_key.cmp(&y_key)
+        });
+        sizes
+    }
+}
+
 impl<T: RawAccess + AsReadonly> SystemSchema<T> {
     /// Returns the state aggregator of the database. The aggregator is up to date for `Snapshot`s
     /// (including `Patch`es), but is generally stale for `Fork`s.
@@ -141,11 +238,11 @@ impl SystemSchema<&Fork> {
 
 #[cfg(test)]
 mod tests {
-    use super::{Fork, ObjectHash, SystemSchema};
+    use super::{Fork, IndexSize, ObjectHash, SystemSchema};
     use crate::{
         access::{AccessExt, CopyAccessExt},
         migration::Migration,
-        Database, HashTag, TemporaryDB,
+        Database, HashTag, IndexAddress, TemporaryDB,
     };
 
     fn initial_changes(fork: &Fork) {
@@ -275,4 +372,67 @@ mod tests {
         let system_schema = SystemSchema::new(&snapshot);
         assert_eq!(system_schema.state_hash(), HashTag::empty_map_hash());
     }
+
+    #[test]
+    fn index_sizes() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        {
+            let mut map = fork.get_map("map");
+            map.put(&1_u32, "foo".to_owned());
+            map.put(&2_u32, "bar".to_owned());
+            map.put(&3_u32, "baz".to_owned());
+        }
+        fork.get_entry(("group", &1_u8)).set(7_u64);
+        fork.get_list::<_, u64>("empty").extend(vec![]);
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let schema = SystemSchema::new(&snapshot);
+        let map_size = schema.index_size("map").unwrap();
+        assert_eq!((map_size.entries, map_size.bytes), (3, 21));
+        assert_eq!(schema.index_size("empty"), Some(IndexSize::default()));
+        assert_eq!(schema.index_size("nonexistent"), None);
+        let sizes = schema.index_sizes();
+        let addresses: Vec<_> = sizes.iter().map(|(addr, _)| addr.clone()).collect();
+        assert_eq!(
+            addresses,
+            vec![IndexAddress::from(("group", &1_u8)), "map".into()]
+        );
+        assert_eq!(sizes[0].1.entries, 1);
+
+        // Overwrites, deletions and range removals are taken into account, including
+        // when the patch is converted back into a fork.
+        let fork = db.fork();
+        {
+            let mut map = fork.get_map("map");
+            map.put(&1_u32, "longer".to_owned());
+            map.remove(&2_u32);
+        }
+        let mut fork = Fork::from(fork.into_patch());
+        fork.remove_range("map", &3_u32, &4_u32).unwrap();
+        fork.get_map("map").put(&5_u32, "qux".to_owned());
+        fork.get_entry::<_, u64>(("group", &1_u8)).remove();
+        // Sizes take into account changes in the fork.
+        let map_size = SystemSchema::new(&fork).index_size("map").unwrap();
+        assert_eq!((map_size.entries, map_size.bytes), (2, 17));
+        fork.flush();
+        let patch = fork.into_patch();
+        let map_size = SystemSchema::new(&patch).index_size("map").unwrap();
+        assert_eq!((map_size.entries, map_size.bytes), (2, 17));
+        db.merge(patch).unwrap();
+
+        let snapshot = db.snapshot();
+        let schema = SystemSchema::new(&snapshot);
+        assert_eq!(schema.index_size("map"), Some(map_size));
+        assert_eq!(schema.index_sizes().len(), 1);
+
+        let fork = db.fork();
+        fork.get_map::<_, u32, String>("map").clear();
+        db.merge(fork.into_patch()).unwrap();
+        let snapshot = db.snapshot();
+        let schema = SystemSchema::new(&snapshot);
+        assert_eq!(schema.index_size("map"), Some(IndexSize::default()));
+        assert!(schema.index_sizes().is_empty());
+    }
 }
@@ -15,14 +15,15 @@
 use assert_matches::assert_matches;
 use url::form_urlencoded::byte_serialize;
 
-use std::{num::NonZeroU64, panic, rc::Rc};
+use std::{num::NonZeroU64, ops::Bound, panic, rc::Rc};
 
 use crate::{
-    access::CopyAccessExt,
+    access::{AccessErrorKind, CopyAccessExt},
     db,
     validation::is_valid_identifier,
     views::{IndexAddress, IndexType, RawAccess, View, ViewWithMetadata},
-    Database, DbOptions, Fork, ListIndex, MapIndex, ResolvedAddress, RocksDB, TemporaryDB,
+    CompressionType, Database, DbOptions, EncryptionKeys, EncryptionOptions, Fork, ListIndex,
+    MapIndex, ResolvedAddress, RocksDB, Snapshot, SystemSchema, TemporaryDB, ValueRef,
 };
 
 const IDX_NAME: &str = "idx_name";
@@ -46,6 +47,16 @@ fn assert_iter<T: RawAccess>(view: &View<T>, from: u8, assumed: &[(u8, u8)]) {
     assert_eq!(values, assumed);
 }
 
+fn assert_iter_rev<T: RawAccess>(view: &View<T>, upper: Bound<&[u8]>, assumed: &[(u8, u8)]) {
+    let mut iter = view.iter_bytes_rev(upper);
+    let mut values = Vec::new();
+    while let Some((k, v)) = iter.next() {
+        values.push((k[0], v[0]));
+    }
+
+    assert_eq!(values, assumed);
+}
+
 fn assert_initial_state<T: RawAccess>(view: &View<T>) {
     assert_eq!(view.get_bytes(&[1]), Some(vec![1]));
     assert_eq!(view.get_bytes(&[2]), Some(vec![2]));
@@ -266,6 +277,66 @@ where
     assert_iter(&view, 4, &[(4, 0)]);
 }
 
+fn test_remove_range<T, I>(db: &T, address: I)
+where
+    T: Database,
+    I: Into<ResolvedAddress> + Copy,
+{
+    let fork = db.fork();
+    {
+        let mut view = View::new(&fork, address);
+        for i in 0_u8..8 {
+            view.put(&vec![i], vec![i]);
+        }
+    }
+    db.merge(fork.into_patch()).unwrap();
+
+    let fork = db.fork();
+    {
+        let mut view = View::new(&fork, address);
+        view.put(&vec![8], vec![8]);
+        view.remove_range(&vec![2], &vec![5]);
+        view.put(&vec![3], vec![33]);
+        // Removes a key from the snapshot and a key from the changes.
+        view.remove_range(&vec![7], &vec![9]);
+        // Empty ranges are ignored.
+        view.remove_range(&vec![6], &vec![6]);
+        view.remove_range(&vec![6], &vec![0]);
+
+        assert_eq!(view.get_bytes(&[2]), None);
+        assert_eq!(view.get_bytes(&[3]), Some(vec![33]));
+        assert_eq!(view.get_bytes(&[8]), None);
+        assert!(!view.contains_raw_key(&[4]));
+        assert!(view.contains_raw_key(&[5]));
+        assert_iter(&view, 0, &[(0, 0), (1, 1), (3, 33), (5, 5), (6, 6)]);
+        assert_iter(&view, 4, &[(5, 5), (6, 6)]);
+        assert_iter_rev(
+            &view,
+            Bound::Unbounded,
+            &[(6, 6), (5, 5), (3, 33), (1, 1), (0, 0)],
+        );
+    }
+
+    let patch = fork.into_patch();
+    {
+        let view = View::new(&patch, address);
+        assert_eq!(view.get_bytes(&[4]), None);
+        assert_iter(&view, 2, &[(3, 33), (5, 5), (6, 6)]);
+        assert_iter_rev(&view, Bound::Excluded(&[5]), &[(3, 33), (1, 1), (0, 0)]);
+    }
+    db.merge(patch).unwrap();
+
+    let snapshot = db.snapshot();
+    let view = View::new(&snapshot, address);
+    assert_eq!(view.get_bytes(&[7]), None);
+    assert_iter(&view, 0, &[(0, 0), (1, 1), (3, 33), (5, 5), (6, 6)]);
+    assert_iter_rev(
+        &view,
+        Bound::Unbounded,
+        &[(6, 6), (5, 5), (3, 33), (1, 1), (0, 0)],
+    );
+}
+
 fn test_fork_iter<T, I>(db: &T, address: I)
 where
     T: Database,
@@ -361,6 +432,195 @@ where
     assert_iter(&view, 0, &[(10, 10), (20, 20), (30, 30)]);
 }
 
+fn test_fork_iter_rev<T, I>(db: &T, address: I)
+where
+    T: Database,
+    I: Into<ResolvedAddress> + Copy,
+{
+    let fork = db.fork();
+    {
+        let mut view = View::new(&fork, address);
+        view.put(&vec![10], vec![10]);
+        view.put(&vec![20], vec![20]);
+        view.put(&vec![30], vec![30]);
+        // Entries of the neighboring views must not be visible.
+        View::new(&fork, ("idx", 41)).put(&vec![255], vec![255]);
+        View::new(&fork, ("idx", 43)).put(&(), vec![0]);
+    }
+    db.merge(fork.into_patch()).unwrap();
+
+    let mut fork = db.fork();
+    {
+        let mut view = View::new(&fork, address);
+
+        // Stored
+        assert_iter_rev(&view, Bound::Unbounded, &[(30, 30), (20, 20), (10, 10)]);
+        assert_iter_rev(&view, Bound::Included(&[20]), &[(20, 20), (10, 10)]);
+        assert_iter_rev(&view, Bound::Excluded(&[20]), &[(10, 10)]);
+        assert_iter_rev(&view, Bound::Included(&[25]), &[(20, 20), (10, 10)]);
+        assert_iter_rev(&view, Bound::Included(&[5]), &[]);
+
+        // Inserted, replaced and deleted
+        view.put(&vec![5], vec![5]);
+        view.put(&vec![25], vec![25]);
+        view.put(&vec![35], vec![35]);
+        view.put(&vec![30], vec![31]);
+        view.remove(&vec![20]);
+        view.remove(&vec![15]);
+        assert_iter_rev(
+            &view,
+            Bound::Unbounded,
+            &[(35, 35), (30, 31), (25, 25), (10, 10), (5, 5)],
+        );
+        assert_iter_rev(&view, Bound::Excluded(&[30]), &[(25, 25), (10, 10), (5, 5)]);
+    }
+
+    // Changes in the flushed patch are merged with the working changes.
+    fork.flush();
+    {
+        let mut view = View::new(&fork, address);
+        view.remove(&vec![35]);
+        view.put(&vec![20], vec![21]);
+        assert_iter_rev(
+            &view,
+            Bound::Unbounded,
+            &[(30, 31), (25, 25), (20, 21), (10, 10), (5, 5)],
+        );
+    }
+
+    // Cleared
+    let fork = db.fork();
+    let mut view = View::new(&fork, address);
+    view.clear();
+    view.put(&vec![15], vec![15]);
+    assert_iter_rev(&view, Bound::Unbounded, &[(15, 15)]);
+}
+
+fn collect_bytes(iter: &mut crate::Iter<'_>) -> Vec<(u8, u8)> {
+    let mut values = Vec::new();
+    while let Some((k, v)) = iter.next() {
+        values.push((k[0], v[0]));
+    }
+    values
+}
+
+fn test_iter_seek<T, I>(db: &T, address: I)
+where
+    T: Database,
+    I: Into<ResolvedAddress> + Copy,
+{
+    let fork = db.fork();
+    {
+        let mut view = View::new(&fork, address);
+        for i in 1..=4 {
+            view.put(&vec![i * 10], vec![i * 10]);
+        }
+        // Entries of the neighboring views must not be visible.
+        View::new(&fork, ("idx", 41)).put(&vec![255], vec![255]);
+        View::new(&fork, ("idx", 43)).put(&(), vec![0]);
+    }
+    db.merge(fork.into_patch()).unwrap();
+
+    // Snapshot
+    let snapshot = db.snapshot();
+    let view = View::new(&snapshot, address);
+    let mut iter = view.iter_bytes(&[]);
+    iter.seek(&[15]);
+    assert_eq!(iter.peek(), Some((&[20_u8][..], &[20_u8][..])));
+    iter.next();
+    // Seeking backwards has no effect.
+    iter.seek(&[10]);
+    assert_eq!(collect_bytes(&mut iter), vec![(30, 30), (40, 40)]);
+    let mut iter = view.iter_bytes(&[]);
+    iter.seek(&[50]);
+    assert!(iter.next().is_none());
+
+    let mut iter = view.iter_bytes_rev(Bound::Unbounded);
+    iter.seek(&[35]);
+    assert_eq!(collect_bytes(&mut iter), vec![(30, 30), (20, 20), (10, 10)]);
+    let mut iter = view.iter_bytes_rev(Bound::Excluded(&[40]));
+    iter.seek(&[30]);
+    iter.seek(&[35]);
+    assert_eq!(collect_bytes(&mut iter), vec![(30, 30), (20, 20), (10, 10)]);
+    let mut iter = view.iter_bytes_rev(Bound::Unbounded);
+    iter.seek(&[5]);
+    assert!(iter.next().is_none());
+
+    // Inserted, replaced and deleted
+    let fork = db.fork();
+    let mut view = View::new(&fork, address);
+    view.put(&vec![25], vec![25]);
+    view.put(&vec![40], vec![41]);
+    view.remove(&vec![30]);
+    {
+        let mut iter = view.iter_bytes(&[]);
+        iter.seek(&[21]);
+        assert_eq!(collect_bytes(&mut iter), vec![(25, 25), (40, 41)]);
+        let mut iter = view.iter_bytes(&[]);
+        iter.seek(&[30]);
+        assert_eq!(collect_bytes(&mut iter), vec![(40, 41)]);
+        let mut iter = view.iter_bytes_rev(Bound::Unbounded);
+        iter.seek(&[30]);
+        assert_eq!(collect_bytes(&mut iter), vec![(25, 25), (20, 20), (10, 10)]);
+    }
+
+    // Cleared
+    view.clear();
+    view.put(&vec![15], vec![15]);
+    view.put(&vec![35], vec![35]);
+    let mut iter = view.iter_bytes(&[]);
+    iter.seek(&[20]);
+    assert_eq!(collect_bytes(&mut iter), vec![(35, 35)]);
+    let mut iter = view.iter_bytes_rev(Bound::Unbounded);
+    iter.seek(&[20]);
+    assert_eq!(collect_bytes(&mut iter), vec![(15, 15)]);
+}
+
+fn test_get_ref<T, I>(db: &T, address: I)
+where
+    T: Database,
+    I: Into<ResolvedAddress> + Copy,
+{
+    let blob = vec![7_u8; 1 << 20];
+    let fork = db.fork();
+    {
+        let mut view = View::new(&fork, address);
+        view.put(&vec![1], blob.clone());
+        view.put(&vec![2], vec![2]);
+        view.put(&vec![3], vec![3]);
+    }
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    let address = address.into();
+    let value = snapshot.get_ref(&address, &[1]).unwrap();
+    assert_eq!(&*value, blob.as_slice());
+    assert_eq!(value.into_vec(), blob);
+    assert!(snapshot.get_ref(&address, &[4]).is_none());
+    let view = View::new(&snapshot, address.clone());
+    assert_eq!(view.get::<_, Vec<u8>>(&vec![1_u8]), Some(blob.clone()));
+
+    // Changes in the fork take precedence over the snapshot.
+    let fork = db.fork();
+    let mut view = View::new(&fork, address.clone());
+    view.put(&vec![2], vec![20]);
+    view.remove(&vec![3]);
+    view.put(&vec![4], vec![4]);
+    for key in 1..=5 {
+        let value = view.get_bytes_ref(&[key]).map(|value| value.to_vec());
+        assert_eq!(value, view.get_bytes(&[key]));
+    }
+    assert_eq!(&*view.get_bytes_ref(&[2]).unwrap(), &[20]);
+    assert!(view.get_bytes_ref(&[3]).is_none());
+    drop(view);
+
+    let patch = fork.into_patch();
+    for key in 1..=5 {
+        let value = patch.get_ref(&address, &[key]).map(ValueRef::into_vec);
+        assert_eq!(value, patch.get(&address, &[key]));
+    }
+}
+
 #[test]
 fn test_database_check_correct_version() {
     let db = TemporaryDB::default();
@@ -400,6 +660,192 @@ fn fork_iter_prefixed() {
     test_fork_iter(&TemporaryDB::new(), PREFIXED_IDX);
 }
 
+#[test]
+fn fork_iter_rev() {
+    test_fork_iter_rev(&TemporaryDB::new(), IDX_NAME);
+}
+
+#[test]
+fn fork_iter_rev_prefixed() {
+    test_fork_iter_rev(&TemporaryDB::new(), PREFIXED_IDX);
+}
+
+#[test]
+fn fork_iter_rev_rocksdb() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    test_fork_iter_rev(&db, IDX_NAME);
+    test_fork_iter_rev(&db, PREFIXED_IDX);
+}
+
+#[test]
+fn iter_seek() {
+    test_iter_seek(&TemporaryDB::new(), IDX_NAME);
+}
+
+#[test]
+fn iter_seek_prefixed() {
+    test_iter_seek(&TemporaryDB::new(), PREFIXED_IDX);
+}
+
+#[test]
+fn iter_seek_rocksdb() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    test_iter_seek(&db, IDX_NAME);
+    test_iter_seek(&db, PREFIXED_IDX);
+}
+
+#[test]
+fn get_ref() {
+    test_get_ref(&TemporaryDB::new(), IDX_NAME);
+    test_get_ref(&TemporaryDB::new(), PREFIXED_IDX);
+}
+
+#[test]
+fn get_ref_rocksdb() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    test_get_ref(&db, IDX_NAME);
+    test_get_ref(&db, PREFIXED_IDX);
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let options = DbOptions::default()
+        .with_encryption(EncryptionOptions::new(0).with_column_family(IDX_NAME));
+    let db = RocksDB::open_encrypted(&dir, &options, EncryptionKeys::new(0, &[1; 32])).unwrap();
+    test_get_ref(&db, IDX_NAME);
+}
+
+#[test]
+fn index_iter_seek() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    let mut map = fork.get_map::<_, u64, u64>("map");
+    for i in 0..100 {
+        map.put(&(i * 2), i);
+    }
+    let mut iter = map.iter();
+    iter.seek(&51);
+    assert_eq!(iter.next(), Some((52, 26)));
+    let mut keys = map.keys();
+    keys.seek(&197);
+    assert_eq!(keys.next(), Some(198));
+    assert_eq!(keys.next(), None);
+}
+
+#[test]
+fn rocksdb_with_tuning_options() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let options = DbOptions::default()
+        .with_block_cache_size(1 << 20)
+        .with_compression_per_level(vec![CompressionType::None, CompressionType::Snappy])
+        .with_bloom_filter_bits(10)
+        .with_write_buffer_size(1 << 20)
+        .with_write_buffer_number(3, 1);
+
+    {
+        let db = RocksDB::open(&dir, &options).unwrap();
+        let fork = db.fork();
+        fork.get_list(IDX_NAME).extend(vec![1_u32, 2, 3]);
+        fork.get_map(("map", &1_u8)).put(&1_u8, "foo".to_owned());
+        db.merge(fork.into_patch()).unwrap();
+    }
+
+    // Column families created before must be opened with the same options.
+    let db = RocksDB::open(&dir, &options).unwrap();
+    let snapshot = db.snapshot();
+    let list = snapshot.get_list::<_, u32>(IDX_NAME);
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    let map = snapshot.get_map::<_, u8, String>(("map", &1_u8));
+    assert_eq!(map.get(&1), Some("foo".to_owned()));
+    drop(snapshot);
+    drop(db);
+
+    let db = RocksDB::open_read_only(&dir, &options).unwrap();
+    let list_len = db.snapshot().get_list::<_, u32>(IDX_NAME).len();
+    assert_eq!(list_len, 3);
+}
+
+#[test]
+fn rocksdb_with_encryption() {
+    const SECRET_LIST: &str = "secret.list";
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let options = DbOptions::default()
+        .with_encryption(EncryptionOptions::new(0).with_column_family("secret"));
+    RocksDB::open(&dir, &options).unwrap_err();
+
+    let old_key = [1; 32];
+    {
+        let db = RocksDB::open_encrypted(&dir, &options, EncryptionKeys::new(0, &old_key)).unwrap();
+        let fork = db.fork();
+        fork.get_list(SECRET_LIST).extend(vec![1_u32, 2, 3]);
+        fork.get_map(("secret.map", &1_u8))
+            .put(&1_u8, "foo".to_owned());
+        fork.get_list(IDX_NAME).extend(vec![4_u32, 5]);
+        db.merge(fork.into_patch()).unwrap();
+
+        let snapshot = db.snapshot();
+        let list = snapshot.get_list::<_, u32>(SECRET_LIST);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(list.get(1), Some(2));
+    }
+
+    // Rotate the key. The values encrypted with the old key remain readable.
+    let options = DbOptions::default()
+        .with_encryption(EncryptionOptions::new(1).with_column_family("secret"));
+    let new_key = [2; 32];
+    RocksDB::open_encrypted(&dir, &options, EncryptionKeys::new(0, &old_key)).unwrap_err();
+    {
+        let keys = EncryptionKeys::new(1, &new_key).with_retired_key(0, &old_key);
+        let db = RocksDB::open_encrypted(&dir, &options, keys).unwrap();
+        let map = db
+            .snapshot()
+            .get_map::<_, u8, String>(("secret.map", &1_u8));
+        assert_eq!(map.get(&1), Some("foo".to_owned()));
+
+        assert_eq!(db.reencrypt().unwrap(), 4);
+        assert_eq!(db.reencrypt().unwrap(), 0);
+    }
+
+    // After re-encryption, the old key is no longer necessary.
+    let db = RocksDB::open_encrypted(&dir, &options, EncryptionKeys::new(1, &new_key)).unwrap();
+    let snapshot = db.snapshot();
+    let list = snapshot.get_list::<_, u32>(SECRET_LIST);
+    assert_eq!(list.iter_from(1).collect::<Vec<_>>(), vec![2, 3]);
+    let map = snapshot.get_map::<_, u8, String>(("secret.map", &1_u8));
+    assert_eq!(map.iter().collect::<Vec<_>>(), vec![(1, "foo".to_owned())]);
+    let list = snapshot.get_list::<_, u32>(IDX_NAME);
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec![4, 5]);
+}
+
+#[test]
+fn rocksdb_backup_and_restore() {
+    let db_dir = tempfile::TempDir::new().unwrap();
+    let backup_dir = tempfile::TempDir::new().unwrap();
+    let db = RocksDB::open(&db_dir, &DbOptions::default()).unwrap();
+    let fork = db.fork();
+    fork.get_list(IDX_NAME).extend(vec![1_u32, 2, 3]);
+    db.merge(fork.into_patch()).unwrap();
+    db.backup(backup_dir.path()).unwrap();
+
+    // Changes made after the backup must not be restored.
+    let fork = db.fork();
+    fork.get_list(IDX_NAME).push(4_u32);
+    db.merge(fork.into_patch()).unwrap();
+
+    let restored_dir = tempfile::TempDir::new().unwrap();
+    let restored =
+        RocksDB::restore(backup_dir.path(), &restored_dir, &DbOptions::default()).unwrap();
+    let list = restored
+        .snapshot()
+        .get_list::<_, u32>(IDX_NAME)
+        .iter()
+        .collect::<Vec<_>>();
+    assert_eq!(list, vec![1, 2, 3]);
+    assert_eq!(db.snapshot().get_list::<_, u32>(IDX_NAME).len(), 4);
+}
+
 #[test]
 fn changelog() {
     test_changelog(&TemporaryDB::new(), IDX_NAME);
@@ -610,6 +1056,156 @@ fn clear_prefixed_view() {
     test_clear_view(&TemporaryDB::new(), PREFIXED_IDX);
 }
 
+#[test]
+fn remove_range() {
+    test_remove_range(&TemporaryDB::new(), IDX_NAME);
+    test_remove_range(&TemporaryDB::new(), PREFIXED_IDX);
+}
+
+#[test]
+fn remove_range_rocksdb() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let db = RocksDB::open(&dir, &DbOptions::default()).unwrap();
+    test_remove_range(&db, IDX_NAME);
+    test_remove_range(&db, PREFIXED_IDX);
+}
+
+#[test]
+fn remove_range_from_fork() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    fork.get_map("map").extend((0_u8..10).map(|i| (i, i)));
+    fork.get_list("list").extend(0_u8..10);
+    {
+        let mut set = fork.get_key_set("set");
+        for i in 0_u8..10 {
+            set.insert(&i);
+        }
+    }
+    db.merge(fork.into_patch()).unwrap();
+
+    let mut fork = db.fork();
+    fork.remove_range("map", &3_u8, &6_u8).unwrap();
+    fork.remove_range("set", &0_u8, &5_u8).unwrap();
+    // Removing a range from a non-existing index is a no-op.
+    fork.remove_range("absent", &0_u8, &5_u8).unwrap();
+    let err = fork.remove_range("list", &0_u8, &5_u8).unwrap_err();
+    assert_matches!(
+        err.kind,
+        AccessErrorKind::WrongIndexType {
+            actual: IndexType::List,
+            ..
+        }
+    );
+
+    // Range removals are rolled back as other changes.
+    fork.flush();
+    fork.remove_range("map", &0_u8, &10_u8).unwrap();
+    fork.rollback();
+
+    let patch = fork.into_patch();
+    let changes = patch.index_changes();
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].address, IndexAddress::from_root("map"));
+    assert_eq!(changes[0].removed_ranges, 1);
+    assert_eq!(changes[0].deletes, 0);
+    db.merge(patch).unwrap();
+
+    let snapshot = db.snapshot();
+    let map: MapIndex<_, u8, u8> = snapshot.get_map("map");
+    assert_eq!(map.keys().collect::<Vec<_>>(), vec![0, 1, 2, 6, 7, 8, 9]);
+    let set = snapshot.get_key_set::<_, u8>("set");
+    assert_eq!(set.iter().collect::<Vec<_>>(), vec![5, 6, 7, 8, 9]);
+}
+
+#[test]
+fn overlapping_removed_ranges() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    fork.get_map("map").extend((0_u8..10).map(|i| (i, i)));
+    db.merge(fork.into_patch()).unwrap();
+
+    let mut fork = db.fork();
+    fork.remove_range("map", &2_u8, &5_u8).unwrap();
+    fork.remove_range("map", &4_u8, &7_u8).unwrap();
+    // Adjacent ranges are merged as well.
+    fork.remove_range("map", &7_u8, &8_u8).unwrap();
+    fork.remove_range("map", &0_u8, &1_u8).unwrap();
+    fork.remove_range("map", &3_u8, &4_u8).unwrap();
+
+    let patch = fork.into_patch();
+    let changes = patch.index_changes();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].removed_ranges, 2);
+    let size = SystemSchema::new(&patch).index_size("map").unwrap();
+    assert_eq!(size.entries, 3);
+    db.merge(patch).unwrap();
+
+    let snapshot = db.snapshot();
+    let map: MapIndex<_, u8, u8> = snapshot.get_map("map");
+    assert_eq!(map.keys().collect::<Vec<_>>(), vec![1, 8, 9]);
+}
+
+#[test]
+fn remove_group_indexes() {
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    for height in 0_u64..5 {
+        fork.get_proof_list(("history", &height)).extend(0_u64..10);
+        fork.get_proof_map(("history_map", &height))
+            .put(&height, height);
+    }
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    let addr = IndexAddress::from_root("history").append_key(&1_u64);
+    let metadata = ViewWithMetadata::get_metadata(&snapshot, &addr)
+        .unwrap()
+        .unwrap();
+    let removed_addr = ResolvedAddress::new("history", Some(metadata.identifier()));
+
+    let fork = db.fork();
+    assert_eq!(fork.remove_group_indexes("history", &3_u64), 3);
+    // Repeated removal is a no-op.
+    assert_eq!(fork.remove_group_indexes("history", &3_u64), 0);
+    assert_eq!(fork.remove_group_indexes("absent", &3_u64), 0);
+    db.merge(fork.into_patch()).unwrap();
+
+    let snapshot = db.snapshot();
+    for height in 0_u64..3 {
+        assert!(snapshot.index_type(("history", &height)).is_none());
+    }
+    for height in 3_u64..5 {
+        assert_eq!(
+            snapshot
+                .get_proof_list::<_, u64>(("history", &height))
+                .len(),
+            10
+        );
+    }
+    // Indexes in other groups are not affected.
+    for height in 0_u64..5 {
+        let map = snapshot.get_proof_map::<_, u64, u64>(("history_map", &height));
+        assert_eq!(map.get(&height), Some(height));
+    }
+    // Data of the removed indexes is removed as well.
+    let view = View::new(&snapshot, removed_addr);
+    assert!(view
+        .iter::<_, Vec<u8>, Vec<u8>>(&[] as &[u8])
+        .next()
+        .is_none());
+
+    // Removed indexes can be re-created.
+    let fork = db.fork();
+    fork.get_proof_list(("history", &0_u64)).push(1_u64);
+    db.merge(fork.into_patch()).unwrap();
+    let snapshot = db.snapshot();
+    assert_eq!(
+        snapshot.get_proof_list::<_, u64>(("history", &0_u64)).len(),
+        1
+    );
+}
+
 #[test]
 fn clear_sibling_views() {
     const IDX_1: (&str, u64) = ("foo", 23);
@@ -1093,3 +1689,52 @@ fn fork_from_patch() {
     db.merge(fork.into_patch())
         .expect("Fork created from patch should be merged successfully");
 }
+
+#[test]
+fn index_changes_in_patch() {
+    use crate::migration::Migration;
+
+    let db = TemporaryDB::new();
+    let fork = db.fork();
+    fork.get_list("list").extend(vec![1_u32, 2, 3]);
+    fork.get_map(("group", &1_u8)).put(&1_u8, 1_u32);
+    fork.get_map(("group", &2_u8)).put(&2_u8, 2_u32);
+    db.merge(fork.into_patch()).unwrap();
+
+    let fork = db.fork();
+    {
+        let mut map = fork.get_map(("group", &2_u8));
+        map.remove(&2_u8);
+        map.put(&3_u8, 3_u32);
+        fork.get_list::<_, u32>("list").clear();
+        // Indexes with a name sharing the prefix with the group should not be confused
+        // with the group members.
+        fork.get_entry("group_entry").set(1_u32);
+        fork.get_map::<_, u8, u32>(("group", &1_u8)).get(&1);
+        Migration::new("foo", &fork).get_entry("bar").set(1_u32);
+    }
+    let changes = fork.into_patch().index_changes();
+
+    let summary: Vec<_> = changes
+        .iter()
+        .map(|changes| {
+            (
+                changes.address.clone(),
+                changes.puts,
+                changes.deletes,
+                changes.is_cleared,
+            )
+        })
+        .collect();
+    let mut migrated_addr = IndexAddress::from_root("foo.bar");
+    migrated_addr.set_in_migration();
+    assert_eq!(
+        summary,
+        vec![
+            (migrated_addr, 1, 0, false),
+            (IndexAddress::from(("group", &2_u8)), 1, 1, false),
+            (IndexAddress::from_root("group_entry"), 1, 0, false),
+            (IndexAddress::from_root("list"), 0, 0, true),
+        ]
+    );
+}
@@ -0,0 +1,137 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for RocksDB tuning based on index access patterns.
+
+use exonum_merkledb::{
+    access::CopyAccessExt, AccessPattern, Database, DbOptions, IndexType, ObjectHash, RocksDB,
+    Snapshot, SystemSchema,
+};
+use tempfile::TempDir;
+
+fn tuned_options() -> DbOptions {
+    DbOptions::default().with_access_pattern_tuning(true)
+}
+
+fn fill_db(db: &RocksDB) {
+    let fork = db.fork();
+    let mut map = fork.get_proof_map("map");
+    for i in 0_u64..1_000 {
+        map.put(&i, i.to_string());
+    }
+    // Lists in a group share a column family, which is configured with a prefix extractor.
+    for list_id in 1_u32..=3 {
+        let mut list = fork.get_proof_list(("lists", &list_id));
+        list.extend((0..u64::from(list_id) * 10).map(|i| i * 2));
+    }
+    fork.get_entry("entry").set(42_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+}
+
+fn check_db(snapshot: &dyn Snapshot) {
+    let map = snapshot.get_proof_map::<_, u64, String>("map");
+    assert_eq!(map.get(&500), Some("500".to_owned()));
+    assert_eq!(map.get(&1_000), None);
+    assert_eq!(map.iter().count(), 1_000);
+
+    for list_id in 1_u32..=3 {
+        let list = snapshot.get_proof_list::<_, u64>(("lists", &list_id));
+        let len = u64::from(list_id) * 10;
+        assert_eq!(list.len(), len);
+        assert_eq!(
+            list.iter().collect::<Vec<_>>(),
+            (0..len).map(|i| i * 2).collect::<Vec<_>>()
+        );
+        assert_eq!(list.iter_from(5).next(), Some(10));
+        assert_eq!(list.last(), Some((len - 1) * 2));
+        assert_eq!(list.iter_rev().next(), Some((len - 1) * 2));
+    }
+    assert_eq!(snapshot.get_entry::<_, u64>("entry").get(), Some(42));
+}
+
+#[test]
+fn access_patterns_of_index_types() {
+    assert_eq!(
+        IndexType::ProofMap.access_pattern(),
+        Some(AccessPattern::PointLookup)
+    );
+    assert_eq!(
+        IndexType::Map.access_pattern(),
+        Some(AccessPattern::PointLookup)
+    );
+    assert_eq!(
+        IndexType::ProofList.access_pattern(),
+        Some(AccessPattern::Scan)
+    );
+    assert_eq!(
+        IndexType::SparseList.access_pattern(),
+        Some(AccessPattern::Scan)
+    );
+    assert_eq!(IndexType::Tombstone.access_pattern(), None);
+}
+
+#[test]
+fn tuned_database_works_after_reopening() {
+    let temp_dir = TempDir::new().unwrap();
+    let state_hash = {
+        let db = RocksDB::open(temp_dir.path(), &tuned_options()).unwrap();
+        fill_db(&db);
+        let snapshot = db.snapshot();
+        check_db(&snapshot);
+        SystemSchema::new(&snapshot).state_hash()
+    };
+
+    let db = RocksDB::open(temp_dir.path(), &tuned_options()).unwrap();
+    let snapshot = db.snapshot();
+    check_db(&snapshot);
+    assert_eq!(SystemSchema::new(&snapshot).state_hash(), state_hash);
+
+    // Indexes created after reopening are tuned as well.
+    let fork = db.fork();
+    fork.get_proof_list(("lists", &4_u32)).push(1_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+    let snapshot = db.snapshot();
+    check_db(&snapshot);
+    let list = snapshot.get_proof_list::<_, u64>(("lists", &4_u32));
+    assert_eq!(list.iter().collect::<Vec<_>>(), vec![1]);
+    drop(snapshot);
+    drop(db);
+
+    let db = RocksDB::open_read_only(temp_dir.path(), &tuned_options()).unwrap();
+    check_db(&db.snapshot());
+}
+
+#[test]
+fn tuning_can_be_toggled_for_existing_database() {
+    let temp_dir = TempDir::new().unwrap();
+    let state_hash = {
+        let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+        fill_db(&db);
+        let snapshot = db.snapshot();
+        snapshot
+            .get_proof_map::<_, u64, String>("map")
+            .object_hash()
+    };
+
+    {
+        let db = RocksDB::open(temp_dir.path(), &tuned_options()).unwrap();
+        let snapshot = db.snapshot();
+        check_db(&snapshot);
+        let map = snapshot.get_proof_map::<_, u64, String>("map");
+        assert_eq!(map.object_hash(), state_hash);
+    }
+
+    let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+    check_db(&db.snapshot());
+}
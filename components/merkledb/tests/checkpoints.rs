@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use exonum_merkledb::{access::CopyAccessExt, Database, DbOptions, RocksDB};
+use std::fs;
 use tempfile::TempDir;
 
 #[test]
@@ -88,3 +89,64 @@ fn checkpoints() {
         checkpoint.merge_sync(fork.into_patch()).unwrap();
     }
 }
+
+#[test]
+fn named_checkpoints() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("db");
+    let db = RocksDB::open(&db_path, &DbOptions::default()).unwrap();
+    assert!(RocksDB::checkpoints(&db_path).unwrap().is_empty());
+
+    let fork = db.fork();
+    fork.get_entry("first").set(1_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+    db.checkpoint("before-second").unwrap();
+
+    let fork = db.fork();
+    fork.get_entry("second").set(2_u64);
+    db.merge_sync(fork.into_patch()).unwrap();
+    db.checkpoint("after_second").unwrap();
+
+    assert_eq!(
+        RocksDB::checkpoints(&db_path).unwrap(),
+        vec!["after_second".to_owned(), "before-second".to_owned()]
+    );
+    let err = db.checkpoint("before-second").unwrap_err();
+    assert!(err.to_string().contains("already exists"), "{}", err);
+    let err = db.checkpoint("bogus/name").unwrap_err();
+    assert!(
+        err.to_string().contains("Invalid checkpoint name"),
+        "{}",
+        err
+    );
+    assert!(db.checkpoint_snapshot("unknown").is_err());
+
+    // The checkpoint is not affected by the data added after its creation.
+    let snapshot = db.checkpoint_snapshot("before-second").unwrap();
+    assert_eq!(snapshot.get_entry::<_, u64>("first").get(), Some(1));
+    assert_eq!(snapshot.get_entry::<_, u64>("second").get(), None);
+
+    // Checkpoints can be opened while the database is in use.
+    let checkpoint =
+        RocksDB::open_checkpoint(&db_path, "after_second", &DbOptions::default()).unwrap();
+    let snapshot = checkpoint.snapshot();
+    assert_eq!(snapshot.get_entry::<_, u64>("second").get(), Some(2));
+    let fork = checkpoint.fork();
+    fork.get_entry("third").set(3_u64);
+    assert!(checkpoint.merge(fork.into_patch()).is_err());
+    drop(checkpoint);
+
+    // The database can be cloned by copying the checkpoint directory.
+    let clone_path = temp_dir.path().join("clone");
+    fs::create_dir(&clone_path).unwrap();
+    let checkpoint_path = RocksDB::checkpoint_path(&db_path, "after_second");
+    for entry in fs::read_dir(checkpoint_path).unwrap() {
+        let entry = entry.unwrap();
+        fs::copy(entry.path(), clone_path.join(entry.file_name())).unwrap();
+    }
+    let clone = RocksDB::open(&clone_path, &DbOptions::default()).unwrap();
+    assert_eq!(
+        clone.snapshot().get_entry::<_, u64>("second").get(),
+        Some(2)
+    );
+}
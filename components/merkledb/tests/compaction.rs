@@ -0,0 +1,51 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use exonum_merkledb::{access::CopyAccessExt, Database, DbOptions, RocksDB};
+use tempfile::TempDir;
+
+#[test]
+fn manual_compaction() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+
+    let fork = db.fork();
+    let mut map = fork.get_map("map");
+    for i in 0_u64..1_000 {
+        map.put(&i, vec![1_u8; 128]);
+    }
+    db.merge_sync(fork.into_patch()).unwrap();
+    let fork = db.fork();
+    fork.get_map::<_, u64, Vec<u8>>("map").clear();
+    db.merge_sync(fork.into_patch()).unwrap();
+
+    db.compact_range(Some("map")).unwrap();
+    db.compact_range(None).unwrap();
+    let err = db.compact_range(Some("unknown")).unwrap_err();
+    assert!(err.to_string().contains("Index `unknown` does not exist"));
+
+    let snapshot = db.snapshot();
+    assert_eq!(snapshot.get_map::<_, u64, Vec<u8>>("map").iter().count(), 0);
+}
+
+#[test]
+fn stall_control() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+
+    let stats = db.stall_stats().unwrap();
+    assert!(!stats.is_write_stopped);
+    db.set_background_jobs_limit(2).unwrap();
+    db.set_background_jobs_limit(0).unwrap_err();
+}
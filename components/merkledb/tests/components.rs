@@ -17,9 +17,9 @@
 use exonum_derive::FromAccess;
 
 use exonum_merkledb::{
-    access::{Access, CopyAccessExt, FromAccess, RawAccessMut},
-    BinaryKey, Database, Entry, Group, Lazy, ListIndex, ObjectHash, ProofEntry, ProofMapIndex,
-    TemporaryDB,
+    access::{Access, CopyAccessExt, DescribeSchema, FromAccess, RawAccessMut},
+    BinaryKey, Database, Entry, Group, IndexType, Lazy, ListIndex, ObjectHash, ProofEntry,
+    ProofMapIndex, Snapshot, TemporaryDB,
 };
 
 #[derive(FromAccess)]
@@ -295,3 +295,56 @@ fn multiple_flattened_fields() {
         Some(1_u64)
     );
 }
+
+#[test]
+fn schema_description() {
+    #[derive(FromAccess)]
+    #[from_access(describe)]
+    struct Inner<T: Access> {
+        list: ListIndex<T::Base, String>,
+        #[from_access(rename = "proof_entry")]
+        entry: ProofEntry<T::Base, u64>,
+    }
+
+    #[derive(FromAccess)]
+    #[from_access(describe)]
+    struct Schema<T: Access> {
+        map: ProofMapIndex<T::Base, u64, String>,
+        lazy: Lazy<T, Inner<T>>,
+        group: Group<T, str, Inner<T>>,
+        #[from_access(flatten)]
+        flattened: Inner<T>,
+    }
+
+    let descriptions = Schema::<&dyn Snapshot>::describe_schema("");
+    let summary: Vec<_> = descriptions
+        .iter()
+        .map(|desc| (desc.name.as_str(), desc.index_type))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("map", IndexType::ProofMap),
+            ("lazy.list", IndexType::List),
+            ("lazy.proof_entry", IndexType::ProofEntry),
+            ("group.list", IndexType::List),
+            ("group.proof_entry", IndexType::ProofEntry),
+            ("list", IndexType::List),
+            ("proof_entry", IndexType::ProofEntry),
+        ]
+    );
+
+    assert_eq!(descriptions[0].key_type.as_deref(), Some("u64"));
+    assert_eq!(
+        descriptions[0].value_type.as_deref(),
+        Some("alloc::string::String")
+    );
+    assert!(descriptions[0].group_key_types.is_empty());
+    assert_eq!(descriptions[3].key_type, None);
+    assert_eq!(descriptions[3].group_key_types, vec!["str".to_owned()]);
+
+    // Descriptions are relative to the provided name.
+    let descriptions = Schema::<&dyn Snapshot>::describe_schema("test");
+    assert_eq!(descriptions[0].name, "test.map");
+    assert_eq!(descriptions[6].name, "test.proof_entry");
+}
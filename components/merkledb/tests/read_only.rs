@@ -0,0 +1,51 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use exonum_merkledb::{access::CopyAccessExt, Database, DbOptions, RocksDB};
+use tempfile::TempDir;
+
+#[test]
+fn read_only_database() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = RocksDB::open(temp_dir.path(), &DbOptions::default()).unwrap();
+    {
+        let fork = db.fork();
+        fork.get_entry("first").set(1_u64);
+        fork.get_list("list").extend(vec![1_u64, 2, 3]);
+        db.merge_sync(fork.into_patch()).unwrap();
+    }
+
+    // The database can be opened in the read-only mode while it is open for writing.
+    let read_only_db = RocksDB::open_read_only(temp_dir.path(), &DbOptions::default()).unwrap();
+    let snapshot = read_only_db.snapshot();
+    assert_eq!(snapshot.get_entry::<_, u64>("first").get(), Some(1));
+    assert_eq!(
+        snapshot
+            .get_list::<_, u64>("list")
+            .iter()
+            .collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+
+    let fork = read_only_db.fork();
+    fork.get_entry("second").set(2_u64);
+    assert!(read_only_db.merge(fork.into_patch()).is_err());
+}
+
+#[test]
+fn read_only_database_should_exist() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("absent");
+    assert!(RocksDB::open_read_only(&path, &DbOptions::default()).is_err());
+}
@@ -0,0 +1,243 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Canonical JSON rendering and human-readable dumps of Protobuf messages.
+//!
+//! The JSON rendering follows the [Protobuf 3 JSON mapping] with the following rules, which make
+//! the output canonical (i.e., equal messages always have the same rendering):
+//!
+//! - Fields are named with their JSON names (`lowerCamelCase` by default); fields with
+//!   default values are omitted.
+//! - 64-bit integers are rendered as strings; non-finite floats as `"NaN"`, `"Infinity"`
+//!   and `"-Infinity"`.
+//! - Bytes are rendered with the standard base64 alphabet without padding, like
+//!   [`ProtobufBase64`] does.
+//! - Enum values are rendered with their names, or with numbers if the name is unknown.
+//! - `google.protobuf.Timestamp` is rendered as an RFC 3339 string.
+//! - Exonum cryptographic types (`exonum.crypto.Hash`, `PublicKey` and `Signature`)
+//!   are rendered as hex strings, in the same way they are serialized by `serde`.
+//! - Keys of JSON objects are sorted.
+//!
+//! [Protobuf 3 JSON mapping]: https://developers.google.com/protocol-buffers/docs/proto3#json
+//! [`ProtobufBase64`]: ../struct.ProtobufBase64.html
+
+use protobuf::{
+    reflect::{MessageDescriptor, ReflectFieldRef, ReflectValueRef},
+    text_format, MessageDyn, MessageFull,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use std::fmt::Write;
+
+use crate::ProtobufConvert;
+
+/// Types rendered as hex strings of their `data` field.
+const HEX_TYPES: &[&str] = &[
+    "exonum.crypto.Hash",
+    "exonum.crypto.PublicKey",
+    "exonum.crypto.Signature",
+];
+/// Full name of the Protobuf timestamp type.
+const TIMESTAMP_TYPE: &str = "google.protobuf.Timestamp";
+
+/// Renders a Protobuf message as canonical JSON. See the [module docs](index.html)
+/// for the description of the rendering rules.
+///
+/// # Examples
+///
+/// ```
+/// use exonum_proto::{json::to_json, proto::bit_vec::BitVec};
+/// use serde_json::json;
+///
+/// let mut bit_vec = BitVec::new();
+/// bit_vec.set_data(vec![0b_1010_0000]);
+/// bit_vec.set_len(3);
+/// assert_eq!(to_json(&bit_vec), json!({ "data": "oA", "len": "3" }));
+/// ```
+pub fn to_json(message: &dyn MessageDyn) -> Value {
+    let descriptor = message.descriptor_dyn();
+    if let Some(value) = render_special(&descriptor, message) {
+        return value;
+    }
+
+    let mut object = Map::new();
+    for field in descriptor.fields() {
+        let value = match field.get_reflect(message) {
+            ReflectFieldRef::Optional(value) => match value.value() {
+                Some(value) => value_to_json(&value),
+                None => continue,
+            },
+            ReflectFieldRef::Repeated(values) => {
+                if values.is_empty() {
+                    continue;
+                }
+                Value::Array(
+                    values
+                        .into_iter()
+                        .map(|value| value_to_json(&value))
+                        .collect(),
+                )
+            }
+            ReflectFieldRef::Map(entries) => {
+                if entries.is_empty() {
+                    continue;
+                }
+                let entries = (&entries)
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value_to_json(&value)));
+                Value::Object(entries.collect())
+            }
+        };
+        object.insert(field.json_name().to_owned(), value);
+    }
+    Value::Object(object)
+}
+
+/// Renders a Protobuf message in the Protobuf text format, which is suitable for debugging.
+pub fn to_debug_string(message: &dyn MessageDyn) -> String {
+    text_format::print_to_string_pretty(message)
+}
+
+/// Renders special types, which have a custom JSON representation.
+fn render_special(descriptor: &MessageDescriptor, message: &dyn MessageDyn) -> Option<Value> {
+    let full_name = descriptor.full_name();
+    if HEX_TYPES.contains(&full_name) {
+        let data = descriptor.field_by_name("data")?.get_singular(message);
+        let data = data
+            .as_ref()
+            .and_then(ReflectValueRef::to_bytes)
+            .unwrap_or(&[]);
+        let mut hex = String::with_capacity(data.len() * 2);
+        for byte in data {
+            write!(hex, "{:02x}", byte).unwrap();
+        }
+        Some(Value::String(hex))
+    } else if full_name == TIMESTAMP_TYPE {
+        let singular_value = |name| {
+            descriptor
+                .field_by_name(name)
+                .and_then(|field| field.get_singular(message))
+        };
+        let seconds =
+            singular_value("seconds").map_or(Some(0), |value| ReflectValueRef::to_i64(&value))?;
+        let nanos =
+            singular_value("nanos").map_or(Some(0), |value| ReflectValueRef::to_i32(&value))?;
+        let timestamp = i128::from(seconds) * 1_000_000_000 + i128::from(nanos);
+        let time = OffsetDateTime::from_unix_timestamp_nanos(timestamp).ok()?;
+        time.format(&Rfc3339).ok().map(Value::String)
+    } else {
+        None
+    }
+}
+
+fn float_to_json(value: f64) -> Value {
+    Number::from_f64(value).map_or_else(
+        || {
+            let repr = if value.is_nan() {
+                "NaN"
+            } else if value.is_sign_positive() {
+                "Infinity"
+            } else {
+                "-Infinity"
+            };
+            Value::String(repr.to_owned())
+        },
+        Value::Number,
+    )
+}
+
+fn value_to_json(value: &ReflectValueRef<'_>) -> Value {
+    match value {
+        ReflectValueRef::U32(value) => Value::from(*value),
+        ReflectValueRef::I32(value) => Value::from(*value),
+        ReflectValueRef::U64(value) => Value::String(value.to_string()),
+        ReflectValueRef::I64(value) => Value::String(value.to_string()),
+        ReflectValueRef::F32(value) => float_to_json(f64::from(*value)),
+        ReflectValueRef::F64(value) => float_to_json(*value),
+        ReflectValueRef::Bool(value) => Value::Bool(*value),
+        ReflectValueRef::String(value) => Value::String((*value).to_owned()),
+        ReflectValueRef::Bytes(value) => {
+            Value::String(base64::encode_config(value, base64::STANDARD_NO_PAD))
+        }
+        ReflectValueRef::Enum(descriptor, number) => descriptor
+            .value_by_number(*number)
+            .map_or_else(|| Value::from(*number), |value| value.name().into()),
+        ReflectValueRef::Message(message) => to_json(&**message),
+    }
+}
+
+/// Protobuf message decoded from its binary representation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DecodedMessage {
+    /// Full name of the Protobuf message type.
+    pub message_type: String,
+    /// Canonical JSON rendering of the message.
+    pub content: Value,
+    /// Human-readable dump of the message in the Protobuf text format.
+    pub debug_dump: String,
+}
+
+impl DecodedMessage {
+    /// Decodes a message of the type specified by the `descriptor` from `bytes`.
+    pub fn decode(descriptor: &MessageDescriptor, bytes: &[u8]) -> protobuf::Result<Self> {
+        let message = descriptor.parse_from_bytes(bytes)?;
+        Ok(Self::new(&*message))
+    }
+
+    /// Creates a decoded representation of the message.
+    pub fn new(message: &dyn MessageDyn) -> Self {
+        Self {
+            message_type: message.descriptor_dyn().full_name().to_owned(),
+            content: to_json(message),
+            debug_dump: to_debug_string(message),
+        }
+    }
+}
+
+/// Canonical JSON rendering for types convertible to Protobuf messages, such as types
+/// deriving `ProtobufConvert` and `BinaryValue`.
+///
+/// # Examples
+///
+/// ```
+/// use exonum_proto::json::ProtobufJson;
+/// use bit_vec::BitVec;
+/// use serde_json::json;
+///
+/// let bit_vec = BitVec::from_bytes(&[0b_1010_0000]);
+/// assert_eq!(bit_vec.to_canonical_json(), json!({ "data": "oA", "len": "8" }));
+/// ```
+pub trait ProtobufJson {
+    /// Renders the value as canonical JSON.
+    fn to_canonical_json(&self) -> Value;
+    /// Renders the value in the Protobuf text format.
+    fn to_debug_string(&self) -> String;
+}
+
+impl<T> ProtobufJson for T
+where
+    T: ProtobufConvert,
+    T::ProtoStruct: MessageFull,
+{
+    fn to_canonical_json(&self) -> Value {
+        to_json(&self.to_pb())
+    }
+
+    fn to_debug_string(&self) -> String {
+        to_debug_string(&self.to_pb())
+    }
+}
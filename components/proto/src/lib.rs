@@ -54,6 +54,7 @@
 
 pub use protobuf_convert::*;
 
+pub mod json;
 pub mod proto;
 
 use anyhow::{ensure, format_err, Error};
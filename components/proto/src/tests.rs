@@ -15,11 +15,16 @@
 // cspell:ignore AQIDBA
 
 use bit_vec::BitVec;
+use protobuf::well_known_types::timestamp::Timestamp;
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use time::OffsetDateTime;
 
-use crate::{ProtobufBase64, ProtobufConvert};
+use crate::{
+    json::{to_json, DecodedMessage, ProtobufJson},
+    ProtobufBase64, ProtobufConvert,
+};
 
 #[test]
 fn test_bitvec_pb_convert() {
@@ -100,3 +105,46 @@ fn roundtrip_mini_fuzz() {
         }
     }
 }
+
+#[test]
+fn canonical_json_omits_default_values() {
+    let bit_vec = BitVec::new();
+    assert_eq!(bit_vec.to_canonical_json(), json!({}));
+
+    let bit_vec = BitVec::from_bytes(&[255, 1]);
+    assert_eq!(
+        bit_vec.to_canonical_json(),
+        json!({ "data": "/wE", "len": "16" })
+    );
+}
+
+#[test]
+fn canonical_json_for_timestamp() {
+    let time = OffsetDateTime::from_unix_timestamp(1_500_000_000).unwrap();
+    assert_eq!(time.to_canonical_json(), json!("2017-07-14T02:40:00Z"));
+
+    let mut timestamp = Timestamp::new();
+    timestamp.seconds = 1;
+    timestamp.nanos = 500_000_000;
+    assert_eq!(to_json(&timestamp), json!("1970-01-01T00:00:01.5Z"));
+}
+
+#[test]
+fn decoding_message() {
+    use protobuf::{Message, MessageFull};
+
+    let bit_vec = BitVec::from_bytes(&[0b_1010_0000]).to_pb();
+    let bytes = bit_vec.write_to_bytes().unwrap();
+    let descriptor = crate::proto::bit_vec::BitVec::descriptor();
+    let decoded = DecodedMessage::decode(&descriptor, &bytes).unwrap();
+    assert_eq!(decoded.message_type, "exonum.common.BitVec");
+    assert_eq!(decoded.content, json!({ "data": "oA", "len": "8" }));
+    assert!(
+        decoded.debug_dump.contains("len: 8"),
+        "{}",
+        decoded.debug_dump
+    );
+
+    let err = DecodedMessage::decode(&descriptor, &[255]).unwrap_err();
+    assert!(!err.to_string().is_empty());
+}
@@ -0,0 +1,210 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Node-local indexes backfilled from historical blocks.
+//!
+//! [`LocalIndexesPlugin`] spawns background jobs building the [local indexes] attached to it.
+//! Jobs start from the genesis block, so the indexes cover blocks committed before
+//! the indexes were introduced, and then follow new blocks committed by the node.
+//! Progress of the jobs is persisted in the node database, thus a restarted node continues
+//! building indexes from where it has stopped.
+//!
+//! # Table of Contents
+//!
+//! - [Get backfill progress](#get-backfill-progress)
+//! - [Get transactions by author](#get-transactions-by-author)
+//!
+//! # Get Backfill Progress
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/indexes/v1/progress` |
+//! | Method      | GET   |
+//! | Query type  | - |
+//! | Return type | `Vec<`[`BackfillProgress`]`>` |
+//!
+//! Returns the progress of building each local index attached to the plugin.
+//!
+//! ```
+//! use exonum::blockchain::{BackfillProgress, TransactionsByAuthor};
+//! use exonum_system_api::indexes::LocalIndexesPlugin;
+//! use exonum_testkit::TestKitBuilder;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let mut testkit = TestKitBuilder::validator()
+//!     .with_plugin(LocalIndexesPlugin::new().with_index(TransactionsByAuthor))
+//!     .build();
+//! let api = testkit.api();
+//! let progress: Vec<BackfillProgress> = api.private("api/indexes")
+//!     .get("v1/progress")
+//!     .await?;
+//! assert_eq!(progress[0].name, TransactionsByAuthor::NAME);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Get Transactions by Author
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/indexes/v1/transactions_by_author` |
+//! | Method      | GET   |
+//! | Query type  | [`AuthorQuery`] |
+//! | Return type | `Vec<`[`AuthoredTransaction`]`>` |
+//!
+//! Returns transactions signed by the specified author in the order of their commitment.
+//! The endpoint is available only if [`TransactionsByAuthor`] is attached to the plugin.
+//! Until the index is backfilled, the response covers only the indexed blocks.
+//!
+//! [`BackfillProgress`]: https://docs.rs/exonum/latest/exonum/blockchain/struct.BackfillProgress.html
+//! [`TransactionsByAuthor`]: https://docs.rs/exonum/latest/exonum/blockchain/struct.TransactionsByAuthor.html
+//! [`LocalIndexesPlugin`]: struct.LocalIndexesPlugin.html
+//! [local indexes]: https://docs.rs/exonum/latest/exonum/blockchain/trait.LocalIndex.html
+//! [`AuthorQuery`]: struct.AuthorQuery.html
+//! [`AuthoredTransaction`]: struct.AuthoredTransaction.html
+
+use exonum::{
+    blockchain::{
+        BackfillHandle, BackfillProgress, Blockchain, LocalIndex, TransactionsByAuthor, TxLocation,
+    },
+    crypto::{Hash, PublicKey},
+    merkledb::Snapshot,
+};
+use exonum_api::{ApiBuilder, ApiScope};
+use exonum_node::{NodePlugin, PluginApiContext};
+use futures::future;
+use serde::{Deserialize, Serialize};
+
+use std::{fmt, mem, sync::Mutex};
+
+/// Query for transactions signed by a specific author.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct AuthorQuery {
+    /// Public key of the transaction author.
+    pub author: PublicKey,
+}
+
+impl AuthorQuery {
+    /// Creates a new query.
+    pub fn new(author: PublicKey) -> Self {
+        Self { author }
+    }
+}
+
+/// Transaction returned by the transactions by author endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct AuthoredTransaction {
+    /// Hash of the transaction.
+    pub tx_hash: Hash,
+    /// Location of the transaction in the blockchain.
+    pub location: TxLocation,
+}
+
+/// Plugin building node-local indexes in the background.
+///
+/// See [module docs](index.html) for the examples of usage.
+#[derive(Default)]
+pub struct LocalIndexesPlugin {
+    names: Vec<String>,
+    pending_indexes: Mutex<Vec<Box<dyn LocalIndex>>>,
+    handles: Mutex<Vec<BackfillHandle>>,
+}
+
+impl fmt::Debug for LocalIndexesPlugin {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("LocalIndexesPlugin")
+            .field("names", &self.names)
+            .finish()
+    }
+}
+
+impl LocalIndexesPlugin {
+    /// Creates a plugin without local indexes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attaches a local index to the plugin.
+    #[must_use]
+    pub fn with_index(mut self, index: impl LocalIndex) -> Self {
+        self.names.push(index.name().to_owned());
+        self.pending_indexes
+            .get_mut()
+            .unwrap()
+            .push(Box::new(index));
+        self
+    }
+
+    /// Spawns backfill jobs for the indexes not processed yet. The API may be wired several
+    /// times, but jobs are spawned only once.
+    fn spawn_jobs(&self, blockchain: &Blockchain) {
+        let pending_indexes = mem::take(&mut *self.pending_indexes.lock().unwrap());
+        let mut handles = self.handles.lock().unwrap();
+        for index in pending_indexes {
+            handles.push(BackfillHandle::spawn(blockchain, index));
+        }
+    }
+
+    fn wire_progress(&self, blockchain: Blockchain, api_scope: &mut ApiScope) {
+        let names = self.names.clone();
+        api_scope.endpoint("v1/progress", move |_query: ()| {
+            let snapshot = blockchain.snapshot();
+            let progress = names
+                .iter()
+                .map(|name| BackfillProgress::read(snapshot.as_ref(), name))
+                .collect::<Vec<_>>();
+            future::ok(progress)
+        });
+    }
+
+    fn wire_transactions_by_author(blockchain: Blockchain, api_scope: &mut ApiScope) {
+        api_scope.endpoint("v1/transactions_by_author", move |query: AuthorQuery| {
+            let snapshot = blockchain.snapshot();
+            let transactions = TransactionsByAuthor::transactions(snapshot.as_ref(), query.author)
+                .into_iter()
+                .map(|(tx_hash, location)| AuthoredTransaction { tx_hash, location })
+                .collect::<Vec<_>>();
+            future::ok(transactions)
+        });
+    }
+}
+
+impl NodePlugin for LocalIndexesPlugin {
+    fn after_commit(&self, _snapshot: &dyn Snapshot) {
+        for handle in &*self.handles.lock().unwrap() {
+            handle.notify();
+        }
+    }
+
+    fn wire_api(&self, context: PluginApiContext<'_>) -> Vec<(String, ApiBuilder)> {
+        let blockchain = context.blockchain();
+        self.spawn_jobs(blockchain);
+
+        let mut builder = ApiBuilder::new();
+        let api_scope = builder.private_scope();
+        self.wire_progress(blockchain.clone(), api_scope);
+        if self
+            .names
+            .iter()
+            .any(|name| name == TransactionsByAuthor::NAME)
+        {
+            Self::wire_transactions_by_author(blockchain.clone(), api_scope);
+        }
+        vec![("indexes".to_owned(), builder)]
+    }
+}
@@ -19,6 +19,7 @@
 //! REST API of the service is documented in the corresponding modules:
 //!
 //! - [private API](private/index.html)
+//! - [local indexes](indexes/index.html)
 //!
 //! # Examples
 //!
@@ -91,6 +92,7 @@
     clippy::missing_errors_doc
 )]
 
+pub mod indexes;
 pub mod private;
 
 use exonum::blockchain::{ApiSender, Blockchain};
@@ -23,6 +23,9 @@
 //! - [Get node statistics](#get-node-statistics)
 //! - [Add peer](#add-peer)
 //! - [Change consensus status](#change-consensus-status)
+//! - [Get consensus rounds](#get-consensus-rounds)
+//! - [Activate standby node](#activate-standby-node)
+//! - [Get storage metrics](#get-storage-metrics)
 //! - [Node shutdown](#node-shutdown)
 //!
 //! # Get Node Info
@@ -34,7 +37,8 @@
 //! | Query type  | - |
 //! | Return type | [`NodeInfo`] |
 //!
-//! Obtains information about node.
+//! Obtains information about node. The information includes connected peers
+//! together with the traffic statistics of connections with them.
 //!
 //! [`NodeInfo`]: struct.NodeInfo.html
 //!
@@ -62,7 +66,8 @@
 //! | Query type  | - |
 //! | Return type | [`NodeStats`] |
 //!
-//! Returns the statistics of the current node.
+//! Returns the statistics of the current node, including the approximate memory usage
+//! of the node subsystems.
 //!
 //! [`PeersInfo`]: struct.NodeStats.html
 //!
@@ -155,6 +160,112 @@
 //! # }
 //! ```
 //!
+//! # Get Consensus Rounds
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/system/v1/consensus_rounds` |
+//! | Method      | GET   |
+//! | Query type  | - |
+//! | Return type | [`ConsensusRounds`] |
+//!
+//! Returns consensus internals of the node for the current epoch: proposals seen
+//! in each round, validators which have sent prevotes and precommits, and timeouts fired
+//! by the node. The information is intended to visualize consensus rounds, e.g., to find out
+//! why the network is stuck in a certain epoch. It is updated periodically together with
+//! other parts of the node state; thus, it may lag behind the actual state of consensus.
+//!
+//! [`ConsensusRounds`]: https://docs.rs/exonum-node/latest/exonum_node/struct.ConsensusRounds.html
+//!
+//! ```
+//! use exonum_node::ConsensusRounds;
+//! use exonum_system_api::SystemApiPlugin;
+//! use exonum_testkit::{ApiKind, TestKitBuilder};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let mut testkit = TestKitBuilder::validator()
+//!     .with_plugin(SystemApiPlugin)
+//!     .build();
+//! let rounds: ConsensusRounds = testkit
+//!     .api()
+//!     .private(ApiKind::System)
+//!     .get("v1/consensus_rounds")
+//!     .await?;
+//! for round_info in &rounds.rounds {
+//!     println!("Round {}: {} proposal(s)", round_info.round, round_info.proposals.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Activate Standby Node
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/system/v1/standby/activate` |
+//! | Method      | POST   |
+//! | Query type  | [`StandbyActivation`] |
+//! | Return type | - |
+//!
+//! Activates a node running in the standby mode, so that it starts participating in consensus
+//! as a validator. The request must be signed with the consensus key of the validator.
+//! The request is rejected if the node does not run in the standby mode, if the request
+//! is stale or expired, or if the primary validator node was active within the fencing timeout
+//! specified in the node configuration. Once activated, the node requests the primary node
+//! to step down via the peers.
+//!
+//! [`StandbyActivation`]: https://docs.rs/exonum-node/latest/exonum_node/struct.StandbyActivation.html
+//!
+//! ```no_run
+//! use exonum::helpers::Height;
+//! use exonum_node::StandbyActivation;
+//! use exonum_system_api::SystemApiPlugin;
+//! use exonum_testkit::{ApiKind, TestKitBuilder};
+//! use std::time::{Duration, SystemTime};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let mut testkit = TestKitBuilder::validator()
+//!     .with_plugin(SystemApiPlugin)
+//!     .build();
+//! // The request must be signed with the consensus key of the validator,
+//! // and the node must run in the standby mode.
+//! let keys = testkit.us().consensus_keypair();
+//! // The request should be valid for a short period of time.
+//! let expires_at = SystemTime::now() + Duration::from_secs(60);
+//! let request = StandbyActivation::new(testkit.height(), expires_at, keys.secret_key());
+//! testkit
+//!     .api()
+//!     .private(ApiKind::System)
+//!     .query(&request)
+//!     .post::<()>("v1/standby/activate")
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Get Storage Metrics
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/system/v1/metrics/storage` |
+//! | Method      | GET   |
+//! | Query type  | - |
+//! | Return type | Prometheus text exposition format |
+//!
+//! Returns metrics of the node storage, such as the number of reads and the latency
+//! of merges, in the [Prometheus text format]. The metrics are only available if
+//! the node is built with [`NodeBuilder::with_storage_metrics`]; otherwise,
+//! the endpoint responds with the 404 status.
+//!
+//! [Prometheus text format]: https://prometheus.io/docs/instrumenting/exposition_formats/
+//! [`NodeBuilder::with_storage_metrics`]: https://docs.rs/exonum-node/latest/exonum_node/struct.NodeBuilder.html#method.with_storage_metrics
+//!
+//! ```sh
+//! curl http://127.0.0.1:8081/api/system/v1/metrics/storage
+//! ```
+//!
 //! # Node Shutdown
 //!
 //! | Property    | Value |
@@ -190,7 +301,9 @@ use exonum::{
     helpers::{exonum_version, os_info, rust_version},
 };
 use exonum_api::{self as api, ApiBackend, ApiScope};
-use exonum_node::{ConnectInfo, ExternalMessage, SharedNodeState};
+use exonum_node::{
+    ConnectInfo, ExternalMessage, PeerTraffic, SharedNodeState, StandbyActivation, SubsystemMemory,
+};
 use futures::{future, prelude::*};
 use semver::Version;
 use serde::{Deserialize, Serialize};
@@ -210,6 +323,9 @@ pub struct NodeStats {
     pub tx_cache_size: usize,
     /// Work duration of the node in seconds.
     pub uptime: u64,
+    /// Approximate memory usage of the node subsystems.
+    #[serde(default)]
+    pub memory: Vec<SubsystemMemory>,
 }
 
 /// Consensus status of the current node.
@@ -246,14 +362,24 @@ pub struct ConnectedPeerInfo {
     pub public_key: PublicKey,
     /// Connect direction.
     pub direction: ConnectDirection,
+    /// Traffic statistics of the connection with the peer.
+    #[serde(default)]
+    pub traffic: PeerTraffic,
 }
 
 impl ConnectedPeerInfo {
-    fn new(connect_info: &ConnectInfo, direction: ConnectDirection) -> Self {
+    fn new(
+        connect_info: &ConnectInfo,
+        direction: ConnectDirection,
+        state: &SharedNodeState,
+    ) -> Self {
         Self {
             address: connect_info.address.clone(),
             public_key: connect_info.public_key,
             direction,
+            traffic: state
+                .peer_traffic(&connect_info.public_key)
+                .unwrap_or_default(),
         }
     }
 }
@@ -319,6 +445,9 @@ impl SystemApi {
             .handle_stats("v1/stats", api_scope)
             .handle_peers("v1/peers", api_scope)
             .handle_consensus_status("v1/consensus_status", api_scope)
+            .handle_consensus_rounds("v1/consensus_rounds", api_scope)
+            .handle_standby_activation("v1/standby/activate", api_scope)
+            .handle_storage_metrics("v1/metrics/storage", api_scope)
             .handle_shutdown("v1/shutdown", api_scope);
         api_scope
     }
@@ -332,6 +461,7 @@ impl SystemApi {
                 connected_peers.push(ConnectedPeerInfo::new(
                     &connect_info,
                     ConnectDirection::Outgoing,
+                    &shared_api_state,
                 ));
             }
 
@@ -339,6 +469,7 @@ impl SystemApi {
                 connected_peers.push(ConnectedPeerInfo::new(
                     &connect_info,
                     ConnectDirection::Incoming,
+                    &shared_api_state,
                 ));
             }
 
@@ -370,6 +501,7 @@ impl SystemApi {
                 tx_count: schema.transactions_len(),
                 tx_cache_size: this.shared_api_state.tx_cache_size(),
                 uptime,
+                memory: this.shared_api_state.memory_usage(),
             };
 
             future::ok(stats)
@@ -405,6 +537,80 @@ impl SystemApi {
         self
     }
 
+    fn handle_consensus_rounds(self, name: &'static str, api_scope: &mut ApiScope) -> Self {
+        let shared_api_state = self.shared_api_state.clone();
+        api_scope.endpoint(name, move |_query: ()| {
+            future::ok(shared_api_state.consensus_rounds())
+        });
+        self
+    }
+
+    fn handle_standby_activation(self, name: &'static str, api_scope: &mut ApiScope) -> Self {
+        let this = self.clone();
+        api_scope.endpoint_mut(name, move |request: StandbyActivation| {
+            let mut sender = this.sender.clone();
+            let snapshot = this.blockchain.snapshot();
+            let blockchain_height = Schema::new(&snapshot).height();
+            let check_result = this
+                .shared_api_state
+                .standby_status()
+                .ok_or_else(|| api::Error::bad_request().title("Node is not in the standby mode"))
+                .and_then(|status| {
+                    status
+                        .check_activation(&request, blockchain_height, SystemTime::now())
+                        .map_err(|e| {
+                            api::Error::bad_request()
+                                .title("Cannot activate standby node")
+                                .detail(e.to_string())
+                        })
+                });
+
+            async move {
+                check_result?;
+                sender
+                    .send_message(ExternalMessage::ActivateStandby(request))
+                    .await
+                    .map_err(|e| api::Error::internal(e).title("Failed to activate standby node"))
+            }
+        });
+        self
+    }
+
+    fn handle_storage_metrics(self, name: &'static str, api_scope: &mut ApiScope) -> Self {
+        // Metrics are returned as plain text, which is not supported by the generic endpoints.
+        use actix_web::HttpResponse;
+        use exonum_api::backends::actix::{RawHandler, RequestHandler};
+
+        /// Content type of the Prometheus text exposition format.
+        const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+        let shared_api_state = self.shared_api_state.clone();
+        let index = move |_, _| {
+            let response = shared_api_state
+                .storage_metrics()
+                .map(|metrics| {
+                    HttpResponse::Ok()
+                        .content_type(CONTENT_TYPE)
+                        .body(metrics.render())
+                })
+                .ok_or_else(|| {
+                    api::Error::not_found()
+                        .title("Storage metrics are not collected by the node")
+                        .into()
+                });
+            future::ready(response).boxed_local()
+        };
+
+        let handler = RequestHandler {
+            name: name.to_owned(),
+            method: actix_web::http::Method::GET,
+            inner: Arc::new(index) as Arc<RawHandler>,
+        };
+        api_scope.web_backend().raw_handler(handler);
+
+        self
+    }
+
     fn handle_shutdown(self, name: &'static str, api_scope: &mut ApiScope) -> Self {
         // These backend-dependent uses are needed to provide realization of the support of empty
         // request which is not easy in the generic approach, so it will be harder to misuse
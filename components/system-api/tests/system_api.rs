@@ -12,11 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use exonum_node::ExternalMessage;
+use exonum::{
+    blockchain::{BackfillProgress, TransactionsByAuthor},
+    crypto::KeyPair,
+    helpers::Height,
+};
+use exonum_api::HttpStatusCode;
+use exonum_node::{ConsensusRounds, ExternalMessage, MemorySubsystem, StandbyActivation};
 use exonum_testkit::{ApiKind, TestKit, TestKitBuilder};
 use pretty_assertions::assert_eq;
+use tokio::time;
+
+use std::time::{Duration, SystemTime};
 
 use exonum_system_api::{
+    indexes::{AuthorQuery, AuthoredTransaction, LocalIndexesPlugin},
     private::{ConsensusStatus, NodeInfo, NodeStats},
     SystemApiPlugin,
 };
@@ -51,6 +61,43 @@ async fn stats() {
     let info: NodeStats = api.private(ApiKind::System).get("v1/stats").await.unwrap();
     assert_eq!(info.height, 0);
     assert_eq!(info.tx_cache_size, 0);
+    let tx_pool_memory = info
+        .memory
+        .iter()
+        .find(|stats| stats.subsystem == MemorySubsystem::TxPool)
+        .unwrap();
+    assert_eq!(tx_pool_memory.used, 0);
+    assert_eq!(tx_pool_memory.budget, None);
+}
+
+#[tokio::test]
+async fn consensus_rounds() {
+    // The testkit does not run consensus, so the endpoint returns an empty snapshot.
+    let mut testkit = create_testkit();
+    let api = testkit.api();
+    let rounds: ConsensusRounds = api
+        .private(ApiKind::System)
+        .get("v1/consensus_rounds")
+        .await
+        .unwrap();
+    assert_eq!(rounds, ConsensusRounds::default());
+}
+
+#[tokio::test]
+async fn storage_metrics_without_collection() {
+    // The testkit database is not instrumented, so storage metrics are unavailable.
+    let mut testkit = create_testkit();
+    let err = testkit
+        .api()
+        .private(ApiKind::System)
+        .get::<String>("v1/metrics/storage")
+        .await
+        .unwrap_err();
+    assert_eq!(err.http_code, HttpStatusCode::NOT_FOUND);
+    assert_eq!(
+        err.body.title,
+        "Storage metrics are not collected by the node"
+    );
 }
 
 #[tokio::test]
@@ -67,3 +114,53 @@ async fn shutdown() {
         _ => panic!("Unexpected control messages: {:?}", control_messages),
     }
 }
+
+#[tokio::test]
+async fn standby_activation_without_standby_mode() {
+    let mut testkit = create_testkit();
+    let keys = testkit.us().consensus_keypair();
+    let expires_at = SystemTime::now() + Duration::from_secs(60);
+    let request = StandbyActivation::new(testkit.height(), expires_at, keys.secret_key());
+
+    let err = testkit
+        .api()
+        .private(ApiKind::System)
+        .query(&request)
+        .post::<()>("v1/standby/activate")
+        .await
+        .unwrap_err();
+    assert_eq!(err.http_code, HttpStatusCode::BAD_REQUEST);
+    assert_eq!(err.body.title, "Node is not in the standby mode");
+    assert!(testkit.poll_control_messages().is_empty());
+}
+
+#[tokio::test]
+async fn local_indexes_backfill() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_plugin(LocalIndexesPlugin::new().with_index(TransactionsByAuthor))
+        .build();
+    testkit.create_blocks_until(Height(3));
+    let api = testkit.api();
+
+    let mut progress: Vec<BackfillProgress> = vec![];
+    for _ in 0..100 {
+        progress = api.private("api/indexes").get("v1/progress").await.unwrap();
+        if progress[0].is_complete() {
+            break;
+        }
+        time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(progress.len(), 1);
+    assert_eq!(progress[0].name, TransactionsByAuthor::NAME);
+    assert_eq!(progress[0].indexed_height, Some(Height(3)));
+    assert_eq!(progress[0].blockchain_height, Some(Height(3)));
+
+    let query = AuthorQuery::new(KeyPair::random().public_key());
+    let transactions: Vec<AuthoredTransaction> = api
+        .private("api/indexes")
+        .query(&query)
+        .get("v1/transactions_by_author")
+        .await
+        .unwrap();
+    assert!(transactions.is_empty());
+}
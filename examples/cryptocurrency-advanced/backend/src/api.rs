@@ -76,7 +76,7 @@ impl PublicApi {
             block_proof,
             index_proof,
             ..
-        } = state.data().proof_for_service_index("wallets").unwrap();
+        } = state.proof_for_service_index("wallets").unwrap();
 
         let currency_schema = SchemaImpl::new(state.service_data());
         let address = Address::from_key(query.pub_key);
@@ -0,0 +1,449 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Faucet dispensing test tokens to developers, intended for test networks.
+//!
+//! The faucet is an optional node-local extension of the cryptocurrency service.
+//! A node running [`CryptocurrencyWithFaucet`] instead of [`CryptocurrencyService`]
+//! maintains a faucet wallet associated with its service key and exposes an HTTP API
+//! transferring tokens from this wallet to the wallets of requesters. The transfers are
+//! ordinary transactions signed by the service key of the node, so other nodes in the network
+//! may run the plain cryptocurrency service.
+//!
+//! The faucet wallet is created and topped up automatically by issuing tokens, which is allowed
+//! for any wallet in this service. Requests are rate-limited with a token bucket shared by
+//! all requesters and a cooldown for individual requesters; additionally, requesters may be
+//! required to solve a captcha, which is checked by a [`CaptchaVerifier`].
+//!
+//! # HTTP API
+//!
+//! | Endpoint | Method | Query / body type | Return type |
+//! |----------|--------|-------------------|-------------|
+//! | `v1/faucet/info` | GET | - | [`FaucetInfo`] |
+//! | `v1/faucet/request` | POST | [`FaucetRequest`] | [`FaucetResponse`] |
+//!
+//! The request endpoint returns the following errors:
+//!
+//! - 403 if the captcha verification failed
+//! - 400 if the requester does not have a wallet
+//! - 429 if the request is rate-limited
+//! - 503 if the faucet wallet does not have enough tokens yet
+//!
+//! [`CryptocurrencyWithFaucet`]: struct.CryptocurrencyWithFaucet.html
+//! [`CryptocurrencyService`]: ../struct.CryptocurrencyService.html
+//! [`CaptchaVerifier`]: trait.CaptchaVerifier.html
+//! [`FaucetInfo`]: struct.FaucetInfo.html
+//! [`FaucetRequest`]: struct.FaucetRequest.html
+//! [`FaucetResponse`]: struct.FaucetResponse.html
+
+use exonum::{
+    crypto::{Hash, PublicKey},
+    runtime::{
+        CallerAddress as Address, ExecutionContext, ExecutionError, InstanceId, InterfaceMethods,
+        MethodId,
+    },
+};
+use exonum_derive::ServiceFactory;
+use exonum_rust_runtime::{
+    api::{self, HttpStatusCode, ServiceApiBuilder, ServiceApiState},
+    AfterCommitContext, DefaultInstance, Service, ServiceDispatcher,
+};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    api::PublicApi as CryptocurrencyApi,
+    schema::SchemaImpl,
+    transactions::{CreateWallet, Issue, Transfer},
+    CryptocurrencyInterface, CryptocurrencyService,
+};
+
+/// Name of the wallet from which the faucet dispenses tokens.
+pub const FAUCET_WALLET_NAME: &str = "faucet";
+
+/// Verifier of captcha responses submitted with faucet requests.
+///
+/// Implementations usually check the response with an external captcha provider.
+pub trait CaptchaVerifier: fmt::Debug + Send + Sync + 'static {
+    /// Checks the captcha `response` submitted by the requester with the specified key.
+    fn verify(&self, requester: &PublicKey, response: &str) -> bool;
+}
+
+/// Faucet configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FaucetConfig {
+    /// Amount of tokens dispensed per request.
+    pub amount: u64,
+    /// Maximum number of requests which can be served in a burst.
+    pub burst_size: u32,
+    /// Interval after which the faucet can serve one more request, up to `burst_size`.
+    pub refill_interval: Duration,
+    /// Minimum interval between requests from the same requester.
+    pub cooldown: Duration,
+    /// The faucet wallet is topped up once its balance drops below this value.
+    pub min_balance: u64,
+    /// Amount of tokens issued to the faucet wallet on each top-up.
+    pub top_up_amount: u64,
+}
+
+impl Default for FaucetConfig {
+    fn default() -> Self {
+        Self {
+            amount: 10,
+            burst_size: 10,
+            refill_interval: Duration::from_secs(6),
+            cooldown: Duration::from_secs(3_600),
+            min_balance: 1_000,
+            top_up_amount: 10_000,
+        }
+    }
+}
+
+/// Request for test tokens.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FaucetRequest {
+    /// Public key of the requester. Tokens are transferred to the wallet associated
+    /// with this key, which should exist.
+    pub pub_key: PublicKey,
+    /// Response to the captcha challenge, if the faucet requires captcha.
+    #[serde(default)]
+    pub captcha: Option<String>,
+}
+
+/// Response of the faucet to a successful request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FaucetResponse {
+    /// Hash of the transaction transferring tokens to the requester.
+    pub tx_hash: Hash,
+    /// Amount of transferred tokens.
+    pub amount: u64,
+}
+
+/// Information about the faucet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FaucetInfo {
+    /// Address of the faucet wallet.
+    pub address: Address,
+    /// Current balance of the faucet wallet, or `None` if the wallet is not created yet.
+    pub balance: Option<u64>,
+    /// Amount of tokens dispensed per request.
+    pub amount: u64,
+    /// Whether requests should contain a captcha response.
+    pub requires_captcha: bool,
+}
+
+/// Token bucket limiting the overall rate of the served requests, combined with
+/// the cooldown for individual requesters.
+#[derive(Debug)]
+struct RateLimiter {
+    available: u32,
+    last_refill: Instant,
+    last_requests: HashMap<PublicKey, Instant>,
+}
+
+impl RateLimiter {
+    fn new(config: &FaucetConfig, now: Instant) -> Self {
+        Self {
+            available: config.burst_size,
+            last_refill: now,
+            last_requests: HashMap::new(),
+        }
+    }
+
+    fn refill(&mut self, config: &FaucetConfig, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        let interval = config.refill_interval.as_nanos().max(1);
+        let new_tokens = u32::try_from(elapsed.as_nanos() / interval).unwrap_or(u32::MAX);
+        if new_tokens >= config.burst_size.saturating_sub(self.available) {
+            self.available = config.burst_size;
+            self.last_refill = now;
+        } else {
+            self.available += new_tokens;
+            self.last_refill += config.refill_interval * new_tokens;
+        }
+    }
+
+    /// Checks whether a request from `requester` can be served, and records it if so.
+    fn check(
+        &mut self,
+        config: &FaucetConfig,
+        requester: PublicKey,
+        now: Instant,
+    ) -> Result<(), &'static str> {
+        self.last_requests.retain(|_, last_request| {
+            now.saturating_duration_since(*last_request) < config.cooldown
+        });
+        if self.last_requests.contains_key(&requester) {
+            return Err("Tokens were recently dispensed to this requester");
+        }
+
+        self.refill(config, now);
+        if self.available == 0 {
+            return Err("Faucet has served too many requests recently");
+        }
+        self.available -= 1;
+        self.last_requests.insert(requester, now);
+        Ok(())
+    }
+}
+
+/// Node-local state of the faucet.
+#[derive(Debug)]
+struct Faucet {
+    config: FaucetConfig,
+    captcha: Option<Arc<dyn CaptchaVerifier>>,
+    limiter: Mutex<RateLimiter>,
+}
+
+impl Faucet {
+    fn info(&self, state: &ServiceApiState) -> FaucetInfo {
+        let address = Address::from_key(state.service_key());
+        let balance = SchemaImpl::new(state.service_data())
+            .wallet(address)
+            .map(|wallet| wallet.balance);
+        FaucetInfo {
+            address,
+            balance,
+            amount: self.config.amount,
+            requires_captcha: self.captcha.is_some(),
+        }
+    }
+
+    async fn request_tokens(
+        &self,
+        state: ServiceApiState,
+        request: FaucetRequest,
+    ) -> api::Result<FaucetResponse> {
+        if let Some(captcha) = &self.captcha {
+            let response = request.captcha.as_deref().unwrap_or_default();
+            if !captcha.verify(&request.pub_key, response) {
+                return Err(api::Error::forbidden().title("Captcha verification failed"));
+            }
+        }
+
+        let transfer = {
+            let schema = SchemaImpl::new(state.service_data());
+            let to = Address::from_key(request.pub_key);
+            let receiver = schema.wallet(to).ok_or_else(|| {
+                api::Error::bad_request()
+                    .title("Wallet not found")
+                    .detail("Create a wallet before requesting tokens")
+            })?;
+
+            let faucet_address = Address::from_key(state.service_key());
+            let faucet_balance = schema
+                .wallet(faucet_address)
+                .map_or(0, |wallet| wallet.balance);
+            if faucet_balance < self.config.amount {
+                return Err(api::Error::new(HttpStatusCode::SERVICE_UNAVAILABLE)
+                    .title("Faucet is depleted")
+                    .detail("The faucet wallet is being topped up; retry later"));
+            }
+
+            Transfer {
+                to,
+                amount: self.config.amount,
+                // The history length of the receiver makes the transfer unique, while
+                // retries before the transfer is committed lead to the same transaction.
+                seed: receiver.history_len,
+            }
+        };
+
+        self.limiter
+            .lock()
+            .unwrap()
+            .check(&self.config, request.pub_key, Instant::now())
+            .map_err(|msg| api::Error::new(HttpStatusCode::TOO_MANY_REQUESTS).title(msg))?;
+
+        let tx_hash = state
+            .generic_broadcaster()
+            .transfer((), transfer)
+            .await
+            .map_err(api::Error::internal)?;
+        Ok(FaucetResponse {
+            tx_hash,
+            amount: self.config.amount,
+        })
+    }
+
+    /// Creates the faucet wallet or tops it up if necessary.
+    fn maintain_wallet(&self, context: &AfterCommitContext<'_>) {
+        if !context.status().is_active() {
+            return;
+        }
+
+        let address = Address::from_key(context.service_key());
+        let wallet = SchemaImpl::new(context.service_data()).wallet(address);
+        let broadcaster = context.generic_broadcaster().blocking();
+        let result = match wallet {
+            None => broadcaster.create_wallet((), CreateWallet::new(FAUCET_WALLET_NAME)),
+            Some(wallet) if wallet.balance < self.config.min_balance => {
+                let issue = Issue {
+                    amount: self.config.top_up_amount,
+                    seed: wallet.history_len,
+                };
+                broadcaster.issue((), issue)
+            }
+            Some(_) => return,
+        };
+        if let Err(e) = result {
+            log::error!("Cannot send faucet wallet transaction: {}", e);
+        }
+    }
+}
+
+/// Cryptocurrency service with a faucet dispensing test tokens.
+///
+/// The service has the same artifact as [`CryptocurrencyService`]; the faucet
+/// only adds node-local logic and HTTP API.
+///
+/// [`CryptocurrencyService`]: ../struct.CryptocurrencyService.html
+#[derive(Debug, Clone, ServiceFactory)]
+#[service_factory(
+    artifact_name = "exonum-cryptocurrency",
+    proto_sources = "crate::proto",
+    service_constructor = "Self::new_instance"
+)]
+pub struct CryptocurrencyWithFaucet {
+    config: FaucetConfig,
+    captcha: Option<Arc<dyn CaptchaVerifier>>,
+}
+
+impl CryptocurrencyWithFaucet {
+    /// Creates a service with the faucet using the specified configuration.
+    pub fn new(config: FaucetConfig) -> Self {
+        Self {
+            config,
+            captcha: None,
+        }
+    }
+
+    /// Requires faucet requests to contain captcha responses checked by the specified verifier.
+    pub fn with_captcha(mut self, verifier: impl CaptchaVerifier) -> Self {
+        self.captcha = Some(Arc::new(verifier));
+        self
+    }
+
+    fn new_instance(&self) -> Box<dyn Service> {
+        let faucet = Faucet {
+            config: self.config,
+            captcha: self.captcha.clone(),
+            limiter: Mutex::new(RateLimiter::new(&self.config, Instant::now())),
+        };
+        Box::new(FaucetService {
+            faucet: Arc::new(faucet),
+        })
+    }
+}
+
+/// Use the same predefined instance name and id as for the service without faucet.
+impl DefaultInstance for CryptocurrencyWithFaucet {
+    const INSTANCE_ID: InstanceId = CryptocurrencyService::INSTANCE_ID;
+    const INSTANCE_NAME: &'static str = CryptocurrencyService::INSTANCE_NAME;
+}
+
+/// Instance of the cryptocurrency service with a faucet.
+#[derive(Debug)]
+struct FaucetService {
+    faucet: Arc<Faucet>,
+}
+
+impl ServiceDispatcher for FaucetService {
+    fn call(
+        &self,
+        context: ExecutionContext<'_>,
+        method: MethodId,
+        payload: &[u8],
+    ) -> Result<(), ExecutionError> {
+        CryptocurrencyService.call(context, method, payload)
+    }
+
+    fn interface_methods(&self) -> InterfaceMethods {
+        CryptocurrencyService.interface_methods()
+    }
+}
+
+impl Service for FaucetService {
+    fn initialize(
+        &self,
+        context: ExecutionContext<'_>,
+        params: Vec<u8>,
+    ) -> Result<(), ExecutionError> {
+        CryptocurrencyService.initialize(context, params)
+    }
+
+    fn after_commit(&self, context: AfterCommitContext<'_>) {
+        self.faucet.maintain_wallet(&context);
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        CryptocurrencyApi::wire(builder);
+
+        let faucet = Arc::clone(&self.faucet);
+        let request_faucet = Arc::clone(&self.faucet);
+        builder
+            .public_scope()
+            .endpoint("v1/faucet/info", move |state, _query: ()| {
+                let info = faucet.info(&state);
+                async move { Ok(info) }
+            })
+            .endpoint_mut("v1/faucet/request", move |state, request| {
+                let faucet = Arc::clone(&request_faucet);
+                async move { faucet.request_tokens(state, request).await }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum::crypto::KeyPair;
+
+    use super::*;
+
+    #[test]
+    fn rate_limiter() {
+        let config = FaucetConfig {
+            burst_size: 2,
+            refill_interval: Duration::from_secs(10),
+            cooldown: Duration::from_secs(60),
+            ..FaucetConfig::default()
+        };
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(&config, start);
+        let keys: Vec<_> = (0..4).map(|_| KeyPair::random().public_key()).collect();
+
+        limiter.check(&config, keys[0], start).unwrap();
+        // Repeated requests are rejected until the cooldown passes.
+        limiter.check(&config, keys[0], start).unwrap_err();
+        limiter.check(&config, keys[1], start).unwrap();
+        // The bucket is empty.
+        limiter.check(&config, keys[2], start).unwrap_err();
+
+        let now = start + Duration::from_secs(15);
+        limiter.check(&config, keys[2], now).unwrap();
+        limiter.check(&config, keys[3], now).unwrap_err();
+        let now = start + Duration::from_secs(20);
+        limiter.check(&config, keys[3], now).unwrap();
+
+        // After the cooldown, the requester can be served again.
+        let now = start + Duration::from_secs(60);
+        limiter.check(&config, keys[0], now).unwrap();
+    }
+}
@@ -20,6 +20,7 @@
 pub use crate::{schema::Schema, transactions::CryptocurrencyInterface};
 
 pub mod api;
+pub mod faucet;
 pub mod migrations;
 pub mod proto;
 pub mod schema;
@@ -0,0 +1,161 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the faucet dispensing test tokens.
+
+use exonum::{
+    crypto::{KeyPair, PublicKey},
+    helpers::Height,
+    merkledb::ObjectHash,
+    runtime::{CallerAddress, SnapshotExt},
+};
+use exonum_rust_runtime::api::HttpStatusCode;
+use exonum_testkit::{ApiKind, Spec, TestKit, TestKitApi, TestKitBuilder};
+
+use exonum_cryptocurrency_advanced::{
+    faucet::{
+        CaptchaVerifier, CryptocurrencyWithFaucet, FaucetConfig, FaucetInfo, FaucetRequest,
+        FaucetResponse,
+    },
+    transactions::CreateWallet,
+    CryptocurrencyInterface, Schema, INITIAL_BALANCE,
+};
+
+/// Service instance ID.
+const SERVICE_ID: u32 = 120;
+/// Service instance name.
+const SERVICE_NAME: &str = "tst-token";
+
+#[derive(Debug)]
+struct StaticCaptcha(&'static str);
+
+impl CaptchaVerifier for StaticCaptcha {
+    fn verify(&self, _requester: &PublicKey, response: &str) -> bool {
+        response == self.0
+    }
+}
+
+/// Creates a testkit with the faucet and waits until the faucet wallet is topped up.
+fn create_testkit(faucet: CryptocurrencyWithFaucet) -> (TestKit, TestKitApi) {
+    let mut testkit = TestKitBuilder::validator()
+        .with(Spec::new(faucet).with_instance(SERVICE_ID, SERVICE_NAME, ()))
+        .build();
+    testkit.create_blocks_until(Height(3));
+    let api = testkit.api();
+    (testkit, api)
+}
+
+/// Creates a wallet for a new requester.
+fn create_wallet(testkit: &mut TestKit, name: &str) -> PublicKey {
+    let keypair = KeyPair::random();
+    let tx = keypair.create_wallet(SERVICE_ID, CreateWallet::new(name));
+    testkit.create_block_with_transaction(tx).transactions[0]
+        .status()
+        .expect("Wallet should be created");
+    keypair.public_key()
+}
+
+fn balance(testkit: &TestKit, pub_key: PublicKey) -> Option<u64> {
+    let snapshot = testkit.snapshot();
+    let schema: Schema<_> = snapshot.service_schema(SERVICE_NAME).unwrap();
+    schema
+        .wallets
+        .get(&CallerAddress::from_key(pub_key))
+        .map(|wallet| wallet.balance)
+}
+
+async fn request_tokens(
+    api: &TestKitApi,
+    pub_key: PublicKey,
+    captcha: Option<&str>,
+) -> Result<FaucetResponse, HttpStatusCode> {
+    let request = FaucetRequest {
+        pub_key,
+        captcha: captcha.map(str::to_owned),
+    };
+    api.public(ApiKind::Service(SERVICE_NAME))
+        .query(&request)
+        .post("v1/faucet/request")
+        .await
+        .map_err(|err| err.http_code)
+}
+
+#[tokio::test]
+async fn faucet_dispenses_tokens() {
+    let config = FaucetConfig::default();
+    let (mut testkit, api) = create_testkit(CryptocurrencyWithFaucet::new(config));
+
+    let info: FaucetInfo = api
+        .public(ApiKind::Service(SERVICE_NAME))
+        .get("v1/faucet/info")
+        .await
+        .unwrap();
+    let faucet_key = testkit.us().service_keypair().public_key();
+    assert_eq!(info.address, CallerAddress::from_key(faucet_key));
+    assert_eq!(info.balance, Some(INITIAL_BALANCE + config.top_up_amount));
+    assert_eq!(info.amount, config.amount);
+    assert!(!info.requires_captcha);
+
+    let alice = create_wallet(&mut testkit, "Alice");
+    let response = request_tokens(&api, alice, None).await.unwrap();
+    assert_eq!(response.amount, config.amount);
+    let block = testkit.create_block();
+    assert_eq!(
+        block.transactions[0].content().object_hash(),
+        response.tx_hash
+    );
+    block.transactions[0].status().unwrap();
+    assert_eq!(
+        balance(&testkit, alice),
+        Some(INITIAL_BALANCE + config.amount)
+    );
+
+    // Repeated requests are rejected until the cooldown passes.
+    let err = request_tokens(&api, alice, None).await.unwrap_err();
+    assert_eq!(err, HttpStatusCode::TOO_MANY_REQUESTS);
+
+    // Tokens are dispensed only to existing wallets.
+    let bob = KeyPair::random().public_key();
+    let err = request_tokens(&api, bob, None).await.unwrap_err();
+    assert_eq!(err, HttpStatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn faucet_burst_limit() {
+    let config = FaucetConfig {
+        burst_size: 1,
+        ..FaucetConfig::default()
+    };
+    let (mut testkit, api) = create_testkit(CryptocurrencyWithFaucet::new(config));
+    let alice = create_wallet(&mut testkit, "Alice");
+    let bob = create_wallet(&mut testkit, "Bob");
+
+    request_tokens(&api, alice, None).await.unwrap();
+    let err = request_tokens(&api, bob, None).await.unwrap_err();
+    assert_eq!(err, HttpStatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn faucet_with_captcha() {
+    let faucet =
+        CryptocurrencyWithFaucet::new(FaucetConfig::default()).with_captcha(StaticCaptcha("42"));
+    let (mut testkit, api) = create_testkit(faucet);
+    let alice = create_wallet(&mut testkit, "Alice");
+
+    let err = request_tokens(&api, alice, None).await.unwrap_err();
+    assert_eq!(err, HttpStatusCode::FORBIDDEN);
+    let err = request_tokens(&api, alice, Some("24")).await.unwrap_err();
+    assert_eq!(err, HttpStatusCode::FORBIDDEN);
+    request_tokens(&api, alice, Some("42")).await.unwrap();
+}
@@ -263,6 +263,10 @@ fn node_config() -> (NodeConfig, Keys) {
         api: api_cfg,
         mempool: Default::default(),
         thread_pool_size: Default::default(),
+        standby: None,
+        bootstrap: None,
+        pruning: None,
+        replication: None,
     };
     (node_config, keys)
 }
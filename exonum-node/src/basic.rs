@@ -13,15 +13,16 @@
 // limitations under the License.
 
 use exonum::{crypto::PublicKey, helpers::Height, merkledb::ObjectHash, messages::Verified};
-use log::{error, info, trace};
+use log::{error, info, trace, warn};
 use rand::Rng;
 
 use crate::{
     events::ConnectedPeerAddr,
-    messages::{Connect, Message, PeersRequest, Responses, Service, Status},
+    messages::{Connect, Message, PeersRequest, Responses, Service, Status, StepDown},
     schema::NodeSchema,
+    standby::unix_time,
     state::{PeerState, RequestData},
-    NodeHandler, NodeRole,
+    NodeHandler, NodeRole, StandbyActivation,
 };
 
 impl NodeHandler {
@@ -33,6 +34,8 @@ impl NodeHandler {
 
             Message::Service(Service::Connect(msg)) => self.handle_connect(msg),
             Message::Service(Service::Status(msg)) => self.handle_status(&msg),
+            Message::Service(Service::ExecutionDigest(msg)) => self.handle_execution_digest(&msg),
+            Message::Service(Service::StepDown(msg)) => self.handle_step_down(&msg),
             Message::Service(Service::AnyTx(msg)) => {
                 if let Err(e) = self.handle_tx(msg.clone()) {
                     log::warn!(
@@ -198,6 +201,11 @@ impl NodeHandler {
         }
 
         let peer = msg.author();
+        if peer == self.state.keys().consensus_pk() {
+            // The message is sent by the primary node if this node is a standby.
+            let now = self.system_state.current_time();
+            self.state.observe_primary_activity(now);
+        }
         let peer_state = PeerState::new(msg.payload());
         self.state.update_peer_state(peer, peer_state);
 
@@ -234,6 +242,55 @@ impl NodeHandler {
         }
     }
 
+    /// Broadcasts the `StepDown` message after the node has been activated from the standby
+    /// mode with the specified request.
+    pub(crate) fn broadcast_step_down(&mut self, request: &StandbyActivation) {
+        let step_down = self.sign_message(StepDown::new(request.height, request.expires_at));
+        self.state.set_issued_step_down(step_down.object_hash());
+
+        trace!("Broadcast step down: {:?}", step_down.payload());
+        self.broadcast(step_down);
+    }
+
+    /// Handles the `StepDown` message. The message is relayed to the node with the consensus key
+    /// of the author; if this node has the same consensus key, it switches to the standby mode.
+    pub(crate) fn handle_step_down(&mut self, msg: &Verified<StepDown>) {
+        let author = msg.author();
+        if !self.state.peer_is_validator(&author) {
+            trace!(
+                "Ignoring step down from {}, which is not a validator",
+                author
+            );
+            return;
+        }
+        let now = self.system_state.current_time();
+        if unix_time(now) > msg.payload().expires_at {
+            trace!("Ignoring expired step down: {:?}", msg.payload());
+            return;
+        }
+
+        if author != self.state.keys().consensus_pk() {
+            // The primary node may be not connected to the activated standby node,
+            // so the message is relayed by the peers.
+            if self.state.peers().contains_key(&author) {
+                self.send_to_peer(author, msg.clone());
+            }
+            return;
+        }
+
+        if self.state.is_issued_step_down(&msg.object_hash()) || !self.state.step_down(now) {
+            return;
+        }
+        self.node_role = NodeRole::new(self.state.validator_id());
+        self.api_state.set_node_role(self.node_role);
+        self.api_state
+            .set_standby_status(self.state.standby_status());
+        warn!(
+            "Another node with the same consensus key has been activated; \
+             switched to the standby mode"
+        );
+    }
+
     /// Handles `NodeTimeout::Status`, broadcasts the `Status` message if it isn't outdated as
     /// result.
     pub(crate) fn handle_status_timeout(&mut self, epoch: Height) {
@@ -17,7 +17,10 @@
 use exonum::{blockchain::ValidatorKeys, crypto::PublicKey};
 use serde::{Deserialize, Serialize};
 
-use std::{collections::BTreeMap, fmt};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
 
 use crate::state::SharedConnectList;
 
@@ -39,11 +42,37 @@ impl fmt::Display for ConnectInfo {
     }
 }
 
+/// Configuration of the bootstrap mode of the node.
+///
+/// In the bootstrap mode, the node only accepts connections and consensus messages from peers
+/// with keys in the allowlist (provided that these peers are in the connect list as well).
+/// The mode is active until the first block after the genesis block is committed; after that,
+/// the node automatically switches to the normal mode. If the node is started on a blockchain
+/// which already has such a block, the configuration is ignored.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BootstrapConfig {
+    /// Consensus keys of the peers allowed to communicate with the node during bootstrapping.
+    pub allowlist: Vec<PublicKey>,
+}
+
+impl BootstrapConfig {
+    /// Creates a bootstrap configuration with the specified allowlist.
+    pub fn new(allowlist: impl IntoIterator<Item = PublicKey>) -> Self {
+        Self {
+            allowlist: allowlist.into_iter().collect(),
+        }
+    }
+}
+
 /// Stores mapping between IP addresses / domain names and public keys.
 #[derive(Debug, Clone, Default)]
 pub struct ConnectList {
     /// Peers to which we can connect.
     pub peers: BTreeMap<PublicKey, String>,
+    /// Keys of the peers allowed to communicate with the node in the bootstrap mode,
+    /// or `None` if the node is not in this mode.
+    bootstrap_allowlist: Option<BTreeSet<PublicKey>>,
 }
 
 impl ConnectList {
@@ -55,7 +84,10 @@ impl ConnectList {
             .map(|peer| (peer.public_key, peer.address))
             .collect();
 
-        Self { peers }
+        Self {
+            peers,
+            bootstrap_allowlist: None,
+        }
     }
 
     /// Creates `ConnectList` from the previously saved list of peers.
@@ -66,12 +98,32 @@ impl ConnectList {
                 .into_iter()
                 .map(|(public_key, connect)| (public_key, connect.payload().host.clone()))
                 .collect(),
+            bootstrap_allowlist: None,
         }
     }
 
     /// Returns `true` if a peer with the given public key can connect.
     pub(super) fn is_peer_allowed(&self, peer: &PublicKey) -> bool {
-        self.peers.contains_key(peer)
+        self.peers.contains_key(peer) && self.is_allowed_in_bootstrap(peer)
+    }
+
+    /// Returns `true` if the peer with the given public key is not restricted
+    /// by the bootstrap mode.
+    pub(super) fn is_allowed_in_bootstrap(&self, peer: &PublicKey) -> bool {
+        self.bootstrap_allowlist
+            .as_ref()
+            .map_or(true, |allowlist| allowlist.contains(peer))
+    }
+
+    /// Switches the list to the bootstrap mode with the specified allowlist.
+    pub(crate) fn start_bootstrap(&mut self, allowlist: impl IntoIterator<Item = PublicKey>) {
+        self.bootstrap_allowlist = Some(allowlist.into_iter().collect());
+    }
+
+    /// Switches the list to the normal mode. Returns `true` if the list was
+    /// in the bootstrap mode.
+    pub(crate) fn finish_bootstrap(&mut self) -> bool {
+        self.bootstrap_allowlist.take().is_some()
     }
 
     /// Gets address of a peer with the specified public key.
@@ -245,4 +297,23 @@ mod test {
             .values()
             .any(|peer_addr| *peer_addr == address));
     }
+
+    #[test]
+    fn test_bootstrap_allowlist() {
+        let validators = make_keys(VALIDATORS[0], 2);
+        let regular = make_keys(REGULAR_PEERS, 2);
+        let mut connect_list = ConnectList::default();
+        add_to_connect_list(&mut connect_list, &validators);
+        add_to_connect_list(&mut connect_list, &regular);
+
+        connect_list.start_bootstrap(vec![validators[0], regular[1]]);
+        check_in_connect_list(&connect_list, &validators, &[0], &[1]);
+        check_in_connect_list(&connect_list, &regular, &[1], &[0]);
+        assert!(!connect_list.is_allowed_in_bootstrap(&validators[1]));
+
+        assert!(connect_list.finish_bootstrap());
+        check_in_connect_list(&connect_list, &validators, &[0, 1], &[]);
+        check_in_connect_list(&connect_list, &regular, &[0, 1], &[]);
+        assert!(!connect_list.finish_bootstrap());
+    }
 }
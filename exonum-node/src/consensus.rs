@@ -15,8 +15,8 @@
 use anyhow::{bail, format_err};
 use exonum::{
     blockchain::{
-        BlockContents, BlockKind, BlockParams, BlockPatch, Blockchain, BlockchainMut,
-        PersistentPool, ProposerId, Schema,
+        BlockContents, BlockKind, BlockParams, BlockPatch, BlockTime, Blockchain, BlockchainMut,
+        PersistentPool, ProposerId, RejectedTransaction, Schema, TransactionCache,
     },
     crypto::{Hash, PublicKey},
     helpers::{Height, Round, ValidatorId},
@@ -25,19 +25,22 @@ use exonum::{
     runtime::ExecutionError,
 };
 use log::{error, info, trace, warn};
+use time::OffsetDateTime;
 
-use std::{collections::HashSet, convert::TryFrom, fmt};
+use std::{collections::HashSet, convert::TryFrom, fmt, time::SystemTime};
 
 use crate::{
     events::InternalRequest,
+    memory::MemorySubsystem,
     messages::{
-        BlockRequest, BlockResponse, Consensus as ConsensusMessage, PoolTransactionsRequest,
-        Prevote, PrevotesRequest, Propose, ProposeRequest, TransactionsRequest,
-        TransactionsResponse,
+        BlockRequest, BlockResponse, CallHash, Consensus as ConsensusMessage, ExecutionDigest,
+        PoolTransactionsRequest, Prevote, PrevotesRequest, Propose, ProposeRequest,
+        TransactionsRequest, TransactionsResponse,
     },
-    pool::{ProposeParams, ProposeTemplate},
+    pool::{ProposeParams, ProposeTemplate, ServiceTxQuotas},
+    rounds::ConsensusTimeout,
     schema::NodeSchema,
-    state::{IncompleteBlock, ProposeState, RequestData},
+    state::{BlockState, IncompleteBlock, ProposeState, RequestData},
     NodeHandler,
 };
 
@@ -109,6 +112,24 @@ impl NodeHandler {
             return;
         }
 
+        if !self
+            .state
+            .connect_list()
+            .is_allowed_in_bootstrap(&msg.author())
+        {
+            trace!(
+                "Ignoring a consensus message from {} which is not in the bootstrap allowlist",
+                msg.author()
+            );
+            return;
+        }
+
+        if msg.author() == self.state.keys().consensus_pk() {
+            // The message is sent by the primary node if this node is a standby.
+            let now = self.system_state.current_time();
+            self.state.observe_primary_activity(now);
+        }
+
         // Warning for messages from previous and future height
         if msg.epoch() < self.state.epoch().previous() || msg.epoch() > self.state.epoch().next() {
             trace!(
@@ -163,7 +184,10 @@ impl NodeHandler {
             self.state.consensus_public_key_of(msg.payload().validator)
         );
 
-        if msg.payload().skip && !msg.payload().transactions.is_empty() {
+        if msg.payload().skip
+            && (!msg.payload().transactions.is_empty()
+                || !msg.payload().rejected_transactions.is_empty())
+        {
             error!(
                 "Received no-op propose with non-empty transaction list: {:?}",
                 msg.payload()
@@ -171,6 +195,17 @@ impl NodeHandler {
             return;
         }
 
+        let rejected_txs_limit = self.state.config().txs_block_limit as usize;
+        if msg.payload().rejected_transactions.len() > rejected_txs_limit
+            || !msg.payload().verify_rejected_transactions()
+        {
+            error!(
+                "Received propose with incorrect list of rejected transactions: {:?}",
+                msg.payload()
+            );
+            return;
+        }
+
         // Check prev_hash
         if msg.payload().prev_hash != self.state.last_hash() {
             error!(
@@ -180,6 +215,15 @@ impl NodeHandler {
             return;
         }
 
+        if let Err(err) = self.validate_last_precommits(msg.payload()) {
+            error!(
+                "Received propose with incorrect precommits for the previous block: {}, msg={:?}",
+                err,
+                msg.payload()
+            );
+            return;
+        }
+
         // Check leader
         if msg.payload().validator != self.state.leader(msg.payload().round) {
             error!(
@@ -220,6 +264,23 @@ impl NodeHandler {
         }
     }
 
+    /// Validates precommits for the previous block included into a `Propose`.
+    fn validate_last_precommits(&self, propose: &Propose) -> anyhow::Result<()> {
+        if propose.last_precommits.is_empty() {
+            return Ok(());
+        }
+        if !self.state.config().block_time {
+            bail!("Block time is not enabled in the consensus configuration");
+        }
+        if propose.skip {
+            bail!("Block skip cannot define block time");
+        }
+
+        let precommits = into_verified::<Precommit>(&propose.last_precommits)?;
+        let epoch = precommits[0].payload().epoch;
+        self.validate_precommits(&precommits, epoch, propose.prev_hash)
+    }
+
     /// Validates a `BlockResponse`. Returns list of precommits authenticating the block, or
     /// an error if the block is invalid.
     fn validate_block_response(
@@ -287,6 +348,12 @@ impl NodeHandler {
                 msg.payload()
             );
         }
+        if !msg.payload().verify_rejected_transactions_hash() {
+            bail!(
+                "Received block has invalid hash of rejected transactions, msg={:?}",
+                msg.payload()
+            );
+        }
 
         let precommits = into_verified(&msg.payload().precommits)?;
         self.validate_precommits(&precommits, epoch, block_hash)?;
@@ -311,17 +378,26 @@ impl NodeHandler {
         };
         // At this point, the block is valid.
 
+        let our_key = self.state.keys().consensus_pk();
+        for precommit in precommits.iter().filter(|msg| msg.author() == our_key) {
+            // The block is signed by the primary node if this node is a standby.
+            let time = SystemTime::from(precommit.payload().time);
+            self.state.observe_primary_activity(time);
+        }
+
         let sender = msg.author();
         let BlockResponse {
             block,
             transactions,
+            rejected_transactions,
             ..
         } = msg.into_payload();
         let block_hash = block.object_hash();
 
         if self.state.block(&block_hash).is_none() {
             let block_height = block.height;
-            let incomplete_block = IncompleteBlock::new(block, transactions, precommits);
+            let incomplete_block =
+                IncompleteBlock::new(block, transactions, rejected_transactions, precommits);
             let snapshot = self.blockchain.snapshot();
             let txs_pool = Schema::new(snapshot.as_ref()).transactions_pool();
 
@@ -400,12 +476,55 @@ impl NodeHandler {
         }
     }
 
+    /// Marks the propose as invalid if it exceeds per-service transaction quotas
+    /// specified in the consensus configuration. All transactions of the propose
+    /// are assumed to be known to the node.
+    fn check_service_tx_quotas(&mut self, propose_hash: &Hash) {
+        let exceeds_quotas = {
+            let config = self.state.config();
+            let propose_state = match self.state.propose(propose_hash) {
+                Some(state) if !state.has_invalid_txs() => state,
+                _ => return,
+            };
+            if config.service_tx_quotas.is_empty() {
+                return;
+            }
+
+            let snapshot = self.blockchain.snapshot();
+            let pool = PersistentPool::new(snapshot.as_ref(), self.state.tx_cache());
+            let mut quotas = ServiceTxQuotas::new(config);
+            propose_state
+                .message()
+                .payload()
+                .transactions
+                .iter()
+                .any(|tx_hash| {
+                    let tx = pool.get_transaction(*tx_hash).unwrap_or_else(|| {
+                        panic!("BUG: Cannot find transaction {:?} of full propose", tx_hash)
+                    });
+                    !quotas.try_add(tx.payload())
+                })
+        };
+
+        if exceeds_quotas {
+            warn!(
+                "Received propose {:?} exceeding per-service transaction quotas",
+                propose_hash
+            );
+            if let Some(propose_state) = self.state.propose_mut(propose_hash) {
+                propose_state.mark_invalid();
+            }
+        }
+    }
+
     /// Executes and commits block. This function is called when node has full propose information.
     ///
     /// # Panics
     ///
     /// This function panics if the hash from precommit doesn't match the calculated one.
     fn handle_full_propose(&mut self, hash: Hash, propose_round: Round) -> RoundAction {
+        self.check_service_tx_quotas(&hash);
+
         // Send prevote
         if self.state.locked_round() == Round::zero() {
             if self.state.is_validator() && !self.state.have_prevote(propose_round) {
@@ -464,6 +583,7 @@ impl NodeHandler {
             header,
             precommits,
             transactions,
+            rejected_transactions,
             ..
         } = self.state.take_completed_block();
 
@@ -482,7 +602,13 @@ impl NodeHandler {
             } else {
                 BlockContents::Transactions(&transactions)
             };
-            let patch = self.create_block(proposer_id, epoch, block_contents);
+            let patch = self.create_block(
+                proposer_id,
+                epoch,
+                block_contents,
+                &rejected_transactions,
+                header.time(),
+            );
             let computed_block_hash = patch.block_hash();
 
             // Verify `block_hash`.
@@ -720,28 +846,45 @@ impl NodeHandler {
 
         trace!("COMMIT {:?} {:?}", block_kind, block_hash);
 
+        // Trace IDs are collected before the block state is borrowed mutably by the commit.
+        let traced_txs: Vec<_> = {
+            let snapshot = self.blockchain.snapshot();
+            let schema = Schema::new(&snapshot);
+            committed_txs
+                .iter()
+                .filter_map(|tx_hash| Some((*tx_hash, schema.trace_id(tx_hash)?)))
+                .collect()
+        };
+
         // Remove committed transactions from the cache.
         for tx_hash in committed_txs {
-            self.state.tx_cache_mut().remove(tx_hash);
+            self.state.remove_tx_from_cache(tx_hash);
         }
+        self.state.remove_rejected_txs(committed_txs);
         let committed_txs_len = committed_txs.len();
 
         // Consensus messages cache is useful only during one height, so it should be
-        // cleared when a new height is achieved.
-        self.blockchain.persist_changes(
-            |schema| schema.consensus_messages_cache().clear(),
-            "Cannot clear consensus messages",
-        );
-
+        // cleared when a new height is achieved. The cache is cleared within the same batch
+        // as the block is committed, so that the database is synced to the disk only once.
+        let fork = self.blockchain.fork();
+        NodeSchema::new(&fork).consensus_messages_cache().clear();
         self.blockchain
-            .commit(block_state.patch(), precommits)
+            .commit_with_patches(vec![fork.into_patch()], block_state.patch(), precommits)
             .expect("Cannot commit block");
 
         match block_kind {
             BlockKind::Normal => {
                 // Update node state.
-                self.state
-                    .update_config(Schema::new(&self.blockchain.snapshot()).consensus_config());
+                let snapshot = self.blockchain.snapshot();
+                let schema = Schema::new(&snapshot);
+                self.state.update_config(schema.consensus_config());
+                // Forget rejected transactions recorded in the block.
+                let recorded_rejections = schema.rejected_transactions(schema.height());
+                let recorded_hashes: Vec<_> = recorded_rejections
+                    .iter()
+                    .map(|rejected| rejected.tx_hash)
+                    .collect();
+                self.state.remove_rejected_txs(&recorded_hashes);
                 // Update state to new height.
                 self.state.new_height(
                     block_hash,
@@ -775,7 +918,7 @@ impl NodeHandler {
 
             let fork = self.blockchain.fork();
             for tx_hash in &tx_hashes_to_remove {
-                self.state.tx_cache_mut().remove(tx_hash);
+                self.state.remove_tx_from_cache(tx_hash);
                 Schema::new(&fork).reject_transaction(*tx_hash);
             }
             self.blockchain
@@ -798,6 +941,15 @@ impl NodeHandler {
             pool_len,
             block_hash.to_hex(),
         );
+        for (tx_hash, trace_id) in traced_txs {
+            info!(
+                "Committed transaction {} at height {} (trace_id={})",
+                tx_hash.to_hex(),
+                blockchain_height,
+                trace_id
+            );
+        }
+        self.check_api_buffers();
 
         self.broadcast_status();
         self.add_status_timeout();
@@ -816,6 +968,19 @@ impl NodeHandler {
         }
     }
 
+    /// Updates memory usage of the API buffers and clears them if they exceed the memory budget.
+    fn check_api_buffers(&self) {
+        let memory = self.api_state.memory();
+        let proof_cache = self.blockchain.as_ref().proof_cache();
+        memory.set_used(MemorySubsystem::ApiBuffers, proof_cache.size() as u64);
+        if memory.is_over_budget(MemorySubsystem::ApiBuffers) {
+            warn!("API buffers exceed the memory budget; clearing the proof cache");
+            proof_cache.clear();
+            memory.record_shed(MemorySubsystem::ApiBuffers);
+            memory.set_used(MemorySubsystem::ApiBuffers, proof_cache.size() as u64);
+        }
+    }
+
     /// Checks if the transaction is new and adds it to the pool. This may trigger an expedited
     /// `Propose` timeout on this node if transaction count in the pool goes over the threshold.
     ///
@@ -850,6 +1015,8 @@ impl NodeHandler {
             // Please note that it **must** happen before calling `check_incomplete_proposes`,
             // since the latter uses `invalid_txs` to recalculate the validity of proposals.
             self.state.invalid_txs_mut().insert(hash);
+            self.state
+                .add_rejected_tx(RejectedTransaction::new(hash, e.kind()));
 
             // Since the transaction, despite being incorrect, is received from within the
             // network, we have to deal with it. We don't consider the transaction unknown
@@ -859,6 +1026,7 @@ impl NodeHandler {
             outcome = Err(HandleTxError::Invalid(e));
         } else {
             // Transaction is OK, store it to the cache or persistent pool.
+            self.state.remove_rejected_txs(&[hash]);
             if self.state.persist_txs_immediately() {
                 let fork = self.blockchain.fork();
                 Schema::new(&fork).add_transaction_into_pool(msg);
@@ -866,7 +1034,12 @@ impl NodeHandler {
                     .merge(fork.into_patch())
                     .expect("Cannot add transaction to persistent pool");
             } else {
-                self.state.tx_cache_mut().insert(hash, msg);
+                self.state.add_tx_to_cache(hash, msg);
+                if self.state.is_tx_cache_over_budget() {
+                    // Shed memory by moving the cached transactions to the persistent pool.
+                    self.api_state.memory().record_shed(MemorySubsystem::TxPool);
+                    self.flush_txs_into_pool();
+                }
             }
             outcome = Ok(());
         }
@@ -936,11 +1109,23 @@ impl NodeHandler {
     pub(crate) fn handle_incoming_tx(&mut self, msg: Verified<AnyTx>) {
         trace!("Handle incoming transaction");
 
+        let tx_hash = msg.object_hash();
+        let trace_id = Schema::new(&self.blockchain.snapshot()).trace_id(&tx_hash);
         match self.handle_tx(msg.clone()) {
-            Ok(()) => self.broadcast(msg),
+            Ok(()) => {
+                if let Some(trace_id) = trace_id {
+                    info!(
+                        "Added transaction {} to the pool (trace_id={})",
+                        tx_hash.to_hex(),
+                        trace_id
+                    );
+                }
+                self.broadcast(msg);
+            }
             Err(e) => log::warn!(
-                "Failed to process transaction {:?} received via `ApiSender`: {}",
+                "Failed to process transaction {:?} received via `ApiSender` (trace_id={}): {}",
                 msg.payload(),
+                trace_id.as_deref().unwrap_or("none"),
                 e
             ),
         }
@@ -991,6 +1176,7 @@ impl NodeHandler {
             return;
         }
         warn!("ROUND TIMEOUT epoch={}, round={}", epoch, round);
+        self.state.add_fired_timeout(round, ConsensusTimeout::Round);
 
         // Update the node state to the new round.
         self.state.new_round();
@@ -1007,6 +1193,8 @@ impl NodeHandler {
         if epoch != self.state.epoch() || round != self.state.round() {
             return;
         }
+        self.state
+            .add_fired_timeout(round, ConsensusTimeout::Propose);
         if self.state.locked_propose().is_some() || self.state.have_prevote(round) {
             return;
         }
@@ -1020,13 +1208,26 @@ impl NodeHandler {
 
         let propose_template = self.get_propose_template();
         let propose = match propose_template {
-            ProposeTemplate::Ordinary { tx_hashes } => Propose::new(
-                validator_id,
-                self.state.epoch(),
-                round,
-                self.state.last_hash(),
-                tx_hashes,
-            ),
+            ProposeTemplate::Ordinary { tx_hashes } => {
+                // A transaction rejected earlier may have been accepted on resubmission.
+                let included_txs: HashSet<_> = tx_hashes.iter().copied().collect();
+                let limit = self.state.config().txs_block_limit as usize;
+                let rejected_txs: Vec<_> = self
+                    .state
+                    .rejected_txs(limit)
+                    .into_iter()
+                    .filter(|rejected| !included_txs.contains(&rejected.tx_hash))
+                    .collect();
+                Propose::new(
+                    validator_id,
+                    self.state.epoch(),
+                    round,
+                    self.state.last_hash(),
+                    tx_hashes,
+                )
+                .with_rejected_transactions(rejected_txs)
+                .with_last_precommits(self.last_precommits())
+            }
 
             ProposeTemplate::Skip => Propose::skip(
                 validator_id,
@@ -1051,6 +1252,36 @@ impl NodeHandler {
         }
     }
 
+    /// Returns serialized precommits for the last committed block to include into a `Propose`
+    /// if the block time is enabled.
+    fn last_precommits(&self) -> Vec<Vec<u8>> {
+        if !self.state.config().block_time {
+            return vec![];
+        }
+
+        let last_hash = self.state.last_hash();
+        let snapshot = self.blockchain.snapshot();
+        let precommits: Vec<_> = Schema::new(&snapshot)
+            .precommits(&last_hash)
+            .iter()
+            .collect();
+        let epoch = match precommits.first() {
+            Some(precommit) => precommit.payload().epoch,
+            // The genesis block has no precommits.
+            None => return vec![],
+        };
+        // Precommits cannot be verified by other validators if the validator set has changed
+        // in the last block. In this case, the proposed block does not record the time.
+        if let Err(err) = self.validate_precommits(&precommits, epoch, last_hash) {
+            warn!(
+                "Cannot use precommits for the last block as block time source: {}",
+                err
+            );
+            return vec![];
+        }
+        precommits.iter().map(BinaryValue::to_bytes).collect()
+    }
+
     fn get_propose_template(&mut self) -> ProposeTemplate {
         let txs_cache_len = self.state.tx_cache_len() as u64;
         info!("LEADER: cache = {}", txs_cache_len);
@@ -1142,8 +1373,17 @@ impl NodeHandler {
         proposer_id: ValidatorId,
         epoch: Height,
         contents: BlockContents<'_>,
+        rejected_transactions: &[RejectedTransaction],
+        time: Option<OffsetDateTime>,
     ) -> BlockPatch {
-        let block_params = BlockParams::with_contents(contents, proposer_id, epoch);
+        let mut block_params = BlockParams::with_contents(contents, proposer_id, epoch)
+            .with_rejected_transactions(rejected_transactions);
+        if let Some(time) = time {
+            block_params = block_params.with_time(time);
+        }
+        if self.state.execution_digests() {
+            block_params = block_params.with_call_hashes();
+        }
         self.blockchain
             .create_patch(block_params, self.state.tx_cache())
     }
@@ -1164,7 +1404,17 @@ impl NodeHandler {
             BlockKind::Skip => BlockContents::Skip,
             _ => unreachable!("No other block kinds are supported"),
         };
-        let patch = self.create_block(propose.validator, propose.epoch, block_contents);
+        // Precommits are checked when the `Propose` is received.
+        let time = into_verified::<Precommit>(&propose.last_precommits)
+            .ok()
+            .and_then(|precommits| BlockTime::from_precommits(&precommits));
+        let patch = self.create_block(
+            propose.validator,
+            propose.epoch,
+            block_contents,
+            &propose.rejected_transactions,
+            time,
+        );
         let block_hash = patch.block_hash();
         self.state.add_block(
             patch,
@@ -1277,6 +1527,106 @@ impl NodeHandler {
 
         trace!("Broadcast precommit: {:?}", precommit.payload());
         self.broadcast(precommit);
+
+        if self.state.execution_digests() {
+            self.broadcast_execution_digest(propose_hash, block_hash);
+        }
+    }
+
+    /// Broadcasts the `ExecutionDigest` message for the executed block to all peers.
+    fn broadcast_execution_digest(&mut self, propose_hash: Hash, block_hash: Hash) {
+        let call_hashes = self
+            .state
+            .block(&block_hash)
+            .map_or(&[][..], BlockState::call_hashes)
+            .iter()
+            .map(|&(call, hash)| CallHash::new(call, hash));
+        let digest =
+            ExecutionDigest::new(self.state.epoch(), propose_hash, block_hash, call_hashes);
+        let digest = self.sign_message(digest);
+
+        trace!("Broadcast execution digest: {:?}", digest.payload());
+        self.broadcast(digest);
+    }
+
+    /// Handles the `ExecutionDigest` message, comparing the execution of the block
+    /// by the message author with the local execution.
+    pub(crate) fn handle_execution_digest(&self, msg: &Verified<ExecutionDigest>) {
+        if !self.state.execution_digests() {
+            return;
+        }
+
+        let digest = msg.payload();
+        let validator = match self
+            .state
+            .validators()
+            .iter()
+            .position(|keys| keys.consensus_key == msg.author())
+        {
+            Some(id) => ValidatorId(id as u16),
+            None => {
+                trace!(
+                    "Ignoring execution digest from {}, which is not a validator",
+                    msg.author()
+                );
+                return;
+            }
+        };
+        if digest.epoch != self.state.epoch() {
+            return;
+        }
+
+        let block_hash = self
+            .state
+            .propose(&digest.propose_hash)
+            .and_then(ProposeState::block_hash);
+        let block_hash = match block_hash {
+            Some(hash) => hash,
+            None => {
+                trace!(
+                    "Ignoring execution digest for propose {:?}, which is not executed yet",
+                    digest.propose_hash
+                );
+                return;
+            }
+        };
+        if block_hash == digest.block_hash {
+            return;
+        }
+
+        let call_hashes = self
+            .state
+            .block(&block_hash)
+            .map_or(&[][..], BlockState::call_hashes);
+        let divergence = call_hashes
+            .iter()
+            .zip(&digest.call_hashes)
+            .find(|((call, hash), remote)| *call != remote.call || *hash != remote.hash);
+        match divergence {
+            Some(((call, _), _)) => error!(
+                "Execution of block at epoch {} diverged from validator {:?} \
+                 (local block hash: {:?}, remote block hash: {:?}). The first call \
+                 with differing changes: {}",
+                digest.epoch, validator, block_hash, digest.block_hash, call
+            ),
+            None if call_hashes.len() != digest.call_hashes.len() => error!(
+                "Execution of block at epoch {} diverged from validator {:?} \
+                 (local block hash: {:?}, remote block hash: {:?}). The number of calls \
+                 differs: {} locally, {} remotely",
+                digest.epoch,
+                validator,
+                block_hash,
+                digest.block_hash,
+                call_hashes.len(),
+                digest.call_hashes.len()
+            ),
+            None => error!(
+                "Execution of block at epoch {} diverged from validator {:?} \
+                 (local block hash: {:?}, remote block hash: {:?}). All calls have made \
+                 the same changes, so the divergence is caused by the core bookkeeping",
+                digest.epoch, validator, block_hash, digest.block_hash
+            ),
+        }
     }
 
     /// Checks that pre-commits count is correct and calls `validate_precommit` for each of them.
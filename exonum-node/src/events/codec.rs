@@ -82,7 +82,15 @@ impl Encoder<SignedMessage> for MessagesCodec {
     type Error = anyhow::Error;
 
     fn encode(&mut self, msg: SignedMessage, buf: &mut BytesMut) -> Result<(), Self::Error> {
-        self.session.encrypt_msg(&msg.into_bytes(), buf)?;
+        self.encode(msg.into_bytes(), buf)
+    }
+}
+
+impl Encoder<Vec<u8>> for MessagesCodec {
+    type Error = anyhow::Error;
+
+    fn encode(&mut self, raw: Vec<u8>, buf: &mut BytesMut) -> Result<(), Self::Error> {
+        self.session.encrypt_msg(&raw, buf)?;
         Ok(())
     }
 }
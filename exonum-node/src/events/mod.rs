@@ -12,16 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub(crate) use self::traffic::SharedTrafficStats;
 pub use self::{
     internal::InternalPart,
     network::{ConnectedPeerAddr, NetworkEvent, NetworkPart, NetworkRequest},
     noise::HandshakeParams,
+    traffic::PeerTraffic,
 };
 
 mod codec;
 mod internal;
 mod network;
 mod noise;
+mod traffic;
 
 use exonum::{
     helpers::{Height, Round},
@@ -18,6 +18,7 @@ use exonum::{
         x25519::{self, into_x25519_public_key},
         PublicKey,
     },
+    merkledb::BinaryValue,
     messages::{SignedMessage, Verified},
 };
 use futures::{channel::mpsc, future, prelude::*};
@@ -32,14 +33,16 @@ use std::{
     net::{SocketAddr, ToSocketAddrs},
     ops,
     sync::{Arc, RwLock},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use crate::{
     events::{
         codec::MessagesCodec,
         noise::{Handshake, HandshakeData, HandshakeParams, NoiseHandshake},
+        traffic::{BandwidthLimiter, SharedTrafficStats, TrafficCounters},
     },
+    memory::{message_size, MemoryAccountant, MemoryReservation, MemorySubsystem},
     messages::{Connect, Message, Service},
     state::SharedConnectList,
     NetworkConfiguration,
@@ -47,6 +50,9 @@ use crate::{
 
 const OUTGOING_CHANNEL_SIZE: usize = 10;
 
+/// Outgoing message together with the memory accounted for it while it is queued.
+type QueuedMessage = (SignedMessage, MemoryReservation);
+
 #[derive(Debug)]
 struct ErrorAction {
     retry_timeout: Duration,
@@ -135,11 +141,13 @@ pub struct NetworkPart {
     pub network_requests: mpsc::Receiver<NetworkRequest>,
     pub network_tx: mpsc::Sender<NetworkEvent>,
     pub(crate) connect_list: SharedConnectList,
+    pub(crate) traffic_stats: SharedTrafficStats,
+    pub(crate) memory: MemoryAccountant,
 }
 
 #[derive(Clone, Debug)]
 struct ConnectionPoolEntry {
-    sender: mpsc::Sender<SignedMessage>,
+    sender: mpsc::Sender<QueuedMessage>,
     address: ConnectedPeerAddr,
     // Connection ID assigned to the connection during instantiation. This ID is unique among
     // all connections and is used in `ConnectList::remove()` to figure out whether
@@ -150,12 +158,23 @@ struct ConnectionPoolEntry {
 #[derive(Clone, Debug)]
 struct SharedConnectionPool {
     inner: Arc<RwLock<ConnectionPool>>,
+    traffic_stats: SharedTrafficStats,
+    memory: MemoryAccountant,
+    max_peer_bandwidth: Option<u64>,
 }
 
 impl SharedConnectionPool {
-    fn new(our_key: PublicKey) -> Self {
+    fn new(
+        our_key: PublicKey,
+        traffic_stats: SharedTrafficStats,
+        memory: MemoryAccountant,
+        max_peer_bandwidth: Option<u64>,
+    ) -> Self {
         Self {
             inner: Arc::new(RwLock::new(ConnectionPool::new(our_key))),
+            traffic_stats,
+            memory,
+            max_peer_bandwidth,
         }
     }
 
@@ -177,7 +196,18 @@ impl SharedConnectionPool {
         };
 
         if let Some((mut sender, connection_id)) = maybe_peer_info {
-            if sender.send(message).await.is_err() {
+            let size = message_size(&message);
+            let reservation = match self.memory.try_reserve(MemorySubsystem::PeerQueues, size) {
+                Some(reservation) => reservation,
+                None => {
+                    log::debug!(
+                        "Dropping message to peer {}: peer queues exceed the memory budget",
+                        peer_key
+                    );
+                    return;
+                }
+            };
+            if sender.send((message, reservation)).await.is_err() {
                 log::warn!("Cannot send message to peer {}", peer_key);
                 self.write().remove(peer_key, Some(connection_id));
             }
@@ -198,12 +228,14 @@ impl SharedConnectionPool {
         }
 
         let (receiver_rx, connection_id) = guard.add(peer_key, address.clone());
+        let traffic = self.traffic_stats.register(peer_key);
         Some(Connection {
             socket,
             receiver_rx,
             address,
             key: peer_key,
             id: connection_id,
+            traffic,
         })
     }
 
@@ -254,7 +286,7 @@ impl ConnectionPool {
         &mut self,
         key: PublicKey,
         address: ConnectedPeerAddr,
-    ) -> (mpsc::Receiver<SignedMessage>, u64) {
+    ) -> (mpsc::Receiver<QueuedMessage>, u64) {
         let id = self.next_connection_id;
         let (sender, receiver_rx) = mpsc::channel(OUTGOING_CHANNEL_SIZE);
         let entry = ConnectionPoolEntry {
@@ -293,10 +325,11 @@ impl ConnectionPool {
 
 struct Connection {
     socket: Framed<TcpStream, MessagesCodec>,
-    receiver_rx: mpsc::Receiver<SignedMessage>,
+    receiver_rx: mpsc::Receiver<QueuedMessage>,
     address: ConnectedPeerAddr,
     key: PublicKey,
     id: u64,
+    traffic: Arc<TrafficCounters>,
 }
 
 #[derive(Clone)]
@@ -490,14 +523,29 @@ impl NetworkHandler {
         let (sink, stream) = connection.socket.split();
         let key = connection.key;
         let connection_id = connection.id;
+        let traffic = connection.traffic;
+        let max_bandwidth = pool.max_peer_bandwidth;
 
         // Processing of incoming messages.
+        let incoming_traffic = Arc::clone(&traffic);
+        let mut incoming_limiter = max_bandwidth.map(BandwidthLimiter::new);
+        let stream = stream.and_then(move |raw| {
+            incoming_traffic.record_received(raw.len());
+            let delay = Self::bandwidth_delay(incoming_limiter.as_mut(), raw.len());
+            async move {
+                Self::throttle(delay).await;
+                Ok(NetworkEvent::MessageReceived(raw))
+            }
+        });
+        futures::pin_mut!(stream);
+
+        let incoming_pool = pool.clone();
         let incoming = async move {
             let res = (&mut network_tx)
                 .sink_map_err(anyhow::Error::from)
-                .send_all(&mut stream.map_ok(NetworkEvent::MessageReceived))
+                .send_all(&mut stream)
                 .await;
-            if pool.write().remove(&key, Some(connection_id)) {
+            if incoming_pool.write().remove(&key, Some(connection_id)) {
                 network_tx
                     .send(NetworkEvent::PeerDisconnected(key))
                     .await
@@ -508,7 +556,23 @@ impl NetworkHandler {
         futures::pin_mut!(incoming);
 
         // Processing of outgoing messages.
-        let outgoing = connection.receiver_rx.map(Ok).forward(sink);
+        let outgoing_traffic = Arc::clone(&traffic);
+        let mut outgoing_limiter = max_bandwidth.map(BandwidthLimiter::new);
+        let outgoing = connection
+            .receiver_rx
+            .then(move |(message, reservation)| {
+                let raw = message.into_bytes();
+                outgoing_traffic.record_sent(raw.len());
+                let delay = Self::bandwidth_delay(outgoing_limiter.as_mut(), raw.len());
+                async move {
+                    Self::throttle(delay).await;
+                    // The message is no longer queued once it is passed to the socket.
+                    drop(reservation);
+                    Ok::<_, anyhow::Error>(raw)
+                }
+            })
+            .forward(sink);
+        futures::pin_mut!(outgoing);
 
         // Select the first future to terminate and drop the remaining one.
         let task = future::select(incoming, outgoing).map(|res| {
@@ -522,6 +586,19 @@ impl NetworkHandler {
             }
         });
         task.await;
+        pool.traffic_stats.unregister(&key, &traffic);
+    }
+
+    fn bandwidth_delay(limiter: Option<&mut BandwidthLimiter>, len: usize) -> Duration {
+        limiter.map_or(Duration::default(), |limiter| {
+            limiter.delay(len, Instant::now())
+        })
+    }
+
+    async fn throttle(delay: Duration) {
+        if delay > Duration::default() {
+            tokio::time::sleep(delay).await;
+        }
     }
 
     fn configure_socket(
@@ -665,9 +742,15 @@ impl NetworkPart {
     pub async fn run(self, handshake_params: HandshakeParams) {
         let our_key = handshake_params.connect.author();
 
+        let pool = SharedConnectionPool::new(
+            our_key,
+            self.traffic_stats,
+            self.memory,
+            self.network_config.max_peer_bandwidth,
+        );
         let handler = NetworkHandler::new(
             self.listen_address,
-            SharedConnectionPool::new(our_key),
+            pool,
             self.network_config,
             self.network_tx,
             handshake_params,
@@ -34,7 +34,11 @@ use std::{
 
 use crate::{
     connect_list::ConnectList,
-    events::{network::NetworkPart, noise::HandshakeParams, NetworkEvent, NetworkRequest},
+    events::{
+        network::NetworkPart, noise::HandshakeParams, NetworkEvent, NetworkRequest, PeerTraffic,
+        SharedTrafficStats,
+    },
+    memory::MemoryAccountant,
     messages::Connect,
     state::SharedConnectList,
     ConnectInfo, EventsPoolCapacity, NetworkConfiguration, NodeChannel,
@@ -46,6 +50,7 @@ struct TestHandler {
     _listen_address: SocketAddr,
     network_events_rx: mpsc::Receiver<NetworkEvent>,
     network_requests_tx: mpsc::Sender<NetworkRequest>,
+    traffic_stats: SharedTrafficStats,
 }
 
 impl TestHandler {
@@ -53,6 +58,7 @@ impl TestHandler {
         listen_address: SocketAddr,
         network_requests_tx: mpsc::Sender<NetworkRequest>,
         network_events_rx: mpsc::Receiver<NetworkEvent>,
+        traffic_stats: SharedTrafficStats,
         network_task: impl Future<Output = ()> + Send + 'static,
     ) -> Self {
         let (network_task, abort_handle) = future::abortable(network_task);
@@ -63,9 +69,16 @@ impl TestHandler {
             _listen_address: listen_address,
             network_events_rx,
             network_requests_tx,
+            traffic_stats,
         }
     }
 
+    pub fn peer_traffic(&self, key: &PublicKey) -> PeerTraffic {
+        self.traffic_stats
+            .get(key)
+            .expect("No traffic stats for the peer")
+    }
+
     async fn wait_for_event(&mut self) -> Result<NetworkEvent, ()> {
         let maybe_event = timeout(Duration::from_secs(5), self.network_events_rx.next())
             .await
@@ -156,6 +169,7 @@ impl TestEvents {
         let network_config = self.network_config;
         let (network_tx, network_rx) = channel.network_events;
         let network_requests_tx = channel.network_requests.0;
+        let traffic_stats = SharedTrafficStats::default();
 
         let network_part = NetworkPart {
             our_connect_message: connect,
@@ -165,12 +179,15 @@ impl TestEvents {
             network_requests: channel.network_requests.1,
             network_tx,
             connect_list: self.connect_list,
+            traffic_stats: traffic_stats.clone(),
+            memory: MemoryAccountant::default(),
         };
 
         TestHandler::new(
             self.listen_address,
             network_requests_tx,
             network_rx,
+            traffic_stats,
             network_part.run(handshake_params),
         )
     }
@@ -324,6 +341,56 @@ async fn test_network_big_message() {
     assert_eq!(e2.wait_for_disconnect().await, first_key);
 }
 
+#[tokio::test]
+async fn test_network_traffic_accounting() {
+    let first = "127.0.0.1:17210".parse().unwrap();
+    let second = "127.0.0.1:17211".parse().unwrap();
+    let mut connect_list = ConnectList::default();
+
+    let mut t1 = ConnectionParams::from_address(first);
+    let first_key = t1.connect_info.public_key;
+    connect_list.add(t1.connect_info.clone());
+
+    let mut t2 = ConnectionParams::from_address(second);
+    let second_key = t2.connect_info.public_key;
+    connect_list.add(t2.connect_info.clone());
+
+    let connect_list = SharedConnectList::from_connect_list(connect_list);
+    let e1 = TestEvents::with_addr(first, &connect_list);
+    let mut e2 = TestEvents::with_addr(second, &connect_list);
+    // Limit the bandwidth of the second node, so that receiving a big message takes time.
+    e2.network_config.max_peer_bandwidth = Some(100_000);
+    let mut e1 = t1.spawn(e1, connect_list.clone());
+    let mut e2 = t2.spawn(e2, connect_list);
+
+    e1.connect_with(second_key, t1.connect).await;
+    e2.wait_for_connect().await;
+    e1.wait_for_connect().await;
+
+    let message = raw_message(100_000);
+    let message_len = message.to_bytes().len() as u64;
+    e1.send_to(second_key, message.clone()).await;
+    assert_eq!(e2.wait_for_message().await, message);
+    e1.send_to(second_key, message.clone()).await;
+    let start = time::Instant::now();
+    assert_eq!(e2.wait_for_message().await, message);
+    // The second message is delayed by the bandwidth limiter.
+    assert!(start.elapsed() >= Duration::from_millis(500));
+
+    let sent = e1.peer_traffic(&second_key).bytes_sent;
+    assert!(sent >= 2 * message_len, "{} < {}", sent, 2 * message_len);
+    let received = e2.peer_traffic(&first_key).bytes_received;
+    assert!(
+        received >= 2 * message_len,
+        "{} < {}",
+        received,
+        2 * message_len
+    );
+
+    e1.disconnect_with(second_key).await;
+    assert_eq!(e1.wait_for_disconnect().await, second_key);
+}
+
 #[tokio::test]
 async fn test_network_max_message_len() {
     let first = "127.0.0.1:17202".parse().unwrap();
@@ -0,0 +1,170 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Traffic accounting and bandwidth limiting for peer connections.
+
+use exonum::crypto::PublicKey;
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// Traffic statistics of a connection with a peer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PeerTraffic {
+    /// Number of bytes received from the peer.
+    pub bytes_received: u64,
+    /// Number of bytes sent to the peer.
+    pub bytes_sent: u64,
+}
+
+/// Traffic counters for a single connection.
+#[derive(Debug, Default)]
+pub(crate) struct TrafficCounters {
+    bytes_received: AtomicU64,
+    bytes_sent: AtomicU64,
+}
+
+impl TrafficCounters {
+    pub fn record_received(&self, len: usize) {
+        self.bytes_received.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_sent(&self, len: usize) {
+        self.bytes_sent.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    fn traffic(&self) -> PeerTraffic {
+        PeerTraffic {
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Traffic counters for all active peer connections, shared between the network part
+/// of the node and the node API.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SharedTrafficStats {
+    inner: Arc<RwLock<HashMap<PublicKey, Arc<TrafficCounters>>>>,
+}
+
+impl SharedTrafficStats {
+    /// Registers a new connection with the peer, resetting the traffic counters
+    /// of the previous connection (if any).
+    pub fn register(&self, peer: PublicKey) -> Arc<TrafficCounters> {
+        let counters = Arc::new(TrafficCounters::default());
+        let mut inner = self.inner.write().unwrap();
+        inner.insert(peer, Arc::clone(&counters));
+        counters
+    }
+
+    /// Removes counters of a closed connection. The counters are not removed if they
+    /// belong to a newer connection with the same peer.
+    pub fn unregister(&self, peer: &PublicKey, counters: &Arc<TrafficCounters>) {
+        let mut inner = self.inner.write().unwrap();
+        let is_current = inner
+            .get(peer)
+            .map_or(false, |current| Arc::ptr_eq(current, counters));
+        if is_current {
+            inner.remove(peer);
+        }
+    }
+
+    /// Returns the traffic statistics of the active connection with the peer.
+    pub fn get(&self, peer: &PublicKey) -> Option<PeerTraffic> {
+        let inner = self.inner.read().unwrap();
+        inner.get(peer).map(|counters| counters.traffic())
+    }
+}
+
+/// Limiter of the bandwidth of a single connection direction.
+///
+/// The limiter delays each message so that the average throughput does not exceed
+/// the configured number of bytes per second.
+#[derive(Debug)]
+pub(crate) struct BandwidthLimiter {
+    bytes_per_second: u64,
+    next_free_time: Instant,
+}
+
+impl BandwidthLimiter {
+    pub fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes_per_second: bytes_per_second.max(1),
+            next_free_time: Instant::now(),
+        }
+    }
+
+    /// Accounts a message with the specified length and returns the time
+    /// the message should be delayed for.
+    pub fn delay(&mut self, len: usize, now: Instant) -> Duration {
+        let start = self.next_free_time.max(now);
+        let transfer_nanos = (len as u64).saturating_mul(1_000_000_000) / self.bytes_per_second;
+        self.next_free_time = start + Duration::from_nanos(transfer_nanos);
+        start - now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum::crypto::KeyPair;
+
+    use super::*;
+
+    #[test]
+    fn traffic_stats_registration() {
+        let stats = SharedTrafficStats::default();
+        let peer = KeyPair::random().public_key();
+
+        let counters = stats.register(peer);
+        counters.record_received(10);
+        counters.record_sent(5);
+        counters.record_sent(7);
+        let expected = PeerTraffic {
+            bytes_received: 10,
+            bytes_sent: 12,
+        };
+        assert_eq!(stats.get(&peer), Some(expected));
+
+        // A new connection resets counters, and the old one cannot unregister them.
+        let new_counters = stats.register(peer);
+        assert_eq!(stats.get(&peer), Some(PeerTraffic::default()));
+        stats.unregister(&peer, &counters);
+        assert!(stats.get(&peer).is_some());
+        stats.unregister(&peer, &new_counters);
+        assert!(stats.get(&peer).is_none());
+    }
+
+    #[test]
+    fn bandwidth_limiter_delays() {
+        let now = Instant::now();
+        let mut limiter = BandwidthLimiter::new(1_000);
+        limiter.next_free_time = now;
+
+        assert_eq!(limiter.delay(500, now), Duration::from_secs(0));
+        assert_eq!(limiter.delay(500, now), Duration::from_millis(500));
+        assert_eq!(limiter.delay(100, now), Duration::from_secs(1));
+        // After the idle period, messages are not delayed.
+        let later = now + Duration::from_secs(5);
+        assert_eq!(limiter.delay(100, later), Duration::from_secs(0));
+    }
+}
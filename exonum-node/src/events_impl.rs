@@ -15,14 +15,12 @@
 use exonum::blockchain::Schema;
 use log::{info, trace, warn};
 
-use std::mem;
-
 use crate::{
     events::{
         Event, EventHandler, EventOutcome, InternalEvent, InternalEventInner, InternalRequest,
         NetworkEvent,
     },
-    ConnectListConfig, ExternalMessage, NodeHandler, NodeTimeout,
+    ConnectListConfig, ExternalMessage, NodeHandler, NodeRole, NodeTimeout,
 };
 
 impl EventHandler for NodeHandler {
@@ -95,6 +93,27 @@ impl NodeHandler {
                 }
             }
 
+            ExternalMessage::ActivateStandby(request) => {
+                let blockchain_height = Schema::new(&self.blockchain.snapshot()).height();
+                let now = self.system_state.current_time();
+                match self
+                    .state
+                    .activate_standby(&request, blockchain_height, now)
+                {
+                    Ok(()) => {
+                        self.node_role = NodeRole::new(self.state.validator_id());
+                        self.api_state.set_node_role(self.node_role);
+                        self.api_state.set_standby_status(None);
+                        info!(
+                            "Standby node is activated; validator ID is {:?}",
+                            self.state.validator_id()
+                        );
+                        self.broadcast_step_down(&request);
+                    }
+                    Err(err) => warn!("Refused to activate standby node: {}", err),
+                }
+            }
+
             ExternalMessage::Shutdown => {
                 self.handle_shutdown();
                 return EventOutcome::Terminated;
@@ -144,12 +163,13 @@ impl NodeHandler {
 
         let fork = self.blockchain.fork();
         let mut schema = Schema::new(&fork);
-        for (_, tx) in mem::take(self.state.tx_cache_mut()) {
+        for (_, tx) in self.state.take_tx_cache() {
             schema.add_transaction_into_pool(tx);
         }
 
         if self.blockchain.merge(fork.into_patch()).is_err() {
             warn!("Failed to flush transactions from cache to persistent pool.");
         }
+        self.state.forget_flushed_txs();
     }
 }
@@ -56,22 +56,30 @@
 )]
 
 pub use crate::{
-    connect_list::{ConnectInfo, ConnectListConfig},
+    connect_list::{BootstrapConfig, ConnectInfo, ConnectListConfig},
+    events::PeerTraffic,
+    memory::{MemoryBudgetConfig, MemorySubsystem, SubsystemMemory},
     plugin::{NodePlugin, PluginApiContext, SharedNodeState},
+    replication::{replicate_from, ReplicationConfig},
+    rounds::{ConsensusRounds, ConsensusTimeout, ProposalInfo, RoundInfo, VotesInfo},
+    standby::{StandbyActivation, StandbyConfig, StandbyStatus},
 };
 
 use anyhow::{ensure, format_err};
 use exonum::{
     blockchain::{
         config::GenesisConfig, ApiSender, Blockchain, BlockchainBuilder, BlockchainMut,
-        ConsensusConfig, Schema, SendError,
+        ConsensusConfig, PruningConfig, Schema, SendError, DEFAULT_PROOF_CACHE_BUDGET,
     },
     crypto::{self, Hash, PublicKey},
     helpers::{user_agent, Height, Milliseconds, Round, ValidateInput, ValidatorId},
-    keys::Keys,
-    merkledb::{Database, ObjectHash},
+    keys::{DataEncryptionSecret, Keys},
+    merkledb::{
+        metrics::DatabaseMetrics, replication::ReplicationLog, Database, DbOptions, EncryptionKeys,
+        ObjectHash, RocksDB,
+    },
     messages::{AnyTx, IntoMessage, SignedMessage, Verified},
-    runtime::RuntimeInstance,
+    runtime::{RuntimeInstance, ShadowInstance},
 };
 use exonum_api::{
     AllowOrigin, ApiAccess, ApiAggregator, ApiManager, ApiManagerConfig, UpdateEndpoints,
@@ -90,6 +98,7 @@ use std::{
     convert::TryFrom,
     fmt, io,
     net::SocketAddr,
+    path::Path,
     sync::Arc,
     thread,
     time::{Duration, SystemTime},
@@ -103,6 +112,7 @@ use crate::{
     },
     messages::Connect,
     pool::{ManagePool, StandardPoolManager},
+    replication::ReplicationServer,
     schema::NodeSchema,
     state::{RequestData, State},
 };
@@ -113,15 +123,20 @@ mod consensus;
 mod events;
 mod events_impl;
 pub mod helpers;
+mod memory;
 mod messages;
 mod plugin;
 pub mod pool;
 mod proto;
+pub mod replication;
 mod requests;
+mod rounds;
 #[cfg(test)]
 mod sandbox;
 mod schema;
+mod standby;
 mod state;
+mod tx_cache;
 
 // Logically private types re-exported for benchmarks.
 #[doc(hidden)]
@@ -143,6 +158,8 @@ pub enum ExternalMessage {
     PeerAdd(ConnectInfo),
     /// Enable or disable the node.
     Enable(bool),
+    /// Activate the node running in the standby mode.
+    ActivateStandby(StandbyActivation),
     /// Shutdown the node.
     Shutdown,
 }
@@ -235,6 +252,15 @@ pub struct NodeApiConfig {
     /// is updated (e.g., due to a new service initialization).
     #[serde(default)]
     pub server_restart: ServerRestartPolicy,
+    /// Memory budget of the cache of proofs generated for API clients, in bytes.
+    /// If value is `None` (default value), the budget is 16 MiB. Zero budget disables
+    /// proof caching.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof_cache_size: Option<usize>,
+    /// Maximum number of values decoded from the storage that are cached for API handlers.
+    /// If value is `None` (default value), value caching is disabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_cache_capacity: Option<usize>,
 }
 
 impl Default for NodeApiConfig {
@@ -247,6 +273,8 @@ impl Default for NodeApiConfig {
             private_allow_origin: None,
             json_payload_size: None,
             server_restart: ServerRestartPolicy::default(),
+            proof_cache_size: None,
+            value_cache_capacity: None,
         }
     }
 }
@@ -287,6 +315,26 @@ pub struct NetworkConfiguration {
     pub tcp_connect_retry_timeout: Milliseconds,
     /// Maximum number of retries when connecting to a peer.
     pub tcp_connect_max_retries: u64,
+    /// Maximum bandwidth (in bytes per second) of a connection with a single peer.
+    /// The limit applies separately to incoming and outgoing traffic. If set to `None`,
+    /// the bandwidth is not limited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_peer_bandwidth: Option<u64>,
+    /// Exchange execution digests with peers. If set, a validator broadcasts hashes of changes
+    /// made by each call within the block along with its `Precommit`, and compares
+    /// the digests received from other validators with its own execution of the block.
+    /// If the execution diverges, the node logs the first call with differing changes,
+    /// which allows to pinpoint a non-deterministic transaction or hook.
+    ///
+    /// Recording of execution digests slows down block execution, so the option
+    /// is intended for debugging.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub execution_digests: bool,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // Required by `serde`.
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 impl Default for NetworkConfiguration {
@@ -298,6 +346,8 @@ impl Default for NetworkConfiguration {
             tcp_nodelay: true,
             tcp_connect_retry_timeout: 15_000,
             tcp_connect_max_retries: 10,
+            max_peer_bandwidth: None,
+            execution_digests: false,
         }
     }
 }
@@ -360,6 +410,22 @@ pub struct MemoryPoolConfig {
     /// allows to specify the coherence interval for the pool.
     #[serde(default)]
     pub flush_pool_strategy: FlushPoolStrategy,
+
+    /// Whether to record transactions rejected on admission to the pool into the blocks
+    /// proposed by this node.
+    ///
+    /// Rejected transactions are neither broadcast nor stored by the node, thus their authors
+    /// have no way to distinguish a rejected transaction from the one lost in the network.
+    /// If this setting is enabled, hashes of rejected transactions together with the reasons
+    /// of rejection are committed to the blockchain, so that clients can obtain a verifiable
+    /// proof of the rejection. The setting is disabled by default.
+    #[serde(default)]
+    pub commit_rejected_transactions: bool,
+
+    /// Memory budgets of the node subsystems. By default, memory usage is accounted,
+    /// but not limited.
+    #[serde(default)]
+    pub memory_budget: MemoryBudgetConfig,
 }
 
 /// Strategy to flush transactions into the pool.
@@ -412,6 +478,24 @@ pub struct NodeConfig {
     pub connect_list: ConnectListConfig,
     /// Number of threads allocated for transaction verification.
     pub thread_pool_size: Option<u8>,
+    /// Standby mode configuration. If set, the node starts in the standby mode: it does not
+    /// participate in consensus even if its consensus key belongs to a validator, until
+    /// it is activated via the private API.
+    #[serde(default)]
+    pub standby: Option<StandbyConfig>,
+    /// Bootstrap mode configuration. If set, the node only communicates with the peers
+    /// from the allowlist until the first block is committed.
+    #[serde(default)]
+    pub bootstrap: Option<BootstrapConfig>,
+    /// Pruning of historical block data. If set, the node removes historical data
+    /// (e.g., lists of transactions in blocks) for blocks older than the configured
+    /// number of heights.
+    #[serde(default)]
+    pub pruning: Option<PruningConfig>,
+    /// Streaming of database patches to read replicas. If set, the node listens to
+    /// replica connections on the configured address.
+    #[serde(default)]
+    pub replication: Option<ReplicationConfig>,
 }
 
 impl ValidateInput for NodeConfig {
@@ -454,6 +538,13 @@ impl ValidateInput for NodeConfig {
             capacity.network_requests_capacity,
             sanity_max,
         );
+        self.mempool.memory_budget.validate()?;
+        if let Some(pruning) = &self.pruning {
+            pruning.validate()?;
+        }
+        if let Some(replication) = &self.replication {
+            replication.validate()?;
+        }
         self.consensus.validate()
     }
 }
@@ -474,6 +565,10 @@ pub struct Configuration {
     pub mempool: MemoryPoolConfig,
     /// Validator keys.
     pub keys: Keys,
+    /// Standby mode configuration.
+    pub standby: Option<StandbyConfig>,
+    /// Bootstrap mode configuration.
+    pub bootstrap: Option<BootstrapConfig>,
 }
 
 /// Channel for messages, timeouts and api requests. Consumed by the `NodeHandler` constructor.
@@ -560,12 +655,15 @@ impl NodeHandler {
             &last_block,
             last_block_skip.as_ref(),
             system_state.current_time(),
+            api_state.tx_cache(),
+            api_state.memory(),
         );
 
         let validator_id = state.validator_id();
         let node_role = NodeRole::new(validator_id);
         let is_enabled = api_state.is_enabled();
         api_state.set_node_role(node_role);
+        api_state.set_standby_status(state.standby_status());
 
         Self {
             blockchain,
@@ -990,6 +1088,7 @@ pub struct Node {
     #[allow(dead_code)]
     thread_pool_size: Option<u8>,
     disable_signals: bool,
+    replication: Option<ReplicationServer>,
 }
 
 impl Default for NodeChannel {
@@ -1046,6 +1145,8 @@ pub struct NodeBuilder {
     pool_manager: Box<dyn ManagePool>,
     plugins: Vec<Box<dyn NodePlugin>>,
     disable_signals: bool,
+    replication: Option<ReplicationServer>,
+    storage_metrics: Option<DatabaseMetrics>,
 }
 
 impl fmt::Debug for NodeBuilder {
@@ -1071,8 +1172,25 @@ impl NodeBuilder {
             .expect("Node configuration is inconsistent");
 
         let channel = NodeChannel::new(&node_config.mempool.events_pool_capacity);
-        let blockchain = Blockchain::new(database, node_keys.service.clone(), channel.api_sender());
-        let blockchain_builder = BlockchainBuilder::new(blockchain);
+        let mut database: Arc<dyn Database> = database.into();
+        let mut replication = None;
+        if let Some(config) = &node_config.replication {
+            let log = ReplicationLog::new(database, config.backlog_size);
+            database = Arc::new(log.clone());
+            replication = Some(ReplicationServer::new(config, log));
+        }
+        let proof_cache_size = node_config
+            .api
+            .proof_cache_size
+            .unwrap_or(DEFAULT_PROOF_CACHE_BUDGET);
+        let value_cache_capacity = node_config.api.value_cache_capacity.unwrap_or(0);
+        let blockchain = Blockchain::new(database, node_keys.service.clone(), channel.api_sender())
+            .with_proof_cache_budget(proof_cache_size)
+            .with_value_cache_capacity(value_cache_capacity);
+        let mut blockchain_builder = BlockchainBuilder::new(blockchain);
+        if let Some(pruning) = node_config.pruning {
+            blockchain_builder = blockchain_builder.with_pruning(pruning);
+        }
 
         Self {
             channel,
@@ -1083,9 +1201,46 @@ impl NodeBuilder {
             plugins: vec![],
             pool_manager: Box::new(StandardPoolManager::default()),
             disable_signals: false,
+            replication,
+            storage_metrics: None,
         }
     }
 
+    /// Instantiates a builder with a `RocksDB` database stored at the specified path.
+    /// The database is opened (or created, if allowed by `db_options`) with the provided
+    /// options, which allows to tune the storage engine of the node.
+    ///
+    /// Databases with encryption at rest should be opened with
+    /// [`with_encrypted_rocksdb`](#method.with_encrypted_rocksdb) instead.
+    pub fn with_rocksdb(
+        db_path: impl AsRef<Path>,
+        db_options: &DbOptions,
+        node_config: NodeConfig,
+        node_keys: Keys,
+    ) -> anyhow::Result<Self> {
+        let database = RocksDB::open(db_path, db_options)?;
+        Ok(Self::new(database, node_config, node_keys))
+    }
+
+    /// Instantiates a builder with a `RocksDB` database with encryption at rest configured
+    /// in `db_options`. Encryption keys are derived from the dedicated data encryption secret
+    /// of the node rather than from its consensus key; besides the active key, only keys
+    /// of the retired versions listed in the encryption options are derived.
+    pub fn with_encrypted_rocksdb(
+        db_path: impl AsRef<Path>,
+        db_options: &DbOptions,
+        data_encryption_secret: &DataEncryptionSecret,
+        node_config: NodeConfig,
+        node_keys: Keys,
+    ) -> anyhow::Result<Self> {
+        let encryption = db_options.encryption.as_ref().ok_or_else(|| {
+            format_err!("Encryption at rest is not configured in the database options")
+        })?;
+        let keys = EncryptionKeys::derive(data_encryption_secret.as_bytes(), encryption);
+        let database = RocksDB::open_encrypted(db_path, db_options, keys)?;
+        Ok(Self::new(database, node_config, node_keys))
+    }
+
     /// Adds a genesis config to use if the blockchain is not initialized yet.
     #[must_use]
     pub fn with_genesis_config(mut self, genesis_config: GenesisConfig) -> Self {
@@ -1115,6 +1270,14 @@ impl NodeBuilder {
         self
     }
 
+    /// Adds a local-only shadow service instance, which executes the same transactions
+    /// as a production service without participating in consensus.
+    #[must_use]
+    pub fn with_shadow_instance(mut self, shadow: ShadowInstance) -> Self {
+        self.blockchain_builder = self.blockchain_builder.with_shadow_instance(shadow);
+        self
+    }
+
     /// Adds the configuration manager.
     #[must_use]
     pub fn with_config_manager<T: ConfigManager + 'static>(mut self, manager: T) -> Self {
@@ -1145,6 +1308,16 @@ impl NodeBuilder {
         self
     }
 
+    /// Publishes storage metrics via the node API (e.g., via the private system API).
+    /// The metrics are collected by a [`MetricsDb`] wrapping the node database.
+    ///
+    /// [`MetricsDb`]: https://docs.rs/exonum-merkledb/latest/exonum_merkledb/metrics/struct.MetricsDb.html
+    #[must_use]
+    pub fn with_storage_metrics(mut self, metrics: DatabaseMetrics) -> Self {
+        self.storage_metrics = Some(metrics);
+        self
+    }
+
     /// Switches off [default signal handling] for the node.
     /// This is useful to implement more complex signal handling, or one that differs
     /// from the default.
@@ -1173,6 +1346,10 @@ impl NodeBuilder {
             self.pool_manager,
         );
         node.disable_signals = self.disable_signals;
+        node.replication = self.replication;
+        if let Some(metrics) = self.storage_metrics {
+            node.handler.api_state.set_storage_metrics(metrics);
+        }
         node
     }
 }
@@ -1197,6 +1374,8 @@ impl Node {
             network: node_cfg.network,
             peer_discovery: peers,
             keys: node_keys,
+            standby: node_cfg.standby,
+            bootstrap: node_cfg.bootstrap,
         };
 
         let api_state = SharedNodeState::new(node_cfg.api.state_update_timeout as u64);
@@ -1253,6 +1432,7 @@ impl Node {
             thread_pool_size: node_cfg.thread_pool_size,
             api_manager_config: api_runtime_config,
             disable_signals: false,
+            replication: None,
         }
     }
 
@@ -1320,6 +1500,7 @@ struct Reactor {
     network_part: NetworkPart,
     internal_part: InternalPart,
     api_part: oneshot::Receiver<io::Result<()>>,
+    replication_part: Option<ReplicationServer>,
     shutdown_handle: ShutdownHandle,
     // Flag indicating whether the reactor should explicitly handle signals.
     // If there is at least one actix HTTP server, signal handling will be performed by it,
@@ -1361,6 +1542,8 @@ impl Reactor {
             network_config: node.network_config,
             max_message_len: node.max_message_len,
             connect_list,
+            traffic_stats: node.handler.api_state.traffic_stats(),
+            memory: node.handler.api_state.memory(),
         };
 
         let (internal_tx, internal_rx) = node.channel.internal_events;
@@ -1382,6 +1565,7 @@ impl Reactor {
             network_part,
             internal_part,
             api_part,
+            replication_part: node.replication,
             shutdown_handle,
             needs_signal_handler,
         }
@@ -1446,6 +1630,16 @@ impl Reactor {
         let handler_task = self.handler_part.run().fuse();
         futures::pin_mut!(handler_task);
         let mut api_task = self.api_part.fuse();
+        let replication_part = self.replication_part;
+        // The replication server never completes, so that its failures cannot stop the node.
+        let replication_task = async move {
+            match replication_part {
+                Some(server) => server.run().await,
+                None => futures::future::pending().await,
+            }
+        }
+        .fuse();
+        futures::pin_mut!(replication_task);
 
         if self.needs_signal_handler {
             // Send the shutdown signal once we received a signal.
@@ -1472,6 +1666,7 @@ impl Reactor {
                 };
                 (res, true)
             }
+            () = replication_task => unreachable!("Replication server never terminates"),
         };
 
         if should_clean_up {
@@ -1515,6 +1710,10 @@ pub fn generate_testnet_config(count: u16, start_port: u16) -> Vec<(NodeConfig,
                 api: NodeApiConfig::default(),
                 mempool: MemoryPoolConfig::default(),
                 thread_pool_size: None,
+                standby: None,
+                bootstrap: None,
+                pruning: None,
+                replication: None,
             };
             (config, keys)
         })
@@ -1608,4 +1807,15 @@ mod tests {
         let restored: MemoryPoolConfig = toml::from_str(config_without_strategy).unwrap();
         assert_eq!(restored, MemoryPoolConfig::default());
     }
+
+    #[test]
+    fn commit_rejected_transactions_is_serializable() {
+        let mut mempool_config = MemoryPoolConfig::default();
+        assert!(!mempool_config.commit_rejected_transactions);
+
+        mempool_config.commit_rejected_transactions = true;
+        let s = toml::to_string(&mempool_config).unwrap();
+        let restored: MemoryPoolConfig = toml::from_str(&s).unwrap();
+        assert_eq!(restored, mempool_config);
+    }
 }
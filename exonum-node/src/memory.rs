@@ -0,0 +1,341 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Accounting of the memory used by the node subsystems.
+
+use anyhow::ensure;
+use exonum::{
+    crypto::{PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH},
+    messages::SignedMessage,
+};
+use serde::{Deserialize, Serialize};
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+/// Node subsystem with accounted memory usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum MemorySubsystem {
+    /// Ephemeral cache of the transactions not yet flushed into the persistent pool.
+    TxPool,
+    /// Consensus messages queued for processing in the future rounds.
+    ConsensusCache,
+    /// Outgoing messages queued for sending to peers.
+    PeerQueues,
+    /// Buffers of the node HTTP API, such as the cache of generated proofs.
+    ApiBuffers,
+}
+
+impl MemorySubsystem {
+    const ALL: [Self; 4] = [
+        Self::TxPool,
+        Self::ConsensusCache,
+        Self::PeerQueues,
+        Self::ApiBuffers,
+    ];
+
+    fn index(self) -> usize {
+        match self {
+            Self::TxPool => 0,
+            Self::ConsensusCache => 1,
+            Self::PeerQueues => 2,
+            Self::ApiBuffers => 3,
+        }
+    }
+}
+
+/// Memory budgets of the node subsystems in bytes. A subsystem without a budget
+/// is only accounted.
+///
+/// Once a subsystem exceeds its budget, the node applies the shedding policy
+/// of the subsystem:
+///
+/// - The transaction cache is flushed into the persistent pool.
+/// - New consensus messages for the future rounds are dropped. The dropped messages
+///   are requested from peers again if they are necessary for the consensus.
+/// - New outgoing messages to peers are dropped.
+/// - The proof cache of the API is cleared.
+///
+/// Memory usage is approximate: only the sizes of serialized messages are taken
+/// into account.
+///
+/// # Examples
+///
+/// ```
+/// # use exonum_node::{MemoryBudgetConfig, MemoryPoolConfig, MemorySubsystem};
+/// let mut pool_config = MemoryPoolConfig::default();
+/// pool_config.memory_budget = MemoryBudgetConfig::default()
+///     .with_budget(MemorySubsystem::TxPool, 64 << 20)
+///     .with_budget(MemorySubsystem::PeerQueues, 16 << 20);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct MemoryBudgetConfig {
+    /// Budget of the ephemeral transaction cache.
+    #[serde(default)]
+    pub tx_pool: Option<u64>,
+    /// Budget of the consensus messages queued for the future rounds.
+    #[serde(default)]
+    pub consensus_cache: Option<u64>,
+    /// Budget of the outgoing messages queued for all peers.
+    #[serde(default)]
+    pub peer_queues: Option<u64>,
+    /// Budget of the API buffers.
+    #[serde(default)]
+    pub api_buffers: Option<u64>,
+}
+
+impl MemoryBudgetConfig {
+    /// Returns the budget of the specified subsystem.
+    pub fn budget(&self, subsystem: MemorySubsystem) -> Option<u64> {
+        match subsystem {
+            MemorySubsystem::TxPool => self.tx_pool,
+            MemorySubsystem::ConsensusCache => self.consensus_cache,
+            MemorySubsystem::PeerQueues => self.peer_queues,
+            MemorySubsystem::ApiBuffers => self.api_buffers,
+        }
+    }
+
+    /// Sets the budget of the specified subsystem.
+    #[must_use]
+    pub fn with_budget(mut self, subsystem: MemorySubsystem, bytes: u64) -> Self {
+        let budget = match subsystem {
+            MemorySubsystem::TxPool => &mut self.tx_pool,
+            MemorySubsystem::ConsensusCache => &mut self.consensus_cache,
+            MemorySubsystem::PeerQueues => &mut self.peer_queues,
+            MemorySubsystem::ApiBuffers => &mut self.api_buffers,
+        };
+        *budget = Some(bytes);
+        self
+    }
+
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        for &subsystem in &MemorySubsystem::ALL {
+            ensure!(
+                self.budget(subsystem) != Some(0),
+                "Memory budget of {:?} must be strictly larger than 0",
+                subsystem
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Memory usage statistics of a node subsystem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SubsystemMemory {
+    /// Subsystem.
+    pub subsystem: MemorySubsystem,
+    /// Approximate number of bytes used by the subsystem.
+    pub used: u64,
+    /// Budget of the subsystem, if any.
+    pub budget: Option<u64>,
+    /// Number of times the shedding policy of the subsystem was applied.
+    pub shed_events: u64,
+}
+
+/// Value of a budget meaning that the subsystem is not limited.
+const NO_BUDGET: u64 = u64::MAX;
+
+#[derive(Debug)]
+struct AccountantInner {
+    budgets: [AtomicU64; 4],
+    used: [AtomicU64; 4],
+    shed_events: [AtomicU64; 4],
+}
+
+impl Default for AccountantInner {
+    fn default() -> Self {
+        Self {
+            budgets: [
+                AtomicU64::new(NO_BUDGET),
+                AtomicU64::new(NO_BUDGET),
+                AtomicU64::new(NO_BUDGET),
+                AtomicU64::new(NO_BUDGET),
+            ],
+            used: Default::default(),
+            shed_events: Default::default(),
+        }
+    }
+}
+
+/// Memory accounting shared between the node parts and the node API.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MemoryAccountant {
+    inner: Arc<AccountantInner>,
+}
+
+impl MemoryAccountant {
+    /// Sets budgets of the subsystems.
+    pub fn set_budgets(&self, config: &MemoryBudgetConfig) {
+        for &subsystem in &MemorySubsystem::ALL {
+            let budget = config.budget(subsystem).unwrap_or(NO_BUDGET);
+            self.inner.budgets[subsystem.index()].store(budget, Ordering::Relaxed);
+        }
+    }
+
+    fn budget(&self, subsystem: MemorySubsystem) -> Option<u64> {
+        match self.inner.budgets[subsystem.index()].load(Ordering::Relaxed) {
+            NO_BUDGET => None,
+            budget => Some(budget),
+        }
+    }
+
+    fn used(&self, subsystem: MemorySubsystem) -> u64 {
+        self.inner.used[subsystem.index()].load(Ordering::Acquire)
+    }
+
+    /// Accounts `bytes` allocated by the subsystem.
+    pub fn add(&self, subsystem: MemorySubsystem, bytes: u64) {
+        self.inner.used[subsystem.index()].fetch_add(bytes, Ordering::AcqRel);
+    }
+
+    /// Accounts `bytes` released by the subsystem.
+    pub fn release(&self, subsystem: MemorySubsystem, bytes: u64) {
+        self.inner.used[subsystem.index()]
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                Some(used.saturating_sub(bytes))
+            })
+            .ok();
+    }
+
+    /// Sets the usage of the subsystem, e.g., after it was cleared.
+    pub fn set_used(&self, subsystem: MemorySubsystem, bytes: u64) {
+        self.inner.used[subsystem.index()].store(bytes, Ordering::Release);
+    }
+
+    /// Checks whether the subsystem exceeds its budget.
+    pub fn is_over_budget(&self, subsystem: MemorySubsystem) -> bool {
+        self.budget(subsystem)
+            .map_or(false, |budget| self.used(subsystem) > budget)
+    }
+
+    /// Checks whether `bytes` can be allocated by the subsystem without exceeding its budget.
+    pub fn fits(&self, subsystem: MemorySubsystem, bytes: u64) -> bool {
+        self.budget(subsystem).map_or(true, |budget| {
+            self.used(subsystem).saturating_add(bytes) <= budget
+        })
+    }
+
+    /// Records that the shedding policy of the subsystem was applied.
+    pub fn record_shed(&self, subsystem: MemorySubsystem) {
+        self.inner.shed_events[subsystem.index()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Accounts `bytes` allocated by the subsystem if this does not exceed its budget.
+    /// The returned reservation releases the bytes once dropped. If the budget would be
+    /// exceeded, a shedding event is recorded and `None` is returned.
+    pub fn try_reserve(&self, subsystem: MemorySubsystem, bytes: u64) -> Option<MemoryReservation> {
+        if self.fits(subsystem, bytes) {
+            self.add(subsystem, bytes);
+            Some(MemoryReservation {
+                accountant: self.clone(),
+                subsystem,
+                bytes,
+            })
+        } else {
+            self.record_shed(subsystem);
+            None
+        }
+    }
+
+    /// Returns memory usage statistics of all subsystems.
+    pub fn usage(&self) -> Vec<SubsystemMemory> {
+        MemorySubsystem::ALL
+            .iter()
+            .map(|&subsystem| SubsystemMemory {
+                subsystem,
+                used: self.used(subsystem),
+                budget: self.budget(subsystem),
+                shed_events: self.inner.shed_events[subsystem.index()].load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
+
+/// Memory accounted by a subsystem, which is released once the reservation is dropped.
+#[derive(Debug)]
+pub(crate) struct MemoryReservation {
+    accountant: MemoryAccountant,
+    subsystem: MemorySubsystem,
+    bytes: u64,
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        self.accountant.release(self.subsystem, self.bytes);
+    }
+}
+
+/// Returns the approximate size of a message in memory.
+pub(crate) fn message_size(message: &SignedMessage) -> u64 {
+    (message.payload.len() + PUBLIC_KEY_LENGTH + SIGNATURE_LENGTH) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_accounting() {
+        let accountant = MemoryAccountant::default();
+        let config = MemoryBudgetConfig::default().with_budget(MemorySubsystem::PeerQueues, 100);
+        accountant.set_budgets(&config);
+
+        accountant.add(MemorySubsystem::TxPool, 1_000);
+        assert!(!accountant.is_over_budget(MemorySubsystem::TxPool));
+        accountant.release(MemorySubsystem::TxPool, 2_000);
+        assert_eq!(accountant.used(MemorySubsystem::TxPool), 0);
+
+        let first = accountant
+            .try_reserve(MemorySubsystem::PeerQueues, 60)
+            .unwrap();
+        assert!(accountant
+            .try_reserve(MemorySubsystem::PeerQueues, 60)
+            .is_none());
+        drop(first);
+        let _second = accountant
+            .try_reserve(MemorySubsystem::PeerQueues, 60)
+            .unwrap();
+
+        let usage = accountant.usage();
+        let peer_queues = usage
+            .iter()
+            .find(|stats| stats.subsystem == MemorySubsystem::PeerQueues)
+            .unwrap();
+        assert_eq!(peer_queues.used, 60);
+        assert_eq!(peer_queues.budget, Some(100));
+        assert_eq!(peer_queues.shed_events, 1);
+    }
+
+    #[test]
+    fn memory_budget_config_serialization() {
+        let config = MemoryBudgetConfig::default().with_budget(MemorySubsystem::TxPool, 1_024);
+        let s = toml::to_string(&config).unwrap();
+        let restored: MemoryBudgetConfig = toml::from_str(&s).unwrap();
+        assert_eq!(restored, config);
+
+        let restored: MemoryBudgetConfig = toml::from_str("").unwrap();
+        assert_eq!(restored, MemoryBudgetConfig::default());
+        MemoryBudgetConfig::default()
+            .with_budget(MemorySubsystem::ApiBuffers, 0)
+            .validate()
+            .unwrap_err();
+    }
+}
@@ -48,6 +48,10 @@ pub enum Service {
     Connect(Verified<Connect>),
     /// Status message.
     Status(Verified<Status>),
+    /// Execution digest message.
+    ExecutionDigest(Verified<ExecutionDigest>),
+    /// Step down message.
+    StepDown(Verified<StepDown>),
 }
 
 impl Service {
@@ -56,6 +60,8 @@ impl Service {
             Self::AnyTx(ref msg) => msg.as_raw(),
             Self::Connect(ref msg) => msg.as_raw(),
             Self::Status(ref msg) => msg.as_raw(),
+            Self::ExecutionDigest(ref msg) => msg.as_raw(),
+            Self::StepDown(ref msg) => msg.as_raw(),
         }
     }
 }
@@ -72,7 +78,7 @@ pub enum Consensus {
 }
 
 impl Consensus {
-    fn as_raw(&self) -> &SignedMessage {
+    pub(crate) fn as_raw(&self) -> &SignedMessage {
         match self {
             Self::Precommit(msg) => msg.as_raw(),
             Self::Propose(msg) => msg.as_raw(),
@@ -231,6 +237,8 @@ impl_message_from_verified! {
     AnyTx: Service,
     Connect: Service,
     Status: Service,
+    ExecutionDigest: Service,
+    StepDown: Service,
     Precommit: Consensus,
     Prevote: Consensus,
     Propose: Consensus,
@@ -328,16 +336,17 @@ impl ObjectHash for Message {
 #[cfg(test)]
 mod tests {
     use exonum::{
-        blockchain::{AdditionalHeaders, Block, BlockProof},
+        blockchain::{AdditionalHeaders, Block, BlockProof, CallInBlock, RejectedTransaction},
         crypto::{self, KeyPair},
         merkledb::ObjectHash,
+        runtime::ErrorKind,
     };
     use pretty_assertions::assert_eq;
 
     use super::{
-        BinaryValue, BlockResponse, ExonumMessage, Hash, Height, Precommit, Round, SignedMessage,
-        Status, TransactionsResponse, ValidatorId, Verified, TX_RES_EMPTY_SIZE,
-        TX_RES_PB_OVERHEAD_PAYLOAD,
+        BinaryValue, BlockResponse, CallHash, ExecutionDigest, ExonumMessage, Hash, Height,
+        Message, Precommit, Propose, Round, Service, SignedMessage, Status, StepDown,
+        TransactionsResponse, ValidatorId, Verified, TX_RES_EMPTY_SIZE, TX_RES_PB_OVERHEAD_PAYLOAD,
     };
 
     #[test]
@@ -407,6 +416,50 @@ mod tests {
         assert_eq!(msg, msg2);
     }
 
+    #[test]
+    fn test_execution_digest_roundtrip() {
+        let keypair = KeyPair::random();
+        let digest = ExecutionDigest::new(
+            Height(1),
+            crypto::hash(b"propose"),
+            crypto::hash(b"block"),
+            vec![
+                CallHash::new(CallInBlock::before_transactions(0), crypto::hash(b"0")),
+                CallHash::new(CallInBlock::transaction(0), crypto::hash(b"1")),
+                CallHash::new(CallInBlock::after_transactions(0), crypto::hash(b"2")),
+            ],
+        );
+        let msg = Verified::from_value(digest.clone(), keypair.public_key(), keypair.secret_key());
+
+        let message = Message::from_raw_buffer(msg.to_bytes()).unwrap();
+        match message {
+            Message::Service(Service::ExecutionDigest(restored)) => {
+                assert_eq!(*restored.payload(), digest);
+            }
+            other => panic!("Unexpected message: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_step_down_roundtrip() {
+        let keypair = KeyPair::random();
+        let step_down = StepDown::new(Height(5), 1_600_000_000);
+        let msg = Verified::from_value(
+            step_down.clone(),
+            keypair.public_key(),
+            keypair.secret_key(),
+        );
+
+        let message = Message::from_raw_buffer(msg.to_bytes()).unwrap();
+        match message {
+            Message::Service(Service::StepDown(restored)) => {
+                assert_eq!(*restored.payload(), step_down);
+                assert_eq!(restored.author(), keypair.public_key());
+            }
+            other => panic!("Unexpected message: {:?}", other),
+        }
+    }
+
     #[test]
     fn test_tx_response_empty_size() {
         let keys = KeyPair::random();
@@ -542,4 +595,55 @@ mod tests {
         let block_proof_1: BlockProof = serde_json::from_str(&json_str).unwrap();
         assert_eq!(block_proof, block_proof_1);
     }
+
+    #[test]
+    fn test_propose_with_rejected_transactions() {
+        let keys = KeyPair::random();
+        let mut rejected = vec![
+            RejectedTransaction::new(crypto::hash(&[1]), ErrorKind::Core { code: 2 }),
+            RejectedTransaction::new(crypto::hash(&[2]), ErrorKind::Service { code: 5 }),
+        ];
+        rejected.sort();
+        let tx_hash = crypto::hash(&[3]);
+        let propose = Propose::new(
+            ValidatorId(0),
+            Height(1),
+            Round(1),
+            crypto::hash(&[]),
+            vec![tx_hash],
+        )
+        .with_rejected_transactions(rejected.clone());
+        assert!(propose.verify_rejected_transactions());
+
+        let propose = Verified::from_value(propose, keys.public_key(), keys.secret_key());
+        let restored: Verified<Propose> = SignedMessage::from_bytes(propose.to_bytes().into())
+            .unwrap()
+            .into_verified()
+            .unwrap();
+        assert_eq!(restored.payload().rejected_transactions, rejected);
+
+        // Rejected transactions must be sorted by hash.
+        let mut unsorted = rejected.clone();
+        unsorted.reverse();
+        let propose = Propose::new(
+            ValidatorId(0),
+            Height(1),
+            Round(1),
+            crypto::hash(&[]),
+            vec![],
+        )
+        .with_rejected_transactions(unsorted);
+        assert!(!propose.verify_rejected_transactions());
+
+        // Rejected transactions must not be included into the proposal.
+        let propose = Propose::new(
+            ValidatorId(0),
+            Height(1),
+            Round(1),
+            crypto::hash(&[]),
+            vec![rejected[1].tx_hash],
+        )
+        .with_rejected_transactions(rejected);
+        assert!(!propose.verify_rejected_transactions());
+    }
 }
@@ -14,7 +14,7 @@
 
 use bit_vec::BitVec;
 use exonum::{
-    blockchain::Block,
+    blockchain::{Block, CallInBlock, RejectedTransaction},
     crypto::{Hash, PublicKey},
     helpers::{Height, Round, ValidatorId},
     impl_exonum_msg_try_from_signed,
@@ -138,6 +138,10 @@ impl Status {
 /// - is sent by non-leader
 /// - contains already committed transactions
 /// - is already known
+/// - contains rejected transactions which are not sorted by hash or are included
+///   into the proposal
+/// - contains precommits for the previous block which do not form a majority
+///   of valid precommits for `prev_hash`
 ///
 /// ### Processing
 ///
@@ -165,6 +169,13 @@ pub struct Propose {
     pub transactions: Vec<Hash>,
     /// Do nothing instead of approving a new block.
     pub skip: bool,
+    /// Transactions rejected by the proposer on admission to its transaction pool,
+    /// sorted by the transaction hash. These transactions are recorded in the block.
+    pub rejected_transactions: Vec<RejectedTransaction>,
+    /// Precommits for the previous block, which define the time of the proposed block.
+    /// The precommits are provided only if the `block_time` option of the consensus
+    /// configuration is enabled.
+    pub last_precommits: Vec<Vec<u8>>,
 }
 
 impl Propose {
@@ -183,6 +194,8 @@ impl Propose {
             prev_hash,
             transactions: transactions.into_iter().collect(),
             skip: false,
+            rejected_transactions: vec![],
+            last_precommits: vec![],
         }
     }
 
@@ -194,8 +207,43 @@ impl Propose {
             prev_hash,
             transactions: vec![],
             skip: true,
+            rejected_transactions: vec![],
+            last_precommits: vec![],
         }
     }
+
+    /// Sets transactions rejected by the proposer, which should be recorded in the block.
+    #[must_use]
+    pub fn with_rejected_transactions(
+        mut self,
+        rejected_transactions: impl IntoIterator<Item = RejectedTransaction>,
+    ) -> Self {
+        self.rejected_transactions = rejected_transactions.into_iter().collect();
+        self
+    }
+
+    /// Sets serialized precommits for the previous block, which define the time
+    /// of the proposed block.
+    #[must_use]
+    pub fn with_last_precommits(mut self, precommits: impl IntoIterator<Item = Vec<u8>>) -> Self {
+        self.last_precommits = precommits.into_iter().collect();
+        self
+    }
+
+    /// Checks that rejected transactions are sorted by hash (and thus unique) and
+    /// are not included into the proposal.
+    pub fn verify_rejected_transactions(&self) -> bool {
+        let is_sorted = self
+            .rejected_transactions
+            .windows(2)
+            .all(|pair| pair[0].tx_hash < pair[1].tx_hash);
+        is_sorted
+            && self.transactions.iter().all(|tx_hash| {
+                self.rejected_transactions
+                    .binary_search_by_key(tx_hash, |rejected| rejected.tx_hash)
+                    .is_err()
+            })
+    }
 }
 
 /// Pre-vote for a new block.
@@ -253,6 +301,103 @@ impl Prevote {
     }
 }
 
+/// Hash of the changes made by a call within a block.
+#[derive(Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Debug, ProtobufConvert)]
+#[protobuf_convert(source = "consensus::CallHash")]
+pub struct CallHash {
+    /// Call within the block.
+    pub call: CallInBlock,
+    /// Hash of the changes made by the call.
+    pub hash: Hash,
+}
+
+impl CallHash {
+    /// Creates a new `CallHash`.
+    pub fn new(call: CallInBlock, hash: Hash) -> Self {
+        Self { call, hash }
+    }
+}
+
+/// Digest of the execution of a block by a validator.
+///
+/// ### Validation
+///
+/// The message is ignored if it is not authored by a validator, or if the node
+/// has not executed the block corresponding to the `Propose` yet.
+///
+/// ### Processing
+///
+/// If the block hash differs from the hash obtained by the node, the node compares
+/// the call hashes with its own ones and logs the first differing call.
+///
+/// ### Generation
+///
+/// A node broadcasts `ExecutionDigest` along with `Precommit` if exchange of execution
+/// digests is enabled in the node configuration.
+#[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Debug, ProtobufConvert)]
+#[protobuf_convert(source = "consensus::ExecutionDigest")]
+pub struct ExecutionDigest {
+    /// The epoch to which the message is related.
+    pub epoch: Height,
+    /// Hash of the `Propose` executed by the validator.
+    pub propose_hash: Hash,
+    /// Hash of the block obtained by the validator.
+    pub block_hash: Hash,
+    /// Hashes of the changes made by each call within the block, in the order of execution.
+    pub call_hashes: Vec<CallHash>,
+}
+
+impl ExecutionDigest {
+    /// Create new `ExecutionDigest` message.
+    pub fn new(
+        epoch: Height,
+        propose_hash: Hash,
+        block_hash: Hash,
+        call_hashes: impl IntoIterator<Item = CallHash>,
+    ) -> Self {
+        Self {
+            epoch,
+            propose_hash,
+            block_hash,
+            call_hashes: call_hashes.into_iter().collect(),
+        }
+    }
+}
+
+/// Request for the primary validator node to step down, which is issued by a standby node
+/// with the same consensus key on its activation.
+///
+/// ### Validation
+///
+/// The message is ignored if it is not authored by a validator, or if it has expired.
+///
+/// ### Processing
+///
+/// A node with another consensus key relays the message to the peer with the consensus key
+/// of the author, so that the message reaches the primary node even if the primary
+/// is not connected to the standby node. A node with the same consensus key as the author
+/// switches to the standby mode unless it has issued the message itself.
+///
+/// ### Generation
+///
+/// A standby node broadcasts `StepDown` once it is activated.
+#[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Debug, ProtobufConvert)]
+#[protobuf_convert(source = "consensus::StepDown")]
+pub struct StepDown {
+    /// Height from the activation request of the standby node.
+    pub height: Height,
+    /// Unix time (in seconds) after which the message is ignored. Coincides
+    /// with the expiration time of the activation request.
+    pub expires_at: u64,
+}
+
+impl StepDown {
+    /// Create new `StepDown` message.
+    pub fn new(height: Height, expires_at: u64) -> Self {
+        Self { height, expires_at }
+    }
+}
+
 /// Information about a block.
 ///
 /// ### Processing
@@ -273,6 +418,8 @@ pub struct BlockResponse {
     pub precommits: Vec<Vec<u8>>,
     /// List of the transaction hashes.
     pub transactions: Vec<Hash>,
+    /// Transactions rejected by the block proposer.
+    pub rejected_transactions: Vec<RejectedTransaction>,
 }
 
 impl BlockResponse {
@@ -288,13 +435,34 @@ impl BlockResponse {
             block,
             precommits: precommits.into_iter().collect(),
             transactions: transactions.into_iter().collect(),
+            rejected_transactions: vec![],
         }
     }
 
+    /// Sets transactions rejected by the block proposer.
+    #[must_use]
+    pub fn with_rejected_transactions(
+        mut self,
+        rejected_transactions: impl IntoIterator<Item = RejectedTransaction>,
+    ) -> Self {
+        self.rejected_transactions = rejected_transactions.into_iter().collect();
+        self
+    }
+
     /// Verifies Merkle root of transactions in the block.
     pub fn verify_tx_hash(&self) -> bool {
         self.block.tx_hash == HashTag::hash_list(&self.transactions)
     }
+
+    /// Verifies the hash of rejected transactions recorded in the block.
+    pub fn verify_rejected_transactions_hash(&self) -> bool {
+        let expected_hash = if self.rejected_transactions.is_empty() {
+            None
+        } else {
+            Some(HashTag::hash_list(&self.rejected_transactions))
+        };
+        self.block.rejected_transactions_hash() == expected_hash
+    }
 }
 
 /// Information about the transactions.
@@ -594,6 +762,10 @@ pub enum ExonumMessage {
     BlockRequest(BlockRequest),
     /// Request of uncommitted transactions.
     PoolTransactionsRequest(PoolTransactionsRequest),
+    /// Digest of the execution of a block.
+    ExecutionDigest(ExecutionDigest),
+    /// Request for the primary validator node to step down.
+    StepDown(StepDown),
 }
 
 impl TryFrom<SignedMessage> for ExonumMessage {
@@ -617,5 +789,6 @@ impl_exonum_msg_try_from_signed! {
     ExonumMessage => Connect, Status,
     Propose, Prevote, TransactionsResponse,
     BlockResponse, ProposeRequest, TransactionsRequest,
-    PrevotesRequest, PeersRequest, BlockRequest, PoolTransactionsRequest
+    PrevotesRequest, PeersRequest, BlockRequest, PoolTransactionsRequest,
+    ExecutionDigest, StepDown
 }
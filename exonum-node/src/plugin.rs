@@ -14,8 +14,9 @@
 
 use exonum::{
     blockchain::{ApiSender, Blockchain, ValidatorKeys},
+    crypto::{Hash, PublicKey},
     helpers::Milliseconds,
-    merkledb::Snapshot,
+    merkledb::{metrics::DatabaseMetrics, Snapshot},
 };
 use exonum_api::ApiBuilder;
 
@@ -25,7 +26,13 @@ use std::{
     sync::{Arc, RwLock},
 };
 
-use crate::{events::ConnectedPeerAddr, state::State, ConnectInfo, ExternalMessage, NodeRole};
+use crate::{
+    events::{ConnectedPeerAddr, PeerTraffic, SharedTrafficStats},
+    memory::{MemoryAccountant, SubsystemMemory},
+    state::State,
+    tx_cache::SharedTxCache,
+    ConnectInfo, ConsensusRounds, ExternalMessage, NodeRole, StandbyStatus,
+};
 
 #[derive(Debug, Default)]
 struct ApiNodeState {
@@ -36,7 +43,9 @@ struct ApiNodeState {
     node_role: NodeRole,
     majority_count: usize,
     validators: Vec<ValidatorKeys>,
-    tx_cache_len: usize,
+    standby: Option<StandbyStatus>,
+    consensus_rounds: ConsensusRounds,
+    storage_metrics: Option<DatabaseMetrics>,
 }
 
 impl ApiNodeState {
@@ -55,6 +64,9 @@ impl ApiNodeState {
 #[derive(Clone, Debug)]
 pub struct SharedNodeState {
     node: Arc<RwLock<ApiNodeState>>,
+    traffic_stats: SharedTrafficStats,
+    tx_cache: SharedTxCache,
+    memory: MemoryAccountant,
     state_update_timeout: Milliseconds,
 }
 
@@ -63,6 +75,9 @@ impl SharedNodeState {
     pub fn new(state_update_timeout: Milliseconds) -> Self {
         Self {
             node: Arc::new(RwLock::new(ApiNodeState::new())),
+            traffic_stats: SharedTrafficStats::default(),
+            tx_cache: SharedTxCache::default(),
+            memory: MemoryAccountant::default(),
             state_update_timeout,
         }
     }
@@ -89,6 +104,26 @@ impl SharedNodeState {
             .collect()
     }
 
+    /// Returns traffic statistics of the connection with the specified peer,
+    /// or `None` if the peer is not connected.
+    pub fn peer_traffic(&self, peer: &PublicKey) -> Option<PeerTraffic> {
+        self.traffic_stats.get(peer)
+    }
+
+    pub(crate) fn traffic_stats(&self) -> SharedTrafficStats {
+        self.traffic_stats.clone()
+    }
+
+    /// Returns approximate memory usage of the node subsystems together with
+    /// their memory budgets.
+    pub fn memory_usage(&self) -> Vec<SubsystemMemory> {
+        self.memory.usage()
+    }
+
+    pub(crate) fn memory(&self) -> MemoryAccountant {
+        self.memory.clone()
+    }
+
     /// Returns a boolean value which indicates whether the consensus is achieved.
     pub fn consensus_status(&self) -> bool {
         let lock = self.node.read().expect("Expected read lock.");
@@ -130,7 +165,8 @@ impl SharedNodeState {
         lock.majority_count = state.majority_count();
         lock.node_role = NodeRole::new(state.validator_id());
         lock.validators = state.validators().to_vec();
-        lock.tx_cache_len = state.tx_cache_len();
+        lock.standby = state.standby_status();
+        lock.consensus_rounds = state.consensus_rounds();
 
         for (public_key, addr) in state.connections() {
             match addr {
@@ -164,15 +200,59 @@ impl SharedNodeState {
         node.node_role = role;
     }
 
+    /// Returns the status of the standby mode, or `None` if the node does not run
+    /// in this mode.
+    pub fn standby_status(&self) -> Option<StandbyStatus> {
+        let state = self.node.read().expect("Expected read lock.");
+        state.standby
+    }
+
+    pub(crate) fn set_standby_status(&self, status: Option<StandbyStatus>) {
+        let mut node = self.node.write().expect("Expected write lock.");
+        node.standby = status;
+    }
+
+    /// Returns information about consensus rounds within the current epoch of the node.
+    /// The information is updated together with other parts of the node state,
+    /// i.e., once per `state_update_timeout`.
+    pub fn consensus_rounds(&self) -> ConsensusRounds {
+        let state = self.node.read().expect("Expected read lock.");
+        state.consensus_rounds.clone()
+    }
+
+    /// Returns the handle to the storage metrics of the node, or `None` if the node
+    /// does not publish them.
+    pub fn storage_metrics(&self) -> Option<DatabaseMetrics> {
+        let state = self.node.read().expect("Expected read lock.");
+        state.storage_metrics.clone()
+    }
+
+    pub(crate) fn set_storage_metrics(&self, metrics: DatabaseMetrics) {
+        let mut node = self.node.write().expect("Expected write lock.");
+        node.storage_metrics = Some(metrics);
+    }
+
     /// Returns the value of the `state_update_timeout`.
     pub fn state_update_timeout(&self) -> Milliseconds {
         self.state_update_timeout
     }
 
-    /// Returns the current size of transaction cache.
+    /// Returns the current size of transaction cache. Unlike other parts of the node state,
+    /// the size is always up to date and is read without locking.
     pub fn tx_cache_size(&self) -> usize {
-        let state = self.node.read().expect("Expected read lock");
-        state.tx_cache_len
+        self.tx_cache.len()
+    }
+
+    /// Checks whether a transaction with the specified hash is in the ephemeral transaction
+    /// cache of the node, i.e., is received by the node, but is not yet flushed
+    /// into the persistent pool. Unlike other parts of the node state, the result is always
+    /// up to date.
+    pub fn is_tx_cached(&self, tx_hash: &Hash) -> bool {
+        self.tx_cache.contains(tx_hash)
+    }
+
+    pub(crate) fn tx_cache(&self) -> SharedTxCache {
+        self.tx_cache.clone()
     }
 }
 
@@ -61,9 +61,13 @@ use exonum::{
     helpers::{Height, Round},
     merkledb::Snapshot,
     messages::{AnyTx, Verified},
+    runtime::InstanceId,
 };
 
-use std::{collections::BTreeMap, fmt};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
 
 use crate::State;
 
@@ -123,7 +127,8 @@ pub enum ProposeTemplate {
     ///
     /// - Transactions with the specified hashes are known to the node
     /// - Transaction hashes do not repeat
-    /// - The amount of hashes is not higher than the constraints in the `ConsensusConfig`
+    /// - The amount of hashes is not higher than the constraints in the `ConsensusConfig`,
+    ///   including per-service quotas (see [`ServiceTxQuotas`])
     /// - Transactions with the specified hashes are correct (i.e., pass `Blockchain::check_tx`).
     Ordinary {
         /// Hashes of the transactions in the proposal.
@@ -143,6 +148,46 @@ impl ProposeTemplate {
     }
 }
 
+/// Tracker of per-service transaction quotas within a block, which are specified
+/// by the `service_tx_quotas` field of the consensus configuration.
+///
+/// [`ManagePool`] implementations should use the tracker to ensure that proposed blocks
+/// satisfy the quotas; otherwise, other validators will not vote for the proposals.
+///
+/// [`ManagePool`]: trait.ManagePool.html
+#[derive(Debug)]
+pub struct ServiceTxQuotas<'a> {
+    config: &'a ConsensusConfig,
+    tx_counts: HashMap<InstanceId, u32>,
+}
+
+impl<'a> ServiceTxQuotas<'a> {
+    /// Creates a tracker for an empty block.
+    pub fn new(config: &'a ConsensusConfig) -> Self {
+        Self {
+            config,
+            tx_counts: HashMap::new(),
+        }
+    }
+
+    /// Accounts for a transaction in the block. Returns `false` if the quota of the service
+    /// targeted by the transaction is exhausted; in this case, the transaction is not accounted.
+    pub fn try_add(&mut self, transaction: &AnyTx) -> bool {
+        let instance_id = transaction.call_info.instance_id;
+        let limit = match self.config.service_tx_limit(instance_id) {
+            Some(limit) => limit,
+            None => return true,
+        };
+        let count = self.tx_counts.entry(instance_id).or_default();
+        if *count < limit {
+            *count += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
 /// Transaction pool management, responsible for proposing new blocks and garbage-collecting
 /// transactions in the memory pool.
 ///
@@ -251,6 +296,7 @@ impl ManagePool for StandardPoolManager {
         let max_transactions = params.consensus_config.txs_block_limit;
         let snapshot = params.snapshot();
         let mut cache = TxCheckCache::new();
+        let mut quotas = ServiceTxQuotas::new(&params.consensus_config);
 
         let tx_hashes = pool
             .transactions()
@@ -258,7 +304,9 @@ impl ManagePool for StandardPoolManager {
                 // TODO: this is wildly inefficient.
                 // It should be easy to cache tx status within single height; however,
                 // spanning cache across multiple heights would be significantly harder.
-                if Blockchain::check_tx_with_cache(snapshot, tx.as_ref(), &mut cache).is_ok() {
+                if Blockchain::check_tx_with_cache(snapshot, tx.as_ref(), &mut cache).is_ok()
+                    && quotas.try_add(tx.payload())
+                {
                     Some(tx_hash)
                 } else {
                     None
@@ -0,0 +1,313 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read replicas of the node database.
+//!
+//! If the node is configured with a [`ReplicationConfig`], all patches merged into its database
+//! are streamed to the connected replicas via a dedicated TCP endpoint. A replica keeps
+//! a copy of the node database up to date by applying the streamed patches; thus, it can serve
+//! read requests (e.g., via HTTP APIs backed by a [`Blockchain`] instance over the replica
+//! database) without loading the node itself.
+//!
+//! The replication protocol is as follows. After connecting, the replica sends the sequence
+//! number of the last patch applied to its database as a little-endian `u64`. The node responds
+//! with the stream of subsequent patches; each patch is prefixed by the length of its
+//! serialization as a little-endian `u32`. If the requested patches are not available,
+//! the node closes the connection. The replica side of the protocol is implemented
+//! by [`replicate_from`].
+//!
+//! Replicas are not authenticated, so the node only accepts replica connections
+//! on a loopback address; remote replicas should connect via a secure tunnel
+//! (e.g., an SSH port forwarding). The number of concurrently served replicas is limited
+//! by [`ReplicationConfig::max_replicas`]; excess connections are closed right away.
+//! Errors of the replication server are logged and never stop the node.
+//!
+//! [`ReplicationConfig`]: struct.ReplicationConfig.html
+//! [`Blockchain`]: https://docs.rs/exonum/latest/exonum/blockchain/struct.Blockchain.html
+//! [`replicate_from`]: fn.replicate_from.html
+//! [`ReplicationConfig::max_replicas`]: struct.ReplicationConfig.html#structfield.max_replicas
+
+use anyhow::ensure;
+use exonum::merkledb::{
+    replication::{replication_seq, ReplicatedPatch, ReplicationLog},
+    BinaryValue, Database,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{net::TcpListener, time::sleep};
+
+use std::{
+    convert::TryFrom,
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Delay before accepting the next connection after an accept error, so that
+/// persistent errors (e.g., exhausted file descriptors) do not result in a busy loop.
+const ACCEPT_ERROR_DELAY: Duration = Duration::from_millis(500);
+
+/// Configuration of patch streaming to read replicas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ReplicationConfig {
+    /// Address to listen to replica connections on. Must be a loopback address.
+    pub listen_address: SocketAddr,
+    /// Maximum number of recent patches retained by the node. A replica lagging behind
+    /// by more patches needs to be re-seeded with a copy of the node database.
+    pub backlog_size: usize,
+    /// Maximum number of replicas served concurrently.
+    #[serde(default = "ReplicationConfig::default_max_replicas")]
+    pub max_replicas: usize,
+}
+
+impl ReplicationConfig {
+    /// Default number of retained patches.
+    pub const DEFAULT_BACKLOG_SIZE: usize = 1_024;
+    /// Default maximum number of concurrently served replicas.
+    pub const DEFAULT_MAX_REPLICAS: usize = 4;
+
+    /// Creates a replication configuration with the specified listen address,
+    /// the default backlog size and the default maximum number of replicas.
+    pub fn new(listen_address: SocketAddr) -> Self {
+        Self {
+            listen_address,
+            backlog_size: Self::DEFAULT_BACKLOG_SIZE,
+            max_replicas: Self::DEFAULT_MAX_REPLICAS,
+        }
+    }
+
+    /// Sets the backlog size.
+    #[must_use]
+    pub fn with_backlog_size(mut self, backlog_size: usize) -> Self {
+        self.backlog_size = backlog_size;
+        self
+    }
+
+    /// Sets the maximum number of concurrently served replicas.
+    #[must_use]
+    pub fn with_max_replicas(mut self, max_replicas: usize) -> Self {
+        self.max_replicas = max_replicas;
+        self
+    }
+
+    const fn default_max_replicas() -> usize {
+        Self::DEFAULT_MAX_REPLICAS
+    }
+
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.listen_address.ip().is_loopback(),
+            "`replication.listen_address` must be a loopback address, since replicas \
+             are not authenticated"
+        );
+        ensure!(
+            self.backlog_size > 0,
+            "`replication.backlog_size` must be strictly larger than 0"
+        );
+        ensure!(
+            self.max_replicas > 0,
+            "`replication.max_replicas` must be strictly larger than 0"
+        );
+        Ok(())
+    }
+}
+
+/// Server streaming patches from the node database to replicas.
+#[derive(Debug)]
+pub(crate) struct ReplicationServer {
+    listen_address: SocketAddr,
+    max_replicas: usize,
+    log: ReplicationLog,
+}
+
+/// Slot of a served replica, which is released once the replica is disconnected.
+#[derive(Debug)]
+struct ReplicaSlot(Arc<AtomicUsize>);
+
+impl ReplicaSlot {
+    fn acquire(active_replicas: &Arc<AtomicUsize>, max_replicas: usize) -> Option<Self> {
+        let prev_count = active_replicas.fetch_add(1, Ordering::SeqCst);
+        let slot = Self(Arc::clone(active_replicas));
+        if prev_count < max_replicas {
+            Some(slot)
+        } else {
+            None // The slot is released on drop.
+        }
+    }
+}
+
+impl Drop for ReplicaSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl ReplicationServer {
+    pub fn new(config: &ReplicationConfig, log: ReplicationLog) -> Self {
+        Self {
+            listen_address: config.listen_address,
+            max_replicas: config.max_replicas,
+            log,
+        }
+    }
+
+    /// Accepts replica connections. Each connection is served in a separate thread,
+    /// since waiting for new patches is blocking.
+    ///
+    /// The returned future never completes; errors are logged, so that replication
+    /// cannot stop the node.
+    pub async fn run(self) {
+        let listener = match TcpListener::bind(self.listen_address).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                log::error!(
+                    "Cannot listen to replica connections on {}: {}",
+                    self.listen_address,
+                    err
+                );
+                return futures::future::pending().await;
+            }
+        };
+
+        let active_replicas = Arc::new(AtomicUsize::new(0));
+        loop {
+            let (stream, address) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(err) => {
+                    log::warn!("Error accepting replica connection: {}", err);
+                    sleep(ACCEPT_ERROR_DELAY).await;
+                    continue;
+                }
+            };
+            let slot = match ReplicaSlot::acquire(&active_replicas, self.max_replicas) {
+                Some(slot) => slot,
+                None => {
+                    log::warn!(
+                        "Rejected replica connection from {}: {} replicas are already served",
+                        address,
+                        self.max_replicas
+                    );
+                    continue;
+                }
+            };
+            let stream = match stream.into_std() {
+                Ok(stream) => stream,
+                Err(err) => {
+                    log::warn!("Error setting up replica connection {}: {}", address, err);
+                    continue;
+                }
+            };
+
+            let log = self.log.clone();
+            thread::spawn(move || {
+                let _slot = slot;
+                if let Err(err) = serve_replica(stream, log) {
+                    log::warn!("Error streaming patches to replica {}: {}", address, err);
+                }
+            });
+        }
+    }
+}
+
+fn serve_replica(mut stream: TcpStream, log: ReplicationLog) -> anyhow::Result<()> {
+    stream.set_nonblocking(false)?;
+    let mut seq_bytes = [0_u8; 8];
+    stream.read_exact(&mut seq_bytes)?;
+    let last_seq = u64::from_le_bytes(seq_bytes);
+
+    let subscription = log.subscribe(last_seq)?;
+    // Do not retain the log, so that the subscription ends once the node database is dropped.
+    drop(log);
+    for patch in subscription {
+        let bytes = patch.to_bytes();
+        let len = u32::try_from(bytes.len())?;
+        stream.write_all(&len.to_le_bytes())?;
+        stream.write_all(&bytes)?;
+    }
+    Ok(())
+}
+
+/// Connects to the node at `primary_address` and applies the streamed patches to the replica
+/// database. The function blocks until the connection is closed by the node, returning
+/// `Ok(())` in this case; it should be called in a dedicated thread and may be called
+/// repeatedly to reconnect.
+///
+/// # Errors
+///
+/// Returns an error if the connection fails or if a streamed patch cannot be applied
+/// to the database. If the node closes the connection right away, the replica may have fallen
+/// behind the patch backlog of the node; in this case, the replica database needs to be
+/// re-seeded with a copy of the node database.
+pub fn replicate_from(primary_address: SocketAddr, db: &dyn Database) -> anyhow::Result<()> {
+    let mut stream = TcpStream::connect(primary_address)?;
+    let last_seq = replication_seq(&db.snapshot());
+    stream.write_all(&last_seq.to_le_bytes())?;
+
+    loop {
+        let mut len_bytes = [0_u8; 4];
+        match stream.read_exact(&mut len_bytes) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(err) => return Err(err.into()),
+        }
+        let mut bytes = vec![0_u8; u32::from_le_bytes(len_bytes) as usize];
+        stream.read_exact(&mut bytes)?;
+        let patch = ReplicatedPatch::from_bytes(bytes.into())?;
+        patch.apply(db)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn replication_config_validation() {
+        let local_address = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 8_000);
+        ReplicationConfig::new(local_address).validate().unwrap();
+
+        let public_address = SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 8_000);
+        let err = ReplicationConfig::new(public_address)
+            .validate()
+            .unwrap_err();
+        assert!(err.to_string().contains("loopback"));
+
+        let err = ReplicationConfig::new(local_address)
+            .with_max_replicas(0)
+            .validate()
+            .unwrap_err();
+        assert!(err.to_string().contains("max_replicas"));
+    }
+
+    #[test]
+    fn replica_slots_are_limited() {
+        let active_replicas = Arc::new(AtomicUsize::new(0));
+        let first = ReplicaSlot::acquire(&active_replicas, 2).unwrap();
+        let _second = ReplicaSlot::acquire(&active_replicas, 2).unwrap();
+        assert!(ReplicaSlot::acquire(&active_replicas, 2).is_none());
+        assert_eq!(active_replicas.load(Ordering::SeqCst), 2);
+
+        drop(first);
+        assert_eq!(active_replicas.load(Ordering::SeqCst), 1);
+        assert!(ReplicaSlot::acquire(&active_replicas, 2).is_some());
+        assert_eq!(active_replicas.load(Ordering::SeqCst), 1);
+    }
+}
@@ -167,23 +167,31 @@ impl NodeHandler {
             if let Some(epoch) = msg.payload().epoch() {
                 if self.state.epoch() >= epoch {
                     if let Some(proof) = schema.block_skip_and_precommits() {
-                        proof_and_transactions = Some((proof, vec![]));
+                        proof_and_transactions = Some((proof, vec![], vec![]));
                     }
                 }
             }
-        } else {
-            let proof = schema.block_and_precommits(height).unwrap();
+        } else if height >= schema.history_retained_from() {
+            let proof = self
+                .blockchain
+                .as_ref()
+                .proof_cache()
+                .block_proof(snapshot.as_ref(), height)
+                .unwrap();
             let transactions = schema.block_transactions(height).iter().collect();
-            proof_and_transactions = Some((proof, transactions));
+            let rejected_transactions = schema.rejected_transactions(height).iter().collect();
+            proof_and_transactions = Some((proof, transactions, rejected_transactions));
         };
 
-        if let Some((proof, transactions)) = proof_and_transactions {
-            let block_msg = self.sign_message(BlockResponse::new(
+        if let Some((proof, transactions, rejected_transactions)) = proof_and_transactions {
+            let block_response = BlockResponse::new(
                 msg.author(),
                 proof.block,
                 proof.precommits.iter().map(BinaryValue::to_bytes),
                 transactions,
-            ));
+            )
+            .with_rejected_transactions(rejected_transactions);
+            let block_msg = self.sign_message(block_response);
             self.send_to_peer(msg.author(), block_msg);
         }
     }
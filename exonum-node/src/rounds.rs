@@ -0,0 +1,157 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Introspection of consensus rounds within the current epoch.
+//!
+//! Information collected here is intended for debugging and visualizing consensus,
+//! e.g., to find out why the network is stuck in a certain epoch. It reflects
+//! the local view of the node and is reset each time the node enters a new epoch.
+
+use exonum::{
+    crypto::Hash,
+    helpers::{Height, Round, ValidatorId},
+};
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+/// Kind of a consensus timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ConsensusTimeout {
+    /// Round timeout, after which the node moves to the next round.
+    Round,
+    /// Propose timeout, after which the round leader creates a proposal.
+    Propose,
+}
+
+/// Proposal seen by the node in a certain round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ProposalInfo {
+    /// Hash of the proposal.
+    pub hash: Hash,
+    /// Validator which has created the proposal.
+    pub proposer: ValidatorId,
+    /// Number of transactions in the proposal.
+    pub tx_count: usize,
+    /// Number of proposal transactions unknown to the node.
+    pub unknown_tx_count: usize,
+    /// Is the proposal a skip proposal?
+    pub skip: bool,
+    /// Hash of the block obtained by executing the proposal, if the proposal was executed.
+    pub block_hash: Option<Hash>,
+}
+
+/// Votes for a certain object (a proposal for prevotes, or a block for precommits)
+/// in a round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct VotesInfo {
+    /// Hash of the object the votes are given for.
+    pub hash: Hash,
+    /// Validators which have sent the votes, in the ascending order.
+    pub validators: Vec<ValidatorId>,
+}
+
+impl VotesInfo {
+    pub(crate) fn new(hash: Hash, validators: impl IntoIterator<Item = ValidatorId>) -> Self {
+        let mut validators: Vec<_> = validators.into_iter().collect();
+        validators.sort();
+        Self { hash, validators }
+    }
+
+    /// Returns the number of votes.
+    pub fn count(&self) -> usize {
+        self.validators.len()
+    }
+}
+
+/// Information about a single consensus round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RoundInfo {
+    /// Round number.
+    pub round: Round,
+    /// Leader of the round.
+    pub leader: ValidatorId,
+    /// Proposals seen by the node.
+    pub proposals: Vec<ProposalInfo>,
+    /// Prevotes grouped by the proposal hash.
+    pub prevotes: Vec<VotesInfo>,
+    /// Precommits grouped by the block hash.
+    pub precommits: Vec<VotesInfo>,
+    /// Timeouts fired by the node in this round.
+    pub timeouts: Vec<ConsensusTimeout>,
+}
+
+impl RoundInfo {
+    pub(crate) fn new(round: Round, leader: ValidatorId) -> Self {
+        Self {
+            round,
+            leader,
+            proposals: Vec::new(),
+            prevotes: Vec::new(),
+            precommits: Vec::new(),
+            timeouts: Vec::new(),
+        }
+    }
+}
+
+/// Snapshot of consensus rounds within the current epoch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ConsensusRounds {
+    /// Current epoch of the node.
+    pub epoch: Height,
+    /// Height of the latest committed block.
+    pub blockchain_height: Height,
+    /// Current round of the node.
+    pub round: Round,
+    /// Round in which the node has locked on a proposal, or zero if the node is not locked.
+    pub locked_round: Round,
+    /// Hash of the proposal on which the node has locked.
+    pub locked_propose: Option<Hash>,
+    /// Number of votes constituting a majority.
+    pub majority_count: usize,
+    /// Latest rounds known to be reached by other validators.
+    pub validators_rounds: BTreeMap<ValidatorId, Round>,
+    /// Information about rounds, in the ascending order. Only rounds with
+    /// any recorded activity and the current round are included.
+    pub rounds: Vec<RoundInfo>,
+}
+
+impl Default for ConsensusRounds {
+    fn default() -> Self {
+        Self {
+            epoch: Height(0),
+            blockchain_height: Height(0),
+            round: Round::zero(),
+            locked_round: Round::zero(),
+            locked_propose: None,
+            majority_count: 0,
+            validators_rounds: BTreeMap::new(),
+            rounds: Vec::new(),
+        }
+    }
+}
+
+impl ConsensusRounds {
+    /// Returns information about the specified round, if it is present in the snapshot.
+    pub fn round_info(&self, round: Round) -> Option<&RoundInfo> {
+        self.rounds.iter().find(|info| info.round == round)
+    }
+}
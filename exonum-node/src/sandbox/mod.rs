@@ -509,6 +509,10 @@ impl Sandbox {
         Ref::map(self.inner.borrow(), |inner| inner.handler.state())
     }
 
+    pub(crate) fn api_state(&self) -> SharedNodeState {
+        self.inner.borrow().handler.api_state.clone()
+    }
+
     pub fn blockchain(&self) -> Blockchain {
         self.inner.borrow().handler.blockchain.as_ref().clone()
     }
@@ -762,10 +766,9 @@ impl Sandbox {
     }
 
     pub fn create_block(&self, txs: &[Verified<AnyTx>]) -> Block {
-        let tx_hashes: Vec<_> = txs.iter().map(ObjectHash::object_hash).collect();
         let (state_hash, error_hash) = self.compute_block_hashes(txs);
         BlockBuilder::new(self)
-            .with_txs_hashes(&tx_hashes)
+            .with_transactions(txs)
             .with_state_hash(&state_hash)
             .with_error_hash(&error_hash)
             .build()
@@ -882,6 +885,7 @@ impl Sandbox {
 
     pub fn assert_tx_cache_len(&self, expected: u64) {
         assert_eq!(expected, self.node_state().tx_cache_len() as u64);
+        assert_eq!(expected, self.api_state().tx_cache_size() as u64);
     }
 
     pub fn assert_lock(&self, expected_round: Round, expected_hash: Option<Hash>) {
@@ -958,6 +962,8 @@ impl Sandbox {
             peer_discovery: Vec::new(),
             mempool: MemoryPoolConfig::default(),
             keys,
+            standby: None,
+            bootstrap: None,
         };
 
         let shared_time = SharedTime::new(Mutex::new(time));
@@ -1234,6 +1240,8 @@ fn sandbox_with_services_uninitialized(
         peer_discovery: Vec::new(),
         mempool: MemoryPoolConfig::default(),
         keys: keys[0].clone(),
+        standby: None,
+        bootstrap: None,
     };
 
     let system_state = SandboxSystemStateProvider {
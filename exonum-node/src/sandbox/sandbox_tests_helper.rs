@@ -16,7 +16,7 @@
 
 use bit_vec::BitVec;
 use exonum::{
-    blockchain::{AdditionalHeaders, Block, Epoch, ProposerId},
+    blockchain::{AdditionalHeaders, Block, BlockStats, Epoch, ProposerId},
     crypto::Hash,
     helpers::{Height, Round, ValidatorId},
     merkledb::{access::CopyAccessExt, Database, HashTag, ObjectHash, TemporaryDB},
@@ -50,6 +50,7 @@ pub struct BlockBuilder<'a> {
     state_hash: Option<Hash>,
     error_hash: Option<Hash>,
     tx_count: Option<u32>,
+    stats: Option<BlockStats>,
     entries: Option<AdditionalHeaders>,
 
     sandbox: &'a TimestampingSandbox,
@@ -65,6 +66,7 @@ impl<'a> BlockBuilder<'a> {
             state_hash: None,
             error_hash: None,
             tx_count: None,
+            stats: None,
             entries: None,
             sandbox,
         }
@@ -89,6 +91,18 @@ impl<'a> BlockBuilder<'a> {
         self
     }
 
+    pub fn with_transactions(mut self, txs: &[Verified<AnyTx>]) -> Self {
+        let tx_hashes: Vec<_> = txs.iter().map(ObjectHash::object_hash).collect();
+        if !txs.is_empty() {
+            let mut stats = BlockStats::new();
+            for tx in txs {
+                stats.record_transaction(tx);
+            }
+            self.stats = Some(stats);
+        }
+        self.with_txs_hashes(&tx_hashes)
+    }
+
     pub fn with_state_hash(mut self, state_hash: &'a Hash) -> Self {
         self.state_hash = Some(*state_hash);
         self
@@ -107,6 +121,9 @@ impl<'a> BlockBuilder<'a> {
         let mut additional_headers = self.entries.clone().unwrap_or_else(AdditionalHeaders::new);
         additional_headers.insert::<ProposerId>(proposer_id);
         additional_headers.insert::<Epoch>(self.sandbox.current_epoch());
+        if let Some(stats) = self.stats.clone() {
+            additional_headers.insert::<BlockStats>(stats);
+        }
 
         Block {
             height: self.height.unwrap_or_else(|| self.sandbox.current_epoch()),
@@ -371,7 +388,7 @@ where
 
             let (state_hash, error_hash) = sandbox.compute_block_hashes(&raw_txs);
             let block = BlockBuilder::new(sandbox)
-                .with_txs_hashes(&hashes)
+                .with_transactions(&raw_txs)
                 .with_state_hash(&state_hash)
                 .with_error_hash(&error_hash)
                 .build();
@@ -473,7 +490,7 @@ pub fn add_one_height_with_transactions_from_other_validator(
             trace!("last_block: {:?}", sandbox.last_block());
             let (state_hash, error_hash) = sandbox.compute_block_hashes(&raw_txs);
             let block = BlockBuilder::new(sandbox)
-                .with_txs_hashes(&hashes)
+                .with_transactions(&raw_txs)
                 .with_state_hash(&state_hash)
                 .with_error_hash(&error_hash)
                 .build();
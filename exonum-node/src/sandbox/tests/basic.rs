@@ -19,7 +19,7 @@
 use exonum::{
     crypto::{Hash, KeyPair, Seed, HASH_SIZE, SEED_LENGTH},
     helpers::{Height, Round, ValidatorId},
-    merkledb::ObjectHash,
+    merkledb::{BinaryValue, ObjectHash},
     messages::{Precommit, Verified},
     runtime::SnapshotExt,
 };
@@ -27,13 +27,17 @@ use rand::{thread_rng, Rng};
 
 use std::collections::BTreeMap;
 
-use crate::sandbox::{
-    sandbox_tests_helper::{
-        add_one_height, add_one_height_with_transactions, add_round_with_transactions,
-        gen_timestamping_tx, BlockBuilder, SandboxState, NOT_LOCKED,
+use crate::{
+    messages::{Propose, StepDown},
+    sandbox::{
+        sandbox_tests_helper::{
+            add_one_height, add_one_height_with_transactions, add_round_with_transactions,
+            gen_timestamping_tx, make_prevote_from_propose, BlockBuilder, SandboxState, NOT_LOCKED,
+        },
+        timestamping::{TimestampingTxGenerator, DATA_SIZE},
+        timestamping_sandbox, timestamping_sandbox_builder, Sandbox,
     },
-    timestamping::{TimestampingTxGenerator, DATA_SIZE},
-    timestamping_sandbox, timestamping_sandbox_builder, Sandbox,
+    standby::unix_time,
 };
 
 /// idea of the test is to verify that at certain periodic rounds we (`validator_0`) become a leader
@@ -311,3 +315,95 @@ fn tx_cache_with_tx_block_limit() {
 
     //TODO: check pool after commit.
 }
+
+/// Idea of the test is to check that precommits for the previous block included into a propose
+/// are validated if the block time is enabled.
+#[test]
+fn propose_with_last_precommits() {
+    let sandbox = timestamping_sandbox_builder()
+        .with_consensus(|config| {
+            config.block_time = true;
+        })
+        .build();
+    let sandbox_state = SandboxState::new();
+    add_one_height(&sandbox, &sandbox_state);
+
+    let snapshot = sandbox.blockchain().snapshot();
+    let last_precommits: Vec<_> = snapshot
+        .for_core()
+        .precommits(&sandbox.last_hash())
+        .iter()
+        .map(|precommit| precommit.to_bytes())
+        .collect();
+    assert!(last_precommits.len() >= Sandbox::majority_count(sandbox.validators().len()));
+
+    // Precommits do not form a majority.
+    let propose = Propose::new(
+        ValidatorId(3),
+        Height(2),
+        Round(1),
+        sandbox.last_hash(),
+        vec![],
+    )
+    .with_last_precommits(last_precommits[..1].to_vec());
+    let propose = Verified::from_value(
+        propose,
+        sandbox.public_key(ValidatorId(3)),
+        sandbox.secret_key(ValidatorId(3)),
+    );
+    // The node does not send a prevote for the propose; otherwise, the sandbox
+    // will panic on drop.
+    sandbox.recv(&propose);
+
+    let propose = Propose::new(
+        ValidatorId(3),
+        Height(2),
+        Round(1),
+        sandbox.last_hash(),
+        vec![],
+    )
+    .with_last_precommits(last_precommits);
+    let propose = Verified::from_value(
+        propose,
+        sandbox.public_key(ValidatorId(3)),
+        sandbox.secret_key(ValidatorId(3)),
+    );
+    sandbox.recv(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+}
+
+#[test]
+fn test_step_down() {
+    let sandbox = timestamping_sandbox();
+    let expires_at = unix_time(sandbox.time()) + 60;
+
+    // `StepDown` from another validator is relayed to it.
+    let step_down = Verified::from_value(
+        StepDown::new(Height(1), expires_at),
+        sandbox.public_key(ValidatorId(1)),
+        sandbox.secret_key(ValidatorId(1)),
+    );
+    sandbox.recv(&step_down);
+    sandbox.send(sandbox.public_key(ValidatorId(1)), &step_down);
+    assert!(sandbox.node_state().is_validator());
+
+    // Expired `StepDown` is ignored.
+    let step_down = Verified::from_value(
+        StepDown::new(Height(1), unix_time(sandbox.time()) - 1),
+        sandbox.public_key(ValidatorId(0)),
+        sandbox.secret_key(ValidatorId(0)),
+    );
+    sandbox.recv(&step_down);
+    assert!(sandbox.node_state().is_validator());
+
+    // The node switches to the standby mode on `StepDown` signed with its consensus key.
+    let step_down = Verified::from_value(
+        StepDown::new(Height(1), expires_at),
+        sandbox.public_key(ValidatorId(0)),
+        sandbox.secret_key(ValidatorId(0)),
+    );
+    sandbox.recv(&step_down);
+    assert!(!sandbox.node_state().is_validator());
+    let status = sandbox.api_state().standby_status().unwrap();
+    assert_eq!(status.last_primary_activity, sandbox.time());
+}
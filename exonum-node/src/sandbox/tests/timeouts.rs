@@ -23,6 +23,7 @@ use exonum::{
 use std::time::Duration;
 
 use crate::{
+    rounds::ConsensusTimeout,
     sandbox::{
         sandbox_tests_helper::{
             add_round_with_transactions, gen_timestamping_tx, make_prevote_from_propose,
@@ -294,3 +295,49 @@ fn test_round_timeout_increase() {
     sandbox.add_time(Duration::from_millis(1));
     sandbox.assert_state(Height(1), Round(5));
 }
+
+/// Checks that consensus rounds introspection records proposals, votes and fired timeouts.
+#[test]
+fn consensus_rounds_record_votes_and_timeouts() {
+    let sandbox = timestamping_sandbox();
+
+    let propose = ProposeBuilder::new(&sandbox).build();
+    sandbox.recv(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+    sandbox.recv(&sandbox.create_prevote(
+        ValidatorId(1),
+        Height(1),
+        Round(1),
+        propose.object_hash(),
+        NOT_LOCKED,
+        sandbox.secret_key(ValidatorId(1)),
+    ));
+
+    let rounds = sandbox.node_state().consensus_rounds();
+    assert_eq!(rounds.epoch, Height(1));
+    assert_eq!(rounds.round, Round(1));
+    let round_info = rounds.round_info(Round(1)).unwrap();
+    assert_eq!(round_info.leader, propose.payload().validator);
+    assert_eq!(round_info.proposals.len(), 1);
+    assert_eq!(round_info.proposals[0].hash, propose.object_hash());
+    assert_eq!(
+        round_info.proposals[0].proposer,
+        propose.payload().validator
+    );
+    assert_eq!(round_info.prevotes.len(), 1);
+    assert_eq!(
+        round_info.prevotes[0].validators,
+        vec![ValidatorId(0), ValidatorId(1)]
+    );
+    assert!(round_info.precommits.is_empty());
+    assert!(round_info.timeouts.is_empty());
+
+    sandbox.add_time(Duration::from_millis(sandbox.current_round_timeout()));
+    sandbox.assert_state(Height(1), Round(2));
+
+    let rounds = sandbox.node_state().consensus_rounds();
+    assert_eq!(rounds.round, Round(2));
+    let round_info = rounds.round_info(Round(1)).unwrap();
+    assert_eq!(round_info.timeouts, vec![ConsensusTimeout::Round]);
+    assert!(rounds.round_info(Round(2)).unwrap().timeouts.is_empty());
+}
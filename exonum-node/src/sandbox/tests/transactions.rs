@@ -16,7 +16,7 @@
 
 use bit_vec::BitVec;
 use exonum::{
-    blockchain::{Blockchain, ProposerId, TransactionCache},
+    blockchain::{Blockchain, ProposerId, ServiceTxQuota, TransactionCache},
     crypto::{Hash, KeyPair, PublicKey},
     helpers::{Height, Round, ValidatorId},
     merkledb::{BinaryValue, ObjectHash, Snapshot},
@@ -199,6 +199,9 @@ fn tx_pool_size_overflow() {
 
     // The first tx should be committed; the second tx should remain in cache.
     sandbox.assert_tx_cache_len(1);
+    let api_state = sandbox.api_state();
+    assert!(api_state.is_tx_cached(&tx2.object_hash()));
+    assert!(!api_state.is_tx_cached(&propose.payload().transactions[0]));
     sandbox.assert_pool_len(0);
     sandbox.broadcast(&Sandbox::create_status(
         sandbox.public_key(ValidatorId(0)),
@@ -626,6 +629,61 @@ fn expedited_propose_on_transaction_pressure() {
     sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
 }
 
+#[test]
+fn propose_respects_service_tx_quotas() {
+    let sandbox = timestamping_sandbox_builder()
+        .with_consensus(|config| {
+            config.max_propose_timeout = MAX_PROPOSE_TIMEOUT;
+            config.min_propose_timeout = MIN_PROPOSE_TIMEOUT;
+            config.propose_timeout_threshold = PROPOSE_THRESHOLD;
+            config.service_tx_quotas = vec![ServiceTxQuota::new(TimestampingService::ID, 2)];
+        })
+        .build();
+    sandbox.add_time(Duration::from_millis(sandbox.current_round_timeout()));
+    sandbox.add_time(Duration::from_millis(sandbox.current_round_timeout()));
+
+    let transactions = TimestampingTxGenerator::new(64)
+        .take(PROPOSE_THRESHOLD as usize)
+        .collect::<Vec<_>>();
+    for tx in &transactions {
+        sandbox.recv(tx);
+    }
+    sandbox.add_time(Duration::from_millis(MIN_PROPOSE_TIMEOUT));
+
+    // Only two transactions fit into the quota of the service.
+    let propose = ProposeBuilder::new(&sandbox)
+        .with_tx_hashes(&tx_hashes(&transactions)[..2])
+        .build();
+    sandbox.broadcast(&propose);
+    sandbox.broadcast(&make_prevote_from_propose(&sandbox, &propose));
+}
+
+#[test]
+fn propose_exceeding_service_tx_quotas_is_not_prevoted() {
+    let sandbox = timestamping_sandbox_builder()
+        .with_consensus(|config| {
+            config.service_tx_quotas = vec![ServiceTxQuota::new(TimestampingService::ID, 2)];
+        })
+        .build();
+
+    let transactions = TimestampingTxGenerator::new(64).take(3).collect::<Vec<_>>();
+    for tx in &transactions {
+        sandbox.recv(tx);
+    }
+
+    let propose = sandbox.create_propose(
+        ValidatorId(2),
+        Height(1),
+        Round(1),
+        sandbox.last_hash(),
+        tx_hashes(&transactions),
+        sandbox.secret_key(ValidatorId(2)),
+    );
+    // The node does not send a prevote for the propose; otherwise, the sandbox
+    // will panic on drop.
+    sandbox.recv(&propose);
+}
+
 #[test]
 fn valid_txs_are_broadcast() {
     let sandbox = timestamping_sandbox();
@@ -0,0 +1,311 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Warm standby mode of validator nodes.
+//!
+//! A standby node is launched with the same keys as a validator node (the *primary*),
+//! but does not sign any consensus messages. Instead, it keeps in sync with the blockchain
+//! like an auditor node and watches for activity of the primary, that is, for consensus
+//! messages signed with the consensus key of the primary and for its precommits
+//! in the committed blocks.
+//!
+//! The standby node can be activated by a [`StandbyActivation`] request signed with
+//! the consensus key of the validator. To prevent both nodes from being active
+//! at the same time (which would lead to double signing), the activation is refused
+//! if the primary has been active within the last `fencing_timeout` milliseconds.
+//! Until any activity of the primary is observed, the start time of the standby node
+//! is used instead, so the node cannot be activated right after the start.
+//! Each request has an expiration time and cannot be replayed after it.
+//!
+//! On activation, the standby node broadcasts a `StepDown` message signed with
+//! the consensus key. Peers relay the message to the node with the same consensus key,
+//! so that it reaches the former primary even if the primary is not connected
+//! to the standby node directly. Having received the message, the primary switches
+//! to the standby mode itself.
+//!
+//! Note that peers identify nodes by their consensus keys; thus, while the primary is online,
+//! the standby node competes with it for peer connections. It is recommended to connect
+//! the standby node only to a subset of peers.
+//!
+//! [`StandbyActivation`]: struct.StandbyActivation.html
+
+use anyhow::{bail, ensure};
+use exonum::{
+    crypto::{self, PublicKey, SecretKey, Signature},
+    helpers::{Height, Milliseconds},
+};
+use serde::{Deserialize, Serialize};
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Configuration of a warm standby node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct StandbyConfig {
+    /// Minimum time (in milliseconds) since the latest observed activity of the primary
+    /// validator node required to activate the standby node.
+    pub fencing_timeout: Milliseconds,
+}
+
+impl Default for StandbyConfig {
+    fn default() -> Self {
+        Self {
+            fencing_timeout: 30_000,
+        }
+    }
+}
+
+impl StandbyConfig {
+    /// Creates a standby configuration with the specified fencing timeout.
+    pub fn new(fencing_timeout: Milliseconds) -> Self {
+        Self { fencing_timeout }
+    }
+}
+
+/// Status of a node running in the standby mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StandbyStatus {
+    /// Consensus key of the mirrored validator.
+    pub consensus_key: PublicKey,
+    /// Time of the latest observed activity of the primary node. If no activity was observed,
+    /// this is the time when the node has entered the standby mode.
+    pub last_primary_activity: SystemTime,
+    /// Minimum time since the latest activity of the primary node required for activation.
+    pub fencing_timeout: Milliseconds,
+}
+
+impl StandbyStatus {
+    /// Checks whether the standby node can be activated at the specified time.
+    pub fn check_fencing(&self, now: SystemTime) -> anyhow::Result<()> {
+        let elapsed = now
+            .duration_since(self.last_primary_activity)
+            .unwrap_or_default();
+        let fencing_timeout = Duration::from_millis(self.fencing_timeout);
+        ensure!(
+            elapsed >= fencing_timeout,
+            "Primary node was active {} ms ago, which is less than the fencing timeout ({} ms)",
+            elapsed.as_millis(),
+            self.fencing_timeout
+        );
+        Ok(())
+    }
+
+    /// Checks the activation request against this status and the current blockchain height.
+    pub fn check_activation(
+        &self,
+        request: &StandbyActivation,
+        blockchain_height: Height,
+        now: SystemTime,
+    ) -> anyhow::Result<()> {
+        if !request.verify(&self.consensus_key) {
+            bail!("Activation request is not signed with the consensus key of the validator");
+        }
+        ensure!(
+            !request.is_expired(now),
+            "Activation request has expired at {} (Unix time)",
+            request.expires_at
+        );
+        ensure!(
+            request.height >= blockchain_height,
+            "Activation request for height {} is stale (current blockchain height is {})",
+            request.height,
+            blockchain_height
+        );
+        self.check_fencing(now)
+    }
+}
+
+/// Request to activate a standby node, signed with the consensus key of the mirrored validator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct StandbyActivation {
+    /// Blockchain height at which the activation is requested. Requests with the height
+    /// lower than the height of the latest committed block are rejected as stale.
+    pub height: Height,
+    /// Unix time (in seconds) after which the request is rejected. It is recommended
+    /// to create requests valid for several minutes at most.
+    pub expires_at: u64,
+    /// Signature of the request.
+    pub signature: Signature,
+}
+
+impl StandbyActivation {
+    /// Domain separator for the signed activation data.
+    const DOMAIN: &'static [u8] = b"exonum-standby-activation";
+
+    /// Creates an activation request expiring at the specified time and signed
+    /// with the provided consensus secret key.
+    pub fn new(height: Height, expires_at: SystemTime, consensus_sk: &SecretKey) -> Self {
+        let expires_at = unix_time(expires_at);
+        let signature = crypto::sign(&Self::signed_data(height, expires_at), consensus_sk);
+        Self {
+            height,
+            expires_at,
+            signature,
+        }
+    }
+
+    /// Checks that the request is signed with the specified consensus key.
+    pub fn verify(&self, consensus_key: &PublicKey) -> bool {
+        crypto::verify(
+            &self.signature,
+            &Self::signed_data(self.height, self.expires_at),
+            consensus_key,
+        )
+    }
+
+    /// Checks whether the request has expired at the specified time.
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        unix_time(now) > self.expires_at
+    }
+
+    fn signed_data(height: Height, expires_at: u64) -> Vec<u8> {
+        let mut data = Self::DOMAIN.to_vec();
+        data.extend_from_slice(&height.0.to_le_bytes());
+        data.extend_from_slice(&expires_at.to_le_bytes());
+        data
+    }
+}
+
+/// Converts the time to the number of seconds since the Unix epoch.
+pub(crate) fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Standby state of the node.
+#[derive(Debug, Clone)]
+pub(crate) struct StandbyState {
+    config: StandbyConfig,
+    last_primary_activity: SystemTime,
+}
+
+impl StandbyState {
+    /// Creates the standby state for the node entering the standby mode at `start_time`.
+    /// The start time is treated as the latest activity of the primary node.
+    pub fn new(config: StandbyConfig, start_time: SystemTime) -> Self {
+        Self {
+            config,
+            last_primary_activity: start_time,
+        }
+    }
+
+    /// Records activity of the primary node at the specified time.
+    pub fn observe_primary_activity(&mut self, time: SystemTime) {
+        if self.last_primary_activity < time {
+            self.last_primary_activity = time;
+        }
+    }
+
+    pub fn status(&self, consensus_key: PublicKey) -> StandbyStatus {
+        StandbyStatus {
+            consensus_key,
+            last_primary_activity: self.last_primary_activity,
+            fencing_timeout: self.config.fencing_timeout,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum::crypto::KeyPair;
+
+    use super::*;
+
+    #[test]
+    fn activation_request_verification() {
+        let keys = KeyPair::random();
+        let start = SystemTime::now();
+        let status = StandbyState::new(StandbyConfig::default(), start).status(keys.public_key());
+        let now = start + Duration::from_secs(60);
+        let expires_at = now + Duration::from_secs(60);
+
+        let request = StandbyActivation::new(Height(5), expires_at, keys.secret_key());
+        status.check_activation(&request, Height(5), now).unwrap();
+
+        let err = status
+            .check_activation(&request, Height(6), now)
+            .unwrap_err();
+        assert!(err.to_string().contains("is stale"));
+
+        let other_keys = KeyPair::random();
+        let request = StandbyActivation::new(Height(5), expires_at, other_keys.secret_key());
+        let err = status
+            .check_activation(&request, Height(5), now)
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("not signed with the consensus key"));
+
+        // The expiration time is covered by the signature.
+        let mut request = StandbyActivation::new(Height(5), expires_at, keys.secret_key());
+        request.expires_at += 3_600;
+        let err = status
+            .check_activation(&request, Height(5), now)
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("not signed with the consensus key"));
+    }
+
+    #[test]
+    fn expired_activation_request() {
+        let keys = KeyPair::random();
+        let start = SystemTime::now();
+        let status = StandbyState::new(StandbyConfig::default(), start).status(keys.public_key());
+        let now = start + Duration::from_secs(60);
+
+        let request = StandbyActivation::new(Height(5), now, keys.secret_key());
+        status.check_activation(&request, Height(5), now).unwrap();
+        let err = status
+            .check_activation(&request, Height(5), now + Duration::from_secs(1))
+            .unwrap_err();
+        assert!(err.to_string().contains("has expired"));
+    }
+
+    #[test]
+    fn fencing_after_start() {
+        let keys = KeyPair::random();
+        let start = SystemTime::now();
+        let status = StandbyState::new(StandbyConfig::new(1_000), start).status(keys.public_key());
+        assert_eq!(status.last_primary_activity, start);
+
+        let err = status.check_fencing(start).unwrap_err();
+        assert!(err.to_string().contains("less than the fencing timeout"));
+        status
+            .check_fencing(start + Duration::from_millis(1_000))
+            .unwrap();
+    }
+
+    #[test]
+    fn fencing_after_primary_activity() {
+        let keys = KeyPair::random();
+        let start = SystemTime::now();
+        let mut state =
+            StandbyState::new(StandbyConfig::new(1_000), start - Duration::from_secs(5));
+        state.observe_primary_activity(start);
+        // Older activity does not affect the state.
+        state.observe_primary_activity(start - Duration::from_secs(10));
+
+        let status = state.status(keys.public_key());
+        let err = status
+            .check_fencing(start + Duration::from_millis(500))
+            .unwrap_err();
+        assert!(err.to_string().contains("less than the fencing timeout"));
+        status
+            .check_fencing(start + Duration::from_millis(1_000))
+            .unwrap();
+    }
+}
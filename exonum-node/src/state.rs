@@ -18,8 +18,8 @@ use anyhow::bail;
 use bit_vec::BitVec;
 use exonum::{
     blockchain::{
-        Block, BlockKind, BlockPatch, BlockchainMut, ConsensusConfig, PersistentPool,
-        TransactionCache, TxCheckCache, ValidatorKeys,
+        Block, BlockKind, BlockPatch, BlockchainMut, CallInBlock, ConsensusConfig, PersistentPool,
+        RejectedTransaction, TransactionCache, TxCheckCache, ValidatorKeys,
     },
     crypto::{Hash, PublicKey},
     helpers::{byzantine_quorum, Height, Milliseconds, Round, ValidatorId},
@@ -27,11 +27,12 @@ use exonum::{
     merkledb::{access::RawAccess, KeySetIndex, MapIndex, ObjectHash, Snapshot},
     messages::{AnyTx, Precommit, Verified},
 };
-use log::{error, trace};
+use log::{error, info, trace};
 
 use std::{
     cmp::Reverse,
     collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
+    iter, mem,
     sync::{Arc, RwLock},
     time::{Duration, SystemTime},
 };
@@ -40,7 +41,11 @@ use crate::{
     connect_list::ConnectList,
     consensus::{PersistChanges, RoundAction},
     events::ConnectedPeerAddr,
+    memory::{message_size, MemoryAccountant, MemorySubsystem},
     messages::{Connect, Consensus as ConsensusMessage, Prevote, Propose, Status},
+    rounds::{ConsensusRounds, ConsensusTimeout, ProposalInfo, RoundInfo, VotesInfo},
+    standby::{StandbyActivation, StandbyConfig, StandbyState, StandbyStatus},
+    tx_cache::SharedTxCache,
     Configuration, ConnectInfo, FlushPoolStrategy,
 };
 
@@ -54,6 +59,9 @@ pub const TRANSACTIONS_REQUEST_TIMEOUT: Milliseconds = 100;
 pub const PREVOTES_REQUEST_TIMEOUT: Milliseconds = 100;
 /// Timeout value for the `BlockRequest` message.
 pub const BLOCK_REQUEST_TIMEOUT: Milliseconds = 100;
+/// Maximum number of rejected transactions buffered by the node until they are recorded
+/// in a block.
+const MAX_REJECTED_TXS: usize = 10_000;
 
 /// Peer's state.
 #[derive(Debug, Clone, Copy)]
@@ -143,6 +151,9 @@ pub struct State {
 
     queued: Vec<ConsensusMessage>,
 
+    // Consensus timeouts fired within the current epoch.
+    fired_timeouts: Vec<(Round, ConsensusTimeout)>,
+
     // Unknown `Propose` messages confirmed by a majority of `Precommit`s.
     proposes_confirmed_by_majority: HashMap<Hash, (Round, Hash)>,
 
@@ -157,6 +168,10 @@ pub struct State {
 
     // Cache that stores transactions before adding to persistent pool.
     tx_cache: BTreeMap<Hash, Verified<AnyTx>>,
+    // Hashes of transactions in the cache, shared with the node API.
+    shared_tx_cache: SharedTxCache,
+    // Memory accounting of the node subsystems, shared with the node API.
+    memory: MemoryAccountant,
     flush_pool_strategy: FlushPoolStrategy,
     tx_check_cache: TxCheckCache,
 
@@ -171,7 +186,22 @@ pub struct State {
     // TODO: This may be a vector for DoS attacks by memory exhaustion. [ECR-2067]
     invalid_txs: HashSet<Hash>,
 
+    // Transactions rejected by the node on admission to the pool, which are not yet
+    // recorded in a block. Rejections are collected only if enabled in the node configuration.
+    commit_rejected_txs: bool,
+    rejected_txs: BTreeMap<Hash, RejectedTransaction>,
+
+    // Are execution digests exchanged with peers?
+    execution_digests: bool,
+
     keys: Keys,
+    // Standby state of the node. If set, the node does not act as a validator
+    // even if its consensus key is in the validator set.
+    standby: Option<StandbyState>,
+    // Configuration used if the node switches to the standby mode on a `StepDown` request.
+    standby_config: StandbyConfig,
+    // Hash of the `StepDown` message issued by the node on its activation, if any.
+    issued_step_down: Option<Hash>,
 }
 
 /// State of a validator node.
@@ -244,6 +274,7 @@ pub struct IncompleteBlock {
     pub header: Block,
     pub precommits: Vec<Verified<Precommit>>,
     pub transactions: Vec<Hash>,
+    pub rejected_transactions: Vec<RejectedTransaction>,
     unknown_txs: HashSet<Hash>,
 }
 
@@ -425,6 +456,11 @@ impl ProposeState {
         !self.is_valid
     }
 
+    /// Marks the propose as invalid, e.g., if it violates per-service transaction quotas.
+    pub(super) fn mark_invalid(&mut self) {
+        self.is_valid = false;
+    }
+
     /// Indicates whether Propose has been saved to the consensus messages cache
     pub fn is_saved(&self) -> bool {
         self.is_saved
@@ -461,17 +497,26 @@ impl BlockState {
     pub fn proposer_id(&self) -> ValidatorId {
         self.proposer_id
     }
+
+    /// Returns hashes of the changes made by each call within the block. The hashes
+    /// are only available before the block is committed, and only if they were recorded
+    /// during execution.
+    pub fn call_hashes(&self) -> &[(CallInBlock, Hash)] {
+        self.patch.as_ref().map_or(&[][..], BlockPatch::call_hashes)
+    }
 }
 
 impl IncompleteBlock {
     pub fn new(
         header: Block,
         transactions: Vec<Hash>,
+        rejected_transactions: Vec<RejectedTransaction>,
         precommits: Vec<Verified<Precommit>>,
     ) -> Self {
         Self {
             header,
             transactions,
+            rejected_transactions,
             precommits,
             unknown_txs: HashSet::new(),
         }
@@ -528,6 +573,20 @@ impl SharedConnectList {
         conn_list.update_peer(public_key, address);
     }
 
+    /// Returns `true` if a peer with the given public key is not restricted
+    /// by the bootstrap mode.
+    pub(crate) fn is_allowed_in_bootstrap(&self, public_key: &PublicKey) -> bool {
+        let connect_list = self.inner.read().expect("ConnectList read lock");
+        connect_list.is_allowed_in_bootstrap(public_key)
+    }
+
+    /// Switches the connect list to the normal mode. Returns `true` if the list was
+    /// in the bootstrap mode.
+    pub(super) fn finish_bootstrap(&mut self) -> bool {
+        let mut connect_list = self.inner.write().expect("ConnectList write lock");
+        connect_list.finish_bootstrap()
+    }
+
     /// Get peer address using public key.
     pub(crate) fn find_address_by_key(&self, public_key: &PublicKey) -> Option<String> {
         let connect_list = self.inner.read().expect("ConnectList read lock");
@@ -539,6 +598,7 @@ impl SharedConnectList {
 
 impl State {
     /// Creates state with the given parameters.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         config: Configuration,
         consensus_config: ConsensusConfig,
@@ -547,11 +607,30 @@ impl State {
         last_block: &Block,
         last_block_skip: Option<&Block>,
         epoch_start_time: SystemTime,
+        shared_tx_cache: SharedTxCache,
+        memory: MemoryAccountant,
     ) -> Self {
-        let validator_id = consensus_config
-            .validator_keys
-            .iter()
-            .position(|pk| pk.consensus_key == config.keys.consensus_pk());
+        // The shared cache may be left over from a previous incarnation of the node.
+        shared_tx_cache.clear();
+        memory.set_budgets(&config.mempool.memory_budget);
+        memory.set_used(MemorySubsystem::TxPool, 0);
+        memory.set_used(MemorySubsystem::ConsensusCache, 0);
+        let standby = config
+            .standby
+            .map(|standby_config| StandbyState::new(standby_config, epoch_start_time));
+        let validator_id = if standby.is_some() {
+            None
+        } else {
+            Self::find_validator_id(&consensus_config, &config.keys)
+        };
+
+        let mut connect_list = config.connect_list;
+        // The bootstrap mode only makes sense before the first block is committed.
+        if let (Some(bootstrap), Height(0)) = (config.bootstrap, last_block.height) {
+            let our_key = config.keys.consensus_pk();
+            connect_list
+                .start_bootstrap(bootstrap.allowlist.into_iter().chain(iter::once(our_key)));
+        }
 
         let our_connect_message = Verified::from_value(
             connect,
@@ -564,8 +643,8 @@ impl State {
             .expect("No `epoch` recorded in the saved block");
 
         Self {
-            validator_state: validator_id.map(|id| ValidatorState::new(ValidatorId(id as u16))),
-            connect_list: SharedConnectList::from_connect_list(config.connect_list),
+            validator_state: validator_id.map(ValidatorState::new),
+            connect_list: SharedConnectList::from_connect_list(connect_list),
             peers,
             connections: HashMap::new(),
             epoch: last_epoch.next(),
@@ -583,6 +662,8 @@ impl State {
 
             queued: Vec::new(),
 
+            fired_timeouts: Vec::new(),
+
             proposes_confirmed_by_majority: HashMap::new(),
 
             peer_states: BTreeMap::new(),
@@ -595,14 +676,31 @@ impl State {
 
             incomplete_block: None,
             tx_cache: BTreeMap::new(),
+            shared_tx_cache,
+            memory,
             flush_pool_strategy: config.mempool.flush_pool_strategy,
             tx_check_cache: TxCheckCache::new(),
             invalid_txs: HashSet::default(),
+            commit_rejected_txs: config.mempool.commit_rejected_transactions,
+            execution_digests: config.network.execution_digests,
+            rejected_txs: BTreeMap::new(),
 
             keys: config.keys,
+            standby,
+            standby_config: config.standby.unwrap_or_default(),
+            issued_step_down: None,
         }
     }
 
+    /// Finds the validator ID corresponding to the node keys in the consensus config.
+    fn find_validator_id(config: &ConsensusConfig, keys: &Keys) -> Option<ValidatorId> {
+        config
+            .validator_keys
+            .iter()
+            .position(|pk| pk.consensus_key == keys.consensus_pk())
+            .map(|id| ValidatorId(id as u16))
+    }
+
     /// Returns `ValidatorState` if the node is validator.
     fn validator_state(&self) -> &Option<ValidatorState> {
         &self.validator_state
@@ -666,11 +764,11 @@ impl State {
         }
 
         trace!("Updating node config={:#?}", config);
-        let validator_id = config
-            .validator_keys
-            .iter()
-            .position(|pk| pk.consensus_key == self.keys().consensus_pk())
-            .map(|id| ValidatorId(id as u16));
+        let validator_id = if self.standby.is_some() {
+            None
+        } else {
+            Self::find_validator_id(&config, &self.keys)
+        };
 
         // TODO: update connect list (ECR-1745)
 
@@ -680,6 +778,66 @@ impl State {
         self.config = config;
     }
 
+    /// Checks whether execution digests are exchanged with peers.
+    pub(crate) fn execution_digests(&self) -> bool {
+        self.execution_digests
+    }
+
+    /// Returns the status of the standby mode, or `None` if the node is not in this mode.
+    pub(crate) fn standby_status(&self) -> Option<StandbyStatus> {
+        self.standby
+            .as_ref()
+            .map(|standby| standby.status(self.keys.consensus_pk()))
+    }
+
+    /// Records activity of the primary node if the node is in the standby mode.
+    pub(crate) fn observe_primary_activity(&mut self, time: SystemTime) {
+        if let Some(ref mut standby) = self.standby {
+            standby.observe_primary_activity(time);
+        }
+    }
+
+    /// Activates the node running in the standby mode, so it starts acting as a validator
+    /// (provided that its consensus key is in the validator set).
+    pub(crate) fn activate_standby(
+        &mut self,
+        request: &StandbyActivation,
+        blockchain_height: Height,
+        now: SystemTime,
+    ) -> anyhow::Result<()> {
+        let status = match self.standby_status() {
+            Some(status) => status,
+            None => bail!("Node is not in the standby mode"),
+        };
+        status.check_activation(request, blockchain_height, now)?;
+
+        self.standby = None;
+        let validator_id = Self::find_validator_id(&self.config, &self.keys);
+        self.renew_validator_id(validator_id);
+        Ok(())
+    }
+
+    /// Records the hash of the `StepDown` message issued by the node on its activation.
+    pub(crate) fn set_issued_step_down(&mut self, hash: Hash) {
+        self.issued_step_down = Some(hash);
+    }
+
+    /// Checks whether the `StepDown` message with the specified hash was issued by the node.
+    pub(crate) fn is_issued_step_down(&self, hash: &Hash) -> bool {
+        self.issued_step_down.as_ref() == Some(hash)
+    }
+
+    /// Switches the node to the standby mode at the specified time, so it stops acting
+    /// as a validator. Returns `false` if the node is already in the standby mode.
+    pub(crate) fn step_down(&mut self, now: SystemTime) -> bool {
+        if self.standby.is_some() {
+            return false;
+        }
+        self.standby = Some(StandbyState::new(self.standby_config, now));
+        self.renew_validator_id(None);
+        true
+    }
+
     /// Adds the public key, address, and `Connect` message of a validator.
     pub(super) fn add_peer(&mut self, pubkey: PublicKey, msg: Verified<Connect>) -> bool {
         self.peers.insert(pubkey, msg).is_none()
@@ -931,6 +1089,7 @@ impl State {
         self.prevotes.clear();
         self.precommits.clear();
         self.validators_rounds.clear();
+        self.fired_timeouts.clear();
         if let Some(ref mut validator_state) = self.validator_state {
             validator_state.clear();
         }
@@ -950,18 +1109,100 @@ impl State {
         self.last_hash = block_hash;
         self.invalid_txs.clear();
         self.tx_check_cache = TxCheckCache::new();
+
+        if self.connect_list.finish_bootstrap() {
+            info!("The first block is committed, switching from the bootstrap mode");
+        }
+    }
+
+    /// Records a consensus timeout fired in the specified round of the current epoch.
+    pub(super) fn add_fired_timeout(&mut self, round: Round, timeout: ConsensusTimeout) {
+        self.fired_timeouts.push((round, timeout));
+    }
+
+    /// Collects information about consensus rounds within the current epoch.
+    pub(crate) fn consensus_rounds(&self) -> ConsensusRounds {
+        let mut rounds = BTreeMap::new();
+        self.round_info(&mut rounds, self.round);
+
+        for (hash, propose_state) in &self.proposes {
+            let propose = propose_state.message().payload();
+            self.round_info(&mut rounds, propose.round)
+                .proposals
+                .push(ProposalInfo {
+                    hash: *hash,
+                    proposer: propose.validator,
+                    tx_count: propose.transactions.len(),
+                    unknown_tx_count: propose_state.unknown_txs().len(),
+                    skip: propose.skip,
+                    block_hash: propose_state.block_hash(),
+                });
+        }
+        for ((round, hash), votes) in &self.prevotes {
+            let validators = votes.messages().iter().map(VoteMessage::validator);
+            self.round_info(&mut rounds, *round)
+                .prevotes
+                .push(VotesInfo::new(*hash, validators));
+        }
+        for ((round, hash), votes) in &self.precommits {
+            let validators = votes.messages().iter().map(VoteMessage::validator);
+            self.round_info(&mut rounds, *round)
+                .precommits
+                .push(VotesInfo::new(*hash, validators));
+        }
+        for &(round, timeout) in &self.fired_timeouts {
+            self.round_info(&mut rounds, round).timeouts.push(timeout);
+        }
+
+        let mut rounds: Vec<_> = rounds.into_iter().map(|(_, info)| info).collect();
+        // Hash maps have no stable iteration order, so we sort the collected items.
+        for info in &mut rounds {
+            info.proposals.sort_by_key(|proposal| proposal.hash);
+            info.prevotes.sort_by_key(|votes| votes.hash);
+            info.precommits.sort_by_key(|votes| votes.hash);
+        }
+
+        ConsensusRounds {
+            epoch: self.epoch,
+            blockchain_height: self.blockchain_height,
+            round: self.round,
+            locked_round: self.locked_round,
+            locked_propose: self.locked_propose,
+            majority_count: self.majority_count(),
+            validators_rounds: self.validators_rounds.clone(),
+            rounds,
+        }
+    }
+
+    fn round_info<'a>(
+        &self,
+        rounds: &'a mut BTreeMap<Round, RoundInfo>,
+        round: Round,
+    ) -> &'a mut RoundInfo {
+        rounds
+            .entry(round)
+            .or_insert_with(|| RoundInfo::new(round, self.leader(round)))
     }
 
     /// Returns a list of queued consensus messages.
     pub(super) fn queued(&mut self) -> Vec<ConsensusMessage> {
         let mut queued = Vec::new();
         std::mem::swap(&mut self.queued, &mut queued);
+        self.memory.set_used(MemorySubsystem::ConsensusCache, 0);
         queued
     }
 
-    /// Add consensus message to the queue.
+    /// Add consensus message to the queue. If the queue exceeds its memory budget,
+    /// the message is dropped.
     pub(super) fn add_queued(&mut self, msg: ConsensusMessage) {
-        self.queued.push(msg);
+        let size = message_size(msg.as_raw());
+        if self.memory.fits(MemorySubsystem::ConsensusCache, size) {
+            self.memory.add(MemorySubsystem::ConsensusCache, size);
+            self.queued.push(msg);
+        } else {
+            self.memory.record_shed(MemorySubsystem::ConsensusCache);
+            trace!("Dropping queued consensus message: memory budget is exceeded");
+        }
     }
 
     /// Checks whether some proposes are waiting for this transaction.
@@ -1417,9 +1658,43 @@ impl State {
         &self.tx_cache
     }
 
-    /// Returns mutable reference to the transactions cache.
-    pub(super) fn tx_cache_mut(&mut self) -> &mut BTreeMap<Hash, Verified<AnyTx>> {
-        &mut self.tx_cache
+    /// Adds a transaction to the transactions cache.
+    pub(super) fn add_tx_to_cache(&mut self, tx_hash: Hash, tx: Verified<AnyTx>) {
+        let size = message_size(tx.as_raw());
+        if self.tx_cache.insert(tx_hash, tx).is_none() {
+            self.memory.add(MemorySubsystem::TxPool, size);
+        }
+        self.shared_tx_cache.insert(tx_hash);
+    }
+
+    /// Removes a transaction from the transactions cache.
+    pub(super) fn remove_tx_from_cache(&mut self, tx_hash: &Hash) {
+        if let Some(tx) = self.tx_cache.remove(tx_hash) {
+            self.memory
+                .release(MemorySubsystem::TxPool, message_size(tx.as_raw()));
+        }
+        self.shared_tx_cache.remove(tx_hash);
+    }
+
+    /// Checks whether the transactions cache exceeds its memory budget.
+    pub(super) fn is_tx_cache_over_budget(&self) -> bool {
+        self.memory.is_over_budget(MemorySubsystem::TxPool)
+    }
+
+    /// Takes all transactions from the transactions cache in order to flush them
+    /// into the persistent pool. The transactions remain visible in the shared view
+    /// of the cache until `forget_flushed_txs` is called, so that the API never observes
+    /// them as missing both from the cache and the pool.
+    pub(super) fn take_tx_cache(&mut self) -> BTreeMap<Hash, Verified<AnyTx>> {
+        self.memory.set_used(MemorySubsystem::TxPool, 0);
+        mem::take(&mut self.tx_cache)
+    }
+
+    /// Removes transactions flushed into the persistent pool from the shared view
+    /// of the transactions cache.
+    pub(super) fn forget_flushed_txs(&mut self) {
+        debug_assert!(self.tx_cache.is_empty());
+        self.shared_tx_cache.clear();
     }
 
     /// Returns mutable reference to transaction checking cache.
@@ -1445,4 +1720,32 @@ impl State {
     pub(super) fn invalid_txs_mut(&mut self) -> &mut HashSet<Hash> {
         &mut self.invalid_txs
     }
+
+    /// Remembers a transaction rejected on admission to the pool, so that it can be recorded
+    /// in a block proposed by the node. Does nothing if recording rejected transactions
+    /// is disabled, or if too many rejected transactions are already buffered.
+    pub(super) fn add_rejected_tx(&mut self, rejected: RejectedTransaction) {
+        if self.commit_rejected_txs && self.rejected_txs.len() < MAX_REJECTED_TXS {
+            self.rejected_txs.insert(rejected.tx_hash, rejected);
+        }
+    }
+
+    /// Returns rejected transactions to be recorded in the next proposal, sorted by hash.
+    pub(super) fn rejected_txs(&self, limit: usize) -> Vec<RejectedTransaction> {
+        self.rejected_txs.values().take(limit).copied().collect()
+    }
+
+    /// Forgets rejected transactions with the specified hashes, e.g., after they were
+    /// recorded in a committed block.
+    pub(super) fn remove_rejected_txs<'a>(
+        &mut self,
+        tx_hashes: impl IntoIterator<Item = &'a Hash>,
+    ) {
+        if self.rejected_txs.is_empty() {
+            return;
+        }
+        for tx_hash in tx_hashes {
+            self.rejected_txs.remove(tx_hash);
+        }
+    }
 }
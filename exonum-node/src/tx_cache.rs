@@ -0,0 +1,119 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Shared view of the ephemeral transaction cache.
+
+use exonum::crypto::Hash;
+
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+};
+
+/// Number of independently locked shards in the cache.
+const SHARDS_COUNT: usize = 16;
+
+#[derive(Debug, Default)]
+struct Shards {
+    shards: [RwLock<HashSet<Hash>>; SHARDS_COUNT],
+    len: AtomicUsize,
+}
+
+/// Hashes of the transactions in the ephemeral transaction cache of the node, shared
+/// between the node thread and the API handlers.
+///
+/// The set is modified only by the node thread, but may be read concurrently by any number
+/// of API handlers. To minimize contention, hashes are distributed among several
+/// independently locked shards, so that a reader only blocks on a writer touching
+/// the same shard. The number of cached transactions is tracked with an atomic counter
+/// and can be read without locking.
+#[derive(Debug, Clone, Default)]
+pub struct SharedTxCache {
+    inner: Arc<Shards>,
+}
+
+impl SharedTxCache {
+    fn shard(&self, hash: &Hash) -> &RwLock<HashSet<Hash>> {
+        let index = usize::from(hash.as_ref()[0]) % SHARDS_COUNT;
+        &self.inner.shards[index]
+    }
+
+    /// Adds a transaction hash to the cache.
+    pub fn insert(&self, hash: Hash) {
+        let mut shard = self.shard(&hash).write().expect("Cache shard write lock");
+        if shard.insert(hash) {
+            self.inner.len.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    /// Removes a transaction hash from the cache.
+    pub fn remove(&self, hash: &Hash) {
+        let mut shard = self.shard(hash).write().expect("Cache shard write lock");
+        if shard.remove(hash) {
+            self.inner.len.fetch_sub(1, Ordering::Release);
+        }
+    }
+
+    /// Removes all hashes from the cache.
+    pub fn clear(&self) {
+        for shard in &self.inner.shards {
+            let mut shard = shard.write().expect("Cache shard write lock");
+            self.inner.len.fetch_sub(shard.len(), Ordering::Release);
+            shard.clear();
+        }
+    }
+
+    /// Checks whether the cache contains a transaction with the specified hash.
+    pub fn contains(&self, hash: &Hash) -> bool {
+        let shard = self.shard(hash).read().expect("Cache shard read lock");
+        shard.contains(hash)
+    }
+
+    /// Returns the number of transactions in the cache.
+    pub fn len(&self) -> usize {
+        self.inner.len.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exonum::crypto::hash;
+
+    #[test]
+    fn shared_tx_cache_basics() {
+        let cache = SharedTxCache::default();
+        let hashes: Vec<_> = (0_u8..100).map(|i| hash(&[i])).collect();
+        for tx_hash in &hashes {
+            cache.insert(*tx_hash);
+        }
+        cache.insert(hashes[0]);
+        assert_eq!(cache.len(), hashes.len());
+        assert!(hashes.iter().all(|tx_hash| cache.contains(tx_hash)));
+
+        let other_handle = cache.clone();
+        other_handle.remove(&hashes[0]);
+        other_handle.remove(&hashes[0]);
+        assert_eq!(cache.len(), hashes.len() - 1);
+        assert!(!cache.contains(&hashes[0]));
+        assert!(cache.contains(&hashes[1]));
+
+        cache.clear();
+        assert_eq!(cache.len(), 0);
+        assert!(!other_handle.contains(&hashes[1]));
+    }
+}
@@ -30,7 +30,7 @@ use std::{
 use exonum_node::{
     generate_testnet_config,
     pool::{SkipEmptyBlocks, StandardPoolManager},
-    Node, NodeBuilder, ShutdownHandle,
+    Node, NodeBuilder, ReplicationConfig, ShutdownHandle,
 };
 
 #[derive(Debug)]
@@ -133,6 +133,7 @@ pub struct Options {
     pub slow_blocks: bool,
     pub skip_empty_blocks: bool,
     pub http_start_port: Option<u16>,
+    pub replication_start_port: Option<u16>,
     pub disable_signals: bool,
 }
 
@@ -158,6 +159,10 @@ pub fn run_nodes(
             let addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), start_port + i as u16);
             node_cfg.api.public_api_address = Some(addr);
         }
+        if let Some(start_port) = options.replication_start_port {
+            let addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), start_port + i as u16);
+            node_cfg.replication = Some(ReplicationConfig::new(addr));
+        }
 
         let service = CommitWatcherService(commit_tx);
         let artifact = service.artifact_id();
@@ -18,7 +18,7 @@ use exonum::{
     blockchain::config::GenesisConfigBuilder,
     crypto::KeyPair,
     helpers::{Height, Round},
-    merkledb::{Database, ObjectHash, TemporaryDB},
+    merkledb::{replication::replication_seq, Database, ObjectHash, SystemSchema, TemporaryDB},
     runtime::SnapshotExt,
 };
 use exonum_rust_runtime::{RustRuntime, ServiceFactory};
@@ -31,7 +31,7 @@ use std::{
     time::Duration,
 };
 
-use exonum_node::{generate_testnet_config, NodeBuilder, NodeConfig};
+use exonum_node::{generate_testnet_config, replicate_from, NodeBuilder, NodeConfig};
 
 pub mod common;
 use crate::common::{
@@ -80,6 +80,81 @@ async fn node_frees_sockets_on_shutdown() {
     TcpListener::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 16_351)).unwrap();
 }
 
+#[tokio::test]
+async fn replica_follows_node_database() {
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    let options = Options {
+        replication_start_port: Some(16_361),
+        ..Options::default()
+    };
+    let (mut nodes, mut commit_rxs) = run_nodes(1, 16_360, options);
+    let node = nodes.pop().unwrap();
+    let blockchain = node.blockchain.clone();
+    let mut commit_rx = commit_rxs.pop().unwrap();
+    // Wait until the replication server is started.
+    commit_rx.next().await;
+
+    let replica = Arc::new(TemporaryDB::new());
+    let replica_db = Arc::clone(&replica);
+    let replica_task = tokio::task::spawn_blocking(move || {
+        let primary_address = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 16_361);
+        replicate_from(primary_address, &*replica_db)
+    });
+
+    for _ in 0..3 {
+        timeout(TIMEOUT, commit_rx.next()).await.unwrap();
+    }
+    node.join().await;
+
+    let primary_snapshot = blockchain.snapshot();
+    let expected_seq = replication_seq(&primary_snapshot);
+    assert!(expected_seq > 0);
+    let wait_for_replica = async {
+        while replication_seq(&replica.snapshot()) < expected_seq {
+            sleep(Duration::from_millis(50)).await;
+        }
+    };
+    timeout(TIMEOUT, wait_for_replica).await.unwrap();
+
+    let replica_snapshot = replica.snapshot();
+    assert_eq!(
+        SystemSchema::new(&replica_snapshot).state_hash(),
+        SystemSchema::new(&primary_snapshot).state_hash()
+    );
+    assert_eq!(
+        replica_snapshot.for_core().height(),
+        primary_snapshot.for_core().height()
+    );
+
+    // The replica should disconnect once the node database is dropped.
+    drop(blockchain);
+    timeout(TIMEOUT, replica_task)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn replication_failure_does_not_stop_node() {
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    // Occupy the replication port, so that the replication server cannot start.
+    let _listener = TcpListener::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 16_371)).unwrap();
+    let options = Options {
+        replication_start_port: Some(16_371),
+        ..Options::default()
+    };
+    let (mut nodes, mut commit_rxs) = run_nodes(1, 16_370, options);
+    let node = nodes.pop().unwrap();
+    let mut commit_rx = commit_rxs.pop().unwrap();
+    for _ in 0..3 {
+        timeout(TIMEOUT, commit_rx.next()).await.unwrap().unwrap();
+    }
+    node.join().await;
+}
+
 #[tokio::test]
 async fn nodes_flush_transactions_to_storage_before_commit() {
     // `slow_blocks: true` argument makes it so that nodes should not create a single block
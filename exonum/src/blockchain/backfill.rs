@@ -0,0 +1,355 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Node-local indexes built from historical blocks in the background.
+
+use exonum_crypto::{Hash, PublicKey, PUBLIC_KEY_LENGTH};
+use exonum_merkledb::{
+    access::{Access, AccessExt},
+    BinaryKey, Database, Fork, MapIndex, Snapshot,
+};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    cmp, fmt,
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use super::{Blockchain, HistoryPruned, Schema, TxLocation, ValueCache};
+use crate::helpers::Height;
+
+/// Name of the index storing the last indexed height for each local index.
+const PROGRESS: &str = "local_indexes.progress";
+/// Maximum number of blocks indexed within a single database merge.
+const BATCH_SIZE: u64 = 256;
+/// Interval between checks for new blocks if the backfill job is not notified about them.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Index derived from the blockchain data and maintained locally by the node.
+///
+/// Local indexes are not a part of the blockchain state: they do not influence the state hash
+/// and may differ among nodes. Since such an index can be introduced on an existing chain,
+/// it is built by a [`BackfillHandle`] job which feeds historical blocks to the index
+/// one by one, starting from the genesis block, and then keeps the index up to date
+/// as new blocks are committed.
+///
+/// Implementations must only use non-merkelized indexes so that the state hash is not affected.
+/// All indexes must be created in `initialize`; `index_block` may only write to them. This is
+/// necessary because the backfill job merges its changes concurrently with the node.
+///
+/// [`BackfillHandle`]: struct.BackfillHandle.html
+pub trait LocalIndex: Send + 'static {
+    /// Returns the name of the index, which is used to track its progress.
+    fn name(&self) -> &str;
+
+    /// Creates the indexes used by this local index.
+    fn initialize(&self, fork: &Fork);
+
+    /// Indexes a committed block at the specified height. The block data can be read
+    /// from the `fork` via the core [`Schema`]. The method is never called for blocks
+    /// with pruned historical data.
+    ///
+    /// [`Schema`]: struct.Schema.html
+    fn index_block(&self, fork: &Fork, block_height: Height);
+}
+
+impl LocalIndex for Box<dyn LocalIndex> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn initialize(&self, fork: &Fork) {
+        (**self).initialize(fork);
+    }
+
+    fn index_block(&self, fork: &Fork, block_height: Height) {
+        (**self).index_block(fork, block_height);
+    }
+}
+
+/// Progress of building a local index.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BackfillProgress {
+    /// Name of the local index.
+    pub name: String,
+    /// Height of the latest indexed block, or `None` if no blocks are indexed yet.
+    pub indexed_height: Option<Height>,
+    /// Height of the latest committed block, or `None` if the genesis block is not created yet.
+    pub blockchain_height: Option<Height>,
+}
+
+impl BackfillProgress {
+    /// Reads the progress of the local index with the specified name from the snapshot.
+    pub fn read(snapshot: &dyn Snapshot, name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            indexed_height: progress_index(snapshot).get(name),
+            blockchain_height: last_height(snapshot),
+        }
+    }
+
+    /// Checks whether the local index covers all committed blocks.
+    pub fn is_complete(&self) -> bool {
+        self.blockchain_height.is_some() && self.indexed_height >= self.blockchain_height
+    }
+}
+
+fn progress_index<T: Access>(access: T) -> MapIndex<T::Base, str, Height> {
+    access.get_map(PROGRESS)
+}
+
+fn last_height(snapshot: &dyn Snapshot) -> Option<Height> {
+    let next_height = Schema::new(snapshot).next_height();
+    if next_height == Height(0) {
+        None
+    } else {
+        Some(next_height.previous())
+    }
+}
+
+/// Handle to a background job building a local index.
+///
+/// The job is stopped when the handle is dropped. Since the progress of the job is persisted
+/// in the database, a job spawned for the same index after the node restart continues
+/// from the last indexed block.
+///
+/// If historical data for the blocks not yet covered by the index is pruned by the node
+/// (see [`PruningConfig`]), the job logs an error and stops, since the index cannot be built.
+///
+/// [`PruningConfig`]: struct.PruningConfig.html
+pub struct BackfillHandle {
+    name: String,
+    db: Arc<dyn Database>,
+    notifier: mpsc::Sender<()>,
+}
+
+impl fmt::Debug for BackfillHandle {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("BackfillHandle")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl BackfillHandle {
+    /// Creates indexes for the local index and spawns a background job filling them.
+    ///
+    /// The job must be spawned before the node starts creating blocks, e.g., when wiring
+    /// a node plugin. Otherwise, creation of the indexes may race with the node.
+    pub fn spawn(blockchain: &Blockchain, index: impl LocalIndex) -> Self {
+        let db = Arc::clone(blockchain.database());
+        let value_cache = Arc::clone(blockchain.value_cache());
+        let fork = db.fork();
+        index.initialize(&fork);
+        // Accessing indexes via a fork creates them if necessary.
+        progress_index(&fork);
+        value_cache
+            .invalidate_after(|| db.merge(fork.into_patch()))
+            .expect("Cannot create indexes for a local index");
+
+        let name = index.name().to_owned();
+        let (notifier, notifications) = mpsc::channel();
+        let job = BackfillJob {
+            db: Arc::clone(&db),
+            value_cache,
+            index,
+        };
+        thread::Builder::new()
+            .name(format!("backfill-{}", name))
+            .spawn(move || job.run(&notifications))
+            .expect("Cannot spawn backfill thread");
+
+        Self { name, db, notifier }
+    }
+
+    /// Returns the name of the local index.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Notifies the job that a new block was committed.
+    pub fn notify(&self) {
+        self.notifier.send(()).ok();
+    }
+
+    /// Returns the current progress of the job.
+    pub fn progress(&self) -> BackfillProgress {
+        BackfillProgress::read(self.db.snapshot().as_ref(), &self.name)
+    }
+}
+
+struct BackfillJob<I> {
+    db: Arc<dyn Database>,
+    value_cache: Arc<ValueCache>,
+    index: I,
+}
+
+impl<I: LocalIndex> BackfillJob<I> {
+    fn run(self, notifications: &mpsc::Receiver<()>) {
+        loop {
+            let is_caught_up = match self.index_next_batch() {
+                Ok(is_caught_up) => is_caught_up,
+                Err(e) => {
+                    log::error!("Cannot update local index `{}`: {}", self.index.name(), e);
+                    if e.is::<HistoryPruned>() {
+                        // Retrying is pointless, since pruned data cannot be restored.
+                        break;
+                    }
+                    true
+                }
+            };
+
+            if is_caught_up {
+                match notifications.recv_timeout(POLL_INTERVAL) {
+                    Ok(()) | Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        }
+    }
+
+    /// Indexes the next batch of blocks. Returns `true` if all committed blocks are indexed.
+    fn index_next_batch(&self) -> anyhow::Result<bool> {
+        let snapshot = self.db.snapshot();
+        let progress = BackfillProgress::read(snapshot.as_ref(), self.index.name());
+        let last_height = match progress.blockchain_height {
+            Some(height) => height,
+            None => return Ok(true),
+        };
+        let from = progress.indexed_height.map_or(Height(0), Height::next);
+        if from > last_height {
+            return Ok(true);
+        }
+
+        let to = cmp::min(last_height, Height(from.0 + BATCH_SIZE - 1));
+        let fork = self.db.fork();
+        // The local index cannot be built from pruned blocks; the check is performed
+        // on the fork so that it is consistent with the indexed data.
+        Schema::new(&fork).check_history_retained(from)?;
+        for height in from.0..=to.0 {
+            self.index.index_block(&fork, Height(height));
+        }
+        progress_index(&fork).put(self.index.name(), to);
+        self.value_cache
+            .invalidate_after(|| self.db.merge(fork.into_patch()))?;
+
+        log::trace!(
+            "Local index `{}` is built up to height {} of {}",
+            self.index.name(),
+            to,
+            last_height
+        );
+        Ok(to == last_height)
+    }
+}
+
+/// Key of the `TransactionsByAuthor` index ordering transactions of each author
+/// by their location.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct AuthorKey {
+    author: PublicKey,
+    location: TxLocation,
+}
+
+impl AuthorKey {
+    const SIZE: usize = PUBLIC_KEY_LENGTH + 8 + 4;
+}
+
+impl BinaryKey for AuthorKey {
+    fn size(&self) -> usize {
+        Self::SIZE
+    }
+
+    fn write(&self, buffer: &mut [u8]) -> usize {
+        self.author.write(&mut buffer[..PUBLIC_KEY_LENGTH]);
+        let location = &mut buffer[PUBLIC_KEY_LENGTH..];
+        self.location.block_height().0.write(&mut location[..8]);
+        self.location.position_in_block().write(&mut location[8..]);
+        Self::SIZE
+    }
+
+    fn read(buffer: &[u8]) -> Self::Owned {
+        let location = &buffer[PUBLIC_KEY_LENGTH..];
+        Self {
+            author: PublicKey::read(&buffer[..PUBLIC_KEY_LENGTH]),
+            location: TxLocation::new(Height(u64::read(&location[..8])), u32::read(&location[8..])),
+        }
+    }
+}
+
+/// Local index of committed transactions by their author.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TransactionsByAuthor;
+
+impl TransactionsByAuthor {
+    /// Name of the local index.
+    pub const NAME: &'static str = "transactions_by_author";
+    const INDEX: &'static str = "local_indexes.transactions_by_author";
+
+    fn index<T: Access>(access: T) -> MapIndex<T::Base, AuthorKey, Hash> {
+        access.get_map(Self::INDEX)
+    }
+
+    /// Returns hashes and locations of the indexed transactions signed by `author`
+    /// in the order of their commitment.
+    ///
+    /// Only blocks covered by the index are taken into account; use [`BackfillProgress`]
+    /// to check whether the index is complete.
+    ///
+    /// [`BackfillProgress`]: struct.BackfillProgress.html
+    pub fn transactions(snapshot: &dyn Snapshot, author: PublicKey) -> Vec<(Hash, TxLocation)> {
+        let start = AuthorKey {
+            author,
+            location: TxLocation::new(Height(0), 0),
+        };
+        Self::index(snapshot)
+            .iter_from(&start)
+            .take_while(|(key, _)| key.author == author)
+            .map(|(key, tx_hash)| (tx_hash, key.location))
+            .collect()
+    }
+}
+
+impl LocalIndex for TransactionsByAuthor {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn initialize(&self, fork: &Fork) {
+        Self::index(fork);
+    }
+
+    fn index_block(&self, fork: &Fork, block_height: Height) {
+        let schema = Schema::new(fork);
+        let transactions = schema.transactions();
+        let mut index = Self::index(fork);
+        for (position, tx_hash) in schema.block_transactions(block_height).iter().enumerate() {
+            let tx = transactions
+                .get(&tx_hash)
+                .expect("BUG: Cannot find committed transaction");
+            let key = AuthorKey {
+                author: tx.author(),
+                location: TxLocation::new(block_height, position as u32),
+            };
+            index.put(&key, tx_hash);
+        }
+    }
+}
@@ -20,15 +20,16 @@ use exonum_merkledb::{
 };
 use exonum_proto::ProtobufConvert;
 use serde::{Deserialize, Serialize};
-use std::borrow::Cow;
+use std::{borrow::Cow, convert::TryFrom};
 use thiserror::Error;
+use time::OffsetDateTime;
 
 use crate::{
     blockchain::CallInBlock,
     helpers::{byzantine_quorum, Height, OrderedMap, ValidatorId},
-    messages::{Precommit, Verified},
+    messages::{AnyTx, Precommit, Verified},
     proto::schema,
-    runtime::{CallSite, ExecutionError, ExecutionErrorAux},
+    runtime::{CallSite, ErrorKind, ExecutionError, ExecutionErrorAux, InstanceId},
 };
 
 /// Trait that represents a key in block header entry map. Provides
@@ -75,6 +76,192 @@ impl BlockHeaderKey for SkipFlag {
     type Value = ();
 }
 
+/// Summary statistics of transactions in a block.
+///
+/// The statistics are recorded in the block headers for each block with at least one transaction,
+/// so they are authenticated by validators along with the rest of the block. Thus, the statistics
+/// can be used, e.g., for capacity planning without downloading and parsing block transactions.
+///
+/// The statistics contain only the data which is the same on all nodes; for example,
+/// execution time of transactions is not recorded since it would make block hashes
+/// nondeterministic.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+#[derive(ProtobufConvert, BinaryValue)]
+#[protobuf_convert(source = "schema::blockchain::BlockStats")]
+#[non_exhaustive]
+pub struct BlockStats {
+    /// Total size of transactions in the block, in bytes.
+    pub total_tx_bytes: u64,
+    /// Per-service statistics, sorted by the service identifier.
+    pub services: Vec<ServiceTxStats>,
+}
+
+impl BlockStats {
+    /// Creates empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Updates statistics with a transaction included into the block.
+    pub fn record_transaction(&mut self, transaction: &Verified<AnyTx>) {
+        let instance_id = transaction.payload().call_info.instance_id;
+        let tx_bytes = transaction.as_raw().to_bytes().len() as u64;
+
+        self.total_tx_bytes += tx_bytes;
+        let index = match self
+            .services
+            .binary_search_by_key(&instance_id, |stats| stats.instance_id)
+        {
+            Ok(index) => index,
+            Err(index) => {
+                self.services
+                    .insert(index, ServiceTxStats::new(instance_id));
+                index
+            }
+        };
+        let service_stats = &mut self.services[index];
+        service_stats.tx_count += 1;
+        service_stats.tx_bytes += tx_bytes;
+    }
+
+    /// Returns statistics for the specified service, or `None` if the block does not contain
+    /// transactions addressed to the service.
+    pub fn service(&self, instance_id: InstanceId) -> Option<&ServiceTxStats> {
+        self.services
+            .binary_search_by_key(&instance_id, |stats| stats.instance_id)
+            .ok()
+            .map(|index| &self.services[index])
+    }
+}
+
+impl BlockHeaderKey for BlockStats {
+    const NAME: &'static str = "stats";
+    type Value = Self;
+}
+
+/// Statistics of transactions addressed to a single service within a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+#[derive(ProtobufConvert)]
+#[protobuf_convert(source = "schema::blockchain::ServiceTxStats")]
+#[non_exhaustive]
+pub struct ServiceTxStats {
+    /// Identifier of the service instance.
+    pub instance_id: InstanceId,
+    /// Number of transactions addressed to the service.
+    pub tx_count: u32,
+    /// Total size of these transactions, in bytes.
+    pub tx_bytes: u64,
+}
+
+impl ServiceTxStats {
+    fn new(instance_id: InstanceId) -> Self {
+        Self {
+            instance_id,
+            tx_count: 0,
+            tx_bytes: 0,
+        }
+    }
+}
+
+/// Hash of the list of transactions rejected by the block proposer on admission
+/// to its transaction pool. The header is set only for blocks recording at least one
+/// rejected transaction.
+///
+/// The list itself is stored in the [`rejected_transactions`] index of the core schema.
+///
+/// [`rejected_transactions`]: struct.Schema.html#method.rejected_transactions
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RejectedTransactionsHash(());
+
+impl BlockHeaderKey for RejectedTransactionsHash {
+    const NAME: &'static str = "rejected_txs";
+    type Value = Hash;
+}
+
+/// Time of the block agreed upon by validators. The header is set only if the block proposer
+/// has provided the precommits for the previous block, which is possible if the [`block_time`]
+/// option of the consensus configuration is enabled.
+///
+/// The time is the median of the times reported by validators in these precommits. As long as
+/// more than 2/3 of validators are honest, the time is bounded by the times reported
+/// by honest validators. The time is not guaranteed to increase monotonically with the block
+/// height, although it usually does.
+///
+/// [`block_time`]: struct.ConsensusConfig.html#structfield.block_time
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BlockTime(());
+
+impl BlockTime {
+    /// Computes the block time from the precommits for the previous block as the median
+    /// of the times reported in the precommits. For an even number of precommits, the larger
+    /// of the two middle values is used. Returns `None` if `precommits` is empty.
+    ///
+    /// The precommits are not checked by this method.
+    pub fn from_precommits<'a>(
+        precommits: impl IntoIterator<Item = &'a Verified<Precommit>>,
+    ) -> Option<OffsetDateTime> {
+        let mut times: Vec<_> = precommits
+            .into_iter()
+            .map(|precommit| precommit.payload().time)
+            .collect();
+        times.sort_unstable();
+        times.get(times.len() / 2).copied()
+    }
+}
+
+impl BlockHeaderKey for BlockTime {
+    const NAME: &'static str = "time";
+    type Value = OffsetDateTime;
+}
+
+/// Transaction rejected by a node on admission to the transaction pool,
+/// together with the reason of the rejection.
+///
+/// Rejected transactions are neither broadcast nor stored by the node. If the node is configured
+/// to do so, it records them into a block it proposes, so that the transaction authors can obtain
+/// a verifiable answer that their transaction was seen and refused. Note that other validators
+/// cannot check the rejection, since it depends on the proposer's state at the moment
+/// the transaction was received; the record only shows that the proposer has claimed
+/// the rejection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(BinaryValue)]
+#[non_exhaustive]
+pub struct RejectedTransaction {
+    /// Hash of the rejected transaction.
+    pub tx_hash: Hash,
+    /// Kind of the error returned by the transaction check.
+    pub reason: ErrorKind,
+}
+
+impl RejectedTransaction {
+    /// Creates a new record about a rejected transaction.
+    pub fn new(tx_hash: Hash, reason: ErrorKind) -> Self {
+        Self { tx_hash, reason }
+    }
+}
+
+impl ProtobufConvert for RejectedTransaction {
+    type ProtoStruct = schema::blockchain::RejectedTransaction;
+
+    fn to_pb(&self) -> Self::ProtoStruct {
+        let mut inner = Self::ProtoStruct::default();
+        inner.set_tx_hash(self.tx_hash.to_pb());
+        let (kind, code) = self.reason.into_raw();
+        inner.set_kind(kind);
+        inner.set_code(u32::from(code));
+        inner
+    }
+
+    fn from_pb(mut pb: Self::ProtoStruct) -> anyhow::Result<Self> {
+        let tx_hash = Hash::from_pb(pb.take_tx_hash())?;
+        let code = u8::try_from(pb.code())?;
+        let reason = ErrorKind::from_raw(pb.kind(), code)?;
+        Ok(Self { tx_hash, reason })
+    }
+}
+
 /// Expandable set of headers allowed to be added to the block.
 ///
 /// In a serialized form, headers are represented as a sequence of
@@ -159,6 +346,32 @@ impl Block {
         self.get_header::<Epoch>().unwrap_or(None)
     }
 
+    /// Adds transaction statistics to this block.
+    pub(super) fn add_stats(&mut self, stats: BlockStats) {
+        self.add_header::<BlockStats>(stats);
+    }
+
+    /// Retrieves transaction statistics associated with this block, or `None` if the statistics
+    /// are not recorded. The statistics are not recorded for blocks without transactions.
+    pub fn stats(&self) -> Option<BlockStats> {
+        self.get_header::<BlockStats>().unwrap_or(None)
+    }
+
+    /// Retrieves the hash of transactions rejected by the block proposer, or `None` if
+    /// the block does not record rejected transactions.
+    pub fn rejected_transactions_hash(&self) -> Option<Hash> {
+        self.get_header::<RejectedTransactionsHash>()
+            .unwrap_or(None)
+    }
+
+    /// Retrieves the time of this block agreed upon by validators, or `None` if the block
+    /// does not record the time. See [`BlockTime`] for details.
+    ///
+    /// [`BlockTime`]: struct.BlockTime.html
+    pub fn time(&self) -> Option<OffsetDateTime> {
+        self.get_header::<BlockTime>().unwrap_or(None)
+    }
+
     /// Adds the `skip` flag to this block.
     pub(super) fn set_skip(&mut self) {
         self.add_header::<SkipFlag>(());
@@ -568,6 +781,72 @@ impl CallProof {
     }
 }
 
+/// Combined proof of authenticity for several top-level calls within a block.
+///
+/// Unlike separate [`CallProof`]s, the proof shares common branches of the Merkle tree
+/// among the calls, so it is considerably more compact if many calls are proven at once.
+/// The proof does not contain descriptions and backtraces of errors; these can be obtained
+/// from the individual `CallProof`s if necessary.
+///
+/// [`CallProof`]: struct.CallProof.html
+#[derive(Debug, Clone, Serialize, Deserialize, ProtobufConvert, BinaryValue)]
+#[protobuf_convert(source = "schema::proofs::CallsProof")]
+#[non_exhaustive]
+pub struct CallsProof {
+    /// Proof of authenticity for the block header.
+    #[serde(flatten)]
+    pub block_proof: BlockProof,
+
+    /// Proof of authenticity for the call statuses. The root hash of the proof must be equal
+    /// to the `error_hash` mentioned in `block_proof`.
+    pub calls_proof: MapProof<CallInBlock, ExecutionError>,
+}
+
+impl CallsProof {
+    /// Creates a new `CallsProof` object.
+    pub fn new(
+        block_proof: BlockProof,
+        calls_proof: MapProof<CallInBlock, ExecutionError>,
+    ) -> Self {
+        Self {
+            block_proof,
+            calls_proof,
+        }
+    }
+
+    /// Verifies this proof, returning the locations of the proven calls together with
+    /// their statuses. Errors in the returned statuses have empty descriptions.
+    ///
+    /// As with [`CallProof`], a successful status is indistinguishable from the status
+    /// of a non-existing call.
+    ///
+    /// [`CallProof`]: struct.CallProof.html
+    pub fn verify(
+        &self,
+        validator_keys: &[PublicKey],
+    ) -> Result<Vec<(CallInBlock, Result<(), ExecutionError>)>, ProofError> {
+        self.block_proof.verify(validator_keys)?;
+
+        let checked_proof = self
+            .calls_proof
+            .check_against_hash(self.block_proof.block.error_hash)
+            .map_err(ProofError::IncorrectEntryProof)?;
+        let statuses = checked_proof
+            .all_entries()
+            .map(|(call, maybe_error)| match maybe_error {
+                None => Ok((*call, Ok(()))),
+                Some(error) if error.has_empty_aux() => Ok((*call, Err(error.clone()))),
+                Some(_) => Err(ProofError::MalformedStatus),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if statuses.is_empty() {
+            return Err(ProofError::NoEntry);
+        }
+        Ok(statuses)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use assert_matches::assert_matches;
@@ -580,9 +859,10 @@ mod tests {
     use time::OffsetDateTime;
 
     use super::{
-        AdditionalHeaders, BinaryValue, Block, BlockHeaderKey, BlockProof, CallInBlock, CallProof,
-        Epoch, ExecutionError, ExecutionErrorAux, Hash, Height, IndexProof, MapProof, OrderedMap,
-        Precommit, ProofError, ProposerId, ProtobufConvert, ValidationError, ValidatorId, Verified,
+        AdditionalHeaders, BinaryValue, Block, BlockHeaderKey, BlockProof, BlockTime, CallInBlock,
+        CallProof, CallsProof, Epoch, ExecutionError, ExecutionErrorAux, Hash, Height, IndexProof,
+        MapProof, OrderedMap, Precommit, ProofError, ProposerId, ProtobufConvert, ValidationError,
+        ValidatorId, Verified,
     };
     use crate::{blockchain::Schema as CoreSchema, helpers::Round, runtime::InstanceId};
 
@@ -768,6 +1048,43 @@ mod tests {
         proof.verify(&public_keys).unwrap();
     }
 
+    #[test]
+    fn block_time_from_precommits() {
+        let keys: Vec<_> = (0..4).map(|_| KeyPair::random()).collect();
+        let base_time = OffsetDateTime::from_unix_timestamp(1_000_000).unwrap();
+        let precommits: Vec<_> = [3_i64, 1, 100, 2]
+            .iter()
+            .zip(&keys)
+            .enumerate()
+            .map(|(i, (&offset, keypair))| {
+                let precommit = Precommit::new(
+                    ValidatorId(i as u16),
+                    Height(1),
+                    Round(1),
+                    Hash::zero(),
+                    Hash::zero(),
+                    base_time + time::Duration::seconds(offset),
+                );
+                Verified::from_value(precommit, keypair.public_key(), keypair.secret_key())
+            })
+            .collect();
+
+        // For an even number of precommits, the upper median is used.
+        let expected_time = base_time + time::Duration::seconds(3);
+        assert_eq!(BlockTime::from_precommits(&precommits), Some(expected_time));
+        let expected_time = base_time + time::Duration::seconds(2);
+        assert_eq!(
+            BlockTime::from_precommits(&precommits[1..]),
+            Some(expected_time)
+        );
+        assert_eq!(BlockTime::from_precommits(&[]), None);
+
+        let mut block = create_block_proof(&keys, Hash::zero(), Hash::zero()).block;
+        assert_eq!(block.time(), None);
+        block.add_header::<BlockTime>(base_time);
+        assert_eq!(block.time(), Some(base_time));
+    }
+
     #[test]
     fn block_proof_without_epoch() {
         let mut block = Block {
@@ -1089,4 +1406,43 @@ mod tests {
         let err = call_proof.verify(&public_keys).unwrap_err();
         assert_matches!(err, ProofError::AmbiguousEntry);
     }
+
+    #[test]
+    fn combined_calls_proof() {
+        let (error_hash, calls_proof) = create_error_proof(CallProofKind::Ambiguous);
+        let keys: Vec<_> = (0..3).map(|_| KeyPair::random()).collect();
+        let public_keys: Vec<_> = keys.iter().map(KeyPair::public_key).collect();
+        let block_proof = create_block_proof(&keys, Hash::zero(), error_hash);
+
+        let mut proof = CallsProof::new(block_proof.clone(), calls_proof);
+        let restored_proof = CallsProof::from_bytes(proof.to_bytes().into()).unwrap();
+        let statuses = restored_proof.verify(&public_keys).unwrap();
+        assert_eq!(
+            statuses,
+            vec![
+                (
+                    CallInBlock::transaction(2),
+                    Err(ExecutionError::service(5, ""))
+                ),
+                (
+                    CallInBlock::after_transactions(0),
+                    Err(ExecutionError::service(16, ""))
+                ),
+            ]
+        );
+
+        proof.calls_proof = proof
+            .calls_proof
+            .map_values(|_| ExecutionError::service(6, ""));
+        let err = proof.verify(&public_keys).unwrap_err();
+        assert_matches!(
+            err,
+            ProofError::IncorrectEntryProof(ValidationError::UnmatchedRootHash)
+        );
+
+        let block_proof = create_block_proof(&keys, Hash::zero(), HashTag::empty_map_hash());
+        let proof = CallsProof::new(block_proof, MapProof::new());
+        let err = proof.verify(&public_keys).unwrap_err();
+        assert_matches!(err, ProofError::NoEntry);
+    }
 }
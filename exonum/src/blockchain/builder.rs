@@ -15,8 +15,8 @@
 //! The module responsible for the correct Exonum blockchain creation.
 
 use crate::{
-    blockchain::{config::GenesisConfig, Blockchain, BlockchainMut, Schema},
-    runtime::{Dispatcher, RuntimeInstance},
+    blockchain::{config::GenesisConfig, Blockchain, BlockchainMut, PruningConfig, Schema},
+    runtime::{Dispatcher, RuntimeInstance, ShadowInstance},
 };
 
 /// The object responsible for the correct Exonum blockchain creation from the components.
@@ -31,6 +31,10 @@ pub struct BlockchainBuilder {
     runtimes: Vec<RuntimeInstance>,
     /// Blockchain configuration used to create the genesis block.
     genesis_config: Option<GenesisConfig>,
+    /// Local-only shadow service instances.
+    shadows: Vec<ShadowInstance>,
+    /// Configuration of the historical data pruning.
+    pruning: Option<PruningConfig>,
 }
 
 impl BlockchainBuilder {
@@ -40,6 +44,8 @@ impl BlockchainBuilder {
             blockchain,
             runtimes: vec![],
             genesis_config: None,
+            shadows: vec![],
+            pruning: None,
         }
     }
 
@@ -58,6 +64,24 @@ impl BlockchainBuilder {
         self
     }
 
+    /// Adds a local-only shadow service instance. See [`ShadowInstance`] for details.
+    ///
+    /// [`ShadowInstance`]: ../runtime/struct.ShadowInstance.html
+    #[must_use]
+    pub fn with_shadow_instance(mut self, shadow: ShadowInstance) -> Self {
+        self.shadows.push(shadow);
+        self
+    }
+
+    /// Enables pruning of historical block data. See [`PruningConfig`] for details.
+    ///
+    /// [`PruningConfig`]: struct.PruningConfig.html
+    #[must_use]
+    pub fn with_pruning(mut self, pruning: PruningConfig) -> Self {
+        self.pruning = Some(pruning);
+        self
+    }
+
     /// Returns blockchain instance, creates and commits the genesis block with the specified
     /// genesis configuration if the blockchain has not been initialized.
     /// Otherwise restores dispatcher state from database.
@@ -67,6 +91,7 @@ impl BlockchainBuilder {
     /// - If the genesis config was not provided and the blockchain is not initialized.
     /// - If the genesis block cannot be created.
     /// - If storage version is not specified or not supported.
+    /// - If a shadow artifact cannot be deployed.
     pub fn build(self) -> BlockchainMut {
         const NO_GENESIS_CFG: &str =
             "No genesis config was provided for an uninitialized blockchain";
@@ -74,6 +99,7 @@ impl BlockchainBuilder {
         let mut blockchain = BlockchainMut {
             dispatcher: Dispatcher::new(&self.blockchain, self.runtimes),
             inner: self.blockchain,
+            pruning: self.pruning,
         };
 
         // If genesis block had been already created just restores dispatcher state from database
@@ -86,6 +112,13 @@ impl BlockchainBuilder {
             let genesis_config = self.genesis_config.expect(NO_GENESIS_CFG);
             blockchain.create_genesis_block(genesis_config);
         };
+
+        blockchain.inner.create_trace_ids_index();
+
+        let snapshot = blockchain.snapshot();
+        for shadow in self.shadows {
+            blockchain.dispatcher.add_shadow_instance(&snapshot, shadow);
+        }
         blockchain
     }
 }
@@ -76,6 +76,33 @@ impl ValidateInput for ValidatorKeys {
     }
 }
 
+/// Limit on the number of transactions of a service instance per block.
+///
+/// Quotas prevent a single service with a lot of transactions from filling whole blocks
+/// and starving other services. The quotas are enforced by the proposer of a block
+/// and are checked by other validators before voting for the proposal.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+#[derive(ProtobufConvert)]
+#[protobuf_convert(source = "schema::blockchain::ServiceTxQuota")]
+#[non_exhaustive]
+pub struct ServiceTxQuota {
+    /// Identifier of the service instance.
+    pub instance_id: InstanceId,
+    /// Maximum number of transactions of the service per block.
+    pub max_transactions: u32,
+}
+
+impl ServiceTxQuota {
+    /// Creates a new quota for the service.
+    pub fn new(instance_id: InstanceId, max_transactions: u32) -> Self {
+        Self {
+            instance_id,
+            max_transactions,
+        }
+    }
+}
+
 /// Consensus algorithm parameters.
 ///
 /// This configuration is initially created with default recommended values,
@@ -131,6 +158,23 @@ pub struct ConsensusConfig {
     /// in a block if the transaction pool is almost empty, and create blocks faster when there are
     /// enough transactions in the pool.
     pub propose_timeout_threshold: u32,
+    /// Limits on the number of transactions of individual services per block.
+    /// Services without a quota are only limited by `txs_block_limit`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub service_tx_quotas: Vec<ServiceTxQuota>,
+    /// Whether blocks record the time agreed upon by validators. If enabled, the block proposer
+    /// includes the precommits for the previous block into its proposal, and the median
+    /// of the times in these precommits is recorded as the [`BlockTime`] of the block.
+    /// The time is available to services via `ExecutionContext::block_time()`.
+    ///
+    /// [`BlockTime`]: ../struct.BlockTime.html
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub block_time: bool,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // Required by `serde`.
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 impl Default for ConsensusConfig {
@@ -145,6 +189,8 @@ impl Default for ConsensusConfig {
             min_propose_timeout: 10,
             max_propose_timeout: 200,
             propose_timeout_threshold: 500,
+            service_tx_quotas: Vec::default(),
+            block_time: false,
         }
     }
 }
@@ -162,6 +208,15 @@ impl ConsensusConfig {
         self
     }
 
+    /// Returns the maximum number of transactions of the specified service per block,
+    /// or `None` if the service has no quota.
+    pub fn service_tx_limit(&self, instance_id: InstanceId) -> Option<u32> {
+        self.service_tx_quotas
+            .iter()
+            .find(|quota| quota.instance_id == instance_id)
+            .map(|quota| quota.max_transactions)
+    }
+
     /// Generates a consensus configuration for testing and returns it together with the keys
     /// for the first validator.
     pub fn for_tests(validator_count: u16) -> (Self, Keys) {
@@ -421,6 +476,28 @@ impl ConsensusConfigBuilder {
 
         Self { config }
     }
+
+    /// Sets the `service_tx_quotas` field of `ConsensusConfig`.
+    #[must_use]
+    pub fn service_tx_quotas(self, service_tx_quotas: Vec<ServiceTxQuota>) -> Self {
+        let config = ConsensusConfig {
+            service_tx_quotas,
+            ..self.config
+        };
+
+        Self { config }
+    }
+
+    /// Sets the `block_time` field of `ConsensusConfig`.
+    #[must_use]
+    pub fn block_time(self, block_time: bool) -> Self {
+        let config = ConsensusConfig {
+            block_time,
+            ..self.config
+        };
+
+        Self { config }
+    }
 }
 
 impl ValidateInput for ConsensusConfig {
@@ -455,6 +532,23 @@ impl ValidateInput for ConsensusConfig {
             bail!("txs_block_limit should not be equal to zero",);
         }
 
+        // Check per-service transaction quotas.
+        let mut services_with_quotas = HashSet::with_capacity(self.service_tx_quotas.len());
+        for quota in &self.service_tx_quotas {
+            if quota.max_transactions == 0 {
+                bail!(
+                    "Transaction quota for service with ID {} should not be equal to zero",
+                    quota.instance_id
+                );
+            }
+            if !services_with_quotas.insert(quota.instance_id) {
+                bail!(
+                    "Duplicate transaction quotas for service with ID {}",
+                    quota.instance_id
+                );
+            }
+        }
+
         // Check maximum message length for sanity.
         if self.max_message_len < MINIMAL_MESSAGE_LENGTH {
             bail!(
@@ -609,7 +703,8 @@ mod tests {
     use std::fmt::Display;
 
     use super::{
-        ArtifactId, ConsensusConfig, GenesisConfigBuilder, PublicKey, ValidateInput, ValidatorKeys,
+        ArtifactId, ConsensusConfig, GenesisConfigBuilder, PublicKey, ServiceTxQuota,
+        ValidateInput, ValidatorKeys,
     };
     use crate::crypto::{self, gen_keypair_from_seed, Seed, SEED_LENGTH};
 
@@ -729,6 +824,20 @@ mod tests {
                 },
                 "max_message_len (0) must be at least",
             ),
+            (
+                ConsensusConfig {
+                    service_tx_quotas: vec![ServiceTxQuota::new(3, 0)],
+                    ..gen_consensus_config()
+                },
+                "Transaction quota for service with ID 3 should not be equal to zero",
+            ),
+            (
+                ConsensusConfig {
+                    service_tx_quotas: vec![ServiceTxQuota::new(3, 10), ServiceTxQuota::new(3, 5)],
+                    ..gen_consensus_config()
+                },
+                "Duplicate transaction quotas for service with ID 3",
+            ),
         ];
 
         for (cfg, expected_msg) in &cases {
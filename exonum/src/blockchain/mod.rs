@@ -16,13 +16,22 @@
 
 pub use self::{
     api_sender::{ApiSender, SendError},
+    backfill::{BackfillHandle, BackfillProgress, LocalIndex, TransactionsByAuthor},
     block::{
-        AdditionalHeaders, Block, BlockHeaderKey, BlockProof, CallProof, Epoch, IndexProof,
-        ProofError, ProposerId, SkipFlag,
+        AdditionalHeaders, Block, BlockHeaderKey, BlockProof, BlockStats, BlockTime, CallProof,
+        CallsProof, Epoch, IndexProof, ProofError, ProposerId, RejectedTransaction,
+        RejectedTransactionsHash, ServiceTxStats, SkipFlag,
     },
     builder::BlockchainBuilder,
-    config::{ConsensusConfig, ConsensusConfigBuilder, ValidatorKeys},
-    schema::{CallErrorsIter, CallInBlock, CallRecords, Schema, TxLocation},
+    config::{ConsensusConfig, ConsensusConfigBuilder, ServiceTxQuota, ValidatorKeys},
+    proof_cache::{ProofCache, ProofCacheKey, DEFAULT_PROOF_CACHE_BUDGET},
+    pruning::{HistoryPruned, PruningConfig},
+    replay::{BlockReplay, ReplayObserver},
+    schema::{
+        CallErrorsCheckpoint, CallErrorsIter, CallInBlock, CallRecords, IndexChange, Schema,
+        TxLocation,
+    },
+    value_cache::{CachedSnapshot, ValueCache},
 };
 pub use crate::runtime::TxCheckCache;
 
@@ -30,6 +39,8 @@ pub mod config;
 
 pub(crate) use crate::runtime::ExecutionError;
 
+use anyhow::ensure;
+
 use exonum_crypto::{Hash, KeyPair};
 use exonum_merkledb::{
     access::{Access, RawAccess},
@@ -37,21 +48,32 @@ use exonum_merkledb::{
     Snapshot, SystemSchema, TemporaryDB,
 };
 
+use time::OffsetDateTime;
+
 use std::{borrow::Cow, collections::BTreeMap, iter, sync::Arc};
 
 use crate::{
     blockchain::config::GenesisConfig,
     helpers::{Height, ValidateInput, ValidatorId},
     messages::{AnyTx, Precommit, Verified},
-    runtime::Dispatcher,
+    runtime::{CallHashes, Dispatcher, DispatcherSchema},
 };
 
 mod api_sender;
+mod backfill;
 mod block;
 mod builder;
+mod proof_cache;
+mod pruning;
+mod replay;
 mod schema;
 #[cfg(test)]
 pub mod tests;
+mod value_cache;
+
+/// Maximum length of a transaction trace ID in bytes.
+/// See [`Blockchain::record_trace_id`](struct.Blockchain.html#method.record_trace_id).
+pub const MAX_TRACE_ID_LEN: usize = 128;
 
 /// Iterator type for transactions in `TransactionCache`.
 pub type Transactions<'a> = Box<dyn Iterator<Item = (Hash, Cow<'a, Verified<AnyTx>>)> + 'a>;
@@ -179,6 +201,8 @@ pub struct Blockchain {
     api_sender: ApiSender,
     db: Arc<dyn Database>,
     service_keypair: KeyPair,
+    proof_cache: Arc<ProofCache>,
+    value_cache: Arc<ValueCache>,
 }
 
 impl Blockchain {
@@ -192,9 +216,43 @@ impl Blockchain {
             db: database.into(),
             service_keypair: service_keypair.into(),
             api_sender,
+            proof_cache: Arc::default(),
+            value_cache: Arc::default(),
         }
     }
 
+    /// Replaces the proof cache of the blockchain with a cache with the specified
+    /// memory budget in bytes. A zero budget disables proof caching.
+    ///
+    /// The cache is shared among the clones of the blockchain, so this method should be called
+    /// before the blockchain is cloned.
+    #[must_use]
+    pub fn with_proof_cache_budget(mut self, budget: usize) -> Self {
+        self.proof_cache = Arc::new(ProofCache::new(budget));
+        self
+    }
+
+    /// Returns the cache of proofs generated for client requests.
+    pub fn proof_cache(&self) -> &Arc<ProofCache> {
+        &self.proof_cache
+    }
+
+    /// Replaces the value cache of the blockchain with a cache holding at most `capacity`
+    /// decoded values. A zero capacity (the default) disables value caching.
+    ///
+    /// The cache is shared among the clones of the blockchain, so this method should be called
+    /// before the blockchain is cloned.
+    #[must_use]
+    pub fn with_value_cache_capacity(mut self, capacity: usize) -> Self {
+        self.value_cache = Arc::new(ValueCache::new(capacity));
+        self
+    }
+
+    /// Returns the cache of values decoded from the storage.
+    pub fn value_cache(&self) -> &Arc<ValueCache> {
+        &self.value_cache
+    }
+
     /// Creates a non-persisting blockchain, all data in which is irrevocably lost on drop.
     ///
     /// The created blockchain cannot send transactions; an attempt to do so will result
@@ -213,6 +271,74 @@ impl Blockchain {
         self.db.snapshot()
     }
 
+    /// Creates a read-only snapshot of the current storage state, reads from which
+    /// can be cached in the [value cache](#method.value_cache) of the blockchain.
+    pub fn cached_snapshot(&self) -> CachedSnapshot {
+        self.value_cache.snapshot(|| self.db.snapshot())
+    }
+
+    /// Merges node-local changes (e.g., bookkeeping data of runtimes) into the database
+    /// outside of the block processing. The changes are collected by `changes` in a fork
+    /// of the current database state and are synced to the disk.
+    ///
+    /// The changes must only affect non-Merkelized indexes, which do not influence
+    /// the blockchain state. Creating indexes in this way is only safe before the blockchain
+    /// starts processing blocks (e.g., when a runtime is initialized), since otherwise
+    /// identifiers of the created indexes may clash with the ones created in a block.
+    #[doc(hidden)] // used by runtimes, should not be used anywhere else
+    pub fn merge_node_local<F>(&self, changes: F) -> StorageResult<()>
+    where
+        F: FnOnce(&Fork),
+    {
+        let fork = self.db.fork();
+        changes(&fork);
+        self.value_cache
+            .invalidate_after(|| self.db.merge_sync(fork.into_patch()))
+    }
+
+    /// Records the trace ID provided by a client submitting the transaction to this node.
+    /// The trace ID is included into the node logs related to the transaction (adding it
+    /// to the pool, executing and committing it), which allows to correlate the client request
+    /// with its processing on the node. Trace IDs are node-local: they are not shared
+    /// with other nodes and do not influence the blockchain state.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the trace ID is empty, is longer than [`MAX_TRACE_ID_LEN`] bytes
+    /// or contains characters other than printable ASCII ones, or if the ID cannot be persisted.
+    ///
+    /// [`MAX_TRACE_ID_LEN`]: constant.MAX_TRACE_ID_LEN.html
+    pub fn record_trace_id(&self, tx_hash: Hash, trace_id: &str) -> anyhow::Result<()> {
+        ensure!(
+            !trace_id.is_empty() && trace_id.len() <= MAX_TRACE_ID_LEN,
+            "Trace ID must be non-empty and contain at most {} bytes",
+            MAX_TRACE_ID_LEN
+        );
+        ensure!(
+            trace_id.bytes().all(|byte| byte.is_ascii_graphic()),
+            "Trace ID must consist of printable ASCII characters"
+        );
+        self.merge_node_local(|fork| {
+            Schema::new(fork)
+                .trace_ids()
+                .put(&tx_hash, trace_id.to_owned());
+        })?;
+        Ok(())
+    }
+
+    /// Creates the index of trace IDs unless it exists. Since trace IDs are recorded
+    /// outside of the block processing, the index must be created before the blockchain
+    /// starts processing blocks.
+    pub(crate) fn create_trace_ids_index(&self) {
+        if Schema::new(&self.snapshot()).has_trace_ids() {
+            return;
+        }
+        self.merge_node_local(|fork| {
+            Schema::new(fork).trace_ids();
+        })
+        .expect("Cannot create index of trace IDs");
+    }
+
     /// Returns the hash of the latest committed block.
     /// If genesis block was not committed returns `Hash::zero()`.
     pub fn last_hash(&self) -> Hash {
@@ -303,6 +429,9 @@ pub struct BlockParams<'a> {
     proposer: ValidatorId,
     epoch: Height,
     contents: BlockContents<'a>,
+    rejected_transactions: &'a [RejectedTransaction],
+    time: Option<OffsetDateTime>,
+    record_call_hashes: bool,
 }
 
 impl<'a> BlockParams<'a> {
@@ -314,6 +443,9 @@ impl<'a> BlockParams<'a> {
             proposer,
             epoch,
             contents: BlockContents::Transactions(tx_hashes),
+            rejected_transactions: &[],
+            time: None,
+            record_call_hashes: false,
         }
     }
 
@@ -325,6 +457,9 @@ impl<'a> BlockParams<'a> {
             proposer,
             epoch,
             contents: BlockContents::Skip,
+            rejected_transactions: &[],
+            time: None,
+            record_call_hashes: false,
         }
     }
 
@@ -338,14 +473,59 @@ impl<'a> BlockParams<'a> {
             proposer,
             epoch,
             contents,
+            rejected_transactions: &[],
+            time: None,
+            record_call_hashes: false,
         }
     }
 
+    /// Sets transactions rejected by the proposer on admission to its transaction pool,
+    /// which will be recorded in the block. The transactions must be sorted by their hash
+    /// and must not be included into the block. Rejected transactions are ignored
+    /// for [block skips].
+    ///
+    /// [block skips]: enum.BlockContents.html#variant.Skip
+    #[must_use]
+    pub fn with_rejected_transactions(
+        mut self,
+        rejected_transactions: &'a [RejectedTransaction],
+    ) -> Self {
+        self.rejected_transactions = rejected_transactions;
+        self
+    }
+
+    /// Sets the time of the block agreed upon by validators. The time is recorded
+    /// in the [`BlockTime`] header of the block and is available to services during
+    /// block execution. The time is ignored for [block skips].
+    ///
+    /// [`BlockTime`]: struct.BlockTime.html
+    /// [block skips]: enum.BlockContents.html#variant.Skip
+    #[must_use]
+    pub fn with_time(mut self, time: OffsetDateTime) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Requests to record hashes of the changes made by each call within the block,
+    /// which are then available via [`BlockPatch::call_hashes()`]. Comparing these hashes
+    /// among nodes allows to pinpoint the call responsible for a divergence of the blockchain
+    /// state. Recording the hashes slows down block execution, so it is disabled by default.
+    ///
+    /// [`BlockPatch::call_hashes()`]: struct.BlockPatch.html#method.call_hashes
+    #[must_use]
+    pub fn with_call_hashes(mut self) -> Self {
+        self.record_call_hashes = true;
+        self
+    }
+
     fn for_genesis_block() -> Self {
         Self {
             proposer: ValidatorId(0),
             epoch: Height(0),
             contents: BlockContents::Transactions(&[]),
+            rejected_transactions: &[],
+            time: None,
+            record_call_hashes: false,
         }
     }
 }
@@ -410,6 +590,7 @@ pub struct BlockPatch {
     inner: Patch,
     block_hash: Hash,
     kind: BlockKind,
+    call_hashes: Vec<(CallInBlock, Hash)>,
 }
 
 impl BlockPatch {
@@ -427,6 +608,19 @@ impl BlockPatch {
     pub fn kind(&self) -> BlockKind {
         self.kind
     }
+
+    /// Returns hashes of the changes made by each call within the block, in the order
+    /// of execution. A hash covers changes made by the call itself (even if they are rolled back
+    /// because the call has failed), but not the bookkeeping performed by the core, such as
+    /// recording execution errors.
+    ///
+    /// The hashes are only recorded if requested via [`BlockParams::with_call_hashes()`];
+    /// otherwise, the returned slice is empty.
+    ///
+    /// [`BlockParams::with_call_hashes()`]: struct.BlockParams.html#method.with_call_hashes
+    pub fn call_hashes(&self) -> &[(CallInBlock, Hash)] {
+        &self.call_hashes
+    }
 }
 
 impl AsRef<Patch> for BlockPatch {
@@ -446,6 +640,7 @@ impl AsRef<Patch> for BlockPatch {
 pub struct BlockchainMut {
     inner: Blockchain,
     dispatcher: Dispatcher,
+    pruning: Option<PruningConfig>,
 }
 
 impl AsRef<Blockchain> for BlockchainMut {
@@ -479,7 +674,15 @@ impl BlockchainMut {
 
     /// Commits changes from the `patch` to the blockchain storage.
     pub fn merge(&mut self, patch: Patch) -> StorageResult<()> {
-        self.inner.db.merge(patch)
+        let db = &self.inner.db;
+        self.inner.value_cache.invalidate_after(|| db.merge(patch))
+    }
+
+    fn merge_batch(&mut self, patches: Vec<Patch>) -> StorageResult<()> {
+        let db = &self.inner.db;
+        self.inner
+            .value_cache
+            .invalidate_after(|| db.merge_batch(patches))
     }
 
     /// Creates and commits the genesis block with the given genesis configuration.
@@ -527,7 +730,7 @@ impl BlockchainMut {
         // We need to activate services before calling `create_patch()`; unlike all other blocks,
         // initial services are considered immediately active in the genesis block, i.e.,
         // their state should be included into `patch` created below.
-        let errors = self.dispatcher.after_transactions(&mut fork);
+        let errors = self.dispatcher.after_transactions(&mut fork, &mut None);
 
         // If there was at least one error during the genesis block creation, the block shouldn't be
         // created at all.
@@ -605,6 +808,7 @@ impl BlockchainMut {
             inner: fork.into_patch(),
             block_hash,
             kind: BlockKind::Skip,
+            call_hashes: vec![],
         }
     }
 
@@ -620,10 +824,20 @@ impl BlockchainMut {
         C: TransactionCache + ?Sized,
     {
         let height = Schema::new(&fork).next_height();
+        // The time should be available to services before any code in the block is executed.
+        Schema::new(&fork).set_block_time(block_data.time);
+
+        let mut call_hashes = if block_data.record_call_hashes {
+            Some(vec![])
+        } else {
+            None
+        };
 
         // Skip execution for genesis block.
         if height > Height(0) {
-            let errors = self.dispatcher.before_transactions(&mut fork);
+            let errors = self
+                .dispatcher
+                .before_transactions(&mut fork, &mut call_hashes);
             let mut schema = Schema::new(&fork);
             for (location, error) in errors {
                 schema.save_error(height, location, error);
@@ -631,21 +845,44 @@ impl BlockchainMut {
         }
 
         // Save & execute transactions.
+        let mut stats = BlockStats::new();
         for (index, hash) in (0..).zip(tx_hashes) {
-            self.execute_transaction(*hash, height, index, &mut fork, tx_cache);
+            self.execute_transaction(
+                *hash,
+                height,
+                index,
+                &mut fork,
+                tx_cache,
+                &mut stats,
+                &mut call_hashes,
+            );
         }
 
         // During processing of the genesis block, this hook is already called in another method.
         if height > Height(0) {
-            let errors = self.dispatcher.after_transactions(&mut fork);
+            let errors = self
+                .dispatcher
+                .after_transactions(&mut fork, &mut call_hashes);
             let mut schema = Schema::new(&fork);
             for (location, error) in errors {
                 schema.save_error(height, location, error);
             }
         }
 
-        let (patch, block) = self.create_block_header(fork, block_data, height, tx_hashes);
+        if !block_data.rejected_transactions.is_empty() {
+            let schema = Schema::new(&fork);
+            let mut locations = schema.rejected_transactions_locations();
+            for (index, rejected) in (0..).zip(block_data.rejected_transactions) {
+                locations.put(&rejected.tx_hash, TxLocation::new(height, index));
+            }
+            schema
+                .rejected_transactions(height)
+                .extend(block_data.rejected_transactions.iter().copied());
+        }
+
+        let (patch, block) = self.create_block_header(fork, block_data, height, tx_hashes, stats);
         log::trace!("Executing {:?}", block);
+        let index_changes = Self::service_index_changes(&patch);
 
         // Calculate block hash.
         let block_hash = block.object_hash();
@@ -655,11 +892,13 @@ impl BlockchainMut {
         schema.block_hashes_by_height().push(block_hash);
         // Save block.
         schema.blocks().put(&block_hash, block);
+        schema.index_changes(height).extend(index_changes);
 
         BlockPatch {
             inner: fork.into_patch(),
             block_hash,
             kind: BlockKind::Normal,
+            call_hashes: call_hashes.unwrap_or_default(),
         }
     }
 
@@ -669,12 +908,14 @@ impl BlockchainMut {
         block_data: &BlockParams<'_>,
         height: Height,
         tx_hashes: &[Hash],
+        stats: BlockStats,
     ) -> (Patch, Block) {
         let prev_hash = self.inner.last_hash();
 
         let mut schema = Schema::new(&fork);
         let error_hash = schema.call_errors_map(height).object_hash();
         let tx_hash = schema.block_transactions(height).object_hash();
+        let rejected_hash = schema.rejected_transactions(height).object_hash();
         schema.clear_block_skip();
 
         let patch = fork.into_patch();
@@ -691,10 +932,40 @@ impl BlockchainMut {
         };
         block.add_header::<ProposerId>(block_data.proposer);
         block.add_epoch(block_data.epoch);
+        if !tx_hashes.is_empty() {
+            block.add_stats(stats);
+        }
+        if !block_data.rejected_transactions.is_empty() {
+            block.add_header::<RejectedTransactionsHash>(rejected_hash);
+        }
+        if let Some(time) = block_data.time {
+            block.add_header::<BlockTime>(time);
+        }
 
         (patch, block)
     }
 
+    /// Summarizes changes made to the service indexes within the block. Indexes in migration
+    /// are not taken into account.
+    fn service_index_changes(patch: &Patch) -> Vec<IndexChange> {
+        let instances = DispatcherSchema::new(patch).instances();
+        let mut changes: Vec<_> = patch
+            .index_changes()
+            .into_iter()
+            .filter(|changes| !changes.address.is_in_migration())
+            .filter_map(|changes| {
+                let name = changes.address.name();
+                let service_name = &name[..name.find('.')?];
+                let instance = instances.get(service_name)?;
+                Some(IndexChange::new(instance.spec.id, changes))
+            })
+            .collect();
+        // The sort is stable, so the changes remain ordered by address within each service.
+        changes.sort_by_key(|change| change.instance_id);
+        changes
+    }
+
+    #[allow(clippy::too_many_arguments)]
     fn execute_transaction<C>(
         &self,
         tx_hash: Hash,
@@ -702,6 +973,8 @@ impl BlockchainMut {
         index: u32,
         fork: &mut Fork,
         tx_cache: &C,
+        stats: &mut BlockStats,
+        call_hashes: &mut CallHashes,
     ) where
         C: TransactionCache + ?Sized,
     {
@@ -709,8 +982,22 @@ impl BlockchainMut {
             .get_transaction(tx_hash)
             .unwrap_or_else(|| panic!("BUG: Cannot find transaction {:?} in database", tx_hash));
         fork.flush();
-
-        let tx_result = self.dispatcher.execute(fork, tx_hash, index, &transaction);
+        stats.record_transaction(&transaction);
+
+        let tx_result = self
+            .dispatcher
+            .execute(fork, tx_hash, index, &transaction, call_hashes);
+        // The trace ID is read via a readonly fork, so that the index is never created
+        // within the block.
+        if let Some(trace_id) = Schema::new(fork.readonly()).trace_id(&tx_hash) {
+            log::debug!(
+                "Executed transaction {} at height {}: {:?} (trace_id={})",
+                tx_hash.to_hex(),
+                height,
+                tx_result,
+                trace_id
+            );
+        }
         let mut schema = Schema::new(&*fork);
 
         if let Err(e) = tx_result {
@@ -730,6 +1017,25 @@ impl BlockchainMut {
     /// [`create_patch()`]: #method.create_patch
     /// [`BlockContents`]: enum.BlockContents.html
     pub fn commit<I>(&mut self, patch: BlockPatch, precommits: I) -> anyhow::Result<()>
+    where
+        I: IntoIterator<Item = Verified<Precommit>>,
+    {
+        self.commit_with_patches(Vec::new(), patch, precommits)
+    }
+
+    /// Same as [`commit()`], but additionally merges `patches` (e.g., changes to the node schema)
+    /// before the block patch. All patches are merged with a single [`Database::merge_batch()`]
+    /// call, which is atomic and syncs the database to the disk only once for supported
+    /// database backends.
+    ///
+    /// [`commit()`]: #method.commit
+    /// [`Database::merge_batch()`]: https://docs.rs/exonum-merkledb/latest/exonum_merkledb/trait.Database.html#method.merge_batch
+    pub fn commit_with_patches<I>(
+        &mut self,
+        mut patches: Vec<Patch>,
+        patch: BlockPatch,
+        precommits: I,
+    ) -> anyhow::Result<()>
     where
         I: IntoIterator<Item = Verified<Precommit>>,
     {
@@ -739,21 +1045,35 @@ impl BlockchainMut {
 
         match patch.kind {
             BlockKind::Skip => {
-                self.merge(fork.into_patch())?;
+                patches.push(fork.into_patch());
+                self.merge_batch(patches)?;
             }
             BlockKind::Normal => {
-                let patch = self.dispatcher.commit_block_and_notify_runtimes(fork);
-                self.merge(patch)?;
-
-                // TODO: this makes `commit` non-atomic; can this be avoided? (ECR-4319)
-                let new_fork = self.fork();
-                Schema::new(&new_fork).update_transaction_count();
-                self.merge(new_fork.into_patch())?;
+                let block_patch = self.dispatcher.commit_block_and_notify_runtimes(fork);
+                let fork = Fork::from(block_patch);
+                Schema::new(&fork).update_transaction_count();
+                patches.push(fork.into_patch());
+                self.merge_batch(patches)?;
+
+                let committed_height = Schema::new(&self.snapshot()).height();
+                self.inner.proof_cache.invalidate(committed_height);
+                if let Some(pruning) = self.pruning {
+                    self.prune_history(pruning.retain_from(committed_height))?;
+                }
             }
         }
         Ok(())
     }
 
+    /// Removes historical data for the blocks preceding `retain_from`.
+    fn prune_history(&mut self, retain_from: Height) -> StorageResult<()> {
+        let fork = self.fork();
+        if Schema::new(&fork).prune_history(retain_from) {
+            self.merge(fork.into_patch())?;
+        }
+        Ok(())
+    }
+
     /// Adds a transaction into pool of uncommitted transactions.
     ///
     /// Unlike the corresponding method in the core schema, this method checks if the
@@ -0,0 +1,388 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bounded cache of generated proofs.
+
+use exonum_merkledb::Snapshot;
+use exonum_proto::ProtobufConvert;
+use protobuf::Message;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use crate::{
+    blockchain::{BlockProof, IndexProof},
+    helpers::Height,
+    runtime::SnapshotExt,
+};
+
+/// Default memory budget of the proof cache, in bytes (16 MiB).
+pub const DEFAULT_PROOF_CACHE_BUDGET: usize = 16 << 20;
+
+/// Approximate memory overhead of a cache entry besides the key and the proof bytes.
+const ENTRY_OVERHEAD: usize = 64;
+
+/// Key of a proof in the [`ProofCache`].
+///
+/// [`ProofCache`]: struct.ProofCache.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProofCacheKey {
+    height: Height,
+    index: String,
+    keys: Vec<u8>,
+}
+
+impl ProofCacheKey {
+    /// Creates a key for a proof of the `keys` in the index with the given full name,
+    /// generated for the blockchain state at `height`. `keys` may be an arbitrary
+    /// serialization of the proven keys or key range; it is only compared for equality.
+    pub fn new(height: Height, index: impl Into<String>, keys: impl Into<Vec<u8>>) -> Self {
+        Self {
+            height,
+            index: index.into(),
+            keys: keys.into(),
+        }
+    }
+
+    /// Creates a key for the proof of a block at the specified height.
+    pub fn block(height: Height) -> Self {
+        Self::new(height, String::new(), vec![])
+    }
+
+    /// Returns the height of the blockchain state the proof was generated for.
+    pub fn height(&self) -> Height {
+        self.height
+    }
+
+    /// Checks if the key corresponds to a block proof. Unlike other proofs, block proofs
+    /// do not depend on the latest blockchain state and are not invalidated on commit.
+    pub fn is_block(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    fn size(&self) -> usize {
+        self.index.len() + self.keys.len()
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    proof: Vec<u8>,
+    last_access: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheInner {
+    entries: HashMap<ProofCacheKey, CacheEntry>,
+    /// Keys of the entries ordered by the last access.
+    access_order: BTreeMap<u64, ProofCacheKey>,
+    access_counter: u64,
+    size: usize,
+}
+
+impl CacheInner {
+    fn entry_size(key: &ProofCacheKey, proof: &[u8]) -> usize {
+        key.size() + proof.len() + ENTRY_OVERHEAD
+    }
+
+    fn next_access(&mut self) -> u64 {
+        self.access_counter += 1;
+        self.access_counter
+    }
+
+    fn get(&mut self, key: &ProofCacheKey) -> Option<Vec<u8>> {
+        let access = self.next_access();
+        let entry = self.entries.get_mut(key)?;
+        self.access_order.remove(&entry.last_access);
+        self.access_order.insert(access, key.clone());
+        entry.last_access = access;
+        Some(entry.proof.clone())
+    }
+
+    fn insert(&mut self, key: ProofCacheKey, proof: Vec<u8>, budget: usize) {
+        let entry_size = Self::entry_size(&key, &proof);
+        if entry_size > budget {
+            return;
+        }
+        self.remove(&key);
+        while self.size + entry_size > budget {
+            let oldest_key = self
+                .access_order
+                .values()
+                .next()
+                .cloned()
+                .expect("BUG: proof cache size is inconsistent with its entries");
+            self.remove(&oldest_key);
+        }
+
+        let access = self.next_access();
+        self.access_order.insert(access, key.clone());
+        self.entries.insert(
+            key,
+            CacheEntry {
+                proof,
+                last_access: access,
+            },
+        );
+        self.size += entry_size;
+    }
+
+    fn remove(&mut self, key: &ProofCacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.access_order.remove(&entry.last_access);
+            self.size -= Self::entry_size(key, &entry.proof);
+        }
+    }
+
+    fn retain(&mut self, mut predicate: impl FnMut(&ProofCacheKey) -> bool) {
+        let stale_keys: Vec<_> = self
+            .entries
+            .keys()
+            .filter(|key| !predicate(key))
+            .cloned()
+            .collect();
+        for key in &stale_keys {
+            self.remove(key);
+        }
+    }
+}
+
+/// Bounded cache of proofs generated for client requests.
+///
+/// Proofs are stored in the serialized form, which allows to bound the memory occupied
+/// by the cache. Once the memory budget is exhausted, the least recently used proofs
+/// are evicted. Proofs depending on the latest blockchain state are invalidated each time
+/// a new block is committed; block proofs are retained until evicted.
+///
+/// The cache is shared among all clones of a [`Blockchain`] and can be accessed with
+/// [`Blockchain::proof_cache()`].
+///
+/// [`Blockchain`]: struct.Blockchain.html
+/// [`Blockchain::proof_cache()`]: struct.Blockchain.html#method.proof_cache
+#[derive(Debug)]
+pub struct ProofCache {
+    budget: usize,
+    inner: Mutex<CacheInner>,
+}
+
+impl Default for ProofCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_PROOF_CACHE_BUDGET)
+    }
+}
+
+impl ProofCache {
+    /// Creates a cache with the specified memory budget in bytes. A zero budget disables
+    /// caching.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Returns the memory budget of the cache in bytes.
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Returns the approximate memory currently occupied by the cached proofs, in bytes.
+    pub fn size(&self) -> usize {
+        self.inner.lock().unwrap().size
+    }
+
+    /// Returns the number of cached proofs.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Checks if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the proof with the specified key, generating and caching it with `generate`
+    /// if it is not in the cache. If `generate` returns `None`, nothing is cached.
+    ///
+    /// The proofs are cached in the serialized form, thus the same key should always
+    /// be used with the same proof type.
+    pub fn get_or_insert_with<P, F>(&self, key: ProofCacheKey, generate: F) -> Option<P>
+    where
+        P: ProtobufConvert,
+        P::ProtoStruct: Message,
+        F: FnOnce() -> Option<P>,
+    {
+        if self.budget == 0 {
+            return generate();
+        }
+
+        let cached = self.inner.lock().unwrap().get(&key);
+        if let Some(bytes) = cached {
+            let proof = P::ProtoStruct::parse_from_bytes(&bytes)
+                .map_err(anyhow::Error::from)
+                .and_then(P::from_pb);
+            match proof {
+                Ok(proof) => return Some(proof),
+                Err(err) => {
+                    log::warn!("Cannot restore cached proof for {:?}: {}", key, err);
+                    self.inner.lock().unwrap().remove(&key);
+                }
+            }
+        }
+
+        let proof = generate()?;
+        match proof.to_pb().write_to_bytes() {
+            Ok(bytes) => self.inner.lock().unwrap().insert(key, bytes, self.budget),
+            Err(err) => log::warn!("Cannot serialize proof for {:?}: {}", key, err),
+        }
+        Some(proof)
+    }
+
+    /// Returns the proof for the block at the specified height, or `None` if the block
+    /// is not committed yet.
+    pub fn block_proof(&self, snapshot: &dyn Snapshot, height: Height) -> Option<BlockProof> {
+        self.get_or_insert_with(ProofCacheKey::block(height), || {
+            snapshot.for_core().block_and_precommits(height)
+        })
+    }
+
+    /// Returns the proof for a Merkelized index with the specified full name
+    /// in the latest blockchain state. See [`SnapshotExt::proof_for_index()`]
+    /// for details.
+    ///
+    /// [`SnapshotExt::proof_for_index()`]: ../runtime/trait.SnapshotExt.html#tymethod.proof_for_index
+    pub fn index_proof(&self, snapshot: &dyn Snapshot, index_name: &str) -> Option<IndexProof> {
+        let height = snapshot.for_core().height();
+        let key = ProofCacheKey::new(height, index_name, vec![]);
+        self.get_or_insert_with(key, || snapshot.proof_for_index(index_name))
+    }
+
+    /// Invalidates proofs depending on the blockchain state preceding the block
+    /// at `committed_height`.
+    pub(crate) fn invalidate(&self, committed_height: Height) {
+        if self.budget == 0 {
+            return;
+        }
+        self.inner
+            .lock()
+            .unwrap()
+            .retain(|key| key.is_block() || key.height >= committed_height);
+    }
+
+    /// Removes all proofs from the cache.
+    pub fn clear(&self) {
+        *self.inner.lock().unwrap() = CacheInner::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::Blockchain;
+
+    fn block_proof_size(cache: &ProofCache) -> usize {
+        cache.size() / cache.len()
+    }
+
+    #[test]
+    fn cached_block_proofs() {
+        let blockchain = Blockchain::build_for_tests()
+            .into_mut_with_dummy_config()
+            .build();
+        let snapshot = blockchain.snapshot();
+        let cache = ProofCache::default();
+
+        assert!(cache.block_proof(snapshot.as_ref(), Height(1)).is_none());
+        assert!(cache.is_empty());
+
+        let proof = cache.block_proof(snapshot.as_ref(), Height(0)).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(
+            cache.block_proof(snapshot.as_ref(), Height(0)).unwrap(),
+            proof
+        );
+        assert_eq!(cache.len(), 1);
+
+        cache.invalidate(Height(1));
+        assert_eq!(
+            cache.block_proof(snapshot.as_ref(), Height(0)).unwrap(),
+            proof
+        );
+        assert_eq!(cache.len(), 1);
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.size(), 0);
+    }
+
+    #[test]
+    fn index_proofs_are_invalidated() {
+        let blockchain = Blockchain::build_for_tests()
+            .into_mut_with_dummy_config()
+            .build();
+        let snapshot = blockchain.snapshot();
+        let cache = ProofCache::default();
+
+        assert!(cache
+            .index_proof(snapshot.as_ref(), "core.nonexisting")
+            .is_none());
+        assert!(cache.is_empty());
+        let proof = cache
+            .index_proof(snapshot.as_ref(), "core.consensus_config")
+            .unwrap();
+        let cached_proof = cache
+            .index_proof(snapshot.as_ref(), "core.consensus_config")
+            .unwrap();
+        assert_eq!(cached_proof.index_proof, proof.index_proof);
+        assert_eq!(cache.len(), 1);
+
+        cache.invalidate(Height(0));
+        assert_eq!(cache.len(), 1);
+        cache.invalidate(Height(1));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn cache_respects_memory_budget() {
+        let blockchain = Blockchain::build_for_tests()
+            .into_mut_with_dummy_config()
+            .build();
+        let snapshot = blockchain.snapshot();
+        let cache = ProofCache::default();
+        cache.block_proof(snapshot.as_ref(), Height(0)).unwrap();
+        let entry_size = block_proof_size(&cache);
+
+        // Cache with space for a single proof.
+        let cache = ProofCache::new(entry_size * 3 / 2);
+        let key = |i| ProofCacheKey::new(Height(0), "test", vec![i]);
+        let generate = || snapshot.for_core().block_and_precommits(Height(0));
+        cache.get_or_insert_with(key(0), generate).unwrap();
+        assert_eq!(cache.len(), 1);
+        cache.get_or_insert_with(key(1), generate).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.size() <= cache.budget());
+
+        let mut generated = false;
+        cache.get_or_insert_with(key(1), || {
+            generated = true;
+            generate()
+        });
+        assert!(!generated);
+
+        let cache = ProofCache::new(0);
+        cache.get_or_insert_with(key(0), generate).unwrap();
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Local pruning of historical block data.
+
+use anyhow::ensure;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::helpers::{Height, ValidateInput};
+
+/// Configuration of the local pruning of historical block data.
+///
+/// If pruning is enabled, the node removes per-block indexes (together with
+/// their internal nodes) for blocks older than the configured number of heights after
+/// each block commit. Pruned data includes:
+///
+/// - Lists of transactions in blocks ([`Schema::block_transactions`])
+/// - Lists of rejected transactions ([`Schema::rejected_transactions`])
+/// - Summaries of index changes ([`Schema::index_changes`])
+/// - Call errors in blocks preceding the [call errors checkpoint]; errors in the later
+///   blocks are retained, since they are dropped by the checkpoint, which is a part
+///   of the blockchain state.
+///
+/// Block headers, precommits and transactions themselves are retained, so the pruned node
+/// can still prove authenticity of any block. However, it cannot serve pruned blocks
+/// to the lagging peers or build proofs of transaction inclusion into pruned blocks.
+/// The height from which the data is retained is available via
+/// [`Schema::history_retained_from`]; readers of the pruned data should check it
+/// with [`Schema::check_history_retained`] rather than treat pruned blocks as empty.
+///
+/// Pruning removes whole per-block indexes. Unlike per-block indexes, Merkelized indexes
+/// of the blockchain state (e.g., `ProofMapIndex` or `ProofListIndex` of a service)
+/// are not pruned: MerkleDB does not keep obsolete versions of their internal nodes,
+/// which are overwritten or removed in place on each update, so there is nothing
+/// to garbage-collect.
+///
+/// Pruning does not affect the blockchain state hash and may be enabled or disabled
+/// by each node independently.
+///
+/// [`Schema::block_transactions`]: struct.Schema.html#method.block_transactions
+/// [`Schema::rejected_transactions`]: struct.Schema.html#method.rejected_transactions
+/// [`Schema::index_changes`]: struct.Schema.html#method.index_changes
+/// [call errors checkpoint]: struct.Schema.html#method.call_errors_checkpoint
+/// [`Schema::history_retained_from`]: struct.Schema.html#method.history_retained_from
+/// [`Schema::check_history_retained`]: struct.Schema.html#method.check_history_retained
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PruningConfig {
+    /// Number of the latest blocks for which historical data is retained.
+    /// Must be positive.
+    pub retained_blocks: u64,
+}
+
+impl PruningConfig {
+    /// Creates a configuration retaining historical data for the specified number
+    /// of the latest blocks.
+    pub fn new(retained_blocks: u64) -> Self {
+        Self { retained_blocks }
+    }
+
+    /// Returns the height of the first block for which historical data should be retained
+    /// if the latest committed block has the specified height.
+    pub(super) fn retain_from(self, latest_height: Height) -> Height {
+        Height(latest_height.next().0.saturating_sub(self.retained_blocks))
+    }
+}
+
+impl ValidateInput for PruningConfig {
+    type Error = anyhow::Error;
+
+    fn validate(&self) -> Result<(), Self::Error> {
+        ensure!(
+            self.retained_blocks > 0,
+            "`retained_blocks` must be strictly larger than 0"
+        );
+        Ok(())
+    }
+}
+
+/// Error returned when historical data for a block is requested, but the data
+/// has been pruned by the node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error(
+    "Historical data for block at height {height} is pruned; the node retains data \
+     starting from height {retained_from}"
+)]
+#[non_exhaustive]
+pub struct HistoryPruned {
+    /// Height of the requested block.
+    pub height: Height,
+    /// Height of the first block for which historical data is retained.
+    pub retained_from: Height,
+}
@@ -0,0 +1,178 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Replaying blocks from an existing blockchain database.
+
+use anyhow::{ensure, format_err};
+use exonum_crypto::Hash;
+use exonum_merkledb::{ObjectHash, Snapshot};
+
+use std::collections::BTreeMap;
+
+use super::{Block, BlockParams, BlockchainMut, ProposerId, Schema};
+use crate::{
+    helpers::{Height, ValidatorId},
+    messages::{AnyTx, Verified},
+};
+
+/// Instrumentation callbacks invoked during a block replay.
+///
+/// The trait is implemented for closures with the `FnMut(&Block, &dyn Snapshot)` signature,
+/// which are invoked as `after_block` callbacks.
+pub trait ReplayObserver {
+    /// Invoked before executing a block. `snapshot` reflects the blockchain state
+    /// before the block.
+    fn before_block(&mut self, _block: &Block, _snapshot: &dyn Snapshot) {}
+
+    /// Invoked after executing a block, but before it is committed. `snapshot` reflects
+    /// the blockchain state after the block; it can be used to inspect results
+    /// of the executed transactions and service hooks via the core [`Schema`].
+    ///
+    /// [`Schema`]: struct.Schema.html
+    fn after_block(&mut self, block: &Block, snapshot: &dyn Snapshot);
+}
+
+impl<F> ReplayObserver for F
+where
+    F: FnMut(&Block, &dyn Snapshot),
+{
+    fn after_block(&mut self, block: &Block, snapshot: &dyn Snapshot) {
+        self(block, snapshot)
+    }
+}
+
+/// Re-executes blocks recorded in an existing blockchain database without any networking.
+///
+/// The replay reads blocks and transactions from the `source` snapshot (e.g., a snapshot
+/// of a node database opened in the read-only mode) and executes them on a separate
+/// blockchain, which should be created from an empty database with the same genesis config
+/// and runtimes as the original blockchain. Since nodes do not keep historical states,
+/// blocks are always replayed sequentially starting from the genesis block; use
+/// [`ReplayObserver`] to inspect the blocks of interest.
+///
+/// Each replayed block is checked to coincide with the original one, including the state hash.
+/// Runtimes are notified about replayed blocks as usual, so the blockchain should use
+/// a closed [`ApiSender`] to prevent services from broadcasting transactions.
+///
+/// The source database must not be pruned (see [`PruningConfig`]); replaying a pruned block
+/// returns a [`HistoryPruned`] error.
+///
+/// [`ReplayObserver`]: trait.ReplayObserver.html
+/// [`ApiSender`]: struct.ApiSender.html
+/// [`PruningConfig`]: struct.PruningConfig.html
+/// [`HistoryPruned`]: struct.HistoryPruned.html
+#[derive(Debug)]
+pub struct BlockReplay {
+    source: Box<dyn Snapshot>,
+    blockchain: BlockchainMut,
+}
+
+impl BlockReplay {
+    /// Creates a replay of the blocks from the `source` snapshot on the specified blockchain.
+    /// Returns an error if the genesis blocks of the blockchains differ.
+    pub fn new(source: Box<dyn Snapshot>, blockchain: BlockchainMut) -> anyhow::Result<Self> {
+        let source_genesis = Schema::new(source.as_ref()).block_hash_by_height(Height(0));
+        let genesis = blockchain.as_ref().last_block();
+        ensure!(
+            genesis.height == Height(0),
+            "Blockchain used for replay should contain only the genesis block"
+        );
+        ensure!(
+            source_genesis == Some(genesis.object_hash()),
+            "Genesis block of the source database differs from the replayed one; \
+             check that the genesis config and runtimes coincide"
+        );
+        Ok(Self { source, blockchain })
+    }
+
+    /// Returns the blockchain on which blocks are replayed.
+    pub fn blockchain(&self) -> &BlockchainMut {
+        &self.blockchain
+    }
+
+    /// Returns the height of the last block in the source database.
+    pub fn source_height(&self) -> Height {
+        Schema::new(self.source.as_ref()).height()
+    }
+
+    /// Returns the height of the next block to replay.
+    pub fn next_height(&self) -> Height {
+        Schema::new(self.blockchain.snapshot().as_ref()).next_height()
+    }
+
+    /// Replays the next block and returns it.
+    pub fn replay_block(&mut self, observer: &mut dyn ReplayObserver) -> anyhow::Result<Block> {
+        let height = self.next_height();
+        let source_schema = Schema::new(self.source.as_ref());
+        // Transactions of pruned blocks are unknown, so such blocks cannot be replayed.
+        source_schema.check_history_retained(height)?;
+        let block_hash = source_schema
+            .block_hash_by_height(height)
+            .ok_or_else(|| format_err!("Block at height {} is absent from the source", height))?;
+        let block = source_schema
+            .blocks()
+            .get(&block_hash)
+            .ok_or_else(|| format_err!("Block with hash {:?} is absent", block_hash))?;
+
+        let tx_hashes: Vec<Hash> = source_schema.block_transactions(height).iter().collect();
+        let transactions = source_schema.transactions();
+        let tx_cache = tx_hashes
+            .iter()
+            .map(|tx_hash| {
+                let tx = transactions
+                    .get(tx_hash)
+                    .ok_or_else(|| format_err!("Transaction {:?} is absent", tx_hash))?;
+                Ok((*tx_hash, tx))
+            })
+            .collect::<anyhow::Result<BTreeMap<Hash, Verified<AnyTx>>>>()?;
+
+        observer.before_block(&block, self.blockchain.snapshot().as_ref());
+        let proposer = block.get_header::<ProposerId>()?.unwrap_or(ValidatorId(0));
+        let epoch = block.epoch().unwrap_or(height);
+        let patch = self
+            .blockchain
+            .create_patch(BlockParams::new(proposer, epoch, &tx_hashes), &tx_cache);
+        ensure!(
+            patch.block_hash() == block_hash,
+            "Replayed block at height {} differs from the original one",
+            height
+        );
+        observer.after_block(&block, patch.as_ref());
+
+        let precommits = source_schema
+            .precommits(&block_hash)
+            .iter()
+            .collect::<Vec<_>>();
+        self.blockchain.commit(patch, precommits)?;
+        Ok(block)
+    }
+
+    /// Replays blocks until the specified height (inclusive).
+    pub fn replay_until(
+        &mut self,
+        height: Height,
+        observer: &mut dyn ReplayObserver,
+    ) -> anyhow::Result<()> {
+        ensure!(
+            height <= self.source_height(),
+            "Height {} exceeds the height of the source database ({})",
+            height,
+            self.source_height()
+        );
+        while self.next_height() <= height {
+            self.replay_block(observer)?;
+        }
+        Ok(())
+    }
+}
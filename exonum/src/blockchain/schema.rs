@@ -18,15 +18,21 @@ use exonum_merkledb::{
     access::{Access, AccessExt, RawAccessMut},
     impl_binary_key_for_binary_value,
     indexes::{Entries, Values},
-    Entry, KeySetIndex, ListIndex, MapIndex, ObjectHash, ProofEntry, ProofListIndex, ProofMapIndex,
+    Entry, Fork, IndexChanges, KeySetIndex, ListIndex, MapIndex, ObjectHash, ProofEntry,
+    ProofListIndex, ProofMapIndex,
 };
-use exonum_proto::ProtobufConvert;
+use exonum_proto::{ProtobufBase64, ProtobufConvert};
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use time::OffsetDateTime;
+
+use std::{cmp, fmt};
 
 use crate::{
-    blockchain::{Block, BlockProof, CallProof, ConsensusConfig},
-    crypto::{Hash, PublicKey},
+    blockchain::{
+        Block, BlockProof, CallProof, CallsProof, ConsensusConfig, HistoryPruned,
+        RejectedTransaction,
+    },
+    crypto::{self, Hash, PublicKey},
     helpers::{Height, ValidatorId},
     messages::{AnyTx, Precommit, Verified},
     proto::schema::blockchain as pb_blockchain,
@@ -48,6 +54,7 @@ define_names!(
     TRANSACTIONS => "transactions";
     CALL_ERRORS => "call_errors";
     CALL_ERRORS_AUX => "call_errors_aux";
+    CALL_ERRORS_CHECKPOINT => "call_errors_checkpoint";
     TRANSACTIONS_LEN => "transactions_len";
     TRANSACTIONS_POOL => "transactions_pool";
     TRANSACTIONS_POOL_LEN => "transactions_pool_len";
@@ -56,8 +63,14 @@ define_names!(
     BLOCK_HASHES_BY_HEIGHT => "block_hashes_by_height";
     BLOCK_TRANSACTIONS => "block_transactions";
     BLOCK_SKIP => "block_skip";
+    BLOCK_TIME => "block_time";
+    INDEX_CHANGES => "index_changes";
+    REJECTED_TRANSACTIONS => "rejected_transactions";
+    REJECTED_TRANSACTIONS_LOCATIONS => "rejected_transactions_locations";
     PRECOMMITS => "precommits";
     CONSENSUS_CONFIG => "consensus_config";
+    HISTORY_RETAINED_FROM => "history_retained_from";
+    TRACE_IDS => "trace_ids";
 );
 
 /// Transaction location in a block. Defines the block where the transaction was
@@ -93,6 +106,98 @@ impl TxLocation {
     }
 }
 
+/// Aggregate digest of call errors removed from the storage by
+/// [`Schema::checkpoint_call_errors`].
+///
+/// Since errors within each block are committed to in the block header (`error_hash`),
+/// detailed error records for old blocks may be dropped to bound the storage size.
+/// The checkpoint retains the number of dropped errors and a hash chain of the error hashes
+/// of the corresponding blocks, which can be verified against the block headers: starting
+/// from the zero hash, each block in the ascending height order updates the chain as
+/// `hash(errors_hash || block.error_hash)`.
+///
+/// [`Schema::checkpoint_call_errors`]: struct.Schema.html#method.checkpoint_call_errors
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash)]
+#[protobuf_convert(source = "pb_blockchain::CallErrorsCheckpoint")]
+#[non_exhaustive]
+pub struct CallErrorsCheckpoint {
+    /// Height of the first block for which call errors are retained.
+    pub height: Height,
+    /// Total number of errors dropped from the storage.
+    pub error_count: u64,
+    /// Hash chain of the error hashes of blocks preceding `height`.
+    pub errors_hash: Hash,
+}
+
+impl Default for CallErrorsCheckpoint {
+    fn default() -> Self {
+        Self {
+            height: Height(0),
+            error_count: 0,
+            errors_hash: Hash::zero(),
+        }
+    }
+}
+
+impl CallErrorsCheckpoint {
+    /// Updates the hash chain and the error count with the errors of the next block.
+    fn push_block(&mut self, error_hash: Hash, error_count: u64) {
+        self.errors_hash = crypto::hash(&[self.errors_hash.as_ref(), error_hash.as_ref()].concat());
+        self.error_count += error_count;
+        self.height = self.height.next();
+    }
+}
+
+/// Summary of changes made to a service index within a block.
+///
+/// Summaries are recorded for each block by the core and can be retrieved via
+/// [`Schema::index_changes`]. External indexers can use them to fetch only the data
+/// affected by the block instead of re-reading entire service indexes. Since summaries
+/// are stored in a non-merkelized index, they are not authenticated by validators.
+///
+/// [`Schema::index_changes`]: struct.Schema.html#method.index_changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+#[derive(ProtobufConvert, BinaryValue)]
+#[protobuf_convert(source = "pb_blockchain::IndexChange")]
+#[non_exhaustive]
+pub struct IndexChange {
+    /// Identifier of the service instance owning the index.
+    pub instance_id: InstanceId,
+    /// Full name of the index, including the service name prefix.
+    pub name: String,
+    /// Key of the index within its group. The key is empty for indexes not belonging
+    /// to a group.
+    #[serde(with = "ProtobufBase64")]
+    pub key: Vec<u8>,
+    /// Number of keys put into the index.
+    pub puts: u64,
+    /// Number of keys removed from the index.
+    pub deletes: u64,
+    /// Was the index cleared within the block?
+    pub is_cleared: bool,
+}
+
+impl IndexChange {
+    /// Creates a summary of changes in an index owned by the specified service.
+    pub fn new(instance_id: InstanceId, changes: IndexChanges) -> Self {
+        let address = changes.address;
+        Self {
+            instance_id,
+            key: address
+                .id_in_group()
+                .map(<[u8]>::to_vec)
+                .unwrap_or_default(),
+            name: address.name().to_owned(),
+            puts: changes.puts,
+            deletes: changes.deletes,
+            is_cleared: changes.is_cleared,
+        }
+    }
+}
+
 /// Information schema for indexes maintained by the Exonum core logic.
 ///
 /// Indexes defined by this schema are present in the blockchain regardless of
@@ -107,6 +212,10 @@ pub struct Schema<T> {
 }
 
 impl<T: Access> Schema<T> {
+    /// Maximum number of blocks rolled into the call errors checkpoint by a single call
+    /// to [`checkpoint_call_errors`](#method.checkpoint_call_errors).
+    pub const MAX_CHECKPOINTED_BLOCKS: u64 = 64;
+
     /// Constructs information schema based on the given `access`.
     #[doc(hidden)]
     pub fn new(access: T) -> Self {
@@ -134,10 +243,48 @@ impl<T: Access> Schema<T> {
         self.access.get_map((CALL_ERRORS_AUX, &block_height.0))
     }
 
+    fn call_errors_checkpoint_entry(&self) -> ProofEntry<T::Base, CallErrorsCheckpoint> {
+        self.access.get_proof_entry(CALL_ERRORS_CHECKPOINT)
+    }
+
+    /// Returns the digest of call errors dropped from the storage, or `None` if
+    /// call errors were never dropped.
+    pub fn call_errors_checkpoint(&self) -> Option<CallErrorsCheckpoint> {
+        self.call_errors_checkpoint_entry().get()
+    }
+
+    /// Returns the height of the first block for which call errors are retained in the storage.
+    /// Errors in the preceding blocks are rolled into the
+    /// [call errors checkpoint](#method.call_errors_checkpoint).
+    pub fn call_errors_retained_from(&self) -> Height {
+        self.call_errors_checkpoint()
+            .map_or(Height(0), |checkpoint| checkpoint.height)
+    }
+
+    /// Checks that call errors for the block at the specified height are retained
+    /// in the storage, i.e., were not rolled into the
+    /// [call errors checkpoint](#method.call_errors_checkpoint).
+    pub fn check_call_errors_retained(&self, height: Height) -> Result<(), HistoryPruned> {
+        let retained_from = self.call_errors_retained_from();
+        if height < retained_from {
+            Err(HistoryPruned {
+                height,
+                retained_from,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
     /// Returns a record of errors that occurred during execution of a particular block.
-    /// If the block is not committed, returns `None`.
+    /// If the block is not committed, or if the errors in the block were rolled into
+    /// the [call errors checkpoint](#method.call_errors_checkpoint), returns `None`.
+    /// Use [`check_call_errors_retained`] to distinguish these cases.
+    ///
+    /// [`check_call_errors_retained`]: #method.check_call_errors_retained
     pub fn call_records(&self, block_height: Height) -> Option<CallRecords<T>> {
         self.block_hash_by_height(block_height)?;
+        self.check_call_errors_retained(block_height).ok()?;
         Some(CallRecords {
             height: block_height,
             errors: self.call_errors_map(block_height),
@@ -147,11 +294,15 @@ impl<T: Access> Schema<T> {
     }
 
     /// Returns the result of the execution for a transaction with the specified location.
-    /// If the location does not correspond to a transaction, returns `None`.
+    /// If the location does not correspond to a transaction, or if the errors in the block
+    /// were dropped from the storage, returns `None`.
     pub fn transaction_result(&self, location: TxLocation) -> Option<Result<(), ExecutionError>> {
         let records = self.call_records(location.block_height)?;
-        let txs_in_block = self.block_transactions(location.block_height).len();
-        if txs_in_block <= u64::from(location.position_in_block) {
+        // The number of transactions is taken from the block header rather than
+        // from `block_transactions`, since the latter may be pruned.
+        let block_hash = self.block_hash_by_height(location.block_height)?;
+        let txs_in_block = self.blocks().get(&block_hash)?.tx_count;
+        if txs_in_block <= location.position_in_block {
             return None;
         }
         let status = records.get(CallInBlock::transaction(location.position_in_block));
@@ -198,6 +349,24 @@ impl<T: Access> Schema<T> {
         self.access.get_map(TRANSACTIONS_LOCATIONS)
     }
 
+    /// Returns a node-local table mapping transaction hashes to trace IDs provided
+    /// by clients when submitting transactions to this node. The table is not a part
+    /// of the blockchain state.
+    pub fn trace_ids(&self) -> MapIndex<T::Base, Hash, String> {
+        self.access.get_map(TRACE_IDS)
+    }
+
+    /// Returns the trace ID of the transaction with the specified hash, or `None` if
+    /// the transaction was not submitted to this node with a trace ID.
+    pub fn trace_id(&self, tx_hash: &Hash) -> Option<String> {
+        self.trace_ids().get(tx_hash)
+    }
+
+    /// Checks whether the index of trace IDs is created.
+    pub(crate) fn has_trace_ids(&self) -> bool {
+        self.access.index_type(TRACE_IDS).is_some()
+    }
+
     /// Returns a table that stores a block object for every block height.
     pub fn blocks(&self) -> MapIndex<T::Base, Hash, Block> {
         self.access.get_map(BLOCKS)
@@ -209,11 +378,87 @@ impl<T: Access> Schema<T> {
     }
 
     /// Returns a table that keeps a list of transactions for each block.
+    ///
+    /// The list is empty if the block is pruned; use [`check_history_retained`]
+    /// to distinguish pruned blocks from empty ones.
+    ///
+    /// [`check_history_retained`]: #method.check_history_retained
     pub fn block_transactions(&self, height: Height) -> ProofListIndex<T::Base, Hash> {
         let height: u64 = height.into();
         self.access.get_proof_list((BLOCK_TRANSACTIONS, &height))
     }
 
+    /// Returns summaries of changes made to service indexes by the block at the specified
+    /// height. The summaries are ordered by the service identifier and then by the index address.
+    pub fn index_changes(&self, height: Height) -> ListIndex<T::Base, IndexChange> {
+        self.access.get_list((INDEX_CHANGES, &height.0))
+    }
+
+    /// Returns a table that keeps a list of transactions rejected by the block proposer
+    /// on admission to its transaction pool for each block. The list is sorted by
+    /// the transaction hash; its hash is recorded in the [`RejectedTransactionsHash`] header
+    /// of the block.
+    ///
+    /// [`RejectedTransactionsHash`]: struct.RejectedTransactionsHash.html
+    pub fn rejected_transactions(
+        &self,
+        height: Height,
+    ) -> ProofListIndex<T::Base, RejectedTransaction> {
+        self.access
+            .get_proof_list((REJECTED_TRANSACTIONS, &height.0))
+    }
+
+    fn history_retained_from_entry(&self) -> Entry<T::Base, Height> {
+        self.access.get_entry(HISTORY_RETAINED_FROM)
+    }
+
+    /// Returns the height of the first block for which historical data (e.g., lists
+    /// of transactions in the block) is retained by the node. Data for the preceding blocks
+    /// is pruned according to the [`PruningConfig`] of the node.
+    ///
+    /// [`PruningConfig`]: struct.PruningConfig.html
+    pub fn history_retained_from(&self) -> Height {
+        self.history_retained_from_entry()
+            .get()
+            .unwrap_or(Height(0))
+    }
+
+    /// Checks that historical data for the block at the specified height is retained
+    /// by the node, i.e., was not pruned according to the [`PruningConfig`] of the node.
+    ///
+    /// [`PruningConfig`]: struct.PruningConfig.html
+    pub fn check_history_retained(&self, height: Height) -> Result<(), HistoryPruned> {
+        let retained_from = self.history_retained_from();
+        if height < retained_from {
+            Err(HistoryPruned {
+                height,
+                retained_from,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns a table that keeps the block height and the position inside the list
+    /// of rejected transactions for every rejected transaction hash. If a transaction
+    /// was recorded as rejected several times, the latest record is kept.
+    pub fn rejected_transactions_locations(&self) -> MapIndex<T::Base, Hash, TxLocation> {
+        self.access.get_map(REJECTED_TRANSACTIONS_LOCATIONS)
+    }
+
+    fn block_time_entry(&self) -> Entry<T::Base, OffsetDateTime> {
+        self.access.get_entry(BLOCK_TIME)
+    }
+
+    /// Returns the time of the latest block agreed upon by validators, or `None` if the block
+    /// does not record the time. During block execution, the time of the executed block
+    /// is returned. See [`BlockTime`] for details.
+    ///
+    /// [`BlockTime`]: struct.BlockTime.html
+    pub fn block_time(&self) -> Option<OffsetDateTime> {
+        self.block_time_entry().get()
+    }
+
     /// Returns an entry storing the latest skip block for the node.
     fn block_skip_entry(&self) -> Entry<T::Base, Block> {
         self.access.get_entry(BLOCK_SKIP)
@@ -412,6 +657,58 @@ where
         self.call_errors_aux(height).put(&call, aux);
     }
 
+    /// Drops call errors for blocks with heights less than `retain_from`, rolling them
+    /// into the [`CallErrorsCheckpoint`]. Blocks preceding the previous checkpoint are
+    /// not processed again, so the method is cheap to call repeatedly.
+    ///
+    /// At most [`MAX_CHECKPOINTED_BLOCKS`] blocks are processed per call, so that the work
+    /// performed within a single block is bounded. If the checkpoint lags further behind
+    /// `retain_from`, the method should be called again (e.g., in the following blocks)
+    /// to catch up.
+    ///
+    /// Returns the updated checkpoint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `retain_from` exceeds the height of the latest committed block.
+    ///
+    /// [`CallErrorsCheckpoint`]: struct.CallErrorsCheckpoint.html
+    /// [`MAX_CHECKPOINTED_BLOCKS`]: #associatedconstant.MAX_CHECKPOINTED_BLOCKS
+    pub fn checkpoint_call_errors(&mut self, retain_from: Height) -> CallErrorsCheckpoint {
+        assert!(
+            retain_from <= self.height(),
+            "Cannot drop call errors for uncommitted blocks"
+        );
+
+        let mut entry = self.call_errors_checkpoint_entry();
+        let mut checkpoint = entry.get().unwrap_or_default();
+        if checkpoint.height >= retain_from {
+            return checkpoint;
+        }
+        let retain_from = cmp::min(
+            retain_from,
+            Height(checkpoint.height.0 + Self::MAX_CHECKPOINTED_BLOCKS),
+        );
+        while checkpoint.height < retain_from {
+            let height = checkpoint.height;
+            let mut errors = self.call_errors_map(height);
+            let error_count = errors.keys().count() as u64;
+            checkpoint.push_block(errors.object_hash(), error_count);
+            errors.clear();
+            self.call_errors_aux(height).clear();
+        }
+        entry.set(checkpoint.clone());
+        checkpoint
+    }
+
+    pub(super) fn set_block_time(&mut self, time: Option<OffsetDateTime>) {
+        let mut entry = self.block_time_entry();
+        match time {
+            Some(time) => entry.set(time),
+            None => entry.remove(),
+        }
+    }
+
     pub(super) fn clear_block_skip(&mut self) {
         if let Some(block_skip) = self.block_skip_entry().take() {
             let block_hash = block_skip.object_hash();
@@ -426,6 +723,41 @@ where
     }
 }
 
+impl Schema<&Fork> {
+    /// Removes historical data for the blocks preceding `retain_from`. See [`PruningConfig`]
+    /// for the description of the pruned data. Returns `false` if the data is already pruned.
+    ///
+    /// [`PruningConfig`]: struct.PruningConfig.html
+    pub(super) fn prune_history(&self, retain_from: Height) -> bool {
+        let mut entry = self.history_retained_from_entry();
+        if entry.get().unwrap_or(Height(0)) >= retain_from {
+            return false;
+        }
+
+        let fork = self.access;
+        let mut removed_indexes = 0;
+        for &group in &[BLOCK_TRANSACTIONS, REJECTED_TRANSACTIONS, INDEX_CHANGES] {
+            removed_indexes += fork.remove_group_indexes(group, &retain_from.0);
+        }
+        // Call errors are retained until the corresponding blocks are covered
+        // by the checkpoint, since the checkpoint is built from the errors.
+        if let Some(checkpoint) = self.call_errors_checkpoint() {
+            let errors_retain_from = retain_from.min(checkpoint.height);
+            for &group in &[CALL_ERRORS, CALL_ERRORS_AUX] {
+                removed_indexes += fork.remove_group_indexes(group, &errors_retain_from.0);
+            }
+        }
+
+        log::trace!(
+            "Pruned {} historical indexes for blocks preceding {}",
+            removed_indexes,
+            retain_from
+        );
+        entry.set(retain_from);
+        true
+    }
+}
+
 /// Information about call errors within a specific block.
 ///
 /// This data type can be used to get information or build proofs that execution
@@ -476,6 +808,20 @@ impl<T: Access> CallRecords<T> {
         let call_proof = self.errors.get_proof(call);
         CallProof::new(block_proof, call_proof, error_aux)
     }
+
+    /// Returns a combined cryptographic proof of authenticity for several top-level calls
+    /// within a block. The proof is more compact than separate proofs for each call,
+    /// since common branches of the Merkle tree are included into it only once.
+    pub fn get_multiproof<I>(&self, calls: I) -> CallsProof
+    where
+        I: IntoIterator<Item = CallInBlock>,
+    {
+        let block_proof = Schema::new(self.access.clone())
+            .block_and_precommits(self.height)
+            .unwrap();
+        let calls_proof = self.errors.get_multiproof(calls);
+        CallsProof::new(block_proof, calls_proof)
+    }
 }
 
 /// Iterator over errors in a block returned by `CallRecords::errors()`.
@@ -12,11 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use exonum_crypto::{Hash, KeyPair};
+use exonum_crypto::{self as crypto, Hash, KeyPair};
 use exonum_derive::{BinaryValue, FromAccess};
 use exonum_merkledb::{
-    access::{Access, FromAccess},
-    BinaryValue, Error as MerkledbError, ObjectHash, ProofListIndex, Snapshot, SystemSchema,
+    access::{Access, CopyAccessExt, FromAccess, Prefixed},
+    BinaryValue, Error as MerkledbError, HashTag, ObjectHash, ProofListIndex, ReadonlyFork,
+    Snapshot, SystemSchema,
 };
 use pretty_assertions::assert_eq;
 use semver::Version;
@@ -25,14 +26,18 @@ use time::OffsetDateTime;
 
 use std::{
     cell::RefCell,
-    collections::{BTreeMap, VecDeque},
-    panic,
+    collections::{BTreeMap, HashMap, VecDeque},
+    panic, thread,
+    time::Duration,
 };
 
 use crate::{
     blockchain::{
         config::{ConsensusConfig, GenesisConfig, GenesisConfigBuilder, InstanceInitParams},
-        BlockParams, Blockchain, BlockchainMut, PersistentPool, Schema, TransactionCache,
+        BackfillHandle, BackfillProgress, Block, BlockParams, BlockReplay, Blockchain,
+        BlockchainMut, CallInBlock, HistoryPruned, PersistentPool, PruningConfig,
+        RejectedTransaction, Schema, TransactionCache, TransactionsByAuthor, TxLocation,
+        MAX_TRACE_ID_LEN,
     },
     helpers::{Height, Round, ValidatorId},
     messages::{Precommit, Verified},
@@ -41,9 +46,9 @@ use crate::{
         migrations::{InitMigrationError, MigrationScript},
         oneshot::Receiver,
         AnyTx, ArtifactId, CallInfo, CommonError, CoreError, Dispatcher, DispatcherSchema,
-        ErrorMatch, ExecutionContext, ExecutionError, ExecutionFail, InstanceId, InstanceSpec,
-        InstanceState, InstanceStatus, Mailbox, MethodId, Runtime, SnapshotExt, WellKnownRuntime,
-        SUPERVISOR_INSTANCE_ID,
+        ErrorKind, ErrorMatch, ExecutionContext, ExecutionError, ExecutionFail, InstanceDescriptor,
+        InstanceId, InstanceSpec, InstanceState, InstanceStatus, InvariantViolation, Mailbox,
+        MethodId, Runtime, ShadowInstance, SnapshotExt, WellKnownRuntime, SUPERVISOR_INSTANCE_ID,
     },
 };
 
@@ -154,6 +159,8 @@ enum Transaction {
     AddService(InstanceSpec, InitAction),
     /// Stop service with the specified ID.
     StopService(InstanceId),
+    /// Fail if the service is executed by the artifact with the specified version.
+    FailOnVersion(Version),
 }
 
 impl Transaction {
@@ -201,6 +208,9 @@ impl Execute for Transaction {
             Self::StopService(instance_id) => {
                 Dispatcher::initiate_stopping_service(context.fork, instance_id)
             }
+
+            // The artifact version is checked by the runtime.
+            Self::FailOnVersion(_) => Ok(()),
         }
     }
 }
@@ -209,7 +219,9 @@ impl Execute for Transaction {
 struct RuntimeInspector {
     available: Vec<ArtifactId>,
     deployed: Vec<ArtifactId>,
+    instances: HashMap<InstanceId, ArtifactId>,
     after_transactions: RefCell<VecDeque<AfterTransactionsAction>>,
+    max_values: Option<u64>,
 }
 
 impl WellKnownRuntime for RuntimeInspector {
@@ -221,7 +233,9 @@ impl RuntimeInspector {
         Self {
             available: Vec::default(),
             deployed: Vec::default(),
+            instances: HashMap::default(),
             after_transactions: RefCell::default(),
+            max_values: None,
         }
     }
 
@@ -242,6 +256,12 @@ impl RuntimeInspector {
         self
     }
 
+    /// Declares an invariant limiting the number of values in the inspector schema.
+    fn with_max_values(mut self, max_values: u64) -> Self {
+        self.max_values = Some(max_values);
+        self
+    }
+
     fn default_artifact_id() -> ArtifactId {
         ArtifactId::from_raw_parts(Self::ID, "runtime-inspector".into(), Version::new(1, 0, 0))
     }
@@ -292,7 +312,10 @@ impl Runtime for RuntimeInspector {
         Ok(())
     }
 
-    fn update_service_status(&mut self, _snapshot: &dyn Snapshot, _state: &InstanceState) {}
+    fn update_service_status(&mut self, _snapshot: &dyn Snapshot, state: &InstanceState) {
+        self.instances
+            .insert(state.spec.id, state.spec.artifact.clone());
+    }
 
     fn migrate(
         &self,
@@ -309,9 +332,15 @@ impl Runtime for RuntimeInspector {
         arguments: &[u8],
     ) -> Result<(), ExecutionError> {
         catch_panic(|| {
-            Transaction::from_bytes(arguments.into())
-                .map_err(|e| CommonError::MalformedArguments.with_description(e))?
-                .execute(context)
+            let tx = Transaction::from_bytes(arguments.into())
+                .map_err(|e| CommonError::MalformedArguments.with_description(e))?;
+            if let Transaction::FailOnVersion(version) = &tx {
+                let artifact = &self.instances[&context.instance().id];
+                if artifact.version == *version {
+                    return Err(ExecutionError::service(0, "Unsupported artifact version"));
+                }
+            }
+            tx.execute(context)
         })
     }
 
@@ -328,6 +357,24 @@ impl Runtime for RuntimeInspector {
         })
     }
 
+    fn check_invariants(
+        &self,
+        _instance: &InstanceDescriptor,
+        fork: ReadonlyFork<'_>,
+    ) -> Result<(), InvariantViolation> {
+        let values_len = InspectorSchema::new(fork).values.len();
+        match self.max_values {
+            Some(max_values) if values_len > max_values => Err(InvariantViolation::new(
+                "max_values",
+                format!(
+                    "{} values are stored, at most {} allowed",
+                    values_len, max_values
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
     fn after_commit(&mut self, _snapshot: &dyn Snapshot, _mailbox: &mut Mailbox) {}
 }
 
@@ -416,6 +463,57 @@ fn after_transactions_failure_causes_genesis_failure() {
     );
 }
 
+/// Checks that blocks are created while service invariants hold.
+#[test]
+fn blocks_are_created_if_invariants_hold() {
+    let keys = KeyPair::random();
+    let mut blockchain = create_blockchain(
+        RuntimeInspector::default().with_max_values(2),
+        vec![InitAction::Noop.into_default_instance()],
+    );
+
+    for value in 1..=2 {
+        let tx = Transaction::AddValue(value).sign(TEST_SERVICE_ID, &keys);
+        execute_transaction(&mut blockchain, tx).unwrap();
+    }
+    let snapshot = blockchain.snapshot();
+    assert_eq!(Schema::new(&snapshot).height(), Height(2));
+    assert_eq!(InspectorSchema::new(&snapshot).values.len(), 2);
+}
+
+/// Checks that changes of a transaction violating a service invariant are rolled back,
+/// and the violation is recorded as the transaction error.
+#[test]
+fn invariant_violation_rolls_back_transaction() {
+    let keys = KeyPair::random();
+    let mut blockchain = create_blockchain(
+        RuntimeInspector::default().with_max_values(1),
+        vec![InitAction::Noop.into_default_instance()],
+    );
+
+    let tx = Transaction::AddValue(1).sign(TEST_SERVICE_ID, &keys);
+    execute_transaction(&mut blockchain, tx).unwrap();
+    let tx = Transaction::AddValue(2).sign(TEST_SERVICE_ID, &keys);
+    let err = execute_transaction(&mut blockchain, tx).unwrap_err();
+    assert_eq!(
+        err,
+        ErrorMatch::from_fail(&CoreError::InvariantViolated).with_description_containing(
+            "Service 0:test_service violates its invariant. Invariant `max_values` is violated"
+        )
+    );
+
+    // The block is created without the violating changes.
+    let snapshot = blockchain.snapshot();
+    assert_eq!(Schema::new(&snapshot).height(), Height(2));
+    assert_eq!(InspectorSchema::new(&snapshot).values.len(), 1);
+
+    // Further violations do not halt the blockchain either.
+    let tx = Transaction::AddValue(3).sign(TEST_SERVICE_ID, &keys);
+    execute_transaction(&mut blockchain, tx).unwrap_err();
+    let snapshot = blockchain.snapshot();
+    assert_eq!(Schema::new(&snapshot).height(), Height(3));
+}
+
 #[test]
 fn handling_tx_panic_error() {
     let keys = KeyPair::random();
@@ -756,6 +854,374 @@ fn blockchain_height() {
     assert_eq!(schema.next_height(), Height(2));
 }
 
+/// Checks recording of client-provided trace IDs.
+#[test]
+fn trace_ids_are_recorded() {
+    let blockchain = Blockchain::build_for_tests()
+        .into_mut(create_genesis_config())
+        .build();
+    let blockchain = blockchain.as_ref();
+    let tx_hash = crypto::hash(b"transaction");
+
+    let long_id = "a".repeat(MAX_TRACE_ID_LEN + 1);
+    for invalid_id in &["", "with space", "line\nbreak", "\u{444}", long_id.as_str()] {
+        blockchain.record_trace_id(tx_hash, invalid_id).unwrap_err();
+    }
+    assert_eq!(Schema::new(&blockchain.snapshot()).trace_id(&tx_hash), None);
+
+    blockchain.record_trace_id(tx_hash, "request-1").unwrap();
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.trace_id(&tx_hash), Some("request-1".to_owned()));
+    assert_eq!(schema.trace_id(&crypto::hash(b"other")), None);
+}
+
+#[test]
+fn block_stats() {
+    let keys = KeyPair::random();
+    let mut blockchain = create_blockchain(
+        RuntimeInspector::default(),
+        vec![InitAction::Noop.into_default_instance()],
+    );
+    // Blocks without transactions do not contain statistics.
+    assert_eq!(blockchain.as_ref().last_block().stats(), None);
+
+    let tx = Transaction::AddValue(10).sign(TEST_SERVICE_ID, &keys);
+    let tx_bytes = tx.to_bytes().len() as u64;
+    execute_transaction(&mut blockchain, tx).expect("Transaction must succeed");
+
+    let stats = blockchain.as_ref().last_block().stats().unwrap();
+    assert_eq!(stats.total_tx_bytes, tx_bytes);
+    assert_eq!(stats.services.len(), 1);
+    let service_stats = stats.service(TEST_SERVICE_ID).unwrap();
+    assert_eq!(service_stats.tx_count, 1);
+    assert_eq!(service_stats.tx_bytes, tx_bytes);
+    assert!(stats.service(TEST_SERVICE_ID + 1).is_none());
+}
+
+#[test]
+fn rejected_transactions_are_recorded() {
+    let mut blockchain = create_blockchain(
+        RuntimeInspector::default(),
+        vec![InitAction::Noop.into_default_instance()],
+    );
+    // Blocks without rejected transactions do not contain the corresponding header.
+    assert_eq!(
+        blockchain
+            .as_ref()
+            .last_block()
+            .rejected_transactions_hash(),
+        None
+    );
+
+    let mut rejected = vec![
+        RejectedTransaction::new(crypto::hash(&[1]), ErrorKind::Core { code: 7 }),
+        RejectedTransaction::new(crypto::hash(&[2]), ErrorKind::Service { code: 3 }),
+    ];
+    rejected.sort();
+    let epoch = blockchain.as_ref().last_block().epoch().unwrap().next();
+    let block_params =
+        BlockParams::new(ValidatorId(0), epoch, &[]).with_rejected_transactions(&rejected);
+    let patch = blockchain.create_patch(block_params, &());
+    blockchain.commit(patch, vec![]).unwrap();
+
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    let block = schema.last_block();
+    assert_eq!(block.tx_count, 0);
+    let recorded = schema.rejected_transactions(Height(1));
+    assert_eq!(recorded.iter().collect::<Vec<_>>(), rejected);
+    assert_eq!(
+        block.rejected_transactions_hash(),
+        Some(HashTag::hash_list(&rejected))
+    );
+    assert_eq!(recorded.object_hash(), HashTag::hash_list(&rejected));
+
+    let location = schema
+        .rejected_transactions_locations()
+        .get(&rejected[1].tx_hash)
+        .unwrap();
+    assert_eq!(location, TxLocation::new(Height(1), 1));
+    // Rejected transactions are not considered committed.
+    assert!(!schema.transactions().contains(&rejected[1].tx_hash));
+}
+
+#[test]
+fn block_time_is_recorded() {
+    let mut blockchain = create_blockchain(
+        RuntimeInspector::default(),
+        vec![InitAction::Noop.into_default_instance()],
+    );
+    assert_eq!(blockchain.as_ref().last_block().time(), None);
+
+    let time = OffsetDateTime::from_unix_timestamp(1_600_000_000).unwrap();
+    let epoch = blockchain.as_ref().last_block().epoch().unwrap().next();
+    let block_params = BlockParams::new(ValidatorId(0), epoch, &[]).with_time(time);
+    let patch = blockchain.create_patch(block_params, &());
+    blockchain.commit(patch, vec![]).unwrap();
+
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.last_block().time(), Some(time));
+    assert_eq!(schema.block_time(), Some(time));
+
+    // The time is not carried over to blocks which do not record it.
+    let epoch = epoch.next();
+    let patch = blockchain.create_patch(BlockParams::new(ValidatorId(0), epoch, &[]), &());
+    blockchain.commit(patch, vec![]).unwrap();
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.last_block().time(), None);
+    assert_eq!(schema.block_time(), None);
+}
+
+#[test]
+fn call_hashes_are_recorded() {
+    let keys = KeyPair::random();
+    let mut blockchain = create_blockchain(
+        RuntimeInspector::default(),
+        vec![InitAction::Noop.into_default_instance()],
+    );
+    let transactions = vec![
+        Transaction::AddValue(1).sign(TEST_SERVICE_ID, &keys),
+        Transaction::ExecutionError(0, "Service error".to_owned()).sign(TEST_SERVICE_ID, &keys),
+        Transaction::AddValue(2).sign(TEST_SERVICE_ID, &keys),
+    ];
+    let tx_hashes: Vec<_> = transactions.iter().map(ObjectHash::object_hash).collect();
+    blockchain.add_transactions_into_pool(transactions);
+
+    let epoch = blockchain.as_ref().last_block().epoch().unwrap().next();
+    let patch = blockchain.create_patch(
+        BlockParams::new(ValidatorId(0), epoch, &tx_hashes[..2]),
+        &(),
+    );
+    assert!(patch.call_hashes().is_empty());
+
+    let block_params = BlockParams::new(ValidatorId(0), epoch, &tx_hashes[..2]).with_call_hashes();
+    let patch = blockchain.create_patch(block_params.clone(), &());
+    let calls: Vec<_> = patch.call_hashes().iter().map(|(call, _)| *call).collect();
+    assert_eq!(
+        calls,
+        vec![
+            CallInBlock::before_transactions(TEST_SERVICE_ID),
+            CallInBlock::transaction(0),
+            CallInBlock::transaction(1),
+            CallInBlock::after_transactions(TEST_SERVICE_ID),
+        ]
+    );
+    // Execution of the same block results in the same hashes.
+    let other_patch = blockchain.create_patch(block_params, &());
+    assert_eq!(other_patch.call_hashes(), patch.call_hashes());
+
+    // If the block contents differ, the hashes diverge starting from the differing call.
+    let block_params =
+        BlockParams::new(ValidatorId(0), epoch, &[tx_hashes[0], tx_hashes[2]]).with_call_hashes();
+    let other_patch = blockchain.create_patch(block_params, &());
+    let (call_hashes, other_call_hashes) = (patch.call_hashes(), other_patch.call_hashes());
+    assert_eq!(call_hashes[..2], other_call_hashes[..2]);
+    assert_ne!(call_hashes[2], other_call_hashes[2]);
+}
+
+#[test]
+fn index_changes_are_recorded_for_service_indexes() {
+    let mut blockchain = create_blockchain(
+        RuntimeInspector::default(),
+        vec![InitAction::Noop.into_default_instance()],
+    );
+
+    let fork = blockchain.fork();
+    {
+        let service_data = Prefixed::new(TEST_SERVICE_NAME, &fork);
+        service_data.get_list("values").extend(vec![1_u64, 2]);
+        let mut map = service_data.get_map(("wallets", &1_u8));
+        map.put(&1_u8, 1_u64);
+        map.remove(&2_u8);
+        // Indexes not belonging to services should be skipped.
+        fork.get_entry("other_service.value").set(1_u64);
+        fork.get_entry("value").set(1_u64);
+    }
+    let epoch = blockchain.as_ref().last_block().epoch().unwrap().next();
+    let block_params = BlockParams::new(ValidatorId(0), epoch, &[]);
+    let patch = blockchain.create_patch_inner(fork, &block_params, &[], &());
+    blockchain.commit(patch, vec![]).unwrap();
+
+    let snapshot = blockchain.snapshot();
+    let changes: Vec<_> = Schema::new(&snapshot)
+        .index_changes(Height(1))
+        .iter()
+        .map(|change| {
+            assert_eq!(change.instance_id, TEST_SERVICE_ID);
+            (change.name, change.key, change.puts, change.deletes)
+        })
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            ("test_service.values".to_owned(), vec![], 2, 0),
+            ("test_service.wallets".to_owned(), vec![1], 1, 1),
+        ]
+    );
+}
+
+#[test]
+fn call_errors_checkpoint() {
+    let keys = KeyPair::random();
+    let mut blockchain = create_blockchain(
+        RuntimeInspector::default(),
+        vec![InitAction::Noop.into_default_instance()],
+    );
+
+    let tx = Transaction::ExecutionError(0, "Service error".to_owned());
+    execute_transaction(&mut blockchain, tx.sign(TEST_SERVICE_ID, &keys))
+        .expect_err("Transaction must fail");
+    execute_transaction(
+        &mut blockchain,
+        Transaction::AddValue(10).sign(TEST_SERVICE_ID, &keys),
+    )
+    .expect("Transaction must succeed");
+
+    let snapshot = blockchain.snapshot();
+    let expected_hash = (0..2)
+        .map(|height| {
+            let block = Schema::new(&snapshot).block_and_precommits(Height(height));
+            block.unwrap().block.error_hash
+        })
+        .fold(Hash::zero(), |acc, error_hash| {
+            crypto::hash(&[acc.as_ref(), error_hash.as_ref()].concat())
+        });
+
+    let fork = blockchain.fork();
+    let checkpoint = Schema::new(&fork).checkpoint_call_errors(Height(2));
+    assert_eq!(checkpoint.height, Height(2));
+    assert_eq!(checkpoint.error_count, 1);
+    assert_eq!(checkpoint.errors_hash, expected_hash);
+    // Already truncated blocks are not processed again.
+    assert_eq!(
+        Schema::new(&fork).checkpoint_call_errors(Height(1)),
+        checkpoint
+    );
+    blockchain.merge(fork.into_patch()).unwrap();
+
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.call_errors_checkpoint(), Some(checkpoint));
+    assert_eq!(schema.call_errors_retained_from(), Height(2));
+    // Truncated errors are not reported as successful calls.
+    assert!(schema.call_records(Height(1)).is_none());
+    let err = schema.check_call_errors_retained(Height(1)).unwrap_err();
+    assert_eq!(err.height, Height(1));
+    assert_eq!(err.retained_from, Height(2));
+    assert_eq!(
+        schema.transaction_result(TxLocation::new(Height(1), 0)),
+        None
+    );
+    assert!(schema.check_call_errors_retained(Height(2)).is_ok());
+    assert!(schema.call_records(Height(2)).is_some());
+    assert_eq!(
+        schema.transaction_result(TxLocation::new(Height(2), 0)),
+        Some(Ok(()))
+    );
+}
+
+#[test]
+fn call_errors_checkpoint_is_built_in_batches() {
+    let keys = KeyPair::random();
+    let mut blockchain = create_blockchain(
+        RuntimeInspector::default(),
+        vec![InitAction::Noop.into_default_instance()],
+    );
+
+    let batch = Schema::<&dyn Snapshot>::MAX_CHECKPOINTED_BLOCKS;
+    for i in 1..=batch + 2 {
+        let tx = Transaction::AddValue(i).sign(TEST_SERVICE_ID, &keys);
+        execute_transaction(&mut blockchain, tx).expect("Transaction must succeed");
+    }
+
+    let retain_from = Height(batch + 2);
+    let fork = blockchain.fork();
+    let checkpoint = Schema::new(&fork).checkpoint_call_errors(retain_from);
+    assert_eq!(checkpoint.height, Height(batch));
+    let checkpoint = Schema::new(&fork).checkpoint_call_errors(retain_from);
+    assert_eq!(checkpoint.height, retain_from);
+}
+
+#[test]
+fn history_pruning() {
+    let keys = KeyPair::random();
+    let instance = InitAction::Noop.into_default_instance();
+    let (config, _) = ConsensusConfig::for_tests(1);
+    let genesis_config = GenesisConfigBuilder::with_consensus_config(config)
+        .with_artifact(instance.instance_spec.artifact.clone())
+        .with_instance(instance)
+        .build();
+    let mut blockchain = Blockchain::build_for_tests()
+        .into_mut(genesis_config.clone())
+        .with_runtime(RuntimeInspector::default())
+        .with_pruning(PruningConfig::new(2))
+        .build();
+    let mut archive_blockchain = create_blockchain(
+        RuntimeInspector::default(),
+        vec![InitAction::Noop.into_default_instance()],
+    );
+
+    for i in 1..=3 {
+        let tx = Transaction::AddValue(i).sign(TEST_SERVICE_ID, &keys);
+        execute_transaction(&mut blockchain, tx.clone()).expect("Transaction must succeed");
+        execute_transaction(&mut archive_blockchain, tx).expect("Transaction must succeed");
+    }
+
+    let snapshot = blockchain.snapshot();
+    let schema = Schema::new(&snapshot);
+    assert_eq!(schema.history_retained_from(), Height(2));
+    for height in 0..2 {
+        assert!(snapshot
+            .index_type(("core.block_transactions", &height))
+            .is_none());
+        assert!(schema.index_changes(Height(height)).is_empty());
+        // Blocks themselves are retained.
+        assert!(schema.block_and_precommits(Height(height)).is_some());
+    }
+    for height in 2..4 {
+        assert_eq!(schema.block_transactions(Height(height)).len(), 1);
+    }
+
+    // Readers of the historical data are able to detect pruned blocks.
+    let err = schema.check_history_retained(Height(1)).unwrap_err();
+    assert_eq!(err.height, Height(1));
+    assert_eq!(err.retained_from, Height(2));
+    schema.check_history_retained(Height(2)).unwrap();
+    // Execution results of transactions in pruned blocks are still available.
+    let location = TxLocation::new(Height(1), 0);
+    assert_eq!(schema.transaction_result(location), Some(Ok(())));
+    let location = TxLocation::new(Height(1), 1);
+    assert_eq!(schema.transaction_result(location), None);
+
+    let replayed_blockchain = Blockchain::build_for_tests()
+        .into_mut(genesis_config)
+        .with_runtime(RuntimeInspector::default())
+        .build();
+    let mut replay = BlockReplay::new(blockchain.snapshot(), replayed_blockchain).unwrap();
+    let err = replay
+        .replay_block(&mut |_: &Block, _: &dyn Snapshot| {})
+        .unwrap_err();
+    assert!(err.is::<HistoryPruned>());
+    assert_eq!(replay.next_height(), Height(1));
+
+    let handle = BackfillHandle::spawn(blockchain.as_ref(), TransactionsByAuthor);
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(handle.progress().indexed_height, None);
+
+    // Pruning does not influence the blockchain state.
+    let archive_snapshot = archive_blockchain.snapshot();
+    let archive_schema = Schema::new(&archive_snapshot);
+    assert_eq!(archive_schema.history_retained_from(), Height(0));
+    assert_eq!(archive_schema.block_transactions(Height(1)).len(), 1);
+    assert_eq!(
+        schema.last_block().state_hash,
+        archive_schema.last_block().state_hash
+    );
+}
+
 #[test]
 fn state_aggregation() {
     let keys = KeyPair::random();
@@ -785,6 +1251,60 @@ fn state_aggregation() {
     assert_eq!(actual_indexes, expected_indexes);
 }
 
+#[test]
+fn shadow_instance_reports_divergences() {
+    let keys = KeyPair::random();
+    let shadow_artifact = ArtifactId::from_raw_parts(
+        RuntimeInspector::ID,
+        "runtime-inspector".into(),
+        Version::new(1, 1, 0),
+    );
+    let shadow = ShadowInstance::new("test_shadow", TEST_SERVICE_NAME, shadow_artifact.clone());
+    let report = shadow.report();
+
+    let (config, _) = ConsensusConfig::for_tests(1);
+    let genesis_config = GenesisConfigBuilder::with_consensus_config(config)
+        .with_artifact(RuntimeInspector::default_artifact_id())
+        .with_instance(InitAction::Noop.into_default_instance())
+        .build();
+    let mut blockchain = Blockchain::build_for_tests()
+        .into_mut(genesis_config)
+        .with_runtime(RuntimeInspector::default().with_available_artifact(shadow_artifact.clone()))
+        .with_shadow_instance(shadow)
+        .build();
+
+    execute_transaction(
+        &mut blockchain,
+        Transaction::AddValue(10).sign(TEST_SERVICE_ID, &keys),
+    )
+    .expect("Transaction must succeed");
+    // Changes made by the shadow instance should be discarded.
+    let snapshot = blockchain.snapshot();
+    assert_eq!(InspectorSchema::new(&snapshot).values.len(), 1);
+    let stats = report.stats();
+    assert_eq!(stats.executed, 1);
+    assert_eq!(stats.diverged, 0);
+
+    let tx = Transaction::FailOnVersion(shadow_artifact.version).sign(TEST_SERVICE_ID, &keys);
+    let tx_hash = tx.object_hash();
+    execute_transaction(&mut blockchain, tx).expect("Transaction must succeed");
+    let stats = report.stats();
+    assert_eq!(stats.executed, 2);
+    assert_eq!(stats.diverged, 1);
+    let divergence = &stats.divergences[0];
+    assert_eq!(divergence.tx_hash, tx_hash);
+    assert!(divergence.production.is_none());
+    let shadow_err = divergence.shadow.as_ref().unwrap();
+    assert_eq!(shadow_err.description(), "Unsupported artifact version");
+
+    // The shadow instance should not be visible to the blockchain.
+    let snapshot = blockchain.snapshot();
+    assert!(snapshot
+        .for_dispatcher()
+        .get_instance("test_shadow")
+        .is_none());
+}
+
 #[test]
 fn no_data_race_for_transaction_pool() {
     let keys = KeyPair::random();
@@ -930,3 +1450,139 @@ fn clearing_block_skip() {
     assert!(schema.block_skip().is_none());
     assert!(schema.precommits(&new_block_hash).is_empty());
 }
+
+#[test]
+fn replaying_blocks() {
+    let keys = KeyPair::random();
+    let (config, _) = ConsensusConfig::for_tests(1);
+    let instance = InitAction::Noop.into_default_instance();
+    let genesis_config = GenesisConfigBuilder::with_consensus_config(config)
+        .with_artifact(instance.instance_spec.artifact.clone())
+        .with_instance(instance)
+        .build();
+    let create_blockchain = |genesis_config| {
+        Blockchain::build_for_tests()
+            .into_mut(genesis_config)
+            .with_runtime(RuntimeInspector::default())
+            .build()
+    };
+
+    let mut blockchain = create_blockchain(genesis_config.clone());
+    for value in 1..=3 {
+        execute_transaction(
+            &mut blockchain,
+            Transaction::AddValue(value).sign(TEST_SERVICE_ID, &keys),
+        )
+        .expect("Transaction must succeed");
+    }
+
+    let replayed_blockchain = create_blockchain(genesis_config);
+    let mut replay = BlockReplay::new(blockchain.snapshot(), replayed_blockchain).unwrap();
+    assert_eq!(replay.source_height(), Height(3));
+    assert_eq!(replay.next_height(), Height(1));
+
+    let mut observed_values = vec![];
+    let mut observer = |block: &Block, snapshot: &dyn Snapshot| {
+        let values = InspectorSchema::new(snapshot).values;
+        observed_values.push((block.height, values.last()));
+    };
+    replay.replay_until(Height(2), &mut observer).unwrap();
+    assert_eq!(replay.next_height(), Height(3));
+    replay.replay_block(&mut observer).unwrap();
+    assert_eq!(
+        observed_values,
+        vec![
+            (Height(1), Some(1)),
+            (Height(2), Some(2)),
+            (Height(3), Some(3)),
+        ]
+    );
+
+    let err = replay.replay_block(&mut observer).unwrap_err();
+    assert!(err.to_string().contains("absent from the source"));
+    assert_eq!(
+        replay.blockchain().as_ref().last_block(),
+        blockchain.as_ref().last_block()
+    );
+}
+
+#[test]
+fn replaying_blocks_with_another_genesis() {
+    let blockchain = create_blockchain(
+        RuntimeInspector::default(),
+        vec![InitAction::Noop.into_default_instance()],
+    );
+    let other_blockchain = create_blockchain(RuntimeInspector::default(), vec![]);
+    let err = BlockReplay::new(blockchain.snapshot(), other_blockchain).unwrap_err();
+    assert!(err.to_string().contains("Genesis block"));
+}
+
+fn wait_for_backfill(handle: &BackfillHandle) -> BackfillProgress {
+    for _ in 0..100 {
+        let progress = handle.progress();
+        if progress.is_complete() {
+            return progress;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    panic!("Backfill job has not caught up with the blockchain");
+}
+
+#[test]
+fn backfilling_transactions_by_author() {
+    let alice = KeyPair::random();
+    let bob = KeyPair::random();
+    let mut blockchain = create_blockchain(
+        RuntimeInspector::default(),
+        vec![InitAction::Noop.into_default_instance()],
+    );
+    for &(value, keys) in &[(1, &alice), (2, &bob), (3, &alice)] {
+        execute_transaction(
+            &mut blockchain,
+            Transaction::AddValue(value).sign(TEST_SERVICE_ID, keys),
+        )
+        .expect("Transaction must succeed");
+    }
+
+    // The index is introduced on an existing chain.
+    let handle = BackfillHandle::spawn(blockchain.as_ref(), TransactionsByAuthor);
+    let progress = wait_for_backfill(&handle);
+    assert_eq!(progress.name, TransactionsByAuthor::NAME);
+    assert_eq!(progress.indexed_height, Some(Height(3)));
+
+    let snapshot = blockchain.snapshot();
+    let locations: Vec<_> =
+        TransactionsByAuthor::transactions(snapshot.as_ref(), alice.public_key())
+            .into_iter()
+            .map(|(_, location)| location)
+            .collect();
+    assert_eq!(
+        locations,
+        vec![TxLocation::new(Height(1), 0), TxLocation::new(Height(3), 0)]
+    );
+    let bob_txs = TransactionsByAuthor::transactions(snapshot.as_ref(), bob.public_key());
+    assert_eq!(bob_txs.len(), 1);
+    let tx_location = Schema::new(&snapshot)
+        .transactions_locations()
+        .get(&bob_txs[0].0);
+    assert_eq!(tx_location, Some(bob_txs[0].1));
+    // Local indexes do not influence the blockchain state.
+    assert_eq!(
+        SystemSchema::new(&snapshot).state_hash(),
+        blockchain.as_ref().last_block().state_hash
+    );
+
+    // New blocks are indexed as well.
+    execute_transaction(
+        &mut blockchain,
+        Transaction::AddValue(4).sign(TEST_SERVICE_ID, &bob),
+    )
+    .expect("Transaction must succeed");
+    handle.notify();
+    let progress = wait_for_backfill(&handle);
+    assert_eq!(progress.indexed_height, Some(Height(4)));
+    let snapshot = blockchain.snapshot();
+    let bob_txs = TransactionsByAuthor::transactions(snapshot.as_ref(), bob.public_key());
+    assert_eq!(bob_txs.len(), 2);
+    assert_eq!(bob_txs[1].1, TxLocation::new(Height(4), 0));
+}
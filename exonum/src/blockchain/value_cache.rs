@@ -0,0 +1,476 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bounded cache of decoded values read from the storage.
+
+use exonum_merkledb::{
+    access::CopyAccessExt, BinaryKey, BinaryValue, IndexAddress, ObjectHash, Snapshot,
+};
+
+use std::{
+    any::{Any, TypeId},
+    collections::{BTreeMap, HashMap},
+    fmt,
+    ops::Deref,
+    sync::{Arc, Mutex, RwLock},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ValueCacheKey {
+    epoch: u64,
+    address: IndexAddress,
+    key: Vec<u8>,
+    // Values of different types read from the same entry are cached separately.
+    value_type: TypeId,
+}
+
+struct CacheEntry {
+    value: Arc<dyn Any + Send + Sync>,
+    last_access: u64,
+}
+
+#[derive(Default)]
+struct CacheInner {
+    /// Epoch of the latest database state. Only values read from this state are cached.
+    epoch: u64,
+    entries: HashMap<ValueCacheKey, CacheEntry>,
+    /// Keys of the entries ordered by the last access.
+    access_order: BTreeMap<u64, ValueCacheKey>,
+    access_counter: u64,
+}
+
+impl CacheInner {
+    fn next_access(&mut self) -> u64 {
+        self.access_counter += 1;
+        self.access_counter
+    }
+
+    fn get(&mut self, key: &ValueCacheKey) -> Option<Arc<dyn Any + Send + Sync>> {
+        let access = self.next_access();
+        let entry = self.entries.get_mut(key)?;
+        self.access_order.remove(&entry.last_access);
+        self.access_order.insert(access, key.clone());
+        entry.last_access = access;
+        Some(Arc::clone(&entry.value))
+    }
+
+    fn insert(&mut self, key: ValueCacheKey, value: Arc<dyn Any + Send + Sync>, capacity: usize) {
+        if key.epoch != self.epoch {
+            // The value is read from an outdated snapshot.
+            return;
+        }
+        if let Some(entry) = self.entries.remove(&key) {
+            self.access_order.remove(&entry.last_access);
+        }
+        while self.entries.len() >= capacity {
+            let oldest_access = *self
+                .access_order
+                .keys()
+                .next()
+                .expect("BUG: value cache size is inconsistent with its entries");
+            let oldest_key = self.access_order.remove(&oldest_access).unwrap();
+            self.entries.remove(&oldest_key);
+        }
+
+        let access = self.next_access();
+        self.access_order.insert(access, key.clone());
+        self.entries.insert(
+            key,
+            CacheEntry {
+                value,
+                last_access: access,
+            },
+        );
+    }
+
+    fn clear(&mut self, epoch: u64) {
+        self.epoch = epoch;
+        self.entries.clear();
+        self.access_order.clear();
+    }
+}
+
+/// Bounded cache of values decoded from the storage, which can be shared by the API handlers
+/// reading the same hot entries.
+///
+/// Values are cached together with the *epoch* of the database state they were read from.
+/// The epoch is incremented each time changes are merged into the database via [`Blockchain`]
+/// or [`BlockchainMut`], which invalidates all cached values. Once the cache capacity
+/// is exhausted, the least recently used values are evicted.
+///
+/// Values are read from the cache via a [`CachedSnapshot`], which can be obtained with
+/// [`Blockchain::cached_snapshot()`]. The cache is disabled by default; its capacity
+/// can be set with [`Blockchain::with_value_cache_capacity()`].
+///
+/// [`Blockchain`]: struct.Blockchain.html
+/// [`BlockchainMut`]: struct.BlockchainMut.html
+/// [`CachedSnapshot`]: struct.CachedSnapshot.html
+/// [`Blockchain::cached_snapshot()`]: struct.Blockchain.html#method.cached_snapshot
+/// [`Blockchain::with_value_cache_capacity()`]: struct.Blockchain.html#method.with_value_cache_capacity
+pub struct ValueCache {
+    capacity: usize,
+    /// Epoch of the database state. The lock is held for writing while changes are merged
+    /// into the database, so that snapshots are always taken together with the matching epoch.
+    epoch: RwLock<u64>,
+    inner: Mutex<CacheInner>,
+}
+
+impl fmt::Debug for ValueCache {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("ValueCache")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl Default for ValueCache {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl ValueCache {
+    /// Creates a cache with the specified capacity, measured in the number of values.
+    /// A zero capacity disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            epoch: RwLock::default(),
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Returns the capacity of the cache.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of cached values.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Checks if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Takes a snapshot with `take_snapshot` and wraps it together with the epoch
+    /// of the database state.
+    pub(crate) fn snapshot<F>(self: &Arc<Self>, take_snapshot: F) -> CachedSnapshot
+    where
+        F: FnOnce() -> Box<dyn Snapshot>,
+    {
+        if self.capacity == 0 {
+            return CachedSnapshot {
+                snapshot: take_snapshot(),
+                cache: None,
+                epoch: 0,
+            };
+        }
+
+        let epoch = self.epoch.read().unwrap();
+        CachedSnapshot {
+            snapshot: take_snapshot(),
+            cache: Some(Arc::clone(self)),
+            epoch: *epoch,
+        }
+    }
+
+    /// Merges changes into the database with `merge` and invalidates all cached values.
+    pub(crate) fn invalidate_after<T>(&self, merge: impl FnOnce() -> T) -> T {
+        if self.capacity == 0 {
+            return merge();
+        }
+
+        let mut epoch = self.epoch.write().unwrap();
+        let output = merge();
+        *epoch += 1;
+        self.inner.lock().unwrap().clear(*epoch);
+        output
+    }
+
+    fn get_or_insert_with<V, F>(&self, key: ValueCacheKey, load: F) -> Option<V>
+    where
+        V: Clone + Send + Sync + 'static,
+        F: FnOnce() -> Option<V>,
+    {
+        let cached = self.inner.lock().unwrap().get(&key);
+        if let Some(value) = cached {
+            return value.downcast_ref::<V>().cloned();
+        }
+
+        let value = load()?;
+        self.inner
+            .lock()
+            .unwrap()
+            .insert(key, Arc::new(value.clone()), self.capacity);
+        Some(value)
+    }
+}
+
+/// Database snapshot, reads from which can be cached in the [`ValueCache`] of the blockchain.
+///
+/// The snapshot dereferences to `dyn Snapshot`, so it can be used everywhere a plain snapshot
+/// is expected (e.g., with [`SnapshotExt`] or to construct service schemas). Only the reads
+/// performed via the methods of this type are cached.
+///
+/// [`ValueCache`]: struct.ValueCache.html
+/// [`SnapshotExt`]: ../runtime/trait.SnapshotExt.html
+pub struct CachedSnapshot {
+    snapshot: Box<dyn Snapshot>,
+    cache: Option<Arc<ValueCache>>,
+    epoch: u64,
+}
+
+impl fmt::Debug for CachedSnapshot {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("CachedSnapshot")
+            .field("cache", &self.cache)
+            .field("epoch", &self.epoch)
+            .finish()
+    }
+}
+
+impl CachedSnapshot {
+    /// Returns the value for the `key` in the index with the specified `address`, loading it
+    /// with `load` if the value is not cached. If `load` returns `None`, nothing is cached.
+    ///
+    /// `load` must read the value from the provided snapshot, which is the same as
+    /// the wrapped one. The cached values are distinguished by their type, thus the same
+    /// address and key may be used with different types of values.
+    pub fn get_or_load<K, V, F>(
+        &self,
+        address: impl Into<IndexAddress>,
+        key: &K,
+        load: F,
+    ) -> Option<V>
+    where
+        K: BinaryKey + ?Sized,
+        V: Clone + Send + Sync + 'static,
+        F: FnOnce(&dyn Snapshot) -> Option<V>,
+    {
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return load(self.snapshot.as_ref()),
+        };
+
+        let mut key_bytes = vec![0; key.size()];
+        key.write(&mut key_bytes);
+        let cache_key = ValueCacheKey {
+            epoch: self.epoch,
+            address: address.into(),
+            key: key_bytes,
+            value_type: TypeId::of::<V>(),
+        };
+        cache.get_or_insert_with(cache_key, || load(self.snapshot.as_ref()))
+    }
+
+    /// Returns the value for the `key` in the `MapIndex` with the specified `address`.
+    pub fn map_value<K, V>(&self, address: impl Into<IndexAddress>, key: &K) -> Option<V>
+    where
+        K: BinaryKey + ?Sized,
+        V: BinaryValue + Clone + Send + Sync + 'static,
+    {
+        let address = address.into();
+        self.get_or_load(address.clone(), key, |snapshot| {
+            snapshot.get_map::<_, K, V>(address).get(key)
+        })
+    }
+
+    /// Returns the value for the `key` in the `ProofMapIndex` with the specified `address`.
+    pub fn proof_map_value<K, V>(&self, address: impl Into<IndexAddress>, key: &K) -> Option<V>
+    where
+        K: BinaryKey + ObjectHash + ?Sized,
+        V: BinaryValue + Clone + Send + Sync + 'static,
+    {
+        let address = address.into();
+        self.get_or_load(address.clone(), key, |snapshot| {
+            snapshot.get_proof_map::<_, K, V>(address).get(key)
+        })
+    }
+
+    /// Returns the wrapped snapshot.
+    pub fn into_inner(self) -> Box<dyn Snapshot> {
+        self.snapshot
+    }
+}
+
+impl Deref for CachedSnapshot {
+    type Target = dyn Snapshot;
+
+    fn deref(&self) -> &Self::Target {
+        self.snapshot.as_ref()
+    }
+}
+
+impl AsRef<dyn Snapshot> for CachedSnapshot {
+    fn as_ref(&self) -> &(dyn Snapshot + 'static) {
+        self.snapshot.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum_merkledb::{Database, TemporaryDB};
+
+    use std::iter;
+
+    use super::*;
+    use crate::{
+        blockchain::{BlockParams, Blockchain, BlockchainMut},
+        helpers::{Height, ValidatorId},
+        runtime::SnapshotExt,
+    };
+
+    fn create_db() -> TemporaryDB {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        fork.get_map("map").put(&1_u64, "one".to_owned());
+        fork.get_proof_map("proof_map").put(&1_u64, 100_u64);
+        db.merge(fork.into_patch()).unwrap();
+        db
+    }
+
+    #[test]
+    fn values_are_cached() {
+        let db = create_db();
+        let cache = Arc::new(ValueCache::new(10));
+        let snapshot = cache.snapshot(|| db.snapshot());
+
+        let value = snapshot.map_value::<u64, String>("map", &1);
+        assert_eq!(value.as_deref(), Some("one"));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(
+            snapshot.proof_map_value::<u64, u64>("proof_map", &1),
+            Some(100)
+        );
+        assert_eq!(cache.len(), 2);
+
+        // Missing values are not cached.
+        assert_eq!(snapshot.map_value::<u64, String>("map", &2), None);
+        assert_eq!(cache.len(), 2);
+
+        let mut loaded = false;
+        let value = snapshot.get_or_load("map", &1_u64, |_| {
+            loaded = true;
+            Some(String::new())
+        });
+        assert!(!loaded);
+        assert_eq!(value.as_deref(), Some("one"));
+
+        // Values of another type are cached separately.
+        let value = snapshot.get_or_load("map", &1_u64, |_| Some(1_u8));
+        assert_eq!(value, Some(1));
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn values_are_invalidated_on_merge() {
+        let db = create_db();
+        let cache = Arc::new(ValueCache::new(10));
+        let old_snapshot = cache.snapshot(|| db.snapshot());
+        assert_eq!(
+            old_snapshot.map_value::<u64, String>("map", &1).as_deref(),
+            Some("one")
+        );
+
+        cache
+            .invalidate_after(|| {
+                let fork = db.fork();
+                fork.get_map("map").put(&1_u64, "uno".to_owned());
+                db.merge(fork.into_patch())
+            })
+            .unwrap();
+        assert!(cache.is_empty());
+
+        let snapshot = cache.snapshot(|| db.snapshot());
+        assert_eq!(
+            snapshot.map_value::<u64, String>("map", &1).as_deref(),
+            Some("uno")
+        );
+        assert_eq!(cache.len(), 1);
+        // Reads from outdated snapshots are neither served from the cache nor cached.
+        assert_eq!(
+            old_snapshot.map_value::<u64, String>("map", &1).as_deref(),
+            Some("one")
+        );
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn blockchain_invalidates_values_on_merge() {
+        let blockchain = Blockchain::build_for_tests().with_value_cache_capacity(10);
+        let mut blockchain = blockchain.into_mut_with_dummy_config().build();
+        let cached_height = |blockchain: &BlockchainMut| {
+            blockchain
+                .as_ref()
+                .cached_snapshot()
+                .get_or_load("height", &(), |snapshot| Some(snapshot.for_core().height()))
+                .unwrap()
+        };
+        assert_eq!(cached_height(&blockchain), Height(0));
+        assert_eq!(blockchain.as_ref().value_cache().len(), 1);
+
+        let patch = blockchain.create_patch(BlockParams::new(ValidatorId(0), Height(1), &[]), &());
+        blockchain.commit(patch, iter::empty()).unwrap();
+        assert!(blockchain.as_ref().value_cache().is_empty());
+        assert_eq!(cached_height(&blockchain), Height(1));
+
+        blockchain
+            .as_ref()
+            .merge_node_local(|fork| {
+                fork.get_entry("local").set(1_u64);
+            })
+            .unwrap();
+        assert!(blockchain.as_ref().value_cache().is_empty());
+    }
+
+    #[test]
+    fn least_recently_used_values_are_evicted() {
+        let db = create_db();
+        let cache = Arc::new(ValueCache::new(2));
+        let snapshot = cache.snapshot(|| db.snapshot());
+        for i in 0_u64..3 {
+            snapshot.get_or_load("test", &i, |_| Some(i));
+            // Keep the first value hot.
+            snapshot.get_or_load("test", &0_u64, |_| Some(0_u64));
+        }
+        assert_eq!(cache.len(), 2);
+
+        let mut loaded = false;
+        snapshot.get_or_load("test", &0_u64, |_| {
+            loaded = true;
+            Some(0_u64)
+        });
+        assert!(!loaded);
+        snapshot.get_or_load("test", &1_u64, |_| {
+            loaded = true;
+            Some(1_u64)
+        });
+        assert!(loaded);
+
+        // Disabled cache does not store values.
+        let cache = Arc::new(ValueCache::default());
+        let snapshot = cache.snapshot(|| db.snapshot());
+        assert_eq!(
+            snapshot.map_value::<u64, String>("map", &1).as_deref(),
+            Some("one")
+        );
+        assert!(cache.is_empty());
+    }
+}
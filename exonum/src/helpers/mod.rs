@@ -92,6 +92,34 @@ pub mod pb_optional_hash {
     }
 }
 
+/// Module for serializing `Option<Height>` to Protobuf. `None` is represented
+/// as zero height.
+///
+/// It can be used with `ProtobufConvert` derive macro, e.g.:
+///
+/// ```ignore
+/// #[derive(Debug, ProtobufConvert)]
+/// #[protobuf_convert(source = "path::to::ProtoStructure")]
+/// struct Structure {
+///     #[protobuf_convert(with = "exonum::helpers::pb_optional_height")]
+///     pub maybe_height: Option<Height>,
+/// }
+/// ```
+pub mod pb_optional_height {
+    use super::Height;
+
+    /// Deserializes `Option<Height>` from Protobuf.
+    #[allow(clippy::unnecessary_wraps)] // Required by the `ProtobufConvert` derive macro.
+    pub fn from_pb(pb: u64) -> anyhow::Result<Option<Height>> {
+        Ok(if pb == 0 { None } else { Some(Height(pb)) })
+    }
+
+    /// Serializes `Option<Height>` to Protobuf.
+    pub fn to_pb(value: &Option<Height>) -> u64 {
+        value.map_or(0, |height| height.0)
+    }
+}
+
 /// Module for serializing `semver::Version` to Protobuf.
 ///
 /// It can be used with `ProtobufConvert` derive macro, e.g.:
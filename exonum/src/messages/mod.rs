@@ -107,6 +107,8 @@ pub use self::{
 
 use crate::crypto::{PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH};
 
+pub mod offline;
+
 mod signed;
 mod types;
 
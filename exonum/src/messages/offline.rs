@@ -0,0 +1,210 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Offline signing of transactions.
+//!
+//! This module encodes transactions according to the Exonum message layout without
+//! the Protobuf runtime, so that transactions can be signed on an air-gapped machine
+//! or a hardware signer. The encoding logic only depends on `core` and `alloc`, and
+//! Ed25519 signatures are provided by `exonum-crypto`; thus, the module can be ported
+//! to `no_std` environments as is.
+//!
+//! The signing workflow consists of two steps:
+//!
+//! 1. [`transaction_payload`] encodes the transaction; the payload is signed with
+//!   the Ed25519 secret key of the transaction author.
+//! 2. [`signed_message`] combines the payload, the author key and the signature
+//!   into a serialized `SignedMessage`, which can be submitted to the node.
+//!
+//! [`sign_transaction`] performs both steps with a keypair available locally.
+//!
+//! # Examples
+//!
+//! ```
+//! use exonum::{
+//!     crypto::KeyPair,
+//!     merkledb::BinaryValue,
+//!     messages::{offline, SignedMessage},
+//!     runtime::AnyTx,
+//! };
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let keypair = KeyPair::random();
+//! let bytes = offline::sign_transaction(100, 0, b"arguments", &keypair);
+//! // The transaction can be decoded and verified as usual.
+//! let tx = SignedMessage::from_bytes(bytes.into())?.into_verified::<AnyTx>()?;
+//! assert_eq!(tx.author(), keypair.public_key());
+//! assert_eq!(tx.payload().call_info.instance_id, 100);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`transaction_payload`]: fn.transaction_payload.html
+//! [`signed_message`]: fn.signed_message.html
+//! [`sign_transaction`]: fn.sign_transaction.html
+
+use crate::{
+    crypto::{self, KeyPair, PublicKey, Signature},
+    runtime::{InstanceId, MethodId},
+};
+
+/// Protobuf wire type for variable-length integers.
+const WIRE_VARINT: u8 = 0;
+/// Protobuf wire type for length-delimited fields.
+const WIRE_LEN: u8 = 2;
+
+// Field numbers of the `CoreMessage`, `AnyTx` and `CallInfo` messages.
+const CORE_MESSAGE_ANY_TX: u8 = 1;
+const ANY_TX_CALL_INFO: u8 = 1;
+const ANY_TX_ARGUMENTS: u8 = 2;
+const CALL_INFO_INSTANCE_ID: u8 = 1;
+const CALL_INFO_METHOD_ID: u8 = 2;
+// Field numbers of the `SignedMessage` message and of the `data` field of crypto types.
+const SIGNED_MESSAGE_PAYLOAD: u8 = 1;
+const SIGNED_MESSAGE_AUTHOR: u8 = 2;
+const SIGNED_MESSAGE_SIGNATURE: u8 = 3;
+const CRYPTO_DATA: u8 = 1;
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Writes an integer field. As per Protobuf 3 encoding rules, zero values are skipped.
+fn write_uint_field(buffer: &mut Vec<u8>, field: u8, value: u32) {
+    if value != 0 {
+        buffer.push((field << 3) | WIRE_VARINT);
+        write_varint(buffer, u64::from(value));
+    }
+}
+
+/// Writes a length-delimited field, even if it is empty. Thus, the function should be used
+/// for embedded messages, but not for `bytes` fields.
+fn write_len_field(buffer: &mut Vec<u8>, field: u8, data: &[u8]) {
+    buffer.push((field << 3) | WIRE_LEN);
+    write_varint(buffer, data.len() as u64);
+    buffer.extend_from_slice(data);
+}
+
+/// Writes a `bytes` field. As per Protobuf 3 encoding rules, empty values are skipped.
+fn write_bytes_field(buffer: &mut Vec<u8>, field: u8, data: &[u8]) {
+    if !data.is_empty() {
+        write_len_field(buffer, field, data);
+    }
+}
+
+/// Encodes a transaction calling the method `method_id` of the service instance `instance_id`
+/// with the specified serialized `arguments`. The returned bytes are the payload to be signed
+/// by the transaction author.
+pub fn transaction_payload(
+    instance_id: InstanceId,
+    method_id: MethodId,
+    arguments: &[u8],
+) -> Vec<u8> {
+    let mut call_info = Vec::with_capacity(12);
+    write_uint_field(&mut call_info, CALL_INFO_INSTANCE_ID, instance_id);
+    write_uint_field(&mut call_info, CALL_INFO_METHOD_ID, method_id);
+
+    let mut any_tx = Vec::with_capacity(call_info.len() + arguments.len() + 12);
+    write_len_field(&mut any_tx, ANY_TX_CALL_INFO, &call_info);
+    write_bytes_field(&mut any_tx, ANY_TX_ARGUMENTS, arguments);
+
+    let mut payload = Vec::with_capacity(any_tx.len() + 6);
+    write_len_field(&mut payload, CORE_MESSAGE_ANY_TX, &any_tx);
+    payload
+}
+
+/// Serializes a `SignedMessage` with the specified payload, public key of the author
+/// and the signature of the payload. The signature is not checked.
+pub fn signed_message(payload: &[u8], author: &PublicKey, signature: &Signature) -> Vec<u8> {
+    let mut author_bytes = Vec::with_capacity(crypto::PUBLIC_KEY_LENGTH + 2);
+    write_bytes_field(&mut author_bytes, CRYPTO_DATA, author.as_ref());
+    let mut signature_bytes = Vec::with_capacity(crypto::SIGNATURE_LENGTH + 2);
+    write_bytes_field(&mut signature_bytes, CRYPTO_DATA, signature.as_ref());
+
+    let mut message =
+        Vec::with_capacity(payload.len() + author_bytes.len() + signature_bytes.len() + 12);
+    write_bytes_field(&mut message, SIGNED_MESSAGE_PAYLOAD, payload);
+    write_len_field(&mut message, SIGNED_MESSAGE_AUTHOR, &author_bytes);
+    write_len_field(&mut message, SIGNED_MESSAGE_SIGNATURE, &signature_bytes);
+    message
+}
+
+/// Encodes and signs a transaction with the provided keypair, returning the serialized
+/// `SignedMessage`. The hash of the returned bytes is the hash of the transaction.
+pub fn sign_transaction(
+    instance_id: InstanceId,
+    method_id: MethodId,
+    arguments: &[u8],
+    keypair: &KeyPair,
+) -> Vec<u8> {
+    let payload = transaction_payload(instance_id, method_id, arguments);
+    let signature = crypto::sign(&payload, keypair.secret_key());
+    signed_message(&payload, &keypair.public_key(), &signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum_merkledb::{BinaryValue, ObjectHash};
+
+    use super::*;
+    use crate::{
+        crypto::hash,
+        messages::{SignedMessage, Verified},
+        runtime::{AnyTx, CallInfo},
+    };
+
+    #[test]
+    fn offline_signing_matches_protobuf_encoding() {
+        let keypair = KeyPair::random();
+        let cases: Vec<(InstanceId, MethodId, Vec<u8>)> = vec![
+            (0, 0, vec![]),
+            (100, 0, b"arguments".to_vec()),
+            (0, 5, vec![0]),
+            (u32::MAX, 300, vec![7; 1_000]),
+        ];
+
+        for (instance_id, method_id, arguments) in cases {
+            let expected = AnyTx::new(CallInfo::new(instance_id, method_id), arguments.clone())
+                .sign_with_keypair(&keypair);
+            let bytes = sign_transaction(instance_id, method_id, &arguments, &keypair);
+            assert_eq!(bytes, expected.to_bytes());
+            assert_eq!(hash(&bytes), expected.object_hash());
+
+            let tx = SignedMessage::from_bytes(bytes.into())
+                .unwrap()
+                .into_verified::<AnyTx>()
+                .unwrap();
+            assert_eq!(tx, expected);
+        }
+    }
+
+    #[test]
+    fn signed_message_with_external_signature() {
+        let keypair = KeyPair::random();
+        let payload = transaction_payload(10, 1, b"test");
+        let signature = crypto::sign(&payload, keypair.secret_key());
+        let bytes = signed_message(&payload, &keypair.public_key(), &signature);
+
+        let tx = SignedMessage::from_bytes(bytes.into()).unwrap();
+        assert_eq!(tx.payload, payload);
+        assert_eq!(tx.author, keypair.public_key());
+        assert_eq!(tx.signature, signature);
+        let tx: Verified<AnyTx> = tx.into_verified().unwrap();
+        assert_eq!(tx.payload().arguments, b"test");
+    }
+}
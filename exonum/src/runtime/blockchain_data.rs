@@ -20,15 +20,26 @@ use exonum_merkledb::{
 
 use super::{
     versioning::{ArtifactReqError, RequireArtifact},
-    DispatcherSchema, InstanceQuery, InstanceSpec, InstanceState,
+    Capabilities, Capability, DispatcherSchema, InstanceQuery, InstanceSpec, InstanceState,
 };
 use crate::blockchain::{IndexProof, Schema as CoreSchema};
 
 /// Provides access to blockchain data for the executing service.
+///
+/// Access obtained from the [`ExecutionContext`] is restricted by the [capabilities] of
+/// the executing service: reading the core schema requires the `read_core_schema` capability,
+/// and reading the dispatcher schema or data of other services requires
+/// the `read_service_data` capability. Accessing the data without the required capability
+/// panics, which makes the call fail. Data of the executing service is always accessible.
+///
+/// [`ExecutionContext`]: struct.ExecutionContext.html
+/// [capabilities]: struct.Capabilities.html
 #[derive(Debug, Clone)]
 pub struct BlockchainData<T> {
     access: T,
     instance_name: String,
+    /// Capabilities of the executing service, or `None` if the access is not restricted.
+    capabilities: Option<Capabilities>,
 }
 
 impl<T: RawAccess + AsReadonly> BlockchainData<T> {
@@ -38,6 +49,32 @@ impl<T: RawAccess + AsReadonly> BlockchainData<T> {
         Self {
             access,
             instance_name: instance_name.into(),
+            capabilities: None,
+        }
+    }
+
+    /// Restricts access to the data according to the capabilities of the executing service.
+    pub(crate) fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
+    /// Panics if the executing service lacks the specified capability.
+    fn require_capability(&self, capability: Capability) {
+        if let Some(capabilities) = self.capabilities {
+            assert!(
+                capabilities.contains(capability),
+                "Service `{}` lacks the `{}` capability required to access the data.",
+                self.instance_name,
+                capability
+            );
+        }
+    }
+
+    /// Panics if the executing service cannot access data of the specified service.
+    fn require_service_access(&self, spec: &InstanceSpec) {
+        if spec.name != self.instance_name {
+            self.require_capability(Capability::ReadServiceData);
         }
     }
 
@@ -55,17 +92,29 @@ impl<T: RawAccess + AsReadonly> BlockchainData<T> {
     /// Check that your wrapper prevent such attempts to access.
     #[doc(hidden)]
     pub fn unstructured_access(&self) -> T::Readonly {
+        self.require_capability(Capability::ReadCoreSchema);
+        self.require_capability(Capability::ReadServiceData);
         self.access.as_readonly()
     }
 
     /// Returns core schema.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the executing service lacks the `read_core_schema` capability.
     pub fn for_core(&self) -> CoreSchema<T::Readonly> {
-        CoreSchema::new(self.unstructured_access())
+        self.require_capability(Capability::ReadCoreSchema);
+        CoreSchema::new(self.access.as_readonly())
     }
 
     /// Returns dispatcher schema.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the executing service lacks the `read_service_data` capability.
     pub fn for_dispatcher(&self) -> DispatcherSchema<T::Readonly> {
-        DispatcherSchema::new(self.unstructured_access())
+        self.require_capability(Capability::ReadServiceData);
+        DispatcherSchema::new(self.access.as_readonly())
     }
 
     /// Returns a mount point for another service. If the service with `id` does not exist,
@@ -79,11 +128,18 @@ impl<T: RawAccess + AsReadonly> BlockchainData<T> {
     /// as a safer alternative, which performs all necessary checks.
     ///
     /// [`service_schema`]: #method.service_schema
+    ///
+    /// # Panics
+    ///
+    /// Panics if the executing service lacks the `read_service_data` capability,
+    /// unless `id` refers to the executing service itself.
     pub fn for_service<'q>(
         &self,
         id: impl Into<InstanceQuery<'q>>,
     ) -> Option<Prefixed<T::Readonly>> {
-        mount_point_for_service(self.access.as_readonly(), id).map(|(access, _)| access)
+        let (access, spec) = mount_point_for_service(self.access.as_readonly(), id)?;
+        self.require_service_access(&spec);
+        Some(access)
     }
 
     /// Retrieves schema for a service.
@@ -97,12 +153,20 @@ impl<T: RawAccess + AsReadonly> BlockchainData<T> {
     /// - Service has an incompatible artifact version
     ///
     /// [`ArtifactReqError`]: versioning/enum.ArtifactReqError.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if the executing service lacks the `read_service_data` capability,
+    /// unless `service_id` refers to the executing service itself.
     pub fn service_schema<'q, S, I>(&self, service_id: I) -> Result<S, ArtifactReqError>
     where
         S: RequireArtifact + FromAccess<Prefixed<T::Readonly>>,
         I: Into<InstanceQuery<'q>>,
     {
-        schema_for_service(self.access.as_readonly(), service_id)
+        let (access, spec) = mount_point_for_service(self.access.as_readonly(), service_id)
+            .ok_or(ArtifactReqError::NoService)?;
+        self.require_service_access(&spec);
+        schema_from_mount_point(access, &spec)
     }
 
     /// Returns a mount point for the data of the executing service instance.
@@ -137,7 +201,11 @@ where
 {
     /// Erases the enclosed access, converting it to the generic form.
     pub fn erase_access(self) -> BlockchainData<GenericRawAccess<'a>> {
-        BlockchainData::new(self.access.into(), self.instance_name)
+        BlockchainData {
+            access: self.access.into(),
+            instance_name: self.instance_name,
+            capabilities: self.capabilities,
+        }
     }
 }
 
@@ -161,7 +229,17 @@ where
 {
     let (access, spec) =
         mount_point_for_service(access, service_id).ok_or(ArtifactReqError::NoService)?;
+    schema_from_mount_point(access, &spec)
+}
 
+fn schema_from_mount_point<T, S>(
+    access: Prefixed<T>,
+    spec: &InstanceSpec,
+) -> Result<S, ArtifactReqError>
+where
+    T: RawAccess,
+    S: RequireArtifact + FromAccess<Prefixed<T>>,
+{
     let artifact_req = S::required_artifact();
     artifact_req.try_match(&spec.artifact)?;
     Ok(S::from_root(access).unwrap())
@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-pub use self::schema::{remove_local_migration_result, Schema};
+pub use self::{
+    schema::{remove_local_migration_result, Schema},
+    shadow::{ShadowDivergence, ShadowInstance, ShadowReport, ShadowStats},
+};
 
 use exonum_merkledb::{
     migration::{
@@ -42,20 +45,27 @@ use crate::{
             InstanceMigration, MigrationContext, MigrationError, MigrationScript, MigrationStatus,
             MigrationType,
         },
-        ArtifactId, ArtifactStatus, CoreError, InstanceDescriptor, InstanceId, InstanceQuery,
+        ArtifactId, Capabilities, CoreError, InstanceDescriptor, InstanceId, InstanceQuery,
         InstanceSpec, InstanceState, InstanceStatus, Runtime, RuntimeFeature, RuntimeIdentifier,
         RuntimeInstance,
     },
 };
 
-use self::schema::{ArtifactAction, MigrationTransition, ModifiedInstanceInfo};
+use self::{
+    schema::{ArtifactAction, MigrationTransition, ModifiedInstanceInfo},
+    shadow::Shadows,
+};
 
 #[cfg(test)]
 mod migration_tests;
 mod schema;
+mod shadow;
 #[cfg(test)]
 mod tests;
 
+/// Hashes of changes made by the calls within a block, which are recorded only if requested.
+pub(crate) type CallHashes = Option<Vec<(CallInBlock, Hash)>>;
+
 // Warning shared among several log messages.
 const NOT_FINAL_WARNING: &str =
     "NB: This operation may be rolled back if the fork with it is discarded, \
@@ -78,7 +88,12 @@ struct CommittedServices {
 impl CommittedServices {
     fn insert(&mut self, id: InstanceId, info: ServiceInfo) {
         let name = info.name.clone();
-        self.instances.insert(id, info);
+        if let Some(old_info) = self.instances.insert(id, info) {
+            // The service may have been renamed.
+            if old_info.name != name {
+                self.instance_names.remove(&old_info.name);
+            }
+        }
         self.instance_names.insert(name, id);
     }
 
@@ -103,6 +118,10 @@ impl CommittedServices {
                 let resolved_id = *self.instance_names.get(name)?;
                 (resolved_id, self.instances.get(&resolved_id)?)
             }
+
+            // Default instances are stored in the dispatcher schema only, so interface
+            // queries should be resolved before reaching this method.
+            InstanceQuery::Interface(_) => return None,
         };
         Some((InstanceDescriptor::new(id, &info.name), &info.status))
     }
@@ -353,6 +372,7 @@ pub struct Dispatcher {
     runtimes: BTreeMap<u32, Box<dyn Runtime>>,
     service_infos: CommittedServices,
     migrations: Migrations,
+    shadows: Shadows,
 }
 
 impl Dispatcher {
@@ -368,6 +388,7 @@ impl Dispatcher {
                 .collect(),
             service_infos: CommittedServices::default(),
             migrations: Migrations::new(blockchain),
+            shadows: Shadows::default(),
         };
         for runtime in this.runtimes.values_mut() {
             runtime.initialize(blockchain);
@@ -385,9 +406,8 @@ impl Dispatcher {
 
         // Restore information about the deployed services.
         for (artifact, state) in schema.artifacts().iter() {
-            debug_assert_eq!(
-                state.status,
-                ArtifactStatus::Active,
+            debug_assert!(
+                state.status.is_deployed(),
                 "BUG: Artifact should not be in pending state."
             );
 
@@ -445,6 +465,30 @@ impl Dispatcher {
             .unwrap_or_else(|err| panic!("Cannot deploy a built-in artifact: {}", err));
     }
 
+    /// Adds a local-only shadow instance. The shadow artifact is deployed locally
+    /// if necessary, and the shadow is started in the corresponding runtime.
+    ///
+    /// # Panics
+    ///
+    /// This method treats errors during artifact deployment as fatal and panics on them.
+    pub(crate) fn add_shadow_instance(&mut self, snapshot: &dyn Snapshot, shadow: ShadowInstance) {
+        let artifact = shadow.artifact.clone();
+        if !self.is_artifact_deployed(&artifact) {
+            self.deploy_artifact(artifact.clone(), shadow.deploy_spec.clone())
+                .unwrap_or_else(|err| panic!("Cannot deploy a shadow artifact: {}", err));
+            self.shadows.add_local_artifact(artifact.clone());
+        }
+
+        let id = self.shadows.add(&shadow);
+        let spec = InstanceSpec::from_raw_parts(id, shadow.name, artifact);
+        log::info!("Starting shadow service {} for `{}`", spec, shadow.target);
+        let state = InstanceState::from_raw_parts(spec, None, Some(InstanceStatus::Active), None);
+        self.runtimes
+            .get_mut(&state.spec.artifact.runtime_id)
+            .expect("BUG: runtime should exist since the shadow artifact is deployed")
+            .update_service_status(snapshot, &state);
+    }
+
     /// Add a built-in service with the predefined identifier.
     ///
     /// This method must be followed by the `start_builtin_instances()` call in order
@@ -499,6 +543,10 @@ impl Dispatcher {
     ) -> Result<(), ExecutionError> {
         // TODO: revise dispatcher integrity checks [ECR-3743]
         debug_assert!(artifact.validate().is_ok());
+        if self.shadows.is_local_artifact(&artifact) {
+            // The artifact is already deployed for a shadow instance.
+            return Ok(());
+        }
         log::info!(
             "Deploying artifact `{}` with payload {:?}",
             artifact,
@@ -549,6 +597,13 @@ impl Dispatcher {
         Schema::new(fork).unload_artifact(artifact)
     }
 
+    pub(crate) fn deprecate_artifact(
+        fork: &Fork,
+        artifact: &ArtifactId,
+    ) -> Result<(), ExecutionError> {
+        Schema::new(fork).deprecate_artifact(artifact)
+    }
+
     /// Initiates migration of an existing stopped service to a newer artifact.
     /// The migration script is started once the block corresponding to `fork`
     /// is committed.
@@ -560,6 +615,7 @@ impl Dispatcher {
     ) -> Result<MigrationType, ExecutionError> {
         let mut schema = Schema::new(fork);
         let instance_state = schema.check_migration_initiation(&new_artifact, service_name)?;
+        self.check_method_ids(&instance_state.spec.artifact, &new_artifact)?;
         let maybe_script =
             self.get_migration_script(&new_artifact, instance_state.data_version())?;
         let migration_type = if let Some(script) = maybe_script {
@@ -628,6 +684,86 @@ impl Dispatcher {
         Ok(())
     }
 
+    /// Makes the specified service instance the default one for the interface name.
+    /// The change takes effect immediately.
+    pub(crate) fn set_default_instance(
+        fork: &Fork,
+        interface: &str,
+        instance_id: InstanceId,
+    ) -> Result<(), ExecutionError> {
+        Schema::new(fork).set_default_instance(interface, instance_id)?;
+        log::info!(
+            "Setting service with ID {} as the default instance for `{}`. {}",
+            instance_id,
+            interface,
+            NOT_FINAL_WARNING
+        );
+        Ok(())
+    }
+
+    /// Sets capabilities of a deployed artifact. The change takes effect immediately.
+    pub(crate) fn set_artifact_capabilities(
+        fork: &Fork,
+        artifact: &ArtifactId,
+        capabilities: Capabilities,
+    ) -> Result<(), ExecutionError> {
+        Schema::new(fork).set_artifact_capabilities(artifact, capabilities)?;
+        log::info!(
+            "Setting capabilities of artifact `{}` to {:?}. {}",
+            artifact,
+            capabilities,
+            NOT_FINAL_WARNING
+        );
+        Ok(())
+    }
+
+    /// Returns capabilities of the artifact: either the ones configured by the supervisor,
+    /// or the default capabilities of the artifact runtime.
+    pub(crate) fn artifact_capabilities(&self, fork: &Fork, artifact: &ArtifactId) -> Capabilities {
+        // The schema is accessed via a readonly fork, so that the index is never created
+        // within a block unless the supervisor sets capabilities.
+        Schema::new(fork.readonly())
+            .get_artifact_capabilities(artifact)
+            .unwrap_or_else(|| {
+                self.runtime_by_id(artifact.runtime_id)
+                    .map_or_else(Capabilities::none, |runtime| runtime.default_capabilities())
+            })
+    }
+
+    /// Returns capabilities of the service instance with the specified ID. A non-existing
+    /// service has no capabilities.
+    pub(crate) fn instance_capabilities(
+        &self,
+        fork: &Fork,
+        instance_id: InstanceId,
+    ) -> Capabilities {
+        Schema::new(fork.readonly())
+            .get_instance(instance_id)
+            .map_or_else(Capabilities::none, |state| {
+                self.artifact_capabilities(fork, &state.spec.artifact)
+            })
+    }
+
+    /// Renames a stopped service instance and moves its data to the namespace corresponding
+    /// to the new name. The runtime is notified about the new service name once the block
+    /// built on top of the provided `fork` is committed.
+    pub(crate) fn rename_service(
+        fork: &mut Fork,
+        instance_id: InstanceId,
+        new_name: &str,
+    ) -> Result<(), ExecutionError> {
+        let old_name = Schema::new(&*fork).rename_instance(instance_id, new_name)?;
+        log::info!(
+            "Renaming service `{}` with ID {} to `{}`. {}",
+            old_name,
+            instance_id,
+            new_name,
+            NOT_FINAL_WARNING
+        );
+        fork.move_namespace(&old_name, new_name);
+        Ok(())
+    }
+
     /// Initiates stopping an existing service instance in the blockchain. The stopping
     /// service is active (i.e., processes transactions and the `after_transactions` hook)
     /// until the block built on top of the provided `fork` is committed.
@@ -751,6 +887,14 @@ impl Dispatcher {
         }
     }
 
+    /// Records the hash of unflushed changes made by the `call`, if call hashes are requested.
+    /// The hash is recorded before the changes are rolled back in case of a failed call.
+    fn record_call_hash(fork: &mut Fork, call: CallInBlock, call_hashes: &mut CallHashes) {
+        if let Some(call_hashes) = call_hashes {
+            call_hashes.push((call, fork.unflushed_changes_hash()));
+        }
+    }
+
     /// Executes transaction with the specified ID with fork isolation.
     pub(crate) fn execute(
         &self,
@@ -758,6 +902,7 @@ impl Dispatcher {
         tx_id: Hash,
         tx_index: u32,
         tx: &Verified<AnyTx>,
+        call_hashes: &mut CallHashes,
     ) -> Result<(), ExecutionError> {
         let call_info = &tx.as_ref().call_info;
         let (runtime_id, runtime) =
@@ -778,26 +923,63 @@ impl Dispatcher {
             CoreError::IncorrectInstanceId.with_description(msg)
         })?;
 
+        let shadow_results = self.execute_shadows(fork, &instance.name, tx_id, tx);
+        let target = instance.name.clone();
+
         let context = TopLevelContext::for_transaction(self, fork, instance, tx.author(), tx_id);
-        let mut res =
-            context.call(|ctx| runtime.execute(ctx, call_info.method_id, &tx.as_ref().arguments));
+        let mut res = context
+            .call(|ctx| runtime.execute(ctx, call_info.method_id, &tx.as_ref().arguments))
+            .and_then(|()| self.check_invariants(fork));
+        let call = CallInBlock::transaction(tx_index);
+        Self::record_call_hash(fork, call, call_hashes);
         if let Err(ref mut err) = res {
             fork.rollback();
 
             err.set_runtime_id(runtime_id)
                 .set_call_site(CallSite::from_call_info(call_info, ""));
-            Self::report_error(err, fork, CallInBlock::transaction(tx_index));
+            Self::report_error(err, fork, call);
         } else {
             fork.flush();
         }
+
+        for (shadow, shadow_res) in self.shadows.attached_to(&target).zip(shadow_results) {
+            shadow.record(tx_id, &res, shadow_res);
+        }
         res
     }
 
+    /// Executes a transaction in shadow instances attached to the `target` service.
+    /// Changes made by the shadows are rolled back, so that they do not influence
+    /// the production instance.
+    fn execute_shadows(
+        &self,
+        fork: &mut Fork,
+        target: &str,
+        tx_id: Hash,
+        tx: &Verified<AnyTx>,
+    ) -> Vec<Result<(), ExecutionError>> {
+        let call_info = &tx.as_ref().call_info;
+        self.shadows
+            .attached_to(target)
+            .map(|shadow| {
+                let runtime = self.runtimes[&shadow.runtime_id].as_ref();
+                let instance = InstanceDescriptor::new(shadow.id, target);
+                let context =
+                    TopLevelContext::for_transaction(self, fork, instance, tx.author(), tx_id);
+                let res = context
+                    .call(|ctx| runtime.execute(ctx, call_info.method_id, &tx.as_ref().arguments));
+                fork.rollback();
+                res
+            })
+            .collect()
+    }
+
     /// Calls service hooks of the specified type for all active services.
     fn call_service_hooks(
         &self,
         fork: &mut Fork,
         call_type: &CallType,
+        call_hashes: &mut CallHashes,
     ) -> Vec<(CallInBlock, ExecutionError)> {
         self.service_infos
             .active_instances()
@@ -808,20 +990,20 @@ impl Dispatcher {
                     CallType::AfterTransactions => Runtime::after_transactions,
                     _ => unreachable!(),
                 };
+                let call = match &call_type {
+                    CallType::BeforeTransactions => CallInBlock::before_transactions(instance.id),
+                    CallType::AfterTransactions => CallInBlock::after_transactions(instance.id),
+                    _ => unreachable!(),
+                };
 
-                let res = context.call(|ctx| call_fn(self.runtimes[&runtime_id].as_ref(), ctx));
+                let res = context
+                    .call(|ctx| call_fn(self.runtimes[&runtime_id].as_ref(), ctx))
+                    .and_then(|()| self.check_invariants(fork));
+                Self::record_call_hash(fork, call, call_hashes);
                 if let Err(mut err) = res {
                     fork.rollback();
                     err.set_runtime_id(runtime_id)
                         .set_call_site(CallSite::new(instance.id, call_type.clone()));
-
-                    let call = match &call_type {
-                        CallType::BeforeTransactions => {
-                            CallInBlock::before_transactions(instance.id)
-                        }
-                        CallType::AfterTransactions => CallInBlock::after_transactions(instance.id),
-                        _ => unreachable!(),
-                    };
                     Self::report_error(&err, fork, call);
                     Some((call, err))
                 } else {
@@ -836,8 +1018,9 @@ impl Dispatcher {
     pub(crate) fn before_transactions(
         &self,
         fork: &mut Fork,
+        call_hashes: &mut CallHashes,
     ) -> Vec<(CallInBlock, ExecutionError)> {
-        self.call_service_hooks(fork, &CallType::BeforeTransactions)
+        self.call_service_hooks(fork, &CallType::BeforeTransactions, call_hashes)
     }
 
     /// Calls `after_transactions` for all currently active services, isolating each call.
@@ -845,12 +1028,32 @@ impl Dispatcher {
     /// Changes the status of pending artifacts and services to active in the merkelized
     /// indexes of the dispatcher information scheme. Thus, these statuses will be equally
     /// calculated for precommit and actually committed block.
-    pub(crate) fn after_transactions(&self, fork: &mut Fork) -> Vec<(CallInBlock, ExecutionError)> {
-        let errors = self.call_service_hooks(fork, &CallType::AfterTransactions);
+    pub(crate) fn after_transactions(
+        &self,
+        fork: &mut Fork,
+        call_hashes: &mut CallHashes,
+    ) -> Vec<(CallInBlock, ExecutionError)> {
+        let errors = self.call_service_hooks(fork, &CallType::AfterTransactions, call_hashes);
         Self::activate_pending(fork);
         errors
     }
 
+    /// Checks invariants of all active services against the changes made by a call.
+    /// Services are checked in the order of their identifiers, so the first encountered
+    /// violation, which is returned as an error, is the same on all nodes.
+    ///
+    /// The check is performed before the changes of the call are flushed; thus,
+    /// a violation is handled in the same way as an error returned by the call:
+    /// the changes are rolled back, and the error is recorded for the call.
+    fn check_invariants(&self, fork: &Fork) -> Result<(), ExecutionError> {
+        for (instance, runtime_id) in self.service_infos.active_instances() {
+            self.runtimes[&runtime_id]
+                .check_invariants(&instance, fork.readonly())
+                .map_err(|violation| CoreError::invariant_violated(&instance, &violation))?;
+        }
+        Ok(())
+    }
+
     /// Commits to service instances and artifacts marked as pending in the provided `fork`.
     pub(crate) fn commit_block(&mut self, mut fork: Fork) -> Patch {
         let mut schema = Schema::new(&fork);
@@ -1076,6 +1279,43 @@ impl Dispatcher {
             .map_or(false, |runtime| runtime.is_artifact_deployed(id))
     }
 
+    /// Returns the checksum of the deployed artifact, if the corresponding runtime provides it.
+    pub(crate) fn artifact_checksum(&self, id: &ArtifactId) -> Option<Hash> {
+        self.runtimes
+            .get(&id.runtime_id)
+            .and_then(|runtime| runtime.artifact_checksum(id))
+    }
+
+    /// Checks that `new_artifact` does not reuse method IDs of `old_artifact` for different
+    /// methods. The check is skipped if the runtime does not provide interface methods
+    /// for either of the artifacts.
+    fn check_method_ids(
+        &self,
+        old_artifact: &ArtifactId,
+        new_artifact: &ArtifactId,
+    ) -> Result<(), ExecutionError> {
+        let runtime = match self.runtimes.get(&new_artifact.runtime_id) {
+            Some(runtime) => runtime,
+            None => return Ok(()),
+        };
+        let old_methods = runtime.interface_methods(old_artifact);
+        let new_methods = runtime.interface_methods(new_artifact);
+
+        if let (Some(old_methods), Some(new_methods)) = (old_methods, new_methods) {
+            old_methods
+                .check_compatibility(&new_methods)
+                .map_err(|details| {
+                    let msg = format!(
+                        "Artifact `{}` is incompatible with `{}`: {}",
+                        new_artifact, old_artifact, details
+                    );
+                    CoreError::IncompatibleMethodIds.with_description(msg)
+                })
+        } else {
+            Ok(())
+        }
+    }
+
     /// Looks up a runtime by its identifier.
     pub(crate) fn runtime_by_id(&self, id: u32) -> Option<&dyn Runtime> {
         self.runtimes.get(&id).map(AsRef::as_ref)
@@ -1156,8 +1396,10 @@ impl Mailbox {
     }
 }
 
-/// The actions that will be performed after the deployment is finished.
-pub type ThenFn = Box<dyn FnOnce(Result<(), ExecutionError>) -> Result<(), ExecutionError> + Send>;
+/// The actions that will be performed after the deployment is finished. On success,
+/// the closure receives the checksum of the deployed artifact, if the runtime provides one.
+pub type ThenFn =
+    Box<dyn FnOnce(Result<Option<Hash>, ExecutionError>) -> Result<(), ExecutionError> + Send>;
 
 /// Action to be performed by the dispatcher.
 #[non_exhaustive]
@@ -1194,7 +1436,10 @@ impl Action {
                 spec,
                 then,
             } => {
-                then(dispatcher.deploy_artifact(artifact.clone(), spec)).unwrap_or_else(|e| {
+                let result = dispatcher
+                    .deploy_artifact(artifact.clone(), spec)
+                    .map(|()| dispatcher.artifact_checksum(&artifact));
+                then(result).unwrap_or_else(|e| {
                     log::error!("Deploying artifact {:?} failed: {}", artifact, e);
                 });
             }
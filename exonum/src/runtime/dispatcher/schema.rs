@@ -18,6 +18,7 @@ use exonum_crypto::Hash;
 use exonum_derive::BinaryValue;
 use exonum_merkledb::{
     access::{Access, AccessExt, AsReadonly},
+    validation::is_valid_index_name_component,
     Fork, KeySetIndex, MapIndex, ProofMapIndex,
 };
 use exonum_proto::ProtobufConvert;
@@ -29,8 +30,8 @@ use crate::{
     },
     runtime::{
         migrations::{InstanceMigration, MigrationStatus},
-        ArtifactId, ArtifactState, ArtifactStatus, CoreError, ExecutionError, ExecutionFail,
-        InstanceId, InstanceQuery, InstanceSpec, InstanceState, InstanceStatus,
+        ArtifactId, ArtifactState, ArtifactStatus, Capabilities, CoreError, ExecutionError,
+        ExecutionFail, InstanceId, InstanceQuery, InstanceSpec, InstanceState, InstanceStatus,
     },
 };
 
@@ -40,6 +41,8 @@ const INSTANCES: &str = "dispatcher_instances";
 const PENDING_INSTANCES: &str = "dispatcher_pending_instances";
 const LOCAL_MIGRATION_RESULTS: &str = "dispatcher_local_migration_results";
 const INSTANCE_IDS: &str = "dispatcher_instance_ids";
+const DEFAULT_INSTANCES: &str = "dispatcher_default_instances";
+const ARTIFACT_CAPABILITIES: &str = "dispatcher_artifact_capabilities";
 
 #[derive(Debug)]
 pub(super) enum ArtifactAction {
@@ -137,6 +140,18 @@ impl<T: Access> Schema<T> {
         self.access.get_map(INSTANCE_IDS)
     }
 
+    /// Returns a lookup table mapping interface names to identifiers of the default
+    /// service instances implementing these interfaces.
+    pub(crate) fn default_instances(&self) -> ProofMapIndex<T::Base, str, InstanceId> {
+        self.access.get_proof_map(DEFAULT_INSTANCES)
+    }
+
+    /// Returns capabilities of the artifacts configured by the supervisor. Artifacts
+    /// missing from this index have the default capabilities of their runtime.
+    pub(crate) fn artifact_capabilities(&self) -> ProofMapIndex<T::Base, ArtifactId, Capabilities> {
+        self.access.get_proof_map(ARTIFACT_CAPABILITIES)
+    }
+
     /// Returns a pending artifacts queue used to notify the runtime about artifacts
     /// to be deployed.
     fn pending_artifacts(&self) -> KeySetIndex<T::Base, ArtifactId> {
@@ -153,6 +168,12 @@ impl<T: Access> Schema<T> {
         self.access.get_map(LOCAL_MIGRATION_RESULTS)
     }
 
+    /// Returns the capabilities of an artifact configured by the supervisor, or `None`
+    /// if the artifact has the default capabilities of its runtime.
+    pub fn get_artifact_capabilities(&self, artifact: &ArtifactId) -> Option<Capabilities> {
+        self.artifact_capabilities().get(artifact)
+    }
+
     /// Returns the information about a service instance by its identifier.
     pub fn get_instance<'q>(&self, query: impl Into<InstanceQuery<'q>>) -> Option<InstanceState> {
         let instances = self.instances();
@@ -164,9 +185,19 @@ impl<T: Access> Schema<T> {
                 .and_then(|instance_name| instances.get(&instance_name)),
 
             InstanceQuery::Name(instance_name) => instances.get(instance_name),
+
+            InstanceQuery::Interface(interface) => self
+                .default_instance_id(interface)
+                .and_then(|id| self.instance_ids().get(&id))
+                .and_then(|instance_name| instances.get(&instance_name)),
         }
     }
 
+    /// Returns the identifier of the default service instance for the specified interface name.
+    pub fn default_instance_id(&self, interface: &str) -> Option<InstanceId> {
+        self.default_instances().get(interface)
+    }
+
     /// Returns information about an artifact by its identifier.
     pub fn get_artifact(&self, name: &ArtifactId) -> Option<ArtifactState> {
         self.artifacts().get(name)
@@ -198,7 +229,7 @@ impl<T: Access> Schema<T> {
             CoreError::ArtifactNotDeployed.with_description(msg)
         })?;
 
-        if state.status != ArtifactStatus::Active {
+        if !state.status.is_deployed() {
             let msg = format!(
                 "Requested to unload artifact `{}`, which has non-active status: {}",
                 artifact, state.status
@@ -251,6 +282,11 @@ impl<T: AsReadonly> Schema<T> {
     pub fn service_instances(&self) -> ProofMapIndex<T::Readonly, str, InstanceState> {
         self.access.as_readonly().get_proof_map(INSTANCES)
     }
+
+    /// Readonly mapping of interface names to the default service instances.
+    pub fn default_service_instances(&self) -> ProofMapIndex<T::Readonly, str, InstanceId> {
+        self.access.as_readonly().get_proof_map(DEFAULT_INSTANCES)
+    }
 }
 
 impl Schema<&Fork> {
@@ -303,6 +339,33 @@ impl Schema<&Fork> {
         Ok(())
     }
 
+    /// Marks the provided artifact as deprecated. Unlike unloading, this takes effect
+    /// immediately and does not affect the service instances associated with the artifact.
+    pub(super) fn deprecate_artifact(
+        &mut self,
+        artifact: &ArtifactId,
+    ) -> Result<(), ExecutionError> {
+        let mut state = self.artifacts().get(artifact).ok_or_else(|| {
+            let msg = format!(
+                "Requested to deprecate artifact `{}`, which is not deployed",
+                artifact
+            );
+            CoreError::ArtifactNotDeployed.with_description(msg)
+        })?;
+
+        if state.status != ArtifactStatus::Active {
+            let msg = format!(
+                "Requested to deprecate artifact `{}`, which has non-active status: {}",
+                artifact, state.status
+            );
+            return Err(CoreError::ArtifactNotDeployed.with_description(msg));
+        }
+
+        state.status = ArtifactStatus::Deprecated;
+        self.artifacts().put(artifact, state);
+        Ok(())
+    }
+
     /// Checks preconditions for migration initiation.
     pub(super) fn check_migration_initiation(
         &self,
@@ -641,6 +704,124 @@ impl Schema<&Fork> {
         }
     }
 
+    /// Changes the name of a stopped service instance. Returns the previous name of the service.
+    ///
+    /// This method only updates the dispatcher schema; the service data should be moved
+    /// to the new namespace separately. The runtime will be notified about the updated
+    /// service specification when the block is accepted.
+    /// Makes the specified service instance the default one for the interface name.
+    pub(super) fn set_default_instance(
+        &mut self,
+        interface: &str,
+        instance_id: InstanceId,
+    ) -> Result<(), ExecutionError> {
+        if interface.is_empty() || !is_valid_index_name_component(interface) {
+            let msg = format!(
+                "Interface name `{}` is invalid; use only: a-zA-Z0-9 and _-",
+                interface
+            );
+            return Err(CoreError::IncorrectCall.with_description(msg));
+        }
+        if !self.instance_ids().contains(&instance_id) {
+            let msg = format!(
+                "Cannot make unknown service with ID {} the default instance for `{}`",
+                instance_id, interface
+            );
+            return Err(CoreError::IncorrectInstanceId.with_description(msg));
+        }
+
+        self.default_instances().put(interface, instance_id);
+        Ok(())
+    }
+
+    /// Sets capabilities of a deployed artifact.
+    pub(super) fn set_artifact_capabilities(
+        &mut self,
+        artifact: &ArtifactId,
+        capabilities: Capabilities,
+    ) -> Result<(), ExecutionError> {
+        let is_deployed = self
+            .artifacts()
+            .get(artifact)
+            .map_or(false, |state| state.status.is_deployed());
+        if !is_deployed {
+            let msg = format!(
+                "Cannot set capabilities of artifact `{}`, which is not deployed",
+                artifact
+            );
+            return Err(CoreError::ArtifactNotDeployed.with_description(msg));
+        }
+
+        self.artifact_capabilities().put(artifact, capabilities);
+        Ok(())
+    }
+
+    pub(super) fn rename_instance(
+        &mut self,
+        instance_id: InstanceId,
+        new_name: &str,
+    ) -> Result<String, ExecutionError> {
+        InstanceSpec::is_valid_name(new_name).map_err(|err| {
+            let msg = format!("Cannot rename service to `{}`: {}", new_name, err);
+            CoreError::IncorrectInstanceId.with_description(msg)
+        })?;
+
+        let mut instance_ids = self.instance_ids();
+        let old_name = instance_ids.get(&instance_id).ok_or_else(|| {
+            let msg = format!("Cannot rename unknown service with ID {}", instance_id);
+            CoreError::IncorrectInstanceId.with_description(msg)
+        })?;
+
+        let mut instances = self.instances();
+        let mut state = instances
+            .get(old_name.as_str())
+            .expect("BUG: Instance identifier exists but the corresponding instance is missing.");
+
+        if let Some(pending_status) = state.pending_status {
+            let msg = format!(
+                "Cannot rename service `{}` because it has another state transition \
+                 in progress ({})",
+                state.spec.as_descriptor(),
+                pending_status
+            );
+            return Err(CoreError::ServicePending.with_description(msg));
+        }
+        if state.status != Some(InstanceStatus::Stopped) {
+            let current_status = state
+                .status
+                .as_ref()
+                .map_or_else(|| "none".to_owned(), ToString::to_string);
+            let msg = format!(
+                "Cannot rename service `{}` because it is not stopped (current status: {})",
+                state.spec.as_descriptor(),
+                current_status
+            );
+            return Err(CoreError::InvalidServiceTransition.with_description(msg));
+        }
+
+        if instances.contains(new_name) {
+            let msg = format!("Service with name `{}` already exists", new_name);
+            return Err(CoreError::ServiceNameExists.with_description(msg));
+        }
+
+        state.spec.name = new_name.to_owned();
+        instances.remove(old_name.as_str());
+        instances.put(new_name, state);
+        instance_ids.put(&instance_id, new_name.to_owned());
+
+        let mut modified_instances = self.modified_instances();
+        let modified_info =
+            modified_instances
+                .get(old_name.as_str())
+                .unwrap_or(ModifiedInstanceInfo {
+                    migration_transition: None,
+                });
+        modified_instances.remove(old_name.as_str());
+        modified_instances.put(new_name, modified_info);
+
+        Ok(old_name)
+    }
+
     /// Makes pending artifacts and instances active.
     pub(super) fn activate_pending(&mut self) {
         // Activate pending artifacts.
@@ -0,0 +1,224 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Local-only shadow service instances used to canary service upgrades.
+
+use exonum_crypto::Hash;
+use exonum_merkledb::BinaryValue;
+
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use crate::runtime::{ArtifactId, ExecutionError, InstanceId};
+
+/// Maximum number of divergences retained by a `ShadowReport`.
+const MAX_DIVERGENCES: usize = 128;
+
+/// Local-only instance of a service artifact shadowing a production service instance.
+///
+/// A shadow instance receives the same transactions as the production instance it is
+/// attached to and executes them on the same blockchain state. Unlike the production
+/// instance, the shadow does not participate in consensus: all changes made by it
+/// are discarded immediately after each call, so they never reach the block, and
+/// the shadow always observes exactly the state the production instance works with.
+///
+/// The outcomes of shadow and production calls are compared with each other; mismatches
+/// (e.g., a transaction succeeds in production, but fails in the shadow, or the calls fail
+/// with different error kinds) are logged and collected into a [`ShadowReport`]. This allows
+/// to canary a new version of a service artifact on a single node before proposing the
+/// upgrade network-wide.
+///
+/// The shadow artifact is deployed locally when the blockchain is built; it does not need
+/// to be registered on the blockchain. The artifact must be available in the corresponding
+/// runtime. The runtime sees the shadow as an ordinary active service with the
+/// shadow name and an identifier from the range reserved for shadows, so the shadow name
+/// must not coincide with names of the blockchain services.
+///
+/// [`ShadowReport`]: struct.ShadowReport.html
+#[derive(Debug)]
+pub struct ShadowInstance {
+    pub(super) name: String,
+    pub(super) target: String,
+    pub(super) artifact: ArtifactId,
+    pub(super) deploy_spec: Vec<u8>,
+    report: ShadowReport,
+}
+
+impl ShadowInstance {
+    /// Creates a shadow instance with the specified local name, which runs `artifact`
+    /// alongside the production service with the `target` name.
+    pub fn new(name: impl Into<String>, target: impl Into<String>, artifact: ArtifactId) -> Self {
+        Self {
+            name: name.into(),
+            target: target.into(),
+            artifact,
+            deploy_spec: Vec::new(),
+            report: ShadowReport::default(),
+        }
+    }
+
+    /// Sets the deploy specification for the shadow artifact.
+    #[must_use]
+    pub fn with_deploy_spec(mut self, deploy_spec: impl BinaryValue) -> Self {
+        self.deploy_spec = deploy_spec.into_bytes();
+        self
+    }
+
+    /// Returns a handle to the report collected for this shadow instance.
+    pub fn report(&self) -> ShadowReport {
+        self.report.clone()
+    }
+}
+
+/// Shared handle to the divergences collected by a shadow instance.
+#[derive(Debug, Clone, Default)]
+pub struct ShadowReport {
+    inner: Arc<Mutex<ShadowStats>>,
+}
+
+impl ShadowReport {
+    /// Returns the current statistics of the shadow instance.
+    pub fn stats(&self) -> ShadowStats {
+        self.inner.lock().unwrap().clone()
+    }
+
+    /// Compares outcomes of a transaction in the production and the shadow instances.
+    fn record(
+        &self,
+        shadow_name: &str,
+        tx_hash: Hash,
+        production: &Result<(), ExecutionError>,
+        shadow: Result<(), ExecutionError>,
+    ) {
+        let mut stats = self.inner.lock().unwrap();
+        stats.executed += 1;
+
+        let is_matching = match (production, &shadow) {
+            (Ok(()), Ok(())) => true,
+            (Err(production), Err(shadow)) => production.kind() == shadow.kind(),
+            _ => false,
+        };
+        if is_matching {
+            return;
+        }
+
+        log::warn!(
+            "Shadow service `{}` diverged on transaction {:?}: production result {:?}, \
+             shadow result {:?}",
+            shadow_name,
+            tx_hash,
+            production,
+            shadow
+        );
+        stats.diverged += 1;
+        if stats.divergences.len() == MAX_DIVERGENCES {
+            stats.divergences.pop_front();
+        }
+        stats.divergences.push_back(ShadowDivergence {
+            tx_hash,
+            production: production.as_ref().err().cloned(),
+            shadow: shadow.err(),
+        });
+    }
+}
+
+/// Statistics collected for a shadow instance since the node start.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct ShadowStats {
+    /// Number of transactions executed by the shadow.
+    pub executed: u64,
+    /// Number of transactions on which the shadow has diverged from the production instance.
+    pub diverged: u64,
+    /// Latest divergences, in the order of their occurrence. Only a limited number
+    /// of divergences is retained.
+    pub divergences: VecDeque<ShadowDivergence>,
+}
+
+/// Mismatch between outcomes of a transaction in the production and the shadow instances.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct ShadowDivergence {
+    /// Hash of the transaction.
+    pub tx_hash: Hash,
+    /// Error returned by the production instance, or `None` if the transaction has succeeded.
+    pub production: Option<ExecutionError>,
+    /// Error returned by the shadow instance, or `None` if the transaction has succeeded.
+    pub shadow: Option<ExecutionError>,
+}
+
+/// Shadow instance started in the runtime.
+#[derive(Debug)]
+pub(super) struct ActiveShadow {
+    pub id: InstanceId,
+    pub name: String,
+    pub runtime_id: u32,
+    report: ShadowReport,
+}
+
+impl ActiveShadow {
+    pub fn record(
+        &self,
+        tx_hash: Hash,
+        production: &Result<(), ExecutionError>,
+        shadow: Result<(), ExecutionError>,
+    ) {
+        self.report.record(&self.name, tx_hash, production, shadow);
+    }
+}
+
+/// Shadow instances grouped by the name of the production instance.
+#[derive(Debug, Default)]
+pub(super) struct Shadows {
+    instances: BTreeMap<String, Vec<ActiveShadow>>,
+    local_artifacts: HashSet<ArtifactId>,
+    count: u32,
+}
+
+impl Shadows {
+    /// Identifiers of shadow instances are allocated downwards from this value.
+    const MAX_INSTANCE_ID: InstanceId = InstanceId::max_value();
+
+    /// Returns shadows attached to the specified production instance.
+    pub fn attached_to<'a>(&'a self, target: &str) -> impl Iterator<Item = &'a ActiveShadow> {
+        self.instances.get(target).into_iter().flatten()
+    }
+
+    /// Checks whether the artifact was deployed locally for shadow instances.
+    pub fn is_local_artifact(&self, artifact: &ArtifactId) -> bool {
+        self.local_artifacts.contains(artifact)
+    }
+
+    pub fn add_local_artifact(&mut self, artifact: ArtifactId) {
+        self.local_artifacts.insert(artifact);
+    }
+
+    /// Registers a shadow instance and returns the identifier allocated for it.
+    pub fn add(&mut self, shadow: &ShadowInstance) -> InstanceId {
+        let id = Self::MAX_INSTANCE_ID - self.count;
+        self.count += 1;
+        self.instances
+            .entry(shadow.target.clone())
+            .or_default()
+            .push(ActiveShadow {
+                id,
+                name: shadow.name.clone(),
+                runtime_id: shadow.artifact.runtime_id,
+                report: shadow.report(),
+            });
+        id
+    }
+}
@@ -33,12 +33,14 @@ use crate::{
     helpers::Height,
     messages::AnyTx,
     runtime::{
-        dispatcher::{Action, ArtifactStatus, Dispatcher, Mailbox},
+        catch_panic,
+        dispatcher::{Action, Dispatcher, Mailbox},
         execution_context::TopLevelContext,
         migrations::{InitMigrationError, MigrationScript},
         oneshot::{self, Receiver},
-        ArtifactId, BlockchainData, CallInfo, CommonError, CoreError, DispatcherSchema, ErrorKind,
-        ErrorMatch, ExecutionContext, ExecutionError, InstanceDescriptor, InstanceId, InstanceSpec,
+        ArtifactId, ArtifactStatus, BlockchainData, CallInfo, Capabilities, Capability,
+        CommonError, CoreError, DispatcherSchema, ErrorKind, ErrorMatch, ExecutionContext,
+        ExecutionContextUnstable, ExecutionError, InstanceDescriptor, InstanceId, InstanceSpec,
         InstanceState, InstanceStatus, MethodId, Runtime, RuntimeFeature, RuntimeInstance,
         SnapshotExt, TxCheckCache,
     },
@@ -1282,6 +1284,191 @@ fn unload_artifact_workflow() {
     assert!(schema.get_artifact(&artifact).is_none());
 }
 
+#[test]
+fn deprecate_artifact_workflow() {
+    const RUNTIME_ID: u32 = 2;
+
+    let db = Arc::new(TemporaryDB::new());
+    let blockchain = Blockchain::new(
+        Arc::clone(&db) as Arc<dyn Database>,
+        gen_keypair(),
+        ApiSender::closed(),
+    );
+    let runtime = DeploymentRuntime::default();
+    let mut dispatcher = DispatcherBuilder::new()
+        .with_runtime(RUNTIME_ID, runtime.clone())
+        .finalize(&blockchain);
+
+    let patch = create_genesis_block(&mut dispatcher, db.fork());
+    db.merge_sync(patch).unwrap();
+
+    // Deploy the artifact and start a service from it.
+    let artifact = ArtifactId::new(RUNTIME_ID, "good", Version::new(1, 0, 0)).unwrap();
+    let fork = db.fork();
+    let spec = DeploymentRuntime::SPEC.to_vec();
+    Dispatcher::commit_artifact(&fork, &artifact, spec);
+    Dispatcher::activate_pending(&fork);
+    let patch = dispatcher.commit_block_and_notify_runtimes(fork);
+    db.merge_sync(patch).unwrap();
+
+    let mut fork = db.fork();
+    let service = InstanceSpec::from_raw_parts(100, "some-service".into(), artifact.clone());
+    TopLevelContext::for_block_call(&dispatcher, &mut fork, service.as_descriptor())
+        .call(|mut ctx| ctx.initiate_adding_service(service, vec![]))
+        .unwrap();
+    Dispatcher::activate_pending(&fork);
+    let patch = dispatcher.commit_block_and_notify_runtimes(fork);
+    db.merge_sync(patch).unwrap();
+
+    // Deprecate the artifact. Deprecation takes effect immediately.
+    let mut fork = db.fork();
+    Dispatcher::deprecate_artifact(&fork, &artifact).unwrap();
+    let state = DispatcherSchema::new(&fork)
+        .get_artifact(&artifact)
+        .unwrap();
+    assert_eq!(state.status, ArtifactStatus::Deprecated);
+    // Check that a duplicate deprecation request fails.
+    let err = Dispatcher::deprecate_artifact(&fork, &artifact).unwrap_err();
+    assert_eq!(
+        err,
+        ErrorMatch::from_fail(&CoreError::ArtifactNotDeployed)
+            .with_description_containing("artifact `2:good:1.0.0`, which has non-active status")
+    );
+
+    // Check that a new service cannot be instantiated from the deprecated artifact.
+    let other_service = InstanceSpec::from_raw_parts(101, "other-service".into(), artifact.clone());
+    let err =
+        TopLevelContext::for_block_call(&dispatcher, &mut fork, other_service.as_descriptor())
+            .call(|mut ctx| ctx.initiate_adding_service(other_service, vec![]))
+            .unwrap_err();
+    let expected_msg = "from non-active artifact `2:good:1.0.0` (artifact status: deprecated)";
+    assert_eq!(
+        err,
+        ErrorMatch::from_fail(&CoreError::ArtifactNotDeployed)
+            .with_description_containing(expected_msg)
+    );
+
+    Dispatcher::activate_pending(&fork);
+    let patch = dispatcher.commit_block_and_notify_runtimes(fork);
+    db.merge_sync(patch).unwrap();
+
+    // The existing service is not affected.
+    let snapshot = db.snapshot();
+    let schema = DispatcherSchema::new(&snapshot);
+    let instance = schema.get_instance("some-service").unwrap();
+    assert_eq!(instance.status, Some(InstanceStatus::Active));
+    assert_eq!(runtime.deploy_attempts(&artifact), 1);
+}
+
+#[test]
+fn artifact_capabilities_workflow() {
+    const RUNTIME_ID: u32 = 2;
+
+    let db = Arc::new(TemporaryDB::new());
+    let blockchain = Blockchain::new(
+        Arc::clone(&db) as Arc<dyn Database>,
+        gen_keypair(),
+        ApiSender::closed(),
+    );
+    let runtime = DeploymentRuntime::default();
+    let mut dispatcher = DispatcherBuilder::new()
+        .with_runtime(RUNTIME_ID, runtime)
+        .finalize(&blockchain);
+
+    let patch = create_genesis_block(&mut dispatcher, db.fork());
+    db.merge_sync(patch).unwrap();
+
+    // Capabilities cannot be set for an artifact which is not deployed.
+    let artifact = ArtifactId::new(RUNTIME_ID, "good", Version::new(1, 0, 0)).unwrap();
+    let fork = db.fork();
+    let err =
+        Dispatcher::set_artifact_capabilities(&fork, &artifact, Capabilities::none()).unwrap_err();
+    assert_eq!(
+        err,
+        ErrorMatch::from_fail(&CoreError::ArtifactNotDeployed)
+            .with_description_containing("artifact `2:good:1.0.0`, which is not deployed")
+    );
+
+    // Deploy the artifact and start a service from it.
+    let spec = DeploymentRuntime::SPEC.to_vec();
+    Dispatcher::commit_artifact(&fork, &artifact, spec);
+    Dispatcher::activate_pending(&fork);
+    let patch = dispatcher.commit_block_and_notify_runtimes(fork);
+    db.merge_sync(patch).unwrap();
+
+    let mut fork = db.fork();
+    let service = InstanceSpec::from_raw_parts(100, "some-service".into(), artifact.clone());
+    TopLevelContext::for_block_call(&dispatcher, &mut fork, service.as_descriptor())
+        .call(|mut ctx| ctx.initiate_adding_service(service.clone(), vec![]))
+        .unwrap();
+    Dispatcher::activate_pending(&fork);
+    let patch = dispatcher.commit_block_and_notify_runtimes(fork);
+    db.merge_sync(patch).unwrap();
+
+    // The service is granted the default capabilities of the runtime.
+    let mut fork = db.fork();
+    assert_eq!(
+        dispatcher.instance_capabilities(&fork, 100),
+        Capabilities::all()
+    );
+
+    // Restrict the artifact. The restriction applies to the running service immediately.
+    let capabilities = Capabilities::none().with(Capability::ReadServiceData);
+    Dispatcher::set_artifact_capabilities(&fork, &artifact, capabilities).unwrap();
+    assert_eq!(
+        DispatcherSchema::new(&fork).get_artifact_capabilities(&artifact),
+        Some(capabilities)
+    );
+    assert_eq!(dispatcher.instance_capabilities(&fork, 100), capabilities);
+
+    let err = TopLevelContext::for_block_call(&dispatcher, &mut fork, service.as_descriptor())
+        .call(|mut ctx| {
+            ctx.require_capability(Capability::ReadServiceData)?;
+            ctx.make_child_call(service.id, "", 0, &[], false)
+        })
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ErrorMatch::from_fail(&CoreError::MissingCapability)
+            .with_description_containing("lacks the `call_services` capability")
+    );
+
+    // Reading blockchain data is restricted by the capabilities as well.
+    let err = TopLevelContext::for_block_call(&dispatcher, &mut fork, service.as_descriptor())
+        .call(|ctx| {
+            catch_panic(|| {
+                ctx.data()
+                    .for_dispatcher()
+                    .get_instance(service.id)
+                    .unwrap();
+                ctx.data().for_core().height();
+                Ok(())
+            })
+        })
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unexpected);
+    assert!(err
+        .description()
+        .contains("lacks the `read_core_schema` capability"));
+
+    // Data of the executing service is accessible without capabilities.
+    Dispatcher::set_artifact_capabilities(&fork, &artifact, Capabilities::none()).unwrap();
+    TopLevelContext::for_block_call(&dispatcher, &mut fork, service.as_descriptor())
+        .call(|ctx| {
+            assert!(ctx.data().for_service(service.id).is_some());
+            let err = catch_panic(|| {
+                ctx.data().for_dispatcher();
+                Ok(())
+            })
+            .unwrap_err();
+            assert!(err
+                .description()
+                .contains("lacks the `read_service_data` capability"));
+            Ok(())
+        })
+        .unwrap();
+}
+
 #[test]
 fn check_tx_caching() {
     let FreezingRig {
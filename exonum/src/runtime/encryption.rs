@@ -0,0 +1,321 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encrypted transaction payloads.
+//!
+//! An [`EncryptedPayload`] allows to submit a transaction whose payload is visible only
+//! to a subset of parties, while the transaction itself is ordered and committed
+//! by all nodes in the network as usual. The payload is encrypted with a symmetric
+//! [`PayloadKey`]; the key is distributed among the parties off-chain. A commitment
+//! to the plaintext is recorded on-chain, so that the parties can prove that the decrypted
+//! payload corresponds to the committed transaction. The commitment is blinded with a random
+//! factor encrypted together with the plaintext, so it does not allow to guess low-entropy
+//! payloads without the key.
+//!
+//! Since only some nodes are able to decrypt the payload, the on-chain transaction processing
+//! must not depend on the plaintext. Runtimes may provide hooks to handle decrypted payloads
+//! off-chain on nodes possessing the corresponding keys, which are stored in
+//! a [`PayloadKeyStore`].
+//!
+//! [`EncryptedPayload`]: struct.EncryptedPayload.html
+//! [`PayloadKey`]: struct.PayloadKey.html
+//! [`PayloadKeyStore`]: struct.PayloadKeyStore.html
+
+use anyhow::ensure;
+use exonum_crypto::{hash, secretbox, Hash, HashStream, PublicKey};
+use exonum_derive::{BinaryValue, ObjectHash};
+use exonum_proto::ProtobufConvert;
+use thiserror::Error;
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+use crate::{helpers::ValidateInput, proto::schema};
+
+/// Length of the blinding factor of the plaintext commitment in bytes.
+pub const BLINDING_LENGTH: usize = 32;
+
+/// Symmetric key used to encrypt transaction payloads.
+#[derive(Clone, PartialEq, Eq)]
+pub struct PayloadKey(secretbox::Key);
+
+impl PayloadKey {
+    /// Generates a random key.
+    pub fn random() -> Self {
+        Self(secretbox::gen_key())
+    }
+
+    /// Creates a key from the bytes slice. Returns `None` if the slice has an incorrect length.
+    pub fn from_slice(bytes: &[u8]) -> Option<Self> {
+        secretbox::Key::from_slice(bytes).map(Self)
+    }
+
+    /// Returns the identifier of the key, which is recorded in the payloads
+    /// encrypted with this key.
+    pub fn id(&self) -> Hash {
+        hash(self.0.as_ref())
+    }
+}
+
+impl AsRef<[u8]> for PayloadKey {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl fmt::Debug for PayloadKey {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("PayloadKey")
+            .field("id", &self.id())
+            .finish()
+    }
+}
+
+/// Transaction payload encrypted to a subset of parties.
+///
+/// # Examples
+///
+/// ```
+/// use exonum::{crypto::KeyPair, runtime::encryption::{EncryptedPayload, PayloadKey}};
+///
+/// let key = PayloadKey::random();
+/// let recipient = KeyPair::random().public_key();
+/// let payload = EncryptedPayload::seal(b"transfer 100 tokens", &key, vec![recipient]);
+/// assert!(payload.is_addressed_to(&recipient));
+/// assert_eq!(payload.open(&key).unwrap(), b"transfer 100 tokens");
+///
+/// let other_key = PayloadKey::random();
+/// assert!(payload.open(&other_key).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash)]
+#[protobuf_convert(source = "schema::encryption::EncryptedPayload")]
+#[non_exhaustive]
+pub struct EncryptedPayload {
+    /// Commitment to the plaintext payload, `hash(blinding || plaintext)`.
+    /// See [`commitment`](#method.commitment) for details.
+    pub commitment: Hash,
+    /// Identifier of the key used to encrypt the payload.
+    pub key_id: Hash,
+    /// Public keys of the parties able to decrypt the payload.
+    pub recipients: Vec<PublicKey>,
+    /// Encryption nonce.
+    pub nonce: Vec<u8>,
+    /// Encrypted payload.
+    pub ciphertext: Vec<u8>,
+}
+
+impl EncryptedPayload {
+    /// Encrypts `plaintext` with the specified `key`.
+    ///
+    /// A random blinding factor is generated for the commitment to the plaintext
+    /// and encrypted together with it.
+    pub fn seal(
+        plaintext: &[u8],
+        key: &PayloadKey,
+        recipients: impl IntoIterator<Item = PublicKey>,
+    ) -> Self {
+        // The secretbox key is a uniformly random byte string of the required length.
+        let blinding = secretbox::gen_key();
+        let mut message = Vec::with_capacity(BLINDING_LENGTH + plaintext.len());
+        message.extend_from_slice(blinding.as_ref());
+        message.extend_from_slice(plaintext);
+
+        let nonce = secretbox::gen_nonce();
+        Self {
+            commitment: Self::commitment(blinding.as_ref(), plaintext),
+            key_id: key.id(),
+            recipients: recipients.into_iter().collect(),
+            nonce: nonce.as_ref().to_vec(),
+            ciphertext: secretbox::seal(&message, &nonce, &key.0),
+        }
+    }
+
+    /// Computes the commitment to the `plaintext` with the specified `blinding` factor.
+    ///
+    /// Revealing the plaintext together with the blinding factor (which can be obtained
+    /// with [`open_with_blinding`](#method.open_with_blinding)) allows to prove
+    /// to third parties that the plaintext corresponds to the committed payload.
+    pub fn commitment(blinding: &[u8], plaintext: &[u8]) -> Hash {
+        HashStream::new().update(blinding).update(plaintext).hash()
+    }
+
+    /// Checks whether the payload is addressed to the party with the specified public key.
+    pub fn is_addressed_to(&self, public_key: &PublicKey) -> bool {
+        self.recipients.contains(public_key)
+    }
+
+    /// Decrypts the payload with the specified `key` and checks that the decrypted payload
+    /// matches the committed plaintext.
+    pub fn open(&self, key: &PayloadKey) -> Result<Vec<u8>, DecryptionError> {
+        self.open_with_blinding(key).map(|(plaintext, _)| plaintext)
+    }
+
+    /// Decrypts the payload with the specified `key` and checks that the decrypted payload
+    /// matches the committed plaintext. Returns the plaintext together with the blinding
+    /// factor of the commitment.
+    pub fn open_with_blinding(
+        &self,
+        key: &PayloadKey,
+    ) -> Result<(Vec<u8>, Vec<u8>), DecryptionError> {
+        if key.id() != self.key_id {
+            return Err(DecryptionError::KeyMismatch);
+        }
+        let nonce = secretbox::Nonce::from_slice(&self.nonce).ok_or(DecryptionError::Malformed)?;
+        let mut plaintext = secretbox::open(&self.ciphertext, &nonce, &key.0)
+            .map_err(|()| DecryptionError::Malformed)?;
+        if plaintext.len() < BLINDING_LENGTH {
+            return Err(DecryptionError::Malformed);
+        }
+        let blinding: Vec<u8> = plaintext.drain(..BLINDING_LENGTH).collect();
+        if Self::commitment(&blinding, &plaintext) != self.commitment {
+            return Err(DecryptionError::CommitmentMismatch);
+        }
+        Ok((plaintext, blinding))
+    }
+}
+
+impl ValidateInput for EncryptedPayload {
+    type Error = anyhow::Error;
+
+    fn validate(&self) -> Result<(), Self::Error> {
+        ensure!(
+            !self.recipients.is_empty(),
+            "Encrypted payload has no recipients"
+        );
+        ensure!(
+            self.nonce.len() == secretbox::NONCE_LENGTH,
+            "Encrypted payload has nonce of invalid length {} (expected {})",
+            self.nonce.len(),
+            secretbox::NONCE_LENGTH
+        );
+        Ok(())
+    }
+}
+
+/// Errors that can occur during decryption of an `EncryptedPayload`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[non_exhaustive]
+pub enum DecryptionError {
+    /// The payload is encrypted with another key.
+    #[error("Payload is encrypted with another key")]
+    KeyMismatch,
+    /// The payload is malformed or was tampered with.
+    #[error("Payload is malformed or was tampered with")]
+    Malformed,
+    /// The decrypted payload does not match the plaintext commitment.
+    #[error("Decrypted payload does not match the plaintext commitment")]
+    CommitmentMismatch,
+}
+
+/// Storage of payload keys distributed to the node off-chain.
+///
+/// The store is shared among its clones, so keys can be added or removed while
+/// the node is running.
+#[derive(Debug, Clone, Default)]
+pub struct PayloadKeyStore {
+    keys: Arc<RwLock<HashMap<Hash, PayloadKey>>>,
+}
+
+impl PayloadKeyStore {
+    /// Creates an empty key store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a key to the store and returns its identifier.
+    pub fn insert(&self, key: PayloadKey) -> Hash {
+        let key_id = key.id();
+        self.keys.write().unwrap().insert(key_id, key);
+        key_id
+    }
+
+    /// Removes a key with the specified identifier from the store. Returns `true`
+    /// if the key was present in the store.
+    pub fn remove(&self, key_id: &Hash) -> bool {
+        self.keys.write().unwrap().remove(key_id).is_some()
+    }
+
+    /// Checks whether the store contains no keys.
+    pub fn is_empty(&self) -> bool {
+        self.keys.read().unwrap().is_empty()
+    }
+
+    /// Decrypts the payload. Returns `None` if the store does not contain
+    /// the key used to encrypt the payload.
+    pub fn decrypt(&self, payload: &EncryptedPayload) -> Option<Result<Vec<u8>, DecryptionError>> {
+        let keys = self.keys.read().unwrap();
+        keys.get(&payload.key_id).map(|key| payload.open(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum_crypto::KeyPair;
+    use exonum_merkledb::BinaryValue;
+
+    use super::*;
+
+    #[test]
+    fn sealing_and_opening_payload() {
+        let key = PayloadKey::random();
+        let recipient = KeyPair::random().public_key();
+        let payload = EncryptedPayload::seal(b"payload", &key, vec![recipient]);
+        payload.validate().unwrap();
+        // The commitment is blinded, so it differs for the same plaintext.
+        assert_ne!(payload.commitment, hash(b"payload"));
+        let other_payload = EncryptedPayload::seal(b"payload", &key, vec![recipient]);
+        assert_ne!(payload.commitment, other_payload.commitment);
+
+        let payload = EncryptedPayload::from_bytes(payload.to_bytes().into()).unwrap();
+        assert_eq!(payload.open(&key).unwrap(), b"payload");
+        let (plaintext, blinding) = payload.open_with_blinding(&key).unwrap();
+        assert_eq!(blinding.len(), BLINDING_LENGTH);
+        assert_eq!(
+            EncryptedPayload::commitment(&blinding, &plaintext),
+            payload.commitment
+        );
+        let err = payload.open(&PayloadKey::random()).unwrap_err();
+        assert_eq!(err, DecryptionError::KeyMismatch);
+
+        let mut tampered = payload.clone();
+        tampered.ciphertext[0] ^= 1;
+        assert_eq!(tampered.open(&key), Err(DecryptionError::Malformed));
+        let mut tampered = payload;
+        tampered.commitment = EncryptedPayload::commitment(&blinding, b"other payload");
+        assert_eq!(
+            tampered.open(&key),
+            Err(DecryptionError::CommitmentMismatch)
+        );
+    }
+
+    #[test]
+    fn payload_key_store() {
+        let store = PayloadKeyStore::new();
+        let key = PayloadKey::random();
+        let payload = EncryptedPayload::seal(b"payload", &key, vec![]);
+        assert!(payload.validate().is_err());
+        assert!(store.decrypt(&payload).is_none());
+
+        let key_id = store.clone().insert(key);
+        assert!(!store.is_empty());
+        assert_eq!(store.decrypt(&payload), Some(Ok(b"payload".to_vec())));
+        assert!(store.remove(&key_id));
+        assert!(store.is_empty());
+    }
+}
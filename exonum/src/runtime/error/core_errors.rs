@@ -16,7 +16,9 @@
 
 use exonum_derive::ExecutionFail;
 
-use crate::runtime::{ExecutionError, ExecutionFail};
+use crate::runtime::{
+    Capability, ExecutionError, ExecutionFail, InstanceDescriptor, InvariantViolation,
+};
 
 /// List of possible core errors.
 ///
@@ -61,6 +63,13 @@ pub enum CoreError {
     IncorrectCall = 14,
     /// Cannot unload artifact.
     CannotUnloadArtifact = 15,
+    /// The artifact to migrate the service to reuses a method ID of the current service
+    /// artifact for a different method.
+    IncompatibleMethodIds = 16,
+    /// The executing service lacks a capability required for the requested action.
+    MissingCapability = 17,
+    /// Changes made by the call violate an invariant of a service.
+    InvariantViolated = 18,
 }
 
 impl CoreError {
@@ -71,4 +80,23 @@ impl CoreError {
         );
         Self::StackOverflow.with_description(description)
     }
+
+    pub(crate) fn missing_capability(
+        instance: &InstanceDescriptor,
+        capability: Capability,
+    ) -> ExecutionError {
+        let description = format!(
+            "Service `{}` lacks the `{}` capability required for the call.",
+            instance, capability
+        );
+        Self::MissingCapability.with_description(description)
+    }
+
+    pub(crate) fn invariant_violated(
+        instance: &InstanceDescriptor,
+        violation: &InvariantViolation,
+    ) -> ExecutionError {
+        let description = format!("Service {} violates its invariant. {}", instance, violation);
+        Self::InvariantViolated.with_description(description)
+    }
 }
@@ -127,7 +127,7 @@ pub enum ErrorKind {
 }
 
 impl ErrorKind {
-    pub(super) fn into_raw(self) -> (errors_proto::ErrorKind, u8) {
+    pub(crate) fn into_raw(self) -> (errors_proto::ErrorKind, u8) {
         match self {
             Self::Unexpected => (errors_proto::ErrorKind::UNEXPECTED, 0),
             Self::Common { code } => (errors_proto::ErrorKind::COMMON, code),
@@ -137,7 +137,7 @@ impl ErrorKind {
         }
     }
 
-    pub(super) fn from_raw(kind: errors_proto::ErrorKind, code: u8) -> anyhow::Result<Self> {
+    pub(crate) fn from_raw(kind: errors_proto::ErrorKind, code: u8) -> anyhow::Result<Self> {
         use errors_proto::ErrorKind::{COMMON, CORE, RUNTIME, SERVICE, UNEXPECTED};
 
         let kind = match kind {
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use time::OffsetDateTime;
+
 use crate::{
     blockchain::Schema as CoreSchema,
     crypto::{Hash, PublicKey},
@@ -19,8 +21,9 @@ use crate::{
     merkledb::{access::Prefixed, BinaryValue, Fork},
     runtime::{
         migrations::MigrationType, ArtifactId, BlockchainData, CallSite, CallType, Caller,
-        CoreError, Dispatcher, DispatcherSchema, ExecutionError, ExecutionFail, InstanceDescriptor,
-        InstanceId, InstanceQuery, InstanceSpec, MethodId, RuntimeFeature, SUPERVISOR_INSTANCE_ID,
+        Capabilities, Capability, CoreError, Dispatcher, DispatcherSchema, ExecutionError,
+        ExecutionFail, InstanceDescriptor, InstanceId, InstanceQuery, InstanceSpec, MethodId,
+        RuntimeFeature, SUPERVISOR_INSTANCE_ID,
     },
 };
 
@@ -83,6 +86,8 @@ pub struct ExecutionContext<'a> {
     call_stack_depth: u64,
     /// Flag indicating an error occurred during the child call.
     call_error_flag: CallErrorFlag<'a>,
+    /// Capabilities of the executing service.
+    capabilities: Capabilities,
 }
 
 impl<'a> ExecutionContext<'a> {
@@ -96,6 +101,7 @@ impl<'a> ExecutionContext<'a> {
         caller: Caller,
         transaction_hash: Option<Hash>,
     ) -> Self {
+        let capabilities = dispatcher.instance_capabilities(fork, instance.id);
         Self {
             dispatcher,
             fork,
@@ -105,6 +111,7 @@ impl<'a> ExecutionContext<'a> {
             interface_name: "",
             call_stack_depth: 0,
             call_error_flag: CallErrorFlag::new(),
+            capabilities,
         }
     }
 
@@ -114,8 +121,19 @@ impl<'a> ExecutionContext<'a> {
         self.transaction_hash
     }
 
-    /// Provides access to blockchain data.
+    /// Provides access to blockchain data. The access is restricted according
+    /// to the [capabilities](#method.capabilities) of the executing service;
+    /// see [`BlockchainData`] for details.
+    ///
+    /// [`BlockchainData`]: struct.BlockchainData.html
     pub fn data(&self) -> BlockchainData<&Fork> {
+        self.unrestricted_data()
+            .with_capabilities(self.capabilities)
+    }
+
+    /// Provides access to blockchain data for the core, regardless of the capabilities
+    /// of the executing service.
+    fn unrestricted_data(&self) -> BlockchainData<&Fork> {
         assert!(!self.call_error_flag.is_set(), "{}", ACCESS_ERROR_STR);
         BlockchainData::new(self.fork, &self.instance.name)
     }
@@ -137,10 +155,24 @@ impl<'a> ExecutionContext<'a> {
 
     /// Returns `true` if currently processed block is a genesis block.
     pub fn in_genesis_block(&self) -> bool {
-        let core_schema = self.data().for_core();
+        let core_schema = self.unrestricted_data().for_core();
         core_schema.next_height() == Height(0)
     }
 
+    /// Returns the time of the currently processed block agreed upon by validators, or `None`
+    /// if the block does not record the time. Unlike the time provided by an oracle service,
+    /// the block time does not require additional transactions and is available
+    /// in all service hooks and transactions within the block.
+    ///
+    /// The time is recorded only if the [`block_time`] option of the consensus configuration
+    /// is enabled; see [`BlockTime`] for details.
+    ///
+    /// [`block_time`]: ../blockchain/config/struct.ConsensusConfig.html#structfield.block_time
+    /// [`BlockTime`]: ../blockchain/struct.BlockTime.html
+    pub fn block_time(&self) -> Option<OffsetDateTime> {
+        self.unrestricted_data().for_core().block_time()
+    }
+
     /// Returns an identifier of the service interface required for the call.
     /// This identifier is always empty for the primary service interface.
     ///
@@ -152,6 +184,30 @@ impl<'a> ExecutionContext<'a> {
         self.interface_name
     }
 
+    /// Returns capabilities granted to the executing service.
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Checks that the executing service has the specified capability. Calls to other
+    /// services and reading blockchain data via [`data()`](#method.data) are checked
+    /// by the core; runtimes may call this method to report a missing capability
+    /// as an error before exposing the corresponding functionality to the service code.
+    ///
+    /// # Return value
+    ///
+    /// An error with the [`MissingCapability`] code is returned if the service lacks
+    /// the capability.
+    ///
+    /// [`MissingCapability`]: enum.CoreError.html#variant.MissingCapability
+    pub fn require_capability(&self, capability: Capability) -> Result<(), ExecutionError> {
+        if self.capabilities().contains(capability) {
+            Ok(())
+        } else {
+            Err(CoreError::missing_capability(&self.instance, capability))
+        }
+    }
+
     /// Returns extensions required for the Supervisor service implementation.
     ///
     /// Make sure that this method invoked by the instance with the [`SUPERVISOR_INSTANCE_ID`]
@@ -184,7 +240,13 @@ impl<'a> ExecutionContext<'a> {
             .runtime_by_id(spec.artifact.runtime_id)
             .ok_or(CoreError::IncorrectRuntime)?;
 
-        let context = self.reborrow(spec.as_descriptor());
+        // The service is not yet recorded in the dispatcher schema, so its capabilities
+        // are resolved from the artifact.
+        let capabilities = self
+            .dispatcher
+            .artifact_capabilities(self.fork, &spec.artifact);
+        let mut context = self.reborrow(spec.as_descriptor());
+        context.capabilities = capabilities;
         runtime
             .initiate_adding_service(context, &spec.artifact, constructor.into_bytes())
             .map_err(|mut err| {
@@ -204,6 +266,12 @@ impl<'a> ExecutionContext<'a> {
     fn reborrow(&mut self, instance: InstanceDescriptor) -> ExecutionContext<'_> {
         assert!(!self.call_error_flag.is_set(), "{}", ACCESS_ERROR_STR);
 
+        let capabilities = if instance.id == self.instance.id {
+            self.capabilities
+        } else {
+            self.dispatcher
+                .instance_capabilities(self.fork, instance.id)
+        };
         ExecutionContext {
             fork: &mut *self.fork,
             caller: self.caller.clone(),
@@ -213,6 +281,7 @@ impl<'a> ExecutionContext<'a> {
             dispatcher: self.dispatcher,
             call_stack_depth: self.call_stack_depth,
             call_error_flag: self.call_error_flag.reborrow(),
+            capabilities,
         }
     }
 
@@ -232,6 +301,9 @@ impl<'a> ExecutionContext<'a> {
     ) -> ExecutionContext<'s> {
         assert!(!self.call_error_flag.is_set(), "{}", ACCESS_ERROR_STR);
 
+        let capabilities = self
+            .dispatcher
+            .instance_capabilities(self.fork, instance.id);
         let caller = if fallthrough_auth {
             self.caller.clone()
         } else {
@@ -249,6 +321,7 @@ impl<'a> ExecutionContext<'a> {
             interface_name,
             call_stack_depth: self.call_stack_depth + 1,
             call_error_flag: self.call_error_flag.reborrow(),
+            capabilities,
         }
     }
 
@@ -359,7 +432,15 @@ impl ExecutionContextUnstable for ExecutionContext<'_> {
             let err = CoreError::stack_overflow(Self::MAX_CALL_STACK_DEPTH);
             return Err(err);
         }
-
+        self.require_capability(Capability::CallServices)?;
+
+        let called_instance = match called_instance.into() {
+            InstanceQuery::Interface(interface) => DispatcherSchema::new(&*self.fork)
+                .default_instance_id(interface)
+                .ok_or(CoreError::IncorrectInstanceId)?
+                .into(),
+            query => query,
+        };
         let descriptor = self
             .dispatcher
             .get_service(called_instance)
@@ -416,6 +497,20 @@ impl SupervisorExtensions<'_> {
         Dispatcher::unload_artifact(self.0.fork, artifact)
     }
 
+    /// Marks the specified artifact as deprecated. New service instances cannot be started
+    /// from a deprecated artifact, and it cannot be used as a data migration target;
+    /// however, existing services associated with the artifact continue running.
+    /// A deprecated artifact can still be unloaded.
+    ///
+    /// Unlike unloading, deprecation takes effect immediately.
+    ///
+    /// # Return value
+    ///
+    /// If the artifact is not active, an error is returned.
+    pub fn deprecate_artifact(&self, artifact: &ArtifactId) -> Result<(), ExecutionError> {
+        Dispatcher::deprecate_artifact(self.0.fork, artifact)
+    }
+
     /// Initiates adding a service instance to the blockchain.
     ///
     /// The service is not immediately activated; it activates if / when the block containing
@@ -502,6 +597,58 @@ impl SupervisorExtensions<'_> {
             })
     }
 
+    /// Renames a stopped service instance, retaining its numeric identifier and data.
+    ///
+    /// Unlike most other operations concerning services, the renaming takes effect immediately:
+    /// the service data is moved to the namespace corresponding to the new name, and the service
+    /// can be looked up by the new name in the dispatcher schema. Runtimes learn about
+    /// the new name when the block containing the renaming is committed.
+    ///
+    /// # Return value
+    ///
+    /// An error is returned if the service does not exist, is not stopped, has a pending
+    /// status transition, or if the new name is invalid or is already taken.
+    pub fn rename_service(
+        &mut self,
+        instance_id: InstanceId,
+        new_name: &str,
+    ) -> Result<(), ExecutionError> {
+        Dispatcher::rename_service(self.0.fork, instance_id, new_name)
+    }
+
+    /// Makes the specified service instance the default one for the interface name, so that
+    /// the service can be addressed via [`InstanceQuery::Interface`] without hard-coding
+    /// its identifier. The change takes effect immediately; the previous default instance
+    /// for the interface, if any, is replaced.
+    ///
+    /// # Return value
+    ///
+    /// An error is returned if the interface name is invalid or if the service does not exist.
+    ///
+    /// [`InstanceQuery::Interface`]: enum.InstanceQuery.html#variant.Interface
+    pub fn set_default_instance(
+        &mut self,
+        interface: &str,
+        instance_id: InstanceId,
+    ) -> Result<(), ExecutionError> {
+        Dispatcher::set_default_instance(self.0.fork, interface, instance_id)
+    }
+
+    /// Sets capabilities of a deployed artifact, overriding the default capabilities
+    /// of its runtime. The change takes effect immediately for all services instantiated
+    /// from the artifact.
+    ///
+    /// # Return value
+    ///
+    /// An error is returned if the artifact is not deployed.
+    pub fn set_artifact_capabilities(
+        &mut self,
+        artifact: &ArtifactId,
+        capabilities: Capabilities,
+    ) -> Result<(), ExecutionError> {
+        Dispatcher::set_artifact_capabilities(self.0.fork, artifact, capabilities)
+    }
+
     /// Provides writeable access to core schema.
     pub fn writeable_core_schema(&self) -> CoreSchema<&Fork> {
         CoreSchema::new(self.0.fork)
@@ -537,6 +684,27 @@ impl SupervisorExtensions<'_> {
         Dispatcher::flush_migration(self.0.fork, service_name)
     }
 
+    /// Checks the deploy specification of an artifact with the help of the runtime
+    /// the artifact belongs to.
+    pub fn check_deploy_spec(
+        &self,
+        artifact: &ArtifactId,
+        spec: &[u8],
+    ) -> Result<(), ExecutionError> {
+        let runtime = self
+            .0
+            .dispatcher
+            .runtime_by_id(artifact.runtime_id)
+            .ok_or_else(|| {
+                let msg = format!(
+                    "Cannot deploy artifact `{}` to non-existing runtime with ID {}",
+                    artifact, artifact.runtime_id
+                );
+                CoreError::IncorrectRuntime.with_description(msg)
+            })?;
+        runtime.check_deploy_spec(artifact, spec)
+    }
+
     /// Checks if the runtime supports the specified optional feature.
     ///
     /// # Panics
@@ -207,7 +207,7 @@ pub use self::{
     blockchain_data::{BlockchainData, SnapshotExt},
     dispatcher::{
         remove_local_migration_result, Action as DispatcherAction, Mailbox,
-        Schema as DispatcherSchema,
+        Schema as DispatcherSchema, ShadowDivergence, ShadowInstance, ShadowReport, ShadowStats,
     },
     error::{
         catch_panic, CallSite, CallType, CommonError, CoreError, ErrorKind, ErrorMatch,
@@ -216,23 +216,29 @@ pub use self::{
     execution_context::{ExecutionContext, ExecutionContextUnstable, SupervisorExtensions},
     types::{
         AnyTx, ArtifactId, ArtifactSpec, ArtifactState, ArtifactStatus, CallInfo, Caller,
-        CallerAddress, InstanceId, InstanceQuery, InstanceSpec, InstanceState, InstanceStatus,
-        MethodId, MigrationStatus,
+        CallerAddress, Capabilities, Capability, DeploySpec, DeploySpecSection, InstanceId,
+        InstanceQuery, InstanceSpec, InstanceState, InstanceStatus, InterfaceMethods, MethodId,
+        MigrationStatus,
     },
 };
-pub(crate) use self::{dispatcher::Dispatcher, error::ExecutionErrorAux};
+pub(crate) use self::{
+    dispatcher::{CallHashes, Dispatcher},
+    error::ExecutionErrorAux,
+};
 
+pub mod encryption;
 pub mod migrations;
 pub mod oneshot;
 pub mod versioning;
 
-use exonum_merkledb::Snapshot;
+use exonum_merkledb::{ReadonlyFork, Snapshot};
 use semver::Version;
+use thiserror::Error;
 
 use std::fmt;
 
 use self::migrations::{InitMigrationError, MigrationScript};
-use crate::blockchain::Blockchain;
+use crate::{blockchain::Blockchain, crypto::Hash};
 
 mod blockchain_data;
 mod dispatcher;
@@ -389,6 +395,16 @@ pub trait Runtime: Send + fmt::Debug + 'static {
         false
     }
 
+    /// Returns the capabilities granted to artifacts of this runtime unless the supervisor
+    /// has configured the capabilities of an artifact explicitly.
+    ///
+    /// Runtimes executing untrusted code (e.g., WASM modules or external processes) should
+    /// return the minimal set of capabilities, so that each artifact is granted
+    /// only the capabilities it needs. The default implementation returns all capabilities.
+    fn default_capabilities(&self) -> Capabilities {
+        Capabilities::all()
+    }
+
     /// Notifies the runtime that the dispatcher has completed re-initialization after the
     /// node restart. Re-initialization includes restoring the deployed artifacts / started service
     /// instances for all the runtimes.
@@ -414,9 +430,43 @@ pub trait Runtime: Send + fmt::Debug + 'static {
     /// a bug in core.
     fn deploy_artifact(&mut self, artifact: ArtifactId, deploy_spec: Vec<u8>) -> oneshot::Receiver;
 
+    /// Checks the deploy specification of an artifact. The supervisor service calls this method
+    /// when processing deploy requests, so that requests with invalid specifications are rejected
+    /// before validators confirm them. Runtimes using [`DeploySpec`] should check the sections
+    /// they recognize in this method.
+    ///
+    /// Since the method is called during transaction execution, its result must be
+    /// deterministic, i.e., it must only depend on the method arguments. In particular, it must
+    /// not depend on whether the artifact is available on the node.
+    ///
+    /// The default implementation accepts any specification.
+    ///
+    /// [`DeploySpec`]: struct.DeploySpec.html
+    fn check_deploy_spec(&self, artifact: &ArtifactId, spec: &[u8]) -> Result<(), ExecutionError> {
+        Ok(())
+    }
+
     /// Returns `true` if the specified artifact is deployed in this runtime.
     fn is_artifact_deployed(&self, artifact: &ArtifactId) -> bool;
 
+    /// Returns the hash of the artifact bytes for a deployed artifact. The supervisor service
+    /// uses checksums to verify that all validators have deployed the same artifact.
+    ///
+    /// The default implementation returns `None`, which is appropriate for runtimes without
+    /// a notion of artifact bytes (e.g., with artifacts compiled into the node binary).
+    fn artifact_checksum(&self, artifact: &ArtifactId) -> Option<Hash> {
+        None
+    }
+
+    /// Returns the names of interface methods exposed by services of a deployed artifact.
+    /// The dispatcher uses them to check that an artifact, to which a service is migrated,
+    /// does not reuse method IDs of the older artifact for different methods.
+    ///
+    /// The default implementation returns `None`, in which case the check is skipped.
+    fn interface_methods(&self, artifact: &ArtifactId) -> Option<InterfaceMethods> {
+        None
+    }
+
     /// Requests to unload an artifact with the given identifier. Unloading may free resources
     /// (e.g., RAM) associated with the artifact.
     ///
@@ -545,6 +595,10 @@ pub trait Runtime: Send + fmt::Debug + 'static {
     ///
     /// `status` is the resulting status of the service instance.
     ///
+    /// Note that the name of a stopped service may change (e.g., if the service was renamed
+    /// by the supervisor); in this case, this method is called with the same `Stopped` status
+    /// and the updated service specification.
+    ///
     /// # Return value
     ///
     /// This method does not return a value, meaning that any error occurred during this method execution
@@ -651,6 +705,33 @@ pub trait Runtime: Send + fmt::Debug + 'static {
     /// in the fork enclosed in the `context`.
     fn after_transactions(&self, context: ExecutionContext<'_>) -> Result<(), ExecutionError>;
 
+    /// Checks invariants of a service stored in this runtime. Invariants are cheap consistency
+    /// checks of the service data (e.g., two indexes having equal lengths), which turn
+    /// silent data corruption caused by bugs in the service logic into immediate failures.
+    ///
+    /// The method is called for every active service after each call within the block
+    /// (a transaction or a `before_transactions` / `after_transactions` hook), including
+    /// the genesis block. `fork` contains the changes made by the call, which are not
+    /// flushed yet. The check must be deterministic, i.e., depend only on the data in `fork`.
+    ///
+    /// The default implementation returns `Ok(())`, i.e., services of the runtime
+    /// do not declare invariants.
+    ///
+    /// # Return value
+    ///
+    /// An invariant violation is treated as an error of the call: the changes made
+    /// by the call are rolled back, and a `CoreError::InvariantViolated` error naming
+    /// the service and the violated invariant is recorded for the call. Since all validators
+    /// execute the block in the same way, the block is still created, but without
+    /// the violating changes.
+    fn check_invariants(
+        &self,
+        instance: &InstanceDescriptor,
+        fork: ReadonlyFork<'_>,
+    ) -> Result<(), InvariantViolation> {
+        Ok(())
+    }
+
     /// Notifies the runtime about commit of a new block.
     ///
     /// This method is called *after* all `update_service_status` calls related
@@ -735,3 +816,28 @@ impl fmt::Display for InstanceDescriptor {
         write!(formatter, "{}:{}", self.id, self.name)
     }
 }
+
+/// Violation of an invariant declared by a service.
+///
+/// See [`Runtime::check_invariants`] for details.
+///
+/// [`Runtime::check_invariants`]: trait.Runtime.html#method.check_invariants
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Invariant `{}` is violated: {}", invariant, description)]
+#[non_exhaustive]
+pub struct InvariantViolation {
+    /// Name of the violated invariant.
+    pub invariant: String,
+    /// Human-readable description of the violation.
+    pub description: String,
+}
+
+impl InvariantViolation {
+    /// Creates a new violation of the specified invariant.
+    pub fn new(invariant: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            invariant: invariant.into(),
+            description: description.into(),
+        }
+    }
+}
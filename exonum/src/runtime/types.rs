@@ -28,6 +28,7 @@ use serde::{Deserialize, Serialize};
 
 use std::{
     borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Display},
     str::FromStr,
 };
@@ -39,7 +40,7 @@ use crate::{
     messages::Verified,
     proto::schema::{
         self,
-        lifecycle::artifact_state::Status::{ACTIVE, DEPLOYING, UNLOADING},
+        lifecycle::artifact_state::Status::{ACTIVE, DEPLOYING, DEPRECATED, UNLOADING},
     },
 };
 
@@ -310,6 +311,99 @@ impl ArtifactSpec {
     }
 }
 
+/// Named runtime-specific section of a [`DeploySpec`].
+///
+/// [`DeploySpec`]: struct.DeploySpec.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash)]
+#[protobuf_convert(source = "schema::base::DeploySpecSection")]
+#[non_exhaustive]
+pub struct DeploySpecSection {
+    /// Section name, e.g., `wasm.module_hash`.
+    pub name: String,
+    /// Section data. The format of the data is defined by the runtime recognizing the section.
+    pub data: Vec<u8>,
+}
+
+/// Deploy specification consisting of named runtime-specific sections, such as a hash
+/// of a WASM module or a JVM classpath.
+///
+/// The specification is a standard layout for the artifact specification passed to
+/// [`Runtime::deploy_artifact`]. Runtimes supporting it validate the sections they recognize
+/// in [`Runtime::check_deploy_spec`], so that deploy requests with invalid specifications
+/// can be rejected before the artifact is deployed.
+///
+/// [`Runtime::deploy_artifact`]: trait.Runtime.html#tymethod.deploy_artifact
+/// [`Runtime::check_deploy_spec`]: trait.Runtime.html#method.check_deploy_spec
+///
+/// # Examples
+///
+/// ```
+/// # use exonum::{crypto, merkledb::BinaryValue, runtime::DeploySpec};
+/// let module_hash = crypto::hash(b"module");
+/// let spec = DeploySpec::new()
+///     .with_section("wasm.module_hash", module_hash.to_bytes())
+///     .with_section("wasm.memory_pages", 16_u32.to_bytes());
+/// assert_eq!(spec.section("wasm.module_hash"), Some(&module_hash[..]));
+///
+/// // The specification is serialized to be passed as an artifact spec.
+/// let bytes = spec.to_bytes();
+/// assert_eq!(DeploySpec::from_bytes(bytes.into()).unwrap(), spec);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash)]
+#[protobuf_convert(source = "schema::base::DeploySpec")]
+#[non_exhaustive]
+pub struct DeploySpec {
+    /// Sections of the specification.
+    pub sections: Vec<DeploySpecSection>,
+}
+
+impl DeploySpec {
+    /// Creates an empty specification.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a section to the specification, replacing the section with the same name
+    /// if it is present.
+    #[must_use]
+    pub fn with_section(mut self, name: impl Into<String>, data: Vec<u8>) -> Self {
+        let name = name.into();
+        self.sections.retain(|section| section.name != name);
+        self.sections.push(DeploySpecSection { name, data });
+        self
+    }
+
+    /// Returns the data of the section with the specified name.
+    pub fn section(&self, name: &str) -> Option<&[u8]> {
+        self.sections
+            .iter()
+            .find(|section| section.name == name)
+            .map(|section| section.data.as_slice())
+    }
+}
+
+impl ValidateInput for DeploySpec {
+    type Error = anyhow::Error;
+
+    /// Checks that the specification has no duplicate or empty section names.
+    fn validate(&self) -> Result<(), Self::Error> {
+        let mut names = BTreeSet::new();
+        for section in &self.sections {
+            ensure!(!section.name.is_empty(), "Section name is empty");
+            ensure!(
+                names.insert(section.name.as_str()),
+                "Section `{}` is specified several times",
+                section.name
+            );
+        }
+        Ok(())
+    }
+}
+
 /// Exhaustive service instance specification.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[derive(Serialize, Deserialize)]
@@ -385,7 +479,8 @@ impl Display for InstanceSpec {
     }
 }
 
-/// Allows to query a service instance by either of the two identifiers.
+/// Allows to query a service instance by either of the two identifiers, or by the name
+/// of an interface implemented by the service.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum InstanceQuery<'a> {
@@ -393,6 +488,9 @@ pub enum InstanceQuery<'a> {
     Id(InstanceId),
     /// Query by an instance name.
     Name(&'a str),
+    /// Query by an interface name. The query resolves to the default instance registered
+    /// for the interface in the dispatcher schema, if any.
+    Interface(&'a str),
 }
 
 impl From<InstanceId> for InstanceQuery<'_> {
@@ -407,6 +505,92 @@ impl<'a> From<&'a str> for InstanceQuery<'a> {
     }
 }
 
+/// Names of the interface methods exposed by services of an artifact, keyed by the interface
+/// name and the method ID. An empty interface name denotes the default service interface.
+///
+/// Runtimes may provide this information for deployed artifacts via
+/// [`Runtime::interface_methods()`]. When a service is migrated to a newer artifact,
+/// the dispatcher checks that the newer artifact does not reuse a method ID
+/// of the older artifact for a method with a different name, or an ID marked as removed
+/// in the older artifact. Otherwise, transactions created by clients of the older
+/// artifact would be silently dispatched to a wrong handler.
+///
+/// [`Runtime::interface_methods()`]: trait.Runtime.html#method.interface_methods
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceMethods {
+    methods: BTreeMap<(String, MethodId), String>,
+    removed_ids: BTreeSet<(String, MethodId)>,
+}
+
+impl InterfaceMethods {
+    /// Creates an empty set of interface methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a method with the specified name and ID to the interface.
+    pub fn add_method(&mut self, interface: &str, id: MethodId, name: &str) -> &mut Self {
+        self.methods
+            .insert((interface.to_owned(), id), name.to_owned());
+        self
+    }
+
+    /// Marks the method ID in the interface as removed.
+    pub fn add_removed_id(&mut self, interface: &str, id: MethodId) -> &mut Self {
+        self.removed_ids.insert((interface.to_owned(), id));
+        self
+    }
+
+    /// Returns the name of the method with the specified ID in the interface.
+    pub fn method_name(&self, interface: &str, id: MethodId) -> Option<&str> {
+        self.methods
+            .get(&(interface.to_owned(), id))
+            .map(String::as_str)
+    }
+
+    /// Checks whether the method ID in the interface is marked as removed.
+    pub fn is_removed(&self, interface: &str, id: MethodId) -> bool {
+        self.removed_ids.contains(&(interface.to_owned(), id))
+    }
+
+    /// Iterates over methods as `(interface, method_id, method_name)` tuples.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, MethodId, &str)> + '_ {
+        self.methods
+            .iter()
+            .map(|((interface, id), name)| (interface.as_str(), *id, name.as_str()))
+    }
+
+    /// Checks that `newer` methods are compatible with these ones, i.e., no method ID
+    /// is reused for a method with a different name or for a removed method.
+    /// Returns the description of the first found incompatibility.
+    pub(crate) fn check_compatibility(&self, newer: &Self) -> Result<(), String> {
+        for (interface, id, name) in newer.iter() {
+            let interface_str = if interface.is_empty() {
+                "the default interface".to_owned()
+            } else {
+                format!("interface `{}`", interface)
+            };
+
+            if self.is_removed(interface, id) {
+                return Err(format!(
+                    "Method ID {} in {} is marked as removed, but is reused for method `{}`",
+                    id, interface_str, name
+                ));
+            }
+            match self.method_name(interface, id) {
+                Some(old_name) if old_name != name => {
+                    return Err(format!(
+                        "Method ID {} in {} is reused for method `{}` (previously `{}`)",
+                        id, interface_str, name, old_name
+                    ));
+                }
+                _ => { /* The method is new or retains its name. */ }
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Status of an artifact deployment.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[non_exhaustive]
@@ -417,6 +601,18 @@ pub enum ArtifactStatus {
     Deploying,
     /// The artifact has been successfully deployed.
     Active,
+    /// The artifact is deployed, but is marked as deprecated. Existing service instances
+    /// associated with the artifact continue to work, but new instances cannot be started
+    /// from it.
+    Deprecated,
+}
+
+impl ArtifactStatus {
+    /// Checks whether the artifact is deployed on the blockchain, i.e., has either
+    /// `Active` or `Deprecated` status.
+    pub fn is_deployed(self) -> bool {
+        matches!(self, Self::Active | Self::Deprecated)
+    }
 }
 
 impl Display for ArtifactStatus {
@@ -425,6 +621,7 @@ impl Display for ArtifactStatus {
             Self::Unloading => f.write_str("unloading"),
             Self::Active => f.write_str("active"),
             Self::Deploying => f.write_str("deploying"),
+            Self::Deprecated => f.write_str("deprecated"),
         }
     }
 }
@@ -437,6 +634,7 @@ impl ProtobufConvert for ArtifactStatus {
             Self::Unloading => UNLOADING,
             Self::Active => ACTIVE,
             Self::Deploying => DEPLOYING,
+            Self::Deprecated => DEPRECATED,
         }
     }
 
@@ -445,6 +643,7 @@ impl ProtobufConvert for ArtifactStatus {
             UNLOADING => Self::Unloading,
             ACTIVE => Self::Active,
             DEPLOYING => Self::Deploying,
+            DEPRECATED => Self::Deprecated,
         })
     }
 }
@@ -652,6 +851,108 @@ impl ArtifactState {
     }
 }
 
+/// Capability of a service to perform a certain kind of actions during execution.
+///
+/// Capabilities are granted to artifacts; all services instantiated from an artifact
+/// share its capabilities. See [`Capabilities`] for details.
+///
+/// [`Capabilities`]: struct.Capabilities.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Capability {
+    /// Calling methods of other services.
+    CallServices,
+    /// Reading the core schema.
+    ReadCoreSchema,
+    /// Reading the dispatcher schema and data of other services.
+    ReadServiceData,
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CallServices => f.write_str("call_services"),
+            Self::ReadCoreSchema => f.write_str("read_core_schema"),
+            Self::ReadServiceData => f.write_str("read_service_data"),
+        }
+    }
+}
+
+/// Set of capabilities granted to the services instantiated from an artifact.
+///
+/// By default, artifacts are granted the capabilities returned by
+/// [`Runtime::default_capabilities()`] of their runtime. The supervisor may override
+/// the capabilities of a specific artifact. Capabilities are checked by the core
+/// for calls to other services and for reading blockchain data via [`ExecutionContext`];
+/// runtimes may additionally check them via [`ExecutionContext::require_capability()`].
+///
+/// [`Runtime::default_capabilities()`]: trait.Runtime.html#method.default_capabilities
+/// [`ExecutionContext`]: struct.ExecutionContext.html
+/// [`ExecutionContext::require_capability()`]: struct.ExecutionContext.html#method.require_capability
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash)]
+#[protobuf_convert(source = "schema::lifecycle::Capabilities")]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// Calling methods of other services.
+    #[serde(default)]
+    pub call_services: bool,
+    /// Reading the core schema.
+    #[serde(default)]
+    pub read_core_schema: bool,
+    /// Reading the dispatcher schema and data of other services.
+    #[serde(default)]
+    pub read_service_data: bool,
+}
+
+impl Capabilities {
+    /// Returns an empty set of capabilities.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Returns the set of all capabilities.
+    pub fn all() -> Self {
+        Self {
+            call_services: true,
+            read_core_schema: true,
+            read_service_data: true,
+        }
+    }
+
+    /// Adds a capability to this set.
+    #[must_use]
+    pub fn with(mut self, capability: Capability) -> Self {
+        *self.flag_mut(capability) = true;
+        self
+    }
+
+    /// Removes a capability from this set.
+    #[must_use]
+    pub fn without(mut self, capability: Capability) -> Self {
+        *self.flag_mut(capability) = false;
+        self
+    }
+
+    /// Checks whether the set contains the specified capability.
+    pub fn contains(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::CallServices => self.call_services,
+            Capability::ReadCoreSchema => self.read_core_schema,
+            Capability::ReadServiceData => self.read_service_data,
+        }
+    }
+
+    fn flag_mut(&mut self, capability: Capability) -> &mut bool {
+        match capability {
+            Capability::CallServices => &mut self.call_services,
+            Capability::ReadCoreSchema => &mut self.read_core_schema,
+            Capability::ReadServiceData => &mut self.read_service_data,
+        }
+    }
+}
+
 /// Current state of service instance in dispatcher.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[derive(Serialize, Deserialize)]
@@ -1167,4 +1468,68 @@ mod tests {
         assert_ne!(supervisor_addr.0, crypto::hash(&[]));
         assert_ne!(blockchain_addr, supervisor_addr);
     }
+
+    #[test]
+    fn interface_methods_compatibility() {
+        let mut old_methods = InterfaceMethods::new();
+        old_methods
+            .add_method("", 0, "transfer")
+            .add_method("", 1, "issue")
+            .add_removed_id("", 2)
+            .add_method("Configure", 0, "verify_config");
+
+        // Adding new methods and retaining old ones is fine.
+        let mut new_methods = old_methods.clone();
+        new_methods.add_method("", 3, "burn");
+        old_methods.check_compatibility(&new_methods).unwrap();
+        // Removing methods is fine as well.
+        old_methods
+            .check_compatibility(&InterfaceMethods::new())
+            .unwrap();
+
+        let mut new_methods = InterfaceMethods::new();
+        new_methods.add_method("", 1, "burn");
+        let err = old_methods.check_compatibility(&new_methods).unwrap_err();
+        assert!(err.contains("Method ID 1 in the default interface is reused"));
+
+        let mut new_methods = InterfaceMethods::new();
+        new_methods.add_method("", 2, "burn");
+        let err = old_methods.check_compatibility(&new_methods).unwrap_err();
+        assert!(err.contains("is marked as removed"));
+
+        let mut new_methods = InterfaceMethods::new();
+        new_methods.add_method("Configure", 0, "apply_config");
+        let err = old_methods.check_compatibility(&new_methods).unwrap_err();
+        assert!(err.contains("interface `Configure`"));
+    }
+
+    #[test]
+    fn deploy_spec_sections() {
+        use super::DeploySpec;
+        use crate::{helpers::ValidateInput, merkledb::BinaryValue};
+
+        let spec = DeploySpec::new()
+            .with_section("jvm.classpath", b"lib/a.jar".to_vec())
+            .with_section("wasm.module_hash", vec![1; 32])
+            .with_section("jvm.classpath", b"lib/b.jar".to_vec());
+        assert_eq!(spec.sections.len(), 2);
+        assert_eq!(spec.section("jvm.classpath"), Some(&b"lib/b.jar"[..]));
+        assert_eq!(spec.section("rust.features"), None);
+        spec.validate().unwrap();
+
+        let bytes = spec.to_bytes();
+        assert_eq!(DeploySpec::from_bytes(bytes.into()).unwrap(), spec);
+
+        let mut invalid_spec = spec.clone();
+        invalid_spec.sections.push(spec.sections[0].clone());
+        let err = invalid_spec.validate().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("`wasm.module_hash` is specified several times"));
+        let err = DeploySpec::new()
+            .with_section("", vec![])
+            .validate()
+            .unwrap_err();
+        assert!(err.to_string().contains("Section name is empty"));
+    }
 }
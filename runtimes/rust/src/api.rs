@@ -21,23 +21,37 @@ use actix_web::{
     FromRequest, HttpMessage,
 };
 use exonum::{
-    blockchain::{Blockchain, Schema as CoreSchema},
+    blockchain::{Blockchain, CachedSnapshot, IndexProof, ProofCache, Schema as CoreSchema},
     crypto::PublicKey,
+    helpers::Height,
     merkledb::{access::Prefixed, Snapshot},
     runtime::{
-        ArtifactId, BlockchainData, InstanceDescriptor, InstanceState, InstanceStatus, SnapshotExt,
+        AnyTx, ArtifactId, BlockchainData, InstanceDescriptor, InstanceId, InstanceState,
+        InstanceStatus, MethodId, SnapshotExt,
     },
 };
-use exonum_api::{backends::actix, ApiBackend, ApiBuilder, ApiScope, MovedPermanentlyError};
-use exonum_proto::ProtobufConvert;
+use exonum_api::{
+    backends::actix, Actuality, ApiBackend, ApiBuilder, ApiScope, MovedPermanentlyError,
+};
+use exonum_proto::{json::DecodedMessage, ProtobufConvert};
 use futures::prelude::*;
-use protobuf::Message;
-use serde::{de::DeserializeOwned, Serialize};
+use protobuf::{reflect::MessageDescriptor, Message};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use super::Broadcaster;
 
+/// HTTP header with the minimum blockchain height, at which the client wants the request
+/// to be served.
+///
+/// If the header is present and the latest block committed by the node has a lesser
+/// height, service endpoints respond with the `409 Conflict` status. Such requests may
+/// be retried later or sent to another node. The header allows clients to read their
+/// own writes even if requests are balanced among several nodes: a client may pin
+/// the height of the block containing its transaction.
+pub const MIN_HEIGHT_HEADER: &str = "X-Exonum-Min-Height";
+
 /// Extracts request payload, which is encoded in either JSON or Protobuf.
 #[allow(clippy::future_not_send)]
 async fn extract_pb_request<Q>(request: actix::HttpRequest, payload: actix::Payload) -> Result<Q>
@@ -90,6 +104,28 @@ where
     }
 }
 
+/// Response of a service endpoint together with the blockchain height, at which
+/// the response data was read.
+///
+/// See [`ServiceApiState::read_consistent()`] for an example of usage.
+///
+/// [`ServiceApiState::read_consistent()`]: struct.ServiceApiState.html#method.read_consistent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct AtHeight<T> {
+    /// Height of the latest block committed to the snapshot the data was read from.
+    pub height: Height,
+    /// Response data.
+    pub data: T,
+}
+
+impl<T> AtHeight<T> {
+    /// Creates a response with the specified height and data.
+    pub fn new(height: Height, data: T) -> Self {
+        Self { height, data }
+    }
+}
+
 /// Provide the current blockchain state snapshot to API handlers.
 ///
 /// This structure allows a service API handler to interact with the service instance
@@ -99,22 +135,30 @@ pub struct ServiceApiState {
     /// Transaction broadcaster.
     broadcaster: Broadcaster,
     // TODO Think about avoiding of unnecessary snapshots creation. [ECR-3222]
-    snapshot: Box<dyn Snapshot>,
+    snapshot: CachedSnapshot,
     /// Endpoint path relative to the service root.
     endpoint: String,
     /// Current status of the service.
     status: InstanceStatus,
+    /// Cache of generated proofs shared with the blockchain.
+    proof_cache: Arc<ProofCache>,
 }
 
 impl ServiceApiState {
     /// Creates service API context from the given blockchain and instance descriptor.
+    /// If `min_height` is specified, the context is only created if the blockchain
+    /// has reached this height.
     fn new<S: Into<String>>(
         blockchain: &Blockchain,
         instance: InstanceDescriptor,
         expected_artifact: &ArtifactId,
         endpoint: S,
+        min_height: Option<Height>,
     ) -> Result<Self> {
-        let snapshot = blockchain.snapshot();
+        let snapshot = blockchain.cached_snapshot();
+        if let Some(min_height) = min_height {
+            Self::check_height(&snapshot, min_height)?;
+        }
         let instance_state = snapshot
             .for_dispatcher()
             .get_instance(instance.id)
@@ -133,9 +177,26 @@ impl ServiceApiState {
             snapshot,
             endpoint: endpoint.into(),
             status,
+            proof_cache: Arc::clone(blockchain.proof_cache()),
         })
     }
 
+    fn check_height(snapshot: &dyn Snapshot, min_height: Height) -> Result<()> {
+        let height = CoreSchema::new(snapshot).height();
+        if height >= min_height {
+            Ok(())
+        } else {
+            let details = format!(
+                "Requested data at height {} or above, but the latest committed block \
+                 has height {}. Retry the request later or send it to another node",
+                min_height, height
+            );
+            Err(Error::new(HttpStatusCode::CONFLICT)
+                .title("Blockchain has not reached the requested height")
+                .detail(details))
+        }
+    }
+
     fn removed_service_error(instance: &InstanceDescriptor) -> Error {
         let details = format!(
             "Service `{}` has been removed from the blockchain services, making it \
@@ -149,7 +210,7 @@ impl ServiceApiState {
 
     /// Returns readonly access to blockchain data.
     pub fn data(&self) -> BlockchainData<&dyn Snapshot> {
-        BlockchainData::new(&self.snapshot, &self.instance().name)
+        BlockchainData::new(self.snapshot(), &self.instance().name)
     }
 
     /// Returns readonly access to the data of the executing service.
@@ -160,9 +221,72 @@ impl ServiceApiState {
     /// Returns the access to the entire blockchain snapshot. Use [`data`](#method.data)
     /// or [`service_data`](#method.service_data) for more structure snapshot presentations.
     pub fn snapshot(&self) -> &dyn Snapshot {
+        &*self.snapshot
+    }
+
+    /// Returns the blockchain snapshot allowing to cache decoded values shared
+    /// among API handlers. Caching is only performed if it is enabled for the node.
+    pub fn cached_snapshot(&self) -> &CachedSnapshot {
         &self.snapshot
     }
 
+    /// Returns the height of the latest block committed to the snapshot used by the handler.
+    pub fn height(&self) -> Height {
+        CoreSchema::new(self.snapshot()).height()
+    }
+
+    /// Reads data with the provided closure and annotates it with the blockchain height,
+    /// at which the data was read.
+    ///
+    /// All indexes accessed via the `BlockchainData` passed to the closure are read
+    /// from the same snapshot as the one used to determine the height. Thus, the results
+    /// of reading several indexes are mutually consistent even if new blocks are committed
+    /// while the handler is executed. Use this method in endpoints combining data
+    /// from multiple indexes to allow clients to correlate responses with each other.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum_rust_runtime::api::{self, AtHeight, ServiceApiState};
+    /// async fn block_stats(
+    ///     state: ServiceApiState,
+    ///     _query: (),
+    /// ) -> api::Result<AtHeight<(u64, u64)>> {
+    ///     state.read_consistent(|data| {
+    ///         let schema = data.for_core();
+    ///         let blocks = schema.block_hashes_by_height().len();
+    ///         let transactions = schema.transactions_len();
+    ///         Ok((blocks, transactions))
+    ///     })
+    /// }
+    /// ```
+    pub fn read_consistent<T, F>(&self, read: F) -> Result<AtHeight<T>>
+    where
+        F: FnOnce(BlockchainData<&dyn Snapshot>) -> Result<T>,
+    {
+        let data = read(self.data())?;
+        Ok(AtHeight::new(self.height(), data))
+    }
+
+    /// Returns the cache of proofs generated for API clients. The cache can be used
+    /// to avoid recomputing proofs for hot keys; see [`ProofCache`] for details.
+    ///
+    /// [`ProofCache`]: https://docs.rs/exonum/latest/exonum/blockchain/struct.ProofCache.html
+    pub fn proof_cache(&self) -> &ProofCache {
+        &self.proof_cache
+    }
+
+    /// Returns a proof for a Merkelized index of the executing service. Unlike
+    /// [`BlockchainData::proof_for_service_index()`], the proof is taken from the proof cache
+    /// if possible.
+    ///
+    /// [`BlockchainData::proof_for_service_index()`]: https://docs.rs/exonum/latest/exonum/runtime/struct.BlockchainData.html#method.proof_for_service_index
+    pub fn proof_for_service_index(&self, index_name: &str) -> Option<IndexProof> {
+        let full_index_name = [&self.instance().name, ".", index_name].concat();
+        self.proof_cache
+            .index_proof(self.snapshot(), &full_index_name)
+    }
+
     /// Returns the service key of this node.
     pub fn service_key(&self) -> PublicKey {
         self.broadcaster.keypair().public_key()
@@ -182,7 +306,7 @@ impl ServiceApiState {
     /// is active (i.e., can process transactions). If these conditions do not hold, returns `None`.
     pub fn broadcaster(&self) -> Option<Broadcaster> {
         if self.status.is_active() {
-            CoreSchema::new(&self.snapshot).validator_id(self.service_key())?;
+            CoreSchema::new(self.snapshot()).validator_id(self.service_key())?;
             Some(self.broadcaster.clone())
         } else {
             None
@@ -266,18 +390,22 @@ impl ScopeData {
         name: &str,
         handler: &F,
         query: Q,
+        request: &actix::HttpRequest,
     ) -> impl Future<Output = exonum_api::Result<I>>
     where
         F: Fn(ServiceApiState, Q) -> R + 'static,
         I: Send,
         R: Future<Output = exonum_api::Result<I>> + Send,
     {
-        let maybe_state = ServiceApiState::new(
-            &self.blockchain,
-            self.descriptor.clone(),
-            &self.artifact,
-            name,
-        );
+        let maybe_state = Self::min_height(request).and_then(|min_height| {
+            ServiceApiState::new(
+                &self.blockchain,
+                self.descriptor.clone(),
+                &self.artifact,
+                name,
+                min_height,
+            )
+        });
         let state = match maybe_state {
             Ok(state) => state,
             Err(err) => return future::err(err).left_future(),
@@ -288,6 +416,46 @@ impl ScopeData {
             .map_err(move |err| err.source(descriptor.to_string()))
             .right_future()
     }
+
+    /// Parses the minimum height requested by the client, if any.
+    fn min_height(request: &actix::HttpRequest) -> Result<Option<Height>> {
+        let header = match request.headers().get(MIN_HEIGHT_HEADER) {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        header
+            .to_str()
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .map(|height| Some(Height(height)))
+            .ok_or_else(|| {
+                Error::bad_request()
+                    .title("Invalid minimum height")
+                    .detail(format!(
+                        "Header `{}` should contain a non-negative integer",
+                        MIN_HEIGHT_HEADER
+                    ))
+            })
+    }
+
+    fn request_handler<Q, I, F, R>(
+        &self,
+        name: &'static str,
+        mutability: EndpointMutability,
+        actuality: Actuality,
+        handler: F,
+    ) -> actix::RequestHandler
+    where
+        Q: DeserializeOwned + 'static,
+        I: Serialize + Send + 'static,
+        F: Fn(ServiceApiState, Q) -> R + 'static + Clone + Send + Sync,
+        R: Future<Output = exonum_api::Result<I>> + Send,
+    {
+        let data = self.clone();
+        actix::RequestHandler::with_request(name, mutability, actuality, move |request, query| {
+            data.wrap(name, &handler, query, &request)
+        })
+    }
 }
 
 impl ServiceApiScope {
@@ -313,9 +481,13 @@ impl ServiceApiScope {
         F: Fn(ServiceApiState, Q) -> R + 'static + Clone + Send + Sync,
         R: Future<Output = exonum_api::Result<I>> + Send,
     {
-        let data = self.data.clone();
-        self.inner
-            .endpoint(name, move |query: Q| data.wrap(name, &handler, query));
+        let handler = self.data.request_handler(
+            name,
+            EndpointMutability::Immutable,
+            Actuality::Actual,
+            handler,
+        );
+        self.inner.web_backend().raw_handler(handler);
         self
     }
 
@@ -329,9 +501,13 @@ impl ServiceApiScope {
         F: Fn(ServiceApiState, Q) -> R + 'static + Clone + Send + Sync,
         R: Future<Output = exonum_api::Result<I>> + Send,
     {
-        let data = self.data.clone();
-        self.inner
-            .endpoint_mut(name, move |query: Q| data.wrap(name, &handler, query));
+        let handler = self.data.request_handler(
+            name,
+            EndpointMutability::Mutable,
+            Actuality::Actual,
+            handler,
+        );
+        self.inner.web_backend().raw_handler(handler);
         self
     }
 
@@ -356,8 +532,8 @@ impl ServiceApiScope {
             let handler = handler.clone();
 
             async move {
-                let query: Q = extract_pb_request(http_request, payload).await?;
-                let response = data.wrap(name, &handler, query).await?;
+                let query: Q = extract_pb_request(http_request.clone(), payload).await?;
+                let response = data.wrap(name, &handler, query, &http_request).await?;
                 Ok(actix::HttpResponse::Ok().json(response))
             }
             .boxed_local()
@@ -385,13 +561,18 @@ impl ServiceApiScope {
         F: Fn(ServiceApiState, Q) -> R + 'static + Clone + Send + Sync,
         R: Future<Output = exonum_api::Result<I>> + Send,
     {
-        let data = self.data.clone();
-        let handler = deprecated.handler.clone();
-
-        let full_handler = move |query: Q| data.wrap(name, &handler, query);
         // Mark endpoint as deprecated.
-        let handler = deprecated.with_different_handler(full_handler);
-        self.inner.endpoint(name, handler);
+        let actuality = Actuality::Deprecated {
+            discontinued_on: deprecated.discontinued_on,
+            description: deprecated.description,
+        };
+        let handler = self.data.request_handler(
+            name,
+            EndpointMutability::Immutable,
+            actuality,
+            deprecated.handler,
+        );
+        self.inner.web_backend().raw_handler(handler);
         self
     }
 
@@ -409,13 +590,18 @@ impl ServiceApiScope {
         F: Fn(ServiceApiState, Q) -> R + 'static + Clone + Send + Sync,
         R: Future<Output = exonum_api::Result<I>> + Send,
     {
-        let data = self.data.clone();
-        let handler = deprecated.handler.clone();
-
-        let full_handler = move |query: Q| data.wrap(name, &handler, query);
         // Mark endpoint as deprecated.
-        let handler = deprecated.with_different_handler(full_handler);
-        self.inner.endpoint_mut(name, handler);
+        let actuality = Actuality::Deprecated {
+            discontinued_on: deprecated.discontinued_on,
+            description: deprecated.description,
+        };
+        let handler = self.data.request_handler(
+            name,
+            EndpointMutability::Mutable,
+            actuality,
+            deprecated.handler,
+        );
+        self.inner.web_backend().raw_handler(handler);
         self
     }
 
@@ -503,6 +689,7 @@ pub struct ServiceApiBuilder {
     public_scope: ServiceApiScope,
     private_scope: ServiceApiScope,
     root_path: Option<String>,
+    payload_decoder: PayloadDecoder,
 }
 
 impl ServiceApiBuilder {
@@ -511,6 +698,7 @@ impl ServiceApiBuilder {
         blockchain: Blockchain,
         instance: InstanceDescriptor,
         artifact: ArtifactId,
+        payload_decoder: PayloadDecoder,
     ) -> Self {
         Self {
             blockchain: blockchain.clone(),
@@ -521,6 +709,7 @@ impl ServiceApiBuilder {
             ),
             private_scope: ServiceApiScope::new(blockchain, instance, artifact),
             root_path: None,
+            payload_decoder,
         }
     }
 
@@ -539,6 +728,11 @@ impl ServiceApiBuilder {
         &self.blockchain
     }
 
+    /// Returns a decoder of transaction payloads for the active Rust services.
+    pub fn payload_decoder(&self) -> &PayloadDecoder {
+        &self.payload_decoder
+    }
+
     /// Overrides the service root path as opposed to the default `services/$service_name`.
     ///
     /// # Safety
@@ -559,6 +753,33 @@ impl ServiceApiBuilder {
     }
 }
 
+/// Decoder of transaction payloads based on the [`payload_descriptors`] of the active
+/// Rust services.
+///
+/// [`payload_descriptors`]: ../trait.Service.html#method.payload_descriptors
+#[derive(Debug, Clone, Default)]
+pub struct PayloadDecoder {
+    descriptors: Arc<HashMap<(InstanceId, MethodId), MessageDescriptor>>,
+}
+
+impl PayloadDecoder {
+    pub(crate) fn new(descriptors: HashMap<(InstanceId, MethodId), MessageDescriptor>) -> Self {
+        Self {
+            descriptors: Arc::new(descriptors),
+        }
+    }
+
+    /// Decodes arguments of the transaction. Returns `None` if the service called
+    /// by the transaction does not provide a descriptor for the called method.
+    pub fn decode(&self, tx: &AnyTx) -> Option<protobuf::Result<DecodedMessage>> {
+        let call_info = &tx.call_info;
+        let descriptor = self
+            .descriptors
+            .get(&(call_info.instance_id, call_info.method_id))?;
+        Some(DecodedMessage::decode(descriptor, &tx.arguments))
+    }
+}
+
 impl From<ServiceApiBuilder> for ApiBuilder {
     fn from(inner: ServiceApiBuilder) -> Self {
         Self {
@@ -0,0 +1,144 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Durable delivery of `after_commit` notifications.
+//!
+//! A service may acknowledge an `after_commit` notification once the side effects triggered
+//! by it (e.g., writes to an external database) are durable. The runtime records the latest
+//! acknowledged height for each service in a node-local index. After the node restarts,
+//! notifications for the blocks committed after the acknowledged height are delivered again,
+//! so that side effects lost in a crash can be repeated.
+
+use exonum::{
+    blockchain::Blockchain,
+    helpers::Height,
+    merkledb::{access::CopyAccessExt, Error as StorageError, Snapshot},
+};
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+/// Node-local index mapping names of service instances to the latest acknowledged heights.
+const ACKNOWLEDGED_HEIGHTS: &str = "rust_runtime_acknowledged_heights";
+
+/// Log of acknowledged `after_commit` notifications.
+#[derive(Debug, Clone)]
+pub(crate) struct DeliveryLog {
+    blockchain: Blockchain,
+    // Serializes acknowledgements, which may be performed from different threads.
+    lock: Arc<Mutex<()>>,
+}
+
+impl DeliveryLog {
+    /// Creates the log, creating the backing index if necessary. Must be called before
+    /// the blockchain starts processing blocks.
+    pub fn new(blockchain: &Blockchain) -> Result<Self, StorageError> {
+        let snapshot = blockchain.snapshot();
+        if snapshot.index_type(ACKNOWLEDGED_HEIGHTS).is_none() {
+            blockchain.merge_node_local(|fork| {
+                fork.get_map::<_, str, Height>(ACKNOWLEDGED_HEIGHTS);
+            })?;
+        }
+        Ok(Self {
+            blockchain: blockchain.clone(),
+            lock: Arc::default(),
+        })
+    }
+
+    /// Returns the latest height acknowledged by the service instance, or `None` if
+    /// the instance has never acknowledged notifications.
+    pub fn acknowledged_height(
+        &self,
+        snapshot: &dyn Snapshot,
+        instance_name: &str,
+    ) -> Option<Height> {
+        snapshot
+            .get_map::<_, str, Height>(ACKNOWLEDGED_HEIGHTS)
+            .get(instance_name)
+    }
+
+    fn acknowledge(&self, instance_name: &str, height: Height) -> Result<(), StorageError> {
+        let _guard = self.lock.lock().unwrap();
+        let snapshot = self.blockchain.snapshot();
+        if self
+            .acknowledged_height(&snapshot, instance_name)
+            .map_or(false, |acknowledged| acknowledged >= height)
+        {
+            return Ok(());
+        }
+        self.blockchain.merge_node_local(|fork| {
+            fork.get_map::<_, str, Height>(ACKNOWLEDGED_HEIGHTS)
+                .put(instance_name, height);
+        })
+    }
+}
+
+/// Handle allowing to acknowledge an `after_commit` notification, possibly after
+/// the handler has returned (e.g., once an external system confirms the side effects
+/// of the notification).
+///
+/// Acknowledging a notification acknowledges all notifications for the preceding heights
+/// as well. Notifications which were not acknowledged before the node is restarted
+/// are delivered again; see [`AfterCommitContext::is_redelivery`] for details.
+///
+/// [`AfterCommitContext::is_redelivery`]: struct.AfterCommitContext.html#method.is_redelivery
+#[derive(Clone)]
+pub struct Acknowledgement {
+    log: Option<DeliveryLog>,
+    instance_name: String,
+    height: Height,
+}
+
+impl fmt::Debug for Acknowledgement {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("Acknowledgement")
+            .field("instance_name", &self.instance_name)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+impl Acknowledgement {
+    pub(crate) fn new(log: Option<DeliveryLog>, instance_name: &str, height: Height) -> Self {
+        Self {
+            log,
+            instance_name: instance_name.to_owned(),
+            height,
+        }
+    }
+
+    /// Returns the height of the block the acknowledged notification corresponds to.
+    pub fn height(&self) -> Height {
+        self.height
+    }
+
+    /// Records the acknowledgement in the node database. Acknowledging a notification
+    /// for a height lower than the already acknowledged one has no effect.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the acknowledgement cannot be persisted, e.g., if the node
+    /// database is opened in the read-only mode.
+    pub fn acknowledge(self) -> Result<(), StorageError> {
+        match self.log {
+            Some(log) => log.acknowledge(&self.instance_name, self.height),
+            None => Err(StorageError::new(
+                "After-commit notifications cannot be acknowledged on this node",
+            )),
+        }
+    }
+}
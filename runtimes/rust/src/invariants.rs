@@ -0,0 +1,94 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Invariants of the service data.
+//!
+//! Invariants are registered by a service in [`Service::invariants`] and are checked
+//! by the core after each call within a block (a transaction or a service hook).
+//! If a call violates an invariant, its changes are rolled back on all validators,
+//! and the violation is recorded as the call error, so that a bug corrupting
+//! the service data is detected immediately and attributed to the call.
+//!
+//! [`Service::invariants`]: ../trait.Service.html#method.invariants
+
+use exonum::{
+    merkledb::ReadonlyFork,
+    runtime::{BlockchainData, InstanceDescriptor, InvariantViolation},
+};
+
+use std::fmt;
+
+type InvariantFn = Box<dyn Fn(BlockchainData<ReadonlyFork<'_>>) -> Result<(), String> + Send>;
+
+/// Collects invariants of a service instance.
+pub struct Invariants {
+    invariants: Vec<(String, InvariantFn)>,
+}
+
+impl fmt::Debug for Invariants {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self.invariants.iter().map(|(name, _)| name).collect();
+        formatter
+            .debug_struct("Invariants")
+            .field("invariants", &names)
+            .finish()
+    }
+}
+
+impl Invariants {
+    pub(crate) fn new() -> Self {
+        Self { invariants: vec![] }
+    }
+
+    /// Adds an invariant with the specified name. The invariant receives the blockchain data
+    /// including all changes made within the block so far, and returns an error describing
+    /// the violation if the data is inconsistent.
+    ///
+    /// Invariants are checked after each call in the block, so they should be cheap to evaluate;
+    /// e.g., comparing lengths of indexes is fine, but iterating over indexes is not.
+    /// Invariants must be deterministic, i.e., depend only on the provided data.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the invariant with the same name is already registered.
+    pub fn add<F>(&mut self, name: impl Into<String>, check: F) -> &mut Self
+    where
+        F: Fn(BlockchainData<ReadonlyFork<'_>>) -> Result<(), String> + Send + 'static,
+    {
+        let name = name.into();
+        assert!(
+            self.invariants
+                .iter()
+                .all(|(existing, _)| *existing != name),
+            "Invariant `{}` is already registered",
+            name
+        );
+        self.invariants.push((name, Box::new(check)));
+        self
+    }
+
+    /// Checks invariants in the order of their registration.
+    pub(crate) fn check(
+        &self,
+        instance: &InstanceDescriptor,
+        fork: ReadonlyFork<'_>,
+    ) -> Result<(), InvariantViolation> {
+        for (name, check) in &self.invariants {
+            let data = BlockchainData::new(fork, &instance.name);
+            check(data)
+                .map_err(|description| InvariantViolation::new(name.as_str(), description))?;
+        }
+        Ok(())
+    }
+}
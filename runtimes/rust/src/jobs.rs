@@ -0,0 +1,341 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Node-local background jobs of services.
+//!
+//! Jobs are registered by a service in [`Service::background_jobs`] and are managed
+//! by the Rust runtime. Each job runs on a dedicated thread while the service is active;
+//! once the service is stopped, frozen, migrated or upgraded, its jobs are stopped.
+//! Jobs are not a part of the consensus: they are executed independently on each node,
+//! and may only affect the blockchain state by broadcasting transactions.
+//!
+//! Information about the running jobs is available via the `jobs` endpoint
+//! of the private Rust runtime API.
+//!
+//! [`Service::background_jobs`]: ../trait.Service.html#method.background_jobs
+
+use exonum::{
+    blockchain::Blockchain,
+    merkledb::{access::Prefixed, Snapshot},
+    runtime::{BlockchainData, InstanceDescriptor, InstanceId},
+};
+use log::{error, trace};
+use serde_derive::{Deserialize, Serialize};
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::Broadcaster;
+
+type JobFn = Box<dyn FnMut(&JobContext) -> Result<(), String> + Send>;
+
+struct JobSpec {
+    name: String,
+    interval: Duration,
+    job: JobFn,
+}
+
+/// Collects background jobs of a service instance.
+pub struct JobsBuilder {
+    jobs: Vec<JobSpec>,
+}
+
+impl fmt::Debug for JobsBuilder {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = self.jobs.iter().map(|job| &job.name).collect();
+        formatter
+            .debug_struct("JobsBuilder")
+            .field("jobs", &names)
+            .finish()
+    }
+}
+
+impl JobsBuilder {
+    pub(crate) fn new() -> Self {
+        Self { jobs: vec![] }
+    }
+
+    /// Adds a job invoked periodically with the specified `interval` between
+    /// the end of an invocation and the start of the next one. The first invocation
+    /// is performed right after the job is started.
+    ///
+    /// An error returned by the job or a panic within it is recorded in the job
+    /// information; the job is invoked again after the interval.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the job with the same name is already registered.
+    pub fn periodic<F>(&mut self, name: impl Into<String>, interval: Duration, job: F) -> &mut Self
+    where
+        F: FnMut(&JobContext) -> Result<(), String> + Send + 'static,
+    {
+        let name = name.into();
+        assert!(
+            self.jobs.iter().all(|spec| spec.name != name),
+            "Background job `{}` is already registered",
+            name
+        );
+        self.jobs.push(JobSpec {
+            name,
+            interval,
+            job: Box::new(job),
+        });
+        self
+    }
+}
+
+/// Context provided to the background jobs.
+pub struct JobContext {
+    blockchain: Blockchain,
+    instance: InstanceDescriptor,
+    shared: Arc<JobShared>,
+}
+
+impl fmt::Debug for JobContext {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("JobContext")
+            .field("instance", &self.instance)
+            .field("job", &self.shared.name)
+            .finish()
+    }
+}
+
+impl JobContext {
+    /// Returns the descriptor of the service instance the job belongs to.
+    pub fn instance(&self) -> &InstanceDescriptor {
+        &self.instance
+    }
+
+    /// Returns blockchain data for the latest committed state of the blockchain.
+    pub fn data(&self) -> BlockchainData<Arc<dyn Snapshot>> {
+        BlockchainData::new(Arc::from(self.blockchain.snapshot()), &self.instance.name)
+    }
+
+    /// Returns data of the service for the latest committed state of the blockchain.
+    pub fn service_data(&self) -> Prefixed<Arc<dyn Snapshot>> {
+        self.data().for_executing_service()
+    }
+
+    /// Returns a transaction broadcaster signing transactions with the service key of the node.
+    pub fn broadcaster(&self) -> Broadcaster {
+        Broadcaster::new(
+            self.instance.clone(),
+            self.blockchain.service_keypair().clone(),
+            self.blockchain.sender().clone(),
+        )
+    }
+
+    /// Returns `true` if the job was stopped. Jobs performing long operations should check
+    /// this flag and return early once it is set.
+    pub fn is_stopped(&self) -> bool {
+        *self.shared.stopped.lock().unwrap()
+    }
+}
+
+/// Information about a background job of a service.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct JobInfo {
+    /// Name of the service instance.
+    pub service: String,
+    /// Name of the job.
+    pub name: String,
+    /// Interval between the job invocations in milliseconds.
+    pub interval: u64,
+    /// Number of completed invocations of the job.
+    pub runs: u64,
+    /// Number of invocations which have returned an error or panicked.
+    pub failures: u64,
+    /// Description of the latest failure, if any.
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct JobStats {
+    runs: u64,
+    failures: u64,
+    last_error: Option<String>,
+}
+
+/// Job state shared between the runtime and the job thread.
+#[derive(Debug)]
+struct JobShared {
+    service: String,
+    name: String,
+    interval: Duration,
+    stopped: Mutex<bool>,
+    stop_signal: Condvar,
+    stats: Mutex<JobStats>,
+}
+
+impl JobShared {
+    fn stop(&self) {
+        *self.stopped.lock().unwrap() = true;
+        self.stop_signal.notify_all();
+    }
+
+    /// Waits for the job interval. Returns `false` if the job was stopped while waiting.
+    fn wait(&self) -> bool {
+        let stopped = self.stopped.lock().unwrap();
+        let (stopped, _) = self
+            .stop_signal
+            .wait_timeout_while(stopped, self.interval, |stopped| !*stopped)
+            .unwrap();
+        !*stopped
+    }
+
+    fn record(&self, res: Result<(), String>) {
+        let mut stats = self.stats.lock().unwrap();
+        stats.runs += 1;
+        if let Err(err) = res {
+            error!(
+                "Background job `{}` of `{}` failed: {}",
+                self.name, self.service, err
+            );
+            stats.failures += 1;
+            stats.last_error = Some(err);
+        }
+    }
+
+    fn info(&self) -> JobInfo {
+        let stats = self.stats.lock().unwrap();
+        JobInfo {
+            service: self.service.clone(),
+            name: self.name.clone(),
+            interval: self.interval.as_millis() as u64,
+            runs: stats.runs,
+            failures: stats.failures,
+            last_error: stats.last_error.clone(),
+        }
+    }
+}
+
+fn run_job(mut job: JobFn, context: JobContext) {
+    let shared = Arc::clone(&context.shared);
+    while !context.is_stopped() {
+        let res = panic::catch_unwind(AssertUnwindSafe(|| job(&context)));
+        let res = res.unwrap_or_else(|panic| {
+            let description = panic
+                .downcast_ref::<&str>()
+                .map(|s| (*s).to_owned())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_owned());
+            Err(format!("Job panicked: {}", description))
+        });
+        shared.record(res);
+        if !shared.wait() {
+            break;
+        }
+    }
+    trace!(
+        "Background job `{}` of `{}` stopped",
+        shared.name,
+        shared.service
+    );
+}
+
+/// Background jobs of the service instances managed by the runtime.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct JobRegistry {
+    jobs: Arc<Mutex<BTreeMap<InstanceId, Vec<Arc<JobShared>>>>>,
+}
+
+impl JobRegistry {
+    /// Checks whether the jobs of the specified instance are running.
+    pub fn has_jobs(&self, instance_id: InstanceId) -> bool {
+        self.jobs.lock().unwrap().contains_key(&instance_id)
+    }
+
+    /// Starts the jobs of the specified instance. An instance without jobs is recorded
+    /// as well, so that its jobs are not collected again.
+    pub fn start(&self, blockchain: &Blockchain, instance: &InstanceDescriptor, jobs: JobsBuilder) {
+        let mut started = Vec::with_capacity(jobs.jobs.len());
+        for spec in jobs.jobs {
+            let shared = Arc::new(JobShared {
+                service: instance.name.clone(),
+                name: spec.name,
+                interval: spec.interval,
+                stopped: Mutex::new(false),
+                stop_signal: Condvar::new(),
+                stats: Mutex::default(),
+            });
+            let context = JobContext {
+                blockchain: blockchain.clone(),
+                instance: instance.clone(),
+                shared: Arc::clone(&shared),
+            };
+            let job = spec.job;
+            let thread_name = format!("job-{}-{}", instance.name, shared.name);
+            let spawned = thread::Builder::new()
+                .name(thread_name)
+                .spawn(move || run_job(job, context));
+            if let Err(err) = spawned {
+                error!(
+                    "Cannot spawn background job `{}` of `{}`: {}",
+                    shared.name, instance.name, err
+                );
+                continue;
+            }
+            trace!(
+                "Started background job `{}` of `{}`",
+                shared.name,
+                instance.name
+            );
+            started.push(shared);
+        }
+        self.jobs.lock().unwrap().insert(instance.id, started);
+    }
+
+    /// Stops the jobs of the specified instance. Job threads are signalled to stop,
+    /// but are not waited for.
+    pub fn stop(&self, instance_id: InstanceId) {
+        if let Some(jobs) = self.jobs.lock().unwrap().remove(&instance_id) {
+            for job in jobs {
+                job.stop();
+            }
+        }
+    }
+
+    /// Returns information about all running jobs.
+    pub fn info(&self) -> Vec<JobInfo> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .values()
+            .flatten()
+            .map(|job| job.info())
+            .collect()
+    }
+}
+
+impl Drop for JobRegistry {
+    fn drop(&mut self) {
+        // Stop jobs once the last handle to the registry is dropped, e.g.,
+        // when the node is shut down.
+        if Arc::strong_count(&self.jobs) == 1 {
+            for jobs in self.jobs.lock().unwrap().values() {
+                for job in jobs {
+                    job.stop();
+                }
+            }
+        }
+    }
+}
@@ -325,8 +325,11 @@
 pub use exonum::runtime::ExecutionContext;
 
 pub use self::{
+    delivery::Acknowledgement,
     error::Error,
-    runtime_api::{ArtifactProtobufSpec, ProtoSourceFile, ProtoSourcesQuery},
+    invariants::Invariants,
+    jobs::{JobContext, JobInfo, JobsBuilder},
+    runtime_api::{ArtifactProtobufSpec, ProtoSourceFile, ProtoSourcesQuery, SchemaQuery},
     service::{
         AfterCommitContext, Broadcaster, DefaultInstance, Service, ServiceDispatcher,
         ServiceFactory,
@@ -338,28 +341,36 @@ pub mod api;
 pub mod spec;
 
 use exonum::{
-    blockchain::{Blockchain, Schema as CoreSchema},
-    helpers::Height,
-    merkledb::Snapshot,
+    blockchain::{Blockchain, Schema as CoreSchema, TxLocation},
+    helpers::{Height, ValidatorId},
+    merkledb::{BinaryValue, ReadonlyFork, Snapshot},
     runtime::{
         catch_panic,
+        encryption::{EncryptedPayload, PayloadKeyStore},
         migrations::{InitMigrationError, MigrateData, MigrationScript},
         oneshot::Receiver,
         versioning::Version,
         ArtifactId, ExecutionError, ExecutionFail, InstanceDescriptor, InstanceId, InstanceSpec,
-        InstanceState, InstanceStatus, Mailbox, MethodId, Runtime, RuntimeFeature,
-        RuntimeIdentifier, WellKnownRuntime,
+        InstanceState, InstanceStatus, InterfaceMethods, InvariantViolation, Mailbox, MethodId,
+        Runtime, RuntimeFeature, RuntimeIdentifier, WellKnownRuntime,
     },
 };
 use exonum_api::{ApiBuilder, UpdateEndpoints};
 use futures::{channel::mpsc, executor, SinkExt};
-use log::trace;
+use log::{trace, warn};
 
 use std::collections::{BTreeMap, HashMap, HashSet};
 
-use self::api::ServiceApiBuilder;
+use self::{
+    api::{PayloadDecoder, ServiceApiBuilder},
+    delivery::DeliveryLog,
+    jobs::JobRegistry,
+};
 
+mod delivery;
 mod error;
+mod invariants;
+mod jobs;
 mod runtime_api;
 mod service;
 mod stubs;
@@ -369,7 +380,8 @@ pub mod _reexports {
     //! Types necessary for `ServiceDispatcher` and `ServiceFactory` derive macros to work.
 
     pub use exonum::runtime::{
-        ArtifactId, CommonError, ExecutionContext, ExecutionError, MethodId, RuntimeIdentifier,
+        ArtifactId, CommonError, ExecutionContext, ExecutionError, InterfaceMethods, MethodId,
+        RuntimeIdentifier,
     };
 }
 
@@ -416,12 +428,19 @@ pub struct RustRuntime {
     started_services: BTreeMap<InstanceId, Instance>,
     started_services_by_name: HashMap<String, InstanceId>,
     changed_services_since_last_block: bool,
+    payload_keys: PayloadKeyStore,
+    jobs: JobRegistry,
+    delivery_log: Option<DeliveryLog>,
+    // Instances for which unacknowledged `after_commit` notifications were delivered again
+    // after the runtime start.
+    redelivered_services: HashSet<InstanceId>,
 }
 
 /// Builder of the `RustRuntime`.
 #[derive(Debug, Default)]
 pub struct RustRuntimeBuilder {
     available_artifacts: HashMap<ArtifactId, Box<dyn FactoryWithMigrations>>,
+    payload_keys: PayloadKeyStore,
 }
 
 #[derive(Debug)]
@@ -430,6 +449,7 @@ struct Instance {
     name: String,
     service: Box<dyn Service>,
     artifact_id: ArtifactId,
+    invariants: Invariants,
 }
 
 impl Instance {
@@ -490,6 +510,21 @@ impl RustRuntimeBuilder {
         self
     }
 
+    /// Checks whether a service factory for the specified artifact is added to the builder.
+    pub fn has_artifact(&self, artifact: &ArtifactId) -> bool {
+        self.available_artifacts.contains_key(artifact)
+    }
+
+    /// Sets the store of keys used to decrypt encrypted transaction payloads. Decrypted
+    /// payloads are passed to the [`Service::handle_decrypted_payload`] hook.
+    ///
+    /// [`Service::handle_decrypted_payload`]: trait.Service.html#method.handle_decrypted_payload
+    #[must_use]
+    pub fn with_payload_keys(mut self, payload_keys: PayloadKeyStore) -> Self {
+        self.payload_keys = payload_keys;
+        self
+    }
+
     /// Completes the build process, converting the builder into a `RustRuntime`.
     pub fn build(self, api_notifier: mpsc::Sender<UpdateEndpoints>) -> RustRuntime {
         RustRuntime {
@@ -503,6 +538,10 @@ impl RustRuntimeBuilder {
             // ^-- We set this flag to `true` to propagate initial changes to API (which always
             // include the runtime API) after the runtime is resumed or the genesis block
             // is created.
+            payload_keys: self.payload_keys,
+            jobs: JobRegistry::default(),
+            delivery_log: None,
+            redelivered_services: HashSet::new(),
         }
     }
 
@@ -557,6 +596,22 @@ impl RustRuntime {
         self.started_services.remove(&instance.id);
     }
 
+    /// Starts background jobs of an active service unless they are already running.
+    fn start_jobs_if_needed(&self, instance_id: InstanceId) {
+        if self.jobs.has_jobs(instance_id) {
+            return;
+        }
+        let instance = match self.started_services.get(&instance_id) {
+            Some(instance) => instance,
+            None => return,
+        };
+
+        let mut jobs = JobsBuilder::new();
+        instance.service.background_jobs(&mut jobs);
+        self.jobs
+            .start(self.blockchain(), &instance.descriptor(), jobs);
+    }
+
     fn deploy(&mut self, artifact: &ArtifactId) -> Result<(), ExecutionError> {
         assert!(
             !self.deployed_artifacts.contains(artifact),
@@ -587,11 +642,14 @@ impl RustRuntime {
         });
 
         let service = factory.create_instance();
+        let mut invariants = Invariants::new();
+        service.invariants(&mut invariants);
         Instance {
             id: instance.id,
             name: instance.name.clone(),
             service,
             artifact_id: artifact.clone(),
+            invariants,
         }
     }
 
@@ -631,7 +689,87 @@ impl RustRuntime {
         Some(self.new_service(artifact, descriptor))
     }
 
+    /// Decrypts encrypted payloads of the transactions in the latest committed block
+    /// and passes them to the services.
+    fn handle_encrypted_payloads(
+        &self,
+        snapshot: &dyn Snapshot,
+        mailbox: &mut Mailbox,
+        validator_id: Option<ValidatorId>,
+    ) {
+        let core_schema = CoreSchema::new(snapshot);
+        let height = core_schema.height();
+        let transactions = core_schema.transactions();
+        let tx_hashes = core_schema.block_transactions(height);
+
+        for (position, tx_hash) in tx_hashes.iter().enumerate() {
+            let tx = transactions
+                .get(&tx_hash)
+                .expect("BUG: Cannot find committed transaction");
+            let call_info = &tx.payload().call_info;
+            let instance = match self.started_services.get(&call_info.instance_id) {
+                Some(instance) => instance,
+                None => continue,
+            };
+            if !instance
+                .as_ref()
+                .encrypted_methods()
+                .contains(&call_info.method_id)
+            {
+                continue;
+            }
+
+            let location = TxLocation::new(height, position as u32);
+            if !matches!(core_schema.transaction_result(location), Some(Ok(()))) {
+                continue;
+            }
+            let arguments = tx.payload().arguments.clone();
+            let decrypted = EncryptedPayload::from_bytes(arguments.into())
+                .ok()
+                .and_then(|payload| self.payload_keys.decrypt(&payload));
+            let plaintext = match decrypted {
+                Some(Ok(plaintext)) => plaintext,
+                Some(Err(err)) => {
+                    warn!(
+                        "Cannot decrypt payload of transaction {:?}: {}",
+                        tx_hash, err
+                    );
+                    continue;
+                }
+                None => continue,
+            };
+
+            let blockchain = self.blockchain();
+            let context = AfterCommitContext::new(
+                mailbox,
+                instance.descriptor(),
+                snapshot,
+                blockchain.service_keypair(),
+                blockchain.sender(),
+                validator_id,
+                self.delivery_log.as_ref(),
+            );
+            instance
+                .as_ref()
+                .handle_decrypted_payload(context, call_info.method_id, plaintext);
+        }
+    }
+
     fn api_endpoints(&self) -> Vec<(String, ApiBuilder)> {
+        let payload_descriptors = self
+            .started_services
+            .values()
+            .flat_map(|instance| {
+                let instance_id = instance.id;
+                instance
+                    .as_ref()
+                    .payload_descriptors()
+                    .into_iter()
+                    .map(move |(method_id, descriptor)| ((instance_id, method_id), descriptor))
+            })
+            .collect();
+        let payload_decoder = PayloadDecoder::new(payload_descriptors);
+
         self.started_services
             .values()
             .map(|instance| {
@@ -639,6 +777,7 @@ impl RustRuntime {
                     self.blockchain().clone(),
                     instance.descriptor(),
                     instance.artifact_id.clone(),
+                    payload_decoder.clone(),
                 );
                 instance.as_ref().wire_api(&mut builder);
                 let root_path = builder
@@ -682,6 +821,16 @@ impl WellKnownRuntime for RustRuntime {
 impl Runtime for RustRuntime {
     fn initialize(&mut self, blockchain: &Blockchain) {
         self.blockchain = Some(blockchain.clone());
+        // The delivery log may be unavailable if the database is opened in the read-only mode.
+        self.delivery_log = DeliveryLog::new(blockchain)
+            .map_err(|err| {
+                warn!(
+                    "Cannot initialize log of `after_commit` notifications: {}. \
+                     Acknowledging notifications is disabled",
+                    err
+                );
+            })
+            .ok();
     }
 
     fn is_supported(&self, feature: &RuntimeFeature) -> bool {
@@ -703,10 +852,27 @@ impl Runtime for RustRuntime {
         Receiver::with_result(result)
     }
 
+    fn check_deploy_spec(&self, _artifact: &ArtifactId, spec: &[u8]) -> Result<(), ExecutionError> {
+        if spec.is_empty() {
+            Ok(())
+        } else {
+            let msg = "Deploy specification of Rust artifacts must be empty";
+            Err(Error::IncorrectArtifactId.with_description(msg))
+        }
+    }
+
     fn is_artifact_deployed(&self, id: &ArtifactId) -> bool {
         self.deployed_artifacts.contains(id)
     }
 
+    fn interface_methods(&self, artifact: &ArtifactId) -> Option<InterfaceMethods> {
+        if !self.deployed_artifacts.contains(artifact) {
+            return None;
+        }
+        let factory = self.available_artifacts.get(artifact)?;
+        Some(factory.create_instance().interface_methods())
+    }
+
     // Unloading an artifact is effectively a no-op.
     fn unload_artifact(&mut self, artifact: &ArtifactId) {
         let was_present = self.deployed_artifacts.remove(artifact);
@@ -778,6 +944,16 @@ impl Runtime for RustRuntime {
             self.remove_started_service(&state.spec);
         }
 
+        // Background jobs run only while the service is active. Jobs of a re-instantiated
+        // service are restarted, since they may refer to the outdated service version.
+        let instance_id = state.spec.id;
+        if service_api_changed || !status.is_active() {
+            self.jobs.stop(instance_id);
+        }
+        if status.is_active() {
+            self.start_jobs_if_needed(instance_id);
+        }
+
         self.changed_services_since_last_block =
             self.changed_services_since_last_block || service_api_changed;
     }
@@ -837,6 +1013,18 @@ impl Runtime for RustRuntime {
         catch_panic(|| instance.as_ref().after_transactions(context))
     }
 
+    fn check_invariants(
+        &self,
+        instance: &InstanceDescriptor,
+        fork: ReadonlyFork<'_>,
+    ) -> Result<(), InvariantViolation> {
+        let instance = self
+            .started_services
+            .get(&instance.id)
+            .expect("`check_invariants` called with non-existing `instance_id`");
+        instance.invariants.check(&instance.descriptor(), fork)
+    }
+
     fn after_commit(&mut self, snapshot: &dyn Snapshot, mailbox: &mut Mailbox) {
         self.push_api_changes();
 
@@ -846,9 +1034,45 @@ impl Runtime for RustRuntime {
             return;
         }
 
+        // Unacknowledged notifications are delivered again once per instance
+        // after the runtime start, before the notification for the current block.
+        let mut redelivered_services = HashSet::new();
+        for &instance_id in self.started_services.keys() {
+            if self.redelivered_services.insert(instance_id) {
+                redelivered_services.insert(instance_id);
+            }
+        }
+
         let blockchain = self.blockchain();
+        let height = core_schema.height();
         let validator_id = core_schema.validator_id(blockchain.service_keypair().public_key());
+        let delivery_log = self.delivery_log.as_ref();
         for service in self.started_services.values() {
+            let acknowledged_height = delivery_log
+                .filter(|_| redelivered_services.contains(&service.id))
+                .and_then(|log| log.acknowledged_height(snapshot, &service.name));
+            if let Some(acknowledged_height) = acknowledged_height {
+                for missed_height in acknowledged_height.next().0..height.0 {
+                    trace!(
+                        "Delivering again `after_commit` notification for height {} to `{}`",
+                        missed_height,
+                        service.name
+                    );
+                    let context = AfterCommitContext::new(
+                        mailbox,
+                        service.descriptor(),
+                        snapshot,
+                        blockchain.service_keypair(),
+                        blockchain.sender(),
+                        validator_id,
+                        delivery_log,
+                    );
+                    service
+                        .as_ref()
+                        .after_commit(context.redelivered(Height(missed_height)));
+                }
+            }
+
             service.as_ref().after_commit(AfterCommitContext::new(
                 mailbox,
                 service.descriptor(),
@@ -856,7 +1080,12 @@ impl Runtime for RustRuntime {
                 blockchain.service_keypair(),
                 blockchain.sender(),
                 validator_id,
+                delivery_log,
             ));
         }
+
+        if !self.payload_keys.is_empty() {
+            self.handle_encrypted_payloads(snapshot, mailbox, validator_id);
+        }
     }
 }
@@ -15,6 +15,7 @@
 //! Rust runtime specific API endpoints.
 
 use exonum::{
+    merkledb::access::IndexDescription,
     proto::schema::{INCLUDES as EXONUM_INCLUDES, PROTO_SOURCES as EXONUM_PROTO_SOURCES},
     runtime::{versioning::Version, ArtifactId, RuntimeIdentifier},
 };
@@ -62,6 +63,23 @@ pub enum ProtoSourcesQuery {
     },
 }
 
+/// Schema description query parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SchemaQuery {
+    /// Name of the service instance.
+    pub instance: String,
+}
+
+impl SchemaQuery {
+    /// Creates a query for the specified service instance.
+    pub fn new(instance: impl Into<String>) -> Self {
+        Self {
+            instance: instance.into(),
+        }
+    }
+}
+
 /// Artifact Protobuf specification for the Exonum clients.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 #[non_exhaustive]
@@ -134,6 +152,20 @@ fn proto_sources(
     }
 }
 
+fn schema_description(
+    descriptions: &HashMap<String, Vec<IndexDescription>>,
+    query: SchemaQuery,
+) -> api::Result<Vec<IndexDescription>> {
+    descriptions.get(&query.instance).cloned().ok_or_else(|| {
+        api::Error::not_found()
+            .title("Schema description not found")
+            .detail(format!(
+                "Service `{}` is not active or does not describe its schema",
+                query.instance
+            ))
+    })
+}
+
 /// Returns API builder instance with the appropriate endpoints for the specified
 /// Rust runtime instance.
 pub fn endpoints(runtime: &RustRuntime) -> impl IntoIterator<Item = (String, ApiBuilder)> {
@@ -162,6 +194,23 @@ pub fn endpoints(runtime: &RustRuntime) -> impl IntoIterator<Item = (String, Api
         })
         .collect();
 
+    // Index names in service schema descriptions are qualified with the service name.
+    let schema_descriptions: HashMap<_, _> = runtime
+        .started_services
+        .values()
+        .filter_map(|instance| {
+            let mut descriptions = instance.service.schema_description()?;
+            for description in &mut descriptions {
+                description.name = if description.name.is_empty() {
+                    instance.name.clone()
+                } else {
+                    [&instance.name, ".", &description.name].concat()
+                };
+            }
+            Some((instance.name.clone(), descriptions))
+        })
+        .collect();
+
     let mut builder = ApiBuilder::new();
     builder
         .public_scope()
@@ -169,7 +218,17 @@ pub fn endpoints(runtime: &RustRuntime) -> impl IntoIterator<Item = (String, Api
         // otherwise it returns source files of Exonum itself.
         .endpoint("proto-sources", move |query| {
             future::ready(proto_sources(&exonum_sources, &filtered_sources, query))
+        })
+        // This endpoint returns the description of the data schema of the specified service.
+        .endpoint("schema", move |query| {
+            future::ready(schema_description(&schema_descriptions, query))
         });
 
+    let jobs = runtime.jobs.clone();
+    builder
+        .private_scope()
+        // This endpoint returns information about background jobs of the active services.
+        .endpoint("jobs", move |_query: ()| future::ready(Ok(jobs.info())));
+
     iter::once((["runtimes/", RustRuntime::NAME].concat(), builder))
 }
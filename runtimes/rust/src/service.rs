@@ -16,20 +16,28 @@ use exonum::{
     blockchain::{config::InstanceInitParams, ApiSender, SendError},
     crypto::{Hash, KeyPair, PublicKey},
     helpers::{Height, ValidatorId},
-    merkledb::{access::Prefixed, BinaryValue, ObjectHash, Snapshot},
+    merkledb::{
+        access::{IndexDescription, Prefixed},
+        BinaryValue, ObjectHash, Snapshot,
+    },
     runtime::{
         ArtifactId, BlockchainData, DispatcherAction, ExecutionContext, ExecutionError,
-        InstanceDescriptor, InstanceId, InstanceStatus, Mailbox, MethodId, SnapshotExt,
+        InstanceDescriptor, InstanceId, InstanceStatus, InterfaceMethods, Mailbox, MethodId,
+        SnapshotExt,
     },
 };
 use futures::{
     executor::block_on,
     future::{BoxFuture, FutureExt},
 };
+use protobuf::reflect::MessageDescriptor;
 
 use std::fmt::{self, Debug};
 
-use super::{api::ServiceApiBuilder, ArtifactProtobufSpec, GenericCall, MethodDescriptor};
+use super::{
+    api::ServiceApiBuilder, delivery::DeliveryLog, Acknowledgement, ArtifactProtobufSpec,
+    GenericCall, Invariants, JobsBuilder, MethodDescriptor,
+};
 
 /// Describes how the service instance should dispatch specific method calls
 /// with consideration of the interface where the method belongs.
@@ -44,6 +52,17 @@ pub trait ServiceDispatcher: Send {
         method: MethodId,
         payload: &[u8],
     ) -> Result<(), ExecutionError>;
+
+    /// Returns the names of methods in the interfaces implemented by the service.
+    /// The runtime uses them to reject migrations to artifacts reusing method IDs
+    /// for different methods.
+    ///
+    /// The implementation derived via the [`ServiceDispatcher`](index.html#examples) macro
+    /// collects methods of all implemented interfaces. The default implementation returns
+    /// no methods, which disables the check for the service artifact.
+    fn interface_methods(&self) -> InterfaceMethods {
+        InterfaceMethods::new()
+    }
 }
 
 /// Describes an Exonum service instance.
@@ -137,8 +156,51 @@ pub trait Service: ServiceDispatcher + Debug + 'static {
     ///
     /// Try not to perform long operations in this handler since it is executed
     /// on the consensus thread.
+    ///
+    /// Side effects of the handler are not persisted by the blockchain and may be lost
+    /// if the node crashes right after the commit. A service may acknowledge a notification
+    /// via [`AfterCommitContext::acknowledgement`] once its side effects are durable;
+    /// after the node restarts, the runtime delivers again notifications for all blocks
+    /// committed after the latest acknowledged one. Thus, handlers of services using
+    /// acknowledgements should be idempotent.
+    ///
+    /// [`AfterCommitContext::acknowledgement`]: struct.AfterCommitContext.html#method.acknowledgement
     fn after_commit(&self, _context: AfterCommitContext<'_>) {}
 
+    /// Returns identifiers of the service methods accepting an [`EncryptedPayload`]
+    /// as the argument.
+    ///
+    /// After a block is committed, the runtime decrypts payloads of successfully executed
+    /// transactions calling these methods, provided that the node possesses the corresponding
+    /// key, and passes decrypted payloads to [`handle_decrypted_payload`].
+    ///
+    /// The default implementation returns an empty slice, i.e., the service
+    /// does not accept encrypted payloads.
+    ///
+    /// [`EncryptedPayload`]: https://docs.rs/exonum/latest/exonum/runtime/encryption/struct.EncryptedPayload.html
+    /// [`handle_decrypted_payload`]: #method.handle_decrypted_payload
+    fn encrypted_methods(&self) -> &[MethodId] {
+        &[]
+    }
+
+    /// Handles the decrypted payload of a committed transaction calling one
+    /// of the [`encrypted_methods`].
+    ///
+    /// This handler is invoked only on nodes possessing the key used to encrypt the payload,
+    /// hence it must not be used to change the blockchain state directly. The service
+    /// may, however, broadcast transactions or store the plaintext outside the blockchain.
+    ///
+    /// The default implementation does nothing.
+    ///
+    /// [`encrypted_methods`]: #method.encrypted_methods
+    fn handle_decrypted_payload(
+        &self,
+        _context: AfterCommitContext<'_>,
+        _method_id: MethodId,
+        _plaintext: Vec<u8>,
+    ) {
+    }
+
     /// Attaches the request handlers of the service API to the Exonum API schema.
     ///
     /// The default implementation does nothing (i.e., does not provide any API for the service).
@@ -146,6 +208,62 @@ pub trait Service: ServiceDispatcher + Debug + 'static {
     /// The request handlers are mounted on the `/api/services/{instance_name}` path at the
     /// listen address of every full node in the blockchain network.
     fn wire_api(&self, _builder: &mut ServiceApiBuilder) {}
+
+    /// Registers node-local background jobs of the service, such as cache warmers
+    /// or pollers of external APIs.
+    ///
+    /// The default implementation does not register any jobs.
+    ///
+    /// Jobs are managed by the runtime: they are started once the service becomes active
+    /// on the node and are stopped when the service is stopped, frozen, migrated or resumed
+    /// with a new artifact. Jobs are not a part of the consensus; they should not
+    /// rely on being executed on all nodes and may affect the blockchain state only
+    /// by broadcasting transactions. Running jobs can be inspected via the `jobs` endpoint
+    /// of the private Rust runtime API.
+    ///
+    /// Services should use this method instead of spawning threads in the service constructor,
+    /// since such threads are not stopped together with the service.
+    fn background_jobs(&self, _jobs: &mut JobsBuilder) {}
+
+    /// Registers invariants of the service data, such as equality of lengths of two indexes
+    /// updated together.
+    ///
+    /// The default implementation does not register any invariants.
+    ///
+    /// Invariants are checked by the core after each call in the block (a transaction
+    /// or a `before_transactions` / `after_transactions` hook), including the genesis block.
+    /// If a call violates an invariant, its changes are rolled back, and
+    /// a `CoreError::InvariantViolated` error naming the service and the invariant
+    /// is recorded for the call. Since the check is performed by every validator,
+    /// the outcome is the same on the entire network. Invariants should only express
+    /// conditions which cannot be violated unless the service logic contains a bug.
+    fn invariants(&self, _invariants: &mut Invariants) {}
+
+    /// Returns a machine-readable description of the service data schema. Index names
+    /// in the description should be relative to the service namespace.
+    ///
+    /// If the service schema derives `FromAccess` with the `#[from_access(describe)]` attribute,
+    /// this method can be implemented as `Some(Schema::<&dyn Snapshot>::describe_schema(""))`.
+    ///
+    /// The description is returned by the `schema` endpoint of the Rust runtime API.
+    /// The default implementation returns `None`, meaning that the service does not describe
+    /// its schema.
+    fn schema_description(&self) -> Option<Vec<IndexDescription>> {
+        None
+    }
+
+    /// Returns descriptors of Protobuf messages used as arguments of the service methods.
+    ///
+    /// Descriptors allow to render transaction payloads as JSON without knowing the service
+    /// interface; for example, the explorer service uses them to show decoded transaction
+    /// bodies. Descriptors of Protobuf-generated types can be obtained
+    /// with `MessageFull::descriptor()`.
+    ///
+    /// The default implementation returns an empty list, meaning that payloads
+    /// of the service transactions cannot be decoded.
+    fn payload_descriptors(&self) -> Vec<(MethodId, MessageDescriptor)> {
+        Vec::new()
+    }
 }
 
 /// Describes a service instance factory for the specific Rust artifact.
@@ -197,6 +315,12 @@ pub struct AfterCommitContext<'a> {
     validator_id: Option<ValidatorId>,
     /// Current status of the service.
     status: InstanceStatus,
+    /// Log of acknowledged notifications.
+    delivery_log: Option<&'a DeliveryLog>,
+    /// Height of the block the notification corresponds to.
+    notification_height: Height,
+    /// Whether the notification is delivered again after the node restart.
+    is_redelivery: bool,
 }
 
 impl<'a> AfterCommitContext<'a> {
@@ -208,6 +332,7 @@ impl<'a> AfterCommitContext<'a> {
         service_keypair: &'a KeyPair,
         tx_sender: &'a ApiSender,
         validator_id: Option<ValidatorId>,
+        delivery_log: Option<&'a DeliveryLog>,
     ) -> Self {
         let status = snapshot
             .for_dispatcher()
@@ -217,15 +342,26 @@ impl<'a> AfterCommitContext<'a> {
             })
             .status
             .expect("BUG: status for a service receiving `after_commit` hook cannot be `None`");
+        let notification_height = snapshot.for_core().height();
         Self {
             mailbox,
             snapshot,
             validator_id,
             broadcaster: Broadcaster::new(instance, service_keypair.clone(), tx_sender.clone()),
             status,
+            delivery_log,
+            notification_height,
+            is_redelivery: false,
         }
     }
 
+    /// Marks the context as delivering again the notification for the specified height.
+    pub(crate) fn redelivered(mut self, height: Height) -> Self {
+        self.notification_height = height;
+        self.is_redelivery = true;
+        self
+    }
+
     /// Returns blockchain data for the snapshot associated with this context.
     pub fn data(&self) -> BlockchainData<&'a dyn Snapshot> {
         BlockchainData::new(self.snapshot, &self.broadcaster.instance().name)
@@ -242,6 +378,36 @@ impl<'a> AfterCommitContext<'a> {
         self.data().for_core().height()
     }
 
+    /// Returns the height of the block this notification corresponds to. The height coincides
+    /// with [`height`] unless the notification [is delivered again](#method.is_redelivery).
+    ///
+    /// [`height`]: #method.height
+    pub fn notification_height(&self) -> Height {
+        self.notification_height
+    }
+
+    /// Returns `true` if this notification was not acknowledged before the node restart
+    /// and is delivered again. Redelivered notifications are passed before the notification
+    /// for the latest committed block, in the order of increasing heights. Note that
+    /// the blockchain data in the context corresponds to the latest committed block
+    /// rather than to the [`notification_height`].
+    ///
+    /// [`notification_height`]: #method.notification_height
+    pub fn is_redelivery(&self) -> bool {
+        self.is_redelivery
+    }
+
+    /// Returns a handle allowing to acknowledge this notification, e.g., once the side effects
+    /// triggered by it are durable. The handle may be moved to another thread and used after
+    /// the handler has returned.
+    pub fn acknowledgement(&self) -> Acknowledgement {
+        Acknowledgement::new(
+            self.delivery_log.cloned(),
+            &self.broadcaster.instance().name,
+            self.notification_height,
+        )
+    }
+
     /// Returns the service key of this node.
     pub fn service_key(&self) -> PublicKey {
         self.broadcaster.service_keypair.public_key()
@@ -440,12 +606,15 @@ pub struct SupervisorExtensions<'a> {
 
 impl SupervisorExtensions<'_> {
     /// Starts the deployment of an artifact. The provided callback is executed after
-    /// the deployment is completed.
+    /// the deployment is completed; on success, it receives the checksum of the deployed
+    /// artifact if the runtime provides one.
     pub fn start_deploy(
         &mut self,
         artifact: ArtifactId,
         spec: impl BinaryValue,
-        then: impl FnOnce(Result<(), ExecutionError>) -> Result<(), ExecutionError> + Send + 'static,
+        then: impl FnOnce(Result<Option<Hash>, ExecutionError>) -> Result<(), ExecutionError>
+            + Send
+            + 'static,
     ) {
         let action = DispatcherAction::StartDeploy {
             artifact,
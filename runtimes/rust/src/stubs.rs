@@ -22,7 +22,7 @@ use exonum::{
     messages::Verified,
     runtime::{
         AnyTx, CallInfo, ExecutionContext, ExecutionContextUnstable, ExecutionError, InstanceId,
-        InstanceQuery, MethodId,
+        InstanceQuery, InterfaceMethods, MethodId,
     },
 };
 
@@ -57,6 +57,20 @@ impl<'a> MethodDescriptor<'a> {
 pub trait Interface<'a> {
     /// Fully qualified name of this interface.
     const INTERFACE_NAME: &'static str;
+    /// IDs and names of the interface methods.
+    const METHODS: &'static [(MethodId, &'static str)] = &[];
+    /// IDs of the methods removed from the interface.
+    const REMOVED_METHOD_IDS: &'static [MethodId] = &[];
+
+    /// Adds methods of this interface to `methods`.
+    fn describe_methods(methods: &mut InterfaceMethods) {
+        for &(id, name) in Self::METHODS {
+            methods.add_method(Self::INTERFACE_NAME, id, name);
+        }
+        for &id in Self::REMOVED_METHOD_IDS {
+            methods.add_removed_id(Self::INTERFACE_NAME, id);
+        }
+    }
 
     /// Invokes the specified method handler of the service instance.
     fn dispatch(
@@ -28,7 +28,15 @@ use exonum_derive::{exonum_interface, BinaryValue, ServiceDispatcher, ServiceFac
 use pretty_assertions::assert_eq;
 use serde::{Deserialize, Serialize};
 
-use exonum_rust_runtime::{DefaultInstance, RustRuntimeBuilder, Service, ServiceFactory};
+use exonum_rust_runtime::{
+    DefaultInstance, Invariants, JobsBuilder, RustRuntimeBuilder, Service, ServiceFactory,
+};
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::Duration,
+};
 
 use self::inspected::{
     create_block_with_transactions, create_genesis_config_builder, execute_transaction,
@@ -200,6 +208,72 @@ impl DefaultInstance for DependentServiceImpl {
     }
 }
 
+/// Number of invocations of the background job of `JobServiceImpl`.
+static JOB_RUNS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, ServiceDispatcher, ServiceFactory)]
+#[service_dispatcher(implements())]
+#[service_factory(artifact_name = "job_service", artifact_version = "0.1.0")]
+pub struct JobServiceImpl;
+
+impl Service for JobServiceImpl {
+    fn background_jobs(&self, jobs: &mut JobsBuilder) {
+        jobs.periodic("counter", Duration::from_millis(10), |context| {
+            assert_eq!(context.instance().id, Self::INSTANCE_ID);
+            JOB_RUNS.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        });
+    }
+}
+
+impl DefaultInstance for JobServiceImpl {
+    const INSTANCE_ID: u32 = TestServiceImpl::INSTANCE_ID + 2;
+    const INSTANCE_NAME: &'static str = "job-service";
+}
+
+/// Service maintaining two lists of the same length. `method_b` is buggy and updates
+/// only one of the lists.
+#[derive(Debug, ServiceFactory, ServiceDispatcher)]
+#[service_dispatcher(implements("Test"))]
+#[service_factory(artifact_name = "invariant_service", artifact_version = "0.1.0")]
+pub struct InvariantServiceImpl;
+
+impl Test<ExecutionContext<'_>> for InvariantServiceImpl {
+    type Output = Result<(), ExecutionError>;
+
+    fn method_a(&self, context: ExecutionContext<'_>, arg: u64) -> Self::Output {
+        let data = context.service_data();
+        data.clone().get_proof_list("values").push(arg);
+        data.get_proof_list("copies").push(arg);
+        Ok(())
+    }
+
+    fn method_b(&self, context: ExecutionContext<'_>, arg: u64) -> Self::Output {
+        context.service_data().get_proof_list("values").push(arg);
+        Ok(())
+    }
+}
+
+impl Service for InvariantServiceImpl {
+    fn invariants(&self, invariants: &mut Invariants) {
+        invariants.add("equal_lengths", |data| {
+            let data = data.for_executing_service();
+            let values_len = data.clone().get_proof_list::<_, u64>("values").len();
+            let copies_len = data.get_proof_list::<_, u64>("copies").len();
+            if values_len == copies_len {
+                Ok(())
+            } else {
+                Err(format!("{} values, but {} copies", values_len, copies_len))
+            }
+        });
+    }
+}
+
+impl DefaultInstance for InvariantServiceImpl {
+    const INSTANCE_ID: u32 = TestServiceImpl::INSTANCE_ID + 3;
+    const INSTANCE_NAME: &'static str = "invariant-service";
+}
+
 fn create_genesis_config_with_supervisor() -> GenesisConfig {
     create_genesis_config_builder()
         .with_artifact(ToySupervisorService.artifact_id())
@@ -217,6 +291,8 @@ fn create_runtime(
             .with_factory(TestServiceImplV2)
             .with_factory(ToySupervisorService)
             .with_factory(DependentServiceImpl)
+            .with_factory(JobServiceImpl)
+            .with_factory(InvariantServiceImpl)
             .build_for_tests(),
     );
     let events_handle = inspected.events.clone();
@@ -1033,6 +1109,94 @@ fn service_freezing() {
     );
 }
 
+#[test]
+fn background_jobs_follow_service_status() {
+    const JOB_INTERVAL: Duration = Duration::from_millis(100);
+
+    let (mut blockchain, _) = create_runtime(
+        Blockchain::build_for_tests(),
+        create_genesis_config_builder()
+            .with_artifact(ToySupervisorService.artifact_id())
+            .with_instance(ToySupervisorService.default_instance())
+            .with_artifact(JobServiceImpl.artifact_id())
+            .with_instance(JobServiceImpl.default_instance())
+            .build(),
+    );
+    let keypair = blockchain.as_ref().service_keypair().clone();
+
+    // The job should be started for the active service.
+    thread::sleep(JOB_INTERVAL);
+    assert!(JOB_RUNS.load(Ordering::SeqCst) > 0);
+
+    execute_transaction(
+        &mut blockchain,
+        keypair.freeze_service(
+            ToySupervisorService::INSTANCE_ID,
+            JobServiceImpl::INSTANCE_ID,
+        ),
+    )
+    .unwrap();
+
+    // The job should not be invoked once the service is frozen.
+    thread::sleep(JOB_INTERVAL);
+    let runs = JOB_RUNS.load(Ordering::SeqCst);
+    thread::sleep(JOB_INTERVAL);
+    assert_eq!(JOB_RUNS.load(Ordering::SeqCst), runs);
+
+    execute_transaction(
+        &mut blockchain,
+        keypair.resume_service(
+            ToySupervisorService::INSTANCE_ID,
+            ResumeService {
+                instance_id: JobServiceImpl::INSTANCE_ID,
+                params: vec![],
+            },
+        ),
+    )
+    .unwrap();
+
+    // The job should be restarted after the service is resumed.
+    thread::sleep(JOB_INTERVAL);
+    assert!(JOB_RUNS.load(Ordering::SeqCst) > runs);
+}
+
+#[test]
+fn invariants_are_checked_after_transactions() {
+    let (mut blockchain, _) = create_runtime(
+        Blockchain::build_for_tests(),
+        create_genesis_config_builder()
+            .with_artifact(InvariantServiceImpl.artifact_id())
+            .with_instance(InvariantServiceImpl.default_instance())
+            .build(),
+    );
+    let keypair = blockchain.as_ref().service_keypair().clone();
+
+    execute_transaction(
+        &mut blockchain,
+        keypair.method_a(InvariantServiceImpl::INSTANCE_ID, 1),
+    )
+    .unwrap();
+
+    // The changes of the buggy transaction are rolled back.
+    let tx = keypair.method_b(InvariantServiceImpl::INSTANCE_ID, 2);
+    let err = execute_transaction(&mut blockchain, tx).unwrap_err();
+    assert_eq!(
+        err,
+        ErrorMatch::from_fail(&CoreError::InvariantViolated).with_description_containing(
+            "Service 5:invariant-service violates its invariant. \
+             Invariant `equal_lengths` is violated: 2 values, but 1 copies"
+        )
+    );
+
+    let snapshot = blockchain.snapshot();
+    assert_eq!(snapshot.for_core().height(), Height(2));
+    let data = snapshot
+        .for_service(InvariantServiceImpl::INSTANCE_NAME)
+        .unwrap();
+    assert_eq!(data.clone().get_proof_list::<_, u64>("values").len(), 1);
+    assert_eq!(data.get_proof_list::<_, u64>("copies").len(), 1);
+}
+
 #[test]
 fn unloading_artifact() {
     let genesis_config = create_genesis_config_with_supervisor();
@@ -21,15 +21,15 @@ use exonum::{
     },
     crypto::Hash,
     helpers::{Height, ValidatorId},
-    merkledb::{ObjectHash, Snapshot},
+    merkledb::{ObjectHash, ReadonlyFork, Snapshot},
     messages::{AnyTx, Verified},
     runtime::{
         migrations::{InitMigrationError, MigrationScript},
         oneshot,
         versioning::Version,
-        ArtifactId, ExecutionContext, ExecutionError, InstanceId, InstanceSpec, InstanceState,
-        InstanceStatus, Mailbox, MethodId, Runtime, RuntimeFeature, SnapshotExt, WellKnownRuntime,
-        SUPERVISOR_INSTANCE_ID,
+        ArtifactId, Capabilities, ExecutionContext, ExecutionError, InstanceDescriptor, InstanceId,
+        InstanceSpec, InstanceState, InstanceStatus, InterfaceMethods, InvariantViolation, Mailbox,
+        MethodId, Runtime, RuntimeFeature, SnapshotExt, WellKnownRuntime, SUPERVISOR_INSTANCE_ID,
     },
 };
 use exonum_api::UpdateEndpoints;
@@ -167,6 +167,10 @@ impl<T: Runtime> Runtime for Inspected<T> {
         self.runtime.is_supported(feature)
     }
 
+    fn default_capabilities(&self) -> Capabilities {
+        self.runtime.default_capabilities()
+    }
+
     fn on_resume(&mut self) {
         self.events.push(RuntimeEvent::ResumeRuntime);
         self.runtime.on_resume()
@@ -189,6 +193,14 @@ impl<T: Runtime> Runtime for Inspected<T> {
         self.runtime.is_artifact_deployed(id)
     }
 
+    fn check_deploy_spec(&self, artifact: &ArtifactId, spec: &[u8]) -> Result<(), ExecutionError> {
+        self.runtime.check_deploy_spec(artifact, spec)
+    }
+
+    fn interface_methods(&self, artifact: &ArtifactId) -> Option<InterfaceMethods> {
+        self.runtime.interface_methods(artifact)
+    }
+
     fn unload_artifact(&mut self, artifact: &ArtifactId) {
         self.events
             .push(RuntimeEvent::UnloadArtifact(artifact.to_owned()));
@@ -284,6 +296,14 @@ impl<T: Runtime> Runtime for Inspected<T> {
         self.runtime.after_transactions(context)
     }
 
+    fn check_invariants(
+        &self,
+        instance: &InstanceDescriptor,
+        fork: ReadonlyFork<'_>,
+    ) -> Result<(), InvariantViolation> {
+        self.runtime.check_invariants(instance, fork)
+    }
+
     fn after_commit(&mut self, snapshot: &dyn Snapshot, mailbox: &mut Mailbox) {
         let height = CoreSchema::new(snapshot).next_height();
         self.events.push(RuntimeEvent::AfterCommit(height));
@@ -176,6 +176,32 @@ impl MigrateData for WithdrawalServiceV2 {
     }
 }
 
+#[exonum_interface(auto_ids)]
+trait Deposit<Ctx> {
+    type Output;
+
+    fn deposit(&self, context: Ctx, arg: u64) -> Self::Output;
+}
+
+/// This implementation reuses the method ID of `withdraw` for an unrelated method,
+/// thus migrating to it is prohibited.
+#[derive(Debug, ServiceFactory, ServiceDispatcher)]
+#[service_dispatcher(implements("Deposit"))]
+#[service_factory(artifact_name = "withdrawal", artifact_version = "0.3.0")]
+struct DepositService;
+
+impl Deposit<ExecutionContext<'_>> for DepositService {
+    type Output = Result<(), ExecutionError>;
+
+    fn deposit(&self, context: ExecutionContext<'_>, arg: u64) -> Self::Output {
+        let mut schema = Schema::new(context.service_data());
+        schema.balance.set(schema.balance.get().unwrap() + arg);
+        Ok(())
+    }
+}
+
+impl Service for DepositService {}
+
 fn create_runtime() -> (BlockchainMut, EventsHandle, mpsc::Receiver<UpdateEndpoints>) {
     let blockchain = Blockchain::build_for_tests();
     let genesis_config = create_genesis_config_builder()
@@ -183,6 +209,7 @@ fn create_runtime() -> (BlockchainMut, EventsHandle, mpsc::Receiver<UpdateEndpoi
         .with_instance(ToySupervisorService.default_instance())
         .with_artifact(WithdrawalServiceV1.artifact_id())
         .with_artifact(WithdrawalServiceV2.artifact_id())
+        .with_artifact(DepositService.artifact_id())
         .with_instance(WithdrawalServiceV1.default_instance())
         .build();
 
@@ -191,6 +218,7 @@ fn create_runtime() -> (BlockchainMut, EventsHandle, mpsc::Receiver<UpdateEndpoi
         RustRuntimeBuilder::new()
             .with_factory(WithdrawalServiceV1)
             .with_migrating_factory(WithdrawalServiceV2)
+            .with_factory(DepositService)
             .with_factory(ToySupervisorService)
             .build(endpoints_tx),
     );
@@ -612,6 +640,49 @@ fn test_resume_service_error() {
     );
 }
 
+#[test]
+fn migration_with_reused_method_ids_error() {
+    let (mut blockchain, ..) = create_runtime();
+    let keypair = blockchain.as_ref().service_keypair().clone();
+
+    execute_transaction(
+        &mut blockchain,
+        keypair.stop_service(
+            ToySupervisorService::INSTANCE_ID,
+            WithdrawalServiceV1::INSTANCE_ID,
+        ),
+    )
+    .unwrap();
+
+    let actual_err = execute_transaction(
+        &mut blockchain,
+        keypair.migrate_service(
+            ToySupervisorService::INSTANCE_ID,
+            MigrateService {
+                instance_name: WithdrawalServiceV1::INSTANCE_NAME.to_owned(),
+                artifact: DepositService.artifact_id(),
+            },
+        ),
+    )
+    .unwrap_err();
+    assert_eq!(
+        actual_err,
+        ErrorMatch::from_fail(&CoreError::IncompatibleMethodIds)
+            .with_description_containing("reused for method `deposit` (previously `withdraw`)")
+    );
+
+    // The service should retain its original artifact.
+    let instance_state = blockchain
+        .snapshot()
+        .for_dispatcher()
+        .get_instance(WithdrawalServiceV1::INSTANCE_ID)
+        .unwrap();
+    assert_eq!(
+        instance_state.spec.artifact,
+        WithdrawalServiceV1.artifact_id()
+    );
+}
+
 #[test]
 fn resume_non_existent_service_error() {
     let (mut blockchain, ..) = create_runtime();
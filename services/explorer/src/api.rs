@@ -18,8 +18,11 @@
 //! # Table of Contents
 //!
 //! - [List blocks](#list-blocks)
+//! - [List blocks with pagination](#list-blocks-with-pagination)
 //! - [Get specific block](#get-specific-block)
+//! - [Get index changes in block](#get-index-changes-in-block)
 //! - [Get transaction by hash](#transaction-by-hash)
+//! - [Get decoded transaction payload](#decoded-transaction-payload)
 //! - Call status:
 //!
 //!     - [for transactions](#call-status-for-transaction)
@@ -103,6 +106,57 @@
 //! # }
 //! ```
 //!
+//! # List Blocks with Pagination
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/explorer/v2/blocks` |
+//! | Method      | GET   |
+//! | Query type  | [`BlocksPageQuery`] |
+//! | Return type | [`Page`]`<serde_json::Value, Height>` |
+//!
+//! Returns a page of blocks following the [common conventions] for list endpoints.
+//! The cursor is the height of the first returned block. Blocks are sorted by height
+//! in the descending order by default; the order can be changed with `sort=height`.
+//! The `fields` parameter selects fields of the returned blocks. The `precommits` and `time`
+//! fields are only returned if they are explicitly selected.
+//!
+//! [`BlocksPageQuery`]: struct.BlocksPageQuery.html
+//! [`Page`]: https://docs.rs/exonum-api/latest/exonum_api/query/struct.Page.html
+//! [common conventions]: https://docs.rs/exonum-api/latest/exonum_api/query/index.html
+//!
+//! ```
+//! # use exonum::helpers::Height;
+//! # use exonum_api::query::Page;
+//! # use exonum_explorer_service::ExplorerFactory;
+//! # use exonum_testkit::{Spec, TestKitBuilder};
+//! # use serde_json::json;
+//! #
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let mut testkit = TestKitBuilder::validator()
+//!     .with(Spec::new(ExplorerFactory).with_default_instance())
+//!     .build();
+//! testkit.create_blocks_until(Height(5));
+//!
+//! let api = testkit.api();
+//! let url = api.public_url("api/explorer/v2/blocks?limit=2&fields=height,tx_count");
+//! let page: Page<serde_json::Value, Height> = reqwest::get(&url).await?
+//!     .error_for_status()?
+//!     .json().await?;
+//! assert_eq!(
+//!     page.items,
+//!     vec![
+//!         json!({ "height": 5, "tx_count": 0 }),
+//!         json!({ "height": 4, "tx_count": 0 }),
+//!     ]
+//! );
+//! // The cursor can be used to request the next page.
+//! assert_eq!(page.next_cursor, Some(Height(3)));
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! # Get Specific Block
 //!
 //! | Property    | Value |
@@ -142,6 +196,47 @@
 //! # }
 //! ```
 //!
+//! # Get Index Changes in Block
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/explorer/v1/index_changes` |
+//! | Method      | GET   |
+//! | Query type  | [`BlockQuery`] |
+//! | Return type | [`BlockIndexChanges`] |
+//!
+//! Returns summaries of changes made to service indexes by the block at a specific `height`.
+//! Each summary contains the address of the changed index and the number of keys put into
+//! and removed from it. External indexers may use this endpoint (or the corresponding
+//! [WebSocket subscription](websocket/index.html)) to fetch only the data affected by a block
+//! instead of re-reading entire indexes.
+//!
+//! [`BlockIndexChanges`]: struct.BlockIndexChanges.html
+//!
+//! ```
+//! # use exonum::helpers::Height;
+//! # use exonum_explorer_service::{api::BlockIndexChanges, ExplorerFactory};
+//! # use exonum_testkit::{Spec, TestKitBuilder};
+//! #
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! # let mut testkit = TestKitBuilder::validator()
+//! #    .with(Spec::new(ExplorerFactory).with_default_instance())
+//! #    .build();
+//! testkit.create_blocks_until(Height(2));
+//!
+//! let api = testkit.api();
+//! let url = api.public_url("api/explorer/v1/index_changes?height=2");
+//! let response: BlockIndexChanges = reqwest::get(&url).await?
+//!     .error_for_status()?
+//!     .json().await?;
+//! assert_eq!(response.height, Height(2));
+//! // The explorer service does not store any data, so there are no changes.
+//! assert!(response.changes.is_empty());
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! # Transaction by Hash
 //!
 //! | Property    | Value |
@@ -152,6 +247,9 @@
 //! | Return type | [`TransactionInfo`] |
 //!
 //! Searches for a transaction, either committed or uncommitted, by the hash.
+//! If the transaction is committed in a block which historical data is pruned by the node,
+//! or call errors in the block were rolled into the call errors checkpoint,
+//! the endpoint returns the "410 Gone" error.
 //!
 //! **Important.** See [*Transaction Processing*] section for details about how transactions
 //! are processed and which invariants are (not) held during processing.
@@ -212,6 +310,24 @@
 //! # }
 //! ```
 //!
+//! # Decoded Transaction Payload
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/explorer/v1/transactions/payload` |
+//! | Method      | GET   |
+//! | Query type  | [`TransactionQuery`] |
+//! | Return type | [`DecodedMessage`] |
+//!
+//! Decodes arguments of a transaction, either committed or uncommitted, and returns
+//! them as canonical JSON together with a human-readable dump in the Protobuf text format.
+//! Decoding is possible only if the service called by the transaction provides
+//! a Protobuf descriptor for the called method via [`Service::payload_descriptors`];
+//! otherwise, the endpoint returns the "not found" error.
+//!
+//! [`DecodedMessage`]: https://docs.rs/exonum-proto/latest/exonum_proto/json/struct.DecodedMessage.html
+//! [`Service::payload_descriptors`]: https://docs.rs/exonum-rust-runtime/latest/exonum_rust_runtime/trait.Service.html#method.payload_descriptors
+//!
 //! # Call Status for Transaction
 //!
 //! | Property    | Value |
@@ -221,7 +337,9 @@
 //! | Query type  | [`TransactionStatusQuery`] |
 //! | Return type | [`CallStatusResponse`] |
 //!
-//! Returns call status of committed transaction.
+//! Returns call status of committed transaction. If call errors in the block containing
+//! the transaction were rolled into the call errors checkpoint, the endpoint returns
+//! the "410 Gone" error.
 //!
 //! [`TransactionStatusQuery`]: struct.TransactionStatusQuery.html
 //! [`CallStatusResponse`]: enum.CallStatusResponse.html
@@ -292,7 +410,8 @@
 //!
 //! Returns call status of a `before_transactions` hook for a specific service at a specific height.
 //! Note that the endpoint returns the normal execution status `Ok(())` if the queried service
-//! was not active at the specified height.
+//! was not active at the specified height. If call errors at the specified height were rolled
+//! into the call errors checkpoint, the endpoint returns the "410 Gone" error.
 //!
 //! [`CallStatusQuery`]: struct.CallStatusQuery.html
 //!
@@ -426,30 +545,75 @@ pub use exonum_explorer::{
         CommittedTransactionSummary, Notification, SubscriptionType, TransactionFilter,
     },
     api::{
-        BlockInfo, BlockQuery, BlocksQuery, BlocksRange, CallStatusQuery, CallStatusResponse,
-        TransactionHex, TransactionQuery, TransactionResponse, TransactionStatusQuery,
-        MAX_BLOCKS_PER_REQUEST,
+        BlockIndexChanges, BlockInfo, BlockQuery, BlocksQuery, BlocksRange, CallStatusQuery,
+        CallStatusResponse, TransactionHex, TransactionQuery, TransactionResponse,
+        TransactionStatusQuery, MAX_BLOCKS_PER_REQUEST,
     },
     TransactionInfo,
 };
+pub use exonum_proto::json::DecodedMessage;
 
 use exonum::{
-    blockchain::{ApiSender, Blockchain, CallInBlock, Schema, TxCheckCache},
+    blockchain::{Blockchain, CallInBlock, Schema, TxCheckCache},
     helpers::Height,
     merkledb::{ObjectHash, Snapshot},
     messages::SignedMessage,
     runtime::{ExecutionStatus, SnapshotExt},
 };
+use exonum_api::query::{FieldSet, Page, PageQuery, SortOrder, SortSpec};
 use exonum_explorer::BlockchainExplorer;
-use exonum_rust_runtime::api::{self, ServiceApiScope};
+use exonum_rust_runtime::api::{self, PayloadDecoder, ServiceApiScope};
 use futures::{future, Future, FutureExt, TryFutureExt};
 use hex::FromHex;
-use serde_json::json;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{json, Value};
 
 use std::{cell::RefCell, ops::Bound};
 
 pub mod websocket;
 
+/// Default number of blocks on a page returned by the `v2/blocks` endpoint.
+const DEFAULT_BLOCKS_PER_PAGE: usize = 100;
+
+/// Fields of blocks which can be selected in the `v2/blocks` endpoint.
+const BLOCK_FIELDS: &[&str] = &[
+    "height",
+    "tx_count",
+    "prev_hash",
+    "tx_hash",
+    "state_hash",
+    "error_hash",
+    "additional_headers",
+    "precommits",
+    "time",
+];
+
+/// Query parameters of the paginated blocks endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BlocksPageQuery {
+    /// Pagination parameters. The cursor is the height of the first returned block.
+    /// The number of blocks on a page should not be greater than `MAX_BLOCKS_PER_REQUEST`.
+    #[serde(flatten)]
+    pub page: PageQuery<Height>,
+    /// Sort order of the blocks. Only sorting by `height` is supported; the default value
+    /// is `-height` (i.e., the latest blocks go first).
+    pub sort: Option<SortSpec>,
+    /// If true, then only non-empty blocks are returned. The default value is false.
+    #[serde(default)]
+    pub skip_empty_blocks: bool,
+    /// Fields of the returned blocks. By default, all block header fields are returned.
+    #[serde(default)]
+    pub fields: FieldSet,
+}
+
+impl BlocksPageQuery {
+    /// Creates a query for the first page of blocks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
 #[derive(Debug)]
 struct ClearableCheckCache {
     inner: TxCheckCache,
@@ -479,12 +643,16 @@ impl ClearableCheckCache {
 #[derive(Debug, Clone)]
 pub(crate) struct ExplorerApi {
     blockchain: Blockchain,
+    payload_decoder: PayloadDecoder,
 }
 
 impl ExplorerApi {
     /// Creates a new `ExplorerApi` instance.
-    pub const fn new(blockchain: Blockchain) -> Self {
-        Self { blockchain }
+    pub const fn new(blockchain: Blockchain, payload_decoder: PayloadDecoder) -> Self {
+        Self {
+            blockchain,
+            payload_decoder,
+        }
     }
 
     fn blocks(schema: Schema<&dyn Snapshot>, query: &BlocksQuery) -> api::Result<BlocksRange> {
@@ -532,6 +700,44 @@ impl ExplorerApi {
         Ok(BlocksRange::new(height..upper.next(), blocks))
     }
 
+    fn blocks_page(
+        schema: Schema<&dyn Snapshot>,
+        query: &BlocksPageQuery,
+    ) -> api::Result<Page<Value, Height>> {
+        let explorer = BlockchainExplorer::from_schema(schema);
+        let limit = query
+            .page
+            .limit_or(DEFAULT_BLOCKS_PER_PAGE, MAX_BLOCKS_PER_REQUEST)?;
+        let order = if let Some(sort) = &query.sort {
+            sort.check_field(&["height"])?;
+            sort.order
+        } else {
+            SortOrder::Desc
+        };
+        query.fields.check_fields(BLOCK_FIELDS)?;
+
+        // Precommits and median precommit time are expensive to compute,
+        // so they are only returned on explicit request.
+        let is_selected = |field| !query.fields.is_all() && query.fields.contains(field);
+        let mut summary_query = BlocksQuery::default();
+        summary_query.add_precommits = is_selected("precommits");
+        summary_query.add_blocks_time = is_selected("time");
+
+        let blocks: Box<dyn Iterator<Item = _> + '_> = if order == SortOrder::Asc {
+            let start = query.page.cursor.unwrap_or(Height(0));
+            Box::new(explorer.blocks(start..))
+        } else {
+            let start = query.page.cursor.unwrap_or_else(|| explorer.height());
+            Box::new(explorer.blocks(..=start).rev())
+        };
+        let blocks = blocks.filter(|block| !query.skip_empty_blocks || !block.is_empty());
+
+        Page::take(blocks, limit, |block| block.height()).try_map(|block| {
+            let info = BlockInfo::summary(block, &summary_query);
+            query.fields.select(&info)
+        })
+    }
+
     fn block(schema: Schema<&dyn Snapshot>, query: &BlockQuery) -> api::Result<BlockInfo> {
         let explorer = BlockchainExplorer::from_schema(schema);
         explorer.block(query.height).map(From::from).ok_or_else(|| {
@@ -545,12 +751,33 @@ impl ExplorerApi {
         })
     }
 
+    fn index_changes(
+        schema: &Schema<&dyn Snapshot>,
+        query: &BlockQuery,
+    ) -> api::Result<BlockIndexChanges> {
+        if query.height > schema.height() {
+            return Err(api::Error::not_found()
+                .title("Failed to get index changes")
+                .detail(format!(
+                    "Requested block height ({}) exceeds the blockchain height ({})",
+                    query.height,
+                    schema.height()
+                )));
+        }
+        Ok(BlockIndexChanges::new(schema, query.height))
+    }
+
     fn transaction_info(
         schema: Schema<&dyn Snapshot>,
         query: &TransactionQuery,
     ) -> api::Result<TransactionInfo> {
         BlockchainExplorer::from_schema(schema)
             .transaction(&query.hash)
+            .map_err(|err| {
+                api::Error::new(api::HttpStatusCode::GONE)
+                    .title("Failed to get transaction info")
+                    .detail(err.to_string())
+            })?
             .ok_or_else(|| {
                 let description = serde_json::to_string(&json!({ "type": "unknown" })).unwrap();
                 api::Error::not_found()
@@ -559,12 +786,46 @@ impl ExplorerApi {
             })
     }
 
+    fn transaction_payload(
+        schema: &Schema<&dyn Snapshot>,
+        payload_decoder: &PayloadDecoder,
+        query: &TransactionQuery,
+    ) -> api::Result<DecodedMessage> {
+        let tx = schema.transactions().get(&query.hash).ok_or_else(|| {
+            api::Error::not_found()
+                .title("Failed to get transaction payload")
+                .detail(format!("Unknown transaction hash ({})", query.hash))
+        })?;
+
+        let call_info = &tx.payload().call_info;
+        let decoded = payload_decoder.decode(tx.payload()).ok_or_else(|| {
+            api::Error::not_found()
+                .title("Payload descriptor not found")
+                .detail(format!(
+                    "Service with ID {} does not provide a descriptor for method {}",
+                    call_info.instance_id, call_info.method_id
+                ))
+        })?;
+        decoded.map_err(|err| {
+            api::Error::bad_request()
+                .title("Cannot decode transaction payload")
+                .detail(err.to_string())
+        })
+    }
+
     fn get_status(
         schema: &Schema<&dyn Snapshot>,
         block_height: Height,
         call_in_block: CallInBlock,
         with_proof: bool,
     ) -> api::Result<CallStatusResponse> {
+        schema
+            .check_call_errors_retained(block_height)
+            .map_err(|err| {
+                api::Error::new(api::HttpStatusCode::GONE)
+                    .title("Call status is not retained")
+                    .detail(err.to_string())
+            })?;
         let records = schema.call_records(block_height).ok_or_else(|| {
             api::Error::not_found()
                 .title("Block not found")
@@ -621,7 +882,7 @@ impl ExplorerApi {
 
     fn add_transaction(
         snapshot: &dyn Snapshot,
-        sender: &ApiSender,
+        blockchain: &Blockchain,
         query: TransactionHex,
     ) -> impl Future<Output = api::Result<TransactionResponse>> {
         thread_local! {
@@ -652,7 +913,19 @@ impl ExplorerApi {
             }
         };
 
-        sender
+        // The trace ID is recorded before broadcasting, so that it is available
+        // once the transaction gets to the pool.
+        if let Some(trace_id) = query.trace_id {
+            if let Err(err) = blockchain.record_trace_id(tx_hash, &trace_id) {
+                let err = api::Error::bad_request()
+                    .title("Failed to record trace ID")
+                    .detail(err.to_string());
+                return future::err(err).left_future();
+            }
+        }
+
+        blockchain
+            .sender()
             .broadcast_transaction(verified)
             .map_ok(move |_| TransactionResponse::new(tx_hash))
             .map_err(|err| api::Error::internal(err).title("Failed to add transaction"))
@@ -665,9 +938,15 @@ impl ExplorerApi {
             .endpoint("v1/blocks", |state, query| {
                 future::ready(Self::blocks(state.data().for_core(), &query))
             })
+            .endpoint("v2/blocks", |state, query| {
+                future::ready(Self::blocks_page(state.data().for_core(), &query))
+            })
             .endpoint("v1/block", |state, query| {
                 future::ready(Self::block(state.data().for_core(), &query))
             })
+            .endpoint("v1/index_changes", |state, query| {
+                future::ready(Self::index_changes(&state.data().for_core(), &query))
+            })
             .endpoint("v1/call_status/transaction", |state, query| {
                 future::ready(Self::transaction_status(&state.data().for_core(), &query))
             })
@@ -687,9 +966,18 @@ impl ExplorerApi {
                 future::ready(Self::transaction_info(state.data().for_core(), &query))
             });
 
-        let tx_sender = self.blockchain.sender().clone();
+        let payload_decoder = self.payload_decoder.clone();
+        api_scope.endpoint("v1/transactions/payload", move |state, query| {
+            future::ready(Self::transaction_payload(
+                &state.data().for_core(),
+                &payload_decoder,
+                &query,
+            ))
+        });
+
+        let blockchain = self.blockchain.clone();
         api_scope.endpoint_mut("v1/transactions", move |state, query| {
-            Self::add_transaction(state.snapshot(), &tx_sender, query)
+            Self::add_transaction(state.snapshot(), &blockchain, query)
         });
         self
     }
@@ -20,21 +20,44 @@ use actix_web::{
     FromRequest,
 };
 use actix_web_actors::ws;
-use exonum::blockchain::Blockchain;
+use exonum::{blockchain::Blockchain, helpers::Height};
 use exonum_api::{
     self as api,
     backends::actix::{self as actix_backend, HttpRequest, RawHandler, RequestHandler},
     ApiBackend,
 };
+use exonum_explorer::api::MAX_BLOCKS_PER_REQUEST;
 use exonum_rust_runtime::api::ServiceApiScope;
 use futures::{future, FutureExt};
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-use super::{Session, SharedStateRef, SubscriptionType, TransactionFilter};
+use super::{CursorQuery, Session, SharedStateRef, SubscriptionType, TransactionFilter};
 use crate::api::ExplorerApi;
 
 impl ExplorerApi {
+    /// Checks that events after the cursor can be replayed to a resumed subscriber.
+    fn check_cursor(blockchain: &Blockchain, since: Height) -> api::Result<()> {
+        let height = blockchain.last_block().height;
+        if since > height {
+            return Err(api::Error::bad_request()
+                .title("Invalid cursor")
+                .detail(format!(
+                    "Cursor {} is greater than the blockchain height {}",
+                    since, height
+                )));
+        }
+        if height.0 - since.0 > MAX_BLOCKS_PER_REQUEST as u64 {
+            return Err(api::Error::bad_request()
+                .title("Cursor is too old")
+                .detail(format!(
+                    "At most {} blocks can be replayed; fetch missed blocks via HTTP API",
+                    MAX_BLOCKS_PER_REQUEST
+                )));
+        }
+        Ok(())
+    }
+
     /// Subscribes to events.
     fn handle_ws<Q>(
         name: &str,
@@ -50,8 +73,20 @@ impl ExplorerApi {
             let address =
                 maybe_address.ok_or_else(|| api::Error::not_found().title("Server shut down"))?;
 
+            let cursor = Query::<CursorQuery>::from_query(request.query_string())
+                .map_err(|e| {
+                    api::Error::bad_request()
+                        .title("Invalid cursor")
+                        .detail(e.to_string())
+                })?
+                .into_inner();
+            if let Some(since) = cursor.since {
+                Self::check_cursor(&blockchain, since)?;
+            }
+
             let query = extract_query(&request);
-            ws::start(Session::new(address, vec![query]), &request, stream)
+            let session = Session::new(address, vec![query], cursor.since);
+            ws::start(session, &request, stream)
         };
         let raw_handler =
             move |request, stream| future::ready(handler(request, stream)).boxed_local();
@@ -79,7 +114,11 @@ impl ExplorerApi {
             self.blockchain.clone(),
             shared_state.clone(),
             |request| {
-                if request.query_string().is_empty() {
+                // The cursor does not affect the subscription type.
+                let has_filter =
+                    Query::<HashMap<String, String>>::from_query(request.query_string())
+                        .map_or(true, |query| query.keys().any(|key| key != "since"));
+                if !has_filter {
                     return SubscriptionType::Transactions { filter: None };
                 }
 
@@ -95,6 +134,14 @@ impl ExplorerApi {
                     .unwrap_or(SubscriptionType::None)
             },
         );
+        // Default subscription for changes in service indexes.
+        Self::handle_ws(
+            "v1/index_changes/subscribe",
+            api_scope.web_backend(),
+            self.blockchain.clone(),
+            shared_state.clone(),
+            |_| SubscriptionType::IndexChanges,
+        );
         // Default websocket connection.
         Self::handle_ws(
             "v1/ws",
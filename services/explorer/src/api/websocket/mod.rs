@@ -19,10 +19,12 @@
 //! All communication via `WebSockets` uses JSON encoding.
 //!
 //! The API follows the publisher-subscriber pattern. Clients can subscribe to events. There are
-//! two types of events encapsulated in [`Notification`]:
+//! three types of events encapsulated in [`Notification`]:
 //!
 //! - block creation
 //! - commitment of a transaction
+//! - changes in service indexes made by a block, which allow external indexers to fetch
+//!   only the affected data
 //!
 //! Subscription types are encapsulated in [`SubscriptionType`]. A single client may have
 //! multiple subscriptions.
@@ -32,12 +34,23 @@
 //! responds to each `IncomingMessage` with a [`Response`], which
 //! wraps the response type (`()` for subscriptions, [`TransactionResponse`] for transactions).
 //!
-//! There are three WS endpoints, which differ by the initial subscription for the client:
+//! There are four WS endpoints, which differ by the initial subscription for the client:
 //!
 //! - `api/explorer/v1/ws` does not set any subscriptions
 //! - `api/explorer/v1/blocks/subscribe` sets subscription to blocks
 //! - `api/explorer/v1/transactions/subscribe` sets subscription to transactions. The parameters
 //!   of the subscription are encoded in the query as [`TransactionFilter`]
+//! - `api/explorer/v1/index_changes/subscribe` sets subscription to changes in service indexes
+//!
+//! # Resuming Subscriptions
+//!
+//! Each [`Notification`] has a cursor equal to the height of the block that has produced
+//! the notification. A client reconnecting to any of the endpoints above may specify the cursor
+//! of the last received notification in the `since` query parameter (see [`CursorQuery`]),
+//! e.g., `api/explorer/v1/blocks/subscribe?since=10`. In this case, the server replays
+//! notifications for the blocks committed after the cursor before streaming new notifications,
+//! so that no events are missed during the disconnect. Notifications can be replayed for
+//! at most [`MAX_BLOCKS_PER_REQUEST`] blocks; older cursors are rejected.
 //!
 //! [`IncomingMessage`]: enum.IncomingMessage.html
 //! [`Response`]: enum.Response.html
@@ -46,6 +59,8 @@
 //! [`TransactionHex`]: ../struct.TransactionHex.html
 //! [`TransactionResponse`]: ../struct.TransactionResponse.html
 //! [`TransactionFilter`]: struct.TransactionFilter.html
+//! [`CursorQuery`]: struct.CursorQuery.html
+//! [`MAX_BLOCKS_PER_REQUEST`]: ../constant.MAX_BLOCKS_PER_REQUEST.html
 //!
 //! # Examples
 //!
@@ -192,18 +207,22 @@
 //! # }
 //! ```
 
-pub use exonum_explorer::api::websocket::{
-    CommittedTransactionSummary, IncomingMessage, Notification, Response, SubscriptionType,
-    TransactionFilter,
+pub use exonum_explorer::api::{
+    websocket::{
+        CommittedTransactionSummary, CursorQuery, IncomingMessage, Notification, Response,
+        SubscriptionType, TransactionFilter,
+    },
+    BlockIndexChanges,
 };
 
 use actix::prelude::*;
 use actix_derive::Message;
 use actix_web_actors::ws;
 use exonum::{
-    blockchain::{Blockchain, Schema},
+    blockchain::{Block, Blockchain, Schema},
     crypto::Hash,
-    merkledb::ObjectHash,
+    helpers::Height,
+    merkledb::{access::Access, ObjectHash},
     messages::{AnyTx, SignedMessage, Verified},
 };
 use exonum_explorer::api::{TransactionHex, TransactionResponse};
@@ -298,6 +317,7 @@ struct Terminate;
 struct Subscribe {
     address: Recipient<Message>,
     subscriptions: Vec<SubscriptionType>,
+    since: Option<Height>,
 }
 
 impl fmt::Debug for Subscribe {
@@ -305,6 +325,7 @@ impl fmt::Debug for Subscribe {
         formatter
             .debug_struct("Subscribe")
             .field("subscriptions", &self.subscriptions)
+            .field("since", &self.since)
             .finish()
     }
 }
@@ -334,6 +355,9 @@ struct Transaction(TransactionHex);
 
 pub(crate) struct Server {
     subscribers: BTreeMap<SubscriptionType, HashMap<u64, Recipient<Message>>>,
+    /// Heights of the latest blocks replayed to resumed subscribers. Notifications about
+    /// these blocks are not broadcast to the corresponding subscribers once again.
+    replayed: HashMap<u64, Height>,
     blockchain: Blockchain,
     next_id: u64,
 }
@@ -355,6 +379,7 @@ impl Server {
     fn new(blockchain: Blockchain) -> Self {
         Self {
             subscribers: BTreeMap::new(),
+            replayed: HashMap::new(),
             blockchain,
             next_id: 0,
         }
@@ -380,6 +405,37 @@ impl Server {
         }
     }
 
+    /// Sends notifications about blocks after the cursor `since` to a new subscriber.
+    fn replay(
+        &mut self,
+        id: u64,
+        addr: &Recipient<Message>,
+        subscriptions: &[SubscriptionType],
+        since: Height,
+    ) {
+        let snapshot = self.blockchain.snapshot();
+        let schema = Schema::new(&snapshot);
+        let height = schema.height();
+        if since >= height {
+            return;
+        }
+
+        let wants_index_changes = subscriptions.contains(&SubscriptionType::IndexChanges);
+        for block_height in since.next().0..=height.0 {
+            let block = schema
+                .block_hash_by_height(Height(block_height))
+                .and_then(|block_hash| schema.blocks().get(&block_hash))
+                .expect("BUG. Block below the blockchain height does not exist");
+            Self::block_notifications(&schema, block, wants_index_changes, |sub_type, data| {
+                if subscriptions.contains(&sub_type) {
+                    let serialized = serde_json::to_string(data).unwrap();
+                    addr.do_send(Message::Data(serialized));
+                }
+            });
+        }
+        self.replayed.insert(id, height);
+    }
+
     fn disconnect_all(&mut self) {
         let subscribers = mem::take(&mut self.subscribers);
         for (_, subscriber_group) in subscribers {
@@ -395,6 +451,10 @@ impl Server {
         let signed = SignedMessage::from_hex(message.0.tx_body.as_bytes())?;
         let verified = signed.into_verified()?;
         Blockchain::check_tx(&self.blockchain.snapshot(), &verified)?;
+        if let Some(trace_id) = &message.0.trace_id {
+            self.blockchain
+                .record_trace_id(verified.object_hash(), trace_id)?;
+        }
         Ok(verified)
     }
 
@@ -432,6 +492,9 @@ impl Handler<Subscribe> for Server {
     fn handle(&mut self, message: Subscribe, _ctx: &mut Self::Context) -> u64 {
         let id = self.next_id;
         self.next_id += 1;
+        if let Some(since) = message.since {
+            self.replay(id, &message.address, &message.subscriptions, since);
+        }
         self.set_subscriptions(id, &message.address, message.subscriptions);
         id
     }
@@ -442,6 +505,7 @@ impl Handler<Unsubscribe> for Server {
 
     fn handle(&mut self, message: Unsubscribe, _ctx: &mut Self::Context) {
         self.remove_subscriber(message.id);
+        self.replayed.remove(&message.id);
     }
 }
 
@@ -486,16 +550,66 @@ impl Handler<Broadcast> for Server {
             ctx.notify_later(message, Self::MERGE_WAIT);
             return;
         };
+        let height = block.height;
+        let has_index_subscribers = self.has_subscribers(&SubscriptionType::IndexChanges);
+        Self::block_notifications(&schema, block, has_index_subscribers, |sub_type, data| {
+            self.broadcast_message(sub_type, height, data);
+        });
+        // Subscribers resumed before this block are up to date now.
+        self.replayed.retain(|_, replayed| *replayed > height);
+    }
+}
+
+impl Handler<Transaction> for Server {
+    type Result = LocalBoxFuture<'static, anyhow::Result<TransactionResponse>>;
+
+    /// Broadcasts transaction if the check was passed, and returns an error otherwise.
+    fn handle(&mut self, message: Transaction, _ctx: &mut Self::Context) -> Self::Result {
+        self.handle_transaction(&message).boxed_local()
+    }
+}
+
+impl Handler<Terminate> for Server {
+    type Result = ();
+
+    fn handle(&mut self, _msg: Terminate, ctx: &mut Self::Context) -> Self::Result {
+        ctx.stop();
+    }
+}
+
+impl Server {
+    fn has_subscribers(&self, sub_type: &SubscriptionType) -> bool {
+        self.subscribers
+            .get(sub_type)
+            .map_or(false, |subscriber_group| !subscriber_group.is_empty())
+    }
+
+    /// Calls `notify` for each notification about the block together with the subscription
+    /// type the notification belongs to.
+    fn block_notifications<T: Access>(
+        schema: &Schema<T>,
+        block: Block,
+        with_index_changes: bool,
+        mut notify: impl FnMut(SubscriptionType, &Notification),
+    ) {
         let height = block.height;
         let block_header = Notification::Block(block);
 
         // Notify about block
-        self.broadcast_message(SubscriptionType::Blocks, &block_header);
+        notify(SubscriptionType::Blocks, &block_header);
+
+        // Notify about changes in service indexes. Changes are only read if requested,
+        // since the list of changes may be large.
+        if with_index_changes {
+            let index_changes = BlockIndexChanges::new(schema, height);
+            let data = Notification::IndexChanges(index_changes);
+            notify(SubscriptionType::IndexChanges, &data);
+        }
 
         // Get list of transactions in block and notify about each of them.
         let tx_hashes_table = schema.block_transactions(height);
         let tx_infos = tx_hashes_table.iter().map(|hash| {
-            CommittedTransactionSummary::new(&schema, &hash).unwrap_or_else(|| {
+            CommittedTransactionSummary::new(schema, &hash).unwrap_or_else(|| {
                 panic!(
                     "BUG. Cannot build summary about committed transaction {:?} \
                      because it doesn't exist in \"transactions\", \
@@ -509,14 +623,14 @@ impl Handler<Broadcast> for Server {
             let instance_id = tx_info.instance_id;
             let method_id = tx_info.method_id;
             let data = Notification::Transaction(tx_info);
-            self.broadcast_message(SubscriptionType::Transactions { filter: None }, &data);
-            self.broadcast_message(
+            notify(SubscriptionType::Transactions { filter: None }, &data);
+            notify(
                 SubscriptionType::Transactions {
                     filter: Some(TransactionFilter::new(instance_id, None)),
                 },
                 &data,
             );
-            self.broadcast_message(
+            notify(
                 SubscriptionType::Transactions {
                     filter: Some(TransactionFilter::new(instance_id, Some(method_id))),
                 },
@@ -524,27 +638,10 @@ impl Handler<Broadcast> for Server {
             );
         }
     }
-}
-
-impl Handler<Transaction> for Server {
-    type Result = LocalBoxFuture<'static, anyhow::Result<TransactionResponse>>;
-
-    /// Broadcasts transaction if the check was passed, and returns an error otherwise.
-    fn handle(&mut self, message: Transaction, _ctx: &mut Self::Context) -> Self::Result {
-        self.handle_transaction(&message).boxed_local()
-    }
-}
-
-impl Handler<Terminate> for Server {
-    type Result = ();
-
-    fn handle(&mut self, _msg: Terminate, ctx: &mut Self::Context) -> Self::Result {
-        ctx.stop();
-    }
-}
 
-impl Server {
-    fn broadcast_message<T>(&mut self, sub_type: SubscriptionType, data: &T)
+    /// Broadcasts a notification about the block at the specified height. Subscribers
+    /// which have already received the notification during replay are skipped.
+    fn broadcast_message<T>(&mut self, sub_type: SubscriptionType, height: Height, data: &T)
     where
         T: serde::Serialize,
     {
@@ -554,8 +651,14 @@ impl Server {
             .or_insert_with(HashMap::new);
 
         let serialized = serde_json::to_string(data).unwrap();
-        for addr in subscriber_group.values() {
-            addr.do_send(Message::Data(serialized.clone()));
+        for (id, addr) in subscriber_group.iter() {
+            let is_replayed = self
+                .replayed
+                .get(id)
+                .map_or(false, |&replayed| replayed >= height);
+            if !is_replayed {
+                addr.do_send(Message::Data(serialized.clone()));
+            }
         }
     }
 }
@@ -563,15 +666,21 @@ impl Server {
 pub(crate) struct Session {
     id: u64,
     subscriptions: Vec<SubscriptionType>,
+    since: Option<Height>,
     server_address: Addr<Server>,
 }
 
 impl Session {
-    pub fn new(server_address: Addr<Server>, subscriptions: Vec<SubscriptionType>) -> Self {
+    pub fn new(
+        server_address: Addr<Server>,
+        subscriptions: Vec<SubscriptionType>,
+        since: Option<Height>,
+    ) -> Self {
         Self {
             id: 0,
             server_address,
             subscriptions,
+            since,
         }
     }
 
@@ -609,6 +718,7 @@ impl Actor for Session {
             .send(Subscribe {
                 address,
                 subscriptions: self.subscriptions.clone(),
+                since: self.since,
             })
             .into_actor(self)
             .then(|response, actor, context| {
@@ -125,10 +125,11 @@ impl Service for ExplorerService {
 
     fn wire_api(&self, builder: &mut ServiceApiBuilder) {
         let blockchain = builder.blockchain().clone();
+        let payload_decoder = builder.payload_decoder().clone();
         let scope = builder
             .with_root_path(ExplorerFactory::INSTANCE_NAME)
             .public_scope();
-        ExplorerApi::new(blockchain)
+        ExplorerApi::new(blockchain, payload_decoder)
             .wire_rest(scope)
             .wire_ws(self.shared_state.get_ref(), scope);
     }
@@ -14,14 +14,18 @@
 
 use assert_matches::assert_matches;
 use exonum::{
-    blockchain::{AdditionalHeaders, CallInBlock, Epoch, ProposerId},
+    blockchain::{AdditionalHeaders, CallInBlock, Epoch, ProposerId, Schema},
     crypto::{Hash, KeyPair},
     helpers::{Height, ValidatorId},
     merkledb::{BinaryValue, HashTag, ObjectHash},
-    runtime::{ErrorKind, ExecutionError, ExecutionStatus},
+    runtime::{ErrorKind, ExecutionError, ExecutionStatus, SUPERVISOR_INSTANCE_ID},
 };
-use exonum_api as api;
-use exonum_explorer::{api::*, BlockchainExplorer, TransactionInfo};
+use exonum_api::{self as api, query::Page};
+use exonum_explorer::{
+    api::{websocket::CommittedTransactionSummary, *},
+    BlockchainExplorer, TransactionInfo,
+};
+use exonum_supervisor::Supervisor;
 use exonum_testkit::{ApiKind, Spec, TestKit, TestKitApi, TestKitBuilder};
 use serde_json::{json, Value};
 
@@ -179,6 +183,33 @@ async fn test_explorer_api_block_request() {
     assert_eq!(response.body.source, "2:explorer");
 }
 
+#[tokio::test]
+async fn test_explorer_api_index_changes_request() {
+    let (mut testkit, api) = init_testkit();
+    let tx = KeyPair::random().increment(SERVICE_ID, 5);
+    testkit.create_block_with_transaction(tx);
+
+    let response: BlockIndexChanges = api
+        .public(ApiKind::Explorer)
+        .query(&BlockQuery::new(Height(1)))
+        .get("v1/index_changes")
+        .await
+        .unwrap();
+    assert_eq!(response.height, Height(1));
+    assert_eq!(response.changes.len(), 1);
+    assert_eq!(response.changes[0].instance_id, SERVICE_ID);
+    assert_eq!(response.changes[0].name, "counter.counter");
+    assert!(response.changes[0].key.is_empty());
+
+    let response = api
+        .public(ApiKind::Explorer)
+        .get::<Value>("v1/index_changes?height=10")
+        .await
+        .unwrap_err();
+    assert_eq!(response.http_code, api::HttpStatusCode::NOT_FOUND);
+    assert_eq!(response.body.title, "Failed to get index changes");
+}
+
 async fn create_sample_block(testkit: &mut TestKit) {
     let height = testkit.height().next().0;
     if height == 2 || height == 5 {
@@ -339,6 +370,98 @@ async fn test_explorer_blocks_bounds() {
     assert!(result.is_err());
 }
 
+#[tokio::test]
+async fn test_explorer_blocks_page() {
+    let (mut testkit, api) = init_testkit();
+    for _ in 0..5 {
+        create_sample_block(&mut testkit).await;
+    }
+
+    let page: Page<Value, Height> = api
+        .public(ApiKind::Explorer)
+        .get("v2/blocks?limit=2&fields=height,tx_count")
+        .await
+        .unwrap();
+    assert_eq!(
+        page.items,
+        vec![
+            json!({ "height": 5, "tx_count": 1 }),
+            json!({ "height": 4, "tx_count": 0 }),
+        ]
+    );
+    assert_eq!(page.next_cursor, Some(Height(3)));
+
+    // Follow the cursor to the last page.
+    let page: Page<Value, Height> = api
+        .public(ApiKind::Explorer)
+        .get("v2/blocks?limit=3&cursor=3&fields=height")
+        .await
+        .unwrap();
+    let heights: Vec<_> = page
+        .items
+        .iter()
+        .map(|block| block["height"].clone())
+        .collect();
+    assert_eq!(heights, vec![json!(3), json!(2), json!(1)]);
+    assert_eq!(page.next_cursor, Some(Height(0)));
+
+    // Check the ascending order and skipping empty blocks.
+    let page: Page<Value, Height> = api
+        .public(ApiKind::Explorer)
+        .get("v2/blocks?sort=height&skip_empty_blocks=true&fields=height")
+        .await
+        .unwrap();
+    assert_eq!(
+        page.items,
+        vec![json!({ "height": 2 }), json!({ "height": 5 })]
+    );
+    assert_eq!(page.next_cursor, None);
+
+    // Precommits and block time are only returned if requested explicitly.
+    let page: Page<Value, Height> = api
+        .public(ApiKind::Explorer)
+        .get("v2/blocks?limit=1")
+        .await
+        .unwrap();
+    let block = page.items[0].as_object().unwrap();
+    assert_eq!(block["height"], json!(5));
+    assert!(block.contains_key("state_hash"));
+    assert!(!block.contains_key("precommits"));
+    assert!(!block.contains_key("time"));
+
+    let page: Page<Value, Height> = api
+        .public(ApiKind::Explorer)
+        .get("v2/blocks?limit=1&fields=height,precommits,time")
+        .await
+        .unwrap();
+    let block = page.items[0].as_object().unwrap();
+    assert_eq!(block.len(), 3);
+    assert_eq!(block["precommits"].as_array().unwrap().len(), 1);
+
+    // Check invalid queries.
+    let err = api
+        .public(ApiKind::Explorer)
+        .get::<Page<Value, Height>>("v2/blocks?limit=1001")
+        .await
+        .unwrap_err();
+    assert_eq!(err.http_code, api::HttpStatusCode::BAD_REQUEST);
+    assert_eq!(err.body.title, "Invalid page limit");
+
+    let err = api
+        .public(ApiKind::Explorer)
+        .get::<Page<Value, Height>>("v2/blocks?sort=tx_count")
+        .await
+        .unwrap_err();
+    assert_eq!(err.body.title, "Invalid sort field");
+
+    let err = api
+        .public(ApiKind::Explorer)
+        .get::<Page<Value, Height>>("v2/blocks?fields=height,unknown")
+        .await
+        .unwrap_err();
+    assert_eq!(err.body.title, "Invalid field selection");
+}
+
 #[tokio::test]
 async fn test_explorer_blocks_loaded_info() {
     let (mut testkit, api) = init_testkit();
@@ -435,13 +558,62 @@ async fn test_explorer_transaction_info() {
         .check_against_hash(block.header().tx_hash)
         .is_ok());
 
-    let proof = block.call_proof(CallInBlock::transaction(0));
+    let proof = block.call_proof(CallInBlock::transaction(0)).unwrap();
     let validator_keys = [testkit.us().public_keys().consensus_key];
     let (call_location, status) = proof.verify(&validator_keys).unwrap();
     assert_eq!(call_location, CallInBlock::transaction(0));
     assert!(status.is_ok());
 }
 
+#[tokio::test]
+async fn test_explorer_transaction_payload() {
+    let (mut testkit, api) = init_testkit();
+    let keys = KeyPair::random();
+    let reset_tx = keys.reset(SERVICE_ID, ());
+    let increment_tx = keys.increment(SERVICE_ID, 5);
+
+    let err = api
+        .public(ApiKind::Explorer)
+        .get::<Value>(&format!(
+            "v1/transactions/payload?hash={}",
+            reset_tx.object_hash().to_hex()
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(err.http_code, api::HttpStatusCode::NOT_FOUND);
+    assert_eq!(err.body.title, "Failed to get transaction payload");
+
+    testkit.create_block_with_transactions(vec![reset_tx.clone(), increment_tx.clone()]);
+    let decoded: Value = api
+        .public(ApiKind::Explorer)
+        .get(&format!(
+            "v1/transactions/payload?hash={}",
+            reset_tx.object_hash().to_hex()
+        ))
+        .await
+        .unwrap();
+    assert_eq!(
+        decoded,
+        json!({
+            "message_type": "google.protobuf.Empty",
+            "content": {},
+            "debug_dump": "",
+        })
+    );
+
+    // The counter service does not provide a descriptor for `increment` arguments.
+    let err = api
+        .public(ApiKind::Explorer)
+        .get::<Value>(&format!(
+            "v1/transactions/payload?hash={}",
+            increment_tx.object_hash().to_hex()
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(err.http_code, api::HttpStatusCode::NOT_FOUND);
+    assert_eq!(err.body.title, "Payload descriptor not found");
+}
+
 #[tokio::test]
 async fn test_explorer_transaction_statuses() {
     let (mut testkit, api) = init_testkit();
@@ -493,16 +665,16 @@ async fn test_explorer_transaction_statuses() {
     let explorer = BlockchainExplorer::new(&snapshot);
     let block_info = explorer.block(testkit.height()).unwrap();
 
-    let proof = block_info.call_proof(CallInBlock::transaction(0));
+    let proof = block_info.call_proof(CallInBlock::transaction(0)).unwrap();
     let validator_keys = [testkit.us().public_keys().consensus_key];
     let (_, res) = proof.verify(&validator_keys).unwrap();
     assert!(res.is_ok());
 
-    let proof = block_info.call_proof(CallInBlock::transaction(1));
+    let proof = block_info.call_proof(CallInBlock::transaction(1)).unwrap();
     let (_, res) = proof.verify(&validator_keys).unwrap();
     assert_eq!(res.unwrap_err().description(), "Adding zero does nothing!");
 
-    let proof = block_info.call_proof(CallInBlock::transaction(2));
+    let proof = block_info.call_proof(CallInBlock::transaction(2)).unwrap();
     let (_, res) = proof.verify(&validator_keys).unwrap();
     assert_eq!(res.unwrap_err().kind(), ErrorKind::Unexpected);
 
@@ -526,6 +698,50 @@ async fn test_explorer_transaction_statuses() {
     check_statuses(&statuses);
 }
 
+/// Checks that statuses of calls which errors were rolled into the call errors checkpoint
+/// are reported as gone rather than successful.
+#[tokio::test]
+async fn test_explorer_truncated_call_errors() {
+    let config = Supervisor::simple_config().with_call_errors_retention(1);
+    let mut testkit = TestKitBuilder::validator()
+        .with(Spec::new(CounterService).with_default_instance())
+        .with(Spec::new(ExplorerFactory).with_default_instance())
+        .with(Spec::new(Supervisor).with_instance(SUPERVISOR_INSTANCE_ID, Supervisor::NAME, config))
+        .build();
+    let api = testkit.api();
+
+    let error_tx = KeyPair::random().increment(SERVICE_ID, 0);
+    let block = testkit.create_block_with_transaction(error_tx.clone());
+    let error_height = block.height();
+    testkit.create_blocks_until(error_height.next().next());
+
+    let snapshot = testkit.snapshot();
+    let explorer = BlockchainExplorer::new(&snapshot);
+    let err = explorer.transaction(&error_tx.object_hash()).unwrap_err();
+    assert_eq!(err.height, error_height);
+    let call = CallInBlock::transaction(0);
+    assert!(explorer.call_status(error_height, call).is_err());
+    let block_info = explorer.block(error_height).unwrap();
+    assert!(block_info.call_proof(call).is_err());
+
+    let err = api
+        .public(ApiKind::Explorer)
+        .query(&TransactionQuery::new(error_tx.object_hash()))
+        .get::<Value>("v1/transactions")
+        .await
+        .unwrap_err();
+    assert_eq!(err.http_code, api::HttpStatusCode::GONE);
+
+    let err = api
+        .public(ApiKind::Explorer)
+        .query(&TransactionStatusQuery::new(error_tx.object_hash()))
+        .get::<Value>("v1/call_status/transaction")
+        .await
+        .unwrap_err();
+    assert_eq!(err.http_code, api::HttpStatusCode::GONE);
+    assert_eq!(err.body.title, "Call status is not retained");
+}
+
 /// Checks that `ExplorerApi` accepts valid transactions and discards transactions with
 /// the incorrect instance ID.
 #[tokio::test]
@@ -566,6 +782,55 @@ async fn test_explorer_add_invalid_transaction() {
     assert_eq!(response.body.source, "2:explorer");
 }
 
+#[tokio::test]
+async fn test_explorer_add_transaction_with_trace_id() {
+    let (mut testkit, api) = init_testkit();
+    let keypair = KeyPair::random();
+
+    // Send a transaction with an invalid trace ID.
+    let tx = keypair.increment(SERVICE_ID, 1);
+    let query = TransactionHex::new(&tx).with_trace_id("not a valid ID");
+    let response = api
+        .public(ApiKind::Explorer)
+        .query(&query)
+        .post::<TransactionResponse>("v1/transactions")
+        .await
+        .expect_err("Expected transaction send to finish with error.");
+    assert_eq!(response.http_code, api::HttpStatusCode::BAD_REQUEST);
+    assert_eq!(response.body.title, "Failed to record trace ID");
+
+    // Send a transaction with a valid trace ID.
+    let query = TransactionHex::new(&tx).with_trace_id("request-42");
+    let response = api
+        .public(ApiKind::Explorer)
+        .query(&query)
+        .post::<TransactionResponse>("v1/transactions")
+        .await
+        .expect("Failed to send valid transaction.");
+    assert_eq!(response.tx_hash, tx.object_hash());
+    testkit.poll_events();
+
+    let snapshot = testkit.snapshot();
+    assert_eq!(
+        Schema::new(&snapshot).trace_id(&tx.object_hash()),
+        Some("request-42".to_owned())
+    );
+
+    // Transactions without trace IDs are processed as usual.
+    let other_tx = keypair.increment(SERVICE_ID, 2);
+    api.send(other_tx.clone()).await;
+    testkit.poll_events();
+
+    let block = testkit.create_block();
+    assert_eq!(block.transactions.len(), 2);
+    let snapshot = testkit.snapshot();
+    let schema = Schema::new(&snapshot);
+    let summary = CommittedTransactionSummary::new(&schema, &tx.object_hash()).unwrap();
+    assert_eq!(summary.trace_id.as_deref(), Some("request-42"));
+    let summary = CommittedTransactionSummary::new(&schema, &other_tx.object_hash()).unwrap();
+    assert_eq!(summary.trace_id, None);
+}
+
 #[tokio::test]
 async fn test_explorer_api_with_before_transactions_error() {
     let (mut testkit, api) = init_testkit();
@@ -18,12 +18,13 @@ use exonum::{
         access::{Access, FromAccess, RawAccessMut},
         ProofEntry,
     },
-    runtime::{ExecutionContext, ExecutionError, InstanceId},
+    runtime::{ExecutionContext, ExecutionError, InstanceId, MethodId},
 };
 use exonum_derive::{
     exonum_interface, ExecutionFail, FromAccess, ServiceDispatcher, ServiceFactory,
 };
 use exonum_rust_runtime::{DefaultInstance, Service};
+use protobuf::{reflect::MessageDescriptor, well_known_types::empty::Empty, MessageFull};
 
 pub const SERVICE_NAME: &str = "counter";
 pub const SERVICE_ID: InstanceId = 100;
@@ -129,4 +130,9 @@ impl Service for CounterService {
             Ok(())
         }
     }
+
+    fn payload_descriptors(&self) -> Vec<(MethodId, MessageDescriptor)> {
+        // The argument of `reset` is serialized as an empty message.
+        vec![(1, Empty::descriptor())]
+    }
 }
@@ -164,6 +164,65 @@ async fn test_blocks_subscription() {
     assert_matches!(notification, Notification::Block(ref block) if block.height == Height(2));
 }
 
+#[tokio::test]
+async fn test_resumed_blocks_subscription() {
+    let (mut testkit, api) = init_testkit();
+    testkit.create_blocks_until(Height(3));
+
+    // Events after the cursor are replayed.
+    let url = api.public_url("api/explorer/v1/blocks/subscribe?since=1");
+    let mut client = create_ws_client(&url).await;
+    for height in 2..=3 {
+        let notification: Notification = receive_message(&mut client).await.unwrap();
+        assert_matches!(notification, Notification::Block(ref block) if block.height == Height(height));
+        assert_eq!(notification.cursor(), Height(height));
+    }
+    assert_no_message(&mut client).await;
+
+    // New blocks are streamed after the replayed ones.
+    testkit.create_block();
+    let notification: Notification = receive_message(&mut client).await.unwrap();
+    assert_matches!(notification, Notification::Block(ref block) if block.height == Height(4));
+    assert_no_message(&mut client).await;
+
+    // A cursor equal to the blockchain height does not lead to replay.
+    let url = api.public_url("api/explorer/v1/blocks/subscribe?since=4");
+    let mut client = create_ws_client(&url).await;
+    assert_no_message(&mut client).await;
+}
+
+#[tokio::test]
+async fn test_resumed_transactions_subscription() {
+    let (mut testkit, api) = init_testkit();
+    let keypair = KeyPair::random();
+    let tx = keypair.increment(SERVICE_ID, 3);
+    testkit.create_block_with_transaction(tx.clone());
+    testkit.create_block();
+
+    // The cursor is not treated as a transaction filter.
+    let url = api.public_url("api/explorer/v1/transactions/subscribe?since=0");
+    let mut client = create_ws_client(&url).await;
+    let notification: Notification = receive_message(&mut client).await.unwrap();
+    assert_eq!(notification.cursor(), Height(1));
+    assert_matches!(
+        notification,
+        Notification::Transaction(ref summary) if summary.tx_hash == tx.object_hash()
+    );
+    assert_no_message(&mut client).await;
+}
+
+#[tokio::test]
+async fn test_subscription_with_invalid_cursor() {
+    let (mut testkit, api) = init_testkit();
+    testkit.create_block();
+
+    for query in &["since=2", "since=abc"] {
+        let url = format!("api/explorer/v1/blocks/subscribe?{}", query);
+        let url = api.public_url(&url).replace("http://", "ws://");
+        assert!(connect_async(url).await.is_err());
+    }
+}
+
 #[tokio::test]
 async fn test_transactions_subscription() {
     let (mut testkit, api) = init_testkit();
@@ -185,6 +244,37 @@ async fn test_transactions_subscription() {
     tx_summary.status.0.unwrap();
 }
 
+#[tokio::test]
+async fn test_index_changes_subscription() {
+    let (mut testkit, api) = init_testkit();
+    let url = api.public_url("api/explorer/v1/index_changes/subscribe");
+    let mut client = create_ws_client(&url).await;
+
+    let keypair = KeyPair::random();
+    let tx = keypair.increment(SERVICE_ID, 3);
+    let block = testkit.create_block_with_transaction(tx);
+
+    let notification: Notification = receive_message(&mut client).await.unwrap();
+    let index_changes = match notification {
+        Notification::IndexChanges(index_changes) => index_changes,
+        notification => panic!("Unexpected notification: {:?}", notification),
+    };
+    assert_eq!(index_changes.height, block.height());
+    assert_eq!(index_changes.changes.len(), 1);
+    let change = &index_changes.changes[0];
+    assert_eq!(change.instance_id, SERVICE_ID);
+    assert_eq!(change.name, "counter.counter");
+    assert_eq!((change.puts, change.deletes), (1, 0));
+
+    // Blocks without changes in service indexes lead to empty notifications.
+    testkit.create_block();
+    let notification: Notification = receive_message(&mut client).await.unwrap();
+    assert_matches!(
+        notification,
+        Notification::IndexChanges(ref index_changes) if index_changes.changes.is_empty()
+    );
+}
+
 #[tokio::test]
 async fn test_transactions_subscription_with_filter() {
     let (mut testkit, api) = init_testkit();
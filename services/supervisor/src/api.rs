@@ -21,19 +21,30 @@
 //! - Public API:
 //!
 //!     - [Obtain consensus configuration](#obtain-consensus-configuration)
+//!     - [Obtain consensus configuration with proof](#obtain-consensus-configuration-with-proof)
 //!     - [Obtain pending configuration proposal](#obtain-pending-configuration-proposal)
 //!     - [Obtain deployed artifacts and services](#obtain-deployed-artifacts-and-services)
+//!     - [Obtain pending deployments](#obtain-pending-deployments)
+//!     - [Obtain readiness attestations](#obtain-readiness-attestations)
 //!
 //! - Private API:
 //!
 //!     - [Request to deploy an artifact](#request-to-deploy-an-artifact)
 //!     - [Request service migration](#request-service-migration)
+//!     - [Abort service migration](#abort-service-migration)
+//!     - [Report migration progress](#report-migration-progress)
+//!     - [Extend deployment deadline](#extend-deployment-deadline)
+//!     - [Emergency stop of a service](#emergency-stop-of-a-service)
+//!     - [Switch supervisor mode](#switch-supervisor-mode)
+//!     - [Attest upgrade readiness](#attest-upgrade-readiness)
 //!     - [Request to accept new configuration](#request-to-accept-new-configuration)
 //!     - [Vote for configuration proposal](#vote-for-configuration-proposal)
 //!     - [Obtain current configuration number](#obtain-current-configuration-number)
 //!     - [Obtain supervisor configuration](#obtain-supervisor-configuration)
 //!     - [Check deployment status](#check-deployment-status)
 //!     - [Check migration status](#check-migration-status)
+//!     - [Subscribe to supervisor events](#subscribe-to-supervisor-events)
+//!     - [Obtain supervisor metrics](#obtain-supervisor-metrics)
 //!
 //! # Public API
 //!
@@ -71,6 +82,50 @@
 //! # }
 //! ```
 //!
+//! ## Obtain Consensus Configuration With Proof
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/services/supervisor/consensus-config-proof` |
+//! | Method      | GET   |
+//! | Query type  | - |
+//! | Return type | [`ConsensusConfigWithProof`] |
+//!
+//! Returns the current consensus configuration together with the proof of its authenticity.
+//! The proof ties the configuration to the state hash of the latest block, which is
+//! authorized by the precommits of the validators. Thus, the client may check
+//! the configuration without trusting the queried node, provided it knows the validator keys
+//! of the previous configuration (e.g., from the genesis block).
+//!
+//! [`ConsensusConfigWithProof`]: struct.ConsensusConfigWithProof.html
+//!
+//! ```
+//! # use exonum_rust_runtime::ServiceFactory;
+//! # use exonum_testkit::{ApiKind, TestKitBuilder};
+//! use exonum_supervisor::{api::ConsensusConfigWithProof, Supervisor};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let mut testkit = // Same as in previous example...
+//! #     TestKitBuilder::validator().with(Supervisor::simple()).build();
+//! let validator_keys: Vec<_> = testkit
+//!     .consensus_config()
+//!     .validator_keys
+//!     .iter()
+//!     .map(|keys| keys.consensus_key)
+//!     .collect();
+//!
+//! let response: ConsensusConfigWithProof = testkit
+//!     .api()
+//!     .public(ApiKind::Service("supervisor"))
+//!     .get("consensus-config-proof")
+//!     .await?;
+//! let consensus_config = response.verify(&validator_keys)?;
+//! # assert_eq!(*consensus_config, testkit.consensus_config());
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ## Obtain Pending Configuration Proposal
 //!
 //! | Property    | Value |
@@ -116,7 +171,9 @@
 //! | Query type  | - |
 //! | Return type | [`DispatcherInfo`] |
 //!
-//! Returns information about services available in the network.
+//! Returns information about services available in the network, including
+//! the metadata attached to services via `SetServiceMetadata` configuration changes
+//! and feature flags enabled via `SetFeatureFlags` configuration changes.
 //!
 //! [`DispatcherInfo`]: struct.DispatcherInfo.html
 //!
@@ -139,6 +196,58 @@
 //! # }
 //! ```
 //!
+//! ## Obtain Pending Deployments
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/services/supervisor/pending-deployments` |
+//! | Method      | GET   |
+//! | Query type  | - |
+//! | Return type | `Vec<[PendingDeployment]>` |
+//!
+//! Returns deployments approved by the validators which have not reached their deadline yet.
+//! For each deployment, the response contains validators which have requested the deployment
+//! and validators which have confirmed that the artifact is successfully deployed on their
+//! nodes, as well as the number of confirmations remaining to complete the deployment.
+//!
+//! [PendingDeployment]: struct.PendingDeployment.html
+//!
+//! ```
+//! # use exonum_rust_runtime::ServiceFactory;
+//! # use exonum_testkit::{ApiKind, TestKitBuilder};
+//! use exonum_supervisor::{api::PendingDeployment, Supervisor};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> anyhow::Result<()> {
+//! let mut testkit = // Same as in previous example...
+//! #     TestKitBuilder::validator().with(Supervisor::simple()).build();
+//!
+//! let deployments: Vec<PendingDeployment> = testkit
+//!     .api()
+//!     .public(ApiKind::Service("supervisor"))
+//!     .get("pending-deployments")
+//!     .await?;
+//! assert!(deployments.is_empty());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Obtain Readiness Attestations
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/services/supervisor/readiness` |
+//! | Method      | GET   |
+//! | Query type  | [`ReadinessQuery`] |
+//! | Return type | `Vec<[ValidatorReadiness]>` |
+//!
+//! Returns the latest readiness attestations of the validators for the upgrade with
+//! the specified hash. The hash of a pending upgrade can be obtained from [`PendingUpgrade`].
+//!
+//! [`ReadinessQuery`]: struct.ReadinessQuery.html
+//! [ValidatorReadiness]: struct.ValidatorReadiness.html
+//! [`PendingUpgrade`]: ../struct.PendingUpgrade.html
+//!
 //! # Private API
 //!
 //! ## Request to Deploy an Artifact
@@ -274,6 +383,132 @@
 //! # }
 //! ```
 //!
+//! ## Abort Service Migration
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/services/supervisor/abort-migration` |
+//! | Method      | POST   |
+//! | Body type   | [`MigrationRequest`] |
+//! | Return type | [`Hash`] |
+//!
+//! Votes for aborting an ongoing migration. Once the vote is approved, the migration
+//! is marked as failed and is rolled back.
+//!
+//! Similar to migration requests, it may be required to send such a request to majority
+//! of nodes (in "decentralized" mode), or one request will be enough (in "simple" mode).
+//! The hash of the broadcast transaction is returned from the endpoint.
+//!
+//! **Warning:** `MigrationRequest` structure should be serialized using corresponding protobuf message,
+//! and represented as a hexadecimal string.
+//!
+//! [`MigrationRequest`]: ../struct.MigrationRequest.html
+//! [`Hash`]: https://docs.rs/exonum-crypto/latest/exonum_crypto/struct.Hash.html
+//!
+//! ## Report Migration Progress
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/services/supervisor/report-migration-progress` |
+//! | Method      | POST   |
+//! | Body type   | [`MigrationProgress`] |
+//! | Return type | [`Hash`] |
+//!
+//! Reports intermediate progress of an ongoing migration on the node (e.g., the number
+//! of processed keys), which can be used by operators to monitor long-running migrations.
+//! Reports are optional and do not affect the outcome of the migration. The latest report
+//! of each validator is stored in the migration state and is returned by the
+//! [`migration-status`](#check-migration-status) endpoint.
+//! The hash of the broadcast transaction is returned from the endpoint.
+//!
+//! **Warning:** `MigrationProgress` structure should be serialized using corresponding
+//! protobuf message, and represented as a hexadecimal string.
+//!
+//! [`MigrationProgress`]: ../struct.MigrationProgress.html
+//! [`Hash`]: https://docs.rs/exonum-crypto/latest/exonum_crypto/struct.Hash.html
+//!
+//! ## Extend Deployment Deadline
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/services/supervisor/extend-deploy-deadline` |
+//! | Method      | POST   |
+//! | Body type   | [`DeployDeadlineExtension`] |
+//! | Return type | [`Hash`] |
+//!
+//! Votes for extending the deadline of a pending artifact deployment. Once the vote is approved,
+//! the new deadline supersedes the deadline height specified in the deploy request.
+//!
+//! Similar to deploy requests, it may be required to send such a request to majority
+//! of nodes (in "decentralized" mode), or one request will be enough (in "simple" mode).
+//! The hash of the broadcast transaction is returned from the endpoint.
+//!
+//! **Warning:** `DeployDeadlineExtension` structure should be serialized using corresponding
+//! protobuf message, and represented as a hexadecimal string.
+//!
+//! [`DeployDeadlineExtension`]: ../struct.DeployDeadlineExtension.html
+//! [`Hash`]: https://docs.rs/exonum-crypto/latest/exonum_crypto/struct.Hash.html
+//!
+//! ## Emergency Stop of a Service
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/services/supervisor/emergency-stop` |
+//! | Method      | POST   |
+//! | Body type   | [`StopService`] |
+//! | Return type | [`Hash`] |
+//!
+//! Votes for an emergency stop of a service. Once the vote is approved, the service
+//! is stopped immediately, without a configuration proposal.
+//!
+//! Similar to deploy requests, it may be required to send such a request to majority
+//! of nodes (in "decentralized" mode), or one request will be enough (in "simple" mode).
+//! The hash of the broadcast transaction is returned from the endpoint.
+//!
+//! **Warning:** `StopService` structure should be serialized using corresponding
+//! protobuf message, and represented as a hexadecimal string.
+//!
+//! [`StopService`]: ../struct.StopService.html
+//! [`Hash`]: https://docs.rs/exonum-crypto/latest/exonum_crypto/struct.Hash.html
+//!
+//! ## Switch Supervisor Mode
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/services/supervisor/change-mode` |
+//! | Method      | POST   |
+//! | Body type   | [`ModeChangeRequest`] |
+//! | Return type | [`Hash`] |
+//!
+//! Votes for switching the supervisor to another operating mode. The vote is approved
+//! according to the rules of the current mode. Votes are rejected while there are pending
+//! deployments, migrations or configuration proposals. Applied mode changes are recorded
+//! in the audit log of the supervisor schema.
+//! The hash of the broadcast transaction is returned from the endpoint.
+//!
+//! **Warning:** `ModeChangeRequest` structure should be serialized using corresponding
+//! protobuf message, and represented as a hexadecimal string.
+//!
+//! [`ModeChangeRequest`]: ../struct.ModeChangeRequest.html
+//! [`Hash`]: https://docs.rs/exonum-crypto/latest/exonum_crypto/struct.Hash.html
+//!
+//! ## Attest Upgrade Readiness
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/services/supervisor/report-readiness` |
+//! | Method      | POST   |
+//! | Body type   | [`ReadinessAttestation`] |
+//! | Return type | [`Hash`] |
+//!
+//! Attests readiness of the node for a pending upgrade. Attestations are informational
+//! and do not affect the outcome of the upgrade. The `preflight` command of `exonum-cli`
+//! uses this endpoint to record the results of its checks.
+//! The hash of the broadcast transaction is returned from the endpoint.
+//!
+//! [`ReadinessAttestation`]: ../struct.ReadinessAttestation.html
+//! [`Hash`]: https://docs.rs/exonum-crypto/latest/exonum_crypto/struct.Hash.html
+//!
 //! ## Request to Accept New Configuration
 //!
 //! | Property    | Value |
@@ -467,7 +702,8 @@
 //! | Query type  | - |
 //! | Return type | [`SupervisorConfig`] |
 //!
-//! Returns the current supervisor configuration, which includes the supervisor operating mode.
+//! Returns the current supervisor configuration, which includes the supervisor operating mode
+//! and artifact upgrade policies.
 //!
 //! [`SupervisorConfig`]: ../struct.SupervisorConfig.html
 //!
@@ -562,7 +798,8 @@
 //! | Query type  | [`MigrationInfoQuery`] |
 //! | Return type | [`MigrationState`] |
 //!
-//! Returns the state of the migration for a certain service instance.
+//! Returns the state of the migration for a certain service instance, including
+//! the latest progress reports of validators.
 //!
 //! [`MigrationInfoQuery`]: struct.MigrationInfoQuery.html
 //! [`MigrationState`]: ../struct.MigrationState.html
@@ -604,13 +841,57 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! ## Subscribe to Supervisor Events
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/services/supervisor/events/subscribe` |
+//! | Method      | GET (`WebSocket`) |
+//! | Query type  | - |
+//! | Message type | [`Notification`] |
+//!
+//! Opens a `WebSocket` connection, over which the supervisor pushes events emitted
+//! within each committed block: registration, confirmation, application and expiration
+//! of configuration proposals, as well as changes of deployment and migration states.
+//! See the [`websocket` module](websocket/index.html) for details.
+//!
+//! [`Notification`]: websocket/struct.Notification.html
+//!
+//! ## Obtain Supervisor Metrics
+//!
+//! | Property    | Value |
+//! |-------------|-------|
+//! | Path        | `/api/services/supervisor/metrics` |
+//! | Method      | GET |
+//! | Query type  | - |
+//! | Return type | Prometheus text exposition format |
+//!
+//! Returns metrics of the supervisor to be scraped by Prometheus. Metrics are collected
+//! by the node since its start:
+//!
+//! | Metric | Type | Description |
+//! |--------|------|-------------|
+//! | `exonum_supervisor_deploys_total` | counter | Deployment state changes, by `state` |
+//! | `exonum_supervisor_deploy_latency_blocks` | histogram | Blocks from deployment start to completion |
+//! | `exonum_supervisor_migrations_total` | counter | Migration state changes, by `state` |
+//! | `exonum_supervisor_migration_latency_blocks` | histogram | Blocks from migration start to completion |
+//! | `exonum_supervisor_config_proposals_total` | counter | Proposal status changes, by `status` |
+//! | `exonum_supervisor_config_proposal_latency_blocks` | histogram | Blocks from proposal registration to application or expiration |
+//! | `exonum_supervisor_pending_deployments` | gauge | Deployments in progress |
+//! | `exonum_supervisor_pending_migrations` | gauge | Migrations in progress |
+//! | `exonum_supervisor_pending_proposal_age_blocks` | gauge | Age of the pending proposal |
+//! | `exonum_supervisor_pending_proposal_confirmations` | gauge | Confirmations of the pending proposal |
+//!
+//! The pending proposal gauges are omitted if there is no pending proposal.
 
+use anyhow::ensure;
 use exonum::{
-    blockchain::ConsensusConfig,
-    crypto::Hash,
-    helpers::Height,
-    merkledb::AsReadonly,
-    runtime::{ArtifactId, DispatcherSchema, InstanceState},
+    blockchain::{ConsensusConfig, IndexProof},
+    crypto::{Hash, PublicKey},
+    helpers::{Height, ValidatorId},
+    merkledb::{access::Access, AsReadonly, ObjectHash},
+    runtime::{ArtifactId, ArtifactStatus, DispatcherSchema, InstanceState},
 };
 use exonum_rust_runtime::{
     api::{self, ServiceApiBuilder, ServiceApiState},
@@ -618,14 +899,22 @@ use exonum_rust_runtime::{
 };
 use serde::{Deserialize, Serialize};
 
-use std::convert::TryFrom;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+};
 
 use super::{
     schema::SchemaImpl, transactions::SupervisorInterface, AsyncEventState, ConfigProposalWithHash,
-    ConfigPropose, ConfigVote, DeployRequest, MigrationRequest, MigrationState, SupervisorConfig,
+    ConfigPropose, ConfigVote, DeployDeadlineExtension, DeployRequest, FeatureFlags,
+    MigrationProgress, MigrationRequest, MigrationState, ModeChangeRequest, ReadinessAttestation,
+    ServiceMetadata, StopService, SupervisorConfig,
 };
 use exonum_proto::ProtobufBase64;
 
+pub(crate) mod metrics;
+pub mod websocket;
+
 /// Query for retrieving information about deploy state.
 /// This is flattened version of `DeployRequest` which can be
 /// encoded via URL query parameters.
@@ -745,20 +1034,169 @@ impl From<MigrationRequest> for MigrationInfoQuery {
 pub struct DispatcherInfo {
     /// List of deployed artifacts.
     pub artifacts: Vec<ArtifactId>,
+    /// List of deployed artifacts marked as deprecated. New services cannot be started
+    /// from these artifacts.
+    #[serde(default)]
+    pub deprecated_artifacts: Vec<ArtifactId>,
     /// List of services.
     pub services: Vec<InstanceState>,
+    /// Metadata attached to services, keyed by the service name. Services without
+    /// metadata are omitted.
+    #[serde(default)]
+    pub service_metadata: BTreeMap<String, ServiceMetadata>,
+    /// Feature flags enabled for services, keyed by the service name. Services without
+    /// enabled flags are omitted.
+    #[serde(default)]
+    pub feature_flags: BTreeMap<String, FeatureFlags>,
 }
 
 impl DispatcherInfo {
     /// Loads dispatcher information from database.
-    fn load<T: AsReadonly>(schema: &DispatcherSchema<T>) -> Self {
+    fn load<T: AsReadonly, U: Access>(
+        schema: &DispatcherSchema<T>,
+        supervisor_schema: &SchemaImpl<U>,
+    ) -> Self {
+        let services: Vec<InstanceState> = schema.service_instances().values().collect();
+        let service_metadata = services
+            .iter()
+            .filter_map(|instance| {
+                let metadata = supervisor_schema.service_metadata.get(&instance.spec.id)?;
+                Some((instance.spec.name.clone(), metadata))
+            })
+            .collect();
+        let feature_flags = services
+            .iter()
+            .filter_map(|instance| {
+                let flags = supervisor_schema
+                    .public
+                    .feature_flags
+                    .get(&instance.spec.id)?;
+                Some((instance.spec.name.clone(), flags))
+            })
+            .collect();
+
         Self {
             artifacts: schema.service_artifacts().keys().collect(),
-            services: schema.service_instances().values().collect(),
+            deprecated_artifacts: schema
+                .service_artifacts()
+                .iter()
+                .filter(|(_, state)| state.status == ArtifactStatus::Deprecated)
+                .map(|(artifact, _)| artifact)
+                .collect(),
+            services,
+            service_metadata,
+            feature_flags,
         }
     }
 }
 
+/// Information about a deployment approved by the validators.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PendingDeployment {
+    /// Deploy request approved by the validators.
+    pub request: DeployRequest,
+    /// Deadline height of the deployment, including approved extensions.
+    pub deadline_height: Height,
+    /// Current state of the deployment.
+    pub state: Option<AsyncEventState>,
+    /// Validators which have requested the deployment.
+    pub requested_by: Vec<ValidatorId>,
+    /// Validators which have confirmed that the artifact is deployed on their nodes.
+    pub confirmed_by: Vec<ValidatorId>,
+    /// Number of confirmations remaining to complete the deployment.
+    pub remaining_confirmations: usize,
+}
+
+impl PendingDeployment {
+    /// Loads information about pending deployments from the database.
+    fn load_all<T: Access>(
+        schema: &SchemaImpl<T>,
+        consensus_config: &ConsensusConfig,
+    ) -> Vec<Self> {
+        let validator_ids = |keys: BTreeSet<PublicKey>| -> Vec<ValidatorId> {
+            keys.iter()
+                .filter_map(|key| {
+                    consensus_config.find_validator(|validator| validator.service_key == *key)
+                })
+                .collect()
+        };
+
+        schema
+            .pending_deployments
+            .values()
+            .map(|request| {
+                let requested_by = validator_ids(schema.deploy_requests.confirmed_keys(&request));
+                let confirmed_by =
+                    validator_ids(schema.deploy_confirmations.confirmed_keys(&request));
+                let remaining_confirmations = consensus_config
+                    .validator_keys
+                    .len()
+                    .saturating_sub(confirmed_by.len());
+                Self {
+                    deadline_height: schema.deploy_deadline(&request),
+                    state: schema.deploy_states.get(&request),
+                    request,
+                    requested_by,
+                    confirmed_by,
+                    remaining_confirmations,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Query for readiness attestations of the validators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+pub struct ReadinessQuery {
+    /// Hash of the pending upgrade.
+    pub subject: Hash,
+}
+
+/// Readiness attestation of a validator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ValidatorReadiness {
+    /// Service key of the validator.
+    pub service_key: PublicKey,
+    /// Latest attestation of the validator.
+    pub attestation: ReadinessAttestation,
+}
+
+/// Consensus configuration together with the proof of its authenticity.
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ConsensusConfigWithProof {
+    /// Actual consensus configuration of the blockchain.
+    pub consensus_config: ConsensusConfig,
+    /// Proof of the configuration against the state hash of the latest block.
+    pub proof: IndexProof,
+}
+
+impl ConsensusConfigWithProof {
+    /// Verifies the proof against the consensus keys of the validators and returns
+    /// the authenticated configuration.
+    pub fn verify(&self, validator_keys: &[PublicKey]) -> anyhow::Result<&ConsensusConfig> {
+        let (index_name, index_hash) = self.proof.verify(validator_keys)?;
+        ensure!(
+            index_name == CONSENSUS_CONFIG_INDEX,
+            "Proof is given for unexpected index `{}`",
+            index_name
+        );
+        ensure!(
+            index_hash == self.consensus_config.object_hash(),
+            "Consensus configuration does not match the proof"
+        );
+        Ok(&self.consensus_config)
+    }
+}
+
+/// Full name of the index storing the consensus configuration.
+const CONSENSUS_CONFIG_INDEX: &str = "core.consensus_config";
+
 /// Public API specification of the supervisor service.
 struct PublicApi;
 
@@ -771,6 +1209,23 @@ impl PublicApi {
         Ok(state.data().for_core().consensus_config())
     }
 
+    /// Returns an actual consensus configuration of the blockchain together with its proof.
+    async fn consensus_config_proof(
+        state: ServiceApiState,
+        _query: (),
+    ) -> Result<ConsensusConfigWithProof, api::Error> {
+        let proof = state
+            .proof_cache()
+            .index_proof(state.snapshot(), CONSENSUS_CONFIG_INDEX)
+            .ok_or_else(|| {
+                api::Error::not_found().title("Consensus configuration proof is not available")
+            })?;
+        Ok(ConsensusConfigWithProof {
+            consensus_config: state.data().for_core().consensus_config(),
+            proof,
+        })
+    }
+
     /// Returns a pending propose config change.
     async fn config_proposal(
         state: ServiceApiState,
@@ -784,7 +1239,37 @@ impl PublicApi {
 
     /// Returns a list of deployed artifacts and initialized services.
     async fn services(state: ServiceApiState, _query: ()) -> Result<DispatcherInfo, api::Error> {
-        Ok(DispatcherInfo::load(&state.data().for_dispatcher()))
+        let supervisor_schema = SchemaImpl::new(state.service_data());
+        Ok(DispatcherInfo::load(
+            &state.data().for_dispatcher(),
+            &supervisor_schema,
+        ))
+    }
+
+    /// Returns deployments approved by the validators along with their confirmation status.
+    async fn pending_deployments(
+        state: ServiceApiState,
+        _query: (),
+    ) -> Result<Vec<PendingDeployment>, api::Error> {
+        let schema = SchemaImpl::new(state.service_data());
+        let consensus_config = state.data().for_core().consensus_config();
+        Ok(PendingDeployment::load_all(&schema, &consensus_config))
+    }
+
+    /// Returns readiness attestations of the validators for the specified upgrade.
+    async fn readiness(
+        state: ServiceApiState,
+        query: ReadinessQuery,
+    ) -> Result<Vec<ValidatorReadiness>, api::Error> {
+        let schema = SchemaImpl::new(state.service_data());
+        let attestations = schema.readiness_attestations.get(&query.subject);
+        Ok(attestations
+            .iter()
+            .map(|(service_key, attestation)| ValidatorReadiness {
+                service_key,
+                attestation,
+            })
+            .collect())
     }
 }
 
@@ -824,6 +1309,78 @@ impl PrivateApi {
             .map_err(|err| api::Error::internal(err).title("Migration start request failed"))
     }
 
+    /// Creates and broadcasts the `AbortMigration` transaction, which is signed
+    /// by the current node, and returns its hash.
+    async fn abort_migration(
+        state: ServiceApiState,
+        request: MigrationRequest,
+    ) -> Result<Hash, api::Error> {
+        Self::broadcaster(&state)?
+            .abort_migration((), request)
+            .await
+            .map_err(|err| api::Error::internal(err).title("Migration abort request failed"))
+    }
+
+    /// Creates and broadcasts the `MigrationProgress` transaction, which is signed
+    /// by the current node, and returns its hash.
+    async fn report_migration_progress(
+        state: ServiceApiState,
+        progress: MigrationProgress,
+    ) -> Result<Hash, api::Error> {
+        Self::broadcaster(&state)?
+            .report_migration_progress((), progress)
+            .await
+            .map_err(|err| api::Error::internal(err).title("Migration progress report failed"))
+    }
+
+    /// Creates and broadcasts the `DeployDeadlineExtension` transaction, which is signed
+    /// by the current node, and returns its hash.
+    async fn extend_deploy_deadline(
+        state: ServiceApiState,
+        extension: DeployDeadlineExtension,
+    ) -> Result<Hash, api::Error> {
+        Self::broadcaster(&state)?
+            .extend_deploy_deadline((), extension)
+            .await
+            .map_err(|err| api::Error::internal(err).title("Deadline extension request failed"))
+    }
+
+    /// Creates and broadcasts the `EmergencyStopService` transaction, which is signed
+    /// by the current node, and returns its hash.
+    async fn emergency_stop(
+        state: ServiceApiState,
+        request: StopService,
+    ) -> Result<Hash, api::Error> {
+        Self::broadcaster(&state)?
+            .emergency_stop_service((), request)
+            .await
+            .map_err(|err| api::Error::internal(err).title("Emergency stop request failed"))
+    }
+
+    /// Creates and broadcasts the `ModeChangeRequest` transaction, which is signed
+    /// by the current node, and returns its hash.
+    async fn change_mode(
+        state: ServiceApiState,
+        request: ModeChangeRequest,
+    ) -> Result<Hash, api::Error> {
+        Self::broadcaster(&state)?
+            .change_mode((), request)
+            .await
+            .map_err(|err| api::Error::internal(err).title("Mode change request failed"))
+    }
+
+    /// Creates and broadcasts the `ReadinessAttestation` transaction, which is signed
+    /// by the current node, and returns its hash.
+    async fn report_readiness(
+        state: ServiceApiState,
+        attestation: ReadinessAttestation,
+    ) -> Result<Hash, api::Error> {
+        Self::broadcaster(&state)?
+            .report_readiness((), attestation)
+            .await
+            .map_err(|err| api::Error::internal(err).title("Readiness attestation failed"))
+    }
+
     /// Creates and broadcasts the `ConfigPropose` transaction, which is signed
     /// by the current node, and returns its hash.
     async fn propose_config(
@@ -895,6 +1452,15 @@ pub(crate) fn wire(builder: &mut ServiceApiBuilder) {
         .private_scope()
         .pb_endpoint_mut("deploy-artifact", PrivateApi::deploy_artifact)
         .pb_endpoint_mut("migrate", PrivateApi::migrate)
+        .pb_endpoint_mut("abort-migration", PrivateApi::abort_migration)
+        .pb_endpoint_mut(
+            "report-migration-progress",
+            PrivateApi::report_migration_progress,
+        )
+        .pb_endpoint_mut("extend-deploy-deadline", PrivateApi::extend_deploy_deadline)
+        .pb_endpoint_mut("emergency-stop", PrivateApi::emergency_stop)
+        .pb_endpoint_mut("change-mode", PrivateApi::change_mode)
+        .pb_endpoint_mut("report-readiness", PrivateApi::report_readiness)
         .pb_endpoint_mut("propose-config", PrivateApi::propose_config)
         .pb_endpoint_mut("confirm-config", PrivateApi::confirm_config)
         .endpoint("configuration-number", PrivateApi::configuration_number)
@@ -904,6 +1470,9 @@ pub(crate) fn wire(builder: &mut ServiceApiBuilder) {
     builder
         .public_scope()
         .endpoint("consensus-config", PublicApi::consensus_config)
+        .endpoint("consensus-config-proof", PublicApi::consensus_config_proof)
         .endpoint("config-proposal", PublicApi::config_proposal)
-        .endpoint("services", PublicApi::services);
+        .endpoint("services", PublicApi::services)
+        .endpoint("pending-deployments", PublicApi::pending_deployments)
+        .endpoint("readiness", PublicApi::readiness);
 }
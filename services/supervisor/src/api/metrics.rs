@@ -0,0 +1,347 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Prometheus metrics of the supervisor.
+//!
+//! Metrics are collected by the node from the supervisor events emitted in the committed
+//! blocks, thus they reflect the activity observed since the node start. Latencies are measured
+//! in blocks between the registration of a request or proposal and its completion; requests
+//! registered before the node start do not contribute to latencies.
+//!
+//! See the [API documentation](../index.html#obtain-supervisor-metrics) for the list
+//! of exported metrics.
+
+use actix_web::{http, web::Payload};
+use exonum::{crypto::Hash, helpers::Height};
+use exonum_api::backends::actix::{HttpRequest, HttpResponse, RawHandler, RequestHandler};
+use exonum_merkledb::{access::Access, ObjectHash};
+use exonum_rust_runtime::api::ServiceApiScope;
+use futures::{future, FutureExt};
+
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    fmt::{self, Write as _},
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    api::websocket::Notification, events::SupervisorEvent, schema::SchemaImpl, AsyncEventState,
+    ConfigProposalStatus,
+};
+
+/// Content type of the Prometheus text exposition format.
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+/// Upper bounds of the latency histogram buckets, in blocks.
+const LATENCY_BUCKETS: [u64; 10] = [1, 2, 5, 10, 20, 50, 100, 200, 500, 1_000];
+
+/// Histogram of latencies measured in blocks.
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: u64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: u64) {
+        for (bucket, &bound) in self.buckets.iter_mut().zip(&LATENCY_BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn write(&self, out: &mut String, name: &str, help: &str) -> fmt::Result {
+        writeln!(out, "# HELP {} {}", name, help)?;
+        writeln!(out, "# TYPE {} histogram", name)?;
+        for (count, bound) in self.buckets.iter().zip(&LATENCY_BUCKETS) {
+            writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, count)?;
+        }
+        writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, self.count)?;
+        writeln!(out, "{}_sum {}", name, self.sum)?;
+        writeln!(out, "{}_count {}", name, self.count)
+    }
+}
+
+/// Tracker of asynchronous processes (deployments, migrations or configuration proposals).
+#[derive(Debug, Default)]
+struct ProcessMetrics {
+    /// Number of observed state changes, keyed by the state label.
+    counts: BTreeMap<&'static str, u64>,
+    /// Registration heights of the processes in progress.
+    started: HashMap<Hash, Height>,
+    latency: Histogram,
+}
+
+impl ProcessMetrics {
+    fn start(&mut self, id: Hash, height: Height, label: &'static str) {
+        // Migrations may emit several events while being in progress.
+        if let Entry::Vacant(entry) = self.started.entry(id) {
+            entry.insert(height);
+            *self.counts.entry(label).or_default() += 1;
+        }
+    }
+
+    fn finish(&mut self, id: &Hash, height: Height, label: &'static str) {
+        *self.counts.entry(label).or_default() += 1;
+        if let Some(start) = self.started.remove(id) {
+            self.latency.observe(height.0.saturating_sub(start.0));
+        }
+    }
+
+    fn record(&mut self, id: Hash, height: Height, state: &AsyncEventState) {
+        match state {
+            AsyncEventState::Pending => self.start(id, height, "pending"),
+            AsyncEventState::Succeed => self.finish(&id, height, "succeeded"),
+            AsyncEventState::Failed { .. } => self.finish(&id, height, "failed"),
+            AsyncEventState::Timeout => self.finish(&id, height, "timeout"),
+        }
+    }
+
+    fn write_counts(&self, out: &mut String, name: &str, label: &str, help: &str) -> fmt::Result {
+        writeln!(out, "# HELP {} {}", name, help)?;
+        writeln!(out, "# TYPE {} counter", name)?;
+        for (value, count) in &self.counts {
+            writeln!(out, "{}{{{}=\"{}\"}} {}", name, label, value, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Gauges reflecting the supervisor state as of the latest committed block.
+#[derive(Debug, Default)]
+struct Gauges {
+    pending_deployments: usize,
+    pending_migrations: usize,
+    /// Age (in blocks) and number of confirmations of the pending configuration proposal.
+    pending_proposal: Option<(u64, usize)>,
+}
+
+/// Node-local supervisor metrics.
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    deploys: ProcessMetrics,
+    migrations: ProcessMetrics,
+    config_proposals: ProcessMetrics,
+    /// Pending configuration proposal registered before the node start, together with
+    /// the height at which it was first observed.
+    observed_proposal: Option<(Hash, Height)>,
+    gauges: Gauges,
+}
+
+impl Metrics {
+    /// Updates metrics based on the events and state of the latest committed block.
+    pub(crate) fn update<T: Access>(
+        &mut self,
+        access: T,
+        height: Height,
+        notification: Option<&Notification>,
+    ) {
+        if let Some(notification) = notification {
+            for event in &notification.events {
+                self.record_event(notification.height, event);
+            }
+        }
+
+        let schema = SchemaImpl::new(access);
+        self.gauges = Gauges {
+            pending_deployments: schema.pending_deployments.values().count(),
+            pending_migrations: schema.pending_migrations.iter().count(),
+            pending_proposal: None,
+        };
+
+        if let Some(proposal) = schema.public.pending_proposal.get() {
+            let hash = proposal.propose_hash;
+            let start = if let Some(&start) = self.config_proposals.started.get(&hash) {
+                start
+            } else {
+                // The proposal was registered before the node start, so its age is measured
+                // from the moment it was first observed.
+                let start = match self.observed_proposal {
+                    Some((observed, start)) if observed == hash => start,
+                    _ => height,
+                };
+                self.observed_proposal = Some((hash, start));
+                start
+            };
+            let age = height.0.saturating_sub(start.0);
+            let confirmations = schema.config_confirms.confirmations(&hash);
+            self.gauges.pending_proposal = Some((age, confirmations));
+        }
+    }
+
+    fn record_event(&mut self, height: Height, event: &SupervisorEvent) {
+        match event {
+            SupervisorEvent::Deploy(event) => {
+                self.deploys
+                    .record(event.request.object_hash(), height, &event.state);
+            }
+            SupervisorEvent::Migration(event) => {
+                self.migrations
+                    .record(event.request.object_hash(), height, &event.state.inner);
+            }
+            SupervisorEvent::ConfigProposal(event) => {
+                let proposals = &mut self.config_proposals;
+                let hash = event.propose_hash;
+                match event.status {
+                    ConfigProposalStatus::Registered => proposals.start(hash, height, "registered"),
+                    ConfigProposalStatus::Applied => proposals.finish(&hash, height, "applied"),
+                    ConfigProposalStatus::Expired => proposals.finish(&hash, height, "expired"),
+                    ConfigProposalStatus::StagesAborted => {
+                        proposals.finish(&hash, height, "stages_aborted");
+                    }
+                    ConfigProposalStatus::Confirmed => {
+                        *proposals.counts.entry("confirmed").or_default() += 1;
+                    }
+                    ConfigProposalStatus::StageApplied => {
+                        *proposals.counts.entry("stage_applied").or_default() += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Renders metrics in the Prometheus text exposition format.
+    fn render(&self) -> Result<String, fmt::Error> {
+        let mut out = String::new();
+        self.deploys.write_counts(
+            &mut out,
+            "exonum_supervisor_deploys_total",
+            "state",
+            "Number of deployment state changes.",
+        )?;
+        self.deploys.latency.write(
+            &mut out,
+            "exonum_supervisor_deploy_latency_blocks",
+            "Number of blocks between deployment start and completion.",
+        )?;
+        self.migrations.write_counts(
+            &mut out,
+            "exonum_supervisor_migrations_total",
+            "state",
+            "Number of migration state changes.",
+        )?;
+        self.migrations.latency.write(
+            &mut out,
+            "exonum_supervisor_migration_latency_blocks",
+            "Number of blocks between migration start and completion.",
+        )?;
+        self.config_proposals.write_counts(
+            &mut out,
+            "exonum_supervisor_config_proposals_total",
+            "status",
+            "Number of configuration proposal status changes.",
+        )?;
+        self.config_proposals.latency.write(
+            &mut out,
+            "exonum_supervisor_config_proposal_latency_blocks",
+            "Number of blocks between proposal registration and its application or expiration.",
+        )?;
+
+        let gauges = &self.gauges;
+        write_gauge(
+            &mut out,
+            "exonum_supervisor_pending_deployments",
+            "Number of deployments in progress.",
+            gauges.pending_deployments,
+        )?;
+        write_gauge(
+            &mut out,
+            "exonum_supervisor_pending_migrations",
+            "Number of migrations in progress.",
+            gauges.pending_migrations,
+        )?;
+        if let Some((age, confirmations)) = gauges.pending_proposal {
+            write_gauge(
+                &mut out,
+                "exonum_supervisor_pending_proposal_age_blocks",
+                "Number of blocks since the registration of the pending configuration proposal.",
+                age,
+            )?;
+            write_gauge(
+                &mut out,
+                "exonum_supervisor_pending_proposal_confirmations",
+                "Number of confirmations of the pending configuration proposal.",
+                confirmations,
+            )?;
+        }
+        Ok(out)
+    }
+}
+
+fn write_gauge(out: &mut String, name: &str, help: &str, value: impl fmt::Display) -> fmt::Result {
+    writeln!(out, "# HELP {} {}", name, help)?;
+    writeln!(out, "# TYPE {} gauge", name)?;
+    writeln!(out, "{} {}", name, value)
+}
+
+/// Wires the Prometheus metrics endpoint.
+pub(crate) fn wire(metrics: Arc<Mutex<Metrics>>, api_scope: &mut ServiceApiScope) {
+    let handler = move |_request: HttpRequest, _stream: Payload| {
+        let metrics = metrics.lock().expect("Cannot lock `Metrics`");
+        let body = metrics.render().expect("Cannot render metrics");
+        Ok::<_, actix_web::Error>(HttpResponse::Ok().content_type(CONTENT_TYPE).body(body))
+    };
+    let raw_handler = move |request, stream| future::ready(handler(request, stream)).boxed_local();
+
+    api_scope.web_backend().raw_handler(RequestHandler {
+        name: "metrics".to_owned(),
+        method: http::Method::GET,
+        inner: Arc::from(raw_handler) as Arc<RawHandler>,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_rendering() {
+        let mut histogram = Histogram::default();
+        histogram.observe(1);
+        histogram.observe(7);
+        histogram.observe(5_000);
+
+        let mut out = String::new();
+        histogram.write(&mut out, "latency", "Latency.").unwrap();
+        assert!(out.contains("# TYPE latency histogram\n"));
+        assert!(out.contains("latency_bucket{le=\"1\"} 1\n"));
+        assert!(out.contains("latency_bucket{le=\"5\"} 1\n"));
+        assert!(out.contains("latency_bucket{le=\"10\"} 2\n"));
+        assert!(out.contains("latency_bucket{le=\"1000\"} 2\n"));
+        assert!(out.contains("latency_bucket{le=\"+Inf\"} 3\n"));
+        assert!(out.contains("latency_sum 5008\n"));
+        assert!(out.contains("latency_count 3\n"));
+    }
+
+    #[test]
+    fn process_latencies() {
+        let mut process = ProcessMetrics::default();
+        let (first, second) = (Hash::zero(), Hash::new([1; 32]));
+        process.record(first, Height(1), &AsyncEventState::Pending);
+        process.record(first, Height(2), &AsyncEventState::Pending);
+        process.record(first, Height(4), &AsyncEventState::Succeed);
+        // The process was started before the metrics were collected.
+        process.record(second, Height(4), &AsyncEventState::Timeout);
+
+        assert_eq!(process.counts["pending"], 1);
+        assert_eq!(process.counts["succeeded"], 1);
+        assert_eq!(process.counts["timeout"], 1);
+        assert!(process.started.is_empty());
+        assert_eq!(process.latency.count, 1);
+        assert_eq!(process.latency.sum, 3);
+    }
+}
@@ -0,0 +1,311 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `WebSocket` API of the supervisor service.
+//!
+//! Clients connected to the `api/services/supervisor/events/subscribe` endpoint
+//! (which belongs to the private part of the supervisor API) receive a [`Notification`]
+//! encoded as JSON after each block in which the supervisor has emitted at least one event.
+//! Events are emitted when:
+//!
+//! - a configuration proposal is registered, confirmed by a validator, applied or expired
+//! - the state of a deployment changes
+//! - the state of a migration changes
+//!
+//! The endpoint is push-only; messages sent by clients are ignored.
+//!
+//! [`Notification`]: struct.Notification.html
+
+use actix::prelude::*;
+use actix_derive::Message;
+use actix_web::{http, web::Payload};
+use actix_web_actors::ws;
+use exonum::helpers::Height;
+use exonum_api::{
+    self as api,
+    backends::actix::{HttpRequest, RawHandler, RequestHandler},
+    ApiBackend,
+};
+use exonum_merkledb::access::Access;
+use exonum_rust_runtime::api::ServiceApiScope;
+use futures::{future, FutureExt};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::HashMap,
+    fmt, mem,
+    sync::{Arc, Mutex, Weak},
+};
+
+use crate::{events::SupervisorEvent, schema::SchemaImpl};
+
+/// Notification about supervisor events emitted within a block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Notification {
+    /// Height of the block in which the events were emitted.
+    pub height: Height,
+    /// Emitted events in the order of their emission.
+    pub events: Vec<SupervisorEvent>,
+}
+
+impl Notification {
+    /// Loads events emitted within the latest block. Returns `None` if there are no events.
+    pub(crate) fn load<T: Access>(access: T, height: Height) -> Option<Self> {
+        let events: Vec<_> = SchemaImpl::new(access).events.iter().collect();
+        if events.is_empty() {
+            None
+        } else {
+            Some(Self { height, events })
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SharedState {
+    inner: Arc<Mutex<SharedStateInner>>,
+}
+
+impl Drop for SharedState {
+    fn drop(&mut self) {
+        // If this is the last instance of the `SharedState`, send termination message
+        // to the server.
+        if Arc::strong_count(&self.inner) == 1 {
+            if let Ok(inner) = self.inner.lock() {
+                if let Some(ref addr) = inner.server_addr {
+                    addr.do_send(Terminate);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SharedStateRef {
+    inner: Weak<Mutex<SharedStateInner>>,
+}
+
+#[derive(Debug, Default)]
+struct SharedStateInner {
+    server_addr: Option<Addr<Server>>,
+}
+
+impl SharedState {
+    pub fn get_ref(&self) -> SharedStateRef {
+        SharedStateRef {
+            inner: Arc::downgrade(&self.inner),
+        }
+    }
+
+    pub fn broadcast(&self, notification: Notification) {
+        let inner = self.inner.lock().expect("Cannot lock `SharedState`");
+        // If there is no server, no client has connected yet, so there is no one to notify.
+        if let Some(ref addr) = inner.server_addr {
+            addr.do_send(Broadcast(notification));
+        }
+    }
+}
+
+impl SharedStateRef {
+    /// Returns `None` if the service has shut down.
+    fn ensure_server(&self) -> Option<Addr<Server>> {
+        let arc = self.inner.upgrade()?;
+        let mut inner = arc.lock().expect("Cannot lock `SharedState`");
+        let addr = inner
+            .server_addr
+            .get_or_insert_with(|| Server::default().start());
+        Some(addr.clone())
+    }
+}
+
+/// `WebSocket` message for communication between clients(`Session`) and server(`Server`).
+#[derive(Message, Debug)]
+#[rtype(result = "()")]
+enum Message {
+    /// This message will send data to a client.
+    Data(String),
+    /// This message will terminate a client session.
+    Close,
+}
+
+/// This message will terminate server.
+#[derive(Debug, Message)]
+#[rtype(result = "()")]
+struct Terminate;
+
+#[derive(Message)]
+#[rtype(u64)]
+struct Subscribe {
+    address: Recipient<Message>,
+}
+
+#[derive(Debug, Message)]
+#[rtype(result = "()")]
+struct Unsubscribe {
+    id: u64,
+}
+
+#[derive(Debug, Message)]
+#[rtype(result = "()")]
+struct Broadcast(Notification);
+
+#[derive(Default)]
+struct Server {
+    subscribers: HashMap<u64, Recipient<Message>>,
+    next_id: u64,
+}
+
+impl fmt::Debug for Server {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("Server")
+            .field("subscribers", &self.subscribers.len())
+            .finish()
+    }
+}
+
+impl Actor for Server {
+    type Context = Context<Self>;
+
+    fn stopping(&mut self, _ctx: &mut Self::Context) -> Running {
+        for (_, recipient) in mem::take(&mut self.subscribers) {
+            if recipient.connected() {
+                recipient.do_send(Message::Close);
+            }
+        }
+        Running::Stop
+    }
+}
+
+impl Handler<Subscribe> for Server {
+    type Result = u64;
+
+    fn handle(&mut self, message: Subscribe, _ctx: &mut Self::Context) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.subscribers.insert(id, message.address);
+        id
+    }
+}
+
+impl Handler<Unsubscribe> for Server {
+    type Result = ();
+
+    fn handle(&mut self, message: Unsubscribe, _ctx: &mut Self::Context) {
+        self.subscribers.remove(&message.id);
+    }
+}
+
+impl Handler<Broadcast> for Server {
+    type Result = ();
+
+    fn handle(&mut self, message: Broadcast, _ctx: &mut Self::Context) {
+        let serialized = serde_json::to_string(&message.0).unwrap();
+        for addr in self.subscribers.values() {
+            addr.do_send(Message::Data(serialized.clone()));
+        }
+    }
+}
+
+impl Handler<Terminate> for Server {
+    type Result = ();
+
+    fn handle(&mut self, _msg: Terminate, ctx: &mut Self::Context) -> Self::Result {
+        ctx.stop();
+    }
+}
+
+struct Session {
+    id: u64,
+    server_address: Addr<Server>,
+}
+
+impl Session {
+    fn new(server_address: Addr<Server>) -> Self {
+        Self {
+            id: 0,
+            server_address,
+        }
+    }
+}
+
+impl Actor for Session {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let address: Recipient<_> = ctx.address().recipient();
+        self.server_address
+            .send(Subscribe { address })
+            .into_actor(self)
+            .then(|response, actor, context| {
+                match response {
+                    Ok(result) => {
+                        actor.id = result;
+                    }
+                    Err(..) => context.stop(),
+                }
+                actix::fut::ready(())
+            })
+            .wait(ctx);
+    }
+
+    fn stopping(&mut self, _ctx: &mut <Self as Actor>::Context) -> Running {
+        self.server_address.do_send(Unsubscribe { id: self.id });
+        Running::Stop
+    }
+}
+
+impl Handler<Message> for Session {
+    type Result = ();
+
+    fn handle(&mut self, msg: Message, ctx: &mut Self::Context) {
+        match msg {
+            Message::Data(x) => ctx.text(x),
+            Message::Close => {
+                ctx.close(Some(ws::CloseReason {
+                    code: ws::CloseCode::Away,
+                    description: Some("Supervisor service shut down".into()),
+                }));
+                ctx.stop();
+            }
+        }
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Session {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
+            Ok(ws::Message::Close(_)) | Err(_) => ctx.stop(),
+            _ => {}
+        }
+    }
+}
+
+/// Wires the `WebSocket` endpoint for supervisor events.
+pub(crate) fn wire(shared_state: SharedStateRef, api_scope: &mut ServiceApiScope) {
+    let handler = move |request: HttpRequest, stream: Payload| {
+        let address = shared_state
+            .ensure_server()
+            .ok_or_else(|| api::Error::not_found().title("Server shut down"))?;
+        ws::start(Session::new(address), &request, stream)
+    };
+    let raw_handler = move |request, stream| future::ready(handler(request, stream)).boxed_local();
+
+    api_scope.web_backend().raw_handler(RequestHandler {
+        name: "events/subscribe".to_owned(),
+        method: http::Method::GET,
+        inner: Arc::from(raw_handler) as Arc<RawHandler>,
+    });
+}
@@ -17,6 +17,7 @@
 use exonum::runtime::{CommonError, ExecutionContext, ExecutionError, InstanceId, MethodId};
 use exonum_merkledb::BinaryValue;
 use exonum_rust_runtime::{GenericCallMut, Interface, MethodDescriptor};
+use serde::{de::DeserializeOwned, Serialize};
 
 /// Fully qualified name of the [`Configure`] interface.
 ///
@@ -78,6 +79,77 @@ pub trait Configure {
     ) -> Result<(), ExecutionError>;
 }
 
+/// Typed configuration parameters of a service.
+///
+/// Types implementing this trait are (de)serialized with `serde` using the JSON format;
+/// thus, fields with `#[serde(default)]` may be omitted from the proposed configuration.
+/// Decoded parameters are checked with the [`validate`] method. The trait is usually
+/// implemented with the `TypedConfig` derive macro from the `exonum-derive` crate, which
+/// also implements `BinaryValue` via [`to_config_bytes`] and [`from_config_bytes`].
+/// This allows to use the type as [`Configure::Params`]; in this case, parameters
+/// which cannot be decoded or fail validation are rejected with
+/// the `MalformedArguments` error before reaching the service.
+///
+/// # Examples
+///
+/// ```
+/// use exonum_derive::TypedConfig;
+/// use exonum_merkledb::BinaryValue;
+/// use exonum_supervisor::TypedConfig;
+/// use serde_derive::{Deserialize, Serialize};
+///
+/// #[derive(Debug, PartialEq, Serialize, Deserialize, TypedConfig)]
+/// #[typed_config(validate = "Config::check")]
+/// pub struct Config {
+///     #[serde(default = "Config::default_fee")]
+///     pub fee: u64,
+///     pub name: String,
+/// }
+///
+/// impl Config {
+///     fn default_fee() -> u64 {
+///         10
+///     }
+///
+///     fn check(&self) -> Result<(), &'static str> {
+///         if self.name.is_empty() {
+///             Err("Name must not be empty")
+///         } else {
+///             Ok(())
+///         }
+///     }
+/// }
+///
+/// let config = Config::from_bytes(br#"{ "name": "test" }"#[..].into())?;
+/// assert_eq!(config, Config { fee: 10, name: "test".to_owned() });
+/// assert!(Config::from_bytes(br#"{ "name": "" }"#[..].into()).is_err());
+/// # Ok::<_, anyhow::Error>(())
+/// ```
+///
+/// [`validate`]: #method.validate
+/// [`to_config_bytes`]: #method.to_config_bytes
+/// [`from_config_bytes`]: #method.from_config_bytes
+/// [`Configure::Params`]: trait.Configure.html#associatedtype.Params
+pub trait TypedConfig: Serialize + DeserializeOwned {
+    /// Checks the decoded configuration parameters. Returns a description of the problem
+    /// if the parameters are invalid.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Serializes the configuration parameters.
+    fn to_config_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Failed to serialize configuration parameters")
+    }
+
+    /// Deserializes and validates the configuration parameters.
+    fn from_config_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let config: Self = serde_json::from_slice(bytes)?;
+        config.validate().map_err(anyhow::Error::msg)?;
+        Ok(config)
+    }
+}
+
 impl<'a, T: BinaryValue> Interface<'a> for dyn Configure<Params = T> {
     const INTERFACE_NAME: &'static str = CONFIGURE_INTERFACE_NAME;
 
@@ -0,0 +1,496 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Incremental changes of the consensus configuration.
+
+use anyhow::ensure;
+use exonum::{
+    blockchain::{ConsensusConfig, ValidatorKeys},
+    crypto::PublicKey,
+    helpers::{Height, Milliseconds},
+};
+use exonum_derive::{BinaryValue, ObjectHash};
+use exonum_proto::ProtobufConvert;
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashSet;
+
+use super::proto;
+
+/// Patch of the consensus configuration, which modifies only the specified parameters.
+///
+/// Unlike the full consensus config, the patch is merged into the consensus configuration
+/// actual at the moment of the proposal application. Thus, patches do not revert
+/// changes of other parameters (e.g., the validator set) made after the proposal was created.
+///
+/// # Examples
+///
+/// ```
+/// # use exonum::blockchain::ConsensusConfig;
+/// # use exonum_supervisor::ConsensusConfigPatch;
+/// let patch = ConsensusConfigPatch::new().min_propose_timeout(50);
+/// let config = patch.apply(&ConsensusConfig::default());
+/// assert_eq!(config.min_propose_timeout, 50);
+/// assert_eq!(config.max_propose_timeout, ConsensusConfig::default().max_propose_timeout);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[derive(BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ConsensusConfigPatch {
+    /// New list of validators keys.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validator_keys: Option<Vec<ValidatorKeys>>,
+    /// New interval between first two rounds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_round_timeout: Option<Milliseconds>,
+    /// New period of sending a Status message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_timeout: Option<Milliseconds>,
+    /// New peer exchange timeout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peers_timeout: Option<Milliseconds>,
+    /// New maximum number of transactions per block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub txs_block_limit: Option<u32>,
+    /// New maximum message length (in bytes).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_message_len: Option<u32>,
+    /// New minimal propose timeout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_propose_timeout: Option<Milliseconds>,
+    /// New maximal propose timeout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_propose_timeout: Option<Milliseconds>,
+    /// New amount of transactions in pool to start use `min_propose_timeout`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub propose_timeout_threshold: Option<u32>,
+}
+
+impl ConsensusConfigPatch {
+    /// Creates an empty patch.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks if the patch does not modify any parameters.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Merges the patch into the provided consensus configuration.
+    #[must_use]
+    pub fn apply(&self, config: &ConsensusConfig) -> ConsensusConfig {
+        let mut config = config.clone();
+        if let Some(ref validator_keys) = self.validator_keys {
+            config.validator_keys = validator_keys.clone();
+        }
+        if let Some(first_round_timeout) = self.first_round_timeout {
+            config.first_round_timeout = first_round_timeout;
+        }
+        if let Some(status_timeout) = self.status_timeout {
+            config.status_timeout = status_timeout;
+        }
+        if let Some(peers_timeout) = self.peers_timeout {
+            config.peers_timeout = peers_timeout;
+        }
+        if let Some(txs_block_limit) = self.txs_block_limit {
+            config.txs_block_limit = txs_block_limit;
+        }
+        if let Some(max_message_len) = self.max_message_len {
+            config.max_message_len = max_message_len;
+        }
+        if let Some(min_propose_timeout) = self.min_propose_timeout {
+            config.min_propose_timeout = min_propose_timeout;
+        }
+        if let Some(max_propose_timeout) = self.max_propose_timeout {
+            config.max_propose_timeout = max_propose_timeout;
+        }
+        if let Some(propose_timeout_threshold) = self.propose_timeout_threshold {
+            config.propose_timeout_threshold = propose_timeout_threshold;
+        }
+        config
+    }
+
+    /// Sets the list of validators keys.
+    #[must_use]
+    pub fn validator_keys(mut self, validator_keys: Vec<ValidatorKeys>) -> Self {
+        self.validator_keys = Some(validator_keys);
+        self
+    }
+
+    /// Sets the interval between first two rounds.
+    #[must_use]
+    pub const fn first_round_timeout(mut self, first_round_timeout: Milliseconds) -> Self {
+        self.first_round_timeout = Some(first_round_timeout);
+        self
+    }
+
+    /// Sets the period of sending a Status message.
+    #[must_use]
+    pub const fn status_timeout(mut self, status_timeout: Milliseconds) -> Self {
+        self.status_timeout = Some(status_timeout);
+        self
+    }
+
+    /// Sets the peer exchange timeout.
+    #[must_use]
+    pub const fn peers_timeout(mut self, peers_timeout: Milliseconds) -> Self {
+        self.peers_timeout = Some(peers_timeout);
+        self
+    }
+
+    /// Sets the maximum number of transactions per block.
+    #[must_use]
+    pub const fn txs_block_limit(mut self, txs_block_limit: u32) -> Self {
+        self.txs_block_limit = Some(txs_block_limit);
+        self
+    }
+
+    /// Sets the maximum message length (in bytes).
+    #[must_use]
+    pub const fn max_message_len(mut self, max_message_len: u32) -> Self {
+        self.max_message_len = Some(max_message_len);
+        self
+    }
+
+    /// Sets the minimal propose timeout.
+    #[must_use]
+    pub const fn min_propose_timeout(mut self, min_propose_timeout: Milliseconds) -> Self {
+        self.min_propose_timeout = Some(min_propose_timeout);
+        self
+    }
+
+    /// Sets the maximal propose timeout.
+    #[must_use]
+    pub const fn max_propose_timeout(mut self, max_propose_timeout: Milliseconds) -> Self {
+        self.max_propose_timeout = Some(max_propose_timeout);
+        self
+    }
+
+    /// Sets the amount of transactions in pool to start use `min_propose_timeout`.
+    #[must_use]
+    pub const fn propose_timeout_threshold(mut self, propose_timeout_threshold: u32) -> Self {
+        self.propose_timeout_threshold = Some(propose_timeout_threshold);
+        self
+    }
+}
+
+impl ProtobufConvert for ConsensusConfigPatch {
+    type ProtoStruct = proto::ConsensusConfigPatch;
+
+    fn to_pb(&self) -> Self::ProtoStruct {
+        let mut pb = proto::ConsensusConfigPatch::new();
+        if let Some(ref validator_keys) = self.validator_keys {
+            let mut keys = proto::ValidatorKeysList::new();
+            keys.set_keys(validator_keys.to_pb().into());
+            pb.set_validator_keys(keys);
+        }
+        if let Some(first_round_timeout) = self.first_round_timeout {
+            pb.set_first_round_timeout(first_round_timeout);
+        }
+        if let Some(status_timeout) = self.status_timeout {
+            pb.set_status_timeout(status_timeout);
+        }
+        if let Some(peers_timeout) = self.peers_timeout {
+            pb.set_peers_timeout(peers_timeout);
+        }
+        if let Some(txs_block_limit) = self.txs_block_limit {
+            pb.set_txs_block_limit(txs_block_limit);
+        }
+        if let Some(max_message_len) = self.max_message_len {
+            pb.set_max_message_len(max_message_len);
+        }
+        if let Some(min_propose_timeout) = self.min_propose_timeout {
+            pb.set_min_propose_timeout(min_propose_timeout);
+        }
+        if let Some(max_propose_timeout) = self.max_propose_timeout {
+            pb.set_max_propose_timeout(max_propose_timeout);
+        }
+        if let Some(propose_timeout_threshold) = self.propose_timeout_threshold {
+            pb.set_propose_timeout_threshold(propose_timeout_threshold);
+        }
+        pb
+    }
+
+    fn from_pb(mut pb: Self::ProtoStruct) -> anyhow::Result<Self> {
+        let validator_keys = if pb.has_validator_keys() {
+            let keys = pb.take_validator_keys().take_keys().into_vec();
+            Some(Vec::<ValidatorKeys>::from_pb(keys)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            validator_keys,
+            first_round_timeout: if pb.has_first_round_timeout() {
+                Some(pb.get_first_round_timeout())
+            } else {
+                None
+            },
+            status_timeout: if pb.has_status_timeout() {
+                Some(pb.get_status_timeout())
+            } else {
+                None
+            },
+            peers_timeout: if pb.has_peers_timeout() {
+                Some(pb.get_peers_timeout())
+            } else {
+                None
+            },
+            txs_block_limit: if pb.has_txs_block_limit() {
+                Some(pb.get_txs_block_limit())
+            } else {
+                None
+            },
+            max_message_len: if pb.has_max_message_len() {
+                Some(pb.get_max_message_len())
+            } else {
+                None
+            },
+            min_propose_timeout: if pb.has_min_propose_timeout() {
+                Some(pb.get_min_propose_timeout())
+            } else {
+                None
+            },
+            max_propose_timeout: if pb.has_max_propose_timeout() {
+                Some(pb.get_max_propose_timeout())
+            } else {
+                None
+            },
+            propose_timeout_threshold: if pb.has_propose_timeout_threshold() {
+                Some(pb.get_propose_timeout_threshold())
+            } else {
+                None
+            },
+        })
+    }
+}
+
+/// Replacement of the keys of a single validator within [`ChangeValidators`].
+///
+/// [`ChangeValidators`]: struct.ChangeValidators.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::ValidatorReplacement")]
+#[non_exhaustive]
+pub struct ValidatorReplacement {
+    /// Consensus key of the replaced validator.
+    pub consensus_key: PublicKey,
+    /// New keys of the validator.
+    pub new_keys: ValidatorKeys,
+}
+
+/// Change of individual validators in the validator set.
+///
+/// Validators are identified by their consensus keys. Like [`ConsensusConfigPatch`],
+/// the change is merged into the validator set actual at the moment of the proposal
+/// application. Replaced validators retain their positions (and thus `ValidatorId`s)
+/// in the validator set, while added validators are appended to the end of the set.
+///
+/// If the service key of a replaced validator changes, supervisor transactions signed
+/// with the old service key are accepted during the `grace_period` after the change
+/// as if they were signed with the new key. Thus, confirmations sent by the validator
+/// before it has switched to the new key are not lost.
+///
+/// # Examples
+///
+/// ```
+/// # use exonum::{blockchain::{ConsensusConfig, ValidatorKeys}, crypto::KeyPair};
+/// # use exonum_supervisor::ChangeValidators;
+/// let (config, _) = ConsensusConfig::for_tests(4);
+/// let old_keys = config.validator_keys[0];
+/// let new_keys = ValidatorKeys::new(old_keys.consensus_key, KeyPair::random().public_key());
+/// let change = ChangeValidators::new()
+///     .replace_validator(old_keys.consensus_key, new_keys)
+///     .remove_validator(config.validator_keys[3].consensus_key)
+///     .grace_period(10);
+/// let new_config = change.apply(&config)?;
+/// assert_eq!(new_config.validator_keys.len(), 3);
+/// assert_eq!(new_config.validator_keys[0], new_keys);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// [`ConsensusConfigPatch`]: struct.ConsensusConfigPatch.html
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::ChangeValidators")]
+#[non_exhaustive]
+pub struct ChangeValidators {
+    /// Keys of the added validators.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub add: Vec<ValidatorKeys>,
+    /// Consensus keys of the removed validators.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<PublicKey>,
+    /// Validators with replaced keys.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replace: Vec<ValidatorReplacement>,
+    /// Number of blocks after the change during which old service keys of the replaced
+    /// validators are accepted. Must not exceed [`MAX_GRACE_PERIOD`].
+    ///
+    /// [`MAX_GRACE_PERIOD`]: #associatedconstant.MAX_GRACE_PERIOD
+    #[serde(default)]
+    pub grace_period: u64,
+}
+
+impl ChangeValidators {
+    /// Maximum allowed [`grace_period`](#structfield.grace_period) of a change.
+    /// Changes with a longer grace period are rejected by the supervisor.
+    pub const MAX_GRACE_PERIOD: u64 = 1_000;
+
+    /// Creates an empty change.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks if the change does not modify the validator set.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.add.is_empty() && self.remove.is_empty() && self.replace.is_empty()
+    }
+
+    /// Adds a new validator.
+    #[must_use]
+    pub fn add_validator(mut self, keys: ValidatorKeys) -> Self {
+        self.add.push(keys);
+        self
+    }
+
+    /// Removes the validator with the specified consensus key.
+    #[must_use]
+    pub fn remove_validator(mut self, consensus_key: PublicKey) -> Self {
+        self.remove.push(consensus_key);
+        self
+    }
+
+    /// Replaces the keys of the validator with the specified consensus key.
+    #[must_use]
+    pub fn replace_validator(mut self, consensus_key: PublicKey, new_keys: ValidatorKeys) -> Self {
+        self.replace.push(ValidatorReplacement {
+            consensus_key,
+            new_keys,
+        });
+        self
+    }
+
+    /// Sets the number of blocks during which old service keys of the replaced validators
+    /// are accepted.
+    #[must_use]
+    pub const fn grace_period(mut self, grace_period: u64) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Merges the change into the provided consensus configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a removed or replaced validator is absent from the validator set,
+    /// or if a validator is mentioned in the change several times. The resulting configuration
+    /// is not validated.
+    pub fn apply(&self, config: &ConsensusConfig) -> anyhow::Result<ConsensusConfig> {
+        let mut affected = HashSet::new();
+        let replaced = self
+            .replace
+            .iter()
+            .map(|replacement| &replacement.consensus_key);
+        for key in self.remove.iter().chain(replaced) {
+            ensure!(
+                affected.insert(*key),
+                "Validator with consensus key {} is changed several times",
+                key
+            );
+            ensure!(
+                config
+                    .validator_keys
+                    .iter()
+                    .any(|keys| keys.consensus_key == *key),
+                "Validator with consensus key {} is not in the validator set",
+                key
+            );
+        }
+
+        let mut config = config.clone();
+        for replacement in &self.replace {
+            let keys = config
+                .validator_keys
+                .iter_mut()
+                .find(|keys| keys.consensus_key == replacement.consensus_key)
+                .unwrap(); // Safe: we've checked that the validator is present.
+            *keys = replacement.new_keys;
+        }
+        config
+            .validator_keys
+            .retain(|keys| !self.remove.contains(&keys.consensus_key));
+        config.validator_keys.extend_from_slice(&self.add);
+        Ok(config)
+    }
+
+    /// Returns pairs of old and new service keys for the replaced validators which have
+    /// changed their service keys.
+    pub(crate) fn service_key_handovers<'a>(
+        &'a self,
+        config: &'a ConsensusConfig,
+    ) -> impl Iterator<Item = (PublicKey, PublicKey)> + 'a {
+        self.replace.iter().filter_map(move |replacement| {
+            let old_keys = config
+                .validator_keys
+                .iter()
+                .find(|keys| keys.consensus_key == replacement.consensus_key)?;
+            let new_key = replacement.new_keys.service_key;
+            if old_keys.service_key == new_key {
+                None
+            } else {
+                Some((old_keys.service_key, new_key))
+            }
+        })
+    }
+}
+
+/// Handover of the service key of a validator replaced by [`ChangeValidators`].
+///
+/// [`ChangeValidators`]: struct.ChangeValidators.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash)]
+#[protobuf_convert(source = "proto::KeyHandover")]
+pub struct KeyHandover {
+    /// New service key of the validator.
+    pub new_service_key: PublicKey,
+    /// Height from which the old service key is no longer accepted.
+    pub expires_at: Height,
+}
+
+#[cfg(test)]
+mod tests {
+    use exonum::merkledb::BinaryValue;
+
+    use super::*;
+
+    #[test]
+    fn patch_roundtrip() {
+        let patch = ConsensusConfigPatch::new()
+            .min_propose_timeout(0)
+            .txs_block_limit(500);
+        let restored = ConsensusConfigPatch::from_bytes(patch.to_bytes().into()).unwrap();
+        assert_eq!(restored, patch);
+        assert_eq!(restored.min_propose_timeout, Some(0));
+        assert_eq!(restored.max_propose_timeout, None);
+
+        let empty = ConsensusConfigPatch::from_bytes(Vec::new().into()).unwrap();
+        assert!(empty.is_empty());
+    }
+}
@@ -27,6 +27,13 @@ pub enum CommonError {
     DeadlineExceeded = 0,
     /// Actual height for transaction is in the past.
     ActualFromIsPast = 1,
+    /// Readiness attestation refers to an upgrade which is not pending.
+    UnknownUpgrade = 2,
+    /// Vote delegation request is invalid, e.g., it exceeds the maximum delegation period
+    /// or the delegate key is already used.
+    InvalidDelegation = 3,
+    /// Expiration height of the configuration proposal is in the past.
+    ProposalExpired = 4,
 }
 
 /// Artifact-related errors group.
@@ -40,11 +47,20 @@ pub enum ArtifactError {
     /// Artifact identifier has incorrect format.
     InvalidArtifactId = 17,
     /// Deploy request has been already registered.
+    ///
+    /// This error is no longer returned: repeating a deploy request with the same params
+    /// from the same validator is a no-op.
     DeployRequestAlreadyRegistered = 18,
     /// Deploy request has not been registered or accepted.
     DeployRequestNotRegistered = 19,
     /// Start request contains unknown artifact.
     UnknownArtifact = 20,
+    /// Validators have reported different checksums of the deployed artifact.
+    ArtifactChecksumMismatch = 21,
+    /// New deadline of a deployment does not exceed its current deadline.
+    InvalidDeadlineExtension = 22,
+    /// Start request refers to a deprecated artifact.
+    ArtifactDeprecated = 23,
 }
 
 /// Instance-related errors group.
@@ -77,6 +93,14 @@ pub enum ConfigurationError {
     IncorrectConfigurationNumber = 52,
     /// Invalid configuration for supervisor.
     InvalidConfig = 53,
+    /// Supervisor is already in the requested mode.
+    ModeAlreadyActive = 54,
+    /// Supervisor mode cannot be changed while there are pending deployments, migrations
+    /// or configuration proposals.
+    ModeChangeConflict = 55,
+    /// Activation height of timelocked configuration changes does not satisfy
+    /// the minimum delay specified in the supervisor configuration.
+    TimelockViolation = 56,
 }
 
 impl ConfigurationError {
@@ -97,4 +121,12 @@ pub enum MigrationError {
     MigrationFailed = 65,
     /// Several nodes reported different state hashes.
     StateHashDivergence = 66,
+    /// Migration was aborted by the validators.
+    MigrationAborted = 67,
+    /// Migration is already completed and thus cannot be aborted.
+    MigrationNotPending = 68,
+    /// Migration target violates the upgrade policy for the artifact.
+    UpgradePolicyViolation = 69,
+    /// Reported migration progress is invalid.
+    InvalidMigrationProgress = 70,
 }
@@ -0,0 +1,145 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Events emitted by the supervisor when the state of configuration proposals,
+//! deployments or migrations changes.
+
+use exonum::crypto::Hash;
+use exonum_derive::{BinaryValue, ObjectHash};
+use exonum_proto::ProtobufConvert;
+use serde::{Deserialize, Serialize};
+
+use super::{proto, AsyncEventState, DeployRequest, MigrationRequest, MigrationState};
+
+/// Status of a configuration proposal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ConfigProposalStatus {
+    /// Proposal has been registered.
+    Registered,
+    /// Proposal has received a vote from a validator.
+    Confirmed,
+    /// Proposal has been applied.
+    Applied,
+    /// Proposal has expired without being applied.
+    Expired,
+    /// Stage of a multi-stage proposal has been applied.
+    StageApplied,
+    /// Remaining stages of a multi-stage proposal have been discarded because
+    /// one of the stages could not be applied.
+    StagesAborted,
+}
+
+impl ProtobufConvert for ConfigProposalStatus {
+    type ProtoStruct = proto::ConfigProposalStatus;
+
+    fn to_pb(&self) -> Self::ProtoStruct {
+        match self {
+            Self::Registered => proto::ConfigProposalStatus::REGISTERED,
+            Self::Confirmed => proto::ConfigProposalStatus::CONFIRMED,
+            Self::Applied => proto::ConfigProposalStatus::APPLIED,
+            Self::Expired => proto::ConfigProposalStatus::EXPIRED,
+            Self::StageApplied => proto::ConfigProposalStatus::STAGE_APPLIED,
+            Self::StagesAborted => proto::ConfigProposalStatus::STAGES_ABORTED,
+        }
+    }
+
+    fn from_pb(pb: Self::ProtoStruct) -> anyhow::Result<Self> {
+        let status = match pb {
+            proto::ConfigProposalStatus::REGISTERED => Self::Registered,
+            proto::ConfigProposalStatus::CONFIRMED => Self::Confirmed,
+            proto::ConfigProposalStatus::APPLIED => Self::Applied,
+            proto::ConfigProposalStatus::EXPIRED => Self::Expired,
+            proto::ConfigProposalStatus::STAGE_APPLIED => Self::StageApplied,
+            proto::ConfigProposalStatus::STAGES_ABORTED => Self::StagesAborted,
+        };
+        Ok(status)
+    }
+}
+
+/// Change of a configuration proposal state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash)]
+#[protobuf_convert(source = "proto::ConfigProposalEvent")]
+#[non_exhaustive]
+pub struct ConfigProposalEvent {
+    /// Hash of the configuration proposal.
+    pub propose_hash: Hash,
+    /// New status of the proposal.
+    pub status: ConfigProposalStatus,
+}
+
+/// Change of a deployment state.
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash)]
+#[protobuf_convert(source = "proto::DeployEvent")]
+#[non_exhaustive]
+pub struct DeployEvent {
+    /// Corresponding deploy request.
+    pub request: DeployRequest,
+    /// New state of the deployment.
+    pub state: AsyncEventState,
+}
+
+/// Change of a migration state.
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash)]
+#[protobuf_convert(source = "proto::MigrationEvent")]
+#[non_exhaustive]
+pub struct MigrationEvent {
+    /// Corresponding migration request.
+    pub request: MigrationRequest,
+    /// New state of the migration.
+    pub state: MigrationState,
+}
+
+/// Event emitted by the supervisor within a block.
+///
+/// Events are stored in the supervisor schema until the next block is processed,
+/// and are pushed to the `WebSocket` subscribers once the block is committed.
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash)]
+#[protobuf_convert(source = "proto::SupervisorEvent", rename(case = "snake_case"))]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum SupervisorEvent {
+    /// Configuration proposal has changed its status.
+    ConfigProposal(ConfigProposalEvent),
+    /// Deployment has changed its state.
+    Deploy(DeployEvent),
+    /// Migration has changed its state.
+    Migration(MigrationEvent),
+}
+
+impl SupervisorEvent {
+    pub(crate) fn config_proposal(propose_hash: Hash, status: ConfigProposalStatus) -> Self {
+        Self::ConfigProposal(ConfigProposalEvent {
+            propose_hash,
+            status,
+        })
+    }
+
+    pub(crate) fn deploy(request: DeployRequest, state: AsyncEventState) -> Self {
+        Self::Deploy(DeployEvent { request, state })
+    }
+
+    pub(crate) fn migration(request: MigrationRequest, state: MigrationState) -> Self {
+        Self::Migration(MigrationEvent { request, state })
+    }
+}
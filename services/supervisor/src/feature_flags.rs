@@ -0,0 +1,179 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Feature flags of service instances.
+
+use exonum::runtime::{ExecutionContext, InstanceId, SUPERVISOR_INSTANCE_ID};
+use exonum_derive::{BinaryValue, ObjectHash};
+use exonum_proto::ProtobufConvert;
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{proto, schema::Schema};
+
+/// Set of feature flags enabled for a service instance. Flags not in the set are disabled.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::FeatureFlags")]
+#[serde(transparent)]
+pub struct FeatureFlags {
+    /// Names of the enabled flags.
+    #[protobuf_convert(with = "self::pb_enabled_flags")]
+    pub enabled: BTreeSet<String>,
+}
+
+impl FeatureFlags {
+    /// Checks whether the flag with the specified name is enabled.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.enabled.contains(name)
+    }
+}
+
+/// Request to enable or disable feature flags of a service instance. Flags
+/// not mentioned in the request retain their state.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::SetFeatureFlags")]
+#[non_exhaustive]
+pub struct SetFeatureFlags {
+    /// Corresponding service instance ID.
+    pub instance_id: InstanceId,
+    /// New states of the flags keyed by the flag name (`true` for enabled flags).
+    #[protobuf_convert(with = "self::pb_flag_updates")]
+    pub flags: BTreeMap<String, bool>,
+}
+
+impl SetFeatureFlags {
+    /// Applies the request to the flags currently enabled for the instance.
+    pub(crate) fn apply(&self, flags: &mut FeatureFlags) {
+        for (name, &is_enabled) in &self.flags {
+            if is_enabled {
+                flags.enabled.insert(name.clone());
+            } else {
+                flags.enabled.remove(name);
+            }
+        }
+    }
+}
+
+/// Extension trait allowing services to check their feature flags.
+///
+/// Feature flags are managed by the supervisor via the [`SetFeatureFlags`] configuration
+/// change, which allows to enable new business logic simultaneously on all nodes
+/// without deploying a new artifact version. All flags are disabled by default.
+///
+/// [`SetFeatureFlags`]: struct.SetFeatureFlags.html
+///
+/// # Examples
+///
+/// ```
+/// use exonum::runtime::{ExecutionContext, ExecutionError};
+/// use exonum_derive::*;
+/// use exonum_rust_runtime::Service;
+/// use exonum_supervisor::FeatureFlagsExt;
+///
+/// #[exonum_interface(auto_ids)]
+/// pub trait Transactions<Ctx> {
+///     type Output;
+///     fn transfer(&self, context: Ctx, amount: u64) -> Self::Output;
+/// }
+///
+/// #[derive(Debug, ServiceDispatcher, ServiceFactory)]
+/// #[service_dispatcher(implements("Transactions"))]
+/// #[service_factory(artifact_name = "token", artifact_version = "1.0.0")]
+/// pub struct TokenService;
+///
+/// impl Transactions<ExecutionContext<'_>> for TokenService {
+///     type Output = Result<(), ExecutionError>;
+///
+///     fn transfer(&self, context: ExecutionContext<'_>, amount: u64) -> Self::Output {
+///         if context.feature_enabled("transfer-fees") {
+///             // Apply the new business logic.
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// impl Service for TokenService {}
+/// ```
+pub trait FeatureFlagsExt {
+    /// Checks whether the feature flag with the specified name is enabled
+    /// for the executing service.
+    fn feature_enabled(&self, name: &str) -> bool;
+}
+
+impl FeatureFlagsExt for ExecutionContext<'_> {
+    fn feature_enabled(&self, name: &str) -> bool {
+        let instance_id = self.instance().id;
+        self.data()
+            .service_schema::<Schema<_>, _>(SUPERVISOR_INSTANCE_ID)
+            .map_or(false, |schema| schema.feature_enabled(instance_id, name))
+    }
+}
+
+/// Enabled flags are encoded as a sorted sequence of names, so that the encoding
+/// of the flags is unambiguous.
+mod pb_enabled_flags {
+    use anyhow::ensure;
+
+    use std::collections::BTreeSet;
+
+    #[allow(clippy::needless_pass_by_value)] // required by `exonum-proto`
+    pub fn from_pb(pb: Vec<String>) -> anyhow::Result<BTreeSet<String>> {
+        ensure!(
+            pb.windows(2).all(|pair| pair[0] < pair[1]),
+            "Feature flags are not sorted or contain duplicates"
+        );
+        Ok(pb.into_iter().collect())
+    }
+
+    pub fn to_pb(value: &BTreeSet<String>) -> Vec<String> {
+        value.iter().cloned().collect()
+    }
+}
+
+/// Flag updates are encoded as a sequence sorted by the flag name, so that the encoding
+/// of the updates is unambiguous.
+mod pb_flag_updates {
+    use anyhow::ensure;
+
+    use std::collections::BTreeMap;
+
+    use crate::proto::FeatureFlag;
+
+    #[allow(clippy::needless_pass_by_value)] // required by `exonum-proto`
+    pub fn from_pb(pb: Vec<FeatureFlag>) -> anyhow::Result<BTreeMap<String, bool>> {
+        ensure!(
+            pb.windows(2).all(|pair| pair[0].name() < pair[1].name()),
+            "Feature flags are not sorted or contain duplicates"
+        );
+        Ok(pb
+            .into_iter()
+            .map(|mut flag| (flag.take_name(), flag.enabled()))
+            .collect())
+    }
+
+    pub fn to_pb(value: &BTreeMap<String, bool>) -> Vec<FeatureFlag> {
+        value
+            .iter()
+            .map(|(name, &is_enabled)| {
+                let mut flag = FeatureFlag::new();
+                flag.set_name(name.clone());
+                flag.set_enabled(is_enabled);
+                flag
+            })
+            .collect()
+    }
+}
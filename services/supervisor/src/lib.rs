@@ -14,12 +14,13 @@
 
 //! Supervisor is an [Exonum][exonum] service capable of the following activities:
 //!
-//! - Deploying service artifacts and unloading unused artifacts
+//! - Deploying service artifacts, deprecating and unloading unused artifacts
 //! - Instantiating services
 //! - Changing configuration of instantiated services
 //! - Changing a state of instantiated services: stopping, freezing, resuming,
 //!   and initiating data migrations
-//! - Changing consensus configuration
+//! - Changing consensus configuration, either as a whole or by [patches] modifying
+//!   only the specified parameters
 //!
 //! More information on the artifact / service lifecycle can be found in the
 //! documentation of [service lifecycle][docs:lifecycle] and the [supervisor][docs:supervisor].
@@ -35,6 +36,12 @@
 //! or for testing purposes (e.g., to test service configuration with `TestKit`).
 //! For a network with a low node confidence, consider using the decentralized mode.
 //!
+//! The mode can be switched by sending a [`ModeChangeRequest`] to the `change-mode` endpoint.
+//! The request is approved according to the rules of the current mode. To avoid approvals
+//! of in-flight activities under changing rules, the mode cannot be switched while there are
+//! pending deployments, migrations or configuration proposals. Applied mode changes
+//! are recorded in the audit log of the supervisor schema.
+//!
 //! # Interaction
 //!
 //! The intended way to interact with supervisor is the REST API. To be precise, requests should
@@ -46,8 +53,14 @@
 //!
 //! To deploy an artifact, one (within the "simple" mode) or majority (within the "decentralized" mode)
 //! of the nodes should receive a [`DeployRequest`] message through API. You may use the `seed`
-//! field of `DeployRequest` to retry the request with the same params. To check the current
-//! status of a request, you may use the `deploy-status` endpoint.
+//! field of `DeployRequest` to retry the request with the same params. Repeating a request
+//! for a pending deployment is a no-op, so it is safe to retry requests without special
+//! error handling. To check the current status of a request, you may use the `deploy-status`
+//! endpoint. Validators report checksums of the deployed artifact (if the runtime provides
+//! them) along with the deployment results; the deployment fails if the checksums differ.
+//! If the deployment legitimately takes longer than expected, validators may extend its deadline
+//! by sending a [`DeployDeadlineExtension`] message to the `extend-deploy-deadline` endpoint.
+//! The extension is approved by the same rules as the deploy request.
 //!
 //! To request a config change, one node should submit a [`ConfigPropose`] message through API.
 //! For the "simple" mode no more actions are required. For the "decentralized" mode the majority of the nodes
@@ -55,8 +68,76 @@
 //! The proposal initiator that receives the original [`ConfigPropose`] message must not vote for the configuration.
 //! This node votes for the configuration propose automatically.
 //!
-//! Starting, resuming or freezing a service, or unloading an artifact
+//! Besides validators, configuration changes may be proposed and voted for by holders
+//! of governance keys listed in the supervisor configuration (see [`SupervisorConfig`]).
+//! This allows to separate governance signers from infrastructure operators. Governance
+//! signers submit signed `propose_config_change` / `confirm_config_change` transactions
+//! directly; in the "decentralized" mode, governance keys are counted together with validators
+//! when determining the required number of votes. Proposals changing the consensus configuration
+//! or the validator set must additionally be approved by 2/3+1 validators, so governance keys
+//! alone cannot reach the quorum for such proposals.
+//!
+//! A validator may also delegate its votes to another key for a bounded period by sending
+//! a [`DelegateVotes`] transaction, e.g., while the holder of the validator key is unavailable.
+//! The delegate may propose and vote for configuration changes, and vote for deployments
+//! and migrations on behalf of the validator. The maximum delegation period is specified
+//! in the supervisor configuration; delegation is disabled by default.
+//!
+//! By default, a proposal can be voted for until its activation height (`actual_from`).
+//! A proposal may also specify an earlier `expires_at` height; if the proposal is not
+//! applied by then, it expires and frees the pending proposal slot for new proposals.
+//!
+//! A proposal may consist of several [stages] applied at different heights (e.g., stop
+//! a service at one height and resume it at a later height), all approved by a single vote.
+//! Changes of the subsequent stages are verified when the corresponding stage is applied;
+//! if a stage turns out to be invalid, the remaining stages are discarded. New proposals
+//! cannot be submitted until all stages of the previous proposal are applied.
+//!
+//! Starting, resuming or freezing a service, or unloading or deprecating an artifact
 //! are treated similarly to a configuration change and follow the same rules.
+//! A misbehaving service may also be stopped immediately, without a configuration proposal,
+//! by sending a [`StopService`] message to the `emergency-stop` endpoint. Such a stop
+//! is approved by the same rules as the deploy request.
+//! Deprecating an artifact is a softer alternative to unloading it: new services cannot
+//! be started from a deprecated artifact, but existing services continue running.
+//! A service may be frozen for a maintenance window by specifying the `resume_at` height
+//! in the freeze request; in this case, the supervisor resumes the service automatically
+//! at this height, without a separate resume proposal.
+//!
+//! Service instances may be labeled with free-form metadata (e.g., environment, owner team
+//! or contact) via the [`SetServiceMetadata`] configuration change. The metadata is stored
+//! by the supervisor and is returned by the `services` endpoint.
+//!
+//! Services may check named feature flags via the [`FeatureFlagsExt`] trait. Flags are
+//! disabled by default and can be toggled with the [`SetFeatureFlags`] configuration change.
+//! This allows to enable new business logic simultaneously on all nodes without deploying
+//! a new artifact version.
+//!
+//! Migrations of services may be restricted by [upgrade policies] specified in the supervisor
+//! configuration. For example, a policy may allow only semver-compatible migrations for
+//! a certain artifact, or additionally allow migrations to explicitly approved major versions.
+//! Migration requests violating the policy are rejected.
+//!
+//! A stopped service can be renamed via the [`RenameService`] configuration change.
+//! The service retains its identifier, and its data is moved to the new namespace
+//! as soon as the change is applied.
+//!
+//! A service can be made the default instance for an interface name (e.g., `token`) via
+//! the [`SetDefaultInstance`] configuration change. Services and clients may then address
+//! the service by the interface name, which is resolved from the dispatcher schema,
+//! instead of hard-coding its numeric identifier.
+//!
+//! Capabilities of services (e.g., whether a service may call other services) are determined
+//! by their artifact. By default, an artifact receives the capabilities provided by its runtime;
+//! these can be overridden via the [`SetArtifactCapabilities`] configuration change, for example,
+//! to sandbox artifacts from untrusted sources.
+//!
+//! Votes of validators are matched by hashes of the voted-for messages, so all supervisor
+//! transaction payloads must use the canonical Protobuf encoding (i.e., the one produced
+//! by `BinaryValue::to_bytes`). Payloads with a non-canonical encoding, such as ones with
+//! unknown fields or explicitly encoded default values, are rejected on transaction
+//! execution. Messages submitted through the API are re-encoded by the node and are thus
+//! always canonical.
 //!
 //! ## Migrations Management
 //!
@@ -77,6 +158,10 @@
 //! Violation of any of requirements listed above will result in a request failure without
 //! actual start of migration.
 //!
+//! If the `unload_superseded_artifact` flag is set in the request, the artifact associated
+//! with the service before the migration is unloaded automatically once the migration
+//! succeeds and the artifact is no longer used by any service.
+//!
 //! ## Migration Workflow
 //!
 //! Migration starts after the block with the request is committed and is performed asynchronously.
@@ -91,6 +176,11 @@
 //! In any other case (e.g. migration failure for at least one node, resulting state hash divergence,
 //! lack of report at the deadline height), migration is considered failed and rolled back.
 //!
+//! An ongoing migration can also be aborted by the validators before all the reports are collected,
+//! e.g., if the migration takes too long. To do this, validators should send a migration request
+//! to the `abort-migration` endpoint. Once the abort is approved according to the supervisor mode,
+//! the migration is considered failed and rolled back.
+//!
 //! After fixing the reason for migration failure, the migration attempt can be performed once again.
 //! It will require a different deadline height or a different seed, since `MigrationRequest` objects
 //! are considered unique and supervisor won't attempt to perform the same `MigrationRequest` again.
@@ -123,8 +213,22 @@
 //! [docs:supervisor]: https://exonum.com/doc/version/latest/advanced/supervisor/
 //! [docs:lifecycle]: https://exonum.com/doc/version/latest/architecture/service-lifecycle/
 //! [`DeployRequest`]: struct.DeployRequest.html
+//! [`DeployDeadlineExtension`]: struct.DeployDeadlineExtension.html
+//! [`DelegateVotes`]: struct.DelegateVotes.html
 //! [`ConfigPropose`]: struct.ConfigPropose.html
 //! [`ConfigVote`]: struct.ConfigVote.html
+//! [`SetServiceMetadata`]: struct.SetServiceMetadata.html
+//! [`RenameService`]: struct.RenameService.html
+//! [`SetDefaultInstance`]: struct.SetDefaultInstance.html
+//! [`SetArtifactCapabilities`]: struct.SetArtifactCapabilities.html
+//! [`StopService`]: struct.StopService.html
+//! [`ModeChangeRequest`]: struct.ModeChangeRequest.html
+//! [`FeatureFlagsExt`]: trait.FeatureFlagsExt.html
+//! [`SetFeatureFlags`]: struct.SetFeatureFlags.html
+//! [`SupervisorConfig`]: struct.SupervisorConfig.html
+//! [upgrade policies]: struct.ArtifactUpgradePolicy.html
+//! [stages]: struct.ConfigStage.html
+//! [patches]: struct.ConsensusConfigPatch.html
 
 #![warn(
     missing_debug_implementations,
@@ -142,45 +246,90 @@
 )]
 
 pub use self::{
-    configure::{Configure, CONFIGURE_INTERFACE_NAME},
+    configure::{Configure, TypedConfig, CONFIGURE_INTERFACE_NAME},
+    consensus_patch::{ChangeValidators, ConsensusConfigPatch, ValidatorReplacement},
     errors::{ArtifactError, CommonError, ConfigurationError, MigrationError, ServiceError},
     event_state::AsyncEventState,
-    migration_state::MigrationState,
+    events::{
+        ConfigProposalEvent, ConfigProposalStatus, DeployEvent, MigrationEvent, SupervisorEvent,
+    },
+    feature_flags::{FeatureFlags, FeatureFlagsExt, SetFeatureFlags},
+    migration_state::{MigrationState, ValidatorMigrationProgress},
     proto_structures::{
-        ConfigChange, ConfigProposalWithHash, ConfigPropose, ConfigVote, DeployRequest,
-        DeployResult, FreezeService, MigrationRequest, MigrationResult, ResumeService,
-        ServiceConfig, StartService, StopService, SupervisorConfig, UnloadArtifact,
+        ConfigChange, ConfigProposalWithHash, ConfigPropose, ConfigStage, ConfigVote,
+        DelegateVotes, DeployDeadlineExtension, DeployRequest, DeployResult, DeprecateArtifact,
+        FreezeService, MigrationProgress, MigrationRequest, MigrationResult, ModeChange,
+        ModeChangeRequest, ReadinessAttestation, RenameService, ResumeService, ServiceConfig,
+        ServiceMetadata, SetArtifactCapabilities, SetDefaultInstance, SetServiceMetadata,
+        StartService, StopService, SupervisorConfig, UnloadArtifact, VoteDelegation,
     },
+    readiness::{PendingUpgrade, UpgradeKind},
     schema::Schema,
+    timelock::{ConfigTimelock, TimelockedChange},
     transactions::SupervisorInterface,
+    upgrade_policy::{ArtifactUpgradePolicy, UpgradePolicyKind},
 };
 
 #[doc(hidden)] // Public for migration tests.
 pub use self::schema::SchemaImpl;
 
-use exonum::runtime::{ExecutionContext, ExecutionError, InstanceId, SUPERVISOR_INSTANCE_ID};
+#[doc(hidden)]
+pub mod _reexports {
+    //! Types necessary for the `TypedConfig` derive macro to work.
+
+    pub use anyhow::Error;
+    pub use exonum_merkledb::BinaryValue;
+}
+
+use exonum::{
+    helpers::{Height, ValidateInput},
+    runtime::{
+        ExecutionContext, ExecutionError, InstanceId, InstanceStatus, InterfaceMethods, MethodId,
+        SUPERVISOR_INSTANCE_ID,
+    },
+};
 use exonum_derive::{ServiceDispatcher, ServiceFactory};
 use exonum_merkledb::BinaryValue;
 use exonum_rust_runtime::{
     api::ServiceApiBuilder,
     spec::{Simple, Spec},
-    AfterCommitContext, Service,
+    AfterCommitContext, Service, ServiceDispatcher,
 };
 
-use crate::{configure::ConfigureMut, mode::Mode};
+use crate::{
+    api::{
+        metrics::Metrics,
+        websocket::{Notification, SharedState},
+    },
+    configure::ConfigureMut,
+    consensus_patch::KeyHandover,
+    mode::Mode,
+    timelock::check_timelocks,
+};
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
 
 pub mod api;
 pub mod mode;
 
 mod configure;
+mod consensus_patch;
 mod errors;
 mod event_state;
+mod events;
+mod feature_flags;
 mod migration_state;
 mod multisig;
 mod proto;
 mod proto_structures;
+mod readiness;
 mod schema;
+mod timelock;
 mod transactions;
+mod upgrade_policy;
 
 /// Error message emitted when the `Supervisor` is installed as a non-privileged service.
 const NOT_SUPERVISOR_MSG: &str = "`Supervisor` is installed as a non-privileged service. \
@@ -207,6 +356,58 @@ fn update_configs(
                     .set(config);
             }
 
+            ConfigChange::ConsensusPatch(patch) => {
+                log::trace!("Patching consensus configuration {:?}", patch);
+
+                // The patch was verified against the consensus config actual at the moment
+                // of the proposal, so we need to check the result again.
+                let config = patch.apply(&context.data().for_core().consensus_config());
+                config
+                    .validate()
+                    .map_err(ConfigurationError::malformed_propose)?;
+                context
+                    .supervisor_extensions()
+                    .writeable_core_schema()
+                    .consensus_config_entry()
+                    .set(config);
+            }
+
+            ConfigChange::ChangeValidators(change) => {
+                log::trace!("Changing validators {:?}", change);
+
+                // As with patches, the change should be checked against the actual config.
+                let core_schema = context.data().for_core();
+                let old_config = core_schema.consensus_config();
+                let expires_at = Height(
+                    core_schema
+                        .next_height()
+                        .0
+                        .saturating_add(change.grace_period),
+                );
+                let config = change
+                    .apply(&old_config)
+                    .map_err(ConfigurationError::malformed_propose)?;
+                config
+                    .validate()
+                    .map_err(ConfigurationError::malformed_propose)?;
+
+                if change.grace_period > 0 {
+                    let mut schema = SchemaImpl::new(context.service_data());
+                    for (old_key, new_service_key) in change.service_key_handovers(&old_config) {
+                        let handover = KeyHandover {
+                            new_service_key,
+                            expires_at,
+                        };
+                        schema.key_handovers.put(&old_key, handover);
+                    }
+                }
+                context
+                    .supervisor_extensions()
+                    .writeable_core_schema()
+                    .consensus_config_entry()
+                    .set(config);
+            }
+
             ConfigChange::Service(config) => {
                 log::trace!(
                     "Updating service instance configuration, instance ID is {}",
@@ -261,6 +462,9 @@ fn update_configs(
                 context
                     .supervisor_extensions()
                     .initiate_stopping_service(stop_service.instance_id)?;
+                SchemaImpl::new(context.service_data())
+                    .scheduled_resumes
+                    .remove(&stop_service.instance_id);
             }
 
             ConfigChange::FreezeService(freeze_service) => {
@@ -279,6 +483,20 @@ fn update_configs(
                 context
                     .supervisor_extensions()
                     .initiate_freezing_service(freeze_service.instance_id)?;
+
+                let mut schema = SchemaImpl::new(context.service_data());
+                if let Some(resume_at) = freeze_service.resume_at {
+                    log::trace!(
+                        "Service with name {} is scheduled to resume at height {}",
+                        instance.spec.name,
+                        resume_at
+                    );
+                    schema
+                        .scheduled_resumes
+                        .put(&freeze_service.instance_id, resume_at);
+                } else {
+                    schema.scheduled_resumes.remove(&freeze_service.instance_id);
+                }
             }
 
             ConfigChange::ResumeService(resume_service) => {
@@ -297,6 +515,9 @@ fn update_configs(
                 context
                     .supervisor_extensions()
                     .initiate_resuming_service(resume_service.instance_id, resume_service.params)?;
+                SchemaImpl::new(context.service_data())
+                    .scheduled_resumes
+                    .remove(&resume_service.instance_id);
             }
 
             ConfigChange::UnloadArtifact(unload_artifact) => {
@@ -305,6 +526,83 @@ fn update_configs(
                     .supervisor_extensions()
                     .unload_artifact(&unload_artifact.artifact_id)?;
             }
+
+            ConfigChange::DeprecateArtifact(deprecate_artifact) => {
+                log::trace!("Deprecating artifact `{}`", deprecate_artifact.artifact_id);
+                context
+                    .supervisor_extensions()
+                    .deprecate_artifact(&deprecate_artifact.artifact_id)?;
+            }
+
+            ConfigChange::SetServiceMetadata(SetServiceMetadata {
+                instance_id,
+                metadata,
+            }) => {
+                log::trace!(
+                    "Setting metadata of service with ID {}: {:?}",
+                    instance_id,
+                    metadata.entries
+                );
+                let mut schema = SchemaImpl::new(context.service_data());
+                if metadata.is_empty() {
+                    schema.service_metadata.remove(&instance_id);
+                } else {
+                    schema.service_metadata.put(&instance_id, metadata);
+                }
+            }
+
+            ConfigChange::SetFeatureFlags(set_flags) => {
+                log::trace!(
+                    "Setting feature flags of service with ID {}: {:?}",
+                    set_flags.instance_id,
+                    set_flags.flags
+                );
+                let mut schema = SchemaImpl::new(context.service_data());
+                let feature_flags = &mut schema.public.feature_flags;
+                let mut flags = feature_flags
+                    .get(&set_flags.instance_id)
+                    .unwrap_or_default();
+                set_flags.apply(&mut flags);
+                if flags.enabled.is_empty() {
+                    feature_flags.remove(&set_flags.instance_id);
+                } else {
+                    feature_flags.put(&set_flags.instance_id, flags);
+                }
+            }
+
+            ConfigChange::RenameService(rename_service) => {
+                log::trace!(
+                    "Renaming service with ID {} to {}",
+                    rename_service.instance_id,
+                    rename_service.new_name
+                );
+                context
+                    .supervisor_extensions()
+                    .rename_service(rename_service.instance_id, &rename_service.new_name)?;
+            }
+
+            ConfigChange::SetDefaultInstance(set_default) => {
+                log::trace!(
+                    "Setting service with ID {} as the default instance for `{}`",
+                    set_default.instance_id,
+                    set_default.interface
+                );
+                context
+                    .supervisor_extensions()
+                    .set_default_instance(&set_default.interface, set_default.instance_id)?;
+            }
+
+            ConfigChange::SetArtifactCapabilities(set_capabilities) => {
+                log::trace!(
+                    "Setting capabilities {:?} for artifact {}",
+                    set_capabilities.capabilities,
+                    set_capabilities.artifact
+                );
+                context.supervisor_extensions().set_artifact_capabilities(
+                    &set_capabilities.artifact,
+                    set_capabilities.capabilities,
+                )?;
+            }
         }
     }
     Ok(())
@@ -350,7 +648,11 @@ fn assign_instance_id(context: &ExecutionContext<'_>) -> InstanceId {
     "SupervisorInterface",
     raw = "Configure<Params = SupervisorConfig>"
 ))]
-#[service_factory(proto_sources = "proto", artifact_name = "exonum-supervisor")]
+#[service_factory(
+    proto_sources = "proto",
+    artifact_name = "exonum-supervisor",
+    service_constructor = "Self::new_instance"
+)]
 pub struct Supervisor;
 
 impl Supervisor {
@@ -360,15 +662,13 @@ impl Supervisor {
     /// Creates a configuration for a simple `Supervisor`.
     #[must_use]
     pub const fn simple_config() -> SupervisorConfig {
-        SupervisorConfig { mode: Mode::Simple }
+        SupervisorConfig::new(Mode::Simple)
     }
 
     /// Creates a configuration for a decentralized `Supervisor`.
     #[must_use]
     pub const fn decentralized_config() -> SupervisorConfig {
-        SupervisorConfig {
-            mode: Mode::Decentralized,
-        }
+        SupervisorConfig::new(Mode::Decentralized)
     }
 
     /// Creates a deploy spec for a builtin `Supervisor` instance with
@@ -391,6 +691,71 @@ impl Supervisor {
     pub fn builtin_instance(config: SupervisorConfig) -> Spec<Self, Simple> {
         Spec::new(Self).with_instance(SUPERVISOR_INSTANCE_ID, Self::NAME, config)
     }
+
+    #[allow(clippy::unused_self)]
+    fn new_instance(&self) -> Box<dyn Service> {
+        Box::new(SupervisorService::default())
+    }
+}
+
+/// Supervisor service instance. In addition to the stateless `Supervisor` logic,
+/// it holds the node-local state of the `WebSocket` event subscriptions and metrics.
+#[derive(Debug, Default)]
+struct SupervisorService {
+    shared_state: SharedState,
+    metrics: Arc<Mutex<Metrics>>,
+}
+
+impl ServiceDispatcher for SupervisorService {
+    fn call(
+        &self,
+        context: ExecutionContext<'_>,
+        method: MethodId,
+        payload: &[u8],
+    ) -> Result<(), ExecutionError> {
+        Supervisor.call(context, method, payload)
+    }
+
+    fn interface_methods(&self) -> InterfaceMethods {
+        Supervisor.interface_methods()
+    }
+}
+
+impl Service for SupervisorService {
+    fn initialize(
+        &self,
+        context: ExecutionContext<'_>,
+        params: Vec<u8>,
+    ) -> Result<(), ExecutionError> {
+        Supervisor.initialize(context, params)
+    }
+
+    fn before_transactions(&self, context: ExecutionContext<'_>) -> Result<(), ExecutionError> {
+        Supervisor.before_transactions(context)
+    }
+
+    fn after_transactions(&self, context: ExecutionContext<'_>) -> Result<(), ExecutionError> {
+        Supervisor.after_transactions(context)
+    }
+
+    fn after_commit(&self, context: AfterCommitContext<'_>) {
+        let notification = Notification::load(context.service_data(), context.height());
+        self.metrics.lock().expect("Cannot lock `Metrics`").update(
+            context.service_data(),
+            context.height(),
+            notification.as_ref(),
+        );
+        Supervisor.after_commit(context);
+        if let Some(notification) = notification {
+            self.shared_state.broadcast(notification);
+        }
+    }
+
+    fn wire_api(&self, builder: &mut ServiceApiBuilder) {
+        Supervisor.wire_api(builder);
+        api::websocket::wire(self.shared_state.get_ref(), builder.private_scope());
+        api::metrics::wire(Arc::clone(&self.metrics), builder.private_scope());
+    }
 }
 
 impl Service for Supervisor {
@@ -409,15 +774,26 @@ impl Service for Supervisor {
 
         let mut schema = SchemaImpl::new(context.service_data());
         schema.public.configuration.set(config);
+        // New blockchains have no governance state to repair.
+        schema.governance_repaired_at.set(Height(0));
 
         Ok(())
     }
 
     fn before_transactions(&self, mut context: ExecutionContext<'_>) -> Result<(), ExecutionError> {
+        Self::clear_events(&context);
+        Self::remove_expired_key_handovers(&context);
+        Self::remove_expired_vote_delegations(&context);
+        Self::remove_stale_stop_votes(&context);
         Self::remove_outdated_deployments(&context);
         Self::remove_outdated_config_proposal(&context);
         Self::flush_completed_migrations(&mut context)?;
+        Self::unload_superseded_artifacts(&mut context)?;
         Self::remove_outdated_migrations(&mut context)?;
+        Self::repair_governance_state(&context);
+        Self::resume_scheduled_services(&mut context)?;
+        Self::apply_config_stage(&mut context)?;
+        Self::checkpoint_call_errors(&mut context);
         Ok(())
     }
 
@@ -426,18 +802,59 @@ impl Service for Supervisor {
         let configuration = schema.supervisor_config();
         let core_schema = context.data().for_core();
         let next_height = core_schema.next_height();
-        let validator_count = core_schema.consensus_config().validator_keys.len();
+        // Governance keys vote for configuration changes alongside validators.
+        let validator_keys = core_schema.consensus_config().validator_keys;
+        let governance_only_keys = configuration
+            .governance_keys
+            .iter()
+            .filter(|key| validator_keys.iter().all(|keys| keys.service_key != **key))
+            .count();
+        let voter_count = validator_keys.len() + governance_only_keys;
 
         // Check if we should apply a new config.
         let entry = schema.public.pending_proposal.get();
         if let Some(entry) = entry {
-            if entry.config_propose.actual_from == next_height {
-                // Config should be applied at the next height.
-                if configuration.mode.config_approved(
+            let mut approved = configuration.mode.config_approved(
+                &entry.propose_hash,
+                &schema.config_confirms,
+                voter_count,
+            );
+            // Governance keys alone cannot change the consensus configuration
+            // or the validator set.
+            if approved && entry.config_propose.affects_consensus() {
+                approved = configuration.mode.consensus_change_approved(
                     &entry.propose_hash,
                     &schema.config_confirms,
-                    validator_count,
-                ) {
+                    validator_keys.iter().map(|keys| keys.service_key),
+                );
+            }
+            // Record the approval height, which is used to enforce timelocks.
+            if approved && !schema.config_approved_at.contains(&entry.propose_hash) {
+                schema
+                    .config_approved_at
+                    .put(&entry.propose_hash, core_schema.height());
+            }
+
+            if approved && entry.config_propose.actual_from == next_height {
+                // Config should be applied at the next height.
+                let approved_at = schema
+                    .config_approved_at
+                    .get(&entry.propose_hash)
+                    .unwrap_or_else(|| core_schema.height());
+                let timelocks_satisfied = check_timelocks(
+                    &configuration,
+                    &entry.config_propose,
+                    &validator_keys,
+                    approved_at,
+                );
+                if let Err(err) = timelocks_satisfied {
+                    // The proposal is not applied and expires at the next height.
+                    log::warn!(
+                        "Configuration proposal {:?} was approved too late: {}",
+                        entry.propose_hash,
+                        err
+                    );
+                } else {
                     log::info!(
                         "New configuration has been accepted: {:?}",
                         entry.config_propose
@@ -448,10 +865,22 @@ impl Service for Supervisor {
                     // However, it won't be actual anymore and will be removed at the beginning
                     // of the next height (within `before_transactions` hook).
                     schema.public.pending_proposal.remove();
+                    schema.config_confirms.reset(&entry.propose_hash);
+                    schema.config_approved_at.remove(&entry.propose_hash);
+                    schema.events.push(SupervisorEvent::config_proposal(
+                        entry.propose_hash,
+                        ConfigProposalStatus::Applied,
+                    ));
+                    // Subsequent stages of the proposal (if any) are applied
+                    // within `before_transactions` hook at the corresponding heights.
+                    let changes = entry.config_propose.changes.clone();
+                    if !entry.config_propose.stages.is_empty() {
+                        schema.staged_proposal.set(entry);
+                    }
                     drop(schema);
 
                     // Perform the application of configs.
-                    update_configs(&mut context, entry.config_propose.changes)?;
+                    update_configs(&mut context, changes)?;
                 }
             }
         }
@@ -470,6 +899,15 @@ impl Service for Supervisor {
 }
 
 impl Supervisor {
+    /// Removes events emitted within the previous block.
+    fn clear_events(context: &ExecutionContext<'_>) {
+        let mut schema = SchemaImpl::new(context.service_data());
+        // Clear the index only if there are entities because it's not a cheap operation.
+        if !schema.events.is_empty() {
+            schema.events.clear();
+        }
+    }
+
     /// Removes deployments for which deadline height is already exceeded.
     fn remove_outdated_deployments(context: &ExecutionContext<'_>) {
         let mut schema = SchemaImpl::new(context.service_data());
@@ -480,19 +918,191 @@ impl Supervisor {
         let requests_to_remove = schema
             .pending_deployments
             .values()
-            .filter(|request| request.deadline_height <= height)
+            .filter(|request| schema.deploy_deadline(request) <= height)
             .collect::<Vec<_>>();
 
         for request in requests_to_remove {
             schema.pending_deployments.remove(&request.artifact);
+            schema.deploy_requests.reset(&request);
             if let Some(AsyncEventState::Pending) = schema.deploy_states.get(&request) {
                 // If state is marked as pending, change it to failed as well.
-                schema.deploy_states.put(&request, AsyncEventState::Timeout);
+                schema.set_deploy_state(&request, AsyncEventState::Timeout);
             }
             log::trace!("Removed outdated deployment request {:?}", request);
         }
     }
 
+    /// Resumes frozen services for which the scheduled resume height has come.
+    fn resume_scheduled_services(context: &mut ExecutionContext<'_>) -> Result<(), ExecutionError> {
+        let next_height = context.data().for_core().next_height();
+        let mut schema = SchemaImpl::new(context.service_data());
+        let services_to_resume = schema
+            .scheduled_resumes
+            .iter()
+            .filter(|(_, resume_at)| *resume_at <= next_height)
+            .map(|(instance_id, _)| instance_id)
+            .collect::<Vec<_>>();
+        for instance_id in &services_to_resume {
+            schema.scheduled_resumes.remove(instance_id);
+        }
+        drop(schema);
+
+        for instance_id in services_to_resume {
+            let instance = context.data().for_dispatcher().get_instance(instance_id);
+            // The service may have been stopped or otherwise transitioned after being frozen.
+            let can_be_resumed = instance.map_or(false, |instance| {
+                instance.pending_status.is_none() && instance.status == Some(InstanceStatus::Frozen)
+            });
+            if !can_be_resumed {
+                log::warn!(
+                    "Skipped scheduled resume of service with ID {}, which is not frozen",
+                    instance_id
+                );
+                continue;
+            }
+
+            log::trace!("Resuming service with ID {} as scheduled", instance_id);
+            context
+                .supervisor_extensions()
+                .initiate_resuming_service(instance_id, ())?;
+        }
+        Ok(())
+    }
+
+    /// Removes handovers of service keys with an expired grace period.
+    fn remove_expired_key_handovers(context: &ExecutionContext<'_>) {
+        let next_height = context.data().for_core().next_height();
+        let mut schema = SchemaImpl::new(context.service_data());
+        let expired_keys: Vec<_> = schema
+            .key_handovers
+            .iter()
+            .filter(|(_, handover)| handover.expires_at <= next_height)
+            .map(|(old_key, _)| old_key)
+            .collect();
+        for old_key in &expired_keys {
+            schema.key_handovers.remove(old_key);
+        }
+    }
+
+    /// Removes outdated votes for emergency stops and votes for services which
+    /// cannot be stopped anymore.
+    fn remove_stale_stop_votes(context: &ExecutionContext<'_>) {
+        let next_height = context.data().for_core().next_height();
+        let dispatcher = context.data().for_dispatcher();
+        let mut schema = SchemaImpl::new(context.service_data());
+        schema.remove_stale_stop_votes(next_height, |instance_id| {
+            dispatcher
+                .get_instance(instance_id)
+                .and_then(|instance| instance.status)
+                .map_or(false, |status| status.can_be_stopped())
+        });
+    }
+
+    /// Removes delegations of validator votes with an expired period.
+    fn remove_expired_vote_delegations(context: &ExecutionContext<'_>) {
+        let next_height = context.data().for_core().next_height();
+        let mut schema = SchemaImpl::new(context.service_data());
+        let expired_delegates: Vec<_> = schema
+            .vote_delegations
+            .iter()
+            .filter(|(_, delegation)| delegation.expires_at <= next_height)
+            .map(|(delegate, _)| delegate)
+            .collect();
+        for delegate in &expired_delegates {
+            schema.vote_delegations.remove(delegate);
+        }
+    }
+
+    /// Drops call errors in blocks outside the retention period specified
+    /// in the supervisor configuration.
+    fn checkpoint_call_errors(context: &mut ExecutionContext<'_>) {
+        let retention = SchemaImpl::new(context.service_data())
+            .supervisor_config()
+            .call_errors_retention;
+        if retention == 0 {
+            return;
+        }
+
+        // Errors are retained for the latest `retention` committed blocks.
+        let next_height = context.data().for_core().next_height();
+        if let Some(retain_from) = next_height.0.checked_sub(retention) {
+            let checkpoint = context
+                .supervisor_extensions()
+                .writeable_core_schema()
+                .checkpoint_call_errors(Height(retain_from));
+            log::trace!("Updated call errors checkpoint: {:?}", checkpoint);
+        }
+    }
+
+    /// Applies the stage of a multi-stage configuration proposal scheduled for the current
+    /// height. If a stage cannot be applied, the remaining stages of the proposal are discarded.
+    fn apply_config_stage(context: &mut ExecutionContext<'_>) -> Result<(), ExecutionError> {
+        let next_height = context.data().for_core().next_height();
+        let mut schema = SchemaImpl::new(context.service_data());
+        let mut entry = if let Some(entry) = schema.staged_proposal.get() {
+            entry
+        } else {
+            return Ok(());
+        };
+        // Stored proposals always have at least one stage which is not applied yet.
+        let stage = entry.config_propose.stages.remove(0);
+        if stage.actual_from > next_height {
+            return Ok(());
+        }
+
+        let is_verified = if stage.actual_from < next_height {
+            // The stage was not applied at its height, e.g., because of an error
+            // during its application.
+            log::warn!(
+                "Stage of config proposal {:?} scheduled at height {} was not applied",
+                entry.propose_hash,
+                stage.actual_from
+            );
+            false
+        } else {
+            drop(schema);
+            let verification =
+                Self::verify_config_changes(context, &stage.changes, stage.actual_from);
+            schema = SchemaImpl::new(context.service_data());
+            verification
+                .map_err(|err| {
+                    log::warn!(
+                        "Stage of config proposal {:?} at height {} is invalid: {}",
+                        entry.propose_hash,
+                        stage.actual_from,
+                        err
+                    );
+                })
+                .is_ok()
+        };
+
+        if !is_verified {
+            schema.staged_proposal.remove();
+            schema.events.push(SupervisorEvent::config_proposal(
+                entry.propose_hash,
+                ConfigProposalStatus::StagesAborted,
+            ));
+            return Ok(());
+        }
+
+        log::info!(
+            "Stage of config proposal {:?} has been accepted: {:?}",
+            entry.propose_hash,
+            stage.changes
+        );
+        schema.events.push(SupervisorEvent::config_proposal(
+            entry.propose_hash,
+            ConfigProposalStatus::StageApplied,
+        ));
+        if entry.config_propose.stages.is_empty() {
+            schema.staged_proposal.remove();
+        } else {
+            schema.staged_proposal.set(entry);
+        }
+        drop(schema);
+        update_configs(context, stage.changes)
+    }
+
     /// Removes pending config proposal if it's outdated.
     fn remove_outdated_config_proposal(context: &ExecutionContext<'_>) {
         let mut schema = SchemaImpl::new(context.service_data());
@@ -501,14 +1111,40 @@ impl Supervisor {
 
         let entry = schema.public.pending_proposal.get();
         if let Some(entry) = entry {
-            if entry.config_propose.actual_from <= height {
+            if entry.config_propose.deadline_height() <= height {
                 // Remove pending config proposal for which deadline was exceeded.
                 log::trace!("Removed outdated config proposal");
                 schema.public.pending_proposal.remove();
+                schema.config_confirms.reset(&entry.propose_hash);
+                schema.config_approved_at.remove(&entry.propose_hash);
+                schema.events.push(SupervisorEvent::config_proposal(
+                    entry.propose_hash,
+                    ConfigProposalStatus::Expired,
+                ));
             }
         }
     }
 
+    /// Removes stale votes left by the previous versions of the service, which did not clean
+    /// them up (e.g., votes for config proposals interrupted by a panic during application).
+    /// The repair is performed once, within the first block processed by the current version.
+    fn repair_governance_state(context: &ExecutionContext<'_>) {
+        let mut schema = SchemaImpl::new(context.service_data());
+        if schema.governance_repaired_at.exists() {
+            return;
+        }
+
+        let height = context.data().for_core().height();
+        let removed = schema.remove_stale_votes(height);
+        schema.governance_repaired_at.set(height);
+        if removed > 0 {
+            log::info!(
+                "Removed {} stale governance votes left from interrupted flows",
+                removed
+            );
+        }
+    }
+
     /// Goes through pending deployments, chooses ones that we're not confirmed by our node
     /// and starts the local deployment routine for them.
     fn process_unconfirmed_deployments(context: &mut AfterCommitContext<'_>) {
@@ -544,7 +1180,9 @@ impl Supervisor {
             extensions.start_deploy(artifact, spec, move |result| {
                 if let Some(tx_sender) = tx_sender {
                     log::trace!("Sending deployment result report {:?}", unconfirmed_request);
-                    let confirmation = DeployResult::new(unconfirmed_request, result);
+                    let checksum = result.as_ref().ok().copied().flatten();
+                    let mut confirmation = DeployResult::new(unconfirmed_request, result.map(drop));
+                    confirmation.artifact_checksum = checksum;
                     // TODO Investigate how to use async operations in the
                     // `after_commit` hook [ECR-4295]
                     if let Err(e) = tx_sender.blocking().report_deploy_result((), confirmation) {
@@ -595,12 +1233,45 @@ impl Supervisor {
             let instance = transactions::get_instance_by_name(context, request.service.as_ref())
                 .expect("BUG: Migration succeed, but there is no such instance in core");
             state.update(AsyncEventState::Succeed, instance.data_version().clone());
-            schema.migration_states.put(&request, state);
+            schema.set_migration_state(&request, state);
+            if request.unload_superseded_artifact {
+                schema.superseded_artifacts.insert(&instance.spec.artifact);
+            }
         }
 
         Ok(())
     }
 
+    /// Unloads artifacts superseded by successful migrations once they are no longer used
+    /// by any service.
+    fn unload_superseded_artifacts(
+        context: &mut ExecutionContext<'_>,
+    ) -> Result<(), ExecutionError> {
+        let artifacts = SchemaImpl::new(context.service_data())
+            .superseded_artifacts
+            .iter()
+            .collect::<Vec<_>>();
+
+        for artifact in artifacts {
+            let dispatcher_data = context.data().for_dispatcher();
+            let is_deployed = dispatcher_data
+                .get_artifact(&artifact)
+                .map_or(false, |state| state.status.is_deployed());
+            if is_deployed {
+                if dispatcher_data.check_unloading_artifact(&artifact).is_err() {
+                    // The artifact is still used by some services; retry in the next block.
+                    continue;
+                }
+                log::trace!("Unloading superseded artifact `{}`", artifact);
+                context.supervisor_extensions().unload_artifact(&artifact)?;
+            }
+            SchemaImpl::new(context.service_data())
+                .superseded_artifacts
+                .remove(&artifact);
+        }
+        Ok(())
+    }
+
     /// Rollbacks and removes migrations for which deadline height is already exceeded.
     fn remove_outdated_migrations(
         context: &mut ExecutionContext<'_>,
@@ -623,12 +1294,14 @@ impl Supervisor {
         for request in requests_to_remove {
             let mut schema = SchemaImpl::new(context.service_data());
             schema.pending_migrations.remove(&request);
+            schema.migration_requests.reset(&request);
+            schema.migration_abort_requests.reset(&request);
 
             let mut state = schema.migration_state_unchecked(&request);
             if state.is_pending() {
                 // If state is marked as pending, change it to failed as well.
                 state.fail(AsyncEventState::Timeout);
-                schema.migration_states.put(&request, state);
+                schema.set_migration_state(&request, state);
 
                 // Then, rollback the migration.
                 drop(schema);
@@ -706,9 +1379,38 @@ impl Configure for Supervisor {
     fn verify_config(
         &self,
         _context: ExecutionContext<'_>,
-        _params: Self::Params,
+        params: Self::Params,
     ) -> Result<(), ExecutionError> {
-        // If config was decoded, it's OK.
+        // Check that each artifact is covered by at most one upgrade policy.
+        let mut artifacts = HashSet::new();
+        for policy in &params.upgrade_policies {
+            if !artifacts.insert((policy.runtime_id, policy.artifact_name.as_str())) {
+                let msg = format!(
+                    "Discarded multiple upgrade policies for artifact `{}:{}`",
+                    policy.runtime_id, policy.artifact_name
+                );
+                return Err(ConfigurationError::malformed_propose(msg));
+            }
+        }
+
+        let mut governance_keys = HashSet::new();
+        for key in &params.governance_keys {
+            if !governance_keys.insert(key) {
+                let msg = format!("Discarded duplicate governance key {:?}", key);
+                return Err(ConfigurationError::malformed_propose(msg));
+            }
+        }
+
+        let mut timelocked_changes = HashSet::new();
+        for timelock in &params.timelocks {
+            if !timelocked_changes.insert(timelock.change) {
+                let msg = format!(
+                    "Discarded multiple timelocks for {:?} changes",
+                    timelock.change
+                );
+                return Err(ConfigurationError::malformed_propose(msg));
+            }
+        }
         Ok(())
     }
 
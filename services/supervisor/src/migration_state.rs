@@ -13,7 +13,8 @@
 // limitations under the License.
 
 use exonum::{
-    crypto::Hash,
+    crypto::{Hash, PublicKey},
+    helpers::Height,
     runtime::{versioning::Version, ExecutionError},
 };
 use exonum_derive::BinaryValue;
@@ -44,6 +45,27 @@ pub struct MigrationState {
     #[protobuf_convert(with = "exonum::helpers::pb_optional_hash")]
     #[serde(skip)]
     pub(crate) reference_state_hash: Option<Hash>,
+
+    /// Latest progress reports of validators, in the order of the first report.
+    /// Reporting progress is optional, so some validators may be absent from the list.
+    #[serde(default)]
+    pub progress: Vec<ValidatorMigrationProgress>,
+}
+
+/// Migration progress reported by a validator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(ProtobufConvert, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::ValidatorMigrationProgress")]
+#[non_exhaustive]
+pub struct ValidatorMigrationProgress {
+    /// Service key of the validator.
+    pub validator: PublicKey,
+    /// Number of processed keys.
+    pub processed_keys: u64,
+    /// Percentage of completion (from 0 to 100).
+    pub percentage: u32,
+    /// Height of the block containing the progress report.
+    pub height: Height,
 }
 
 impl MigrationState {
@@ -54,6 +76,20 @@ impl MigrationState {
             inner,
             version,
             reference_state_hash: None,
+            progress: Vec::new(),
+        }
+    }
+
+    /// Records the progress reported by a validator, replacing its previous report.
+    pub(crate) fn report_progress(&mut self, progress: ValidatorMigrationProgress) {
+        let entry = self
+            .progress
+            .iter_mut()
+            .find(|entry| entry.validator == progress.validator);
+        if let Some(entry) = entry {
+            *entry = progress;
+        } else {
+            self.progress.push(progress);
         }
     }
 
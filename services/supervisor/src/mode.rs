@@ -22,14 +22,20 @@
 //!   and config proposals should be approved by at least (2/3+1) validators.
 
 use anyhow::format_err;
-use exonum::{crypto::Hash, helpers::byzantine_quorum};
+use exonum::{
+    crypto::{Hash, PublicKey},
+    helpers::byzantine_quorum,
+};
 use exonum_merkledb::access::Access;
 use exonum_proto::ProtobufConvert;
 use serde::{Deserialize, Serialize};
 
 use std::{fmt, str::FromStr};
 
-use super::{multisig::MultisigIndex, proto, DeployRequest, MigrationRequest};
+use super::{
+    multisig::MultisigIndex, proto, DeployDeadlineExtension, DeployRequest, MigrationRequest,
+    ModeChangeRequest, StopService,
+};
 
 /// Supervisor operating mode.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -85,12 +91,75 @@ impl Mode {
         }
     }
 
-    /// Checks whether config can be applied for the network.
+    /// Checks whether the deadline of a pending deployment should be extended.
+    pub fn deadline_extension_approved<T: Access>(
+        self,
+        extension: &DeployDeadlineExtension,
+        extension_requests: &MultisigIndex<T, DeployDeadlineExtension>,
+        validators: usize,
+    ) -> bool {
+        match self {
+            Self::Simple => {
+                // For simple supervisor request from 1 validator is enough.
+                extension_requests.confirmations(extension) >= 1
+            }
+            Self::Decentralized => {
+                // Approve extension if 2/3+1 validators confirmed it.
+                let confirmations = extension_requests.confirmations(extension);
+                confirmations >= byzantine_quorum(validators)
+            }
+        }
+    }
+
+    /// Checks whether the emergency stop of a service should be performed.
+    pub fn emergency_stop_approved<T: Access>(
+        self,
+        request: &StopService,
+        stop_requests: &MultisigIndex<T, StopService>,
+        validators: usize,
+    ) -> bool {
+        match self {
+            Self::Simple => {
+                // For simple supervisor request from 1 validator is enough.
+                stop_requests.confirmations(request) >= 1
+            }
+            Self::Decentralized => {
+                // Approve the stop if 2/3+1 validators confirmed it.
+                let confirmations = stop_requests.confirmations(request);
+                confirmations >= byzantine_quorum(validators)
+            }
+        }
+    }
+
+    /// Checks whether the supervisor should switch to another mode. The approval rules
+    /// of the current mode are applied.
+    pub fn mode_change_approved<T: Access>(
+        self,
+        request: &ModeChangeRequest,
+        mode_change_requests: &MultisigIndex<T, ModeChangeRequest>,
+        validators: usize,
+    ) -> bool {
+        match self {
+            Self::Simple => {
+                // For simple supervisor request from 1 validator is enough.
+                mode_change_requests.confirmations(request) >= 1
+            }
+            Self::Decentralized => {
+                // Approve the mode change if 2/3+1 validators confirmed it.
+                let confirmations = mode_change_requests.confirmations(request);
+                confirmations >= byzantine_quorum(validators)
+            }
+        }
+    }
+
+    /// Checks whether config can be applied for the network. `voters` is the number
+    /// of parties allowed to vote for the config, i.e., validators and holders
+    /// of governance keys.
     pub fn config_approved<T: Access>(
         self,
         config_hash: &Hash,
         config_confirms: &MultisigIndex<T, Hash>,
-        validators: usize,
+        voters: usize,
     ) -> bool {
         match self {
             Self::Simple => {
@@ -98,8 +167,40 @@ impl Mode {
                 config_confirms.confirmations(config_hash) >= 1
             }
             Self::Decentralized => {
-                // Apply pending config if 2/3+1 validators voted for it.
+                // Apply pending config if 2/3+1 voters voted for it.
                 let confirmations = config_confirms.confirmations(config_hash);
+                confirmations >= byzantine_quorum(voters)
+            }
+        }
+    }
+
+    /// Checks whether a config changing the consensus configuration or the validator set
+    /// is approved by validators. In the "decentralized" mode, such configs require votes
+    /// of 2/3+1 validators in addition to the overall quorum checked by
+    /// [`config_approved`](#method.config_approved); votes of governance keys
+    /// not belonging to validators are not taken into account.
+    pub fn consensus_change_approved<T: Access>(
+        self,
+        config_hash: &Hash,
+        config_confirms: &MultisigIndex<T, Hash>,
+        validator_keys: impl IntoIterator<Item = PublicKey>,
+    ) -> bool {
+        match self {
+            Self::Simple => {
+                // For simple supervisor one confirmation (from us) is enough.
+                config_confirms.confirmations(config_hash) >= 1
+            }
+            Self::Decentralized => {
+                // Apply pending config if 2/3+1 validators voted for it.
+                let confirmed_keys = config_confirms.confirmed_keys(config_hash);
+                let mut validators = 0;
+                let mut confirmations = 0;
+                for key in validator_keys {
+                    validators += 1;
+                    if confirmed_keys.contains(&key) {
+                        confirmations += 1;
+                    }
+                }
                 confirmations >= byzantine_quorum(validators)
             }
         }
@@ -25,7 +25,7 @@ use exonum::{
 use exonum_derive::FromAccess;
 
 use std::{
-    borrow::Cow,
+    borrow::{Borrow, Cow},
     collections::BTreeSet,
     io::{Cursor, Write},
     mem,
@@ -66,6 +66,11 @@ where
     pub fn confirmations(&self, id: &V) -> usize {
         self.index.get(id).map_or(0, |confirms| confirms.0.len())
     }
+
+    /// Returns keys of the authors who confirmed the specified value.
+    pub fn confirmed_keys(&self, id: &V) -> BTreeSet<PublicKey> {
+        self.index.get(id).unwrap_or_default().0
+    }
 }
 
 impl<T, V> MultisigIndex<T, V>
@@ -82,6 +87,26 @@ where
         len
     }
 
+    /// Removes all confirmations for the specified value.
+    pub fn reset(&mut self, id: &V) {
+        self.index.remove(id);
+    }
+
+    /// Removes confirmations for all the values which do not satisfy the predicate.
+    ///
+    /// Returns the amount of removed values.
+    pub fn retain(&mut self, mut predicate: impl FnMut(&V) -> bool) -> usize {
+        let stale_values = self
+            .index
+            .keys()
+            .filter(|value| !predicate(value.borrow()))
+            .collect::<Vec<_>>();
+        for value in &stale_values {
+            self.index.remove(value.borrow());
+        }
+        stale_values.len()
+    }
+
     /// Updates the stored confirmations to be an intersection of the set
     /// with current confirmations, and set of the actual validator keys.
     ///
@@ -119,6 +144,18 @@ where
         let validators_amount = validator_keys.len();
         self.intersect(id, &validator_keys) == validators_amount
     }
+
+    /// Leaves only confirmations of the actual validators for the specified item.
+    ///
+    /// Returns the amount of the remaining confirmations.
+    pub fn retain_validator_confirmations(
+        &mut self,
+        id: &V,
+        validator_keys: impl IntoIterator<Item = PublicKey>,
+    ) -> usize {
+        let validator_keys: BTreeSet<PublicKey> = validator_keys.into_iter().collect();
+        self.intersect(id, &validator_keys)
+    }
 }
 
 impl<T, V> ObjectHash for MultisigIndex<T, V>
@@ -193,7 +230,12 @@ impl<T: Ord + BinaryValue> ObjectHash for BinarySet<T> {
 
 #[cfg(test)]
 mod tests {
-    use super::{BinarySet, BinaryValue};
+    use exonum::{
+        crypto::{self, KeyPair},
+        merkledb::{access::FromAccess, Database, TemporaryDB},
+    };
+
+    use super::{BinarySet, BinaryValue, MultisigIndex};
 
     #[test]
     fn test_multisig_values_binary_value() {
@@ -210,4 +252,26 @@ mod tests {
         let set2 = BinarySet::from_bytes(bytes.into()).unwrap();
         assert_eq!(set, set2);
     }
+
+    #[test]
+    fn test_multisig_retain() {
+        let db = TemporaryDB::new();
+        let fork = db.fork();
+        let mut index: MultisigIndex<_, crypto::Hash> =
+            MultisigIndex::from_access(&fork, "multisig".into()).unwrap();
+
+        let author = KeyPair::random().public_key();
+        let values: Vec<_> = (0_u8..4).map(|i| crypto::hash(&[i])).collect();
+        for value in &values {
+            index.confirm(value, author);
+        }
+
+        let removed = index.retain(|value| *value == values[0] || *value == values[2]);
+        assert_eq!(removed, 2);
+        assert_eq!(index.confirmations(&values[0]), 1);
+        assert_eq!(index.confirmations(&values[1]), 0);
+        assert_eq!(index.confirmations(&values[2]), 1);
+        assert_eq!(index.confirmations(&values[3]), 0);
+        assert_eq!(index.retain(|_| true), 0);
+    }
 }
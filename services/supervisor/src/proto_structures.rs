@@ -13,17 +13,29 @@
 // limitations under the License.
 
 use exonum::{
-    blockchain::ConsensusConfig,
-    crypto::Hash,
+    blockchain::{ConsensusConfig, ValidatorKeys},
+    crypto::{Hash, PublicKey},
     helpers::Height,
     merkledb::{impl_binary_key_for_binary_value, BinaryValue, ObjectHash},
-    runtime::{ArtifactId, ExecutionStatus, InstanceId, InstanceSpec, MigrationStatus},
+    runtime::{
+        ArtifactId, Capabilities, DeploySpec, ExecutionStatus, InstanceId, InstanceSpec,
+        MigrationStatus,
+    },
 };
 use exonum_derive::{BinaryValue, ObjectHash};
 use exonum_proto::{ProtobufBase64, ProtobufConvert};
 use serde::{Deserialize, Serialize};
 
-use super::{mode::Mode, proto};
+use std::collections::BTreeMap;
+
+use super::{
+    consensus_patch::{ChangeValidators, ConsensusConfigPatch},
+    feature_flags::SetFeatureFlags,
+    mode::Mode,
+    proto,
+    timelock::ConfigTimelock,
+    upgrade_policy::ArtifactUpgradePolicy,
+};
 
 /// Supervisor service configuration (not to be confused with `ConfigPropose`, which
 /// contains core/service configuration change proposal).
@@ -35,13 +47,112 @@ use super::{mode::Mode, proto};
 pub struct SupervisorConfig {
     /// Supervisor operating mode.
     pub mode: Mode,
+    /// Policies restricting migrations of services to new artifact versions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub upgrade_policies: Vec<ArtifactUpgradePolicy>,
+    /// Governance keys allowed to propose and vote for configuration changes
+    /// in addition to the validators. Governance keys may differ from the service keys
+    /// of the validators, which allows to separate infrastructure operators
+    /// from governance signers. Keys must be sorted and unique.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[binary_value(sorted)]
+    pub governance_keys: Vec<PublicKey>,
+    /// Number of the latest blocks for which detailed call errors are retained in the storage.
+    /// Errors in older blocks are rolled into the `CallErrorsCheckpoint` of the core schema.
+    /// Zero means that call errors are retained indefinitely.
+    #[serde(default)]
+    pub call_errors_retention: u64,
+    /// Minimum delays between approval and activation of sensitive configuration changes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timelocks: Vec<ConfigTimelock>,
+    /// Maximum number of blocks for which a validator may delegate its votes to another key.
+    /// Zero means that vote delegation is disabled.
+    #[serde(default)]
+    pub max_delegation_period: u64,
 }
 
 impl SupervisorConfig {
     /// Creates a new configuration with the specified supervisor mode.
     #[must_use]
     pub const fn new(mode: Mode) -> Self {
-        Self { mode }
+        Self {
+            mode,
+            upgrade_policies: Vec::new(),
+            governance_keys: Vec::new(),
+            call_errors_retention: 0,
+            timelocks: Vec::new(),
+            max_delegation_period: 0,
+        }
+    }
+
+    /// Adds an upgrade policy to this configuration.
+    #[must_use]
+    pub fn with_upgrade_policy(mut self, policy: ArtifactUpgradePolicy) -> Self {
+        self.upgrade_policies.push(policy);
+        self
+    }
+
+    /// Adds a governance key to this configuration. Keys are kept sorted; adding a key
+    /// which is already present leaves the configuration unchanged.
+    #[must_use]
+    pub fn with_governance_key(mut self, key: PublicKey) -> Self {
+        if let Err(pos) = self.governance_keys.binary_search(&key) {
+            self.governance_keys.insert(pos, key);
+        }
+        self
+    }
+
+    /// Sets the number of the latest blocks for which detailed call errors are retained.
+    #[must_use]
+    pub const fn with_call_errors_retention(mut self, blocks: u64) -> Self {
+        self.call_errors_retention = blocks;
+        self
+    }
+
+    /// Sets the maximum number of blocks for which validators may delegate their votes.
+    #[must_use]
+    pub const fn with_max_delegation_period(mut self, blocks: u64) -> Self {
+        self.max_delegation_period = blocks;
+        self
+    }
+
+    /// Adds a timelock to this configuration.
+    #[must_use]
+    pub fn with_timelock(mut self, timelock: ConfigTimelock) -> Self {
+        self.timelocks.push(timelock);
+        self
+    }
+
+    /// Checks whether the specified key is a governance key.
+    pub fn is_governance_key(&self, key: &PublicKey) -> bool {
+        self.governance_keys.contains(key)
+    }
+
+    /// Returns the upgrade policy applicable to the specified artifact, if any.
+    pub fn upgrade_policy(&self, artifact: &ArtifactId) -> Option<&ArtifactUpgradePolicy> {
+        self.upgrade_policies
+            .iter()
+            .find(|policy| policy.applies_to(artifact))
+    }
+
+    /// Returns the minimum number of blocks between approval and activation of
+    /// the specified changes, or zero if the changes are not timelocked.
+    /// `validator_keys` are keys of the current validator set.
+    pub fn required_delay(
+        &self,
+        changes: &[ConfigChange],
+        validator_keys: &[ValidatorKeys],
+    ) -> u64 {
+        self.timelocks
+            .iter()
+            .filter(|timelock| {
+                changes
+                    .iter()
+                    .any(|change| timelock.applies_to(change, validator_keys))
+            })
+            .map(|timelock| timelock.min_delay)
+            .max()
+            .unwrap_or(0)
     }
 }
 
@@ -49,6 +160,7 @@ impl SupervisorConfig {
 #[derive(Debug, Clone, PartialEq, Eq, ProtobufConvert, BinaryValue, ObjectHash)]
 #[derive(Serialize, Deserialize)]
 #[protobuf_convert(source = "proto::DeployRequest")]
+#[binary_value(canonical)]
 #[non_exhaustive]
 pub struct DeployRequest {
     /// Artifact identifier.
@@ -84,18 +196,39 @@ impl DeployRequest {
         self.spec = spec;
         self
     }
+
+    /// Sets the artifact specification consisting of runtime-specific sections
+    /// for this request. The target runtime validates the specification when
+    /// the request is processed by the supervisor.
+    #[must_use]
+    pub fn with_deploy_spec(self, spec: &DeploySpec) -> Self {
+        self.with_spec(spec.to_bytes())
+    }
+
+    /// Checks whether this request has the same parameters as the `other` one,
+    /// i.e., differs from it only in the seed.
+    pub(crate) fn has_same_params(&self, other: &Self) -> bool {
+        self.artifact == other.artifact
+            && self.spec == other.spec
+            && self.deadline_height == other.deadline_height
+    }
 }
 
 /// Confirmation that artifact deployment has ended for a validator.
 /// Result can be either successful or unsuccessful.
 #[derive(Debug, Clone, BinaryValue, ObjectHash, ProtobufConvert)]
 #[protobuf_convert(source = "proto::DeployResult")]
+#[binary_value(canonical)]
 #[non_exhaustive]
 pub struct DeployResult {
     /// Corresponding request.
     pub request: DeployRequest,
     /// Result of deployment.
     pub result: ExecutionStatus,
+    /// Checksum of the deployed artifact, if provided by the runtime. All validators
+    /// must report the same checksum for the deployment to succeed.
+    #[protobuf_convert(with = "exonum::helpers::pb_optional_hash")]
+    pub artifact_checksum: Option<Hash>,
 }
 
 impl DeployResult {
@@ -105,6 +238,7 @@ impl DeployResult {
         Self {
             request,
             result: Ok(()).into(),
+            artifact_checksum: None,
         }
     }
 
@@ -113,6 +247,40 @@ impl DeployResult {
         Self {
             request,
             result: result.into(),
+            artifact_checksum: None,
+        }
+    }
+
+    /// Sets the checksum of the deployed artifact.
+    #[must_use]
+    pub fn with_artifact_checksum(mut self, checksum: Hash) -> Self {
+        self.artifact_checksum = Some(checksum);
+        self
+    }
+}
+
+/// Request to extend the deadline of a pending artifact deployment.
+///
+/// The original deploy request is not modified; the new deadline supersedes
+/// the `deadline_height` specified in it.
+#[derive(Debug, Clone, PartialEq, Eq, ProtobufConvert, BinaryValue, ObjectHash)]
+#[derive(Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::DeployDeadlineExtension")]
+#[binary_value(canonical)]
+#[non_exhaustive]
+pub struct DeployDeadlineExtension {
+    /// Pending deploy request.
+    pub request: DeployRequest,
+    /// New height until which the deployment procedure should be completed.
+    pub new_deadline_height: Height,
+}
+
+impl DeployDeadlineExtension {
+    /// Creates a request to extend the deadline of the pending deployment.
+    pub fn new(request: DeployRequest, new_deadline_height: Height) -> Self {
+        Self {
+            request,
+            new_deadline_height,
         }
     }
 }
@@ -149,12 +317,25 @@ impl StartService {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
 #[protobuf_convert(source = "proto::StopService")]
+#[binary_value(canonical)]
 #[non_exhaustive]
 pub struct StopService {
     /// Corresponding service instance ID.
     pub instance_id: InstanceId,
 }
 
+impl StopService {
+    /// Maximum amount of blocks during which votes for an emergency stop are collected.
+    /// Votes which have not reached the quorum within this period are discarded.
+    pub const VOTING_PERIOD: u64 = 1_000;
+
+    /// Creates a request to stop the service with the specified ID.
+    #[must_use]
+    pub const fn new(instance_id: InstanceId) -> Self {
+        Self { instance_id }
+    }
+}
+
 /// Request to freeze an existing service instance.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
@@ -163,6 +344,11 @@ pub struct StopService {
 pub struct FreezeService {
     /// Corresponding service instance ID.
     pub instance_id: InstanceId,
+    /// Optional height at which the supervisor automatically resumes the service.
+    /// If not set, the service remains frozen until an explicit resume request.
+    #[protobuf_convert(with = "exonum::helpers::pb_optional_height")]
+    #[serde(default)]
+    pub resume_at: Option<Height>,
 }
 
 /// Request to resume a previously stopped service instance.
@@ -188,6 +374,137 @@ pub struct UnloadArtifact {
     pub artifact_id: ArtifactId,
 }
 
+/// Request to deprecate an artifact. New service instances cannot be started from
+/// a deprecated artifact, but existing instances continue running.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::DeprecateArtifact")]
+pub struct DeprecateArtifact {
+    /// Artifact identifier.
+    pub artifact_id: ArtifactId,
+}
+
+/// Free-form labeled metadata of a service instance, such as its environment,
+/// owner team or contact.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::ServiceMetadata")]
+#[serde(transparent)]
+pub struct ServiceMetadata {
+    /// Metadata entries.
+    #[protobuf_convert(with = "self::pb_metadata_entries")]
+    pub entries: BTreeMap<String, String>,
+}
+
+impl ServiceMetadata {
+    /// Creates metadata from the provided labeled entries.
+    #[must_use]
+    pub fn new<K, V>(entries: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        Self {
+            entries: entries
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        }
+    }
+
+    /// Checks if the metadata has no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Metadata entries are encoded as a sequence sorted by key, so that the encoding
+/// of the metadata is unambiguous.
+mod pb_metadata_entries {
+    use anyhow::ensure;
+
+    use std::collections::BTreeMap;
+
+    use crate::proto::MetadataEntry;
+
+    #[allow(clippy::needless_pass_by_value)] // required by `exonum-proto`
+    pub fn from_pb(pb: Vec<MetadataEntry>) -> anyhow::Result<BTreeMap<String, String>> {
+        ensure!(
+            pb.windows(2).all(|pair| pair[0].key() < pair[1].key()),
+            "Metadata keys are not sorted or contain duplicates"
+        );
+        Ok(pb
+            .into_iter()
+            .map(|mut entry| (entry.take_key(), entry.take_value()))
+            .collect())
+    }
+
+    pub fn to_pb(value: &BTreeMap<String, String>) -> Vec<MetadataEntry> {
+        value
+            .iter()
+            .map(|(key, value)| {
+                let mut entry = MetadataEntry::new();
+                entry.set_key(key.clone());
+                entry.set_value(value.clone());
+                entry
+            })
+            .collect()
+    }
+}
+
+/// Request to set the metadata of a service instance. The new metadata replaces
+/// the previous one; empty metadata removes it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::SetServiceMetadata")]
+#[non_exhaustive]
+pub struct SetServiceMetadata {
+    /// Corresponding service instance ID.
+    pub instance_id: InstanceId,
+    /// New metadata of the instance.
+    pub metadata: ServiceMetadata,
+}
+
+/// Request to rename a stopped service instance. The service retains its numeric identifier
+/// and data; the data is moved to the namespace corresponding to the new name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::RenameService")]
+#[non_exhaustive]
+pub struct RenameService {
+    /// Corresponding service instance ID.
+    pub instance_id: InstanceId,
+    /// New name of the service instance.
+    pub new_name: String,
+}
+
+/// Request to make a service instance the default one for an interface name. Afterwards,
+/// the service can be addressed by the interface name instead of its numeric identifier.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::SetDefaultInstance")]
+#[non_exhaustive]
+pub struct SetDefaultInstance {
+    /// Name of the interface.
+    pub interface: String,
+    /// Corresponding service instance ID.
+    pub instance_id: InstanceId,
+}
+
+/// Request to override capabilities of a deployed artifact. The capabilities apply
+/// to all services instantiated from the artifact, including already running ones.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::SetArtifactCapabilities")]
+#[non_exhaustive]
+pub struct SetArtifactCapabilities {
+    /// Corresponding artifact.
+    pub artifact: ArtifactId,
+    /// New capabilities of the artifact.
+    pub capabilities: Capabilities,
+}
+
 /// Configuration parameters of the certain service instance.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
@@ -234,12 +551,58 @@ pub enum ConfigChange {
     FreezeService(FreezeService),
     /// Request to unload an unused artifact.
     UnloadArtifact(UnloadArtifact),
+    /// Request to deprecate an artifact.
+    DeprecateArtifact(DeprecateArtifact),
+    /// Request to set the metadata of a service instance.
+    SetServiceMetadata(SetServiceMetadata),
+    /// Request to rename a stopped service instance.
+    RenameService(RenameService),
+    /// Request to enable or disable feature flags of a service instance.
+    SetFeatureFlags(SetFeatureFlags),
+    /// Patch of the consensus config, which is merged into the consensus config
+    /// actual at the moment of the proposal application.
+    ConsensusPatch(ConsensusConfigPatch),
+    /// Change of individual validators, which is merged into the validator set
+    /// actual at the moment of the proposal application.
+    ChangeValidators(ChangeValidators),
+    /// Request to make a service instance the default one for an interface name.
+    SetDefaultInstance(SetDefaultInstance),
+    /// Request to override capabilities of a deployed artifact.
+    SetArtifactCapabilities(SetArtifactCapabilities),
+}
+
+/// Stage of a multi-stage configuration proposal.
+///
+/// Unlike the changes in the first stage of the proposal, changes in the subsequent stages
+/// are verified only when the stage is applied, since they may depend on the changes
+/// in the previous stages (e.g., a service may be resumed only after it is stopped).
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::ConfigStage")]
+#[non_exhaustive]
+pub struct ConfigStage {
+    /// The height at which the stage should be applied.
+    pub actual_from: Height,
+    /// Changes applied within the stage.
+    pub changes: Vec<ConfigChange>,
+}
+
+impl ConfigStage {
+    /// Creates an empty stage which should be applied at the specified height.
+    #[must_use]
+    pub const fn new(actual_from: Height) -> Self {
+        Self {
+            actual_from,
+            changes: Vec::new(),
+        }
+    }
 }
 
 /// Request for the configuration change
 #[derive(Debug, Clone, Eq, PartialEq)]
 #[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
 #[protobuf_convert(source = "proto::ConfigPropose")]
+#[binary_value(canonical)]
 #[non_exhaustive]
 pub struct ConfigPropose {
     /// The height until which the update configuration procedure should be completed.
@@ -248,6 +611,15 @@ pub struct ConfigPropose {
     pub changes: Vec<ConfigChange>,
     /// Configuration proposal number to avoid conflicting proposals.
     pub configuration_number: u64,
+    /// Height at which the proposal expires and frees the pending proposal slot
+    /// if it is not yet applied. If not set, the proposal expires at `actual_from` height.
+    #[protobuf_convert(with = "exonum::helpers::pb_optional_height")]
+    #[serde(default)]
+    pub expires_at: Option<Height>,
+    /// Additional stages of the proposal applied after `changes` at the specified heights.
+    /// All stages are approved by a single vote for the proposal.
+    #[serde(default)]
+    pub stages: Vec<ConfigStage>,
 }
 
 impl ConfigPropose {
@@ -258,9 +630,75 @@ impl ConfigPropose {
             actual_from,
             changes: Vec::default(),
             configuration_number,
+            expires_at: None,
+            stages: Vec::default(),
+        }
+    }
+
+    /// Starts a new stage of the proposal, which should be applied at the specified height.
+    /// Changes added to the proposal after this call are applied within this stage.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum::helpers::Height;
+    /// # use exonum_supervisor::ConfigPropose;
+    /// // Stop the service at height 10 and resume it at height 20.
+    /// let propose = ConfigPropose::new(0, Height(10))
+    ///     .stop_service(100)
+    ///     .stage(Height(20))
+    ///     .resume_service(100, ());
+    /// assert_eq!(propose.changes.len(), 1);
+    /// assert_eq!(propose.stages[0].changes.len(), 1);
+    /// ```
+    #[must_use]
+    pub fn stage(mut self, actual_from: Height) -> Self {
+        self.stages.push(ConfigStage::new(actual_from));
+        self
+    }
+
+    /// Adds a change to the last stage of the proposal.
+    fn push_change(&mut self, change: ConfigChange) {
+        if let Some(stage) = self.stages.last_mut() {
+            stage.changes.push(change);
+        } else {
+            self.changes.push(change);
         }
     }
 
+    /// Sets the height at which the proposal expires if it is not yet applied.
+    /// This allows to free the pending proposal slot before the activation height.
+    #[must_use]
+    pub const fn with_expiration(mut self, expires_at: Height) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Checks whether the proposal changes the consensus configuration or the validator set
+    /// in any of its stages.
+    pub(crate) fn affects_consensus(&self) -> bool {
+        self.changes
+            .iter()
+            .chain(self.stages.iter().flat_map(|stage| &stage.changes))
+            .any(|change| {
+                matches!(
+                    change,
+                    ConfigChange::Consensus(_)
+                        | ConfigChange::ConsensusPatch(_)
+                        | ConfigChange::ChangeValidators(_)
+                )
+            })
+    }
+
+    /// Returns the height until which the proposal can be voted for, that is,
+    /// the earlier of `actual_from` and `expires_at` heights.
+    #[must_use]
+    pub fn deadline_height(&self) -> Height {
+        self.expires_at.map_or(self.actual_from, |expires_at| {
+            expires_at.min(self.actual_from)
+        })
+    }
+
     /// Creates a new proposal which should be activated at the next height.
     #[must_use]
     pub fn immediate(configuration_number: u64) -> Self {
@@ -270,14 +708,39 @@ impl ConfigPropose {
     /// Adds a change of consensus configuration to this proposal.
     #[must_use]
     pub fn consensus_config(mut self, config: ConsensusConfig) -> Self {
-        self.changes.push(ConfigChange::Consensus(config));
+        self.push_change(ConfigChange::Consensus(config));
+        self
+    }
+
+    /// Adds a patch of consensus configuration to this proposal. Unlike
+    /// [`consensus_config`](#method.consensus_config), the patch modifies only
+    /// the parameters set in it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use exonum::helpers::Height;
+    /// # use exonum_supervisor::{ConfigPropose, ConsensusConfigPatch};
+    /// let patch = ConsensusConfigPatch::new().min_propose_timeout(50);
+    /// let propose = ConfigPropose::new(0, Height(10)).consensus_patch(patch);
+    /// ```
+    #[must_use]
+    pub fn consensus_patch(mut self, patch: ConsensusConfigPatch) -> Self {
+        self.push_change(ConfigChange::ConsensusPatch(patch));
+        self
+    }
+
+    /// Adds a change of individual validators to this proposal.
+    #[must_use]
+    pub fn change_validators(mut self, change: ChangeValidators) -> Self {
+        self.push_change(ConfigChange::ChangeValidators(change));
         self
     }
 
     /// Adds change of the configuration for the specified service instance.
     #[must_use]
     pub fn service_config(mut self, instance_id: InstanceId, config: impl BinaryValue) -> Self {
-        self.changes.push(ConfigChange::Service(ServiceConfig {
+        self.push_change(ConfigChange::Service(ServiceConfig {
             instance_id,
             params: config.into_bytes(),
         }));
@@ -298,42 +761,136 @@ impl ConfigPropose {
             config: constructor.into_bytes(),
         };
 
-        self.changes.push(ConfigChange::StartService(start_service));
+        self.push_change(ConfigChange::StartService(start_service));
         self
     }
 
     /// Adds a service stop request to this proposal.
     #[must_use]
     pub fn stop_service(mut self, instance_id: InstanceId) -> Self {
-        self.changes
-            .push(ConfigChange::StopService(StopService { instance_id }));
+        self.push_change(ConfigChange::StopService(StopService { instance_id }));
         self
     }
 
     /// Adds a service freeze request to this proposal.
     #[must_use]
     pub fn freeze_service(mut self, instance_id: InstanceId) -> Self {
-        self.changes
-            .push(ConfigChange::FreezeService(FreezeService { instance_id }));
+        self.push_change(ConfigChange::FreezeService(FreezeService {
+            instance_id,
+            resume_at: None,
+        }));
+        self
+    }
+
+    /// Adds a request to freeze a service until the specified height to this proposal.
+    /// The service is resumed with empty resume parameters in the block at the `resume_at`
+    /// height, which must be greater than the activation height of the proposal.
+    #[must_use]
+    pub fn freeze_service_until(mut self, instance_id: InstanceId, resume_at: Height) -> Self {
+        self.push_change(ConfigChange::FreezeService(FreezeService {
+            instance_id,
+            resume_at: Some(resume_at),
+        }));
         self
     }
 
     /// Adds a service resume request to this proposal.
     #[must_use]
     pub fn resume_service(mut self, instance_id: InstanceId, params: impl BinaryValue) -> Self {
-        self.changes
-            .push(ConfigChange::ResumeService(ResumeService {
-                instance_id,
-                params: params.into_bytes(),
-            }));
+        self.push_change(ConfigChange::ResumeService(ResumeService {
+            instance_id,
+            params: params.into_bytes(),
+        }));
         self
     }
 
     /// Adds an artifact unloading request to this proposal.
     #[must_use]
     pub fn unload_artifact(mut self, artifact_id: ArtifactId) -> Self {
-        self.changes
-            .push(ConfigChange::UnloadArtifact(UnloadArtifact { artifact_id }));
+        self.push_change(ConfigChange::UnloadArtifact(UnloadArtifact { artifact_id }));
+        self
+    }
+
+    /// Adds an artifact deprecation request to this proposal.
+    #[must_use]
+    pub fn deprecate_artifact(mut self, artifact_id: ArtifactId) -> Self {
+        self.push_change(ConfigChange::DeprecateArtifact(DeprecateArtifact {
+            artifact_id,
+        }));
+        self
+    }
+
+    /// Adds a request to set the metadata of a service instance to this proposal.
+    /// Empty metadata removes the previously set one.
+    #[must_use]
+    pub fn set_service_metadata(
+        mut self,
+        instance_id: InstanceId,
+        metadata: ServiceMetadata,
+    ) -> Self {
+        self.push_change(ConfigChange::SetServiceMetadata(SetServiceMetadata {
+            instance_id,
+            metadata,
+        }));
+        self
+    }
+
+    /// Adds a request to enable or disable feature flags of a service instance
+    /// to this proposal. Each flag is specified with its new state (`true` to enable the flag).
+    #[must_use]
+    pub fn set_feature_flags<K: Into<String>>(
+        mut self,
+        instance_id: InstanceId,
+        flags: impl IntoIterator<Item = (K, bool)>,
+    ) -> Self {
+        self.push_change(ConfigChange::SetFeatureFlags(SetFeatureFlags {
+            instance_id,
+            flags: flags
+                .into_iter()
+                .map(|(name, is_enabled)| (name.into(), is_enabled))
+                .collect(),
+        }));
+        self
+    }
+
+    /// Adds a request to rename a stopped service instance to this proposal.
+    #[must_use]
+    pub fn rename_service(mut self, instance_id: InstanceId, new_name: impl Into<String>) -> Self {
+        self.push_change(ConfigChange::RenameService(RenameService {
+            instance_id,
+            new_name: new_name.into(),
+        }));
+        self
+    }
+
+    /// Adds a request to make a service instance the default one for the interface name
+    /// to this proposal.
+    #[must_use]
+    pub fn set_default_instance(
+        mut self,
+        interface: impl Into<String>,
+        instance_id: InstanceId,
+    ) -> Self {
+        self.push_change(ConfigChange::SetDefaultInstance(SetDefaultInstance {
+            interface: interface.into(),
+            instance_id,
+        }));
+        self
+    }
+
+    /// Adds a request to override capabilities of a deployed artifact to this proposal.
+    #[must_use]
+    pub fn set_artifact_capabilities(
+        mut self,
+        artifact: ArtifactId,
+        capabilities: Capabilities,
+    ) -> Self {
+        self.push_change(ConfigChange::SetArtifactCapabilities(
+            SetArtifactCapabilities {
+                artifact,
+                capabilities,
+            },
+        ));
         self
     }
 }
@@ -342,6 +899,7 @@ impl ConfigPropose {
 #[derive(Debug, Clone, PartialEq, Eq, ProtobufConvert, BinaryValue, ObjectHash)]
 #[derive(Serialize, Deserialize)]
 #[protobuf_convert(source = "proto::ConfigVote")]
+#[binary_value(canonical)]
 #[non_exhaustive]
 pub struct ConfigVote {
     /// Hash of configuration proposition.
@@ -368,6 +926,7 @@ impl From<ConfigPropose> for ConfigVote {
 #[derive(Debug, Clone, PartialEq, Eq, ProtobufConvert, BinaryValue, ObjectHash)]
 #[derive(Serialize, Deserialize)]
 #[protobuf_convert(source = "proto::MigrationRequest")]
+#[binary_value(canonical)]
 #[non_exhaustive]
 pub struct MigrationRequest {
     /// New artifact identifier.
@@ -382,6 +941,12 @@ pub struct MigrationRequest {
     /// Seed to allow several migrations with the same params.
     #[serde(default)]
     pub seed: u64,
+
+    /// Whether the artifact superseded by the migration (i.e., the artifact associated
+    /// with the service before the migration) should be unloaded automatically once
+    /// the migration succeeds and the artifact is no longer used by any service.
+    #[serde(default)]
+    pub unload_superseded_artifact: bool,
 }
 
 impl MigrationRequest {
@@ -396,14 +961,23 @@ impl MigrationRequest {
             service: service.into(),
             deadline_height,
             seed: 0,
+            unload_superseded_artifact: false,
         }
     }
+
+    /// Requests to automatically unload the superseded artifact after the migration succeeds.
+    #[must_use]
+    pub fn with_superseded_artifact_unload(mut self) -> Self {
+        self.unload_superseded_artifact = true;
+        self
+    }
 }
 
 /// Confirmation that migration has ended for a validator.
 /// Result can be either successful or unsuccessful.
 #[derive(Debug, Clone, BinaryValue, ObjectHash, ProtobufConvert)]
 #[protobuf_convert(source = "proto::MigrationResult")]
+#[binary_value(canonical)]
 #[non_exhaustive]
 pub struct MigrationResult {
     /// Corresponding request.
@@ -422,6 +996,149 @@ impl MigrationResult {
     }
 }
 
+/// Intermediate progress of a migration on a validator node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::MigrationProgress")]
+#[binary_value(canonical)]
+#[non_exhaustive]
+pub struct MigrationProgress {
+    /// Corresponding request.
+    pub request: MigrationRequest,
+    /// Number of processed keys.
+    pub processed_keys: u64,
+    /// Percentage of completion (from 0 to 100).
+    pub percentage: u32,
+}
+
+impl MigrationProgress {
+    /// Creates a migration progress report.
+    pub const fn new(request: MigrationRequest, processed_keys: u64, percentage: u32) -> Self {
+        Self {
+            request,
+            processed_keys,
+            percentage,
+        }
+    }
+}
+
+/// Attestation of a validator node readiness for a pending upgrade, i.e., an artifact
+/// deployment, a service migration or a configuration proposal.
+///
+/// Attestations are informational: they do not affect the outcome of the upgrade, but allow
+/// to find out unprepared validators before the upgrade is carried out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::ReadinessAttestation")]
+#[binary_value(canonical)]
+#[non_exhaustive]
+pub struct ReadinessAttestation {
+    /// Hash of the pending deploy request, migration request or configuration proposal.
+    pub subject: Hash,
+    /// Descriptions of the failed readiness checks; empty if the node is ready.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_checks: Vec<String>,
+}
+
+impl ReadinessAttestation {
+    /// Creates an attestation for the upgrade with the specified hash.
+    pub const fn new(subject: Hash, failed_checks: Vec<String>) -> Self {
+        Self {
+            subject,
+            failed_checks,
+        }
+    }
+
+    /// Checks whether the node has passed all readiness checks.
+    pub fn is_ready(&self) -> bool {
+        self.failed_checks.is_empty()
+    }
+}
+
+/// Request of a validator to delegate its supervisor votes to another key.
+///
+/// The delegate may propose and vote for configuration changes, and vote for deployments
+/// and migrations on behalf of the validator until the `expires_at` height. A validator
+/// may have at most one delegate; a new request replaces the previous delegation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::DelegateVotes")]
+#[binary_value(canonical)]
+#[non_exhaustive]
+pub struct DelegateVotes {
+    /// Key authorized to vote on behalf of the validator.
+    pub delegate: PublicKey,
+    /// Height from which the delegation is no longer valid. `Height(0)` revokes
+    /// the delegation to the `delegate` key.
+    pub expires_at: Height,
+}
+
+impl DelegateVotes {
+    /// Creates a request to delegate votes to `delegate` until the `expires_at` height.
+    pub const fn new(delegate: PublicKey, expires_at: Height) -> Self {
+        Self {
+            delegate,
+            expires_at,
+        }
+    }
+
+    /// Creates a request to revoke the delegation to the `delegate` key.
+    pub const fn revoke(delegate: PublicKey) -> Self {
+        Self::new(delegate, Height(0))
+    }
+
+    /// Checks whether this request revokes the delegation.
+    pub fn is_revocation(&self) -> bool {
+        self.expires_at == Height(0)
+    }
+}
+
+/// Active delegation of the supervisor votes of a validator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::VoteDelegation")]
+#[non_exhaustive]
+pub struct VoteDelegation {
+    /// Service key of the validator which has delegated its votes.
+    pub validator_key: PublicKey,
+    /// Height from which the delegation is no longer valid.
+    pub expires_at: Height,
+}
+
+/// Request to switch the supervisor operating mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash, Serialize, Deserialize)]
+#[protobuf_convert(source = "proto::ModeChangeRequest")]
+#[binary_value(canonical)]
+#[non_exhaustive]
+pub struct ModeChangeRequest {
+    /// New supervisor operating mode.
+    pub new_mode: Mode,
+}
+
+impl ModeChangeRequest {
+    /// Creates a request to switch the supervisor to the specified mode.
+    #[must_use]
+    pub const fn new(new_mode: Mode) -> Self {
+        Self { new_mode }
+    }
+}
+
+/// Record about a change of the supervisor operating mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+#[derive(ProtobufConvert, BinaryValue, ObjectHash)]
+#[protobuf_convert(source = "proto::ModeChange")]
+#[non_exhaustive]
+pub struct ModeChange {
+    /// Supervisor mode before the change.
+    pub previous_mode: Mode,
+    /// Supervisor mode after the change.
+    pub new_mode: Mode,
+    /// Height of the block in which the change was applied.
+    pub height: Height,
+}
+
 /// Pending config change proposal entry
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[derive(Serialize, Deserialize)]
@@ -436,4 +1153,70 @@ pub struct ConfigProposalWithHash {
 }
 
 impl_binary_key_for_binary_value! { DeployRequest }
+impl_binary_key_for_binary_value! { DeployDeadlineExtension }
+impl_binary_key_for_binary_value! { StopService }
+impl_binary_key_for_binary_value! { ModeChangeRequest }
 impl_binary_key_for_binary_value! { MigrationRequest }
+
+#[cfg(test)]
+mod tests {
+    use exonum::crypto::KeyPair;
+    use protobuf::Message;
+
+    use super::*;
+
+    #[test]
+    fn governance_keys_must_be_sorted() {
+        let keys = [
+            KeyPair::random().public_key(),
+            KeyPair::random().public_key(),
+        ];
+        let config = SupervisorConfig::new(Mode::Simple)
+            .with_governance_key(keys[0])
+            .with_governance_key(keys[1]);
+        assert!(config.governance_keys[0] < config.governance_keys[1]);
+        let restored = SupervisorConfig::from_bytes(config.to_bytes().into()).unwrap();
+        assert_eq!(restored, config);
+
+        let mut unsorted_config = config.clone();
+        unsorted_config.governance_keys.reverse();
+        let err = SupervisorConfig::from_bytes(unsorted_config.to_bytes().into()).unwrap_err();
+        assert!(format!("{:#}", err)
+            .contains("Elements of `governance_keys` in SupervisorConfig are not sorted"));
+
+        let mut config_with_duplicates = config;
+        config_with_duplicates.governance_keys.insert(0, keys[0]);
+        config_with_duplicates.governance_keys.sort();
+        assert!(SupervisorConfig::from_bytes(config_with_duplicates.to_bytes().into()).is_err());
+    }
+
+    #[test]
+    fn adding_governance_key_twice() {
+        let key = KeyPair::random().public_key();
+        let config = SupervisorConfig::new(Mode::Simple).with_governance_key(key);
+        let same_config = config.clone().with_governance_key(key);
+        assert_eq!(same_config, config);
+        assert_eq!(same_config.governance_keys, vec![key]);
+        SupervisorConfig::from_bytes(same_config.to_bytes().into()).unwrap();
+    }
+
+    #[test]
+    fn non_canonically_ordered_map_in_proposal_is_rejected() {
+        let metadata = ServiceMetadata::new(vec![("env", "prod"), ("owner", "team")]);
+        let propose = ConfigPropose::immediate(0).set_service_metadata(100, metadata);
+        let restored = ConfigPropose::from_bytes(propose.to_bytes().into()).unwrap();
+        assert_eq!(restored, propose);
+
+        // Reverse the order of metadata entries in the encoded proposal.
+        let mut pb = propose.to_pb();
+        pb.changes[0]
+            .mut_set_service_metadata()
+            .metadata
+            .mut_or_insert_default()
+            .entries
+            .reverse();
+        let bytes = pb.write_to_bytes().unwrap();
+        let err = ConfigPropose::from_bytes(bytes.into()).unwrap_err();
+        assert!(format!("{:#}", err).contains("Metadata keys are not sorted or contain duplicates"));
+    }
+}
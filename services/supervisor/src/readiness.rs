@@ -0,0 +1,102 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Readiness of validator nodes for pending upgrades.
+
+use exonum::{
+    crypto::Hash,
+    helpers::Height,
+    merkledb::{access::Access, ObjectHash, Snapshot},
+    runtime::{ArtifactId, SnapshotExt, SUPERVISOR_INSTANCE_ID},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{schema::SchemaImpl, ConfigChange};
+
+/// Kind of a pending upgrade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum UpgradeKind {
+    /// Deployment of an artifact approved by the validators.
+    Deployment,
+    /// Data migration of a service approved by the validators.
+    Migration,
+    /// Configuration proposal waiting for the votes of the validators.
+    ConfigProposal,
+}
+
+/// Upgrade pending in the supervisor, which validator nodes should be prepared for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PendingUpgrade {
+    /// Kind of the upgrade.
+    pub kind: UpgradeKind,
+    /// Hash identifying the upgrade: the object hash of the deploy or migration request,
+    /// or the hash of the configuration proposal. Readiness attestations refer to the upgrade
+    /// by this hash.
+    pub subject: Hash,
+    /// Artifacts which should be available on the node to carry out the upgrade.
+    pub artifacts: Vec<ArtifactId>,
+    /// Height until which the upgrade should be completed.
+    pub deadline_height: Height,
+}
+
+impl PendingUpgrade {
+    /// Loads pending upgrades from the supervisor data in the snapshot. Returns an empty list
+    /// if the supervisor is not started yet.
+    pub fn load_all(snapshot: &dyn Snapshot) -> Vec<Self> {
+        snapshot
+            .for_service(SUPERVISOR_INSTANCE_ID)
+            .map_or_else(Vec::new, |access| {
+                Self::from_schema(&SchemaImpl::new(access))
+            })
+    }
+
+    pub(crate) fn from_schema<T: Access>(schema: &SchemaImpl<T>) -> Vec<Self> {
+        let deployments = schema.pending_deployments.values().map(|request| Self {
+            kind: UpgradeKind::Deployment,
+            subject: request.object_hash(),
+            deadline_height: schema.deploy_deadline(&request),
+            artifacts: vec![request.artifact],
+        });
+        let migrations = schema.pending_migrations.iter().map(|(_, request)| Self {
+            kind: UpgradeKind::Migration,
+            subject: request.object_hash(),
+            deadline_height: request.deadline_height,
+            artifacts: vec![request.new_artifact],
+        });
+        let proposal = schema.public.pending_proposal.get().map(|proposal| {
+            let propose = &proposal.config_propose;
+            let changes = propose
+                .changes
+                .iter()
+                .chain(propose.stages.iter().flat_map(|stage| &stage.changes));
+            let artifacts = changes
+                .filter_map(|change| match change {
+                    ConfigChange::StartService(start) => Some(start.artifact.clone()),
+                    _ => None,
+                })
+                .collect();
+            Self {
+                kind: UpgradeKind::ConfigProposal,
+                subject: proposal.propose_hash,
+                deadline_height: propose.deadline_height(),
+                artifacts,
+            }
+        });
+
+        deployments.chain(migrations).chain(proposal).collect()
+    }
+}
@@ -13,18 +13,22 @@
 // limitations under the License.
 
 use exonum::{
-    crypto::Hash,
+    crypto::{Hash, PublicKey},
+    helpers::Height,
     runtime::{ArtifactId, InstanceId},
 };
 use exonum_derive::{FromAccess, RequireArtifact};
 use exonum_merkledb::{
     access::{Access, FromAccess, Prefixed},
-    Entry, Fork, ProofEntry, ProofMapIndex, ValueSetIndex,
+    Entry, Fork, Group, KeySetIndex, ListIndex, MapIndex, ProofEntry, ProofListIndex,
+    ProofMapIndex, ValueSetIndex,
 };
 
 use super::{
-    migration_state::MigrationState, multisig::MultisigIndex, AsyncEventState,
-    ConfigProposalWithHash, DeployRequest, MigrationRequest, SupervisorConfig,
+    consensus_patch::KeyHandover, events::SupervisorEvent, migration_state::MigrationState,
+    multisig::MultisigIndex, AsyncEventState, ConfigProposalWithHash, DeployDeadlineExtension,
+    DeployRequest, FeatureFlags, MigrationRequest, ModeChange, ModeChangeRequest,
+    ReadinessAttestation, ServiceMetadata, StopService, SupervisorConfig, VoteDelegation,
 };
 
 /// Service information schema.
@@ -46,13 +50,25 @@ pub struct SchemaImpl<T: Access> {
     pub deploy_confirmations: MultisigIndex<T, DeployRequest>,
     /// Deployment failures.
     pub deploy_states: ProofMapIndex<T::Base, DeployRequest, AsyncEventState>,
+    /// Artifact checksums reported by the first validator confirming the deployment.
+    pub deploy_checksums: ProofMapIndex<T::Base, DeployRequest, Hash>,
     /// Artifacts to be deployed.
     pub pending_deployments: ProofMapIndex<T::Base, ArtifactId, DeployRequest>,
+    /// Validator votes for extending deadlines of pending deployments.
+    pub deadline_extension_requests: MultisigIndex<T, DeployDeadlineExtension>,
+    /// Extended deadlines of deployments, which supersede deadlines specified
+    /// in the deploy requests.
+    pub deploy_deadlines: ProofMapIndex<T::Base, DeployRequest, Height>,
 
     /// Votes for a configuration change.
     pub config_confirms: MultisigIndex<T, Hash>,
+    /// Heights at which the pending configuration proposal has been approved.
+    pub config_approved_at: MapIndex<T::Base, Hash, Height>,
     /// Number of the processed configurations. Used to avoid conflicting configuration proposals.
     pub configuration_number: Entry<T::Base, u64>,
+    /// Applied multi-stage configuration proposal with the stages that are not applied yet.
+    /// Stages are removed from the proposal as they are applied.
+    pub staged_proposal: Entry<T::Base, ConfigProposalWithHash>,
 
     /// Stored migration requests with the confirmations from the validators.
     pub migration_requests: MultisigIndex<T, MigrationRequest>,
@@ -66,6 +82,41 @@ pub struct SchemaImpl<T: Access> {
     pub pending_migrations: ValueSetIndex<T::Base, MigrationRequest>,
     /// Migrations that completed but not flushed yet.
     pub migrations_to_flush: ValueSetIndex<T::Base, MigrationRequest>,
+    /// Validator votes for aborting the ongoing migrations.
+    pub migration_abort_requests: MultisigIndex<T, MigrationRequest>,
+    /// Artifacts superseded by successful migrations, which should be unloaded
+    /// once they are no longer used by services.
+    pub superseded_artifacts: KeySetIndex<T::Base, ArtifactId>,
+
+    /// Validator votes for emergency stops of services.
+    pub emergency_stop_requests: MultisigIndex<T, StopService>,
+    /// Heights at which the voting for emergency stops of services has started.
+    pub emergency_stop_started: MapIndex<T::Base, InstanceId, Height>,
+    /// Heights at which frozen services should be automatically resumed.
+    pub scheduled_resumes: MapIndex<T::Base, InstanceId, Height>,
+    /// Metadata attached to service instances.
+    pub service_metadata: ProofMapIndex<T::Base, InstanceId, ServiceMetadata>,
+    /// Handovers of the service keys of replaced validators, keyed by the old service key.
+    pub key_handovers: ProofMapIndex<T::Base, PublicKey, KeyHandover>,
+    /// Active delegations of the validator votes, keyed by the delegate key.
+    pub vote_delegations: ProofMapIndex<T::Base, PublicKey, VoteDelegation>,
+
+    /// Validator votes for switching the supervisor mode.
+    pub mode_change_requests: MultisigIndex<T, ModeChangeRequest>,
+    /// Audit log of the supervisor mode changes.
+    pub mode_changes: ProofListIndex<T::Base, ModeChange>,
+
+    /// Readiness attestations of the validators grouped by the hash of the upgrade
+    /// and keyed by the service key of the validator.
+    pub readiness_attestations: Group<T, Hash, MapIndex<T::Base, PublicKey, ReadinessAttestation>>,
+
+    /// Height at which stale votes left by the previous versions of the service were removed.
+    /// Absence of the entry means that the repair is not performed yet.
+    pub governance_repaired_at: Entry<T::Base, Height>,
+
+    /// Events emitted within the latest processed block.
+    /// The list is cleared at the beginning of each block.
+    pub events: ListIndex<T::Base, SupervisorEvent>,
 }
 
 /// Public part of the supervisor service.
@@ -75,6 +126,17 @@ pub struct Schema<T: Access> {
     pub configuration: ProofEntry<T::Base, SupervisorConfig>,
     /// Current pending configuration proposal.
     pub pending_proposal: ProofEntry<T::Base, ConfigProposalWithHash>,
+    /// Feature flags enabled for service instances.
+    pub feature_flags: ProofMapIndex<T::Base, InstanceId, FeatureFlags>,
+}
+
+impl<T: Access> Schema<T> {
+    /// Checks whether the feature flag with the specified name is enabled for the service.
+    pub fn feature_enabled(&self, instance_id: InstanceId, name: &str) -> bool {
+        self.feature_flags
+            .get(&instance_id)
+            .map_or(false, |flags| flags.is_enabled(name))
+    }
 }
 
 impl<T: Access> SchemaImpl<T> {
@@ -99,6 +161,24 @@ impl<T: Access> SchemaImpl<T> {
             .expect("Supervisor entity was not configured; unable to load configuration")
     }
 
+    /// Returns the kinds of pending activities which prevent switching the supervisor mode.
+    /// Activities approved under one mode are required to complete under the same mode.
+    pub(crate) fn activities_blocking_mode_change(&self) -> Vec<&'static str> {
+        let mut activities = vec![];
+        if self.pending_deployments.keys().next().is_some() {
+            activities.push("deployments");
+        }
+        if self.pending_migrations.iter().next().is_some()
+            || self.migrations_to_flush.iter().next().is_some()
+        {
+            activities.push("migrations");
+        }
+        if self.public.pending_proposal.exists() || self.staged_proposal.exists() {
+            activities.push("configuration proposals");
+        }
+        activities
+    }
+
     /// Obtains the migration state, panicking if there is no state for provided
     /// request.
     pub fn migration_state_unchecked(&self, request: &MigrationRequest) -> MigrationState {
@@ -115,6 +195,108 @@ impl SchemaImpl<Prefixed<&Fork>> {
         self.configuration_number.set(new_configuration_number);
     }
 
+    /// Returns the height until which the deployment should be completed, taking
+    /// deadline extensions into account.
+    pub fn deploy_deadline(&self, request: &DeployRequest) -> Height {
+        self.deploy_deadlines
+            .get(request)
+            .unwrap_or(request.deadline_height)
+    }
+
+    /// Removes validator votes which cannot influence the service state anymore. These are
+    /// votes for config proposals other than the pending one, for deployments and migrations
+    /// which are neither pending nor within their deadlines, for outdated deadline extensions,
+    /// for aborts of migrations which are not pending, and for emergency stops collected
+    /// for longer than the voting period.
+    ///
+    /// Returns the amount of removed multisig entries.
+    pub(crate) fn remove_stale_votes(&mut self, height: Height) -> usize {
+        let pending_hash = self
+            .public
+            .pending_proposal
+            .get()
+            .map(|entry| entry.propose_hash);
+        let mut removed = self
+            .config_confirms
+            .retain(|hash| Some(*hash) == pending_hash);
+
+        let pending_deployments = &self.pending_deployments;
+        let deploy_deadlines = &self.deploy_deadlines;
+        let is_pending_deploy = |request: &DeployRequest| {
+            pending_deployments.get(&request.artifact).as_ref() == Some(request)
+        };
+        let deploy_deadline = |request: &DeployRequest| {
+            deploy_deadlines
+                .get(request)
+                .unwrap_or(request.deadline_height)
+        };
+        removed += self
+            .deploy_requests
+            .retain(|request| is_pending_deploy(request) || deploy_deadline(request) > height);
+        removed += self.deadline_extension_requests.retain(|extension| {
+            is_pending_deploy(&extension.request)
+                && extension.new_deadline_height > deploy_deadline(&extension.request)
+        });
+
+        let pending_migrations = &self.pending_migrations;
+        removed += self.migration_requests.retain(|request| {
+            pending_migrations.contains(request) || request.deadline_height > height
+        });
+        removed += self
+            .migration_abort_requests
+            .retain(|request| pending_migrations.contains(request));
+        removed += self.remove_stale_stop_votes(height, |_| true);
+        removed
+    }
+
+    /// Removes votes for emergency stops which were collected for longer than
+    /// `StopService::VOTING_PERIOD` blocks, and votes for services which cannot be stopped
+    /// according to the predicate.
+    ///
+    /// Returns the amount of removed multisig entries.
+    pub(crate) fn remove_stale_stop_votes(
+        &mut self,
+        height: Height,
+        mut can_be_stopped: impl FnMut(InstanceId) -> bool,
+    ) -> usize {
+        let started = &self.emergency_stop_started;
+        let removed = self.emergency_stop_requests.retain(|request| {
+            let is_recent = started.get(&request.instance_id).map_or(false, |start| {
+                start.0.saturating_add(StopService::VOTING_PERIOD) > height.0
+            });
+            is_recent && can_be_stopped(request.instance_id)
+        });
+
+        let requests = &self.emergency_stop_requests;
+        let finished_votings: Vec<_> = self
+            .emergency_stop_started
+            .keys()
+            .filter(|&instance_id| requests.confirmations(&StopService::new(instance_id)) == 0)
+            .collect();
+        for instance_id in &finished_votings {
+            self.emergency_stop_started.remove(instance_id);
+        }
+        removed
+    }
+
+    /// Updates the state of a deployment and emits the corresponding event.
+    pub(crate) fn set_deploy_state(&mut self, request: &DeployRequest, state: AsyncEventState) {
+        self.deploy_states.put(request, state.clone());
+        self.events
+            .push(SupervisorEvent::deploy(request.clone(), state));
+    }
+
+    /// Updates the state of a migration and emits the corresponding event.
+    pub(crate) fn set_migration_state(
+        &mut self,
+        request: &MigrationRequest,
+        state: MigrationState,
+    ) {
+        self.migration_states.put(request, state.clone());
+        self.events
+            .push(SupervisorEvent::migration(request.clone(), state));
+    }
+
     /// Assigns a unique identifier for an instance.
     /// Returns `None` if `vacant_instance_id` entry was not initialized.
     pub(crate) fn assign_instance_id(&mut self) -> Option<InstanceId> {
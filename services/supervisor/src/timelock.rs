@@ -0,0 +1,186 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Timelocks delaying activation of sensitive configuration changes.
+
+use exonum::{blockchain::ValidatorKeys, helpers::Height, runtime::ExecutionError};
+use exonum_proto::ProtobufConvert;
+use serde::{Deserialize, Serialize};
+
+use super::{proto, ConfigChange, ConfigPropose, ConfigurationError, SupervisorConfig};
+
+/// Kind of configuration changes subject to a timelock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum TimelockedChange {
+    /// Changes of the validator set, performed via a new consensus config, a consensus
+    /// config patch or a `ChangeValidators` change. Changes of other consensus parameters
+    /// are not timelocked.
+    ValidatorSet,
+    /// Unloading of artifacts.
+    UnloadArtifact,
+}
+
+impl ProtobufConvert for TimelockedChange {
+    type ProtoStruct = proto::TimelockedChange;
+
+    fn to_pb(&self) -> Self::ProtoStruct {
+        match self {
+            Self::ValidatorSet => proto::TimelockedChange::VALIDATOR_SET,
+            Self::UnloadArtifact => proto::TimelockedChange::UNLOAD_ARTIFACT,
+        }
+    }
+
+    fn from_pb(pb: Self::ProtoStruct) -> anyhow::Result<Self> {
+        let result = match pb {
+            proto::TimelockedChange::VALIDATOR_SET => Self::ValidatorSet,
+            proto::TimelockedChange::UNLOAD_ARTIFACT => Self::UnloadArtifact,
+        };
+        Ok(result)
+    }
+}
+
+/// Minimum delay between approval and activation of configuration changes of a certain kind.
+///
+/// The delay is measured in blocks. A configuration proposal with timelocked changes
+/// is rejected if the activation height of the changes is closer to the current height
+/// than the delay. Furthermore, if the proposal is approved by the validators later
+/// than `min_delay` blocks before the activation height, the proposal is not applied
+/// and expires.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ProtobufConvert)]
+#[protobuf_convert(source = "proto::ConfigTimelock")]
+#[non_exhaustive]
+pub struct ConfigTimelock {
+    /// Kind of timelocked changes.
+    pub change: TimelockedChange,
+    /// Minimum number of blocks between approval and activation of a change.
+    pub min_delay: u64,
+}
+
+impl ConfigTimelock {
+    /// Creates a timelock for the specified kind of changes.
+    pub fn new(change: TimelockedChange, min_delay: u64) -> Self {
+        Self { change, min_delay }
+    }
+
+    /// Checks whether this timelock applies to the configuration change. `validator_keys`
+    /// are keys of the current validator set.
+    pub fn applies_to(&self, change: &ConfigChange, validator_keys: &[ValidatorKeys]) -> bool {
+        match (self.change, change) {
+            (TimelockedChange::ValidatorSet, ConfigChange::Consensus(config)) => {
+                config.validator_keys != validator_keys
+            }
+            (TimelockedChange::ValidatorSet, ConfigChange::ConsensusPatch(patch)) => patch
+                .validator_keys
+                .as_ref()
+                .map_or(false, |keys| keys != validator_keys),
+            (TimelockedChange::ValidatorSet, ConfigChange::ChangeValidators(_))
+            | (TimelockedChange::UnloadArtifact, ConfigChange::UnloadArtifact(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Checks that the changes in the proposal (including its stages) are activated no sooner
+/// than required by the timelocks, provided that the proposal is approved at `approved_at`.
+pub(crate) fn check_timelocks(
+    config: &SupervisorConfig,
+    propose: &ConfigPropose,
+    validator_keys: &[ValidatorKeys],
+    approved_at: Height,
+) -> Result<(), ExecutionError> {
+    let stages = propose
+        .stages
+        .iter()
+        .map(|stage| (stage.actual_from, &stage.changes));
+    let stages = Some((propose.actual_from, &propose.changes))
+        .into_iter()
+        .chain(stages);
+
+    for (actual_from, changes) in stages {
+        let delay = config.required_delay(changes, validator_keys);
+        if approved_at.0 + delay > actual_from.0 {
+            let msg = format!(
+                "Changes activated at height {} require at least {} blocks between approval \
+                 and activation (approval height: {})",
+                actual_from, delay, approved_at
+            );
+            return Err(ConfigurationError::TimelockViolation.with_description(msg));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChangeValidators, ConsensusConfigPatch, UnloadArtifact};
+
+    use exonum::{blockchain::ConsensusConfig, crypto::KeyPair, runtime::ArtifactId};
+
+    fn validator_keys() -> Vec<ValidatorKeys> {
+        (0..2)
+            .map(|_| {
+                ValidatorKeys::new(
+                    KeyPair::random().public_key(),
+                    KeyPair::random().public_key(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn validator_set_timelock() {
+        let keys = validator_keys();
+        let timelock = ConfigTimelock::new(TimelockedChange::ValidatorSet, 10);
+
+        let config = ConsensusConfig::default().with_validator_keys(keys.clone());
+        let change = ConfigChange::Consensus(config.clone());
+        assert!(!timelock.applies_to(&change, &keys));
+        let change = ConfigChange::Consensus(config.with_validator_keys(validator_keys()));
+        assert!(timelock.applies_to(&change, &keys));
+
+        let patch = ConsensusConfigPatch {
+            txs_block_limit: Some(10),
+            ..ConsensusConfigPatch::default()
+        };
+        assert!(!timelock.applies_to(&ConfigChange::ConsensusPatch(patch), &keys));
+        let patch = ConsensusConfigPatch {
+            validator_keys: Some(keys[..1].to_vec()),
+            ..ConsensusConfigPatch::default()
+        };
+        assert!(timelock.applies_to(&ConfigChange::ConsensusPatch(patch), &keys));
+
+        let change = ConfigChange::ChangeValidators(ChangeValidators::default());
+        assert!(timelock.applies_to(&change, &keys));
+        let artifact = ArtifactId::from_raw_parts(0, "test".into(), "1.0.0".parse().unwrap());
+        let change = ConfigChange::UnloadArtifact(UnloadArtifact {
+            artifact_id: artifact,
+        });
+        assert!(!timelock.applies_to(&change, &keys));
+    }
+
+    #[test]
+    fn unload_artifact_timelock() {
+        let timelock = ConfigTimelock::new(TimelockedChange::UnloadArtifact, 10);
+        let artifact = ArtifactId::from_raw_parts(0, "test".into(), "1.0.0".parse().unwrap());
+        let change = ConfigChange::UnloadArtifact(UnloadArtifact {
+            artifact_id: artifact,
+        });
+        assert!(timelock.applies_to(&change, &[]));
+        let change = ConfigChange::ChangeValidators(ChangeValidators::default());
+        assert!(!timelock.applies_to(&change, &[]));
+    }
+}
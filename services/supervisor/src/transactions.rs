@@ -16,21 +16,28 @@ use exonum::{
     crypto::{Hash, PublicKey},
     helpers::{Height, ValidateInput},
     runtime::{
-        migrations::MigrationType, CommonError, ExecutionContext, ExecutionError, ExecutionFail,
-        InstanceId, InstanceSpec, InstanceState, InstanceStatus, RuntimeFeature,
+        migrations::MigrationType, Capabilities, CommonError, ExecutionContext, ExecutionError,
+        ExecutionFail, InstanceId, InstanceSpec, InstanceState, InstanceStatus, RuntimeFeature,
     },
 };
 use exonum_derive::{exonum_interface, interface_method};
-use exonum_merkledb::ObjectHash;
+use exonum_merkledb::{validation::is_valid_index_name_component, ObjectHash};
 
 use std::collections::HashSet;
 
 use super::{
-    configure::ConfigureMut, migration_state::MigrationState, ArtifactError, AsyncEventState,
-    CommonError as SupervisorCommonError, ConfigChange, ConfigProposalWithHash, ConfigPropose,
-    ConfigVote, ConfigurationError, DeployRequest, DeployResult, FreezeService, MigrationError,
-    MigrationRequest, MigrationResult, ResumeService, SchemaImpl, ServiceError, StartService,
-    StopService, Supervisor, UnloadArtifact,
+    configure::ConfigureMut,
+    events::{ConfigProposalStatus, SupervisorEvent},
+    migration_state::{MigrationState, ValidatorMigrationProgress},
+    timelock::check_timelocks,
+    ArtifactError, AsyncEventState, ChangeValidators, CommonError as SupervisorCommonError,
+    ConfigChange, ConfigProposalWithHash, ConfigPropose, ConfigVote, ConfigurationError,
+    DelegateVotes, DeployDeadlineExtension, DeployRequest, DeployResult, DeprecateArtifact,
+    FreezeService, MigrationError, MigrationProgress, MigrationRequest, MigrationResult,
+    ModeChange, ModeChangeRequest, PendingUpgrade, ReadinessAttestation, RenameService,
+    ResumeService, SchemaImpl, ServiceError, SetArtifactCapabilities, SetDefaultInstance,
+    SetFeatureFlags, SetServiceMetadata, StartService, StopService, Supervisor, UnloadArtifact,
+    VoteDelegation,
 };
 use exonum::runtime::ArtifactStatus;
 
@@ -48,6 +55,9 @@ pub trait SupervisorInterface<Ctx> {
     /// of other validators as well).
     /// After that, the supervisor will try to deploy the artifact, and once this procedure
     /// is completed, it will send `report_deploy_result` transaction.
+    ///
+    /// Repeating a request for a pending deployment with the same artifact, spec and deadline
+    /// height (possibly with a different seed) from the same validator is a no-op.
     #[interface_method(id = 0)]
     fn request_artifact_deploy(&self, context: Ctx, artifact: DeployRequest) -> Self::Output;
 
@@ -98,6 +108,73 @@ pub trait SupervisorInterface<Ctx> {
     /// completes.
     #[interface_method(id = 5)]
     fn report_migration_result(&self, context: Ctx, result: MigrationResult) -> Self::Output;
+
+    /// Votes for aborting an ongoing migration.
+    ///
+    /// This request should be sent by validators to stop a migration before all the validators
+    /// report its result (e.g., if the migration takes too long). Once the vote is approved
+    /// (depending on the `Supervisor` mode, by one validator or by 2/3+1 validators),
+    /// the migration is marked as failed and is rolled back.
+    #[interface_method(id = 6)]
+    fn abort_migration(&self, context: Ctx, request: MigrationRequest) -> Self::Output;
+
+    /// Votes for extending the deadline of a pending artifact deployment.
+    ///
+    /// This request should be sent by validators if the deployment legitimately takes longer
+    /// than expected (e.g., for large artifacts). Once the vote is approved (depending on
+    /// the `Supervisor` mode, by one validator or by 2/3+1 validators), the new deadline
+    /// supersedes the deadline specified in the deploy request.
+    #[interface_method(id = 7)]
+    fn extend_deploy_deadline(
+        &self,
+        context: Ctx,
+        extension: DeployDeadlineExtension,
+    ) -> Self::Output;
+
+    /// Votes for an emergency stop of a service.
+    ///
+    /// Unlike stopping a service via a configuration proposal, the service is stopped
+    /// immediately once the vote is approved (depending on the `Supervisor` mode, by one
+    /// validator or by 2/3+1 validators), without waiting for the activation height.
+    /// This request is intended for incident response, e.g., for stopping a misbehaving service.
+    #[interface_method(id = 8)]
+    fn emergency_stop_service(&self, context: Ctx, request: StopService) -> Self::Output;
+
+    /// Reports intermediate progress of a migration on the validator node.
+    ///
+    /// Progress reports are optional and do not affect the outcome of the migration; they are
+    /// intended to provide visibility into long-running migrations. The latest report
+    /// of each validator is stored in the migration state.
+    #[interface_method(id = 9)]
+    fn report_migration_progress(&self, context: Ctx, progress: MigrationProgress) -> Self::Output;
+
+    /// Votes for switching the supervisor to another operating mode.
+    ///
+    /// The vote is approved according to the current mode (by one validator in the simple mode,
+    /// or by 2/3+1 validators in the decentralized mode). Votes are rejected if there are
+    /// pending deployments, migrations or configuration proposals, since these were approved
+    /// under the rules of the current mode. Applied mode changes are recorded
+    /// in the audit log of the supervisor schema.
+    #[interface_method(id = 10)]
+    fn change_mode(&self, context: Ctx, request: ModeChangeRequest) -> Self::Output;
+
+    /// Attests readiness of the validator node for a pending upgrade (an artifact deployment,
+    /// a service migration or a configuration proposal).
+    ///
+    /// Attestations do not affect the outcome of the upgrade; they allow to find out
+    /// unprepared nodes in advance. The latest attestation of each validator is stored
+    /// in the supervisor schema.
+    #[interface_method(id = 11)]
+    fn report_readiness(&self, context: Ctx, attestation: ReadinessAttestation) -> Self::Output;
+
+    /// Delegates supervisor votes of the validator to another key for a bounded period.
+    ///
+    /// The delegate may propose and vote for configuration changes, and vote for artifact
+    /// deployments and migrations on behalf of the validator; it cannot report results
+    /// of deployments or migrations. The delegation period is limited by
+    /// the `max_delegation_period` of the supervisor configuration.
+    #[interface_method(id = 12)]
+    fn delegate_votes(&self, context: Ctx, request: DelegateVotes) -> Self::Output;
 }
 
 impl ConfigChange {
@@ -109,6 +186,7 @@ impl ConfigChange {
             Self::StopService(service) => Some(service.instance_id),
             Self::FreezeService(service) => Some(service.instance_id),
             Self::ResumeService(service) => Some(service.instance_id),
+            Self::RenameService(service) => Some(service.instance_id),
             Self::Service(service) => Some(service.instance_id),
             _ => None,
         };
@@ -143,6 +221,13 @@ impl StartService {
                 );
                 ArtifactError::UnknownArtifact.with_description(msg)
             })?;
+        if artifact_state.status == ArtifactStatus::Deprecated {
+            let msg = format!(
+                "Discarded start of service `{}` from the deprecated artifact `{}`.",
+                self.name, self.artifact,
+            );
+            return Err(ArtifactError::ArtifactDeprecated.with_description(msg));
+        }
         if artifact_state.status != ArtifactStatus::Active {
             let msg = format!(
                 "Discarded start of service `{}` from the non-active artifact `{}`.",
@@ -226,21 +311,201 @@ impl UnloadArtifact {
     }
 }
 
-/// Checks if method was called by transaction, and transaction author is a validator.
-fn get_validator(context: &ExecutionContext<'_>) -> Result<PublicKey, ExecutionError> {
+impl DeprecateArtifact {
+    fn validate(&self, context: &ExecutionContext<'_>) -> Result<(), ExecutionError> {
+        let artifact_state = context
+            .data()
+            .for_dispatcher()
+            .get_artifact(&self.artifact_id)
+            .ok_or_else(|| {
+                let msg = format!(
+                    "Discarded deprecation of the unknown artifact `{}`",
+                    self.artifact_id
+                );
+                ArtifactError::UnknownArtifact.with_description(msg)
+            })?;
+
+        if artifact_state.status != ArtifactStatus::Active {
+            let msg = format!(
+                "Discarded deprecation of artifact `{}` with non-active status ({})",
+                self.artifact_id, artifact_state.status
+            );
+            return Err(ConfigurationError::malformed_propose(msg));
+        }
+        Ok(())
+    }
+}
+
+impl SetFeatureFlags {
+    fn validate(&self, context: &ExecutionContext<'_>) -> Result<(), ExecutionError> {
+        let instance = get_instance(context, self.instance_id)?;
+        if self.flags.is_empty() {
+            let msg = format!(
+                "Discarded an empty feature flags update for service `{}`",
+                instance.spec.name
+            );
+            return Err(ConfigurationError::malformed_propose(msg));
+        }
+        if self.flags.keys().any(String::is_empty) {
+            let msg = format!(
+                "Discarded a feature flag of service `{}` with an empty name",
+                instance.spec.name
+            );
+            return Err(ConfigurationError::malformed_propose(msg));
+        }
+        Ok(())
+    }
+}
+
+impl SetServiceMetadata {
+    fn validate(&self, context: &ExecutionContext<'_>) -> Result<(), ExecutionError> {
+        let instance = get_instance(context, self.instance_id)?;
+        if self.metadata.entries.keys().any(String::is_empty) {
+            let msg = format!(
+                "Discarded metadata of service `{}` with an empty key",
+                instance.spec.name
+            );
+            return Err(ConfigurationError::malformed_propose(msg));
+        }
+        Ok(())
+    }
+}
+
+impl RenameService {
+    fn validate(&self, context: &ExecutionContext<'_>) -> Result<(), ExecutionError> {
+        InstanceSpec::is_valid_name(&self.new_name).map_err(|e| {
+            let msg = format!("Service name `{}` is invalid: {}", self.new_name, e);
+            ServiceError::InvalidInstanceName.with_description(msg)
+        })?;
+
+        let instance = get_instance(context, self.instance_id)?;
+        let is_stopped = instance.status == Some(InstanceStatus::Stopped);
+        if !is_stopped || instance.pending_status.is_some() {
+            let status = instance
+                .status
+                .as_ref()
+                .map_or_else(|| "none".to_owned(), ToString::to_string);
+            let msg = format!(
+                "Discarded an attempt to rename service `{}` with inappropriate status ({}); \
+                 only stopped services can be renamed",
+                instance.spec.name, status
+            );
+            return Err(ConfigurationError::malformed_propose(msg));
+        }
+
+        let dispatcher_data = context.data().for_dispatcher();
+        if dispatcher_data
+            .get_instance(self.new_name.as_str())
+            .is_some()
+        {
+            return Err(ServiceError::InstanceExists.with_description(format!(
+                "Discarded renaming of service `{}` to the already used name `{}`",
+                instance.spec.name, self.new_name
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl SetDefaultInstance {
+    fn validate(&self, context: &ExecutionContext<'_>) -> Result<(), ExecutionError> {
+        if self.interface.is_empty() || !is_valid_index_name_component(&self.interface) {
+            let msg = format!(
+                "Interface name `{}` is invalid; use only: a-zA-Z0-9 and _-",
+                self.interface
+            );
+            return Err(ConfigurationError::malformed_propose(msg));
+        }
+        get_instance(context, self.instance_id)?;
+        Ok(())
+    }
+}
+
+impl SetArtifactCapabilities {
+    fn validate(&self, context: &ExecutionContext<'_>) -> Result<(), ExecutionError> {
+        let dispatcher_data = context.data().for_dispatcher();
+        let artifact_state = dispatcher_data
+            .get_artifact(&self.artifact)
+            .ok_or_else(|| {
+                let msg = format!(
+                    "Discarded setting capabilities of the unknown artifact `{}`",
+                    self.artifact
+                );
+                ArtifactError::UnknownArtifact.with_description(msg)
+            })?;
+
+        if !artifact_state.status.is_deployed() {
+            let msg = format!(
+                "Discarded setting capabilities of artifact `{}` which is not deployed ({})",
+                self.artifact, artifact_state.status
+            );
+            return Err(ConfigurationError::malformed_propose(msg));
+        }
+
+        // The supervisor must retain all capabilities in order to remain operational.
+        let is_supervisor_artifact = dispatcher_data
+            .get_instance(context.instance().id)
+            .map_or(false, |state| state.spec.artifact == self.artifact);
+        if is_supervisor_artifact && self.capabilities != Capabilities::all() {
+            let msg = format!(
+                "Discarded restricting capabilities of the supervisor artifact `{}`",
+                self.artifact
+            );
+            return Err(ConfigurationError::malformed_propose(msg));
+        }
+        Ok(())
+    }
+}
+
+/// Role required from the author of a supervisor transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    /// The author must be a validator.
+    Validator,
+    /// The author must be either a validator or a delegate of a validator.
+    Voter,
+    /// The author must be either a validator, a delegate of a validator, or a holder
+    /// of a governance key from the supervisor configuration.
+    Governance,
+}
+
+/// Checks if method was called by transaction, and transaction author has the specified role.
+fn get_author(context: &ExecutionContext<'_>, role: Role) -> Result<PublicKey, ExecutionError> {
     let author = context
         .caller()
         .author()
         .ok_or(CommonError::UnauthorizedCaller)?;
 
-    // Verify that transaction author is validator.
-    context
-        .data()
-        .for_core()
-        .validator_id(author)
-        .ok_or(CommonError::UnauthorizedCaller)?;
-
-    Ok(author)
+    // Validators are allowed to perform any action.
+    let core_schema = context.data().for_core();
+    if core_schema.validator_id(author).is_some() {
+        return Ok(author);
+    }
+    // During the grace period after the key replacement, the old service key of a validator
+    // acts on behalf of the new one, provided that the validator is still a part
+    // of the validator set.
+    let schema = SchemaImpl::new(context.service_data());
+    if let Some(handover) = schema.key_handovers.get(&author) {
+        if core_schema.validator_id(handover.new_service_key).is_some() {
+            return Ok(handover.new_service_key);
+        }
+    }
+    if role == Role::Validator {
+        return Err(CommonError::UnauthorizedCaller.into());
+    }
+    // Delegates vote on behalf of the validator which is still a part of the validator set.
+    if let Some(delegation) = schema.vote_delegations.get(&author) {
+        if core_schema.validator_id(delegation.validator_key).is_some() {
+            return Ok(delegation.validator_key);
+        }
+    }
+    if role == Role::Governance {
+        let config = schema.supervisor_config();
+        if config.is_governance_key(&author) {
+            return Ok(author);
+        }
+    }
+    Err(CommonError::UnauthorizedCaller.into())
 }
 
 /// Returns the information about a service instance by its identifier.
@@ -307,7 +572,7 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
         mut context: ExecutionContext<'_>,
         mut propose: ConfigPropose,
     ) -> Self::Output {
-        let author = get_validator(&context)?;
+        let author = get_author(&context, Role::Governance)?;
         let current_height = context.data().for_core().height();
 
         // If `actual_from` field is not set, set it to the next height.
@@ -322,23 +587,48 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
             return Err(SupervisorCommonError::ActualFromIsPast.with_description(msg));
         }
 
+        // Verify that the expiration height (if any) is in the future.
+        if let Some(expires_at) = propose.expires_at {
+            if current_height >= expires_at {
+                let msg = format!(
+                    "Expiration height for config proposal ({}) is in the past \
+                     (current height: {}).",
+                    expires_at, current_height
+                );
+                return Err(SupervisorCommonError::ProposalExpired.with_description(msg));
+            }
+        }
+
         let mut schema = SchemaImpl::new(context.service_data());
 
         // Verify that there are no pending config changes.
         if let Some(proposal) = schema.public.pending_proposal.get() {
             // We have a proposal, check that it's actual.
-            if current_height < proposal.config_propose.actual_from {
+            if current_height < proposal.config_propose.deadline_height() {
                 return Err(ConfigurationError::ConfigProposeExists.into());
             }
             // Proposal is outdated but was not removed (e.g. because of the panic
-            // during config applying), clean it.
+            // during config applying), clean it along with the votes for it.
             schema.public.pending_proposal.remove();
+            schema.config_confirms.reset(&proposal.propose_hash);
+            schema.config_approved_at.remove(&proposal.propose_hash);
+        }
+        // Verify that the stages of the previous multi-stage proposal are applied.
+        if schema.staged_proposal.exists() {
+            let msg = "Stages of the previously applied proposal are not yet applied";
+            return Err(ConfigurationError::ConfigProposeExists.with_description(msg));
         }
         drop(schema);
 
         // Verify changes in the proposal.
-        Self::verify_config_changes(&mut context, &propose.changes)?;
+        Self::verify_config_changes(&mut context, &propose.changes, propose.actual_from)?;
+        Self::verify_config_stages(&propose)?;
+        // Verify that timelocked changes are not activated too soon. The proposal cannot
+        // be approved earlier than at the current height.
+        let validator_keys = context.data().for_core().consensus_config().validator_keys;
         let mut schema = SchemaImpl::new(context.service_data());
+        let configuration = schema.supervisor_config();
+        check_timelocks(&configuration, &propose, &validator_keys, current_height)?;
 
         // After all the checks verify that configuration number is expected one.
         let expected_config_number = schema.get_configuration_number();
@@ -359,6 +649,10 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
             propose_hash,
         };
         schema.public.pending_proposal.set(config_entry);
+        schema.events.push(SupervisorEvent::config_proposal(
+            propose_hash,
+            ConfigProposalStatus::Registered,
+        ));
 
         Ok(())
     }
@@ -368,7 +662,7 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
         context: ExecutionContext<'_>,
         vote: ConfigVote,
     ) -> Self::Output {
-        let author = get_validator(&context)?;
+        let author = get_author(&context, Role::Governance)?;
 
         let core_schema = context.data().for_core();
         let mut schema = SchemaImpl::new(context.service_data());
@@ -388,14 +682,15 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
             return Err(ConfigurationError::ConfigProposeNotRegistered.with_description(msg));
         }
 
-        // Verify that we didn't reach the deadline height.
-        let config_propose = entry.config_propose;
+        // Verify that we didn't reach the deadline height, which is the earlier
+        // of the activation and expiration heights.
+        let deadline_height = entry.config_propose.deadline_height();
         let current_height = core_schema.height();
-        if config_propose.actual_from <= current_height {
+        if deadline_height <= current_height {
             let msg = format!(
                 "Deadline height ({}) exceeded for the config proposal ({}); \
                  voting for it is impossible",
-                config_propose.actual_from, current_height
+                deadline_height, current_height
             );
             return Err(SupervisorCommonError::DeadlineExceeded.with_description(msg));
         }
@@ -408,6 +703,10 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
         }
 
         schema.config_confirms.confirm(&vote.propose_hash, author);
+        schema.events.push(SupervisorEvent::config_proposal(
+            vote.propose_hash,
+            ConfigProposalStatus::Confirmed,
+        ));
         log::trace!(
             "Propose config {:?} has been confirmed by {:?}",
             vote.propose_hash,
@@ -419,11 +718,11 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
 
     fn request_artifact_deploy(
         &self,
-        context: ExecutionContext<'_>,
+        mut context: ExecutionContext<'_>,
         deploy: DeployRequest,
     ) -> Self::Output {
         // Verify that transaction author is validator.
-        let author = get_validator(&context)?;
+        let author = get_author(&context, Role::Voter)?;
 
         deploy.artifact.validate().map_err(|e| {
             let msg = format!(
@@ -433,6 +732,11 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
             ArtifactError::InvalidArtifactId.with_description(msg)
         })?;
 
+        // Let the target runtime validate the deploy specification.
+        context
+            .supervisor_extensions()
+            .check_deploy_spec(&deploy.artifact, &deploy.spec)?;
+
         // Check that we didn't reach the deadline height.
         let core_schema = context.data().for_core();
         let current_height = core_schema.height();
@@ -453,27 +757,32 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
         }
 
         // If deployment is already registered, check whether the request is new.
-        if schema.pending_deployments.contains(&deploy.artifact) {
-            let new_confirmation = !schema.deploy_requests.confirmed_by(&deploy, &author);
-            return if new_confirmation {
+        if let Some(pending_deploy) = schema.pending_deployments.get(&deploy.artifact) {
+            let is_repeated = schema.deploy_requests.confirmed_by(&deploy, &author)
+                || (deploy.has_same_params(&pending_deploy)
+                    && schema
+                        .deploy_requests
+                        .confirmed_by(&pending_deploy, &author));
+            if is_repeated {
+                // Author already confirmed deployment of this artifact with the same params,
+                // so the request is a retry and does not change anything.
+                log::trace!(
+                    "Ignored repeated deploy request {:?} from validator {}",
+                    deploy,
+                    author
+                );
+            } else {
                 // It's OK, just an additional confirmation.
                 schema.deploy_requests.confirm(&deploy, author);
-                Ok(())
-            } else {
-                // Author already confirmed deployment of this artifact, so it's a duplicate.
-                let msg = format!(
-                    "Deploy of artifact `{}` is already confirmed by validator {}",
-                    deploy.artifact, author
-                );
-                Err(ArtifactError::DeployRequestAlreadyRegistered.with_description(msg))
-            };
+            }
+            return Ok(());
         }
 
         schema.deploy_requests.confirm(&deploy, author);
         let supervisor_mode = schema.supervisor_config().mode;
         let validator_count = core_schema.consensus_config().validator_keys.len();
         if supervisor_mode.deploy_approved(&deploy, &schema.deploy_requests, validator_count) {
-            schema.deploy_states.put(&deploy, AsyncEventState::Pending);
+            schema.set_deploy_state(&deploy, AsyncEventState::Pending);
             log::trace!("Deploy artifact request accepted {:?}", deploy.artifact);
             let artifact = deploy.artifact.clone();
             schema.pending_deployments.put(&artifact, deploy);
@@ -487,7 +796,7 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
         deploy_result: DeployResult,
     ) -> Self::Output {
         // Verify that transaction author is validator.
-        let author = get_validator(&context)?;
+        let author = get_author(&context, Role::Validator)?;
         let core_schema = context.data().for_core();
         let current_height = core_schema.height();
         let schema = SchemaImpl::new(context.service_data());
@@ -526,18 +835,22 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
         }
 
         // Verify that we didn't reach deadline height.
-        if deploy_request.deadline_height < current_height {
+        let deadline_height = schema.deploy_deadline(&deploy_request);
+        if deadline_height < current_height {
             let msg = format!(
                 "Deadline height ({}) exceeded for the deploy request ({}); \
                  reporting deploy result is impossible",
-                deploy_request.deadline_height, current_height
+                deadline_height, current_height
             );
             return Err(SupervisorCommonError::DeadlineExceeded.with_description(msg));
         }
 
         drop(schema);
         match deploy_result.result.0 {
-            Ok(()) => Self::confirm_deploy(context, deploy_request, author)?,
+            Ok(()) => {
+                let checksum = deploy_result.artifact_checksum;
+                Self::confirm_deploy(context, deploy_request, author, checksum)?
+            }
             Err(error) => Self::fail_deploy(&context, &deploy_request, error),
         }
         Ok(())
@@ -549,7 +862,7 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
         request: MigrationRequest,
     ) -> Self::Output {
         // Verify that transaction author is validator.
-        let author = get_validator(&context)?;
+        let author = get_author(&context, Role::Voter)?;
 
         // Check that target instance exists.
         let instance = get_instance_by_name(&context, &request.service)?;
@@ -567,8 +880,17 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
         }
 
         let mut schema = SchemaImpl::new(context.service_data());
+        let supervisor_config = schema.supervisor_config();
+
+        // Check that the migration conforms to the upgrade policy for the artifact.
+        if let Some(policy) = supervisor_config.upgrade_policy(&request.new_artifact) {
+            policy
+                .check_upgrade(instance.data_version(), &request.new_artifact.version)
+                .map_err(|e| MigrationError::UpgradePolicyViolation.with_description(e))?;
+        }
+
         schema.migration_requests.confirm(&request, author);
-        let supervisor_mode = schema.supervisor_config().mode;
+        let supervisor_mode = supervisor_config.mode;
         let migration_approved = supervisor_mode.migration_approved(
             &request,
             &schema.migration_requests,
@@ -583,7 +905,7 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
             // Store initial state of the request.
             let mut state =
                 MigrationState::new(AsyncEventState::Pending, instance.data_version().clone());
-            schema.migration_states.put(&request, state.clone());
+            schema.set_migration_state(&request, state.clone());
             // Store the migration as pending. It will be removed in `before_transactions` hook
             // once the migration will be completed (either successfully or unsuccessfully).
             schema.pending_migrations.insert(request.clone());
@@ -617,9 +939,12 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
                 let mut schema = SchemaImpl::new(context.service_data());
                 // Update the state of a migration.
                 state.update(AsyncEventState::Succeed, new_version);
-                schema.migration_states.put(&request, state);
+                schema.set_migration_state(&request, state);
                 // Remove the migration from the list of pending.
                 schema.pending_migrations.remove(&request);
+                if request.unload_superseded_artifact {
+                    schema.superseded_artifacts.insert(&instance.spec.artifact);
+                }
             }
         }
         Ok(())
@@ -631,7 +956,7 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
         result: MigrationResult,
     ) -> Self::Output {
         // Verifies that transaction author is validator.
-        let author = get_validator(&context)?;
+        let author = get_author(&context, Role::Validator)?;
 
         let core_schema = context.data().for_core();
         let current_height = core_schema.height();
@@ -681,13 +1006,369 @@ impl SupervisorInterface<ExecutionContext<'_>> for Supervisor {
             }
         }
     }
+
+    fn report_migration_progress(
+        &self,
+        context: ExecutionContext<'_>,
+        progress: MigrationProgress,
+    ) -> Self::Output {
+        // Verify that transaction author is validator.
+        let author = get_author(&context, Role::Validator)?;
+
+        if progress.percentage > 100 {
+            let msg = format!(
+                "Migration progress percentage ({}) exceeds 100",
+                progress.percentage
+            );
+            return Err(MigrationError::InvalidMigrationProgress.with_description(msg));
+        }
+
+        let height = context.data().for_core().next_height();
+        let mut schema = SchemaImpl::new(context.service_data());
+
+        // Verify that this migration is registered.
+        let request = progress.request;
+        let mut state = schema.migration_states.get(&request).ok_or_else(|| {
+            let msg = format!(
+                "Migration request {:?} is not registered; impossible to report its progress",
+                request
+            );
+            MigrationError::MigrationRequestNotRegistered.with_description(msg)
+        })?;
+
+        // Progress can be reported only for an ongoing migration.
+        if !state.is_pending() {
+            let msg = format!(
+                "Migration request {:?} is already completed; impossible to report its progress",
+                request
+            );
+            return Err(MigrationError::MigrationNotPending.with_description(msg));
+        }
+
+        state.report_progress(ValidatorMigrationProgress {
+            validator: author,
+            processed_keys: progress.processed_keys,
+            percentage: progress.percentage,
+            height,
+        });
+        schema.migration_states.put(&request, state);
+        Ok(())
+    }
+
+    fn abort_migration(
+        &self,
+        context: ExecutionContext<'_>,
+        request: MigrationRequest,
+    ) -> Self::Output {
+        // Verify that transaction author is validator.
+        let author = get_author(&context, Role::Voter)?;
+
+        let core_schema = context.data().for_core();
+        let validator_count = core_schema.consensus_config().validator_keys.len();
+        let mut schema = SchemaImpl::new(context.service_data());
+
+        // Verify that this migration is registered.
+        let state = schema.migration_states.get(&request).ok_or_else(|| {
+            let msg = format!(
+                "Migration request {:?} is not registered; impossible to abort it",
+                request
+            );
+            MigrationError::MigrationRequestNotRegistered.with_description(msg)
+        })?;
+
+        // Only an ongoing migration can be aborted.
+        if !state.is_pending() {
+            let msg = format!(
+                "Migration request {:?} is already completed; impossible to abort it",
+                request
+            );
+            return Err(MigrationError::MigrationNotPending.with_description(msg));
+        }
+
+        schema.migration_abort_requests.confirm(&request, author);
+        let supervisor_mode = schema.supervisor_config().mode;
+        let abort_approved = supervisor_mode.migration_approved(
+            &request,
+            &schema.migration_abort_requests,
+            validator_count,
+        );
+
+        if abort_approved {
+            log::trace!("Abort of migration request {:?} accepted", request);
+            drop(schema);
+            let fail_cause = MigrationError::MigrationAborted
+                .with_description("Migration aborted by validators");
+            let initiate_rollback = true;
+            Self::fail_migration(context, &request, fail_cause, initiate_rollback)?;
+        }
+        Ok(())
+    }
+
+    fn extend_deploy_deadline(
+        &self,
+        context: ExecutionContext<'_>,
+        extension: DeployDeadlineExtension,
+    ) -> Self::Output {
+        // Verify that transaction author is validator.
+        let author = get_author(&context, Role::Voter)?;
+
+        let core_schema = context.data().for_core();
+        let validator_count = core_schema.consensus_config().validator_keys.len();
+        let mut schema = SchemaImpl::new(context.service_data());
+
+        // Verify that the deployment is pending.
+        let request = &extension.request;
+        let is_pending = schema
+            .pending_deployments
+            .get(&request.artifact)
+            .map_or(false, |pending_request| pending_request == *request);
+        if !is_pending {
+            let msg = format!(
+                "Deploy request {:?} is not pending; impossible to extend its deadline",
+                request
+            );
+            return Err(ArtifactError::DeployRequestNotRegistered.with_description(msg));
+        }
+
+        let deadline_height = schema.deploy_deadline(request);
+        if extension.new_deadline_height <= deadline_height {
+            let msg = format!(
+                "New deadline height ({}) for deploy of artifact `{}` does not exceed \
+                 the current deadline height ({})",
+                extension.new_deadline_height, request.artifact, deadline_height
+            );
+            return Err(ArtifactError::InvalidDeadlineExtension.with_description(msg));
+        }
+
+        schema
+            .deadline_extension_requests
+            .confirm(&extension, author);
+        let supervisor_mode = schema.supervisor_config().mode;
+        let extension_approved = supervisor_mode.deadline_extension_approved(
+            &extension,
+            &schema.deadline_extension_requests,
+            validator_count,
+        );
+
+        if extension_approved {
+            log::trace!(
+                "Extended deadline of deploy request {:?} to height {}",
+                request,
+                extension.new_deadline_height
+            );
+            schema
+                .deploy_deadlines
+                .put(request, extension.new_deadline_height);
+        }
+        Ok(())
+    }
+
+    fn emergency_stop_service(
+        &self,
+        mut context: ExecutionContext<'_>,
+        request: StopService,
+    ) -> Self::Output {
+        // Verify that transaction author is validator.
+        let author = get_author(&context, Role::Validator)?;
+        request.validate(&context)?;
+
+        let core_schema = context.data().for_core();
+        let next_height = core_schema.next_height();
+        let config = core_schema.consensus_config();
+        let validator_count = config.validator_keys.len();
+        let validator_keys = config.validator_keys.iter().map(|keys| keys.service_key);
+        let mut schema = SchemaImpl::new(context.service_data());
+
+        if !schema.emergency_stop_started.contains(&request.instance_id) {
+            schema
+                .emergency_stop_started
+                .put(&request.instance_id, next_height);
+        }
+        schema.emergency_stop_requests.confirm(&request, author);
+        // Votes of the nodes which are not validators anymore are not taken into account.
+        schema
+            .emergency_stop_requests
+            .retain_validator_confirmations(&request, validator_keys);
+        let supervisor_mode = schema.supervisor_config().mode;
+        let stop_approved = supervisor_mode.emergency_stop_approved(
+            &request,
+            &schema.emergency_stop_requests,
+            validator_count,
+        );
+
+        if stop_approved {
+            log::warn!(
+                "Emergency stop of service with ID {} accepted",
+                request.instance_id
+            );
+            // Votes are reset, so that the service could be stopped again once resumed.
+            schema.emergency_stop_requests.reset(&request);
+            schema.emergency_stop_started.remove(&request.instance_id);
+            schema.scheduled_resumes.remove(&request.instance_id);
+            drop(schema);
+            context
+                .supervisor_extensions()
+                .initiate_stopping_service(request.instance_id)?;
+        }
+        Ok(())
+    }
+
+    fn change_mode(
+        &self,
+        context: ExecutionContext<'_>,
+        request: ModeChangeRequest,
+    ) -> Self::Output {
+        // Verify that transaction author is validator.
+        let author = get_author(&context, Role::Validator)?;
+
+        let core_schema = context.data().for_core();
+        let validator_count = core_schema.consensus_config().validator_keys.len();
+        let height = core_schema.next_height();
+        let mut schema = SchemaImpl::new(context.service_data());
+        let mut config = schema.supervisor_config();
+
+        if config.mode == request.new_mode {
+            let msg = format!("Supervisor is already in the {} mode", request.new_mode);
+            return Err(ConfigurationError::ModeAlreadyActive.with_description(msg));
+        }
+        let activities = schema.activities_blocking_mode_change();
+        if !activities.is_empty() {
+            let msg = format!(
+                "Cannot switch supervisor to the {} mode while there are pending {}",
+                request.new_mode,
+                activities.join(", ")
+            );
+            return Err(ConfigurationError::ModeChangeConflict.with_description(msg));
+        }
+
+        schema.mode_change_requests.confirm(&request, author);
+        let change_approved = config.mode.clone().mode_change_approved(
+            &request,
+            &schema.mode_change_requests,
+            validator_count,
+        );
+
+        if change_approved {
+            log::info!(
+                "Switching supervisor from the {} mode to the {} mode",
+                config.mode,
+                request.new_mode
+            );
+            // Votes are reset, so that the mode could be switched back later.
+            schema.mode_change_requests.reset(&request);
+            schema.mode_changes.push(ModeChange {
+                previous_mode: config.mode,
+                new_mode: request.new_mode.clone(),
+                height,
+            });
+            config.mode = request.new_mode;
+            schema.public.configuration.set(config);
+        }
+        Ok(())
+    }
+
+    fn report_readiness(
+        &self,
+        context: ExecutionContext<'_>,
+        attestation: ReadinessAttestation,
+    ) -> Self::Output {
+        // Verify that transaction author is validator.
+        let author = get_author(&context, Role::Validator)?;
+
+        let schema = SchemaImpl::new(context.service_data());
+        let is_pending = PendingUpgrade::from_schema(&schema)
+            .iter()
+            .any(|upgrade| upgrade.subject == attestation.subject);
+        if !is_pending {
+            let msg = format!("Upgrade with hash {} is not pending", attestation.subject);
+            return Err(SupervisorCommonError::UnknownUpgrade.with_description(msg));
+        }
+
+        schema
+            .readiness_attestations
+            .get(&attestation.subject)
+            .put(&author, attestation);
+        Ok(())
+    }
+
+    fn delegate_votes(
+        &self,
+        context: ExecutionContext<'_>,
+        request: DelegateVotes,
+    ) -> Self::Output {
+        let author = context
+            .caller()
+            .author()
+            .ok_or(CommonError::UnauthorizedCaller)?;
+        // Only the validator itself may delegate its votes.
+        let core_schema = context.data().for_core();
+        if core_schema.validator_id(author).is_none() {
+            return Err(CommonError::UnauthorizedCaller.into());
+        }
+
+        let next_height = core_schema.next_height();
+        let mut schema = SchemaImpl::new(context.service_data());
+        let current_delegate = schema
+            .vote_delegations
+            .iter()
+            .find(|(_, delegation)| delegation.validator_key == author)
+            .map(|(delegate, _)| delegate);
+
+        if request.is_revocation() {
+            if current_delegate != Some(request.delegate) {
+                let msg = format!("Votes are not delegated to key {}", request.delegate);
+                return Err(SupervisorCommonError::InvalidDelegation.with_description(msg));
+            }
+            schema.vote_delegations.remove(&request.delegate);
+            return Ok(());
+        }
+
+        let max_period = schema.supervisor_config().max_delegation_period;
+        if max_period == 0 {
+            let msg = "Vote delegation is disabled by the supervisor configuration";
+            return Err(SupervisorCommonError::InvalidDelegation.with_description(msg));
+        }
+        if request.expires_at <= next_height || request.expires_at.0 - next_height.0 > max_period {
+            let msg = format!(
+                "Delegation should expire after height {} and not later than height {}",
+                next_height,
+                next_height.0.saturating_add(max_period)
+            );
+            return Err(SupervisorCommonError::InvalidDelegation.with_description(msg));
+        }
+
+        let is_delegate_used = request.delegate == author
+            || core_schema.validator_id(request.delegate).is_some()
+            || schema
+                .vote_delegations
+                .get(&request.delegate)
+                .map_or(false, |delegation| delegation.validator_key != author);
+        if is_delegate_used {
+            let msg = format!(
+                "Key {} is already used by another validator or delegate",
+                request.delegate
+            );
+            return Err(SupervisorCommonError::InvalidDelegation.with_description(msg));
+        }
+
+        if let Some(delegate) = current_delegate {
+            schema.vote_delegations.remove(&delegate);
+        }
+        let delegation = VoteDelegation {
+            validator_key: author,
+            expires_at: request.expires_at,
+        };
+        schema.vote_delegations.put(&request.delegate, delegation);
+        Ok(())
+    }
 }
 
 impl Supervisor {
     /// Verifies that each change introduced within config proposal is valid.
-    fn verify_config_changes(
+    pub(crate) fn verify_config_changes(
         context: &mut ExecutionContext<'_>,
         changes: &[ConfigChange],
+        actual_from: Height,
     ) -> Result<(), ExecutionError> {
         // To prevent multiple consensus change proposition in one request
         let mut consensus_propose_added = false;
@@ -695,9 +1376,20 @@ impl Supervisor {
         let mut modified_instances = HashSet::new();
         // To prevent multiple services start in one request.
         let mut services_to_start = HashSet::new();
-        // To prevent starting services with an unloaded artifact.
+        // To prevent starting services with an unloaded or deprecated artifact.
         let mut artifacts_for_started_services = HashSet::new();
         let mut unloaded_artifacts = HashSet::new();
+        let mut deprecated_artifacts = HashSet::new();
+        // To prevent multiple metadata updates for a single service in one request.
+        let mut services_with_metadata = HashSet::new();
+        // To prevent multiple feature flag updates for a single service in one request.
+        let mut services_with_flag_updates = HashSet::new();
+        // To prevent assigning the same name to several services in one request.
+        let mut new_service_names = HashSet::new();
+        // To prevent assigning several default instances to an interface in one request.
+        let mut interfaces_with_defaults = HashSet::new();
+        // To prevent several capability updates for a single artifact in one request.
+        let mut artifacts_with_capabilities = HashSet::new();
 
         // Perform config verification.
         for change in changes {
@@ -714,6 +1406,46 @@ impl Supervisor {
                         .map_err(ConfigurationError::malformed_propose)?;
                 }
 
+                ConfigChange::ConsensusPatch(patch) => {
+                    if consensus_propose_added {
+                        let msg = "Discarded multiple consensus change proposals in one request";
+                        return Err(ConfigurationError::malformed_propose(msg));
+                    }
+                    consensus_propose_added = true;
+                    if patch.is_empty() {
+                        let msg = "Consensus config patch does not modify any parameters";
+                        return Err(ConfigurationError::malformed_propose(msg));
+                    }
+                    patch
+                        .apply(&context.data().for_core().consensus_config())
+                        .validate()
+                        .map_err(ConfigurationError::malformed_propose)?;
+                }
+
+                ConfigChange::ChangeValidators(change) => {
+                    if consensus_propose_added {
+                        let msg = "Discarded multiple consensus change proposals in one request";
+                        return Err(ConfigurationError::malformed_propose(msg));
+                    }
+                    consensus_propose_added = true;
+                    if change.is_empty() {
+                        let msg = "Validator set change does not modify any validators";
+                        return Err(ConfigurationError::malformed_propose(msg));
+                    }
+                    if change.grace_period > ChangeValidators::MAX_GRACE_PERIOD {
+                        let msg = format!(
+                            "Grace period of the validator set change exceeds the maximum ({})",
+                            ChangeValidators::MAX_GRACE_PERIOD
+                        );
+                        return Err(ConfigurationError::malformed_propose(msg));
+                    }
+                    change
+                        .apply(&context.data().for_core().consensus_config())
+                        .map_err(ConfigurationError::malformed_propose)?
+                        .validate()
+                        .map_err(ConfigurationError::malformed_propose)?;
+                }
+
                 ConfigChange::Service(config) => {
                     context.verify_config(config.instance_id, config.params.clone())?;
                 }
@@ -753,6 +1485,19 @@ impl Supervisor {
                         );
                         return Err(ConfigurationError::malformed_propose(msg));
                     }
+
+                    if let Some(resume_at) = freeze_service.resume_at {
+                        if resume_at <= actual_from {
+                            let msg = format!(
+                                "Discarded freezing of service `{}` with resume height ({}) \
+                                 not exceeding the activation height of the proposal ({})",
+                                instance_state.spec.as_descriptor(),
+                                resume_at,
+                                actual_from
+                            );
+                            return Err(ConfigurationError::malformed_propose(msg));
+                        }
+                    }
                 }
 
                 ConfigChange::UnloadArtifact(unload_artifact) => {
@@ -765,9 +1510,85 @@ impl Supervisor {
                     }
                     unload_artifact.validate(context)?;
                 }
+
+                ConfigChange::DeprecateArtifact(deprecate_artifact) => {
+                    if !deprecated_artifacts.insert(&deprecate_artifact.artifact_id) {
+                        let msg = format!(
+                            "Discarded multiple deprecations of artifact `{}`",
+                            deprecate_artifact.artifact_id
+                        );
+                        return Err(ConfigurationError::malformed_propose(msg));
+                    }
+                    deprecate_artifact.validate(context)?;
+                }
+
+                ConfigChange::SetServiceMetadata(set_metadata) => {
+                    if !services_with_metadata.insert(set_metadata.instance_id) {
+                        let msg = format!(
+                            "Discarded multiple metadata updates for service with ID {}",
+                            set_metadata.instance_id
+                        );
+                        return Err(ConfigurationError::malformed_propose(msg));
+                    }
+                    set_metadata.validate(context)?;
+                }
+
+                ConfigChange::SetFeatureFlags(set_flags) => {
+                    if !services_with_flag_updates.insert(set_flags.instance_id) {
+                        let msg = format!(
+                            "Discarded multiple feature flag updates for service with ID {}",
+                            set_flags.instance_id
+                        );
+                        return Err(ConfigurationError::malformed_propose(msg));
+                    }
+                    set_flags.validate(context)?;
+                }
+
+                ConfigChange::RenameService(rename_service) => {
+                    if !new_service_names.insert(&rename_service.new_name) {
+                        let msg = format!(
+                            "Discarded multiple renames of services to `{}`",
+                            rename_service.new_name
+                        );
+                        return Err(ConfigurationError::malformed_propose(msg));
+                    }
+                    rename_service.validate(context)?;
+                }
+
+                ConfigChange::SetDefaultInstance(set_default) => {
+                    if !interfaces_with_defaults.insert(&set_default.interface) {
+                        let msg = format!(
+                            "Discarded multiple default instances for interface `{}`",
+                            set_default.interface
+                        );
+                        return Err(ConfigurationError::malformed_propose(msg));
+                    }
+                    set_default.validate(context)?;
+                }
+
+                ConfigChange::SetArtifactCapabilities(set_capabilities) => {
+                    if !artifacts_with_capabilities.insert(&set_capabilities.artifact) {
+                        let msg = format!(
+                            "Discarded multiple capability updates for artifact `{}`",
+                            set_capabilities.artifact
+                        );
+                        return Err(ConfigurationError::malformed_propose(msg));
+                    }
+                    set_capabilities.validate(context)?;
+                }
             }
         }
 
+        let mut intersection = new_service_names.intersection(&services_to_start);
+        if let Some(&name) = intersection.next() {
+            let msg = format!(
+                "Discarded proposal which both starts service `{}` and renames another \
+                 service to this name",
+                name
+            );
+            return Err(ConfigurationError::malformed_propose(msg));
+        }
+
         let mut intersection = unloaded_artifacts.intersection(&artifacts_for_started_services);
         if let Some(&artifact) = intersection.next() {
             let msg = format!(
@@ -777,6 +1598,51 @@ impl Supervisor {
             return Err(ConfigurationError::malformed_propose(msg));
         }
 
+        let mut intersection = deprecated_artifacts.intersection(&artifacts_for_started_services);
+        if let Some(&artifact) = intersection.next() {
+            let msg = format!(
+                "Discarded proposal which both starts a service from artifact `{}` \
+                 and deprecates it",
+                artifact
+            );
+            return Err(ConfigurationError::malformed_propose(msg));
+        }
+
+        let mut intersection = deprecated_artifacts.intersection(&unloaded_artifacts);
+        if let Some(&artifact) = intersection.next() {
+            let msg = format!(
+                "Discarded proposal which both deprecates and unloads artifact `{}`",
+                artifact
+            );
+            return Err(ConfigurationError::malformed_propose(msg));
+        }
+
+        Ok(())
+    }
+
+    /// Verifies that the stages of a multi-stage proposal are applied in the order
+    /// of increasing heights after the activation height of the proposal. Changes within
+    /// the stages are verified when the corresponding stage is applied.
+    fn verify_config_stages(propose: &ConfigPropose) -> Result<(), ExecutionError> {
+        let mut previous_height = propose.actual_from;
+        for stage in &propose.stages {
+            if stage.actual_from <= previous_height {
+                let msg = format!(
+                    "Discarded proposal stage with activation height ({}) not exceeding \
+                     the activation height of the previous stage ({})",
+                    stage.actual_from, previous_height
+                );
+                return Err(ConfigurationError::malformed_propose(msg));
+            }
+            if stage.changes.is_empty() {
+                let msg = format!(
+                    "Discarded empty proposal stage with activation height {}",
+                    stage.actual_from
+                );
+                return Err(ConfigurationError::malformed_propose(msg));
+            }
+            previous_height = stage.actual_from;
+        }
         Ok(())
     }
 
@@ -787,9 +1653,28 @@ impl Supervisor {
         mut context: ExecutionContext<'_>,
         deploy_request: DeployRequest,
         author: PublicKey,
+        checksum: Option<Hash>,
     ) -> Result<(), ExecutionError> {
         let core_schema = context.data().for_core();
         let mut schema = SchemaImpl::new(context.service_data());
+
+        // Check that the artifact checksum matches the one reported by other validators.
+        if schema.deploy_confirmations.confirmations(&deploy_request) == 0 {
+            if let Some(checksum) = checksum {
+                schema.deploy_checksums.put(&deploy_request, checksum);
+            }
+        } else if schema.deploy_checksums.get(&deploy_request) != checksum {
+            drop(schema);
+            let msg = format!(
+                "Validator with key {} reported a checksum of artifact `{}` different \
+                 from the checksum reported by other validators",
+                author, deploy_request.artifact
+            );
+            let error = ArtifactError::ArtifactChecksumMismatch.with_description(msg);
+            Self::fail_deploy(&context, &deploy_request, error);
+            return Ok(());
+        }
+
         schema.deploy_confirmations.confirm(&deploy_request, author);
 
         // Check if we have enough confirmations for the deployment.
@@ -806,9 +1691,7 @@ impl Supervisor {
             );
 
             // Remove artifact from pending deployments.
-            schema
-                .deploy_states
-                .put(&deploy_request, AsyncEventState::Succeed);
+            schema.set_deploy_state(&deploy_request, AsyncEventState::Succeed);
             drop(schema);
             // We have enough confirmations to register the deployed artifact in the dispatcher;
             // if this action fails, this transaction will be canceled.
@@ -835,9 +1718,7 @@ impl Supervisor {
         let mut schema = SchemaImpl::new(context.service_data());
 
         // Mark deploy as failed.
-        schema
-            .deploy_states
-            .put(deploy_request, AsyncEventState::Failed { height, error });
+        schema.set_deploy_state(deploy_request, AsyncEventState::Failed { height, error });
 
         // Remove artifact from pending deployments: since we require
         // a confirmation from every node, failure for one node means failure
@@ -938,7 +1819,7 @@ impl Supervisor {
         let mut state = schema.migration_state_unchecked(request);
 
         state.fail(AsyncEventState::Failed { height, error });
-        schema.migration_states.put(request, state);
+        schema.set_migration_state(request, state);
 
         // Migration is not pending anymore, remove it.
         schema.pending_migrations.remove(request);
@@ -0,0 +1,189 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Policies restricting migrations of services to new artifact versions.
+
+use anyhow::ensure;
+use exonum::runtime::{versioning::Version, ArtifactId};
+use exonum_proto::ProtobufConvert;
+use serde::{Deserialize, Serialize};
+
+use super::proto;
+
+/// Kind of the artifact upgrade policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum UpgradePolicyKind {
+    /// Only migrations to semver-compatible artifact versions are allowed. For example,
+    /// `1.2.3 -> 1.4.0` and `0.2.1 -> 0.2.5` migrations are allowed, while `1.2.3 -> 2.0.0`
+    /// and `0.2.1 -> 0.3.0` ones are not.
+    SemverCompatible,
+    /// Besides semver-compatible migrations, migrations to explicitly approved
+    /// major versions of the artifact are allowed.
+    ApprovedMajor,
+}
+
+impl ProtobufConvert for UpgradePolicyKind {
+    type ProtoStruct = proto::UpgradePolicyKind;
+
+    fn to_pb(&self) -> Self::ProtoStruct {
+        match self {
+            Self::SemverCompatible => proto::UpgradePolicyKind::SEMVER_COMPATIBLE,
+            Self::ApprovedMajor => proto::UpgradePolicyKind::APPROVED_MAJOR,
+        }
+    }
+
+    fn from_pb(pb: Self::ProtoStruct) -> anyhow::Result<Self> {
+        let result = match pb {
+            proto::UpgradePolicyKind::SEMVER_COMPATIBLE => Self::SemverCompatible,
+            proto::UpgradePolicyKind::APPROVED_MAJOR => Self::ApprovedMajor,
+        };
+        Ok(result)
+    }
+}
+
+/// Policy restricting migrations of services to new versions of an artifact.
+///
+/// The policy applies to all artifacts with the specified runtime ID and name. Migrations
+/// of services associated with artifacts without a policy are not restricted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ProtobufConvert)]
+#[protobuf_convert(source = "proto::ArtifactUpgradePolicy")]
+#[non_exhaustive]
+pub struct ArtifactUpgradePolicy {
+    /// Runtime identifier of the artifact.
+    pub runtime_id: u32,
+    /// Name of the artifact.
+    pub artifact_name: String,
+    /// Kind of the policy.
+    pub kind: UpgradePolicyKind,
+    /// Major versions of the artifact approved as migration targets. Only used
+    /// with the `ApprovedMajor` policy kind.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approved_major_versions: Vec<u64>,
+}
+
+impl ArtifactUpgradePolicy {
+    /// Creates a policy allowing only semver-compatible migrations.
+    pub fn semver_compatible(runtime_id: u32, artifact_name: impl Into<String>) -> Self {
+        Self {
+            runtime_id,
+            artifact_name: artifact_name.into(),
+            kind: UpgradePolicyKind::SemverCompatible,
+            approved_major_versions: vec![],
+        }
+    }
+
+    /// Creates a policy allowing semver-compatible migrations and migrations
+    /// to the specified major versions of the artifact.
+    pub fn approved_major(
+        runtime_id: u32,
+        artifact_name: impl Into<String>,
+        approved_major_versions: impl IntoIterator<Item = u64>,
+    ) -> Self {
+        Self {
+            runtime_id,
+            artifact_name: artifact_name.into(),
+            kind: UpgradePolicyKind::ApprovedMajor,
+            approved_major_versions: approved_major_versions.into_iter().collect(),
+        }
+    }
+
+    /// Checks whether this policy applies to the specified artifact.
+    pub fn applies_to(&self, artifact: &ArtifactId) -> bool {
+        self.runtime_id == artifact.runtime_id && self.artifact_name == artifact.name
+    }
+
+    /// Checks whether the migration of service data from the `data_version`
+    /// to the `new_version` conforms to this policy.
+    pub fn check_upgrade(
+        &self,
+        data_version: &Version,
+        new_version: &Version,
+    ) -> anyhow::Result<()> {
+        if is_semver_compatible(data_version, new_version) {
+            return Ok(());
+        }
+
+        let is_approved = self.kind == UpgradePolicyKind::ApprovedMajor
+            && self.approved_major_versions.contains(&new_version.major);
+        ensure!(
+            is_approved,
+            "Migration of `{}` data from version {} to {} is not semver-compatible \
+             and is not approved by the upgrade policy",
+            self.artifact_name,
+            data_version,
+            new_version
+        );
+        Ok(())
+    }
+}
+
+/// Checks whether `new_version` is semver-compatible with `old_version`, i.e.,
+/// satisfies the `^old_version` requirement.
+fn is_semver_compatible(old_version: &Version, new_version: &Version) -> bool {
+    if old_version.major != new_version.major {
+        false
+    } else if old_version.major != 0 {
+        true
+    } else if old_version.minor != new_version.minor {
+        false
+    } else {
+        old_version.minor != 0 || old_version.patch == new_version.patch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(s: &str) -> Version {
+        Version::parse(s).unwrap()
+    }
+
+    #[test]
+    fn semver_compatibility() {
+        assert!(is_semver_compatible(&version("1.2.3"), &version("1.4.0")));
+        assert!(is_semver_compatible(&version("0.2.1"), &version("0.2.5")));
+        assert!(is_semver_compatible(
+            &version("1.0.0-beta.1"),
+            &version("1.0.0")
+        ));
+        assert!(!is_semver_compatible(&version("1.2.3"), &version("2.0.0")));
+        assert!(!is_semver_compatible(&version("0.2.1"), &version("0.3.0")));
+        assert!(!is_semver_compatible(&version("0.0.1"), &version("0.0.2")));
+    }
+
+    #[test]
+    fn checking_upgrades() {
+        let policy = ArtifactUpgradePolicy::semver_compatible(0, "token");
+        policy
+            .check_upgrade(&version("1.0.0"), &version("1.1.0"))
+            .unwrap();
+        let err = policy
+            .check_upgrade(&version("1.0.0"), &version("2.0.0"))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("not approved by the upgrade policy"));
+
+        let policy = ArtifactUpgradePolicy::approved_major(0, "token", vec![2]);
+        policy
+            .check_upgrade(&version("1.0.0"), &version("2.0.1"))
+            .unwrap();
+        policy
+            .check_upgrade(&version("1.0.0"), &version("3.0.0"))
+            .unwrap_err();
+    }
+}
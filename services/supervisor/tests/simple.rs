@@ -36,6 +36,7 @@ use exonum_testkit::{ApiKind, TestKit, TestKitBuilder};
 use exonum_supervisor::{
     ConfigPropose, Configure, DeployRequest, Schema, Supervisor, SupervisorInterface,
 };
+use serde_derive::{Deserialize, Serialize};
 
 pub fn sign_config_propose_transaction(
     testkit: &TestKit,
@@ -115,6 +116,65 @@ impl Configure for ConfigChangeService {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypedConfig)]
+#[typed_config(validate = "TypedParams::check")]
+pub struct TypedParams {
+    #[serde(default = "TypedParams::default_threshold")]
+    threshold: u32,
+    name: String,
+}
+
+impl TypedParams {
+    fn default_threshold() -> u32 {
+        5
+    }
+
+    fn check(&self) -> Result<(), &'static str> {
+        if self.threshold == 0 {
+            Err("Threshold must be positive")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, ServiceDispatcher, ServiceFactory)]
+#[service_dispatcher(implements(raw = "Configure<Params = TypedParams>"))]
+#[service_factory(artifact_name = "typed-config-test-service")]
+pub struct TypedConfigService;
+
+impl DefaultInstance for TypedConfigService {
+    const INSTANCE_ID: InstanceId = 120;
+    const INSTANCE_NAME: &'static str = "typed-config";
+}
+
+impl Service for TypedConfigService {}
+
+impl Configure for TypedConfigService {
+    type Params = TypedParams;
+
+    fn verify_config(
+        &self,
+        _context: ExecutionContext<'_>,
+        _params: Self::Params,
+    ) -> Result<(), ExecutionError> {
+        Ok(())
+    }
+
+    fn apply_config(
+        &self,
+        context: ExecutionContext<'_>,
+        params: Self::Params,
+    ) -> Result<(), ExecutionError> {
+        context.service_data().get_entry("params").set(params.name);
+        context
+            .service_data()
+            .get_entry("threshold")
+            .set(params.threshold);
+        Ok(())
+    }
+}
+
 fn assert_config_change_is_applied(testkit: &TestKit) {
     let snapshot = testkit.snapshot();
     let schema: Schema<_> = snapshot.service_schema(Supervisor::NAME).unwrap();
@@ -203,6 +263,65 @@ fn service_config_change() {
     assert_eq!(actual_params, params);
 }
 
+/// Checks that typed service configuration is decoded and validated by the supervisor.
+#[test]
+fn typed_service_config_change() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(2)
+        .with(Supervisor::simple())
+        .with(Spec::new(TypedConfigService).with_default_instance())
+        .build();
+
+    // Parameters failing validation.
+    let config_propose = ConfigPropose::new(0, Height(5)).service_config(
+        TypedConfigService::INSTANCE_ID,
+        br#"{ "name": "test", "threshold": 0 }"#.to_vec(),
+    );
+    let block = testkit.create_block_with_transaction(sign_config_propose_transaction_by_us(
+        &testkit,
+        config_propose,
+    ));
+    let err = block.transactions[0].status().unwrap_err();
+    let expected_err = ErrorMatch::from_fail(&CommonError::MalformedArguments)
+        .with_description_containing("Threshold must be positive");
+    assert_eq!(*err, expected_err);
+
+    // Parameters which cannot be decoded.
+    let config_propose = ConfigPropose::new(0, Height(5))
+        .service_config(TypedConfigService::INSTANCE_ID, b"garbage".to_vec());
+    let block = testkit.create_block_with_transaction(sign_config_propose_transaction_by_us(
+        &testkit,
+        config_propose,
+    ));
+    let err = block.transactions[0].status().unwrap_err();
+    let expected_err =
+        ErrorMatch::from_fail(&CommonError::MalformedArguments).with_any_description();
+    assert_eq!(*err, expected_err);
+
+    // Valid parameters with the omitted `threshold` field.
+    let cfg_change_height = Height(5);
+    let config_propose = ConfigPropose::new(0, cfg_change_height).service_config(
+        TypedConfigService::INSTANCE_ID,
+        br#"{ "name": "test" }"#.to_vec(),
+    );
+    testkit.create_block_with_transaction(sign_config_propose_transaction_by_us(
+        &testkit,
+        config_propose,
+    ))[0]
+        .status()
+        .unwrap();
+    testkit.create_blocks_until(cfg_change_height);
+
+    let snapshot = testkit.snapshot();
+    let service_data = snapshot
+        .for_service(TypedConfigService::INSTANCE_NAME)
+        .unwrap();
+    let name: String = service_data.get_entry("params").get().unwrap();
+    let threshold: u32 = service_data.get_entry("threshold").get().unwrap();
+    assert_eq!(name, "test");
+    assert_eq!(threshold, TypedParams::default_threshold());
+}
+
 #[test]
 fn incorrect_actual_from_field() {
     let mut testkit = TestKitBuilder::validator()
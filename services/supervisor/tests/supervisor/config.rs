@@ -16,15 +16,17 @@ use exonum::{
     blockchain::CallInBlock,
     crypto::{self, KeyPair},
     helpers::{Height, ValidatorId},
-    merkledb::ObjectHash,
-    runtime::{CommonError, ErrorMatch, InstanceId, SnapshotExt, SUPERVISOR_INSTANCE_ID},
+    merkledb::{BinaryValue, ObjectHash},
+    runtime::{
+        AnyTx, CallInfo, CommonError, ErrorMatch, InstanceId, SnapshotExt, SUPERVISOR_INSTANCE_ID,
+    },
 };
 use exonum_testkit::{Spec, TestKitBuilder};
 
 use crate::{utils::*, IncService as ConfigChangeService};
 use exonum_supervisor::{
-    CommonError as SupervisorCommonError, ConfigVote, ConfigurationError, Supervisor,
-    SupervisorInterface,
+    CommonError as SupervisorCommonError, ConfigVote, ConfigurationError, DelegateVotes,
+    DeployRequest, DeployResult, SchemaImpl, Supervisor, SupervisorInterface,
 };
 
 #[test]
@@ -74,6 +76,105 @@ fn test_deadline_config_exceeded() {
     assert_eq!(testkit.consensus_config(), consensus_config);
 }
 
+#[test]
+fn test_config_proposal_expires_before_actual_from() {
+    let mut testkit = testkit_with_supervisor(4);
+    let initiator_id = testkit.network().us().validator_id().unwrap();
+    let consensus_config = testkit.consensus_config();
+    let new_consensus_config = consensus_config_propose_first_variant(&testkit);
+
+    let expires_at = Height(2);
+    let config_proposal = ConfigProposeBuilder::new(Height(5))
+        .extend_consensus_config_propose(new_consensus_config)
+        .build()
+        .with_expiration(expires_at);
+    let proposal_hash = config_proposal.object_hash();
+    testkit
+        .create_block_with_transaction(sign_config_propose_transaction(
+            &testkit,
+            config_proposal,
+            initiator_id,
+        ))
+        .transactions[0]
+        .status()
+        .expect("Transaction with change propose discarded.");
+    testkit.create_blocks_until(expires_at);
+
+    // Votes after the expiration height should be rejected.
+    let signed_txs = build_confirmation_transactions(&testkit, proposal_hash, initiator_id);
+    let block = testkit.create_block_with_transactions(signed_txs);
+    let err = block.transactions[0].status().unwrap_err();
+    assert_eq!(
+        *err,
+        ErrorMatch::from_fail(&ConfigurationError::ConfigProposeNotRegistered)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+    );
+    assert_eq!(config_propose_entry(&testkit), None);
+
+    // The pending proposal slot should be free for a new proposal.
+    let config_proposal = ConfigProposeBuilder::new(Height(5))
+        .configuration_number(1)
+        .extend_consensus_config_propose(consensus_config_propose_second_variant(&testkit))
+        .build();
+    testkit
+        .create_block_with_transaction(sign_config_propose_transaction(
+            &testkit,
+            config_proposal,
+            initiator_id,
+        ))
+        .transactions[0]
+        .status()
+        .expect("Transaction with change propose discarded.");
+    assert!(config_propose_entry(&testkit).is_some());
+    assert_eq!(testkit.consensus_config(), consensus_config);
+}
+
+#[test]
+fn test_config_proposal_with_expiration_in_past() {
+    let mut testkit = testkit_with_supervisor(1);
+    testkit.create_blocks_until(Height(2));
+
+    let config_proposal = ConfigProposeBuilder::new(Height(5))
+        .build()
+        .with_expiration(Height(1));
+    let signed_proposal =
+        sign_config_propose_transaction(&testkit, config_proposal, ValidatorId(0));
+    let block = testkit.create_block_with_transaction(signed_proposal);
+    let err = block.transactions[0].status().unwrap_err();
+    assert_eq!(
+        *err,
+        ErrorMatch::from_fail(&SupervisorCommonError::ProposalExpired)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+            .with_description_containing("Expiration height")
+    );
+}
+
+#[test]
+fn test_non_canonical_config_proposal() {
+    let mut testkit = testkit_with_supervisor(1);
+
+    let config_proposal = ConfigProposeBuilder::new(CFG_CHANGE_HEIGHT)
+        .extend_consensus_config_propose(consensus_config_propose_first_variant(&testkit))
+        .build();
+    let mut payload = config_proposal.to_bytes();
+    // Append an unknown varint field. The payload is still a valid Protobuf message
+    // decoded to the same proposal, but its encoding is not canonical.
+    payload.extend_from_slice(&[0x78, 0x01]);
+
+    // `propose_config_change` has method ID 2.
+    let keys = testkit.us().service_keypair();
+    let tx = AnyTx::new(CallInfo::new(SUPERVISOR_INSTANCE_ID, 2), payload).sign_with_keypair(&keys);
+    let block = testkit.create_block_with_transaction(tx);
+    let err = block.transactions[0].status().unwrap_err();
+    assert_eq!(
+        *err,
+        ErrorMatch::from_fail(&CommonError::MalformedArguments)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+            .with_description_containing("Non-canonical binary encoding of ConfigPropose")
+    );
+    assert_eq!(config_propose_entry(&testkit), None);
+}
+
 #[test]
 fn test_sent_new_config_after_expired_one() {
     let mut testkit = testkit_with_supervisor(4);
@@ -297,6 +398,168 @@ fn test_confirm_config_by_incorrect_validator() {
     );
 }
 
+#[test]
+fn test_config_change_by_governance_keys() {
+    let governance_keys = [KeyPair::random(), KeyPair::random()];
+    let supervisor_config = governance_keys
+        .iter()
+        .fold(Supervisor::decentralized_config(), |config, keys| {
+            config.with_governance_key(keys.public_key())
+        });
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(1)
+        .with(Supervisor::builtin_instance(supervisor_config))
+        .build();
+
+    let new_consensus_config = consensus_config_propose_first_variant(&testkit);
+    let config_proposal = ConfigProposeBuilder::new(CFG_CHANGE_HEIGHT)
+        .extend_consensus_config_propose(new_consensus_config.clone())
+        .build();
+    let proposal_hash = config_proposal.object_hash();
+
+    // A governance key may propose a config change.
+    let tx = governance_keys[0].propose_config_change(SUPERVISOR_INSTANCE_ID, config_proposal);
+    let block = testkit.create_block_with_transaction(tx);
+    block[0]
+        .status()
+        .expect("Proposal by governance key discarded");
+
+    // Governance keys are counted together with the validator, so all 3 voters
+    // need to approve the proposal.
+    let vote = ConfigVote::new(proposal_hash);
+    let validator_keys = testkit.us().service_keypair();
+    let block = testkit.create_block_with_transactions(vec![
+        validator_keys.confirm_config_change(SUPERVISOR_INSTANCE_ID, vote.clone()),
+        governance_keys[1].confirm_config_change(SUPERVISOR_INSTANCE_ID, vote),
+    ]);
+    block[0].status().expect("Vote by validator discarded");
+    block[1].status().expect("Vote by governance key discarded");
+
+    testkit.create_blocks_until(CFG_CHANGE_HEIGHT);
+    assert_eq!(config_propose_entry(&testkit), None);
+    assert_eq!(testkit.consensus_config(), new_consensus_config);
+}
+
+#[test]
+fn test_governance_keys_alone_cannot_change_consensus() {
+    let governance_keys = [KeyPair::random(), KeyPair::random(), KeyPair::random()];
+    let supervisor_config = governance_keys
+        .iter()
+        .fold(Supervisor::decentralized_config(), |config, keys| {
+            config.with_governance_key(keys.public_key())
+        });
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(1)
+        .with(Supervisor::builtin_instance(supervisor_config))
+        .build();
+    let initial_consensus_config = testkit.consensus_config();
+
+    let new_consensus_config = consensus_config_propose_first_variant(&testkit);
+    let config_proposal = ConfigProposeBuilder::new(CFG_CHANGE_HEIGHT)
+        .extend_consensus_config_propose(new_consensus_config)
+        .build();
+    let proposal_hash = config_proposal.object_hash();
+    let tx = governance_keys[0].propose_config_change(SUPERVISOR_INSTANCE_ID, config_proposal);
+    let block = testkit.create_block_with_transaction(tx);
+    block[0]
+        .status()
+        .expect("Proposal by governance key discarded");
+
+    // Governance keys make up 3 of 4 voters, which is enough for the overall quorum,
+    // but the consensus configuration requires a quorum of validators as well.
+    let vote = ConfigVote::new(proposal_hash);
+    let block = testkit.create_block_with_transactions(vec![
+        governance_keys[1].confirm_config_change(SUPERVISOR_INSTANCE_ID, vote.clone()),
+        governance_keys[2].confirm_config_change(SUPERVISOR_INSTANCE_ID, vote),
+    ]);
+    block[0].status().expect("Vote by governance key discarded");
+    block[1].status().expect("Vote by governance key discarded");
+
+    testkit.create_blocks_until(CFG_CHANGE_HEIGHT);
+    assert_eq!(config_propose_entry(&testkit), None);
+    assert_eq!(testkit.consensus_config(), initial_consensus_config);
+}
+
+#[test]
+fn test_governance_keys_cannot_request_deploy() {
+    let governance_keys = KeyPair::random();
+    let supervisor_config =
+        Supervisor::decentralized_config().with_governance_key(governance_keys.public_key());
+    let mut testkit = TestKitBuilder::validator()
+        .with(Supervisor::builtin_instance(supervisor_config))
+        .build();
+
+    let artifact = "0:some-service:1.0.0".parse().unwrap();
+    let request = DeployRequest::new(artifact, Height(5));
+    let tx = governance_keys.request_artifact_deploy(SUPERVISOR_INSTANCE_ID, request);
+    let block = testkit.create_block_with_transaction(tx);
+    let err = block[0].status().unwrap_err();
+    assert_eq!(
+        *err,
+        ErrorMatch::from_fail(&CommonError::UnauthorizedCaller).for_service(SUPERVISOR_INSTANCE_ID)
+    );
+}
+
+#[test]
+fn test_config_vote_by_delegate() {
+    let supervisor_config = Supervisor::decentralized_config().with_max_delegation_period(10);
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(2)
+        .with(Supervisor::builtin_instance(supervisor_config))
+        .build();
+    let validator_keys = testkit.network().validators()[1].service_keypair();
+    let delegate_keys = KeyPair::random();
+
+    // The delegation period is bounded by the supervisor configuration.
+    let too_long = DelegateVotes::new(delegate_keys.public_key(), Height(20));
+    let delegation = DelegateVotes::new(delegate_keys.public_key(), Height(10));
+    let block = testkit.create_block_with_transactions(vec![
+        validator_keys.delegate_votes(SUPERVISOR_INSTANCE_ID, too_long),
+        validator_keys.delegate_votes(SUPERVISOR_INSTANCE_ID, delegation),
+    ]);
+    let err = block[0].status().unwrap_err();
+    assert_eq!(
+        *err,
+        ErrorMatch::from_fail(&SupervisorCommonError::InvalidDelegation)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+            .with_any_description()
+    );
+    block[1].status().expect("Delegation discarded");
+
+    // The delegate votes on behalf of the validator.
+    let new_consensus_config = consensus_config_propose_first_variant(&testkit);
+    let config_proposal = ConfigProposeBuilder::new(CFG_CHANGE_HEIGHT)
+        .extend_consensus_config_propose(new_consensus_config.clone())
+        .build();
+    let vote = ConfigVote::new(config_proposal.object_hash());
+    let block = testkit.create_block_with_transactions(vec![
+        sign_config_propose_transaction(&testkit, config_proposal, ValidatorId(0)),
+        delegate_keys.confirm_config_change(SUPERVISOR_INSTANCE_ID, vote),
+    ]);
+    block[1].status().expect("Vote by delegate discarded");
+    testkit.create_blocks_until(CFG_CHANGE_HEIGHT);
+    assert_eq!(testkit.consensus_config(), new_consensus_config);
+
+    // The delegate cannot report deployment results on behalf of the validator.
+    let artifact = "0:some-service:1.0.0".parse().unwrap();
+    let request = DeployRequest::new(artifact, Height(5));
+    let tx = delegate_keys.report_deploy_result(SUPERVISOR_INSTANCE_ID, DeployResult::ok(request));
+    let block = testkit.create_block_with_transaction(tx);
+    let err = block[0].status().unwrap_err();
+    assert_eq!(
+        *err,
+        ErrorMatch::from_fail(&CommonError::UnauthorizedCaller).for_service(SUPERVISOR_INSTANCE_ID)
+    );
+
+    // The delegation is removed once expired.
+    testkit.create_blocks_until(Height(10));
+    let snapshot = testkit.snapshot();
+    let schema = SchemaImpl::new(snapshot.for_service(Supervisor::NAME).unwrap());
+    assert!(!schema
+        .vote_delegations
+        .contains(&delegate_keys.public_key()));
+}
+
 #[test]
 fn test_try_confirm_non_existent_proposal() {
     let mut testkit = testkit_with_supervisor(4);
@@ -892,3 +1155,38 @@ fn test_all_changes_are_discarded_on_panic() {
         assert_eq!(testkit.consensus_config(), old_consensus_config);
     }
 }
+
+#[test]
+fn test_votes_are_removed_after_failed_config_apply() {
+    let mut testkit = testkit_with_supervisor_and_service(4);
+    let initiator_id = testkit.network().us().validator_id().unwrap();
+
+    let propose = ConfigProposeBuilder::new(CFG_CHANGE_HEIGHT)
+        .extend_service_config_propose("apply_panic".to_owned())
+        .build();
+    let proposal_hash = propose.object_hash();
+    testkit
+        .create_block_with_transaction(sign_config_propose_transaction(
+            &testkit,
+            propose,
+            initiator_id,
+        ))
+        .transactions[0]
+        .status()
+        .expect("Transaction with change propose discarded.");
+
+    let signed_txs = build_confirmation_transactions(&testkit, proposal_hash, initiator_id);
+    testkit.create_block_with_transactions(signed_txs)[0]
+        .status()
+        .unwrap();
+    // The panic during config application rolls back the removal of the proposal
+    // and its votes, so they should be cleaned up once the proposal expires.
+    testkit.create_blocks_until(CFG_CHANGE_HEIGHT);
+    testkit.create_block();
+    assert_eq!(config_propose_entry(&testkit), None);
+    let snapshot = testkit.snapshot();
+    let schema = SchemaImpl::new(snapshot.for_service(Supervisor::NAME).unwrap());
+    assert_eq!(schema.config_confirms.confirmations(&proposal_hash), 0);
+    // Governance state of the new blockchain does not need to be repaired.
+    assert_eq!(schema.governance_repaired_at.get(), Some(Height(0)));
+}
@@ -20,7 +20,10 @@ use exonum_merkledb::ObjectHash;
 use exonum_testkit::{ApiKind, TestKit, TestKitApi};
 
 use crate::utils::*;
-use exonum_supervisor::{ConfigProposalWithHash, ConfigPropose, ConfigVote, SupervisorInterface};
+use exonum_supervisor::{
+    api::ConsensusConfigWithProof, ConfigProposalWithHash, ConfigPropose, ConfigVote,
+    SupervisorInterface,
+};
 
 async fn actual_consensus_config(api: &TestKitApi) -> ConsensusConfig {
     api.public(ApiKind::Service("supervisor"))
@@ -70,6 +73,35 @@ async fn test_consensus_config_api() {
     assert_eq!(testkit.consensus_config(), consensus_config);
 }
 
+#[tokio::test]
+async fn test_consensus_config_proof_api() {
+    let mut testkit = testkit_with_supervisor(4);
+    testkit.create_block();
+    let validator_keys: Vec<_> = testkit
+        .consensus_config()
+        .validator_keys
+        .iter()
+        .map(|keys| keys.consensus_key)
+        .collect();
+
+    let response: ConsensusConfigWithProof = testkit
+        .api()
+        .public(ApiKind::Service("supervisor"))
+        .get("consensus-config-proof")
+        .await
+        .unwrap();
+    let consensus_config = response.verify(&validator_keys).unwrap();
+    assert_eq!(*consensus_config, testkit.consensus_config());
+
+    // The proof cannot be verified with other validator keys.
+    let other_keys: Vec<_> = validator_keys.iter().rev().copied().collect();
+    response.verify(&other_keys).unwrap_err();
+    // A tampered configuration is not covered by the proof.
+    let mut tampered = response.clone();
+    tampered.consensus_config.first_round_timeout += 1;
+    tampered.verify(&validator_keys).unwrap_err();
+}
+
 #[tokio::test]
 async fn test_config_proposal_api() {
     let mut testkit = testkit_with_supervisor(1);
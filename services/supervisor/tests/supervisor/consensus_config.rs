@@ -12,11 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use exonum::{helpers::ValidatorId, merkledb::ObjectHash};
+use exonum::{
+    blockchain::ValidatorKeys,
+    crypto::KeyPair,
+    helpers::{Height, ValidatorId},
+    merkledb::ObjectHash,
+    runtime::{CommonError, ErrorMatch, SUPERVISOR_INSTANCE_ID},
+};
 use exonum_testkit::TestKitBuilder;
 
 use crate::utils::*;
-use exonum_supervisor::Supervisor;
+use exonum_supervisor::{
+    ChangeValidators, ConfigPropose, ConfigurationError, ConsensusConfigPatch, Supervisor,
+    SupervisorInterface,
+};
 
 #[test]
 fn test_add_nodes_to_validators() {
@@ -152,3 +161,213 @@ fn test_change_our_validator_id() {
     assert_eq!(&testkit.network().validators()[1], testkit.network().us());
     assert_eq!(testkit.consensus_config(), new_consensus_config);
 }
+
+#[test]
+fn test_consensus_config_patch() {
+    let mut testkit = testkit_with_supervisor(1);
+    let old_config = testkit.consensus_config();
+
+    let patch = ConsensusConfigPatch::new().min_propose_timeout(old_config.min_propose_timeout + 5);
+    let config_proposal = ConfigPropose::new(0, CFG_CHANGE_HEIGHT).consensus_patch(patch);
+    let signed_proposal =
+        sign_config_propose_transaction(&testkit, config_proposal, ValidatorId(0));
+    testkit.create_block_with_transaction(signed_proposal);
+    testkit.create_blocks_until(CFG_CHANGE_HEIGHT);
+
+    let mut expected_config = old_config.clone();
+    expected_config.min_propose_timeout = old_config.min_propose_timeout + 5;
+    assert_eq!(config_propose_entry(&testkit), None);
+    assert_eq!(testkit.consensus_config(), expected_config);
+}
+
+#[test]
+fn test_consensus_config_patch_preserves_validator_changes() {
+    let mut testkit = testkit_with_supervisor(4);
+    let initiator_id = testkit.network().us().validator_id().unwrap();
+
+    let new_consensus_config = {
+        let mut cfg = testkit.consensus_config();
+        cfg.validator_keys.remove(3);
+        cfg
+    };
+    // The patch is applied after the validator set is changed; it should not revert the change.
+    let patch_height = Height(CFG_CHANGE_HEIGHT.0 + 2);
+    let config_proposal = ConfigPropose::new(0, CFG_CHANGE_HEIGHT)
+        .consensus_config(new_consensus_config.clone())
+        .stage(patch_height)
+        .consensus_patch(ConsensusConfigPatch::new().txs_block_limit(100));
+    let proposal_hash = config_proposal.object_hash();
+    testkit.create_block_with_transaction(sign_config_propose_transaction(
+        &testkit,
+        config_proposal,
+        initiator_id,
+    ));
+    let signed_txs = build_confirmation_transactions(&testkit, proposal_hash, initiator_id);
+    testkit.create_block_with_transactions(signed_txs);
+
+    testkit.create_blocks_until(CFG_CHANGE_HEIGHT);
+    assert_eq!(testkit.consensus_config(), new_consensus_config);
+
+    testkit.create_blocks_until(patch_height);
+    let consensus_config = testkit.consensus_config();
+    assert_eq!(
+        consensus_config.validator_keys,
+        new_consensus_config.validator_keys
+    );
+    assert_eq!(consensus_config.txs_block_limit, 100);
+}
+
+#[test]
+fn test_invalid_consensus_config_patch() {
+    let mut testkit = testkit_with_supervisor(1);
+    let max_propose_timeout = testkit.consensus_config().max_propose_timeout;
+
+    let patches = vec![
+        ConsensusConfigPatch::new(),
+        ConsensusConfigPatch::new().min_propose_timeout(max_propose_timeout + 1),
+    ];
+    for patch in patches {
+        let config_proposal = ConfigPropose::new(0, CFG_CHANGE_HEIGHT).consensus_patch(patch);
+        let signed_proposal =
+            sign_config_propose_transaction(&testkit, config_proposal, ValidatorId(0));
+        let block = testkit.create_block_with_transaction(signed_proposal);
+        let err = block.transactions[0].status().unwrap_err();
+        assert_eq!(
+            *err,
+            ErrorMatch::from_fail(&ConfigurationError::MalformedConfigPropose)
+                .for_service(SUPERVISOR_INSTANCE_ID)
+                .with_any_description()
+        );
+        assert_eq!(config_propose_entry(&testkit), None);
+    }
+}
+
+#[test]
+fn test_change_validators_with_key_handover() {
+    let mut testkit = testkit_with_supervisor(4);
+    let initiator_id = testkit.network().us().validator_id().unwrap();
+    let old_config = testkit.consensus_config();
+    let consensus_key = old_config.validator_keys[3].consensus_key;
+    let old_service_keys = testkit.validator(ValidatorId(3)).service_keypair();
+    let new_keys = ValidatorKeys::new(consensus_key, KeyPair::random().public_key());
+
+    let change = ChangeValidators::new()
+        .replace_validator(consensus_key, new_keys)
+        .grace_period(5);
+    let config_proposal = ConfigPropose::new(0, CFG_CHANGE_HEIGHT).change_validators(change);
+    let proposal_hash = config_proposal.object_hash();
+    testkit.create_block_with_transaction(sign_config_propose_transaction(
+        &testkit,
+        config_proposal,
+        initiator_id,
+    ));
+    let signed_txs = build_confirmation_transactions(&testkit, proposal_hash, initiator_id);
+    testkit.create_block_with_transactions(signed_txs);
+    testkit.create_blocks_until(CFG_CHANGE_HEIGHT);
+
+    let mut expected_config = old_config;
+    expected_config.validator_keys[3] = new_keys;
+    assert_eq!(testkit.consensus_config(), expected_config);
+
+    // During the grace period, the old service key acts on behalf of the validator.
+    let proposal = ConfigPropose::new(1, Height(100));
+    let tx = old_service_keys.propose_config_change(SUPERVISOR_INSTANCE_ID, proposal.clone());
+    let block = testkit.create_block_with_transaction(tx);
+    block.transactions[0].status().unwrap();
+    assert_eq!(config_propose_entry(&testkit), Some(proposal));
+
+    // After the grace period, the old key is no longer accepted.
+    testkit.create_blocks_until(Height(10));
+    let proposal = ConfigPropose::new(1, Height(200));
+    let tx = old_service_keys.propose_config_change(SUPERVISOR_INSTANCE_ID, proposal);
+    let block = testkit.create_block_with_transaction(tx);
+    let err = block.transactions[0].status().unwrap_err();
+    assert_eq!(
+        *err,
+        ErrorMatch::from_fail(&CommonError::UnauthorizedCaller).for_service(SUPERVISOR_INSTANCE_ID)
+    );
+}
+
+/// Checks that the old service key of a validator is not accepted after the validator
+/// is removed, even if the grace period of the key handover has not expired.
+#[test]
+fn test_key_handover_for_removed_validator() {
+    let mut testkit = testkit_with_supervisor(4);
+    let initiator_id = testkit.network().us().validator_id().unwrap();
+    let consensus_key = testkit.consensus_config().validator_keys[3].consensus_key;
+    let old_service_keys = testkit.validator(ValidatorId(3)).service_keypair();
+    let new_keys = ValidatorKeys::new(consensus_key, KeyPair::random().public_key());
+
+    let change = ChangeValidators::new()
+        .replace_validator(consensus_key, new_keys)
+        .grace_period(ChangeValidators::MAX_GRACE_PERIOD);
+    let config_proposal = ConfigPropose::new(0, CFG_CHANGE_HEIGHT).change_validators(change);
+    let proposal_hash = config_proposal.object_hash();
+    testkit.create_block_with_transaction(sign_config_propose_transaction(
+        &testkit,
+        config_proposal,
+        initiator_id,
+    ));
+    let signed_txs = build_confirmation_transactions(&testkit, proposal_hash, initiator_id);
+    testkit.create_block_with_transactions(signed_txs);
+    testkit.create_blocks_until(CFG_CHANGE_HEIGHT);
+
+    let removal_height = Height(CFG_CHANGE_HEIGHT.0 + 3);
+    let change = ChangeValidators::new().remove_validator(consensus_key);
+    let config_proposal = ConfigPropose::new(1, removal_height).change_validators(change);
+    let proposal_hash = config_proposal.object_hash();
+    testkit.create_block_with_transaction(sign_config_propose_transaction(
+        &testkit,
+        config_proposal,
+        initiator_id,
+    ));
+    let signed_txs = build_confirmation_transactions(&testkit, proposal_hash, initiator_id);
+    testkit.create_block_with_transactions(signed_txs);
+    testkit.create_blocks_until(removal_height);
+    assert_eq!(testkit.consensus_config().validator_keys.len(), 3);
+
+    let proposal = ConfigPropose::new(2, Height(100));
+    let tx = old_service_keys.propose_config_change(SUPERVISOR_INSTANCE_ID, proposal);
+    let block = testkit.create_block_with_transaction(tx);
+    let err = block.transactions[0].status().unwrap_err();
+    assert_eq!(
+        *err,
+        ErrorMatch::from_fail(&CommonError::UnauthorizedCaller).for_service(SUPERVISOR_INSTANCE_ID)
+    );
+}
+
+#[test]
+fn test_invalid_change_validators() {
+    let mut testkit = testkit_with_supervisor(1);
+    let validator_keys = testkit.consensus_config().validator_keys[0];
+    let unknown_key = KeyPair::random().public_key();
+
+    let changes = vec![
+        ChangeValidators::new(),
+        ChangeValidators::new().remove_validator(unknown_key),
+        // The validator set cannot be empty.
+        ChangeValidators::new().remove_validator(validator_keys.consensus_key),
+        // The same validator cannot be affected several times.
+        ChangeValidators::new()
+            .remove_validator(validator_keys.consensus_key)
+            .replace_validator(validator_keys.consensus_key, validator_keys),
+        // The grace period is bounded.
+        ChangeValidators::new()
+            .replace_validator(validator_keys.consensus_key, validator_keys)
+            .grace_period(ChangeValidators::MAX_GRACE_PERIOD + 1),
+    ];
+    for change in changes {
+        let config_proposal = ConfigPropose::new(0, CFG_CHANGE_HEIGHT).change_validators(change);
+        let signed_proposal =
+            sign_config_propose_transaction(&testkit, config_proposal, ValidatorId(0));
+        let block = testkit.create_block_with_transaction(signed_proposal);
+        let err = block.transactions[0].status().unwrap_err();
+        assert_eq!(
+            *err,
+            ErrorMatch::from_fail(&ConfigurationError::MalformedConfigPropose)
+                .for_service(SUPERVISOR_INSTANCE_ID)
+                .with_any_description()
+        );
+        assert_eq!(config_propose_entry(&testkit), None);
+    }
+}
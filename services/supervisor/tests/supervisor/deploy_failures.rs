@@ -24,16 +24,18 @@
 use exonum::{
     crypto::Hash,
     helpers::{Height, ValidatorId},
+    merkledb::BinaryValue,
     messages::{AnyTx, Verified},
-    runtime::{ExecutionError, SUPERVISOR_INSTANCE_ID},
+    runtime::{DeploySpec, ErrorMatch, ExecutionError, SnapshotExt, SUPERVISOR_INSTANCE_ID},
 };
 use exonum_testkit::{ApiKind, TestKit, TestKitApi, TestKitBuilder};
 
 use std::sync::atomic::Ordering;
 
 use exonum_supervisor::{
-    api::DeployInfoQuery, AsyncEventState, DeployRequest, DeployResult, Supervisor,
-    SupervisorInterface,
+    api::{DeployInfoQuery, PendingDeployment},
+    ArtifactError, AsyncEventState, DeployDeadlineExtension, DeployRequest, DeployResult,
+    Supervisor, SupervisorInterface,
 };
 
 use self::failing_runtime::{FailingRuntime, FailingRuntimeError};
@@ -48,13 +50,15 @@ mod failing_runtime {
         },
     };
 
-    use exonum::merkledb::Snapshot;
+    use exonum::crypto::{self, Hash};
+    use exonum::helpers::ValidateInput;
+    use exonum::merkledb::{BinaryValue, Snapshot};
     use exonum::runtime::{
         migrations::{InitMigrationError, MigrationScript},
         oneshot::Receiver,
         versioning::Version,
-        ArtifactId, ExecutionContext, ExecutionError, InstanceState, Mailbox, MethodId, Runtime,
-        WellKnownRuntime,
+        ArtifactId, DeploySpec, ExecutionContext, ExecutionError, ExecutionFail, InstanceState,
+        Mailbox, MethodId, Runtime, WellKnownRuntime,
     };
     use exonum_derive::ExecutionFail;
 
@@ -74,6 +78,8 @@ mod failing_runtime {
         pub const ARTIFACT_SHOULD_FAIL: &'static str = "fail";
         pub const CONTROLLED_ARTIFACT: &'static str = "controlled";
         pub const ARTIFACT_VERSION: &'static str = "0.1.0";
+        /// Section of the deploy spec with the expected artifact checksum.
+        pub const CHECKSUM_SECTION: &'static str = "failing.checksum";
 
         pub fn artifact_should_fail() -> ArtifactId {
             Self::artifact(Self::ARTIFACT_SHOULD_FAIL)
@@ -94,6 +100,11 @@ mod failing_runtime {
             ArtifactId::from_str(&artifact_id_str).unwrap()
         }
 
+        /// Returns the checksum reported by the runtime for a deployed artifact.
+        pub fn checksum(artifact: &ArtifactId) -> Hash {
+            crypto::hash(artifact.name.as_bytes())
+        }
+
         pub fn failure_switch(&self) -> Arc<AtomicBool> {
             Arc::clone(&self.failure_switch)
         }
@@ -107,6 +118,8 @@ mod failing_runtime {
         GenericError = 0,
         /// Deployment error upon a request.
         PlannedError = 1,
+        /// Invalid deploy specification.
+        InvalidSpec = 2,
     }
 
     impl Runtime for FailingRuntime {
@@ -134,10 +147,38 @@ mod failing_runtime {
             Receiver::with_result(result)
         }
 
+        /// Checks that the spec is either empty, or contains the expected artifact checksum.
+        fn check_deploy_spec(
+            &self,
+            artifact: &ArtifactId,
+            spec: &[u8],
+        ) -> Result<(), ExecutionError> {
+            if spec.is_empty() {
+                return Ok(());
+            }
+            let spec = DeploySpec::from_bytes(spec.into())
+                .and_then(|spec| spec.validate().map(|()| spec))
+                .map_err(|e| FailingRuntimeError::InvalidSpec.with_description(e))?;
+
+            match spec.section(Self::CHECKSUM_SECTION) {
+                Some(checksum) if checksum == Self::checksum(artifact).as_ref() => Ok(()),
+                _ => Err(FailingRuntimeError::InvalidSpec
+                    .with_description("Artifact checksum is missing or incorrect")),
+            }
+        }
+
         fn is_artifact_deployed(&self, id: &ArtifactId) -> bool {
             self.deployed_artifacts.contains(id)
         }
 
+        fn artifact_checksum(&self, id: &ArtifactId) -> Option<Hash> {
+            if self.deployed_artifacts.contains(id) {
+                Some(Self::checksum(id))
+            } else {
+                None
+            }
+        }
+
         /// Initiates adding a new service and sets the counter value for this.
         fn initiate_adding_service(
             &self,
@@ -226,10 +267,15 @@ fn build_result_transaction(
         .find(|validator| validator.validator_id() == Some(VALIDATOR_OTHER))
         .unwrap();
 
-    validator.service_keypair().report_deploy_result(
-        SUPERVISOR_INSTANCE_ID,
-        DeployResult::new(request.clone(), result),
-    )
+    let mut deploy_result = DeployResult::new(request.clone(), result.clone());
+    if result.is_ok() {
+        // Report the same checksum as our node.
+        let checksum = FailingRuntime::checksum(&request.artifact);
+        deploy_result = deploy_result.with_artifact_checksum(checksum);
+    }
+    validator
+        .service_keypair()
+        .report_deploy_result(SUPERVISOR_INSTANCE_ID, deploy_result)
 }
 
 /// Creates `AsyncEventState::Failed` for planned error of `FailingRuntime`.
@@ -608,3 +654,223 @@ async fn not_requested_deploy_status() {
 
     assert_eq!(u16::from(error.http_code), 404);
 }
+
+/// Checks that repeating a request for a pending deployment is a no-op.
+#[tokio::test]
+async fn repeated_deploy_request() {
+    const DEPLOY_HEIGHT: Height = Height(5);
+
+    let mut testkit = testkit_with_failing_runtime(VALIDATORS_AMOUNT);
+    let api = testkit.api();
+
+    let deploy_request =
+        DeployRequest::new(FailingRuntime::artifact_should_be_deployed(), DEPLOY_HEIGHT);
+    let tx_hash = send_deploy_request(&api, &deploy_request).await;
+    let block = testkit.create_block();
+    block[tx_hash].status().unwrap();
+
+    // Retry the request with another seed; it should not be rejected.
+    let mut repeated_request = deploy_request.clone();
+    repeated_request.seed += 1;
+    let tx_hash = send_deploy_request(&api, &repeated_request).await;
+    let block = testkit.create_block();
+    block[tx_hash].status().unwrap();
+
+    // The original request remains pending, and the repeated one is not registered.
+    let state = get_deploy_status(&api, &deploy_request).await;
+    assert_deploy_state(state, AsyncEventState::Pending);
+    let query = DeployInfoQuery::from(repeated_request);
+    let error = api
+        .private(ApiKind::Service("supervisor"))
+        .query(&query)
+        .get::<AsyncEventState>("deploy-status")
+        .await
+        .expect_err("Repeated deploy request should not be registered");
+    assert_eq!(u16::from(error.http_code), 404);
+
+    // Confirm deploy.
+    let deploy_confirmation = build_result_transaction(&testkit, &deploy_request, Ok(()));
+    testkit.create_block_with_transaction(deploy_confirmation);
+    testkit.create_blocks_until(DEPLOY_HEIGHT.next());
+
+    let state = get_deploy_status(&api, &deploy_request).await;
+    assert_deploy_state(state, AsyncEventState::Succeed);
+}
+
+/// Checks that deployment fails if validators report different checksums of the artifact.
+#[tokio::test]
+async fn deploy_failure_because_checksum_mismatch() {
+    let mut testkit = testkit_with_failing_runtime(VALIDATORS_AMOUNT);
+    let api = testkit.api();
+
+    let deploy_request =
+        DeployRequest::new(FailingRuntime::artifact_should_be_deployed(), DEPLOY_HEIGHT);
+    let tx_hash = send_deploy_request(&api, &deploy_request).await;
+    let block = testkit.create_block();
+    block[tx_hash].status().unwrap();
+
+    // Confirm deploy with a checksum different from the one reported by our node.
+    let validators = testkit.network().validators();
+    let validator = validators
+        .iter()
+        .find(|validator| validator.validator_id() == Some(VALIDATOR_OTHER))
+        .unwrap();
+    let deploy_result =
+        DeployResult::ok(deploy_request.clone()).with_artifact_checksum(Hash::zero());
+    let deploy_confirmation = validator
+        .service_keypair()
+        .report_deploy_result(SUPERVISOR_INSTANCE_ID, deploy_result);
+    testkit.create_block_with_transaction(deploy_confirmation);
+
+    testkit.create_blocks_until(DEPLOY_HEIGHT.next());
+
+    let state = get_deploy_status(&api, &deploy_request).await;
+    let error = state.execution_error().expect("Deployment should fail");
+    assert_eq!(
+        error,
+        ErrorMatch::from_fail(&ArtifactError::ArtifactChecksumMismatch)
+            .with_description_containing("different from the checksum reported")
+    );
+    let snapshot = testkit.snapshot();
+    assert!(snapshot
+        .for_dispatcher()
+        .get_artifact(&deploy_request.artifact)
+        .is_none());
+}
+
+/// Checks that the deadline of a pending deployment can be extended.
+#[tokio::test]
+async fn deploy_deadline_extension() {
+    const EXTENDED_DEPLOY_HEIGHT: Height = Height(8);
+
+    let mut testkit = testkit_with_failing_runtime(VALIDATORS_AMOUNT);
+    let api = testkit.api();
+
+    let deploy_request =
+        DeployRequest::new(FailingRuntime::artifact_should_be_deployed(), DEPLOY_HEIGHT);
+    let tx_hash = send_deploy_request(&api, &deploy_request).await;
+    let block = testkit.create_block();
+    block[tx_hash].status().unwrap();
+
+    // An extension not exceeding the current deadline is rejected.
+    let keys = testkit.us().service_keypair();
+    let extension = DeployDeadlineExtension::new(deploy_request.clone(), DEPLOY_HEIGHT);
+    let tx = keys.extend_deploy_deadline(SUPERVISOR_INSTANCE_ID, extension);
+    let block = testkit.create_block_with_transaction(tx);
+    let err = block.transactions[0].status().unwrap_err();
+    assert_eq!(
+        *err,
+        ErrorMatch::from_fail(&ArtifactError::InvalidDeadlineExtension)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+    );
+
+    let extension = DeployDeadlineExtension::new(deploy_request.clone(), EXTENDED_DEPLOY_HEIGHT);
+    let tx_hash: Hash = api
+        .private(ApiKind::Service("supervisor"))
+        .query(&extension)
+        .post("extend-deploy-deadline")
+        .await
+        .expect("Call for `extend-deploy-deadline` API endpoint failed");
+    let block = testkit.create_block();
+    block[tx_hash].status().unwrap();
+
+    // The deployment is not timed out at the original deadline.
+    testkit.create_blocks_until(DEPLOY_HEIGHT.next());
+    let state = get_deploy_status(&api, &deploy_request).await;
+    assert_deploy_state(state, AsyncEventState::Pending);
+
+    // Confirm deploy after the original deadline.
+    let deploy_confirmation = build_result_transaction(&testkit, &deploy_request, Ok(()));
+    let block = testkit.create_block_with_transaction(deploy_confirmation);
+    block.transactions[0].status().unwrap();
+    testkit.create_blocks_until(EXTENDED_DEPLOY_HEIGHT.next());
+
+    let state = get_deploy_status(&api, &deploy_request).await;
+    assert_deploy_state(state, AsyncEventState::Succeed);
+}
+
+/// Checks that the `pending-deployments` endpoint reports confirmations of pending deployments.
+#[tokio::test]
+async fn pending_deployments_api() {
+    async fn get_pending_deployments(api: &TestKitApi) -> Vec<PendingDeployment> {
+        api.public(ApiKind::Service("supervisor"))
+            .get("pending-deployments")
+            .await
+            .expect("Call for `pending-deployments` API endpoint failed")
+    }
+
+    let mut testkit = testkit_with_failing_runtime(VALIDATORS_AMOUNT);
+    let api = testkit.api();
+
+    let deploy_request =
+        DeployRequest::new(FailingRuntime::artifact_should_be_deployed(), DEPLOY_HEIGHT);
+    let tx_hash = send_deploy_request(&api, &deploy_request).await;
+    let block = testkit.create_block();
+    block[tx_hash].status().unwrap();
+
+    let deployments = get_pending_deployments(&api).await;
+    assert_eq!(deployments.len(), 1);
+    let deployment = &deployments[0];
+    assert_eq!(deployment.request, deploy_request);
+    assert_eq!(deployment.deadline_height, DEPLOY_HEIGHT);
+    assert_eq!(deployment.requested_by, vec![ValidatorId(0)]);
+    assert!(!deployment.confirmed_by.contains(&VALIDATOR_OTHER));
+
+    // Confirm deploy on behalf of the other validator.
+    let deploy_confirmation = build_result_transaction(&testkit, &deploy_request, Ok(()));
+    let block = testkit.create_block_with_transaction(deploy_confirmation);
+    block.transactions[0].status().unwrap();
+
+    let deployments = get_pending_deployments(&api).await;
+    let deployment = &deployments[0];
+    assert!(deployment.confirmed_by.contains(&VALIDATOR_OTHER));
+    assert_eq!(
+        deployment.remaining_confirmations,
+        usize::from(VALIDATORS_AMOUNT) - deployment.confirmed_by.len()
+    );
+
+    // Deployments are removed after the deadline.
+    testkit.create_blocks_until(DEPLOY_HEIGHT.next());
+    assert!(get_pending_deployments(&api).await.is_empty());
+}
+
+/// Checks that deploy requests with runtime-specific spec sections are validated
+/// by the target runtime before the deployment starts.
+#[tokio::test]
+async fn deploy_spec_sections_are_validated_by_runtime() {
+    let mut testkit = testkit_with_failing_runtime(VALIDATORS_AMOUNT);
+    let api = testkit.api();
+    let artifact = FailingRuntime::artifact_should_be_deployed();
+
+    let invalid_specs = vec![
+        DeploySpec::new()
+            .with_section("wasm.module_hash", vec![1; 32])
+            .to_bytes(),
+        DeploySpec::new()
+            .with_section(FailingRuntime::CHECKSUM_SECTION, vec![0; 32])
+            .to_bytes(),
+        b"not a spec".to_vec(),
+    ];
+    for spec in invalid_specs {
+        let deploy_request = DeployRequest::new(artifact.clone(), DEPLOY_HEIGHT).with_spec(spec);
+        let tx_hash = send_deploy_request(&api, &deploy_request).await;
+        let block = testkit.create_block();
+        let err = block[tx_hash].status().unwrap_err();
+        assert_eq!(
+            *err,
+            ErrorMatch::from_fail(&FailingRuntimeError::InvalidSpec)
+                .for_service(SUPERVISOR_INSTANCE_ID)
+        );
+    }
+    assert!(get_pending_deployments(&api).await.is_empty());
+
+    let checksum = FailingRuntime::checksum(&artifact);
+    let spec =
+        DeploySpec::new().with_section(FailingRuntime::CHECKSUM_SECTION, checksum.to_bytes());
+    let deploy_request = DeployRequest::new(artifact, DEPLOY_HEIGHT.next()).with_deploy_spec(&spec);
+    let tx_hash = send_deploy_request(&api, &deploy_request).await;
+    let block = testkit.create_block();
+    block[tx_hash].status().unwrap();
+    let state = get_deploy_status(&api, &deploy_request).await;
+    assert_deploy_state(state, AsyncEventState::Pending);
+}
@@ -0,0 +1,120 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the `WebSocket` notifications about supervisor events.
+
+use exonum::{crypto::Hash, helpers::Height, merkledb::ObjectHash};
+use exonum_supervisor::{
+    api::websocket::Notification, ConfigProposalStatus, ConfigPropose, SupervisorEvent,
+};
+use exonum_testkit::TestKitApi;
+use futures::StreamExt;
+use tokio::{net::TcpStream, time::timeout};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use std::time::Duration;
+
+use crate::{config_api::create_proposal, utils::testkit_with_supervisor};
+
+type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn create_ws_client(api: &TestKitApi) -> Client {
+    let url = api
+        .private_url("api/services/supervisor/events/subscribe")
+        .replace("http://", "ws://");
+    connect_async(url)
+        .await
+        .map(|(socket, _)| socket)
+        .expect("Couldn't create web socket client")
+}
+
+async fn receive_notification(client: &mut Client) -> Notification {
+    let message = timeout(Duration::from_secs(5), client.next())
+        .await
+        .expect("Notification was not received in time")
+        .expect("Web socket closed unexpectedly")
+        .expect("Web socket error");
+    match message {
+        Message::Text(ref text) => serde_json::from_str(text).unwrap(),
+        other => panic!("Unexpected WS message: {:?}", other),
+    }
+}
+
+fn assert_config_event(event: &SupervisorEvent, hash: Hash, expected: ConfigProposalStatus) {
+    match event {
+        SupervisorEvent::ConfigProposal(event) => {
+            assert_eq!(event.propose_hash, hash);
+            assert_eq!(event.status, expected);
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn config_proposal_events() {
+    let mut testkit = testkit_with_supervisor(1);
+    let api = testkit.api();
+    let mut client = create_ws_client(&api).await;
+
+    let proposal = ConfigPropose::new(0, Height(2));
+    let proposal_hash = proposal.object_hash();
+    create_proposal(&api, proposal).await;
+    testkit.create_block();
+
+    let notification = receive_notification(&mut client).await;
+    assert_eq!(notification.height, Height(1));
+    assert_eq!(notification.events.len(), 1);
+    assert_config_event(
+        &notification.events[0],
+        proposal_hash,
+        ConfigProposalStatus::Registered,
+    );
+
+    testkit.create_block();
+    let notification = receive_notification(&mut client).await;
+    assert_eq!(notification.height, Height(2));
+    assert_config_event(
+        &notification.events[0],
+        proposal_hash,
+        ConfigProposalStatus::Applied,
+    );
+}
+
+#[tokio::test]
+async fn expired_config_proposal_event() {
+    let mut testkit = testkit_with_supervisor(2);
+    let api = testkit.api();
+    let mut client = create_ws_client(&api).await;
+
+    // The proposal cannot be applied since the second validator does not vote for it.
+    let proposal = ConfigPropose::new(0, Height(2));
+    let proposal_hash = proposal.object_hash();
+    create_proposal(&api, proposal).await;
+    testkit.create_block();
+    let notification = receive_notification(&mut client).await;
+    assert_config_event(
+        &notification.events[0],
+        proposal_hash,
+        ConfigProposalStatus::Registered,
+    );
+
+    testkit.create_blocks_until(Height(3));
+    let notification = receive_notification(&mut client).await;
+    assert_eq!(notification.height, Height(3));
+    assert_config_event(
+        &notification.events[0],
+        proposal_hash,
+        ConfigProposalStatus::Expired,
+    );
+}
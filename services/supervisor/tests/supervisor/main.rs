@@ -30,8 +30,10 @@ use exonum_rust_runtime::{
     RustRuntimeBuilder, ServiceFactory,
 };
 use exonum_supervisor::{
+    api::{ReadinessQuery, ValidatorReadiness},
     ArtifactError, CommonError as SupervisorCommonError, ConfigPropose, DeployRequest,
-    DeployResult, ServiceError, Supervisor, SupervisorInterface,
+    DeployResult, PendingUpgrade, ReadinessAttestation, ServiceError, Supervisor,
+    SupervisorInterface, UpgradeKind,
 };
 use exonum_testkit::{ApiKind, TestKit, TestKitApi, TestKitBuilder};
 
@@ -47,7 +49,9 @@ mod config;
 mod config_api;
 mod consensus_config;
 mod deploy_failures;
+mod events;
 mod inc;
+mod metrics;
 mod migrations;
 mod service_lifecycle;
 mod supervisor_config;
@@ -967,3 +971,74 @@ async fn test_id_assignment_sparse() {
         max_builtin_id + 1
     );
 }
+
+/// Checks that validators can attest their readiness for pending upgrades.
+#[tokio::test]
+async fn test_readiness_attestations() {
+    let mut testkit = testkit_with_inc_service_and_two_validators();
+    // The deployment stays pending since the second validator never confirms it.
+    let request = deploy_request(default_artifact(), Height(10));
+    let subject = request.object_hash();
+
+    // Attestations for upgrades which are not pending are rejected.
+    let attestation = ReadinessAttestation::new(subject, vec![]);
+    let tx = testkit
+        .us()
+        .service_keypair()
+        .report_readiness(SUPERVISOR_INSTANCE_ID, attestation.clone());
+    let block = testkit.create_block_with_transaction(tx);
+    let err = block.transactions[0].status().unwrap_err();
+    assert_eq!(
+        *err,
+        ErrorMatch::from_fail(&SupervisorCommonError::UnknownUpgrade)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+    );
+
+    // Approve the deployment by both validators.
+    deploy_artifact_manually(&mut testkit, &request, ValidatorId(0));
+    deploy_artifact_manually(&mut testkit, &request, ValidatorId(1));
+    testkit.create_block();
+
+    let upgrades = PendingUpgrade::load_all(testkit.snapshot().as_ref());
+    assert_eq!(upgrades.len(), 1);
+    assert_eq!(upgrades[0].kind, UpgradeKind::Deployment);
+    assert_eq!(upgrades[0].subject, subject);
+    assert_eq!(upgrades[0].artifacts, vec![default_artifact()]);
+
+    let api = testkit.api();
+    let hash: crypto::Hash = api
+        .private(ApiKind::Service("supervisor"))
+        .query(&attestation)
+        .post("report-readiness")
+        .await
+        .unwrap();
+    let failed_checks = vec!["Not enough disk space".to_owned()];
+    let tx = testkit
+        .validator(ValidatorId(1))
+        .service_keypair()
+        .report_readiness(
+            SUPERVISOR_INSTANCE_ID,
+            ReadinessAttestation::new(subject, failed_checks.clone()),
+        );
+    let other_hash = tx.object_hash();
+    testkit.add_tx(tx);
+    let block = testkit.create_block();
+    block[hash].status().unwrap();
+    block[other_hash].status().unwrap();
+
+    let readiness: Vec<ValidatorReadiness> = api
+        .public(ApiKind::Service("supervisor"))
+        .query(&ReadinessQuery { subject })
+        .get("readiness")
+        .await
+        .unwrap();
+    assert_eq!(readiness.len(), 2);
+    for validator_readiness in readiness {
+        let attestation = validator_readiness.attestation;
+        if validator_readiness.service_key == testkit.us().service_keypair().public_key() {
+            assert!(attestation.is_ready());
+        } else {
+            assert_eq!(attestation.failed_checks, failed_checks);
+        }
+    }
+}
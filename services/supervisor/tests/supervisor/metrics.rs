@@ -0,0 +1,56 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the Prometheus metrics of the supervisor.
+
+use exonum::helpers::Height;
+use exonum_supervisor::ConfigPropose;
+use exonum_testkit::TestKitApi;
+
+use crate::{config_api::create_proposal, utils::testkit_with_supervisor};
+
+async fn get_metrics(api: &TestKitApi) -> String {
+    let url = api.private_url("api/services/supervisor/metrics");
+    let response = api.client().inner().get(&url).send().await.unwrap();
+    assert!(response.status().is_success());
+    let content_type = response.headers()["content-type"].to_str().unwrap();
+    assert!(content_type.starts_with("text/plain"));
+    response.text().await.unwrap()
+}
+
+#[tokio::test]
+async fn config_proposal_metrics() {
+    let mut testkit = testkit_with_supervisor(2);
+    let api = testkit.api();
+
+    // The proposal cannot be applied since the second validator does not vote for it.
+    let proposal = ConfigPropose::new(0, Height(3));
+    create_proposal(&api, proposal).await;
+    testkit.create_block();
+    let metrics = get_metrics(&api).await;
+    assert!(metrics.contains("exonum_supervisor_config_proposals_total{status=\"registered\"} 1\n"));
+    assert!(metrics.contains("exonum_supervisor_pending_proposal_age_blocks 0\n"));
+    assert!(metrics.contains("exonum_supervisor_pending_proposal_confirmations 1\n"));
+    assert!(metrics.contains("exonum_supervisor_pending_deployments 0\n"));
+
+    testkit.create_block();
+    let metrics = get_metrics(&api).await;
+    assert!(metrics.contains("exonum_supervisor_pending_proposal_age_blocks 1\n"));
+
+    testkit.create_blocks_until(Height(4));
+    let metrics = get_metrics(&api).await;
+    assert!(metrics.contains("exonum_supervisor_config_proposals_total{status=\"expired\"} 1\n"));
+    assert!(metrics.contains("exonum_supervisor_config_proposal_latency_blocks_count 1\n"));
+    assert!(!metrics.contains("exonum_supervisor_pending_proposal_age_blocks"));
+}
@@ -17,16 +17,17 @@ use exonum::{
     helpers::{Height, ValidatorId},
     merkledb::access::Prefixed,
     runtime::{
-        versioning::Version, CoreError, ErrorMatch, ExecutionError, InstanceId, SnapshotExt,
-        SUPERVISOR_INSTANCE_ID,
+        versioning::Version, CoreError, ErrorMatch, ExecutionError, InstanceId, RuntimeIdentifier,
+        SnapshotExt, SUPERVISOR_INSTANCE_ID,
     },
 };
 use exonum_rust_runtime::{DefaultInstance, ServiceFactory};
 use exonum_testkit::{ApiKind, Spec, TestKit, TestKitApi, TestKitBuilder};
 
 use exonum_supervisor::{
-    api::MigrationInfoQuery, AsyncEventState, ConfigPropose, ConfigurationError, MigrationError,
-    MigrationRequest, MigrationResult, MigrationState, SchemaImpl, Supervisor, SupervisorInterface,
+    api::MigrationInfoQuery, ArtifactUpgradePolicy, AsyncEventState, ConfigPropose,
+    ConfigurationError, MigrationError, MigrationProgress, MigrationRequest, MigrationResult,
+    MigrationState, SchemaImpl, Supervisor, SupervisorInterface,
 };
 
 use std::{thread, time::Duration};
@@ -668,6 +669,155 @@ async fn migration_hash_divergence() {
     migration_service::v01::verify_schema(prefixed);
 }
 
+/// Test for aborting a migration with multiple validators.
+///
+/// This test checks that a migration can be aborted by validators before
+/// all of them report the migration result.
+///
+/// Expected behavior is that migration is failed and no changes are performed to schema.
+#[tokio::test]
+async fn migration_abort() {
+    let validators_amount = 2;
+    let mut testkit = testkit_with_supervisor_and_service(validators_amount);
+
+    // Stop service instance before running the migration.
+    stop_service(&mut testkit, MigrationService::INSTANCE_ID);
+
+    // Request migration.
+    let deadline_height = DEADLINE_HEIGHT;
+    let request = MigrationRequest::new(
+        MigrationServiceV02.artifact_id(),
+        MigrationService::INSTANCE_NAME,
+        deadline_height,
+    );
+
+    send_migration_request(&mut testkit, request.clone()).await;
+    // Wait until our node completes the migration locally. The migration is still pending,
+    // since the other validator has not reported the result.
+    obtain_reference_hash(&mut testkit, &request);
+
+    let api = testkit.api();
+    let tx_hash: Hash = api
+        .private(ApiKind::Service("supervisor"))
+        .query(&request)
+        .post("abort-migration")
+        .await
+        .unwrap();
+    let block = testkit.create_block();
+    block[tx_hash]
+        .status()
+        .expect("Transaction should be executed successfully");
+
+    let state = migration_state(&api, request.clone()).await;
+    let error = state
+        .inner
+        .execution_error()
+        .expect("Migration should fail");
+    assert_eq!(
+        error,
+        ErrorMatch::from_fail(&MigrationError::MigrationAborted).with_any_description()
+    );
+
+    // Check that schema did not change.
+    let snapshot = testkit.snapshot();
+    let prefixed = Prefixed::new(MigrationService::INSTANCE_NAME, snapshot.as_ref());
+    migration_service::v01::verify_schema(prefixed);
+
+    // An attempt to abort the migration again should fail.
+    let abort_tx = testkit
+        .us()
+        .service_keypair()
+        .abort_migration(SUPERVISOR_INSTANCE_ID, request);
+    let err = execute_transaction(&mut testkit, abort_tx).unwrap_err();
+    assert_eq!(
+        err,
+        ErrorMatch::from_fail(&MigrationError::MigrationNotPending)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+            .with_description_containing("is already completed; impossible to abort it")
+    );
+}
+
+/// Checks that validators can report intermediate progress of a pending migration.
+#[tokio::test]
+async fn migration_progress() {
+    let validators_amount = 2;
+    let mut testkit = testkit_with_supervisor_and_service(validators_amount);
+    stop_service(&mut testkit, MigrationService::INSTANCE_ID);
+
+    let deadline_height = DEADLINE_HEIGHT;
+    let request = MigrationRequest::new(
+        MigrationServiceV02.artifact_id(),
+        MigrationService::INSTANCE_NAME,
+        deadline_height,
+    );
+    send_migration_request(&mut testkit, request.clone()).await;
+    // The migration is still pending, since the other validator has not reported the result.
+    obtain_reference_hash(&mut testkit, &request);
+
+    // Report progress of our node via API.
+    let api = testkit.api();
+    let progress = MigrationProgress::new(request.clone(), 100, 50);
+    let tx_hash: Hash = api
+        .private(ApiKind::Service("supervisor"))
+        .query(&progress)
+        .post("report-migration-progress")
+        .await
+        .unwrap();
+    let block = testkit.create_block();
+    block[tx_hash]
+        .status()
+        .expect("Transaction should be executed successfully");
+
+    // Report progress of another validator, and then update it.
+    let other_keys = testkit.validator(ValidatorId(1)).service_keypair();
+    for &(processed_keys, percentage) in &[(10, 5), (20, 10)] {
+        let progress = MigrationProgress::new(request.clone(), processed_keys, percentage);
+        let tx = other_keys.report_migration_progress(SUPERVISOR_INSTANCE_ID, progress);
+        execute_transaction(&mut testkit, tx).expect("Progress should be reported");
+    }
+
+    let state = migration_state(&api, request.clone()).await;
+    assert!(state.is_pending());
+    assert_eq!(state.progress.len(), 2);
+    assert_eq!(
+        state.progress[0].validator,
+        testkit.us().service_keypair().public_key()
+    );
+    assert_eq!(state.progress[0].processed_keys, 100);
+    assert_eq!(state.progress[0].percentage, 50);
+    assert_eq!(state.progress[1].validator, other_keys.public_key());
+    assert_eq!(state.progress[1].processed_keys, 20);
+    assert_eq!(state.progress[1].percentage, 10);
+    assert_eq!(state.progress[1].height, testkit.height());
+
+    // Percentage cannot exceed 100.
+    let progress = MigrationProgress::new(request.clone(), 20, 101);
+    let tx = other_keys.report_migration_progress(SUPERVISOR_INSTANCE_ID, progress);
+    let err = execute_transaction(&mut testkit, tx).unwrap_err();
+    assert_eq!(
+        err,
+        ErrorMatch::from_fail(&MigrationError::InvalidMigrationProgress)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+            .with_any_description()
+    );
+
+    // Progress cannot be reported once the migration is completed.
+    let abort_tx = testkit
+        .us()
+        .service_keypair()
+        .abort_migration(SUPERVISOR_INSTANCE_ID, request.clone());
+    execute_transaction(&mut testkit, abort_tx).expect("Migration should be aborted");
+    let progress = MigrationProgress::new(request, 30, 15);
+    let tx = other_keys.report_migration_progress(SUPERVISOR_INSTANCE_ID, progress);
+    let err = execute_transaction(&mut testkit, tx).unwrap_err();
+    assert_eq!(
+        err,
+        ErrorMatch::from_fail(&MigrationError::MigrationNotPending)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+            .with_description_containing("impossible to report its progress")
+    );
+}
+
 /// Test for a fast-forward migration (0.1.0 - 0.1.1)
 #[tokio::test]
 async fn fast_forward_migration() {
@@ -769,3 +919,87 @@ async fn mixed_migration() {
     // Data should not change.
     migration_service::v05::verify_schema(prefixed);
 }
+
+/// Checks that migrations violating the upgrade policy for the artifact are rejected.
+#[tokio::test]
+async fn migration_with_upgrade_policy() {
+    let policy = ArtifactUpgradePolicy::semver_compatible(
+        RuntimeIdentifier::Rust as u32,
+        MigrationService.artifact_id().name,
+    );
+    let config = Supervisor::simple_config().with_upgrade_policy(policy);
+    let mut testkit = TestKitBuilder::validator()
+        .with(Supervisor::builtin_instance(config))
+        .with(Spec::new(MigrationService).with_default_instance())
+        .with(Spec::migrating(MigrationServiceV01_1))
+        .with(Spec::migrating(MigrationServiceV02))
+        .build();
+    stop_service(&mut testkit, MigrationService::INSTANCE_ID);
+
+    // Migration to 0.2.0 is not semver-compatible.
+    let request = MigrationRequest::new(
+        MigrationServiceV02.artifact_id(),
+        MigrationService::INSTANCE_NAME,
+        DEADLINE_HEIGHT,
+    );
+    let tx = testkit
+        .us()
+        .service_keypair()
+        .request_migration(SUPERVISOR_INSTANCE_ID, request);
+    let err = execute_transaction(&mut testkit, tx).unwrap_err();
+    assert_eq!(
+        err,
+        ErrorMatch::from_fail(&MigrationError::UpgradePolicyViolation)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+            .with_description_containing("from version 0.1.0 to 0.2.0 is not semver-compatible")
+    );
+
+    // Migration to 0.1.1 is allowed.
+    let request = MigrationRequest::new(
+        MigrationServiceV01_1.artifact_id(),
+        MigrationService::INSTANCE_NAME,
+        DEADLINE_HEIGHT,
+    );
+    send_migration_request(&mut testkit, request.clone()).await;
+    wait_for_migration_success(
+        &mut testkit,
+        DEADLINE_HEIGHT,
+        request,
+        Version::new(0, 1, 1),
+    )
+    .await;
+}
+
+/// Checks that the superseded artifact is unloaded after the migration if requested.
+#[tokio::test]
+async fn migration_with_superseded_artifact_unload() {
+    let mut testkit = testkit_with_supervisor_and_service(1);
+    stop_service(&mut testkit, MigrationService::INSTANCE_ID);
+
+    let old_artifact = MigrationService.artifact_id();
+    let request = MigrationRequest::new(
+        MigrationServiceV01_1.artifact_id(),
+        MigrationService::INSTANCE_NAME,
+        DEADLINE_HEIGHT,
+    )
+    .with_superseded_artifact_unload();
+    send_migration_request(&mut testkit, request.clone()).await;
+    wait_for_migration_success(
+        &mut testkit,
+        DEADLINE_HEIGHT,
+        request,
+        Version::new(0, 1, 1),
+    )
+    .await;
+
+    // The artifact is unloaded in the next block.
+    testkit.create_block();
+    let snapshot = testkit.snapshot();
+    let dispatcher = snapshot.for_dispatcher();
+    assert!(dispatcher.get_artifact(&old_artifact).is_none());
+    assert!(dispatcher
+        .get_artifact(&MigrationServiceV01_1.artifact_id())
+        .is_some());
+    let schema = SchemaImpl::new(Prefixed::new(Supervisor::NAME, snapshot.as_ref()));
+    assert!(!schema.superseded_artifacts.contains(&old_artifact));
+}
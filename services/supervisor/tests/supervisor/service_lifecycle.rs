@@ -23,8 +23,9 @@ use exonum::{
         migrations::{InitMigrationError, MigrationScript},
         oneshot::Receiver,
         versioning::Version,
-        ArtifactId, ErrorMatch, ExecutionError, InstanceState, InstanceStatus, Mailbox, Runtime,
-        SnapshotExt, WellKnownRuntime, SUPERVISOR_INSTANCE_ID,
+        ArtifactId, ArtifactStatus, Capabilities, Capability, ErrorMatch, ExecutionError,
+        InstanceQuery, InstanceState, InstanceStatus, Mailbox, Runtime, SnapshotExt,
+        WellKnownRuntime, SUPERVISOR_INSTANCE_ID,
     },
 };
 use exonum_rust_runtime::{
@@ -35,7 +36,8 @@ use exonum_testkit::{ApiKind, TestKit, TestKitBuilder};
 
 use crate::inc::IncService;
 use exonum_supervisor::{
-    ArtifactError, ConfigPropose, ConfigurationError, Supervisor, SupervisorInterface,
+    api::DispatcherInfo, ArtifactError, ConfigPropose, ConfigurationError, Schema, ServiceError,
+    ServiceMetadata, StopService, Supervisor, SupervisorInterface,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -225,6 +227,155 @@ async fn start_freeze_and_stop_inc_service() {
     );
 }
 
+#[test]
+fn freeze_service_with_scheduled_resume() {
+    let mut testkit = create_testkit();
+    let keypair = testkit.us().service_keypair();
+    let instance_id = start_inc_service(&mut testkit).spec.id;
+    let instance_status = |testkit: &TestKit| {
+        testkit
+            .snapshot()
+            .for_dispatcher()
+            .get_instance(instance_id)
+            .unwrap()
+            .status
+    };
+
+    // Freeze service instance at height 2 and resume it at height 4.
+    let change = ConfigPropose::immediate(1).freeze_service_until(instance_id, Height(4));
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change)
+        .expect("Freeze service transaction should be processed");
+    assert_eq!(instance_status(&testkit), Some(InstanceStatus::Frozen));
+
+    testkit.create_block();
+    assert_eq!(testkit.height(), Height(3));
+    assert_eq!(instance_status(&testkit), Some(InstanceStatus::Frozen));
+
+    testkit.create_block();
+    assert_eq!(instance_status(&testkit), Some(InstanceStatus::Active));
+}
+
+#[test]
+fn freeze_service_with_resume_height_in_past() {
+    let mut testkit = create_testkit();
+    let keypair = testkit.us().service_keypair();
+    let instance_id = start_inc_service(&mut testkit).spec.id;
+
+    // The proposal is applied at height 2, so resuming at the same height makes no sense.
+    let change = ConfigPropose::immediate(1).freeze_service_until(instance_id, Height(2));
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    let actual_err =
+        execute_transaction(&mut testkit, change).expect_err("Transaction shouldn't be processed");
+
+    assert_eq!(
+        actual_err,
+        ErrorMatch::from_fail(&ConfigurationError::MalformedConfigPropose)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+            .with_description_containing(
+                "with resume height (2) not exceeding the activation height of the proposal (2)"
+            )
+    );
+}
+
+#[test]
+fn multi_stage_proposal() {
+    let mut testkit = create_testkit();
+    let keypair = testkit.us().service_keypair();
+    let instance_id = start_inc_service(&mut testkit).spec.id;
+    let instance_status = |testkit: &TestKit| {
+        testkit
+            .snapshot()
+            .for_dispatcher()
+            .get_instance(instance_id)
+            .unwrap()
+            .status
+    };
+
+    // Stop service instance at height 2 and resume it at height 4.
+    let change = ConfigPropose::immediate(1)
+        .stop_service(instance_id)
+        .stage(Height(4))
+        .resume_service(instance_id, ());
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Transaction should be processed");
+    assert_eq!(instance_status(&testkit), Some(InstanceStatus::Stopped));
+
+    // New proposals are not accepted until all stages are applied.
+    let change = ConfigPropose::immediate(2)
+        .set_service_metadata(instance_id, ServiceMetadata::new(vec![("owner", "team")]));
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    let actual_err =
+        execute_transaction(&mut testkit, change).expect_err("Transaction shouldn't be processed");
+    assert_eq!(
+        actual_err,
+        ErrorMatch::from_fail(&ConfigurationError::ConfigProposeExists)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+            .with_description_containing("Stages of the previously applied proposal")
+    );
+    assert_eq!(testkit.height(), Height(3));
+    assert_eq!(instance_status(&testkit), Some(InstanceStatus::Stopped));
+
+    testkit.create_block();
+    assert_eq!(instance_status(&testkit), Some(InstanceStatus::Active));
+
+    let change = ConfigPropose::immediate(2).stop_service(instance_id);
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Transaction should be processed");
+    assert_eq!(instance_status(&testkit), Some(InstanceStatus::Stopped));
+}
+
+#[test]
+fn multi_stage_proposal_with_invalid_stage() {
+    let mut testkit = create_testkit();
+    let keypair = testkit.us().service_keypair();
+    let instance_id = start_inc_service(&mut testkit).spec.id;
+
+    // The second stage is invalid, since the service is already stopped.
+    let change = ConfigPropose::immediate(1)
+        .stop_service(instance_id)
+        .stage(Height(3))
+        .stop_service(instance_id)
+        .stage(Height(4))
+        .resume_service(instance_id, ());
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Transaction should be processed");
+    testkit.create_blocks_until(Height(5));
+
+    // The remaining stages are discarded, so the service is not resumed.
+    let snapshot = testkit.snapshot();
+    let instance = snapshot.for_dispatcher().get_instance(instance_id).unwrap();
+    assert_eq!(instance.status, Some(InstanceStatus::Stopped));
+
+    // Since the staged proposal is discarded, new proposals are accepted.
+    let change = ConfigPropose::immediate(2).resume_service(instance_id, ());
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Transaction should be processed");
+}
+
+#[test]
+fn multi_stage_proposal_with_unordered_stages() {
+    let mut testkit = create_testkit();
+    let keypair = testkit.us().service_keypair();
+    let instance_id = start_inc_service(&mut testkit).spec.id;
+
+    let change = ConfigPropose::immediate(1)
+        .stop_service(instance_id)
+        .stage(Height(2))
+        .resume_service(instance_id, ());
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    let actual_err =
+        execute_transaction(&mut testkit, change).expect_err("Transaction shouldn't be processed");
+    assert_eq!(
+        actual_err,
+        ErrorMatch::from_fail(&ConfigurationError::MalformedConfigPropose)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+            .with_description_containing(
+                "activation height (2) not exceeding the activation height of the previous stage"
+            )
+    );
+}
+
 #[test]
 fn stop_non_existent_service() {
     let mut testkit = create_testkit();
@@ -370,6 +521,171 @@ fn duplicate_artifact_unload_request() {
     );
 }
 
+#[tokio::test]
+async fn deprecate_used_artifact() {
+    let mut testkit = create_testkit();
+    let keypair = testkit.us().service_keypair();
+    start_inc_service(&mut testkit);
+
+    let artifact = IncService.artifact_id();
+    let change = ConfigPropose::immediate(1).deprecate_artifact(artifact.clone());
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Cannot deprecate artifact");
+
+    let snapshot = testkit.snapshot();
+    let state = snapshot.for_dispatcher().get_artifact(&artifact).unwrap();
+    assert_eq!(state.status, ArtifactStatus::Deprecated);
+    // The existing service continues to work.
+    assert!(is_inc_service_api_available(&mut testkit).await);
+
+    // Check that an attempt to start another `IncService` instance fails now.
+    let change = ConfigPropose::immediate(2).start_service(artifact.clone(), "test", ());
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    let err = execute_transaction(&mut testkit, change).unwrap_err();
+    let expected_msg = "Discarded start of service `test` from the deprecated artifact";
+    assert_eq!(
+        err,
+        ErrorMatch::from_fail(&ArtifactError::ArtifactDeprecated)
+            .with_description_containing(expected_msg)
+    );
+
+    // So does deprecating the artifact again.
+    let change = ConfigPropose::immediate(2).deprecate_artifact(artifact);
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    let err = execute_transaction(&mut testkit, change).unwrap_err();
+    let expected_msg = "Discarded deprecation of artifact `0:inc:1.0.0` with non-active status \
+                        (deprecated)";
+    assert_eq!(
+        err,
+        ErrorMatch::from_fail(&ConfigurationError::MalformedConfigPropose)
+            .with_description_containing(expected_msg)
+    );
+
+    // The deprecated artifact is listed in the supervisor API.
+    let info: DispatcherInfo = testkit
+        .api()
+        .public(ApiKind::Service("supervisor"))
+        .get("services")
+        .await
+        .unwrap();
+    assert_eq!(info.deprecated_artifacts, vec![IncService.artifact_id()]);
+}
+
+#[tokio::test]
+async fn set_and_clear_service_metadata() {
+    let mut testkit = create_testkit();
+    let keypair = testkit.us().service_keypair();
+    let instance_id = start_inc_service(&mut testkit).spec.id;
+
+    let metadata = ServiceMetadata::new(vec![("environment", "staging"), ("owner", "core-team")]);
+    let change = ConfigPropose::immediate(1).set_service_metadata(instance_id, metadata.clone());
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Cannot set service metadata");
+
+    let info: DispatcherInfo = testkit
+        .api()
+        .public(ApiKind::Service("supervisor"))
+        .get("services")
+        .await
+        .unwrap();
+    assert_eq!(info.service_metadata.len(), 1);
+    assert_eq!(info.service_metadata[IncService::INSTANCE_NAME], metadata);
+
+    // Empty metadata removes the previously set one.
+    let change =
+        ConfigPropose::immediate(2).set_service_metadata(instance_id, ServiceMetadata::default());
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Cannot clear service metadata");
+
+    let info: DispatcherInfo = testkit
+        .api()
+        .public(ApiKind::Service("supervisor"))
+        .get("services")
+        .await
+        .unwrap();
+    assert!(info.service_metadata.is_empty());
+}
+
+#[tokio::test]
+async fn toggle_feature_flags() {
+    let mut testkit = create_testkit();
+    let keypair = testkit.us().service_keypair();
+    let instance_id = start_inc_service(&mut testkit).spec.id;
+
+    let flags = vec![("new-logic", true), ("fees", true)];
+    let change = ConfigPropose::immediate(1).set_feature_flags(instance_id, flags);
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Cannot set feature flags");
+
+    let snapshot = testkit.snapshot();
+    let schema: Schema<_> = snapshot.service_schema(SUPERVISOR_INSTANCE_ID).unwrap();
+    assert!(schema.feature_enabled(instance_id, "new-logic"));
+    assert!(schema.feature_enabled(instance_id, "fees"));
+    assert!(!schema.feature_enabled(instance_id, "other"));
+
+    // Flags not mentioned in the update retain their state.
+    let change = ConfigPropose::immediate(2).set_feature_flags(instance_id, vec![("fees", false)]);
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Cannot set feature flags");
+
+    let info: DispatcherInfo = testkit
+        .api()
+        .public(ApiKind::Service("supervisor"))
+        .get("services")
+        .await
+        .unwrap();
+    let flags = &info.feature_flags[IncService::INSTANCE_NAME];
+    assert!(flags.is_enabled("new-logic"));
+    assert!(!flags.is_enabled("fees"));
+
+    // Flags with empty names are rejected.
+    let change = ConfigPropose::immediate(3).set_feature_flags(instance_id, vec![("", true)]);
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    let actual_err =
+        execute_transaction(&mut testkit, change).expect_err("Transaction shouldn't be processed");
+    assert_eq!(
+        actual_err,
+        ErrorMatch::from_fail(&ConfigurationError::MalformedConfigPropose)
+            .with_description_containing("with an empty name")
+    );
+}
+
+#[test]
+fn set_metadata_for_unknown_service() {
+    let mut testkit = create_testkit();
+    let metadata = ServiceMetadata::new(vec![("environment", "production")]);
+    let change = ConfigPropose::immediate(0).set_service_metadata(100, metadata);
+    let keypair = testkit.us().service_keypair();
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    let err = execute_transaction(&mut testkit, change).unwrap_err();
+
+    assert_eq!(
+        err,
+        ErrorMatch::from_fail(&ConfigurationError::MalformedConfigPropose)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+            .with_description_containing("Instance with ID 100 is absent from the blockchain")
+    );
+}
+
+#[test]
+fn deprecating_artifact_with_concurrent_service_start() {
+    let mut testkit = create_testkit();
+    let keypair = testkit.us().service_keypair();
+    let artifact = IncService.artifact_id();
+    let change = ConfigPropose::immediate(0)
+        .deprecate_artifact(artifact.clone())
+        .start_service(artifact, "inc", ());
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+
+    let err = execute_transaction(&mut testkit, change).expect_err("Config should fail");
+    let expected_msg = "starts a service from artifact `0:inc:1.0.0` and deprecates it";
+    assert_eq!(
+        err,
+        ErrorMatch::from_fail(&ConfigurationError::MalformedConfigPropose)
+            .with_description_containing(expected_msg)
+    );
+}
+
 #[test]
 fn stop_already_stopped_service() {
     let mut testkit = create_testkit();
@@ -477,3 +793,257 @@ fn freeze_without_runtime_support() {
             .with_description_containing("Cannot freeze service `100:test`")
     );
 }
+
+#[test]
+fn rename_stopped_service() {
+    let mut testkit = create_testkit();
+    let keypair = testkit.us().service_keypair();
+    let instance_id = start_inc_service(&mut testkit).spec.id;
+
+    // Active services cannot be renamed.
+    let change = ConfigPropose::immediate(1).rename_service(instance_id, "inc-renamed");
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    let actual_err =
+        execute_transaction(&mut testkit, change).expect_err("Transaction shouldn't be processed");
+    assert_eq!(
+        actual_err,
+        ErrorMatch::from_fail(&ConfigurationError::MalformedConfigPropose)
+            .with_description_containing(
+                "Discarded an attempt to rename service `inc` with inappropriate status (active)"
+            )
+    );
+
+    // Stop and rename the service.
+    let change = ConfigPropose::immediate(2).stop_service(instance_id);
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Transaction should be processed");
+    let change = ConfigPropose::immediate(3).rename_service(instance_id, "inc-renamed");
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Transaction should be processed");
+
+    let snapshot = testkit.snapshot();
+    let dispatcher = snapshot.for_dispatcher();
+    assert!(dispatcher.get_instance(IncService::INSTANCE_NAME).is_none());
+    let instance = dispatcher.get_instance("inc-renamed").unwrap();
+    assert_eq!(instance.spec.id, instance_id);
+    assert_eq!(instance.status, Some(InstanceStatus::Stopped));
+    let instance = dispatcher.get_instance(instance_id).unwrap();
+    assert_eq!(instance.spec.name, "inc-renamed");
+
+    // The renamed service can be resumed.
+    let change = ConfigPropose::immediate(4).resume_service(instance_id, ());
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Transaction should be processed");
+    let snapshot = testkit.snapshot();
+    let instance = snapshot
+        .for_dispatcher()
+        .get_instance("inc-renamed")
+        .unwrap();
+    assert_eq!(instance.status, Some(InstanceStatus::Active));
+}
+
+#[test]
+fn rename_service_to_existing_name() {
+    let mut testkit = create_testkit();
+    let keypair = testkit.us().service_keypair();
+    let instance_id = start_inc_service(&mut testkit).spec.id;
+
+    let change = ConfigPropose::immediate(1).stop_service(instance_id);
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Transaction should be processed");
+
+    let change = ConfigPropose::immediate(2).rename_service(instance_id, "supervisor");
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    let actual_err =
+        execute_transaction(&mut testkit, change).expect_err("Transaction shouldn't be processed");
+    assert_eq!(
+        actual_err,
+        ErrorMatch::from_fail(&ServiceError::InstanceExists)
+            .with_description_containing("to the already used name `supervisor`")
+    );
+}
+
+#[test]
+fn set_default_instance_for_interface() {
+    let mut testkit = create_testkit();
+    let keypair = testkit.us().service_keypair();
+    let instance_id = start_inc_service(&mut testkit).spec.id;
+
+    let change = ConfigPropose::immediate(1).set_default_instance("counter", instance_id);
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Transaction should be processed");
+
+    let snapshot = testkit.snapshot();
+    let dispatcher = snapshot.for_dispatcher();
+    assert_eq!(dispatcher.default_instance_id("counter"), Some(instance_id));
+    let instance = dispatcher
+        .get_instance(InstanceQuery::Interface("counter"))
+        .unwrap();
+    assert_eq!(instance.spec.name, IncService::INSTANCE_NAME);
+    assert!(dispatcher
+        .get_instance(InstanceQuery::Interface("token"))
+        .is_none());
+
+    // Interface names are validated.
+    let change = ConfigPropose::immediate(2).set_default_instance("count er", instance_id);
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    let actual_err =
+        execute_transaction(&mut testkit, change).expect_err("Transaction shouldn't be processed");
+    assert_eq!(
+        actual_err,
+        ErrorMatch::from_fail(&ConfigurationError::MalformedConfigPropose)
+            .with_description_containing("Interface name `count er` is invalid")
+    );
+
+    // Unknown services cannot become default instances.
+    let change = ConfigPropose::immediate(2).set_default_instance("token", 1_000);
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    let actual_err =
+        execute_transaction(&mut testkit, change).expect_err("Transaction shouldn't be processed");
+    assert_eq!(
+        actual_err,
+        ErrorMatch::from_fail(&ConfigurationError::MalformedConfigPropose)
+            .with_description_containing("Instance with ID 1000 is absent from the blockchain")
+    );
+}
+
+#[test]
+fn set_artifact_capabilities() {
+    let mut testkit = create_testkit();
+    let keypair = testkit.us().service_keypair();
+    let artifact = IncService.artifact_id();
+    assert_eq!(
+        testkit
+            .snapshot()
+            .for_dispatcher()
+            .get_artifact_capabilities(&artifact),
+        None
+    );
+
+    let capabilities = Capabilities::none().with(Capability::ReadServiceData);
+    let change =
+        ConfigPropose::immediate(0).set_artifact_capabilities(artifact.clone(), capabilities);
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    execute_transaction(&mut testkit, change).expect("Transaction should be processed");
+
+    let snapshot = testkit.snapshot();
+    let dispatcher = snapshot.for_dispatcher();
+    assert_eq!(
+        dispatcher.get_artifact_capabilities(&artifact),
+        Some(capabilities)
+    );
+
+    // Capabilities of the supervisor cannot be restricted.
+    let change = ConfigPropose::immediate(1)
+        .set_artifact_capabilities(Supervisor.artifact_id(), Capabilities::none());
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    let actual_err =
+        execute_transaction(&mut testkit, change).expect_err("Transaction shouldn't be processed");
+    assert_eq!(
+        actual_err,
+        ErrorMatch::from_fail(&ConfigurationError::MalformedConfigPropose)
+            .with_description_containing("Discarded restricting capabilities of the supervisor")
+    );
+
+    // Capabilities can be set only for known artifacts.
+    let unknown_artifact = ArtifactId::from_raw_parts(
+        artifact.runtime_id,
+        "unknown".to_owned(),
+        Version::new(1, 0, 0),
+    );
+    let change = ConfigPropose::immediate(1)
+        .set_artifact_capabilities(unknown_artifact, Capabilities::all());
+    let change = keypair.propose_config_change(SUPERVISOR_INSTANCE_ID, change);
+    let actual_err =
+        execute_transaction(&mut testkit, change).expect_err("Transaction shouldn't be processed");
+    assert_eq!(
+        actual_err,
+        ErrorMatch::from_fail(&ArtifactError::UnknownArtifact)
+            .with_description_containing("Discarded setting capabilities of the unknown artifact")
+    );
+}
+
+#[test]
+fn emergency_stop_service() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(4)
+        .with(Supervisor::decentralized())
+        .with(Spec::new(IncService).with_default_instance())
+        .build();
+    let request = StopService::new(IncService::INSTANCE_ID);
+    let validators = testkit.network().validators();
+
+    // Two votes out of four are not enough to stop the service.
+    for validator in &validators[..2] {
+        let tx = validator
+            .service_keypair()
+            .emergency_stop_service(SUPERVISOR_INSTANCE_ID, request.clone());
+        execute_transaction(&mut testkit, tx).expect("Transaction should be processed");
+    }
+    let snapshot = testkit.snapshot();
+    let instance = snapshot
+        .for_dispatcher()
+        .get_instance(IncService::INSTANCE_ID)
+        .unwrap();
+    assert_eq!(instance.status, Some(InstanceStatus::Active));
+
+    // The third vote stops the service immediately.
+    let tx = validators[2]
+        .service_keypair()
+        .emergency_stop_service(SUPERVISOR_INSTANCE_ID, request.clone());
+    execute_transaction(&mut testkit, tx).expect("Transaction should be processed");
+    let snapshot = testkit.snapshot();
+    let instance = snapshot
+        .for_dispatcher()
+        .get_instance(IncService::INSTANCE_ID)
+        .unwrap();
+    assert_eq!(instance.status, Some(InstanceStatus::Stopped));
+
+    // The stopped service cannot be stopped again.
+    let tx = validators[3]
+        .service_keypair()
+        .emergency_stop_service(SUPERVISOR_INSTANCE_ID, request);
+    let actual_err =
+        execute_transaction(&mut testkit, tx).expect_err("Transaction shouldn't be processed");
+    assert_eq!(
+        actual_err,
+        ErrorMatch::from_fail(&ConfigurationError::MalformedConfigPropose)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+            .with_description_containing(
+                "Discarded an attempt to stop service `inc` with inappropriate status (stopped)"
+            )
+    );
+}
+
+#[test]
+fn emergency_stop_votes_expire() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(4)
+        .with(Supervisor::decentralized())
+        .with(Spec::new(IncService).with_default_instance())
+        .build();
+    let request = StopService::new(IncService::INSTANCE_ID);
+    let validators = testkit.network().validators();
+
+    for validator in &validators[..2] {
+        let tx = validator
+            .service_keypair()
+            .emergency_stop_service(SUPERVISOR_INSTANCE_ID, request.clone());
+        execute_transaction(&mut testkit, tx).expect("Transaction should be processed");
+    }
+
+    // Votes collected before the end of the voting period are discarded.
+    let expiration_height = testkit.height().0 + StopService::VOTING_PERIOD;
+    testkit.create_blocks_until(Height(expiration_height));
+
+    let tx = validators[2]
+        .service_keypair()
+        .emergency_stop_service(SUPERVISOR_INSTANCE_ID, request);
+    execute_transaction(&mut testkit, tx).expect("Transaction should be processed");
+    let snapshot = testkit.snapshot();
+    let instance = snapshot
+        .for_dispatcher()
+        .get_instance(IncService::INSTANCE_ID)
+        .unwrap();
+    assert_eq!(instance.status, Some(InstanceStatus::Active));
+}
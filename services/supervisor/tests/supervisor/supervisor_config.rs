@@ -16,12 +16,27 @@
 //! `Supervisor` service initialization, using `Configure` interface
 //! and API endpoints associated with configuration.
 
-use exonum::runtime::{SnapshotExt, SUPERVISOR_INSTANCE_ID};
+use exonum::{
+    blockchain::TxLocation,
+    crypto::KeyPair,
+    helpers::{Height, ValidatorId},
+    merkledb::ObjectHash,
+    runtime::{ErrorMatch, SnapshotExt, SUPERVISOR_INSTANCE_ID},
+};
 use exonum_testkit::{ApiKind, Spec, TestKit, TestKitBuilder};
 
-use exonum_supervisor::{ConfigPropose, Schema, Supervisor, SupervisorConfig};
+use exonum_supervisor::{
+    mode::Mode, ConfigPropose, ConfigTimelock, ConfigurationError, ModeChangeRequest, Schema,
+    SchemaImpl, Supervisor, SupervisorConfig, SupervisorInterface, TimelockedChange,
+};
 
-use crate::{config_api::create_proposal, utils::CFG_CHANGE_HEIGHT};
+use crate::{
+    config_api::create_proposal,
+    utils::{
+        build_confirmation_transactions, config_propose_entry, sign_config_propose_transaction,
+        CFG_CHANGE_HEIGHT,
+    },
+};
 
 /// Asserts that current supervisor configuration equals to the provided one.
 fn assert_supervisor_config(testkit: &TestKit, config: SupervisorConfig) {
@@ -113,3 +128,165 @@ async fn supervisor_config_api() {
         Supervisor::decentralized_config(),
     );
 }
+
+/// Checks that call errors outside the retention period are rolled into the checkpoint.
+#[test]
+fn call_errors_retention() {
+    let config = Supervisor::simple_config().with_call_errors_retention(3);
+    let supervisor =
+        Spec::new(Supervisor).with_instance(SUPERVISOR_INSTANCE_ID, Supervisor::NAME, config);
+    let mut testkit = TestKitBuilder::validator().with(supervisor).build();
+
+    // The transaction fails since it is not signed by a validator.
+    let tx = KeyPair::random()
+        .propose_config_change(SUPERVISOR_INSTANCE_ID, ConfigPropose::new(0, Height(100)));
+    let block = testkit.create_block_with_transaction(tx);
+    assert!(block.transactions[0].status().is_err());
+    let location = TxLocation::new(Height(1), 0);
+
+    testkit.create_blocks_until(Height(4));
+    let snapshot = testkit.snapshot();
+    let core_schema = snapshot.for_core();
+    assert_eq!(
+        core_schema.call_errors_checkpoint().unwrap().height,
+        Height(1)
+    );
+    assert!(core_schema.transaction_result(location).unwrap().is_err());
+
+    testkit.create_block();
+    let snapshot = testkit.snapshot();
+    let core_schema = snapshot.for_core();
+    let checkpoint = core_schema.call_errors_checkpoint().unwrap();
+    assert_eq!(checkpoint.height, Height(2));
+    assert_eq!(checkpoint.error_count, 1);
+    assert!(core_schema.transaction_result(location).is_none());
+    assert!(core_schema.call_records(Height(1)).is_none());
+}
+
+/// Checks that the supervisor mode can be switched with a dedicated transaction
+/// only if there are no pending activities.
+#[test]
+fn change_supervisor_mode() {
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(4)
+        .with(Supervisor::simple())
+        .build();
+    let validators = testkit.network().validators();
+    let to_decentralized = ModeChangeRequest::new(Mode::Decentralized);
+
+    // The mode cannot be switched while a configuration proposal is pending.
+    let propose = ConfigPropose::new(0, CFG_CHANGE_HEIGHT)
+        .service_config(SUPERVISOR_INSTANCE_ID, Supervisor::simple_config());
+    let tx = sign_config_propose_transaction(&testkit, propose, ValidatorId(0));
+    testkit.create_block_with_transaction(tx);
+    let tx = validators[0]
+        .service_keypair()
+        .change_mode(SUPERVISOR_INSTANCE_ID, to_decentralized.clone());
+    let block = testkit.create_block_with_transaction(tx);
+    let err = block.transactions[0].status().unwrap_err();
+    assert_eq!(
+        *err,
+        ErrorMatch::from_fail(&ConfigurationError::ModeChangeConflict)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+            .with_description_containing("pending configuration proposals")
+    );
+
+    // Once the proposal is applied, a single vote is enough in the simple mode.
+    testkit.create_blocks_until(CFG_CHANGE_HEIGHT.next());
+    let tx = validators[0]
+        .service_keypair()
+        .change_mode(SUPERVISOR_INSTANCE_ID, to_decentralized.clone());
+    let block = testkit.create_block_with_transaction(tx);
+    block.transactions[0].status().unwrap();
+    assert_supervisor_config(&testkit, Supervisor::decentralized_config());
+
+    // The supervisor cannot be switched to the current mode.
+    let tx = validators[1]
+        .service_keypair()
+        .change_mode(SUPERVISOR_INSTANCE_ID, to_decentralized);
+    let block = testkit.create_block_with_transaction(tx);
+    let err = block.transactions[0].status().unwrap_err();
+    assert_eq!(
+        *err,
+        ErrorMatch::from_fail(&ConfigurationError::ModeAlreadyActive)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+    );
+
+    // Switching back requires a majority of validators in the decentralized mode.
+    let to_simple = ModeChangeRequest::new(Mode::Simple);
+    let txs = validators[..2].iter().map(|validator| {
+        validator
+            .service_keypair()
+            .change_mode(SUPERVISOR_INSTANCE_ID, to_simple.clone())
+    });
+    testkit.create_block_with_transactions(txs);
+    assert_supervisor_config(&testkit, Supervisor::decentralized_config());
+
+    let tx = validators[2]
+        .service_keypair()
+        .change_mode(SUPERVISOR_INSTANCE_ID, to_simple);
+    testkit.create_block_with_transaction(tx);
+    assert_supervisor_config(&testkit, Supervisor::simple_config());
+
+    // Both changes are recorded in the audit log.
+    let snapshot = testkit.snapshot();
+    let schema = SchemaImpl::new(snapshot.for_service(Supervisor::NAME).unwrap());
+    let changes: Vec<_> = schema.mode_changes.iter().collect();
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].previous_mode, Mode::Simple);
+    assert_eq!(changes[0].new_mode, Mode::Decentralized);
+    assert_eq!(changes[0].height, Height(CFG_CHANGE_HEIGHT.0 + 2));
+    assert_eq!(changes[1].previous_mode, Mode::Decentralized);
+    assert_eq!(changes[1].new_mode, Mode::Simple);
+}
+
+/// Checks that changes of the validator set are subject to the timelock specified
+/// in the supervisor configuration.
+#[test]
+fn validator_set_timelock() {
+    let config = Supervisor::decentralized_config()
+        .with_timelock(ConfigTimelock::new(TimelockedChange::ValidatorSet, 5));
+    let supervisor =
+        Spec::new(Supervisor).with_instance(SUPERVISOR_INSTANCE_ID, Supervisor::NAME, config);
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(4)
+        .with(supervisor)
+        .build();
+
+    let mut consensus_config = testkit.consensus_config();
+    consensus_config.validator_keys.pop();
+
+    // The proposal is rejected if the activation height is too close.
+    let propose = ConfigPropose::new(0, Height(3)).consensus_config(consensus_config.clone());
+    let tx = sign_config_propose_transaction(&testkit, propose, ValidatorId(0));
+    let block = testkit.create_block_with_transaction(tx);
+    let err = block.transactions[0].status().unwrap_err();
+    assert_eq!(
+        *err,
+        ErrorMatch::from_fail(&ConfigurationError::TimelockViolation)
+            .for_service(SUPERVISOR_INSTANCE_ID)
+    );
+
+    // The proposal is accepted, but it is not applied if the validators approve it too late.
+    let propose = ConfigPropose::new(0, Height(20)).consensus_config(consensus_config.clone());
+    let propose_hash = propose.object_hash();
+    let tx = sign_config_propose_transaction(&testkit, propose, ValidatorId(0));
+    let block = testkit.create_block_with_transaction(tx);
+    block.transactions[0].status().unwrap();
+    testkit.create_blocks_until(Height(17));
+    let txs = build_confirmation_transactions(&testkit, propose_hash, ValidatorId(0));
+    testkit.create_block_with_transactions(txs);
+    testkit.create_blocks_until(Height(21));
+    assert!(config_propose_entry(&testkit).is_none());
+    assert_eq!(testkit.consensus_config().validator_keys.len(), 4);
+
+    // The proposal is applied if approved in time.
+    let propose = ConfigPropose::new(1, Height(30)).consensus_config(consensus_config.clone());
+    let propose_hash = propose.object_hash();
+    let tx = sign_config_propose_transaction(&testkit, propose, ValidatorId(0));
+    testkit.create_block_with_transaction(tx);
+    let txs = build_confirmation_transactions(&testkit, propose_hash, ValidatorId(0));
+    testkit.create_block_with_transactions(txs);
+    testkit.create_blocks_until(Height(31));
+    assert_eq!(testkit.consensus_config(), consensus_config);
+}
@@ -12,7 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use exonum_derive::{BinaryValue, ServiceDispatcher, ServiceFactory};
+use exonum::merkledb::{
+    access::{Access, DescribeSchema, IndexDescription},
+    Entry, Group, ProofMapIndex, Snapshot,
+};
+use exonum_derive::{BinaryValue, FromAccess, ServiceDispatcher, ServiceFactory};
 use exonum_proto::ProtobufConvert;
 use exonum_rust_runtime::{api::ServiceApiBuilder, DefaultInstance, Service};
 use serde::{Deserialize, Serialize};
@@ -34,12 +38,24 @@ pub struct Transfer {
     pub seed: u64,
 }
 
+/// Service schema, which is described via the Rust runtime API.
+#[derive(Debug, FromAccess)]
+#[from_access(describe)]
+pub struct Schema<T: Access> {
+    pub transfers: ProofMapIndex<T::Base, str, u64>,
+    pub counters: Group<T, u64, Entry<T::Base, u64>>,
+}
+
 impl Service for TestRuntimeApiService {
     fn wire_api(&self, builder: &mut ServiceApiBuilder) {
         builder
             .public_scope()
             .pb_endpoint_mut("transfer", |_, transfer: Transfer| async { Ok(transfer) });
     }
+
+    fn schema_description(&self) -> Option<Vec<IndexDescription>> {
+        Some(Schema::<&dyn Snapshot>::describe_schema(""))
+    }
 }
 
 impl DefaultInstance for TestRuntimeApiService {
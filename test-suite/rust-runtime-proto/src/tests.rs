@@ -15,12 +15,12 @@
 use bit_vec::BitVec;
 use exonum::{
     crypto::{self, Hash, PublicKey},
-    merkledb::BinaryValue,
+    merkledb::{access::IndexDescription, BinaryValue, IndexType},
 };
 use exonum_api::ErrorBody;
 use exonum_derive::{BinaryValue, ObjectHash};
 use exonum_proto::ProtobufConvert;
-use exonum_rust_runtime::{ProtoSourceFile, ProtoSourcesQuery};
+use exonum_rust_runtime::{ProtoSourceFile, ProtoSourcesQuery, SchemaQuery};
 use exonum_testkit::{ApiKind, TestKitBuilder};
 use pretty_assertions::assert_eq;
 use reqwest::{Client, StatusCode};
@@ -341,3 +341,33 @@ async fn request_to_pb_endpoint() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Rust-runtime API returns the schema description of a service.
+#[tokio::test]
+async fn service_schema_description() {
+    let (_, api) = testkit_with_rust_service();
+
+    let descriptions: Vec<IndexDescription> = api
+        .public(ApiKind::RustRuntime)
+        .query(&SchemaQuery::new("test-runtime-api"))
+        .get("schema")
+        .await
+        .expect("Rust runtime Api unexpectedly failed");
+
+    assert_eq!(descriptions.len(), 2);
+    assert_eq!(descriptions[0].name, "test-runtime-api.transfers");
+    assert_eq!(descriptions[0].index_type, IndexType::ProofMap);
+    assert_eq!(descriptions[0].key_type.as_deref(), Some("str"));
+    assert_eq!(descriptions[0].value_type.as_deref(), Some("u64"));
+    assert_eq!(descriptions[1].name, "test-runtime-api.counters");
+    assert_eq!(descriptions[1].index_type, IndexType::Entry);
+    assert_eq!(descriptions[1].group_key_types, vec!["u64".to_owned()]);
+
+    let error = api
+        .public(ApiKind::RustRuntime)
+        .query(&SchemaQuery::new("unknown-service"))
+        .get::<Vec<IndexDescription>>("schema")
+        .await
+        .expect_err("Rust runtime Api returns a description of an unknown service");
+    assert_eq!(&error.body.title, "Schema description not found");
+}
@@ -20,7 +20,7 @@ use exonum::{
     helpers::ValidatorId,
     keys::Keys,
     merkledb::TemporaryDB,
-    runtime::{RuntimeInstance, WellKnownRuntime},
+    runtime::{encryption::PayloadKeyStore, RuntimeInstance, WellKnownRuntime},
 };
 #[cfg(feature = "exonum-node")]
 use exonum_node::NodePlugin;
@@ -132,6 +132,13 @@ impl TestKitBuilder {
         self
     }
 
+    /// Sets the store of keys used by the Rust runtime to decrypt encrypted transaction payloads.
+    #[must_use]
+    pub fn with_payload_keys(mut self, payload_keys: PayloadKeyStore) -> Self {
+        self.rust_runtime = self.rust_runtime.with_payload_keys(payload_keys);
+        self
+    }
+
     /// Enables a logger inside the testkit.
     #[must_use]
     pub fn with_logger(mut self) -> Self {
@@ -12,10 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use exonum_merkledb::{Database, DatabaseExt, Patch, Result as StorageResult, Snapshot};
+use exonum_merkledb::{
+    Database, DatabaseExt, Patch, Result as StorageResult, Snapshot, StallStats,
+};
 
 use std::{
     fmt,
+    path::Path,
     sync::{Arc, RwLock},
 };
 
@@ -80,6 +83,54 @@ impl<T: Database> Database for CheckpointDb<T> {
     fn merge_sync(&self, patch: Patch) -> StorageResult<()> {
         self.merge(patch)
     }
+
+    fn backup(&self, path: &Path) -> StorageResult<()> {
+        self.inner
+            .read()
+            .expect("Cannot lock CheckpointDb for backup")
+            .db
+            .backup(path)
+    }
+
+    fn checkpoint(&self, name: &str) -> StorageResult<()> {
+        self.inner
+            .read()
+            .expect("Cannot lock CheckpointDb for checkpoint")
+            .db
+            .checkpoint(name)
+    }
+
+    fn checkpoint_snapshot(&self, name: &str) -> StorageResult<Box<dyn Snapshot>> {
+        self.inner
+            .read()
+            .expect("Cannot lock CheckpointDb for checkpoint")
+            .db
+            .checkpoint_snapshot(name)
+    }
+
+    fn compact_range(&self, name: Option<&str>) -> StorageResult<()> {
+        self.inner
+            .read()
+            .expect("Cannot lock CheckpointDb for compaction")
+            .db
+            .compact_range(name)
+    }
+
+    fn stall_stats(&self) -> StorageResult<StallStats> {
+        self.inner
+            .read()
+            .expect("Cannot lock CheckpointDb for stall stats")
+            .db
+            .stall_stats()
+    }
+
+    fn set_background_jobs_limit(&self, limit: u32) -> StorageResult<()> {
+        self.inner
+            .read()
+            .expect("Cannot lock CheckpointDb for setting options")
+            .db
+            .set_background_jobs_limit(limit)
+    }
 }
 
 #[allow(clippy::use_self)] // false positive
@@ -14,9 +14,12 @@
 
 //! Tests related to the API.
 
-use exonum::runtime::SUPERVISOR_INSTANCE_ID;
+use exonum::{helpers::Height, runtime::SUPERVISOR_INSTANCE_ID};
 use exonum_api as api;
-use exonum_rust_runtime::{RustRuntime, ServiceFactory};
+use exonum_rust_runtime::{
+    api::{AtHeight, MIN_HEIGHT_HEADER},
+    RustRuntime, ServiceFactory,
+};
 use exonum_testkit::{ApiKind, Spec, TestKit, TestKitApi, TestKitBuilder};
 use pretty_assertions::assert_eq;
 
@@ -72,6 +75,85 @@ async fn submit_tx() {
     assert_eq!(*block[0].message(), expected_tx);
 }
 
+/// Checks that data read from several indexes is annotated with the snapshot height.
+#[tokio::test]
+async fn consistent_read() {
+    let (mut testkit, api) = init_testkit();
+
+    let stats: AtHeight<(u64, u64)> = api
+        .public(ApiKind::Service("api-service"))
+        .get("block-stats")
+        .await
+        .expect("Request to the valid endpoint failed");
+    assert_eq!(stats.height, Height(0));
+    assert_eq!(stats.data.0, 1);
+
+    let tx = testkit.us().service_keypair().do_nothing(SERVICE_ID, 1);
+    testkit.create_block_with_transaction(tx);
+    testkit.create_block();
+    let stats: AtHeight<(u64, u64)> = api
+        .public(ApiKind::Service("api-service"))
+        .get("block-stats")
+        .await
+        .expect("Request to the valid endpoint failed");
+    assert_eq!(stats.height, Height(2));
+    assert_eq!(stats.data.0, 3);
+    assert_eq!(stats.data.1, 1);
+}
+
+/// Checks that requests with the minimum height are only served once the blockchain
+/// reaches this height.
+#[tokio::test]
+async fn min_height() {
+    let (mut testkit, api) = init_testkit();
+
+    let err = api
+        .public(ApiKind::Service("api-service"))
+        .with(|request| request.header(MIN_HEIGHT_HEADER, "1"))
+        .get::<AtHeight<(u64, u64)>>("block-stats")
+        .await
+        .expect_err("Stale request succeeded");
+    assert_eq!(err.http_code, api::HttpStatusCode::CONFLICT);
+    assert_eq!(
+        err.body.title,
+        "Blockchain has not reached the requested height"
+    );
+
+    let ping = PingQuery { value: 64 };
+    let err = api
+        .public(ApiKind::Service("api-service"))
+        .query(&ping)
+        .with(|request| request.header(MIN_HEIGHT_HEADER, "1"))
+        .post::<u64>("ping-pong-deprecated-mut")
+        .await
+        .expect_err("Stale request succeeded");
+    assert_eq!(err.http_code, api::HttpStatusCode::CONFLICT);
+
+    let err = api
+        .public(ApiKind::Service("api-service"))
+        .with(|request| request.header(MIN_HEIGHT_HEADER, "-1"))
+        .get::<AtHeight<(u64, u64)>>("block-stats")
+        .await
+        .expect_err("Request with invalid header succeeded");
+    assert_eq!(err.http_code, api::HttpStatusCode::BAD_REQUEST);
+
+    testkit.create_block();
+    let stats: AtHeight<(u64, u64)> = api
+        .public(ApiKind::Service("api-service"))
+        .with(|request| request.header(MIN_HEIGHT_HEADER, "1"))
+        .get("block-stats")
+        .await
+        .expect("Request to the valid endpoint failed");
+    assert_eq!(stats.height, Height(1));
+    let stats: AtHeight<(u64, u64)> = api
+        .public(ApiKind::Service("api-service"))
+        .with(|request| request.header(MIN_HEIGHT_HEADER, "0"))
+        .get("block-stats")
+        .await
+        .expect("Request to the valid endpoint failed");
+    assert_eq!(stats.height, Height(1));
+}
+
 /// Checks that for deprecated endpoints the corresponding warning is added to the headers
 /// of the response.
 #[tokio::test]
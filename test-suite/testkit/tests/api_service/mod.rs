@@ -22,7 +22,7 @@ use exonum::runtime::{
 };
 use exonum_derive::*;
 use exonum_rust_runtime::{
-    api::{self, Deprecated, ServiceApiBuilder, ServiceApiState},
+    api::{self, AtHeight, Deprecated, ServiceApiBuilder, ServiceApiState},
     DefaultInstance, Service,
 };
 use serde::{Deserialize, Serialize};
@@ -62,6 +62,17 @@ impl Api {
         }
     }
 
+    /// Returns the number of blocks and transactions read from the same snapshot.
+    async fn block_stats(state: ServiceApiState, _query: ()) -> api::Result<AtHeight<(u64, u64)>> {
+        state.read_consistent(|data| {
+            let schema = data.for_core();
+            Ok((
+                schema.block_hashes_by_height().len(),
+                schema.transactions_len(),
+            ))
+        })
+    }
+
     /// Returns `Gone` error.
     async fn gone(_state: ServiceApiState, _ping: PingQuery) -> api::Result<u64> {
         Err(api::Error::new(api::HttpStatusCode::GONE))
@@ -73,6 +84,7 @@ impl Api {
         // Normal endpoint.
         public_scope
             .endpoint("ping-pong", Self::ping_pong)
+            .endpoint("block-stats", Self::block_stats)
             .endpoint_mut("submit-tx", Self::submit_tx);
 
         // Deprecated endpoints.
@@ -0,0 +1,137 @@
+// Copyright 2020 The Exonum Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for handling encrypted transaction payloads in the Rust runtime.
+
+use exonum::{
+    crypto::Hash,
+    helpers::ValidateInput,
+    merkledb::access::AccessExt,
+    runtime::{
+        encryption::{EncryptedPayload, PayloadKey, PayloadKeyStore},
+        CommonError, ExecutionContext, ExecutionError, InstanceId, MethodId, SnapshotExt,
+    },
+};
+use exonum_derive::{exonum_interface, ServiceDispatcher, ServiceFactory};
+use exonum_rust_runtime::{AfterCommitContext, DefaultInstance, Service};
+use exonum_testkit::{Spec, TestKitBuilder};
+use pretty_assertions::assert_eq;
+
+use std::sync::{Arc, Mutex};
+
+const SERVICE_ID: InstanceId = 100;
+const SERVICE_NAME: &str = "private-notes";
+
+#[exonum_interface(auto_ids)]
+trait PrivateNotesInterface<Ctx> {
+    type Output;
+    fn add_note(&self, ctx: Ctx, note: EncryptedPayload) -> Self::Output;
+}
+
+#[derive(Clone, Default, Debug, ServiceFactory, ServiceDispatcher)]
+#[service_factory(
+    artifact_name = "private-notes",
+    artifact_version = "1.0.0",
+    service_constructor = "Self::new_instance"
+)]
+#[service_dispatcher(implements("PrivateNotesInterface"))]
+struct PrivateNotesService {
+    decrypted_notes: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl PrivateNotesService {
+    fn new_instance(&self) -> Box<dyn Service> {
+        Box::new(self.clone())
+    }
+
+    fn decrypted_notes(&self) -> Vec<Vec<u8>> {
+        self.decrypted_notes.lock().unwrap().clone()
+    }
+}
+
+impl PrivateNotesInterface<ExecutionContext<'_>> for PrivateNotesService {
+    type Output = Result<(), ExecutionError>;
+
+    fn add_note(&self, ctx: ExecutionContext<'_>, note: EncryptedPayload) -> Self::Output {
+        note.validate().map_err(CommonError::malformed_arguments)?;
+        // Only the plaintext commitment is recorded on-chain.
+        ctx.service_data()
+            .get_proof_list("note_hashes")
+            .push(note.commitment);
+        Ok(())
+    }
+}
+
+impl Service for PrivateNotesService {
+    fn encrypted_methods(&self) -> &[MethodId] {
+        &[0]
+    }
+
+    fn handle_decrypted_payload(
+        &self,
+        _context: AfterCommitContext<'_>,
+        method_id: MethodId,
+        plaintext: Vec<u8>,
+    ) {
+        assert_eq!(method_id, 0);
+        self.decrypted_notes.lock().unwrap().push(plaintext);
+    }
+}
+
+impl DefaultInstance for PrivateNotesService {
+    const INSTANCE_ID: InstanceId = SERVICE_ID;
+    const INSTANCE_NAME: &'static str = SERVICE_NAME;
+}
+
+#[test]
+fn decrypting_payloads_after_commit() {
+    let key = PayloadKey::random();
+    let payload_keys = PayloadKeyStore::new();
+    payload_keys.insert(key.clone());
+
+    let service = PrivateNotesService::default();
+    let mut testkit = TestKitBuilder::validator()
+        .with(Spec::new(service.clone()).with_default_instance())
+        .with_payload_keys(payload_keys)
+        .build();
+    let keypair = testkit.us().service_keypair();
+    let recipients = vec![keypair.public_key()];
+
+    let note = EncryptedPayload::seal(b"first note", &key, recipients.clone());
+    let first_commitment = note.commitment;
+    let block = testkit.create_block_with_transaction(keypair.add_note(SERVICE_ID, note));
+    block[0].status().unwrap();
+    assert_eq!(service.decrypted_notes(), vec![b"first note".to_vec()]);
+
+    // Payloads encrypted with unknown keys are committed, but not decrypted.
+    let other_key = PayloadKey::random();
+    let note = EncryptedPayload::seal(b"second note", &other_key, recipients);
+    let block = testkit.create_block_with_transaction(keypair.add_note(SERVICE_ID, note));
+    block[0].status().unwrap();
+    assert_eq!(service.decrypted_notes().len(), 1);
+
+    // Payloads of failed transactions are not decrypted.
+    let note = EncryptedPayload::seal(b"third note", &key, vec![]);
+    let block = testkit.create_block_with_transaction(keypair.add_note(SERVICE_ID, note));
+    block[0].status().unwrap_err();
+    assert_eq!(service.decrypted_notes().len(), 1);
+
+    let snapshot = testkit.snapshot();
+    let note_hashes = snapshot
+        .for_service(SERVICE_NAME)
+        .unwrap()
+        .get_proof_list::<_, Hash>("note_hashes");
+    assert_eq!(note_hashes.len(), 2);
+    assert_eq!(note_hashes.get(0), Some(first_commitment));
+}
@@ -14,17 +14,20 @@
 
 //! A special service which generates transactions on `after_commit` events.
 
-use exonum::runtime::{
-    migrations::{InitMigrationError, MigrateData, MigrationScript},
-    versioning::Version,
-    ExecutionContext, ExecutionError, InstanceId,
+use exonum::{
+    helpers::Height,
+    runtime::{
+        migrations::{InitMigrationError, MigrateData, MigrationScript},
+        versioning::Version,
+        ExecutionContext, ExecutionError, InstanceId,
+    },
 };
 use exonum_derive::{exonum_interface, ServiceDispatcher, ServiceFactory};
 use exonum_rust_runtime::{AfterCommitContext, DefaultInstance, Service};
 
 use std::sync::{
-    atomic::{AtomicUsize, Ordering},
-    Arc,
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc, Mutex,
 };
 
 pub const SERVICE_ID: InstanceId = 512;
@@ -133,3 +136,52 @@ impl MigrateData for AfterCommitServiceV2 {
         }
     }
 }
+
+/// Service acknowledging `after_commit` notifications up to the specified height.
+#[derive(Clone, Debug, Default, ServiceFactory, ServiceDispatcher)]
+#[service_factory(
+    artifact_name = "acknowledging",
+    artifact_version = "1.0.0",
+    service_constructor = "Self::new_instance"
+)]
+pub struct AcknowledgingService {
+    acknowledge_until: Arc<AtomicU64>,
+    notifications: Arc<Mutex<Vec<(Height, bool)>>>,
+}
+
+impl AcknowledgingService {
+    pub fn new(acknowledge_until: Height) -> Self {
+        let service = Self::default();
+        service
+            .acknowledge_until
+            .store(acknowledge_until.0, Ordering::SeqCst);
+        service
+    }
+
+    /// Returns heights of received notifications together with the redelivery flag.
+    pub fn take_notifications(&self) -> Vec<(Height, bool)> {
+        std::mem::take(&mut *self.notifications.lock().unwrap())
+    }
+
+    pub fn new_instance(&self) -> Box<dyn Service> {
+        Box::new(self.clone())
+    }
+}
+
+impl Service for AcknowledgingService {
+    fn after_commit(&self, context: AfterCommitContext<'_>) {
+        let height = context.notification_height();
+        self.notifications
+            .lock()
+            .unwrap()
+            .push((height, context.is_redelivery()));
+        if height.0 <= self.acknowledge_until.load(Ordering::SeqCst) {
+            context.acknowledgement().acknowledge().unwrap();
+        }
+    }
+}
+
+impl DefaultInstance for AcknowledgingService {
+    const INSTANCE_ID: u32 = 513;
+    const INSTANCE_NAME: &'static str = "acknowledging";
+}
@@ -25,7 +25,8 @@ use pretty_assertions::assert_eq;
 
 pub use crate::{
     hooks_service::{
-        AfterCommitInterface, AfterCommitService, AfterCommitServiceV2, SERVICE_ID, SERVICE_NAME,
+        AcknowledgingService, AfterCommitInterface, AfterCommitService, AfterCommitServiceV2,
+        SERVICE_ID, SERVICE_NAME,
     },
     supervisor::{StartMigration, Supervisor, SupervisorInterface},
 };
@@ -281,6 +282,38 @@ async fn restart_testkit() {
     assert!(transactions_are_committed);
 }
 
+#[test]
+fn unacknowledged_notifications_are_delivered_after_restart() {
+    let service = AcknowledgingService::new(Height(3));
+    let mut testkit = TestKitBuilder::validator()
+        .with(Spec::new(service.clone()).with_default_instance())
+        .build();
+    testkit.create_blocks_until(Height(5));
+    let expected: Vec<_> = (1..=5).map(|i| (Height(i), false)).collect();
+    assert_eq!(service.take_notifications(), expected);
+
+    let service = AcknowledgingService::new(Height(u64::MAX));
+    let rust_runtime = RustRuntime::builder().with_factory(service.clone());
+    let mut testkit = testkit.stop().resume(rust_runtime);
+    testkit.create_block();
+    // Notifications for heights 4 and 5 were not acknowledged before the restart.
+    assert_eq!(
+        service.take_notifications(),
+        vec![(Height(4), true), (Height(5), true), (Height(6), false)]
+    );
+
+    // Redelivery is performed only once after the restart.
+    testkit.create_block();
+    assert_eq!(service.take_notifications(), vec![(Height(7), false)]);
+
+    // All notifications are acknowledged, so nothing is delivered again after the next restart.
+    let service = AcknowledgingService::new(Height(u64::MAX));
+    let rust_runtime = RustRuntime::builder().with_factory(service.clone());
+    let mut testkit = testkit.stop().resume(rust_runtime);
+    testkit.create_block();
+    assert_eq!(service.take_notifications(), vec![(Height(8), false)]);
+}
+
 #[test]
 fn tx_pool_is_retained_on_restart() {
     let mut testkit = TestKitBuilder::validator()